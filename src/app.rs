@@ -2,14 +2,17 @@ use crate::errors::ToolError;
 use crate::managers;
 use crate::services::alias::AliasService;
 use crate::services::audit::AuditService;
+use crate::services::audit_forwarder::AuditForwarder;
 use crate::services::cache::CacheService;
 use crate::services::capability::CapabilityService;
 use crate::services::context::ContextService;
 use crate::services::context_session::ContextSessionService;
 use crate::services::description::DescriptionService;
+use crate::services::doctor;
 use crate::services::evidence::EvidenceService;
 use crate::services::job::JobService;
 use crate::services::logger::Logger;
+use crate::services::middleware::ToolMiddleware;
 use crate::services::operation::OperationService;
 use crate::services::policy::PolicyService;
 use crate::services::preset::PresetService;
@@ -26,6 +29,8 @@ use crate::services::vault_client::VaultClient;
 use crate::services::workspace::WorkspaceService;
 use crate::tooling::catalog::tool_contract_catalog;
 use crate::tooling::names::builtin_tool_alias_map_owned;
+use crate::utils::feature_flags::is_startup_probe_enabled;
+use crate::utils::shutdown::{self, Supervised};
 use std::collections::HashMap;
 use std::sync::Arc;
 
@@ -40,6 +45,8 @@ pub struct App {
     pub runbook_manager: Arc<managers::runbook::RunbookManager>,
     pub state_service: Arc<StateService>,
     pub job_service: Arc<JobService>,
+    pub audit_service: Arc<AuditService>,
+    pub postgres_manager: Arc<managers::postgres::PostgresManager>,
     pub project_manager: Arc<managers::project::ProjectManager>,
     pub target_manager: Arc<managers::target::TargetManager>,
     pub profile_manager: Arc<managers::profile::ProfileManager>,
@@ -80,26 +87,53 @@ impl App {
         Self::initialize_with_session(new_session_state())
     }
 
+    // For embedders: every tool call runs through `middleware`, in order (see
+    // `ToolExecutor::chain`).
+    pub fn initialize_with_middleware(
+        middleware: Vec<Arc<dyn ToolMiddleware>>,
+    ) -> Result<Self, ToolError> {
+        Self::build(new_session_state(), middleware)
+    }
+
     pub fn initialize_with_session(session_state: SessionState) -> Result<Self, ToolError> {
+        Self::build(session_state, Vec::new())
+    }
+
+    fn build(
+        session_state: SessionState,
+        middleware: Vec<Arc<dyn ToolMiddleware>>,
+    ) -> Result<Self, ToolError> {
         let logger = Logger::new("infra");
         let validation = Validation::new();
 
+        let mut startup_checks = doctor::local_checks();
+        doctor::ensure_startable(&startup_checks)?;
+
         let security = Arc::new(Security::new()?);
-        let state_service = Arc::new(StateService::new_with_session(session_state)?);
+        let state_service = Arc::new(
+            StateService::new_with_session(session_state)?.with_security(security.clone()),
+        );
         let profile_service = Arc::new(ProfileService::new(security.clone())?);
+        if is_startup_probe_enabled() {
+            startup_checks.extend(doctor::profile_probes(&profile_service));
+        }
+        doctor::log_startup(&logger, &startup_checks);
         let project_service = Arc::new(ProjectService::new()?);
-        let project_resolver = Arc::new(ProjectResolver::new(
-            validation.clone(),
-            project_service.clone(),
-            Some(state_service.clone()),
-        ));
+        let cache_service = Arc::new(CacheService::new(logger.clone()));
+        let project_resolver = Arc::new(
+            ProjectResolver::new(
+                validation.clone(),
+                project_service.clone(),
+                Some(state_service.clone()),
+            )
+            .with_cache(Arc::new(cache_service.namespaced("project_resolver")?)),
+        );
         let context_service = Arc::new(ContextService::new()?);
         let runbook_service = Arc::new(RunbookService::new()?);
         let capability_service = Arc::new(CapabilityService::new(security.clone())?);
         let alias_service = Arc::new(AliasService::new()?);
         let preset_service = Arc::new(PresetService::new()?);
         let audit_service = Arc::new(AuditService::new(logger.clone()));
-        let cache_service = Arc::new(CacheService::new(logger.clone()));
         let job_service = Arc::new(JobService::new(logger.clone())?);
         let evidence_service = Arc::new(EvidenceService::new(logger.clone(), (*security).clone()));
         let operation_service = Arc::new(OperationService::new()?);
@@ -118,26 +152,32 @@ impl App {
             Some(project_resolver.clone()),
             Some(profile_service.clone()),
         ));
-        let workspace_service = Arc::new(WorkspaceService::new(
-            logger.clone(),
-            context_service.clone(),
-            Some(context_session.clone()),
-            Some(project_resolver.clone()),
-            profile_service.clone(),
-            runbook_service.clone(),
-            capability_service.clone(),
-            project_service.clone(),
-            alias_service.clone(),
-            preset_service.clone(),
-            state_service.clone(),
-        ));
-        let secret_ref_resolver = Arc::new(SecretRefResolver::new(
-            logger.clone(),
-            validation.clone(),
-            Some(profile_service.clone()),
-            Some(vault_client.clone()),
-            Some(project_resolver.clone()),
-        ));
+        let workspace_service = Arc::new(
+            WorkspaceService::new(
+                logger.clone(),
+                context_service.clone(),
+                Some(context_session.clone()),
+                Some(project_resolver.clone()),
+                profile_service.clone(),
+                runbook_service.clone(),
+                capability_service.clone(),
+                project_service.clone(),
+                alias_service.clone(),
+                preset_service.clone(),
+                state_service.clone(),
+            )
+            .with_history(audit_service.clone(), Some(job_service.clone())),
+        );
+        let secret_ref_resolver = Arc::new(
+            SecretRefResolver::new(
+                logger.clone(),
+                validation.clone(),
+                Some(profile_service.clone()),
+                Some(vault_client.clone()),
+                Some(project_resolver.clone()),
+            )
+            .with_cache(Arc::new(cache_service.namespaced("secret_refs")?)),
+        );
 
         let alias_manager = Arc::new(managers::alias::AliasManager::new(
             logger.clone(),
@@ -146,20 +186,17 @@ impl App {
         let preset_manager = Arc::new(managers::preset::PresetManager::new(
             logger.clone(),
             preset_service.clone(),
+            Some(alias_service.clone()),
         ));
-        let state_manager = Arc::new(managers::state::StateManager::new(
-            logger.clone(),
-            state_service.clone(),
-        ));
-        let audit_manager = Arc::new(managers::audit::AuditManager::new(
-            logger.clone(),
-            audit_service.clone(),
-        ));
+        let state_manager = Arc::new(
+            managers::state::StateManager::new(logger.clone(), state_service.clone())
+                .with_project_resolver(project_resolver.clone()),
+        );
+        let audit_manager = Arc::new(
+            managers::audit::AuditManager::new(logger.clone(), audit_service.clone())
+                .with_evidence_service(evidence_service.clone()),
+        );
         let artifacts_manager = Arc::new(managers::artifacts::ArtifactManager::new(logger.clone()));
-        let context_manager = Arc::new(managers::context::ContextManager::new(
-            logger.clone(),
-            context_service.clone(),
-        ));
         let profile_manager = Arc::new(managers::profile::ProfileManager::new(
             logger.clone(),
             profile_service.clone(),
@@ -169,6 +206,7 @@ impl App {
             validation.clone(),
             project_service.clone(),
             state_service.clone(),
+            Some(project_resolver.clone()),
         ));
         let target_manager = Arc::new(managers::target::TargetManager::new(
             logger.clone(),
@@ -182,25 +220,18 @@ impl App {
             logger.clone(),
             policy_service.clone(),
         ));
-        let capability_manager = Arc::new(managers::capability::CapabilityManager::new(
-            logger.clone(),
-            validation.clone(),
-            capability_service.clone(),
-            Some(context_service.clone()),
-        ));
-        let evidence_manager = Arc::new(managers::evidence::EvidenceManager::new(
-            logger.clone(),
-            evidence_service.clone(),
-        ));
-        let ssh_manager = Arc::new(managers::ssh::SshManager::new(
-            logger.clone(),
-            security.clone(),
-            validation.clone(),
-            profile_service.clone(),
-            Some(project_resolver.clone()),
-            Some(secret_ref_resolver.clone()),
-            Some(job_service.clone()),
-        ));
+        let ssh_manager = Arc::new(
+            managers::ssh::SshManager::new(
+                logger.clone(),
+                security.clone(),
+                validation.clone(),
+                profile_service.clone(),
+                Some(project_resolver.clone()),
+                Some(secret_ref_resolver.clone()),
+                Some(job_service.clone()),
+            )
+            .with_audit_service(audit_service.clone()),
+        );
         let env_manager = Arc::new(managers::env::EnvManager::new(
             logger.clone(),
             validation.clone(),
@@ -219,10 +250,19 @@ impl App {
             logger.clone(),
             validation.clone(),
             profile_service.clone(),
-            Some(cache_service.clone()),
+            Some(Arc::new(cache_service.namespaced("api")?)),
             Some(project_resolver.clone()),
             Some(secret_ref_resolver.clone()),
         ));
+        if let Some(forwarder) = AuditForwarder::from_flags(&logger, |url, profile| {
+            Arc::new(managers::api::AuditWebhook::new(
+                api_manager.clone(),
+                url,
+                profile,
+            ))
+        }) {
+            audit_service.set_forwarder(forwarder);
+        }
         let postgres_manager = Arc::new(managers::postgres::PostgresManager::new(
             logger.clone(),
             validation.clone(),
@@ -230,22 +270,53 @@ impl App {
             Some(project_resolver.clone()),
             Some(secret_ref_resolver.clone()),
         ));
-        let local_manager = Arc::new(managers::local::LocalManager::new(
+        let capability_manager = Arc::new(
+            managers::capability::CapabilityManager::new(
+                logger.clone(),
+                validation.clone(),
+                capability_service.clone(),
+                Some(context_service.clone()),
+            )
+            .with_project_resolver(project_resolver.clone())
+            .with_runbook_service(runbook_service.clone())
+            .with_ssh_manager(ssh_manager.clone())
+            .with_postgres_manager(postgres_manager.clone())
+            .with_api_manager(api_manager.clone()),
+        );
+        let context_manager = Arc::new(managers::context::ContextManager::new(
             logger.clone(),
-            validation.clone(),
-            None,
+            context_service.clone(),
+            Some(profile_service.clone()),
+            Some(project_service.clone()),
+            Some(job_service.clone()),
+            Some(postgres_manager.clone()),
         ));
-        let repo_manager = Arc::new(managers::repo::RepoManager::new(logger.clone()));
-        let pipeline_manager = Arc::new(managers::pipeline::PipelineManager::new(
+        let evidence_manager = Arc::new(managers::evidence::EvidenceManager::new(
             logger.clone(),
-            validation.clone(),
-            api_manager.clone(),
-            ssh_manager.clone(),
-            postgres_manager.clone(),
-            Some(cache_service.clone()),
-            Some(audit_service.clone()),
-            Some(project_resolver.clone()),
+            evidence_service.clone(),
+            Some(api_manager.clone()),
+            Some(ssh_manager.clone()),
+            Some(postgres_manager.clone()),
         ));
+        let local_manager = Arc::new(
+            managers::local::LocalManager::new(logger.clone(), validation.clone(), None)
+                .with_job_service(job_service.clone()),
+        );
+        let repo_manager = Arc::new(managers::repo::RepoManager::new(logger.clone()));
+        let pipeline_manager = Arc::new(
+            managers::pipeline::PipelineManager::new(
+                logger.clone(),
+                validation.clone(),
+                api_manager.clone(),
+                ssh_manager.clone(),
+                postgres_manager.clone(),
+                Some(Arc::new(cache_service.namespaced("pipeline")?)),
+                Some(audit_service.clone()),
+                Some(project_resolver.clone()),
+                Some(evidence_service.clone()),
+            )
+            .with_job_service(job_service.clone()),
+        );
         let intent_manager = Arc::new(managers::intent::IntentManager::new(
             logger.clone(),
             security.clone(),
@@ -285,14 +356,18 @@ impl App {
             runbook_service.clone(),
             state_service.clone(),
         ));
-        let workspace_manager = Arc::new(managers::workspace::WorkspaceManager::new(
-            logger.clone(),
-            validation.clone(),
-            workspace_service.clone(),
-            runbook_manager.clone(),
-            Some(intent_manager.clone()),
-            Some(ssh_manager.clone()),
-        ));
+        let workspace_manager = Arc::new(
+            managers::workspace::WorkspaceManager::new(
+                logger.clone(),
+                validation.clone(),
+                workspace_service.clone(),
+                runbook_manager.clone(),
+                Some(intent_manager.clone()),
+                Some(ssh_manager.clone()),
+            )
+            .with_audit_service(audit_service.clone())
+            .with_cache_service(cache_service.clone()),
+        );
 
         let mut handlers: HashMap<String, Arc<dyn ToolHandler>> = HashMap::new();
         handlers.insert("alias".to_string(), alias_manager);
@@ -313,7 +388,7 @@ impl App {
         handlers.insert("vault".to_string(), vault_manager);
         handlers.insert("ssh".to_string(), ssh_manager);
         handlers.insert("api".to_string(), api_manager);
-        handlers.insert("sql".to_string(), postgres_manager);
+        handlers.insert("sql".to_string(), postgres_manager.clone());
         handlers.insert("local".to_string(), local_manager);
         handlers.insert("repo".to_string(), repo_manager);
         handlers.insert("pipeline".to_string(), pipeline_manager);
@@ -326,17 +401,23 @@ impl App {
 
         Self::validate_tool_wiring(&handlers, &alias_map)?;
 
-        let tool_executor = Arc::new(ToolExecutor::new(
-            logger.clone(),
-            state_service.clone(),
-            Some(alias_service.clone()),
-            Some(audit_service.clone()),
-            handlers,
-            alias_map,
-        ));
+        let tool_executor = Arc::new(
+            ToolExecutor::new(
+                logger.clone(),
+                state_service.clone(),
+                Some(alias_service.clone()),
+                Some(audit_service.clone()),
+                handlers,
+                alias_map,
+            )
+            .with_preset_service(preset_service.clone())
+            .with_project_resolver(project_resolver.clone())
+            .with_middleware(middleware),
+        );
 
         intent_manager.set_tool_executor(tool_executor.clone());
         runbook_manager.set_tool_executor(tool_executor.clone());
+        project_manager.set_tool_executor(tool_executor.clone());
 
         Ok(Self {
             logger,
@@ -349,6 +430,8 @@ impl App {
             runbook_manager,
             state_service,
             job_service,
+            audit_service,
+            postgres_manager,
             project_manager,
             target_manager,
             profile_manager,
@@ -360,6 +443,18 @@ impl App {
         })
     }
 
+    // Process teardown after the last call; the order lives in `shutdown::teardown`.
+    pub async fn close<T>(&self, supervised: &Supervised<T>) {
+        shutdown::teardown(
+            supervised,
+            &self.logger,
+            &self.job_service,
+            &self.audit_service,
+            || self.postgres_manager.close_pools(),
+        )
+        .await;
+    }
+
     pub fn description_snapshot(&self) -> Result<serde_json::Value, ToolError> {
         DescriptionService::snapshot(
             self.capability_service.as_ref(),
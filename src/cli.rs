@@ -1,8 +1,13 @@
 use crate::app::App;
 use crate::errors::{ToolError, ToolErrorKind};
+use crate::tooling::catalog::{effects_catalog, versions_catalog};
+use crate::tooling::topics::{capability_matrix, topic_index};
+use crate::utils::feature_flags::{is_readonly_enabled, SHUTDOWN_DRAIN_MS};
+use crate::utils::shutdown::{self, Supervised};
 use clap::{Args, Parser, Subcommand};
 use serde_json::{Map, Value};
 use std::path::PathBuf;
+use std::time::Duration;
 
 #[derive(Debug, Parser)]
 #[command(name = "infra", version, about = "CLI-first canonical infra operator")]
@@ -71,6 +76,7 @@ pub async fn run() -> i32 {
         Ok(app) => app,
         Err(err) => return emit_error(Value::Null, None, None, err),
     };
+    app.job_service.mark_one_shot();
     let snapshot = app.description_snapshot().unwrap_or(Value::Null);
 
     let (surface, routed) = match cli.command {
@@ -91,19 +97,57 @@ pub async fn run() -> i32 {
         Err(err) => return emit_error(snapshot, Some(surface), Some(&action), err),
     };
 
-    let result = if surface == "describe" {
-        handle_describe(&snapshot, &action)
-    } else {
-        execute_surface(&app, surface, payload).await
+    let call = async {
+        if surface == "describe" && action == "doctor" {
+            app.workspace_service.doctor(&payload)
+        } else if surface == "describe" {
+            handle_describe(&snapshot, &action, &payload)
+        } else {
+            execute_surface(&app, surface, payload).await
+        }
     };
+    let drain = Duration::from_millis(SHUTDOWN_DRAIN_MS.number());
+    let supervised = match shutdown::listen() {
+        Ok(signal) => shutdown::supervise(call, signal, drain).await,
+        Err(_) => shutdown::supervise(call, std::future::pending(), drain).await,
+    };
+    app.close(&supervised).await;
 
-    match result {
-        Ok(result) => emit_success(snapshot, surface, &action, result),
-        Err(err) => emit_error(snapshot, Some(surface), Some(&action), err),
-    }
+    let shutdown_code = supervised.exit_code();
+    let code = match supervised.outcome {
+        Some(Ok(result)) => emit_success(snapshot, surface, &action, result),
+        Some(Err(err)) => emit_error(snapshot, Some(surface), Some(&action), err),
+        None => emit_error(
+            snapshot,
+            Some(surface),
+            Some(&action),
+            forced_shutdown_error(&supervised),
+        ),
+    };
+    shutdown_code.unwrap_or(code)
+}
+
+fn forced_shutdown_error<T>(supervised: &Supervised<T>) -> ToolError {
+    ToolError::new(
+        ToolErrorKind::Retryable,
+        "SHUTDOWN_FORCED",
+        format!(
+            "{} arrived and the call did not finish within the {} ms drain window",
+            supervised.signal.unwrap_or("shutdown signal"),
+            supervised.drain_ms
+        ),
+    )
+    .with_hint(
+        "The call was dropped mid-flight; check its target state before re-running it, or raise INFRA_SHUTDOWN_DRAIN_MS."
+            .to_string(),
+    )
+    .with_details(serde_json::json!({
+        "signal": supervised.signal,
+        "drain_ms": supervised.drain_ms,
+    }))
 }
 
-fn handle_describe(snapshot: &Value, action: &str) -> Result<Value, ToolError> {
+fn handle_describe(snapshot: &Value, action: &str, payload: &Value) -> Result<Value, ToolError> {
     match action {
         "status" => Ok(serde_json::json!({
             "success": true,
@@ -112,9 +156,27 @@ fn handle_describe(snapshot: &Value, action: &str) -> Result<Value, ToolError> {
             "active_sources": snapshot.get("sources").cloned().unwrap_or(Value::Null),
             "loaded_at": snapshot.get("loaded_at").cloned().unwrap_or(Value::Null),
         })),
+        "effects" => Ok(serde_json::json!({
+            "success": true,
+            "readonly": is_readonly_enabled(),
+            "tools": effects_catalog(payload.get("tool").and_then(|v| v.as_str())),
+        })),
+        "help" => match payload.get("topic").and_then(|v| v.as_str()) {
+            Some(topic) => {
+                let mut matrix = capability_matrix(topic)?;
+                matrix["success"] = Value::Bool(true);
+                Ok(matrix)
+            }
+            None => Ok(serde_json::json!({"success": true, "topics": topic_index()})),
+        },
+        "versions" => {
+            let mut versions = versions_catalog();
+            versions["success"] = Value::Bool(true);
+            Ok(versions)
+        }
         _ => Err(
             ToolError::invalid_params(format!("unknown describe action '{}'", action))
-                .with_hint("Use: infra describe status".to_string()),
+                .with_hint("Use: infra describe status|effects|versions|help|doctor".to_string()),
         ),
     }
 }
@@ -320,6 +382,8 @@ fn normalize_action(surface: &str, action: &str) -> String {
         ("runbook", "get") => "runbook_get".to_string(),
         ("runbook", "list") => "runbook_list".to_string(),
         ("runbook", "run") => "runbook_run".to_string(),
+        ("runbook", "resume") => "runbook_resume".to_string(),
+        ("runbook", "runs") => "runbook_runs".to_string(),
         ("project", action) => action.to_string(),
         _ => action.to_string(),
     }
@@ -44,7 +44,14 @@ pub mod retry {
 
 pub mod pagination {
     pub const MAX_PAGES: usize = 10;
+    pub const MAX_PARALLEL: usize = 16;
+    // Upper bound on the pages planned from `total_path` when no max_pages is given.
+    pub const MAX_PLANNED_PAGES: usize = 1_000;
     pub const PAGE_SIZE: usize = 100;
+    pub const RATE_LIMIT_THRESHOLD: u64 = 1;
+    pub const RATE_LIMIT_MAX_WAIT_MS: u64 = 60_000;
+    pub const RATE_LIMIT_MAX_RETRIES: usize = 3;
+    pub const RATE_LIMIT_FALLBACK_WAIT_MS: u64 = 1_000;
 }
 
 pub mod cache {
@@ -69,6 +76,10 @@ pub mod crypto {
     pub const HASH_ALGORITHM: &str = "sha512";
 }
 
+pub mod runbook {
+    pub const CHECKPOINT_TTL_MS: u64 = 24 * 60 * 60 * 1_000;
+}
+
 pub mod rate_limit {
     pub const WINDOW_MS: u64 = 60_000;
     pub const MAX_REQUESTS: usize = 100;
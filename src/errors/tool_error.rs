@@ -25,6 +25,10 @@ pub struct ToolError {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub details: Option<Value>,
     pub retryable: bool,
+    // An array of calls that would fix or diagnose the failure, filled in from the remediation
+    // catalog. Boxed so that errors without any stay small.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_actions: Option<Box<Value>>,
 }
 
 impl ToolError {
@@ -36,6 +40,7 @@ impl ToolError {
             hint: None,
             details: None,
             retryable: matches!(kind, ToolErrorKind::Timeout | ToolErrorKind::Retryable),
+            next_actions: None,
         }
     }
 
@@ -49,6 +54,13 @@ impl ToolError {
         self
     }
 
+    pub fn next_actions(&self) -> &[Value] {
+        self.next_actions
+            .as_deref()
+            .and_then(|v| v.as_array())
+            .map_or(&[], Vec::as_slice)
+    }
+
     pub fn invalid_params(message: impl Into<String>) -> Self {
         Self::new(ToolErrorKind::InvalidParams, "INVALID_PARAMS", message)
     }
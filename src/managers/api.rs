@@ -2,37 +2,61 @@ use crate::constants::{
     cache as cache_constants, network as network_constants, pagination as pagination_constants,
     protocols::ALLOWED_HTTP, retry as retry_constants,
 };
-use crate::errors::ToolError;
+use crate::errors::{ToolError, ToolErrorKind};
+use crate::services::audit_forwarder::AuditTransport;
 use crate::services::cache::CacheService;
 use crate::services::logger::Logger;
-use crate::services::profile::ProfileService;
+use crate::services::profile::{ProfileService, UpsertOptions};
 use crate::services::project_resolver::ProjectResolver;
 use crate::services::secret_ref::SecretRefResolver;
 use crate::services::validation::Validation;
+use crate::utils::api_fixtures::{self, FixtureMode};
 use crate::utils::artifacts::{
-    build_tool_call_file_ref, create_artifact_write_stream, resolve_context_root,
+    build_run_file_ref, build_tool_call_file_ref, create_artifact_write_stream,
+    resolve_artifact_path, resolve_context_root, resolve_output_path, write_text_artifact,
+    ArtifactRef, ArtifactWriter,
 };
+use crate::utils::cert_check::{
+    check_certificate, parse_target, parse_target_str, CertCheckOptions, CertTarget,
+};
+use crate::utils::content_encoding::{BodyDecoder, ContentEncoding, ACCEPT_ENCODING};
 use crate::utils::data_path::get_path_value;
-use crate::utils::redact::redact_text;
+use crate::utils::dynamic_values::DynamicValues;
+use crate::utils::extract::{parse_extract_arg, Extract};
+use crate::utils::feature_flags::{self, is_allow_secret_export_enabled, is_api_record_enabled};
+use crate::utils::http_proxy::HttpProxyConfig;
+use crate::utils::http_tls::{classify_tls_error, HttpTlsConfig};
+use crate::utils::http_tuning::{observe_response, record_client_built, HttpTuning};
+use crate::utils::redact::{redact_object, redact_text};
+use crate::utils::segmented_download::{
+    content_range_start, parse_expected_sha256, parse_segments, plan_ranges, ranged_length,
+    throughput,
+};
+use crate::utils::smoke_sla::{SampleSummary, SmokeSample, SmokeSampling, MAX_SAMPLE_RESULTS};
+use crate::utils::ssrf::{denial_from_error, SsrfPolicy};
 use crate::utils::stability::{
     apply_stability_source, classify_tool_error, compute_backoff_delay_ms, should_emit_stability,
     StabilityClassification, StabilityDefaults, StabilityMeta, StabilityMode, StabilityPolicy,
     StabilityPreset,
 };
 use crate::utils::tool_errors::unknown_action_error;
-use crate::utils::user_paths::expand_home_path;
+use crate::utils::usage::{self, Counter};
 use base64::Engine;
 use futures::StreamExt;
 use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
 use reqwest::{Client, Method};
 use serde_json::Value;
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
-use tokio::io::AsyncWriteExt;
+use tokio::io::{AsyncSeekExt, AsyncWriteExt};
 use url::Url;
 
 const API_PROFILE_TYPE: &str = "api";
+const API_RECORDING_FILENAME: &str = "api_recording.har.json";
 pub(crate) const API_ACTIONS: &[&str] = &[
     "profile_upsert",
     "profile_get",
@@ -43,8 +67,19 @@ pub(crate) const API_ACTIONS: &[&str] = &[
     "download",
     "check",
     "smoke_http",
+    "recording_get",
+    "fixtures_list",
+    "fixtures_clear",
+    "cert_check",
 ];
 
+const CERT_CHECK_DEFAULT_WARN_DAYS: i64 = 30;
+const CERT_CHECK_DEFAULT_CONCURRENCY: usize = 8;
+const CERT_CHECK_MAX_CONCURRENCY: usize = 32;
+const CERT_CHECK_MAX_TARGETS: usize = 500;
+// Part of the tool-call budget sampled smoke_http keeps back for building its response.
+const SMOKE_BUDGET_MARGIN_MS: u64 = 1_000;
+
 #[derive(Clone)]
 pub struct ApiManager {
     logger: Logger,
@@ -53,11 +88,25 @@ pub struct ApiManager {
     cache_service: Option<Arc<CacheService>>,
     project_resolver: Option<Arc<ProjectResolver>>,
     secret_ref_resolver: Option<Arc<SecretRefResolver>>,
-    clients: Arc<Mutex<HashMap<(bool, bool), Client>>>,
+    clients: Arc<Mutex<HashMap<ClientKey, Client>>>,
     token_cache: Arc<Mutex<HashMap<String, CachedToken>>>,
     circuits: Arc<Mutex<HashMap<String, Instant>>>,
+    recording_lock: Arc<Mutex<()>>,
+    host_slots: Arc<Mutex<HashMap<String, Arc<tokio::sync::Semaphore>>>>,
 }
 
+// (follow_redirects, insecure_ok, TLS identity/CA fingerprint, SSRF allow list, proxy,
+// connection tuning, reqwest decodes content-encoding)
+type ClientKey = (
+    bool,
+    bool,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    bool,
+);
+
 #[derive(Clone)]
 struct CachedToken {
     token: String,
@@ -91,6 +140,7 @@ pub(crate) struct RetryPolicy {
     pub(crate) retry_on_network_error: bool,
     pub(crate) respect_retry_after: bool,
     pub(crate) circuit_open_ms: u64,
+    pub(crate) regenerate_on_retry: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -101,10 +151,226 @@ struct PaginationConfig {
     size: usize,
     start: i64,
     max_pages: usize,
+    max_pages_set: bool,
+    parallel: usize,
+    total_path: Option<String>,
     item_path: Option<String>,
     cursor_path: Option<String>,
     link_rel: String,
     stop_on_empty: bool,
+    min_interval_ms: u64,
+    rate_limit: Option<RateLimitPacing>,
+}
+
+impl PaginationConfig {
+    // Sets the page/offset and size query parameters for the page at `index` (0-based).
+    fn inject_page(&self, request_args: &mut Value, index: usize) {
+        let value = if self.kind == "offset" {
+            self.start + (index * self.size) as i64
+        } else {
+            self.start + index as i64
+        };
+        inject_query_param(request_args, &self.param, value.into());
+        inject_query_param(request_args, &self.size_param, self.size.into());
+    }
+
+    // Adds a fetched page (and its items) to the result; false when pagination stops after it.
+    fn collect_page(
+        &self,
+        page: Value,
+        pages: &mut Vec<Value>,
+        items: &mut Vec<Value>,
+    ) -> Result<bool, ToolError> {
+        let throttled = page.get("status").and_then(|v| v.as_u64()) == Some(429);
+        let page_items = match self.item_path.as_deref() {
+            Some(item_path) => Some(get_path_value(
+                &page,
+                item_path,
+                false,
+                Some(Value::Array(Vec::new())),
+            )?),
+            None => None,
+        };
+        pages.push(page);
+        if throttled && self.rate_limit.is_some() {
+            return Ok(false);
+        }
+        match page_items {
+            Some(Value::Array(arr)) => {
+                if self.stop_on_empty && arr.is_empty() {
+                    return Ok(false);
+                }
+                items.extend(arr);
+            }
+            Some(_) if self.stop_on_empty => return Ok(false),
+            _ => {}
+        }
+        Ok(true)
+    }
+
+    fn is_empty_page(&self, page: &Value) -> bool {
+        let Some(item_path) = self.item_path.as_deref() else {
+            return false;
+        };
+        match get_path_value(page, item_path, false, Some(Value::Array(Vec::new()))) {
+            Ok(Value::Array(arr)) => arr.is_empty(),
+            _ => true,
+        }
+    }
+
+    // Pages to fetch for `total` items, bounded by max_pages (or MAX_PLANNED_PAGES without it).
+    fn planned_pages(&self, total: u64) -> usize {
+        let cap = if self.max_pages_set {
+            self.max_pages
+        } else {
+            pagination_constants::MAX_PLANNED_PAGES
+        };
+        (total as usize)
+            .div_ceil(self.size.max(1))
+            .clamp(1, cap.max(1))
+    }
+}
+
+// Shared by the page fetches of a parallel paginate: request starts are spaced
+// `min_interval_ms` apart and held back while a rate-limit wait asked for by any response runs.
+struct PagePacer {
+    min_interval: Duration,
+    next_at: tokio::sync::Mutex<Option<Instant>>,
+    waits: AtomicU64,
+    wait_ms_total: AtomicU64,
+    rate_limited: AtomicBool,
+    in_flight: AtomicUsize,
+    peak_in_flight: AtomicUsize,
+}
+
+impl PagePacer {
+    fn new(min_interval_ms: u64) -> Self {
+        Self {
+            min_interval: Duration::from_millis(min_interval_ms),
+            next_at: tokio::sync::Mutex::new(None),
+            waits: AtomicU64::new(0),
+            wait_ms_total: AtomicU64::new(0),
+            rate_limited: AtomicBool::new(false),
+            in_flight: AtomicUsize::new(0),
+            peak_in_flight: AtomicUsize::new(0),
+        }
+    }
+
+    async fn turn(&self) {
+        let mut next_at = self.next_at.lock().await;
+        if let Some(at) = *next_at {
+            let wait = at.saturating_duration_since(Instant::now());
+            if !wait.is_zero() {
+                self.waits.fetch_add(1, Ordering::Relaxed);
+                self.wait_ms_total
+                    .fetch_add(wait.as_millis() as u64, Ordering::Relaxed);
+                tokio::time::sleep(wait).await;
+            }
+        }
+        *next_at = Some(Instant::now() + self.min_interval);
+    }
+
+    async fn hold(&self, wait_ms: u64) {
+        self.rate_limited.store(true, Ordering::Relaxed);
+        let until = Instant::now() + Duration::from_millis(wait_ms);
+        let mut next_at = self.next_at.lock().await;
+        *next_at = Some(next_at.map_or(until, |at| at.max(until)));
+    }
+
+    fn to_value(&self) -> Value {
+        serde_json::json!({
+            "waits": self.waits.load(Ordering::Relaxed),
+            "wait_ms_total": self.wait_ms_total.load(Ordering::Relaxed),
+            "rate_limited": self.rate_limited.load(Ordering::Relaxed),
+        })
+    }
+}
+
+// What the ranges of one segmented download share: the request, the pre-allocated `.part` file
+// and the per-host slots.
+struct SegmentFetch<'a> {
+    client: &'a Client,
+    url: &'a str,
+    headers: &'a HeaderMap,
+    timeout: Option<Duration>,
+    tls: Option<&'a HttpTlsConfig>,
+    path: &'a Path,
+    policy: &'a RetryPolicy,
+    slots: &'a tokio::sync::Semaphore,
+    in_flight: AtomicUsize,
+    peak_in_flight: AtomicUsize,
+}
+
+impl SegmentFetch<'_> {
+    // Writes bytes `from..=end` at their offset, counting each written byte into `written` so a
+    // retry can continue after them.
+    async fn range(&self, from: u64, end: u64, written: &mut u64) -> Result<(), ToolError> {
+        let mut req = self
+            .client
+            .get(self.url)
+            .headers(self.headers.clone())
+            .header(reqwest::header::RANGE, format!("bytes={}-{}", from, end));
+        if let Some(timeout) = self.timeout {
+            req = req.timeout(timeout);
+        }
+        let response = req
+            .send()
+            .await
+            .map_err(|err| map_request_error(err, self.tls))?;
+        let status = response.status().as_u16();
+        if status != 206 || content_range_start(response.headers()) != Some(from) {
+            let message = format!("Range bytes={}-{} returned HTTP {}", from, end, status);
+            if self.policy.status_codes.contains(&status) {
+                return Err(ToolError::retryable(message));
+            }
+            return Err(
+                ToolError::new(ToolErrorKind::Conflict, "RANGE_NOT_SERVED", message)
+                    .with_hint("Download without segments to fetch the file as a single stream."),
+            );
+        }
+
+        let write_error = |err: std::io::Error| {
+            ToolError::internal(format!("Failed to write download chunk: {}", err))
+        };
+        let mut file = tokio::fs::OpenOptions::new()
+            .write(true)
+            .open(self.path)
+            .await
+            .map_err(write_error)?;
+        file.seek(std::io::SeekFrom::Start(from))
+            .await
+            .map_err(write_error)?;
+        let mut remaining = end + 1 - from;
+        let mut stream = response.bytes_stream();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(map_reqwest_error)?;
+            usage::record(Counter::HttpBodyReadBytes, chunk.len() as u64);
+            let take = (chunk.len() as u64).min(remaining) as usize;
+            file.write_all(&chunk[..take]).await.map_err(write_error)?;
+            remaining -= take as u64;
+            *written += take as u64;
+            if remaining == 0 {
+                break;
+            }
+        }
+        file.flush().await.map_err(write_error)?;
+        if remaining > 0 {
+            return Err(ToolError::retryable(format!(
+                "Range bytes={}-{} ended {} bytes early",
+                from, end, remaining
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+struct RateLimitPacing {
+    remaining_header: String,
+    reset_header: String,
+    threshold: u64,
+    max_wait_ms: u64,
+    max_retries: usize,
 }
 
 #[derive(Clone, Debug)]
@@ -116,12 +382,17 @@ pub(crate) struct ApiProfile {
     pub(crate) retry: Option<Value>,
     pub(crate) pagination: Option<Value>,
     pub(crate) cache: Option<Value>,
+    pub(crate) tls: Option<Value>,
+    pub(crate) proxy: Option<Value>,
 }
 
 #[derive(Debug)]
 struct BodyCapture {
     buffer: Vec<u8>,
+    // Decoded bytes; `wire_bytes` is what the connection carried.
     body_read_bytes: u64,
+    wire_bytes: u64,
+    encoding: BodyEncoding,
     body_captured_bytes: u64,
     body_truncated: bool,
     body_ref: Option<Value>,
@@ -147,6 +418,8 @@ impl ApiManager {
             clients: Arc::new(Mutex::new(HashMap::new())),
             token_cache: Arc::new(Mutex::new(HashMap::new())),
             circuits: Arc::new(Mutex::new(HashMap::new())),
+            recording_lock: Arc::new(Mutex::new(())),
+            host_slots: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
@@ -158,11 +431,30 @@ impl ApiManager {
             "profile_get" => self.profile_get(&args),
             "profile_list" => self.profile_list(),
             "profile_delete" => self.profile_delete(&args),
-            "request" => self.request(args).await,
-            "paginate" => self.paginate(args).await,
+            "request" | "paginate" => {
+                // Parsed before any request is sent so a bad expression fails fast.
+                let extract = parse_extract_arg(args.get("extract"))?;
+                let keep_raw = args
+                    .get("keep_raw")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+                let result = if action_name == "request" {
+                    self.request(args).await?
+                } else {
+                    self.paginate(args).await?
+                };
+                match extract {
+                    Some(extract) => apply_response_extract(result, &extract, keep_raw),
+                    None => Ok(result),
+                }
+            }
             "download" => self.download(args).await,
             "check" => self.check_api(args).await,
             "smoke_http" => self.smoke_http(args).await,
+            "recording_get" => self.recording_get(&args),
+            "fixtures_list" => self.fixtures_list(&args),
+            "fixtures_clear" => self.fixtures_clear(&args),
+            "cert_check" => self.cert_check(&args).await,
             _ => Err(unknown_action_error("api", action, API_ACTIONS)),
         }
     }
@@ -180,11 +472,27 @@ impl ApiManager {
         let headers = self.validation.ensure_headers(args.get("headers"))?;
         let (data_auth, secrets) = split_auth(args.get("auth"));
         let (provider_data, provider_secrets) = split_auth_provider(args.get("auth_provider"));
+        let (tls_data, tls_secrets) = split_tls(args.get("tls"));
+        if !matches!(
+            args.get("proxy"),
+            None | Some(Value::Null | Value::Bool(false) | Value::String(_) | Value::Object(_))
+        ) {
+            return Err(ToolError::invalid_params(
+                "proxy must be false, a URL, or an object { url, no_proxy }",
+            ));
+        }
+        let (proxy_data, proxy_secrets) = split_proxy(args.get("proxy"));
 
         let mut data = serde_json::Map::new();
         if let Some(base) = base {
             data.insert("base_url".to_string(), Value::String(base));
         }
+        if let Some(openapi_url) = args.get("openapi_url").and_then(|v| v.as_str()) {
+            data.insert(
+                "openapi_url".to_string(),
+                Value::String(openapi_url.to_string()),
+            );
+        }
         if !headers.is_empty() {
             data.insert("headers".to_string(), Value::Object(headers));
         }
@@ -194,6 +502,12 @@ impl ApiManager {
         if let Some(provider) = provider_data {
             data.insert("auth_provider".to_string(), provider);
         }
+        if let Some(tls) = tls_data {
+            data.insert("tls".to_string(), tls);
+        }
+        if let Some(proxy) = proxy_data {
+            data.insert("proxy".to_string(), proxy);
+        }
         if let Some(retry) = args.get("retry") {
             data.insert("retry".to_string(), retry.clone());
         }
@@ -215,6 +529,18 @@ impl ApiManager {
         if let Some(redirect) = args.get("redirect") {
             data.insert("redirect".to_string(), redirect.clone());
         }
+        if let Some(decompress) = args.get("decompress") {
+            decompress_requested(args, None)?;
+            data.insert("decompress".to_string(), decompress.clone());
+        }
+        if let Some(ssrf) = args.get("ssrf") {
+            SsrfPolicy::resolve(Some(ssrf))?;
+            data.insert("ssrf".to_string(), ssrf.clone());
+        }
+        if let Some(http) = args.get("http").filter(|v| !v.is_null()) {
+            HttpTuning::resolve(Some(http), None)?;
+            data.insert("http".to_string(), http.clone());
+        }
 
         let mut secrets_map = serde_json::Map::new();
         if let Some(obj) = secrets {
@@ -223,21 +549,29 @@ impl ApiManager {
         if let Some(obj) = provider_secrets {
             secrets_map.extend(obj);
         }
+        if let Some(obj) = tls_secrets {
+            secrets_map.extend(obj);
+        }
+        if let Some(obj) = proxy_secrets {
+            secrets_map.extend(obj);
+        }
 
-        let mut config = serde_json::Map::new();
-        config.insert(
-            "type".to_string(),
-            Value::String(API_PROFILE_TYPE.to_string()),
-        );
-        config.insert("data".to_string(), Value::Object(data));
-        if !secrets_map.is_empty() {
-            config.insert("secrets".to_string(), Value::Object(secrets_map));
+        let options = UpsertOptions::from_args(args)?;
+        let mut draft = self
+            .profile_service
+            .upsert_draft(&name, API_PROFILE_TYPE, &options)?;
+        for (key, value) in &data {
+            draft.overlay_data(key, value);
+        }
+        for (key, value) in &secrets_map {
+            draft.overlay_secret(key, value);
         }
 
+        // API profiles have no connectivity check; `tested` keeps the response shape uniform.
         let profile = self
             .profile_service
-            .set_profile(&name, &Value::Object(config))?;
-        Ok(serde_json::json!({"success": true, "profile": profile}))
+            .save_draft(&name, API_PROFILE_TYPE, &draft)?;
+        Ok(serde_json::json!({"success": true, "tested": false, "profile": profile}))
     }
 
     fn profile_get(&self, args: &Value) -> Result<Value, ToolError> {
@@ -253,11 +587,7 @@ impl ApiManager {
             .get("include_secrets")
             .and_then(|v| v.as_bool())
             .unwrap_or(false);
-        let allow = std::env::var("INFRA_ALLOW_SECRET_EXPORT")
-            .ok()
-            .filter(|v| v.trim() == "1" || v.trim().eq_ignore_ascii_case("true"))
-            .is_some();
-        if include_secrets && allow {
+        if include_secrets && is_allow_secret_export_enabled() {
             return Ok(serde_json::json!({"success": true, "profile": profile}));
         }
         let secret_keys = profile
@@ -306,7 +636,12 @@ impl ApiManager {
         };
 
         let resolved_provider = self
-            .resolve_auth_provider(auth_provider, profile.name.as_deref(), &args)
+            .resolve_auth_provider(
+                auth_provider,
+                profile.name.as_deref(),
+                profile.proxy.as_ref(),
+                &args,
+            )
             .await?;
         if resolved_provider.is_some() {
             auth = resolved_provider;
@@ -317,7 +652,11 @@ impl ApiManager {
         if cache_policy.enabled {
             if let Some(cache_service) = self.cache_service.as_ref() {
                 cache_key = cache_policy.key.clone().or_else(|| {
-                    let config = self.build_request_config(&args, &profile, auth.as_ref(), None).ok()?;
+                    let overrides = RequestOverrides {
+                        dynamic: Some(DynamicValues::verbatim()),
+                        ..Default::default()
+                    };
+                    let config = self.build_request_config(&args, &profile, auth.as_ref(), Some(overrides)).ok()?;
                     let payload = serde_json::json!({
                         "url": config.url,
                         "method": config.method.as_str(),
@@ -405,7 +744,12 @@ impl ApiManager {
             profile.auth_provider.clone()
         };
         let resolved_provider = self
-            .resolve_auth_provider(auth_provider, profile.name.as_deref(), &args)
+            .resolve_auth_provider(
+                auth_provider,
+                profile.name.as_deref(),
+                profile.proxy.as_ref(),
+                &args,
+            )
             .await?;
         if resolved_provider.is_some() {
             auth = resolved_provider;
@@ -413,40 +757,50 @@ impl ApiManager {
 
         let pagination =
             self.normalize_pagination(args.get("pagination"), profile.pagination.as_ref())?;
+        if pagination.parallel > 1 {
+            return self
+                .paginate_parallel(&args, &profile, auth.as_ref(), &pagination)
+                .await;
+        }
+        let started = Instant::now();
         let mut pages = Vec::new();
         let mut items = Vec::new();
+        let mut page_limit = pagination.max_pages;
+        let mut total = Value::Null;
 
         let mut cursor = pagination.start;
-        let mut page_number = pagination.start;
-        let mut offset = pagination.start;
         let mut next_url = args
             .get("url")
             .and_then(|v| v.as_str())
             .map(|s| s.to_string());
 
-        for _ in 0..pagination.max_pages {
+        let mut waits = 0u64;
+        let mut wait_ms_total = 0u64;
+        let mut rate_limited = false;
+        let mut rate_limit_retries = 0usize;
+        let mut pending_wait_ms = 0u64;
+        let mut last_request_at: Option<Instant> = None;
+
+        while pages.len() < page_limit {
+            if let Some(last) = last_request_at {
+                let elapsed = last.elapsed().as_millis() as u64;
+                let wait_ms =
+                    pending_wait_ms.max(pagination.min_interval_ms.saturating_sub(elapsed));
+                if wait_ms > 0 {
+                    waits += 1;
+                    wait_ms_total += wait_ms;
+                    tokio::time::sleep(Duration::from_millis(wait_ms)).await;
+                }
+            }
+            pending_wait_ms = 0;
+
             let mut request_args = args.clone();
             if let Value::Object(map) = &mut request_args {
                 map.remove("pagination");
             }
 
             match pagination.kind.as_str() {
-                "page" => {
-                    inject_query_param(&mut request_args, &pagination.param, page_number.into());
-                    inject_query_param(
-                        &mut request_args,
-                        &pagination.size_param,
-                        pagination.size.into(),
-                    );
-                }
-                "offset" => {
-                    inject_query_param(&mut request_args, &pagination.param, offset.into());
-                    inject_query_param(
-                        &mut request_args,
-                        &pagination.size_param,
-                        pagination.size.into(),
-                    );
-                }
+                "page" | "offset" => pagination.inject_page(&mut request_args, pages.len()),
                 "cursor" => {
                     if cursor != 0 {
                         inject_query_param(&mut request_args, &pagination.param, cursor.into());
@@ -476,28 +830,36 @@ impl ApiManager {
             let response = self
                 .request_with_retry(&request_args, &profile, auth.as_ref())
                 .await?;
-            pages.push(response.clone());
-
-            if let Some(item_path) = pagination.item_path.as_deref() {
-                let page_items =
-                    get_path_value(&response, item_path, false, Some(Value::Array(Vec::new())))?;
-                if let Some(arr) = page_items.as_array() {
-                    if pagination.stop_on_empty && arr.is_empty() {
-                        break;
-                    }
-                    items.extend(arr.iter().cloned());
-                } else if pagination.stop_on_empty {
-                    break;
+            last_request_at = Some(Instant::now());
+            let throttled = response.get("status").and_then(|v| v.as_u64()) == Some(429);
+            if let Some(pacing) = pagination.rate_limit.as_ref() {
+                if throttled {
+                    rate_limited = true;
+                }
+                if let Some(wait_ms) = rate_limit_wait_ms(&response, pacing) {
+                    rate_limited = true;
+                    pending_wait_ms = wait_ms;
                 }
+                // A page still throttled after the retry policy gave up is fetched again after
+                // the wait instead of being counted as consumed.
+                if throttled && rate_limit_retries < pacing.max_retries {
+                    rate_limit_retries += 1;
+                    continue;
+                }
+                rate_limit_retries = 0;
+            }
+            if pages.is_empty() {
+                if let Some(total_path) = pagination.total_path.as_deref() {
+                    let count = read_total_count(&response, total_path)?;
+                    total = count.into();
+                    page_limit = page_limit.min(pagination.planned_pages(count));
+                }
+            }
+            if !pagination.collect_page(response.clone(), &mut pages, &mut items)? {
+                break;
             }
 
             match pagination.kind.as_str() {
-                "page" => {
-                    page_number += 1;
-                }
-                "offset" => {
-                    offset += pagination.size as i64;
-                }
                 "cursor" => {
                     let cursor_path = pagination.cursor_path.as_deref().ok_or_else(|| {
                         ToolError::invalid_params(
@@ -541,16 +903,163 @@ impl ApiManager {
             "pages": pages,
             "page_count": pages.len(),
             "next_cursor": if pagination.kind == "cursor" { Value::Number(cursor.into()) } else { Value::Null },
+            "pacing": {
+                "waits": waits,
+                "wait_ms_total": wait_ms_total,
+                "rate_limited": rate_limited,
+            },
+            "concurrency": {"requested": 1, "effective": 1},
+            "duration_ms": started.elapsed().as_millis() as u64,
+        });
+        if let Value::Object(map) = &mut result {
+            if pagination.total_path.is_some() {
+                map.insert("total".to_string(), total);
+            }
+            if pagination.item_path.is_some() {
+                map.insert("items".to_string(), Value::Array(items));
+            }
+        }
+        Ok(result)
+    }
+
+    // Page and offset pagination: every page's parameters are known up front (from max_pages, or
+    // from the total count in the first response), so up to `parallel` pages are in flight at
+    // once. Pages are merged in order; nothing past an empty page is requested or kept.
+    async fn paginate_parallel(
+        &self,
+        args: &Value,
+        profile: &ApiProfile,
+        auth: Option<&Value>,
+        pagination: &PaginationConfig,
+    ) -> Result<Value, ToolError> {
+        let started = Instant::now();
+        let pacer = PagePacer::new(pagination.min_interval_ms);
+        let mut request_base = args.clone();
+        if let Value::Object(map) = &mut request_base {
+            map.remove("pagination");
+        }
+        let fetch_page = |index: usize| {
+            let mut request_args = request_base.clone();
+            pagination.inject_page(&mut request_args, index);
+            let pacer = &pacer;
+            async move {
+                self.fetch_paced_page(&request_args, profile, auth, pagination, pacer)
+                    .await
+            }
+        };
+
+        let mut pages = Vec::new();
+        let mut items = Vec::new();
+        let mut planned = pagination.max_pages;
+        let mut total = Value::Null;
+        let mut more = true;
+        if let Some(total_path) = pagination.total_path.as_deref() {
+            let first = fetch_page(0).await?;
+            let count = read_total_count(&first, total_path)?;
+            total = count.into();
+            planned = pagination.planned_pages(count);
+            more = pagination.collect_page(first, &mut pages, &mut items)?;
+        }
+
+        let first_empty = AtomicUsize::new(usize::MAX);
+        let mut skipped = 0usize;
+        if more && pages.len() < planned {
+            let mut fetches = futures::stream::iter(pages.len()..planned)
+                .map(|index| {
+                    let first_empty = &first_empty;
+                    let fetch = fetch_page(index);
+                    async move {
+                        if pagination.stop_on_empty && first_empty.load(Ordering::Relaxed) < index {
+                            return Ok(None);
+                        }
+                        let page = fetch.await?;
+                        if pagination.stop_on_empty && pagination.is_empty_page(&page) {
+                            first_empty.fetch_min(index, Ordering::Relaxed);
+                        }
+                        Ok::<_, ToolError>(Some(page))
+                    }
+                })
+                .buffered(pagination.parallel);
+            while let Some(page) = fetches.next().await {
+                let Some(page) = page? else {
+                    skipped += 1;
+                    continue;
+                };
+                if !pagination.collect_page(page, &mut pages, &mut items)? {
+                    break;
+                }
+            }
+        }
+
+        let success = pages.iter().all(|page| {
+            page.get("success")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(true)
+        });
+        let mut result = serde_json::json!({
+            "success": success,
+            "pages": pages,
+            "page_count": pages.len(),
+            "planned_pages": planned,
+            "requests_skipped": skipped,
+            "next_cursor": Value::Null,
+            "pacing": pacer.to_value(),
+            "concurrency": {
+                "requested": pagination.parallel,
+                "effective": pacer.peak_in_flight.load(Ordering::Relaxed),
+            },
+            "duration_ms": started.elapsed().as_millis() as u64,
         });
-        if pagination.item_path.is_some() {
-            if let Value::Object(map) = &mut result {
+        if let Value::Object(map) = &mut result {
+            if pagination.total_path.is_some() {
+                map.insert("total".to_string(), total);
+            }
+            if pagination.item_path.is_some() {
                 map.insert("items".to_string(), Value::Array(items));
             }
         }
         Ok(result)
     }
 
+    // One page with the retry policy and rate-limit pacing applied: a page still throttled after
+    // the retry policy is fetched again after the wait, up to rate_limit.max_retries times.
+    async fn fetch_paced_page(
+        &self,
+        request_args: &Value,
+        profile: &ApiProfile,
+        auth: Option<&Value>,
+        pagination: &PaginationConfig,
+        pacer: &PagePacer,
+    ) -> Result<Value, ToolError> {
+        let mut retries = 0usize;
+        loop {
+            pacer.turn().await;
+            let in_flight = pacer.in_flight.fetch_add(1, Ordering::Relaxed) + 1;
+            pacer.peak_in_flight.fetch_max(in_flight, Ordering::Relaxed);
+            let response = self.request_with_retry(request_args, profile, auth).await;
+            pacer.in_flight.fetch_sub(1, Ordering::Relaxed);
+            let response = response?;
+            let Some(pacing) = pagination.rate_limit.as_ref() else {
+                return Ok(response);
+            };
+            let throttled = response.get("status").and_then(|v| v.as_u64()) == Some(429);
+            if throttled {
+                pacer.rate_limited.store(true, Ordering::Relaxed);
+            }
+            if let Some(wait_ms) = rate_limit_wait_ms(&response, pacing) {
+                pacer.hold(wait_ms).await;
+            }
+            if throttled && retries < pacing.max_retries {
+                retries += 1;
+                continue;
+            }
+            return Ok(response);
+        }
+    }
+
     async fn download(&self, args: Value) -> Result<Value, ToolError> {
+        let segments = parse_segments(args.get("segments"))?;
+        let expect_sha256 = parse_expected_sha256(args.get("expect_sha256"))?;
         let profile = self
             .resolve_profile(args.get("profile_name"), &args)
             .await?;
@@ -564,7 +1073,12 @@ impl ApiManager {
             profile.auth_provider.clone()
         };
         let resolved_provider = self
-            .resolve_auth_provider(auth_provider, profile.name.as_deref(), &args)
+            .resolve_auth_provider(
+                auth_provider,
+                profile.name.as_deref(),
+                profile.proxy.as_ref(),
+                &args,
+            )
             .await?;
         if resolved_provider.is_some() {
             auth = resolved_provider;
@@ -585,6 +1099,42 @@ impl ApiManager {
             }
         }
 
+        let mut fallback = None;
+        if segments > 1 {
+            let segmented = self
+                .download_segmented(
+                    &args,
+                    &profile,
+                    auth.as_ref(),
+                    segments,
+                    &policy,
+                    expect_sha256.as_deref(),
+                )
+                .await;
+            match segmented {
+                Ok(Ok(result)) => {
+                    self.close_circuit(&circuit_key);
+                    return Ok(result);
+                }
+                Ok(Err(reason)) => {
+                    fallback = Some(serde_json::json!({
+                        "requested": segments,
+                        "used": false,
+                        "reason": reason,
+                    }));
+                }
+                Err(err) => {
+                    if policy.enabled
+                        && policy.circuit_open_ms > 0
+                        && classify_tool_error(&err) == StabilityClassification::Transient
+                    {
+                        self.open_circuit(&circuit_key, policy.circuit_open_ms);
+                    }
+                    return Err(err);
+                }
+            }
+        }
+
         let mut attempt = 0;
         let mut last_error: Option<ToolError> = None;
         let max_attempts = if policy.enabled {
@@ -597,8 +1147,12 @@ impl ApiManager {
             attempt += 1;
             match self.download_once(&args, &profile, auth.as_ref()).await {
                 Ok(response) => {
-                    let should_retry =
-                        policy.enabled && self.should_retry_response(&response, &policy);
+                    // A replayed response never changes, so retrying it cannot help.
+                    let replayed =
+                        response.pointer("/fixture/mode") == Some(&Value::from("replay"));
+                    let should_retry = policy.enabled
+                        && !replayed
+                        && self.should_retry_response(&response, &policy);
                     if !should_retry || attempt >= max_attempts {
                         if should_retry && policy.circuit_open_ms > 0 {
                             self.open_circuit(&circuit_key, policy.circuit_open_ms);
@@ -630,6 +1184,9 @@ impl ApiManager {
                             if should_emit_stability(&stability, debug_requested) {
                                 map.insert("stability".to_string(), stability.to_value());
                             }
+                            if let Some(fallback) = fallback.take() {
+                                map.insert("segmented".to_string(), fallback);
+                            }
                         }
                         return Ok(out);
                     }
@@ -700,7 +1257,317 @@ impl ApiManager {
         }
     }
 
+    // Targets plus the tls settings (CA bundle, client identity) to handshake with.
+    async fn cert_check_targets(
+        &self,
+        args: &Value,
+    ) -> Result<(String, Vec<(CertTarget, Option<HttpTlsConfig>)>), ToolError> {
+        let mut targets = Vec::new();
+        let explicit = match (args.get("targets"), args.get("url")) {
+            (Some(Value::Array(items)), _) => Some(items.clone()),
+            (Some(other), _) if !other.is_null() => {
+                return Err(ToolError::invalid_params(
+                    "targets must be an array of URLs, host:port strings or objects",
+                ));
+            }
+            (_, Some(url)) if !url.is_null() => Some(vec![url.clone()]),
+            _ => None,
+        };
+        let source = if let Some(items) = explicit {
+            let tls_value = self.resolve_tls(None, args).await?;
+            let tls = HttpTlsConfig::resolve(tls_value.as_ref())?;
+            for item in &items {
+                targets.push((parse_target(item)?, tls.clone()));
+            }
+            "targets".to_string()
+        } else {
+            let (source, profiles) = match args.get("profiles") {
+                Some(Value::Array(items)) => {
+                    let mut names = Vec::new();
+                    for item in items {
+                        let name = item.as_str().ok_or_else(|| {
+                            ToolError::invalid_params("profiles must be an array of profile names")
+                        })?;
+                        names.push(self.validation.ensure_identifier(name, "profiles")?);
+                    }
+                    ("profiles".to_string(), names)
+                }
+                Some(Value::String(all)) if all == "all" => {
+                    let listed = self.profile_service.list_profiles(Some(API_PROFILE_TYPE))?;
+                    let names = listed
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|p| p.get("name").and_then(|v| v.as_str()))
+                        .map(str::to_string)
+                        .collect();
+                    ("all".to_string(), names)
+                }
+                Some(other) if !other.is_null() => {
+                    return Err(ToolError::invalid_params(
+                        "profiles must be an array of profile names or \"all\"",
+                    ));
+                }
+                _ => {
+                    let project = match &self.project_resolver {
+                        Some(resolver) => resolver.project_targets(args).await?,
+                        None => None,
+                    };
+                    let Some((project, project_targets)) = project else {
+                        return Err(ToolError::invalid_params(
+                            "cert_check needs targets, url, profiles or a project",
+                        )
+                        .with_hint(
+                            "Example: { action: 'cert_check', targets: ['https://api.internal', 'db.internal:5433'] }"
+                                .to_string(),
+                        ));
+                    };
+                    let mut names = Vec::new();
+                    for target in project_targets.values() {
+                        if let Some(url) = target.get("api_base_url").and_then(|v| v.as_str()) {
+                            let tls_value = self.resolve_tls(None, args).await?;
+                            targets.push((
+                                parse_target_str(url)?,
+                                HttpTlsConfig::resolve(tls_value.as_ref())?,
+                            ));
+                        } else if let Some(name) =
+                            target.get("api_profile").and_then(|v| v.as_str())
+                        {
+                            names.push(self.validation.ensure_identifier(name, "api_profile")?);
+                        }
+                    }
+                    (format!("project:{}", project), names)
+                }
+            };
+            for name in profiles {
+                let profile = self
+                    .resolve_profile(Some(&Value::String(name.clone())), args)
+                    .await?;
+                let Some(base_url) = profile.data.get("base_url").and_then(|v| v.as_str()) else {
+                    continue;
+                };
+                targets.push((
+                    parse_target_str(base_url)?,
+                    HttpTlsConfig::resolve(profile.tls.as_ref())?,
+                ));
+            }
+            source
+        };
+        let mut seen = std::collections::HashSet::new();
+        targets.retain(|(target, _)| {
+            seen.insert((target.host.clone(), target.port, target.servername.clone()))
+        });
+        if targets.is_empty() {
+            return Err(ToolError::invalid_params(format!(
+                "cert_check found no endpoints ({})",
+                source
+            )));
+        }
+        if targets.len() > CERT_CHECK_MAX_TARGETS {
+            return Err(ToolError::invalid_params(format!(
+                "cert_check accepts at most {} targets (got {})",
+                CERT_CHECK_MAX_TARGETS,
+                targets.len()
+            )));
+        }
+        Ok((source, targets))
+    }
+
+    async fn cert_check(&self, args: &Value) -> Result<Value, ToolError> {
+        let (source, targets) = self.cert_check_targets(args).await?;
+        let warn_days = args
+            .get("warn_days")
+            .and_then(|v| v.as_i64())
+            .unwrap_or(CERT_CHECK_DEFAULT_WARN_DAYS);
+        let concurrency = args
+            .get("concurrency")
+            .and_then(|v| v.as_u64())
+            .map(|v| (v as usize).clamp(1, CERT_CHECK_MAX_CONCURRENCY))
+            .unwrap_or(CERT_CHECK_DEFAULT_CONCURRENCY);
+        let timeout_ms = std::cmp::min(
+            read_positive_int(args.get("timeout_ms")).unwrap_or(10_000),
+            120_000,
+        ) as u64;
+        let options = CertCheckOptions {
+            insecure_ok: args
+                .get("insecure_ok")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false),
+            warn_days,
+            timeout: Duration::from_millis(timeout_ms),
+            ssrf: SsrfPolicy::resolve(None)?,
+        };
+        let checked_at = chrono::Utc::now();
+        let now = checked_at.timestamp();
+        let started = Instant::now();
+
+        let tasks: Vec<_> = targets
+            .into_iter()
+            .enumerate()
+            .map(|(index, (target, tls))| {
+                let options = options.clone();
+                async move {
+                    let probe = {
+                        let target = target.clone();
+                        tokio::task::spawn_blocking(move || {
+                            check_certificate(&target, tls.as_ref(), &options, now)
+                        })
+                    };
+                    // Socket timeouts bound each phase; this bounds the whole handshake.
+                    let outcome =
+                        tokio::time::timeout(Duration::from_millis(timeout_ms * 3), probe).await;
+                    let mut entry = match outcome {
+                        Ok(Ok(Ok(entry))) => entry,
+                        Ok(Ok(Err(err))) => serde_json::json!({
+                            "error": err.message,
+                            "code": err.code,
+                        }),
+                        Ok(Err(err)) => serde_json::json!({
+                            "error": err.to_string(),
+                            "code": "INTERNAL",
+                        }),
+                        Err(_) => serde_json::json!({
+                            "error": format!("timed out after {} ms", timeout_ms * 3),
+                            "code": "TIMEOUT",
+                        }),
+                    };
+                    entry["status"] = Value::from(if entry.get("error").is_some() {
+                        "error"
+                    } else if entry["expired"] == true {
+                        "expired"
+                    } else if entry["chain_valid"] == false {
+                        "invalid"
+                    } else if entry["warning"] == true {
+                        "warning"
+                    } else {
+                        "ok"
+                    });
+                    entry["target"] = Value::String(target.target.clone());
+                    entry["host"] = Value::String(target.host.clone());
+                    entry["port"] = Value::from(target.port);
+                    entry["servername"] = Value::from(target.servername.clone());
+                    (index, entry)
+                }
+            })
+            .collect();
+        let mut certificates: Vec<(usize, Value)> = futures::stream::iter(tasks)
+            .buffer_unordered(concurrency)
+            .collect()
+            .await;
+        certificates.sort_by_key(|(index, _)| *index);
+        let certificates: Vec<Value> = certificates.into_iter().map(|(_, entry)| entry).collect();
+
+        let count = |status: &str| {
+            certificates
+                .iter()
+                .filter(|c| c["status"] == status)
+                .count()
+        };
+        let min_days = certificates
+            .iter()
+            .filter_map(|c| c.get("days_until_expiry").and_then(|v| v.as_i64()))
+            .min();
+        let stats = serde_json::json!({
+            "targets": certificates.len(),
+            "ok": count("ok"),
+            "warning": count("warning"),
+            "expired": count("expired"),
+            "invalid": count("invalid"),
+            "error": count("error"),
+            "min_days_until_expiry": min_days,
+        });
+        Ok(serde_json::json!({
+            "success": true,
+            "source": source,
+            "warn_days": warn_days,
+            "checked_at": checked_at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            "stats": stats,
+            "certificates": certificates,
+            "duration_ms": started.elapsed().as_millis() as u64,
+        }))
+    }
+
     async fn smoke_http(&self, args: Value) -> Result<Value, ToolError> {
+        let Some(sampling) = SmokeSampling::parse(&args)? else {
+            return self.smoke_probe(args).await;
+        };
+        let started = Instant::now();
+        let tool_budget_ms = feature_flags::TOOL_CALL_TIMEOUT_MS
+            .positive_number()
+            .saturating_sub(SMOKE_BUDGET_MARGIN_MS);
+        let budget_ms = read_positive_int(args.get("budget_ms"))
+            .map(|requested| std::cmp::min(requested, tool_budget_ms))
+            .unwrap_or(tool_budget_ms);
+        let probe_timeout_ms = std::cmp::min(
+            read_positive_int(args.get("timeout_ms")).unwrap_or(10_000),
+            std::cmp::min(120_000, budget_ms.max(1)),
+        );
+        let mut probe_args = args.clone();
+        probe_args["timeout_ms"] = Value::from(probe_timeout_ms);
+
+        // Samples run one after another; the next one starts only if it can finish, at its
+        // timeout, inside the budget.
+        let mut samples = Vec::new();
+        let mut results = Vec::new();
+        let mut last = Value::Null;
+        for index in 0..sampling.samples {
+            if index > 0 {
+                let elapsed = started.elapsed().as_millis() as u64;
+                if elapsed + sampling.interval_ms + probe_timeout_ms > budget_ms {
+                    break;
+                }
+                if sampling.interval_ms > 0 {
+                    tokio::time::sleep(Duration::from_millis(sampling.interval_ms)).await;
+                }
+            }
+            let probe = self.smoke_probe(probe_args.clone()).await?;
+            let responded = probe.get("success").and_then(|v| v.as_bool()) == Some(true);
+            let ok = responded && probe.get("ok").and_then(|v| v.as_bool()) == Some(true);
+            let duration_ms = probe.get("duration_ms").and_then(|v| v.as_u64());
+            samples.push(SmokeSample {
+                ok,
+                latency_ms: duration_ms.filter(|_| responded),
+            });
+            if results.len() < MAX_SAMPLE_RESULTS {
+                results.push(serde_json::json!({
+                    "sample": index + 1,
+                    "ok": ok,
+                    "status": probe.get("status").cloned().unwrap_or(Value::Null),
+                    "duration_ms": duration_ms,
+                    "error": probe.get("error").cloned().unwrap_or(Value::Null),
+                }));
+            }
+            last = probe;
+        }
+
+        let summary = SampleSummary::from_samples(&samples);
+        let violations = sampling.sla.violations(&summary);
+        let mut sla = sampling.sla.to_value();
+        sla["passed"] = Value::Bool(violations.is_empty());
+        sla["violations"] = serde_json::json!(violations);
+        let samples_run = samples.len() as u64;
+        Ok(serde_json::json!({
+            "success": true,
+            "ok": violations.is_empty(),
+            "url": args.get("url").cloned().unwrap_or(Value::Null),
+            "expect_code": last.get("expect_code").cloned().unwrap_or(Value::Null),
+            "status": last.get("status").cloned().unwrap_or(Value::Null),
+            "samples_planned": sampling.samples,
+            "samples_run": samples_run,
+            "budget_limited": samples_run < sampling.samples,
+            "budget_ms": budget_ms,
+            "interval_ms": sampling.interval_ms,
+            "timeout_ms": probe_timeout_ms,
+            "stats": summary.to_value(),
+            "sla": sla,
+            "results": results,
+            "results_truncated": samples.len() > MAX_SAMPLE_RESULTS,
+            "last": last,
+            "duration_ms": started.elapsed().as_millis(),
+        }))
+    }
+
+    async fn smoke_probe(&self, args: Value) -> Result<Value, ToolError> {
         let url =
             self.validation
                 .ensure_string(args.get("url").unwrap_or(&Value::Null), "url", true)?;
@@ -737,7 +1604,26 @@ impl ApiManager {
             }));
         }
 
-        let client = self.get_client(false, insecure_ok)?;
+        let tls_value = self.resolve_tls(None, &args).await?;
+        let tls = HttpTlsConfig::resolve(tls_value.as_ref())?;
+        let ssrf = SsrfPolicy::resolve(None)?;
+        let proxy_value = self.resolve_proxy(None, &args).await?;
+        let proxy = HttpProxyConfig::resolve(proxy_value.as_ref())?;
+        let tuning = HttpTuning::resolve(None, args.get("http"))?;
+        let decompress = decompress_requested(&args, None)?;
+        let client = self.get_wire_client(
+            false,
+            insecure_ok,
+            tls.as_ref(),
+            ssrf.as_ref(),
+            proxy.as_ref(),
+            tuning.as_ref(),
+        )?;
+        let accept_encoding = if decompress {
+            ACCEPT_ENCODING
+        } else {
+            "identity"
+        };
         let mut current_url = parsed;
         let mut final_url = current_url.clone();
         let mut redirected = false;
@@ -756,11 +1642,17 @@ impl ApiManager {
                 }));
             }
             let remaining = timeout_ms.saturating_sub(elapsed.as_millis() as u64);
+            // Redirects are followed by hand here, so every hop's IP literal is checked too.
+            if let Some(policy) = &ssrf {
+                policy
+                    .check_url_literal(&current_url)
+                    .map_err(|denied| denied.to_tool_error())?;
+            }
 
             let response = client
                 .request(Method::GET, current_url.clone())
                 .header("accept", "*/*")
-                .header("accept-encoding", "identity")
+                .header("accept-encoding", accept_encoding)
                 .header("connection", "close")
                 .timeout(Duration::from_millis(remaining))
                 .send()
@@ -769,11 +1661,16 @@ impl ApiManager {
             let response = match response {
                 Ok(resp) => resp,
                 Err(err) => {
+                    if let Some(denied) = denial_from_error(&err) {
+                        return Err(denied);
+                    }
+                    let code = classify_tls_error(&err, tls.as_ref()).map(|err| err.code);
                     return Ok(serde_json::json!({
                         "success": false,
                         "url": url,
                         "expect_code": expect_code,
                         "error": err.to_string(),
+                        "code": code,
                         "duration_ms": started.elapsed().as_millis(),
                     }));
                 }
@@ -786,7 +1683,8 @@ impl ApiManager {
                 .and_then(|v| v.to_str().ok())
                 .map(|s| s.to_string());
 
-            let body = read_response_body(response, max_bytes, None, None, None, None).await?;
+            let body =
+                read_response_body(response, max_bytes, decompress, None, None, None, None).await?;
             capture = Some(body);
             final_url = current_url.clone();
 
@@ -838,6 +1736,8 @@ impl ApiManager {
         let capture = capture.unwrap_or(BodyCapture {
             buffer: Vec::new(),
             body_read_bytes: 0,
+            wire_bytes: 0,
+            encoding: BodyEncoding::default(),
             body_captured_bytes: 0,
             body_truncated: false,
             body_ref: None,
@@ -855,11 +1755,16 @@ impl ApiManager {
             "final_url": final_url.to_string(),
             "redirected": redirected,
             "insecure_ok": insecure_ok,
+            "tls": tls.as_ref().map(|tls| tls.describe()),
+            "proxy": proxy.as_ref().map(|proxy| proxy.describe()),
             "follow_redirects": follow_redirects,
             "expect_code": expect_code,
             "status": status,
             "duration_ms": started.elapsed().as_millis(),
             "bytes": capture.body_read_bytes,
+            "wire_bytes": capture.wire_bytes,
+            "content_encoding": capture.encoding.content_encoding,
+            "body_decoded": capture.encoding.decoded,
             "captured_bytes": capture.body_captured_bytes,
             "truncated": capture.body_truncated,
             "body_preview": body_preview,
@@ -873,36 +1778,25 @@ impl ApiManager {
         auth: Option<&Value>,
     ) -> Result<Value, ToolError> {
         let config = self.build_request_config(args, profile, auth, None)?;
-        let raw_path = args
-            .get("download_path")
-            .or_else(|| args.get("file_path"))
-            .and_then(|v| v.as_str())
-            .unwrap_or("")
-            .trim();
-        if raw_path.is_empty() {
-            return Err(
-                ToolError::invalid_params("download_path is required").with_hint(
-                    "Provide args.download_path (or args.file_path) as a local filesystem path.",
-                ),
-            );
-        }
-        let file_path = expand_home_path(raw_path);
-        let overwrite = args
-            .get("overwrite")
-            .and_then(|v| v.as_bool())
-            .unwrap_or(false);
-        if !overwrite && file_path.exists() {
-            return Err(ToolError::conflict(format!(
-                "Local path already exists: {}",
-                file_path.display()
-            ))
-            .with_hint("Set overwrite=true to replace it."));
-        }
+        let file_path = download_target(args)?;
+        let expect_sha256 = parse_expected_sha256(args.get("expect_sha256"))?;
 
-        let client = self.get_client(true, false)?;
+        let decompress = decompress_requested(args, Some(profile))?;
+        let client = self.get_wire_client(
+            true,
+            false,
+            config.tls.as_ref(),
+            config.ssrf.as_ref(),
+            config.proxy.as_ref(),
+            config.tuning.as_ref(),
+        )?;
         let mut req = client.request(config.method.clone(), config.url.clone());
         req = req.headers(config.headers.clone());
+        if decompress && !config.headers.contains_key("accept-encoding") {
+            req = req.header("accept-encoding", ACCEPT_ENCODING);
+        }
         if let Some(body) = config.body {
+            usage::record(Counter::HttpBodySentBytes, body_len(&body));
             req = req.body(body);
         }
         if let Some(timeout_ms) = config.timeout_ms {
@@ -910,7 +1804,10 @@ impl ApiManager {
         }
 
         let started = Instant::now();
-        let response = req.send().await.map_err(map_reqwest_error)?;
+        let response = req
+            .send()
+            .await
+            .map_err(|err| map_request_error(err, config.tls.as_ref()))?;
         let status = response.status();
         let headers = response.headers().clone();
 
@@ -922,23 +1819,50 @@ impl ApiManager {
             ToolError::internal(format!("Failed to create download file: {}", err))
         })?;
 
+        let (encoding, mut decoder) = BodyEncoding::detect(&headers, decompress);
+
         let mut stream = response.bytes_stream();
         let mut bytes: u64 = 0;
+        let mut wire_bytes: u64 = 0;
+        let mut hasher = expect_sha256.as_ref().map(|_| Sha256::new());
+        let write_error = |err: std::io::Error| {
+            ToolError::internal(format!("Failed to write download chunk: {}", err))
+        };
         while let Some(chunk) = stream.next().await {
             let chunk = chunk.map_err(map_reqwest_error)?;
-            bytes += chunk.len() as u64;
-            file.write_all(&chunk).await.map_err(|err| {
-                ToolError::internal(format!("Failed to write download chunk: {}", err))
-            })?;
+            wire_bytes += chunk.len() as u64;
+            let decoded = match decoder.as_mut() {
+                Some(decoder) => decoder.push(&chunk)?,
+                None => chunk.to_vec(),
+            };
+            bytes += decoded.len() as u64;
+            if let Some(hasher) = hasher.as_mut() {
+                hasher.update(&decoded);
+            }
+            file.write_all(&decoded).await.map_err(write_error)?;
+        }
+        if let Some(decoder) = decoder.filter(|_| wire_bytes > 0) {
+            let tail = decoder.finish()?;
+            bytes += tail.len() as u64;
+            if let Some(hasher) = hasher.as_mut() {
+                hasher.update(&tail);
+            }
+            file.write_all(&tail).await.map_err(write_error)?;
         }
         file.flush().await.ok();
         drop(file);
-        tokio::fs::rename(&tmp_path, &file_path)
-            .await
-            .map_err(|err| ToolError::internal(format!("Failed to finalize download: {}", err)))?;
+        usage::record(Counter::HttpBodyReadBytes, wire_bytes);
+        let sha256 = hasher.map(|hasher| hex::encode(hasher.finalize()));
+        finalize_download(
+            &tmp_path,
+            &file_path,
+            expect_sha256.as_deref(),
+            sha256.as_deref(),
+        )
+        .await?;
 
         let headers_map = headers_to_value(&headers);
-        Ok(serde_json::json!({
+        let mut out = serde_json::json!({
             "success": status.is_success(),
             "method": config.method.as_str(),
             "url": config.url,
@@ -947,8 +1871,250 @@ impl ApiManager {
             "headers": headers_map,
             "file_path": file_path.display().to_string(),
             "bytes": bytes,
+            "wire_bytes": wire_bytes,
+            "content_encoding": encoding.content_encoding,
+            "body_decoded": encoding.decoded,
             "duration_ms": started.elapsed().as_millis(),
-        }))
+        });
+        if let Some(sha256) = sha256 {
+            out["sha256"] = Value::String(sha256);
+        }
+        Ok(out)
+    }
+
+    // Fetches the file as `segments` byte ranges in parallel, each written at its offset of a
+    // `.part` file pre-allocated to the size a HEAD probe reported. A failed range is retried on
+    // its own (from the last byte it wrote) under the download's retry policy. Ok(Err(reason))
+    // when the server cannot serve byte ranges and the single stream has to be used instead.
+    async fn download_segmented(
+        &self,
+        args: &Value,
+        profile: &ApiProfile,
+        auth: Option<&Value>,
+        segments: usize,
+        policy: &RetryPolicy,
+        expect_sha256: Option<&str>,
+    ) -> Result<Result<Value, String>, ToolError> {
+        let config = self.build_request_config(args, profile, auth, None)?;
+        if config.method != Method::GET || config.body.is_some() {
+            return Err(ToolError::invalid_params(
+                "segments only applies to GET downloads without a body",
+            ));
+        }
+        let file_path = download_target(args)?;
+        let client = self.get_wire_client(
+            true,
+            false,
+            config.tls.as_ref(),
+            config.ssrf.as_ref(),
+            config.proxy.as_ref(),
+            config.tuning.as_ref(),
+        )?;
+        // Ranges address the bytes as stored; an encoded response cannot be split.
+        let mut headers = config.headers.clone();
+        headers.insert(
+            reqwest::header::ACCEPT_ENCODING,
+            HeaderValue::from_static("identity"),
+        );
+        let timeout = config.timeout_ms.map(Duration::from_millis);
+
+        let started = Instant::now();
+        let mut head = client.head(config.url.clone()).headers(headers.clone());
+        if let Some(timeout) = timeout {
+            head = head.timeout(timeout);
+        }
+        let probe = head
+            .send()
+            .await
+            .map_err(|err| map_request_error(err, config.tls.as_ref()))?;
+        let status = probe.status();
+        let total = match ranged_length(status.as_u16(), probe.headers()) {
+            Ok(total) => total,
+            Err(reason) => return Ok(Err(reason)),
+        };
+        let response_headers = headers_to_value(probe.headers());
+
+        let tmp_path = file_path.with_extension("part");
+        if let Some(parent) = file_path.parent() {
+            tokio::fs::create_dir_all(parent).await.ok();
+        }
+        let file = tokio::fs::File::create(&tmp_path).await.map_err(|err| {
+            ToolError::internal(format!("Failed to create download file: {}", err))
+        })?;
+        file.set_len(total).await.map_err(|err| {
+            ToolError::internal(format!("Failed to pre-allocate download file: {}", err))
+        })?;
+        drop(file);
+
+        let (slots, per_host_limit) = self.host_slots(&config.url);
+        let fetch = SegmentFetch {
+            client: &client,
+            url: &config.url,
+            headers: &headers,
+            timeout,
+            tls: config.tls.as_ref(),
+            path: &tmp_path,
+            policy,
+            slots: &slots,
+            in_flight: AtomicUsize::new(0),
+            peak_in_flight: AtomicUsize::new(0),
+        };
+        let ranges = plan_ranges(total, segments);
+        let fetched = futures::future::try_join_all(
+            ranges
+                .iter()
+                .enumerate()
+                .map(|(index, &(start, end))| self.fetch_segment(&fetch, index, start, end)),
+        )
+        .await;
+        let reports = match fetched {
+            Ok(reports) => reports,
+            Err(err) => {
+                tokio::fs::remove_file(&tmp_path).await.ok();
+                return Err(err);
+            }
+        };
+
+        let written: u64 = reports
+            .iter()
+            .filter_map(|report| report.get("bytes").and_then(|v| v.as_u64()))
+            .sum();
+        let on_disk = tokio::fs::metadata(&tmp_path)
+            .await
+            .map(|meta| meta.len())
+            .unwrap_or(0);
+        if written != total || on_disk != total {
+            tokio::fs::remove_file(&tmp_path).await.ok();
+            return Err(ToolError::retryable(format!(
+                "Segmented download wrote {} bytes ({} on disk), expected {}",
+                written, on_disk, total
+            )));
+        }
+        let sha256 = match expect_sha256 {
+            Some(_) => Some(file_sha256(&tmp_path).await?),
+            None => None,
+        };
+        finalize_download(&tmp_path, &file_path, expect_sha256, sha256.as_deref()).await?;
+
+        let duration_ms = started.elapsed().as_millis() as u64;
+        let mut out = serde_json::json!({
+            "success": true,
+            "method": config.method.as_str(),
+            "url": config.url,
+            "status": status.as_u16(),
+            "statusText": status.canonical_reason().unwrap_or(""),
+            "headers": response_headers,
+            "file_path": file_path.display().to_string(),
+            "bytes": total,
+            "wire_bytes": written,
+            "content_encoding": Value::Null,
+            "body_decoded": false,
+            "duration_ms": duration_ms,
+            "throughput_bytes_per_s": throughput(total, duration_ms),
+            "segmented": {
+                "requested": segments,
+                "used": reports.len(),
+                "per_host_limit": per_host_limit,
+                "peak_in_flight": fetch.peak_in_flight.load(Ordering::Relaxed),
+            },
+            "segments": reports,
+        });
+        if let Some(sha256) = sha256 {
+            out["sha256"] = Value::String(sha256);
+        }
+        Ok(Ok(out))
+    }
+
+    // One range of a segmented download, retried from where the last attempt stopped.
+    async fn fetch_segment(
+        &self,
+        fetch: &SegmentFetch<'_>,
+        index: usize,
+        start: u64,
+        end: u64,
+    ) -> Result<Value, ToolError> {
+        let max_attempts = if fetch.policy.enabled {
+            fetch.policy.max_attempts.max(1)
+        } else {
+            1
+        };
+        let mut written = 0u64;
+        let mut attempts = 0usize;
+        let mut started = None;
+        loop {
+            attempts += 1;
+            let permit = fetch
+                .slots
+                .acquire()
+                .await
+                .map_err(|_| ToolError::internal("Download slots are closed"))?;
+            let started = *started.get_or_insert_with(Instant::now);
+            let in_flight = fetch.in_flight.fetch_add(1, Ordering::Relaxed) + 1;
+            fetch.peak_in_flight.fetch_max(in_flight, Ordering::Relaxed);
+            let outcome = fetch.range(start + written, end, &mut written).await;
+            fetch.in_flight.fetch_sub(1, Ordering::Relaxed);
+            drop(permit);
+            match outcome {
+                Ok(()) => {
+                    let duration_ms = started.elapsed().as_millis() as u64;
+                    return Ok(serde_json::json!({
+                        "index": index,
+                        "start": start,
+                        "end": end,
+                        "bytes": written,
+                        "attempts": attempts,
+                        "duration_ms": duration_ms,
+                        "throughput_bytes_per_s": throughput(written, duration_ms),
+                    }));
+                }
+                Err(err) => {
+                    let retryable = fetch.policy.enabled
+                        && fetch.policy.retry_on_network_error
+                        && classify_tool_error(&err) == StabilityClassification::Transient;
+                    if !retryable || attempts >= max_attempts {
+                        return Err(err.with_details(serde_json::json!({
+                            "segment": index,
+                            "range": [start, end],
+                            "bytes_written": written,
+                            "attempts": attempts,
+                        })));
+                    }
+                    self.logger.warn(
+                        "Retrying download segment",
+                        Some(&serde_json::json!({"segment": index, "attempt": attempts})),
+                    );
+                    let delay = self.compute_retry_delay(attempts, fetch.policy, None);
+                    tokio::time::sleep(Duration::from_millis(delay)).await;
+                }
+            }
+        }
+    }
+
+    // Ranged download requests to one host share a semaphore, so concurrent segmented downloads
+    // from the same server stay within INFRA_API_MAX_CONNECTIONS_PER_HOST together.
+    fn host_slots(&self, url: &str) -> (Arc<tokio::sync::Semaphore>, usize) {
+        let limit = feature_flags::API_MAX_CONNECTIONS_PER_HOST
+            .positive_number()
+            .max(1) as usize;
+        let host = Url::parse(url)
+            .ok()
+            .map(|parsed| {
+                format!(
+                    "{}:{}",
+                    parsed.host_str().unwrap_or(""),
+                    parsed.port_or_known_default().unwrap_or(0)
+                )
+            })
+            .unwrap_or_else(|| url.to_string());
+        let key = format!("{}#{}", host, limit);
+        let semaphore = match self.host_slots.lock() {
+            Ok(mut slots) => slots
+                .entry(key)
+                .or_insert_with(|| Arc::new(tokio::sync::Semaphore::new(limit)))
+                .clone(),
+            Err(_) => Arc::new(tokio::sync::Semaphore::new(limit)),
+        };
+        (semaphore, limit)
     }
 
     async fn request_with_retry(
@@ -980,9 +2146,29 @@ impl ApiManager {
             1
         };
 
+        let record = recording_requested(args, profile);
+        let mut recording = None;
+        let pinned = (!policy.regenerate_on_retry).then(|| DynamicValues::from_args(args));
+
         while attempt < max_attempts {
             attempt += 1;
-            match self.request_once(args, profile, auth, None).await {
+            let started_at = chrono::Utc::now();
+            let dynamic = pinned
+                .clone()
+                .unwrap_or_else(|| DynamicValues::from_args(args));
+            let overrides = RequestOverrides {
+                dynamic: Some(dynamic.clone()),
+                ..Default::default()
+            };
+            let outcome = self
+                .request_once(args, profile, auth, Some(overrides))
+                .await;
+            if record {
+                recording = self
+                    .record_exchange(args, profile, auth, &dynamic, attempt, started_at, &outcome)
+                    .or(recording);
+            }
+            match outcome {
                 Ok(response) => {
                     let should_retry =
                         policy.enabled && self.should_retry_response(&response, &policy);
@@ -1016,6 +2202,9 @@ impl ApiManager {
                             if should_emit_stability(&stability, debug_requested) {
                                 map.insert("stability".to_string(), stability.to_value());
                             }
+                            if let Some(recording) = recording.take() {
+                                map.insert("recording".to_string(), recording);
+                            }
                         }
                         return Ok(out);
                     }
@@ -1061,11 +2250,42 @@ impl ApiManager {
         overrides: Option<RequestOverrides>,
     ) -> Result<Value, ToolError> {
         let config = self.build_request_config(args, profile, auth, overrides)?;
-        let client = self.get_client(config.follow_redirects, config.insecure_ok)?;
+        let fixtures = api_fixtures::mode()?;
+        let fixture = fixtures.map(|mode| {
+            let body = api_fixtures::body_value(config.body.as_ref().and_then(|b| b.as_bytes()));
+            let key = api_fixtures::fixture_key(config.method.as_str(), &config.url, &body);
+            (mode, key, body)
+        });
+        if let Some((FixtureMode::Replay { fallback }, key, _)) = &fixture {
+            match api_fixtures::load(key)? {
+                Some(recorded) => return Ok(api_fixtures::replay_response(&recorded, key)),
+                None if !fallback => {
+                    return Err(api_fixtures::miss_error(
+                        key,
+                        config.method.as_str(),
+                        &config.url,
+                    ))
+                }
+                None => {}
+            }
+        }
+        let decompress = decompress_requested(args, Some(profile))?;
+        let client = self.get_wire_client(
+            config.follow_redirects,
+            config.insecure_ok,
+            config.tls.as_ref(),
+            config.ssrf.as_ref(),
+            config.proxy.as_ref(),
+            config.tuning.as_ref(),
+        )?;
 
         let mut req = client.request(config.method.clone(), config.url.clone());
         req = req.headers(config.headers.clone());
+        if decompress && !config.headers.contains_key("accept-encoding") {
+            req = req.header("accept-encoding", ACCEPT_ENCODING);
+        }
         if let Some(body) = config.body {
+            usage::record(Counter::HttpBodySentBytes, body_len(&body));
             req = req.body(body);
         }
         if let Some(timeout_ms) = config.timeout_ms {
@@ -1073,7 +2293,11 @@ impl ApiManager {
         }
 
         let started = Instant::now();
-        let response = req.send().await.map_err(map_reqwest_error)?;
+        let response = req
+            .send()
+            .await
+            .map_err(|err| map_request_error(err, config.tls.as_ref()))?;
+        let connection = observe_response(&response);
         let status = response.status();
         let status_text = status.canonical_reason().unwrap_or("").to_string();
         let response_headers = response.headers().clone();
@@ -1098,6 +2322,7 @@ impl ApiManager {
         let capture = read_response_body(
             response,
             max_capture_bytes,
+            decompress,
             stream_mode,
             context_root.as_ref(),
             trace_id,
@@ -1160,7 +2385,7 @@ impl ApiManager {
             }
         };
 
-        let out = serde_json::json!({
+        let mut out = serde_json::json!({
             "success": status.is_success(),
             "method": config.method.as_str(),
             "url": config.url,
@@ -1173,21 +2398,213 @@ impl ApiManager {
             "body_base64": body_base64,
             "body_bytes": body_bytes,
             "body_read_bytes": capture.body_read_bytes,
+            "wire_bytes": capture.wire_bytes,
+            "content_encoding": capture.encoding.content_encoding,
+            "body_decoded": capture.encoding.decoded,
             "body_captured_bytes": capture.body_captured_bytes,
             "body_truncated": capture.body_truncated,
             "body_ref": capture.body_ref,
             "body_ref_truncated": capture.body_ref_truncated,
+            "http_version": connection["http_version"],
+            "connection_reused": connection["connection_reused"],
         });
+        if let Some((FixtureMode::Record, key, body)) = &fixture {
+            let mut secrets = Vec::new();
+            collect_secret_strings(auth, &mut secrets);
+            let request = api_fixtures::RecordedRequest {
+                method: config.method.as_str(),
+                url: &config.url,
+                headers: serde_json::json!(config.headers_raw),
+                body,
+            };
+            match api_fixtures::store(key, request, &out, &secrets) {
+                Ok(_) => out["fixture"] = serde_json::json!({"mode": "record", "key": key}),
+                Err(err) => self.logger.warn(
+                    "API fixture not written",
+                    Some(&serde_json::json!({"error": err.message})),
+                ),
+            }
+        }
 
         Ok(out)
     }
 
+    #[allow(clippy::too_many_arguments)]
+    fn record_exchange(
+        &self,
+        args: &Value,
+        profile: &ApiProfile,
+        auth: Option<&Value>,
+        dynamic: &DynamicValues,
+        attempt: usize,
+        started_at: chrono::DateTime<chrono::Utc>,
+        outcome: &Result<Value, ToolError>,
+    ) -> Option<Value> {
+        let Some(context_root) = resolve_context_root() else {
+            self.logger.warn(
+                "API recording skipped: context root is not configured",
+                None,
+            );
+            return None;
+        };
+        let trace_id = args.get("trace_id").and_then(|v| v.as_str());
+        let reference = match build_run_file_ref(trace_id, API_RECORDING_FILENAME) {
+            Ok(reference) => reference,
+            Err(err) => {
+                self.logger.warn(
+                    "API recording skipped",
+                    Some(&serde_json::json!({"error": err.message})),
+                );
+                return None;
+            }
+        };
+        let mut secrets = Vec::new();
+        collect_secret_strings(auth, &mut secrets);
+        let overrides = RequestOverrides {
+            dynamic: Some(dynamic.clone()),
+            ..Default::default()
+        };
+        let entry = build_recording_entry(
+            self.build_request_config(args, profile, auth, Some(overrides))
+                .ok(),
+            args,
+            attempt,
+            started_at,
+            outcome,
+            resolve_record_body_bytes(),
+            &secrets,
+        );
+
+        let _guard = self
+            .recording_lock
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let result = resolve_artifact_path(&context_root, &reference.rel).and_then(|path| {
+            let mut har = std::fs::read_to_string(&path)
+                .ok()
+                .and_then(|raw| serde_json::from_str::<Value>(&raw).ok())
+                .filter(|value| value.pointer("/log/entries").is_some_and(Value::is_array))
+                .unwrap_or_else(empty_recording);
+            let entries = har
+                .pointer_mut("/log/entries")
+                .and_then(|v| v.as_array_mut())
+                .map(|entries| {
+                    entries.push(entry);
+                    entries.len()
+                })
+                .unwrap_or(0);
+            let content = serde_json::to_string_pretty(&har).map_err(|err| {
+                ToolError::internal(format!("Failed to encode recording: {}", err))
+            })?;
+            let info = write_text_artifact(&context_root, &reference, &content)?;
+            Ok(serde_json::json!({
+                "uri": info.uri,
+                "rel": info.rel,
+                "entries": entries,
+            }))
+        });
+        match result {
+            Ok(value) => Some(value),
+            Err(err) => {
+                self.logger.warn(
+                    "API recording failed",
+                    Some(&serde_json::json!({"error": err.message})),
+                );
+                None
+            }
+        }
+    }
+
+    fn recording_get(&self, args: &Value) -> Result<Value, ToolError> {
+        let trace_id = args
+            .get("recording_trace_id")
+            .or_else(|| args.get("trace_id"))
+            .and_then(|v| v.as_str());
+        let context_root = resolve_context_root().ok_or_else(|| {
+            ToolError::not_found("Context root is not configured")
+                .with_hint("Set INFRA_CONTEXT_REPO_ROOT to an existing directory.")
+        })?;
+        let reference = build_run_file_ref(trace_id, API_RECORDING_FILENAME)?;
+        let path = resolve_artifact_path(&context_root, &reference.rel)?;
+        let raw = std::fs::read_to_string(&path).map_err(|_| {
+            ToolError::not_found(format!(
+                "No API recording for trace '{}'",
+                trace_id.unwrap_or("run")
+            ))
+            .with_hint(
+                "Run api.request or api.paginate with record=true (or INFRA_API_RECORD=1) and the same trace_id.",
+            )
+        })?;
+        let entries = serde_json::from_str::<Value>(&raw)
+            .ok()
+            .and_then(|har| {
+                har.pointer("/log/entries")
+                    .and_then(|v| v.as_array())
+                    .map(|v| v.len())
+            })
+            .unwrap_or(0);
+        Ok(serde_json::json!({
+            "success": true,
+            "trace_id": trace_id.unwrap_or("run"),
+            "recording": {
+                "uri": reference.uri,
+                "rel": reference.rel,
+                "bytes": raw.len(),
+                "entries": entries,
+            },
+        }))
+    }
+
+    fn fixtures_list(&self, args: &Value) -> Result<Value, ToolError> {
+        let limit = args
+            .get("limit")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(100)
+            .clamp(1, 1000) as usize;
+        let url_contains = args.get("url_contains").and_then(|v| v.as_str());
+        let mut out = api_fixtures::list(url_contains, limit)?;
+        out["success"] = Value::Bool(true);
+        out["mode"] = match api_fixtures::mode()? {
+            Some(FixtureMode::Record) => Value::from("record"),
+            Some(FixtureMode::Replay { .. }) => Value::from("replay"),
+            None => Value::Null,
+        };
+        Ok(out)
+    }
+
+    fn fixtures_clear(&self, args: &Value) -> Result<Value, ToolError> {
+        let keys = match args.get("fixture_keys") {
+            None | Some(Value::Null) => None,
+            Some(Value::Array(items)) => Some(
+                items
+                    .iter()
+                    .map(|item| {
+                        item.as_str().map(str::to_string).ok_or_else(|| {
+                            ToolError::invalid_params("fixture_keys must be an array of strings")
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?,
+            ),
+            Some(_) => {
+                return Err(ToolError::invalid_params(
+                    "fixture_keys must be an array of strings",
+                ))
+            }
+        };
+        let removed = api_fixtures::clear(keys.as_deref())?;
+        Ok(serde_json::json!({
+            "success": true,
+            "removed": removed.len(),
+            "keys": removed,
+        }))
+    }
+
     pub(crate) fn build_request_config(
         &self,
         args: &Value,
         profile: &ApiProfile,
         auth: Option<&Value>,
-        overrides: Option<RequestOverrides>,
+        mut overrides: Option<RequestOverrides>,
     ) -> Result<RequestConfig, ToolError> {
         let base_url = args
             .get("base_url")
@@ -1200,10 +2617,15 @@ impl ApiManager {
                     .and_then(|v| v.as_str())
                     .map(|s| s.to_string())
             });
+        let dynamic = overrides
+            .as_mut()
+            .and_then(|overrides| overrides.dynamic.take())
+            .unwrap_or_else(|| DynamicValues::from_args(args));
         let headers = merge_headers(
             profile.data.get("headers"),
             args.get("headers"),
             auth.and_then(|v| build_auth_headers(v).ok()),
+            &dynamic,
         )?;
 
         let (body, content_type) = prepare_body(
@@ -1224,6 +2646,7 @@ impl ApiManager {
             args.get("path"),
             args.get("query"),
             args.get("url"),
+            &dynamic,
         )?;
         let method = args
             .get("method")
@@ -1259,6 +2682,10 @@ impl ApiManager {
                 .get("insecure_ok")
                 .and_then(|v| v.as_bool())
                 .unwrap_or(false),
+            tls: HttpTlsConfig::resolve(profile.tls.as_ref())?,
+            ssrf: SsrfPolicy::resolve(profile.data.get("ssrf"))?,
+            proxy: HttpProxyConfig::resolve(profile.proxy.as_ref())?,
+            tuning: HttpTuning::resolve(profile.data.get("http"), args.get("http"))?,
         };
 
         if let Some(overrides) = overrides {
@@ -1278,6 +2705,9 @@ impl ApiManager {
                 config.timeout_ms = Some(timeout);
             }
         }
+        if let Some(policy) = &config.ssrf {
+            policy.check_url(&config.url)?;
+        }
 
         Ok(config)
     }
@@ -1325,6 +2755,8 @@ impl ApiManager {
                 retry: None,
                 pagination: None,
                 cache: None,
+                tls: self.resolve_tls(None, args).await?,
+                proxy: self.resolve_proxy(None, args).await?,
             });
         }
 
@@ -1355,6 +2787,18 @@ impl ApiManager {
                 auth_provider = Some(resolver.resolve_deep(&provider, args).await?);
             }
         }
+        let tls = self
+            .resolve_tls(
+                merge_tls(data.get("tls"), Some(&Value::Object(secrets.clone()))),
+                args,
+            )
+            .await?;
+        let proxy = self
+            .resolve_proxy(
+                merge_proxy(data.get("proxy"), Some(&Value::Object(secrets))),
+                args,
+            )
+            .await?;
 
         Ok(ApiProfile {
             name: profile_name,
@@ -1367,9 +2811,72 @@ impl ApiManager {
                 .and_then(|v| v.get("pagination"))
                 .cloned(),
             cache: profile.get("data").and_then(|v| v.get("cache")).cloned(),
+            tls,
+            proxy,
         })
     }
 
+    // Request-level tls keys override the profile's; secret refs (e.g. a vault-held key PEM)
+    // are resolved here so key material never has to sit in the profile file.
+    async fn resolve_tls(
+        &self,
+        profile_tls: Option<Value>,
+        args: &Value,
+    ) -> Result<Option<Value>, ToolError> {
+        let mut merged = match profile_tls {
+            Some(Value::Object(map)) => map,
+            _ => serde_json::Map::new(),
+        };
+        if let Some(Value::Object(request_tls)) = args.get("tls") {
+            merged.extend(request_tls.clone());
+        } else if args.get("tls").map(|v| !v.is_null()).unwrap_or(false) {
+            return Err(ToolError::invalid_params("tls must be an object"));
+        }
+        if merged.is_empty() {
+            return Ok(None);
+        }
+        let merged = Value::Object(merged);
+        match &self.secret_ref_resolver {
+            Some(resolver) => Ok(Some(resolver.resolve_deep(&merged, args).await?)),
+            None => Ok(Some(merged)),
+        }
+    }
+
+    // A request-level `proxy` merges over the profile's (`false` forces a direct connection);
+    // secret refs are resolved here so proxy credentials can live in a vault.
+    async fn resolve_proxy(
+        &self,
+        profile_proxy: Option<Value>,
+        args: &Value,
+    ) -> Result<Option<Value>, ToolError> {
+        let as_object = |value: Value| match value {
+            Value::String(url) => Some(serde_json::json!({ "url": url })),
+            Value::Object(_) => Some(value),
+            _ => None,
+        };
+        let merged = match args.get("proxy").cloned() {
+            None | Some(Value::Null) => profile_proxy,
+            Some(Value::Bool(false)) => Some(Value::Bool(false)),
+            Some(request) => {
+                let Some(Value::Object(request)) = as_object(request) else {
+                    return Err(ToolError::invalid_params(
+                        "proxy must be false, a URL, or an object { url, no_proxy }",
+                    ));
+                };
+                let mut merged = match profile_proxy.and_then(as_object) {
+                    Some(Value::Object(map)) => map,
+                    _ => serde_json::Map::new(),
+                };
+                merged.extend(request);
+                Some(Value::Object(merged))
+            }
+        };
+        match (merged, &self.secret_ref_resolver) {
+            (Some(value), Some(resolver)) => Ok(Some(resolver.resolve_deep(&value, args).await?)),
+            (merged, _) => Ok(merged),
+        }
+    }
+
     pub(crate) fn normalize_retry_policy(
         &self,
         request: Option<&Value>,
@@ -1390,6 +2897,7 @@ impl ApiManager {
             retry_on_network_error: true,
             respect_retry_after: true,
             circuit_open_ms: 5_000,
+            regenerate_on_retry: true,
         };
 
         if let Some(profile) = profile {
@@ -1504,6 +3012,49 @@ impl ApiManager {
             .get("max_pages")
             .and_then(|v| v.as_u64())
             .unwrap_or(pagination_constants::MAX_PAGES as u64) as usize;
+        let max_pages_set = merged.get("max_pages").is_some_and(|v| !v.is_null());
+        let parallel = match merged.get("parallel") {
+            None | Some(Value::Null) => 1,
+            Some(value) => value
+                .as_u64()
+                .filter(|n| (1..=pagination_constants::MAX_PARALLEL as u64).contains(n))
+                .ok_or_else(|| {
+                    ToolError::invalid_params(format!(
+                        "pagination.parallel must be an integer between 1 and {}",
+                        pagination_constants::MAX_PARALLEL
+                    ))
+                })? as usize,
+        };
+        let total_path = merged
+            .get("total_path")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
+        if kind != "page" && kind != "offset" {
+            let key = if parallel > 1 {
+                Some("parallel")
+            } else if total_path.is_some() {
+                Some("total_path")
+            } else {
+                None
+            };
+            if let Some(key) = key {
+                return Err(ToolError::invalid_params(format!(
+                    "pagination.{} is only supported for page and offset pagination",
+                    key
+                ))
+                .with_hint(
+                    "Cursor and link pagination need the previous response, so their pages are fetched one at a time.",
+                ));
+            }
+        }
+        if parallel > 1 && !max_pages_set && total_path.is_none() {
+            return Err(
+                ToolError::invalid_params("pagination.parallel needs max_pages or total_path")
+                    .with_hint(
+                        "Set max_pages to the number of pages to fetch, or total_path to read the total item count from the first response.",
+                    ),
+            );
+        }
         let item_path = merged
             .get("item_path")
             .and_then(|v| v.as_str())
@@ -1521,6 +3072,11 @@ impl ApiManager {
             .get("stop_on_empty")
             .and_then(|v| v.as_bool())
             .unwrap_or(true);
+        let min_interval_ms = merged
+            .get("min_interval_ms")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0);
+        let rate_limit = normalize_rate_limit_pacing(merged.get("rate_limit"))?;
 
         Ok(PaginationConfig {
             kind,
@@ -1529,10 +3085,15 @@ impl ApiManager {
             size,
             start,
             max_pages,
+            max_pages_set,
+            parallel,
+            total_path,
             item_path,
             cursor_path,
             link_rel,
             stop_on_empty,
+            min_interval_ms,
+            rate_limit,
         })
     }
 
@@ -1550,6 +3111,8 @@ impl ApiManager {
         policy: &RetryPolicy,
         response: Option<&Value>,
     ) -> u64 {
+        // Every caller sleeps for this delay and then retries, so retries are counted here.
+        usage::record(Counter::Retries, 1);
         let mut delay = compute_backoff_delay_ms(
             attempt,
             policy.base_delay_ms,
@@ -1591,6 +3154,7 @@ impl ApiManager {
             args.get("path"),
             args.get("query"),
             args.get("url"),
+            &DynamicValues::verbatim(),
         ) {
             if let Ok(parsed) = parse_url(&url) {
                 if let Some(host) = parsed.host_str() {
@@ -1666,6 +3230,7 @@ impl ApiManager {
         &self,
         provider: Option<Value>,
         profile_name: Option<&str>,
+        proxy: Option<&Value>,
         args: &Value,
     ) -> Result<Option<Value>, ToolError> {
         let Some(mut provider) = provider else {
@@ -1831,7 +3396,12 @@ impl ApiManager {
                 }
             }
 
-            let client = self.get_client(true, false)?;
+            let ssrf = SsrfPolicy::resolve(None)?;
+            if let Some(policy) = &ssrf {
+                policy.check_url(token_url)?;
+            }
+            let proxy = HttpProxyConfig::resolve(proxy)?;
+            let client = self.get_client(true, false, None, ssrf.as_ref(), proxy.as_ref(), None)?;
             let response = client
                 .post(token_url)
                 .header("Content-Type", "application/x-www-form-urlencoded")
@@ -1889,13 +3459,71 @@ impl ApiManager {
         &self,
         follow_redirects: bool,
         insecure_ok: bool,
+        tls: Option<&HttpTlsConfig>,
+        ssrf: Option<&SsrfPolicy>,
+        proxy: Option<&HttpProxyConfig>,
+        tuning: Option<&HttpTuning>,
+    ) -> Result<Client, ToolError> {
+        self.cached_client(
+            follow_redirects,
+            insecure_ok,
+            tls,
+            ssrf,
+            proxy,
+            tuning,
+            true,
+        )
+    }
+
+    // Hands bodies over as they came off the wire; the caller decodes (see read_response_body).
+    fn get_wire_client(
+        &self,
+        follow_redirects: bool,
+        insecure_ok: bool,
+        tls: Option<&HttpTlsConfig>,
+        ssrf: Option<&SsrfPolicy>,
+        proxy: Option<&HttpProxyConfig>,
+        tuning: Option<&HttpTuning>,
+    ) -> Result<Client, ToolError> {
+        self.cached_client(
+            follow_redirects,
+            insecure_ok,
+            tls,
+            ssrf,
+            proxy,
+            tuning,
+            false,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn cached_client(
+        &self,
+        follow_redirects: bool,
+        insecure_ok: bool,
+        tls: Option<&HttpTlsConfig>,
+        ssrf: Option<&SsrfPolicy>,
+        proxy: Option<&HttpProxyConfig>,
+        tuning: Option<&HttpTuning>,
+        auto_decompress: bool,
     ) -> Result<Client, ToolError> {
-        let key = (follow_redirects, insecure_ok);
+        let key = (
+            follow_redirects,
+            insecure_ok,
+            tls.map(|tls| tls.fingerprint()),
+            ssrf.map(|policy| policy.fingerprint()),
+            proxy.map(|proxy| proxy.fingerprint()),
+            tuning.map(|tuning| tuning.fingerprint()),
+            auto_decompress,
+        );
         if let Ok(mut guard) = self.clients.lock() {
             if let Some(existing) = guard.get(&key) {
                 return Ok(existing.clone());
             }
             let mut builder = Client::builder();
+            if !auto_decompress {
+                builder = builder.no_gzip().no_brotli().no_deflate();
+            }
             if follow_redirects {
                 builder = builder.redirect(reqwest::redirect::Policy::limited(10));
             } else {
@@ -1904,9 +3532,29 @@ impl ApiManager {
             if insecure_ok {
                 builder = builder.danger_accept_invalid_certs(true);
             }
-            let client = builder.build().map_err(|err| {
-                ToolError::internal(format!("Failed to build HTTP client: {}", err))
+            if let Some(tls) = tls {
+                builder = tls.apply(builder)?;
+            }
+            if let Some(policy) = ssrf {
+                builder = policy.apply(builder, follow_redirects, proxy);
+            }
+            if let Some(proxy) = proxy {
+                builder = proxy.apply(builder, ssrf);
+            }
+            if let Some(tuning) = tuning {
+                builder = tuning.apply(builder);
+            }
+            let client = builder.build().map_err(|err| match tls {
+                // rustls only validates the key/certificates when the client config is built.
+                Some(tls) => ToolError::invalid_params(format!(
+                    "TLS client configuration was rejected: {}",
+                    err
+                ))
+                .with_hint("Check that tls.client_cert matches tls.client_key and that tls.ca_cert holds valid certificates.")
+                .with_details(serde_json::json!({ "tls": tls.describe() })),
+                None => ToolError::internal(format!("Failed to build HTTP client: {}", err)),
             })?;
+            record_client_built(tuning.is_some());
             guard.insert(key, client.clone());
             return Ok(client);
         }
@@ -1938,6 +3586,113 @@ impl ApiManager {
     }
 }
 
+// Audit webhook sink: each batch is a JSON array POSTed through the api client stack, so the
+// named profile supplies auth (including oauth2 providers), headers, TLS and proxy.
+pub struct AuditWebhook {
+    api: Arc<ApiManager>,
+    url: String,
+    profile_name: Option<String>,
+}
+
+impl AuditWebhook {
+    pub fn new(api: Arc<ApiManager>, url: String, profile_name: Option<String>) -> Self {
+        Self {
+            api,
+            url,
+            profile_name,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl AuditTransport for AuditWebhook {
+    async fn deliver(&self, batch: &[Value]) -> Result<(), ToolError> {
+        let args = serde_json::json!({
+            "url": self.url,
+            "method": "POST",
+            "body": batch,
+        });
+        // Without a named profile the webhook must not pick up whichever api profile is the
+        // only one configured.
+        let profile = match &self.profile_name {
+            Some(name) => {
+                self.api
+                    .resolve_profile(Some(&Value::String(name.clone())), &args)
+                    .await?
+            }
+            None => ApiProfile {
+                name: None,
+                data: Default::default(),
+                auth: None,
+                auth_provider: None,
+                retry: None,
+                pagination: None,
+                cache: None,
+                tls: None,
+                proxy: None,
+            },
+        };
+        let mut auth = profile.auth.clone();
+        if let Some(provider) = profile.auth_provider.clone() {
+            auth = self
+                .api
+                .resolve_auth_provider(
+                    Some(provider),
+                    profile.name.as_deref(),
+                    profile.proxy.as_ref(),
+                    &args,
+                )
+                .await?;
+        }
+        let config = self
+            .api
+            .build_request_config(&args, &profile, auth.as_ref(), None)?;
+        let client = self.api.get_client(
+            config.follow_redirects,
+            config.insecure_ok,
+            config.tls.as_ref(),
+            config.ssrf.as_ref(),
+            config.proxy.as_ref(),
+            config.tuning.as_ref(),
+        )?;
+        let mut req = client
+            .request(config.method.clone(), config.url.clone())
+            .headers(config.headers.clone());
+        if let Some(body) = config.body {
+            usage::record(Counter::HttpBodySentBytes, body_len(&body));
+            req = req.body(body);
+        }
+        if let Some(timeout_ms) = config.timeout_ms {
+            req = req.timeout(Duration::from_millis(timeout_ms));
+        }
+        let response = req
+            .send()
+            .await
+            .map_err(|err| map_request_error(err, config.tls.as_ref()))?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        let message = format!("Audit webhook answered HTTP {}", status.as_u16());
+        if status.is_server_error() || status.as_u16() == 429 || status.as_u16() == 408 {
+            Err(ToolError::retryable(message))
+        } else {
+            Err(ToolError::invalid_params(message))
+        }
+    }
+
+    fn describe(&self) -> Value {
+        let url = reqwest::Url::parse(&self.url)
+            .map(|url| format!("{}://{}", url.scheme(), url.host_str().unwrap_or_default()))
+            .unwrap_or_else(|_| "invalid url".to_string());
+        serde_json::json!({
+            "kind": "webhook",
+            "host": url,
+            "profile": self.profile_name,
+        })
+    }
+}
+
 pub(crate) struct RequestConfig {
     pub(crate) url: String,
     pub(crate) method: Method,
@@ -1947,14 +3702,20 @@ pub(crate) struct RequestConfig {
     pub(crate) timeout_ms: Option<u64>,
     pub(crate) follow_redirects: bool,
     pub(crate) insecure_ok: bool,
+    pub(crate) tls: Option<HttpTlsConfig>,
+    pub(crate) ssrf: Option<SsrfPolicy>,
+    pub(crate) proxy: Option<HttpProxyConfig>,
+    pub(crate) tuning: Option<HttpTuning>,
 }
 
+#[derive(Default)]
 pub(crate) struct RequestOverrides {
     url: Option<String>,
     method: Option<Method>,
     headers: Option<HeaderMap>,
     body: Option<reqwest::Body>,
     timeout_ms: Option<u64>,
+    dynamic: Option<DynamicValues>,
 }
 
 #[derive(Clone, Debug)]
@@ -1963,6 +3724,60 @@ struct LinkHeader {
     rel: String,
 }
 
+// `request` extracts from `data`; `paginate` from the collected `items`, or from every page's
+// `data` when no item_path is set. Without keep_raw the source is replaced (paginate also drops
+// per-page bodies); with it the result goes to `extracted`. Failed responses pass through as-is
+// since their body rarely matches the expression.
+fn apply_response_extract(
+    mut result: Value,
+    extract: &Extract,
+    keep_raw: bool,
+) -> Result<Value, ToolError> {
+    if result.get("success").and_then(|v| v.as_bool()) == Some(false) {
+        return Ok(result);
+    }
+    let Value::Object(map) = &mut result else {
+        return Ok(result);
+    };
+    let paginated = map.contains_key("pages");
+    let (field, source) = match (paginated, map.get("items")) {
+        (true, Some(items)) => ("items", items.clone()),
+        (true, None) => (
+            "items",
+            Value::Array(
+                map.get("pages")
+                    .and_then(|v| v.as_array())
+                    .map(|pages| {
+                        pages
+                            .iter()
+                            .map(|page| page.get("data").cloned().unwrap_or(Value::Null))
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+            ),
+        ),
+        (false, _) => ("data", map.get("data").cloned().unwrap_or(Value::Null)),
+    };
+    let extracted = extract.apply(&source)?;
+    if keep_raw {
+        map.insert("extracted".to_string(), extracted);
+    } else {
+        map.insert(field.to_string(), extracted);
+        if paginated {
+            if let Some(Value::Array(pages)) = map.get_mut("pages") {
+                for page in pages.iter_mut().filter_map(|page| page.as_object_mut()) {
+                    page.remove("data");
+                }
+            }
+        }
+    }
+    map.insert(
+        "extract".to_string(),
+        Value::String(extract.source().to_string()),
+    );
+    Ok(result)
+}
+
 fn parse_link_header(header: &str) -> Vec<LinkHeader> {
     header
         .split(',')
@@ -1988,6 +3803,113 @@ fn parse_link_header(header: &str) -> Vec<LinkHeader> {
         .collect()
 }
 
+// Rate-limit pacing is on by default; `rate_limit: false` turns it off and an object overrides
+// the header names, threshold and wait caps.
+fn normalize_rate_limit_pacing(
+    value: Option<&Value>,
+) -> Result<Option<RateLimitPacing>, ToolError> {
+    let config = match value {
+        None | Some(Value::Null) | Some(Value::Bool(true)) => serde_json::Map::new(),
+        Some(Value::Bool(false)) => return Ok(None),
+        Some(Value::Object(map)) => {
+            if map.get("enabled").and_then(|v| v.as_bool()) == Some(false) {
+                return Ok(None);
+            }
+            map.clone()
+        }
+        Some(_) => {
+            return Err(ToolError::invalid_params(
+                "pagination.rate_limit must be a boolean or an object",
+            ))
+        }
+    };
+    let header = |key: &str, default: &str| {
+        config
+            .get(key)
+            .and_then(|v| v.as_str())
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .unwrap_or(default)
+            .to_lowercase()
+    };
+    Ok(Some(RateLimitPacing {
+        remaining_header: header("remaining_header", "x-ratelimit-remaining"),
+        reset_header: header("reset_header", "x-ratelimit-reset"),
+        threshold: config
+            .get("threshold")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(pagination_constants::RATE_LIMIT_THRESHOLD),
+        max_wait_ms: config
+            .get("max_wait_ms")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(pagination_constants::RATE_LIMIT_MAX_WAIT_MS),
+        max_retries: config
+            .get("max_retries")
+            .and_then(|v| v.as_u64())
+            .map(|v| v as usize)
+            .unwrap_or(pagination_constants::RATE_LIMIT_MAX_RETRIES),
+    }))
+}
+
+fn response_header<'a>(response: &'a Value, name: &str) -> Option<&'a str> {
+    let headers = response.get("headers").and_then(|v| v.as_object())?;
+    headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .and_then(|(_, value)| value.as_str())
+        .map(|value| value.trim())
+}
+
+// Retry-After and the reset header are seconds; a reset value that looks like a unix timestamp
+// is treated as the moment the window reopens.
+fn rate_limit_reset_ms(response: &Value, pacing: &RateLimitPacing) -> Option<u64> {
+    if let Some(seconds) =
+        response_header(response, "retry-after").and_then(|v| v.parse::<u64>().ok())
+    {
+        return Some(seconds.saturating_mul(1000));
+    }
+    let reset = response_header(response, &pacing.reset_header)?
+        .parse::<u64>()
+        .ok()?;
+    if reset > 1_000_000_000 {
+        let now = chrono::Utc::now().timestamp().max(0) as u64;
+        return Some(reset.saturating_sub(now).saturating_mul(1000));
+    }
+    Some(reset.saturating_mul(1000))
+}
+
+// How long to pause before the next page, if the response says the budget is (nearly) spent.
+fn read_total_count(response: &Value, total_path: &str) -> Result<u64, ToolError> {
+    let value = get_path_value(response, total_path, false, Some(Value::Null))?;
+    value
+        .as_u64()
+        .or_else(|| value.as_str().and_then(|s| s.trim().parse::<u64>().ok()))
+        .ok_or_else(|| {
+            ToolError::invalid_params(format!(
+                "pagination.total_path '{}' is not a count in the first response",
+                total_path
+            ))
+            .with_details(serde_json::json!({"value": value}))
+        })
+}
+
+fn rate_limit_wait_ms(response: &Value, pacing: &RateLimitPacing) -> Option<u64> {
+    let status = response.get("status").and_then(|v| v.as_u64()).unwrap_or(0);
+    let wait = if status == 429 {
+        rate_limit_reset_ms(response, pacing)
+            .unwrap_or(pagination_constants::RATE_LIMIT_FALLBACK_WAIT_MS)
+    } else {
+        let remaining = response_header(response, &pacing.remaining_header)?
+            .parse::<u64>()
+            .ok()?;
+        if remaining >= pacing.threshold {
+            return None;
+        }
+        rate_limit_reset_ms(response, pacing)?
+    };
+    Some(wait.min(pacing.max_wait_ms))
+}
+
 fn read_positive_int(value: Option<&Value>) -> Option<u64> {
     let value = value?;
     if let Some(n) = value.as_u64() {
@@ -2000,17 +3922,256 @@ fn read_positive_int(value: Option<&Value>) -> Option<u64> {
 }
 
 fn resolve_max_capture_bytes() -> usize {
-    let raw = std::env::var("INFRA_API_MAX_CAPTURE_BYTES")
-        .or_else(|_| std::env::var("INFRA_MAX_CAPTURE_BYTES"))
-        .ok();
-    raw.and_then(|v| v.parse::<usize>().ok())
-        .unwrap_or(256 * 1024)
+    feature_flags::API_MAX_CAPTURE_BYTES.number() as usize
+}
+
+fn resolve_record_body_bytes() -> usize {
+    feature_flags::API_RECORD_BODY_BYTES.number() as usize
+}
+
+fn recording_requested(args: &Value, profile: &ApiProfile) -> bool {
+    args.get("record")
+        .or_else(|| profile.data.get("record"))
+        .and_then(|v| v.as_bool())
+        .unwrap_or_else(is_api_record_enabled)
+}
+
+// Content-encoding is decoded unless `decompress: false` (per call, or in the profile).
+// The local path a download writes to, refusing to replace a file unless overwrite is set.
+fn download_target(args: &Value) -> Result<PathBuf, ToolError> {
+    let raw_path = args
+        .get("download_path")
+        .or_else(|| args.get("file_path"))
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .trim();
+    if raw_path.is_empty() {
+        return Err(
+            ToolError::invalid_params("download_path is required").with_hint(
+                "Provide args.download_path (or args.file_path) as a local filesystem path or artifact://<rel>.",
+            ),
+        );
+    }
+    let file_path = resolve_output_path(raw_path, "download_path")?;
+    let overwrite = args
+        .get("overwrite")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    if !overwrite && file_path.exists() {
+        return Err(ToolError::conflict(format!(
+            "Local path already exists: {}",
+            file_path.display()
+        ))
+        .with_hint("Set overwrite=true to replace it."));
+    }
+    Ok(file_path)
+}
+
+async fn file_sha256(path: &Path) -> Result<String, ToolError> {
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        let mut file = std::fs::File::open(&path)?;
+        let mut hasher = Sha256::new();
+        std::io::copy(&mut file, &mut hasher)?;
+        Ok::<_, std::io::Error>(hex::encode(hasher.finalize()))
+    })
+    .await
+    .map_err(|err| ToolError::internal(format!("Checksum task failed: {}", err)))?
+    .map_err(|err| ToolError::internal(format!("Failed to checksum download: {}", err)))
+}
+
+// Moves a complete `.part` file into place; a checksum mismatch discards it instead.
+async fn finalize_download(
+    tmp_path: &Path,
+    file_path: &Path,
+    expect_sha256: Option<&str>,
+    sha256: Option<&str>,
+) -> Result<(), ToolError> {
+    if let (Some(expected), Some(actual)) = (expect_sha256, sha256) {
+        if expected != actual {
+            tokio::fs::remove_file(tmp_path).await.ok();
+            return Err(ToolError::new(
+                ToolErrorKind::Conflict,
+                "CHECKSUM_MISMATCH",
+                format!(
+                    "Downloaded file has sha256 {}, expected {}",
+                    actual, expected
+                ),
+            )
+            .with_details(serde_json::json!({"expected": expected, "actual": actual})));
+        }
+    }
+    tokio::fs::rename(tmp_path, file_path)
+        .await
+        .map_err(|err| ToolError::internal(format!("Failed to finalize download: {}", err)))
+}
+
+fn decompress_requested(args: &Value, profile: Option<&ApiProfile>) -> Result<bool, ToolError> {
+    let value = args
+        .get("decompress")
+        .or_else(|| profile.and_then(|p| p.data.get("decompress")));
+    match value {
+        None | Some(Value::Null) => Ok(true),
+        Some(Value::Bool(flag)) => Ok(*flag),
+        Some(other) => Err(ToolError::invalid_params(format!(
+            "decompress must be a boolean (got {})",
+            other
+        ))),
+    }
+}
+
+fn empty_recording() -> Value {
+    serde_json::json!({
+        "log": {
+            "version": "1.2",
+            "creator": { "name": "infra", "version": env!("CARGO_PKG_VERSION") },
+            "entries": [],
+        }
+    })
+}
+
+fn collect_secret_strings(value: Option<&Value>, out: &mut Vec<String>) {
+    match value {
+        Some(Value::String(text)) => out.push(text.clone()),
+        Some(Value::Array(items)) => items
+            .iter()
+            .for_each(|item| collect_secret_strings(Some(item), out)),
+        Some(Value::Object(map)) => map
+            .values()
+            .for_each(|item| collect_secret_strings(Some(item), out)),
+        _ => {}
+    }
+}
+
+fn har_headers(headers: &Value, secrets: &[String]) -> Value {
+    let redacted = redact_object(
+        &serde_json::json!({ "headers": headers }),
+        usize::MAX,
+        Some(secrets),
+    );
+    let mut list: Vec<Value> = redacted
+        .get("headers")
+        .and_then(|v| v.as_object())
+        .map(|map| {
+            map.iter()
+                .map(|(name, value)| serde_json::json!({ "name": name, "value": value }))
+                .collect()
+        })
+        .unwrap_or_default();
+    list.sort_by(|a, b| a["name"].as_str().cmp(&b["name"].as_str()));
+    Value::Array(list)
+}
+
+// JSON bodies are redacted structurally so sensitive keys are masked; the cap applies to
+// the redacted form.
+fn har_body_text(raw: &str, cap: usize, secrets: &[String]) -> (String, bool) {
+    let redacted = match serde_json::from_str::<Value>(raw) {
+        Ok(Value::String(text)) => redact_text(&text, usize::MAX, Some(secrets)),
+        Ok(parsed) => redact_object(&parsed, usize::MAX, Some(secrets)).to_string(),
+        Err(_) => redact_text(raw, usize::MAX, Some(secrets)),
+    };
+    if redacted.len() <= cap {
+        return (redacted, false);
+    }
+    let mut end = cap;
+    while end > 0 && !redacted.is_char_boundary(end) {
+        end -= 1;
+    }
+    (redacted[..end].to_string(), true)
+}
+
+fn build_recording_entry(
+    config: Option<RequestConfig>,
+    args: &Value,
+    attempt: usize,
+    started_at: chrono::DateTime<chrono::Utc>,
+    outcome: &Result<Value, ToolError>,
+    body_cap: usize,
+    secrets: &[String],
+) -> Value {
+    let mut request = serde_json::json!({
+        "method": config.as_ref().map(|c| c.method.as_str().to_string()),
+        "url": config.as_ref().map(|c| c.url.clone()),
+        "headers": config
+            .as_ref()
+            .map(|c| har_headers(&serde_json::json!(c.headers_raw), secrets))
+            .unwrap_or_else(|| Value::Array(Vec::new())),
+    });
+    if let Some(config) = config.as_ref() {
+        if let Some(bytes) = config.body.as_ref().and_then(|body| body.as_bytes()) {
+            let (text, truncated) =
+                har_body_text(&String::from_utf8_lossy(bytes), body_cap, secrets);
+            let mime_type = config
+                .headers_raw
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case("content-type"))
+                .map(|(_, value)| value.clone())
+                .unwrap_or_default();
+            request["postData"] = serde_json::json!({
+                "mimeType": mime_type,
+                "text": text,
+                "size": bytes.len(),
+                "truncated": truncated,
+            });
+        }
+    }
+
+    let mut entry = serde_json::json!({
+        "startedDateTime": started_at.to_rfc3339(),
+        "request": request,
+        "_action": args.get("action").cloned().unwrap_or(Value::Null),
+        "_span_id": args.get("span_id").cloned().unwrap_or(Value::Null),
+        "_attempt": attempt,
+    });
+    match outcome {
+        Ok(response) => {
+            let raw = match response.get("data") {
+                None | Some(Value::Null) => String::new(),
+                Some(Value::String(text)) => text.clone(),
+                Some(other) => other.to_string(),
+            };
+            let (text, truncated) = har_body_text(&raw, body_cap, secrets);
+            let headers = response
+                .get("headers")
+                .cloned()
+                .unwrap_or_else(|| serde_json::json!({}));
+            let body_truncated = response
+                .get("body_truncated")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+            entry["time"] = response.get("duration_ms").cloned().unwrap_or(Value::Null);
+            entry["response"] = serde_json::json!({
+                "status": response.get("status").cloned().unwrap_or(Value::Null),
+                "statusText": response.get("statusText").cloned().unwrap_or(Value::Null),
+                "headers": har_headers(&headers, secrets),
+                "content": {
+                    "mimeType": headers.get("content-type").cloned().unwrap_or(Value::Null),
+                    "size": response.get("body_read_bytes").cloned().unwrap_or(Value::Null),
+                    "text": text,
+                    "truncated": truncated || body_truncated,
+                },
+                "_body_ref": response.get("body_ref").cloned().unwrap_or(Value::Null),
+            });
+        }
+        Err(err) => {
+            entry["time"] =
+                Value::from((chrono::Utc::now() - started_at).num_milliseconds().max(0));
+            entry["response"] = serde_json::json!({
+                "status": 0,
+                "statusText": "",
+                "headers": [],
+                "_error": {
+                    "code": err.code,
+                    "message": redact_text(&err.message, 2000, Some(secrets)),
+                },
+            });
+        }
+    }
+    entry
 }
 
 fn resolve_stream_to_artifact_mode() -> Option<StreamMode> {
-    let raw = std::env::var("INFRA_API_STREAM_TO_ARTIFACT")
-        .or_else(|_| std::env::var("INFRA_STREAM_TO_ARTIFACT"))
-        .ok()?;
+    let raw = feature_flags::API_STREAM_TO_ARTIFACT.text()?;
     let normalized = raw.trim().to_lowercase();
     if normalized.is_empty() {
         return None;
@@ -2028,29 +4189,113 @@ fn resolve_stream_to_artifact_mode() -> Option<StreamMode> {
     }
 }
 
+// How a body's content-encoding was handled: `decoded` is false when decompress=false or the
+// coding is one this tool cannot decode, and the stored bytes are then the encoded ones.
+#[derive(Debug, Default)]
+struct BodyEncoding {
+    content_encoding: Option<String>,
+    decoded: bool,
+}
+
+impl BodyEncoding {
+    fn detect(headers: &HeaderMap, decompress: bool) -> (Self, Option<BodyDecoder>) {
+        let content_encoding = headers
+            .get("content-encoding")
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty() && !v.eq_ignore_ascii_case("identity"));
+        let decoder = content_encoding
+            .as_deref()
+            .and_then(|header| ContentEncoding::parse(Some(header)))
+            .filter(|_| decompress)
+            .map(BodyDecoder::new);
+        let encoding = BodyEncoding {
+            decoded: decoder.is_some(),
+            content_encoding,
+        };
+        (encoding, decoder)
+    }
+
+    fn annotate(&self, target: &mut Value) {
+        target["content_encoding"] = serde_json::json!(self.content_encoding);
+        target["decoded"] = Value::Bool(self.decoded);
+    }
+}
+
+// Preview and artifact sinks for decoded body bytes.
+struct BodySinks {
+    max_capture_bytes: usize,
+    read_bytes: u64,
+    captured: usize,
+    truncated: bool,
+    preview: Vec<u8>,
+    writer: Option<(ArtifactRef, ArtifactWriter)>,
+    artifact_limit: usize,
+    artifact_written: usize,
+    body_ref_truncated: Option<bool>,
+}
+
+impl BodySinks {
+    // False once nothing more is wanted from the body.
+    async fn take(&mut self, chunk: &[u8]) -> Result<bool, ToolError> {
+        self.read_bytes += chunk.len() as u64;
+
+        if self.captured < self.max_capture_bytes {
+            let remaining = self.max_capture_bytes - self.captured;
+            if chunk.len() <= remaining {
+                self.preview.extend_from_slice(chunk);
+                self.captured += chunk.len();
+            } else {
+                self.preview.extend_from_slice(&chunk[..remaining]);
+                self.captured += remaining;
+                self.truncated = true;
+            }
+        } else if !chunk.is_empty() {
+            self.truncated = true;
+        }
+
+        if let Some((_, ref mut stream)) = self.writer {
+            if self.artifact_written < self.artifact_limit {
+                let remaining = self.artifact_limit - self.artifact_written;
+                let slice = if chunk.len() <= remaining {
+                    chunk
+                } else {
+                    &chunk[..remaining]
+                };
+                if !slice.is_empty() {
+                    stream.write(slice).await?;
+                    self.artifact_written += slice.len();
+                }
+                if slice.len() < chunk.len() {
+                    self.body_ref_truncated = Some(true);
+                }
+            } else if self.artifact_limit != usize::MAX && !chunk.is_empty() {
+                self.body_ref_truncated = Some(true);
+            }
+        }
+
+        Ok(!(self.captured >= self.max_capture_bytes && self.artifact_limit == 0))
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 async fn read_response_body(
     response: reqwest::Response,
     max_capture_bytes: usize,
+    decompress: bool,
     stream_mode: Option<StreamMode>,
     context_root: Option<&std::path::PathBuf>,
     trace_id: Option<&str>,
     span_id: Option<&str>,
 ) -> Result<BodyCapture, ToolError> {
-    let mut read_bytes: u64 = 0;
-    let mut captured: usize = 0;
-    let mut truncated = false;
-    let mut preview = Vec::new();
+    let (encoding, mut decoder) = BodyEncoding::detect(response.headers(), decompress);
 
-    let mut body_ref = None;
-    let mut body_ref_truncated = None;
-    let mut writer = None;
-    let mut artifact_written = 0usize;
     let artifact_limit = match stream_mode {
         Some(StreamMode::Full) => usize::MAX,
         Some(StreamMode::Capped) => max_capture_bytes,
         None => 0,
     };
-
+    let mut writer = None;
     if artifact_limit > 0 {
         if let Some(root) = context_root {
             let filename = format!("api-body-{}.bin", uuid::Uuid::new_v4());
@@ -2061,83 +4306,95 @@ async fn read_response_body(
             }
         }
     }
+    let mut sinks = BodySinks {
+        max_capture_bytes,
+        read_bytes: 0,
+        captured: 0,
+        truncated: false,
+        preview: Vec::new(),
+        writer,
+        artifact_limit,
+        artifact_written: 0,
+        body_ref_truncated: None,
+    };
 
+    let mut wire_bytes: u64 = 0;
+    let mut complete = true;
     let mut stream = response.bytes_stream();
     while let Some(chunk) = stream.next().await {
         let chunk = chunk.map_err(map_reqwest_error)?;
-        read_bytes += chunk.len() as u64;
-
-        if captured < max_capture_bytes {
-            let remaining = max_capture_bytes - captured;
-            if chunk.len() <= remaining {
-                preview.extend_from_slice(&chunk);
-                captured += chunk.len();
-            } else {
-                preview.extend_from_slice(&chunk[..remaining]);
-                captured += remaining;
-                truncated = true;
-            }
-        } else {
-            truncated = true;
-        }
-
-        if let Some((_, ref mut stream)) = writer {
-            if artifact_written < artifact_limit {
-                let remaining = artifact_limit - artifact_written;
-                let slice = if chunk.len() <= remaining {
-                    &chunk[..]
-                } else {
-                    &chunk[..remaining]
-                };
-                if !slice.is_empty() {
-                    stream.write(slice).await?;
-                    artifact_written += slice.len();
-                }
-                if slice.len() < chunk.len() {
-                    body_ref_truncated = Some(true);
-                }
-            } else if artifact_limit != usize::MAX {
-                body_ref_truncated = Some(true);
-            }
-        }
-
-        if captured >= max_capture_bytes && artifact_limit == 0 {
+        wire_bytes += chunk.len() as u64;
+        let more = match decoder.as_mut() {
+            Some(decoder) => sinks.take(&decoder.push(&chunk)?).await?,
+            None => sinks.take(&chunk).await?,
+        };
+        if !more {
+            complete = false;
             break;
         }
     }
+    if let Some(decoder) = decoder {
+        if complete && wire_bytes > 0 {
+            sinks.take(&decoder.finish()?).await?;
+        }
+    }
 
-    if let Some((reference, stream)) = writer {
-        if artifact_written > 0 {
+    let mut body_ref = None;
+    let mut body_ref_truncated = sinks.body_ref_truncated;
+    if let Some((_, stream)) = sinks.writer.take() {
+        if sinks.artifact_written > 0 {
             let written = stream.finalize().await?;
-            body_ref = Some(serde_json::json!({
+            let mut reference = serde_json::json!({
                 "uri": written.uri,
                 "rel": written.rel,
                 "bytes": written.bytes,
-            }));
+            });
+            encoding.annotate(&mut reference);
+            body_ref = Some(reference);
             body_ref_truncated = body_ref_truncated.or(Some(false));
-            let _ = reference;
         } else {
             let _ = stream.abort().await;
         }
     }
 
+    usage::record(Counter::HttpBodyReadBytes, wire_bytes);
     Ok(BodyCapture {
-        buffer: preview,
-        body_read_bytes: read_bytes,
-        body_captured_bytes: captured as u64,
-        body_truncated: truncated,
+        buffer: sinks.preview,
+        body_read_bytes: sinks.read_bytes,
+        wire_bytes,
+        encoding,
+        body_captured_bytes: sinks.captured as u64,
+        body_truncated: sinks.truncated,
         body_ref,
         body_ref_truncated,
     })
 }
 
+// Streaming bodies have no length up front; their source counts the bytes instead.
+fn body_len(body: &reqwest::Body) -> u64 {
+    body.as_bytes().map_or(0, |bytes| bytes.len() as u64)
+}
+
 pub(crate) fn map_reqwest_error(err: reqwest::Error) -> ToolError {
+    if let Some(denied) = denial_from_error(&err) {
+        return denied;
+    }
     if err.is_timeout() {
         return ToolError::timeout("HTTP request timed out");
     }
     ToolError::retryable(err.to_string())
 }
 
+pub(crate) fn map_request_error(err: reqwest::Error, tls: Option<&HttpTlsConfig>) -> ToolError {
+    if let Some(denied) = denial_from_error(&err) {
+        return denied;
+    }
+    if err.is_timeout() {
+        return ToolError::timeout("HTTP request timed out");
+    }
+    classify_tls_error(&err, tls).unwrap_or_else(|| map_reqwest_error(err))
+}
+
 pub(crate) fn normalize_auth_value(raw: &Value) -> Option<Value> {
     if raw.is_null() {
         return None;
@@ -2190,6 +4447,72 @@ fn split_auth(auth: Option<&Value>) -> (Option<Value>, Option<serde_json::Map<St
     )
 }
 
+// Inline key PEM (or a secret ref to it) is stored with the profile secrets; paths stay in data.
+fn split_tls(tls: Option<&Value>) -> (Option<Value>, Option<serde_json::Map<String, Value>>) {
+    let Some(Value::Object(mut tls_map)) = tls.cloned() else {
+        return (None, None);
+    };
+    let mut secrets = serde_json::Map::new();
+    for key in ["client_key_pem", "client_key"] {
+        if let Some(value) = tls_map.remove(key) {
+            secrets.insert("tls_client_key_pem".to_string(), value);
+        }
+    }
+    (
+        Some(Value::Object(tls_map)),
+        if secrets.is_empty() {
+            None
+        } else {
+            Some(secrets)
+        },
+    )
+}
+
+fn merge_tls(data_tls: Option<&Value>, secrets: Option<&Value>) -> Option<Value> {
+    let mut tls = data_tls.filter(|v| v.is_object()).cloned();
+    if let Some(key) = secrets.and_then(|v| v.get("tls_client_key_pem")) {
+        tls.get_or_insert_with(|| serde_json::json!({}))["client_key_pem"] = key.clone();
+    }
+    tls
+}
+
+// The proxy URL may carry credentials, so profiles keep it with the secrets.
+fn split_proxy(proxy: Option<&Value>) -> (Option<Value>, Option<serde_json::Map<String, Value>>) {
+    let mut proxy_map = match proxy {
+        Some(Value::Bool(false)) => return (Some(Value::Bool(false)), None),
+        Some(Value::String(url)) => {
+            let mut map = serde_json::Map::new();
+            map.insert("url".to_string(), Value::String(url.clone()));
+            map
+        }
+        Some(Value::Object(map)) => map.clone(),
+        _ => return (None, None),
+    };
+    let mut secrets = serde_json::Map::new();
+    if let Some(url) = proxy_map.remove("url") {
+        secrets.insert("proxy_url".to_string(), url);
+    }
+    (
+        Some(Value::Object(proxy_map)),
+        if secrets.is_empty() {
+            None
+        } else {
+            Some(secrets)
+        },
+    )
+}
+
+fn merge_proxy(data_proxy: Option<&Value>, secrets: Option<&Value>) -> Option<Value> {
+    if let Some(Value::Bool(false)) = data_proxy {
+        return Some(Value::Bool(false));
+    }
+    let mut proxy = data_proxy.filter(|v| v.is_object()).cloned();
+    if let Some(url) = secrets.and_then(|v| v.get("proxy_url")) {
+        proxy.get_or_insert_with(|| serde_json::json!({}))["url"] = url.clone();
+    }
+    proxy
+}
+
 fn merge_auth(data_auth: Option<&Value>, secrets: Option<&Value>) -> Option<Value> {
     let mut auth = data_auth.and_then(normalize_auth_value);
     let Some(Value::Object(map)) = auth.as_mut() else {
@@ -2339,6 +4662,7 @@ fn merge_headers(
     profile_headers: Option<&Value>,
     request_headers: Option<&Value>,
     auth_headers: Option<HashMap<String, String>>,
+    dynamic: &DynamicValues,
 ) -> Result<HashMap<String, String>, ToolError> {
     let mut merged = HashMap::new();
     merged.insert(
@@ -2350,22 +4674,15 @@ fn merge_headers(
         "application/json, text/plain, */*".to_string(),
     );
 
-    if let Some(Value::Object(map)) = profile_headers {
-        for (k, v) in map {
-            if let Some(s) = v.as_str() {
-                merged.insert(k.to_string(), s.to_string());
-            } else if !v.is_null() {
-                merged.insert(k.to_string(), v.to_string());
-            }
-        }
-    }
-
-    if let Some(Value::Object(map)) = request_headers {
-        for (k, v) in map {
-            if let Some(s) = v.as_str() {
-                merged.insert(k.to_string(), s.to_string());
-            } else if !v.is_null() {
-                merged.insert(k.to_string(), v.to_string());
+    for headers in [profile_headers, request_headers] {
+        if let Some(Value::Object(map)) = headers {
+            for (k, v) in map {
+                if let Some(s) = v.as_str() {
+                    let value = dynamic.substitute(s, &format!("header {}", k))?;
+                    merged.insert(k.to_string(), value);
+                } else if !v.is_null() {
+                    merged.insert(k.to_string(), v.to_string());
+                }
             }
         }
     }
@@ -2475,6 +4792,7 @@ fn build_url(
     path: Option<&Value>,
     query: Option<&Value>,
     url: Option<&Value>,
+    dynamic: &DynamicValues,
 ) -> Result<String, ToolError> {
     let mut url = if let Some(url) = url.and_then(|v| v.as_str()) {
         Url::parse(url).map_err(|_| ToolError::invalid_params("Invalid url"))?
@@ -2500,11 +4818,19 @@ fn build_url(
 
     match query {
         Some(Value::Object(map)) => {
+            let field = |k: &str| format!("query parameter {}", k);
             for (k, v) in map {
                 if let Some(arr) = v.as_array() {
                     for item in arr {
-                        url.query_pairs_mut().append_pair(k, &item.to_string());
+                        let value = match item.as_str() {
+                            Some(s) => dynamic.substitute(s, &field(k))?,
+                            None => item.to_string(),
+                        };
+                        url.query_pairs_mut().append_pair(k, &value);
                     }
+                } else if let Some(s) = v.as_str() {
+                    let value = dynamic.substitute(s, &field(k))?;
+                    url.query_pairs_mut().append_pair(k, &value);
                 } else if !v.is_null() {
                     url.query_pairs_mut().append_pair(k, &v.to_string());
                 }
@@ -2627,6 +4953,9 @@ fn apply_retry_policy(policy: &mut RetryPolicy, source: &Value) {
     if let Some(circuit_open_ms) = source.get("circuit_open_ms").and_then(|v| v.as_u64()) {
         policy.circuit_open_ms = circuit_open_ms;
     }
+    if let Some(regenerate) = source.get("regenerate_on_retry").and_then(|v| v.as_bool()) {
+        policy.regenerate_on_retry = regenerate;
+    }
 }
 
 fn apply_cache_policy(policy: &mut CachePolicy, source: &Value) {
@@ -1,15 +1,18 @@
 use crate::errors::ToolError;
 use crate::services::logger::Logger;
-use crate::utils::artifacts::{resolve_artifact_path, resolve_context_root};
+use crate::utils::artifacts::{
+    blob_refs, file_sha256, resolve_artifact_path, resolve_blob_path, resolve_context_root,
+    BLOBS_DIR,
+};
 use crate::utils::feature_flags::is_allow_secret_export_enabled;
 use crate::utils::redact::redact_text;
 use crate::utils::tool_errors::unknown_action_error;
 use base64::Engine;
 use serde_json::Value;
 use std::io::Seek;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-pub(crate) const ARTIFACT_ACTIONS: &[&str] = &["get", "head", "tail", "list"];
+pub(crate) const ARTIFACT_ACTIONS: &[&str] = &["get", "head", "tail", "list", "delete", "gc"];
 
 fn allow_secret_export() -> bool {
     is_allow_secret_export_enabled()
@@ -195,6 +198,18 @@ impl ArtifactManager {
                     .await
                     .map_err(|_| ToolError::internal("Artifacts task failed"))?
             }
+            "delete" => {
+                let this = self.clone();
+                tokio::task::spawn_blocking(move || this.delete(args))
+                    .await
+                    .map_err(|_| ToolError::internal("Artifacts task failed"))?
+            }
+            "gc" => {
+                let this = self.clone();
+                tokio::task::spawn_blocking(move || this.gc())
+                    .await
+                    .map_err(|_| ToolError::internal("Artifacts task failed"))?
+            }
             _ => Err(unknown_action_error("artifacts", action, ARTIFACT_ACTIONS)),
         }
     }
@@ -324,7 +339,7 @@ impl ArtifactManager {
         let search_root = if prefix.is_empty() {
             artifacts_root.clone()
         } else {
-            artifacts_root.join(&prefix)
+            resolve_artifact_path(&context_root, &prefix)?
         };
         if !search_root.exists() {
             return Ok(
@@ -343,6 +358,9 @@ impl ArtifactManager {
                 .path()
                 .strip_prefix(&artifacts_root)
                 .unwrap_or(entry.path());
+            if rel_path.starts_with(BLOBS_DIR) {
+                continue;
+            }
             let rel = rel_path.to_string_lossy().replace('\\', "/");
             entries.push(serde_json::json!({
                 "uri": build_artifact_uri(&rel),
@@ -359,6 +377,108 @@ impl ArtifactManager {
             "total": entries.len(),
         }))
     }
+
+    // Removes one ref. A deduped ref drops its blob's reference count; the blob goes with the
+    // last ref.
+    fn delete(&self, args: Value) -> Result<Value, ToolError> {
+        let (path, rel, uri) = resolve_file_path(args.get("uri"), args.get("rel"))?;
+        if Path::new(&rel).starts_with(BLOBS_DIR) {
+            return Err(ToolError::invalid_params(
+                "blobs are removed through their refs, not directly",
+            )
+            .with_hint("Delete the artifact refs, then run { action: 'gc' }.".to_string()));
+        }
+        let context_root = resolve_context_root()
+            .ok_or_else(|| ToolError::internal("Context root not available"))?;
+        let meta = std::fs::metadata(&path).map_err(|err| ToolError::internal(err.to_string()))?;
+        // Only a hardlinked ref can be backed by a blob; plain artifacts are not hashed.
+        let blob = if blob_refs(&meta) > 0 {
+            let (hash, _) = file_sha256(&path)
+                .map_err(|err| ToolError::internal(format!("Failed to hash artifact: {}", err)))?;
+            Some((resolve_blob_path(&context_root, &hash), hash))
+        } else {
+            None
+        };
+        std::fs::remove_file(&path)
+            .map_err(|err| ToolError::internal(format!("Failed to delete artifact: {}", err)))?;
+        let mut out = serde_json::json!({
+            "success": true,
+            "uri": uri,
+            "rel": rel,
+            "bytes": meta.len(),
+        });
+        if let Some((blob, hash)) = blob {
+            if let Ok(blob_meta) = std::fs::metadata(&blob) {
+                let refs = blob_refs(&blob_meta);
+                if refs == 0 {
+                    std::fs::remove_file(&blob).map_err(|err| {
+                        ToolError::internal(format!("Failed to delete blob: {}", err))
+                    })?;
+                }
+                out["blob"] = serde_json::json!({
+                    "sha256": hash,
+                    "refs": refs,
+                    "removed": refs == 0,
+                });
+            }
+        }
+        Ok(out)
+    }
+
+    // Sweeps blobs no ref points at any more (refs replaced by a rewrite, or removed by hand).
+    fn gc(&self) -> Result<Value, ToolError> {
+        let context_root = resolve_context_root().ok_or_else(|| {
+            ToolError::denied("Artifacts are unavailable (context repo root is not configured)")
+                .with_hint(
+                    "Set INFRA_CONTEXT_REPO_ROOT to the repo root that owns artifacts.".to_string(),
+                )
+        })?;
+        let blobs_root = context_root.join("artifacts").join(BLOBS_DIR);
+        let entries = match std::fs::read_dir(&blobs_root) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(serde_json::json!({
+                    "success": true,
+                    "removed": 0,
+                    "kept": 0,
+                    "freed_bytes": 0,
+                }))
+            }
+            Err(err) => {
+                return Err(ToolError::internal(format!(
+                    "Failed to list blobs: {}",
+                    err
+                )))
+            }
+        };
+        let (mut removed, mut kept, mut freed_bytes) = (0u64, 0u64, 0u64);
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            if !meta.is_file() {
+                continue;
+            }
+            if blob_refs(&meta) > 0 {
+                kept += 1;
+                continue;
+            }
+            if std::fs::remove_file(entry.path()).is_ok() {
+                removed += 1;
+                freed_bytes += meta.len();
+            }
+        }
+        self.logger.info(
+            "Artifact blobs collected",
+            Some(&serde_json::json!({"removed": removed, "kept": kept})),
+        );
+        Ok(serde_json::json!({
+            "success": true,
+            "removed": removed,
+            "kept": kept,
+            "freed_bytes": freed_bytes,
+        }))
+    }
 }
 
 #[async_trait::async_trait]
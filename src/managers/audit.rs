@@ -1,17 +1,30 @@
 use crate::errors::ToolError;
 use crate::services::audit::AuditService;
+use crate::services::evidence::EvidenceService;
 use crate::services::logger::Logger;
+use crate::services::trace_bundle::{self, BundleLimits};
+use crate::utils::artifacts::resolve_context_root;
 use crate::utils::tool_errors::unknown_action_error;
 use serde_json::Value;
 use std::sync::Arc;
 
-pub(crate) const AUDIT_ACTIONS: &[&str] =
-    &["audit_list", "audit_tail", "audit_clear", "audit_stats"];
+pub(crate) const AUDIT_ACTIONS: &[&str] = &[
+    "audit_list",
+    "audit_tail",
+    "audit_clear",
+    "audit_stats",
+    "audit_verify",
+    "audit_trace",
+    "audit_flush",
+    "export_trace",
+];
+const DEFAULT_FLUSH_TIMEOUT_MS: u64 = 10_000;
 
 #[derive(Clone)]
 pub struct AuditManager {
     logger: Logger,
     audit_service: Arc<AuditService>,
+    evidence_service: Option<Arc<EvidenceService>>,
 }
 
 impl AuditManager {
@@ -19,9 +32,50 @@ impl AuditManager {
         Self {
             logger: logger.child("audit"),
             audit_service,
+            evidence_service: None,
         }
     }
 
+    pub fn with_evidence_service(mut self, evidence_service: Arc<EvidenceService>) -> Self {
+        self.evidence_service = Some(evidence_service);
+        self
+    }
+
+    fn export_trace(&self, args: &Value) -> Result<Value, ToolError> {
+        let trace_id = args
+            .get("trace_id")
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.trim().is_empty())
+            .ok_or_else(|| {
+                ToolError::invalid_params("trace_id is required").with_hint(
+                    "Use the trace_id from a tool call's meta, e.g. { action: 'export_trace', trace_id }.",
+                )
+            })?;
+        let context_root = resolve_context_root().ok_or_else(|| {
+            ToolError::denied("export_trace requires context repo root").with_hint(
+                "Set INFRA_CONTEXT_REPO_ROOT to the repo root that owns artifacts.".to_string(),
+            )
+        })?;
+        let mut limits = BundleLimits::default();
+        for (key, slot) in [
+            ("max_file_bytes", &mut limits.max_file_bytes),
+            ("max_total_bytes", &mut limits.max_total_bytes),
+        ] {
+            if let Some(value) = args.get(key).filter(|v| !v.is_null()) {
+                *slot = value.as_u64().filter(|v| *v > 0).ok_or_else(|| {
+                    ToolError::invalid_params(format!("{} must be a positive integer", key))
+                })? as usize;
+            }
+        }
+        trace_bundle::export_trace(
+            &self.audit_service,
+            self.evidence_service.as_deref(),
+            &context_root,
+            trace_id.trim(),
+            limits,
+        )
+    }
+
     pub async fn handle_action(&self, args: Value) -> Result<Value, ToolError> {
         let action = args.get("action");
         match action.and_then(|v| v.as_str()).unwrap_or("") {
@@ -56,6 +110,30 @@ impl AuditManager {
                 "success": true,
                 "stats": self.audit_service.stats(),
             })),
+            "audit_verify" => self.audit_service.verify(),
+            "audit_trace" => {
+                let trace_id = args
+                    .get("trace_id")
+                    .and_then(|v| v.as_str())
+                    .filter(|s| !s.trim().is_empty())
+                    .ok_or_else(|| {
+                        ToolError::invalid_params("trace_id is required").with_hint(
+                            "Use the trace_id from a tool call's meta, e.g. { action: 'audit_trace', trace_id }.",
+                        )
+                    })?;
+                self.audit_service.trace_tree(trace_id)
+            }
+            "audit_flush" => {
+                let timeout_ms = args
+                    .get("timeout_ms")
+                    .and_then(|v| v.as_u64())
+                    .filter(|v| *v > 0)
+                    .unwrap_or(DEFAULT_FLUSH_TIMEOUT_MS);
+                self.audit_service
+                    .flush(std::time::Duration::from_millis(timeout_ms))
+                    .await
+            }
+            "export_trace" => self.export_trace(&args),
             _ => Err(unknown_action_error("audit", action, AUDIT_ACTIONS)),
         }
     }
@@ -1,23 +1,57 @@
-use crate::errors::ToolError;
+use crate::errors::{ToolError, ToolErrorKind};
+use crate::managers::api::ApiManager;
+use crate::managers::postgres::PostgresManager;
+use crate::managers::ssh::SshManager;
 use crate::services::capability::CapabilityService;
 use crate::services::context::ContextService;
 use crate::services::logger::Logger;
+use crate::services::project_resolver::ProjectResolver;
+use crate::services::runbook::RunbookService;
 use crate::services::validation::Validation;
+use crate::utils::capability_discovery::{
+    api_check_proposal, list_units_command, openapi_proposals, parse_units, table_proposals,
+    unit_proposals, DEFAULT_UNIT_FILTER, DISCOVERY_KINDS, WRITABLE_TABLES_SQL,
+};
 use crate::utils::listing::ListFilters;
+use crate::utils::shell::ensure_shell_arg;
 use crate::utils::tool_errors::unknown_action_error;
-use serde_json::Value;
+use serde_json::{Map, Value};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 pub(crate) const CAPABILITY_ACTIONS: &[&str] = &[
-    "list", "get", "set", "delete", "resolve", "families", "suggest", "graph", "stats",
+    "list",
+    "get",
+    "set",
+    "delete",
+    "resolve",
+    "families",
+    "suggest",
+    "graph",
+    "stats",
+    "capability_discover",
 ];
 
+const DEFAULT_PROBE_TIMEOUT_MS: u64 = 10_000;
+
+// One read-only look at one binding of one project target.
+struct Probe {
+    target: String,
+    kind: &'static str,
+    binding: Value,
+}
+
 #[derive(Clone)]
 pub struct CapabilityManager {
     logger: Logger,
     validation: Validation,
     capability_service: Arc<CapabilityService>,
     context_service: Option<Arc<ContextService>>,
+    project_resolver: Option<Arc<ProjectResolver>>,
+    runbook_service: Option<Arc<RunbookService>>,
+    ssh_manager: Option<Arc<SshManager>>,
+    postgres_manager: Option<Arc<PostgresManager>>,
+    api_manager: Option<Arc<ApiManager>>,
 }
 
 impl CapabilityManager {
@@ -32,7 +66,439 @@ impl CapabilityManager {
             validation,
             capability_service,
             context_service,
+            project_resolver: None,
+            runbook_service: None,
+            ssh_manager: None,
+            postgres_manager: None,
+            api_manager: None,
+        }
+    }
+
+    pub fn with_project_resolver(mut self, project_resolver: Arc<ProjectResolver>) -> Self {
+        self.project_resolver = Some(project_resolver);
+        self
+    }
+
+    pub fn with_runbook_service(mut self, runbook_service: Arc<RunbookService>) -> Self {
+        self.runbook_service = Some(runbook_service);
+        self
+    }
+
+    pub fn with_ssh_manager(mut self, ssh_manager: Arc<SshManager>) -> Self {
+        self.ssh_manager = Some(ssh_manager);
+        self
+    }
+
+    pub fn with_postgres_manager(mut self, postgres_manager: Arc<PostgresManager>) -> Self {
+        self.postgres_manager = Some(postgres_manager);
+        self
+    }
+
+    pub fn with_api_manager(mut self, api_manager: Arc<ApiManager>) -> Self {
+        self.api_manager = Some(api_manager);
+        self
+    }
+
+    fn discovery_kinds(args: &Value) -> Result<Vec<&'static str>, ToolError> {
+        let requested: Vec<String> = match args.get("kinds") {
+            None | Some(Value::Null) => return Ok(DISCOVERY_KINDS.to_vec()),
+            Some(Value::String(list)) => list.split(',').map(|k| k.trim().to_string()).collect(),
+            Some(Value::Array(items)) => items
+                .iter()
+                .map(|k| k.as_str().unwrap_or("").trim().to_string())
+                .collect(),
+            Some(_) => Vec::new(),
+        };
+        let kinds: Vec<&'static str> = DISCOVERY_KINDS
+            .iter()
+            .copied()
+            .filter(|kind| requested.iter().any(|k| k == kind))
+            .collect();
+        if kinds.is_empty()
+            || requested
+                .iter()
+                .any(|k| !DISCOVERY_KINDS.contains(&k.as_str()))
+        {
+            return Err(ToolError::invalid_params(format!(
+                "kinds must be a non-empty subset of {}",
+                DISCOVERY_KINDS.join(", ")
+            )));
+        }
+        Ok(kinds)
+    }
+
+    // Proposes capability records from what the project's targets are bound to. Probes only
+    // read (systemctl list-units, a catalog query, an OpenAPI GET); records are written to the
+    // manifests only with apply=true.
+    async fn discover(&self, args: &Value) -> Result<Value, ToolError> {
+        let resolver = self.project_resolver.as_ref().ok_or_else(|| {
+            ToolError::internal("capability_discover is not wired to the project resolver")
+        })?;
+        let (project, targets) = resolver.project_targets(args).await?.ok_or_else(|| {
+            ToolError::invalid_params("capability_discover needs a project")
+                .with_hint("Pass project (or set the active project with project_use).".to_string())
+        })?;
+        let kinds = Self::discovery_kinds(args)?;
+        let unit_filter = args
+            .get("unit_filter")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .unwrap_or(DEFAULT_UNIT_FILTER)
+            .to_string();
+        ensure_shell_arg(&unit_filter, "unit_filter")?;
+        let timeout_ms = args
+            .get("probe_timeout_ms")
+            .and_then(|v| v.as_u64())
+            .filter(|ms| *ms > 0)
+            .unwrap_or(DEFAULT_PROBE_TIMEOUT_MS);
+        let requested_target = args
+            .get("target")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|v| !v.is_empty());
+        if let Some(name) = requested_target {
+            if !targets.contains_key(name) {
+                return Err(ToolError::invalid_params(format!(
+                    "Unknown project target: {}.",
+                    name
+                ))
+                .with_details(serde_json::json!({
+                    "known_targets": targets.keys().collect::<Vec<_>>(),
+                })));
+            }
+        }
+
+        let mut probes = Vec::new();
+        for (name, target) in &targets {
+            if requested_target.is_some_and(|wanted| wanted != name) {
+                continue;
+            }
+            let field = |key: &str| {
+                target
+                    .get(key)
+                    .and_then(|v| v.as_str())
+                    .map(str::trim)
+                    .filter(|v| !v.is_empty())
+            };
+            for kind in &kinds {
+                let binding = match *kind {
+                    "ssh" => field("ssh_profile").map(|p| serde_json::json!({"ssh_profile": p})),
+                    "postgres" => field("postgres_profile")
+                        .map(|p| serde_json::json!({"postgres_profile": p})),
+                    _ if field("api_profile").is_some() || field("api_base_url").is_some() => {
+                        Some(serde_json::json!({
+                            "api_profile": field("api_profile"),
+                            "api_base_url": field("api_base_url"),
+                            "openapi_url": args
+                                .get("openapi_url")
+                                .and_then(|v| v.as_str())
+                                .or_else(|| field("openapi_url")),
+                        }))
+                    }
+                    _ => None,
+                };
+                if let Some(binding) = binding {
+                    probes.push(Probe {
+                        target: name.clone(),
+                        kind,
+                        binding,
+                    });
+                }
+            }
+        }
+
+        let runs = probes.iter().map(|probe| {
+            let project = project.as_str();
+            let unit_filter = unit_filter.as_str();
+            async move {
+                let started = Instant::now();
+                let outcome = tokio::time::timeout(
+                    Duration::from_millis(timeout_ms),
+                    self.run_probe(project, probe, unit_filter, timeout_ms),
+                )
+                .await;
+                (outcome, started.elapsed().as_millis() as u64)
+            }
+        });
+        let outcomes = futures::future::join_all(runs).await;
+
+        let mut reports = Vec::new();
+        let mut proposals = Vec::new();
+        for (probe, (outcome, duration_ms)) in probes.iter().zip(outcomes) {
+            let mut report = serde_json::json!({
+                "target": probe.target,
+                "kind": probe.kind,
+                "binding": probe.binding,
+                "duration_ms": duration_ms,
+            });
+            match outcome {
+                Ok(Ok((found, evidence))) => {
+                    report["status"] = Value::from("ok");
+                    report["proposals"] = Value::from(found.len());
+                    report["evidence"] = evidence;
+                    proposals.extend(found);
+                }
+                Ok(Err(err)) => {
+                    report["status"] = Value::from(if err.code == "NOT_WIRED" {
+                        "skipped"
+                    } else {
+                        "error"
+                    });
+                    report["error"] = Value::from(err.message);
+                }
+                Err(_) => {
+                    report["status"] = Value::from("timeout");
+                    report["error"] = Value::from(format!(
+                        "{} probe timed out after {}ms",
+                        probe.kind, timeout_ms
+                    ));
+                }
+            }
+            reports.push(report);
+        }
+
+        let apply = args.get("apply").and_then(|v| v.as_bool()) == Some(true);
+        let mut out = serde_json::json!({
+            "success": true,
+            "project": project,
+            "dry_run": !apply,
+            "probes": reports,
+            "proposals": proposals,
+        });
+        if apply {
+            out["applied"] = self.apply_proposals(&proposals, args.get("names"))?;
+        }
+        Ok(out)
+    }
+
+    // Returns the proposals found by one probe and what they were derived from.
+    async fn run_probe(
+        &self,
+        project: &str,
+        probe: &Probe,
+        unit_filter: &str,
+        timeout_ms: u64,
+    ) -> Result<(Vec<Value>, Value), ToolError> {
+        let not_wired = |what: &str| {
+            ToolError::new(
+                ToolErrorKind::Internal,
+                "NOT_WIRED",
+                format!("{} probes are not available here", what),
+            )
+        };
+        let binding = |key: &str| probe.binding.get(key).and_then(|v| v.as_str());
+        match probe.kind {
+            "ssh" => {
+                let ssh = self.ssh_manager.as_ref().ok_or_else(|| not_wired("ssh"))?;
+                let profile = binding("ssh_profile").unwrap_or_default();
+                let command = list_units_command(unit_filter);
+                let result = ssh
+                    .handle_action(serde_json::json!({
+                        "action": "exec",
+                        "profile_name": profile,
+                        "command": command,
+                        "timeout_ms": timeout_ms,
+                    }))
+                    .await?;
+                if result.get("exitCode").and_then(|v| v.as_i64()) != Some(0) {
+                    return Err(ToolError::internal(format!(
+                        "systemctl list-units failed: {}",
+                        result
+                            .get("stderr")
+                            .and_then(|v| v.as_str())
+                            .unwrap_or("")
+                            .trim()
+                    )));
+                }
+                let units =
+                    parse_units(result.get("stdout").and_then(|v| v.as_str()).unwrap_or(""));
+                Ok((
+                    unit_proposals(project, &probe.target, profile, &units),
+                    serde_json::json!({"command": command, "units": units.len()}),
+                ))
+            }
+            "postgres" => {
+                let postgres = self
+                    .postgres_manager
+                    .as_ref()
+                    .ok_or_else(|| not_wired("postgres"))?;
+                let profile = binding("postgres_profile").unwrap_or_default();
+                let result = postgres
+                    .handle_action(serde_json::json!({
+                        "action": "query",
+                        "profile_name": profile,
+                        "sql": WRITABLE_TABLES_SQL,
+                        "timeout_ms": timeout_ms,
+                    }))
+                    .await?;
+                let rows = result
+                    .get("rows")
+                    .and_then(|v| v.as_array())
+                    .cloned()
+                    .unwrap_or_default();
+                Ok((
+                    table_proposals(project, &probe.target, profile, &rows),
+                    serde_json::json!({"query": "writable tables (has_table_privilege INSERT)", "tables": rows.len()}),
+                ))
+            }
+            _ => {
+                let api = self.api_manager.as_ref().ok_or_else(|| not_wired("api"))?;
+                let profile = binding("api_profile");
+                let profile_data = match profile {
+                    Some(name) => api
+                        .handle_action(
+                            serde_json::json!({"action": "profile_get", "profile_name": name}),
+                        )
+                        .await?
+                        .get("profile")
+                        .and_then(|p| p.get("data"))
+                        .cloned()
+                        .unwrap_or(Value::Null),
+                    None => Value::Null,
+                };
+                let base_url = binding("api_base_url")
+                    .or_else(|| profile_data.get("base_url").and_then(|v| v.as_str()))
+                    .ok_or_else(|| {
+                        ToolError::invalid_params(format!(
+                            "api profile {} has no base_url",
+                            profile.unwrap_or("")
+                        ))
+                    })?
+                    .to_string();
+                let Some(openapi_url) = binding("openapi_url")
+                    .or_else(|| profile_data.get("openapi_url").and_then(|v| v.as_str()))
+                else {
+                    return Ok((
+                        vec![api_check_proposal(
+                            project,
+                            &probe.target,
+                            profile,
+                            &base_url,
+                        )],
+                        serde_json::json!({"base_url": base_url, "openapi": "no openapi_url configured"}),
+                    ));
+                };
+                let openapi_url = reqwest::Url::parse(&base_url)
+                    .and_then(|base| base.join(openapi_url))
+                    .map_err(|_| {
+                        ToolError::invalid_params(format!("Invalid openapi_url: {}", openapi_url))
+                    })?
+                    .to_string();
+                let mut request = serde_json::json!({
+                    "action": "request",
+                    "method": "GET",
+                    "url": openapi_url,
+                    "response_type": "json",
+                    "timeout_ms": timeout_ms,
+                });
+                if let Some(profile) = profile {
+                    request["profile_name"] = Value::from(profile);
+                }
+                let response = api.handle_action(request).await?;
+                let status = response.get("status").and_then(|v| v.as_u64()).unwrap_or(0);
+                if !(200..300).contains(&status) {
+                    return Err(ToolError::internal(format!(
+                        "GET {} returned status {}",
+                        openapi_url, status
+                    )));
+                }
+                let doc = response.get("data").cloned().unwrap_or(Value::Null);
+                if !doc.is_object() {
+                    return Err(ToolError::internal(format!(
+                        "{} did not return a JSON OpenAPI document",
+                        openapi_url
+                    )));
+                }
+                let (found, truncated) = openapi_proposals(
+                    project,
+                    &probe.target,
+                    profile,
+                    &base_url,
+                    &openapi_url,
+                    &doc,
+                )
+                .map_err(ToolError::internal)?;
+                Ok((
+                    found,
+                    serde_json::json!({
+                        "base_url": base_url,
+                        "openapi_url": openapi_url,
+                        "operations_truncated": truncated,
+                    }),
+                ))
+            }
+        }
+    }
+
+    // Runbooks go first so no capability ever points at a runbook that was not written.
+    fn apply_proposals(
+        &self,
+        proposals: &[Value],
+        names: Option<&Value>,
+    ) -> Result<Value, ToolError> {
+        let runbook_service = self.runbook_service.as_ref().ok_or_else(|| {
+            ToolError::internal("capability_discover apply is not wired to the runbook manifest")
+        })?;
+        let selected: Option<Vec<String>> = match names {
+            None | Some(Value::Null) => None,
+            Some(Value::Array(items)) => Some(
+                items
+                    .iter()
+                    .map(|v| v.as_str().map(str::to_string))
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(|| {
+                        ToolError::invalid_params("names must be an array of proposal names")
+                    })?,
+            ),
+            Some(_) => {
+                return Err(ToolError::invalid_params(
+                    "names must be an array of proposal names",
+                ))
+            }
+        };
+        let proposal_name = |p: &Value| {
+            p.get("name")
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_string()
+        };
+        if let Some(selected) = &selected {
+            if let Some(unknown) = selected
+                .iter()
+                .find(|name| !proposals.iter().any(|p| &proposal_name(p) == *name))
+            {
+                return Err(ToolError::invalid_params(format!(
+                    "names lists {}, which is not among the proposals",
+                    unknown
+                )));
+            }
+        }
+        let mut capabilities = Map::new();
+        let mut runbooks = Map::new();
+        for proposal in proposals {
+            let name = proposal_name(proposal);
+            if selected.as_ref().is_some_and(|s| !s.contains(&name)) {
+                continue;
+            }
+            runbooks.insert(name.clone(), proposal["runbook"].clone());
+            capabilities.insert(name, proposal["capability"].clone());
         }
+        let (runbooks_added, runbooks_skipped) = runbook_service.add_runbooks(&runbooks)?;
+        let (capabilities_added, capabilities_skipped) =
+            self.capability_service.add_capabilities(&capabilities)?;
+        Ok(serde_json::json!({
+            "capabilities": {
+                "path": self.capability_service.manifest_path().display().to_string(),
+                "added": capabilities_added,
+                "skipped": capabilities_skipped,
+            },
+            "runbooks": {
+                "path": runbook_service.manifest_path().display().to_string(),
+                "added": runbooks_added,
+                "skipped": runbooks_skipped,
+            },
+        }))
     }
 
     pub async fn handle_action(&self, args: Value) -> Result<Value, ToolError> {
@@ -138,6 +604,7 @@ impl CapabilityManager {
             "suggest" => Ok(serde_json::json!({"success": true, "suggestions": []})),
             "graph" => Ok(serde_json::json!({"success": true, "graph": []})),
             "stats" => Ok(serde_json::json!({"success": true, "stats": {}})),
+            "capability_discover" => self.discover(&args).await,
             _ => Err(unknown_action_error(
                 "capability",
                 action,
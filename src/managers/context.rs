@@ -1,57 +1,419 @@
 use crate::errors::ToolError;
-use crate::services::context::ContextService;
+use crate::managers::postgres::PostgresManager;
+use crate::services::context::{ContextService, CONTEXT_SOURCES};
+use crate::services::job::JobService;
 use crate::services::logger::Logger;
+use crate::services::profile::ProfileService;
+use crate::services::project::ProjectService;
+use crate::utils::artifacts::resolve_context_root;
+use crate::utils::listing::ListFilters;
+use crate::utils::suggest::suggest;
 use crate::utils::tool_errors::unknown_action_error;
 use serde_json::Value;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 pub(crate) const CONTEXT_ACTIONS: &[&str] = &["get", "refresh", "summary", "list", "stats"];
 
+const DEFAULT_SOURCE_TIMEOUT_MS: u64 = 5000;
+const RECENT_JOBS_LIMIT: usize = 20;
+const RECENT_ARTIFACTS_LIMIT: usize = 20;
+
+fn requested_sources(args: &Value) -> Result<Vec<String>, ToolError> {
+    let raw: Vec<String> = match args.get("sources") {
+        None | Some(Value::Null) => {
+            return Ok(CONTEXT_SOURCES.iter().map(|s| s.to_string()).collect())
+        }
+        Some(Value::String(name)) => vec![name.clone()],
+        Some(Value::Array(items)) => items
+            .iter()
+            .map(|item| {
+                item.as_str()
+                    .map(|s| s.trim().to_string())
+                    .ok_or_else(|| ToolError::invalid_params("sources must be an array of strings"))
+            })
+            .collect::<Result<_, _>>()?,
+        Some(_) => {
+            return Err(ToolError::invalid_params(
+                "sources must be an array of strings",
+            ))
+        }
+    };
+    let known: Vec<String> = CONTEXT_SOURCES.iter().map(|s| s.to_string()).collect();
+    let mut out = Vec::new();
+    for name in raw {
+        if !known.contains(&name) {
+            let did_you_mean = suggest(&name, &known, 3);
+            let mut hint = format!("Use one of: {}.", known.join(", "));
+            if !did_you_mean.is_empty() {
+                hint = format!("Did you mean: {}? {}", did_you_mean.join(", "), hint);
+            }
+            return Err(
+                ToolError::invalid_params(format!("Unknown context source: {}", name))
+                    .with_hint(hint)
+                    .with_details(serde_json::json!({
+                        "known_sources": known,
+                        "did_you_mean": did_you_mean,
+                    })),
+            );
+        }
+        if !out.contains(&name) {
+            out.push(name);
+        }
+    }
+    Ok(out)
+}
+
+fn optional_ms(args: &Value, key: &str) -> Result<Option<u64>, ToolError> {
+    match args.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => value.as_u64().map(Some).ok_or_else(|| {
+            ToolError::invalid_params(format!("{} must be a non-negative integer", key))
+        }),
+    }
+}
+
 #[derive(Clone)]
 pub struct ContextManager {
     logger: Logger,
     context_service: Arc<ContextService>,
+    profile_service: Option<Arc<ProfileService>>,
+    project_service: Option<Arc<ProjectService>>,
+    job_service: Option<Arc<JobService>>,
+    postgres_manager: Option<Arc<PostgresManager>>,
 }
 
 impl ContextManager {
-    pub fn new(logger: Logger, context_service: Arc<ContextService>) -> Self {
+    pub fn new(
+        logger: Logger,
+        context_service: Arc<ContextService>,
+        profile_service: Option<Arc<ProfileService>>,
+        project_service: Option<Arc<ProjectService>>,
+        job_service: Option<Arc<JobService>>,
+        postgres_manager: Option<Arc<PostgresManager>>,
+    ) -> Self {
         Self {
             logger: logger.child("context"),
             context_service,
+            profile_service,
+            project_service,
+            job_service,
+            postgres_manager,
+        }
+    }
+
+    async fn fetch_repo(&self, args: &Value) -> Result<Value, ToolError> {
+        let result = self.context_service.get_context(args).await?;
+        let mut context = result.get("context").cloned().unwrap_or(Value::Null);
+        if let Value::Object(map) = &mut context {
+            map.remove("updated_at");
         }
+        Ok(context)
+    }
+
+    fn fetch_profiles(&self) -> Result<Value, ToolError> {
+        let service = self
+            .profile_service
+            .as_ref()
+            .ok_or_else(|| ToolError::not_found("Profile service is not available"))?;
+        let profiles = service.list_profiles(None)?;
+        let items: Vec<Value> = profiles
+            .as_array()
+            .map(|items| {
+                items
+                    .iter()
+                    .map(|item| {
+                        serde_json::json!({
+                            "name": item.get("name").cloned().unwrap_or(Value::Null),
+                            "type": item.get("type").cloned().unwrap_or(Value::Null),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(serde_json::json!({ "count": items.len(), "profiles": items }))
+    }
+
+    fn fetch_projects(&self) -> Result<Value, ToolError> {
+        let service = self
+            .project_service
+            .as_ref()
+            .ok_or_else(|| ToolError::not_found("Project service is not available"))?;
+        let listed = service.list_projects(&ListFilters::default())?;
+        let items: Vec<Value> = listed
+            .get("projects")
+            .and_then(|v| v.as_array())
+            .map(|items| {
+                items
+                    .iter()
+                    .map(|item| {
+                        let targets: Vec<String> = item
+                            .get("targets")
+                            .and_then(|v| v.as_object())
+                            .map(|map| map.keys().cloned().collect())
+                            .unwrap_or_default();
+                        serde_json::json!({
+                            "name": item.get("name").cloned().unwrap_or(Value::Null),
+                            "default_target": item.get("default_target").cloned().unwrap_or(Value::Null),
+                            "targets": targets,
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(serde_json::json!({ "count": items.len(), "projects": items }))
+    }
+
+    fn fetch_jobs(&self) -> Result<Value, ToolError> {
+        let service = self
+            .job_service
+            .as_ref()
+            .ok_or_else(|| ToolError::not_found("Job service is not available"))?;
+        let items: Vec<Value> = service
+            .list(RECENT_JOBS_LIMIT, None)
+            .iter()
+            .map(|job| {
+                serde_json::json!({
+                    "job_id": job.get("job_id").cloned().unwrap_or(Value::Null),
+                    "kind": job.get("kind").cloned().unwrap_or(Value::Null),
+                    "status": job.get("status").cloned().unwrap_or(Value::Null),
+                    "created_at": job.get("created_at").cloned().unwrap_or(Value::Null),
+                })
+            })
+            .collect();
+        Ok(serde_json::json!({ "count": items.len(), "jobs": items }))
+    }
+
+    fn fetch_artifacts(&self) -> Result<Value, ToolError> {
+        let root = resolve_context_root()
+            .ok_or_else(|| ToolError::not_found("Context root is not configured"))?;
+        let mut files: Vec<(std::time::SystemTime, String, u64)> = Vec::new();
+        for entry in walkdir::WalkDir::new(&root)
+            .into_iter()
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_type().is_file())
+        {
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            let rel = entry
+                .path()
+                .strip_prefix(&root)
+                .unwrap_or(entry.path())
+                .to_string_lossy()
+                .replace('\\', "/");
+            let modified = meta.modified().unwrap_or(std::time::UNIX_EPOCH);
+            files.push((modified, rel, meta.len()));
+        }
+        let total = files.len();
+        files.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+        files.truncate(RECENT_ARTIFACTS_LIMIT);
+        let items: Vec<Value> = files
+            .into_iter()
+            .map(|(modified, rel, bytes)| {
+                serde_json::json!({
+                    "uri": format!("artifact://{}", rel),
+                    "bytes": bytes,
+                    "modified_at": chrono::DateTime::<chrono::Utc>::from(modified).to_rfc3339(),
+                })
+            })
+            .collect();
+        Ok(serde_json::json!({ "total": total, "recent": items }))
+    }
+
+    async fn fetch_postgres(&self, timeout_ms: u64) -> Result<Value, ToolError> {
+        let postgres = self
+            .postgres_manager
+            .as_ref()
+            .ok_or_else(|| ToolError::not_found("Postgres manager is not available"))?;
+        let service = self
+            .profile_service
+            .as_ref()
+            .ok_or_else(|| ToolError::not_found("Profile service is not available"))?;
+        let profiles = service.list_profiles(Some("postgresql"))?;
+        let names: Vec<String> = profiles
+            .as_array()
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|item| item.get("name").and_then(|v| v.as_str()))
+                    .map(|s| s.to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let calls = names.iter().map(|name| async move {
+            let call = postgres.handle_action(serde_json::json!({
+                "action": "catalog_tables",
+                "profile_name": name,
+            }));
+            let outcome = match tokio::time::timeout(Duration::from_millis(timeout_ms), call).await
+            {
+                Ok(Ok(result)) => {
+                    let tables: Vec<String> = result
+                        .get("rows")
+                        .and_then(|v| v.as_array())
+                        .map(|rows| {
+                            rows.iter()
+                                .map(|row| {
+                                    format!(
+                                        "{}.{}",
+                                        row.get("schema").and_then(|v| v.as_str()).unwrap_or(""),
+                                        row.get("name").and_then(|v| v.as_str()).unwrap_or("")
+                                    )
+                                })
+                                .collect()
+                        })
+                        .unwrap_or_default();
+                    serde_json::json!({ "status": "ok", "tables": tables })
+                }
+                Ok(Err(err)) => serde_json::json!({ "status": "error", "error": err.message }),
+                Err(_) => serde_json::json!({
+                    "status": "timeout",
+                    "error": format!("catalog_tables timed out after {}ms", timeout_ms),
+                }),
+            };
+            (name.clone(), outcome)
+        });
+        let results = futures::future::join_all(calls).await;
+        let mut catalogs = serde_json::Map::new();
+        for (name, outcome) in results {
+            catalogs.insert(name, outcome);
+        }
+        Ok(serde_json::json!({ "count": catalogs.len(), "catalogs": catalogs }))
+    }
+
+    async fn fetch_source(
+        &self,
+        name: &str,
+        args: &Value,
+        timeout_ms: u64,
+    ) -> Result<Value, ToolError> {
+        match name {
+            "repo" => self.fetch_repo(args).await,
+            "profiles" => self.fetch_profiles(),
+            "projects" => self.fetch_projects(),
+            "postgres" => self.fetch_postgres(timeout_ms).await,
+            "jobs" => self.fetch_jobs(),
+            "artifacts" => self.fetch_artifacts(),
+            _ => Err(ToolError::invalid_params(format!(
+                "Unknown context source: {}",
+                name
+            ))),
+        }
+    }
+
+    async fn refresh_sources(&self, names: &[String], args: &Value, timeout_ms: u64) -> Vec<Value> {
+        let refreshes = names.iter().map(|name| async move {
+            let started = Instant::now();
+            // Per-profile catalog calls carry their own timeout; the outer one is a backstop.
+            let budget = if name == "postgres" {
+                timeout_ms.saturating_mul(2)
+            } else {
+                timeout_ms
+            };
+            let outcome = match tokio::time::timeout(
+                Duration::from_millis(budget),
+                self.fetch_source(name, args, timeout_ms),
+            )
+            .await
+            {
+                Ok(result) => result,
+                Err(_) => Err(ToolError::timeout(format!(
+                    "context source '{}' timed out after {}ms",
+                    name, budget
+                ))),
+            };
+            let duration_ms = started.elapsed().as_millis() as u64;
+            self.context_service
+                .record_source(name, outcome, duration_ms)
+        });
+        let reports = futures::future::join_all(refreshes).await;
+        self.logger.debug(
+            "context sources refreshed",
+            Some(&serde_json::json!({ "sources": names })),
+        );
+        reports
+    }
+
+    async fn refresh(&self, args: &Value) -> Result<Value, ToolError> {
+        let names = requested_sources(args)?;
+        let timeout_ms = optional_ms(args, "timeout_ms")?.unwrap_or(DEFAULT_SOURCE_TIMEOUT_MS);
+        let mut next = args.clone();
+        if let Value::Object(map) = &mut next {
+            map.insert("refresh".to_string(), Value::Bool(true));
+        }
+        let reports = self.refresh_sources(&names, &next, timeout_ms).await;
+        let mut result = if names.iter().any(|name| name == "repo") {
+            self.context_service.get_context(&next).await?
+        } else {
+            serde_json::json!({ "success": true })
+        };
+        result["refreshed"] = Value::Array(reports);
+        result["sources"] = self.context_service.source_freshness(&names, None, false);
+        Ok(result)
+    }
+
+    async fn summary(&self, args: &Value) -> Result<Value, ToolError> {
+        let names = requested_sources(args)?;
+        let max_age_ms = optional_ms(args, "max_age_ms")?;
+        let timeout_ms = optional_ms(args, "timeout_ms")?.unwrap_or(DEFAULT_SOURCE_TIMEOUT_MS);
+        let force = args
+            .get("refresh")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let to_refresh = if force {
+            names.clone()
+        } else if let Some(max_age_ms) = max_age_ms {
+            self.context_service.stale_sources(&names, max_age_ms)
+        } else {
+            Vec::new()
+        };
+        let reports = if to_refresh.is_empty() {
+            Vec::new()
+        } else {
+            self.refresh_sources(&to_refresh, args, timeout_ms).await
+        };
+        let result = self.context_service.get_context(args).await?;
+        let mut summary = result.get("context").cloned().unwrap_or(Value::Null);
+        let sources = self
+            .context_service
+            .source_freshness(&names, max_age_ms, true);
+        let stale: Vec<String> = sources
+            .as_object()
+            .map(|map| {
+                map.iter()
+                    .filter(|(_, entry)| entry.get("stale").and_then(|v| v.as_bool()) == Some(true))
+                    .map(|(name, _)| name.clone())
+                    .collect()
+            })
+            .unwrap_or_default();
+        summary["sources"] = sources;
+        summary["stale_sources"] = serde_json::json!(stale);
+        Ok(serde_json::json!({
+            "success": true,
+            "summary": summary,
+            "refreshed": to_refresh,
+            "refresh_reports": reports,
+        }))
     }
 
     pub async fn handle_action(&self, args: Value) -> Result<Value, ToolError> {
         let action = args.get("action");
         match action.and_then(|v| v.as_str()).unwrap_or("") {
             "get" => self.context_service.get_context(&args).await,
-            "refresh" => {
-                let mut next = args.clone();
-                if let Value::Object(map) = &mut next {
-                    map.insert("refresh".to_string(), Value::Bool(true));
-                }
-                self.context_service.get_context(&next).await
-            }
-            "summary" => {
-                let mut next = args.clone();
-                if let Value::Object(map) = &mut next {
-                    map.insert(
-                        "refresh".to_string(),
-                        Value::Bool(
-                            map.get("refresh")
-                                .and_then(|v| v.as_bool())
-                                .unwrap_or(false),
-                        ),
-                    );
-                }
-                let result = self.context_service.get_context(&next).await?;
+            "refresh" => self.refresh(&args).await,
+            "summary" => self.summary(&args).await,
+            "list" => Ok(serde_json::json!({"success": true, "items": []})),
+            "stats" => {
+                let names = requested_sources(&args)?;
+                let max_age_ms = optional_ms(&args, "max_age_ms")?;
                 Ok(serde_json::json!({
                     "success": true,
-                    "summary": result.get("context").cloned().unwrap_or(Value::Null),
+                    "stats": {
+                        "sources": self.context_service.source_freshness(&names, max_age_ms, false),
+                    },
                 }))
             }
-            "list" => Ok(serde_json::json!({"success": true, "items": []})),
-            "stats" => Ok(serde_json::json!({"success": true, "stats": {}})),
             _ => Err(unknown_action_error("context", action, CONTEXT_ACTIONS)),
         }
     }
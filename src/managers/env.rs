@@ -5,7 +5,9 @@ use crate::services::profile::ProfileService;
 use crate::services::project_resolver::ProjectResolver;
 use crate::services::secret_ref::SecretRefResolver;
 use crate::services::validation::Validation;
+use crate::utils::dotenv::{escape_env_value, DotenvFile, SetOutcome};
 use crate::utils::feature_flags::is_allow_secret_export_enabled;
+use crate::utils::shell::{ensure_shell_arg, restart_service_command};
 use crate::utils::stdin::{apply_stdin_source, resolve_stdin_source};
 use crate::utils::tool_errors::unknown_action_error;
 use once_cell::sync::Lazy;
@@ -13,7 +15,7 @@ use regex::Regex;
 use serde_json::Value;
 use ssh2::{FileStat, OpenFlags, OpenType};
 use std::collections::BTreeMap;
-use std::io::Write;
+use std::io::{Read, Write};
 use std::path::Path;
 use std::sync::Arc;
 
@@ -25,7 +27,13 @@ pub(crate) const ENV_ACTIONS: &[&str] = &[
     "profile_delete",
     "write_remote",
     "run_remote",
+    "env_get",
+    "env_set",
+    "env_diff",
+    "env_validate",
 ];
+const MAX_ENV_FILE_BYTES: u64 = 1024 * 1024;
+const REDACTED_VALUE: &str = "[REDACTED]";
 
 static ENV_KEY_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[A-Za-z_][A-Za-z0-9_]*$").unwrap());
 
@@ -96,22 +104,6 @@ fn normalize_env_key(key: &str) -> Result<String, ToolError> {
     Ok(trimmed.to_string())
 }
 
-fn escape_env_value(value: &str) -> String {
-    let mut escaped = String::with_capacity(value.len() + 2);
-    escaped.push('"');
-    for ch in value.chars() {
-        match ch {
-            '\\' => escaped.push_str("\\\\"),
-            '\r' => escaped.push_str("\\r"),
-            '\n' => escaped.push_str("\\n"),
-            '"' => escaped.push_str("\\\""),
-            _ => escaped.push(ch),
-        }
-    }
-    escaped.push('"');
-    escaped
-}
-
 fn render_dotenv(vars: &BTreeMap<String, String>) -> String {
     if vars.is_empty() {
         return "\n".to_string();
@@ -134,6 +126,222 @@ fn random_token() -> String {
     hex::encode(bytes)
 }
 
+fn parse_mode(args: &Value) -> Option<u32> {
+    args.get("mode")
+        .and_then(|v| {
+            v.as_i64()
+                .or_else(|| v.as_str().and_then(|s| s.parse::<i64>().ok()))
+        })
+        .map(|mode| mode.max(0) as u32)
+}
+
+// Writes to a temp file next to the target and renames it into place; an existing file is
+// moved aside first and restored if the swap fails.
+fn write_remote_atomic(
+    sftp: &ssh2::Sftp,
+    remote_path: &str,
+    content: &str,
+    mode: u32,
+    exists: bool,
+    keep_backup: bool,
+) -> Result<Option<String>, ToolError> {
+    let tmp_path = format!(
+        "{}.tmp-{}-{}-{}",
+        remote_path,
+        std::process::id(),
+        chrono::Utc::now().timestamp_millis(),
+        random_token()
+    );
+    let backup_path = if exists {
+        Some(format!(
+            "{}.bak-{}-{}",
+            remote_path,
+            chrono::Utc::now().timestamp_millis(),
+            random_token()
+        ))
+    } else {
+        None
+    };
+
+    let mut moved_to_backup = false;
+    let mut keep_backup_path: Option<String> = None;
+
+    let attempt = (|| -> Result<(), ToolError> {
+        {
+            let mut remote_file = sftp
+                .open_mode(
+                    Path::new(&tmp_path),
+                    OpenFlags::WRITE | OpenFlags::CREATE | OpenFlags::TRUNCATE,
+                    mode as i32,
+                    OpenType::File,
+                )
+                .map_err(|err| ToolError::internal(err.to_string()))?;
+            remote_file
+                .write_all(content.as_bytes())
+                .map_err(|err| ToolError::internal(err.to_string()))?;
+        }
+
+        let _ = sftp.setstat(
+            Path::new(&tmp_path),
+            FileStat {
+                size: None,
+                uid: None,
+                gid: None,
+                perm: Some(mode),
+                atime: None,
+                mtime: None,
+            },
+        );
+
+        if exists {
+            if let Some(backup_path) = backup_path.as_ref() {
+                sftp.rename(Path::new(&remote_path), Path::new(backup_path), None)
+                    .map_err(|err| ToolError::internal(err.to_string()))?;
+                moved_to_backup = true;
+            }
+        }
+
+        sftp.rename(Path::new(&tmp_path), Path::new(&remote_path), None)
+            .map_err(|err| ToolError::internal(err.to_string()))?;
+
+        let _ = sftp.setstat(
+            Path::new(&remote_path),
+            FileStat {
+                size: None,
+                uid: None,
+                gid: None,
+                perm: Some(mode),
+                atime: None,
+                mtime: None,
+            },
+        );
+
+        if moved_to_backup {
+            if let Some(backup_path) = backup_path.as_ref() {
+                if keep_backup {
+                    keep_backup_path = Some(backup_path.clone());
+                } else {
+                    let _ = sftp.unlink(Path::new(backup_path));
+                }
+            }
+        }
+        Ok(())
+    })();
+
+    if let Err(err) = attempt {
+        let _ = sftp.unlink(Path::new(&tmp_path));
+        if moved_to_backup {
+            if let Some(backup_path) = backup_path.as_ref() {
+                let _ = sftp.rename(Path::new(backup_path), Path::new(&remote_path), None);
+            }
+        }
+        return Err(err);
+    }
+
+    Ok(keep_backup_path)
+}
+
+fn read_remote_text(
+    sftp: &ssh2::Sftp,
+    remote_path: &str,
+) -> Result<Option<(String, Option<u32>)>, ToolError> {
+    let stat = match sftp.stat(Path::new(remote_path)) {
+        Ok(stat) => stat,
+        Err(err) => {
+            let io_err: std::io::Error = err.into();
+            if io_err.kind() == std::io::ErrorKind::NotFound {
+                return Ok(None);
+            }
+            return Err(ToolError::internal(format!(
+                "Failed to stat remote path: {}",
+                io_err
+            )));
+        }
+    };
+    if stat.size.unwrap_or(0) > MAX_ENV_FILE_BYTES {
+        return Err(ToolError::invalid_params(format!(
+            "Remote env file is too large ({} bytes, max {})",
+            stat.size.unwrap_or(0),
+            MAX_ENV_FILE_BYTES
+        ))
+        .with_details(serde_json::json!({"remote_path": remote_path})));
+    }
+    let mut file = sftp
+        .open(Path::new(remote_path))
+        .map_err(|err| ToolError::internal(err.to_string()))?;
+    let mut buf = Vec::new();
+    file.read_to_end(&mut buf)
+        .map_err(|err| ToolError::internal(err.to_string()))?;
+    let content = String::from_utf8(buf).map_err(|_| {
+        ToolError::invalid_params("Remote env file is not valid UTF-8")
+            .with_details(serde_json::json!({"remote_path": remote_path}))
+    })?;
+    Ok(Some((content, stat.perm.map(|perm| perm & 0o7777))))
+}
+
+struct RemoteEnvFile {
+    ssh_profile_name: String,
+    remote_path: String,
+    target_name: Option<String>,
+}
+
+impl RemoteEnvFile {
+    fn missing_error(&self) -> ToolError {
+        ToolError::not_found(format!("Remote env file not found: {}", self.remote_path))
+            .with_details(serde_json::json!({
+                "ssh_profile_name": self.ssh_profile_name,
+                "remote_path": self.remote_path,
+            }))
+    }
+}
+
+enum DiffSource {
+    Expected(serde_json::Map<String, Value>),
+    Remote(RemoteEnvFile),
+}
+
+#[derive(Default)]
+struct EnvSetOutcome {
+    created: bool,
+    added: Vec<String>,
+    updated: Vec<String>,
+    removed: Vec<String>,
+    unchanged: Vec<String>,
+    backup_path: Option<String>,
+}
+
+impl EnvSetOutcome {
+    fn changed(&self) -> bool {
+        !self.added.is_empty() || !self.updated.is_empty() || !self.removed.is_empty()
+    }
+}
+
+// Mirrors ssh deploy_file: `restart` names a systemd unit, `restart_command` is free-form.
+fn resolve_restart_command(args: &Value) -> Result<Option<(Option<String>, String)>, ToolError> {
+    let service = args
+        .get("restart")
+        .and_then(|v| v.as_str())
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty());
+    let command = args
+        .get("restart_command")
+        .and_then(|v| v.as_str())
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty());
+    match (service, command) {
+        (Some(_), Some(_)) => Err(ToolError::invalid_params(
+            "Provide only one of restart (service) or restart_command",
+        )),
+        (Some(service), None) => {
+            ensure_shell_arg(&service, "restart")?;
+            let command = restart_service_command(&service);
+            Ok(Some((Some(service), command)))
+        }
+        (None, Some(command)) => Ok(Some((None, command))),
+        (None, None) => Ok(None),
+    }
+}
+
 #[derive(Clone)]
 pub struct EnvManager {
     logger: Logger,
@@ -183,6 +391,10 @@ impl EnvManager {
             }
             "write_remote" => self.write_remote(&args).await,
             "run_remote" => self.run_remote(&args).await,
+            "env_get" => self.env_get(&args).await,
+            "env_set" => self.env_set(&args).await,
+            "env_diff" => self.env_diff(&args).await,
+            "env_validate" => self.env_validate(&args).await,
             _ => Err(unknown_action_error("env", action, ENV_ACTIONS)),
         }
     }
@@ -463,28 +675,454 @@ impl EnvManager {
         }))
     }
 
+    fn resolve_remote_path(
+        &self,
+        args: &Value,
+        defaults: &ProjectDefaults,
+    ) -> Result<String, ToolError> {
+        if let Some(path) = args.get("remote_path").and_then(|v| v.as_str()) {
+            return self.validation.ensure_string(
+                &Value::String(path.to_string()),
+                "remote_path",
+                false,
+            );
+        }
+        if let Some(env_path) = defaults.env_path.as_ref() {
+            return self.validation.ensure_string(
+                &Value::String(env_path.clone()),
+                "remote_path",
+                false,
+            );
+        }
+        if let Some(cwd) = defaults.cwd.as_ref() {
+            let cwd = self
+                .validation
+                .ensure_string(&Value::String(cwd.clone()), "cwd", false)?;
+            return Ok(Path::new(&cwd).join(".env").to_string_lossy().to_string());
+        }
+        Err(ToolError::invalid_params("remote_path is required (or configure project target.env_path / target.cwd)")
+            .with_hint("Pass args.remote_path explicitly, or set target.env_path / target.cwd in the project target.".to_string()))
+    }
+
+    async fn resolve_remote_env(&self, args: &Value) -> Result<RemoteEnvFile, ToolError> {
+        let defaults = self.resolve_profiles_from_project(args).await;
+        Ok(RemoteEnvFile {
+            ssh_profile_name: self.resolve_ssh_profile_name(args, &defaults)?,
+            remote_path: self.resolve_remote_path(args, &defaults)?,
+            target_name: defaults.target_name,
+        })
+    }
+
+    // compare_* args describe the other side of env_diff; anything not overridden falls back
+    // to the same project/target resolution as the primary file.
+    async fn resolve_compare_env(&self, args: &Value) -> Result<Option<RemoteEnvFile>, ToolError> {
+        let compare_target = args.get("compare_target").and_then(|v| v.as_str());
+        let compare_ssh = args
+            .get("compare_ssh_profile_name")
+            .and_then(|v| v.as_str());
+        let compare_path = args.get("compare_remote_path").and_then(|v| v.as_str());
+        if compare_target.is_none() && compare_ssh.is_none() && compare_path.is_none() {
+            return Ok(None);
+        }
+
+        let mut scoped = args.clone();
+        if let Value::Object(map) = &mut scoped {
+            for key in ["ssh_profile_name", "ssh_profile", "remote_path"] {
+                map.remove(key);
+            }
+            if let Some(target) = compare_target {
+                let target = self
+                    .validation
+                    .ensure_identifier(target, "compare_target")?;
+                map.remove("project_target");
+                map.insert("target".to_string(), Value::String(target));
+            }
+            if let Some(name) = compare_ssh {
+                map.insert(
+                    "ssh_profile_name".to_string(),
+                    Value::String(name.to_string()),
+                );
+            }
+            if let Some(path) = compare_path {
+                map.insert("remote_path".to_string(), Value::String(path.to_string()));
+            }
+        }
+        let other = self.resolve_remote_env(&scoped).await?;
+        if let Some(target) = compare_target {
+            if other.target_name.as_deref() != Some(target) {
+                return Err(ToolError::invalid_params(format!(
+                    "compare_target not found in the active project: {}",
+                    target
+                ))
+                .with_hint("Pass a target defined in the project (project.targets) or use compare_ssh_profile_name + compare_remote_path.".to_string()));
+            }
+        }
+        Ok(Some(other))
+    }
+
+    async fn read_remote_env(
+        &self,
+        remote: &RemoteEnvFile,
+    ) -> Result<(DotenvFile, Option<u32>), ToolError> {
+        let remote_path = remote.remote_path.clone();
+        let loaded = self
+            .ssh_manager
+            .with_sftp(
+                &serde_json::json!({"profile_name": remote.ssh_profile_name}),
+                move |sftp| read_remote_text(sftp, &remote_path),
+            )
+            .await?;
+        let (content, perm) = loaded.ok_or_else(|| remote.missing_error())?;
+        Ok((DotenvFile::parse(&content), perm))
+    }
+
+    fn reveal_values(args: &Value) -> bool {
+        args.get("include_values")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+            && Self::allow_secret_export()
+    }
+
+    async fn resolve_value_map(
+        &self,
+        map: serde_json::Map<String, Value>,
+        args: &Value,
+    ) -> Result<BTreeMap<String, String>, ToolError> {
+        let resolved = self.resolve_env_variables(map, args).await?;
+        let mut out = BTreeMap::new();
+        for (key, value) in resolved {
+            let rendered = value
+                .as_str()
+                .map(|s| s.to_string())
+                .unwrap_or_else(|| value.to_string());
+            out.insert(normalize_env_key(&key)?, rendered);
+        }
+        Ok(out)
+    }
+
+    async fn env_get(&self, args: &Value) -> Result<Value, ToolError> {
+        let remote = self.resolve_remote_env(args).await?;
+        let (file, _) = self.read_remote_env(&remote).await?;
+        let reveal = Self::reveal_values(args);
+        let values = file.to_map();
+        let keys = file.keys();
+        let empty_keys: Vec<&String> = keys
+            .iter()
+            .filter(|key| {
+                values
+                    .get(*key)
+                    .map(|v| v.trim().is_empty())
+                    .unwrap_or(true)
+            })
+            .collect();
+        let variables: serde_json::Map<String, Value> = keys
+            .iter()
+            .map(|key| {
+                let value = if reveal {
+                    values.get(key).cloned().unwrap_or_default()
+                } else {
+                    REDACTED_VALUE.to_string()
+                };
+                (key.clone(), Value::String(value))
+            })
+            .collect();
+
+        Ok(serde_json::json!({
+            "success": true,
+            "ssh_profile_name": remote.ssh_profile_name,
+            "remote_path": remote.remote_path,
+            "count": keys.len(),
+            "keys": keys,
+            "empty_keys": empty_keys,
+            "duplicate_keys": file.duplicate_keys(),
+            "variables": variables,
+            "values_redacted": !reveal,
+        }))
+    }
+
+    async fn env_set(&self, args: &Value) -> Result<Value, ToolError> {
+        let remote = self.resolve_remote_env(args).await?;
+        let requested = normalize_string_map(args.get("set"), "set", true)?.unwrap_or_default();
+        let mut unset: Vec<String> = Vec::new();
+        match args.get("unset") {
+            None | Some(Value::Null) => {}
+            Some(Value::Array(items)) => {
+                for item in items {
+                    let key = item.as_str().ok_or_else(|| {
+                        ToolError::invalid_params("unset must be an array of strings")
+                    })?;
+                    unset.push(normalize_env_key(key)?);
+                }
+            }
+            Some(_) => {
+                return Err(ToolError::invalid_params(
+                    "unset must be an array of strings",
+                ))
+            }
+        }
+        let (to_set, to_unset): (
+            serde_json::Map<String, Value>,
+            serde_json::Map<String, Value>,
+        ) = requested
+            .into_iter()
+            .partition(|(_, value)| !value.is_null());
+        for key in to_unset.keys() {
+            unset.push(normalize_env_key(key)?);
+        }
+        let to_set = self.resolve_value_map(to_set, args).await?;
+        if to_set.is_empty() && unset.is_empty() {
+            return Err(ToolError::invalid_params("env_set requires set and/or unset")
+                .with_hint("Pass args.set as {KEY: value} (null removes a key) and/or args.unset as [KEY].".to_string()));
+        }
+        let restart = resolve_restart_command(args)?;
+
+        let create = args
+            .get("create")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let mkdirs = args
+            .get("mkdirs")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let keep_backup = args
+            .get("backup")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let mode = parse_mode(args);
+
+        let remote_path = remote.remote_path.clone();
+        let missing = remote.missing_error().with_hint(
+            "Set create=true (optionally mkdirs=true) to create the env file.".to_string(),
+        );
+        let outcome = self
+            .ssh_manager
+            .with_sftp(
+                &serde_json::json!({"profile_name": remote.ssh_profile_name}),
+                move |sftp| {
+                    let existing = read_remote_text(sftp, &remote_path)?;
+                    if existing.is_none() && !create {
+                        return Err(missing);
+                    }
+                    let exists = existing.is_some();
+                    let (content, perm) = existing.unwrap_or_default();
+                    let mut file = DotenvFile::parse(&content);
+
+                    let mut outcome = EnvSetOutcome {
+                        created: !exists,
+                        ..Default::default()
+                    };
+                    for (key, value) in to_set.iter() {
+                        match file.set(key, value) {
+                            SetOutcome::Added => outcome.added.push(key.clone()),
+                            SetOutcome::Updated => outcome.updated.push(key.clone()),
+                            SetOutcome::Unchanged => outcome.unchanged.push(key.clone()),
+                        }
+                    }
+                    for key in unset.iter() {
+                        if file.remove(key) && !outcome.removed.contains(key) {
+                            outcome.removed.push(key.clone());
+                        }
+                    }
+                    if !outcome.changed() {
+                        return Ok(outcome);
+                    }
+
+                    if mkdirs && !exists {
+                        ensure_remote_dir(sftp, &remote_path)?;
+                    }
+                    // Keep the file's current permissions unless the caller asks for a mode.
+                    let mode = mode.or(perm).unwrap_or(0o600);
+                    outcome.backup_path = write_remote_atomic(
+                        sftp,
+                        &remote_path,
+                        &file.render(),
+                        mode,
+                        exists,
+                        keep_backup,
+                    )?;
+                    Ok(outcome)
+                },
+            )
+            .await?;
+
+        let changed = outcome.changed();
+        let mut response = serde_json::json!({
+            "success": true,
+            "ssh_profile_name": remote.ssh_profile_name,
+            "remote_path": remote.remote_path,
+            "changed": changed,
+            "created": changed && outcome.created,
+            "added": outcome.added,
+            "updated": outcome.updated,
+            "removed": outcome.removed,
+            "unchanged": outcome.unchanged,
+            "restart": Value::Null,
+        });
+        if let Some(backup_path) = outcome.backup_path {
+            response["backup_path"] = Value::String(backup_path);
+        }
+
+        // Restart only when the file actually changed; a no-op set leaves the service alone.
+        if let Some((service, command)) = restart.filter(|_| changed) {
+            let started = std::time::Instant::now();
+            let out = self
+                .ssh_manager
+                .handle_action(serde_json::json!({
+                    "action": "exec",
+                    "profile_name": remote.ssh_profile_name,
+                    "command": command,
+                    "pty": false,
+                }))
+                .await?;
+            let exit_code = out.get("exitCode").and_then(|v| v.as_i64());
+            let timed_out = out
+                .get("timedOut")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false)
+                || out
+                    .get("hardTimedOut")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+            response["restart"] = serde_json::json!({
+                "requested": true,
+                "service": service,
+                "exit_code": exit_code,
+                "timed_out": timed_out,
+                "restart_ms": started.elapsed().as_millis(),
+            });
+            if exit_code.unwrap_or(-1) != 0 || timed_out {
+                response["success"] = Value::Bool(false);
+                response["code"] = Value::String("RESTART_FAILED".to_string());
+            }
+        }
+        Ok(response)
+    }
+
+    async fn env_diff(&self, args: &Value) -> Result<Value, ToolError> {
+        let source = match normalize_string_map(args.get("expected"), "expected", false)? {
+            Some(expected) => DiffSource::Expected(expected),
+            None => match self.resolve_compare_env(args).await? {
+                Some(other) => DiffSource::Remote(other),
+                None => {
+                    return Err(ToolError::invalid_params(
+                        "env_diff requires expected or compare_target / compare_remote_path",
+                    )
+                    .with_hint("Pass args.expected as {KEY: value}, or args.compare_target (project target) / compare_ssh_profile_name + compare_remote_path.".to_string()))
+                }
+            },
+        };
+
+        let remote = self.resolve_remote_env(args).await?;
+        let (file, _) = self.read_remote_env(&remote).await?;
+        let left = file.to_map();
+
+        let (other, right) = match source {
+            DiffSource::Expected(expected) => {
+                let right = self.resolve_value_map(expected, args).await?;
+                (serde_json::json!({"kind": "expected"}), right)
+            }
+            DiffSource::Remote(other) => {
+                let (other_file, _) = self.read_remote_env(&other).await?;
+                (
+                    serde_json::json!({
+                        "kind": "remote",
+                        "ssh_profile_name": other.ssh_profile_name,
+                        "remote_path": other.remote_path,
+                    }),
+                    other_file.to_map(),
+                )
+            }
+        };
+
+        let reveal = Self::reveal_values(args);
+        let only_remote: Vec<&String> = left.keys().filter(|k| !right.contains_key(*k)).collect();
+        let only_other: Vec<&String> = right.keys().filter(|k| !left.contains_key(*k)).collect();
+        let mut changed: Vec<&String> = Vec::new();
+        let mut changes = serde_json::Map::new();
+        let mut unchanged_count = 0usize;
+        for (key, value) in left.iter() {
+            let Some(other_value) = right.get(key) else {
+                continue;
+            };
+            if value == other_value {
+                unchanged_count += 1;
+                continue;
+            }
+            changed.push(key);
+            if reveal {
+                changes.insert(
+                    key.clone(),
+                    serde_json::json!({"remote": value, "other": other_value}),
+                );
+            }
+        }
+
+        let mut response = serde_json::json!({
+            "success": true,
+            "ssh_profile_name": remote.ssh_profile_name,
+            "remote_path": remote.remote_path,
+            "other": other,
+            "identical": only_remote.is_empty() && only_other.is_empty() && changed.is_empty(),
+            "only_remote": only_remote,
+            "only_other": only_other,
+            "changed": changed,
+            "unchanged_count": unchanged_count,
+            "values_redacted": !reveal,
+        });
+        if reveal {
+            response["changes"] = Value::Object(changes);
+        }
+        Ok(response)
+    }
+
+    async fn env_validate(&self, args: &Value) -> Result<Value, ToolError> {
+        let required = match args.get("required") {
+            Some(Value::Array(items)) if !items.is_empty() => items
+                .iter()
+                .map(|item| {
+                    item.as_str()
+                        .ok_or_else(|| {
+                            ToolError::invalid_params("required must be an array of strings")
+                        })
+                        .and_then(normalize_env_key)
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            _ => {
+                return Err(ToolError::invalid_params(
+                    "required must be a non-empty array of env var keys",
+                ))
+            }
+        };
+        let remote = self.resolve_remote_env(args).await?;
+        let (file, _) = self.read_remote_env(&remote).await?;
+
+        let mut missing = Vec::new();
+        let mut empty = Vec::new();
+        for key in required.iter() {
+            match file.get(key) {
+                None => missing.push(key.clone()),
+                Some(value) if value.trim().is_empty() => empty.push(key.clone()),
+                Some(_) => {}
+            }
+        }
+        let valid = missing.is_empty() && empty.is_empty();
+        Ok(serde_json::json!({
+            "success": valid,
+            "valid": valid,
+            "ssh_profile_name": remote.ssh_profile_name,
+            "remote_path": remote.remote_path,
+            "required_count": required.len(),
+            "missing": missing,
+            "empty": empty,
+        }))
+    }
+
     async fn write_remote(&self, args: &Value) -> Result<Value, ToolError> {
         let defaults = self.resolve_profiles_from_project(args).await;
         let env_profile_name = self.resolve_env_profile_name(args, &defaults)?;
         let ssh_profile_name = self.resolve_ssh_profile_name(args, &defaults)?;
 
-        let mut remote_path = args
-            .get("remote_path")
-            .and_then(|v| v.as_str())
-            .map(|s| {
-                self.validation
-                    .ensure_string(&Value::String(s.to_string()), "remote_path", false)
-            })
-            .transpose()?;
-
-        let mode = args
-            .get("mode")
-            .and_then(|v| {
-                v.as_i64()
-                    .or_else(|| v.as_str().and_then(|s| s.parse::<i64>().ok()))
-            })
-            .unwrap_or(0o600)
-            .max(0) as u32;
+        let mode = parse_mode(args).unwrap_or(0o600);
         let mkdirs = args
             .get("mkdirs")
             .and_then(|v| v.as_bool())
@@ -498,25 +1136,7 @@ impl EnvManager {
             .and_then(|v| v.as_bool())
             .unwrap_or(false);
 
-        if remote_path.is_none() {
-            if let Some(env_path) = defaults.env_path.as_ref() {
-                remote_path = Some(self.validation.ensure_string(
-                    &Value::String(env_path.clone()),
-                    "remote_path",
-                    false,
-                )?);
-            } else if let Some(cwd) = defaults.cwd.as_ref() {
-                let cwd =
-                    self.validation
-                        .ensure_string(&Value::String(cwd.clone()), "cwd", false)?;
-                let joined = Path::new(&cwd).join(".env");
-                remote_path = Some(joined.to_string_lossy().to_string());
-            } else {
-                return Err(ToolError::invalid_params("remote_path is required (or configure project target.env_path / target.cwd)")
-                    .with_hint("Pass args.remote_path explicitly, or set target.env_path / target.cwd in the project target.".to_string()));
-            }
-        }
-        let remote_path = remote_path.unwrap();
+        let remote_path = self.resolve_remote_path(args, &defaults)?;
 
         let bundle = self.load_env_bundle(&env_profile_name).await?;
         let resolved_vars = self
@@ -570,104 +1190,14 @@ impl EnvManager {
                         .with_details(serde_json::json!({"remote_path": remote_clone})));
                     }
 
-                    let tmp_path = format!(
-                        "{}.tmp-{}-{}-{}",
-                        remote_clone,
-                        std::process::id(),
-                        chrono::Utc::now().timestamp_millis(),
-                        random_token()
-                    );
-                    let backup_path = if exists {
-                        Some(format!(
-                            "{}.bak-{}-{}",
-                            remote_clone,
-                            chrono::Utc::now().timestamp_millis(),
-                            random_token()
-                        ))
-                    } else {
-                        None
-                    };
-
-                    let mut moved_to_backup = false;
-                    let mut keep_backup_path: Option<String> = None;
-
-                    let attempt = (|| -> Result<(), ToolError> {
-                        {
-                            let mut remote_file = sftp
-                                .open_mode(
-                                    Path::new(&tmp_path),
-                                    OpenFlags::WRITE | OpenFlags::CREATE | OpenFlags::TRUNCATE,
-                                    mode as i32,
-                                    OpenType::File,
-                                )
-                                .map_err(|err| ToolError::internal(err.to_string()))?;
-                            remote_file
-                                .write_all(content_clone.as_bytes())
-                                .map_err(|err| ToolError::internal(err.to_string()))?;
-                        }
-
-                        let _ = sftp.setstat(
-                            Path::new(&tmp_path),
-                            FileStat {
-                                size: None,
-                                uid: None,
-                                gid: None,
-                                perm: Some(mode),
-                                atime: None,
-                                mtime: None,
-                            },
-                        );
-
-                        if exists && overwrite {
-                            if let Some(backup_path) = backup_path.as_ref() {
-                                sftp.rename(Path::new(&remote_clone), Path::new(backup_path), None)
-                                    .map_err(|err| ToolError::internal(err.to_string()))?;
-                                moved_to_backup = true;
-                            }
-                        }
-
-                        sftp.rename(Path::new(&tmp_path), Path::new(&remote_clone), None)
-                            .map_err(|err| ToolError::internal(err.to_string()))?;
-
-                        let _ = sftp.setstat(
-                            Path::new(&remote_clone),
-                            FileStat {
-                                size: None,
-                                uid: None,
-                                gid: None,
-                                perm: Some(mode),
-                                atime: None,
-                                mtime: None,
-                            },
-                        );
-
-                        if moved_to_backup {
-                            if let Some(backup_path) = backup_path.as_ref() {
-                                if keep_backup {
-                                    keep_backup_path = Some(backup_path.clone());
-                                } else {
-                                    let _ = sftp.unlink(Path::new(backup_path));
-                                }
-                            }
-                        }
-                        Ok(())
-                    })();
-
-                    if let Err(err) = attempt {
-                        let _ = sftp.unlink(Path::new(&tmp_path));
-                        if moved_to_backup {
-                            if let Some(backup_path) = backup_path.as_ref() {
-                                let _ = sftp.rename(
-                                    Path::new(backup_path),
-                                    Path::new(&remote_clone),
-                                    None,
-                                );
-                            }
-                        }
-                        return Err(err);
-                    }
-
-                    Ok(keep_backup_path)
+                    write_remote_atomic(
+                        sftp,
+                        &remote_clone,
+                        &content_clone,
+                        mode,
+                        exists,
+                        keep_backup,
+                    )
                 },
             )
             .await?;
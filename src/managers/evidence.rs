@@ -1,27 +1,237 @@
 use crate::errors::ToolError;
+use crate::managers::api::ApiManager;
+use crate::managers::postgres::PostgresManager;
+use crate::managers::ssh::SshManager;
 use crate::services::evidence::EvidenceService;
 use crate::services::logger::Logger;
+use crate::utils::feature_flags::is_unsafe_local_enabled;
 use crate::utils::listing::ListFilters;
+use crate::utils::shell::shell_quote;
 use crate::utils::tool_errors::unknown_action_error;
 use serde_json::Value;
+use sha2::{Digest, Sha256};
 use std::sync::Arc;
 
-pub(crate) const EVIDENCE_ACTIONS: &[&str] = &["list", "get"];
+pub(crate) const EVIDENCE_ACTIONS: &[&str] = &["list", "get", "create", "evidence_verify"];
+
+const VERIFIABLE_CHECKS: &[&str] = &["http_status", "file_hash", "sql_count"];
+
+fn normalize_hash(value: &Value) -> Option<String> {
+    value
+        .as_str()
+        .map(|s| s.trim().trim_start_matches("sha256:").to_lowercase())
+        .filter(|s| !s.is_empty())
+}
+
+fn with_action(target: &Value, action: &str) -> Value {
+    let mut call = target.clone();
+    if let Value::Object(map) = &mut call {
+        map.insert("action".to_string(), Value::String(action.to_string()));
+        map.remove("via");
+    }
+    call
+}
 
 #[derive(Clone)]
 pub struct EvidenceManager {
     logger: Logger,
     evidence_service: Arc<EvidenceService>,
+    api_manager: Option<Arc<ApiManager>>,
+    ssh_manager: Option<Arc<SshManager>>,
+    postgres_manager: Option<Arc<PostgresManager>>,
 }
 
 impl EvidenceManager {
-    pub fn new(logger: Logger, evidence_service: Arc<EvidenceService>) -> Self {
+    pub fn new(
+        logger: Logger,
+        evidence_service: Arc<EvidenceService>,
+        api_manager: Option<Arc<ApiManager>>,
+        ssh_manager: Option<Arc<SshManager>>,
+        postgres_manager: Option<Arc<PostgresManager>>,
+    ) -> Self {
         Self {
             logger: logger.child("evidence"),
             evidence_service,
+            api_manager,
+            ssh_manager,
+            postgres_manager,
+        }
+    }
+
+    async fn current_value(&self, check: &str, target: &Value) -> Result<Value, ToolError> {
+        match check {
+            "http_status" => {
+                let api = self.api_manager.as_ref().ok_or_else(|| {
+                    ToolError::internal("Api manager is not available for evidence_verify")
+                })?;
+                let method = target
+                    .get("method")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("GET")
+                    .to_uppercase();
+                if method != "GET" && method != "HEAD" {
+                    return Err(ToolError::denied(format!(
+                        "http_status re-checks only GET/HEAD (got {})",
+                        method
+                    )));
+                }
+                let mut call = with_action(target, "request");
+                call["method"] = Value::String(method);
+                let result = api.handle_action(call).await?;
+                Ok(result.get("status").cloned().unwrap_or(Value::Null))
+            }
+            "file_hash" => {
+                let path = target
+                    .get("path")
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.trim())
+                    .filter(|s| !s.is_empty())
+                    .ok_or_else(|| {
+                        ToolError::invalid_params("file_hash target.path is required")
+                    })?;
+                let via = target.get("via").and_then(|v| v.as_str()).unwrap_or("ssh");
+                if via == "local" {
+                    if !is_unsafe_local_enabled() {
+                        return Err(ToolError::denied(
+                            "Local file hashes require INFRA_UNSAFE_LOCAL=1",
+                        ));
+                    }
+                    let bytes = std::fs::read(path).map_err(|err| {
+                        ToolError::invalid_params(format!("path must be readable: {}", err))
+                    })?;
+                    return Ok(Value::String(hex::encode(Sha256::digest(&bytes))));
+                }
+                let ssh = self.ssh_manager.as_ref().ok_or_else(|| {
+                    ToolError::internal("Ssh manager is not available for evidence_verify")
+                })?;
+                let mut call = with_action(target, "exec");
+                call["command"] = Value::String(format!("sha256sum -- {}", shell_quote(path)));
+                if let Value::Object(map) = &mut call {
+                    map.remove("path");
+                }
+                let result = ssh.handle_action(call).await?;
+                let hash = result
+                    .get("stdout")
+                    .and_then(|v| v.as_str())
+                    .and_then(|stdout| stdout.split_whitespace().next())
+                    .map(|s| s.to_lowercase());
+                Ok(hash.map(Value::String).unwrap_or(Value::Null))
+            }
+            "sql_count" => {
+                let postgres = self.postgres_manager.as_ref().ok_or_else(|| {
+                    ToolError::internal("Postgres manager is not available for evidence_verify")
+                })?;
+                let result = postgres.handle_action(with_action(target, "count")).await?;
+                Ok(result.get("count").cloned().unwrap_or(Value::Null))
+            }
+            _ => Err(ToolError::invalid_params(format!(
+                "check '{}' is not verifiable",
+                check
+            ))),
         }
     }
 
+    async fn verify_assertion(&self, index: usize, assertion: &Value) -> Value {
+        let check = assertion
+            .get("check")
+            .and_then(|v| v.as_str())
+            .unwrap_or("");
+        let expected = assertion.get("expected").cloned().unwrap_or(Value::Null);
+        let captured = assertion.get("actual").cloned().unwrap_or(Value::Null);
+        let mut out = serde_json::json!({
+            "index": index,
+            "check": check,
+            "expected": expected,
+            "captured": captured,
+        });
+        let target = match assertion.get("target") {
+            Some(target) if target.is_object() && VERIFIABLE_CHECKS.contains(&check) => target,
+            _ => {
+                out["verified"] = Value::Bool(false);
+                out["reason"] = Value::String(format!(
+                    "not verifiable (supported checks with a target: {})",
+                    VERIFIABLE_CHECKS.join(", ")
+                ));
+                return out;
+            }
+        };
+        match self.current_value(check, target).await {
+            Ok(current) => {
+                let (pass, drift) = if check == "file_hash" {
+                    let current_hash = normalize_hash(&current);
+                    (
+                        current_hash.is_some() && current_hash == normalize_hash(&expected),
+                        current_hash != normalize_hash(&captured),
+                    )
+                } else {
+                    (current == expected, current != captured)
+                };
+                out["verified"] = Value::Bool(true);
+                out["current"] = current;
+                out["pass"] = Value::Bool(pass);
+                out["drift"] = Value::Bool(drift);
+            }
+            Err(err) => {
+                out["verified"] = Value::Bool(false);
+                out["reason"] = Value::String(err.message);
+            }
+        }
+        out
+    }
+
+    async fn verify(&self, args: &Value) -> Result<Value, ToolError> {
+        let id = args.get("id").and_then(|v| v.as_str()).unwrap_or("");
+        let loaded = self.evidence_service.get_evidence(id)?;
+        let payload = loaded.get("payload").cloned().unwrap_or(Value::Null);
+        let assertions = payload
+            .get("assertions")
+            .and_then(|v| v.as_array())
+            .cloned()
+            .unwrap_or_default();
+        if assertions.is_empty() {
+            return Err(
+                ToolError::invalid_params("evidence record has no assertions to verify")
+                    .with_hint(
+                        "Create records with action=create and assertions[].target to make them verifiable."
+                            .to_string(),
+                    ),
+            );
+        }
+        let mut results = Vec::new();
+        for (index, assertion) in assertions.iter().enumerate() {
+            results.push(self.verify_assertion(index, assertion).await);
+        }
+        let count = |key: &str, expected: bool| {
+            results
+                .iter()
+                .filter(|item| item.get(key).and_then(|v| v.as_bool()) == Some(expected))
+                .count()
+        };
+        let verified = count("verified", true);
+        let failed = count("pass", false);
+        let drifted = count("drift", true);
+        self.logger.info(
+            "evidence_verify",
+            Some(&serde_json::json!({ "id": loaded.get("id"), "verified": verified, "drifted": drifted })),
+        );
+        Ok(serde_json::json!({
+            "success": true,
+            "id": loaded.get("id").cloned().unwrap_or(Value::Null),
+            "captured_at": payload.get("captured_at").cloned().unwrap_or(Value::Null),
+            "verified_at": chrono::Utc::now().to_rfc3339(),
+            "passed": verified > 0 && failed == 0,
+            "drift": drifted > 0,
+            "counts": {
+                "total": results.len(),
+                "verified": verified,
+                "unverifiable": results.len() - verified,
+                "failed": failed,
+                "drifted": drifted,
+            },
+            "assertions": results,
+        }))
+    }
+
     pub async fn handle_action(&self, args: Value) -> Result<Value, ToolError> {
         let action = args.get("action");
         match action.and_then(|v| v.as_str()).unwrap_or("") {
@@ -47,6 +257,19 @@ impl EvidenceManager {
                 let id = args.get("id").and_then(|v| v.as_str()).unwrap_or("");
                 self.evidence_service.get_evidence(id)
             }
+            "create" => {
+                let record = args.get("record").cloned().unwrap_or_else(|| {
+                    let mut record = args.clone();
+                    if let Value::Object(map) = &mut record {
+                        for key in ["action", "trace_id", "span_id", "parent_span_id"] {
+                            map.remove(key);
+                        }
+                    }
+                    record
+                });
+                self.evidence_service.create_record(&record)
+            }
+            "evidence_verify" => self.verify(&args).await,
             _ => Err(unknown_action_error("evidence", action, EVIDENCE_ACTIONS)),
         }
     }
@@ -1,4 +1,5 @@
-use crate::errors::ToolError;
+use crate::errors::{ToolError, ToolErrorKind};
+use crate::managers::ssh::compute_local_sha256_hex;
 use crate::services::capability::CapabilityService;
 use crate::services::context::ContextService;
 use crate::services::evidence::EvidenceService;
@@ -10,15 +11,165 @@ use crate::services::security::Security;
 use crate::services::state::StateService;
 use crate::services::tool_executor::{ToolExecutor, ToolHandler};
 use crate::services::validation::Validation;
+use crate::tooling::effects::{resolve_steps_effects, resolve_tool_call_effects};
+use crate::tooling::names::canonical_tool_name;
+use crate::utils::artifacts::resolve_input_path;
 use crate::utils::manifests::manifest_ref;
+use crate::utils::template::resolve_templates;
 use crate::utils::tool_errors::unknown_action_error;
 use once_cell::sync::OnceCell;
 use regex::Regex;
 use serde_json::Value;
+use sha2::{Digest, Sha256};
 use std::collections::HashSet;
 use std::sync::{Arc, Weak};
 
-pub(crate) const INTENT_ACTIONS: &[&str] = &["compile", "dry_run", "execute", "explain"];
+pub(crate) const INTENT_ACTIONS: &[&str] = &[
+    "compile", "dry_run", "preview", "execute", "explain", "list",
+];
+
+const PREVIEW_STATE_PREFIX: &str = "intent_preview/";
+const PREVIEW_SAMPLE_ROWS: u64 = 5;
+// A preview is a one-time apply token: it expires, is bound to the project/target resolved when
+// it was taken, and a successful execute consumes it. Failed applies count against max_attempts.
+const DEFAULT_PREVIEW_TTL_MS: u64 = 24 * 60 * 60 * 1000;
+const DEFAULT_PREVIEW_MAX_ATTEMPTS: u64 = 3;
+const MAX_PREVIEW_ATTEMPTS: u64 = 100;
+const PREVIEW_STATUSES: &[&str] = &["pending", "applied", "expired", "exhausted"];
+
+// An execute holds a claim on the stored preview (`applying`) until it records the outcome, so
+// a second execute, from this process or another one sharing the store, is refused. A claim
+// older than the lease was left by a process that died mid-apply and may be taken over.
+const PREVIEW_CLAIM_LEASE_MS: i64 = 60 * 60 * 1000;
+
+struct PreviewClaim {
+    state_service: Arc<StateService>,
+    key: String,
+    claim_id: String,
+    released: bool,
+}
+
+impl PreviewClaim {
+    // Claims the preview in one store transaction. No claim comes back when the preview was
+    // already applied; the record is returned either way.
+    fn acquire(
+        state_service: &Arc<StateService>,
+        preview_id: &str,
+        trace_id: &str,
+        plan: &Value,
+    ) -> Result<(Option<Self>, Value), ToolError> {
+        let key = format!("{}{}", PREVIEW_STATE_PREFIX, preview_id);
+        let claim_id = uuid::Uuid::new_v4().to_string();
+        let record = state_service.update_persistent(&key, |mut record| {
+            if !record.is_object() {
+                return Err(preview_not_found(preview_id));
+            }
+            if preview_applied(&record) {
+                return Ok((None, record));
+            }
+            if claim_is_live(&record) {
+                return Err(ToolError::new(
+                    ToolErrorKind::Conflict,
+                    "INTENT_APPLY_IN_PROGRESS",
+                    format!("Intent preview '{}' is being applied", preview_id),
+                )
+                .with_hint(
+                    "Wait for the running execute to finish, then check intent action=list.",
+                ));
+            }
+            ensure_preview_applicable(&record, plan)?;
+            record["applying"] = serde_json::json!({
+                "claim_id": claim_id,
+                "trace_id": trace_id,
+                "claimed_at": chrono::Utc::now().to_rfc3339(),
+            });
+            Ok((Some(record.clone()), record))
+        })?;
+        if preview_applied(&record) {
+            return Ok((None, record));
+        }
+        let claim = Self {
+            state_service: state_service.clone(),
+            key,
+            claim_id,
+            released: false,
+        };
+        Ok((Some(claim), record))
+    }
+
+    // Counts the attempt on the stored preview and releases the claim: a successful apply
+    // consumes the preview (`applied` keeps the trace and evidence of that run), a failed one
+    // only bumps `attempts`.
+    fn finish(
+        mut self,
+        trace_id: &str,
+        outcome: &Result<Value, ToolError>,
+    ) -> Result<Value, ToolError> {
+        self.released = true;
+        let claim_id = self.claim_id.clone();
+        self.state_service
+            .update_persistent(&self.key, |mut record| {
+                let Some(map) = record.as_object_mut() else {
+                    return Err(preview_not_found(
+                        self.key.trim_start_matches(PREVIEW_STATE_PREFIX),
+                    ));
+                };
+                if map.get("applying").and_then(|v| v.get("claim_id")) == Some(&Value::from(claim_id))
+                {
+                    map.remove("applying");
+                }
+                let now = chrono::Utc::now().to_rfc3339();
+                let attempts = record.get("attempts").and_then(|v| v.as_u64()).unwrap_or(0) + 1;
+                record["attempts"] = Value::from(attempts);
+                let succeeded = match outcome {
+                    Ok(result) => result.get("success").and_then(|v| v.as_bool()) == Some(true),
+                    Err(_) => false,
+                };
+                record["last_attempt"] = serde_json::json!({
+                    "at": now,
+                    "trace_id": trace_id,
+                    "success": succeeded,
+                    "error": outcome.as_ref().err().map(|err| err.message.clone()),
+                });
+                if let (true, Ok(result)) = (succeeded, outcome) {
+                    record["applied"] = serde_json::json!({
+                        "applied_at": now,
+                        "trace_id": trace_id,
+                        "evidence_path": result.get("evidence_path").cloned().unwrap_or(Value::Null),
+                    });
+                }
+                let summary = preview_summary(&record);
+                Ok((Some(record), summary))
+            })
+    }
+}
+
+// An execute that never recorded its outcome (cancelled, timed out) gives the claim back; the
+// attempt is not counted.
+impl Drop for PreviewClaim {
+    fn drop(&mut self) {
+        if self.released {
+            return;
+        }
+        let claim_id = Value::from(self.claim_id.as_str());
+        let _ = self
+            .state_service
+            .update_persistent(&self.key, |mut record| {
+                let Some(map) = record.as_object_mut() else {
+                    return Ok((None, ()));
+                };
+                if map.get("applying").and_then(|v| v.get("claim_id")) != Some(&claim_id) {
+                    return Ok((None, ()));
+                }
+                map.remove("applying");
+                Ok((Some(record), ()))
+            });
+    }
+}
+
+// Keys that only steer how a write runs; the read-only preview calls never carry them.
+const PREVIEW_WRITE_ONLY_KEYS: &[&str] =
+    &["apply", "confirm", "data", "returning", "expect", "mode"];
 
 #[derive(Clone)]
 pub struct IntentManager {
@@ -35,6 +186,20 @@ pub struct IntentManager {
     tool_executor: Arc<OnceCell<Weak<ToolExecutor>>>,
 }
 
+// A write step of the compiled plan with its args rendered the way runbook_run renders them.
+struct PlannedWrite {
+    id: String,
+    tool: String,
+    action: String,
+    args: Result<Value, String>,
+}
+
+impl PlannedWrite {
+    fn describe(&self) -> Value {
+        serde_json::json!({"step": self.id, "tool": self.tool, "action": self.action})
+    }
+}
+
 #[derive(Clone)]
 struct NormalizedIntent {
     intent_type: String,
@@ -84,8 +249,10 @@ impl IntentManager {
         match action {
             "compile" => self.compile(&args).await,
             "dry_run" => self.execute(&args, true).await,
+            "preview" => self.preview(&args).await,
             "execute" => self.execute(&args, false).await,
             "explain" => self.explain(&args).await,
+            "list" => self.list_previews(&args),
             _ => Err(unknown_action_error(
                 "intent",
                 args.get("action"),
@@ -197,6 +364,58 @@ impl IntentManager {
             ));
         }
 
+        let Some(preview_id) = args
+            .get("preview_id")
+            .and_then(|v| v.as_str())
+            .map(|s| s.trim().to_string())
+        else {
+            return self
+                .apply_plan(args, &plan, &trace_id, apply, confirm, None)
+                .await;
+        };
+        let (claim, record) =
+            PreviewClaim::acquire(&self.state_service, &preview_id, &trace_id, &plan)?;
+        let Some(claim) = claim else {
+            return Ok(serde_json::json!({
+                "success": true,
+                "dry_run": false,
+                "already_applied": true,
+                "preview_id": preview_id,
+                "applied": record["applied"],
+                "intent_record": preview_summary(&record),
+            }));
+        };
+        let preview = self.preview_reference(&record, &plan)?;
+        let outcome = self
+            .apply_plan(args, &plan, &trace_id, apply, confirm, Some(preview))
+            .await;
+        let summary = claim.finish(&trace_id, &outcome)?;
+        match outcome {
+            Ok(mut result) => {
+                result["intent_record"] = summary;
+                Ok(result)
+            }
+            Err(err) => {
+                let mut details = match err.details.clone() {
+                    Some(Value::Object(map)) => map,
+                    Some(other) => serde_json::Map::from_iter([("error".to_string(), other)]),
+                    None => serde_json::Map::new(),
+                };
+                details.insert("intent_record".to_string(), summary);
+                Err(err.with_details(Value::Object(details)))
+            }
+        }
+    }
+
+    async fn apply_plan(
+        &self,
+        args: &Value,
+        plan: &Value,
+        trace_id: &str,
+        apply: bool,
+        confirm: bool,
+        preview: Option<Value>,
+    ) -> Result<Value, ToolError> {
         let intent_type = plan
             .get("intent")
             .and_then(|v| v.get("type"))
@@ -244,7 +463,7 @@ impl IntentManager {
                     intent_type,
                     &inputs,
                     GitopsWriteScope {
-                        trace_id: trace_id.as_str(),
+                        trace_id,
                         project_name,
                         target_name,
                         repo_root,
@@ -266,15 +485,7 @@ impl IntentManager {
         let mut results = Vec::new();
         let mut success = true;
 
-        let tool_executor = self
-            .tool_executor
-            .get()
-            .and_then(|executor| executor.upgrade())
-            .ok_or_else(|| {
-                ToolError::internal("Tool executor is not available for intent execution").with_hint(
-                    "App wiring bug: IntentManager.set_tool_executor(...) must be called during initialization.".to_string(),
-                )
-            })?;
+        let tool_executor = self.resolve_tool_executor()?;
         let runbook_manager = crate::managers::runbook::RunbookManager::new(
             self.logger.clone(),
             self.runbook_service.clone(),
@@ -358,10 +569,12 @@ impl IntentManager {
         let evidence = serde_json::json!({
             "intent": redact_value(plan.get("intent").unwrap_or(&Value::Null)),
             "effects": plan.get("effects").cloned().unwrap_or(Value::Null),
+            "computed_effects": plan.get("computed_effects").cloned().unwrap_or(Value::Null),
             "dry_run": false,
             "executed_at": chrono::Utc::now().to_rfc3339(),
             "steps": results,
             "success": success,
+            "preview": preview,
         });
 
         let mut evidence_path = None;
@@ -380,11 +593,443 @@ impl IntentManager {
             "dry_run": false,
             "plan": plan,
             "results": results,
+            "preview": preview,
             "evidence": evidence,
             "evidence_path": evidence_path,
         }))
     }
 
+    fn resolve_tool_executor(&self) -> Result<Arc<ToolExecutor>, ToolError> {
+        self.tool_executor
+            .get()
+            .and_then(|executor| executor.upgrade())
+            .ok_or_else(|| {
+                ToolError::internal("Tool executor is not available for intent execution").with_hint(
+                    "App wiring bug: IntentManager.set_tool_executor(...) must be called during initialization.".to_string(),
+                )
+            })
+    }
+
+    // Runs read-only probes for the write steps of the plan (sql update/delete, env env_set,
+    // ssh deploy_file) and records the findings under `intent_preview/<id>` so a later
+    // execute with `preview_id` can show whether the applied steps still match.
+    async fn preview(&self, args: &Value) -> Result<Value, ToolError> {
+        let expires_at = preview_expiry(args)?;
+        let max_attempts = match args.get("max_attempts") {
+            None | Some(Value::Null) => DEFAULT_PREVIEW_MAX_ATTEMPTS,
+            Some(value) => value
+                .as_u64()
+                .filter(|n| (1..=MAX_PREVIEW_ATTEMPTS).contains(n))
+                .ok_or_else(|| {
+                    ToolError::invalid_params(format!(
+                        "max_attempts must be an integer between 1 and {}",
+                        MAX_PREVIEW_ATTEMPTS
+                    ))
+                })?,
+        };
+        let (plan, _) = self.build_plan(args, false).await?;
+        let tool_executor = self.resolve_tool_executor()?;
+        let trace_id = args
+            .get("trace_id")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        let sample_rows = args
+            .get("sample_rows")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(PREVIEW_SAMPLE_ROWS)
+            .clamp(1, 50);
+
+        let writes = self.planned_writes(&plan)?;
+        let mut findings = Vec::new();
+        for write in &writes {
+            let mut finding = match &write.args {
+                Err(message) => serde_json::json!({"status": "unresolved", "error": message}),
+                Ok(step_args) => match self
+                    .preview_write(&tool_executor, write, step_args, &trace_id, sample_rows)
+                    .await
+                {
+                    Ok(Some(finding)) => finding,
+                    Ok(None) => serde_json::json!({
+                        "status": "unsupported",
+                        "note": "no preview for this action; review the step inputs instead",
+                    }),
+                    Err(err) => serde_json::json!({
+                        "status": "error",
+                        "error": err.message,
+                        "code": err.code,
+                    }),
+                },
+            };
+            if let (Value::Object(map), Value::Object(head)) = (&mut finding, write.describe()) {
+                for (key, value) in head {
+                    map.insert(key, value);
+                }
+            }
+            findings.push(finding);
+        }
+
+        let preview_id = uuid::Uuid::new_v4().to_string();
+        let record = serde_json::json!({
+            "preview_id": preview_id,
+            "created_at": chrono::Utc::now().to_rfc3339(),
+            "trace_id": trace_id,
+            "intent_type": plan.get("intent").and_then(|v| v.get("type")).cloned().unwrap_or(Value::Null),
+            "fingerprint": plan_fingerprint(&writes),
+            "findings": findings,
+            "expires_at": expires_at.to_rfc3339(),
+            "scope": plan_scope(&plan),
+            "max_attempts": max_attempts,
+            "attempts": 0,
+            "applied": Value::Null,
+        });
+        self.state_service.set(
+            &format!("{}{}", PREVIEW_STATE_PREFIX, preview_id),
+            record.clone(),
+            Some("persistent"),
+        )?;
+
+        Ok(serde_json::json!({
+            "success": true,
+            "preview_id": preview_id,
+            "fingerprint": record["fingerprint"],
+            "expires_at": record["expires_at"],
+            "scope": record["scope"],
+            "max_attempts": max_attempts,
+            "effects": plan.get("effects").cloned().unwrap_or(Value::Null),
+            "findings": record["findings"],
+        }))
+    }
+
+    fn preview_reference(&self, record: &Value, plan: &Value) -> Result<Value, ToolError> {
+        let fingerprint = plan_fingerprint(&self.planned_writes(plan)?);
+        Ok(serde_json::json!({
+            "preview_id": record.get("preview_id").cloned().unwrap_or(Value::Null),
+            "created_at": record.get("created_at").cloned().unwrap_or(Value::Null),
+            "scope": record.get("scope").cloned().unwrap_or(Value::Null),
+            "fingerprint": fingerprint,
+            "matched": record.get("fingerprint").and_then(|v| v.as_str()) == Some(fingerprint.as_str()),
+        }))
+    }
+
+    fn list_previews(&self, args: &Value) -> Result<Value, ToolError> {
+        let status = args.get("status").and_then(|v| v.as_str());
+        if let Some(status) = status {
+            if !PREVIEW_STATUSES.contains(&status) {
+                return Err(ToolError::invalid_params(format!(
+                    "status must be one of: {}",
+                    PREVIEW_STATUSES.join(", ")
+                )));
+            }
+        }
+        let listed =
+            self.state_service
+                .list(Some(PREVIEW_STATE_PREFIX), Some("persistent"), true)?;
+        let mut previews: Vec<Value> = listed
+            .get("items")
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|item| item.get("value").filter(|v| v.is_object()))
+            .map(preview_summary)
+            .filter(|summary| status.is_none_or(|status| summary["status"] == status))
+            .collect();
+        previews.sort_by(|a, b| {
+            let created = |v: &Value| v["created_at"].as_str().unwrap_or("").to_string();
+            created(b).cmp(&created(a))
+        });
+        Ok(serde_json::json!({
+            "success": true,
+            "count": previews.len(),
+            "previews": previews,
+        }))
+    }
+
+    // Write-classified runbook steps of every plan step, rendered with the same context shape
+    // as runbook_run. `foreach` steps stay unrendered (their items only exist at run time).
+    fn planned_writes(&self, plan: &Value) -> Result<Vec<PlannedWrite>, ToolError> {
+        let state = self
+            .state_service
+            .dump(Some("any"))?
+            .get("state")
+            .cloned()
+            .unwrap_or(Value::Object(Default::default()));
+        let apply = plan
+            .get("intent")
+            .and_then(|v| v.get("apply"))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let mut writes = Vec::new();
+        for step in plan
+            .get("steps")
+            .and_then(|v| v.as_array())
+            .cloned()
+            .unwrap_or_default()
+        {
+            let Some(runbook_name) = step.get("runbook").and_then(|v| v.as_str()) else {
+                continue;
+            };
+            let capability = step
+                .get("capability")
+                .and_then(|v| v.as_str())
+                .unwrap_or(runbook_name);
+            let runbook = self.runbook_service.resolve_runbook(runbook_name)?;
+            let context = serde_json::json!({
+                "input": step.get("inputs").cloned().unwrap_or(Value::Null),
+                "state": state,
+                "steps": {},
+                "apply": apply,
+                "confirm": false,
+            });
+            for (index, runbook_step) in runbook
+                .get("steps")
+                .and_then(|v| v.as_array())
+                .cloned()
+                .unwrap_or_default()
+                .into_iter()
+                .enumerate()
+            {
+                let tool = runbook_step
+                    .get("tool")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("");
+                let raw_args = runbook_step.get("args").cloned().unwrap_or(Value::Null);
+                let action = raw_args
+                    .get("action")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("")
+                    .to_string();
+                let rendered = if runbook_step.get("foreach").is_some() {
+                    Err("foreach steps are rendered per item at run time".to_string())
+                } else {
+                    resolve_templates(&raw_args, &context, "error").map_err(|err| err.message)
+                };
+                let effects =
+                    resolve_tool_call_effects(tool, rendered.as_ref().unwrap_or(&raw_args));
+                if effects.effects.class() == "read" {
+                    continue;
+                }
+                let id = runbook_step
+                    .get("id")
+                    .or_else(|| runbook_step.get("name"))
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_string())
+                    .unwrap_or_else(|| format!("steps[{}]", index));
+                writes.push(PlannedWrite {
+                    id: format!("{}/{}", capability, id),
+                    tool: canonical_tool_name(tool).to_string(),
+                    action,
+                    args: rendered,
+                });
+            }
+        }
+        Ok(writes)
+    }
+
+    async fn preview_write(
+        &self,
+        tool_executor: &ToolExecutor,
+        write: &PlannedWrite,
+        step_args: &Value,
+        trace_id: &str,
+        sample_rows: u64,
+    ) -> Result<Option<Value>, ToolError> {
+        let probe = |action: &str, extra: Value| {
+            let mut probe_args = step_args.clone();
+            if let Value::Object(map) = &mut probe_args {
+                for key in PREVIEW_WRITE_ONLY_KEYS {
+                    map.remove(*key);
+                }
+                map.insert("action".to_string(), Value::String(action.to_string()));
+                map.insert("trace_id".to_string(), Value::String(trace_id.to_string()));
+                if let Value::Object(extra) = extra {
+                    map.extend(extra);
+                }
+            }
+            probe_args
+        };
+        match (write.tool.as_str(), write.action.as_str()) {
+            ("sql", "update") | ("sql", "delete") => {
+                let count = self
+                    .read_only_call(tool_executor, "sql", probe("count", Value::Null))
+                    .await?;
+                // An update samples the current values of the columns it sets.
+                let mut select = serde_json::json!({"limit": sample_rows});
+                if write.action == "update" {
+                    select["columns"] = select_columns(step_args);
+                }
+                let sample = self
+                    .read_only_call(tool_executor, "sql", probe("select", select))
+                    .await?;
+                let mut finding = serde_json::json!({
+                    "status": "ok",
+                    "kind": "sql_rows",
+                    "table": step_args.get("table").cloned().unwrap_or(Value::Null),
+                    "affected_rows": count.get("count").cloned().unwrap_or(Value::Null),
+                    "sample": sample
+                        .get("result")
+                        .and_then(|v| v.get("rows"))
+                        .cloned()
+                        .unwrap_or(Value::Null),
+                });
+                if write.action == "update" {
+                    finding["set_columns"] = select_columns(step_args);
+                    if step_args.get("expect").is_some() {
+                        finding["note"] = Value::String(
+                            "expect guards are evaluated at apply time; affected_rows ignores them"
+                                .to_string(),
+                        );
+                    }
+                }
+                Ok(Some(finding))
+            }
+            ("env", "env_set") => {
+                let set = step_args
+                    .get("set")
+                    .and_then(|v| v.as_object())
+                    .cloned()
+                    .unwrap_or_default();
+                let (to_set, to_unset): (
+                    serde_json::Map<String, Value>,
+                    serde_json::Map<String, Value>,
+                ) = set.into_iter().partition(|(_, value)| !value.is_null());
+                let mut unset: Vec<String> = to_unset.keys().cloned().collect();
+                unset.extend(
+                    step_args
+                        .get("unset")
+                        .and_then(|v| v.as_array())
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|v| v.as_str().map(str::to_string)),
+                );
+                let strip = serde_json::json!({
+                    "set": Value::Null,
+                    "unset": Value::Null,
+                    "restart": Value::Null,
+                    "create": Value::Null,
+                    "mkdirs": Value::Null,
+                    "backup": Value::Null,
+                });
+                let current = self
+                    .read_only_call(tool_executor, "env", probe("env_get", strip.clone()))
+                    .await?;
+                let present: HashSet<String> = current
+                    .get("keys")
+                    .and_then(|v| v.as_array())
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|v| v.as_str().map(str::to_string))
+                    .collect();
+                let (changed, unchanged) = if to_set.is_empty() {
+                    (Vec::new(), Vec::new())
+                } else {
+                    let diff = self
+                        .read_only_call(
+                            tool_executor,
+                            "env",
+                            probe("env_diff", {
+                                let mut extra = strip.clone();
+                                extra["expected"] = Value::Object(to_set.clone());
+                                extra
+                            }),
+                        )
+                        .await?;
+                    let changed: Vec<String> = diff
+                        .get("changed")
+                        .and_then(|v| v.as_array())
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|v| v.as_str().map(str::to_string))
+                        .collect();
+                    let unchanged = to_set
+                        .keys()
+                        .filter(|key| present.contains(*key) && !changed.contains(key))
+                        .cloned()
+                        .collect();
+                    (changed, unchanged)
+                };
+                let added: Vec<&String> = to_set
+                    .keys()
+                    .filter(|key| !present.contains(*key))
+                    .collect();
+                let removed: Vec<&String> =
+                    unset.iter().filter(|key| present.contains(*key)).collect();
+                Ok(Some(serde_json::json!({
+                    "status": "ok",
+                    "kind": "env_keys",
+                    "remote_path": current.get("remote_path").cloned().unwrap_or(Value::Null),
+                    "added": added,
+                    "changed": changed,
+                    "unchanged": unchanged,
+                    "removed": removed,
+                    "changes": !added.is_empty() || !changed.is_empty() || !removed.is_empty(),
+                })))
+            }
+            ("ssh", "deploy_file") => {
+                let local_path = step_args
+                    .get("local_path")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| {
+                        ToolError::invalid_params("deploy_file step has no local_path")
+                    })?;
+                let local_sha256 =
+                    compute_local_sha256_hex(&resolve_input_path(local_path, "local_path")?)?;
+                let remote = self
+                    .read_only_call(
+                        tool_executor,
+                        "ssh",
+                        probe(
+                            "sftp_exists",
+                            serde_json::json!({
+                                "sha256": true,
+                                "local_path": Value::Null,
+                                "restart": Value::Null,
+                                "overwrite": Value::Null,
+                                "mkdirs": Value::Null,
+                                "preserve_mtime": Value::Null,
+                                "max_rate_bps": Value::Null,
+                            }),
+                        ),
+                    )
+                    .await?;
+                let remote_sha256 = remote.get("sha256").cloned().unwrap_or(Value::Null);
+                Ok(Some(serde_json::json!({
+                    "status": "ok",
+                    "kind": "file_sha256",
+                    "remote_path": step_args.get("remote_path").cloned().unwrap_or(Value::Null),
+                    "exists": remote.get("exists").cloned().unwrap_or(Value::Bool(false)),
+                    "local_sha256": local_sha256,
+                    "remote_sha256": remote_sha256,
+                    "changes": remote_sha256.as_str() != Some(local_sha256.as_str()),
+                })))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    // The only way preview touches a target: the call must classify as a plain read.
+    async fn read_only_call(
+        &self,
+        tool_executor: &ToolExecutor,
+        tool: &str,
+        mut args: Value,
+    ) -> Result<Value, ToolError> {
+        if let Value::Object(map) = &mut args {
+            map.retain(|_, value| !value.is_null());
+        }
+        let effects = resolve_tool_call_effects(tool, &args);
+        if effects.effects.class() != "read" || effects.effects.requires_apply {
+            return Err(ToolError::denied(format!(
+                "intent preview refuses {} {} ({} effects)",
+                tool,
+                args.get("action").and_then(|v| v.as_str()).unwrap_or(""),
+                effects.effects.class()
+            )));
+        }
+        let output = tool_executor.execute(tool, args).await?;
+        Ok(output.get("result").cloned().unwrap_or(output))
+    }
+
     async fn normalize_intent(&self, args: &Value) -> Result<NormalizedIntent, ToolError> {
         let intent_obj = self
             .validation
@@ -644,6 +1289,7 @@ impl IntentManager {
 
         let mut steps = Vec::new();
         let mut missing = Vec::new();
+        let mut runbook_steps = Vec::new();
 
         for capability in ordered.iter() {
             let (resolved_inputs, missing_inputs) = normalize_inputs(&intent.inputs, capability);
@@ -667,10 +1313,33 @@ impl IntentManager {
                 .get("runbook")
                 .and_then(|v| v.as_str())
                 .unwrap_or("");
+            let capability_name = capability
+                .get("name")
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
             let runbook_manifest = if runbook_name.is_empty() {
                 Value::Null
             } else {
-                manifest_ref(&self.runbook_service.resolve_runbook(runbook_name)?)
+                let runbook = self.runbook_service.resolve_runbook(runbook_name)?;
+                for (index, step) in runbook
+                    .get("steps")
+                    .and_then(|v| v.as_array())
+                    .cloned()
+                    .unwrap_or_default()
+                    .into_iter()
+                    .enumerate()
+                {
+                    let id = step
+                        .get("id")
+                        .or_else(|| step.get("name"))
+                        .and_then(|v| v.as_str())
+                        .map(|s| s.to_string())
+                        .unwrap_or_else(|| format!("steps[{}]", index));
+                    let mut step = step;
+                    step["id"] = Value::String(format!("{}/{}", capability_name, id));
+                    runbook_steps.push(step);
+                }
+                manifest_ref(&runbook)
             };
             steps.push(serde_json::json!({
                 "capability": capability.get("name").cloned().unwrap_or(Value::Null),
@@ -713,6 +1382,11 @@ impl IntentManager {
             },
             "steps": steps,
             "effects": aggregate_effects(&steps),
+            "computed_effects": resolve_steps_effects(
+                &format!("intent {}", intent.intent_type),
+                &runbook_steps,
+            )
+            .to_value(),
         });
 
         self.security
@@ -729,6 +1403,178 @@ impl ToolHandler for IntentManager {
     }
 }
 
+fn plan_scope(plan: &Value) -> Value {
+    let intent = plan.get("intent");
+    serde_json::json!({
+        "project": intent.and_then(|v| v.get("project")).cloned().unwrap_or(Value::Null),
+        "target": intent.and_then(|v| v.get("target")).cloned().unwrap_or(Value::Null),
+    })
+}
+
+// `ttl_ms` or an RFC 3339 `expires_at` in the future; DEFAULT_PREVIEW_TTL_MS without either.
+fn preview_expiry(args: &Value) -> Result<chrono::DateTime<chrono::Utc>, ToolError> {
+    let now = chrono::Utc::now();
+    match (args.get("ttl_ms"), args.get("expires_at")) {
+        (Some(ttl), Some(expires)) if !ttl.is_null() && !expires.is_null() => Err(
+            ToolError::invalid_params("Pass either ttl_ms or expires_at, not both"),
+        ),
+        (Some(ttl), _) if !ttl.is_null() => {
+            let ttl_ms = ttl
+                .as_u64()
+                .filter(|ms| *ms > 0)
+                .ok_or_else(|| ToolError::invalid_params("ttl_ms must be a positive integer"))?;
+            Ok(now + chrono::Duration::milliseconds(ttl_ms.min(i64::MAX as u64) as i64))
+        }
+        (_, Some(expires)) if !expires.is_null() => {
+            let parsed = expires
+                .as_str()
+                .and_then(|text| chrono::DateTime::parse_from_rfc3339(text).ok())
+                .map(|at| at.with_timezone(&chrono::Utc))
+                .ok_or_else(|| {
+                    ToolError::invalid_params("expires_at must be an RFC 3339 timestamp")
+                })?;
+            if parsed <= now {
+                return Err(ToolError::invalid_params(
+                    "expires_at must be in the future",
+                ));
+            }
+            Ok(parsed)
+        }
+        _ => Ok(now + chrono::Duration::milliseconds(DEFAULT_PREVIEW_TTL_MS as i64)),
+    }
+}
+
+fn preview_not_found(preview_id: &str) -> ToolError {
+    ToolError::not_found(format!("Intent preview '{}' not found", preview_id))
+        .with_hint("Run intent action=preview with the same intent and pass its preview_id.")
+}
+
+fn preview_applied(record: &Value) -> bool {
+    record.get("applied").is_some_and(|v| !v.is_null())
+}
+
+fn claim_is_live(record: &Value) -> bool {
+    record
+        .get("applying")
+        .and_then(|v| v.get("claimed_at"))
+        .and_then(|v| v.as_str())
+        .and_then(|text| chrono::DateTime::parse_from_rfc3339(text).ok())
+        .is_some_and(|at| {
+            chrono::Utc::now()
+                .signed_duration_since(at)
+                .num_milliseconds()
+                < PREVIEW_CLAIM_LEASE_MS
+        })
+}
+
+// Previews stored before expiry and attempts were tracked never expire and have no limit.
+fn preview_status(record: &Value) -> &'static str {
+    if preview_applied(record) {
+        return "applied";
+    }
+    let attempts = record.get("attempts").and_then(|v| v.as_u64()).unwrap_or(0);
+    let max_attempts = record.get("max_attempts").and_then(|v| v.as_u64());
+    if max_attempts.is_some_and(|max| attempts >= max) {
+        return "exhausted";
+    }
+    let expired = record
+        .get("expires_at")
+        .and_then(|v| v.as_str())
+        .and_then(|text| chrono::DateTime::parse_from_rfc3339(text).ok())
+        .is_some_and(|at| at <= chrono::Utc::now());
+    if expired {
+        return "expired";
+    }
+    "pending"
+}
+
+fn preview_summary(record: &Value) -> Value {
+    let field = |key: &str| record.get(key).cloned().unwrap_or(Value::Null);
+    serde_json::json!({
+        "preview_id": field("preview_id"),
+        "intent_type": field("intent_type"),
+        "status": preview_status(record),
+        "scope": field("scope"),
+        "created_at": field("created_at"),
+        "expires_at": field("expires_at"),
+        "attempts": record.get("attempts").and_then(|v| v.as_u64()).unwrap_or(0),
+        "max_attempts": field("max_attempts"),
+        "applied": field("applied"),
+    })
+}
+
+fn ensure_preview_applicable(record: &Value, plan: &Value) -> Result<(), ToolError> {
+    let preview_id = record["preview_id"].as_str().unwrap_or("");
+    let refuse = |code: &str, message: String| {
+        ToolError::new(ToolErrorKind::Denied, code, message)
+            .with_hint("Run intent action=preview again and apply with the new preview_id.")
+            .with_details(serde_json::json!({"intent_record": preview_summary(record)}))
+    };
+    match preview_status(record) {
+        "expired" => {
+            return Err(refuse(
+                "INTENT_EXPIRED",
+                format!(
+                    "Intent preview '{}' expired at {}",
+                    preview_id,
+                    record["expires_at"].as_str().unwrap_or("")
+                ),
+            ))
+        }
+        "exhausted" => {
+            return Err(refuse(
+                "INTENT_EXHAUSTED",
+                format!("Intent preview '{}' has no apply attempts left", preview_id),
+            ))
+        }
+        _ => {}
+    }
+    let Some(bound) = record.get("scope").filter(|v| v.is_object()) else {
+        return Ok(());
+    };
+    let resolved = plan_scope(plan);
+    if *bound != resolved {
+        let mut err = refuse(
+            "INTENT_SCOPE_MISMATCH",
+            format!(
+                "Intent preview '{}' was taken for a different project/target",
+                preview_id
+            ),
+        );
+        if let Some(Value::Object(details)) = err.details.as_mut() {
+            details.insert("resolved_scope".to_string(), resolved);
+        }
+        return Err(err);
+    }
+    Ok(())
+}
+
+fn select_columns(args: &Value) -> Value {
+    args.get("data")
+        .and_then(|v| v.as_object())
+        .map(|data| Value::Array(data.keys().cloned().map(Value::String).collect()))
+        .unwrap_or(Value::Null)
+}
+
+// Hash of the rendered write steps (minus apply/confirm), so a preview and a later execute
+// can be compared even though their traces differ.
+fn plan_fingerprint(writes: &[PlannedWrite]) -> String {
+    let canonical: Vec<Value> = writes
+        .iter()
+        .map(|write| {
+            let mut args = write.args.clone().unwrap_or(Value::Null);
+            if let Value::Object(map) = &mut args {
+                map.remove("apply");
+                map.remove("confirm");
+            }
+            serde_json::json!([write.id, write.tool, write.action, args])
+        })
+        .collect();
+    let mut hasher = Sha256::new();
+    hasher.update(serde_json::to_vec(&canonical).unwrap_or_default());
+    format!("{:x}", hasher.finalize())
+}
+
 fn get_by_path(source: &Value, path: &str) -> Option<Value> {
     if path.trim().is_empty() {
         return None;
@@ -1,8 +1,10 @@
-use crate::errors::ToolError;
-use crate::services::job::JobService;
+use crate::errors::{ToolError, ToolErrorKind};
+use crate::services::job::{JobService, LOCAL_JOB_TOOLS};
 use crate::services::logger::Logger;
 use crate::services::validation::Validation;
 use crate::tooling::names::canonical_tool_name;
+use crate::utils::feature_flags;
+use crate::utils::shutdown;
 use crate::utils::tool_errors::unknown_action_error;
 use chrono::TimeZone;
 use serde_json::Value;
@@ -11,14 +13,20 @@ use std::sync::Arc;
 pub(crate) const JOB_ACTIONS: &[&str] = &[
     "job_status",
     "job_wait",
+    "job_wait_all",
     "job_logs_tail",
     "tail_job",
     "follow_job",
     "job_cancel",
+    "job_kill",
     "job_forget",
     "job_list",
 ];
 
+const WAIT_ALL_MAX_JOBS: usize = 100;
+// Keys of a job_wait_all call that describe the wait itself; the rest selects the ssh host.
+const WAIT_ALL_KEYS: &[&str] = &["action", "jobs", "lines", "timeout_ms", "poll_interval_ms"];
+
 fn now_iso() -> String {
     chrono::Utc::now().to_rfc3339()
 }
@@ -40,6 +48,42 @@ fn read_positive_int(value: Option<&Value>) -> Option<u64> {
     None
 }
 
+fn provider_tool(job: &Value) -> Option<&str> {
+    job.get("provider")
+        .and_then(|v| v.get("tool"))
+        .and_then(|v| v.as_str())
+        .map(canonical_tool_name)
+}
+
+fn is_local_job(job: &Value) -> bool {
+    provider_tool(job).is_some_and(|tool| LOCAL_JOB_TOOLS.contains(&tool))
+}
+
+// Last `lines` lines of a local job log, read from at most the final 1 MiB of the file.
+fn tail_log_file(path: &str, lines: usize) -> Result<Value, ToolError> {
+    use std::io::{Read, Seek, SeekFrom};
+    let mut file = std::fs::File::open(path)
+        .map_err(|err| ToolError::internal(format!("Failed to open job log: {}", err)))?;
+    let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+    let window = 1024 * 1024;
+    let start = size.saturating_sub(window);
+    file.seek(SeekFrom::Start(start))
+        .map_err(|err| ToolError::internal(format!("Failed to read job log: {}", err)))?;
+    let mut buf = Vec::new();
+    file.read_to_end(&mut buf)
+        .map_err(|err| ToolError::internal(format!("Failed to read job log: {}", err)))?;
+    let text = String::from_utf8_lossy(&buf);
+    let all: Vec<&str> = text.lines().collect();
+    let skip = all.len().saturating_sub(lines);
+    Ok(serde_json::json!({
+        "success": true,
+        "log_path": path,
+        "bytes": size,
+        "lines": all.len() - skip,
+        "text": all[skip..].join("\n"),
+    }))
+}
+
 fn public_job_view(job: &Value) -> Value {
     if !job.is_object() {
         return Value::Null;
@@ -77,10 +121,51 @@ fn public_job_view(job: &Value) -> Value {
         "progress": job.get("progress").cloned().unwrap_or(Value::Null),
         "artifacts": job.get("artifacts").cloned().unwrap_or(Value::Null),
         "provider": job.get("provider").cloned().unwrap_or(Value::Null),
+        "result": job.get("result").cloned().unwrap_or(Value::Null),
         "error": job.get("error").cloned().unwrap_or(Value::Null),
     })
 }
 
+// One `jobs` entry of job_wait_all. ssh entries are probed with `spec` (the entry itself, or
+// `{job_id}`), batched per `profile`.
+struct WaitTarget {
+    key: String,
+    job_id: Option<String>,
+    spec: Value,
+    profile: Option<String>,
+    ssh: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum WaitState {
+    Running,
+    Succeeded,
+    Failed,
+    Unknown,
+}
+
+impl WaitState {
+    fn name(self) -> &'static str {
+        match self {
+            WaitState::Running => "running",
+            WaitState::Succeeded => "succeeded",
+            WaitState::Failed => "failed",
+            WaitState::Unknown => "unknown",
+        }
+    }
+}
+
+// Latest view of one job; `done` once its state can no longer change.
+struct WaitOutcome {
+    state: WaitState,
+    done: bool,
+    entry: Value,
+}
+
+fn ssh_job_done(status: &Value) -> bool {
+    status.get("exited").and_then(|v| v.as_bool()) == Some(true)
+}
+
 #[derive(Clone)]
 pub struct JobManager {
     logger: Logger,
@@ -113,10 +198,12 @@ impl JobManager {
         match action.and_then(|v| v.as_str()).unwrap_or("") {
             "job_status" | "status" => self.job_status(args).await,
             "job_wait" | "wait" => self.job_wait(args).await,
+            "job_wait_all" | "wait_all" => self.job_wait_all(args).await,
             "job_logs_tail" | "logs" => self.job_logs_tail(args).await,
             "tail_job" | "tail" => self.tail_job(args).await,
             "follow_job" => self.follow_job(args).await,
             "job_cancel" | "cancel" => self.job_cancel(args).await,
+            "job_kill" | "kill" => self.job_kill(args).await,
             "job_forget" | "forget" => self.job_forget(args).await,
             "job_list" | "list" => self.job_list(args).await,
             _ => Err(unknown_action_error("job", action, JOB_ACTIONS)),
@@ -131,13 +218,7 @@ impl JobManager {
                 serde_json::json!({"success": false, "code": "NOT_FOUND", "job_id": job_id}),
             );
         };
-        if job
-            .get("provider")
-            .and_then(|v| v.get("tool"))
-            .and_then(|v| v.as_str())
-            .map(canonical_tool_name)
-            == Some("ssh")
-        {
+        if provider_tool(&job) == Some("ssh") {
             if let Some(ssh) = &self.ssh_manager {
                 let status = ssh.handle_action(args.clone()).await?;
                 if status
@@ -169,6 +250,10 @@ impl JobManager {
             }
             return Err(ToolError::internal("SSH manager is not available"));
         }
+        // In-process and local jobs keep their whole state in the job store.
+        if job.get("provider").map(|v| v.is_null()).unwrap_or(true) || is_local_job(&job) {
+            return Ok(serde_json::json!({"success": true, "job": public_job_view(&job)}));
+        }
         Ok(
             serde_json::json!({"success": false, "code": "NOT_SUPPORTED", "job_id": job_id, "kind": job.get("kind").cloned().unwrap_or(Value::Null)}),
         )
@@ -182,13 +267,7 @@ impl JobManager {
                 serde_json::json!({"success": false, "code": "NOT_FOUND", "job_id": job_id}),
             );
         };
-        if job
-            .get("provider")
-            .and_then(|v| v.get("tool"))
-            .and_then(|v| v.as_str())
-            .map(canonical_tool_name)
-            == Some("ssh")
-        {
+        if provider_tool(&job) == Some("ssh") {
             if let Some(ssh) = &self.ssh_manager {
                 let wait = ssh.handle_action(args.clone()).await?;
                 if let Some(status) = wait.get("status") {
@@ -209,17 +288,19 @@ impl JobManager {
                         }));
                     }
                 }
-                return Ok(
-                    serde_json::json!({"success": true, "job": public_job_view(self.job_service.get(&job_id).as_ref().unwrap_or(&Value::Null)), "wait": wait}),
-                );
+                let mut out = serde_json::json!({"success": true, "job": public_job_view(self.job_service.get(&job_id).as_ref().unwrap_or(&Value::Null))});
+                // follow_job results carry the log delta next to the wait.
+                if let Some(logs) = wait.get("logs") {
+                    out["logs"] = logs.clone();
+                    out["log_offset"] = wait.get("log_offset").cloned().unwrap_or(Value::Null);
+                }
+                out["wait"] = wait;
+                return Ok(out);
             }
             return Err(ToolError::internal("SSH manager is not available"));
         }
 
-        let budget_ms = read_positive_int(Some(&Value::String(
-            std::env::var("INFRA_TOOL_CALL_TIMEOUT_MS").unwrap_or_else(|_| "55000".to_string()),
-        )))
-        .unwrap_or(55_000);
+        let budget_ms = feature_flags::TOOL_CALL_TIMEOUT_MS.positive_number();
         let requested = read_positive_int(args.get("timeout_ms")).unwrap_or(30_000);
         let timeout_ms = std::cmp::min(requested, budget_ms);
         let poll_ms = std::cmp::min(
@@ -236,7 +317,7 @@ impl JobManager {
             let current = self.job_service.get(&job_id);
             if let Some(current) = current {
                 let status = current.get("status").and_then(|v| v.as_str()).unwrap_or("");
-                if status == "succeeded" || status == "failed" || status == "canceled" {
+                if matches!(status, "succeeded" | "failed" | "canceled" | "interrupted") {
                     return Ok(serde_json::json!({
                         "success": true,
                         "job": public_job_view(&current),
@@ -248,17 +329,410 @@ impl JobManager {
                     serde_json::json!({"success": false, "code": "NOT_FOUND", "job_id": job_id}),
                 );
             }
-            tokio::time::sleep(std::time::Duration::from_millis(poll_ms)).await;
+            // A shutting-down server stops waiting right away; the job keeps its own status.
+            tokio::select! {
+                _ = tokio::time::sleep(std::time::Duration::from_millis(poll_ms)) => {}
+                _ = shutdown::cancelled() => break,
+            }
         }
 
         let current = self.job_service.get(&job_id).unwrap_or(Value::Null);
+        let interrupted = shutdown::is_shutting_down();
+        let mut wait = serde_json::json!({"completed": false, "timed_out": !interrupted, "waited_ms": (chrono::Utc::now().timestamp_millis() - started) as u64, "timeout_ms": timeout_ms, "poll_interval_ms": poll_ms});
+        if interrupted {
+            wait["interrupted"] = Value::Bool(true);
+        }
         Ok(serde_json::json!({
             "success": true,
             "job": public_job_view(&current),
-            "wait": {"completed": false, "timed_out": true, "waited_ms": (chrono::Utc::now().timestamp_millis() - started) as u64, "timeout_ms": timeout_ms, "poll_interval_ms": poll_ms},
+            "wait": wait,
         }))
     }
 
+    // Waits on several jobs in one call. Local and in-process jobs are read from the job store;
+    // ssh jobs are probed once per poll with one exec per profile, all profiles concurrently.
+    // Nothing is changed besides recording finished ssh jobs, so the call can simply be repeated
+    // (or continued with `next`) to keep waiting.
+    async fn job_wait_all(&self, args: Value) -> Result<Value, ToolError> {
+        let targets = self.wait_targets(&args)?;
+        let budget_ms = feature_flags::TOOL_CALL_TIMEOUT_MS.positive_number();
+        let requested = read_positive_int(args.get("timeout_ms")).unwrap_or(30_000);
+        let timeout_ms = std::cmp::min(requested, budget_ms);
+        let poll_ms = std::cmp::min(
+            read_positive_int(args.get("poll_interval_ms")).unwrap_or(1000),
+            5000,
+        );
+        let probe_timeout_ms = std::cmp::min(10_000, budget_ms);
+        let lines = read_positive_int(args.get("lines")).map(|n| std::cmp::min(n, 2000));
+        let mut base = args.as_object().cloned().unwrap_or_default();
+        for key in WAIT_ALL_KEYS {
+            base.remove(*key);
+        }
+        let base = Value::Object(base);
+        let started = std::time::Instant::now();
+
+        let mut outcomes: Vec<Option<WaitOutcome>> = targets.iter().map(|_| None).collect();
+        let mut polls = 0u64;
+        let mut remote_probes = 0u64;
+        loop {
+            polls += 1;
+            remote_probes += self
+                .poll_wait_targets(&base, &targets, &mut outcomes, probe_timeout_ms)
+                .await;
+            let pending = outcomes
+                .iter()
+                .any(|outcome| !outcome.as_ref().is_some_and(|o| o.done));
+            if !pending || started.elapsed().as_millis() as u64 + poll_ms > timeout_ms {
+                break;
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(std::time::Duration::from_millis(poll_ms)) => {}
+                _ = shutdown::cancelled() => break,
+            }
+        }
+
+        let mut entries = Vec::new();
+        let mut buckets: [(WaitState, Vec<String>); 4] = [
+            (WaitState::Succeeded, Vec::new()),
+            (WaitState::Failed, Vec::new()),
+            (WaitState::Running, Vec::new()),
+            (WaitState::Unknown, Vec::new()),
+        ];
+        let mut next_jobs = Vec::new();
+        for (target, outcome) in targets.iter().zip(outcomes) {
+            let Some(mut outcome) = outcome else { continue };
+            if let Some(lines) = lines.filter(|_| outcome.state == WaitState::Failed) {
+                outcome.entry["logs"] = self.failed_job_tail(&base, target, lines).await;
+            }
+            if !outcome.done {
+                next_jobs.push(target.spec.clone());
+            }
+            if let Some((_, keys)) = buckets.iter_mut().find(|(s, _)| *s == outcome.state) {
+                keys.push(target.key.clone());
+            }
+            entries.push(outcome.entry);
+        }
+        let [(_, succeeded), (_, failed), (_, still_running), (_, unknown)] = buckets;
+        let interrupted = shutdown::is_shutting_down();
+        let completed = next_jobs.is_empty();
+        let mut wait = serde_json::json!({
+            "completed": completed,
+            "timed_out": !completed && !interrupted,
+            "waited_ms": started.elapsed().as_millis() as u64,
+            "timeout_ms": timeout_ms,
+            "poll_interval_ms": poll_ms,
+            "polls": polls,
+            "remote_probes": remote_probes,
+        });
+        if interrupted {
+            wait["interrupted"] = Value::Bool(true);
+        }
+        let mut out = serde_json::json!({
+            "success": true,
+            "all_succeeded": succeeded.len() == targets.len(),
+            "succeeded": succeeded,
+            "failed": failed,
+            "still_running": still_running,
+            "unknown": unknown,
+            "jobs": entries,
+            "wait": wait,
+        });
+        if !completed {
+            let mut next = base.as_object().cloned().unwrap_or_default();
+            next.insert(
+                "action".to_string(),
+                Value::String("job_wait_all".to_string()),
+            );
+            next.insert("jobs".to_string(), Value::Array(next_jobs));
+            next.insert("timeout_ms".to_string(), Value::from(requested));
+            next.insert("poll_interval_ms".to_string(), Value::from(poll_ms));
+            if let Some(lines) = lines {
+                next.insert("lines".to_string(), Value::from(lines));
+            }
+            out["next"] = Value::Object(next);
+        }
+        Ok(out)
+    }
+
+    fn wait_targets(&self, args: &Value) -> Result<Vec<WaitTarget>, ToolError> {
+        let entries = args
+            .get("jobs")
+            .and_then(|v| v.as_array())
+            .filter(|items| !items.is_empty())
+            .ok_or_else(|| {
+                ToolError::invalid_params(
+                    "jobs must be a non-empty array of job ids or {pid|pid_path, exit_path, log_path, profile_name} specs",
+                )
+            })?;
+        if entries.len() > WAIT_ALL_MAX_JOBS {
+            return Err(ToolError::invalid_params(format!(
+                "jobs accepts at most {} entries",
+                WAIT_ALL_MAX_JOBS
+            )));
+        }
+        let default_profile = args.get("profile_name").and_then(|v| v.as_str());
+        let mut targets = Vec::new();
+        for (index, entry) in entries.iter().enumerate() {
+            let spec = match entry {
+                Value::String(job_id) => serde_json::json!({"job_id": job_id}),
+                Value::Object(_) => entry.clone(),
+                _ => {
+                    return Err(ToolError::invalid_params(format!(
+                        "jobs[{}] must be a job id or an object",
+                        index
+                    )))
+                }
+            };
+            let job_id = spec
+                .get("job_id")
+                .and_then(|v| v.as_str())
+                .filter(|id| !id.trim().is_empty())
+                .map(str::to_string);
+            let own_profile = spec.get("profile_name").and_then(|v| v.as_str());
+            let target = match &job_id {
+                Some(job_id) => {
+                    let record = self.job_service.get(job_id);
+                    let recorded_profile = record
+                        .as_ref()
+                        .and_then(|job| job.get("profile_name"))
+                        .and_then(|v| v.as_str());
+                    WaitTarget {
+                        key: job_id.clone(),
+                        job_id: Some(job_id.clone()),
+                        profile: own_profile
+                            .or(recorded_profile)
+                            .or(default_profile)
+                            .map(str::to_string),
+                        ssh: record
+                            .as_ref()
+                            .is_some_and(|job| provider_tool(job) == Some("ssh")),
+                        spec,
+                    }
+                }
+                None => {
+                    let locator = spec
+                        .get("pid_path")
+                        .and_then(|v| v.as_str())
+                        .map(str::to_string)
+                        .or_else(|| {
+                            spec.get("pid")
+                                .and_then(|v| v.as_i64())
+                                .map(|pid| format!("pid:{}", pid))
+                        })
+                        .ok_or_else(|| {
+                            ToolError::invalid_params(format!(
+                                "jobs[{}] needs job_id, pid or pid_path",
+                                index
+                            ))
+                        })?;
+                    let profile = own_profile.or(default_profile).map(str::to_string);
+                    WaitTarget {
+                        key: format!("{}:{}", profile.as_deref().unwrap_or("ssh"), locator),
+                        job_id: None,
+                        profile,
+                        ssh: true,
+                        spec,
+                    }
+                }
+            };
+            targets.push(target);
+        }
+        Ok(targets)
+    }
+
+    // Refreshes every unfinished target; returns how many remote execs it took.
+    async fn poll_wait_targets(
+        &self,
+        base: &Value,
+        targets: &[WaitTarget],
+        outcomes: &mut [Option<WaitOutcome>],
+        probe_timeout_ms: u64,
+    ) -> u64 {
+        let mut groups: std::collections::BTreeMap<Option<String>, Vec<usize>> =
+            std::collections::BTreeMap::new();
+        for (index, target) in targets.iter().enumerate() {
+            if outcomes[index].as_ref().is_some_and(|o| o.done) {
+                continue;
+            }
+            if target.ssh {
+                groups
+                    .entry(target.profile.clone())
+                    .or_default()
+                    .push(index);
+            } else {
+                outcomes[index] = Some(self.stored_job_outcome(target));
+            }
+        }
+        if groups.is_empty() {
+            return 0;
+        }
+        let Some(ssh) = self.ssh_manager.as_ref() else {
+            let err = ToolError::internal("SSH manager is not available");
+            for index in groups.into_values().flatten() {
+                outcomes[index] = Some(probe_error_outcome(&targets[index], &err, false));
+            }
+            return 0;
+        };
+        let probes = groups.into_iter().map(|(profile, indexes)| {
+            let mut group_args = base.clone();
+            if let (Some(profile), Value::Object(map)) = (profile, &mut group_args) {
+                map.insert("profile_name".to_string(), Value::String(profile));
+            }
+            let specs: Vec<Value> = indexes.iter().map(|i| targets[*i].spec.clone()).collect();
+            async move {
+                let result = ssh.probe_jobs(&group_args, &specs, probe_timeout_ms).await;
+                (indexes, result)
+            }
+        });
+        let results = futures::future::join_all(probes).await;
+        let execs = results.len() as u64;
+        for (indexes, result) in results {
+            match result {
+                Ok(statuses) => {
+                    for (index, status) in indexes.into_iter().zip(statuses) {
+                        outcomes[index] = Some(self.ssh_job_outcome(&targets[index], status));
+                    }
+                }
+                Err(err) => {
+                    // Connection problems are retried on the next poll; a rejected request will
+                    // not improve.
+                    let retry = matches!(
+                        err.kind,
+                        ToolErrorKind::Retryable | ToolErrorKind::Timeout | ToolErrorKind::Internal
+                    );
+                    for index in indexes {
+                        outcomes[index] = Some(probe_error_outcome(&targets[index], &err, retry));
+                    }
+                }
+            }
+        }
+        execs
+    }
+
+    fn stored_job_outcome(&self, target: &WaitTarget) -> WaitOutcome {
+        let job_id = target.job_id.clone().unwrap_or_default();
+        let Some(job) = self.job_service.get(&job_id) else {
+            return WaitOutcome {
+                state: WaitState::Unknown,
+                done: true,
+                entry: serde_json::json!({"key": target.key, "job_id": job_id, "status": "unknown", "code": "NOT_FOUND"}),
+            };
+        };
+        if job.get("provider").is_some_and(|v| !v.is_null()) && !is_local_job(&job) {
+            return WaitOutcome {
+                state: WaitState::Unknown,
+                done: true,
+                entry: serde_json::json!({"key": target.key, "job_id": job_id, "status": "unknown", "code": "NOT_SUPPORTED"}),
+            };
+        }
+        let state = match job.get("status").and_then(|v| v.as_str()).unwrap_or("") {
+            "succeeded" | "completed" => WaitState::Succeeded,
+            "failed" | "canceled" | "interrupted" => WaitState::Failed,
+            _ => WaitState::Running,
+        };
+        WaitOutcome {
+            state,
+            done: state != WaitState::Running,
+            entry: serde_json::json!({
+                "key": target.key,
+                "job_id": job_id,
+                "status": state.name(),
+                "exit_code": job.get("result").and_then(|v| v.get("exit_code")).cloned().unwrap_or(Value::Null),
+                "job": public_job_view(&job),
+            }),
+        }
+    }
+
+    // A pid that is gone without an exit file means the wrapper was killed: the job is lost.
+    fn ssh_job_outcome(&self, target: &WaitTarget, status: Value) -> WaitOutcome {
+        if status.get("code").and_then(|v| v.as_str()) == Some("NOT_FOUND") {
+            return WaitOutcome {
+                state: WaitState::Unknown,
+                done: true,
+                entry: serde_json::json!({"key": target.key, "job_id": target.job_id, "status": "unknown", "code": "NOT_FOUND"}),
+            };
+        }
+        let probed = status.get("success").and_then(|v| v.as_bool()) == Some(true);
+        let running = status.get("running").and_then(|v| v.as_bool()) == Some(true);
+        let exit_code = status.get("exit_code").and_then(|v| v.as_i64());
+        let (state, lost) = if !probed {
+            (WaitState::Unknown, false)
+        } else if ssh_job_done(&status) {
+            if exit_code == Some(0) {
+                (WaitState::Succeeded, false)
+            } else {
+                (WaitState::Failed, false)
+            }
+        } else if !running && status.get("pid").is_some_and(|v| !v.is_null()) {
+            (WaitState::Failed, true)
+        } else {
+            (WaitState::Running, false)
+        };
+        let mut entry = serde_json::json!({
+            "key": target.key,
+            "job_id": target.job_id,
+            "status": if lost { "lost" } else { state.name() },
+            "exit_code": exit_code,
+            "probe": status,
+        });
+        if let Some(job_id) = target.job_id.as_ref().filter(|_| !lost) {
+            if matches!(state, WaitState::Succeeded | WaitState::Failed) {
+                let job = self.job_service.get(job_id);
+                let _ = self.job_service.upsert(serde_json::json!({
+                    "job_id": job_id,
+                    "status": state.name(),
+                    "ended_at": job.as_ref().and_then(|j| j.get("ended_at")).filter(|v| !v.is_null()).cloned().unwrap_or(Value::String(now_iso())),
+                }));
+            }
+            if let Some(job) = self.job_service.get(job_id) {
+                entry["job"] = public_job_view(&job);
+            }
+        }
+        WaitOutcome {
+            state,
+            done: matches!(state, WaitState::Succeeded | WaitState::Failed),
+            entry,
+        }
+    }
+
+    async fn failed_job_tail(&self, base: &Value, target: &WaitTarget, lines: u64) -> Value {
+        let tail = if target.ssh {
+            match self.ssh_manager.as_ref() {
+                Some(ssh) => {
+                    let mut tail_args = base.as_object().cloned().unwrap_or_default();
+                    if let Some(spec) = target.spec.as_object() {
+                        tail_args.extend(spec.clone());
+                    }
+                    if let Some(profile) = &target.profile {
+                        tail_args
+                            .insert("profile_name".to_string(), Value::String(profile.clone()));
+                    }
+                    tail_args.insert(
+                        "action".to_string(),
+                        Value::String("job_logs_tail".to_string()),
+                    );
+                    tail_args.insert("lines".to_string(), Value::from(lines));
+                    ssh.handle_action(Value::Object(tail_args)).await
+                }
+                None => Err(ToolError::internal("SSH manager is not available")),
+            }
+        } else {
+            self.job_service
+                .get(target.job_id.as_deref().unwrap_or(""))
+                .and_then(|job| {
+                    job.get("provider")
+                        .and_then(|v| v.get("log_path"))
+                        .and_then(|v| v.as_str())
+                        .map(str::to_string)
+                })
+                .ok_or_else(|| ToolError::not_found("job has no log_path"))
+                .and_then(|path| tail_log_file(&path, lines as usize))
+        };
+        tail.unwrap_or_else(
+            |err| serde_json::json!({"success": false, "code": err.code, "message": err.message}),
+        )
+    }
+
     async fn job_logs_tail(&self, args: Value) -> Result<Value, ToolError> {
         let job_id = self.ensure_job_id(args.get("job_id").unwrap_or(&Value::Null))?;
         let job = self.job_service.get(&job_id);
@@ -267,13 +741,7 @@ impl JobManager {
                 serde_json::json!({"success": false, "code": "NOT_FOUND", "job_id": job_id}),
             );
         };
-        if job
-            .get("provider")
-            .and_then(|v| v.get("tool"))
-            .and_then(|v| v.as_str())
-            .map(canonical_tool_name)
-            == Some("ssh")
-        {
+        if provider_tool(&job) == Some("ssh") {
             if let Some(ssh) = &self.ssh_manager {
                 let logs = ssh.handle_action(args.clone()).await?;
                 return Ok(
@@ -282,6 +750,18 @@ impl JobManager {
             }
             return Err(ToolError::internal("SSH manager is not available"));
         }
+        if is_local_job(&job) {
+            let path = job
+                .get("provider")
+                .and_then(|v| v.get("log_path"))
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| ToolError::internal("local job has no log_path"))?;
+            let lines = std::cmp::min(read_positive_int(args.get("lines")).unwrap_or(200), 2000);
+            let logs = tail_log_file(path, lines as usize)?;
+            return Ok(
+                serde_json::json!({"success": true, "job": public_job_view(&job), "logs": logs}),
+            );
+        }
         Ok(
             serde_json::json!({"success": false, "code": "NOT_SUPPORTED", "job_id": job_id, "kind": job.get("kind").cloned().unwrap_or(Value::Null)}),
         )
@@ -291,10 +771,19 @@ impl JobManager {
         self.job_logs_tail(args).await
     }
 
+    // ssh jobs keep action=follow_job so the ssh manager polls adaptively and returns the log
+    // delta; other jobs are waited on.
     async fn follow_job(&self, args: Value) -> Result<Value, ToolError> {
+        let job_id = self.ensure_job_id(args.get("job_id").unwrap_or(&Value::Null))?;
+        let is_ssh = self
+            .job_service
+            .get(&job_id)
+            .is_some_and(|job| provider_tool(&job) == Some("ssh"));
         let mut next = args.clone();
         if let Value::Object(map) = &mut next {
-            map.insert("action".to_string(), Value::String("job_wait".to_string()));
+            if !is_ssh {
+                map.insert("action".to_string(), Value::String("job_wait".to_string()));
+            }
         }
         self.job_wait(next).await
     }
@@ -302,6 +791,13 @@ impl JobManager {
     async fn job_cancel(&self, args: Value) -> Result<Value, ToolError> {
         let job_id = self.ensure_job_id(args.get("job_id").unwrap_or(&Value::Null))?;
         let reason = args.get("reason").and_then(|v| v.as_str());
+        if self
+            .job_service
+            .get(&job_id)
+            .is_some_and(|job| is_local_job(&job))
+        {
+            return self.job_kill(args).await;
+        }
         let canceled = self.job_service.cancel(&job_id, reason);
         if canceled.is_none() {
             return Ok(
@@ -311,6 +807,58 @@ impl JobManager {
         Ok(serde_json::json!({"success": true, "job": public_job_view(canceled.as_ref().unwrap())}))
     }
 
+    // Stops the work itself: local jobs abort their task and kill the child's process group,
+    // ssh jobs signal the remote pid.
+    async fn job_kill(&self, args: Value) -> Result<Value, ToolError> {
+        let job_id = self.ensure_job_id(args.get("job_id").unwrap_or(&Value::Null))?;
+        let Some(job) = self.job_service.get(&job_id) else {
+            return Ok(
+                serde_json::json!({"success": false, "code": "NOT_FOUND", "job_id": job_id}),
+            );
+        };
+        if is_local_job(&job) {
+            let reason = args.get("reason").and_then(|v| v.as_str());
+            let Some((updated, killed)) = self.job_service.kill_local(&job_id, reason) else {
+                return Ok(
+                    serde_json::json!({"success": false, "code": "NOT_FOUND", "job_id": job_id}),
+                );
+            };
+            if !killed && updated.get("status").and_then(|v| v.as_str()) == Some("running") {
+                return Ok(serde_json::json!({
+                    "success": false,
+                    "code": "NOT_OWNED",
+                    "job_id": job_id,
+                    "owner_pid": job.get("provider").and_then(|v| v.get("owner_pid")).cloned().unwrap_or(Value::Null),
+                }));
+            }
+            return Ok(
+                serde_json::json!({"success": true, "killed": killed, "job": public_job_view(&updated)}),
+            );
+        }
+        if provider_tool(&job) == Some("ssh") {
+            let ssh = self
+                .ssh_manager
+                .as_ref()
+                .ok_or_else(|| ToolError::internal("SSH manager is not available"))?;
+            let mut next = args.clone();
+            if let Value::Object(map) = &mut next {
+                map.insert("action".to_string(), Value::String("job_kill".to_string()));
+            }
+            let kill = ssh.handle_action(next).await?;
+            let updated = self
+                .job_service
+                .cancel(&job_id, args.get("reason").and_then(|v| v.as_str()));
+            return Ok(serde_json::json!({
+                "success": true,
+                "job": public_job_view(updated.as_ref().unwrap_or(&job)),
+                "kill": kill,
+            }));
+        }
+        Ok(
+            serde_json::json!({"success": false, "code": "NOT_SUPPORTED", "job_id": job_id, "kind": job.get("kind").cloned().unwrap_or(Value::Null)}),
+        )
+    }
+
     async fn job_forget(&self, args: Value) -> Result<Value, ToolError> {
         let job_id = self.ensure_job_id(args.get("job_id").unwrap_or(&Value::Null))?;
         let removed = self.job_service.forget(&job_id);
@@ -325,6 +873,23 @@ impl JobManager {
     }
 }
 
+fn probe_error_outcome(target: &WaitTarget, err: &ToolError, retry: bool) -> WaitOutcome {
+    WaitOutcome {
+        state: if retry {
+            WaitState::Running
+        } else {
+            WaitState::Unknown
+        },
+        done: !retry,
+        entry: serde_json::json!({
+            "key": target.key,
+            "job_id": target.job_id,
+            "status": if retry { "running" } else { "unknown" },
+            "error": {"code": err.code, "message": err.message},
+        }),
+    }
+}
+
 #[async_trait::async_trait]
 impl crate::services::tool_executor::ToolHandler for JobManager {
     async fn handle(&self, args: Value) -> Result<Value, ToolError> {
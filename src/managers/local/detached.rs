@@ -0,0 +1,139 @@
+use crate::errors::ToolError;
+use crate::services::job::kill_process_group;
+use crate::utils::stdin::{resolve_stdin_source, StdinSource};
+use serde_json::Value;
+use tokio::io::{copy, AsyncWriteExt};
+
+use super::LocalManager;
+
+async fn feed_stdin(
+    child: &mut tokio::process::Child,
+    input: StdinSource,
+) -> Result<(), ToolError> {
+    let Some(mut writer) = child.stdin.take() else {
+        return Ok(());
+    };
+    match input {
+        StdinSource::Bytes(bytes) => writer
+            .write_all(&bytes)
+            .await
+            .map_err(|err| ToolError::internal(format!("Failed to write stdin: {}", err))),
+        StdinSource::File(path) => {
+            let mut file = tokio::fs::File::open(&path).await.map_err(|err| {
+                ToolError::invalid_params(format!("stdin_file must be readable: {}", err))
+            })?;
+            copy(&mut file, &mut writer)
+                .await
+                .map(|_| ())
+                .map_err(|err| ToolError::internal(format!("Failed to stream stdin file: {}", err)))
+        }
+    }
+}
+
+impl LocalManager {
+    // Runs the command on a tokio task in its own process group; stdout and stderr stream into
+    // the job log and the outcome lands in the job store.
+    pub(super) async fn exec_detached(&self, args: Value) -> Result<Value, ToolError> {
+        let service = self.job_service.clone().ok_or_else(|| {
+            ToolError::invalid_params("detached exec requires the job service")
+                .with_hint("Run through the infra app, which wires jobs into the local tool.")
+        })?;
+        let mut cmd = self.build_command(&args)?;
+        let timeout_ms = args
+            .get("timeout_ms")
+            .and_then(|v| v.as_i64())
+            .filter(|v| *v > 0)
+            .map(|v| v as u64);
+        let stdin = resolve_stdin_source(&args)?;
+        let command = args.get("command").cloned().unwrap_or(Value::Null);
+        let job = service.start_local(
+            "local_exec",
+            serde_json::json!({"tool": "local", "command": command}),
+            args.get("trace_id").and_then(|v| v.as_str()),
+        )?;
+
+        let open_log = || {
+            std::fs::OpenOptions::new()
+                .append(true)
+                .open(&job.log_path)
+                .map_err(|err| ToolError::internal(format!("Failed to open job log: {}", err)))
+        };
+        let spawned = open_log().and_then(|stdout| {
+            cmd.stdout(stdout);
+            cmd.stderr(open_log()?);
+            cmd.stdin(if stdin.is_some() {
+                std::process::Stdio::piped()
+            } else {
+                std::process::Stdio::null()
+            });
+            #[cfg(unix)]
+            cmd.process_group(0);
+            cmd.kill_on_drop(true);
+            cmd.spawn()
+                .map_err(|err| ToolError::internal(format!("Failed to spawn command: {}", err)))
+        });
+        let mut child = match spawned {
+            Ok(child) => child,
+            Err(err) => {
+                service.finish_local(&job.job_id, Err(err.clone()));
+                return Err(err);
+            }
+        };
+        let pid = child.id();
+
+        let task_service = service.clone();
+        let job_id = job.job_id.clone();
+        let handle = tokio::spawn(async move {
+            let started = std::time::Instant::now();
+            if let Some(input) = stdin {
+                if let Err(err) = feed_stdin(&mut child, input).await {
+                    let _ = child.kill().await;
+                    task_service.finish_local(&job_id, Err(err));
+                    return;
+                }
+            }
+            let mut timed_out = false;
+            let status = match timeout_ms {
+                Some(timeout) => {
+                    match tokio::time::timeout(
+                        std::time::Duration::from_millis(timeout),
+                        child.wait(),
+                    )
+                    .await
+                    {
+                        Ok(result) => result,
+                        Err(_) => {
+                            timed_out = true;
+                            if let Some(pgid) = pid {
+                                kill_process_group(pgid);
+                            }
+                            child.wait().await
+                        }
+                    }
+                }
+                None => child.wait().await,
+            };
+            let outcome = status
+                .map(|status| {
+                    serde_json::json!({
+                        "success": status.success() && !timed_out,
+                        "exit_code": status.code().unwrap_or(-1),
+                        "timed_out": timed_out,
+                        "duration_ms": started.elapsed().as_millis() as u64,
+                    })
+                })
+                .map_err(|err| ToolError::internal(format!("Failed to wait for process: {}", err)));
+            task_service.finish_local(&job_id, outcome);
+        });
+        service.attach_local_task(&job.job_id, handle.abort_handle(), pid);
+
+        Ok(serde_json::json!({
+            "success": true,
+            "detached": true,
+            "job_id": job.job_id,
+            "pid": pid,
+            "log_path": job.log_path,
+            "log_uri": job.log_uri,
+        }))
+    }
+}
@@ -1,22 +1,21 @@
 use crate::errors::ToolError;
+use crate::utils::feature_flags;
 use crate::utils::user_paths::expand_home_path;
 use futures::future::join_all;
 use serde_json::Value;
 use std::path::PathBuf;
 use tokio::io::{copy, AsyncReadExt, AsyncWriteExt};
 
-use super::{random_token, read_positive_int, LocalManager};
+use super::pty::PtyOptions;
+use super::{random_token, LocalManager};
 use crate::utils::stdin::{resolve_stdin_source, StdinSource};
 
-const DEFAULT_STDOUT_INLINE_BYTES: usize = 32 * 1024;
-const DEFAULT_STDERR_INLINE_BYTES: usize = 16 * 1024;
-
 fn build_temp_dir() -> PathBuf {
     std::env::temp_dir().join(format!("infra-local-{}", std::process::id()))
 }
 
 impl LocalManager {
-    pub(super) async fn exec(&self, args: Value) -> Result<Value, ToolError> {
+    pub(super) fn build_command(&self, args: &Value) -> Result<tokio::process::Command, ToolError> {
         let command = self.validation.ensure_string(
             args.get("command").unwrap_or(&Value::Null),
             "command",
@@ -31,19 +30,6 @@ impl LocalManager {
             .get("cwd")
             .and_then(|v| v.as_str())
             .map(expand_home_path);
-        let timeout_ms = args
-            .get("timeout_ms")
-            .and_then(|v| v.as_i64())
-            .map(|v| v as u64);
-        let stdin = resolve_stdin_source(&args)?;
-        let stdin_eof = args
-            .get("stdin_eof")
-            .and_then(|v| v.as_bool())
-            .unwrap_or(true);
-        let inline = args
-            .get("inline")
-            .and_then(|v| v.as_bool())
-            .unwrap_or(false);
 
         let shell_value = args.get("shell");
         let (use_shell, shell_program) = match shell_value {
@@ -94,6 +80,31 @@ impl LocalManager {
             }
         }
 
+        Ok(cmd)
+    }
+
+    pub(super) async fn exec(&self, args: Value) -> Result<Value, ToolError> {
+        if let Some(options) = PtyOptions::parse(&args)? {
+            return self.exec_pty(&args, options).await;
+        }
+        if args.get("detached").and_then(|v| v.as_bool()) == Some(true) {
+            return self.exec_detached(args).await;
+        }
+        let mut cmd = self.build_command(&args)?;
+        let timeout_ms = args
+            .get("timeout_ms")
+            .and_then(|v| v.as_i64())
+            .map(|v| v as u64);
+        let stdin = resolve_stdin_source(&args)?;
+        let stdin_eof = args
+            .get("stdin_eof")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true);
+        let inline = args
+            .get("inline")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
         cmd.stdin(std::process::Stdio::piped());
         cmd.stdout(std::process::Stdio::piped());
         cmd.stderr(std::process::Stdio::piped());
@@ -134,16 +145,12 @@ impl LocalManager {
         let stdout_path = temp_dir.join(format!("stdout-{}.log", token));
         let stderr_path = temp_dir.join(format!("stderr-{}.log", token));
 
-        let stdout_env = std::env::var("INFRA_LOCAL_EXEC_MAX_STDOUT_INLINE_BYTES").ok();
-        let stderr_env = std::env::var("INFRA_LOCAL_EXEC_MAX_STDERR_INLINE_BYTES").ok();
-        let stdout_override = stdout_env.as_ref().map(|s| Value::String(s.clone()));
-        let stderr_override = stderr_env.as_ref().map(|s| Value::String(s.clone()));
         let max_stdout_inline = std::cmp::min(
-            read_positive_int(stdout_override.as_ref()).unwrap_or(DEFAULT_STDOUT_INLINE_BYTES),
+            feature_flags::LOCAL_EXEC_MAX_STDOUT_INLINE_BYTES.positive_number() as usize,
             256 * 1024,
         );
         let max_stderr_inline = std::cmp::min(
-            read_positive_int(stderr_override.as_ref()).unwrap_or(DEFAULT_STDERR_INLINE_BYTES),
+            feature_flags::LOCAL_EXEC_MAX_STDERR_INLINE_BYTES.positive_number() as usize,
             256 * 1024,
         );
 
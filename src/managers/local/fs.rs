@@ -1,12 +1,109 @@
 use crate::errors::ToolError;
+use crate::utils::artifacts::resolve_input_path;
+use crate::utils::text_patch::{apply_edits, parse_edits, parse_line_range, unified_diff};
 use crate::utils::user_paths::expand_home_path;
 use base64::Engine;
 use serde_json::Value;
-use std::path::PathBuf;
-use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use std::path::{Path, PathBuf};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
 
 use super::{random_token, read_positive_int, LocalManager};
 
+const FS_READ_MAX_BYTES: usize = 256 * 1024;
+
+fn parse_encoding(args: &Value) -> Result<&'static str, ToolError> {
+    match args
+        .get("encoding")
+        .and_then(|v| v.as_str())
+        .map(|v| v.trim().to_lowercase())
+        .as_deref()
+    {
+        None | Some("utf8") | Some("utf-8") => Ok("utf8"),
+        Some("base64") => Ok("base64"),
+        Some(other) => Err(ToolError::invalid_params(format!(
+            "encoding must be utf8 or base64 (got '{}')",
+            other
+        ))),
+    }
+}
+
+// Returns (content, bytes represented, lossy). A UTF-8 sequence split by the byte cap is dropped
+// rather than rendered as a replacement character.
+fn encode_content(buffer: &[u8], encoding: &str, cut: bool) -> (String, usize, bool) {
+    if encoding == "base64" {
+        return (
+            base64::engine::general_purpose::STANDARD.encode(buffer),
+            buffer.len(),
+            false,
+        );
+    }
+    let mut end = buffer.len();
+    if cut {
+        if let Err(err) = std::str::from_utf8(buffer) {
+            if err.error_len().is_none() {
+                end = err.valid_up_to();
+            }
+        }
+    }
+    let bytes = &buffer[..end];
+    match std::str::from_utf8(bytes) {
+        Ok(text) => (text.to_string(), end, false),
+        Err(_) => (String::from_utf8_lossy(bytes).to_string(), end, true),
+    }
+}
+
+async fn set_mode(path: &Path, mode: u32) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let permissions = std::fs::Permissions::from_mode(mode);
+        tokio::fs::set_permissions(path, permissions).await.ok();
+    }
+    #[cfg(not(unix))]
+    let _ = (path, mode);
+}
+
+// Writes a sibling temp file and renames it over `path`, so readers see either the old or the
+// new content, never a partial write.
+async fn atomic_write(
+    path: &Path,
+    content: &[u8],
+    mode: u32,
+    overwrite: bool,
+) -> Result<(), ToolError> {
+    let tmp_path = path.with_file_name(format!(
+        "{}.part-{}",
+        path.file_name()
+            .map(|v| v.to_string_lossy().to_string())
+            .unwrap_or_else(|| "file".to_string()),
+        random_token()
+    ));
+
+    if let Err(err) = tokio::fs::write(&tmp_path, content).await {
+        tokio::fs::remove_file(&tmp_path).await.ok();
+        return Err(ToolError::internal(format!(
+            "Failed to write file: {}",
+            err
+        )));
+    }
+    set_mode(&tmp_path, mode).await;
+
+    let mut rename_result = tokio::fs::rename(&tmp_path, path).await;
+    if rename_result.is_err() && overwrite {
+        let _ = tokio::fs::remove_file(path).await;
+        rename_result = tokio::fs::rename(&tmp_path, path).await;
+    }
+    if let Err(err) = rename_result {
+        tokio::fs::remove_file(&tmp_path).await.ok();
+        return Err(ToolError::internal(format!(
+            "Failed to replace file: {}",
+            err
+        )));
+    }
+    set_mode(path, mode).await;
+    Ok(())
+}
+
 impl LocalManager {
     pub(super) async fn fs_read(&self, args: Value) -> Result<Value, ToolError> {
         let path = self.validation.ensure_string(
@@ -14,10 +111,22 @@ impl LocalManager {
             "path",
             false,
         )?;
-        let resolved = expand_home_path(&path);
+        let resolved = resolve_input_path(&path, "path")?;
+        let encoding = parse_encoding(&args)?;
+        let max_bytes = read_positive_int(args.get("max_bytes")).unwrap_or(FS_READ_MAX_BYTES);
+
+        let lines = match args.get("lines") {
+            None | Some(Value::Null) => None,
+            Some(range) => {
+                if args.get("offset").is_some() || args.get("length").is_some() {
+                    return Err(ToolError::invalid_params(
+                        "lines cannot be combined with offset/length",
+                    ));
+                }
+                Some(parse_line_range(range, "lines", true)?)
+            }
+        };
 
-        let offset = read_positive_int(args.get("offset")).unwrap_or(0);
-        let length = read_positive_int(args.get("length")).unwrap_or(256 * 1024);
         let mut file = tokio::fs::File::open(&resolved)
             .await
             .map_err(|err| ToolError::invalid_params(format!("path must be readable: {}", err)))?;
@@ -26,37 +135,80 @@ impl LocalManager {
             .await
             .map_err(|err| ToolError::internal(err.to_string()))?;
         let file_bytes = metadata.len() as usize;
-        let start = offset.min(file_bytes);
-        let to_read = length.min(file_bytes.saturating_sub(start));
-        let mut buffer = vec![0u8; to_read];
-        if to_read > 0 {
-            file.seek(std::io::SeekFrom::Start(start as u64))
-                .await
-                .map_err(|err| ToolError::internal(format!("Failed to seek file: {}", err)))?;
-            file.read_exact(&mut buffer)
-                .await
-                .map_err(|err| ToolError::internal(format!("Failed to read file: {}", err)))?;
-        }
-        let encoding = args
-            .get("encoding")
-            .and_then(|v| v.as_str())
-            .unwrap_or("utf8")
-            .to_lowercase();
-        let content = if encoding == "base64" {
-            base64::engine::general_purpose::STANDARD.encode(&buffer)
-        } else {
-            String::from_utf8_lossy(&buffer).to_string()
-        };
-        Ok(serde_json::json!({
+
+        let mut out = serde_json::json!({
             "success": true,
             "path": resolved,
-            "content": content,
             "encoding": encoding,
             "file_bytes": file_bytes,
-            "offset": start,
-            "length": to_read,
-            "truncated": start + to_read < file_bytes,
-        }))
+        });
+        let (buffer, start, truncated, inline_truncated) = if let Some((first, last)) = lines {
+            let mut reader = tokio::io::BufReader::new(file);
+            let mut buffer = Vec::new();
+            let mut line = Vec::new();
+            let (mut number, mut position, mut start) = (0usize, 0usize, None);
+            let mut returned_last = None;
+            let mut inline_truncated = false;
+            loop {
+                line.clear();
+                let read = reader
+                    .read_until(b'\n', &mut line)
+                    .await
+                    .map_err(|err| ToolError::internal(format!("Failed to read file: {}", err)))?;
+                if read == 0 {
+                    break;
+                }
+                number += 1;
+                if number >= first && last.is_none_or(|last| number <= last) {
+                    start.get_or_insert(position);
+                    let room = max_bytes.saturating_sub(buffer.len());
+                    buffer.extend_from_slice(&line[..read.min(room)]);
+                    if read > room {
+                        inline_truncated = true;
+                        break;
+                    }
+                    returned_last = Some(number);
+                }
+                position += read;
+                if last.is_some_and(|last| number >= last) {
+                    break;
+                }
+            }
+            let start = start.unwrap_or(position.min(file_bytes));
+            out["lines"] = serde_json::json!({"start": first, "end": returned_last});
+            let truncated = start + buffer.len() < file_bytes;
+            (buffer, start, truncated, inline_truncated)
+        } else {
+            let offset = read_positive_int(args.get("offset")).unwrap_or(0);
+            let start = offset.min(file_bytes);
+            let available = file_bytes.saturating_sub(start);
+            let available = read_positive_int(args.get("length"))
+                .map_or(available, |length| length.min(available));
+            let to_read = available.min(max_bytes);
+            let mut buffer = vec![0u8; to_read];
+            if to_read > 0 {
+                file.seek(std::io::SeekFrom::Start(start as u64))
+                    .await
+                    .map_err(|err| ToolError::internal(format!("Failed to seek file: {}", err)))?;
+                file.read_exact(&mut buffer)
+                    .await
+                    .map_err(|err| ToolError::internal(format!("Failed to read file: {}", err)))?;
+            }
+            let truncated = start + to_read < file_bytes;
+            (buffer, start, truncated, to_read < available)
+        };
+
+        let (content, returned, lossy) = encode_content(&buffer, encoding, inline_truncated);
+        out["content"] = Value::String(content);
+        out["offset"] = Value::from(start);
+        out["length"] = Value::from(returned);
+        out["truncated"] = Value::Bool(truncated || returned < buffer.len());
+        out["inline_truncated"] = Value::Bool(inline_truncated);
+        out["max_bytes"] = Value::from(max_bytes);
+        if lossy {
+            out["lossy"] = Value::Bool(true);
+        }
+        Ok(out)
     }
 
     pub(super) async fn fs_write(&self, args: Value) -> Result<Value, ToolError> {
@@ -70,24 +222,34 @@ impl LocalManager {
             .get("overwrite")
             .and_then(|v| v.as_bool())
             .unwrap_or(false);
-        if resolved.exists() && !overwrite {
-            return Err(ToolError::conflict("path already exists"));
-        }
+        let append = args.get("append").and_then(|v| v.as_bool()) == Some(true);
+        let create_dirs = args
+            .get("create_dirs")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true);
 
-        let mode = args.get("mode").and_then(|v| v.as_i64()).unwrap_or(0o600);
-        if !(0..=0o7777).contains(&mode) {
-            return Err(ToolError::invalid_params(
-                "mode must be a valid unix permission mask",
-            ));
-        }
-        let mode = mode as u32;
+        let mode = match args.get("mode") {
+            None | Some(Value::Null) => None,
+            Some(value) => match value.as_i64() {
+                Some(mode) if (0..=0o7777).contains(&mode) => Some(mode as u32),
+                _ => {
+                    return Err(ToolError::invalid_params(
+                        "mode must be a valid unix permission mask",
+                    ))
+                }
+            },
+        };
 
-        let encoding = args
-            .get("encoding")
-            .and_then(|v| v.as_str())
-            .unwrap_or("utf8")
-            .to_lowercase();
+        if let Some(patch) = args.get("patch").filter(|v| !v.is_null()) {
+            if append || args.get("content").is_some() || args.get("content_base64").is_some() {
+                return Err(ToolError::invalid_params(
+                    "patch cannot be combined with content, content_base64 or append",
+                ));
+            }
+            return self.fs_patch(&resolved, patch, mode).await;
+        }
 
+        let encoding = parse_encoding(&args)?;
         let content = if let Some(raw) = args.get("content_base64").and_then(|v| v.as_str()) {
             base64::engine::general_purpose::STANDARD
                 .decode(raw.as_bytes())
@@ -106,60 +268,54 @@ impl LocalManager {
             ));
         };
 
-        if let Some(parent) = resolved.parent() {
-            tokio::fs::create_dir_all(parent)
-                .await
-                .map_err(|err| ToolError::internal(format!("Failed to create dir: {}", err)))?;
+        if !append && resolved.exists() && !overwrite {
+            return Err(ToolError::conflict("path already exists"));
         }
 
-        let tmp_path = resolved.with_file_name(format!(
-            "{}.part-{}",
-            resolved
-                .file_name()
-                .map(|v| v.to_string_lossy().to_string())
-                .unwrap_or_else(|| "file".to_string()),
-            random_token()
-        ));
-
-        if let Err(err) = tokio::fs::write(&tmp_path, &content).await {
-            tokio::fs::remove_file(&tmp_path).await.ok();
-            return Err(ToolError::internal(format!(
-                "Failed to write file: {}",
-                err
-            )));
+        if let Some(parent) = resolved.parent().filter(|p| !p.as_os_str().is_empty()) {
+            if create_dirs {
+                tokio::fs::create_dir_all(parent)
+                    .await
+                    .map_err(|err| ToolError::internal(format!("Failed to create dir: {}", err)))?;
+            } else if !parent.is_dir() {
+                return Err(ToolError::invalid_params(format!(
+                    "parent directory does not exist: {}",
+                    parent.display()
+                ))
+                .with_hint("Pass create_dirs=true to create it."));
+            }
         }
 
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            let permissions = std::fs::Permissions::from_mode(mode);
-            tokio::fs::set_permissions(&tmp_path, permissions)
+        if append {
+            // Appends go straight to the file (O_APPEND); only a newly created file gets `mode`.
+            let existed = resolved.exists();
+            let mut file = tokio::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&resolved)
                 .await
-                .ok();
-        }
-
-        let mut rename_result = tokio::fs::rename(&tmp_path, &resolved).await;
-        if rename_result.is_err() && overwrite {
-            let _ = tokio::fs::remove_file(&resolved).await;
-            rename_result = tokio::fs::rename(&tmp_path, &resolved).await;
-        }
-        if let Err(err) = rename_result {
-            tokio::fs::remove_file(&tmp_path).await.ok();
-            return Err(ToolError::internal(format!(
-                "Failed to replace file: {}",
-                err
-            )));
-        }
-
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            let permissions = std::fs::Permissions::from_mode(mode);
-            tokio::fs::set_permissions(&resolved, permissions)
+                .map_err(|err| ToolError::internal(format!("Failed to open file: {}", err)))?;
+            file.write_all(&content)
                 .await
-                .ok();
+                .map_err(|err| ToolError::internal(format!("Failed to write file: {}", err)))?;
+            file.flush()
+                .await
+                .map_err(|err| ToolError::internal(format!("Failed to write file: {}", err)))?;
+            if !existed {
+                set_mode(&resolved, mode.unwrap_or(0o600)).await;
+            }
+            let file_bytes = file.metadata().await.map(|m| m.len()).unwrap_or_default();
+            return Ok(serde_json::json!({
+                "success": true,
+                "path": resolved,
+                "bytes": content.len(),
+                "bytes_written": content.len(),
+                "appended": true,
+                "file_bytes": file_bytes,
+            }));
         }
 
+        atomic_write(&resolved, &content, mode.unwrap_or(0o600), overwrite).await?;
         Ok(serde_json::json!({
             "success": true,
             "path": resolved,
@@ -168,12 +324,63 @@ impl LocalManager {
         }))
     }
 
+    async fn fs_patch(
+        &self,
+        resolved: &Path,
+        patch: &Value,
+        mode: Option<u32>,
+    ) -> Result<Value, ToolError> {
+        let edits = parse_edits(patch)?;
+        let raw = tokio::fs::read(resolved)
+            .await
+            .map_err(|err| ToolError::invalid_params(format!("path must be readable: {}", err)))?;
+        let before = String::from_utf8(raw).map_err(|_| {
+            ToolError::invalid_params("patch requires a UTF-8 text file")
+                .with_hint("Rewrite binary files with content_base64 instead.")
+        })?;
+        let (after, replacements) = apply_edits(&before, &edits)?;
+        let changed = after != before;
+        if changed {
+            #[cfg(unix)]
+            let current_mode = {
+                use std::os::unix::fs::PermissionsExt;
+                tokio::fs::metadata(resolved)
+                    .await
+                    .map(|m| m.permissions().mode() & 0o7777)
+                    .unwrap_or(0o600)
+            };
+            #[cfg(not(unix))]
+            let current_mode = 0o600;
+            atomic_write(
+                resolved,
+                after.as_bytes(),
+                mode.unwrap_or(current_mode),
+                true,
+            )
+            .await?;
+        }
+        let label = resolved
+            .file_name()
+            .map(|v| v.to_string_lossy().to_string())
+            .unwrap_or_default();
+        Ok(serde_json::json!({
+            "success": true,
+            "path": resolved,
+            "patched": true,
+            "changed": changed,
+            "replacements": replacements,
+            "bytes": after.len(),
+            "bytes_written": if changed { after.len() } else { 0 },
+            "diff": unified_diff(&label, &before, &after),
+        }))
+    }
+
     pub(super) async fn fs_list(&self, args: Value) -> Result<Value, ToolError> {
         let root = match args.get("path") {
             None | Some(Value::Null) => PathBuf::from("."),
             Some(value) => PathBuf::from(self.validation.ensure_string(value, "path", false)?),
         };
-        let root = expand_home_path(root.to_string_lossy().as_ref());
+        let root = resolve_input_path(root.to_string_lossy().as_ref(), "path")?;
 
         let recursive = args.get("recursive").and_then(|v| v.as_bool()) == Some(true);
         let max_depth = args
@@ -255,7 +462,7 @@ impl LocalManager {
             "path",
             false,
         )?;
-        let resolved = expand_home_path(&path);
+        let resolved = resolve_input_path(&path, "path")?;
         let metadata = tokio::fs::symlink_metadata(&resolved)
             .await
             .map_err(|err| ToolError::invalid_params(format!("path must exist: {}", err)))?;
@@ -1,12 +1,16 @@
 use crate::errors::ToolError;
+use crate::services::job::JobService;
 use crate::services::logger::Logger;
 use crate::services::validation::Validation;
 use crate::utils::feature_flags::is_unsafe_local_enabled;
 use crate::utils::tool_errors::unknown_action_error;
 use serde_json::Value;
+use std::sync::Arc;
 
+mod detached;
 mod exec;
 mod fs;
+mod pty;
 
 pub(crate) const LOCAL_ACTIONS: &[&str] = &[
     "exec", "batch", "fs_read", "fs_write", "fs_list", "fs_stat", "fs_mkdir", "fs_rm",
@@ -40,6 +44,7 @@ pub struct LocalManager {
     logger: Logger,
     validation: Validation,
     enabled: bool,
+    job_service: Option<Arc<JobService>>,
 }
 
 impl LocalManager {
@@ -48,9 +53,15 @@ impl LocalManager {
             logger: logger.child("local"),
             validation,
             enabled: enabled.unwrap_or_else(is_unsafe_local_enabled),
+            job_service: None,
         }
     }
 
+    pub fn with_job_service(mut self, job_service: Arc<JobService>) -> Self {
+        self.job_service = Some(job_service);
+        self
+    }
+
     fn ensure_enabled(&self) -> Result<(), ToolError> {
         if !self.enabled {
             return Err(ToolError::denied("Unsafe local tool is disabled.")
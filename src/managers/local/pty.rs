@@ -0,0 +1,504 @@
+use crate::errors::ToolError;
+use regex::Regex;
+use serde_json::Value;
+
+use super::LocalManager;
+
+const DEFAULT_ROWS: u64 = 24;
+const DEFAULT_COLS: u64 = 80;
+const MAX_WINDOW_SIZE: u64 = 1_000;
+const DEFAULT_EXPECT_TIMEOUT_MS: u64 = 10_000;
+const MAX_EXPECT_TIMEOUT_MS: u64 = 600_000;
+const MAX_EXPECT_STEPS: usize = 50;
+// Output kept for matching the current expect step; older output is dropped.
+#[cfg(unix)]
+const EXPECT_WINDOW_BYTES: usize = 64 * 1024;
+// Once the command exits, output still buffered in the pty is read for this long.
+#[cfg(unix)]
+const EXIT_DRAIN_MS: u64 = 500;
+const PTY_ONLY_KEYS: &[&str] = &["expect", "pty_rows", "pty_cols", "strip_ansi"];
+const STDIN_KEYS: &[&str] = &["stdin", "stdin_base64", "stdin_file", "stdin_ref"];
+
+// One prompt/response step: wait until `pattern` (a regex over the ANSI-stripped output)
+// appears, then write `send` as-is (include the "\n").
+struct ExpectStep {
+    pattern: Regex,
+    source: String,
+    send: Option<String>,
+    timeout_ms: u64,
+}
+
+pub(super) struct PtyOptions {
+    rows: u16,
+    cols: u16,
+    strip_ansi: bool,
+    expect: Vec<ExpectStep>,
+}
+
+fn window_size(args: &Value, key: &str, default: u64) -> Result<u16, ToolError> {
+    match args.get(key) {
+        None | Some(Value::Null) => Ok(default as u16),
+        Some(value) => value
+            .as_u64()
+            .filter(|n| (1..=MAX_WINDOW_SIZE).contains(n))
+            .map(|n| n as u16)
+            .ok_or_else(|| {
+                ToolError::invalid_params(format!(
+                    "{} must be an integer between 1 and {}",
+                    key, MAX_WINDOW_SIZE
+                ))
+            }),
+    }
+}
+
+fn parse_expect(value: Option<&Value>) -> Result<Vec<ExpectStep>, ToolError> {
+    let steps = match value {
+        None | Some(Value::Null) => return Ok(Vec::new()),
+        Some(Value::Array(steps)) => steps,
+        Some(_) => {
+            return Err(ToolError::invalid_params(
+                "expect must be an array of { pattern, send, timeout_ms }",
+            ))
+        }
+    };
+    if steps.len() > MAX_EXPECT_STEPS {
+        return Err(ToolError::invalid_params(format!(
+            "expect allows at most {} steps",
+            MAX_EXPECT_STEPS
+        )));
+    }
+    steps
+        .iter()
+        .enumerate()
+        .map(|(index, step)| {
+            let source = step
+                .get("pattern")
+                .and_then(|v| v.as_str())
+                .filter(|v| !v.is_empty())
+                .ok_or_else(|| {
+                    ToolError::invalid_params(format!(
+                        "expect[{}].pattern must be a non-empty string",
+                        index
+                    ))
+                })?;
+            let pattern = Regex::new(source).map_err(|err| {
+                ToolError::invalid_params(format!(
+                    "expect[{}].pattern is not a valid regex: {}",
+                    index, err
+                ))
+                .with_hint("Escape literal brackets and question marks, e.g. \"\\\\[y/N\\\\]\".")
+            })?;
+            let send = match step.get("send") {
+                None | Some(Value::Null) => None,
+                Some(Value::String(text)) => Some(text.clone()),
+                Some(_) => {
+                    return Err(ToolError::invalid_params(format!(
+                        "expect[{}].send must be a string",
+                        index
+                    )))
+                }
+            };
+            let timeout_ms = match step.get("timeout_ms") {
+                None | Some(Value::Null) => DEFAULT_EXPECT_TIMEOUT_MS,
+                Some(value) => value
+                    .as_u64()
+                    .filter(|ms| (1..=MAX_EXPECT_TIMEOUT_MS).contains(ms))
+                    .ok_or_else(|| {
+                        ToolError::invalid_params(format!(
+                            "expect[{}].timeout_ms must be between 1 and {}",
+                            index, MAX_EXPECT_TIMEOUT_MS
+                        ))
+                    })?,
+            };
+            Ok(ExpectStep {
+                pattern,
+                source: source.to_string(),
+                send,
+                timeout_ms,
+            })
+        })
+        .collect()
+}
+
+impl PtyOptions {
+    // None unless `pty: true`; the pty-only options are refused without it.
+    pub(super) fn parse(args: &Value) -> Result<Option<Self>, ToolError> {
+        let pty = match args.get("pty") {
+            None | Some(Value::Null) => false,
+            Some(Value::Bool(flag)) => *flag,
+            Some(_) => return Err(ToolError::invalid_params("pty must be a boolean")),
+        };
+        if !pty {
+            if let Some(key) = PTY_ONLY_KEYS
+                .iter()
+                .find(|key| args.get(**key).is_some_and(|v| !v.is_null()))
+            {
+                return Err(ToolError::invalid_params(format!(
+                    "{} needs pty: true",
+                    key
+                )));
+            }
+            return Ok(None);
+        }
+        if args.get("detached").and_then(|v| v.as_bool()) == Some(true) {
+            return Err(ToolError::invalid_params(
+                "pty cannot be combined with detached",
+            ));
+        }
+        if STDIN_KEYS
+            .iter()
+            .any(|key| args.get(*key).is_some_and(|v| !v.is_null()))
+        {
+            return Err(ToolError::invalid_params(
+                "stdin, stdin_base64, stdin_file and stdin_ref are not supported with pty",
+            )
+            .with_hint("Answer prompts with expect steps: [{ pattern, send }]."));
+        }
+        Ok(Some(Self {
+            rows: window_size(args, "pty_rows", DEFAULT_ROWS)?,
+            cols: window_size(args, "pty_cols", DEFAULT_COLS)?,
+            strip_ansi: args
+                .get("strip_ansi")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false),
+            expect: parse_expect(args.get("expect"))?,
+        }))
+    }
+}
+
+#[cfg(unix)]
+mod unix {
+    use crate::errors::ToolError;
+    use std::io;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+    use tokio::io::unix::AsyncFd;
+
+    fn last_error(what: &str) -> ToolError {
+        ToolError::internal(format!("{}: {}", what, io::Error::last_os_error()))
+    }
+
+    // (master, slave). The master is non-blocking and close-on-exec; the slave becomes the
+    // child's stdin, stdout and stderr.
+    pub(super) fn open_pty(rows: u16, cols: u16) -> Result<(OwnedFd, OwnedFd), ToolError> {
+        let mut master = -1;
+        let mut slave = -1;
+        let mut size = libc::winsize {
+            ws_row: rows,
+            ws_col: cols,
+            ws_xpixel: 0,
+            ws_ypixel: 0,
+        };
+        // SAFETY: openpty only writes the two descriptors; the name and termios pointers may be
+        // null.
+        let rc = unsafe {
+            libc::openpty(
+                &mut master,
+                &mut slave,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                std::ptr::addr_of_mut!(size),
+            )
+        };
+        if rc != 0 {
+            return Err(last_error("Failed to open pty"));
+        }
+        // SAFETY: both descriptors were just opened and are owned by nothing else.
+        let (master, slave) =
+            unsafe { (OwnedFd::from_raw_fd(master), OwnedFd::from_raw_fd(slave)) };
+        for fd in [master.as_raw_fd(), slave.as_raw_fd()] {
+            // SAFETY: fcntl on a descriptor we own.
+            if unsafe { libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) } == -1 {
+                return Err(last_error("Failed to configure pty"));
+            }
+        }
+        // SAFETY: as above.
+        let flags = unsafe { libc::fcntl(master.as_raw_fd(), libc::F_GETFL) };
+        if flags == -1
+            || unsafe { libc::fcntl(master.as_raw_fd(), libc::F_SETFL, flags | libc::O_NONBLOCK) }
+                == -1
+        {
+            return Err(last_error("Failed to configure pty"));
+        }
+        Ok((master, slave))
+    }
+
+    // Makes the child a session leader with the pty as its controlling terminal.
+    pub(super) fn set_controlling_terminal() -> io::Result<()> {
+        // SAFETY: called between fork and exec; setsid and ioctl are async-signal-safe.
+        unsafe {
+            if libc::setsid() == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            if libc::ioctl(0, libc::TIOCSCTTY as _, 0) == -1 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+
+    // Ok(0) at end of output: Linux reports EIO once every slave descriptor is closed.
+    pub(super) async fn read(master: &AsyncFd<OwnedFd>, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let mut guard = master.readable().await?;
+            let result = guard.try_io(|fd| {
+                // SAFETY: reads into a buffer we own, at most its length.
+                let n = unsafe { libc::read(fd.as_raw_fd(), buf.as_mut_ptr().cast(), buf.len()) };
+                if n < 0 {
+                    Err(io::Error::last_os_error())
+                } else {
+                    Ok(n as usize)
+                }
+            });
+            match result {
+                Ok(Err(err)) if err.raw_os_error() == Some(libc::EIO) => return Ok(0),
+                Ok(result) => return result,
+                Err(_would_block) => continue,
+            }
+        }
+    }
+
+    pub(super) async fn write_all(master: &AsyncFd<OwnedFd>, mut data: &[u8]) -> io::Result<()> {
+        while !data.is_empty() {
+            let mut guard = master.writable().await?;
+            let result = guard.try_io(|fd| {
+                // SAFETY: writes from a slice we borrow, at most its length.
+                let n = unsafe { libc::write(fd.as_raw_fd(), data.as_ptr().cast(), data.len()) };
+                if n < 0 {
+                    Err(io::Error::last_os_error())
+                } else {
+                    Ok(n as usize)
+                }
+            });
+            match result {
+                Ok(Ok(n)) => data = &data[n..],
+                Ok(Err(err)) => return Err(err),
+                Err(_would_block) => continue,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl LocalManager {
+    #[cfg(not(unix))]
+    pub(super) async fn exec_pty(
+        &self,
+        _args: &Value,
+        _options: PtyOptions,
+    ) -> Result<Value, ToolError> {
+        Err(ToolError::invalid_params(
+            "pty is only supported on Unix hosts",
+        ))
+    }
+
+    // Runs the command on a pseudo-terminal. stdout and stderr arrive merged, so everything is
+    // reported under `stdout`, captured like ssh exec output (inline prefix, artifact ref when
+    // truncated or streamed). Expect steps are answered in order; a step that times out, or a
+    // command that exits before all steps matched, fails the call under `expect_failed`.
+    #[cfg(unix)]
+    pub(super) async fn exec_pty(
+        &self,
+        args: &Value,
+        options: PtyOptions,
+    ) -> Result<Value, ToolError> {
+        use crate::utils::ansi::AnsiStripper;
+        use crate::utils::exec_capture::{resolve_stream_to_artifact_mode, CaptureState};
+        use crate::utils::feature_flags;
+        use crate::utils::redact::redact_text;
+        use std::os::unix::process::ExitStatusExt;
+        use std::time::{Duration, Instant};
+        use tokio::io::unix::AsyncFd;
+
+        let mut cmd = self.build_command(args)?;
+        let timeout_ms = args.get("timeout_ms").and_then(|v| v.as_u64());
+        let (master, slave) = unix::open_pty(options.rows, options.cols)?;
+        let clone_slave = || {
+            slave
+                .try_clone()
+                .map_err(|err| ToolError::internal(format!("Failed to open pty: {}", err)))
+        };
+        cmd.stdin(std::process::Stdio::from(clone_slave()?));
+        cmd.stdout(std::process::Stdio::from(clone_slave()?));
+        cmd.stderr(std::process::Stdio::from(clone_slave()?));
+        cmd.kill_on_drop(true);
+        // SAFETY: the hook only calls async-signal-safe functions.
+        unsafe {
+            cmd.pre_exec(unix::set_controlling_terminal);
+        }
+        let mut child = cmd
+            .spawn()
+            .map_err(|err| ToolError::internal(format!("Failed to spawn command: {}", err)))?;
+        // The parent's slave descriptors must be closed, or the master never sees end of output.
+        drop(cmd);
+        drop(slave);
+        let master = AsyncFd::new(master)
+            .map_err(|err| ToolError::internal(format!("Failed to watch pty: {}", err)))?;
+
+        let trace_id = args.get("trace_id").and_then(|v| v.as_str());
+        let span_id = args.get("span_id").and_then(|v| v.as_str());
+        let max_inline = std::cmp::min(
+            feature_flags::LOCAL_EXEC_MAX_STDOUT_INLINE_BYTES.positive_number() as usize,
+            256 * 1024,
+        );
+        let mut capture = CaptureState::new(
+            feature_flags::SSH_MAX_CAPTURE_BYTES.number() as usize,
+            max_inline,
+            resolve_stream_to_artifact_mode().as_deref(),
+            "pty.log",
+            trace_id,
+            span_id,
+        )?;
+        let mut output_stripper = AnsiStripper::default();
+        let mut expect_stripper = AnsiStripper::default();
+        let mut window = String::new();
+
+        let started = Instant::now();
+        let overall_deadline = timeout_ms.map(|ms| started + Duration::from_millis(ms));
+        let mut step = 0usize;
+        let mut step_started = Instant::now();
+        let mut steps = Vec::new();
+        let mut exit_status = None;
+        let mut drain_deadline: Option<Instant> = None;
+        let mut timed_out = false;
+        let mut expect_failed = Value::Null;
+        let mut buf = [0u8; 8192];
+
+        loop {
+            while let Some(current) = options.expect.get(step) {
+                let Some(found) = current.pattern.find(&window) else {
+                    break;
+                };
+                let end = found.end();
+                window.drain(..end);
+                if let Some(send) = current.send.as_deref() {
+                    unix::write_all(&master, send.as_bytes())
+                        .await
+                        .map_err(|err| {
+                            ToolError::internal(format!("Failed to write to pty: {}", err))
+                        })?;
+                }
+                steps.push(serde_json::json!({
+                    "step": step + 1,
+                    "pattern": current.source,
+                    "sent": current.send.is_some(),
+                    "elapsed_ms": step_started.elapsed().as_millis() as u64,
+                }));
+                step += 1;
+                step_started = Instant::now();
+            }
+            let step_deadline = options
+                .expect
+                .get(step)
+                .filter(|_| exit_status.is_none())
+                .map(|current| step_started + Duration::from_millis(current.timeout_ms));
+            let next_deadline = [overall_deadline, step_deadline, drain_deadline]
+                .into_iter()
+                .flatten()
+                .min();
+            let wake = async {
+                match next_deadline {
+                    Some(at) => tokio::time::sleep_until(at.into()).await,
+                    None => std::future::pending().await,
+                }
+            };
+            tokio::select! {
+                read = unix::read(&master, &mut buf) => {
+                    let n = read.unwrap_or(0);
+                    if n == 0 {
+                        break;
+                    }
+                    let chunk = &buf[..n];
+                    if options.strip_ansi {
+                        capture.capture(&output_stripper.feed(chunk));
+                    } else {
+                        capture.capture(chunk);
+                    }
+                    if step < options.expect.len() {
+                        window.push_str(&String::from_utf8_lossy(&expect_stripper.feed(chunk)));
+                        if window.len() > EXPECT_WINDOW_BYTES {
+                            let mut cut = window.len() - EXPECT_WINDOW_BYTES;
+                            while !window.is_char_boundary(cut) {
+                                cut += 1;
+                            }
+                            window.drain(..cut);
+                        }
+                    }
+                }
+                status = child.wait(), if exit_status.is_none() => {
+                    exit_status = Some(status.map_err(|err| {
+                        ToolError::internal(format!("Failed to wait for process: {}", err))
+                    })?);
+                    drain_deadline = Some(Instant::now() + Duration::from_millis(EXIT_DRAIN_MS));
+                }
+                _ = wake => {
+                    let now = Instant::now();
+                    if drain_deadline.is_some_and(|at| now >= at) {
+                        break;
+                    }
+                    if overall_deadline.is_some_and(|at| now >= at) {
+                        timed_out = true;
+                        break;
+                    }
+                    if let (Some(at), Some(current)) = (step_deadline, options.expect.get(step)) {
+                        if now >= at {
+                            expect_failed = serde_json::json!({
+                                "step": step + 1,
+                                "pattern": current.source,
+                                "reason": format!("pattern not seen within {} ms", current.timeout_ms),
+                            });
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        if options.strip_ansi {
+            capture.capture(&output_stripper.finish());
+        }
+        let status = match exit_status {
+            Some(status) => status,
+            None => {
+                let _ = child.kill().await;
+                child.wait().await.map_err(|err| {
+                    ToolError::internal(format!("Failed to wait for process: {}", err))
+                })?
+            }
+        };
+        if expect_failed.is_null() {
+            if let Some(current) = options.expect.get(step) {
+                expect_failed = serde_json::json!({
+                    "step": step + 1,
+                    "pattern": current.source,
+                    "reason": if timed_out {
+                        "timeout_ms reached first"
+                    } else {
+                        "command exited before the pattern appeared"
+                    },
+                });
+            }
+        }
+
+        let stdout = redact_text(&capture.inline_string(), usize::MAX, None)
+            .trim_end_matches(&['\r', '\n'][..])
+            .to_string();
+        let stdout_ref = capture.finalize_artifact(None)?;
+        Ok(serde_json::json!({
+            "success": status.success() && !timed_out && expect_failed.is_null(),
+            "exit_code": status.code().unwrap_or(-1),
+            "signal": status.signal(),
+            "timed_out": timed_out,
+            "duration_ms": started.elapsed().as_millis() as u64,
+            "pty": {"rows": options.rows, "cols": options.cols, "strip_ansi": options.strip_ansi},
+            "stdout": stdout,
+            "stderr": "",
+            "stderr_merged": true,
+            "stdout_bytes": capture.total,
+            "stdout_captured_bytes": capture.captured,
+            "stdout_truncated": capture.truncated,
+            "stdout_inline_truncated": capture.inline_truncated,
+            "stdout_ref": stdout_ref,
+            "expect": steps,
+            "expect_failed": expect_failed,
+        }))
+    }
+}
@@ -0,0 +1,197 @@
+use crate::errors::ToolError;
+use crate::utils::fs_atomic::atomic_write_text_file;
+use crate::utils::paths::resolve_pipeline_checkpoints_dir;
+use crate::utils::safe_name::ensure_safe_name;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+// Completed source files of a multi-file run, keyed by remote path. A file counts as done while
+// its size and mtime match what was recorded, so a re-dropped file of the same name runs again.
+pub(super) struct FileCheckpoint {
+    name: String,
+    path: PathBuf,
+    flow: String,
+    files: serde_json::Map<String, Value>,
+}
+
+// The stored document of checkpoint `name` (None when it does not exist yet), refused when it
+// belongs to another flow.
+fn read_stored(name: &str, flow: &str) -> Result<(PathBuf, Option<Value>), ToolError> {
+    let name = ensure_safe_name(name, "checkpoint")?;
+    let path = resolve_pipeline_checkpoints_dir().join(format!("{}.json", name));
+    let raw = match std::fs::read_to_string(&path) {
+        Ok(raw) => raw,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok((path, None)),
+        Err(err) => return Err(ToolError::internal(err.to_string())),
+    };
+    let stored: Value = serde_json::from_str(&raw).map_err(|err| {
+        ToolError::internal(format!("checkpoint {} is unreadable: {}", name, err))
+    })?;
+    let stored_flow = stored.get("flow").and_then(|v| v.as_str()).unwrap_or("");
+    if stored_flow != flow {
+        return Err(ToolError::conflict(format!(
+            "checkpoint {} belongs to flow {}",
+            name, stored_flow
+        ))
+        .with_hint("Use a separate checkpoint name per flow."));
+    }
+    Ok((path, Some(stored)))
+}
+
+fn write_stored(path: &Path, content: &Value) -> Result<(), ToolError> {
+    atomic_write_text_file(path, &format!("{:#}\n", content), 0o600)
+        .map_err(|err| ToolError::internal(format!("checkpoint write failed: {}", err)))
+}
+
+impl FileCheckpoint {
+    pub(super) fn load(name: &str, flow: &str) -> Result<Self, ToolError> {
+        let (path, stored) = read_stored(name, flow)?;
+        let files = stored
+            .as_ref()
+            .and_then(|stored| stored.get("files"))
+            .and_then(|v| v.as_object())
+            .cloned()
+            .unwrap_or_default();
+        Ok(Self {
+            name: name.to_string(),
+            path,
+            flow: flow.to_string(),
+            files,
+        })
+    }
+
+    pub(super) fn is_done(&self, path: &str, size: Option<u64>, mtime: Option<u64>) -> bool {
+        self.files.get(path).is_some_and(|entry| {
+            entry.get("size").and_then(|v| v.as_u64()) == size
+                && entry.get("mtime").and_then(|v| v.as_u64()) == mtime
+        })
+    }
+
+    pub(super) fn record(
+        &mut self,
+        path: &str,
+        size: Option<u64>,
+        mtime: Option<u64>,
+    ) -> Result<(), ToolError> {
+        self.files.insert(
+            path.to_string(),
+            serde_json::json!({
+                "size": size,
+                "mtime": mtime,
+                "completed_at": chrono::Utc::now().to_rfc3339(),
+            }),
+        );
+        let content = serde_json::json!({
+            "checkpoint": self.name,
+            "flow": self.flow,
+            "files": self.files,
+        });
+        write_stored(&self.path, &content)
+    }
+
+    pub(super) fn summary(&self) -> Value {
+        serde_json::json!({
+            "name": self.name,
+            "path": self.path.display().to_string(),
+            "completed": self.files.len(),
+        })
+    }
+}
+
+// A fan_out run: the staged source (kept next to the checkpoint as <name>.source until every
+// sink has succeeded) and each sink's last outcome, keyed by sink name.
+pub(super) struct FanOutCheckpoint {
+    name: String,
+    path: PathBuf,
+    source: Option<Value>,
+    sinks: serde_json::Map<String, Value>,
+}
+
+impl FanOutCheckpoint {
+    pub(super) fn load(name: &str) -> Result<Self, ToolError> {
+        let (path, stored) = read_stored(name, "fan_out")?;
+        let stored = stored.unwrap_or(Value::Null);
+        Ok(Self {
+            name: name.to_string(),
+            path,
+            source: stored.get("source").filter(|v| v.is_object()).cloned(),
+            sinks: stored
+                .get("sinks")
+                .and_then(|v| v.as_object())
+                .cloned()
+                .unwrap_or_default(),
+        })
+    }
+
+    pub(super) fn staged_path(&self) -> PathBuf {
+        self.path.with_extension("source")
+    }
+
+    // (bytes, sha256) of the source a previous run staged, if it is still on disk.
+    pub(super) fn staged_source(&self) -> Option<(u64, String)> {
+        let source = self.source.as_ref()?;
+        let bytes = source.get("bytes")?.as_u64()?;
+        let sha256 = source.get("sha256")?.as_str()?.to_string();
+        let on_disk = std::fs::metadata(self.staged_path()).ok()?.len();
+        (on_disk == bytes).then_some((bytes, sha256))
+    }
+
+    pub(super) fn is_done(&self, sink: &str) -> bool {
+        self.sinks
+            .get(sink)
+            .and_then(|entry| entry.get("status"))
+            .and_then(|v| v.as_str())
+            == Some("ok")
+    }
+
+    pub(super) fn record_source(&mut self, bytes: u64, sha256: &str) -> Result<(), ToolError> {
+        self.source = Some(serde_json::json!({
+            "bytes": bytes,
+            "sha256": sha256,
+            "staged_at": chrono::Utc::now().to_rfc3339(),
+        }));
+        self.save()
+    }
+
+    pub(super) fn record_sink(&mut self, sink: &str, error: Option<&str>) -> Result<(), ToolError> {
+        self.sinks.insert(
+            sink.to_string(),
+            serde_json::json!({
+                "status": if error.is_some() { "failed" } else { "ok" },
+                "error": error,
+                "at": chrono::Utc::now().to_rfc3339(),
+            }),
+        );
+        self.save()
+    }
+
+    // Once every sink is done the staged copy is no longer needed.
+    pub(super) fn release_source(&mut self) -> Result<(), ToolError> {
+        match std::fs::remove_file(self.staged_path()) {
+            Ok(()) => {}
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => return Err(ToolError::internal(err.to_string())),
+        }
+        self.source = None;
+        self.save()
+    }
+
+    fn save(&self) -> Result<(), ToolError> {
+        let content = serde_json::json!({
+            "checkpoint": self.name,
+            "flow": "fan_out",
+            "source": self.source,
+            "sinks": self.sinks,
+        });
+        write_stored(&self.path, &content)
+    }
+
+    pub(super) fn summary(&self) -> Value {
+        serde_json::json!({
+            "name": self.name,
+            "path": self.path.display().to_string(),
+            "staged_source": self.source.as_ref().map(|_| self.staged_path().display().to_string()),
+            "completed": self.sinks.keys().filter(|name| self.is_done(name)).collect::<Vec<_>>(),
+        })
+    }
+}
@@ -0,0 +1,635 @@
+use super::Trace;
+use crate::errors::ToolError;
+use crate::managers::api::{map_reqwest_error, RequestConfig, RetryPolicy};
+use crate::utils::redact::redact_text;
+use crate::utils::usage::{self, Counter};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde_json::Value;
+
+const CHUNK_INDEX_HEADER: &str = "x-chunk-index";
+const CHUNK_TOTAL_HEADER: &str = "x-chunk-total";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum ChunkFormat {
+    Ndjson,
+    JsonArray,
+}
+
+impl ChunkFormat {
+    fn name(self) -> &'static str {
+        match self {
+            ChunkFormat::Ndjson => "ndjson",
+            ChunkFormat::JsonArray => "json",
+        }
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            ChunkFormat::Ndjson => "application/x-ndjson",
+            ChunkFormat::JsonArray => "application/json",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(super) struct Finalize {
+    path: String,
+    method: String,
+    body: Option<Value>,
+}
+
+#[derive(Clone, Debug)]
+pub(super) struct ChunkedUpload {
+    rows: u64,
+    format: ChunkFormat,
+    headers: bool,
+    resume_from: u64,
+    finalize: Option<Finalize>,
+}
+
+// chunk_rows enables chunked mode; chunk_format, chunk_headers, resume_from_chunk and finalize tune it.
+pub(super) fn parse_chunked_upload(args: &Value) -> Result<Option<ChunkedUpload>, ToolError> {
+    let Some(rows) = super::util::read_positive_int(args.get("chunk_rows")) else {
+        for key in [
+            "chunk_format",
+            "chunk_headers",
+            "resume_from_chunk",
+            "finalize",
+        ] {
+            if args.get(key).is_some_and(|v| !v.is_null()) {
+                return Err(
+                    ToolError::invalid_params(format!("{} requires chunk_rows", key))
+                        .with_hint("Set chunk_rows to upload the export in chunks.".to_string()),
+                );
+            }
+        }
+        return Ok(None);
+    };
+    let format = match args
+        .get("chunk_format")
+        .and_then(|v| v.as_str())
+        .map(|s| s.trim().to_lowercase())
+        .as_deref()
+    {
+        None | Some("ndjson") | Some("jsonl") => ChunkFormat::Ndjson,
+        Some("json") | Some("json_array") => ChunkFormat::JsonArray,
+        Some(_) => {
+            return Err(ToolError::invalid_params(
+                "chunk_format must be ndjson or json",
+            ))
+        }
+    };
+    if args
+        .get("format")
+        .and_then(|v| v.as_str())
+        .is_some_and(|f| f.trim().eq_ignore_ascii_case("csv"))
+    {
+        return Err(
+            ToolError::invalid_params("chunked uploads do not support format=csv")
+                .with_hint("Use chunk_format (ndjson or json) instead of format.".to_string()),
+        );
+    }
+    let resume_from = match args.get("resume_from_chunk").filter(|v| !v.is_null()) {
+        None => 0,
+        Some(value) => value.as_u64().ok_or_else(|| {
+            ToolError::invalid_params("resume_from_chunk must be a non-negative integer")
+        })?,
+    };
+    let finalize = match args.get("finalize").filter(|v| !v.is_null()) {
+        None => None,
+        Some(Value::Object(map)) => {
+            let path = map
+                .get("path")
+                .and_then(|v| v.as_str())
+                .map(|s| s.trim())
+                .filter(|s| !s.is_empty())
+                .ok_or_else(|| ToolError::invalid_params("finalize.path is required"))?;
+            let method = map
+                .get("method")
+                .and_then(|v| v.as_str())
+                .map(|s| s.trim().to_uppercase())
+                .filter(|s| !s.is_empty())
+                .unwrap_or_else(|| "POST".to_string());
+            Some(Finalize {
+                path: path.to_string(),
+                method,
+                body: map.get("body").filter(|v| !v.is_null()).cloned(),
+            })
+        }
+        Some(_) => return Err(ToolError::invalid_params("finalize must be an object")),
+    };
+    Ok(Some(ChunkedUpload {
+        rows,
+        format,
+        headers: args
+            .get("chunk_headers")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false),
+        resume_from,
+        finalize,
+    }))
+}
+
+fn serialize_chunk(rows: &[Value], format: ChunkFormat) -> Vec<u8> {
+    match format {
+        ChunkFormat::Ndjson => {
+            let mut out = Vec::new();
+            for row in rows {
+                out.extend_from_slice(row.to_string().as_bytes());
+                out.push(b'\n');
+            }
+            out
+        }
+        ChunkFormat::JsonArray => Value::Array(rows.to_vec()).to_string().into_bytes(),
+    }
+}
+
+pub(super) struct Delivery {
+    pub(super) status: u64,
+    pub(super) response: String,
+    pub(super) attempts: usize,
+}
+
+#[derive(Default)]
+struct Progress {
+    delivered: u64,
+    last_delivered: Option<u64>,
+    rows: u64,
+    bytes: u64,
+    retries: u64,
+    last_status: Option<u64>,
+}
+
+impl super::PipelineManager {
+    // Fetches one page per chunk and only reads the next page after the previous chunk was
+    // accepted, so a slow sink throttles the export instead of buffering the whole result.
+    pub(super) async fn upload_postgres_chunks(
+        &self,
+        spec: &ChunkedUpload,
+        hydrated: &Value,
+        trace: &Trace,
+    ) -> Result<Value, ToolError> {
+        let started = std::time::Instant::now();
+        let http_cfg = hydrated.get("http").unwrap_or(&Value::Null);
+        if !http_cfg.is_object() {
+            return Err(ToolError::invalid_params("http config is required"));
+        }
+        let mut http_args = http_cfg.as_object().cloned().unwrap_or_default();
+        http_args
+            .entry("method".to_string())
+            .or_insert_with(|| Value::String("POST".to_string()));
+        for key in ["body", "data", "form", "body_base64", "body_type"] {
+            http_args.remove(key);
+        }
+        let user_headers = self.validation.ensure_headers(http_args.get("headers"))?;
+        let mut headers = user_headers.clone();
+        if !headers
+            .keys()
+            .any(|k| k.eq_ignore_ascii_case("content-type"))
+        {
+            headers.insert(
+                "Content-Type".to_string(),
+                Value::String(spec.format.content_type().to_string()),
+            );
+        }
+        http_args.insert("headers".to_string(), Value::Object(headers));
+        let http_value = Value::Object(http_args);
+
+        let (profile, auth) = self.resolve_http_profile(&http_value).await?;
+        let config =
+            self.api_manager
+                .build_request_config(&http_value, &profile, auth.as_ref(), None)?;
+        let policy = self.api_manager.normalize_retry_policy(
+            http_value.get("retry"),
+            profile.retry.as_ref(),
+            http_value.get("stability"),
+            profile.data.get("stability"),
+            http_value.get("method"),
+        );
+
+        let export_args = self.build_export_args(hydrated);
+        let base_offset = export_args
+            .get("offset")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0);
+        let limit = export_args.get("limit").and_then(|v| v.as_u64());
+        let chunks_total = if spec.headers {
+            Some(
+                self.count_chunks(&export_args, base_offset, limit, spec.rows)
+                    .await?,
+            )
+        } else {
+            None
+        };
+
+        self.audit_stage(
+            "postgres_export",
+            trace,
+            serde_json::json!({
+                "table": export_args.get("table"),
+                "schema": export_args.get("schema"),
+                "format": spec.format.name(),
+                "chunk_rows": spec.rows,
+                "resume_from_chunk": spec.resume_from,
+            }),
+            None,
+        );
+
+        let mut progress = Progress::default();
+        let mut index = spec.resume_from;
+        // Keyset pages follow the table key; a resumed upload seeks its first page by offset.
+        let mut keyset = match self.postgres_manager.source_keyset(&export_args).await {
+            Ok(keyset) => keyset,
+            Err(err) => {
+                return Ok(self.chunk_failure(
+                    spec, &config, &progress, index, "fetch", None, &err, started, trace,
+                ))
+            }
+        };
+        loop {
+            let sent_rows = index.saturating_mul(spec.rows);
+            let page = match limit {
+                Some(limit) => spec.rows.min(limit.saturating_sub(sent_rows)),
+                None => spec.rows,
+            };
+            if page == 0 || chunks_total.is_some_and(|total| index >= total) {
+                break;
+            }
+
+            let mut select = export_args.as_object().cloned().unwrap_or_default();
+            select.insert("action".to_string(), Value::String("select".to_string()));
+            select.insert("mode".to_string(), Value::String("rows".to_string()));
+            match keyset.as_mut() {
+                Some(keyset) => {
+                    keyset.page_size = page as usize;
+                    for key in ["order_by", "order_by_sql", "limit", "offset"] {
+                        select.remove(key);
+                    }
+                    let mut paginate = keyset.to_value();
+                    match &keyset.after {
+                        Some(after) => paginate["after"] = Value::Array(after.clone()),
+                        None => {
+                            select
+                                .insert("offset".to_string(), Value::from(base_offset + sent_rows));
+                        }
+                    }
+                    select.insert("paginate".to_string(), paginate);
+                }
+                None => {
+                    select.insert("limit".to_string(), Value::from(page));
+                    select.insert("offset".to_string(), Value::from(base_offset + sent_rows));
+                }
+            }
+            let rows = match self
+                .postgres_manager
+                .handle_action(Value::Object(select))
+                .await
+            {
+                Ok(result) => result
+                    .get("result")
+                    .and_then(|v| v.get("rows"))
+                    .and_then(|v| v.as_array())
+                    .cloned()
+                    .unwrap_or_default(),
+                Err(err) => {
+                    return Ok(self.chunk_failure(
+                        spec, &config, &progress, index, "fetch", None, &err, started, trace,
+                    ))
+                }
+            };
+            if rows.is_empty() {
+                break;
+            }
+            if let Some(keyset) = keyset.as_mut() {
+                match keyset.cursor(&rows[rows.len() - 1]) {
+                    Ok(cursor) => keyset.after = Some(cursor),
+                    Err(err) => {
+                        return Ok(self.chunk_failure(
+                            spec, &config, &progress, index, "fetch", None, &err, started, trace,
+                        ))
+                    }
+                }
+            }
+
+            let body = serialize_chunk(&rows, spec.format);
+            let mut extra = HeaderMap::new();
+            if spec.headers {
+                extra.insert(
+                    HeaderName::from_static(CHUNK_INDEX_HEADER),
+                    HeaderValue::from(index),
+                );
+                if let Some(total) = chunks_total {
+                    extra.insert(
+                        HeaderName::from_static(CHUNK_TOTAL_HEADER),
+                        HeaderValue::from(total),
+                    );
+                }
+            }
+            let delivery = self
+                .deliver_chunk(&config, extra, body.clone(), &policy)
+                .await;
+            match delivery {
+                Ok(delivery) if (200..300).contains(&delivery.status) => {
+                    progress.delivered += 1;
+                    progress.last_delivered = Some(index);
+                    progress.rows += rows.len() as u64;
+                    progress.bytes += body.len() as u64;
+                    progress.retries += delivery.attempts.saturating_sub(1) as u64;
+                    progress.last_status = Some(delivery.status);
+                }
+                Ok(delivery) => {
+                    progress.retries += delivery.attempts.saturating_sub(1) as u64;
+                    let err = ToolError::invalid_params(format!(
+                        "HTTP sink rejected chunk {} ({})",
+                        index, delivery.status
+                    ))
+                    .with_details(serde_json::json!({
+                        "response": redact_text(&delivery.response, 4096, None),
+                    }));
+                    return Ok(self.chunk_failure(
+                        spec,
+                        &config,
+                        &progress,
+                        index,
+                        "upload",
+                        Some(delivery.status),
+                        &err,
+                        started,
+                        trace,
+                    ));
+                }
+                Err((err, attempts)) => {
+                    progress.retries += attempts.saturating_sub(1) as u64;
+                    return Ok(self.chunk_failure(
+                        spec, &config, &progress, index, "upload", None, &err, started, trace,
+                    ));
+                }
+            }
+            index += 1;
+            if (rows.len() as u64) < page {
+                break;
+            }
+        }
+
+        let mut finalize_result = Value::Null;
+        if let Some(finalize) = spec.finalize.as_ref() {
+            let mut finalize_args = http_value.as_object().cloned().unwrap_or_default();
+            let url = reqwest::Url::parse(&config.url)
+                .and_then(|base| base.join(&finalize.path))
+                .map_err(|_| ToolError::invalid_params("finalize.path is not a valid path"))?;
+            finalize_args.insert("url".to_string(), Value::String(url.to_string()));
+            finalize_args.insert("method".to_string(), Value::String(finalize.method.clone()));
+            finalize_args.insert("headers".to_string(), Value::Object(user_headers));
+            if let Some(body) = finalize.body.as_ref() {
+                finalize_args.insert("body".to_string(), body.clone());
+            }
+            let finalize_config = self.api_manager.build_request_config(
+                &Value::Object(finalize_args),
+                &profile,
+                auth.as_ref(),
+                None,
+            )?;
+            let body = finalize_body(&finalize_config);
+            let mut extra = HeaderMap::new();
+            if let Some(total) = chunks_total.filter(|_| spec.headers) {
+                extra.insert(
+                    HeaderName::from_static(CHUNK_TOTAL_HEADER),
+                    HeaderValue::from(total),
+                );
+            }
+            let outcome = self
+                .deliver_chunk(&finalize_config, extra, body, &policy)
+                .await;
+            let (status, response, failure) = match outcome {
+                Ok(delivery) => {
+                    progress.retries += delivery.attempts.saturating_sub(1) as u64;
+                    let failure = (!(200..300).contains(&delivery.status)).then(|| {
+                        ToolError::invalid_params(format!(
+                            "HTTP sink rejected finalize ({})",
+                            delivery.status
+                        ))
+                    });
+                    (Some(delivery.status), delivery.response, failure)
+                }
+                Err((err, attempts)) => {
+                    progress.retries += attempts.saturating_sub(1) as u64;
+                    (None, String::new(), Some(err))
+                }
+            };
+            finalize_result = serde_json::json!({
+                "url": finalize_config.url,
+                "method": finalize_config.method.as_str(),
+                "status": status,
+                "response": response,
+            });
+            if let Some(err) = failure {
+                let mut out = self.chunk_failure(
+                    spec, &config, &progress, index, "finalize", status, &err, started, trace,
+                );
+                out["finalize"] = finalize_result;
+                return Ok(out);
+            }
+        }
+
+        self.audit_stage(
+            "http_upload",
+            trace,
+            serde_json::json!({
+                "url": config.url,
+                "status": progress.last_status,
+                "chunks": progress.delivered,
+                "rows": progress.rows,
+                "bytes": progress.bytes,
+            }),
+            None,
+        );
+        let mut out = summarize(spec, &config, &progress, index, chunks_total, started);
+        out["success"] = Value::Bool(true);
+        out["finalize"] = finalize_result;
+        Ok(out)
+    }
+
+    async fn count_chunks(
+        &self,
+        export_args: &Value,
+        base_offset: u64,
+        limit: Option<u64>,
+        chunk_rows: u64,
+    ) -> Result<u64, ToolError> {
+        let mut count_args = export_args.as_object().cloned().unwrap_or_default();
+        count_args.insert("action".to_string(), Value::String("count".to_string()));
+        let counted = self
+            .postgres_manager
+            .handle_action(Value::Object(count_args))
+            .await?;
+        let total = counted.get("count").and_then(|v| v.as_u64()).unwrap_or(0);
+        let mut rows = total.saturating_sub(base_offset);
+        if let Some(limit) = limit {
+            rows = rows.min(limit);
+        }
+        Ok(rows.div_ceil(chunk_rows))
+    }
+
+    // Sends one body with the api retry policy; Err carries the attempts spent before giving up.
+    pub(super) async fn deliver_chunk(
+        &self,
+        config: &RequestConfig,
+        extra_headers: HeaderMap,
+        body: Vec<u8>,
+        policy: &RetryPolicy,
+    ) -> Result<Delivery, (ToolError, usize)> {
+        let max_attempts = if policy.enabled {
+            policy.max_attempts.max(1)
+        } else {
+            1
+        };
+        let client = self
+            .api_manager
+            .get_client(
+                config.follow_redirects,
+                config.insecure_ok,
+                config.tls.as_ref(),
+                config.ssrf.as_ref(),
+                config.proxy.as_ref(),
+                config.tuning.as_ref(),
+            )
+            .map_err(|err| (err, 0))?;
+        let mut headers = config.headers.clone();
+        headers.extend(extra_headers);
+
+        let mut attempt = 0usize;
+        loop {
+            attempt += 1;
+            usage::record(Counter::HttpBodySentBytes, body.len() as u64);
+            let mut req = client
+                .request(config.method.clone(), config.url.clone())
+                .headers(headers.clone())
+                .body(body.clone());
+            if let Some(timeout_ms) = config.timeout_ms {
+                req = req.timeout(std::time::Duration::from_millis(timeout_ms));
+            }
+            match req.send().await.map_err(map_reqwest_error) {
+                Ok(response) => {
+                    let status = response.status().as_u16() as u64;
+                    let summary = serde_json::json!({
+                        "status": status,
+                        "headers": response
+                            .headers()
+                            .iter()
+                            .filter_map(|(k, v)| {
+                                v.to_str()
+                                    .ok()
+                                    .map(|val| (k.to_string(), Value::String(val.to_string())))
+                            })
+                            .collect::<serde_json::Map<_, _>>(),
+                    });
+                    let response_text = response.text().await.unwrap_or_default();
+                    if !self.api_manager.should_retry_response(&summary, policy)
+                        || attempt >= max_attempts
+                    {
+                        return Ok(Delivery {
+                            status,
+                            response: response_text,
+                            attempts: attempt,
+                        });
+                    }
+                    let delay =
+                        self.api_manager
+                            .compute_retry_delay(attempt, policy, Some(&summary));
+                    tokio::time::sleep(std::time::Duration::from_millis(delay)).await;
+                }
+                Err(err) => {
+                    if !policy.retry_on_network_error || attempt >= max_attempts {
+                        return Err((err, attempt));
+                    }
+                    let delay = self.api_manager.compute_retry_delay(attempt, policy, None);
+                    tokio::time::sleep(std::time::Duration::from_millis(delay)).await;
+                }
+            }
+        }
+    }
+
+    // The failure keeps everything a resume needs: the last chunk the sink accepted and the
+    // resume_from_chunk value that skips it.
+    #[allow(clippy::too_many_arguments)]
+    fn chunk_failure(
+        &self,
+        spec: &ChunkedUpload,
+        config: &RequestConfig,
+        progress: &Progress,
+        index: u64,
+        stage: &str,
+        status: Option<u64>,
+        err: &ToolError,
+        started: std::time::Instant,
+        trace: &Trace,
+    ) -> Value {
+        self.audit_stage(
+            "http_upload",
+            trace,
+            serde_json::json!({
+                "url": config.url,
+                "stage": stage,
+                "chunk": index,
+                "status": status,
+                "last_delivered_chunk": progress.last_delivered,
+            }),
+            Some(err),
+        );
+        let mut out = summarize(spec, config, progress, index, None, started);
+        out["success"] = Value::Bool(false);
+        out["failed"] = serde_json::json!({
+            "stage": stage,
+            "chunk": index,
+            "status": status,
+            "error": {
+                "kind": err.kind,
+                "code": err.code,
+                "message": redact_text(&err.message, 2048, None),
+                "details": err.details.clone().unwrap_or(Value::Null),
+            },
+        });
+        out
+    }
+}
+
+fn finalize_body(config: &RequestConfig) -> Vec<u8> {
+    config
+        .body
+        .as_ref()
+        .and_then(|body| body.as_bytes())
+        .map(|bytes| bytes.to_vec())
+        .unwrap_or_default()
+}
+
+fn summarize(
+    spec: &ChunkedUpload,
+    config: &RequestConfig,
+    progress: &Progress,
+    next_index: u64,
+    chunks_total: Option<u64>,
+    started: std::time::Instant,
+) -> Value {
+    serde_json::json!({
+        "flow": "postgres_to_http",
+        "chunked": true,
+        "http": {
+            "url": config.url,
+            "method": config.method.as_str(),
+            "status": progress.last_status,
+        },
+        "chunks": {
+            "rows_per_chunk": spec.rows,
+            "format": spec.format.name(),
+            "delivered": progress.delivered,
+            "skipped": spec.resume_from,
+            "total": chunks_total,
+            "last_delivered": progress.last_delivered,
+            "resume_from_chunk": next_index,
+        },
+        "rows": progress.rows,
+        "bytes": progress.bytes,
+        "retries": progress.retries,
+        "duration_ms": started.elapsed().as_millis() as u64,
+    })
+}
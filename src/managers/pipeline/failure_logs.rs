@@ -0,0 +1,195 @@
+use super::Trace;
+use crate::errors::ToolError;
+use crate::utils::artifacts::{
+    build_tool_call_file_ref, resolve_context_root, write_text_artifact,
+};
+use crate::utils::redact::redact_text;
+use crate::utils::text::truncate_utf8_suffix;
+use serde_json::Value;
+
+const DEFAULT_LOG_LINES: u64 = 200;
+const MAX_LOG_LINES: u64 = 5_000;
+const MAX_INLINE_LOG_BYTES: usize = 8 * 1024;
+const LOG_TIMEOUT_MS: u64 = 30_000;
+
+#[derive(Clone, Debug)]
+pub(super) struct CollectLogs {
+    command: String,
+    lines: u64,
+}
+
+fn is_unit_name(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '@' | '.' | '_' | ':' | '-'))
+}
+
+// on_failure: { collect_logs: { command | journalctl_unit, lines } }
+pub(super) fn parse_collect_logs(args: &Value) -> Result<Option<CollectLogs>, ToolError> {
+    let Some(on_failure) = args.get("on_failure").filter(|v| !v.is_null()) else {
+        return Ok(None);
+    };
+    let Some(spec) = on_failure.get("collect_logs").filter(|v| !v.is_null()) else {
+        return Ok(None);
+    };
+    let Some(spec) = spec.as_object() else {
+        return Err(ToolError::invalid_params(
+            "on_failure.collect_logs must be an object",
+        ));
+    };
+    let lines = super::util::read_positive_int(spec.get("lines"))
+        .unwrap_or(DEFAULT_LOG_LINES)
+        .min(MAX_LOG_LINES);
+    let command = spec
+        .get("command")
+        .and_then(|v| v.as_str())
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty());
+    let unit = spec
+        .get("journalctl_unit")
+        .and_then(|v| v.as_str())
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty());
+    let command = match (command, unit) {
+        (Some(_), Some(_)) => {
+            return Err(ToolError::invalid_params(
+                "on_failure.collect_logs accepts either command or journalctl_unit, not both",
+            ))
+        }
+        (Some(command), None) => command.to_string(),
+        (None, Some(unit)) => {
+            if !is_unit_name(unit) {
+                return Err(ToolError::invalid_params(
+                    "on_failure.collect_logs.journalctl_unit must be a plain unit name",
+                )
+                .with_hint(
+                    "Use a command for anything beyond `journalctl -u <unit>`.".to_string(),
+                ));
+            }
+            format!("journalctl -u {} -n {} --no-pager", unit, lines)
+        }
+        (None, None) => {
+            return Err(ToolError::invalid_params(
+                "on_failure.collect_logs requires command or journalctl_unit",
+            ))
+        }
+    };
+    Ok(Some(CollectLogs { command, lines }))
+}
+
+fn last_lines(text: &str, lines: u64) -> &str {
+    let mut seen = 0;
+    for (idx, ch) in text.trim_end_matches('\n').char_indices().rev() {
+        if ch == '\n' {
+            seen += 1;
+            if seen >= lines {
+                return &text[idx + 1..];
+            }
+        }
+    }
+    text
+}
+
+impl super::PipelineManager {
+    // Runs the log collection command in its own child span. A failure here is reported inside
+    // failure_logs and never replaces the smoke failure it was collected for.
+    pub(super) async fn collect_failure_logs(
+        &self,
+        spec: &CollectLogs,
+        args: &Value,
+        trace: &Trace,
+    ) -> Value {
+        let span = trace.child();
+        let started = chrono::Utc::now().timestamp_millis();
+        let mut exec_args = serde_json::json!({
+            "action": "exec",
+            "command": spec.command,
+            "timeout_ms": LOG_TIMEOUT_MS,
+        });
+        for key in [
+            "profile_name",
+            "connection",
+            "project",
+            "project_name",
+            "target",
+            "project_target",
+            "environment",
+            "vault_profile_name",
+            "vault_profile",
+        ] {
+            if let Some(value) = args.get(key).filter(|v| !v.is_null()) {
+                exec_args[key] = value.clone();
+            }
+        }
+        let outcome = self.ssh_manager.handle_action(span.apply(exec_args)).await;
+        self.audit_span(&span, "ssh", "exec", started, &outcome);
+
+        let exec = match outcome {
+            Ok(exec) => exec,
+            Err(err) => {
+                return serde_json::json!({
+                    "success": false,
+                    "command": spec.command,
+                    "span_id": span.span_id,
+                    "error": {
+                        "kind": err.kind,
+                        "code": err.code,
+                        "message": redact_text(&err.message, 2048, None),
+                    },
+                });
+            }
+        };
+
+        let stdout = exec.get("stdout").and_then(|v| v.as_str()).unwrap_or("");
+        let stderr = exec.get("stderr").and_then(|v| v.as_str()).unwrap_or("");
+        let combined = if stderr.is_empty() {
+            stdout.to_string()
+        } else if stdout.is_empty() {
+            stderr.to_string()
+        } else {
+            format!("{}\n{}", stdout.trim_end_matches('\n'), stderr)
+        };
+        let output = redact_text(last_lines(&combined, spec.lines), usize::MAX, None);
+        let inline = truncate_utf8_suffix(&output, MAX_INLINE_LOG_BYTES);
+        let truncated = inline.len() < output.len();
+
+        let mut artifact = Value::Null;
+        if let Some(context_root) = resolve_context_root() {
+            let written = build_tool_call_file_ref(
+                Some(&trace.trace_id),
+                Some(&span.span_id),
+                "failure_logs.txt",
+            )
+            .and_then(|reference| write_text_artifact(&context_root, &reference, &output));
+            match written {
+                Ok(written) => {
+                    artifact = serde_json::json!({
+                        "uri": written.uri,
+                        "rel": written.rel,
+                        "bytes": written.bytes,
+                    });
+                }
+                Err(err) => {
+                    self.logger.warn(
+                        "Failed to write failure logs artifact",
+                        Some(&serde_json::json!({ "error": err.message })),
+                    );
+                }
+            }
+        }
+
+        let exit_code = exec.get("exitCode").cloned().unwrap_or(Value::Null);
+        serde_json::json!({
+            "success": exec.get("success").and_then(|v| v.as_bool()).unwrap_or(false),
+            "command": spec.command,
+            "span_id": span.span_id,
+            "exit_code": exit_code,
+            "lines": spec.lines,
+            "output": inline,
+            "bytes": output.len(),
+            "truncated": truncated,
+            "artifact": artifact,
+        })
+    }
+}
@@ -0,0 +1,711 @@
+use super::checkpoint::FanOutCheckpoint;
+use super::Trace;
+use crate::errors::ToolError;
+use reqwest::header::HeaderMap;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt, DuplexStream};
+use tokio::task::JoinHandle;
+
+// `flow: "fan_out"` fetches one source once and feeds it to several sinks:
+// { source: { type: http|sftp|postgres, ... }, sinks: [{ type: postgres|sftp|http, name?, ... }] }.
+// The source is staged in memory up to stage_memory_bytes and in a temp file beyond that; with a
+// checkpoint it is always staged next to the checkpoint so a rerun can retry failed sinks alone.
+
+const SOURCE_TYPES: &[&str] = &["http", "sftp", "postgres"];
+const SINK_TYPES: &[&str] = &["postgres", "sftp", "http"];
+const MAX_SINKS: usize = 16;
+const DEFAULT_STAGE_MEMORY_BYTES: u64 = 16 * 1024 * 1024;
+const COPY_BUFFER_BYTES: usize = 64 * 1024;
+// Row-parsing options a postgres sink may set for itself; the rest of the block goes to insert_bulk.
+const POSTGRES_SINK_OPTIONS: &[&str] = &[
+    "format",
+    "batch_size",
+    "max_rows",
+    "csv_header",
+    "csv_delimiter",
+];
+
+pub(super) const EXAMPLE: &str = r#"{"action":"run","flow":"fan_out","source":{"type":"http","url":"https://example.com/events.jsonl"},"sinks":[{"type":"postgres","name":"warehouse","profile_name":"analytics","table":"events"},{"type":"sftp","name":"archive","profile_name":"archive-1","remote_path":"/archive/events.jsonl","overwrite":true}],"parallel":true,"checkpoint":"events-daily"}"#;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Require {
+    All,
+    Any,
+}
+
+struct SinkSpec {
+    name: String,
+    kind: String,
+    config: Value,
+}
+
+pub(super) struct FanOutSpec {
+    source_kind: String,
+    source: Value,
+    sinks: Vec<SinkSpec>,
+    parallel: bool,
+    require: Require,
+    checkpoint: Option<String>,
+    stage_memory_bytes: u64,
+}
+
+fn block_error(message: String) -> ToolError {
+    ToolError::invalid_params(message).with_hint(format!("Example: {}", EXAMPLE))
+}
+
+fn present(block: &Value, field: &str) -> bool {
+    block
+        .get(field)
+        .is_some_and(|v| !v.is_null() && !v.as_str().is_some_and(|s| s.trim().is_empty()))
+}
+
+// The block without its fan_out keys, as the underlying tool expects it.
+fn strip_keys(block: &serde_json::Map<String, Value>, keys: &[&str]) -> Value {
+    let mut out = block.clone();
+    for key in keys {
+        out.remove(*key);
+    }
+    Value::Object(out)
+}
+
+pub(super) fn parse_fan_out(args: &Value) -> Result<FanOutSpec, ToolError> {
+    let source = args
+        .get("source")
+        .and_then(|v| v.as_object())
+        .ok_or_else(|| block_error("fan_out requires a `source` object".to_string()))?;
+    let source_kind = source
+        .get("type")
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .trim()
+        .to_lowercase();
+    if !SOURCE_TYPES.contains(&source_kind.as_str()) {
+        return Err(block_error(format!(
+            "source.type must be one of: {}",
+            SOURCE_TYPES.join(", ")
+        )));
+    }
+    let source_value = Value::Object(source.clone());
+    match source_kind.as_str() {
+        "sftp" if present(&source_value, "remote_glob") => {
+            return Err(block_error(
+                "fan_out reads a single sftp source.remote_path, not remote_glob".to_string(),
+            ));
+        }
+        "sftp" if !present(&source_value, "remote_path") => {
+            return Err(block_error(
+                "source.remote_path is required for fan_out".to_string(),
+            ));
+        }
+        "postgres" if !present(&source_value, "table") => {
+            return Err(block_error(
+                "source.table is required for fan_out".to_string(),
+            ));
+        }
+        _ => {}
+    }
+
+    let raw_sinks = args
+        .get("sinks")
+        .and_then(|v| v.as_array())
+        .filter(|sinks| !sinks.is_empty())
+        .ok_or_else(|| block_error("fan_out requires a non-empty `sinks` array".to_string()))?;
+    if raw_sinks.len() > MAX_SINKS {
+        return Err(ToolError::invalid_params(format!(
+            "fan_out takes at most {} sinks",
+            MAX_SINKS
+        )));
+    }
+    let mut sinks: Vec<SinkSpec> = Vec::with_capacity(raw_sinks.len());
+    for (idx, raw) in raw_sinks.iter().enumerate() {
+        let Some(block) = raw.as_object() else {
+            return Err(block_error(format!("sinks[{}] must be an object", idx)));
+        };
+        let kind = block
+            .get("type")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .trim()
+            .to_lowercase();
+        if !SINK_TYPES.contains(&kind.as_str()) {
+            return Err(block_error(format!(
+                "sinks[{}].type must be one of: {}",
+                idx,
+                SINK_TYPES.join(", ")
+            )));
+        }
+        let required = match kind.as_str() {
+            "sftp" => Some("remote_path"),
+            "postgres" => Some("table"),
+            _ => None,
+        };
+        if let Some(field) = required.filter(|field| !present(raw, field)) {
+            return Err(block_error(format!(
+                "sinks[{}].{} is required for fan_out",
+                idx, field
+            )));
+        }
+        let name = match block.get("name").and_then(|v| v.as_str()) {
+            Some(name) if !name.trim().is_empty() => name.trim().to_string(),
+            _ => format!("{}-{}", kind, idx),
+        };
+        if sinks.iter().any(|sink| sink.name == name) {
+            return Err(
+                ToolError::invalid_params(format!("sink name '{}' is used twice", name))
+                    .with_hint("Give each sink a distinct name; checkpoints track sinks by name."),
+            );
+        }
+        sinks.push(SinkSpec {
+            name,
+            kind,
+            config: strip_keys(block, &["type", "name"]),
+        });
+    }
+
+    let require = match args.get("require").and_then(|v| v.as_str()) {
+        None | Some("all") => Require::All,
+        Some("any") => Require::Any,
+        Some(other) => {
+            return Err(ToolError::invalid_params(format!(
+                "require must be all or any (got {})",
+                other
+            )))
+        }
+    };
+    Ok(FanOutSpec {
+        source_kind,
+        source: strip_keys(source, &["type"]),
+        sinks,
+        parallel: args
+            .get("parallel")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false),
+        require,
+        checkpoint: args
+            .get("checkpoint")
+            .and_then(|v| v.as_str())
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty()),
+        stage_memory_bytes: super::util::read_positive_int(args.get("stage_memory_bytes"))
+            .unwrap_or(DEFAULT_STAGE_MEMORY_BYTES),
+    })
+}
+
+pub(super) fn describe() -> Value {
+    serde_json::json!({
+        "flow": "fan_out",
+        "summary": "Fetch one source once, stage it, and feed it to several sinks (sequentially, or concurrently with parallel=true); each sink reports on its own.",
+        "source": {
+            "block": "source",
+            "types": SOURCE_TYPES,
+            "required": { "sftp": ["remote_path"], "postgres": ["table"] },
+        },
+        "sinks": {
+            "block": "sinks",
+            "types": SINK_TYPES,
+            "required": { "sftp": ["remote_path"], "postgres": ["table"] },
+            "optional": ["name"],
+            "max": MAX_SINKS,
+        },
+        "options": ["parallel", "require", "checkpoint", "stage_memory_bytes", "format", "batch_size", "max_rows", "csv_header", "csv_delimiter", "cache"],
+        "example": serde_json::from_str::<Value>(EXAMPLE).unwrap_or(Value::Null),
+    })
+}
+
+enum StagedData {
+    Memory(Arc<Vec<u8>>),
+    File { path: PathBuf, temporary: bool },
+}
+
+// The fetched source. Sinks only ever read it, so a failed sink leaves it intact for the others
+// and for a checkpointed retry.
+struct Staged {
+    data: StagedData,
+    bytes: u64,
+    sha256: String,
+}
+
+impl Drop for Staged {
+    fn drop(&mut self) {
+        if let StagedData::File {
+            path,
+            temporary: true,
+        } = &self.data
+        {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+impl Staged {
+    fn location(&self) -> &'static str {
+        match self.data {
+            StagedData::Memory(_) => "memory",
+            StagedData::File { .. } => "file",
+        }
+    }
+
+    // A fresh reader over the staged bytes, fed by its own task.
+    fn open(&self) -> (DuplexStream, JoinHandle<Result<u64, ToolError>>) {
+        let (mut writer, reader) = tokio::io::duplex(COPY_BUFFER_BYTES);
+        let data = match &self.data {
+            StagedData::Memory(buf) => Ok(buf.clone()),
+            StagedData::File { path, .. } => Err(path.clone()),
+        };
+        let completion = tokio::spawn(async move {
+            let copied = match data {
+                Ok(buf) => writer.write_all(&buf).await.map(|_| buf.len() as u64),
+                Err(path) => match tokio::fs::File::open(&path).await {
+                    Ok(mut file) => tokio::io::copy(&mut file, &mut writer).await,
+                    Err(err) => Err(err),
+                },
+            };
+            let _ = writer.shutdown().await;
+            Ok(copied?)
+        });
+        (reader, completion)
+    }
+}
+
+async fn stage(
+    reader: &mut DuplexStream,
+    memory_limit: u64,
+    file: Option<PathBuf>,
+) -> Result<Staged, ToolError> {
+    let mut hasher = Sha256::new();
+    let mut memory: Vec<u8> = Vec::new();
+    let mut spilled: Option<(tokio::fs::File, PathBuf, bool)> = None;
+    if let Some(path) = file {
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        spilled = Some((tokio::fs::File::create(&path).await?, path, false));
+    }
+    let mut total = 0u64;
+    let mut buf = vec![0u8; COPY_BUFFER_BYTES];
+    loop {
+        let n = reader.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        total += n as u64;
+        if spilled.is_none() && total > memory_limit {
+            let path =
+                std::env::temp_dir().join(format!("infra-fan-out-{}.stage", uuid::Uuid::new_v4()));
+            let mut file = tokio::fs::File::create(&path).await?;
+            file.write_all(&memory).await?;
+            memory = Vec::new();
+            spilled = Some((file, path, true));
+        }
+        match spilled.as_mut() {
+            Some((file, _, _)) => file.write_all(&buf[..n]).await?,
+            None => memory.extend_from_slice(&buf[..n]),
+        }
+    }
+    let data = match spilled {
+        Some((mut file, path, temporary)) => {
+            file.flush().await?;
+            StagedData::File { path, temporary }
+        }
+        None => StagedData::Memory(Arc::new(memory)),
+    };
+    Ok(Staged {
+        data,
+        bytes: total,
+        sha256: hex::encode(hasher.finalize()),
+    })
+}
+
+async fn file_sha256(path: &PathBuf) -> Result<String, ToolError> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; COPY_BUFFER_BYTES];
+    loop {
+        let n = file.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+fn sink_failed(result: &Result<Value, ToolError>) -> Option<String> {
+    match result {
+        Err(err) => Some(format!("{}: {}", err.code, err.message)),
+        Ok(value) if value.get("success").and_then(|v| v.as_bool()) == Some(false) => Some(
+            value
+                .get("http")
+                .and_then(|http| http.get("status"))
+                .map(|status| format!("sink reported success=false (status {})", status))
+                .unwrap_or_else(|| "sink reported success=false".to_string()),
+        ),
+        Ok(_) => None,
+    }
+}
+
+impl super::PipelineManager {
+    pub(super) async fn fan_out(&self, args: &Value) -> Result<Value, ToolError> {
+        let started = std::time::Instant::now();
+        let spec = parse_fan_out(args)?;
+        let trace = self.build_trace(args);
+        let mut checkpoint = spec
+            .checkpoint
+            .as_deref()
+            .map(FanOutCheckpoint::load)
+            .transpose()?;
+
+        let pending: Vec<&SinkSpec> = spec
+            .sinks
+            .iter()
+            .filter(|sink| !checkpoint.as_ref().is_some_and(|cp| cp.is_done(&sink.name)))
+            .collect();
+        let (staged, source_report) = if pending.is_empty() {
+            (
+                None,
+                serde_json::json!({"type": spec.source_kind, "skipped": true}),
+            )
+        } else {
+            let (staged, report) = self
+                .stage_source(&spec, args, checkpoint.as_mut(), &trace)
+                .await?;
+            (Some(staged), report)
+        };
+
+        let checkpoint_lock = checkpoint.map(Mutex::new);
+        let mut reports: Vec<Value> = Vec::with_capacity(spec.sinks.len());
+        if let Some(staged) = staged.as_ref() {
+            let runs = pending
+                .iter()
+                .map(|sink| self.traced_sink(sink, staged, args, &trace, checkpoint_lock.as_ref()));
+            if spec.parallel {
+                reports = futures::future::join_all(runs).await;
+            } else {
+                for run in runs {
+                    reports.push(run.await);
+                }
+            }
+        }
+        // Report every sink in the order given, done ones included.
+        let mut sinks_out = Vec::with_capacity(spec.sinks.len());
+        let mut ran = reports.into_iter();
+        for sink in &spec.sinks {
+            if pending.iter().any(|p| p.name == sink.name) {
+                sinks_out.push(ran.next().unwrap_or(Value::Null));
+            } else {
+                sinks_out.push(serde_json::json!({
+                    "name": sink.name,
+                    "type": sink.kind,
+                    "status": "skipped",
+                    "reason": "completed in an earlier run of this checkpoint",
+                }));
+            }
+        }
+
+        let count = |status: &str| sinks_out.iter().filter(|s| s["status"] == status).count();
+        let (ok, failed, skipped) = (count("ok"), count("failed"), count("skipped"));
+        let success = match spec.require {
+            Require::All => failed == 0,
+            Require::Any => ok + skipped > 0,
+        };
+
+        let mut checkpoint =
+            checkpoint_lock.map(|lock| lock.into_inner().unwrap_or_else(|e| e.into_inner()));
+        if let Some(cp) = checkpoint.as_mut() {
+            if failed == 0 {
+                cp.release_source()?;
+            }
+        }
+        drop(staged);
+
+        self.audit_stage(
+            "fan_out",
+            &trace,
+            serde_json::json!({"source": spec.source_kind, "ok": ok, "failed": failed, "skipped": skipped}),
+            None,
+        );
+
+        let mut result = serde_json::json!({
+            "success": success,
+            "flow": "fan_out",
+            "require": if spec.require == Require::All { "all" } else { "any" },
+            "parallel": spec.parallel,
+            "source": source_report,
+            "sinks": sinks_out,
+            "summary": {
+                "sinks": spec.sinks.len(),
+                "ok": ok,
+                "failed": failed,
+                "skipped": skipped,
+            },
+            "checkpoint": checkpoint.as_ref().map(|cp| cp.summary()),
+            "duration_ms": started.elapsed().as_millis(),
+        });
+        if failed > 0 {
+            result["hint"] = Value::String(if spec.checkpoint.is_some() {
+                "Rerun with the same checkpoint to retry only the failed sinks from the staged source."
+                    .to_string()
+            } else {
+                "Set checkpoint to keep the staged source and retry only the failed sinks."
+                    .to_string()
+            });
+        }
+        Ok(result)
+    }
+
+    // Per-block project defaults, as the two-block flows get them.
+    async fn hydrate_block(
+        &self,
+        kind: &str,
+        block: &Value,
+        args: &Value,
+    ) -> Result<Value, ToolError> {
+        let mut wrapped = args.as_object().cloned().unwrap_or_default();
+        for key in ["source", "sinks", "http", "sftp", "postgres"] {
+            wrapped.remove(key);
+        }
+        wrapped.insert(kind.to_string(), block.clone());
+        let hydrated = self
+            .hydrate_project_defaults(&Value::Object(wrapped))
+            .await?;
+        Ok(hydrated.get(kind).cloned().unwrap_or_else(|| block.clone()))
+    }
+
+    async fn stage_source(
+        &self,
+        spec: &FanOutSpec,
+        args: &Value,
+        checkpoint: Option<&mut FanOutCheckpoint>,
+        trace: &Trace,
+    ) -> Result<(Staged, Value), ToolError> {
+        let staged_path = checkpoint.as_ref().map(|cp| cp.staged_path());
+        if let (Some(cp), Some(path)) = (checkpoint.as_ref(), staged_path.as_ref()) {
+            if let Some((bytes, sha256)) = cp.staged_source() {
+                if file_sha256(path).await? == sha256 {
+                    let staged = Staged {
+                        data: StagedData::File {
+                            path: path.clone(),
+                            temporary: false,
+                        },
+                        bytes,
+                        sha256,
+                    };
+                    let report = serde_json::json!({
+                        "type": spec.source_kind,
+                        "reused": true,
+                        "staged": "file",
+                        "bytes": staged.bytes,
+                        "sha256": staged.sha256,
+                    });
+                    self.audit_stage("fan_out.source", trace, report.clone(), None);
+                    return Ok((staged, report));
+                }
+            }
+        }
+
+        let source = self
+            .hydrate_block(&spec.source_kind, &spec.source, args)
+            .await?;
+        let (staged, details) = match spec.source_kind.as_str() {
+            "http" => {
+                let mut opened = self
+                    .open_http_stream(&source, args.get("cache"), trace)
+                    .await?;
+                let staged =
+                    match stage(&mut opened.reader, spec.stage_memory_bytes, staged_path).await {
+                        Ok(staged) => staged,
+                        Err(err) => {
+                            opened.completion.abort();
+                            return Err(err);
+                        }
+                    };
+                let completion = opened
+                    .completion
+                    .await
+                    .map_err(|_| ToolError::internal("HTTP stream task failed"))??;
+                (staged, completion.attach_body_ref(opened.response))
+            }
+            "sftp" => {
+                let mut opened = self.open_sftp_stream(&source).await?;
+                let staged =
+                    match stage(&mut opened.reader, spec.stage_memory_bytes, staged_path).await {
+                        Ok(staged) => staged,
+                        Err(err) => {
+                            opened.completion.abort();
+                            return Err(err);
+                        }
+                    };
+                opened
+                    .completion
+                    .await
+                    .map_err(|_| ToolError::internal("SFTP stream task failed"))??;
+                (
+                    staged,
+                    serde_json::json!({"remote_path": source.get("remote_path")}),
+                )
+            }
+            _ => {
+                let export_args = self.build_export_args(&serde_json::json!({"postgres": source}));
+                let mut export = self.postgres_manager.export_stream(&export_args);
+                let staged =
+                    match stage(&mut export.reader, spec.stage_memory_bytes, staged_path).await {
+                        Ok(staged) => staged,
+                        Err(err) => {
+                            export.completion.abort();
+                            return Err(err);
+                        }
+                    };
+                let exported = export
+                    .completion
+                    .await
+                    .map_err(|_| ToolError::internal("Postgres export task failed"))??;
+                (
+                    staged,
+                    serde_json::json!({
+                        "rows_written": exported.get("rows_written").cloned().unwrap_or(Value::Null),
+                        "table": exported.get("table").cloned().unwrap_or(Value::Null),
+                        "format": exported.get("format").cloned().unwrap_or(Value::Null),
+                    }),
+                )
+            }
+        };
+        if let Some(cp) = checkpoint {
+            cp.record_source(staged.bytes, &staged.sha256)?;
+        }
+        let mut report = serde_json::json!({
+            "type": spec.source_kind,
+            "reused": false,
+            "staged": staged.location(),
+            "bytes": staged.bytes,
+            "sha256": staged.sha256,
+        });
+        report[spec.source_kind.as_str()] = details;
+        self.audit_stage(
+            "fan_out.source",
+            trace,
+            serde_json::json!({"type": spec.source_kind, "bytes": staged.bytes, "staged": staged.location()}),
+            None,
+        );
+        Ok((staged, report))
+    }
+
+    // Runs one sink under its own child span and never fails the fan-out itself.
+    async fn traced_sink(
+        &self,
+        sink: &SinkSpec,
+        staged: &Staged,
+        args: &Value,
+        trace: &Trace,
+        checkpoint: Option<&Mutex<FanOutCheckpoint>>,
+    ) -> Value {
+        let span = trace.child();
+        let started_at = chrono::Utc::now().timestamp_millis();
+        let timer = std::time::Instant::now();
+        let outcome = self.run_sink(sink, staged, args, &span).await;
+        self.audit_span(
+            &span,
+            "pipeline",
+            &format!("fan_out.{}", sink.kind),
+            started_at,
+            &outcome,
+        );
+        let failure = sink_failed(&outcome);
+        if let Some(lock) = checkpoint {
+            let mut cp = lock.lock().unwrap_or_else(|e| e.into_inner());
+            if let Err(err) = cp.record_sink(&sink.name, failure.as_deref()) {
+                self.logger.warn(
+                    "Failed to record fan_out checkpoint",
+                    Some(&serde_json::json!({"sink": sink.name, "error": err.message})),
+                );
+            }
+        }
+        let mut report = serde_json::json!({
+            "name": sink.name,
+            "type": sink.kind,
+            "status": if failure.is_some() { "failed" } else { "ok" },
+            "span_id": span.span_id,
+            "duration_ms": timer.elapsed().as_millis(),
+        });
+        match outcome {
+            Ok(result) => report["result"] = result,
+            Err(err) => {
+                report["error"] = serde_json::json!({
+                    "kind": err.kind,
+                    "code": err.code,
+                    "message": err.message,
+                    "hint": err.hint,
+                })
+            }
+        }
+        report
+    }
+
+    async fn run_sink(
+        &self,
+        sink: &SinkSpec,
+        staged: &Staged,
+        args: &Value,
+        span: &Trace,
+    ) -> Result<Value, ToolError> {
+        let config = self.hydrate_block(&sink.kind, &sink.config, args).await?;
+        match sink.kind.as_str() {
+            "sftp" => {
+                let (mut reader, copy) = staged.open();
+                let source = async {
+                    copy.await
+                        .map_err(|_| ToolError::internal("staged source task failed"))?
+                };
+                let (uploaded, _) = self
+                    .upload_stream_to_sftp(&mut reader, &config, source)
+                    .await?;
+                Ok(uploaded)
+            }
+            "postgres" => {
+                let option = |key: &str| {
+                    config
+                        .get(key)
+                        .filter(|v| !v.is_null())
+                        .or_else(|| args.get(key))
+                        .cloned()
+                };
+                let options: Vec<Option<Value>> = POSTGRES_SINK_OPTIONS
+                    .iter()
+                    .map(|key| option(key))
+                    .collect();
+                let table_cfg = match config.as_object() {
+                    Some(map) => strip_keys(map, POSTGRES_SINK_OPTIONS),
+                    None => config.clone(),
+                };
+                let (mut reader, copy) = staged.open();
+                let ingest = self
+                    .ingest_stream(
+                        &mut reader,
+                        &table_cfg,
+                        options[0].as_ref(),
+                        options[1].as_ref(),
+                        options[2].as_ref(),
+                        options[3].as_ref(),
+                        options[4].as_ref(),
+                    )
+                    .await;
+                // max_rows may stop reading early; the copy has nothing left to deliver.
+                drop(reader);
+                copy.abort();
+                ingest
+            }
+            _ => {
+                let upload = self.prepare_sftp_upload(&config).await?;
+                let (sent, _) = self
+                    .send_upload(&upload, "fan_out", &HeaderMap::new(), span, || async {
+                        Ok(staged.open())
+                    })
+                    .await?;
+                Ok(sent)
+            }
+        }
+    }
+}
@@ -1,3 +1,7 @@
+use super::http_template::{parse_http_template, HttpTemplate};
+use super::records::RecordReader;
+use super::sftp_batch::{parse_sftp_batch, BatchSink};
+use super::Trace;
 use crate::errors::ToolError;
 use serde_json::Value;
 
@@ -18,8 +22,14 @@ impl super::PipelineManager {
         );
 
         let sftp_cfg = hydrated.get("sftp").unwrap_or(&Value::Null);
-        let sftp_result = self
-            .upload_stream_to_sftp(&mut opened.reader, sftp_cfg)
+        let source = async {
+            opened
+                .completion
+                .await
+                .map_err(|_| ToolError::internal("HTTP stream task failed"))?
+        };
+        let (sftp_result, completion) = self
+            .upload_stream_to_sftp(&mut opened.reader, sftp_cfg, source)
             .await?;
         self.audit_stage(
             "sftp_upload",
@@ -28,10 +38,6 @@ impl super::PipelineManager {
             None,
         );
 
-        let completion = opened
-            .completion
-            .await
-            .map_err(|_| ToolError::internal("HTTP stream task failed"))??;
         let http_response = completion.attach_body_ref(opened.response);
 
         Ok(serde_json::json!({
@@ -96,6 +102,13 @@ impl super::PipelineManager {
     pub(super) async fn sftp_to_postgres(&self, args: &Value) -> Result<Value, ToolError> {
         let hydrated = self.hydrate_project_defaults(args).await?;
         let trace = self.build_trace(&hydrated);
+        if let Some(batch) = parse_sftp_batch(&hydrated)? {
+            let pg_cfg = hydrated.get("postgres").unwrap_or(&Value::Null);
+            let sink = BatchSink::Postgres(pg_cfg);
+            return self
+                .run_sftp_batch("sftp_to_postgres", &batch, sink, &hydrated, &trace)
+                .await;
+        }
 
         let sftp_cfg = hydrated.get("sftp").unwrap_or(&Value::Null);
         let mut opened = self.open_sftp_stream(sftp_cfg).await?;
@@ -144,6 +157,24 @@ impl super::PipelineManager {
 
         let http_cfg = hydrated.get("http").unwrap_or(&Value::Null);
         let sftp_cfg = hydrated.get("sftp").unwrap_or(&Value::Null);
+        if let Some(template) = parse_http_template(&hydrated)? {
+            if parse_sftp_batch(&hydrated)?.is_some() {
+                return Err(ToolError::invalid_params(
+                    "templated http sinks read a single sftp.remote_path",
+                )
+                .with_hint("Drop remote_glob/checkpoint, or drop body_template/path_template."));
+            }
+            return self
+                .sftp_to_templated_http(&template, &hydrated, &trace)
+                .await;
+        }
+        if let Some(batch) = parse_sftp_batch(&hydrated)? {
+            let upload = self.prepare_sftp_upload(http_cfg).await?;
+            let sink = BatchSink::Http(&upload);
+            return self
+                .run_sftp_batch("sftp_to_http", &batch, sink, &hydrated, &trace)
+                .await;
+        }
 
         self.upload_sftp_to_http(http_cfg, sftp_cfg, &trace).await
     }
@@ -163,15 +194,16 @@ impl super::PipelineManager {
         );
 
         let sftp_cfg = hydrated.get("sftp").unwrap_or(&Value::Null);
-        let sftp_result = self
-            .upload_stream_to_sftp(&mut export.reader, sftp_cfg)
+        let source = async {
+            export
+                .completion
+                .await
+                .map_err(|_| ToolError::internal("Postgres export task failed"))?
+        };
+        let (sftp_result, export_result) = self
+            .upload_stream_to_sftp(&mut export.reader, sftp_cfg, source)
             .await?;
 
-        let export_result = export
-            .completion
-            .await
-            .map_err(|_| ToolError::internal("Postgres export task failed"))??;
-
         self.audit_stage(
             "sftp_upload",
             &trace,
@@ -196,6 +228,117 @@ impl super::PipelineManager {
     pub(super) async fn postgres_to_http(&self, args: &Value) -> Result<Value, ToolError> {
         let hydrated = self.hydrate_project_defaults(args).await?;
         let trace = self.build_trace(&hydrated);
+        if let Some(template) = parse_http_template(&hydrated)? {
+            if hydrated.get("chunk_rows").is_some_and(|v| !v.is_null()) {
+                return Err(ToolError::invalid_params(
+                    "chunk_rows cannot be combined with http.body_template/path_template",
+                )
+                .with_hint("Use http.per=batch with http.batch_size to group records."));
+            }
+            return self
+                .postgres_to_templated_http(&template, &hydrated, &trace)
+                .await;
+        }
+        if let Some(spec) = super::chunked::parse_chunked_upload(&hydrated)? {
+            return self.upload_postgres_chunks(&spec, &hydrated, &trace).await;
+        }
         self.upload_postgres_to_http(&hydrated, &trace).await
     }
+
+    // The export is read as JSONL records; a dry run only exports the rows its examples need.
+    async fn postgres_to_templated_http(
+        &self,
+        template: &HttpTemplate,
+        hydrated: &Value,
+        trace: &Trace,
+    ) -> Result<Value, ToolError> {
+        let mut export_args = self.build_export_args(hydrated);
+        export_args["format"] = Value::String("jsonl".to_string());
+        if template.dry_run() {
+            let limit = export_args
+                .get("limit")
+                .and_then(|v| v.as_u64())
+                .map_or(template.dry_run_rows(), |limit| {
+                    limit.min(template.dry_run_rows())
+                });
+            export_args["limit"] = Value::from(limit);
+        }
+        self.audit_stage(
+            "postgres_export",
+            trace,
+            serde_json::json!({"table": export_args.get("table"), "schema": export_args.get("schema"), "format": "jsonl", "templated": true}),
+            None,
+        );
+
+        let export = self.postgres_manager.export_stream(&export_args);
+        let mut records = RecordReader::new(export.reader, None, None, None, None)?;
+        let http_cfg = hydrated.get("http").unwrap_or(&Value::Null);
+        let sent = self
+            .send_templated("postgres_to_http", template, http_cfg, &mut records, trace)
+            .await;
+        drop(records);
+        let mut out = match sent {
+            Ok(out) if out["stopped"].is_null() && !template.dry_run() => out,
+            other => {
+                export.completion.abort();
+                return other;
+            }
+        };
+        let export_result = export
+            .completion
+            .await
+            .map_err(|_| ToolError::internal("Postgres export task failed"))??;
+        out["postgres"] = serde_json::json!({
+            "rows_written": export_result.get("rows_written").cloned().unwrap_or(Value::Null),
+            "table": export_result.get("table").cloned().unwrap_or(Value::Null),
+            "schema": export_result.get("schema").cloned().unwrap_or(Value::Null),
+            "duration_ms": export_result.get("duration_ms").cloned().unwrap_or(Value::Null),
+        });
+        Ok(out)
+    }
+
+    async fn sftp_to_templated_http(
+        &self,
+        template: &HttpTemplate,
+        hydrated: &Value,
+        trace: &Trace,
+    ) -> Result<Value, ToolError> {
+        let sftp_cfg = hydrated.get("sftp").unwrap_or(&Value::Null);
+        let opened = self.open_sftp_stream(sftp_cfg).await?;
+        self.audit_stage(
+            "sftp_download",
+            trace,
+            serde_json::json!({"remote_path": sftp_cfg.get("remote_path"), "templated": true}),
+            None,
+        );
+
+        let mut records = RecordReader::new(
+            opened.reader,
+            hydrated.get("format"),
+            None,
+            hydrated.get("csv_header"),
+            hydrated.get("csv_delimiter"),
+        )?;
+        let http_cfg = hydrated.get("http").unwrap_or(&Value::Null);
+        let sent = self
+            .send_templated("sftp_to_http", template, http_cfg, &mut records, trace)
+            .await;
+        drop(records);
+        let mut out = match sent {
+            Ok(out) if out["stopped"].is_null() && !template.dry_run() => out,
+            other => {
+                opened.completion.abort();
+                return other;
+            }
+        };
+        let bytes = opened
+            .completion
+            .await
+            .map_err(|_| ToolError::internal("SFTP stream task failed"))??;
+        out["sftp"] = serde_json::json!({
+            "remote_path": sftp_cfg.get("remote_path").cloned().unwrap_or(Value::Null),
+            "bytes": bytes,
+        });
+        Ok(out)
+    }
 }
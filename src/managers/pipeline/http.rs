@@ -1,16 +1,21 @@
+use super::sftp::SftpRead;
 use super::Trace;
 use crate::constants::cache as cache_constants;
 use crate::errors::ToolError;
-use crate::managers::api::{map_reqwest_error, ApiProfile, RequestConfig};
+use crate::managers::api::{map_reqwest_error, ApiProfile, RequestConfig, RetryPolicy};
 use crate::utils::artifacts::{
     build_tool_call_file_ref, create_artifact_write_stream, resolve_context_root,
 };
 use crate::utils::redact::redact_text;
+use crate::utils::usage::{self, Counter};
 use bytes::Bytes;
 use futures::StreamExt;
+use reqwest::header::HeaderMap;
 use serde_json::Value;
+use std::future::Future;
 use std::path::PathBuf;
 use tokio::io::{AsyncReadExt, AsyncWriteExt, DuplexStream};
+use tokio::task::JoinHandle;
 
 pub(super) struct HttpCompletion {
     body_ref: Option<Value>,
@@ -34,6 +39,11 @@ impl HttpCompletion {
     }
 }
 
+pub(super) struct SftpUpload {
+    config: RequestConfig,
+    policy: RetryPolicy,
+}
+
 pub(super) struct OpenedHttpStream {
     pub(super) reader: DuplexStream,
     pub(super) response: Value,
@@ -76,7 +86,12 @@ impl super::PipelineManager {
         if let Some(provider) = auth_provider {
             auth = self
                 .api_manager
-                .resolve_auth_provider(Some(provider), profile.name.as_deref(), http_args)
+                .resolve_auth_provider(
+                    Some(provider),
+                    profile.name.as_deref(),
+                    profile.proxy.as_ref(),
+                    http_args,
+                )
                 .await?;
         }
 
@@ -110,6 +125,10 @@ impl super::PipelineManager {
             timeout_ms,
             follow_redirects,
             insecure_ok,
+            tls,
+            ssrf,
+            proxy,
+            tuning,
         } = config;
 
         if cache_policy.enabled {
@@ -136,10 +155,19 @@ impl super::PipelineManager {
             }
         }
 
-        let client = self.api_manager.get_client(follow_redirects, insecure_ok)?;
+        let client = self.api_manager.get_client(
+            follow_redirects,
+            insecure_ok,
+            tls.as_ref(),
+            ssrf.as_ref(),
+            proxy.as_ref(),
+            tuning.as_ref(),
+        )?;
         let mut req = client.request(method.clone(), url.clone());
         req = req.headers(headers.clone());
         if let Some(body) = body {
+            let sent = body.as_bytes().map_or(0, |bytes| bytes.len() as u64);
+            usage::record(Counter::HttpBodySentBytes, sent);
             req = req.body(body);
         }
         if let Some(timeout_ms) = timeout_ms {
@@ -307,6 +335,7 @@ impl super::PipelineManager {
         let mut stream = response.bytes_stream();
         while let Some(chunk) = stream.next().await {
             let chunk = chunk.map_err(map_reqwest_error)?;
+            usage::record(Counter::HttpBodyReadBytes, chunk.len() as u64);
             file.write_all(&chunk).await?;
         }
         file.flush().await.ok();
@@ -371,11 +400,12 @@ impl super::PipelineManager {
         let trace = trace.clone();
         let prefix = prefix.to_string();
 
-        let completion = tokio::spawn(async move {
+        let completion = tokio::spawn(usage::in_current_scope(async move {
             let mut capture = ArtifactCapture::new(trace, prefix).await;
             let mut stream = response.bytes_stream();
             while let Some(chunk) = stream.next().await {
                 let chunk = chunk.map_err(map_reqwest_error)?;
+                usage::record(Counter::HttpBodyReadBytes, chunk.len() as u64);
                 writer.write_all(&chunk).await?;
                 capture.capture_bytes(&chunk).await;
             }
@@ -386,7 +416,7 @@ impl super::PipelineManager {
                 body_ref: capture.finalize().await,
                 body_ref_truncated,
             })
-        });
+        }));
 
         Ok(OpenedHttpStream {
             reader,
@@ -402,12 +432,24 @@ impl super::PipelineManager {
         sftp_cfg: &Value,
         trace: &Trace,
     ) -> Result<Value, ToolError> {
-        if !http_cfg.is_object() {
-            return Err(ToolError::invalid_params("http config is required"));
-        }
+        let upload = self.prepare_sftp_upload(http_cfg).await?;
         if !sftp_cfg.is_object() {
             return Err(ToolError::invalid_params("sftp config is required"));
         }
+        let read = self.plain_sftp_read(sftp_cfg)?;
+        let (result, _) = self
+            .send_sftp_upload(&upload, sftp_cfg, &read, &HeaderMap::new(), trace)
+            .await?;
+        Ok(result)
+    }
+
+    pub(super) async fn prepare_sftp_upload(
+        &self,
+        http_cfg: &Value,
+    ) -> Result<SftpUpload, ToolError> {
+        if !http_cfg.is_object() {
+            return Err(ToolError::invalid_params("http config is required"));
+        }
 
         let mut http_args = http_cfg.as_object().cloned().unwrap_or_default();
         http_args
@@ -429,6 +471,40 @@ impl super::PipelineManager {
             profile.data.get("stability"),
             http_value.get("method"),
         );
+        Ok(SftpUpload { config, policy })
+    }
+
+    // One remote file (or archive member) as one request body, retried per the policy; returns
+    // the flow result and the bytes streamed on the final attempt.
+    pub(super) async fn send_sftp_upload(
+        &self,
+        upload: &SftpUpload,
+        sftp_cfg: &Value,
+        read: &SftpRead,
+        extra_headers: &HeaderMap,
+        trace: &Trace,
+    ) -> Result<(Value, u64), ToolError> {
+        self.send_upload(upload, "sftp_to_http", extra_headers, trace, || async {
+            let opened = self.open_sftp_read(sftp_cfg, read.clone()).await?;
+            Ok((opened.reader, opened.completion))
+        })
+        .await
+    }
+
+    // `open` yields a fresh body (reader plus the task feeding it) for every attempt.
+    pub(super) async fn send_upload<F, Fut>(
+        &self,
+        upload: &SftpUpload,
+        flow: &str,
+        extra_headers: &HeaderMap,
+        trace: &Trace,
+        open: F,
+    ) -> Result<(Value, u64), ToolError>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<(DuplexStream, JoinHandle<Result<u64, ToolError>>), ToolError>>,
+    {
+        let SftpUpload { config, policy } = upload;
         let max_attempts = if policy.enabled {
             policy.max_attempts.max(1)
         } else {
@@ -441,25 +517,32 @@ impl super::PipelineManager {
         while attempt < max_attempts {
             attempt += 1;
 
-            let opened = self.open_sftp_stream(sftp_cfg).await?;
-            let body = duplex_to_body(opened.reader);
-
-            let client = self
-                .api_manager
-                .get_client(config.follow_redirects, config.insecure_ok)?;
+            let (reader, completion) = open().await?;
+            let body = duplex_to_body(reader);
+
+            let client = self.api_manager.get_client(
+                config.follow_redirects,
+                config.insecure_ok,
+                config.tls.as_ref(),
+                config.ssrf.as_ref(),
+                config.proxy.as_ref(),
+                config.tuning.as_ref(),
+            )?;
             let mut req = client.request(config.method.clone(), config.url.clone());
-            req = req.headers(config.headers.clone()).body(body);
+            req = req
+                .headers(config.headers.clone())
+                .headers(extra_headers.clone())
+                .body(body);
             if let Some(timeout_ms) = config.timeout_ms {
                 req = req.timeout(std::time::Duration::from_millis(timeout_ms));
             }
 
             let sent = req.send().await.map_err(map_reqwest_error);
-            let read_result = opened
-                .completion
+            let read_result = completion
                 .await
-                .map_err(|_| ToolError::internal("SFTP stream task failed"))?;
+                .map_err(|_| ToolError::internal("upload body task failed"))?;
 
-            read_result?;
+            let bytes = read_result?;
 
             match sent {
                 Ok(response) => {
@@ -478,7 +561,7 @@ impl super::PipelineManager {
                         "headers": Value::Object(headers_snapshot.clone()),
                     });
 
-                    if !self.api_manager.should_retry_response(&summary, &policy)
+                    if !self.api_manager.should_retry_response(&summary, policy)
                         || attempt >= max_attempts
                     {
                         let response_text = response.text().await.unwrap_or_default();
@@ -488,9 +571,9 @@ impl super::PipelineManager {
                             serde_json::json!({"url": config.url, "status": status}),
                             None,
                         );
-                        return Ok(serde_json::json!({
+                        let result = serde_json::json!({
                             "success": (200..300).contains(&status),
-                            "flow": "sftp_to_http",
+                            "flow": flow,
                             "http": {
                                 "url": config.url,
                                 "method": config.method.as_str(),
@@ -500,12 +583,13 @@ impl super::PipelineManager {
                             },
                             "attempts": attempt,
                             "retries": attempt.saturating_sub(1),
-                        }));
+                        });
+                        return Ok((result, bytes));
                     }
 
                     let delay =
                         self.api_manager
-                            .compute_retry_delay(attempt, &policy, Some(&summary));
+                            .compute_retry_delay(attempt, policy, Some(&summary));
                     tokio::time::sleep(std::time::Duration::from_millis(delay)).await;
                 }
                 Err(err) => {
@@ -519,7 +603,7 @@ impl super::PipelineManager {
                         );
                         return Err(err);
                     }
-                    let delay = self.api_manager.compute_retry_delay(attempt, &policy, None);
+                    let delay = self.api_manager.compute_retry_delay(attempt, policy, None);
                     tokio::time::sleep(std::time::Duration::from_millis(delay)).await;
                 }
             }
@@ -600,10 +684,7 @@ impl ArtifactCapture {
             return Self::disabled();
         }
 
-        let span_id = trace
-            .parent_span_id
-            .clone()
-            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        let span_id = trace.span_id.clone();
         let filename = format!("{}-{}.bin", prefix, uuid::Uuid::new_v4());
         let reference =
             match build_tool_call_file_ref(Some(&trace.trace_id), Some(&span_id), &filename) {
@@ -0,0 +1,551 @@
+use super::records::RecordReader;
+use super::Trace;
+use crate::errors::ToolError;
+use crate::managers::api::RequestConfig;
+use crate::utils::redact::{redact_object, redact_text};
+use crate::utils::template::{resolve_template_string, resolve_templates};
+use futures::stream::{FuturesUnordered, StreamExt};
+use reqwest::header::HeaderMap;
+use serde_json::Value;
+use tokio::io::AsyncRead;
+
+const TEMPLATE_KEYS: &[&str] = &[
+    "body_template",
+    "path_template",
+    "per",
+    "batch_size",
+    "missing",
+    "concurrency",
+];
+const DEFAULT_BATCH_SIZE: usize = 100;
+const DEFAULT_CONCURRENCY: usize = 4;
+const MAX_CONCURRENCY: usize = 32;
+const DRY_RUN_EXAMPLES: usize = 2;
+const FAILURE_SAMPLE: usize = 5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Per {
+    Record,
+    Batch,
+}
+
+impl Per {
+    fn name(self) -> &'static str {
+        match self {
+            Per::Record => "record",
+            Per::Batch => "batch",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Missing {
+    Error,
+    Skip,
+    Null,
+}
+
+impl Missing {
+    fn name(self) -> &'static str {
+        match self {
+            Missing::Error => "error",
+            Missing::Skip => "skip",
+            Missing::Null => "null",
+        }
+    }
+}
+
+// http.body_template / http.path_template turn the sink into one request per record (or per
+// batch of records) instead of one streamed body.
+#[derive(Clone, Debug)]
+pub(super) struct HttpTemplate {
+    body: Option<Value>,
+    path: Option<String>,
+    per: Per,
+    batch_size: usize,
+    missing: Missing,
+    concurrency: usize,
+    dry_run: bool,
+}
+
+impl HttpTemplate {
+    fn unit_size(&self) -> usize {
+        match self.per {
+            Per::Record => 1,
+            Per::Batch => self.batch_size,
+        }
+    }
+
+    // Batches go out in order, one at a time; records fan out up to `concurrency`.
+    fn in_flight(&self) -> usize {
+        match self.per {
+            Per::Record => self.concurrency,
+            Per::Batch => 1,
+        }
+    }
+
+    pub(super) fn dry_run_rows(&self) -> u64 {
+        (DRY_RUN_EXAMPLES * self.unit_size()) as u64
+    }
+
+    pub(super) fn dry_run(&self) -> bool {
+        self.dry_run
+    }
+}
+
+pub(super) fn parse_http_template(args: &Value) -> Result<Option<HttpTemplate>, ToolError> {
+    let http = args.get("http").unwrap_or(&Value::Null);
+    let present = |key: &str| http.get(key).is_some_and(|v| !v.is_null());
+    let dry_run = args
+        .get("dry_run")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    if !present("body_template") && !present("path_template") {
+        if let Some(key) = TEMPLATE_KEYS.iter().find(|key| present(key)) {
+            return Err(ToolError::invalid_params(format!(
+                "http.{} requires http.body_template or http.path_template",
+                key
+            ))
+            .with_hint(
+                "Set body_template and/or path_template to send one request per record or batch.",
+            ));
+        }
+        if dry_run {
+            return Err(ToolError::invalid_params(
+                "dry_run requires http.body_template or http.path_template",
+            )
+            .with_hint("dry_run renders example requests of a templated http sink."));
+        }
+        return Ok(None);
+    }
+
+    let path = match http.get("path_template").filter(|v| !v.is_null()) {
+        None => None,
+        Some(Value::String(path)) if !path.trim().is_empty() => Some(path.trim().to_string()),
+        Some(_) => {
+            return Err(ToolError::invalid_params(
+                "http.path_template must be a non-empty string",
+            ))
+        }
+    };
+    let per = match http
+        .get("per")
+        .and_then(|v| v.as_str())
+        .map(|s| s.trim().to_lowercase())
+        .as_deref()
+    {
+        None | Some("record") => Per::Record,
+        Some("batch") => Per::Batch,
+        Some(_) => {
+            return Err(ToolError::invalid_params(
+                "http.per must be record or batch",
+            ))
+        }
+    };
+    if per == Per::Record && present("batch_size") {
+        return Err(ToolError::invalid_params(
+            "http.batch_size requires http.per=batch",
+        ));
+    }
+    let missing = match http
+        .get("missing")
+        .and_then(|v| v.as_str())
+        .map(|s| s.trim().to_lowercase())
+        .as_deref()
+    {
+        None | Some("error") => Missing::Error,
+        Some("skip") => Missing::Skip,
+        Some("null") => Missing::Null,
+        Some(_) => {
+            return Err(ToolError::invalid_params(
+                "http.missing must be error, skip or null",
+            ))
+        }
+    };
+    Ok(Some(HttpTemplate {
+        body: http.get("body_template").filter(|v| !v.is_null()).cloned(),
+        path,
+        per,
+        batch_size: super::util::read_positive_int(http.get("batch_size"))
+            .map(|v| v as usize)
+            .unwrap_or(DEFAULT_BATCH_SIZE),
+        missing,
+        concurrency: super::util::read_positive_int(http.get("concurrency"))
+            .map(|v| (v as usize).min(MAX_CONCURRENCY))
+            .unwrap_or(DEFAULT_CONCURRENCY),
+        dry_run,
+    }))
+}
+
+// One record, or one batch of records, and what the sink made of it.
+struct Unit {
+    index: u64,
+    records: Vec<Value>,
+}
+
+struct Rendered {
+    path: Option<String>,
+    body: Value,
+}
+
+#[derive(Default)]
+struct Tally {
+    records_read: u64,
+    records_delivered: u64,
+    records_failed: u64,
+    records_skipped: u64,
+    requests: u64,
+    requests_failed: u64,
+    retries: u64,
+    last_status: Option<u64>,
+    failures: Vec<Value>,
+}
+
+impl Tally {
+    fn fail(&mut self, template: &HttpTemplate, unit: &Unit, status: Option<u64>, err: &ToolError) {
+        self.records_failed += unit.records.len() as u64;
+        if self.failures.len() >= FAILURE_SAMPLE {
+            return;
+        }
+        let mut sample = serde_json::json!({
+            template.per.name(): unit.index,
+            "status": status,
+            "code": err.code,
+            "error": redact_text(&err.message, 1024, None),
+        });
+        match template.per {
+            Per::Record => {
+                sample["record"] = redact_object(&unit.records[0], 256, None);
+            }
+            Per::Batch => {
+                sample["records"] = Value::from(unit.records.len());
+            }
+        }
+        self.failures.push(sample);
+    }
+}
+
+impl super::PipelineManager {
+    // Renders each record (or batch) into its own request and sends it with the api retry policy.
+    // Delivery failures are counted and sampled; a render error stops the run under
+    // missing=error. With dry_run the first rendered requests are returned instead of sent.
+    pub(super) async fn send_templated<R: AsyncRead + Unpin>(
+        &self,
+        flow: &str,
+        template: &HttpTemplate,
+        http_cfg: &Value,
+        records: &mut RecordReader<R>,
+        trace: &Trace,
+    ) -> Result<Value, ToolError> {
+        let started = std::time::Instant::now();
+        if !http_cfg.is_object() {
+            return Err(ToolError::invalid_params("http config is required"));
+        }
+        let mut http_args = http_cfg.as_object().cloned().unwrap_or_default();
+        http_args
+            .entry("method".to_string())
+            .or_insert_with(|| Value::String("POST".to_string()));
+        for key in TEMPLATE_KEYS
+            .iter()
+            .chain(["body", "data", "form", "body_base64", "body_type"].iter())
+        {
+            http_args.remove(*key);
+        }
+        // path_template resolves against http.url when there is no base_url.
+        if template.path.is_some() {
+            if let Some(url) = http_args.remove("url") {
+                http_args.entry("base_url".to_string()).or_insert(url);
+            }
+        }
+        let mut headers = self.validation.ensure_headers(http_args.get("headers"))?;
+        if !headers
+            .keys()
+            .any(|k| k.eq_ignore_ascii_case("content-type"))
+        {
+            headers.insert(
+                "Content-Type".to_string(),
+                Value::String("application/json".to_string()),
+            );
+        }
+        http_args.insert("headers".to_string(), Value::Object(headers));
+        let http_value = Value::Object(http_args);
+
+        let (profile, auth) = self.resolve_http_profile(&http_value).await?;
+        let policy = self.api_manager.normalize_retry_policy(
+            http_value.get("retry"),
+            profile.retry.as_ref(),
+            http_value.get("stability"),
+            profile.data.get("stability"),
+            http_value.get("method"),
+        );
+        let build = |path: Option<&str>| -> Result<RequestConfig, ToolError> {
+            let mut args = http_value.clone();
+            if let Some(path) = path {
+                args["path"] = Value::String(path.to_string());
+            }
+            self.api_manager
+                .build_request_config(&args, &profile, auth.as_ref(), None)
+        };
+
+        let mut tally = Tally::default();
+        let mut examples: Vec<Value> = Vec::new();
+        let mut stopped: Option<Value> = None;
+        let mut pending = FuturesUnordered::new();
+        let mut next_index = 0u64;
+        let mut exhausted = false;
+
+        loop {
+            while !exhausted && stopped.is_none() && pending.len() < template.in_flight() {
+                let mut batch = Vec::new();
+                while batch.len() < template.unit_size() {
+                    match records.next_record().await? {
+                        Some(record) => batch.push(record),
+                        None => {
+                            exhausted = true;
+                            break;
+                        }
+                    }
+                }
+                if batch.is_empty() {
+                    break;
+                }
+                tally.records_read += batch.len() as u64;
+                let unit = Unit {
+                    index: next_index,
+                    records: batch,
+                };
+                next_index += 1;
+
+                let rendered = match render(template, &unit) {
+                    Ok(rendered) => rendered,
+                    Err(_) if template.missing == Missing::Skip => {
+                        tally.records_skipped += unit.records.len() as u64;
+                        continue;
+                    }
+                    Err(err) => {
+                        tally.fail(template, &unit, None, &err);
+                        stopped = Some(serde_json::json!({
+                            "stage": "render",
+                            template.per.name(): unit.index,
+                            "error": redact_text(&err.message, 1024, None),
+                        }));
+                        break;
+                    }
+                };
+                let config = match build(rendered.path.as_deref()) {
+                    Ok(config) => config,
+                    Err(err) => {
+                        tally.requests_failed += 1;
+                        tally.fail(template, &unit, None, &err);
+                        continue;
+                    }
+                };
+
+                if template.dry_run {
+                    examples.push(serde_json::json!({
+                        template.per.name(): unit.index,
+                        "records": unit.records.len(),
+                        "method": config.method.as_str(),
+                        "url": config.url,
+                        "body": redact_object(&rendered.body, 2048, None),
+                    }));
+                    if examples.len() >= DRY_RUN_EXAMPLES {
+                        exhausted = true;
+                    }
+                    continue;
+                }
+
+                let body = serde_json::to_vec(&rendered.body).unwrap_or_default();
+                let policy = &policy;
+                pending.push(async move {
+                    let outcome = self
+                        .deliver_chunk(&config, HeaderMap::new(), body, policy)
+                        .await;
+                    (unit, outcome)
+                });
+            }
+
+            let Some((unit, outcome)) = pending.next().await else {
+                break;
+            };
+            tally.requests += 1;
+            match outcome {
+                Ok(delivery) => {
+                    tally.retries += delivery.attempts.saturating_sub(1) as u64;
+                    tally.last_status = Some(delivery.status);
+                    if (200..300).contains(&delivery.status) {
+                        tally.records_delivered += unit.records.len() as u64;
+                    } else {
+                        tally.requests_failed += 1;
+                        let err = ToolError::invalid_params(format!(
+                            "HTTP sink rejected {} {} ({})",
+                            template.per.name(),
+                            unit.index,
+                            delivery.status
+                        ));
+                        tally.fail(template, &unit, Some(delivery.status), &err);
+                    }
+                }
+                Err((err, attempts)) => {
+                    tally.retries += attempts.saturating_sub(1) as u64;
+                    tally.requests_failed += 1;
+                    tally.fail(template, &unit, None, &err);
+                }
+            }
+        }
+
+        let method = http_value
+            .get("method")
+            .and_then(|v| v.as_str())
+            .unwrap_or("POST")
+            .to_uppercase();
+        if template.dry_run {
+            return Ok(serde_json::json!({
+                "success": stopped.is_none(),
+                "flow": flow,
+                "dry_run": true,
+                "per": template.per.name(),
+                "missing": template.missing.name(),
+                "records_read": tally.records_read,
+                "skipped": tally.records_skipped,
+                "examples": examples,
+                "failures": tally.failures,
+                "stopped": stopped,
+            }));
+        }
+
+        let success = stopped.is_none() && tally.records_failed == 0;
+        let err = (!success).then(|| {
+            ToolError::invalid_params(format!(
+                "{} of {} records failed",
+                tally.records_failed, tally.records_read
+            ))
+        });
+        self.audit_stage(
+            "http_upload",
+            trace,
+            serde_json::json!({
+                "templated": true,
+                "per": template.per.name(),
+                "method": method,
+                "requests": tally.requests,
+                "failed_requests": tally.requests_failed,
+                "records": tally.records_delivered,
+                "status": tally.last_status,
+            }),
+            err.as_ref(),
+        );
+        Ok(serde_json::json!({
+            "success": success,
+            "flow": flow,
+            "templated": true,
+            "per": template.per.name(),
+            "missing": template.missing.name(),
+            "http": {
+                "method": method,
+                "status": tally.last_status,
+                "concurrency": template.in_flight(),
+            },
+            "records": {
+                "read": tally.records_read,
+                "delivered": tally.records_delivered,
+                "failed": tally.records_failed,
+                "skipped": tally.records_skipped,
+            },
+            "requests": {
+                "sent": tally.requests,
+                "failed": tally.requests_failed,
+            },
+            "retries": tally.retries,
+            "failures": tally.failures,
+            "stopped": stopped,
+            "duration_ms": started.elapsed().as_millis() as u64,
+        }))
+    }
+}
+
+// Per record the context is { record, index }; per batch it is { batch, index, count }.
+// Without a body_template the record (or the batch array) is the body.
+fn render(template: &HttpTemplate, unit: &Unit) -> Result<Rendered, ToolError> {
+    let (context, default_body) = match template.per {
+        Per::Record => (
+            serde_json::json!({"record": unit.records[0], "index": unit.index}),
+            unit.records[0].clone(),
+        ),
+        Per::Batch => (
+            serde_json::json!({
+                "batch": unit.records,
+                "index": unit.index,
+                "count": unit.records.len(),
+            }),
+            Value::Array(unit.records.clone()),
+        ),
+    };
+    let missing = match template.missing {
+        Missing::Null => "null",
+        Missing::Error | Missing::Skip => "error",
+    };
+    let body = match template.body.as_ref() {
+        Some(body) => resolve_templates(body, &context, missing)?,
+        None => default_body,
+    };
+    let path = match template.path.as_ref() {
+        Some(path) => Some(render_path(path, &context, missing)?),
+        None => None,
+    };
+    Ok(Rendered { path, body })
+}
+
+// Values are percent-encoded before they land in the path, so a record cannot add segments or a
+// query string; a rendered `.`/`..` segment is rejected rather than normalized away.
+fn render_path(template: &str, context: &Value, missing: &str) -> Result<String, ToolError> {
+    let rendered = resolve_template_string(template, &encode_values(context), missing)?;
+    let Value::String(path) = rendered else {
+        return Err(ToolError::invalid_params(
+            "http.path_template must render to a string",
+        ));
+    };
+    let dot_segment = path
+        .split(['?', '#'])
+        .next()
+        .unwrap_or("")
+        .split('/')
+        .any(|segment| {
+            matches!(
+                segment.to_ascii_lowercase().as_str(),
+                "." | ".." | "%2e" | "%2e%2e" | ".%2e" | "%2e."
+            )
+        });
+    if dot_segment {
+        return Err(ToolError::invalid_params(format!(
+            "http.path_template rendered a dot segment: {}",
+            path
+        )));
+    }
+    Ok(path)
+}
+
+fn encode_values(value: &Value) -> Value {
+    match value {
+        Value::String(text) => Value::String(encode_segment(text)),
+        Value::Array(items) => Value::Array(items.iter().map(encode_values).collect()),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, entry)| (key.clone(), encode_values(entry)))
+                .collect(),
+        ),
+        _ => value.clone(),
+    }
+}
+
+fn encode_segment(raw: &str) -> String {
+    let mut out = String::with_capacity(raw.len());
+    for byte in raw.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    out
+}
@@ -1,7 +1,15 @@
+mod checkpoint;
+mod chunked;
+mod failure_logs;
+mod fan_out;
 mod flows;
 mod http;
+mod http_template;
 mod postgres;
+mod records;
 mod sftp;
+mod sftp_batch;
+mod spec;
 mod util;
 
 use crate::errors::ToolError;
@@ -10,31 +18,24 @@ use crate::managers::postgres::PostgresManager;
 use crate::managers::ssh::SshManager;
 use crate::services::audit::AuditService;
 use crate::services::cache::CacheService;
+use crate::services::evidence::{artifact_refs_in, EvidenceService};
+use crate::services::job::JobService;
 use crate::services::logger::Logger;
 use crate::services::project_resolver::ProjectResolver;
 use crate::services::tool_executor::ToolHandler;
 use crate::services::validation::Validation;
+use crate::utils::feature_flags;
 use crate::utils::redact::redact_object;
+use crate::utils::run_report;
+use crate::utils::smoke_sla::SmokeSampling;
 use crate::utils::tool_errors::unknown_action_error;
+use crate::utils::trace_context::TraceContext;
 use serde_json::Value;
 use std::sync::Arc;
 
 pub(crate) const PIPELINE_ACTIONS: &[&str] = &["run", "describe", "deploy_smoke"];
 
-const PIPELINE_FLOWS: &[&str] = &[
-    "http_to_sftp",
-    "sftp_to_http",
-    "http_to_postgres",
-    "sftp_to_postgres",
-    "postgres_to_sftp",
-    "postgres_to_http",
-];
-
-#[derive(Clone, Debug)]
-struct Trace {
-    trace_id: String,
-    parent_span_id: Option<String>,
-}
+type Trace = TraceContext;
 
 #[derive(Clone)]
 pub struct PipelineManager {
@@ -46,6 +47,10 @@ pub struct PipelineManager {
     cache_service: Option<Arc<CacheService>>,
     audit_service: Option<Arc<AuditService>>,
     project_resolver: Option<Arc<ProjectResolver>>,
+    evidence_service: Option<Arc<EvidenceService>>,
+    job_service: Option<Arc<JobService>>,
+    // Set on the copy that runs a background flow, so long stages can report progress.
+    background_job: Option<String>,
 }
 
 impl PipelineManager {
@@ -58,6 +63,7 @@ impl PipelineManager {
         cache_service: Option<Arc<CacheService>>,
         audit_service: Option<Arc<AuditService>>,
         project_resolver: Option<Arc<ProjectResolver>>,
+        evidence_service: Option<Arc<EvidenceService>>,
     ) -> Self {
         Self {
             logger: logger.child("pipeline"),
@@ -68,27 +74,80 @@ impl PipelineManager {
             cache_service,
             audit_service,
             project_resolver,
+            evidence_service,
+            job_service: None,
+            background_job: None,
         }
     }
 
+    pub fn with_job_service(mut self, job_service: Arc<JobService>) -> Self {
+        self.job_service = Some(job_service);
+        self
+    }
+
     pub async fn handle_action(&self, args: Value) -> Result<Value, ToolError> {
         let action = args.get("action");
         match action.and_then(|v| v.as_str()).unwrap_or("") {
-            "describe" => Ok(self.describe()),
-            "run" => self.run_pipeline(&args).await,
-            "deploy_smoke" => self.deploy_smoke(&args).await,
+            "describe" => self.describe(&args),
+            "run" if args.get("background").and_then(|v| v.as_bool()) == Some(true) => {
+                self.run_background(args)
+            }
+            "run" => {
+                run_report::requested(&args)?;
+                let started = std::time::Instant::now();
+                let result = self.run_pipeline(&args).await?;
+                let result = self.attach_evidence(&args, result, Vec::new());
+                Ok(run_report::attach(
+                    "Pipeline run report",
+                    &args,
+                    result,
+                    started.elapsed().as_millis() as u64,
+                ))
+            }
+            "deploy_smoke" => {
+                run_report::requested(&args)?;
+                let started = std::time::Instant::now();
+                let result = self.deploy_smoke(&args).await?;
+                let assertions = vec![serde_json::json!({
+                    "check": "http_status",
+                    "target": { "url": args.get("url").cloned().unwrap_or(Value::Null) },
+                    "expected": args.get("expect_code").cloned().unwrap_or(Value::Number(200.into())),
+                    "actual": result.get("smoke").and_then(|v| v.get("status")).cloned().unwrap_or(Value::Null),
+                    "pass": result.get("success").and_then(|v| v.as_bool()).unwrap_or(false),
+                })];
+                let result = self.attach_evidence(&args, result, assertions);
+                Ok(run_report::attach(
+                    "Deploy smoke report",
+                    &args,
+                    result,
+                    started.elapsed().as_millis() as u64,
+                ))
+            }
             _ => Err(unknown_action_error("pipeline", action, PIPELINE_ACTIONS)),
         }
     }
 
-    fn describe(&self) -> Value {
-        serde_json::json!({
+    fn describe(&self, args: &Value) -> Result<Value, ToolError> {
+        if let Some(name) = args.get("flow").and_then(|v| v.as_str()) {
+            let name = name.trim().to_lowercase();
+            if name == "fan_out" {
+                return Ok(serde_json::json!({"success": true, "flow": fan_out::describe()}));
+            }
+            let flow = spec::find_flow(&name)?;
+            return Ok(serde_json::json!({
+                "success": true,
+                "flow": flow.describe(true),
+            }));
+        }
+        Ok(serde_json::json!({
             "success": true,
-            "flows": PIPELINE_FLOWS,
-        })
+            "names": spec::flow_names(),
+            "flows": spec::FLOWS.iter().map(|flow| flow.describe(false)).collect::<Vec<_>>(),
+            "fan_out": fan_out::describe(),
+        }))
     }
 
-    async fn run_pipeline(&self, args: &Value) -> Result<Value, ToolError> {
+    fn validated_flow(&self, args: &Value) -> Result<String, ToolError> {
         let flow = args
             .get("flow")
             .and_then(|v| v.as_str())
@@ -97,10 +156,90 @@ impl PipelineManager {
             .to_lowercase();
 
         if flow.is_empty() {
-            return Err(ToolError::invalid_params("pipeline flow is required")
-                .with_hint(format!("Use one of: {}", PIPELINE_FLOWS.join(", "))));
+            return Err(
+                ToolError::invalid_params("pipeline flow is required").with_hint(format!(
+                    "Use one of: {}, fan_out",
+                    spec::flow_names().join(", ")
+                )),
+            );
+        }
+        // fan_out has a source and a list of sinks rather than two fixed blocks.
+        if flow == "fan_out" {
+            fan_out::parse_fan_out(args)?;
+            return Ok(flow);
+        }
+        spec::find_flow(&flow)?.validate(args)?;
+        Ok(flow)
+    }
+
+    // Validates up front, then runs the flow on a tokio task tracked as a local job; the run's
+    // start, result or error go to the job log and the outcome to the job store.
+    fn run_background(&self, args: Value) -> Result<Value, ToolError> {
+        let service = self.job_service.clone().ok_or_else(|| {
+            ToolError::invalid_params("background runs require the job service")
+                .with_hint("Run through the infra app, which wires jobs into the pipeline tool.")
+        })?;
+        let flow = self.validated_flow(&args)?;
+        run_report::requested(&args)?;
+        let job = service.start_local(
+            "pipeline_run",
+            serde_json::json!({"tool": "pipeline", "flow": flow}),
+            args.get("trace_id").and_then(|v| v.as_str()),
+        )?;
+        let mut args = args;
+        if let Value::Object(map) = &mut args {
+            map.remove("background");
         }
 
+        let mut manager = self.clone();
+        manager.background_job = Some(job.job_id.clone());
+        let task_service = service.clone();
+        let task_job = job.clone();
+        let task_flow = flow.clone();
+        let handle = tokio::spawn(async move {
+            task_job.log(&format!(
+                "{} pipeline flow={} started",
+                chrono::Utc::now().to_rfc3339(),
+                task_flow
+            ));
+            let started = std::time::Instant::now();
+            let outcome = manager.run_pipeline(&args).await.map(|result| {
+                let result = manager.attach_evidence(&args, result, Vec::new());
+                run_report::attach(
+                    "Pipeline run report",
+                    &args,
+                    result,
+                    started.elapsed().as_millis() as u64,
+                )
+            });
+            let summary = match &outcome {
+                Ok(result) => format!("finished: {}", result),
+                Err(err) => format!("failed: {}: {}", err.code, err.message),
+            };
+            task_job.log(&format!("{} {}", chrono::Utc::now().to_rfc3339(), summary));
+            task_service.finish_local(&task_job.job_id, outcome);
+        });
+        service.attach_local_task(&job.job_id, handle.abort_handle(), None);
+
+        Ok(serde_json::json!({
+            "success": true,
+            "background": true,
+            "flow": flow,
+            "job_id": job.job_id,
+            "log_path": job.log_path,
+            "log_uri": job.log_uri,
+        }))
+    }
+
+    // Merges `progress` into the background run's job record; foreground runs skip it.
+    fn report_progress(&self, progress: Value) {
+        if let (Some(service), Some(job_id)) = (&self.job_service, &self.background_job) {
+            service.update_progress(job_id, progress);
+        }
+    }
+
+    async fn run_pipeline(&self, args: &Value) -> Result<Value, ToolError> {
+        let flow = self.validated_flow(args)?;
         match flow.as_str() {
             "http_to_sftp" => self.http_to_sftp(args).await,
             "sftp_to_http" => self.sftp_to_http(args).await,
@@ -108,34 +247,67 @@ impl PipelineManager {
             "sftp_to_postgres" => self.sftp_to_postgres(args).await,
             "postgres_to_sftp" => self.postgres_to_sftp(args).await,
             "postgres_to_http" => self.postgres_to_http(args).await,
-            _ => Err(
-                ToolError::invalid_params(format!("Unknown pipeline flow: {}", flow))
-                    .with_hint(format!("Use one of: {}", PIPELINE_FLOWS.join(", "))),
-            ),
+            "fan_out" => self.fan_out(args).await,
+            // find_flow already rejected unknown names; this only trips on a table entry
+            // added without a runner.
+            _ => Err(ToolError::internal(format!(
+                "pipeline flow {} has no runner",
+                flow
+            ))),
         }
     }
 
-    fn build_trace(&self, args: &Value) -> Trace {
-        let trace_id = args
-            .get("trace_id")
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string())
-            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
-
-        let parent_span_id = args
-            .get("span_id")
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string())
-            .or_else(|| {
-                args.get("parent_span_id")
-                    .and_then(|v| v.as_str())
-                    .map(|s| s.to_string())
-            });
-
-        Trace {
-            trace_id,
-            parent_span_id,
+    fn attach_evidence(&self, args: &Value, mut result: Value, assertions: Vec<Value>) -> Value {
+        let Some(spec) = args.get("evidence").filter(|v| v.is_object()) else {
+            return result;
+        };
+        let Some(evidence_service) = self.evidence_service.as_ref() else {
+            return result;
+        };
+        let mut assertions = assertions;
+        assertions.push(serde_json::json!({
+            "check": "pipeline_success",
+            "expected": true,
+            "actual": result.get("success").cloned().unwrap_or(Value::Bool(false)),
+        }));
+        let record = serde_json::json!({
+            "capability": spec.get("capability").cloned().unwrap_or(Value::Null),
+            "intent": spec.get("intent").cloned().unwrap_or(Value::Null),
+            "summary": result.get("summary").cloned().unwrap_or_else(|| {
+                Value::String(format!(
+                    "pipeline {}",
+                    args.get("action").and_then(|v| v.as_str()).unwrap_or("")
+                ))
+            }),
+            "trace_ids": args.get("trace_id").into_iter().cloned().collect::<Vec<_>>(),
+            "artifacts": artifact_refs_in(&result),
+            "assertions": assertions,
+            "data": {
+                "action": args.get("action").cloned().unwrap_or(Value::Null),
+                "flow": args.get("flow").cloned().unwrap_or(Value::Null),
+            },
+        });
+        let evidence = match evidence_service.create_record(&record) {
+            Ok(saved) => serde_json::json!({
+                "id": saved.get("id").cloned().unwrap_or(Value::Null),
+                "path": saved.get("path").cloned().unwrap_or(Value::Null),
+            }),
+            Err(err) => {
+                self.logger.warn(
+                    "Failed to record pipeline evidence",
+                    Some(&serde_json::json!({ "error": err.message })),
+                );
+                serde_json::json!({ "error": err.message })
+            }
+        };
+        if let Value::Object(map) = &mut result {
+            map.insert("evidence".to_string(), evidence);
         }
+        result
+    }
+
+    fn build_trace(&self, args: &Value) -> Trace {
+        TraceContext::from_args(args)
     }
 
     fn merge_project_context(&self, child_args: &Value, root_args: &Value) -> Value {
@@ -274,7 +446,7 @@ impl PipelineManager {
             "action": stage,
             "trace_id": trace.trace_id,
             "span_id": uuid::Uuid::new_v4().to_string(),
-            "parent_span_id": trace.parent_span_id,
+            "parent_span_id": trace.span_id,
             "details": redact_object(&details, 2048, None),
         });
 
@@ -287,6 +459,19 @@ impl PipelineManager {
         audit_service.append(&entry);
     }
 
+    fn audit_span(
+        &self,
+        span: &Trace,
+        tool: &str,
+        action: &str,
+        started_at: i64,
+        outcome: &Result<Value, ToolError>,
+    ) {
+        if let Some(audit_service) = self.audit_service.as_ref() {
+            audit_service.record_span(span, tool, action, started_at, outcome.as_ref());
+        }
+    }
+
     async fn deploy_smoke(&self, args: &Value) -> Result<Value, ToolError> {
         let started = std::time::Instant::now();
         let trace = self.build_trace(args);
@@ -306,21 +491,23 @@ impl PipelineManager {
                 .ensure_string(args.get("url").unwrap_or(&Value::Null), "url", true)?;
 
         let settle_ms = std::cmp::min(
-            util::read_positive_int(args.get("settle_ms")).unwrap_or(0) as u64,
+            util::read_positive_int(args.get("settle_ms")).unwrap_or(0),
             120_000,
         );
         let max_attempts = std::cmp::min(
-            util::read_positive_int(args.get("smoke_attempts")).unwrap_or(5) as u64,
+            util::read_positive_int(args.get("smoke_attempts")).unwrap_or(5),
             20,
         ) as usize;
         let delay_ms = std::cmp::min(
-            util::read_positive_int(args.get("smoke_delay_ms")).unwrap_or(1_000) as u64,
+            util::read_positive_int(args.get("smoke_delay_ms")).unwrap_or(1_000),
             60_000,
         );
         let smoke_timeout_ms = std::cmp::min(
-            util::read_positive_int(args.get("smoke_timeout_ms")).unwrap_or(10_000) as u64,
+            util::read_positive_int(args.get("smoke_timeout_ms")).unwrap_or(10_000),
             120_000,
         );
+        let collect_logs = failure_logs::parse_collect_logs(args)?;
+        let sampling = SmokeSampling::parse(args)?;
 
         self.audit_stage(
             "deploy_smoke.deploy",
@@ -329,9 +516,11 @@ impl PipelineManager {
             None,
         );
 
+        let deploy_span = trace.child();
+        let deploy_started = chrono::Utc::now().timestamp_millis();
         let deploy = self
             .ssh_manager
-            .handle_action(serde_json::json!({
+            .handle_action(deploy_span.apply(serde_json::json!({
                 "action": "deploy_file",
                 "local_path": local_path,
                 "remote_path": remote_path,
@@ -346,23 +535,37 @@ impl PipelineManager {
                 "environment": args.get("environment").cloned().unwrap_or(Value::Null),
                 "vault_profile_name": args.get("vault_profile_name").cloned().unwrap_or(Value::Null),
                 "vault_profile": args.get("vault_profile").cloned().unwrap_or(Value::Null),
-            }))
-            .await?;
+                "preflight": args.get("preflight").cloned().unwrap_or(Value::Null),
+                "min_bytes": args.get("min_bytes").cloned().unwrap_or(Value::Null),
+                "required_free_mb": args.get("required_free_mb").cloned().unwrap_or(Value::Null),
+                "skip_unchanged": args.get("skip_unchanged").cloned().unwrap_or(Value::Null),
+            })))
+            .await;
+        self.audit_span(&deploy_span, "ssh", "deploy_file", deploy_started, &deploy);
+        let deploy = deploy_span.tag(deploy?);
 
         let deploy_ok = deploy
             .get("success")
             .and_then(|v| v.as_bool())
             .unwrap_or(false);
         if !deploy_ok {
+            let preflight_failed =
+                deploy.get("code").and_then(|v| v.as_str()) == Some("PREFLIGHT_FAILED");
+            let stage = if preflight_failed {
+                "preflight"
+            } else {
+                "deploy"
+            };
             self.audit_stage(
                 "deploy_smoke.failed",
                 &trace,
-                serde_json::json!({"stage": "deploy"}),
+                serde_json::json!({"stage": stage}),
                 None,
             );
             return Ok(serde_json::json!({
                 "success": false,
-                "code": "DEPLOY_FAILED",
+                "code": if preflight_failed { "PREFLIGHT_FAILED" } else { "DEPLOY_FAILED" },
+                "preflight": deploy.get("preflight").cloned().unwrap_or(Value::Null),
                 "deploy": deploy,
                 "smoke": Value::Null,
                 "duration_ms": started.elapsed().as_millis(),
@@ -383,17 +586,21 @@ impl PipelineManager {
         let mut last: Option<Value> = None;
         let mut ok_at: Option<usize> = None;
         for attempt in 1..=max_attempts {
+            let smoke_span = trace.child();
+            let smoke_started = chrono::Utc::now().timestamp_millis();
             let smoke = self
                 .api_manager
-                .handle_action(serde_json::json!({
+                .handle_action(smoke_span.apply(serde_json::json!({
                     "action": "smoke_http",
                     "url": url,
                     "timeout_ms": smoke_timeout_ms,
                     "expect_code": args.get("expect_code").cloned().unwrap_or(Value::Null),
                     "follow_redirects": args.get("follow_redirects").cloned().unwrap_or(Value::Null),
                     "insecure_ok": args.get("insecure_ok").cloned().unwrap_or(Value::Null),
-                }))
-                .await?;
+                })))
+                .await;
+            self.audit_span(&smoke_span, "api", "smoke_http", smoke_started, &smoke);
+            let smoke = smoke_span.tag(smoke?);
             let ok = smoke
                 .get("success")
                 .and_then(|v| v.as_bool())
@@ -409,7 +616,34 @@ impl PipelineManager {
             }
         }
 
-        let last = last.unwrap_or(Value::Null);
+        let mut last = last.unwrap_or(Value::Null);
+        // With samples/sla the attempts above only wait for the service to answer; the sampled
+        // run that follows decides, on its SLA verdict.
+        let mut readiness = Value::Null;
+        if let (Some(sampling), Some(_)) = (sampling.as_ref(), ok_at) {
+            let remaining_ms = feature_flags::TOOL_CALL_TIMEOUT_MS
+                .positive_number()
+                .saturating_sub(started.elapsed().as_millis() as u64);
+            let smoke_span = trace.child();
+            let smoke_started = chrono::Utc::now().timestamp_millis();
+            let sampled = self
+                .api_manager
+                .handle_action(smoke_span.apply(serde_json::json!({
+                    "action": "smoke_http",
+                    "url": url,
+                    "timeout_ms": smoke_timeout_ms,
+                    "expect_code": args.get("expect_code").cloned().unwrap_or(Value::Null),
+                    "follow_redirects": args.get("follow_redirects").cloned().unwrap_or(Value::Null),
+                    "insecure_ok": args.get("insecure_ok").cloned().unwrap_or(Value::Null),
+                    "samples": sampling.samples,
+                    "interval_ms": sampling.interval_ms,
+                    "sla": args.get("sla").cloned().unwrap_or(Value::Null),
+                    "budget_ms": remaining_ms.max(1),
+                })))
+                .await;
+            self.audit_span(&smoke_span, "api", "smoke_http", smoke_started, &sampled);
+            readiness = std::mem::replace(&mut last, smoke_span.tag(sampled?));
+        }
         let smoke_ok = last
             .get("success")
             .and_then(|v| v.as_bool())
@@ -417,6 +651,28 @@ impl PipelineManager {
             && last.get("ok").and_then(|v| v.as_bool()).unwrap_or(false);
         let success = deploy_ok && smoke_ok;
 
+        let mut failure_logs = Value::Null;
+        if !smoke_ok {
+            if let Some(spec) = collect_logs.as_ref() {
+                failure_logs = self.collect_failure_logs(spec, args, &trace).await;
+            }
+            self.audit_stage(
+                "deploy_smoke.failed",
+                &trace,
+                serde_json::json!({
+                    "stage": "smoke",
+                    "url": url,
+                    "status": last.get("status").cloned().unwrap_or(Value::Null),
+                    "expect_code": args.get("expect_code").cloned().unwrap_or(Value::Null),
+                    "restart": args.get("restart").cloned().unwrap_or(Value::Null),
+                    "profile_name": args.get("profile_name").cloned().unwrap_or(Value::Null),
+                    "target": args.get("target").cloned().unwrap_or(Value::Null),
+                    "failure_logs": failure_logs,
+                }),
+                None,
+            );
+        }
+
         let summary = if smoke_ok {
             "deploy ok; smoke ok"
         } else {
@@ -425,19 +681,25 @@ impl PipelineManager {
         let next_actions = if smoke_ok {
             Vec::new()
         } else {
+            let mut smoke_args = serde_json::json!({
+                "url": url,
+                "expect_code": args.get("expect_code").cloned().unwrap_or(Value::Number(200.into())),
+                "follow_redirects": args.get("follow_redirects").cloned().unwrap_or(Value::Bool(true)),
+                "insecure_ok": args.get("insecure_ok").cloned().unwrap_or(Value::Bool(true)),
+            });
+            if let Some(sampling) = sampling.as_ref() {
+                smoke_args["samples"] = Value::from(sampling.samples);
+                smoke_args["interval_ms"] = Value::from(sampling.interval_ms);
+                smoke_args["sla"] = args.get("sla").cloned().unwrap_or(Value::Null);
+            }
             vec![serde_json::json!({
                 "tool": "api",
                 "action": "smoke_http",
-                "args": {
-                    "url": url,
-                    "expect_code": args.get("expect_code").cloned().unwrap_or(Value::Number(200.into())),
-                    "follow_redirects": args.get("follow_redirects").cloned().unwrap_or(Value::Bool(true)),
-                    "insecure_ok": args.get("insecure_ok").cloned().unwrap_or(Value::Bool(true)),
-                }
+                "args": smoke_args,
             })]
         };
 
-        Ok(serde_json::json!({
+        let mut result = serde_json::json!({
             "success": success,
             "summary": summary,
             "deploy": deploy,
@@ -450,7 +712,14 @@ impl PipelineManager {
             },
             "next_actions": next_actions,
             "duration_ms": started.elapsed().as_millis(),
-        }))
+        });
+        if !readiness.is_null() {
+            result["readiness"] = readiness;
+        }
+        if !failure_logs.is_null() {
+            result["failure_logs"] = failure_logs;
+        }
+        Ok(result)
     }
 }
 
@@ -1,9 +1,10 @@
+use super::records::RecordReader;
 use super::Trace;
 use crate::errors::ToolError;
 use crate::managers::api::{map_reqwest_error, RequestConfig};
 use bytes::Bytes;
 use serde_json::Value;
-use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader, DuplexStream};
+use tokio::io::{AsyncReadExt, DuplexStream};
 
 impl super::PipelineManager {
     pub(super) fn build_export_args(&self, args: &Value) -> Value {
@@ -23,6 +24,7 @@ impl super::PipelineManager {
             "columns_sql",
             "order_by",
             "order_by_sql",
+            "paginate",
             "filters",
             "where_sql",
             "where_params",
@@ -52,19 +54,10 @@ impl super::PipelineManager {
             return Err(ToolError::invalid_params("postgres config is required"));
         }
 
-        let format = format
-            .and_then(|v| v.as_str())
-            .unwrap_or("jsonl")
-            .trim()
-            .to_lowercase();
-        if format != "jsonl" && format != "csv" {
-            return Err(ToolError::invalid_params("format must be jsonl or csv"));
-        }
-
         let batch_size = super::util::read_positive_int(batch_size).unwrap_or(500) as usize;
         let max_rows = super::util::read_positive_int(max_rows).map(|v| v as usize);
 
-        let mut columns: Option<Vec<String>> = postgres_cfg
+        let columns: Option<Vec<String>> = postgres_cfg
             .get("columns")
             .and_then(|v| v.as_array())
             .map(|arr| {
@@ -74,67 +67,30 @@ impl super::PipelineManager {
                     .collect::<Vec<_>>()
             })
             .filter(|arr| !arr.is_empty());
-
-        let use_header = csv_header
-            .and_then(|v| v.as_bool())
-            .unwrap_or(columns.is_none());
-
-        let delimiter = csv_delimiter
-            .and_then(|v| v.as_str())
-            .unwrap_or(",")
-            .to_string();
+        let mut records = RecordReader::new(reader, format, columns, csv_header, csv_delimiter)?;
 
         let mut rows: Vec<Value> = Vec::with_capacity(batch_size);
         let mut inserted = 0usize;
+        let mut table_setup: Option<Value> = None;
 
-        let mut lines = BufReader::new(reader).lines();
-        while let Some(line) = lines.next_line().await? {
+        loop {
             if max_rows.is_some() && inserted + rows.len() >= max_rows.unwrap() {
                 break;
             }
-            let trimmed = line.trim();
-            if trimmed.is_empty() {
-                continue;
-            }
-
-            if format == "jsonl" {
-                let parsed: Value = serde_json::from_str(trimmed)
-                    .map_err(|_| ToolError::invalid_params("jsonl line must be valid JSON"))?;
-                let is_object = parsed.is_object() && !parsed.is_array();
-                if !is_object {
-                    return Err(ToolError::invalid_params("jsonl line must be an object"));
-                }
-                rows.push(parsed);
-            } else {
-                let values = parse_csv_line(trimmed, &delimiter);
-                if use_header && columns.is_none() {
-                    columns = Some(
-                        values
-                            .iter()
-                            .map(|entry| entry.trim().to_string())
-                            .collect(),
-                    );
-                    continue;
-                }
-                let Some(cols) = columns.as_ref() else {
-                    return Err(ToolError::invalid_params("csv columns are required"));
-                };
-                let mut row = serde_json::Map::new();
-                for (idx, col) in cols.iter().enumerate() {
-                    row.insert(
-                        col.clone(),
-                        values
-                            .get(idx)
-                            .map(|v| Value::String(v.clone()))
-                            .unwrap_or(Value::Null),
-                    );
-                }
-                rows.push(Value::Object(row));
-            }
+            let Some(record) = records.next_record().await? else {
+                break;
+            };
+            rows.push(record);
 
             if rows.len() >= batch_size {
                 inserted += self
-                    .flush_rows(postgres_cfg, &rows, columns.as_ref())
+                    .flush_rows(
+                        postgres_cfg,
+                        &rows,
+                        records.columns(),
+                        records.is_csv(),
+                        &mut table_setup,
+                    )
                     .await?;
                 rows.clear();
             }
@@ -142,11 +98,21 @@ impl super::PipelineManager {
 
         if !rows.is_empty() {
             inserted += self
-                .flush_rows(postgres_cfg, &rows, columns.as_ref())
+                .flush_rows(
+                    postgres_cfg,
+                    &rows,
+                    records.columns(),
+                    records.is_csv(),
+                    &mut table_setup,
+                )
                 .await?;
         }
 
-        Ok(serde_json::json!({"inserted": inserted}))
+        let mut out = serde_json::json!({"inserted": inserted});
+        if let Some(setup) = table_setup.filter(|v| !v.is_null()) {
+            out["table_setup"] = setup;
+        }
+        Ok(out)
     }
 
     async fn flush_rows(
@@ -154,12 +120,21 @@ impl super::PipelineManager {
         postgres_cfg: &Value,
         rows: &[Value],
         columns: Option<&Vec<String>>,
+        csv: bool,
+        table_setup: &mut Option<Value>,
     ) -> Result<usize, ToolError> {
         let mut args = postgres_cfg.as_object().cloned().unwrap_or_default();
         args.insert(
             "action".to_string(),
             Value::String("insert_bulk".to_string()),
         );
+        // Table creation runs once, inferred from the first batch; later batches insert only.
+        if table_setup.is_some() {
+            args.remove("create_table");
+            args.remove("primary_key");
+        } else if csv {
+            args.insert("infer_strings".to_string(), Value::Bool(true));
+        }
         args.insert("rows".to_string(), Value::Array(rows.to_vec()));
         if let Some(cols) = columns {
             args.insert(
@@ -171,6 +146,9 @@ impl super::PipelineManager {
             .postgres_manager
             .handle_action(Value::Object(args))
             .await?;
+        if table_setup.is_none() {
+            *table_setup = Some(result.get("table_setup").cloned().unwrap_or(Value::Null));
+        }
         Ok(result
             .get("inserted")
             .and_then(|v| v.as_u64())
@@ -312,9 +290,14 @@ impl super::PipelineManager {
         let export = self.postgres_manager.export_stream(export_args);
         let body = async_read_to_body(export.reader);
 
-        let client = self
-            .api_manager
-            .get_client(config.follow_redirects, config.insecure_ok)?;
+        let client = self.api_manager.get_client(
+            config.follow_redirects,
+            config.insecure_ok,
+            config.tls.as_ref(),
+            config.ssrf.as_ref(),
+            config.proxy.as_ref(),
+            config.tuning.as_ref(),
+        )?;
         let mut req = client.request(config.method.clone(), config.url.clone());
         req = req.headers(config.headers.clone()).body(body);
         if let Some(timeout_ms) = config.timeout_ms {
@@ -372,39 +355,6 @@ fn has_header(headers: &serde_json::Map<String, Value>, needle: &str) -> bool {
     headers.keys().any(|k| k.to_lowercase() == needle)
 }
 
-fn parse_csv_line(line: &str, delimiter: &str) -> Vec<String> {
-    let delim = delimiter.chars().next().unwrap_or(',');
-    let mut out = Vec::new();
-    let mut current = String::new();
-    let mut in_quotes = false;
-
-    let chars: Vec<char> = line.chars().collect();
-    let mut idx = 0usize;
-    while idx < chars.len() {
-        let ch = chars[idx];
-        if ch == '"' {
-            if in_quotes && idx + 1 < chars.len() && chars[idx + 1] == '"' {
-                current.push('"');
-                idx += 2;
-                continue;
-            }
-            in_quotes = !in_quotes;
-            idx += 1;
-            continue;
-        }
-        if ch == delim && !in_quotes {
-            out.push(current.clone());
-            current.clear();
-            idx += 1;
-            continue;
-        }
-        current.push(ch);
-        idx += 1;
-    }
-    out.push(current);
-    out
-}
-
 fn async_read_to_body(reader: DuplexStream) -> reqwest::Body {
     let stream = futures::stream::try_unfold(reader, |mut reader| async move {
         let mut buf = vec![0u8; 64 * 1024];
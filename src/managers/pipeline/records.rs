@@ -0,0 +1,99 @@
+use crate::errors::ToolError;
+use crate::utils::text_parse::split_csv_line;
+use serde_json::Value;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader, Lines};
+
+// Reads a JSONL or CSV stream one record (JSON object) at a time. CSV values stay strings; the
+// header line, when used, names the columns.
+pub(super) struct RecordReader<R> {
+    lines: Lines<BufReader<R>>,
+    csv: bool,
+    columns: Option<Vec<String>>,
+    use_header: bool,
+    delimiter: char,
+}
+
+impl<R: AsyncRead + Unpin> RecordReader<R> {
+    pub(super) fn new(
+        reader: R,
+        format: Option<&Value>,
+        columns: Option<Vec<String>>,
+        csv_header: Option<&Value>,
+        csv_delimiter: Option<&Value>,
+    ) -> Result<Self, ToolError> {
+        let format = format
+            .and_then(|v| v.as_str())
+            .unwrap_or("jsonl")
+            .trim()
+            .to_lowercase();
+        if format != "jsonl" && format != "csv" {
+            return Err(ToolError::invalid_params("format must be jsonl or csv"));
+        }
+        let use_header = csv_header
+            .and_then(|v| v.as_bool())
+            .unwrap_or(columns.is_none());
+        let delimiter = csv_delimiter
+            .and_then(|v| v.as_str())
+            .and_then(|s| s.chars().next())
+            .unwrap_or(',');
+        Ok(Self {
+            lines: BufReader::new(reader).lines(),
+            csv: format == "csv",
+            columns,
+            use_header,
+            delimiter,
+        })
+    }
+
+    pub(super) fn is_csv(&self) -> bool {
+        self.csv
+    }
+
+    pub(super) fn columns(&self) -> Option<&Vec<String>> {
+        self.columns.as_ref()
+    }
+
+    pub(super) async fn next_record(&mut self) -> Result<Option<Value>, ToolError> {
+        while let Some(line) = self.lines.next_line().await? {
+            let trimmed = line.trim();
+            if trimmed.is_empty() {
+                continue;
+            }
+
+            if !self.csv {
+                let parsed: Value = serde_json::from_str(trimmed)
+                    .map_err(|_| ToolError::invalid_params("jsonl line must be valid JSON"))?;
+                if !parsed.is_object() {
+                    return Err(ToolError::invalid_params("jsonl line must be an object"));
+                }
+                return Ok(Some(parsed));
+            }
+
+            let values = split_csv_line(trimmed, self.delimiter);
+            if self.use_header && self.columns.is_none() {
+                self.columns = Some(
+                    values
+                        .iter()
+                        .map(|entry| entry.trim().to_string())
+                        .collect(),
+                );
+                continue;
+            }
+            let Some(cols) = self.columns.as_ref() else {
+                return Err(ToolError::invalid_params("csv columns are required"));
+            };
+            let mut row = serde_json::Map::new();
+            for (idx, col) in cols.iter().enumerate() {
+                row.insert(
+                    col.clone(),
+                    values
+                        .get(idx)
+                        .map(|v| Value::String(v.clone()))
+                        .unwrap_or(Value::Null),
+                );
+            }
+            return Ok(Some(Value::Object(row)));
+        }
+        Ok(None)
+    }
+}
@@ -1,18 +1,57 @@
-use crate::errors::ToolError;
+use crate::errors::{ToolError, ToolErrorKind};
 use crate::managers::ssh::ensure_remote_dir;
+use crate::utils::archive::{decode_reader, open_member, Decompress, ZipMember};
+use crate::utils::usage::{self, Counter};
 use bytes::Bytes;
 use serde_json::Value;
+use sha2::{Digest, Sha256};
 use ssh2::{OpenFlags, OpenType};
+use std::future::Future;
 use std::io::{Read, Write};
 use std::path::Path;
+use std::time::{Duration, Instant};
 use tokio::io::{AsyncReadExt, AsyncWriteExt, DuplexStream};
+use tokio::sync::mpsc::Receiver;
+
+// How often a background upload writes its byte count into the job record.
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
 
 pub(super) struct OpenedSftpStream {
     pub(super) reader: DuplexStream,
-    pub(super) completion: tokio::task::JoinHandle<Result<(), ToolError>>,
+    pub(super) completion: tokio::task::JoinHandle<Result<u64, ToolError>>,
+}
+
+// What one source read streams: a remote file or one zip member inside it, optionally gunzipped.
+#[derive(Clone, Debug)]
+pub(super) struct SftpRead {
+    pub(super) remote_path: String,
+    pub(super) member: Option<ZipMember>,
+    pub(super) decompress: Decompress,
+}
+
+fn read_error(remote_path: &str, err: std::io::Error) -> ToolError {
+    match err.kind() {
+        std::io::ErrorKind::InvalidData | std::io::ErrorKind::InvalidInput => {
+            ToolError::invalid_params(format!("{}: {}", remote_path, err))
+        }
+        _ => ToolError::internal(err.to_string()),
+    }
 }
 
 impl super::PipelineManager {
+    pub(super) fn plain_sftp_read(&self, sftp_args: &Value) -> Result<SftpRead, ToolError> {
+        let remote_path = self.validation.ensure_string(
+            sftp_args.get("remote_path").unwrap_or(&Value::Null),
+            "remote_path",
+            true,
+        )?;
+        Ok(SftpRead {
+            remote_path,
+            member: None,
+            decompress: Decompress::None,
+        })
+    }
+
     pub(super) async fn open_sftp_stream(
         &self,
         sftp_args: &Value,
@@ -20,41 +59,53 @@ impl super::PipelineManager {
         if !sftp_args.is_object() {
             return Err(ToolError::invalid_params("sftp config is required"));
         }
-        let remote_path = self.validation.ensure_string(
-            sftp_args.get("remote_path").unwrap_or(&Value::Null),
-            "remote_path",
-            true,
-        )?;
+        let read = self.plain_sftp_read(sftp_args)?;
+        self.open_sftp_read(sftp_args, read).await
+    }
 
+    // The completion resolves to the number of bytes handed to the reader.
+    pub(super) async fn open_sftp_read(
+        &self,
+        sftp_args: &Value,
+        read: SftpRead,
+    ) -> Result<OpenedSftpStream, ToolError> {
         let args = sftp_args.clone();
         let ssh_manager = self.ssh_manager.clone();
         let (mut writer, reader) = tokio::io::duplex(64 * 1024);
 
-        let completion = tokio::spawn(async move {
+        let completion = tokio::spawn(usage::in_current_scope(async move {
             let (tx, mut rx) = tokio::sync::mpsc::channel::<Bytes>(8);
-            let remote_clone = remote_path.clone();
-            let read_task = tokio::spawn(async move {
-                ssh_manager
+            let read_task = tokio::spawn(usage::in_current_scope(async move {
+                let sent = ssh_manager
                     .with_sftp(&args, move |sftp| {
-                        let mut file = sftp
-                            .open(Path::new(&remote_clone))
+                        let file = sftp
+                            .open(Path::new(&read.remote_path))
                             .map_err(|err| ToolError::internal(err.to_string()))?;
+                        let source: Box<dyn Read> = match &read.member {
+                            Some(member) => open_member(file, member)?,
+                            None => Box::new(file),
+                        };
+                        let mut source = decode_reader(source, read.decompress)?;
                         let mut buf = [0u8; 64 * 1024];
+                        let mut sent = 0u64;
                         loop {
-                            let n = file
+                            let n = source
                                 .read(&mut buf)
-                                .map_err(|err| ToolError::internal(err.to_string()))?;
+                                .map_err(|err| read_error(&read.remote_path, err))?;
                             if n == 0 {
                                 break;
                             }
                             if tx.blocking_send(Bytes::copy_from_slice(&buf[..n])).is_err() {
                                 break;
                             }
+                            sent += n as u64;
                         }
-                        Ok(())
+                        Ok(sent)
                     })
-                    .await
-            });
+                    .await?;
+                usage::record(Counter::SftpBytesRead, sent);
+                Ok(sent)
+            }));
 
             while let Some(chunk) = rx.recv().await {
                 if writer.write_all(&chunk).await.is_err() {
@@ -65,18 +116,21 @@ impl super::PipelineManager {
 
             read_task
                 .await
-                .map_err(|_| ToolError::internal("SFTP read task failed"))??;
-            Ok(())
-        });
+                .map_err(|_| ToolError::internal("SFTP read task failed"))?
+        }));
 
         Ok(OpenedSftpStream { reader, completion })
     }
 
-    pub(super) async fn upload_stream_to_sftp(
+    // Streams `reader` into `<remote_path>.part` and renames it into place once `source` (the
+    // task feeding the reader) succeeds and the remote copy verifies. Nothing is staged locally:
+    // the bounded channel holds the producer back to the SFTP write speed.
+    pub(super) async fn upload_stream_to_sftp<T>(
         &self,
         reader: &mut DuplexStream,
         sftp_args: &Value,
-    ) -> Result<Value, ToolError> {
+        source: impl Future<Output = Result<T, ToolError>>,
+    ) -> Result<(Value, T), ToolError> {
         if !sftp_args.is_object() {
             return Err(ToolError::invalid_params("sftp config is required"));
         }
@@ -86,69 +140,281 @@ impl super::PipelineManager {
             "remote_path",
             true,
         )?;
-        let overwrite = sftp_args
-            .get("overwrite")
-            .and_then(|v| v.as_bool())
-            .unwrap_or(false);
-        let mkdirs = sftp_args
-            .get("mkdirs")
-            .and_then(|v| v.as_bool())
-            .unwrap_or(false);
+        let target = UploadTarget {
+            part_path: format!("{}.part", remote_path),
+            remote_path: remote_path.clone(),
+            overwrite: sftp_args
+                .get("overwrite")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false),
+            mkdirs: sftp_args
+                .get("mkdirs")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false),
+            keep_partial: sftp_args
+                .get("keep_partial")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false),
+            verify: UploadVerify::parse(sftp_args.get("verify"))?,
+        };
 
         let args = sftp_args.clone();
         let ssh_manager = self.ssh_manager.clone();
-        let (tx, mut rx) = tokio::sync::mpsc::channel::<Bytes>(8);
-        let remote_clone = remote_path.clone();
+        let (tx, mut rx) = tokio::sync::mpsc::channel::<UploadChunk>(8);
 
-        let write_task = tokio::spawn(async move {
+        let write_task = tokio::spawn(usage::in_current_scope(async move {
             ssh_manager
-                .with_sftp(&args, move |sftp| {
-                    if !overwrite && sftp.stat(Path::new(&remote_clone)).is_ok() {
-                        return Err(ToolError::conflict(format!(
-                            "Remote path already exists: {}",
-                            remote_clone
-                        ))
-                        .with_hint("Set overwrite=true to replace it."));
-                    }
-                    if mkdirs {
-                        ensure_remote_dir(sftp, &remote_clone)?;
-                    }
-
-                    let mut remote_file = sftp
-                        .open_mode(
-                            Path::new(&remote_clone),
-                            OpenFlags::WRITE | OpenFlags::CREATE | OpenFlags::TRUNCATE,
-                            0o600,
-                            OpenType::File,
-                        )
-                        .map_err(|err| ToolError::internal(err.to_string()))?;
-
-                    while let Some(chunk) = rx.blocking_recv() {
-                        remote_file
-                            .write_all(&chunk)
-                            .map_err(|err| ToolError::internal(err.to_string()))?;
-                    }
-                    Ok(())
-                })
+                .with_sftp(&args, move |sftp| write_upload(sftp, &mut rx, &target))
                 .await
-        });
+        }));
 
         let mut buf = vec![0u8; 64 * 1024];
-        loop {
-            let n = reader.read(&mut buf).await?;
+        let mut written = 0u64;
+        let mut reported = Instant::now();
+        let mut writer_gone = false;
+        let pumped = loop {
+            let n = match reader.read(&mut buf).await {
+                Ok(n) => n,
+                Err(err) => break Err(ToolError::from(err)),
+            };
             if n == 0 {
-                break;
+                break Ok(());
             }
-            if tx.send(Bytes::copy_from_slice(&buf[..n])).await.is_err() {
-                break;
+            if tx
+                .send(UploadChunk::Data(Bytes::copy_from_slice(&buf[..n])))
+                .await
+                .is_err()
+            {
+                writer_gone = true;
+                break Ok(());
             }
-        }
+            written += n as u64;
+            if reported.elapsed() >= PROGRESS_INTERVAL {
+                reported = Instant::now();
+                self.report_progress(serde_json::json!({
+                    "stage": "sftp_upload",
+                    "remote_path": remote_path,
+                    "bytes_uploaded": written,
+                }));
+            }
+        };
+
+        // When the writer stopped reading, the source may be blocked on a full pipe: report
+        // the writer's error instead of waiting on it.
+        let source_result = match pumped {
+            Ok(()) if writer_gone => None,
+            Ok(()) => Some(source.await),
+            Err(err) => Some(Err(err)),
+        };
+        let value = match source_result {
+            Some(Ok(value)) => {
+                let _ = tx.send(UploadChunk::Commit).await;
+                Some(value)
+            }
+            Some(Err(err)) => {
+                // Closing the channel without a commit discards the `.part` file.
+                drop(tx);
+                let _ = write_task.await;
+                return Err(err);
+            }
+            None => None,
+        };
         drop(tx);
 
-        write_task
+        let mut uploaded = write_task
             .await
             .map_err(|_| ToolError::internal("SFTP upload task failed"))??;
+        let Some(value) = value else {
+            return Err(ToolError::internal(
+                "SFTP upload stopped before the stream ended",
+            ));
+        };
+        usage::record(Counter::SftpBytesWritten, written);
 
-        Ok(serde_json::json!({"success": true, "remote_path": remote_path}))
+        uploaded["success"] = Value::Bool(true);
+        uploaded["remote_path"] = Value::String(remote_path);
+        Ok((uploaded, value))
     }
 }
+
+// What the upload loop hands the SFTP writer. Only `Commit` lets the `.part` file replace the
+// target; a channel that closes without one aborts the upload.
+enum UploadChunk {
+    Data(Bytes),
+    Commit,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum UploadVerify {
+    None,
+    Size,
+    Sha256,
+}
+
+impl UploadVerify {
+    fn parse(value: Option<&Value>) -> Result<Self, ToolError> {
+        let mode = match value {
+            None | Some(Value::Null) => return Ok(Self::Size),
+            Some(Value::String(mode)) => mode.clone(),
+            Some(other) => other.to_string(),
+        };
+        match mode.as_str() {
+            "none" => Ok(Self::None),
+            "size" => Ok(Self::Size),
+            "sha256" => Ok(Self::Sha256),
+            _ => Err(ToolError::invalid_params(format!(
+                "sftp.verify must be one of: none, size, sha256 (got {})",
+                mode
+            ))),
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Size => "size",
+            Self::Sha256 => "sha256",
+        }
+    }
+}
+
+struct UploadTarget {
+    remote_path: String,
+    part_path: String,
+    overwrite: bool,
+    mkdirs: bool,
+    keep_partial: bool,
+    verify: UploadVerify,
+}
+
+fn write_upload(
+    sftp: &ssh2::Sftp,
+    rx: &mut Receiver<UploadChunk>,
+    target: &UploadTarget,
+) -> Result<Value, ToolError> {
+    if !target.overwrite && sftp.stat(Path::new(&target.remote_path)).is_ok() {
+        return Err(ToolError::conflict(format!(
+            "Remote path already exists: {}",
+            target.remote_path
+        ))
+        .with_hint("Set overwrite=true to replace it."));
+    }
+    if target.mkdirs {
+        ensure_remote_dir(sftp, &target.remote_path)?;
+    }
+
+    let outcome = write_part(sftp, rx, target);
+    let Err(err) = outcome else {
+        return outcome;
+    };
+    if !target.keep_partial {
+        let _ = sftp.unlink(Path::new(&target.part_path));
+        return Err(err);
+    }
+    let mut details = err.details.clone().unwrap_or_else(|| serde_json::json!({}));
+    if let Value::Object(map) = &mut details {
+        map.insert(
+            "partial_path".to_string(),
+            Value::String(target.part_path.clone()),
+        );
+    }
+    Err(err.with_details(details))
+}
+
+fn verify_failed(target: &UploadTarget, details: Value) -> ToolError {
+    ToolError::new(
+        ToolErrorKind::Retryable,
+        "SFTP_VERIFY_FAILED",
+        format!(
+            "Uploaded file {} does not match the streamed data",
+            target.part_path
+        ),
+    )
+    .with_hint("Re-run the flow; set sftp.keep_partial=true to inspect the partial file.")
+    .with_details(details)
+}
+
+fn write_part(
+    sftp: &ssh2::Sftp,
+    rx: &mut Receiver<UploadChunk>,
+    target: &UploadTarget,
+) -> Result<Value, ToolError> {
+    let part = Path::new(&target.part_path);
+    let mut remote_file = sftp
+        .open_mode(
+            part,
+            OpenFlags::WRITE | OpenFlags::CREATE | OpenFlags::TRUNCATE,
+            0o600,
+            OpenType::File,
+        )
+        .map_err(|err| ToolError::internal(err.to_string()))?;
+
+    let mut hasher = Sha256::new();
+    let mut bytes = 0u64;
+    loop {
+        match rx.blocking_recv() {
+            Some(UploadChunk::Data(chunk)) => {
+                remote_file
+                    .write_all(&chunk)
+                    .map_err(|err| ToolError::internal(err.to_string()))?;
+                hasher.update(&chunk);
+                bytes += chunk.len() as u64;
+            }
+            Some(UploadChunk::Commit) => break,
+            None => return Err(ToolError::internal("SFTP upload aborted")),
+        }
+    }
+    drop(remote_file);
+    let sha256 = hex::encode(hasher.finalize());
+
+    if target.verify != UploadVerify::None {
+        let remote_bytes = sftp
+            .stat(part)
+            .map_err(|err| ToolError::internal(err.to_string()))?
+            .size;
+        if remote_bytes != Some(bytes) {
+            return Err(verify_failed(
+                target,
+                serde_json::json!({"expected_bytes": bytes, "remote_bytes": remote_bytes}),
+            ));
+        }
+    }
+    if target.verify == UploadVerify::Sha256 {
+        let mut remote_file = sftp
+            .open(part)
+            .map_err(|err| ToolError::internal(err.to_string()))?;
+        let mut remote_hasher = Sha256::new();
+        let mut buf = vec![0u8; 64 * 1024];
+        loop {
+            let n = remote_file
+                .read(&mut buf)
+                .map_err(|err| ToolError::internal(err.to_string()))?;
+            if n == 0 {
+                break;
+            }
+            remote_hasher.update(&buf[..n]);
+        }
+        let remote_sha256 = hex::encode(remote_hasher.finalize());
+        if remote_sha256 != sha256 {
+            return Err(verify_failed(
+                target,
+                serde_json::json!({"expected_sha256": sha256, "remote_sha256": remote_sha256}),
+            ));
+        }
+    }
+
+    // SFTP v3 servers refuse to rename onto an existing file.
+    let remote = Path::new(&target.remote_path);
+    if target.overwrite && sftp.stat(remote).is_ok() {
+        sftp.unlink(remote)
+            .map_err(|err| ToolError::internal(err.to_string()))?;
+    }
+    sftp.rename(part, remote, None)
+        .map_err(|err| ToolError::internal(err.to_string()))?;
+
+    Ok(serde_json::json!({
+        "bytes": bytes,
+        "sha256": sha256,
+        "verified": target.verify.as_str(),
+    }))
+}
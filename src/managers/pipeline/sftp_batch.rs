@@ -0,0 +1,601 @@
+use super::checkpoint::FileCheckpoint;
+use super::http::SftpUpload;
+use super::sftp::SftpRead;
+use super::Trace;
+use crate::errors::ToolError;
+use crate::managers::ssh::ensure_remote_dir;
+use crate::utils::archive::{zip_members, Decompress, ZipMember};
+use crate::utils::redact::redact_text;
+use crate::utils::sftp_listing::glob_to_regex;
+use regex::Regex;
+use reqwest::header::{HeaderMap, HeaderValue};
+use serde_json::Value;
+use std::path::Path;
+
+const SOURCE_FILE_HEADER: &str = "x-source-file";
+const SOURCE_MEMBER_HEADER: &str = "x-source-member";
+
+// Any of these in the sftp block (or a top-level checkpoint) switches the source to batch mode.
+const BATCH_KEYS: &[&str] = &[
+    "remote_glob",
+    "decompress",
+    "archive",
+    "archive_member_glob",
+    "post_process",
+    "done_dir",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PostProcess {
+    None,
+    Move,
+    Delete,
+}
+
+struct MemberGlob {
+    raw: String,
+    pattern: Regex,
+    // Globs with a `/` match the full member path, others only its file name.
+    full_path: bool,
+}
+
+impl MemberGlob {
+    fn matches(&self, member: &ZipMember) -> bool {
+        if self.full_path {
+            self.pattern.is_match(&member.name)
+        } else {
+            self.pattern.is_match(member.file_name())
+        }
+    }
+}
+
+enum Selection {
+    Path(String),
+    Glob {
+        raw: String,
+        dir: String,
+        pattern: Regex,
+    },
+}
+
+pub(super) struct SftpBatch {
+    selection: Selection,
+    decompress: Decompress,
+    archive: bool,
+    member_glob: Option<MemberGlob>,
+    post_process: PostProcess,
+    done_dir: Option<String>,
+    checkpoint: Option<String>,
+}
+
+pub(super) enum BatchSink<'a> {
+    Postgres(&'a Value),
+    Http(&'a SftpUpload),
+}
+
+struct RemoteFile {
+    path: String,
+    name: String,
+    size: Option<u64>,
+    mtime: Option<u64>,
+}
+
+struct Delivered {
+    member: Option<String>,
+    rows: Option<u64>,
+    bytes: u64,
+    http: Option<Value>,
+}
+
+fn text<'a>(cfg: &'a Value, key: &str) -> Result<Option<&'a str>, ToolError> {
+    match cfg.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(raw)) => Ok(Some(raw.trim()).filter(|s| !s.is_empty())),
+        Some(_) => Err(ToolError::invalid_params(format!(
+            "sftp.{} must be a string",
+            key
+        ))),
+    }
+}
+
+fn has_wildcard(text: &str) -> bool {
+    text.contains(['*', '?', '['])
+}
+
+// Batch options from the hydrated run args; None keeps the single-file flow and its result shape.
+pub(super) fn parse_sftp_batch(args: &Value) -> Result<Option<SftpBatch>, ToolError> {
+    let cfg = args.get("sftp").unwrap_or(&Value::Null);
+    let checkpoint = match args.get("checkpoint") {
+        None | Some(Value::Null) => None,
+        Some(Value::String(name)) => Some(name.trim().to_string()),
+        Some(_) => return Err(ToolError::invalid_params("checkpoint must be a string")),
+    };
+    if checkpoint.is_none()
+        && !BATCH_KEYS
+            .iter()
+            .any(|key| cfg.get(*key).is_some_and(|v| !v.is_null()))
+    {
+        return Ok(None);
+    }
+
+    let selection = match (text(cfg, "remote_path")?, text(cfg, "remote_glob")?) {
+        (Some(_), Some(_)) => {
+            return Err(ToolError::invalid_params(
+                "sftp.remote_path and sftp.remote_glob are mutually exclusive",
+            ))
+        }
+        (Some(path), None) => Selection::Path(path.to_string()),
+        (None, Some(glob)) => {
+            let (dir, name) = match glob.rsplit_once('/') {
+                Some(("", name)) => ("/", name),
+                Some((dir, name)) => (dir, name),
+                None => (".", glob),
+            };
+            if has_wildcard(dir) {
+                return Err(ToolError::invalid_params(
+                    "sftp.remote_glob wildcards are only supported in the file name",
+                )
+                .with_hint("List one directory per run, e.g. /inbox/data-*.csv.gz"));
+            }
+            Selection::Glob {
+                raw: glob.to_string(),
+                dir: dir.to_string(),
+                pattern: glob_to_regex(name)?,
+            }
+        }
+        (None, None) => {
+            return Err(ToolError::invalid_params(
+                "sftp.remote_path or sftp.remote_glob is required",
+            ))
+        }
+    };
+
+    let archive = match text(cfg, "archive")?.map(str::to_lowercase).as_deref() {
+        None | Some("none") => false,
+        Some("zip") => true,
+        Some(other) => {
+            return Err(ToolError::invalid_params(format!(
+                "sftp.archive must be zip or none (got {})",
+                other
+            )))
+        }
+    };
+    let member_glob = match text(cfg, "archive_member_glob")? {
+        Some(_) if !archive => {
+            return Err(ToolError::invalid_params(
+                "sftp.archive_member_glob requires sftp.archive=zip",
+            ))
+        }
+        Some(raw) => Some(MemberGlob {
+            raw: raw.to_string(),
+            pattern: glob_to_regex(raw)?,
+            full_path: raw.contains('/'),
+        }),
+        None => None,
+    };
+    let decompress = Decompress::parse(cfg.get("decompress"))
+        .map_err(|err| ToolError::invalid_params(format!("sftp.{}", err.message)))?;
+
+    let post_process = match text(cfg, "post_process")?.map(str::to_lowercase).as_deref() {
+        None | Some("none") => PostProcess::None,
+        Some("move") => PostProcess::Move,
+        Some("delete") => PostProcess::Delete,
+        Some(other) => {
+            return Err(ToolError::invalid_params(format!(
+                "sftp.post_process must be one of: move, delete, none (got {})",
+                other
+            )))
+        }
+    };
+    let done_dir = text(cfg, "done_dir")?.map(|dir| dir.trim_end_matches('/').to_string());
+    match (post_process, &done_dir) {
+        (PostProcess::Move, None) => {
+            return Err(ToolError::invalid_params(
+                "sftp.post_process=move requires sftp.done_dir",
+            ))
+        }
+        (PostProcess::None | PostProcess::Delete, Some(_)) => {
+            return Err(ToolError::invalid_params(
+                "sftp.done_dir requires sftp.post_process=move",
+            ))
+        }
+        _ => {}
+    }
+
+    Ok(Some(SftpBatch {
+        selection,
+        decompress,
+        archive,
+        member_glob,
+        post_process,
+        done_dir,
+        checkpoint,
+    }))
+}
+
+fn error_json(stage: &str, err: &ToolError) -> Value {
+    serde_json::json!({
+        "stage": stage,
+        "kind": err.kind,
+        "code": err.code,
+        "message": redact_text(&err.message, 2048, None),
+    })
+}
+
+fn header_value(raw: &str) -> HeaderValue {
+    HeaderValue::from_str(raw).unwrap_or_else(|_| HeaderValue::from_static("invalid"))
+}
+
+impl super::PipelineManager {
+    // Files run one at a time in name order, each as its own sink batch. The first failure stops
+    // the run and leaves later files pending; with a checkpoint, a rerun skips completed files.
+    pub(super) async fn run_sftp_batch(
+        &self,
+        flow: &str,
+        batch: &SftpBatch,
+        sink: BatchSink<'_>,
+        hydrated: &Value,
+        trace: &Trace,
+    ) -> Result<Value, ToolError> {
+        let sftp_cfg = hydrated.get("sftp").unwrap_or(&Value::Null);
+        let mut checkpoint = batch
+            .checkpoint
+            .as_deref()
+            .map(|name| FileCheckpoint::load(name, flow))
+            .transpose()?;
+        let files = self.list_batch_files(sftp_cfg, batch).await?;
+
+        let mut reports = Vec::with_capacity(files.len());
+        let mut counts = serde_json::Map::new();
+        let mut total_rows = 0u64;
+        let mut total_bytes = 0u64;
+        let mut stopped = false;
+        for file in &files {
+            let mut report = serde_json::json!({"path": file.path, "size": file.size});
+            let status = if stopped {
+                "pending"
+            } else if checkpoint
+                .as_ref()
+                .is_some_and(|cp| cp.is_done(&file.path, file.size, file.mtime))
+            {
+                // A completed file that is still listed missed its post-processing last time.
+                match self.post_process_file(sftp_cfg, batch, file).await {
+                    Ok(done) => {
+                        report["post_process"] = done;
+                        "skipped"
+                    }
+                    Err(err) => {
+                        report["error"] = error_json("post_process", &err);
+                        "failed"
+                    }
+                }
+            } else {
+                match self
+                    .deliver_file(sftp_cfg, batch, &sink, hydrated, file, trace)
+                    .await
+                {
+                    Ok(delivered) => {
+                        let rows = delivered.iter().map(|d| d.rows).sum::<Option<u64>>();
+                        let bytes = delivered.iter().map(|d| d.bytes).sum::<u64>();
+                        total_rows += rows.unwrap_or(0);
+                        total_bytes += bytes;
+                        report["rows"] = Value::from(rows);
+                        report["bytes"] = Value::from(bytes);
+                        if batch.archive {
+                            report["members"] = delivered
+                                .iter()
+                                .map(|d| {
+                                    serde_json::json!({
+                                        "name": d.member,
+                                        "rows": d.rows,
+                                        "bytes": d.bytes,
+                                        "http": d.http,
+                                    })
+                                })
+                                .collect();
+                        } else if let Some(http) = delivered.first().and_then(|d| d.http.clone()) {
+                            report["http"] = http;
+                        }
+                        let recorded = match checkpoint.as_mut() {
+                            Some(cp) => cp.record(&file.path, file.size, file.mtime),
+                            None => Ok(()),
+                        };
+                        match recorded {
+                            Ok(()) => match self.post_process_file(sftp_cfg, batch, file).await {
+                                Ok(done) => {
+                                    report["post_process"] = done;
+                                    "done"
+                                }
+                                Err(err) => {
+                                    report["error"] = error_json("post_process", &err);
+                                    "failed"
+                                }
+                            },
+                            Err(err) => {
+                                report["error"] = error_json("checkpoint", &err);
+                                "failed"
+                            }
+                        }
+                    }
+                    Err((stage, err)) => {
+                        report["error"] = error_json(stage, &err);
+                        "failed"
+                    }
+                }
+            };
+            stopped |= status == "failed";
+            report["status"] = Value::from(status);
+            let count = counts.entry(status.to_string()).or_insert(Value::from(0));
+            *count = Value::from(count.as_u64().unwrap_or(0) + 1);
+            if status != "pending" {
+                self.audit_stage(
+                    "sftp_file",
+                    trace,
+                    serde_json::json!({
+                        "remote_path": file.path,
+                        "status": status,
+                        "rows": report.get("rows"),
+                        "bytes": report.get("bytes"),
+                    }),
+                    None,
+                );
+            }
+            reports.push(report);
+        }
+
+        let source = match &batch.selection {
+            Selection::Path(path) => serde_json::json!({"remote_path": path}),
+            Selection::Glob { raw, .. } => serde_json::json!({"remote_glob": raw}),
+        };
+        let mut sftp = source;
+        sftp["matched"] = Value::from(files.len());
+        sftp["decompress"] = Value::from(batch.decompress.as_str());
+        sftp["archive"] = Value::from(if batch.archive { "zip" } else { "none" });
+        sftp["post_process"] = Value::from(match batch.post_process {
+            PostProcess::None => "none",
+            PostProcess::Move => "move",
+            PostProcess::Delete => "delete",
+        });
+        let count = |status: &str| counts.get(status).cloned().unwrap_or(Value::from(0));
+        Ok(serde_json::json!({
+            "success": !stopped,
+            "flow": flow,
+            "sftp": sftp,
+            "files": reports,
+            "totals": {
+                "files": files.len(),
+                "done": count("done"),
+                "skipped": count("skipped"),
+                "failed": count("failed"),
+                "pending": count("pending"),
+                "rows": matches!(sink, BatchSink::Postgres(_)).then_some(total_rows),
+                "bytes": total_bytes,
+            },
+            "checkpoint": checkpoint.as_ref().map(FileCheckpoint::summary),
+        }))
+    }
+
+    async fn list_batch_files(
+        &self,
+        sftp_cfg: &Value,
+        batch: &SftpBatch,
+    ) -> Result<Vec<RemoteFile>, ToolError> {
+        match &batch.selection {
+            Selection::Path(path) => {
+                let path = path.clone();
+                self.ssh_manager
+                    .with_sftp(sftp_cfg, move |sftp| {
+                        let stat = sftp.stat(Path::new(&path)).map_err(|_| {
+                            ToolError::not_found(format!("Remote path not found: {}", path))
+                        })?;
+                        let name = path.rsplit('/').next().unwrap_or(&path).to_string();
+                        Ok(vec![RemoteFile {
+                            name,
+                            size: stat.size,
+                            mtime: stat.mtime,
+                            path,
+                        }])
+                    })
+                    .await
+            }
+            Selection::Glob { dir, pattern, .. } => {
+                let dir = dir.clone();
+                let pattern = pattern.clone();
+                self.ssh_manager
+                    .with_sftp(sftp_cfg, move |sftp| {
+                        let entries = sftp.readdir(Path::new(&dir)).map_err(|err| {
+                            ToolError::internal(format!("readdir {} failed: {}", dir, err))
+                        })?;
+                        let mut files: Vec<RemoteFile> = entries
+                            .into_iter()
+                            .filter(|(_, stat)| stat.is_file())
+                            .filter_map(|(path, stat)| {
+                                let name = path.file_name()?.to_string_lossy().to_string();
+                                pattern.is_match(&name).then(|| RemoteFile {
+                                    path: path.to_string_lossy().to_string(),
+                                    name,
+                                    size: stat.size,
+                                    mtime: stat.mtime,
+                                })
+                            })
+                            .collect();
+                        files.sort_by(|a, b| a.name.cmp(&b.name));
+                        Ok(files)
+                    })
+                    .await
+            }
+        }
+    }
+
+    // Every selected read of one file through the sink; errors carry the stage that failed.
+    async fn deliver_file(
+        &self,
+        sftp_cfg: &Value,
+        batch: &SftpBatch,
+        sink: &BatchSink<'_>,
+        hydrated: &Value,
+        file: &RemoteFile,
+        trace: &Trace,
+    ) -> Result<Vec<Delivered>, (&'static str, ToolError)> {
+        let reads = if batch.archive {
+            let path = file.path.clone();
+            let members = self
+                .ssh_manager
+                .with_sftp(sftp_cfg, move |sftp| {
+                    let mut archive = sftp
+                        .open(Path::new(&path))
+                        .map_err(|err| ToolError::internal(err.to_string()))?;
+                    zip_members(&mut archive)
+                })
+                .await
+                .map_err(|err| ("archive", err))?;
+            let selected: Vec<ZipMember> = members
+                .into_iter()
+                .filter(|member| batch.member_glob.as_ref().is_none_or(|g| g.matches(member)))
+                .collect();
+            if selected.is_empty() {
+                let wanted = batch.member_glob.as_ref().map(|g| g.raw.as_str());
+                return Err((
+                    "archive",
+                    ToolError::not_found(format!(
+                        "No members of {} match {}",
+                        file.path,
+                        wanted.unwrap_or("*")
+                    )),
+                ));
+            }
+            selected
+                .into_iter()
+                .map(|member| SftpRead {
+                    remote_path: file.path.clone(),
+                    member: Some(member),
+                    decompress: batch.decompress,
+                })
+                .collect()
+        } else {
+            vec![SftpRead {
+                remote_path: file.path.clone(),
+                member: None,
+                decompress: batch.decompress,
+            }]
+        };
+
+        let mut delivered = Vec::with_capacity(reads.len());
+        for read in reads {
+            let member = read.member.as_ref().map(|m| m.name.clone());
+            let outcome = match sink {
+                BatchSink::Postgres(pg_cfg) => {
+                    let mut opened = self
+                        .open_sftp_read(sftp_cfg, read)
+                        .await
+                        .map_err(|err| ("download", err))?;
+                    let ingest = self
+                        .ingest_stream(
+                            &mut opened.reader,
+                            pg_cfg,
+                            hydrated.get("format"),
+                            hydrated.get("batch_size"),
+                            hydrated.get("max_rows"),
+                            hydrated.get("csv_header"),
+                            hydrated.get("csv_delimiter"),
+                        )
+                        .await;
+                    drop(opened.reader);
+                    let bytes = opened
+                        .completion
+                        .await
+                        .map_err(|_| ("download", ToolError::internal("SFTP stream task failed")))?
+                        .map_err(|err| ("download", err))?;
+                    let ingest = ingest.map_err(|err| ("insert", err))?;
+                    Delivered {
+                        member,
+                        rows: ingest.get("inserted").and_then(|v| v.as_u64()),
+                        bytes,
+                        http: None,
+                    }
+                }
+                BatchSink::Http(upload) => {
+                    let mut headers = HeaderMap::new();
+                    headers.insert(SOURCE_FILE_HEADER, header_value(&file.name));
+                    if let Some(member) = &member {
+                        headers.insert(SOURCE_MEMBER_HEADER, header_value(member));
+                    }
+                    let (result, bytes) = self
+                        .send_sftp_upload(upload, sftp_cfg, &read, &headers, trace)
+                        .await
+                        .map_err(|err| ("upload", err))?;
+                    let status = result["http"]["status"].as_u64().unwrap_or(0);
+                    if result["success"] != Value::Bool(true) {
+                        let response = result["http"]["response"].as_str().unwrap_or("");
+                        return Err((
+                            "upload",
+                            ToolError::invalid_params(format!(
+                                "HTTP sink rejected {} ({})",
+                                member.as_deref().unwrap_or(&file.name),
+                                status
+                            ))
+                            .with_details(serde_json::json!({
+                                "response": redact_text(response, 4096, None),
+                            })),
+                        ));
+                    }
+                    Delivered {
+                        member,
+                        rows: None,
+                        bytes,
+                        http: Some(serde_json::json!({
+                            "status": status,
+                            "attempts": result["attempts"],
+                        })),
+                    }
+                }
+            };
+            delivered.push(outcome);
+        }
+        Ok(delivered)
+    }
+
+    async fn post_process_file(
+        &self,
+        sftp_cfg: &Value,
+        batch: &SftpBatch,
+        file: &RemoteFile,
+    ) -> Result<Value, ToolError> {
+        let source = file.path.clone();
+        match batch.post_process {
+            PostProcess::None => Ok(Value::Null),
+            PostProcess::Delete => {
+                self.ssh_manager
+                    .with_sftp(sftp_cfg, move |sftp| {
+                        sftp.unlink(Path::new(&source))
+                            .map_err(|err| ToolError::internal(err.to_string()))
+                    })
+                    .await?;
+                Ok(serde_json::json!({"action": "deleted"}))
+            }
+            PostProcess::Move => {
+                let target = format!(
+                    "{}/{}",
+                    batch.done_dir.as_deref().unwrap_or_default(),
+                    file.name
+                );
+                let dest = target.clone();
+                self.ssh_manager
+                    .with_sftp(sftp_cfg, move |sftp| {
+                        if sftp.stat(Path::new(&dest)).is_ok() {
+                            return Err(ToolError::conflict(format!(
+                                "done_dir already holds {}",
+                                dest
+                            ))
+                            .with_hint("Clear the earlier copy or use post_process=delete."));
+                        }
+                        ensure_remote_dir(sftp, &dest)?;
+                        sftp.rename(Path::new(&source), Path::new(&dest), None)
+                            .map_err(|err| ToolError::internal(err.to_string()))
+                    })
+                    .await?;
+                Ok(serde_json::json!({"action": "moved", "to": target}))
+            }
+        }
+    }
+}
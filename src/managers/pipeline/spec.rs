@@ -0,0 +1,394 @@
+use crate::errors::ToolError;
+use crate::utils::suggest::suggest;
+use serde_json::Value;
+
+// The flow table behind `pipeline describe` and the block checks in `run`, so the documented
+// argument structure is the one that is enforced.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum Role {
+    Source,
+    Sink,
+}
+
+impl Role {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Role::Source => "source",
+            Role::Sink => "sink",
+        }
+    }
+}
+
+pub(super) struct BlockSpec {
+    pub(super) block: &'static str,
+    pub(super) role: Role,
+    // Fields run() rejects the call without.
+    pub(super) required: &'static [&'static str],
+    // (required field, field that stands in for it).
+    pub(super) alternatives: &'static [(&'static str, &'static str)],
+    // Any one of these selects the endpoint; the project target binding fills it otherwise.
+    pub(super) connection: &'static [&'static str],
+    pub(super) target_binding: &'static str,
+    pub(super) optional: &'static [&'static str],
+    pub(super) tool: &'static str,
+    pub(super) actions: &'static [&'static str],
+}
+
+pub(super) struct FlowSpec {
+    pub(super) name: &'static str,
+    pub(super) summary: &'static str,
+    pub(super) source: BlockSpec,
+    pub(super) sink: BlockSpec,
+    // Top-level arguments next to the blocks.
+    pub(super) options: &'static [&'static str],
+    pub(super) example: &'static str,
+    pub(super) extended_example: &'static str,
+}
+
+const HTTP_CONNECTION: &[&str] = &["url", "profile_name", "base_url"];
+const SFTP_CONNECTION: &[&str] = &["profile_name", "connection"];
+const POSTGRES_CONNECTION: &[&str] = &["profile_name", "connection", "connection_url"];
+
+const HTTP_SOURCE: BlockSpec = BlockSpec {
+    block: "http",
+    role: Role::Source,
+    required: &[],
+    alternatives: &[],
+    connection: HTTP_CONNECTION,
+    target_binding: "api_profile",
+    optional: &[
+        "path",
+        "method",
+        "query",
+        "headers",
+        "auth",
+        "body",
+        "cache",
+        "retry",
+        "timeout_ms",
+    ],
+    tool: "api",
+    actions: &["request", "download"],
+};
+
+const HTTP_SINK: BlockSpec = BlockSpec {
+    block: "http",
+    role: Role::Sink,
+    required: &[],
+    alternatives: &[],
+    connection: HTTP_CONNECTION,
+    target_binding: "api_profile",
+    optional: &[
+        "path",
+        "method",
+        "query",
+        "headers",
+        "auth",
+        "retry",
+        "timeout_ms",
+        "body_template",
+        "path_template",
+        "per",
+        "batch_size",
+        "missing",
+        "concurrency",
+    ],
+    tool: "api",
+    actions: &["request"],
+};
+
+const SFTP_SOURCE: BlockSpec = BlockSpec {
+    block: "sftp",
+    role: Role::Source,
+    required: &["remote_path"],
+    alternatives: &[("remote_path", "remote_glob")],
+    connection: SFTP_CONNECTION,
+    target_binding: "ssh_profile",
+    optional: &[
+        "remote_glob",
+        "decompress",
+        "archive",
+        "archive_member_glob",
+        "post_process",
+        "done_dir",
+    ],
+    tool: "ssh",
+    actions: &["sftp_download"],
+};
+
+const SFTP_SINK: BlockSpec = BlockSpec {
+    block: "sftp",
+    role: Role::Sink,
+    required: &["remote_path"],
+    alternatives: &[],
+    connection: SFTP_CONNECTION,
+    target_binding: "ssh_profile",
+    optional: &["overwrite", "mkdirs", "keep_partial", "verify"],
+    tool: "ssh",
+    actions: &["sftp_upload"],
+};
+
+const POSTGRES_SOURCE: BlockSpec = BlockSpec {
+    block: "postgres",
+    role: Role::Source,
+    required: &["table"],
+    alternatives: &[],
+    connection: POSTGRES_CONNECTION,
+    target_binding: "postgres_profile",
+    optional: &["schema"],
+    tool: "sql",
+    actions: &["export", "select"],
+};
+
+const POSTGRES_SINK: BlockSpec = BlockSpec {
+    block: "postgres",
+    role: Role::Sink,
+    required: &["table"],
+    alternatives: &[],
+    connection: POSTGRES_CONNECTION,
+    target_binding: "postgres_profile",
+    optional: &["schema", "columns", "create_table", "primary_key"],
+    tool: "sql",
+    actions: &["insert_bulk"],
+};
+
+const EXPORT_OPTIONS: &[&str] = &[
+    "format",
+    "batch_size",
+    "limit",
+    "offset",
+    "csv_header",
+    "csv_delimiter",
+    "columns",
+    "columns_sql",
+    "order_by",
+    "order_by_sql",
+    "filters",
+    "where_sql",
+    "where_params",
+    "timeout_ms",
+];
+
+pub(super) const FLOWS: &[FlowSpec] = &[
+    FlowSpec {
+        name: "http_to_sftp",
+        summary: "Stream an HTTP response body into a remote file.",
+        source: HTTP_SOURCE,
+        sink: SFTP_SINK,
+        options: &["cache"],
+        example: r#"{"action":"run","flow":"http_to_sftp","http":{"url":"https://example.com/report.csv"},"sftp":{"profile_name":"web-1","remote_path":"/srv/data/report.csv"}}"#,
+        extended_example: r#"{"action":"run","flow":"http_to_sftp","project":"shop","target":"prod","http":{"path":"/exports/report.csv","headers":{"Accept":"text/csv"}},"sftp":{"remote_path":"/srv/data/report.csv","overwrite":true,"mkdirs":true},"cache":{"enabled":true,"ttl_ms":600000}}"#,
+    },
+    FlowSpec {
+        name: "sftp_to_http",
+        summary: "Upload a remote file, or each file matching remote_glob, as an HTTP request body (PUT by default); with http.body_template/path_template, one request per JSONL/CSV record or batch.",
+        source: SFTP_SOURCE,
+        sink: HTTP_SINK,
+        options: &[
+            "checkpoint",
+            "format",
+            "csv_header",
+            "csv_delimiter",
+            "dry_run",
+        ],
+        example: r#"{"action":"run","flow":"sftp_to_http","sftp":{"profile_name":"web-1","remote_path":"/var/log/app.log"},"http":{"url":"https://upload.example.com/logs/app.log"}}"#,
+        extended_example: r#"{"action":"run","flow":"sftp_to_http","project":"shop","target":"prod","sftp":{"remote_path":"/var/log/app.log"},"http":{"path":"/logs/app.log","method":"POST","headers":{"Content-Type":"text/plain"},"retry":{"max_attempts":3}}}"#,
+    },
+    FlowSpec {
+        name: "http_to_postgres",
+        summary: "Insert JSONL or CSV rows from an HTTP response into a table.",
+        source: HTTP_SOURCE,
+        sink: POSTGRES_SINK,
+        options: &[
+            "format",
+            "batch_size",
+            "max_rows",
+            "csv_header",
+            "csv_delimiter",
+            "cache",
+        ],
+        example: r#"{"action":"run","flow":"http_to_postgres","http":{"url":"https://example.com/events.jsonl"},"postgres":{"profile_name":"analytics","table":"events"}}"#,
+        extended_example: r#"{"action":"run","flow":"http_to_postgres","project":"shop","target":"prod","http":{"path":"/events.csv"},"postgres":{"table":"events","schema":"staging","columns":["id","kind","at"]},"format":"csv","csv_header":false,"batch_size":1000,"max_rows":50000}"#,
+    },
+    FlowSpec {
+        name: "sftp_to_postgres",
+        summary: "Insert JSONL or CSV rows from a remote file, or from each file matching remote_glob, into a table.",
+        source: SFTP_SOURCE,
+        sink: POSTGRES_SINK,
+        options: &[
+            "format",
+            "batch_size",
+            "max_rows",
+            "csv_header",
+            "csv_delimiter",
+            "checkpoint",
+        ],
+        example: r#"{"action":"run","flow":"sftp_to_postgres","sftp":{"profile_name":"etl-1","remote_path":"/exports/orders.jsonl"},"postgres":{"profile_name":"warehouse","table":"orders"}}"#,
+        extended_example: r#"{"action":"run","flow":"sftp_to_postgres","project":"shop","target":"prod","sftp":{"remote_glob":"/inbox/orders-*.csv.gz","decompress":"auto","post_process":"move","done_dir":"/inbox/done"},"postgres":{"table":"orders","schema":"staging"},"format":"csv","csv_header":true,"csv_delimiter":";","batch_size":1000,"checkpoint":"orders-inbox"}"#,
+    },
+    FlowSpec {
+        name: "postgres_to_sftp",
+        summary: "Export a table as CSV or JSONL into a remote file.",
+        source: POSTGRES_SOURCE,
+        sink: SFTP_SINK,
+        options: EXPORT_OPTIONS,
+        example: r#"{"action":"run","flow":"postgres_to_sftp","postgres":{"profile_name":"warehouse","table":"orders"},"sftp":{"profile_name":"etl-1","remote_path":"/exports/orders.csv"}}"#,
+        extended_example: r#"{"action":"run","flow":"postgres_to_sftp","project":"shop","target":"prod","postgres":{"table":"orders","schema":"public"},"sftp":{"remote_path":"/exports/orders.jsonl","overwrite":true},"format":"jsonl","columns":["id","total","created_at"],"filters":{"status":"paid"},"order_by":["id"],"limit":100000}"#,
+    },
+    FlowSpec {
+        name: "postgres_to_http",
+        summary: "Export a table as one HTTP request body (POST by default), as retried chunks with chunk_rows, or as one templated request per row or batch with http.body_template/path_template.",
+        source: POSTGRES_SOURCE,
+        sink: HTTP_SINK,
+        options: &[
+            "format",
+            "batch_size",
+            "limit",
+            "offset",
+            "csv_header",
+            "csv_delimiter",
+            "columns",
+            "columns_sql",
+            "order_by",
+            "order_by_sql",
+            "filters",
+            "where_sql",
+            "where_params",
+            "timeout_ms",
+            "chunk_rows",
+            "chunk_format",
+            "chunk_headers",
+            "resume_from_chunk",
+            "finalize",
+            "dry_run",
+        ],
+        example: r#"{"action":"run","flow":"postgres_to_http","postgres":{"profile_name":"warehouse","table":"orders"},"http":{"url":"https://ingest.example.com/orders"}}"#,
+        extended_example: r#"{"action":"run","flow":"postgres_to_http","project":"shop","target":"prod","postgres":{"table":"orders"},"http":{"path":"/ingest/orders/"},"order_by":["id"],"chunk_rows":5000,"chunk_format":"ndjson","chunk_headers":true,"finalize":{"path":"commit","method":"POST"}}"#,
+    },
+];
+
+pub(super) fn flow_names() -> Vec<&'static str> {
+    FLOWS.iter().map(|flow| flow.name).collect()
+}
+
+pub(super) fn find_flow(name: &str) -> Result<&'static FlowSpec, ToolError> {
+    if let Some(flow) = FLOWS.iter().find(|flow| flow.name == name) {
+        return Ok(flow);
+    }
+    let known: Vec<String> = flow_names().into_iter().map(str::to_string).collect();
+    let suggestions = suggest(name, &known, 3);
+    Err(
+        ToolError::invalid_params(format!("Unknown pipeline flow: {}", name))
+            .with_hint(format!("Use one of: {}", known.join(", ")))
+            .with_details(serde_json::json!({
+                "known_flows": known,
+                "did_you_mean": suggestions,
+            })),
+    )
+}
+
+fn parse_example(raw: &str) -> Value {
+    serde_json::from_str(raw).unwrap_or(Value::Null)
+}
+
+fn describe_block(block: &BlockSpec) -> Value {
+    serde_json::json!({
+        "block": block.block,
+        "role": block.role.as_str(),
+        "required": block.required,
+        "alternatives": block
+            .alternatives
+            .iter()
+            .map(|(field, alternative)| (field.to_string(), Value::from(*alternative)))
+            .collect::<serde_json::Map<_, _>>(),
+        "connection": {
+            "one_of": block.connection,
+            "project_target_binding": block.target_binding,
+        },
+        "optional": block.optional,
+        "help": { "tool": block.tool, "actions": block.actions },
+    })
+}
+
+impl FlowSpec {
+    pub(super) fn describe(&self, extended: bool) -> Value {
+        let mut out = serde_json::json!({
+            "flow": self.name,
+            "summary": self.summary,
+            "source": describe_block(&self.source),
+            "sink": describe_block(&self.sink),
+            "options": self.options,
+            "example": parse_example(self.example),
+        });
+        if extended {
+            out["extended_example"] = parse_example(self.extended_example);
+            out["project_shorthand"] = Value::String(format!(
+                "With project/target set, {} and {} without a connection use the target's {} and {}.",
+                self.source.block,
+                self.sink.block,
+                self.source.target_binding,
+                self.sink.target_binding
+            ));
+        }
+        out
+    }
+
+    // Block presence and required fields; connections are left to project hydration and the
+    // underlying tools, which also accept a single stored profile.
+    pub(super) fn validate(&self, args: &Value) -> Result<(), ToolError> {
+        for block in [&self.source, &self.sink] {
+            let Some(config) = args.get(block.block).and_then(|v| v.as_object()) else {
+                return Err(ToolError::invalid_params(format!(
+                    "{} requires a `{}` object ({})",
+                    self.name,
+                    block.block,
+                    block.role.as_str()
+                ))
+                .with_hint(format!("Example: {}", self.example))
+                .with_details(serde_json::json!({
+                    "flow": self.name,
+                    "block": block.block,
+                    "role": block.role.as_str(),
+                })));
+            };
+            let missing: Vec<&str> = block
+                .required
+                .iter()
+                .copied()
+                .filter(|field| {
+                    let absent = |name: &str| {
+                        config
+                            .get(name)
+                            .map(|v| v.is_null() || v.as_str().is_some_and(|s| s.trim().is_empty()))
+                            .unwrap_or(true)
+                    };
+                    absent(field)
+                        && block
+                            .alternatives
+                            .iter()
+                            .filter(|(required, _)| required == field)
+                            .all(|(_, alternative)| absent(alternative))
+                })
+                .collect();
+            if !missing.is_empty() {
+                let fields: Vec<String> = missing
+                    .iter()
+                    .map(|field| format!("{}.{}", block.block, field))
+                    .collect();
+                return Err(ToolError::invalid_params(format!(
+                    "{} is required for {}",
+                    fields.join(", "),
+                    self.name
+                ))
+                .with_hint(format!("Example: {}", self.example))
+                .with_details(serde_json::json!({
+                    "flow": self.name,
+                    "block": block.block,
+                    "missing": missing,
+                })));
+            }
+        }
+        Ok(())
+    }
+}
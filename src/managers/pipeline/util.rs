@@ -1,4 +1,4 @@
-use crate::utils::feature_flags::is_truthy;
+use crate::utils::feature_flags::{self, is_truthy};
 use serde_json::Value;
 
 pub(crate) fn read_positive_int(value: Option<&Value>) -> Option<u64> {
@@ -29,10 +29,7 @@ pub(crate) enum StreamToArtifactMode {
 }
 
 pub(crate) fn resolve_stream_to_artifact_mode() -> Option<StreamToArtifactMode> {
-    let raw = std::env::var("INFRA_PIPELINE_STREAM_TO_ARTIFACT")
-        .or_else(|_| std::env::var("INFRA_STREAM_TO_ARTIFACT"))
-        .ok();
-    let raw = raw?;
+    let raw = feature_flags::PIPELINE_STREAM_TO_ARTIFACT.text()?;
     let normalized = raw.trim().to_lowercase();
     if normalized.is_empty() {
         return None;
@@ -50,10 +47,5 @@ pub(crate) fn resolve_stream_to_artifact_mode() -> Option<StreamToArtifactMode>
 }
 
 pub(crate) fn resolve_max_capture_bytes() -> usize {
-    let raw = std::env::var("INFRA_PIPELINE_MAX_CAPTURE_BYTES")
-        .or_else(|_| std::env::var("INFRA_MAX_CAPTURE_BYTES"))
-        .ok();
-    raw.and_then(|v| v.trim().parse::<usize>().ok())
-        .filter(|v| *v > 0)
-        .unwrap_or(256 * 1024)
+    feature_flags::PIPELINE_MAX_CAPTURE_BYTES.positive_number() as usize
 }
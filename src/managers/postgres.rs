@@ -1,26 +1,64 @@
 use crate::constants::{limits as limit_constants, network as network_constants};
 use crate::errors::ToolError;
 use crate::services::logger::Logger;
-use crate::services::profile::ProfileService;
+use crate::services::profile::{ProfileService, UpsertOptions};
 use crate::services::project_resolver::ProjectResolver;
 use crate::services::secret_ref::SecretRefResolver;
 use crate::services::validation::Validation;
-use crate::utils::sql::{build_where_clause, normalize_table_context, quote_qualified_identifier};
+use crate::utils::artifacts::{
+    build_tool_call_file_ref, resolve_context_root, write_text_artifact,
+};
+use crate::utils::feature_flags::is_allow_secret_export_enabled;
+use crate::utils::pg_bulk::{
+    conflict_clause, ensure_row_shape, failure_message, row_values, sample_row, upsert_returning,
+    OnConflict, INSERTED_FLAG, MAX_FAILED_SAMPLE_ROWS,
+};
+use crate::utils::pg_catalog_diff::{
+    catalog_queries, diff_catalogs, diff_item_count, sql_hints, truncate_diff, SchemaCatalog,
+    DEFAULT_DIFF_SCHEMAS, MAX_INLINE_DIFF_ITEMS,
+};
+use crate::utils::pg_keyset::{ordering, Keyset};
+use crate::utils::pg_params::{binds_natively, to_pg_param, PgParam};
+use crate::utils::pg_redaction::{source_table_oids, RedactionPlan, RedactionPolicy, SourceColumn};
+use crate::utils::pg_reports::{
+    activity_sql, locks_sql, parse_reports, recovery_status_sql, replica_status_sql,
+    replication_senders_sql, replication_slots_sql, PgReportOptions, DEFAULT_REPORT_LIMIT,
+    MAX_QUERY_CHARS, MAX_REPORT_LIMIT, PG_REPORTS_MIN_VERSION,
+};
+use crate::utils::pg_retry::{is_retryable_sqlstate, run_with_retry, SerializationRetry};
+use crate::utils::pg_schema::{
+    create_table_sql, infer_schema, CreateTable, DEFAULT_INFER_SAMPLE_ROWS,
+};
+use crate::utils::pg_tls::{map_connect_error, split_tls_params, PgTlsConfig};
+use crate::utils::pg_values::{decodes, numeric_as_float, row_to_value, type_label};
+use crate::utils::redact::redact_text;
+use crate::utils::sql::{
+    bind_named_params, build_expect_clause, build_returning_clause, build_where_clause,
+    cast_placeholders, normalize_table_context, parse_sample_size, quote_qualified_identifier,
+    BoundParams, DryRunStatement,
+};
 use crate::utils::tool_errors::unknown_action_error;
+use crate::utils::trace_context::TraceContext;
+use crate::utils::usage::{self, Counter};
 use async_trait::async_trait;
-use bb8::Pool;
+use bb8::{ErrorSink, Pool, PooledConnection, RunError};
 use bb8_postgres::PostgresConnectionManager;
 use dashmap::DashMap;
+use futures::TryStreamExt;
+use postgres_native_tls::MakeTlsConnector;
 use serde_json::Value;
 use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
 use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::io::{AsyncWrite, AsyncWriteExt, DuplexStream};
-use tokio_postgres::types::{Json, ToSql, Type};
-use tokio_postgres::{Config, GenericClient, NoTls, Row};
+use tokio_postgres::types::{Kind, ToSql, Type};
+use tokio_postgres::{Column, Config, GenericClient, Statement};
 
 const PG_PROFILE_TYPE: &str = "postgresql";
+
+type PgConnectionManager = PostgresConnectionManager<MakeTlsConnector>;
 pub(crate) const PG_ACTIONS: &[&str] = &[
     "profile_upsert",
     "profile_get",
@@ -40,6 +78,7 @@ pub(crate) const PG_ACTIONS: &[&str] = &[
     "export",
     "catalog_tables",
     "catalog_columns",
+    "catalog_diff",
     "database_info",
 ];
 
@@ -50,7 +89,61 @@ pub struct PostgresManager {
     profile_service: Arc<ProfileService>,
     project_resolver: Option<Arc<ProjectResolver>>,
     secret_ref_resolver: Option<Arc<SecretRefResolver>>,
-    pools: Arc<DashMap<String, Pool<PostgresConnectionManager<NoTls>>>>,
+    pools: Arc<DashMap<String, PgPool>>,
+}
+
+// bb8 only reports connect failures to its error sink and then times out `get()`, so the
+// last failure is kept to explain the timeout (TLS vs auth vs network).
+#[derive(Clone)]
+struct PgPool {
+    pool: Pool<PgConnectionManager>,
+    tls: PgTlsConfig,
+    last_error: Arc<std::sync::Mutex<Option<ToolError>>>,
+}
+
+#[derive(Clone, Debug)]
+struct ConnectErrorSink {
+    tls: PgTlsConfig,
+    last_error: Arc<std::sync::Mutex<Option<ToolError>>>,
+}
+
+impl ErrorSink<tokio_postgres::Error> for ConnectErrorSink {
+    fn sink(&self, error: tokio_postgres::Error) {
+        if let Ok(mut slot) = self.last_error.lock() {
+            *slot = Some(map_connect_error(&error, &self.tls));
+        }
+    }
+
+    fn boxed_clone(&self) -> Box<dyn ErrorSink<tokio_postgres::Error>> {
+        Box::new(self.clone())
+    }
+}
+
+impl PgPool {
+    async fn get(&self) -> Result<PooledConnection<'_, PgConnectionManager>, ToolError> {
+        // A cold pool is probed with a dedicated connection so the first failure surfaces
+        // immediately with its real cause instead of after the checkout timeout.
+        if self.pool.state().connections == 0 {
+            let probe = self
+                .pool
+                .dedicated_connection()
+                .await
+                .map_err(|err| map_connect_error(&err, &self.tls))?;
+            drop(probe);
+        }
+        match self.pool.get().await {
+            Ok(conn) => Ok(conn),
+            Err(RunError::User(err)) => Err(map_connect_error(&err, &self.tls)),
+            Err(RunError::TimedOut) => Err(self
+                .last_error
+                .lock()
+                .ok()
+                .and_then(|mut slot| slot.take())
+                .unwrap_or_else(|| {
+                    ToolError::timeout("PostgreSQL pool timed out waiting for a connection")
+                })),
+        }
+    }
 }
 
 pub(crate) struct ExportStream {
@@ -70,8 +163,10 @@ struct PoolOptions {
 struct ResolvedConnection {
     config: Config,
     pool_options: PoolOptions,
+    tls: PgTlsConfig,
     profile_name: Option<String>,
     key_seed: String,
+    redaction: RedactionPolicy,
 }
 
 impl PostgresManager {
@@ -114,6 +209,7 @@ impl PostgresManager {
             "export" => self.export_data(&args).await,
             "catalog_tables" => self.catalog_tables(&args).await,
             "catalog_columns" => self.catalog_columns(&args).await,
+            "catalog_diff" => self.catalog_diff(&args).await,
             "database_info" => self.database_info(&args).await,
             _ => Err(unknown_action_error("psql", action, PG_ACTIONS)),
         }
@@ -131,46 +227,54 @@ impl PostgresManager {
             .unwrap_or(Value::Object(Default::default()));
         let connection_url = args.get("connection_url").and_then(|v| v.as_str());
 
-        let mut merged = merge_connection(connection_url, &connection)?;
+        let mut incoming = merge_connection(connection_url, &connection)?;
         if let Some(pool) = args.get("pool") {
-            if let Value::Object(map) = &mut merged {
+            if let Value::Object(map) = &mut incoming {
                 map.insert("pool".to_string(), pool.clone());
             }
         }
         if let Some(options) = args.get("options") {
-            if let Value::Object(map) = &mut merged {
+            if let Value::Object(map) = &mut incoming {
                 map.insert("options".to_string(), options.clone());
             }
         }
+        if let Some(redaction) = args.get("redaction").filter(|v| v.is_object()) {
+            RedactionPolicy::parse(Some(redaction))?;
+            if let Value::Object(map) = &mut incoming {
+                map.insert("redaction".to_string(), redaction.clone());
+            }
+        }
 
-        let (data, secrets) = split_connection_secrets(&merged);
-        let resolved = if let Some(resolver) = &self.secret_ref_resolver {
-            resolver
-                .resolve_deep(&Value::Object(data.clone()), args)
-                .await?
-        } else {
-            Value::Object(data.clone())
-        };
-
-        let config = build_config_from_value(&resolved, None)?;
-        self.test_connection(&config, args.get("pool")).await?;
+        let upsert = UpsertOptions::from_args(args)?;
+        let mut draft = self
+            .profile_service
+            .upsert_draft(&name, PG_PROFILE_TYPE, &upsert)?;
+        let (data, secrets) = split_connection_secrets(&incoming);
+        for (key, value) in &data {
+            draft.overlay_data(key, value);
+        }
+        for (key, value) in &secrets {
+            draft.overlay_secret(key, value);
+        }
 
-        let mut profile_payload = serde_json::Map::new();
-        profile_payload.insert(
-            "type".to_string(),
-            Value::String(PG_PROFILE_TYPE.to_string()),
-        );
-        profile_payload.insert("data".to_string(), Value::Object(data));
-        if !secrets.is_empty() {
-            profile_payload.insert("secrets".to_string(), Value::Object(secrets));
+        let tested = !upsert.skip_test;
+        if tested {
+            let merged = draft.connection();
+            let resolved = if let Some(resolver) = &self.secret_ref_resolver {
+                resolver.resolve_deep(&merged, args).await?
+            } else {
+                merged
+            };
+            let (config, tls) = build_config_from_value(&resolved, None)?;
+            self.test_connection(&config, &tls).await?;
         }
 
         let profile = self
             .profile_service
-            .set_profile(&name, &Value::Object(profile_payload))?;
-        self.pools.remove(&format!("profile:{}", name));
+            .save_draft(&name, PG_PROFILE_TYPE, &draft)?;
+        self.drop_profile_pools(&name);
 
-        Ok(serde_json::json!({"success": true, "profile": profile}))
+        Ok(serde_json::json!({"success": true, "tested": tested, "profile": profile}))
     }
 
     fn profile_get(&self, args: &Value) -> Result<Value, ToolError> {
@@ -186,11 +290,7 @@ impl PostgresManager {
             .get("include_secrets")
             .and_then(|v| v.as_bool())
             .unwrap_or(false);
-        let allow = std::env::var("INFRA_ALLOW_SECRET_EXPORT")
-            .ok()
-            .filter(|v| v.trim() == "1" || v.trim().eq_ignore_ascii_case("true"))
-            .is_some();
-        if include_secrets && allow {
+        if include_secrets && is_allow_secret_export_enabled() {
             return Ok(serde_json::json!({"success": true, "profile": profile}));
         }
         let secret_keys = profile
@@ -222,34 +322,53 @@ impl PostgresManager {
             true,
         )?;
         let result = self.profile_service.delete_profile(&name)?;
-        self.pools.remove(&format!("profile:{}", name));
+        self.drop_profile_pools(&name);
         Ok(result)
     }
 
     async fn profile_test(&self, args: &Value) -> Result<Value, ToolError> {
         let resolved = self.resolve_connection(args).await?;
-        self.test_connection(&resolved.config, args.get("pool"))
+        self.test_connection(&resolved.config, &resolved.tls)
             .await?;
-        Ok(serde_json::json!({"success": true}))
+        Ok(serde_json::json!({"success": true, "tls": resolved.tls.describe()}))
     }
 
     async fn query(&self, args: &Value) -> Result<Value, ToolError> {
         let sql =
             self.validation
                 .ensure_string(args.get("sql").unwrap_or(&Value::Null), "sql", true)?;
+        if dry_run_requested(args)? {
+            let statement = DryRunStatement::parse(&sql)?;
+            let bound = bind_named_params(
+                &statement.sample_sql(&sql),
+                args.get("params"),
+                args.get("param_types"),
+            )?;
+            return self.dry_run(args, &bound).await;
+        }
+        let bound = bind_named_params(&sql, args.get("params"), args.get("param_types"))?;
         let resolved = self.resolve_connection(args).await?;
         let pool = self.get_pool(&resolved).await?;
-        let params = args
-            .get("params")
-            .and_then(|v| v.as_array())
-            .cloned()
-            .unwrap_or_default();
         let mode = args.get("mode").and_then(|v| v.as_str());
         let timeout_ms = args.get("timeout_ms").and_then(|v| v.as_u64());
-        let result = execute_query_with_pool(&pool, &sql, &params, mode, timeout_ms).await?;
-        Ok(result)
+        let float_numeric = numeric_float_mode(args)?;
+        let retry = SerializationRetry::parse(args.get("retry_serialization"), false)?;
+        let (pool, bound, redaction) = (&pool, &bound, &resolved.redaction);
+        let (mut result, retries) = run_with_retry(retry.as_ref(), move || async move {
+            let conn = pool.get().await?;
+            let mut result = execute_named_query(&*conn, bound, mode, timeout_ms).await?;
+            redact_payload(&*conn, redaction, &mut result).await?;
+            Ok(result)
+        })
+        .await?;
+        if float_numeric {
+            numeric_as_float(&mut result);
+        }
+        Ok(with_retries(result, retry.map(|_| retries)))
     }
 
+    // Items are plain SQL strings or `{sql, params, param_types, mode, timeout_ms, name}`; all of
+    // them are bound up front so a bad item fails before anything runs.
     async fn batch(&self, args: &Value) -> Result<Value, ToolError> {
         let statements = args
             .get("statements")
@@ -259,55 +378,79 @@ impl PostgresManager {
         if statements.is_empty() {
             return Err(
                 ToolError::invalid_params("statements must be a non-empty array")
-                    .with_hint("Provide at least one statement: [{ sql: \"SELECT 1\" }]."),
+                    .with_hint("Provide at least one statement: [\"SELECT 1\", { sql: \"SELECT :id\", params: { id: 1 }, name: \"one\" }]."),
             );
         }
+        let statements = parse_batch_statements(&statements)?;
+        let transactional = ["transaction", "transactional"]
+            .iter()
+            .any(|key| args.get(*key).and_then(|v| v.as_bool()).unwrap_or(false));
+        let stop_on_error = args
+            .get("stop_on_error")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true);
+        // The transaction action (transactional batches) retries serialization failures and
+        // deadlocks by default, re-running the whole body; plain batches only when asked.
+        let retry = SerializationRetry::parse(args.get("retry_serialization"), transactional)?;
         let resolved = self.resolve_connection(args).await?;
         let pool = self.get_pool(&resolved).await?;
-        let transactional = args
-            .get("transactional")
-            .and_then(|v| v.as_bool())
-            .unwrap_or(false);
-        let mut results = Vec::new();
+        let float_numeric = numeric_float_mode(args)?;
+        let redaction = &resolved.redaction;
 
         if !transactional {
-            for statement in statements {
-                let sql = statement.get("sql").and_then(|v| v.as_str()).unwrap_or("");
-                if sql.trim().is_empty() {
-                    return Err(ToolError::invalid_params("statement.sql is required"));
+            let mut results = Vec::new();
+            let mut failed = 0usize;
+            let mut total_retries = 0u32;
+            for statement in &statements {
+                let pool = &pool;
+                let outcome = run_with_retry(retry.as_ref(), move || async move {
+                    let conn = pool.get().await?;
+                    let mut result = execute_named_query(
+                        &*conn,
+                        &statement.bound,
+                        statement.mode.as_deref(),
+                        statement.timeout_ms,
+                    )
+                    .await?;
+                    redact_payload(&*conn, redaction, &mut result).await?;
+                    Ok::<_, ToolError>(result)
+                })
+                .await
+                .map(|(result, retries)| {
+                    total_retries += retries;
+                    result
+                });
+                let ok = outcome.is_ok();
+                results.push(statement.annotate(outcome, float_numeric));
+                if !ok {
+                    failed += 1;
+                    if stop_on_error {
+                        break;
+                    }
                 }
-                let params = statement
-                    .get("params")
-                    .and_then(|v| v.as_array())
-                    .cloned()
-                    .unwrap_or_default();
-                let mode = statement.get("mode").and_then(|v| v.as_str());
-                let timeout_ms = statement.get("timeout_ms").and_then(|v| v.as_u64());
-                let result = execute_query_with_pool(&pool, sql, &params, mode, timeout_ms).await?;
-                results.push(result);
             }
-            return Ok(serde_json::json!({"success": true, "results": results}));
+            let payload = batch_payload(&statements, results, failed, None);
+            return Ok(with_retries(payload, retry.map(|_| total_retries)));
         }
 
-        let mut conn = pool.get().await.map_err(map_pool_error)?;
-        let transaction = conn.transaction().await.map_err(map_pg_error)?;
-        for statement in statements {
-            let sql = statement.get("sql").and_then(|v| v.as_str()).unwrap_or("");
-            if sql.trim().is_empty() {
-                return Err(ToolError::invalid_params("statement.sql is required"));
-            }
-            let params = statement
-                .get("params")
-                .and_then(|v| v.as_array())
-                .cloned()
-                .unwrap_or_default();
-            let mode = statement.get("mode").and_then(|v| v.as_str());
-            let timeout_ms = statement.get("timeout_ms").and_then(|v| v.as_u64());
-            let result = execute_query(&transaction, sql, &params, mode, timeout_ms).await?;
-            results.push(result);
-        }
-        transaction.commit().await.map_err(map_pg_error)?;
-        Ok(serde_json::json!({"success": true, "results": results}))
+        let retry_enabled = retry.is_some();
+        let pool = &pool;
+        let statements_ref = &statements;
+        let ((results, failed, committed), retries) =
+            run_with_retry(retry.as_ref(), move || async move {
+                run_transaction(
+                    pool,
+                    statements_ref,
+                    redaction,
+                    stop_on_error,
+                    float_numeric,
+                    retry_enabled,
+                )
+                .await
+            })
+            .await?;
+        let payload = batch_payload(&statements, results, failed, Some(committed));
+        Ok(with_retries(payload, retry.map(|_| retries)))
     }
 
     async fn transaction(&self, args: &Value) -> Result<Value, ToolError> {
@@ -337,7 +480,7 @@ impl PostgresManager {
             values.push(data.get(col).cloned().unwrap_or(Value::Null));
         }
         let placeholders: Vec<String> = (1..=values.len()).map(|idx| format!("${}", idx)).collect();
-        let returning = build_returning(args.get("returning"));
+        let returning = build_returning_clause(args.get("returning"))?;
         let sql = format!(
             "INSERT INTO {} ({}) VALUES ({}){}",
             context
@@ -350,20 +493,9 @@ impl PostgresManager {
         );
         let resolved = self.resolve_connection(args).await?;
         let pool = self.get_pool(&resolved).await?;
-        let result = execute_query_with_pool(
-            &pool,
-            &sql,
-            &values,
-            args.get("mode").and_then(|v| v.as_str()),
-            args.get("timeout_ms").and_then(|v| v.as_u64()),
-        )
-        .await?;
-        Ok(serde_json::json!({
-            "success": true,
-            "table": context.get("table").cloned().unwrap_or(Value::Null),
-            "schema": context.get("schema").cloned().unwrap_or(Value::Null),
-            "result": result,
-        }))
+        let (result, retries) =
+            execute_write_retrying(args, &pool, &sql, &values, !returning.is_empty()).await?;
+        Ok(with_retries(write_response(&context, result), retries))
     }
 
     async fn insert_bulk(&self, args: &Value) -> Result<Value, ToolError> {
@@ -377,12 +509,13 @@ impl PostgresManager {
             .get("rows")
             .or_else(|| args.get("data"))
             .and_then(|v| v.as_array())
-            .cloned()
-            .unwrap_or_default();
-        if rows.is_empty() {
-            return Err(ToolError::invalid_params("rows must be a non-empty array")
-                .with_hint("Provide args.rows as an array of objects (or arrays) to insert."));
-        }
+            .filter(|rows| !rows.is_empty())
+            .ok_or_else(|| {
+                ToolError::invalid_params("rows must be a non-empty array")
+                    .with_hint("Provide args.rows as an array of objects (or arrays) to insert.")
+            })?;
+        // Checked upfront so a malformed row never leaves earlier chunks committed.
+        rows.iter().try_for_each(ensure_row_shape)?;
         let mut columns: Option<Vec<String>> =
             args.get("columns").and_then(|v| v.as_array()).map(|arr| {
                 arr.iter()
@@ -409,82 +542,222 @@ impl PostgresManager {
             .iter()
             .map(|col| quote_qualified_identifier(col))
             .collect::<Result<Vec<_>, _>>()?;
-        let returning = build_returning(args.get("returning"));
+        let returning = build_returning_clause(args.get("returning"))?;
+        let create_table = CreateTable::parse(args.get("create_table"))?;
+        let primary_key = parse_primary_key(args.get("primary_key"))?;
+        let on_conflict = OnConflict::parse(args.get("on_conflict"))?;
+        let conflict_target = string_list(args.get("conflict_columns"), "conflict_columns")?
+            .unwrap_or_else(|| primary_key.clone());
+        let update_columns = string_list(args.get("update_columns"), "update_columns")?;
+        let conflict_sql = conflict_clause(
+            on_conflict,
+            &columns,
+            &conflict_target,
+            update_columns.as_deref(),
+        )?;
+        let upsert = on_conflict == OnConflict::Upsert;
+        let returning_sql = if upsert {
+            upsert_returning(&returning)
+        } else {
+            returning.clone()
+        };
+        let continue_on_error = args
+            .get("continue_on_error")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
 
         let max_params = 65535usize;
         let max_batch = std::cmp::max(1, max_params / column_sql.len());
         let requested_batch = args
-            .get("batch_size")
+            .get("chunk_size")
+            .or_else(|| args.get("batch_size"))
             .and_then(|v| v.as_u64())
-            .unwrap_or(500) as usize;
+            .unwrap_or(500)
+            .max(1) as usize;
         let batch_size = std::cmp::min(requested_batch, max_batch);
+        let qualified = context
+            .get("qualified")
+            .and_then(|v| v.as_str())
+            .unwrap_or("");
+        let planned_table = if create_table == CreateTable::Never {
+            None
+        } else {
+            let schema = infer_schema(
+                rows,
+                &columns,
+                args.get("infer_strings")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false),
+                DEFAULT_INFER_SAMPLE_ROWS,
+            );
+            let ddl = create_table_sql(qualified, &schema, &primary_key, false)?;
+            Some((schema, ddl))
+        };
 
         let resolved = self.resolve_connection(args).await?;
         let pool = self.get_pool(&resolved).await?;
+        let table_setup = match planned_table {
+            None => Value::Null,
+            Some((schema, ddl)) => {
+                if ensure_table(&pool, qualified, create_table, &ddl).await? {
+                    let mut setup = schema.describe();
+                    setup["mode"] = Value::String(create_table.as_str().to_string());
+                    setup["created"] = Value::Bool(true);
+                    setup["ddl"] = Value::String(ddl);
+                    setup
+                } else {
+                    serde_json::json!({"mode": create_table.as_str(), "created": false})
+                }
+            }
+        };
+        let sample_redaction = resolved.redaction.plan_for_table(
+            context.get("schema").and_then(|v| v.as_str()),
+            context.get("table").and_then(|v| v.as_str()).unwrap_or(""),
+            &columns,
+        );
+        let mode = if upsert {
+            Some("rows")
+        } else {
+            args.get("mode").and_then(|v| v.as_str())
+        };
 
-        let mut inserted = 0usize;
+        let retry = SerializationRetry::parse(args.get("retry_serialization"), false)?;
+        let mut total_retries = 0u32;
+        let (mut inserted, mut updated, mut ignored, mut failed) = (0usize, 0usize, 0usize, 0usize);
         let mut all_rows: Vec<Value> = Vec::new();
+        let mut chunks: Vec<Value> = Vec::new();
 
-        for offset in (0..rows.len()).step_by(batch_size) {
-            let batch = &rows[offset..std::cmp::min(offset + batch_size, rows.len())];
-            let mut values: Vec<Value> = Vec::new();
-            let mut placeholders = Vec::new();
+        // Each chunk is one statement and commits on its own.
+        for (index, batch) in rows.chunks(batch_size).enumerate() {
+            let offset = index * batch_size;
+            let mut values: Vec<Value> = Vec::with_capacity(batch.len() * column_sql.len());
+            let mut placeholders = Vec::with_capacity(batch.len());
             for (row_index, row) in batch.iter().enumerate() {
-                let row_values = if let Some(obj) = row.as_object() {
-                    columns
-                        .iter()
-                        .map(|col| obj.get(col).cloned().unwrap_or(Value::Null))
-                        .collect::<Vec<_>>()
-                } else if let Some(arr) = row.as_array() {
-                    columns
-                        .iter()
-                        .enumerate()
-                        .map(|(idx, _)| arr.get(idx).cloned().unwrap_or(Value::Null))
-                        .collect::<Vec<_>>()
-                } else {
-                    return Err(ToolError::invalid_params(
-                        "Each row must be an object or array",
-                    ));
-                };
                 let start_index = row_index * column_sql.len();
                 let row_placeholders = (0..column_sql.len())
                     .map(|col_idx| format!("${}", start_index + col_idx + 1))
                     .collect::<Vec<_>>();
                 placeholders.push(format!("({})", row_placeholders.join(", ")));
-                values.extend(row_values);
+                values.extend(row_values(row, &columns));
             }
             let sql = format!(
-                "INSERT INTO {} ({}) VALUES {}{}",
-                context
-                    .get("qualified")
-                    .and_then(|v| v.as_str())
-                    .unwrap_or(""),
+                "INSERT INTO {} ({}) VALUES {}{}{}",
+                qualified,
                 column_sql.join(", "),
                 placeholders.join(", "),
-                returning
+                conflict_sql,
+                returning_sql
             );
-            let result = execute_query_with_pool(
-                &pool,
-                &sql,
-                &values,
-                args.get("mode").and_then(|v| v.as_str()),
-                args.get("timeout_ms").and_then(|v| v.as_u64()),
-            )
-            .await?;
-            inserted += batch.len();
-            if let Some(rows) = result.get("rows").and_then(|v| v.as_array()) {
-                all_rows.extend(rows.iter().cloned());
+            let started = std::time::Instant::now();
+            let outcome = run_with_retry(retry.as_ref(), || {
+                execute_query_with_pool(
+                    &pool,
+                    &sql,
+                    &values,
+                    mode,
+                    args.get("timeout_ms").and_then(|v| v.as_u64()),
+                )
+            })
+            .await;
+            let duration_ms = started.elapsed().as_millis() as u64;
+            drop(values);
+            let mut result = match outcome {
+                Ok((result, retries)) => {
+                    total_retries += retries;
+                    result
+                }
+                Err(err) if continue_on_error => {
+                    failed += batch.len();
+                    let sample_rows = batch
+                        .iter()
+                        .take(MAX_FAILED_SAMPLE_ROWS)
+                        .map(|row| {
+                            let mut sample = sample_row(row, &columns);
+                            sample_redaction.apply_row(&mut sample);
+                            sample
+                        })
+                        .collect::<Vec<_>>();
+                    chunks.push(serde_json::json!({
+                        "index": index,
+                        "offset": offset,
+                        "rows": batch.len(),
+                        "status": "failed",
+                        "duration_ms": duration_ms,
+                        "error": {
+                            "kind": err.kind,
+                            "code": err.code,
+                            "message": failure_message(&err.message, !sample_redaction.is_empty()),
+                            "sqlstate": err.details.as_ref().and_then(|d| d.get("sqlstate")).cloned(),
+                        },
+                        "sample_rows": sample_rows,
+                    }));
+                    continue;
+                }
+                Err(err) if offset > 0 && err.hint.is_none() => {
+                    return Err(err.with_hint(format!(
+                        "Rows before offset {} were already committed ({} inserted). Re-run the rest with on_conflict=ignore, or set continue_on_error=true to skip failing chunks.",
+                        offset, inserted
+                    )));
+                }
+                Err(err) => return Err(err),
+            };
+            let affected = result
+                .get("affected_rows")
+                .and_then(|v| v.as_u64())
+                .map(|n| n as usize)
+                .unwrap_or(batch.len());
+            let mut returned = match result.get_mut("rows") {
+                Some(Value::Array(rows)) => std::mem::take(rows),
+                _ => Vec::new(),
+            };
+            let (chunk_inserted, chunk_updated) = if upsert {
+                let fresh = returned
+                    .iter_mut()
+                    .filter_map(|row| row.as_object_mut()?.remove(INSERTED_FLAG))
+                    .filter(|flag| flag == &Value::Bool(true))
+                    .count();
+                (fresh, affected.saturating_sub(fresh))
+            } else {
+                (affected, 0)
+            };
+            let chunk_ignored = batch.len().saturating_sub(chunk_inserted + chunk_updated);
+            inserted += chunk_inserted;
+            updated += chunk_updated;
+            ignored += chunk_ignored;
+            if !returning.is_empty() {
+                all_rows.extend(returned);
             }
+            chunks.push(serde_json::json!({
+                "index": index,
+                "offset": offset,
+                "rows": batch.len(),
+                "status": "ok",
+                "inserted": chunk_inserted,
+                "updated": chunk_updated,
+                "ignored": chunk_ignored,
+                "duration_ms": duration_ms,
+            }));
         }
 
-        Ok(serde_json::json!({
-            "success": true,
+        let response = serde_json::json!({
+            "success": failed == 0,
             "table": context.get("table").cloned().unwrap_or(Value::Null),
             "schema": context.get("schema").cloned().unwrap_or(Value::Null),
+            "on_conflict": on_conflict.as_str(),
             "inserted": inserted,
-            "batches": rows.len().div_ceil(batch_size),
+            "updated": updated,
+            "ignored": ignored,
+            "failed": failed,
+            "affected": inserted + updated,
+            // One statement is all-or-nothing; with several, each chunk commits on its own.
+            "atomic": chunks.len() == 1,
+            "chunk_size": batch_size,
+            "batches": chunks.len(),
+            "chunks": chunks,
             "rows": if returning.is_empty() { Value::Null } else { Value::Array(all_rows) },
-        }))
+            "table_setup": table_setup,
+        });
+        Ok(with_retries(response, retry.map(|_| total_retries)))
     }
 
     async fn update(&self, args: &Value) -> Result<Value, ToolError> {
@@ -510,13 +783,20 @@ impl PostgresManager {
             .enumerate()
             .map(|(idx, col)| format!("{} = ${}", col, idx + 1))
             .collect();
-        let (where_sql, where_params, _) = build_where_clause(
+        let (where_sql, where_params, next_index) = build_where_clause(
             args.get("filters"),
             args.get("where_sql").and_then(|v| v.as_str()),
             args.get("where_params").and_then(|v| v.as_array()),
             values.len() as i64 + 1,
         )?;
-        let returning = build_returning(args.get("returning"));
+        let (expect_sql, expect_params, _) = build_expect_clause(args.get("expect"), next_index)?;
+        let guarded = !expect_sql.is_empty();
+        let where_sql = match (where_sql.is_empty(), expect_sql.is_empty()) {
+            (_, true) => where_sql,
+            (true, false) => expect_sql,
+            (false, false) => format!("({}) AND {}", where_sql, expect_sql),
+        };
+        let returning = build_returning_clause(args.get("returning"))?;
         let sql = format!(
             "UPDATE {} SET {}{}{}",
             context
@@ -531,25 +811,29 @@ impl PostgresManager {
                 where_sql.as_str()
             },
         );
-        let sql = format!("{}{}", sql, returning);
         let mut params = values;
         params.extend(where_params);
+        params.extend(expect_params);
+        if dry_run_requested(args)? {
+            return self.dry_run_write(args, &context, sql, params).await;
+        }
+        let sql = format!("{}{}", sql, returning);
         let resolved = self.resolve_connection(args).await?;
         let pool = self.get_pool(&resolved).await?;
-        let result = execute_query_with_pool(
-            &pool,
-            &sql,
-            &params,
-            args.get("mode").and_then(|v| v.as_str()),
-            args.get("timeout_ms").and_then(|v| v.as_u64()),
-        )
-        .await?;
-        Ok(serde_json::json!({
-            "success": true,
-            "table": context.get("table").cloned().unwrap_or(Value::Null),
-            "schema": context.get("schema").cloned().unwrap_or(Value::Null),
-            "result": result,
-        }))
+        let (result, retries) =
+            execute_write_retrying(args, &pool, &sql, &params, !returning.is_empty()).await?;
+        let mut response = with_retries(write_response(&context, result), retries);
+        // Zero affected rows under an expectation means the row changed (or vanished) since it
+        // was read; report it instead of silently succeeding.
+        if guarded && response["affected"] == 0 {
+            response["success"] = Value::Bool(false);
+            response["conflict"] = Value::Bool(true);
+            response["hint"] = Value::String(
+                "The row no longer matches expect; re-read it and retry with current values."
+                    .to_string(),
+            );
+        }
+        Ok(response)
     }
 
     async fn delete(&self, args: &Value) -> Result<Value, ToolError> {
@@ -565,7 +849,7 @@ impl PostgresManager {
             args.get("where_params").and_then(|v| v.as_array()),
             1,
         )?;
-        let returning = build_returning(args.get("returning"));
+        let returning = build_returning_clause(args.get("returning"))?;
         let sql = format!(
             "DELETE FROM {}{}{}",
             context
@@ -579,17 +863,75 @@ impl PostgresManager {
                 where_sql.as_str()
             },
         );
+        if dry_run_requested(args)? {
+            return self.dry_run_write(args, &context, sql, where_params).await;
+        }
         let sql = format!("{}{}", sql, returning);
         let resolved = self.resolve_connection(args).await?;
         let pool = self.get_pool(&resolved).await?;
-        let result = execute_query_with_pool(
+        let (result, retries) =
+            execute_write_retrying(args, &pool, &sql, &where_params, !returning.is_empty()).await?;
+        Ok(with_retries(write_response(&context, result), retries))
+    }
+
+    // update/delete with `dry_run`: the generated statement returns every column for the sample;
+    // the caller's `returning` only shapes real writes.
+    async fn dry_run_write(
+        &self,
+        args: &Value,
+        context: &Value,
+        sql: String,
+        params: Vec<Value>,
+    ) -> Result<Value, ToolError> {
+        let bound = BoundParams {
+            sql: format!("{} RETURNING *", sql),
+            values: params,
+            ..BoundParams::default()
+        };
+        let mut response = self.dry_run(args, &bound).await?;
+        response["table"] = context.get("table").cloned().unwrap_or(Value::Null);
+        response["schema"] = context.get("schema").cloned().unwrap_or(Value::Null);
+        Ok(response)
+    }
+
+    async fn dry_run(&self, args: &Value, bound: &BoundParams) -> Result<Value, ToolError> {
+        let sample_size = parse_sample_size(args.get("sample_size"))?;
+        let float_numeric = numeric_float_mode(args)?;
+        let resolved = self.resolve_connection(args).await?;
+        let pool = self.get_pool(&resolved).await?;
+        let timeout_ms = args.get("timeout_ms").and_then(|v| v.as_u64());
+        let mut result =
+            run_dry_run(&pool, bound, &resolved.redaction, sample_size, timeout_ms).await?;
+        if float_numeric {
+            numeric_as_float(&mut result);
+        }
+        if let Value::Object(map) = &mut result {
+            let sample = map.remove("rows").unwrap_or(Value::Array(Vec::new()));
+            map.insert("sample".to_string(), sample);
+        }
+        Ok(result)
+    }
+
+    async fn select(&self, args: &Value) -> Result<Value, ToolError> {
+        if let Some(paginate) = args.get("paginate").filter(|v| !v.is_null()) {
+            return self.select_page(args, Keyset::parse(paginate)?).await;
+        }
+        let (sql, params, context) = build_select_query(args)?;
+        let float_numeric = numeric_float_mode(args)?;
+        let resolved = self.resolve_connection(args).await?;
+        let pool = self.get_pool(&resolved).await?;
+        let mut result = execute_query_with_pool(
             &pool,
             &sql,
-            &where_params,
+            &params,
             args.get("mode").and_then(|v| v.as_str()),
             args.get("timeout_ms").and_then(|v| v.as_u64()),
         )
         .await?;
+        redact_payload(&*pool.get().await?, &resolved.redaction, &mut result).await?;
+        if float_numeric {
+            numeric_as_float(&mut result);
+        }
         Ok(serde_json::json!({
             "success": true,
             "table": context.get("table").cloned().unwrap_or(Value::Null),
@@ -598,23 +940,75 @@ impl PostgresManager {
         }))
     }
 
-    async fn select(&self, args: &Value) -> Result<Value, ToolError> {
-        let (sql, params, context) = build_select_query(args, "select")?;
+    // One keyset page: rows ordered by the paginate keys, continuing after `paginate.after`.
+    // `next_after` is fed back as the next call's `after`; `has_more` comes from a look-ahead row.
+    async fn select_page(&self, args: &Value, keyset: Keyset) -> Result<Value, ToolError> {
+        for key in ["order_by", "order_by_sql", "limit"] {
+            if args.get(key).is_some_and(|v| !v.is_null()) {
+                return Err(ToolError::invalid_params(format!(
+                    "{} cannot be combined with paginate; rows are ordered by paginate.keys and sized by paginate.page_size",
+                    key
+                )));
+            }
+        }
+        let offset = normalize_limit(args.get("offset"), "offset")?;
+        if offset.is_some() && keyset.after.is_some() {
+            return Err(ToolError::invalid_params(
+                "offset cannot be combined with paginate.after",
+            ));
+        }
+        let (select_from, where_sql, mut params, context) = select_parts(args)?;
+        let sql = keyset.page_sql(&select_from, &where_sql, &mut params, offset)?;
+        let float_numeric = numeric_float_mode(args)?;
         let resolved = self.resolve_connection(args).await?;
         let pool = self.get_pool(&resolved).await?;
-        let result = execute_query_with_pool(
+        let mut result = execute_query_with_pool(
             &pool,
             &sql,
             &params,
-            args.get("mode").and_then(|v| v.as_str()),
+            Some("rows"),
             args.get("timeout_ms").and_then(|v| v.as_u64()),
         )
         .await?;
+        let mut rows = result
+            .get("rows")
+            .and_then(|v| v.as_array())
+            .cloned()
+            .unwrap_or_default();
+        let has_more = keyset.trim_page(&mut rows);
+        let cursor = rows.last().map(|row| keyset.cursor(row)).transpose()?;
+        result["rowCount"] = Value::from(rows.len());
+        result["rows"] = Value::Array(rows);
+        redact_payload(&*pool.get().await?, &resolved.redaction, &mut result).await?;
+        // The cursor is handed back verbatim, so keys the profile masks cannot page.
+        if let (Some(cursor), Some(last)) = (
+            cursor.as_ref(),
+            result
+                .get("rows")
+                .and_then(|v| v.as_array())
+                .and_then(|rows| rows.last()),
+        ) {
+            if keyset.cursor(last).ok().as_ref() != Some(cursor) {
+                return Err(ToolError::denied(
+                    "paginate.keys include a column redacted by the profile policy",
+                ));
+            }
+        }
+        if float_numeric {
+            numeric_as_float(&mut result);
+        }
+        let next_after = cursor
+            .or_else(|| keyset.after.clone())
+            .map(|cursor| keyset.after_value(&cursor))
+            .unwrap_or(Value::Null);
         Ok(serde_json::json!({
             "success": true,
             "table": context.get("table").cloned().unwrap_or(Value::Null),
             "schema": context.get("schema").cloned().unwrap_or(Value::Null),
+            "paginate": keyset.to_value(),
             "result": result,
+            "next_after": next_after,
+            "has_more": has_more,
         }))
     }
 
@@ -760,6 +1154,9 @@ impl PostgresManager {
         Ok(out)
     }
 
+    // Chunked reads go by keyset over the table's key when one is usable (see `source_keyset`),
+    // and otherwise through a server-side cursor in one read-only transaction; neither re-scans
+    // skipped rows the way LIMIT/OFFSET pages did.
     async fn export_to_writer<W>(&self, args: &Value, writer: &mut W) -> Result<Value, ToolError>
     where
         W: AsyncWrite + Unpin,
@@ -772,141 +1169,165 @@ impl PostgresManager {
         if format != "csv" && format != "jsonl" {
             return Err(ToolError::invalid_params("format must be csv or jsonl"));
         }
-        let batch_size = args
-            .get("batch_size")
-            .and_then(|v| v.as_u64())
-            .unwrap_or(1000) as usize;
         let base_offset = args.get("offset").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
-        let limit = args
-            .get("limit")
-            .and_then(|v| v.as_u64())
-            .map(|v| v as usize);
-        let header_enabled = args
-            .get("csv_header")
-            .and_then(|v| v.as_bool())
-            .unwrap_or(true);
-        let delimiter = args
-            .get("csv_delimiter")
-            .and_then(|v| v.as_str())
-            .unwrap_or(",")
-            .to_string();
+        let mut sink = ExportSink {
+            format,
+            header_enabled: args
+                .get("csv_header")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(true),
+            delimiter: args
+                .get("csv_delimiter")
+                .and_then(|v| v.as_str())
+                .unwrap_or(",")
+                .to_string(),
+            batch_size: args
+                .get("batch_size")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(1000)
+                .max(1) as usize,
+            limit: args
+                .get("limit")
+                .and_then(|v| v.as_u64())
+                .map(|v| v as usize),
+            rows_written: 0,
+            header_written: false,
+            columns: None,
+            redaction: None,
+        };
+        let timeout_ms = args.get("timeout_ms").and_then(|v| v.as_u64());
 
-        let (sql, params, context) = build_select_query(args, "export")?;
+        let (select_from, where_sql, params, context) = select_parts(args)?;
         let resolved = self.resolve_connection(args).await?;
         let pool = self.get_pool(&resolved).await?;
+        let keyset = {
+            let conn = pool.get().await?;
+            source_keyset(&*conn, args, &context).await?
+        };
 
-        let mut offset = base_offset;
-        let mut rows_written = 0usize;
-        let mut header_written = false;
-        let mut columns: Option<Vec<String>> = None;
-
-        loop {
-            let page_limit = match limit {
-                Some(limit) => {
-                    if rows_written >= limit {
-                        0
-                    } else {
-                        std::cmp::min(batch_size, limit - rows_written)
-                    }
+        let pagination = if let Some(mut keyset) = keyset {
+            let mut offset = Some(base_offset);
+            loop {
+                keyset.page_size = sink.next_page_size();
+                if keyset.page_size == 0 {
+                    break;
+                }
+                let mut page_params = params.clone();
+                let sql =
+                    keyset.page_sql(&select_from, &where_sql, &mut page_params, offset.take())?;
+                let result =
+                    execute_query_with_pool(&pool, &sql, &page_params, Some("rows"), timeout_ms)
+                        .await?;
+                let mut rows = result
+                    .get("rows")
+                    .and_then(|v| v.as_array())
+                    .cloned()
+                    .unwrap_or_default();
+                let has_more = keyset.trim_page(&mut rows);
+                let Some(last) = rows.last() else {
+                    break;
+                };
+                keyset.after = Some(keyset.cursor(last)?);
+                let conn = pool.get().await?;
+                sink.write_page(&*conn, &resolved.redaction, writer, &result, rows)
+                    .await?;
+                if !has_more {
+                    break;
                 }
-                None => batch_size,
-            };
-            if page_limit == 0 {
-                break;
             }
-
-            let paged_sql = format!("{} LIMIT {} OFFSET {}", sql, page_limit, offset);
-            let result = execute_query_with_pool(
-                &pool,
-                &paged_sql,
+            serde_json::json!({"mode": "keyset", "keys": keyset.keys})
+        } else {
+            let sql = format!(
+                "{}{}{}{}",
+                select_from,
+                if where_sql.is_empty() { "" } else { " WHERE " },
+                where_sql,
+                build_order_by(args.get("order_by"), args.get("order_by_sql"))?,
+            );
+            let sql = if base_offset > 0 {
+                format!("{} OFFSET {}", sql, base_offset)
+            } else {
+                sql
+            };
+            let mut conn = pool.get().await?;
+            let tx = conn
+                .build_transaction()
+                .read_only(true)
+                .start()
+                .await
+                .map_err(map_pg_error)?;
+            execute_query(
+                &tx,
+                &format!("DECLARE infra_export NO SCROLL CURSOR FOR {}", sql),
                 &params,
                 Some("rows"),
-                args.get("timeout_ms").and_then(|v| v.as_u64()),
+                timeout_ms,
             )
             .await?;
-            let rows = result
-                .get("rows")
-                .and_then(|v| v.as_array())
-                .cloned()
-                .unwrap_or_default();
-            if rows.is_empty() {
-                break;
-            }
-
-            if format == "csv" && header_enabled && !header_written {
-                if let Some(first) = rows.first().and_then(|v| v.as_object()) {
-                    columns = Some(first.keys().cloned().collect());
-                }
-                if let Some(cols) = columns.as_ref() {
-                    let line = cols
-                        .iter()
-                        .map(|c| csv_escape(c, &delimiter))
-                        .collect::<Vec<_>>()
-                        .join(&delimiter);
-                    writer
-                        .write_all(format!("{}\n", line).as_bytes())
-                        .await
-                        .ok();
-                }
-                header_written = true;
-            }
-
-            for row in rows {
-                if format == "jsonl" {
-                    writer.write_all(format!("{}\n", row).as_bytes()).await.ok();
-                } else {
-                    let cols = columns.clone().unwrap_or_else(|| {
-                        row.as_object()
-                            .map(|m| m.keys().cloned().collect())
-                            .unwrap_or_default()
-                    });
-                    let line = cols
-                        .iter()
-                        .map(|col| {
-                            let value = row.get(col.as_str()).cloned().unwrap_or(Value::Null);
-                            csv_escape(&value.to_string(), &delimiter)
-                        })
-                        .collect::<Vec<_>>()
-                        .join(&delimiter);
-                    writer
-                        .write_all(format!("{}\n", line).as_bytes())
-                        .await
-                        .ok();
+            loop {
+                let page_size = sink.next_page_size();
+                if page_size == 0 {
+                    break;
                 }
-                rows_written += 1;
-                if let Some(limit) = limit {
-                    if rows_written >= limit {
-                        break;
-                    }
+                let result = execute_query(
+                    &tx,
+                    &format!("FETCH FORWARD {} FROM infra_export", page_size),
+                    &[],
+                    Some("rows"),
+                    timeout_ms,
+                )
+                .await?;
+                let rows = result
+                    .get("rows")
+                    .and_then(|v| v.as_array())
+                    .cloned()
+                    .unwrap_or_default();
+                if rows.is_empty() {
+                    break;
                 }
-            }
-
-            if let Some(limit) = limit {
-                if rows_written >= limit {
+                let fetched = rows.len();
+                sink.write_page(&tx, &resolved.redaction, writer, &result, rows)
+                    .await?;
+                if fetched < page_size {
                     break;
                 }
             }
-            offset += page_limit;
-        }
+            tx.commit().await.map_err(map_pg_error)?;
+            serde_json::json!({"mode": "cursor"})
+        };
 
-        Ok(serde_json::json!({
+        let mut out = serde_json::json!({
             "success": true,
             "table": context.get("table").cloned().unwrap_or(Value::Null),
             "schema": context.get("schema").cloned().unwrap_or(Value::Null),
-            "format": format,
-            "rows_written": rows_written,
-        }))
+            "format": sink.format,
+            "rows_written": sink.rows_written,
+            "pagination": pagination,
+        });
+        if let Some(plan) = sink.redaction.filter(|plan| !plan.is_empty()) {
+            out["redaction"] = plan.note();
+        }
+        Ok(out)
+    }
+
+    // Keyset order for chunked reads of a postgres source, if the source has a usable key.
+    pub(crate) async fn source_keyset(&self, args: &Value) -> Result<Option<Keyset>, ToolError> {
+        let (_, _, _, context) = select_parts(args)?;
+        let resolved = self.resolve_connection(args).await?;
+        let pool = self.get_pool(&resolved).await?;
+        let conn = pool.get().await?;
+        source_keyset(&*conn, args, &context).await
     }
 
     pub(crate) fn export_stream(&self, args: &Value) -> ExportStream {
         let (mut writer, reader) = tokio::io::duplex(64 * 1024);
         let args = args.clone();
         let manager = self.clone();
-        let completion = tokio::spawn(async move {
+        let completion = tokio::spawn(usage::in_current_scope(async move {
             let result = manager.export_to_writer(&args, &mut writer).await;
             let _ = writer.shutdown().await;
             result
-        });
+        }));
         ExportStream { reader, completion }
     }
 
@@ -932,23 +1353,231 @@ impl PostgresManager {
             .get("schema")
             .and_then(|v| v.as_str())
             .unwrap_or("public");
-        let sql = "SELECT column_name, data_type, is_nullable, column_default, character_maximum_length, numeric_precision, numeric_scale FROM information_schema.columns WHERE table_schema = $1 AND table_name = $2 ORDER BY ordinal_position";
+        let sql = "SELECT column_name, data_type, udt_name, is_nullable, column_default, character_maximum_length, numeric_precision, numeric_scale FROM information_schema.columns WHERE table_schema = $1 AND table_name = $2 ORDER BY ordinal_position";
         let params = vec![
             Value::String(schema.to_string()),
             Value::String(table.clone()),
         ];
-        let result = self.query_with_params(args, sql, &params).await?;
+        let mut result = self.query_with_params(args, sql, &params).await?;
+        // `type` matches `fields[].dataType` of query results over the same column.
+        if let Some(rows) = result.get_mut("rows").and_then(|v| v.as_array_mut()) {
+            for row in rows.iter_mut() {
+                let label = row.get("udt_name").and_then(|v| v.as_str()).map(type_label);
+                if let (Some(label), Value::Object(map)) = (label, row) {
+                    map.insert("type".to_string(), Value::String(label));
+                }
+            }
+        }
         Ok(
             serde_json::json!({"success": true, "table": table, "schema": schema, "columns": result.get("rows").cloned().unwrap_or(Value::Null)}),
         )
     }
 
-    async fn database_info(&self, args: &Value) -> Result<Value, ToolError> {
-        let sql = "SELECT current_database() AS database_name, current_user AS current_user, version() AS version, pg_size_pretty(pg_database_size(current_database())) AS size";
-        self.query_with_params(args, sql, &[]).await
-    }
+    // Compares the catalogs behind `side_a` and `side_b`; each side is a connection selector
+    // (profile name string or an object with profile_name / connection / project+target)
+    // overlaid on the args.
+    async fn catalog_diff(&self, args: &Value) -> Result<Value, ToolError> {
+        let side_a = catalog_diff_side(args, "side_a")?;
+        let side_b = catalog_diff_side(args, "side_b")?;
+        let schemas = string_list(args.get("schemas"), "schemas")?
+            .unwrap_or_else(|| DEFAULT_DIFF_SCHEMAS.iter().map(|s| s.to_string()).collect());
+        if schemas.is_empty() {
+            return Err(ToolError::invalid_params("schemas must not be empty"));
+        }
+        let ignore = string_list(args.get("ignore"), "ignore")?.unwrap_or_default();
+        let format = args
+            .get("format")
+            .and_then(|v| v.as_str())
+            .unwrap_or("json");
+        if format != "json" && format != "sql_hint" {
+            return Err(ToolError::invalid_params("format must be json or sql_hint"));
+        }
 
-    async fn query_with_params(
+        let (catalog_a, catalog_b) = tokio::try_join!(
+            self.read_catalog(&side_a, "side_a", &schemas, &ignore),
+            self.read_catalog(&side_b, "side_b", &schemas, &ignore),
+        )?;
+        let diff = diff_catalogs(&catalog_a, &catalog_b);
+        let hints = (format == "sql_hint").then(|| sql_hints(&catalog_a, &catalog_b));
+
+        let mut out = if diff_item_count(&diff) > MAX_INLINE_DIFF_ITEMS {
+            let mut inline = truncate_diff(&diff, MAX_INLINE_DIFF_ITEMS);
+            inline["truncated"] = Value::Bool(true);
+            if let Some(root) = resolve_context_root() {
+                let trace = TraceContext::from_args(args);
+                let reference = build_tool_call_file_ref(
+                    Some(&trace.trace_id),
+                    Some(&trace.span_id),
+                    "catalog_diff.json",
+                )?;
+                let mut full = diff.clone();
+                if let Some(hints) = &hints {
+                    full["sql_hints"] = Value::Array(hints.clone());
+                }
+                let content = serde_json::to_string_pretty(&full).unwrap_or_default();
+                inline["diff_ref"] =
+                    Value::String(write_text_artifact(&root, &reference, &content)?.uri);
+            }
+            inline
+        } else {
+            diff
+        };
+        out["success"] = Value::Bool(true);
+        out["schemas"] = serde_json::json!(schemas);
+        if let Some(hints) = hints {
+            out["sql_hints"] = Value::Array(hints);
+            out["sql_hints_note"] = Value::String(
+                "Best-effort suggestions to turn side_a into side_b; not guaranteed safe or complete. Review locking, data loss and ordering before running any of them.".to_string(),
+            );
+        }
+        Ok(out)
+    }
+
+    async fn read_catalog(
+        &self,
+        args: &Value,
+        side: &str,
+        schemas: &[String],
+        ignore: &[String],
+    ) -> Result<SchemaCatalog, ToolError> {
+        let tag = |mut err: ToolError| {
+            err.message = format!("catalog_diff {}: {}", side, err.message);
+            err
+        };
+        let resolved = self.resolve_connection(args).await.map_err(tag)?;
+        let pool = self.get_pool(&resolved).await.map_err(tag)?;
+        let timeout_ms = args.get("timeout_ms").and_then(|v| v.as_u64());
+        let params: Vec<Value> = schemas.iter().map(|s| Value::String(s.clone())).collect();
+        let mut rows = Vec::with_capacity(3);
+        for sql in catalog_queries(schemas.len()) {
+            let result = execute_query_with_pool(&pool, &sql, &params, Some("rows"), timeout_ms)
+                .await
+                .map_err(tag)?;
+            rows.push(
+                result
+                    .get("rows")
+                    .and_then(|v| v.as_array())
+                    .cloned()
+                    .unwrap_or_default(),
+            );
+        }
+        Ok(SchemaCatalog::from_rows(
+            &rows[0], &rows[1], &rows[2], ignore,
+        ))
+    }
+
+    async fn database_info(&self, args: &Value) -> Result<Value, ToolError> {
+        let sql = "SELECT current_database() AS database_name, current_user AS current_user, version() AS version, pg_size_pretty(pg_database_size(current_database())) AS size";
+        let reports = parse_reports(args.get("reports"))?;
+        if reports.is_empty() {
+            return self.query_with_params(args, sql, &[]).await;
+        }
+
+        let resolved = self.resolve_connection(args).await?;
+        let pool = self.get_pool(&resolved).await?;
+        let timeout_ms = args.get("timeout_ms").and_then(|v| v.as_u64());
+        let mut out = execute_query_with_pool(&pool, sql, &[], Some("rows"), timeout_ms).await?;
+        let status =
+            execute_query_with_pool(&pool, recovery_status_sql(), &[], Some("row"), timeout_ms)
+                .await?;
+        let version_num = status
+            .pointer("/row/version_num")
+            .and_then(|v| v.as_i64())
+            .unwrap_or(0);
+        let in_recovery = status
+            .pointer("/row/in_recovery")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        if version_num < PG_REPORTS_MIN_VERSION {
+            return Err(ToolError::invalid_params(format!(
+                "database_info reports require PostgreSQL 12+ (server_version_num={})",
+                version_num
+            ))
+            .with_hint("Call database_info without reports on older servers."));
+        }
+
+        let options = PgReportOptions {
+            version_num,
+            include_queries: args
+                .get("include_queries")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false),
+        };
+        let limit = normalize_limit(args.get("limit"), "limit")?
+            .unwrap_or(DEFAULT_REPORT_LIMIT)
+            .clamp(1, MAX_REPORT_LIMIT);
+        let min_duration_ms =
+            normalize_limit(args.get("min_duration_ms"), "min_duration_ms")?.unwrap_or(0);
+
+        let mut sections = serde_json::Map::new();
+        for report in reports {
+            let section = match report {
+                "activity" => {
+                    let mut section = report_rows(
+                        &pool,
+                        &activity_sql(&options),
+                        &[Value::from(min_duration_ms as u64)],
+                        limit,
+                        timeout_ms,
+                    )
+                    .await?;
+                    section["min_duration_ms"] = Value::from(min_duration_ms as u64);
+                    section
+                }
+                "locks" => report_rows(&pool, &locks_sql(&options), &[], limit, timeout_ms).await?,
+                _ => {
+                    let standbys =
+                        report_rows(&pool, &replication_senders_sql(), &[], limit, timeout_ms)
+                            .await?;
+                    let slots = report_rows(
+                        &pool,
+                        &replication_slots_sql(&options),
+                        &[],
+                        limit,
+                        timeout_ms,
+                    )
+                    .await?;
+                    let receiver = if in_recovery {
+                        execute_query_with_pool(
+                            &pool,
+                            &replica_status_sql(&options),
+                            &[],
+                            Some("row"),
+                            timeout_ms,
+                        )
+                        .await?
+                        .get("row")
+                        .cloned()
+                        .unwrap_or(Value::Null)
+                    } else {
+                        Value::Null
+                    };
+                    serde_json::json!({
+                        "role": if in_recovery { "replica" } else { "primary" },
+                        "standbys": standbys,
+                        "slots": slots,
+                        "receiver": receiver,
+                    })
+                }
+            };
+            sections.insert(report.to_string(), section);
+        }
+
+        if let Value::Object(map) = &mut out {
+            map.insert(
+                "server".to_string(),
+                serde_json::json!({ "version_num": version_num, "in_recovery": in_recovery }),
+            );
+            map.insert(
+                "include_queries".to_string(),
+                Value::Bool(options.include_queries),
+            );
+            map.insert("reports".to_string(), Value::Object(sections));
+        }
+        Ok(out)
+    }
+
+    async fn query_with_params(
         &self,
         args: &Value,
         sql: &str,
@@ -998,13 +1627,15 @@ impl PostgresManager {
                 merged.clone()
             };
             let pool_options = pool_options_from_value(resolved.get("pool"));
-            let config = build_config_from_value(&resolved, None)?;
-            let key_seed = format!("profile:{}", profile_name);
+            let (config, tls) = build_config_from_value(&resolved, None)?;
+            let key_seed = format!("profile:{}:{}", profile_name, tls.fingerprint());
             return Ok(ResolvedConnection {
                 config,
                 pool_options,
+                tls,
                 profile_name: Some(profile_name),
                 key_seed,
+                redaction: resolve_redaction(args, &resolved)?,
             });
         }
 
@@ -1021,13 +1652,19 @@ impl PostgresManager {
         };
         let pool_options = pool_options_from_value(resolved.get("pool"))
             .merge(pool_options_from_value(args.get("pool")));
-        let config = build_config_from_value(&resolved, connection_url)?;
-        let key_seed = format!("inline:{}", hash_seed(&resolved, &pool_options));
+        let (config, tls) = build_config_from_value(&resolved, connection_url)?;
+        let key_seed = format!(
+            "inline:{}:{}",
+            hash_seed(&resolved, &pool_options),
+            tls.fingerprint()
+        );
         Ok(ResolvedConnection {
             config,
             pool_options,
+            tls,
             profile_name: None,
             key_seed,
+            redaction: resolve_redaction(args, &resolved)?,
         })
     }
 
@@ -1067,22 +1704,31 @@ impl PostgresManager {
         Ok(None)
     }
 
-    async fn get_pool(
-        &self,
-        resolved: &ResolvedConnection,
-    ) -> Result<Pool<PostgresConnectionManager<NoTls>>, ToolError> {
-        if let Some(profile_name) = resolved.profile_name.as_ref() {
-            let key = format!("profile:{}", profile_name);
-            if let Some(existing) = self.pools.get(&key) {
-                return Ok(existing.value().clone());
-            }
-        }
+    // Dropping the last handle to a pool closes its idle connections; checked-out ones close as
+    // their calls return them.
+    pub fn close_pools(&self) -> usize {
+        let count = self.pools.len();
+        self.pools.clear();
+        count
+    }
+
+    fn drop_profile_pools(&self, profile_name: &str) {
+        let prefix = format!("profile:{}:", profile_name);
+        self.pools.retain(|key, _| !key.starts_with(&prefix));
+    }
+
+    async fn get_pool(&self, resolved: &ResolvedConnection) -> Result<PgPool, ToolError> {
         if let Some(existing) = self.pools.get(&resolved.key_seed) {
             return Ok(existing.value().clone());
         }
 
-        let manager = PostgresConnectionManager::new(resolved.config.clone(), NoTls);
-        let mut builder = Pool::builder();
+        let manager =
+            PostgresConnectionManager::new(resolved.config.clone(), resolved.tls.connector()?);
+        let last_error = Arc::new(std::sync::Mutex::new(None));
+        let mut builder = Pool::builder().error_sink(Box::new(ConnectErrorSink {
+            tls: resolved.tls.clone(),
+            last_error: last_error.clone(),
+        }));
         if let Some(max) = resolved.pool_options.max_size {
             builder = builder.max_size(max);
         } else {
@@ -1102,38 +1748,45 @@ impl PostgresManager {
             ));
         }
 
-        let pool = builder.build(manager).await.map_err(map_pool_error)?;
-        let key = resolved.key_seed.clone();
-        self.pools.insert(key.clone(), pool.clone());
+        let pool = builder
+            .build(manager)
+            .await
+            .map_err(|err| map_connect_error(&err, &resolved.tls))?;
+        let pool = PgPool {
+            pool,
+            tls: resolved.tls.clone(),
+            last_error,
+        };
+        // Pools are keyed by the TLS fingerprint, so a cert or sslmode change starts a new
+        // pool; the superseded one for the same profile is dropped.
         if let Some(profile_name) = resolved.profile_name.as_ref() {
-            self.pools
-                .insert(format!("profile:{}", profile_name), pool.clone());
+            self.drop_profile_pools(profile_name);
         }
+        self.pools.insert(resolved.key_seed.clone(), pool.clone());
         Ok(pool)
     }
 
-    async fn test_connection(
-        &self,
-        config: &Config,
-        pool_value: Option<&Value>,
-    ) -> Result<(), ToolError> {
-        let manager = PostgresConnectionManager::new(config.clone(), NoTls);
-        let mut builder = Pool::builder();
-        if let Some(pool_opts) = pool_value {
-            let opts = pool_options_from_value(Some(pool_opts));
-            if let Some(max) = opts.max_size {
-                builder = builder.max_size(max);
-            }
+    async fn test_connection(&self, config: &Config, tls: &PgTlsConfig) -> Result<(), ToolError> {
+        let mut config = config.clone();
+        if config.get_connect_timeout().is_none() {
+            config.connect_timeout(Duration::from_millis(
+                network_constants::TIMEOUT_CONNECTION_MS,
+            ));
         }
-        let pool = builder.build(manager).await.map_err(map_pool_error)?;
-        let conn = pool.get().await.map_err(map_pool_error)?;
-        conn.query("SELECT 1", &[]).await.map_err(map_pg_error)?;
-        drop(conn);
-        Ok(())
+        let (client, connection) = config
+            .connect(tls.connector()?)
+            .await
+            .map_err(|err| map_connect_error(&err, tls))?;
+        let driver = tokio::spawn(connection);
+        let result = client.simple_query("SELECT 1").await.map_err(map_pg_error);
+        drop(client);
+        let _ = driver.await;
+        result.map(|_| ())
     }
 }
 
-fn build_select_query(args: &Value, mode: &str) -> Result<(String, Vec<Value>, Value), ToolError> {
+// `SELECT <columns> FROM <table>`, the WHERE condition (without the keyword) and its params.
+fn select_parts(args: &Value) -> Result<(String, String, Vec<Value>, Value), ToolError> {
     let table = args.get("table").and_then(|v| v.as_str()).unwrap_or("");
     let schema = args.get("schema").and_then(|v| v.as_str());
     let context = normalize_table_context(table, schema)?;
@@ -1144,29 +1797,26 @@ fn build_select_query(args: &Value, mode: &str) -> Result<(String, Vec<Value>, V
         args.get("where_params").and_then(|v| v.as_array()),
         1,
     )?;
-    let order_by_sql = if mode == "select" {
-        build_order_by(args.get("order_by"), args.get("order_by_sql"))?
-    } else {
-        String::new()
-    };
-    let limit = if mode == "select" {
-        normalize_limit(args.get("limit"), "limit")?
-    } else {
-        None
-    };
-    let offset = if mode == "select" {
-        normalize_limit(args.get("offset"), "offset")?
-    } else {
-        None
-    };
-
-    let sql = format!(
-        "SELECT {} FROM {}{}{}{}{}",
+    let select_from = format!(
+        "SELECT {} FROM {}",
         columns_sql,
         context
             .get("qualified")
             .and_then(|v| v.as_str())
             .unwrap_or(""),
+    );
+    Ok((select_from, where_sql, params, context))
+}
+
+fn build_select_query(args: &Value) -> Result<(String, Vec<Value>, Value), ToolError> {
+    let (select_from, where_sql, params, context) = select_parts(args)?;
+    let order_by_sql = build_order_by(args.get("order_by"), args.get("order_by_sql"))?;
+    let limit = normalize_limit(args.get("limit"), "limit")?;
+    let offset = normalize_limit(args.get("offset"), "offset")?;
+
+    let sql = format!(
+        "{}{}{}{}{}",
+        select_from,
         if where_sql.is_empty() { "" } else { " WHERE " },
         if where_sql.is_empty() {
             ""
@@ -1318,41 +1968,153 @@ fn build_order_by(
     }
 }
 
-fn build_returning(returning: Option<&Value>) -> String {
-    let Some(value) = returning else {
-        return String::new();
-    };
-    if value.is_boolean() {
-        if value.as_bool().unwrap_or(false) {
-            return " RETURNING *".to_string();
+// Writes fetched export pages as csv or jsonl, redacting with a plan built from the first page.
+struct ExportSink {
+    format: String,
+    header_enabled: bool,
+    delimiter: String,
+    batch_size: usize,
+    limit: Option<usize>,
+    rows_written: usize,
+    header_written: bool,
+    columns: Option<Vec<String>>,
+    redaction: Option<RedactionPlan>,
+}
+
+impl ExportSink {
+    fn next_page_size(&self) -> usize {
+        match self.limit {
+            Some(limit) => self.batch_size.min(limit.saturating_sub(self.rows_written)),
+            None => self.batch_size,
         }
-        return String::new();
     }
-    if let Some(text) = value.as_str() {
-        if text.trim().is_empty() {
-            return String::new();
+
+    async fn write_page<C, W>(
+        &mut self,
+        client: &C,
+        policy: &RedactionPolicy,
+        writer: &mut W,
+        result: &Value,
+        mut rows: Vec<Value>,
+    ) -> Result<(), ToolError>
+    where
+        C: GenericClient + Sync,
+        W: AsyncWrite + Unpin,
+    {
+        if self.redaction.is_none() {
+            let fields = result.get("fields").unwrap_or(&Value::Null);
+            self.redaction = Some(redaction_plan(client, policy, fields).await?);
         }
-        return format!(
-            " RETURNING {}",
-            quote_qualified_identifier(text).unwrap_or_else(|_| text.to_string())
-        );
-    }
-    if let Some(arr) = value.as_array() {
-        if arr.is_empty() {
-            return String::new();
+        if let Some(plan) = self.redaction.as_ref() {
+            rows.iter_mut().for_each(|row| plan.apply_row(row));
         }
-        let mut cols = Vec::new();
-        for col in arr {
-            if let Some(text) = col.as_str() {
-                cols.push(quote_qualified_identifier(text).unwrap_or_else(|_| text.to_string()));
+
+        let delimiter = self.delimiter.as_str();
+        if self.format == "csv" && self.header_enabled && !self.header_written {
+            if let Some(first) = rows.first().and_then(|v| v.as_object()) {
+                self.columns = Some(first.keys().cloned().collect());
+            }
+            if let Some(cols) = self.columns.as_ref() {
+                let line = cols
+                    .iter()
+                    .map(|c| csv_escape(c, delimiter))
+                    .collect::<Vec<_>>()
+                    .join(delimiter);
+                writer
+                    .write_all(format!("{}\n", line).as_bytes())
+                    .await
+                    .ok();
             }
+            self.header_written = true;
         }
-        if cols.is_empty() {
-            return String::new();
+
+        for row in rows {
+            if self.limit.is_some_and(|limit| self.rows_written >= limit) {
+                break;
+            }
+            if self.format == "jsonl" {
+                writer.write_all(format!("{}\n", row).as_bytes()).await.ok();
+            } else {
+                let cols = self.columns.clone().unwrap_or_else(|| {
+                    row.as_object()
+                        .map(|m| m.keys().cloned().collect())
+                        .unwrap_or_default()
+                });
+                let line = cols
+                    .iter()
+                    .map(|col| {
+                        let value = row.get(col.as_str()).cloned().unwrap_or(Value::Null);
+                        csv_escape(&value.to_string(), delimiter)
+                    })
+                    .collect::<Vec<_>>()
+                    .join(delimiter);
+                writer
+                    .write_all(format!("{}\n", line).as_bytes())
+                    .await
+                    .ok();
+            }
+            self.rows_written += 1;
         }
-        return format!(" RETURNING {}", cols.join(", "));
+        Ok(())
     }
-    String::new()
+}
+
+// `paginate.keys` when given; otherwise the primary key, as long as any `order_by` names exactly
+// those columns in one direction and every key column is selected.
+async fn source_keyset<C: GenericClient + Sync>(
+    client: &C,
+    args: &Value,
+    context: &Value,
+) -> Result<Option<Keyset>, ToolError> {
+    if let Some(paginate) = args.get("paginate").filter(|v| !v.is_null()) {
+        return Keyset::parse(paginate).map(Some);
+    }
+    let present = |key: &str| args.get(key).is_some_and(|v| !v.is_null());
+    if present("columns_sql") || present("order_by_sql") {
+        return Ok(None);
+    }
+    let requested = match args.get("order_by").filter(|v| !v.is_null()) {
+        Some(order_by) => match ordering(order_by) {
+            Some(requested) => Some(requested),
+            None => return Ok(None),
+        },
+        None => None,
+    };
+    let qualified = context
+        .get("qualified")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    let primary_key: Vec<String> = client
+        .query(
+            "SELECT a.attname::text FROM pg_index i \
+             JOIN pg_attribute a ON a.attrelid = i.indrelid AND a.attnum = ANY(i.indkey) \
+             WHERE i.indrelid = to_regclass($1::text) AND i.indisprimary \
+             ORDER BY array_position(i.indkey::int2[], a.attnum)",
+            &[&qualified],
+        )
+        .await
+        .map_err(map_pg_error)?
+        .iter()
+        .map(|row| row.get(0))
+        .collect();
+    if primary_key.is_empty() {
+        return Ok(None);
+    }
+    let descending = match requested {
+        Some((keys, descending)) if keys == primary_key => descending,
+        Some(_) => return Ok(None),
+        None => false,
+    };
+    if let Some(Value::Array(columns)) = args.get("columns") {
+        let selected: Vec<&str> = columns.iter().filter_map(|c| c.as_str()).collect();
+        if !primary_key
+            .iter()
+            .all(|key| selected.contains(&key.as_str()))
+        {
+            return Ok(None);
+        }
+    }
+    Ok(Some(Keyset::new(primary_key, descending)))
 }
 
 fn csv_escape(value: &str, delimiter: &str) -> String {
@@ -1399,11 +2161,14 @@ fn split_connection_secrets(
 fn build_config_from_value(
     connection: &Value,
     connection_url: Option<&str>,
-) -> Result<Config, ToolError> {
+) -> Result<(Config, PgTlsConfig), ToolError> {
+    let mut url_tls_params = Default::default();
     let mut config = if let Some(url) =
         connection_url.or_else(|| connection.get("connection_url").and_then(|v| v.as_str()))
     {
-        Config::from_str(url).map_err(|_| ToolError::invalid_params("Invalid connection_url"))?
+        let (url, tls_params) = split_tls_params(url);
+        url_tls_params = tls_params;
+        Config::from_str(&url).map_err(|_| ToolError::invalid_params("Invalid connection_url"))?
     } else {
         Config::new()
     };
@@ -1433,7 +2198,9 @@ fn build_config_from_value(
     if let Some(options) = connection.get("options").and_then(|v| v.as_str()) {
         config.options(options);
     }
-    Ok(config)
+    let tls = PgTlsConfig::resolve(connection, &url_tls_params)?;
+    tls.apply(&mut config);
+    Ok((config, tls))
 }
 
 fn pool_options_from_value(value: Option<&Value>) -> PoolOptions {
@@ -1493,6 +2260,107 @@ fn hash_seed(connection: &Value, pool: &PoolOptions) -> String {
     hex::encode(hasher.finalize())
 }
 
+// Prepares first so each JSON value is converted for the type the server inferred. Types
+// without a native conversion are re-prepared with a `$n::text::<type>` cast so Postgres
+// parses the text form itself.
+async fn bind_statement<C: GenericClient + Sync>(
+    client: &C,
+    sql: &str,
+    params: &[Value],
+    names: &[String],
+) -> Result<(Statement, Vec<PgParam>, Vec<Type>), ToolError> {
+    let mut statement = client.prepare(sql).await.map_err(map_pg_error)?;
+    let fallback: BTreeMap<usize, String> = statement
+        .params()
+        .iter()
+        .enumerate()
+        .filter(|(_, ty)| !binds_natively(ty) && !matches!(ty.kind(), Kind::Array(_)))
+        .map(|(idx, ty)| {
+            let qualified = format!(
+                "\"{}\".\"{}\"",
+                ty.schema().replace('"', "\"\""),
+                ty.name().replace('"', "\"\"")
+            );
+            (idx + 1, format!("text::{}", qualified))
+        })
+        .collect();
+    let mut prepared_sql = sql.to_string();
+    if !fallback.is_empty() {
+        prepared_sql = cast_placeholders(sql, &fallback);
+        statement = client.prepare(&prepared_sql).await.map_err(map_pg_error)?;
+    }
+    let source_types: Vec<Type> = statement
+        .columns()
+        .iter()
+        .map(|col| col.type_().clone())
+        .collect();
+    if let Some(wrapped) = cast_result_columns(&prepared_sql, &statement) {
+        // Statements that cannot sit in a CTE keep the original shape; their
+        // undecodable columns then come back as raw text where possible.
+        if let Ok(cast) = client.prepare_typed(&wrapped, statement.params()).await {
+            statement = cast;
+        }
+    }
+    if statement.params().len() != params.len() {
+        return Err(ToolError::invalid_params(format!(
+            "sql expects {} parameter(s), got {}",
+            statement.params().len(),
+            params.len()
+        )));
+    }
+    let bindings = statement
+        .params()
+        .iter()
+        .zip(params)
+        .enumerate()
+        .map(|(idx, (ty, value))| {
+            to_pg_param(value, ty).map_err(|err| {
+                let name = names
+                    .get(idx)
+                    .cloned()
+                    .unwrap_or_else(|| format!("${}", idx + 1));
+                ToolError::invalid_params(format!(
+                    "Cannot bind param {} as {}: {}",
+                    name,
+                    ty.name(),
+                    err
+                ))
+                .with_hint("Pass a value of the expected type or set param_types for it.")
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((statement, bindings, source_types))
+}
+
+// Rewrites a statement whose result has columns of types `row_to_value` cannot decode
+// so those columns are cast to text server-side, keeping names and order.
+fn cast_result_columns(sql: &str, statement: &Statement) -> Option<String> {
+    let columns = statement.columns();
+    if columns.iter().all(|col| decodes(col.type_())) {
+        return None;
+    }
+    let body = sql.trim().trim_end_matches(';').trim_end();
+    let aliases: Vec<String> = (1..=columns.len()).map(|idx| format!("c{}", idx)).collect();
+    let select: Vec<String> = columns
+        .iter()
+        .zip(&aliases)
+        .map(|(col, alias)| {
+            let name = format!("\"{}\"", col.name().replace('"', "\"\""));
+            if decodes(col.type_()) {
+                format!("{} AS {}", alias, name)
+            } else {
+                format!("{}::text AS {}", alias, name)
+            }
+        })
+        .collect();
+    Some(format!(
+        "WITH infra_result({}) AS (\n{}\n) SELECT {} FROM infra_result",
+        aliases.join(", "),
+        body,
+        select.join(", ")
+    ))
+}
+
 async fn execute_query<C: GenericClient + Sync>(
     client: &C,
     sql: &str,
@@ -1500,33 +2368,393 @@ async fn execute_query<C: GenericClient + Sync>(
     mode: Option<&str>,
     timeout_ms: Option<u64>,
 ) -> Result<Value, ToolError> {
-    let bindings = build_params(params);
-    let bind_refs: Vec<&(dyn ToSql + Sync)> = bindings
+    let bound = BoundParams {
+        sql: sql.to_string(),
+        values: params.to_vec(),
+        names: Vec::new(),
+    };
+    execute_named_query(client, &bound, mode, timeout_ms).await
+}
+
+struct BatchStatement {
+    index: usize,
+    name: Option<String>,
+    bound: BoundParams,
+    mode: Option<String>,
+    timeout_ms: Option<u64>,
+}
+
+impl BatchStatement {
+    // Every result carries `index` (and `name` when given); a failure becomes a
+    // `success: false` entry with the error code, as ssh batch reports failed commands.
+    fn annotate(&self, outcome: Result<Value, ToolError>, float_numeric: bool) -> Value {
+        let mut entry = match outcome {
+            Ok(mut result) => {
+                if float_numeric {
+                    numeric_as_float(&mut result);
+                }
+                result
+            }
+            Err(err) => {
+                let mut failure = serde_json::json!({
+                    "success": false,
+                    "error": err.message,
+                    "code": err.code,
+                    "kind": err.kind,
+                });
+                if err.retryable {
+                    failure["retryable"] = Value::Bool(true);
+                }
+                if let (Some(details), Value::Object(map)) = (err.details, &mut failure) {
+                    map.insert("details".to_string(), details);
+                }
+                failure
+            }
+        };
+        if let Value::Object(map) = &mut entry {
+            map.insert("index".to_string(), Value::from(self.index));
+            if let Some(name) = &self.name {
+                map.insert("name".to_string(), Value::String(name.clone()));
+            }
+        }
+        entry
+    }
+}
+
+fn parse_batch_statements(items: &[Value]) -> Result<Vec<BatchStatement>, ToolError> {
+    let mut names = std::collections::HashSet::new();
+    let mut statements = Vec::with_capacity(items.len());
+    for (index, item) in items.iter().enumerate() {
+        let (sql, spec) = match item {
+            Value::String(sql) => (sql.as_str(), None),
+            Value::Object(_) => (
+                item.get("sql").and_then(|v| v.as_str()).unwrap_or(""),
+                Some(item),
+            ),
+            _ => {
+                return Err(ToolError::invalid_params(format!(
+                    "statements[{}] must be a SQL string or an object with sql",
+                    index
+                )))
+            }
+        };
+        if sql.trim().is_empty() {
+            return Err(ToolError::invalid_params(format!(
+                "statements[{}].sql is required",
+                index
+            )));
+        }
+        let field = |key: &str| spec.and_then(|spec| spec.get(key));
+        let name = match field("name") {
+            None | Some(Value::Null) => None,
+            Some(Value::String(name)) if !name.trim().is_empty() => Some(name.trim().to_string()),
+            Some(_) => {
+                return Err(ToolError::invalid_params(format!(
+                    "statements[{}].name must be a non-empty string",
+                    index
+                )))
+            }
+        };
+        if let Some(name) = &name {
+            if !names.insert(name.clone()) {
+                return Err(ToolError::invalid_params(format!(
+                    "statements[{}].name {:?} is used more than once",
+                    index, name
+                ))
+                .with_hint("Statement names key the results, so each must be unique."));
+            }
+        }
+        let timeout_ms = match field("timeout_ms") {
+            None | Some(Value::Null) => None,
+            Some(value) => match value.as_u64() {
+                Some(ms) if ms > 0 => Some(ms),
+                _ => {
+                    return Err(ToolError::invalid_params(format!(
+                        "statements[{}].timeout_ms must be a positive integer",
+                        index
+                    )))
+                }
+            },
+        };
+        let bound =
+            bind_named_params(sql, field("params"), field("param_types")).map_err(|err| {
+                ToolError {
+                    message: format!("statements[{}]: {}", index, err.message),
+                    ..err
+                }
+            })?;
+        statements.push(BatchStatement {
+            index,
+            name,
+            bound,
+            mode: field("mode").and_then(|v| v.as_str()).map(str::to_string),
+            timeout_ms,
+        });
+    }
+    Ok(statements)
+}
+
+// SQLSTATE 57014 (query_canceled) after `SET LOCAL statement_timeout` is the statement's own
+// timeout, reported as such.
+// One attempt at a transactional batch. Inside a transaction the timeout is enforced
+// server-side (`SET LOCAL`), so a slow statement is cancelled by Postgres instead of leaving the
+// session mid-query. With `stop_on_error=false` every statement runs under a savepoint so a
+// failure only rolls back that statement. When `retry_serialization` applies, a serialization
+// failure or deadlock rolls everything back and surfaces as the error, so the caller re-runs the
+// whole body.
+async fn run_transaction(
+    pool: &PgPool,
+    statements: &[BatchStatement],
+    redaction: &RedactionPolicy,
+    stop_on_error: bool,
+    float_numeric: bool,
+    retry_enabled: bool,
+) -> Result<(Vec<Value>, usize, bool), ToolError> {
+    let mut results = Vec::new();
+    let mut failed = 0usize;
+    let mut conn = pool.get().await?;
+    let mut transaction = conn.transaction().await.map_err(map_pg_error)?;
+    for statement in statements {
+        let outcome = if stop_on_error {
+            run_batch_statement(&transaction, statement, redaction).await
+        } else {
+            let savepoint = transaction
+                .savepoint(format!("infra_batch_{}", statement.index))
+                .await
+                .map_err(map_pg_error)?;
+            let outcome = run_batch_statement(&savepoint, statement, redaction).await;
+            if outcome.is_ok() {
+                savepoint.commit().await.map_err(map_pg_error)?;
+            } else {
+                savepoint.rollback().await.map_err(map_pg_error)?;
+            }
+            outcome
+        };
+        let outcome = match outcome {
+            Err(err) if retry_enabled && is_retryable_sqlstate(&err) => {
+                transaction.rollback().await.map_err(map_pg_error)?;
+                return Err(err);
+            }
+            other => other,
+        };
+        let ok = outcome.is_ok();
+        results.push(statement.annotate(outcome, float_numeric));
+        if !ok {
+            failed += 1;
+            if stop_on_error {
+                break;
+            }
+        }
+    }
+    let committed = !(stop_on_error && failed > 0);
+    if committed {
+        transaction.commit().await.map_err(map_pg_error)?;
+    } else {
+        transaction.rollback().await.map_err(map_pg_error)?;
+    }
+    Ok((results, failed, committed))
+}
+
+async fn run_batch_statement<C: GenericClient + Sync>(
+    client: &C,
+    statement: &BatchStatement,
+    redaction: &RedactionPolicy,
+) -> Result<Value, ToolError> {
+    if let Some(timeout_ms) = statement.timeout_ms {
+        client
+            .batch_execute(&format!("SET LOCAL statement_timeout = {}", timeout_ms))
+            .await
+            .map_err(map_pg_error)?;
+    }
+    let mut result = match execute_named_query(
+        client,
+        &statement.bound,
+        statement.mode.as_deref(),
+        None,
+    )
+    .await
+    {
+        Err(err)
+            if statement.timeout_ms.is_some()
+                && err
+                    .details
+                    .as_ref()
+                    .and_then(|d| d.get("sqlstate"))
+                    .and_then(|v| v.as_str())
+                    == Some("57014") =>
+        {
+            return Err(ToolError::timeout(format!(
+                "PostgreSQL statement exceeded timeout_ms={}",
+                statement.timeout_ms.unwrap_or_default()
+            ))
+            .with_details(serde_json::json!({"sqlstate": "57014"})));
+        }
+        other => other?,
+    };
+    if statement.timeout_ms.is_some() {
+        client
+            .batch_execute("SET LOCAL statement_timeout TO DEFAULT")
+            .await
+            .map_err(map_pg_error)?;
+    }
+    redact_payload(client, redaction, &mut result).await?;
+    Ok(result)
+}
+
+// `results` keeps input order (so plain-string batches read as before); `by_name` maps
+// statement names to their position in `results`.
+fn batch_payload(
+    statements: &[BatchStatement],
+    results: Vec<Value>,
+    failed: usize,
+    committed: Option<bool>,
+) -> Value {
+    let by_name: serde_json::Map<String, Value> = statements
         .iter()
-        .map(|b| b.as_ref() as &(dyn ToSql + Sync))
+        .take(results.len())
+        .filter_map(|statement| {
+            statement
+                .name
+                .clone()
+                .map(|name| (name, Value::from(statement.index)))
+        })
         .collect();
+    let mut payload = serde_json::json!({
+        "success": failed == 0,
+        "results": results,
+        "executed": results.len(),
+        "failed": failed,
+        "skipped": statements.len() - results.len(),
+    });
+    if let Value::Object(map) = &mut payload {
+        if !by_name.is_empty() {
+            map.insert("by_name".to_string(), Value::Object(by_name));
+        }
+        if let Some(committed) = committed {
+            map.insert("committed".to_string(), Value::Bool(committed));
+        }
+    }
+    payload
+}
+
+// Runs one data-modifying statement in a transaction that is always rolled back. Every
+// affected row is counted; only the first `sample_size` returned rows are kept (under `rows`,
+// redacted like a query result).
+async fn run_dry_run(
+    pool: &PgPool,
+    bound: &BoundParams,
+    redaction: &RedactionPolicy,
+    sample_size: usize,
+    timeout_ms: Option<u64>,
+) -> Result<Value, ToolError> {
+    let started = std::time::Instant::now();
+    let mut conn = pool.get().await?;
+    let transaction = conn.transaction().await.map_err(map_pg_error)?;
+    let outcome = async {
+        if let Some(timeout_ms) = timeout_ms {
+            transaction
+                .batch_execute(&format!("SET LOCAL statement_timeout = {}", timeout_ms))
+                .await
+                .map_err(map_pg_error)?;
+        }
+        let (statement, bindings, source_types) =
+            bind_statement(&transaction, &bound.sql, &bound.values, &bound.names).await?;
+        let bind_refs: Vec<&(dyn ToSql + Sync)> =
+            bindings.iter().map(|b| b as &(dyn ToSql + Sync)).collect();
+        let stream = transaction
+            .query_raw(&statement, bind_refs)
+            .await
+            .map_err(map_pg_error)?;
+        futures::pin_mut!(stream);
+        let mut sample = Vec::new();
+        let mut returned = 0u64;
+        while let Some(row) = stream.try_next().await.map_err(map_pg_error)? {
+            returned += 1;
+            if sample.len() < sample_size {
+                sample.push(row_to_value(&row));
+            }
+        }
+        let would_affect = stream.rows_affected().unwrap_or(returned);
+        let mut payload = serde_json::json!({
+            "success": true,
+            "dry_run": true,
+            "rolled_back": true,
+            "command": bound.sql.split_whitespace().next().unwrap_or("").to_uppercase(),
+            "would_affect": would_affect,
+            "sample_truncated": returned > sample.len() as u64,
+            "fields": describe_fields(statement.columns(), &source_types),
+            "rows": sample,
+        });
+        redact_payload(&transaction, redaction, &mut payload).await?;
+        Ok::<_, ToolError>(payload)
+    }
+    .await;
+    let rolled_back = transaction.rollback().await.map_err(map_pg_error);
+    let mut payload = outcome.map_err(|err| match timeout_ms {
+        Some(timeout_ms)
+            if err
+                .details
+                .as_ref()
+                .and_then(|d| d.get("sqlstate"))
+                .and_then(|v| v.as_str())
+                == Some("57014") =>
+        {
+            ToolError::timeout(format!(
+                "PostgreSQL statement exceeded timeout_ms={}",
+                timeout_ms
+            ))
+            .with_details(serde_json::json!({"sqlstate": "57014"}))
+        }
+        _ => err,
+    })?;
+    rolled_back?;
+    payload["duration_ms"] = Value::from(started.elapsed().as_millis() as u64);
+    Ok(payload)
+}
+
+async fn execute_named_query<C: GenericClient + Sync>(
+    client: &C,
+    bound: &BoundParams,
+    mode: Option<&str>,
+    timeout_ms: Option<u64>,
+) -> Result<Value, ToolError> {
+    let sql = bound.sql.as_str();
     let started = std::time::Instant::now();
-    let query_fut = client.query(sql, &bind_refs);
-    let rows = if let Some(timeout_ms) = timeout_ms {
+    let query_fut = async {
+        let (statement, bindings, source_types) =
+            bind_statement(client, sql, &bound.values, &bound.names).await?;
+        let bind_refs: Vec<&(dyn ToSql + Sync)> =
+            bindings.iter().map(|b| b as &(dyn ToSql + Sync)).collect();
+        let stream = client
+            .query_raw(&statement, bind_refs)
+            .await
+            .map_err(map_pg_error)?;
+        futures::pin_mut!(stream);
+        let mut rows = Vec::new();
+        while let Some(row) = stream.try_next().await.map_err(map_pg_error)? {
+            rows.push(row);
+        }
+        let affected_rows = stream.rows_affected();
+        Ok::<_, ToolError>((rows, source_types, affected_rows))
+    };
+    let (rows, source_types, affected_rows) = if let Some(timeout_ms) = timeout_ms {
         tokio::time::timeout(Duration::from_millis(timeout_ms), query_fut)
             .await
-            .map_err(|_| ToolError::timeout("PostgreSQL query timed out"))?
-            .map_err(map_pg_error)?
+            .map_err(|_| ToolError::timeout("PostgreSQL query timed out"))??
     } else {
-        query_fut.await.map_err(map_pg_error)?
+        query_fut.await?
     };
 
     let duration_ms = started.elapsed().as_millis();
     let row_count = rows.len() as i64;
+    usage::record(
+        Counter::PostgresRows,
+        (rows.len() as u64).max(affected_rows.unwrap_or(0)),
+    );
     let command = sql.split_whitespace().next().unwrap_or("").to_uppercase();
     let fields = rows
         .first()
-        .map(|row| {
-            row.columns()
-                .iter()
-                .map(|col| serde_json::json!({"name": col.name(), "dataTypeId": col.type_().oid()}))
-                .collect::<Vec<_>>()
-        })
+        .map(|row| describe_fields(row.columns(), &source_types))
         .unwrap_or_default();
 
     let normalized_mode = mode.unwrap_or("rows").to_lowercase();
@@ -1534,6 +2762,7 @@ async fn execute_query<C: GenericClient + Sync>(
         "success": true,
         "command": command,
         "rowCount": row_count,
+        "affected_rows": affected_rows,
         "fields": fields,
         "duration_ms": duration_ms,
     });
@@ -1570,111 +2799,371 @@ async fn execute_query<C: GenericClient + Sync>(
     Ok(payload)
 }
 
+fn describe_fields(columns: &[Column], source_types: &[Type]) -> Vec<Value> {
+    columns
+        .iter()
+        .enumerate()
+        .map(|(idx, col)| {
+            // Columns cast to text for decoding still report their declared type.
+            let ty = source_types.get(idx).unwrap_or(col.type_());
+            serde_json::json!({
+                "name": col.name(),
+                "dataTypeId": ty.oid(),
+                "dataType": type_label(ty.name()),
+                "tableId": col.table_oid(),
+                "columnId": col.column_id(),
+            })
+        })
+        .collect()
+}
+
+fn dry_run_requested(args: &Value) -> Result<bool, ToolError> {
+    match args.get("dry_run") {
+        None | Some(Value::Null) => Ok(false),
+        Some(Value::Bool(flag)) => Ok(*flag),
+        Some(_) => Err(ToolError::invalid_params("dry_run must be a boolean")),
+    }
+}
+
+// NUMERIC values are strings so no precision is lost; `numeric: "float"` opts into numbers.
+fn numeric_float_mode(args: &Value) -> Result<bool, ToolError> {
+    match args.get("numeric") {
+        None | Some(Value::Null) => Ok(false),
+        Some(Value::String(mode)) if mode == "string" => Ok(false),
+        Some(Value::String(mode)) if mode == "float" => Ok(true),
+        Some(other) => Err(ToolError::invalid_params(format!(
+            "numeric must be one of: string, float (got {})",
+            other
+                .as_str()
+                .map(str::to_string)
+                .unwrap_or_else(|| other.to_string())
+        ))),
+    }
+}
+
+// Per-call `redaction: "off"` skips the profile policy and is gated like secret export.
+fn resolve_redaction(args: &Value, connection: &Value) -> Result<RedactionPolicy, ToolError> {
+    match args.get("redaction") {
+        None | Some(Value::Null) => {}
+        Some(Value::String(mode)) if mode.trim() == "off" => {
+            if !is_allow_secret_export_enabled() {
+                return Err(ToolError::denied(
+                    "redaction=off requires INFRA_ALLOW_SECRET_EXPORT=1",
+                )
+                .with_hint(
+                    "Drop redaction=off; redacted columns stay masked, hashed or dropped.",
+                ));
+            }
+            return Ok(RedactionPolicy::default());
+        }
+        Some(_) => {
+            return Err(ToolError::invalid_params(
+                "redaction accepts only \"off\" per call; configure policies on the profile",
+            ))
+        }
+    }
+    RedactionPolicy::parse(connection.get("redaction"))
+}
+
+// Maps the (table oid, attnum) pairs of the result fields to schema.table.column names.
+async fn redaction_plan<C: GenericClient + Sync>(
+    client: &C,
+    policy: &RedactionPolicy,
+    fields: &Value,
+) -> Result<RedactionPlan, ToolError> {
+    if policy.is_empty() {
+        return Ok(RedactionPlan::default());
+    }
+    let oids = source_table_oids(fields);
+    let mut sources = HashMap::new();
+    if !oids.is_empty() {
+        let list = oids
+            .iter()
+            .map(|oid| oid.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        let rows = client
+            .query(
+                "SELECT a.attrelid::int8 AS rel, a.attnum AS num, n.nspname::text AS schema_name, \
+                 c.relname::text AS table_name, a.attname::text AS column_name \
+                 FROM pg_attribute a JOIN pg_class c ON c.oid = a.attrelid \
+                 JOIN pg_namespace n ON n.oid = c.relnamespace \
+                 WHERE a.attrelid = ANY(string_to_array($1, ',')::oid[]) AND a.attnum > 0",
+                &[&list],
+            )
+            .await
+            .map_err(map_pg_error)?;
+        for row in rows {
+            let rel: i64 = row.get("rel");
+            let num: i16 = row.get("num");
+            sources.insert(
+                (rel as u32, num),
+                SourceColumn {
+                    schema: row.get("schema_name"),
+                    table: row.get("table_name"),
+                    column: row.get("column_name"),
+                },
+            );
+        }
+    }
+    Ok(policy.plan(fields, &sources))
+}
+
+// Runs after rows are materialized and before the executor shapes or stores the result.
+async fn redact_payload<C: GenericClient + Sync>(
+    client: &C,
+    policy: &RedactionPolicy,
+    payload: &mut Value,
+) -> Result<(), ToolError> {
+    if policy.is_empty() {
+        return Ok(());
+    }
+    let fields = payload.get("fields").cloned().unwrap_or(Value::Null);
+    redaction_plan(client, policy, &fields)
+        .await?
+        .apply(payload);
+    Ok(())
+}
+
 async fn execute_query_with_pool(
-    pool: &Pool<PostgresConnectionManager<NoTls>>,
+    pool: &PgPool,
     sql: &str,
     params: &[Value],
     mode: Option<&str>,
     timeout_ms: Option<u64>,
 ) -> Result<Value, ToolError> {
-    let conn = pool.get().await.map_err(map_pool_error)?;
+    let conn = pool.get().await?;
     let client = &*conn;
     execute_query(client, sql, params, mode, timeout_ms).await
 }
 
-fn build_params(values: &[Value]) -> Vec<Box<dyn ToSql + Sync + Send>> {
-    values
-        .iter()
-        .map(|value| match value {
-            Value::Null => Box::new(Option::<String>::None) as Box<dyn ToSql + Sync + Send>,
-            Value::Bool(val) => Box::new(*val) as Box<dyn ToSql + Sync + Send>,
-            Value::Number(num) => {
-                if let Some(int) = num.as_i64() {
-                    Box::new(int) as Box<dyn ToSql + Sync + Send>
-                } else if let Some(float) = num.as_f64() {
-                    Box::new(float) as Box<dyn ToSql + Sync + Send>
-                } else {
-                    Box::new(num.to_string()) as Box<dyn ToSql + Sync + Send>
-                }
-            }
-            Value::String(text) => Box::new(text.clone()) as Box<dyn ToSql + Sync + Send>,
-            Value::Array(_) | Value::Object(_) => {
-                Box::new(Json(value.clone())) as Box<dyn ToSql + Sync + Send>
-            }
-        })
-        .collect()
+// `replace` drops and recreates in one simple-query batch, which Postgres runs as a single
+// implicit transaction; `if_missing` leaves an existing table (and its columns) untouched.
+async fn ensure_table(
+    pool: &PgPool,
+    qualified: &str,
+    mode: CreateTable,
+    ddl: &str,
+) -> Result<bool, ToolError> {
+    let conn = pool.get().await?;
+    let client = &*conn;
+    if mode == CreateTable::Replace {
+        client
+            .batch_execute(&format!("DROP TABLE IF EXISTS {}; {}", qualified, ddl))
+            .await
+            .map_err(map_pg_error)?;
+        return Ok(true);
+    }
+    let exists: bool = client
+        .query_one("SELECT to_regclass($1::text) IS NOT NULL", &[&qualified])
+        .await
+        .map_err(map_pg_error)?
+        .get(0);
+    if exists {
+        return Ok(false);
+    }
+    client.batch_execute(ddl).await.map_err(map_pg_error)?;
+    Ok(true)
 }
 
-fn row_to_value(row: &Row) -> Value {
-    let mut map = serde_json::Map::new();
-    for (idx, col) in row.columns().iter().enumerate() {
-        let value = match *col.type_() {
-            Type::BOOL => row
-                .try_get::<usize, Option<bool>>(idx)
-                .ok()
-                .flatten()
-                .map(Value::Bool)
-                .unwrap_or(Value::Null),
-            Type::INT2 | Type::INT4 | Type::INT8 => row
-                .try_get::<usize, Option<i64>>(idx)
-                .ok()
-                .flatten()
-                .map(|v| Value::Number(v.into()))
-                .unwrap_or(Value::Null),
-            Type::FLOAT4 | Type::FLOAT8 => row
-                .try_get::<usize, Option<f64>>(idx)
-                .ok()
-                .flatten()
-                .and_then(serde_json::Number::from_f64)
-                .map(Value::Number)
-                .unwrap_or(Value::Null),
-            Type::JSON | Type::JSONB => row
-                .try_get::<usize, Option<Value>>(idx)
-                .ok()
-                .flatten()
-                .unwrap_or(Value::Null),
-            Type::UUID => row
-                .try_get::<usize, Option<uuid::Uuid>>(idx)
-                .ok()
-                .flatten()
-                .map(|v| Value::String(v.to_string()))
-                .unwrap_or(Value::Null),
-            Type::TIMESTAMP => row
-                .try_get::<usize, Option<chrono::NaiveDateTime>>(idx)
-                .ok()
-                .flatten()
-                .map(|v| Value::String(v.to_string()))
-                .unwrap_or(Value::Null),
-            Type::TIMESTAMPTZ => row
-                .try_get::<usize, Option<chrono::DateTime<chrono::Utc>>>(idx)
-                .ok()
-                .flatten()
-                .map(|v| Value::String(v.to_rfc3339()))
-                .unwrap_or(Value::Null),
-            Type::DATE => row
-                .try_get::<usize, Option<chrono::NaiveDate>>(idx)
-                .ok()
-                .flatten()
-                .map(|v| Value::String(v.to_string()))
-                .unwrap_or(Value::Null),
-            _ => row
-                .try_get::<usize, Option<String>>(idx)
-                .ok()
-                .flatten()
-                .map(Value::String)
-                .unwrap_or(Value::Null),
-        };
-        map.insert(col.name().to_string(), value);
+fn parse_primary_key(value: Option<&Value>) -> Result<Vec<String>, ToolError> {
+    match value {
+        None | Some(Value::Null) => Ok(Vec::new()),
+        Some(Value::String(key)) if !key.trim().is_empty() => Ok(vec![key.trim().to_string()]),
+        Some(Value::Array(keys))
+            if keys
+                .iter()
+                .all(|k| k.as_str().is_some_and(|s| !s.trim().is_empty())) =>
+        {
+            Ok(keys
+                .iter()
+                .filter_map(|k| k.as_str().map(|s| s.trim().to_string()))
+                .collect())
+        }
+        Some(_) => Err(ToolError::invalid_params(
+            "primary_key must be a column name or an array of column names",
+        )),
+    }
+}
+
+fn string_list(value: Option<&Value>, label: &str) -> Result<Option<Vec<String>>, ToolError> {
+    match value {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(item)) if !item.trim().is_empty() => {
+            Ok(Some(vec![item.trim().to_string()]))
+        }
+        Some(Value::Array(items)) if items.iter().all(|item| item.is_string()) => Ok(Some(
+            items
+                .iter()
+                .filter_map(|item| item.as_str())
+                .map(|item| item.trim().to_string())
+                .filter(|item| !item.is_empty())
+                .collect(),
+        )),
+        Some(_) => Err(ToolError::invalid_params(format!(
+            "{} must be a string or an array of strings",
+            label
+        ))),
     }
-    Value::Object(map)
 }
 
-fn map_pool_error<E: std::fmt::Display>(err: E) -> ToolError {
-    ToolError::internal(format!("PostgreSQL pool error: {}", err))
+const CONNECTION_SELECTOR_KEYS: &[&str] = &[
+    "profile_name",
+    "connection",
+    "connection_url",
+    "project",
+    "project_name",
+    "target",
+    "target_name",
+];
+
+// Args for one catalog_diff side: the top-level args without their connection selector, with
+// args.<side> overlaid (a string is a profile name).
+fn catalog_diff_side(args: &Value, side: &str) -> Result<Value, ToolError> {
+    let selector = match args.get(side) {
+        Some(Value::String(name)) => serde_json::json!({"profile_name": name}),
+        Some(Value::Object(map)) if !map.is_empty() => Value::Object(map.clone()),
+        _ => {
+            return Err(ToolError::invalid_params(format!(
+                "catalog_diff requires {} as a profile name or connection object",
+                side
+            ))
+            .with_hint(
+                "Pass side_a: \"prod\", side_b: {\"connection_url\": \"postgres://...\"} or project/target objects.",
+            ))
+        }
+    };
+    let mut out = args.as_object().cloned().unwrap_or_default();
+    for key in CONNECTION_SELECTOR_KEYS {
+        out.remove(*key);
+    }
+    out.remove("side_a");
+    out.remove("side_b");
+    out.extend(selector.as_object().cloned().unwrap_or_default());
+    Ok(Value::Object(out))
+}
+
+// Without RETURNING, query() yields no rows and rowCount would read 0; execute() reports the
+// affected count instead.
+async fn execute_write_with_pool(
+    pool: &PgPool,
+    sql: &str,
+    params: &[Value],
+    returning: bool,
+    mode: Option<&str>,
+    timeout_ms: Option<u64>,
+) -> Result<Value, ToolError> {
+    if returning {
+        return execute_query_with_pool(pool, sql, params, mode, timeout_ms).await;
+    }
+    let conn = pool.get().await?;
+    let client = &*conn;
+    let started = std::time::Instant::now();
+    let execute_fut = async {
+        let (statement, bindings, _) = bind_statement(client, sql, params, &[]).await?;
+        let bind_refs: Vec<&(dyn ToSql + Sync)> =
+            bindings.iter().map(|b| b as &(dyn ToSql + Sync)).collect();
+        client
+            .execute(&statement, &bind_refs)
+            .await
+            .map_err(map_pg_error)
+    };
+    let affected = if let Some(timeout_ms) = timeout_ms {
+        tokio::time::timeout(Duration::from_millis(timeout_ms), execute_fut)
+            .await
+            .map_err(|_| ToolError::timeout("PostgreSQL query timed out"))??
+    } else {
+        execute_fut.await?
+    };
+    usage::record(Counter::PostgresRows, affected);
+    Ok(serde_json::json!({
+        "success": true,
+        "command": sql.split_whitespace().next().unwrap_or("").to_uppercase(),
+        "rowCount": affected,
+        "fields": [],
+        "duration_ms": started.elapsed().as_millis(),
+    }))
+}
+
+// insert/update/delete: with `retry_serialization` (off by default) the statement is re-run on
+// serialization failures and deadlocks; the retry count is returned when the policy applies.
+async fn execute_write_retrying(
+    args: &Value,
+    pool: &PgPool,
+    sql: &str,
+    params: &[Value],
+    returning: bool,
+) -> Result<(Value, Option<u32>), ToolError> {
+    let retry = SerializationRetry::parse(args.get("retry_serialization"), false)?;
+    let mode = args.get("mode").and_then(|v| v.as_str());
+    let timeout_ms = args.get("timeout_ms").and_then(|v| v.as_u64());
+    let (result, retries) = run_with_retry(retry.as_ref(), || {
+        execute_write_with_pool(pool, sql, params, returning, mode, timeout_ms)
+    })
+    .await?;
+    Ok((result, retry.map(|_| retries)))
+}
+
+fn with_retries(mut response: Value, retries: Option<u32>) -> Value {
+    if let Some(retries) = retries {
+        response["retries"] = Value::from(retries);
+    }
+    response
+}
+
+fn write_response(context: &Value, result: Value) -> Value {
+    serde_json::json!({
+        "success": true,
+        "table": context.get("table").cloned().unwrap_or(Value::Null),
+        "schema": context.get("schema").cloned().unwrap_or(Value::Null),
+        "affected": result.get("rowCount").cloned().unwrap_or(Value::from(0)),
+        "rows": result.get("rows").cloned().unwrap_or(Value::Null),
+        "result": result,
+    })
+}
+
+// Fetches limit + 1 rows so the report can say whether it was cut, and redacts any
+// captured query text before it leaves the manager.
+async fn report_rows(
+    pool: &PgPool,
+    sql: &str,
+    params: &[Value],
+    limit: usize,
+    timeout_ms: Option<u64>,
+) -> Result<Value, ToolError> {
+    let mut bound = params.to_vec();
+    bound.push(Value::from(limit as u64 + 1));
+    let result = execute_query_with_pool(pool, sql, &bound, Some("rows"), timeout_ms).await?;
+    let mut rows = result
+        .get("rows")
+        .and_then(|v| v.as_array())
+        .cloned()
+        .unwrap_or_default();
+    let truncated = rows.len() > limit;
+    rows.truncate(limit);
+    for row in rows.iter_mut() {
+        if let Value::Object(map) = row {
+            for key in ["query", "blocked_query", "blocking_query"] {
+                if let Some(Value::String(text)) = map.get(key) {
+                    let redacted = redact_text(text, MAX_QUERY_CHARS, None);
+                    map.insert(key.to_string(), Value::String(redacted));
+                }
+            }
+        }
+    }
+    Ok(serde_json::json!({
+        "count": rows.len(),
+        "truncated": truncated,
+        "rows": rows,
+    }))
 }
 
 fn map_pg_error(err: tokio_postgres::Error) -> ToolError {
-    ToolError::internal(format!("PostgreSQL error: {}", err))
+    let mapped = ToolError::internal(format!("PostgreSQL error: {}", err));
+    match err.as_db_error() {
+        Some(db) => mapped.with_details(serde_json::json!({"sqlstate": db.code().code()})),
+        None => mapped,
+    }
 }
 
 #[async_trait]
@@ -1,6 +1,8 @@
 use crate::errors::ToolError;
+use crate::services::alias::{expand_alias_call, AliasService};
 use crate::services::logger::Logger;
 use crate::services::preset::PresetService;
+use crate::tooling::names::canonical_tool_name;
 use crate::utils::listing::ListFilters;
 use crate::utils::tool_errors::unknown_action_error;
 use serde_json::Value;
@@ -11,22 +13,75 @@ pub(crate) const PRESET_ACTIONS: &[&str] = &[
     "preset_get",
     "preset_list",
     "preset_delete",
+    "preset_resolve",
 ];
 
 #[derive(Clone)]
 pub struct PresetManager {
     logger: Logger,
     preset_service: Arc<PresetService>,
+    alias_service: Option<Arc<AliasService>>,
 }
 
 impl PresetManager {
-    pub fn new(logger: Logger, preset_service: Arc<PresetService>) -> Self {
+    pub fn new(
+        logger: Logger,
+        preset_service: Arc<PresetService>,
+        alias_service: Option<Arc<AliasService>>,
+    ) -> Self {
         Self {
             logger: logger.child("preset"),
             preset_service,
+            alias_service,
         }
     }
 
+    fn resolve_call(&self, args: &Value) -> Result<Value, ToolError> {
+        let requested = args
+            .get("tool")
+            .and_then(|v| v.as_str())
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .ok_or_else(|| ToolError::invalid_params("tool must be a non-empty string"))?;
+        let call_args = args
+            .get("args")
+            .cloned()
+            .unwrap_or_else(|| Value::Object(Default::default()));
+        if !call_args.is_object() {
+            return Err(ToolError::invalid_params("args must be an object"));
+        }
+        let alias = self
+            .alias_service
+            .as_ref()
+            .and_then(|service| service.resolve_alias(requested));
+        let tool = alias
+            .as_ref()
+            .and_then(|value| value.get("tool"))
+            .and_then(|v| v.as_str())
+            .unwrap_or(requested);
+        let tool = canonical_tool_name(tool).to_string();
+        let (alias_args, call_args) = match alias.as_ref() {
+            Some(spec) => expand_alias_call(requested, spec, &call_args)?,
+            None => (None, call_args),
+        };
+        let layered =
+            self.preset_service
+                .layer_call_args(&tool, alias_args.as_ref(), &call_args)?;
+        Ok(serde_json::json!({
+            "success": true,
+            "tool": tool,
+            "invoked_as": if alias.is_some() { Value::String(requested.to_string()) } else { Value::Null },
+            "action": layered.args.get("action").cloned().unwrap_or(Value::Null),
+            "presets": layered.presets,
+            "layers": {
+                "defaults": layered.defaults,
+                "alias": alias_args.unwrap_or(Value::Null),
+                "call": call_args,
+            },
+            "args": layered.args,
+        }))
+    }
+
     pub async fn handle_action(&self, args: Value) -> Result<Value, ToolError> {
         let action = args.get("action");
         match action.and_then(|v| v.as_str()).unwrap_or("") {
@@ -47,6 +102,7 @@ impl PresetManager {
                 let name = args.get("name").and_then(|v| v.as_str()).unwrap_or("");
                 self.preset_service.delete_preset(name)
             }
+            "preset_resolve" => self.resolve_call(&args),
             _ => Err(unknown_action_error("preset", action, PRESET_ACTIONS)),
         }
     }
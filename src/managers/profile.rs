@@ -2,6 +2,7 @@ use crate::errors::ToolError;
 use crate::services::logger::Logger;
 use crate::services::profile::ProfileService;
 use crate::services::validation::Validation;
+use crate::utils::feature_flags::is_allow_secret_export_enabled;
 use crate::utils::tool_errors::unknown_action_error;
 use serde_json::Value;
 use std::sync::Arc;
@@ -22,13 +23,6 @@ impl ProfileManager {
         }
     }
 
-    fn allow_secret_export() -> bool {
-        std::env::var("INFRA_ALLOW_SECRET_EXPORT")
-            .ok()
-            .map(|value| value.trim() == "1" || value.trim().eq_ignore_ascii_case("true"))
-            .unwrap_or(false)
-    }
-
     fn resolve_profile_name(&self, args: &Value) -> Result<String, ToolError> {
         let raw = args
             .get("name")
@@ -87,7 +81,7 @@ impl ProfileManager {
                     .get("include_secrets")
                     .and_then(|value| value.as_bool())
                     .unwrap_or(false);
-                let profile = if include_secrets && Self::allow_secret_export() {
+                let profile = if include_secrets && is_allow_secret_export_enabled() {
                     profile
                 } else {
                     self.redact_profile(&name, &profile)
@@ -1,14 +1,23 @@
 use crate::errors::ToolError;
 use crate::services::logger::Logger;
 use crate::services::project::ProjectService;
+use crate::services::project_resolver::ProjectResolver;
 use crate::services::state::StateService;
+use crate::services::tool_executor::ToolExecutor;
 use crate::services::validation::Validation;
+use crate::tooling::names::canonical_tool_name;
 use crate::utils::listing::ListFilters;
 use crate::utils::tool_errors::unknown_action_error;
+use futures::StreamExt;
+use once_cell::sync::OnceCell;
 use serde_json::Value;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Weak};
 
 const ACTIVE_PROJECT_KEY: &str = "project.active";
+const DEFAULT_FOREACH_CONCURRENCY: usize = 4;
+const MAX_FOREACH_CONCURRENCY: usize = 16;
+const MAX_FOREACH_PROJECTS: usize = 200;
 pub(crate) const PROJECT_ACTIONS: &[&str] = &[
     "project_upsert",
     "project_get",
@@ -17,6 +26,8 @@ pub(crate) const PROJECT_ACTIONS: &[&str] = &[
     "project_use",
     "project_active",
     "project_unuse",
+    "project_foreach",
+    "resolver_stats",
 ];
 
 #[derive(Clone)]
@@ -25,6 +36,20 @@ pub struct ProjectManager {
     validation: Validation,
     project_service: Arc<ProjectService>,
     state_service: Arc<StateService>,
+    project_resolver: Option<Arc<ProjectResolver>>,
+    tool_executor: Arc<OnceCell<Weak<ToolExecutor>>>,
+}
+
+struct ForeachPlan {
+    tool: String,
+    action: String,
+    args: serde_json::Map<String, Value>,
+    target: Option<String>,
+    apply: bool,
+    confirm: bool,
+    stop_on_error: bool,
+    trace_id: String,
+    span_id: String,
 }
 
 impl ProjectManager {
@@ -33,13 +58,317 @@ impl ProjectManager {
         validation: Validation,
         project_service: Arc<ProjectService>,
         state_service: Arc<StateService>,
+        project_resolver: Option<Arc<ProjectResolver>>,
     ) -> Self {
         Self {
             logger: logger.child("project"),
             validation,
             project_service,
             state_service,
+            project_resolver,
+            tool_executor: Arc::new(OnceCell::new()),
+        }
+    }
+
+    pub fn set_tool_executor(&self, tool_executor: Arc<ToolExecutor>) {
+        let _ = self.tool_executor.set(Arc::downgrade(&tool_executor));
+    }
+
+    fn resolve_tool_executor(&self) -> Result<Arc<ToolExecutor>, ToolError> {
+        self.tool_executor
+            .get()
+            .and_then(|executor| executor.upgrade())
+            .ok_or_else(|| {
+                ToolError::internal("Tool executor is not available for project_foreach")
+                    .with_hint(
+                        "App wiring bug: ProjectManager.set_tool_executor(...) must be called during initialization."
+                            .to_string(),
+                    )
+            })
+    }
+
+    fn foreach_projects(&self, args: &Value) -> Result<Vec<String>, ToolError> {
+        let mut names: Vec<String> = Vec::new();
+        match (args.get("projects"), args.get("project_filter")) {
+            (Some(Value::Array(items)), None) => {
+                for item in items {
+                    let name = self.validation.ensure_identifier(
+                        item.as_str().ok_or_else(|| {
+                            ToolError::invalid_params("projects must be an array of strings")
+                        })?,
+                        "project",
+                    )?;
+                    if !names.contains(&name) {
+                        names.push(name);
+                    }
+                }
+            }
+            (None, Some(filter)) if filter.is_object() => {
+                let listed = self
+                    .project_service
+                    .list_projects(&ListFilters::from_args(filter))?;
+                for item in listed
+                    .get("projects")
+                    .and_then(|v| v.as_array())
+                    .into_iter()
+                    .flatten()
+                {
+                    if let Some(name) = item.get("name").and_then(|v| v.as_str()) {
+                        names.push(name.to_string());
+                    }
+                }
+            }
+            (Some(_), Some(_)) => {
+                return Err(ToolError::invalid_params(
+                    "Provide either projects or project_filter, not both",
+                ))
+            }
+            (Some(_), None) => {
+                return Err(ToolError::invalid_params(
+                    "projects must be an array of strings",
+                ))
+            }
+            (None, Some(_)) => {
+                return Err(ToolError::invalid_params(
+                    "project_filter must be an object (query/tags/where)",
+                ))
+            }
+            (None, None) => {
+                return Err(ToolError::invalid_params(
+                    "project_foreach requires projects or project_filter",
+                )
+                .with_hint(
+                    "Pass projects: [\"a\", \"b\"] or project_filter: {\"query\": \"...\"}."
+                        .to_string(),
+                ))
+            }
+        }
+        if names.is_empty() {
+            return Err(
+                ToolError::not_found("project_foreach matched no projects").with_hint(
+                    "Use action=project_list to check the registry and filter.".to_string(),
+                ),
+            );
+        }
+        if names.len() > MAX_FOREACH_PROJECTS {
+            return Err(ToolError::invalid_params(format!(
+                "project_foreach supports at most {} projects (got {})",
+                MAX_FOREACH_PROJECTS,
+                names.len()
+            )));
+        }
+        Ok(names)
+    }
+
+    fn foreach_plan(&self, args: &Value) -> Result<ForeachPlan, ToolError> {
+        let call = args
+            .get("call")
+            .and_then(|v| v.as_object())
+            .ok_or_else(|| {
+                ToolError::invalid_params("project_foreach requires call: {tool, action, args}")
+            })?;
+        let tool = self.validation.ensure_string(
+            call.get("tool").unwrap_or(&Value::Null),
+            "call.tool",
+            true,
+        )?;
+        let action = self.validation.ensure_string(
+            call.get("action").unwrap_or(&Value::Null),
+            "call.action",
+            true,
+        )?;
+        if canonical_tool_name(&tool) == "project" && action == "project_foreach" {
+            return Err(ToolError::denied(
+                "Nested project_foreach execution is not supported",
+            ));
+        }
+        let mut call_args = match call.get("args") {
+            None | Some(Value::Null) => serde_json::Map::new(),
+            Some(Value::Object(map)) => map.clone(),
+            Some(_) => return Err(ToolError::invalid_params("call.args must be an object")),
+        };
+        // The fan-out owns project/target selection and tracing.
+        for key in [
+            "project",
+            "project_name",
+            "target",
+            "project_target",
+            "environment",
+            "trace_id",
+            "span_id",
+            "parent_span_id",
+        ] {
+            call_args.remove(key);
+        }
+        call_args.insert("action".to_string(), Value::String(action.clone()));
+        let target = match args.get("target") {
+            None | Some(Value::Null) => None,
+            Some(value) => Some(
+                self.validation.ensure_identifier(
+                    value
+                        .as_str()
+                        .ok_or_else(|| ToolError::invalid_params("target must be a string"))?,
+                    "target",
+                )?,
+            ),
+        };
+        let string_arg = |key: &str| {
+            args.get(key)
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string())
+                .unwrap_or_else(|| uuid::Uuid::new_v4().to_string())
+        };
+        Ok(ForeachPlan {
+            tool,
+            action,
+            args: call_args,
+            target,
+            apply: args.get("apply").and_then(|v| v.as_bool()).unwrap_or(false),
+            confirm: args
+                .get("confirm")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false),
+            stop_on_error: args
+                .get("stop_on_error")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false),
+            trace_id: string_arg("trace_id"),
+            span_id: string_arg("span_id"),
+        })
+    }
+
+    async fn foreach_one(
+        &self,
+        tool_executor: &ToolExecutor,
+        plan: &ForeachPlan,
+        project: &str,
+        stopped: &AtomicBool,
+    ) -> Value {
+        let span_id = uuid::Uuid::new_v4().to_string();
+        let mut entry = serde_json::json!({
+            "project": project,
+            "target": plan.target,
+            "span_id": span_id,
+        });
+        if plan.stop_on_error && stopped.load(Ordering::SeqCst) {
+            entry["status"] = Value::from("skipped");
+            return entry;
+        }
+        let started_at = chrono::Utc::now().timestamp_millis();
+        let mut selector = serde_json::json!({"project": project});
+        if let Some(target) = plan.target.as_ref() {
+            selector["target"] = Value::String(target.clone());
         }
+        let resolved = match self.project_resolver.as_ref() {
+            Some(resolver) => resolver.resolve_context(&selector).await,
+            None => Ok(None),
+        };
+        let target_name = match resolved {
+            Ok(context) => context
+                .as_ref()
+                .and_then(|ctx| ctx.get("targetName"))
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string())
+                .or_else(|| plan.target.clone()),
+            Err(err) => {
+                stopped.store(true, Ordering::SeqCst);
+                entry["status"] = Value::from("error");
+                entry["stage"] = Value::from("resolve");
+                entry["error"] = serde_json::to_value(&err).unwrap_or(Value::Null);
+                return entry;
+            }
+        };
+        entry["target"] = serde_json::json!(target_name);
+
+        let mut call_args = plan.args.clone();
+        call_args.insert("project".to_string(), Value::String(project.to_string()));
+        if let Some(target) = target_name.as_ref() {
+            call_args.insert("target".to_string(), Value::String(target.clone()));
+        }
+        call_args
+            .entry("apply".to_string())
+            .or_insert(Value::Bool(plan.apply));
+        call_args
+            .entry("confirm".to_string())
+            .or_insert(Value::Bool(plan.confirm));
+        call_args.insert("trace_id".to_string(), Value::String(plan.trace_id.clone()));
+        call_args.insert("span_id".to_string(), Value::String(span_id));
+        call_args.insert(
+            "parent_span_id".to_string(),
+            Value::String(plan.span_id.clone()),
+        );
+
+        let outcome = tool_executor
+            .execute(&plan.tool, Value::Object(call_args))
+            .await;
+        entry["duration_ms"] = Value::from(chrono::Utc::now().timestamp_millis() - started_at);
+        match outcome {
+            Ok(output) => {
+                let result = output.get("result").cloned().unwrap_or(output);
+                // Tools that report their own outcome (e.g. success=false) count as failures.
+                let ok = result
+                    .get("success")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(true);
+                if !ok {
+                    stopped.store(true, Ordering::SeqCst);
+                }
+                entry["status"] = Value::from(if ok { "ok" } else { "error" });
+                entry["result"] = result;
+            }
+            Err(err) => {
+                stopped.store(true, Ordering::SeqCst);
+                entry["status"] = Value::from("error");
+                entry["stage"] = Value::from("call");
+                entry["error"] = serde_json::to_value(&err).unwrap_or(Value::Null);
+            }
+        }
+        entry
+    }
+
+    // One child span per project under the foreach span; the executor audits each call with
+    // parent_span_id set, so the fan-out can be rebuilt from audit_list trace_id=<trace_id>.
+    async fn project_foreach(&self, args: &Value) -> Result<Value, ToolError> {
+        let plan = self.foreach_plan(args)?;
+        let projects = self.foreach_projects(args)?;
+        let tool_executor = self.resolve_tool_executor()?;
+        let concurrency = args
+            .get("concurrency")
+            .and_then(|v| v.as_u64())
+            .map(|v| (v as usize).clamp(1, MAX_FOREACH_CONCURRENCY))
+            .unwrap_or(DEFAULT_FOREACH_CONCURRENCY);
+
+        let stopped = AtomicBool::new(false);
+        let calls: Vec<_> = projects
+            .iter()
+            .map(|project| self.foreach_one(&tool_executor, &plan, project, &stopped))
+            .collect();
+        let results: Vec<Value> = futures::stream::iter(calls)
+            .buffered(concurrency)
+            .collect()
+            .await;
+
+        let count = |status: &str| {
+            results
+                .iter()
+                .filter(|item| item["status"] == status)
+                .count()
+        };
+        let (succeeded, failed, skipped) = (count("ok"), count("error"), count("skipped"));
+        Ok(serde_json::json!({
+            "success": failed == 0 && skipped == 0,
+            "call": {"tool": plan.tool, "action": plan.action},
+            "target": plan.target,
+            "trace_id": plan.trace_id,
+            "span_id": plan.span_id,
+            "concurrency": concurrency,
+            "stop_on_error": plan.stop_on_error,
+            "total": results.len(),
+            "succeeded": succeeded,
+            "failed": failed,
+            "skipped": skipped,
+            "results": results,
+        }))
     }
 
     fn build_project_payload(&self, args: &Value) -> Value {
@@ -117,6 +446,11 @@ impl ProjectManager {
                 let cleared = self.state_service.unset(ACTIVE_PROJECT_KEY, Some(scope))?;
                 Ok(serde_json::json!({"success": true, "cleared": cleared}))
             }
+            "project_foreach" => self.project_foreach(&args).await,
+            "resolver_stats" => Ok(serde_json::json!({
+                "success": true,
+                "resolver": self.project_resolver.as_ref().map(|resolver| resolver.stats()),
+            })),
             _ => Err(unknown_action_error("project", action, PROJECT_ACTIONS)),
         }
     }
@@ -3,6 +3,7 @@ use crate::services::logger::Logger;
 use crate::utils::artifacts::{
     build_tool_call_file_ref, resolve_context_root, write_text_artifact,
 };
+use crate::utils::feature_flags;
 use crate::utils::sandbox::resolve_sandbox_path;
 use crate::utils::stdin::{resolve_stdin_source, StdinSource};
 use crate::utils::tool_errors::unknown_action_error;
@@ -88,8 +89,11 @@ fn split_allowlist(raw: Option<String>) -> Vec<String> {
 }
 
 fn allowed_commands() -> Vec<String> {
-    let raw = std::env::var("INFRA_REPO_ALLOWED_COMMANDS").ok();
-    let list = split_allowlist(raw);
+    let list = split_allowlist(
+        feature_flags::REPO_ALLOWED_COMMANDS
+            .raw()
+            .map(|(_, raw)| raw),
+    );
     if list.is_empty() {
         vec!["git".to_string()]
     } else {
@@ -1,14 +1,18 @@
+use crate::constants::runbook as runbook_constants;
 use crate::errors::ToolError;
 use crate::services::logger::Logger;
 use crate::services::runbook::RunbookService;
 use crate::services::state::StateService;
 use crate::services::tool_executor::ToolExecutor;
-use crate::tooling::effects::resolve_tool_call_effects;
+use crate::tooling::effects::{resolve_steps_effects, ResolvedEffects};
 use crate::tooling::names::canonical_tool_name;
 use crate::utils::data_path::get_path_value;
 use crate::utils::effects::{resolve_effects, Effects};
+use crate::utils::feature_flags::is_readonly_enabled;
 use crate::utils::listing::ListFilters;
 use crate::utils::manifests::manifest_ref;
+use crate::utils::merge::merge_deep;
+use crate::utils::runbook_capture::{build_draft, new_capture, CAPTURE_STATE_KEY, DRAFT_STATE_KEY};
 use crate::utils::template::{resolve_template_string, resolve_templates};
 use crate::utils::tool_errors::unknown_action_error;
 use once_cell::sync::OnceCell;
@@ -22,8 +26,13 @@ pub(crate) const RUNBOOK_ACTIONS: &[&str] = &[
     "runbook_list",
     "runbook_delete",
     "runbook_run",
+    "runbook_resume",
+    "runbook_runs",
     "runbook_run_dsl",
     "runbook_compile",
+    "runbook_capture_start",
+    "runbook_capture_stop",
+    "runbook_capture_save",
 ];
 
 fn merge_effects(mut base: Effects, other: Effects) -> Effects {
@@ -42,33 +51,82 @@ fn merge_effects(mut base: Effects, other: Effects) -> Effects {
     base
 }
 
-fn infer_effects_from_steps(runbook: &Value, context: Option<&Value>, missing: &str) -> Effects {
+fn infer_effects_from_steps(
+    runbook: &Value,
+    context: Option<&Value>,
+    missing: &str,
+) -> ResolvedEffects {
     let steps = runbook
         .get("steps")
         .and_then(|v| v.as_array())
         .cloned()
         .unwrap_or_default();
-    let mut out = Effects {
-        kind: Some("read".to_string()),
-        requires_apply: false,
-        irreversible: false,
-    };
-    for step in steps.iter() {
-        let tool = step.get("tool").and_then(|v| v.as_str()).unwrap_or("");
-        if tool.trim().is_empty() {
-            continue;
-        }
-        let raw_args = step
-            .get("args")
-            .cloned()
-            .unwrap_or_else(|| Value::Object(Default::default()));
-        let args = context
-            .and_then(|context| resolve_templates(&raw_args, context, missing).ok())
-            .unwrap_or(raw_args);
-        let resolved = resolve_tool_call_effects(tool, &args);
-        out = merge_effects(out, resolved.effects);
+    let resolved_steps: Vec<Value> = steps
+        .into_iter()
+        .map(|mut step| {
+            if let (Some(context), Some(raw_args)) = (context, step.get("args").cloned()) {
+                if let Ok(args) = resolve_templates(&raw_args, context, missing) {
+                    step["args"] = args;
+                }
+            }
+            step
+        })
+        .collect();
+    let label = format!(
+        "runbook {}",
+        runbook.get("name").and_then(|v| v.as_str()).unwrap_or("")
+    );
+    resolve_steps_effects(label.trim(), &resolved_steps)
+}
+
+fn runbook_effects(runbook: &Value, context: &Value, missing: &str) -> ResolvedEffects {
+    let declared = resolve_effects(runbook);
+    let mut inferred = infer_effects_from_steps(runbook, Some(context), missing);
+    let declared_dominates = (declared.irreversible && !inferred.effects.irreversible)
+        || (declared.requires_apply && !inferred.effects.requires_apply);
+    if declared_dominates {
+        inferred.trigger = Some("runbook metadata (tags/effects)".to_string());
     }
-    out
+    inferred.effects = merge_effects(declared, inferred.effects);
+    inferred
+}
+
+const RUN_STATE_PREFIX: &str = "runbook_run/";
+
+fn run_state_key(run_id: &str) -> String {
+    format!("{}{}", RUN_STATE_PREFIX, run_id)
+}
+
+fn is_expired(record: &Value) -> bool {
+    record.get("status").and_then(|v| v.as_str()) == Some("paused")
+        && record
+            .get("expires_at")
+            .and_then(|v| v.as_str())
+            .and_then(|at| chrono::DateTime::parse_from_rfc3339(at).ok())
+            .is_some_and(|at| at < chrono::Utc::now())
+}
+
+struct RunDefinition<'a> {
+    runbook: &'a Value,
+    steps: &'a [Value],
+    effects: Value,
+}
+
+// Where a run stands between steps; persisted under runbook_run/<run_id> once it pauses.
+struct RunProgress {
+    run_id: String,
+    created_at: String,
+    persisted: bool,
+    next_index: usize,
+    results: Vec<Value>,
+    approved_index: Option<usize>,
+    override_args: Option<Value>,
+    approvals: Vec<Value>,
+    // (trace_id, span_id) of the approving runbook_resume call, stamped onto resumed steps.
+    resume_trace: Option<(String, String)>,
+    stop_on_error: bool,
+    template_missing: String,
+    checkpoint_ttl_ms: u64,
 }
 
 #[derive(Clone)]
@@ -158,9 +216,14 @@ impl RunbookManager {
                 Err(self.compatibility_only_error("runbook_compile", "compatibility_runbook_dsl"))
             }
             "runbook_run" => self.runbook_run(args).await,
+            "runbook_resume" => self.runbook_resume(args).await,
+            "runbook_runs" => self.runbook_runs(&args),
             "runbook_run_dsl" => {
                 Err(self.compatibility_only_error("runbook_run_dsl", "compatibility_runbook_dsl"))
             }
+            "runbook_capture_start" => self.capture_start(&args),
+            "runbook_capture_stop" => self.capture_stop(&args),
+            "runbook_capture_save" => self.capture_save(&args),
             _ => Err(unknown_action_error("runbook", action, RUNBOOK_ACTIONS)),
         }
     }
@@ -169,6 +232,114 @@ impl RunbookManager {
         Ok(serde_json::json!({ "success": true }))
     }
 
+    fn session_value(&self, key: &str) -> Result<Value, ToolError> {
+        Ok(self
+            .state_service
+            .get(key, Some("session"))?
+            .get("value")
+            .cloned()
+            .unwrap_or(Value::Null))
+    }
+
+    fn capture_start(&self, args: &Value) -> Result<Value, ToolError> {
+        let active = self.session_value(CAPTURE_STATE_KEY)?;
+        if !active.is_null() {
+            let recorded = active
+                .get("steps")
+                .and_then(|v| v.as_array())
+                .map_or(0, |steps| steps.len());
+            return Err(ToolError::conflict(format!(
+                "a runbook capture is already active ({} step(s) recorded)",
+                recorded
+            ))
+            .with_hint("Call action=runbook_capture_stop to get its draft first."));
+        }
+        let name = args
+            .get("name")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|name| !name.is_empty());
+        let capture = new_capture(name);
+        self.state_service
+            .set(CAPTURE_STATE_KEY, capture.clone(), Some("session"))?;
+        Ok(serde_json::json!({
+            "success": true,
+            "capturing": true,
+            "name": capture["name"],
+            "started_at": capture["started_at"],
+        }))
+    }
+
+    fn capture_stop(&self, args: &Value) -> Result<Value, ToolError> {
+        let capture = self.session_value(CAPTURE_STATE_KEY)?;
+        if capture.is_null() {
+            return Err(ToolError::invalid_params("no runbook capture is active")
+                .with_hint("Start one with action=runbook_capture_start."));
+        }
+        let parameterize = args
+            .get("parameterize")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true);
+        let mut draft = build_draft(&capture, parameterize);
+        draft["name"] = capture.get("name").cloned().unwrap_or(Value::Null);
+        self.state_service
+            .unset(CAPTURE_STATE_KEY, Some("session"))?;
+        self.state_service
+            .set(DRAFT_STATE_KEY, draft.clone(), Some("session"))?;
+        let mut out = serde_json::json!({"success": true, "capturing": false});
+        if let (Some(out), Some(draft)) = (out.as_object_mut(), draft.as_object()) {
+            out.extend(draft.clone());
+        }
+        out["next"] = Value::String(
+            "Review runbook, then action=runbook_capture_save name=<name> (pass runbook to save an edited draft)."
+                .to_string(),
+        );
+        Ok(out)
+    }
+
+    // Adds the reviewed draft (or the last one runbook_capture_stop produced) to the runbook
+    // manifest; names a loaded manifest already defines are never overwritten.
+    fn capture_save(&self, args: &Value) -> Result<Value, ToolError> {
+        let draft = self.session_value(DRAFT_STATE_KEY)?;
+        let runbook = match args.get("runbook") {
+            Some(Value::Object(runbook)) => Value::Object(runbook.clone()),
+            Some(Value::Null) | None => draft.get("runbook").cloned().ok_or_else(|| {
+                ToolError::invalid_params("no captured runbook draft to save").with_hint(
+                    "Run action=runbook_capture_stop first, or pass the reviewed draft as runbook.",
+                )
+            })?,
+            Some(_) => return Err(ToolError::invalid_params("runbook must be an object")),
+        };
+        let name = args
+            .get("name")
+            .or_else(|| draft.get("name"))
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .ok_or_else(|| {
+                ToolError::invalid_params("runbook_capture_save requires name")
+                    .with_hint("Pass name=<runbook-name>, or set it on runbook_capture_start.")
+            })?
+            .to_string();
+        let mut records = serde_json::Map::new();
+        records.insert(name.clone(), runbook.clone());
+        let (added, _) = self.runbook_service.add_runbooks(&records)?;
+        if added.is_empty() {
+            return Err(ToolError::conflict(format!(
+                "runbook '{}' already exists in a loaded manifest",
+                name
+            ))
+            .with_hint("Choose another name; captured runbooks never replace existing ones."));
+        }
+        self.state_service.unset(DRAFT_STATE_KEY, Some("session"))?;
+        Ok(serde_json::json!({
+            "success": true,
+            "name": name,
+            "manifest_path": self.manifest_path_display(),
+            "runbook": runbook,
+        }))
+    }
+
     fn resolve_tool_executor(&self) -> Result<Arc<ToolExecutor>, ToolError> {
         self.tool_executor
             .get()
@@ -184,11 +355,6 @@ impl RunbookManager {
 
     async fn runbook_run(&self, args: Value) -> Result<Value, ToolError> {
         let tool_executor = self.resolve_tool_executor()?;
-        let input = args
-            .get("input")
-            .and_then(|v| v.as_object())
-            .cloned()
-            .unwrap_or_default();
         let stop_on_error = args
             .get("stop_on_error")
             .and_then(|v| v.as_bool())
@@ -244,6 +410,15 @@ impl RunbookManager {
                     )
             })?;
         let runbook = self.runbook_service.resolve_runbook(name)?;
+        // `defaults` (set by captured runbooks) fill inputs the caller leaves out.
+        let mut input = runbook
+            .get("defaults")
+            .and_then(|v| v.as_object())
+            .cloned()
+            .unwrap_or_default();
+        if let Some(given) = args.get("input").and_then(|v| v.as_object()) {
+            input.extend(given.clone());
+        }
 
         let steps = runbook
             .get("steps")
@@ -268,30 +443,86 @@ impl RunbookManager {
             "confirm": confirm,
         });
 
-        let effects = merge_effects(
-            resolve_effects(&runbook),
-            infer_effects_from_steps(&runbook, Some(&context), template_missing),
-        );
-        if effects.requires_apply && !apply {
-            return Err(
-                ToolError::denied("Runbook requires apply=true for write/mixed effects").with_hint(
-                    "Rerun with apply=true if you intend to perform write operations.".to_string(),
-                ),
-            );
+        let effects = runbook_effects(&runbook, &context, template_missing);
+        if is_readonly_enabled() && effects.effects.is_write_classified() {
+            return Err(ToolError::denied(format!(
+                "INFRA_READONLY=1 denies {} runbook effects{}",
+                effects.effects.class(),
+                effects.triggered_by()
+            ))
+            .with_hint(
+                "Unset INFRA_READONLY to allow write operations, or run a read-only runbook."
+                    .to_string(),
+            )
+            .with_details(serde_json::json!({ "effects": effects.to_value(), "readonly": true })));
         }
-        if effects.irreversible && !confirm {
-            return Err(ToolError::denied(
-                "Runbook requires confirm=true for irreversible effects",
+        if effects.effects.requires_apply && !apply {
+            return Err(ToolError::denied(format!(
+                "Runbook requires apply=true for write/mixed effects{}",
+                effects.triggered_by()
+            ))
+            .with_hint(
+                "Rerun with apply=true if you intend to perform write operations.".to_string(),
             )
+            .with_details(serde_json::json!({ "effects": effects.to_value() })));
+        }
+        if effects.effects.irreversible && !confirm {
+            return Err(ToolError::denied(format!(
+                "Runbook requires confirm=true for irreversible effects{}",
+                effects.triggered_by()
+            ))
             .with_hint(
                 "Rerun with confirm=true if you understand this cannot be safely auto-rolled-back."
                     .to_string(),
-            ));
+            )
+            .with_details(serde_json::json!({ "effects": effects.to_value() })));
         }
 
-        let mut results: Vec<Value> = Vec::new();
+        let checkpoint_ttl_ms = args
+            .get("checkpoint_ttl_ms")
+            .and_then(|v| v.as_u64())
+            .filter(|ttl| *ttl > 0)
+            .unwrap_or(runbook_constants::CHECKPOINT_TTL_MS);
+        let mut progress = RunProgress {
+            run_id: uuid::Uuid::new_v4().to_string(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+            persisted: false,
+            next_index: 0,
+            results: Vec::new(),
+            approved_index: None,
+            override_args: None,
+            approvals: Vec::new(),
+            resume_trace: None,
+            stop_on_error,
+            template_missing: template_missing.to_string(),
+            checkpoint_ttl_ms,
+        };
+        let run = RunDefinition {
+            runbook: &runbook,
+            steps: &steps,
+            effects: effects.to_value(),
+        };
+        self.run_steps(&tool_executor, &run, &mut context, &mut progress)
+            .await
+    }
 
-        for (index, step) in steps.iter().enumerate() {
+    // Executes steps from progress.next_index on. A `checkpoint: true` step pauses the run before
+    // it executes (unless it is the step just approved via runbook_resume) and persists enough
+    // state to continue later.
+    async fn run_steps(
+        &self,
+        tool_executor: &ToolExecutor,
+        run: &RunDefinition<'_>,
+        context: &mut Value,
+        progress: &mut RunProgress,
+    ) -> Result<Value, ToolError> {
+        let runbook = run.runbook;
+        let trace_id = context.get("trace_id").cloned().unwrap_or(Value::Null);
+        let template_missing = progress.template_missing.clone();
+
+        while progress.next_index < run.steps.len() {
+            let index = progress.next_index;
+            let mut step = run.steps[index].clone();
             let step_key = step
                 .get("id")
                 .or_else(|| step.get("name"))
@@ -299,8 +530,47 @@ impl RunbookManager {
                 .unwrap_or(&format!("step_{}", index + 1))
                 .to_string();
 
+            let approved = progress.approved_index == Some(index);
+            if approved {
+                if let Some(overrides) = progress.override_args.take() {
+                    let base = step.get("args").cloned().unwrap_or(Value::Null);
+                    step["args"] = merge_deep(&base, &overrides);
+                }
+                progress.approved_index = None;
+            } else if step
+                .get("checkpoint")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false)
+                && Self::evaluate_when(step.get("when"), context, &template_missing)
+            {
+                let awaiting =
+                    Self::pending_step_preview(&step, &step_key, index, context, &template_missing);
+                let record = self.save_run(run, context, progress, "paused", Some(&awaiting))?;
+                return Ok(serde_json::json!({
+                    "success": true,
+                    "paused": true,
+                    "run_id": progress.run_id,
+                    "awaiting": awaiting,
+                    "expires_at": record.get("expires_at").cloned().unwrap_or(Value::Null),
+                    "runbook": runbook.get("name").cloned().unwrap_or(Value::Null),
+                    "runbook_manifest": manifest_ref(runbook),
+                    "effects": run.effects,
+                    "steps": progress.results,
+                    "trace_id": trace_id,
+                }));
+            }
+            if let (Some((resume_trace, resume_span)), Some(args)) = (
+                progress.resume_trace.as_ref(),
+                step.get_mut("args").and_then(|v| v.as_object_mut()),
+            ) {
+                args.entry("trace_id".to_string())
+                    .or_insert_with(|| Value::String(resume_trace.clone()));
+                args.entry("parent_span_id".to_string())
+                    .or_insert_with(|| Value::String(resume_span.clone()));
+            }
+
             match self
-                .execute_step(&tool_executor, step, &step_key, &context, template_missing)
+                .execute_step(tool_executor, &step, &step_key, context, &template_missing)
                 .await
             {
                 Ok(outcome) => {
@@ -310,7 +580,7 @@ impl RunbookManager {
                             outcome.get("result").cloned().unwrap_or(Value::Null),
                         );
                     }
-                    results.push(outcome);
+                    progress.results.push(outcome);
                 }
                 Err(err) => {
                     let entry = serde_json::json!({
@@ -320,22 +590,30 @@ impl RunbookManager {
                         "success": false,
                         "error": err.message,
                     });
-                    results.push(entry);
-                    if stop_on_error
+                    progress.results.push(entry);
+                    if progress.stop_on_error
                         && !step
                             .get("continue_on_error")
                             .and_then(|v| v.as_bool())
                             .unwrap_or(false)
                     {
-                        return Ok(serde_json::json!({
+                        progress.next_index = index + 1;
+                        if progress.persisted {
+                            self.save_run(run, context, progress, "failed", None)?;
+                        }
+                        let mut out = serde_json::json!({
                             "success": false,
                             "runbook": runbook.get("name").cloned().unwrap_or(Value::Null),
-                            "runbook_manifest": manifest_ref(&runbook),
-                            "effects": effects.to_value(),
-                            "steps": results,
+                            "runbook_manifest": manifest_ref(runbook),
+                            "effects": run.effects,
+                            "steps": progress.results,
                             "error": err.message,
                             "trace_id": trace_id,
-                        }));
+                        });
+                        if progress.persisted {
+                            out["run_id"] = Value::String(progress.run_id.clone());
+                        }
+                        return Ok(out);
                     }
                 }
             }
@@ -346,16 +624,321 @@ impl RunbookManager {
                     obj.insert("state".to_string(), state.clone());
                 }
             }
+            progress.next_index = index + 1;
         }
 
-        Ok(serde_json::json!({
-            "success": results.iter().all(|item| item.get("success").and_then(|v| v.as_bool()).unwrap_or(true)),
+        let success = progress.results.iter().all(|item| {
+            item.get("success")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(true)
+        });
+        let mut out = serde_json::json!({
+            "success": success,
             "runbook": runbook.get("name").cloned().unwrap_or(Value::Null),
-            "runbook_manifest": manifest_ref(&runbook),
-            "effects": effects.to_value(),
-            "steps": results,
+            "runbook_manifest": manifest_ref(runbook),
+            "effects": run.effects,
+            "steps": progress.results,
             "trace_id": trace_id,
-        }))
+        });
+        if progress.persisted {
+            self.save_run(run, context, progress, "completed", None)?;
+            out["run_id"] = Value::String(progress.run_id.clone());
+        }
+        Ok(out)
+    }
+
+    // The step exactly as it would run on approval: templates resolved against the current
+    // context and the run's apply/confirm filled in.
+    fn pending_step_preview(
+        step: &Value,
+        step_key: &str,
+        index: usize,
+        context: &Value,
+        missing: &str,
+    ) -> Value {
+        let raw_args = step
+            .get("args")
+            .cloned()
+            .unwrap_or(Value::Object(Default::default()));
+        let mut args = resolve_templates(&raw_args, context, missing).unwrap_or(raw_args);
+        if let Some(obj) = args.as_object_mut() {
+            for key in ["apply", "confirm"] {
+                if !obj.contains_key(key) {
+                    let flag = context.get(key).and_then(|v| v.as_bool()).unwrap_or(false);
+                    obj.insert(key.to_string(), Value::Bool(flag));
+                }
+            }
+        }
+        let mut preview = serde_json::json!({
+            "index": index,
+            "id": step_key,
+            "tool": step.get("tool").cloned().unwrap_or(Value::Null),
+            "action": args.get("action").cloned().unwrap_or(Value::Null),
+            "args": args,
+        });
+        if let Some(foreach) = step.get("foreach") {
+            preview["foreach"] =
+                resolve_templates(foreach, context, missing).unwrap_or_else(|_| foreach.clone());
+        }
+        preview
+    }
+
+    fn save_run(
+        &self,
+        run: &RunDefinition<'_>,
+        context: &Value,
+        progress: &mut RunProgress,
+        status: &str,
+        awaiting: Option<&Value>,
+    ) -> Result<Value, ToolError> {
+        let now = chrono::Utc::now();
+        let mut stored_context = context.clone();
+        if let Some(obj) = stored_context.as_object_mut() {
+            obj.remove("state");
+        }
+        let expires_at = awaiting.map(|_| {
+            (now + chrono::Duration::milliseconds(progress.checkpoint_ttl_ms as i64)).to_rfc3339()
+        });
+        let record = serde_json::json!({
+            "run_id": progress.run_id,
+            "runbook": run.runbook.get("name").cloned().unwrap_or(Value::Null),
+            "runbook_manifest": manifest_ref(run.runbook),
+            "status": status,
+            "created_at": progress.created_at,
+            "updated_at": now.to_rfc3339(),
+            "expires_at": expires_at,
+            "trace_id": context.get("trace_id").cloned().unwrap_or(Value::Null),
+            "next_index": progress.next_index,
+            "awaiting": awaiting.cloned().unwrap_or(Value::Null),
+            "steps": run.steps,
+            "effects": run.effects,
+            "results": progress.results,
+            "context": stored_context,
+            "approvals": progress.approvals,
+            "options": {
+                "stop_on_error": progress.stop_on_error,
+                "template_missing": progress.template_missing,
+                "checkpoint_ttl_ms": progress.checkpoint_ttl_ms,
+            },
+        });
+        self.state_service.set(
+            &run_state_key(&progress.run_id),
+            record.clone(),
+            Some("persistent"),
+        )?;
+        progress.persisted = true;
+        Ok(record)
+    }
+
+    // Paused runs past their expires_at are marked expired the first time anything looks at them.
+    fn load_run(&self, run_id: &str) -> Result<Value, ToolError> {
+        let key = run_state_key(run_id);
+        let mut record = self
+            .state_service
+            .get(&key, Some("persistent"))?
+            .get("value")
+            .cloned()
+            .filter(|v| v.is_object())
+            .ok_or_else(|| {
+                ToolError::not_found(format!("Runbook run '{}' not found", run_id))
+                    .with_hint("List runs with action=runbook_runs.".to_string())
+            })?;
+        if is_expired(&record) {
+            record["status"] = Value::String("expired".to_string());
+            record["updated_at"] = Value::String(chrono::Utc::now().to_rfc3339());
+            self.state_service
+                .set(&key, record.clone(), Some("persistent"))?;
+        }
+        Ok(record)
+    }
+
+    async fn runbook_resume(&self, args: Value) -> Result<Value, ToolError> {
+        let tool_executor = self.resolve_tool_executor()?;
+        let run_id = args
+            .get("run_id")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .ok_or_else(|| ToolError::invalid_params("runbook_resume requires run_id"))?;
+        let approve = args
+            .get("approve")
+            .and_then(|v| v.as_bool())
+            .ok_or_else(|| {
+                ToolError::invalid_params("runbook_resume requires approve=true|false")
+            })?;
+        let override_args = args.get("override_args").filter(|v| !v.is_null()).cloned();
+        if override_args.as_ref().is_some_and(|v| !v.is_object()) {
+            return Err(ToolError::invalid_params(
+                "override_args must be an object merged into the pending step args",
+            ));
+        }
+
+        let record = self.load_run(run_id)?;
+        let status = record.get("status").and_then(|v| v.as_str()).unwrap_or("");
+        if status != "paused" {
+            return Err(ToolError::conflict(format!(
+                "Runbook run '{}' is {}, not paused",
+                run_id, status
+            ))
+            .with_details(serde_json::json!({ "run_id": run_id, "status": status })));
+        }
+
+        let awaiting = record.get("awaiting").cloned().unwrap_or(Value::Null);
+        let approval = serde_json::json!({
+            "step": awaiting.get("id").cloned().unwrap_or(Value::Null),
+            "approve": approve,
+            "approved_by": args.get("approved_by").cloned().unwrap_or(Value::Null),
+            "override_args": override_args.is_some(),
+            "trace_id": args.get("trace_id").cloned().unwrap_or(Value::Null),
+            "span_id": args.get("span_id").cloned().unwrap_or(Value::Null),
+            "parent_span_id": args.get("parent_span_id").cloned().unwrap_or(Value::Null),
+            "at": chrono::Utc::now().to_rfc3339(),
+        });
+
+        let runbook = record
+            .get("runbook_manifest")
+            .filter(|v| v.is_object())
+            .cloned()
+            .unwrap_or_else(|| serde_json::json!({ "name": record.get("runbook") }));
+        let steps = record
+            .get("steps")
+            .and_then(|v| v.as_array())
+            .cloned()
+            .unwrap_or_default();
+        let options = record.get("options").cloned().unwrap_or(Value::Null);
+        let next_index = record
+            .get("next_index")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0) as usize;
+        let mut progress = RunProgress {
+            run_id: run_id.to_string(),
+            created_at: record
+                .get("created_at")
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_string(),
+            persisted: true,
+            next_index,
+            results: record
+                .get("results")
+                .and_then(|v| v.as_array())
+                .cloned()
+                .unwrap_or_default(),
+            approved_index: Some(next_index),
+            override_args,
+            approvals: record
+                .get("approvals")
+                .and_then(|v| v.as_array())
+                .cloned()
+                .unwrap_or_default(),
+            resume_trace: args
+                .get("trace_id")
+                .and_then(|v| v.as_str())
+                .zip(args.get("span_id").and_then(|v| v.as_str()))
+                .map(|(trace, span)| (trace.to_string(), span.to_string())),
+            stop_on_error: options
+                .get("stop_on_error")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(true),
+            template_missing: options
+                .get("template_missing")
+                .and_then(|v| v.as_str())
+                .unwrap_or("error")
+                .to_string(),
+            checkpoint_ttl_ms: options
+                .get("checkpoint_ttl_ms")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(runbook_constants::CHECKPOINT_TTL_MS),
+        };
+        progress.approvals.push(approval.clone());
+        let run = RunDefinition {
+            runbook: &runbook,
+            steps: &steps,
+            effects: record.get("effects").cloned().unwrap_or(Value::Null),
+        };
+        let mut context = record.get("context").cloned().unwrap_or(Value::Null);
+        if !context.is_object() {
+            context = serde_json::json!({ "steps": {} });
+        }
+
+        if !approve {
+            self.save_run(&run, &context, &mut progress, "aborted", None)?;
+            return Ok(serde_json::json!({
+                "success": false,
+                "aborted": true,
+                "run_id": run_id,
+                "runbook": runbook.get("name").cloned().unwrap_or(Value::Null),
+                "skipped": awaiting,
+                "approval": approval,
+                "steps": progress.results,
+                "trace_id": context.get("trace_id").cloned().unwrap_or(Value::Null),
+            }));
+        }
+
+        if is_readonly_enabled()
+            && resolve_effects(&serde_json::json!({ "effects": run.effects })).is_write_classified()
+        {
+            return Err(ToolError::denied(
+                "INFRA_READONLY=1 denies resuming a write-classified runbook run",
+            )
+            .with_details(serde_json::json!({ "run_id": run_id, "readonly": true })));
+        }
+        let state_snapshot = self.state_service.dump(Some("any"))?;
+        if let Some(obj) = context.as_object_mut() {
+            obj.insert(
+                "state".to_string(),
+                state_snapshot
+                    .get("state")
+                    .cloned()
+                    .unwrap_or(Value::Object(Default::default())),
+            );
+        }
+        let mut result = self
+            .run_steps(&tool_executor, &run, &mut context, &mut progress)
+            .await?;
+        result["approval"] = approval;
+        Ok(result)
+    }
+
+    fn runbook_runs(&self, args: &Value) -> Result<Value, ToolError> {
+        let status_filter = args.get("status").and_then(|v| v.as_str());
+        let listed = self
+            .state_service
+            .list(Some(RUN_STATE_PREFIX), Some("persistent"), false)?;
+        let mut runs = Vec::new();
+        for item in listed
+            .get("items")
+            .and_then(|v| v.as_array())
+            .cloned()
+            .unwrap_or_default()
+        {
+            let Some(run_id) = item
+                .get("key")
+                .and_then(|v| v.as_str())
+                .and_then(|key| key.strip_prefix(RUN_STATE_PREFIX))
+            else {
+                continue;
+            };
+            let Ok(record) = self.load_run(run_id) else {
+                continue;
+            };
+            let status = record.get("status").and_then(|v| v.as_str()).unwrap_or("");
+            if status_filter.is_some_and(|wanted| wanted != status) {
+                continue;
+            }
+            runs.push(serde_json::json!({
+                "run_id": run_id,
+                "runbook": record.get("runbook").cloned().unwrap_or(Value::Null),
+                "status": status,
+                "created_at": record.get("created_at").cloned().unwrap_or(Value::Null),
+                "updated_at": record.get("updated_at").cloned().unwrap_or(Value::Null),
+                "expires_at": record.get("expires_at").cloned().unwrap_or(Value::Null),
+                "awaiting": record.get("awaiting").and_then(|v| v.get("id")).cloned().unwrap_or(Value::Null),
+                "completed_steps": record.get("results").and_then(|v| v.as_array()).map(|v| v.len()).unwrap_or(0),
+                "trace_id": record.get("trace_id").cloned().unwrap_or(Value::Null),
+            }));
+        }
+        Ok(serde_json::json!({ "success": true, "count": runs.len(), "runs": runs }))
     }
 
     fn evaluate_when(condition: Option<&Value>, context: &Value, missing: &str) -> bool {
@@ -462,11 +1045,6 @@ impl RunbookManager {
         let tool = step.get("tool").and_then(|v| v.as_str()).ok_or_else(|| {
             ToolError::invalid_params(format!("runbook step '{}' missing tool", step_key))
         })?;
-        if canonical_tool_name(tool) == "runbook" {
-            return Err(ToolError::denied(
-                "Nested runbook execution is not supported",
-            ));
-        }
         let should_run = Self::evaluate_when(step.get("when"), context, missing);
         if !should_run {
             return Ok(serde_json::json!({
@@ -477,6 +1055,11 @@ impl RunbookManager {
                 "success": true,
             }));
         }
+        if canonical_tool_name(tool) == "runbook" {
+            return Err(ToolError::denied(
+                "Nested runbook execution is not supported",
+            ));
+        }
 
         let base_args = step
             .get("args")
@@ -1,27 +1,53 @@
 use crate::constants::network as network_constants;
-use crate::errors::ToolError;
+use crate::errors::{ToolError, ToolErrorKind};
+use crate::services::audit::AuditService;
 use crate::services::job::JobService;
 use crate::services::logger::Logger;
-use crate::services::profile::ProfileService;
+use crate::services::profile::{ProfileService, UpsertOptions};
 use crate::services::project_resolver::ProjectResolver;
 use crate::services::secret_ref::SecretRefResolver;
 use crate::services::security::Security;
 use crate::services::validation::Validation;
 use crate::utils::artifacts::{
-    build_tool_call_file_ref, resolve_context_root, write_text_artifact,
+    build_run_file_ref, build_tool_call_file_ref, resolve_artifact_path, resolve_context_root,
+    resolve_input_path, resolve_output_path, write_text_artifact,
+};
+use crate::utils::exec_capture::{resolve_stream_to_artifact_mode, CaptureState};
+use crate::utils::exec_policy::ExecPolicy;
+use crate::utils::feature_flags::{self, is_allow_secret_export_enabled};
+use crate::utils::file_attrs::FileAttrs;
+use crate::utils::fs_atomic::ensure_dir_for_file;
+use crate::utils::inventory::{parse_inventory, DEFAULT_DISK_WARN_PCT, INVENTORY_SCRIPT};
+use crate::utils::path_watch::{
+    watch_stat_script, WatchSpec, WatchStat, WatchState, DEFAULT_BACKGROUND_TIMEOUT_MS,
 };
-use crate::utils::feature_flags::is_allow_secret_export_enabled;
-use crate::utils::fs_atomic::{ensure_dir_for_file, temp_sibling_path};
 use crate::utils::redact::redact_text;
+use crate::utils::sftp_listing::{ListedEntry, ListingQuery, ListingWalk, MAX_INLINE_ENTRIES};
+use crate::utils::shell::{
+    deploy_preflight_script, detached_script, ensure_shell_arg, job_status_batch_script,
+    job_status_script, jobs_gc_script, log_delta_script, remove_files_command,
+    restart_service_command, scratch_dir_command, sha256_script, shell_quote, stdin_upload_command,
+    LOG_DELTA_UNSUPPORTED_EXIT,
+};
+use crate::utils::ssh_env::{EnvMode, ExecEnv};
+use crate::utils::ssh_probe::{
+    self, fingerprint_host_key_sha256, ProbeOptions, ProbeTarget, DEFAULT_LATENCY_SAMPLES,
+    MAX_LATENCY_SAMPLES,
+};
 use crate::utils::stability::{
     apply_stability_source, classify_message, classify_tool_error, compute_backoff_delay_ms,
     should_emit_stability, StabilityClassification, StabilityDefaults, StabilityMeta,
     StabilityMode, StabilityPolicy, StabilityPreset,
 };
 use crate::utils::stdin::{resolve_stdin_source, StdinSource};
+use crate::utils::text_parse::{attach_parsed, parse_spec};
 use crate::utils::tool_errors::unknown_action_error;
+use crate::utils::trace_context::TraceContext;
+use crate::utils::transfer::{copy_with_progress, TransferOptions, TransferProgress};
+use crate::utils::usage::{self, Counter};
 use crate::utils::user_paths::expand_home_path;
 use base64::Engine;
+use futures::StreamExt;
 use regex::Regex;
 use serde_json::Value;
 use sha2::{Digest, Sha256};
@@ -29,14 +55,27 @@ use ssh2::{FileStat, OpenFlags, OpenType, Session};
 use std::collections::HashMap;
 use std::fs;
 use std::io::{Read, Write};
-use std::net::TcpStream;
+use std::net::{Shutdown, TcpListener, TcpStream};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
 const SSH_PROFILE_TYPE: &str = "ssh";
-const DEFAULT_MAX_CAPTURE_BYTES: usize = 256 * 1024;
-const DEFAULT_MAX_INLINE_BYTES: usize = 16 * 1024;
+const SSH_SECRET_FIELDS: &[&str] = &["password", "private_key", "passphrase"];
+const MAX_JUMP_HOPS: usize = 4;
+const INVENTORY_DEFAULT_CONCURRENCY: usize = 8;
+const INVENTORY_MAX_CONCURRENCY: usize = 32;
+const INVENTORY_DEFAULT_HOST_TIMEOUT_MS: u64 = 15_000;
+// Above this many hosts only a per-host summary is inlined; full results go to an artifact.
+const INVENTORY_INLINE_HOSTS: usize = 20;
+// exec_detached stdin uploads and default log/pid/exit files live here unless the profile's
+// `scratch_dir` or INFRA_SSH_SCRATCH_DIR points elsewhere.
+const DEFAULT_SCRATCH_DIR: &str = "/tmp/infra-scratch";
+const JOBS_GC_DEFAULT_MAX_AGE_MS: u64 = 24 * 60 * 60 * 1000;
+const FOLLOW_FIRST_POLL_MS: u64 = 250;
+const FOLLOW_MAX_POLL_MS: u64 = 5000;
+const FOLLOW_MAX_LOG_BYTES: u64 = 1024 * 1024;
 
 pub(crate) const SSH_ACTIONS: &[&str] = &[
     "profile_upsert",
@@ -57,9 +96,13 @@ pub(crate) const SSH_ACTIONS: &[&str] = &[
     "follow_job",
     "job_kill",
     "job_forget",
+    "jobs_gc",
     "batch",
     "system_info",
+    "inventory",
     "check_host",
+    "probe",
+    "watch_path",
     "sftp_list",
     "sftp_exists",
     "sftp_upload",
@@ -81,10 +124,35 @@ struct SshConnection {
     password: Option<String>,
     private_key: Option<String>,
     passphrase: Option<String>,
+    // ssh-agent auth; agent_key_fingerprint pins one identity on multi-key agents.
+    agent: bool,
+    agent_key_fingerprint: Option<String>,
     ready_timeout_ms: u64,
     keepalive_interval_ms: u64,
     host_key_policy: HostKeyPolicy,
     host_key_fingerprint: Option<String>,
+    connect_retry: ConnectRetry,
+    jumps: Vec<JumpHop>,
+}
+
+// Retries only cover reaching an authenticated session (TCP connect, handshake, auth I/O);
+// once a channel is open nothing is retried here, so commands never run twice.
+#[derive(Clone, Copy, Debug)]
+struct ConnectRetry {
+    attempts: u32,
+    delay_ms: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum CommandOrigin {
+    User,
+    Internal,
+}
+
+#[derive(Clone, Debug)]
+struct JumpHop {
+    connection: SshConnection,
+    profile_name: Option<String>,
 }
 
 #[derive(Clone, Debug)]
@@ -93,6 +161,109 @@ struct ResolvedConnection {
     profile_name: Option<String>,
 }
 
+#[derive(Clone, Debug)]
+struct SftpTransfer {
+    direction: String,
+    local_path: PathBuf,
+    remote_path: String,
+    overwrite: bool,
+    mkdirs: bool,
+    preserve_mtime: bool,
+    max_rate_bps: Option<u64>,
+}
+
+// Job record for a background transfer; the copy loop writes progress into it from the
+// blocking SFTP thread.
+#[derive(Clone)]
+struct TransferJob {
+    service: Arc<JobService>,
+    record: Arc<Mutex<Value>>,
+    min_progress_bytes: u64,
+}
+
+impl TransferJob {
+    fn start(service: Arc<JobService>, args: &Value, transfer: &SftpTransfer) -> Self {
+        let mut record = service.create(serde_json::json!({
+            "kind": "ssh_transfer",
+            "trace_id": args.get("trace_id").cloned().unwrap_or(Value::Null),
+            "parent_span_id": args.get("span_id").cloned().unwrap_or(Value::Null),
+            "progress": {
+                "direction": transfer.direction,
+                "local_path": transfer.local_path.display().to_string(),
+                "remote_path": transfer.remote_path,
+                "max_rate_bps": transfer.max_rate_bps,
+                "bytes": 0,
+            },
+        }));
+        record["status"] = Value::from("running");
+        record["started_at"] = Value::String(chrono::Utc::now().to_rfc3339());
+        let _ = service.upsert(record.clone());
+        let min_progress_bytes = feature_flags::SSH_PROGRESS_MIN_BYTES.number();
+        Self {
+            service,
+            record: Arc::new(Mutex::new(record)),
+            min_progress_bytes,
+        }
+    }
+
+    fn job_id(&self) -> String {
+        self.record
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .get("job_id")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string()
+    }
+
+    fn abort_flag(&self) -> Arc<AtomicBool> {
+        self.service.get_abort_flag(&self.job_id())
+    }
+
+    fn update(&self, status: Option<&str>, progress: Value, error: Option<&str>) {
+        let mut record = self.record.lock().unwrap_or_else(|err| err.into_inner());
+        if let (Some(current), Value::Object(update)) =
+            (record["progress"].as_object_mut(), progress)
+        {
+            current.extend(update);
+        }
+        if let Some(status) = status {
+            record["status"] = Value::from(status);
+            record["ended_at"] = Value::String(chrono::Utc::now().to_rfc3339());
+        }
+        if let Some(error) = error {
+            record["error"] = Value::from(error);
+        }
+        let _ = self.service.upsert(record.clone());
+    }
+
+    fn report(&self, progress: &TransferProgress) {
+        self.update(None, progress.to_json(), None);
+    }
+
+    fn finish(&self, outcome: &Result<Value, ToolError>) {
+        match outcome {
+            Ok(result) => self.update(
+                Some("succeeded"),
+                serde_json::json!({
+                    "bytes": result.get("bytes").cloned().unwrap_or(Value::Null),
+                    "duration_ms": result.get("duration_ms").cloned().unwrap_or(Value::Null),
+                    "rate_bps": result.get("rate_bps").cloned().unwrap_or(Value::Null),
+                }),
+                None,
+            ),
+            Err(err) => {
+                let status = if self.abort_flag().load(Ordering::SeqCst) {
+                    "canceled"
+                } else {
+                    "failed"
+                };
+                self.update(Some(status), serde_json::json!({}), Some(&err.message));
+            }
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct SshManager {
     logger: Logger,
@@ -102,6 +273,7 @@ pub struct SshManager {
     project_resolver: Option<Arc<ProjectResolver>>,
     secret_ref_resolver: Option<Arc<SecretRefResolver>>,
     job_service: Option<Arc<JobService>>,
+    audit_service: Option<Arc<AuditService>>,
     jobs: Arc<dashmap::DashMap<String, Value>>,
     max_jobs: usize,
     circuits: Arc<Mutex<HashMap<String, Instant>>>,
@@ -117,10 +289,7 @@ impl SshManager {
         secret_ref_resolver: Option<Arc<SecretRefResolver>>,
         job_service: Option<Arc<JobService>>,
     ) -> Self {
-        let max_jobs = std::env::var("INFRA_SSH_MAX_JOBS")
-            .ok()
-            .and_then(|v| v.parse::<usize>().ok())
-            .unwrap_or(200);
+        let max_jobs = feature_flags::SSH_MAX_JOBS.number() as usize;
         Self {
             logger: logger.child("ssh"),
             security,
@@ -129,12 +298,33 @@ impl SshManager {
             project_resolver,
             secret_ref_resolver,
             job_service,
+            audit_service: None,
             jobs: Arc::new(dashmap::DashMap::new()),
             max_jobs,
             circuits: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
+    // Audits the per-command child spans of batch/system_info.
+    pub fn with_audit_service(mut self, audit_service: Arc<AuditService>) -> Self {
+        self.audit_service = Some(audit_service);
+        self
+    }
+
+    async fn exec_in_span(
+        &self,
+        span: &TraceContext,
+        args: Value,
+        origin: CommandOrigin,
+    ) -> Result<Value, ToolError> {
+        let started_at = chrono::Utc::now().timestamp_millis();
+        let result = self.exec_command(&span.apply(args), origin).await;
+        if let Some(audit_service) = self.audit_service.as_ref() {
+            audit_service.record_span(span, "ssh", "exec", started_at, result.as_ref());
+        }
+        result.map(|value| span.tag(value))
+    }
+
     pub async fn handle_action(&self, args: Value) -> Result<Value, ToolError> {
         let action = args.get("action").and_then(|v| v.as_str()).unwrap_or("");
         match action {
@@ -144,9 +334,9 @@ impl SshManager {
             "profile_delete" => self.profile_delete(&args),
             "profile_test" | "connect" => self.profile_test(&args).await,
             "authorized_keys_add" => self.authorized_keys_add(&args).await,
-            "exec" => self.exec_command(&args).await,
-            "exec_detached" => self.exec_detached(&args).await,
-            "exec_follow" => self.exec_follow(&args).await,
+            "exec" => self.exec_command(&args, CommandOrigin::User).await,
+            "exec_detached" => self.exec_detached(&args, CommandOrigin::User).await,
+            "exec_follow" => self.exec_follow(&args, CommandOrigin::User).await,
             "deploy_file" => self.deploy_file(&args).await,
             "job_status" => self.job_status(&args).await,
             "job_wait" => self.job_wait(&args).await,
@@ -155,9 +345,13 @@ impl SshManager {
             "follow_job" => self.follow_job(&args).await,
             "job_kill" => self.job_kill(&args).await,
             "job_forget" => self.job_forget(&args).await,
+            "jobs_gc" => self.jobs_gc(&args).await,
             "batch" => self.batch(&args).await,
             "system_info" => self.system_info(&args).await,
+            "inventory" => self.inventory(&args).await,
             "check_host" => self.check_host(&args).await,
+            "probe" => self.probe(&args).await,
+            "watch_path" => self.watch_path(&args).await,
             "sftp_list" => self.sftp_list(&args).await,
             "sftp_exists" => self.sftp_exists(&args).await,
             "sftp_upload" => self.sftp_upload(&args).await,
@@ -181,43 +375,54 @@ impl SshManager {
             "profile_name",
             true,
         )?;
-        let connection = args
-            .get("connection")
-            .cloned()
-            .unwrap_or(Value::Object(Default::default()));
-        let secrets = serde_json::json!({
-            "password": connection.get("password"),
-            "private_key": connection.get("private_key"),
-            "passphrase": connection.get("passphrase"),
-        });
-        let mut data = connection.clone();
-        if let Some(obj) = data.as_object_mut() {
-            obj.remove("password");
-            obj.remove("private_key");
-            obj.remove("passphrase");
-            if let Some(stability) = args.get("stability") {
-                obj.insert("stability".to_string(), stability.clone());
+        let options = UpsertOptions::from_args(args)?;
+        let mut draft = self
+            .profile_service
+            .upsert_draft(&name, SSH_PROFILE_TYPE, &options)?;
+        if let Some(map) = args.get("connection").and_then(|v| v.as_object()) {
+            for (key, value) in map {
+                if SSH_SECRET_FIELDS.contains(&key.as_str()) {
+                    draft.overlay_secret(key, value);
+                } else {
+                    draft.overlay_data(key, value);
+                }
             }
         }
+        if let Some(stability) = args.get("stability") {
+            draft.overlay_data("stability", stability);
+        }
+        let connection = draft.connection();
+        ExecPolicy::from_value(connection.get("exec_policy"))?;
+        if let Some(dir) = connection.get("scratch_dir") {
+            normalize_scratch_dir(dir, "scratch_dir")?;
+        }
+        if jump_has_inline_secrets(connection.get("jump")) {
+            return Err(ToolError::invalid_params(
+                "jump hops stored in a profile must not carry inline secrets",
+            )
+            .with_hint(
+                "Store the bastion as its own ssh profile and reference it via jump.profile_name."
+                    .to_string(),
+            ));
+        }
 
-        self.profile_test(&serde_json::json!({"connection": connection}))
-            .await?;
-        let profile = self.profile_service.set_profile(
-            &name,
-            &serde_json::json!({
-                "type": SSH_PROFILE_TYPE,
-                "data": data,
-                "secrets": secrets,
-            }),
-        )?;
+        let tested = !options.skip_test;
+        if tested {
+            self.profile_test(&serde_json::json!({"connection": connection}))
+                .await?;
+        }
+        let profile = self
+            .profile_service
+            .save_draft(&name, SSH_PROFILE_TYPE, &draft)?;
 
         Ok(serde_json::json!({
             "success": true,
+            "tested": tested,
             "profile": {
                 "name": name,
                 "type": SSH_PROFILE_TYPE,
                 "data": profile.get("data").cloned().unwrap_or(Value::Object(Default::default())),
-                "auth": if secrets.get("private_key").and_then(|v| v.as_str()).map(|s| !s.is_empty()).unwrap_or(false) { "private_key" } else { "password" },
+                "auth": stored_auth_method(&connection),
             }
         }))
     }
@@ -290,18 +495,28 @@ impl SshManager {
             attempt += 1;
             let resolved = self.resolve_connection(args).await?;
             let connection = resolved.connection.clone();
-            let outcome = tokio::task::spawn_blocking(move || test_connection(&connection))
-                .await
-                .map_err(|_| ToolError::internal("SSH profile test task failed"))?;
+            let profile_service = self.profile_service.clone();
+            let outcome =
+                tokio::task::spawn_blocking(move || test_connection(&connection, &profile_service))
+                    .await
+                    .map_err(|_| ToolError::internal("SSH profile test task failed"))?;
 
             match outcome {
-                Ok(()) => {
+                Ok((connect_attempts, agent_identity)) => {
                     self.close_circuit(&circuit_key);
                     let mut result = serde_json::json!({
                         "success": true,
                         "attempts": attempt,
                         "retries": attempt.saturating_sub(1),
+                        "connect_attempts": connect_attempts,
+                        "auth": auth_method(&resolved.connection),
                     });
+                    if let Some(identity) = agent_identity {
+                        result["agent_identity"] = identity;
+                    }
+                    if !resolved.connection.jumps.is_empty() {
+                        result["chain"] = Value::Array(describe_chain(&resolved.connection));
+                    }
                     let stability = StabilityMeta {
                         retried: attempt > 1,
                         attempts: attempt,
@@ -351,19 +566,21 @@ impl SshManager {
         ))
     }
 
-    async fn exec_command(&self, args: &Value) -> Result<Value, ToolError> {
+    async fn exec_command(&self, args: &Value, origin: CommandOrigin) -> Result<Value, ToolError> {
         let raw_command = self.validation.ensure_string(
             args.get("command").unwrap_or(&Value::Null),
             "command",
             false,
         )?;
         let cwd = args.get("cwd").and_then(|v| v.as_str());
-        let command = build_command(&self.security, &raw_command, cwd)?;
+        let parse = parse_spec(args.get("parse"))?;
+        let policy = self.resolve_exec_policy(args, origin).await?;
+        let command = build_command(&self.security, &raw_command, cwd, policy.as_ref())?;
 
         let requested_timeout = read_positive_int(args.get("timeout_ms"));
         let budget_ms = resolve_tool_call_budget_ms();
         if requested_timeout.map(|v| v > budget_ms).unwrap_or(false) {
-            let follow = self.exec_follow(args).await?;
+            let follow = self.exec_follow(args, origin).await?;
             return Ok(serde_json::json!({
                 "success": follow.get("success").cloned().unwrap_or(Value::Bool(false)),
                 "detached": true,
@@ -378,8 +595,21 @@ impl SshManager {
             budget_ms,
         );
 
-        self.exec_command_with_stability(args, command, timeout_ms, requested_timeout)
-            .await
+        let mut result = self
+            .exec_command_with_stability(args, command, timeout_ms, requested_timeout)
+            .await?;
+        if let Some(spec) = parse {
+            let stdout = result
+                .get("stdout")
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_string();
+            let truncated = ["stdout_truncated", "stdout_inline_truncated"]
+                .iter()
+                .any(|key| result.get(*key).and_then(|v| v.as_bool()).unwrap_or(false));
+            attach_parsed(&mut result, &spec, &stdout, truncated);
+        }
+        Ok(result)
     }
 
     async fn exec_command_with_stability(
@@ -473,8 +703,8 @@ impl SshManager {
         timeout_ms: u64,
         requested_timeout: Option<u64>,
     ) -> Result<Value, ToolError> {
+        let env = ExecEnv::parse(args)?;
         let resolved = self.resolve_connection(args).await?;
-        let env = args.get("env").cloned();
         let pty = args.get("pty").and_then(|v| v.as_bool()).unwrap_or(false);
         let stdin = resolve_stdin_source(args)?;
         let stdin_eof = args
@@ -520,8 +750,14 @@ impl SshManager {
                 )
             }
         };
+        usage::record(Counter::SshStdoutBytes, result.stdout_bytes);
+        usage::record(Counter::SshStderrBytes, result.stderr_bytes);
+        usage::record(
+            Counter::Retries,
+            u64::from(result.connect_attempts.saturating_sub(1)),
+        );
 
-        Ok(serde_json::json!({
+        let mut response = serde_json::json!({
             "success": result.exit_code == 0 && !result.timed_out,
             "command": command,
             "timeout_ms": timeout_ms,
@@ -543,27 +779,34 @@ impl SshManager {
             "timedOut": result.timed_out,
             "hardTimedOut": result.hard_timed_out,
             "duration_ms": result.duration_ms,
-        }))
+            "connect_attempts": result.connect_attempts,
+        });
+        if let Some(report) = result.env {
+            response["env"] = report;
+        }
+        Ok(response)
     }
 
-    async fn exec_detached(&self, args: &Value) -> Result<Value, ToolError> {
+    async fn exec_detached(&self, args: &Value, origin: CommandOrigin) -> Result<Value, ToolError> {
         let raw_command = self.validation.ensure_string(
             args.get("command").unwrap_or(&Value::Null),
             "command",
             false,
         )?;
         let cwd = args.get("cwd").and_then(|v| v.as_str());
-        let command = build_command(&self.security, &raw_command, cwd)?;
+        let policy = self.resolve_exec_policy(args, origin).await?;
+        let command = build_command(&self.security, &raw_command, cwd, policy.as_ref())?;
 
         let start_timeout_ms = std::cmp::min(
             read_positive_int(args.get("timeout_ms"))
                 .unwrap_or(resolve_detached_start_timeout_ms()),
             resolve_tool_call_budget_ms(),
         );
+        let scratch_dir = self.resolve_scratch_dir(args).await?;
         let stdin_source = resolve_stdin_source(args)?;
         let stdin_path = if let Some(source) = stdin_source.as_ref() {
-            let path = format!("/tmp/infra-stdin-{}.txt", uuid::Uuid::new_v4());
-            let upload_command = format!("cat > {}", escape_shell_value(&path));
+            let path = format!("{}/infra-stdin-{}.txt", scratch_dir, uuid::Uuid::new_v4());
+            let upload_command = stdin_upload_command(&scratch_dir, &path);
             let mut upload_args = args.clone();
             if let Value::Object(map) = &mut upload_args {
                 map.insert("command".to_string(), Value::String(upload_command.clone()));
@@ -588,17 +831,19 @@ impl SshManager {
             None
         };
 
-        let log_path = args
+        let custom_log_path = args
             .get("log_path")
             .and_then(|v| v.as_str())
-            .map(|s| s.to_string())
-            .unwrap_or_else(|| {
-                format!(
-                    "/tmp/infra-detached-{}-{}.log",
-                    chrono::Utc::now().timestamp_millis(),
-                    rand::random::<u32>()
-                )
-            });
+            .map(|s| s.to_string());
+        let log_in_scratch = custom_log_path.is_none();
+        let log_path = custom_log_path.unwrap_or_else(|| {
+            format!(
+                "{}/infra-detached-{}-{}.log",
+                scratch_dir,
+                chrono::Utc::now().timestamp_millis(),
+                rand::random::<u32>()
+            )
+        });
         let pid_path = args
             .get("pid_path")
             .and_then(|v| v.as_str())
@@ -609,34 +854,29 @@ impl SshManager {
             .and_then(|v| v.as_str())
             .map(|s| s.to_string())
             .unwrap_or_else(|| format!("{}.exit", log_path));
+        for (field, path) in [
+            ("log_path", &log_path),
+            ("pid_path", &pid_path),
+            ("exit_path", &exit_path),
+        ] {
+            ensure_shell_arg(path, field)?;
+        }
 
         let job_id = uuid::Uuid::new_v4().to_string();
-        let inner_body = if let Some(path) = stdin_path.as_ref() {
-            format!("({}) < {}", command, escape_shell_value(path))
-        } else {
-            format!("({})", command)
-        };
-        let inner = if let Some(path) = stdin_path.as_ref() {
-            format!(
-                "{body}\nrc=$?\nrm -f {stdin}\necho \"$rc\" > {exit}\nexit \"$rc\"",
-                body = inner_body,
-                stdin = escape_shell_value(path),
-                exit = escape_shell_value(&exit_path)
-            )
-        } else {
-            format!(
-                "{body}\nrc=$?\necho \"$rc\" > {exit}\nexit \"$rc\"",
-                body = inner_body,
-                exit = escape_shell_value(&exit_path)
-            )
-        };
-        let detached_command = format!(
-            "rm -f {pid} {exit} 2>/dev/null || true; nohup sh -lc {inner} > {log} 2>&1 < /dev/null & echo $! > {pid}; cat {pid}",
-            pid = escape_shell_value(&pid_path),
-            exit = escape_shell_value(&exit_path),
-            inner = escape_shell_value(&inner),
-            log = escape_shell_value(&log_path)
+        let mut detached_command = detached_script(
+            &command,
+            stdin_path.as_deref(),
+            &pid_path,
+            &exit_path,
+            &log_path,
         );
+        if log_in_scratch && stdin_path.is_none() {
+            detached_command = format!(
+                "{} || exit 1; {}",
+                scratch_dir_command(&scratch_dir),
+                detached_command
+            );
+        }
 
         let exec_args = serde_json::json!({
             "command": detached_command,
@@ -686,6 +926,7 @@ impl SshManager {
             "log_path": log_path,
             "pid_path": pid_path,
             "exit_path": exit_path,
+            "scratch_dir": scratch_dir,
             "start_timeout_ms": start_timeout_ms,
             "stdout": exec.get("stdout").cloned().unwrap_or(Value::Null),
             "stderr": exec.get("stderr").cloned().unwrap_or(Value::Null),
@@ -693,7 +934,7 @@ impl SshManager {
         }))
     }
 
-    async fn exec_follow(&self, args: &Value) -> Result<Value, ToolError> {
+    async fn exec_follow(&self, args: &Value, origin: CommandOrigin) -> Result<Value, ToolError> {
         let started_at = Instant::now();
         let budget_ms = resolve_tool_call_budget_ms();
         let start_timeout_ms = std::cmp::min(
@@ -709,7 +950,7 @@ impl SshManager {
                 Value::Number(start_timeout_ms.into()),
             );
         }
-        let started = self.exec_detached(&start_args).await?;
+        let started = self.exec_detached(&start_args, origin).await?;
         let job_id = started
             .get("job_id")
             .and_then(|v| v.as_str())
@@ -755,6 +996,7 @@ impl SshManager {
             "wait": follow.get("wait").cloned().unwrap_or(Value::Null),
             "status": follow.get("status").cloned().unwrap_or(Value::Null),
             "logs": follow.get("logs").cloned().unwrap_or(Value::Null),
+            "log_offset": follow.get("log_offset").cloned().unwrap_or(Value::Null),
         }))
     }
 
@@ -811,7 +1053,9 @@ impl SshManager {
             map.insert("pty".to_string(), Value::Bool(false));
         }
 
-        let result = self.exec_command(&exec_args).await?;
+        let result = self
+            .exec_command(&exec_args, CommandOrigin::Internal)
+            .await?;
         let marker = result
             .get("stdout")
             .and_then(|v| v.as_str())
@@ -846,16 +1090,24 @@ impl SshManager {
 
     async fn deploy_file(&self, args: &Value) -> Result<Value, ToolError> {
         let started = Instant::now();
-        let local_path = expand_home_path(self.validation.ensure_string(
-            args.get("local_path").unwrap_or(&Value::Null),
+        let local_path = resolve_input_path(
+            &self.validation.ensure_string(
+                args.get("local_path").unwrap_or(&Value::Null),
+                "local_path",
+                true,
+            )?,
             "local_path",
-            true,
-        )?);
+        )?;
         let remote_path = self.validation.ensure_string(
             args.get("remote_path").unwrap_or(&Value::Null),
             "remote_path",
             true,
         )?;
+        ensure_shell_arg(&remote_path, "remote_path")?;
+        if let Some(service) = args.get("restart").and_then(|v| v.as_str()) {
+            ensure_shell_arg(service, "restart")?;
+        }
+        let attrs = FileAttrs::parse(args)?;
 
         let overwrite = args
             .get("overwrite")
@@ -869,9 +1121,84 @@ impl SshManager {
             .get("preserve_mtime")
             .and_then(|v| v.as_bool())
             .unwrap_or(false);
+        let restart_service = args
+            .get("restart")
+            .and_then(|v| v.as_str())
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty());
+
+        let preflight = if args
+            .get("preflight")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+        {
+            let report = self
+                .deploy_preflight(
+                    args,
+                    &local_path,
+                    &remote_path,
+                    restart_service.as_deref(),
+                    mkdirs,
+                )
+                .await?;
+            if report.get("passed").and_then(|v| v.as_bool()) != Some(true) {
+                return Ok(serde_json::json!({
+                    "success": false,
+                    "code": "PREFLIGHT_FAILED",
+                    "local_path": local_path.display().to_string(),
+                    "remote_path": remote_path,
+                    "preflight": report,
+                    "duration_ms": started.elapsed().as_millis(),
+                }));
+            }
+            Some(report)
+        } else {
+            None
+        };
 
         let local_sha256 = compute_local_sha256_hex(&local_path)?;
 
+        if let Some(report) = preflight.as_ref() {
+            if report.get("current_sha256").and_then(|v| v.as_str()) == Some(local_sha256.as_str())
+            {
+                // The content is already there; the requested attributes are still enforced.
+                let attributes = match attrs.as_ref() {
+                    None => None,
+                    Some(attrs) => match self.apply_file_attrs(args, &remote_path, attrs).await? {
+                        Ok(observed) => Some(observed),
+                        Err(failure) => {
+                            return Ok(attrs_failed_response(
+                                failure,
+                                serde_json::json!({
+                                    "local_path": local_path.display().to_string(),
+                                    "remote_path": remote_path,
+                                    "local_sha256": local_sha256,
+                                    "remote_sha256": local_sha256,
+                                    "duration_ms": started.elapsed().as_millis(),
+                                }),
+                            ))
+                        }
+                    },
+                };
+                let mut response = serde_json::json!({
+                    "success": true,
+                    "unchanged": true,
+                    "skipped": true,
+                    "local_path": local_path.display().to_string(),
+                    "remote_path": remote_path,
+                    "local_sha256": local_sha256,
+                    "remote_sha256": local_sha256,
+                    "preflight": report,
+                    "restart": Value::Null,
+                    "duration_ms": started.elapsed().as_millis(),
+                });
+                if let Some(observed) = attributes {
+                    response["attributes"] = observed;
+                }
+                return Ok(response);
+            }
+        }
+
         let upload = self
             .sftp_upload(&serde_json::json!({
                 "profile_name": args.get("profile_name"),
@@ -881,27 +1208,38 @@ impl SshManager {
                 "overwrite": overwrite,
                 "mkdirs": mkdirs,
                 "preserve_mtime": preserve_mtime,
+                "max_rate_bps": args.get("max_rate_bps"),
             }))
             .await;
-        if let Err(err) = upload {
-            return Ok(serde_json::json!({
-                "success": false,
-                "code": "UPLOAD_FAILED",
-                "local_path": local_path.display().to_string(),
-                "remote_path": remote_path,
-                "local_sha256": local_sha256,
-                "error": err.message,
-                "duration_ms": started.elapsed().as_millis(),
-            }));
-        }
+        let upload = match upload {
+            Ok(upload) => upload,
+            Err(err) => {
+                return Ok(serde_json::json!({
+                    "success": false,
+                    "code": "UPLOAD_FAILED",
+                    "local_path": local_path.display().to_string(),
+                    "remote_path": remote_path,
+                    "local_sha256": local_sha256,
+                    "error": err.message,
+                    "duration_ms": started.elapsed().as_millis(),
+                }));
+            }
+        };
+        let transfer = serde_json::json!({
+            "bytes": upload.get("bytes").cloned().unwrap_or(Value::Null),
+            "duration_ms": upload.get("duration_ms").cloned().unwrap_or(Value::Null),
+            "rate_bps": upload.get("rate_bps").cloned().unwrap_or(Value::Null),
+        });
 
-        let hash_cmd = build_remote_sha256_command(&remote_path);
+        let hash_cmd = sha256_script(&remote_path);
         let mut exec_args = args.clone();
         if let Value::Object(map) = &mut exec_args {
             map.insert("command".to_string(), Value::String(hash_cmd));
             map.insert("pty".to_string(), Value::Bool(false));
         }
-        let hash_exec = self.exec_command(&exec_args).await?;
+        let hash_exec = self
+            .exec_command(&exec_args, CommandOrigin::Internal)
+            .await?;
         let remote_sha256 = hash_exec
             .get("stdout")
             .and_then(|v| v.as_str())
@@ -935,11 +1273,25 @@ impl SshManager {
             }));
         }
 
-        let restart_service = args
-            .get("restart")
-            .and_then(|v| v.as_str())
-            .map(|s| s.trim().to_string())
-            .filter(|s| !s.is_empty());
+        let attributes = match attrs.as_ref() {
+            None => None,
+            Some(attrs) => match self.apply_file_attrs(args, &remote_path, attrs).await? {
+                Ok(observed) => Some(observed),
+                Err(failure) => {
+                    return Ok(attrs_failed_response(
+                        failure,
+                        serde_json::json!({
+                            "local_path": local_path.display().to_string(),
+                            "remote_path": remote_path,
+                            "local_sha256": local_sha256,
+                            "remote_sha256": remote_sha256,
+                            "duration_ms": started.elapsed().as_millis(),
+                        }),
+                    ))
+                }
+            },
+        };
+
         let restart_command = args
             .get("restart_command")
             .and_then(|v| v.as_str())
@@ -961,19 +1313,21 @@ impl SshManager {
         let mut restart_result = Value::Null;
         if restart_service.is_some() || restart_command.is_some() {
             let restart_started = Instant::now();
+            // A caller-supplied restart_command is still subject to the profile exec_policy.
+            let origin = if restart_command.is_some() {
+                CommandOrigin::User
+            } else {
+                CommandOrigin::Internal
+            };
             let cmd = restart_command.unwrap_or_else(|| {
-                format!(
-                    "systemctl restart {} && systemctl is-active {}",
-                    escape_shell_value(restart_service.as_ref().unwrap()),
-                    escape_shell_value(restart_service.as_ref().unwrap())
-                )
+                restart_service_command(restart_service.as_deref().unwrap_or_default())
             });
             let mut restart_args = args.clone();
             if let Value::Object(map) = &mut restart_args {
                 map.insert("command".to_string(), Value::String(cmd));
                 map.insert("pty".to_string(), Value::Bool(false));
             }
-            let out = self.exec_command(&restart_args).await?;
+            let out = self.exec_command(&restart_args, origin).await?;
             let exit_code = out.get("exitCode").and_then(|v| v.as_i64());
             let timed_out = out
                 .get("timedOut")
@@ -1004,7 +1358,7 @@ impl SshManager {
             }
         }
 
-        Ok(serde_json::json!({
+        let mut response = serde_json::json!({
             "success": true,
             "local_path": local_path.display().to_string(),
             "remote_path": remote_path,
@@ -1014,9 +1368,173 @@ impl SshManager {
             "local_sha256": local_sha256,
             "remote_sha256": remote_sha256,
             "verified": true,
+            "transfer": transfer,
             "restart": restart_result,
+            "preflight": preflight,
             "duration_ms": started.elapsed().as_millis(),
-        }))
+        });
+        if let Some(observed) = attributes {
+            response["attributes"] = observed;
+        }
+        Ok(response)
+    }
+
+    // Applies `attrs` to an uploaded file and checks them with stat. The inner Err carries the
+    // failure report (stderr, exit code, or which attributes did not stick).
+    async fn apply_file_attrs(
+        &self,
+        args: &Value,
+        remote_path: &str,
+        attrs: &FileAttrs,
+    ) -> Result<Result<Value, Value>, ToolError> {
+        let mut exec_args = args.clone();
+        if let Value::Object(map) = &mut exec_args {
+            map.insert(
+                "command".to_string(),
+                Value::String(attrs.script(remote_path)),
+            );
+            map.insert("pty".to_string(), Value::Bool(false));
+        }
+        let out = self
+            .exec_command(&exec_args, CommandOrigin::Internal)
+            .await?;
+        let exit_code = out.get("exitCode").and_then(|v| v.as_i64());
+        let stdout = out.get("stdout").and_then(|v| v.as_str()).unwrap_or("");
+        let stderr = out.get("stderr").cloned().unwrap_or(Value::Null);
+        if exit_code != Some(0) {
+            return Ok(Err(serde_json::json!({
+                "error": "Applying file attributes failed",
+                "exit_code": exit_code,
+                "stderr": stderr,
+            })));
+        }
+        match attrs.verify(stdout) {
+            Some((observed, mismatches)) if mismatches.is_empty() => Ok(Ok(observed)),
+            Some((observed, mismatches)) => Ok(Err(serde_json::json!({
+                "error": format!("Remote file does not carry the requested {}", mismatches.join(", ")),
+                "mismatches": mismatches,
+                "attributes": observed,
+            }))),
+            None => Ok(Err(serde_json::json!({
+                "error": "Unable to parse remote stat output",
+                "stdout": stdout,
+                "stderr": stderr,
+            }))),
+        }
+    }
+
+    // Read-only gates for deploy_file. Local checks run first, so a missing or empty artifact
+    // fails without opening a connection; the remote probe never writes.
+    async fn deploy_preflight(
+        &self,
+        args: &Value,
+        local_path: &Path,
+        remote_path: &str,
+        unit: Option<&str>,
+        mkdirs: bool,
+    ) -> Result<Value, ToolError> {
+        let min_bytes = read_positive_int(args.get("min_bytes")).unwrap_or(1);
+        let required_free_mb = read_positive_int(args.get("required_free_mb"));
+        let skip_unchanged = args
+            .get("skip_unchanged")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        let local_bytes = fs::metadata(local_path)
+            .ok()
+            .filter(|meta| meta.is_file())
+            .map(|meta| meta.len());
+        let local_ok = local_bytes.is_some_and(|bytes| bytes >= min_bytes);
+        let mut checks = vec![serde_json::json!({
+            "check": "local_file",
+            "ok": local_ok,
+            "exists": local_bytes.is_some(),
+            "bytes": local_bytes,
+            "min_bytes": min_bytes,
+        })];
+        if !local_ok {
+            return Ok(serde_json::json!({
+                "passed": false,
+                "remote_checked": false,
+                "checks": checks,
+            }));
+        }
+
+        let mut exec_args = args.clone();
+        if let Value::Object(map) = &mut exec_args {
+            map.insert(
+                "command".to_string(),
+                Value::String(deploy_preflight_script(remote_path, unit, skip_unchanged)),
+            );
+            map.insert("pty".to_string(), Value::Bool(false));
+        }
+        let exec = self
+            .exec_command(&exec_args, CommandOrigin::Internal)
+            .await?;
+        let stdout = exec.get("stdout").and_then(|v| v.as_str()).unwrap_or("");
+        let pick = |prefix: &str| -> Option<String> {
+            stdout
+                .lines()
+                .find_map(|line| line.strip_prefix(prefix))
+                .map(|value| value.trim().to_string())
+        };
+        if exec.get("exitCode").and_then(|v| v.as_i64()) != Some(0) {
+            checks.push(serde_json::json!({
+                "check": "remote_probe",
+                "ok": false,
+                "exit_code": exec.get("exitCode").cloned().unwrap_or(Value::Null),
+                "stderr": exec.get("stderr").cloned().unwrap_or(Value::Null),
+            }));
+        }
+
+        let dir_exists = pick("__INFRA_PF_DIR__=").as_deref() == Some("1");
+        let writable = pick("__INFRA_PF_WRITABLE__=").as_deref() == Some("1");
+        checks.push(serde_json::json!({
+            "check": "remote_dir",
+            "ok": (dir_exists || mkdirs) && writable,
+            "exists": dir_exists,
+            "writable": writable,
+            "mkdirs": mkdirs,
+        }));
+        if let Some(required) = required_free_mb {
+            let free_mb = pick("__INFRA_PF_FREE_KB__=")
+                .and_then(|value| value.parse::<u64>().ok())
+                .map(|kb| kb / 1024);
+            checks.push(serde_json::json!({
+                "check": "free_space",
+                "ok": free_mb.is_some_and(|free| free >= required),
+                "free_mb": free_mb,
+                "required_free_mb": required,
+            }));
+        }
+        if let Some(unit) = unit {
+            let state = pick("__INFRA_PF_UNIT__=");
+            let mut check = serde_json::json!({
+                "check": "service_unit",
+                "ok": state.as_deref() == Some("1"),
+                "unit": unit,
+            });
+            if state.as_deref() == Some("no_systemctl") {
+                check["error"] = Value::from("systemctl is not available on the target");
+            }
+            checks.push(check);
+        }
+
+        let passed = checks
+            .iter()
+            .all(|check| check.get("ok").and_then(|v| v.as_bool()) == Some(true));
+        let mut report = serde_json::json!({
+            "passed": passed,
+            "remote_checked": true,
+            "checks": checks,
+        });
+        if skip_unchanged {
+            report["current_sha256"] = pick("__INFRA_PF_SHA256__=")
+                .and_then(|value| parse_sha256_from_output(&value))
+                .map(Value::from)
+                .unwrap_or(Value::Null);
+        }
+        Ok(report)
     }
 
     async fn job_status(&self, args: &Value) -> Result<Value, ToolError> {
@@ -1037,26 +1555,7 @@ impl SshManager {
         let exit_path = spec.exit_path.clone().unwrap_or_default();
         let log_path = spec.log_path.clone().unwrap_or_default();
 
-        let script = [
-            "set -u",
-            &format!("PID_VALUE={}", escape_shell_value(&pid_value)),
-            &format!("PID_PATH={}", escape_shell_value(&pid_path)),
-            &format!("EXIT_PATH={}", escape_shell_value(&exit_path)),
-            &format!("LOG_PATH={}", escape_shell_value(&log_path)),
-            "pid=\"$PID_VALUE\"",
-            "if [ -z \"$pid\" ] && [ -n \"$PID_PATH\" ] && [ -f \"$PID_PATH\" ]; then pid=\"$(cat \"$PID_PATH\" 2>/dev/null | tr -dc '0-9' | head -c 32)\"; fi",
-            "running=0",
-            "if [ -n \"$pid\" ] && kill -0 \"$pid\" 2>/dev/null; then running=1; fi",
-            "exit_code=\"\"",
-            "if [ -n \"$EXIT_PATH\" ] && [ -f \"$EXIT_PATH\" ]; then exit_code=\"$(cat \"$EXIT_PATH\" 2>/dev/null | tr -d '\\r\\n' | head -c 64)\"; fi",
-            "log_bytes=\"\"",
-            "if [ -n \"$LOG_PATH\" ] && [ -f \"$LOG_PATH\" ]; then log_bytes=\"$(wc -c < \"$LOG_PATH\" 2>/dev/null | tr -d ' ')\"; fi",
-            "echo \"__INFRA_PID__=$pid\"",
-            "echo \"__INFRA_RUNNING__=$running\"",
-            "echo \"__INFRA_EXIT_CODE__=$exit_code\"",
-            "echo \"__INFRA_LOG_BYTES__=$log_bytes\"",
-        ]
-        .join("\n");
+        let script = job_status_script(&pid_value, &pid_path, &exit_path, &log_path);
         let script_for_exec = script.clone();
 
         let mut exec_args = args.clone();
@@ -1092,7 +1591,7 @@ impl SshManager {
         let exited = !exit_str.trim().is_empty() && exit_code.is_some();
         let log_bytes = log_bytes_str.parse::<i64>().ok();
 
-        Ok(serde_json::json!({
+        let mut result = serde_json::json!({
             "success": true,
             "job_id": spec.job_id,
             "pid": resolved_pid.or(spec.pid),
@@ -1103,19 +1602,109 @@ impl SshManager {
             "pid_path": spec.pid_path,
             "exit_path": spec.exit_path,
             "log_bytes": log_bytes,
-        }))
+        });
+        // Only a finished job's files are removed; a running job keeps its log.
+        if args.get("cleanup").and_then(|v| v.as_bool()) == Some(true) {
+            let cleaned = if exited && !running {
+                self.remove_job_files(args, &spec).await?
+            } else {
+                Vec::new()
+            };
+            result["cleaned"] = serde_json::json!(cleaned);
+        }
+        Ok(result)
     }
 
-    async fn job_wait(&self, args: &Value) -> Result<Value, ToolError> {
-        let budget_ms = resolve_tool_call_budget_ms();
-        let requested = read_positive_int(args.get("timeout_ms")).unwrap_or(30_000);
-        let timeout_ms = std::cmp::min(requested, budget_ms);
-        let poll_ms = std::cmp::min(
-            read_positive_int(args.get("poll_interval_ms")).unwrap_or(1000),
-            5000,
-        );
-        let started = Instant::now();
-
+    // job_status for several jobs on the host `args` selects, in one exec. Results keep the
+    // order of `jobs`; job ids without a record come back as NOT_FOUND without being probed.
+    pub(crate) async fn probe_jobs(
+        &self,
+        args: &Value,
+        jobs: &[Value],
+        timeout_ms: u64,
+    ) -> Result<Vec<Value>, ToolError> {
+        let specs = jobs
+            .iter()
+            .map(|job| self.resolve_job_spec(job, false))
+            .collect::<Result<Vec<_>, _>>()?;
+        let probed: Vec<&JobSpec> = specs.iter().filter(|spec| !spec.not_found).collect();
+        let mut fields: HashMap<usize, Vec<String>> = HashMap::new();
+        if !probed.is_empty() {
+            let pids: Vec<String> = probed
+                .iter()
+                .map(|spec| spec.pid.map(|v| v.to_string()).unwrap_or_default())
+                .collect();
+            let inputs: Vec<[&str; 4]> = probed
+                .iter()
+                .zip(&pids)
+                .map(|(spec, pid)| {
+                    [
+                        pid.as_str(),
+                        spec.pid_path.as_deref().unwrap_or(""),
+                        spec.exit_path.as_deref().unwrap_or(""),
+                        spec.log_path.as_deref().unwrap_or(""),
+                    ]
+                })
+                .collect();
+            let script = job_status_batch_script(&inputs);
+            let mut exec_args = args.clone();
+            if let Value::Object(map) = &mut exec_args {
+                map.insert("command".to_string(), Value::String(script.clone()));
+                map.insert("timeout_ms".to_string(), Value::Number(timeout_ms.into()));
+                map.insert("pty".to_string(), Value::Bool(false));
+            }
+            let exec = self
+                .exec_command_once(&exec_args, script, timeout_ms, Some(timeout_ms))
+                .await?;
+            let stdout = exec.get("stdout").and_then(|v| v.as_str()).unwrap_or("");
+            for line in stdout.lines() {
+                let Some(rest) = line.strip_prefix("__INFRA_JOB__=") else {
+                    continue;
+                };
+                let parts: Vec<String> = rest.splitn(5, '|').map(str::to_string).collect();
+                if let Some(index) = parts.first().and_then(|v| v.parse::<usize>().ok()) {
+                    fields.insert(index, parts);
+                }
+            }
+        }
+
+        let mut probed_index = 0;
+        Ok(specs
+            .iter()
+            .map(|spec| {
+                if spec.not_found {
+                    return serde_json::json!({"success": false, "code": "NOT_FOUND", "job_id": spec.job_id});
+                }
+                let parts = fields.remove(&probed_index).unwrap_or_default();
+                probed_index += 1;
+                let field = |idx: usize| parts.get(idx).map(String::as_str).unwrap_or("");
+                let exit_code = field(4).trim().parse::<i64>().ok();
+                serde_json::json!({
+                    "success": !parts.is_empty(),
+                    "job_id": spec.job_id,
+                    "pid": field(1).parse::<i64>().ok().or(spec.pid),
+                    "running": field(2) == "1",
+                    "exited": exit_code.is_some(),
+                    "exit_code": exit_code,
+                    "log_path": spec.log_path,
+                    "pid_path": spec.pid_path,
+                    "exit_path": spec.exit_path,
+                    "log_bytes": field(3).parse::<i64>().ok(),
+                })
+            })
+            .collect())
+    }
+
+    async fn job_wait(&self, args: &Value) -> Result<Value, ToolError> {
+        let budget_ms = resolve_tool_call_budget_ms();
+        let requested = read_positive_int(args.get("timeout_ms")).unwrap_or(30_000);
+        let timeout_ms = std::cmp::min(requested, budget_ms);
+        let poll_ms = std::cmp::min(
+            read_positive_int(args.get("poll_interval_ms")).unwrap_or(1000),
+            5000,
+        );
+        let started = Instant::now();
+
         let mut status = self
             .job_status(&serde_json::json!({
                 "job_id": args.get("job_id").cloned().unwrap_or(Value::Null),
@@ -1170,9 +1759,9 @@ impl SshManager {
             budget_ms,
         );
         let cmd = format!(
-            "tail -n {} {} 2>/dev/null || true",
+            "tail -n {} -- {} 2>/dev/null || true",
             lines,
-            escape_shell_value(&log_path)
+            shell_quote(&log_path)
         );
         let cmd_for_exec = cmd.clone();
         let mut exec_args = args.clone();
@@ -1229,42 +1818,179 @@ impl SshManager {
         }))
     }
 
+    // Polls from `poll_interval_ms` (default 250) doubling up to `max_poll_interval_ms` (default
+    // 5000), and after every poll reads only the log bytes written since `log_offset`. The result's
+    // `log_offset` is where the next call continues; every byte read is also mirrored into
+    // artifact://runs/jobs/ssh-follow-<job>.log at its log offset, so the whole log can be rebuilt
+    // while `logs.text` only keeps the newest part.
     async fn follow_job(&self, args: &Value) -> Result<Value, ToolError> {
         let budget_ms = resolve_tool_call_budget_ms();
         let requested = read_positive_int(args.get("timeout_ms")).unwrap_or(30_000);
-        let wait_timeout_ms = std::cmp::min(requested, budget_ms);
-        let wait = self
-            .job_wait(&serde_json::json!({
-                "job_id": args.get("job_id").cloned().unwrap_or(Value::Null),
-                "pid": args.get("pid").cloned().unwrap_or(Value::Null),
-                "pid_path": args.get("pid_path").cloned().unwrap_or(Value::Null),
-                "log_path": args.get("log_path").cloned().unwrap_or(Value::Null),
-                "exit_path": args.get("exit_path").cloned().unwrap_or(Value::Null),
-                "profile_name": args.get("profile_name").cloned().unwrap_or(Value::Null),
-                "timeout_ms": wait_timeout_ms,
-            }))
-            .await?;
-        let lines = std::cmp::min(read_positive_int(args.get("lines")).unwrap_or(200), 2000);
-        let logs = self
-            .job_logs_tail(&serde_json::json!({
-                "job_id": args.get("job_id").cloned().unwrap_or(Value::Null),
-                "pid": args.get("pid").cloned().unwrap_or(Value::Null),
-                "pid_path": args.get("pid_path").cloned().unwrap_or(Value::Null),
-                "log_path": args.get("log_path").cloned().unwrap_or(Value::Null),
-                "exit_path": args.get("exit_path").cloned().unwrap_or(Value::Null),
-                "profile_name": args.get("profile_name").cloned().unwrap_or(Value::Null),
-                "lines": lines,
-                "timeout_ms": std::cmp::min(10_000, budget_ms),
-            }))
-            .await?;
+        let timeout_ms = std::cmp::min(requested, budget_ms);
+        let max_poll_ms = std::cmp::min(
+            read_positive_int(args.get("max_poll_interval_ms")).unwrap_or(FOLLOW_MAX_POLL_MS),
+            30_000,
+        );
+        let first_poll_ms = std::cmp::min(
+            read_positive_int(args.get("poll_interval_ms")).unwrap_or(FOLLOW_FIRST_POLL_MS),
+            max_poll_ms,
+        );
+        let from_offset = read_log_offset(args.get("log_offset"))?;
+        let max_log_bytes =
+            read_positive_int(args.get("max_log_bytes")).unwrap_or(FOLLOW_MAX_LOG_BYTES);
+        let job_args = serde_json::json!({
+            "job_id": args.get("job_id").cloned().unwrap_or(Value::Null),
+            "pid": args.get("pid").cloned().unwrap_or(Value::Null),
+            "pid_path": args.get("pid_path").cloned().unwrap_or(Value::Null),
+            "log_path": args.get("log_path").cloned().unwrap_or(Value::Null),
+            "exit_path": args.get("exit_path").cloned().unwrap_or(Value::Null),
+            "profile_name": args.get("profile_name").cloned().unwrap_or(Value::Null),
+            "timeout_ms": std::cmp::min(10_000, budget_ms),
+        });
+        let started = Instant::now();
+
+        let mut status = self.job_status(&job_args).await?;
+        if status.get("success").and_then(|v| v.as_bool()) == Some(false)
+            && status.get("code").and_then(|v| v.as_str()) == Some("NOT_FOUND")
+        {
+            return Ok(
+                serde_json::json!({"success": false, "code": "NOT_FOUND", "job_id": args.get("job_id").cloned().unwrap_or(Value::Null)}),
+            );
+        }
+        let spec = self.resolve_job_spec(args, true)?;
+        let log_path = spec.log_path.clone().unwrap_or_default();
+        let mut follow = LogFollow::new(
+            from_offset,
+            max_log_bytes,
+            LogMirror::for_job(&spec.job_id, spec.profile_name.as_deref(), &log_path),
+        );
+
+        let mut poll_ms = first_poll_ms;
+        let mut polls = 1u64;
+        loop {
+            if !follow.lines_fallback {
+                let log_bytes = status.get("log_bytes").and_then(|v| v.as_u64());
+                self.read_log_delta(args, &spec, log_bytes, &mut follow)
+                    .await?;
+            }
+            if status.get("exited").and_then(|v| v.as_bool()) == Some(true)
+                || started.elapsed().as_millis() as u64 + poll_ms > timeout_ms
+            {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(poll_ms)).await;
+            poll_ms = std::cmp::min(poll_ms.saturating_mul(2), max_poll_ms);
+            status = self.job_status(&job_args).await?;
+            polls += 1;
+        }
+        let exited = status.get("exited").and_then(|v| v.as_bool()) == Some(true);
+        let wait = serde_json::json!({
+            "success": true,
+            "completed": exited,
+            "timed_out": !exited,
+            "waited_ms": started.elapsed().as_millis() as u64,
+            "timeout_ms": timeout_ms,
+            "poll_interval_ms": first_poll_ms,
+            "max_poll_interval_ms": max_poll_ms,
+            "polls": polls,
+        });
+
+        let log_bytes = status.get("log_bytes").and_then(|v| v.as_u64());
+        let logs = if follow.lines_fallback {
+            // Without byte-addressed reads only the last lines can be shown; output written
+            // between this and the previous call may be missing from them.
+            let lines = std::cmp::min(read_positive_int(args.get("lines")).unwrap_or(200), 2000);
+            let mut tail = self
+                .job_logs_tail(&serde_json::json!({
+                    "job_id": args.get("job_id").cloned().unwrap_or(Value::Null),
+                    "pid": args.get("pid").cloned().unwrap_or(Value::Null),
+                    "pid_path": args.get("pid_path").cloned().unwrap_or(Value::Null),
+                    "log_path": args.get("log_path").cloned().unwrap_or(Value::Null),
+                    "exit_path": args.get("exit_path").cloned().unwrap_or(Value::Null),
+                    "profile_name": args.get("profile_name").cloned().unwrap_or(Value::Null),
+                    "lines": lines,
+                    "timeout_ms": std::cmp::min(10_000, budget_ms),
+                }))
+                .await?;
+            follow.offset = log_bytes.unwrap_or(follow.offset);
+            tail["mode"] = Value::String("lines".to_string());
+            tail["gaps_possible"] = Value::Bool(true);
+            tail["log_offset"] = Value::from(follow.offset);
+            tail
+        } else {
+            follow.view(&spec, log_bytes)
+        };
         Ok(serde_json::json!({
             "success": true,
             "wait": wait,
-            "status": wait.get("status").cloned().unwrap_or(Value::Null),
+            "status": status,
             "logs": logs,
+            "log_offset": follow.offset,
+            "log_gaps_possible": follow.lines_fallback,
         }))
     }
 
+    // Reads [offset, log_bytes) in chunks that fit the inline exec output, up to the call's byte
+    // budget. A log smaller than the offset was truncated or rotated and is read again from 0.
+    async fn read_log_delta(
+        &self,
+        args: &Value,
+        spec: &JobSpec,
+        log_bytes: Option<u64>,
+        follow: &mut LogFollow,
+    ) -> Result<(), ToolError> {
+        let (Some(log_path), Some(log_bytes)) = (spec.log_path.as_deref(), log_bytes) else {
+            return Ok(());
+        };
+        if log_bytes < follow.offset {
+            follow.offset = 0;
+            follow.rewound = true;
+        }
+        let chunk = std::cmp::max(resolve_exec_max_inline_bytes() as u64 / 77 * 57, 57);
+        let timeout_ms = std::cmp::min(10_000, resolve_tool_call_budget_ms());
+        while follow.offset < log_bytes && follow.read < follow.budget {
+            let len = chunk
+                .min(log_bytes - follow.offset)
+                .min(follow.budget - follow.read);
+            let script = log_delta_script(log_path, follow.offset, len);
+            let mut exec_args = args.clone();
+            if let Value::Object(map) = &mut exec_args {
+                if let Some(profile) = spec.profile_name.clone() {
+                    map.insert("profile_name".to_string(), Value::String(profile));
+                }
+                map.insert("command".to_string(), Value::String(script.clone()));
+                map.insert("timeout_ms".to_string(), Value::Number(timeout_ms.into()));
+                map.insert("pty".to_string(), Value::Bool(false));
+                map.remove("stdin");
+                map.remove("stdin_file");
+            }
+            let out = self
+                .exec_command_once(&exec_args, script, timeout_ms, Some(timeout_ms))
+                .await?;
+            if out.get("exitCode").and_then(|v| v.as_i64()) == Some(LOG_DELTA_UNSUPPORTED_EXIT) {
+                follow.lines_fallback = true;
+                return Ok(());
+            }
+            let encoded: String = out
+                .get("stdout")
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .chars()
+                .filter(|ch| !ch.is_whitespace())
+                .collect();
+            let bytes = base64::engine::general_purpose::STANDARD
+                .decode(encoded)
+                .map_err(|err| {
+                    ToolError::internal(format!("Failed to decode job log chunk: {}", err))
+                })?;
+            if bytes.is_empty() {
+                break;
+            }
+            follow.push(&bytes);
+        }
+        Ok(())
+    }
+
     async fn job_kill(&self, args: &Value) -> Result<Value, ToolError> {
         let spec = self.resolve_job_spec(args, false)?;
         if spec.not_found {
@@ -1284,7 +2010,9 @@ impl SshManager {
             map.insert("command".to_string(), Value::String(cmd));
             map.insert("pty".to_string(), Value::Bool(false));
         }
-        let _ = self.exec_command(&exec_args).await?;
+        let _ = self
+            .exec_command(&exec_args, CommandOrigin::Internal)
+            .await?;
         Ok(serde_json::json!({"success": true, "job_id": spec.job_id, "pid": pid}))
     }
 
@@ -1293,12 +2021,103 @@ impl SshManager {
         if job_id.trim().is_empty() {
             return Err(ToolError::invalid_params("job_id is required"));
         }
+        let cleaned = if args.get("cleanup").and_then(|v| v.as_bool()) == Some(true) {
+            let spec = self.resolve_job_spec(args, false)?;
+            if spec.not_found {
+                return Ok(
+                    serde_json::json!({"success": false, "code": "NOT_FOUND", "job_id": job_id}),
+                );
+            }
+            Some(self.remove_job_files(args, &spec).await?)
+        } else {
+            None
+        };
         if let Some(service) = &self.job_service {
             service.forget(job_id);
         } else {
             self.jobs.remove(job_id);
         }
-        Ok(serde_json::json!({"success": true, "job_id": job_id}))
+        let mut result = serde_json::json!({"success": true, "job_id": job_id});
+        if let Some(cleaned) = cleaned {
+            result["cleaned"] = serde_json::json!(cleaned);
+        }
+        Ok(result)
+    }
+
+    async fn remove_job_files(
+        &self,
+        args: &Value,
+        spec: &JobSpec,
+    ) -> Result<Vec<String>, ToolError> {
+        let paths: Vec<&str> = [&spec.log_path, &spec.pid_path, &spec.exit_path]
+            .into_iter()
+            .filter_map(|path| path.as_deref())
+            .filter(|path| !path.trim().is_empty())
+            .collect();
+        if paths.is_empty() {
+            return Ok(Vec::new());
+        }
+        for path in &paths {
+            ensure_shell_arg(path, "job path")?;
+        }
+        let command = remove_files_command(&paths);
+        let timeout_ms = std::cmp::min(
+            read_positive_int(args.get("timeout_ms")).unwrap_or(10_000),
+            resolve_tool_call_budget_ms(),
+        );
+        let mut exec_args = args.clone();
+        if let Value::Object(map) = &mut exec_args {
+            if let Some(profile) = spec.profile_name.clone() {
+                map.insert("profile_name".to_string(), Value::String(profile));
+            }
+            map.insert("command".to_string(), Value::String(command.clone()));
+            map.insert("timeout_ms".to_string(), Value::Number(timeout_ms.into()));
+            map.insert("pty".to_string(), Value::Bool(false));
+        }
+        self.exec_command_once(&exec_args, command, timeout_ms, Some(timeout_ms))
+            .await?;
+        Ok(paths.into_iter().map(|path| path.to_string()).collect())
+    }
+
+    async fn jobs_gc(&self, args: &Value) -> Result<Value, ToolError> {
+        let scratch_dir = self.resolve_scratch_dir(args).await?;
+        let max_age_ms =
+            read_positive_int(args.get("max_age_ms")).unwrap_or(JOBS_GC_DEFAULT_MAX_AGE_MS);
+        let max_age_minutes = std::cmp::max(1, max_age_ms / 60_000);
+        let script = jobs_gc_script(&scratch_dir, max_age_minutes);
+        let timeout_ms = std::cmp::min(
+            read_positive_int(args.get("timeout_ms")).unwrap_or(30_000),
+            resolve_tool_call_budget_ms(),
+        );
+        let mut exec_args = args.clone();
+        if let Value::Object(map) = &mut exec_args {
+            map.insert("command".to_string(), Value::String(script.clone()));
+            map.insert("timeout_ms".to_string(), Value::Number(timeout_ms.into()));
+            map.insert("pty".to_string(), Value::Bool(false));
+        }
+        let exec = self
+            .exec_command_once(&exec_args, script, timeout_ms, Some(timeout_ms))
+            .await?;
+        let stdout = exec.get("stdout").and_then(|v| v.as_str()).unwrap_or("");
+        let collect = |prefix: &str| -> Vec<String> {
+            stdout
+                .lines()
+                .filter_map(|line| line.strip_prefix(prefix))
+                .map(|path| path.to_string())
+                .collect()
+        };
+        let removed = collect("__INFRA_GC_REMOVED__=");
+        let running = collect("__INFRA_GC_RUNNING__=");
+        Ok(serde_json::json!({
+            "success": exec.get("exitCode").and_then(|v| v.as_i64()) == Some(0),
+            "scratch_dir": scratch_dir,
+            "scratch_dir_exists": !stdout.lines().any(|line| line == "__INFRA_GC_NO_DIR__"),
+            "max_age_ms": max_age_ms,
+            "removed_count": removed.len(),
+            "removed": removed,
+            "kept_running": running,
+            "stderr": exec.get("stderr").cloned().unwrap_or(Value::Null),
+        }))
     }
 
     async fn batch(&self, args: &Value) -> Result<Value, ToolError> {
@@ -1317,6 +2136,7 @@ impl SshManager {
             .get("stop_on_error")
             .and_then(|v| v.as_bool())
             .unwrap_or(true);
+        let trace = TraceContext::from_args(args);
         let mut results = Vec::new();
         for command in commands {
             if let Some(cmd_obj) = command.as_object() {
@@ -1326,7 +2146,8 @@ impl SshManager {
                         map.insert(k.clone(), v.clone());
                     }
                 }
-                match self.exec_command(&merged).await {
+                let span = trace.child();
+                match self.exec_in_span(&span, merged, CommandOrigin::User).await {
                     Ok(result) => {
                         let exit_code = result
                             .get("exitCode")
@@ -1338,7 +2159,11 @@ impl SshManager {
                         }
                     }
                     Err(err) => {
-                        results.push(serde_json::json!({"success": false, "error": err.message}));
+                        results.push(serde_json::json!({
+                            "success": false,
+                            "error": err.message,
+                            "span_id": span.span_id,
+                        }));
                         if stop_on_error {
                             break;
                         }
@@ -1360,13 +2185,18 @@ impl SshManager {
             ("memory", "free -h 2>/dev/null || vm_stat"),
             ("uptime", "uptime"),
         ];
+        let trace = TraceContext::from_args(args);
         let mut report = serde_json::Map::new();
         for (key, cmd) in commands {
             let mut exec_args = args.clone();
             if let Value::Object(map) = &mut exec_args {
                 map.insert("command".to_string(), Value::String(cmd.to_string()));
             }
-            let entry = match self.exec_command(&exec_args).await {
+            let span = trace.child();
+            let entry = match self
+                .exec_in_span(&span, exec_args, CommandOrigin::Internal)
+                .await
+            {
                 Ok(result) => {
                     let mut obj = serde_json::Map::new();
                     obj.insert("success".to_string(), Value::Bool(true));
@@ -1377,13 +2207,214 @@ impl SshManager {
                     }
                     Value::Object(obj)
                 }
-                Err(err) => serde_json::json!({"success": false, "error": err.message}),
+                Err(err) => serde_json::json!({
+                    "success": false,
+                    "error": err.message,
+                    "span_id": span.span_id,
+                }),
             };
             report.insert(key.to_string(), entry);
         }
         Ok(serde_json::json!({"success": true, "system_info": report}))
     }
 
+    // `profiles: [..]`, `profiles: "all"` (every ssh profile) or the ssh_profile of each target
+    // of `project`.
+    async fn inventory_profiles(&self, args: &Value) -> Result<(String, Vec<String>), ToolError> {
+        let mut names: Vec<String> = Vec::new();
+        let source = match args.get("profiles") {
+            Some(Value::Array(items)) => {
+                for item in items {
+                    let name = item.as_str().ok_or_else(|| {
+                        ToolError::invalid_params("profiles must be an array of profile names")
+                    })?;
+                    names.push(self.validation.ensure_identifier(name, "profiles")?);
+                }
+                "profiles".to_string()
+            }
+            Some(Value::String(all)) if all == "all" => {
+                let profiles = self.profile_service.list_profiles(Some(SSH_PROFILE_TYPE))?;
+                for profile in profiles.as_array().into_iter().flatten() {
+                    if let Some(name) = profile.get("name").and_then(|v| v.as_str()) {
+                        names.push(name.to_string());
+                    }
+                }
+                "all".to_string()
+            }
+            Some(other) if !other.is_null() => {
+                return Err(ToolError::invalid_params(
+                    "profiles must be an array of profile names or \"all\"",
+                ));
+            }
+            _ => {
+                let targets = match &self.project_resolver {
+                    Some(resolver) => resolver.project_targets(args).await?,
+                    None => None,
+                };
+                let Some((project, targets)) = targets else {
+                    return Err(ToolError::invalid_params(
+                        "inventory needs profiles, profiles=\"all\" or a project",
+                    )
+                    .with_hint(
+                        "Example: { action: 'inventory', profiles: ['web-1', 'web-2'] }"
+                            .to_string(),
+                    ));
+                };
+                for target in targets.values() {
+                    if let Some(name) = target.get("ssh_profile").and_then(|v| v.as_str()) {
+                        names.push(self.validation.ensure_identifier(name, "ssh_profile")?);
+                    }
+                }
+                format!("project:{}", project)
+            }
+        };
+        let mut seen = std::collections::HashSet::new();
+        names.retain(|name| seen.insert(name.clone()));
+        if names.is_empty() {
+            return Err(ToolError::invalid_params(format!(
+                "inventory found no ssh profiles ({})",
+                source
+            )));
+        }
+        Ok((source, names))
+    }
+
+    async fn inventory_host(
+        &self,
+        span: &TraceContext,
+        profile: &str,
+        timeout_ms: u64,
+        disk_warn_pct: u64,
+    ) -> Value {
+        let started = Instant::now();
+        let exec_args = serde_json::json!({
+            "profile_name": profile,
+            "command": INVENTORY_SCRIPT,
+            "timeout_ms": timeout_ms,
+        });
+        // The outer timeout also bounds connect and retries, so a dead host cannot stall the call.
+        let outcome = tokio::time::timeout(
+            Duration::from_millis(timeout_ms),
+            self.exec_in_span(span, exec_args, CommandOrigin::Internal),
+        )
+        .await;
+        let mut entry = match outcome {
+            Ok(Ok(result)) => {
+                let stdout = result.get("stdout").and_then(|v| v.as_str()).unwrap_or("");
+                let mut entry = parse_inventory(stdout, disk_warn_pct);
+                entry["reachable"] = Value::Bool(true);
+                entry
+            }
+            Ok(Err(err)) => serde_json::json!({
+                "reachable": false,
+                "error": err.message,
+                "code": err.code,
+            }),
+            Err(_) => serde_json::json!({
+                "reachable": false,
+                "error": format!("timed out after {} ms", timeout_ms),
+                "code": "TIMEOUT",
+            }),
+        };
+        entry["profile"] = Value::String(profile.to_string());
+        entry["span_id"] = Value::String(span.span_id.clone());
+        entry["duration_ms"] = Value::from(started.elapsed().as_millis() as u64);
+        entry
+    }
+
+    async fn inventory(&self, args: &Value) -> Result<Value, ToolError> {
+        let (source, profiles) = self.inventory_profiles(args).await?;
+        let concurrency = args
+            .get("concurrency")
+            .and_then(|v| v.as_u64())
+            .map(|v| (v as usize).clamp(1, INVENTORY_MAX_CONCURRENCY))
+            .unwrap_or(INVENTORY_DEFAULT_CONCURRENCY);
+        let timeout_ms = args
+            .get("host_timeout_ms")
+            .and_then(|v| v.as_u64())
+            .filter(|v| *v > 0)
+            .unwrap_or(INVENTORY_DEFAULT_HOST_TIMEOUT_MS);
+        let disk_warn_pct = args
+            .get("disk_warn_pct")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(DEFAULT_DISK_WARN_PCT);
+        let trace = TraceContext::from_args(args);
+        let started = Instant::now();
+
+        // Futures own their inputs so the stream stays Send for the tool handler.
+        let tasks: Vec<_> = profiles
+            .into_iter()
+            .enumerate()
+            .map(|(index, profile)| {
+                let manager = self.clone();
+                let span = trace.child();
+                async move {
+                    let entry = manager
+                        .inventory_host(&span, &profile, timeout_ms, disk_warn_pct)
+                        .await;
+                    (index, entry)
+                }
+            })
+            .collect();
+        let mut hosts: Vec<(usize, Value)> = futures::stream::iter(tasks)
+            .buffer_unordered(concurrency)
+            .collect()
+            .await;
+        hosts.sort_by_key(|(index, _)| *index);
+        let hosts: Vec<Value> = hosts.into_iter().map(|(_, entry)| entry).collect();
+
+        let warns = |host: &Value| {
+            host.get("disk_warnings")
+                .and_then(|v| v.as_array())
+                .is_some_and(|w| !w.is_empty())
+        };
+        let reachable = hosts.iter().filter(|h| h["reachable"] == true).count();
+        let warning = hosts.iter().filter(|h| warns(h)).count();
+        let stats = serde_json::json!({
+            "hosts": hosts.len(),
+            "reachable": reachable,
+            "unreachable": hosts.len() - reachable,
+            "warning": warning,
+        });
+
+        let mut details_ref = Value::Null;
+        let mut inline = hosts.clone();
+        if hosts.len() > INVENTORY_INLINE_HOSTS {
+            if let Some(root) = resolve_context_root() {
+                let reference = build_tool_call_file_ref(
+                    Some(&trace.trace_id),
+                    Some(&trace.span_id),
+                    "inventory.json",
+                )?;
+                let content = serde_json::to_string_pretty(&hosts).unwrap_or_default();
+                details_ref = Value::String(write_text_artifact(&root, &reference, &content)?.uri);
+                inline = hosts
+                    .iter()
+                    .map(|host| {
+                        let mut summary = serde_json::json!({
+                            "profile": host["profile"],
+                            "reachable": host["reachable"],
+                            "warning": warns(host),
+                        });
+                        if let Some(error) = host.get("error") {
+                            summary["error"] = error.clone();
+                        }
+                        summary
+                    })
+                    .collect();
+            }
+        }
+
+        Ok(serde_json::json!({
+            "success": true,
+            "source": source,
+            "stats": stats,
+            "hosts": inline,
+            "details_ref": details_ref,
+            "duration_ms": started.elapsed().as_millis() as u64,
+        }))
+    }
+
     async fn check_host(&self, args: &Value) -> Result<Value, ToolError> {
         let mut exec_args = args.clone();
         if let Value::Object(map) = &mut exec_args {
@@ -1392,7 +2423,7 @@ impl SshManager {
                 Value::String("echo \"Connection OK\" && whoami && hostname".to_string()),
             );
         }
-        match self.exec_command(&exec_args).await {
+        match self.exec_command(&exec_args, CommandOrigin::Internal).await {
             Ok(result) => {
                 let mut out = serde_json::json!({
                     "success": result.get("exitCode").and_then(|v| v.as_i64()) == Some(0),
@@ -1429,6 +2460,226 @@ impl SshManager {
         }
     }
 
+    // Reachability before credentials exist: only host and port are read from the inline
+    // connection or the profile, and nothing past the key exchange is attempted.
+    async fn probe(&self, args: &Value) -> Result<Value, ToolError> {
+        let (connection, profile_name) = if let Some(connection) = args.get("connection") {
+            (connection.clone(), None)
+        } else {
+            let profile_name = self.resolve_profile_name(args).await?.ok_or_else(|| {
+                ToolError::invalid_params("probe requires connection.host or profile_name")
+                    .with_hint(
+                        "Example: { action: 'probe', connection: { host: '10.0.0.5', port: 22 } }",
+                    )
+            })?;
+            let profile = self
+                .profile_service
+                .get_profile(&profile_name, Some(SSH_PROFILE_TYPE))?;
+            let data = profile.get("data").cloned().unwrap_or(Value::Null);
+            (data, Some(profile_name))
+        };
+        let host = connection
+            .get("host")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .trim()
+            .to_string();
+        if host.is_empty() {
+            return Err(ToolError::invalid_params("connection.host is required"));
+        }
+        let port = self.validation.ensure_port(
+            connection.get("port"),
+            Some(network_constants::SSH_DEFAULT_PORT),
+        )?;
+        let pinned = normalize_fingerprint_sha256(
+            args.get("host_key_fingerprint_sha256")
+                .or_else(|| connection.get("host_key_fingerprint_sha256")),
+        );
+        let options = ProbeOptions {
+            timeout: Duration::from_millis(
+                args.get("timeout_ms")
+                    .and_then(|v| v.as_u64())
+                    .filter(|ms| *ms > 0)
+                    .unwrap_or(5_000),
+            ),
+            latency_samples: args
+                .get("latency_samples")
+                .and_then(|v| v.as_u64())
+                .map(|n| n as usize)
+                .unwrap_or(DEFAULT_LATENCY_SAMPLES)
+                .min(MAX_LATENCY_SAMPLES),
+        };
+
+        let target = ProbeTarget { host, port };
+        let mut result = tokio::task::spawn_blocking(move || ssh_probe::probe(&target, &options))
+            .await
+            .map_err(|_| ToolError::internal("SSH probe task failed"))?;
+        if let Some(name) = profile_name {
+            result["profile_name"] = Value::String(name);
+        }
+        if let Some(observed) = result
+            .pointer("/host_key/fingerprint_sha256")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+        {
+            result["host_key"]["matches_pin"] = pinned
+                .as_ref()
+                .map(|pin| Value::Bool(*pin == observed))
+                .unwrap_or(Value::Null);
+            result["pin"] = serde_json::json!({
+                "host_key_policy": "pin",
+                "host_key_fingerprint_sha256": observed,
+            });
+        }
+        Ok(result)
+    }
+
+    // Polls a remote path or glob until its condition holds or the wait runs out. Each poll is
+    // one exec of `watch_stat_script` through exec_command, so it shares that path's connection
+    // handling. background=true hands the wait to a local job that jobs follow_job tracks.
+    async fn watch_path(&self, args: &Value) -> Result<Value, ToolError> {
+        let spec = WatchSpec::parse(args)?;
+        let background = args
+            .get("background")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        if background {
+            return self.watch_path_background(args, spec);
+        }
+        let timeout_ms = std::cmp::min(
+            read_positive_int(args.get("timeout_ms")).unwrap_or(u64::MAX),
+            resolve_tool_call_budget_ms(),
+        );
+        self.run_watch(args, &spec, timeout_ms, None).await
+    }
+
+    fn watch_path_background(&self, args: &Value, spec: WatchSpec) -> Result<Value, ToolError> {
+        let service = self.job_service.clone().ok_or_else(|| {
+            ToolError::invalid_params("background watches require the job service")
+                .with_hint("Run through the infra app, which wires jobs into the ssh tool.")
+        })?;
+        let timeout_ms =
+            read_positive_int(args.get("timeout_ms")).unwrap_or(DEFAULT_BACKGROUND_TIMEOUT_MS);
+        let job = service.start_local(
+            "ssh_watch",
+            serde_json::json!({
+                "tool": "ssh_watch",
+                "remote_path": spec.remote_path,
+                "condition": spec.condition.to_json(),
+            }),
+            args.get("trace_id").and_then(|v| v.as_str()),
+        )?;
+        let mut args = args.clone();
+        if let Value::Object(map) = &mut args {
+            map.remove("background");
+        }
+
+        let manager = self.clone();
+        let task_service = service.clone();
+        let task_job = job.clone();
+        let task_spec = spec.clone();
+        let handle = tokio::spawn(async move {
+            task_job.log(&format!(
+                "{} watch {} started",
+                chrono::Utc::now().to_rfc3339(),
+                task_spec.remote_path
+            ));
+            let outcome = manager
+                .run_watch(
+                    &args,
+                    &task_spec,
+                    timeout_ms,
+                    Some((task_service.as_ref(), task_job.job_id.as_str())),
+                )
+                .await
+                .map(|mut result| {
+                    // A watch that ran out of time leaves the job failed rather than succeeded.
+                    result["success"] = result["condition_met"].clone();
+                    result
+                });
+            let summary = match &outcome {
+                Ok(result) => format!(
+                    "finished: condition_met={} waited_ms={}",
+                    result["condition_met"], result["waited_ms"]
+                ),
+                Err(err) => format!("failed: {}: {}", err.code, err.message),
+            };
+            task_job.log(&format!("{} {}", chrono::Utc::now().to_rfc3339(), summary));
+            task_service.finish_local(&task_job.job_id, outcome);
+        });
+        service.attach_local_task(&job.job_id, handle.abort_handle(), None);
+
+        Ok(serde_json::json!({
+            "success": true,
+            "background": true,
+            "remote_path": spec.remote_path,
+            "condition": spec.condition.to_json(),
+            "job_id": job.job_id,
+            "log_path": job.log_path,
+            "log_uri": job.log_uri,
+        }))
+    }
+
+    async fn run_watch(
+        &self,
+        args: &Value,
+        spec: &WatchSpec,
+        timeout_ms: u64,
+        job: Option<(&JobService, &str)>,
+    ) -> Result<Value, ToolError> {
+        let mut exec_args = args.clone();
+        if let Value::Object(map) = &mut exec_args {
+            for key in ["cwd", "parse", "stdin", "stdin_file", "stdin_ref", "env"] {
+                map.remove(key);
+            }
+            map.insert(
+                "command".to_string(),
+                Value::String(watch_stat_script(&spec.remote_path)),
+            );
+            map.insert("pty".to_string(), Value::Bool(false));
+        }
+        let started = Instant::now();
+        let mut state = WatchState::default();
+        let mut polls = 0u64;
+        let (stat, condition_met) = loop {
+            let elapsed = started.elapsed().as_millis() as u64;
+            exec_args["timeout_ms"] = Value::from(
+                timeout_ms
+                    .saturating_sub(elapsed)
+                    .clamp(1, resolve_exec_default_timeout_ms()),
+            );
+            let exec = self
+                .exec_command(&exec_args, CommandOrigin::Internal)
+                .await?;
+            polls += 1;
+            let stat = WatchStat::parse(exec.get("stdout").and_then(|v| v.as_str()).unwrap_or(""));
+            let elapsed = started.elapsed().as_millis() as u64;
+            let met = state.observe(&spec.condition, &stat, elapsed);
+            if let Some((service, job_id)) = job {
+                service.update_progress(
+                    job_id,
+                    serde_json::json!({"polls": polls, "waited_ms": elapsed, "stat": stat.to_json()}),
+                );
+            }
+            if met || elapsed + spec.poll_interval_ms > timeout_ms {
+                break (stat, met);
+            }
+            tokio::time::sleep(Duration::from_millis(spec.poll_interval_ms)).await;
+        };
+        Ok(serde_json::json!({
+            "success": true,
+            "remote_path": spec.remote_path,
+            "condition": spec.condition.to_json(),
+            "condition_met": condition_met,
+            "timed_out": !condition_met,
+            "stat": stat.to_json(),
+            "waited_ms": started.elapsed().as_millis() as u64,
+            "polls": polls,
+            "poll_interval_ms": spec.poll_interval_ms,
+            "timeout_ms": timeout_ms,
+        }))
+    }
+
     async fn sftp_list(&self, args: &Value) -> Result<Value, ToolError> {
         let remote_path = self.validation.ensure_string(
             args.get("path")
@@ -1443,55 +2694,108 @@ impl SshManager {
             .unwrap_or(false);
         let max_depth = args.get("max_depth").and_then(|v| v.as_i64()).unwrap_or(3) as i32;
 
+        let query = ListingQuery::from_args(args)?;
+        let trace = TraceContext::from_args(args);
+
         let remote_path_clone = remote_path.clone();
-        let entries = self
+        let walk_query = query.clone();
+        let mut listing = self
             .with_sftp(args, move |sftp| {
-                let mut out = Vec::new();
+                let mut listing = ListingWalk::default();
+                // Returns false once the walk hit max_entries.
                 fn walk(
                     sftp: &ssh2::Sftp,
                     current: &str,
                     depth: i32,
                     max_depth: i32,
                     recursive: bool,
-                    out: &mut Vec<Value>,
-                ) -> Result<(), ToolError> {
+                    query: &ListingQuery,
+                    listing: &mut ListingWalk,
+                ) -> Result<bool, ToolError> {
                     let list = sftp.readdir(Path::new(current)).map_err(map_ssh_error)?;
                     for (path, stat) in list {
-                        let filename = path
-                            .file_name()
-                            .and_then(|s| s.to_str())
-                            .unwrap_or("")
-                            .to_string();
-                        let full_path = path.to_string_lossy().to_string();
                         let is_dir = stat.perm.map(|p| p & 0o40000 != 0).unwrap_or(false);
-                        out.push(serde_json::json!({
-                            "path": full_path,
-                            "filename": filename,
-                            "type": if is_dir { "dir" } else { "file" },
-                            "size": stat.size,
-                            "mode": stat.perm,
-                            "mtime": stat.mtime,
-                            "atime": stat.atime,
-                        }));
-                        if recursive && is_dir && depth < max_depth {
-                            walk(
+                        let entry = ListedEntry {
+                            path: path.to_string_lossy().to_string(),
+                            filename: path
+                                .file_name()
+                                .and_then(|s| s.to_str())
+                                .unwrap_or("")
+                                .to_string(),
+                            is_dir,
+                            size: stat.size,
+                            mode: stat.perm,
+                            mtime: stat.mtime,
+                            atime: stat.atime,
+                        };
+                        if !listing.visit(query, entry) {
+                            return Ok(false);
+                        }
+                        if recursive
+                            && is_dir
+                            && depth < max_depth
+                            && !walk(
                                 sftp,
                                 &path.to_string_lossy(),
                                 depth + 1,
                                 max_depth,
                                 recursive,
-                                out,
-                            )?;
+                                query,
+                                listing,
+                            )?
+                        {
+                            return Ok(false);
                         }
                     }
-                    Ok(())
+                    Ok(true)
                 }
-                walk(sftp, &remote_path_clone, 0, max_depth, recursive, &mut out)?;
-                Ok(out)
+                walk(
+                    sftp,
+                    &remote_path_clone,
+                    0,
+                    max_depth,
+                    recursive,
+                    &walk_query,
+                    &mut listing,
+                )?;
+                Ok(listing)
             })
             .await?;
 
-        Ok(serde_json::json!({"success": true, "path": remote_path, "entries": entries}))
+        query.sort(&mut listing.matched);
+        let total_matched = listing.matched.len();
+        let entries: Vec<Value> = query
+            .page(&listing.matched)
+            .iter()
+            .map(ListedEntry::to_json)
+            .collect();
+        let mut listing_ref = Value::Null;
+        if total_matched > MAX_INLINE_ENTRIES {
+            if let Some(root) = resolve_context_root() {
+                let reference = build_tool_call_file_ref(
+                    Some(&trace.trace_id),
+                    Some(&trace.span_id),
+                    "sftp_list.json",
+                )?;
+                let all: Vec<Value> = listing.matched.iter().map(ListedEntry::to_json).collect();
+                let content = serde_json::to_string_pretty(&all).unwrap_or_default();
+                listing_ref = Value::String(write_text_artifact(&root, &reference, &content)?.uri);
+            }
+        }
+
+        Ok(serde_json::json!({
+            "success": true,
+            "path": remote_path,
+            "truncated": query.offset + entries.len() < total_matched,
+            "entries": entries,
+            "total_matched": total_matched,
+            "offset": query.offset,
+            "limit": query.limit,
+            "scanned": listing.scanned,
+            "max_entries_reached": listing.stopped_at.is_some(),
+            "stopped_at": listing.stopped_at,
+            "listing_ref": listing_ref,
+        }))
     }
 
     async fn sftp_exists(&self, args: &Value) -> Result<Value, ToolError> {
@@ -1506,16 +2810,42 @@ impl SshManager {
             read_positive_int(args.get("timeout_ms")).unwrap_or(10_000),
             resolve_tool_call_budget_ms(),
         );
+        let with_sha256 = args
+            .get("sha256")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
         let remote_path_clone = remote_path.clone();
         let exists = self
             .with_sftp(args, move |sftp| {
                 let _ = timeout_ms; // placeholder, sftp stat is blocking
                 match sftp.stat(Path::new(&remote_path_clone)) {
-                    Ok(stat) => Ok((true, Some(stat))),
+                    Ok(stat) => {
+                        // Hashed over SFTP so checking a file never runs a remote command.
+                        let sha256 = if with_sha256 && stat.is_file() {
+                            let mut file = sftp
+                                .open(Path::new(&remote_path_clone))
+                                .map_err(map_ssh_error)?;
+                            let mut hasher = Sha256::new();
+                            let mut buf = [0u8; 32 * 1024];
+                            loop {
+                                let n = file
+                                    .read(&mut buf)
+                                    .map_err(|err| ToolError::internal(err.to_string()))?;
+                                if n == 0 {
+                                    break;
+                                }
+                                hasher.update(&buf[..n]);
+                            }
+                            Some(format!("{:x}", hasher.finalize()))
+                        } else {
+                            None
+                        };
+                        Ok((true, Some(stat), sha256))
+                    }
                     Err(err) => {
                         let io_err: std::io::Error = err.into();
                         if io_err.kind() == std::io::ErrorKind::NotFound {
-                            Ok((false, None))
+                            Ok((false, None, None))
                         } else {
                             Err(ToolError::internal(io_err.to_string()))
                         }
@@ -1523,7 +2853,7 @@ impl SshManager {
                 }
             })
             .await?;
-        Ok(serde_json::json!({
+        let mut result = serde_json::json!({
             "success": true,
             "remote_path": remote_path,
             "exists": exists.0,
@@ -1535,165 +2865,291 @@ impl SshManager {
                 "atime": stat.atime,
                 "mtime": stat.mtime,
             })),
-        }))
+        });
+        if with_sha256 {
+            result["sha256"] = exists.2.map(Value::String).unwrap_or(Value::Null);
+        }
+        Ok(result)
     }
 
-    async fn sftp_upload(&self, args: &Value) -> Result<Value, ToolError> {
-        let local_path = expand_home_path(self.validation.ensure_string(
+    fn transfer_request(&self, args: &Value, direction: &str) -> Result<SftpTransfer, ToolError> {
+        let raw_local_path = self.validation.ensure_string(
             args.get("local_path").unwrap_or(&Value::Null),
             "local_path",
             true,
-        )?);
+        )?;
+        let local_path = if direction == "download" {
+            resolve_output_path(&raw_local_path, "local_path")?
+        } else {
+            resolve_input_path(&raw_local_path, "local_path")?
+        };
         let remote_path = self.validation.ensure_string(
             args.get("remote_path").unwrap_or(&Value::Null),
             "remote_path",
             true,
         )?;
-        let overwrite = args
-            .get("overwrite")
-            .and_then(|v| v.as_bool())
-            .unwrap_or(false);
-        let mkdirs = args
-            .get("mkdirs")
-            .and_then(|v| v.as_bool())
-            .unwrap_or(false);
-        let preserve_mtime = args
-            .get("preserve_mtime")
+        let flag = |key: &str| args.get(key).and_then(|v| v.as_bool()).unwrap_or(false);
+        let max_rate_bps = match args.get("max_rate_bps") {
+            None | Some(Value::Null) => None,
+            Some(value) => Some(value.as_u64().filter(|rate| *rate > 0).ok_or_else(|| {
+                ToolError::invalid_params("max_rate_bps must be a positive integer (bytes/second)")
+            })?),
+        };
+        Ok(SftpTransfer {
+            direction: direction.to_string(),
+            local_path,
+            remote_path,
+            overwrite: flag("overwrite"),
+            mkdirs: flag("mkdirs"),
+            preserve_mtime: flag("preserve_mtime"),
+            max_rate_bps,
+        })
+    }
+
+    // background=true returns a job_id right away; follow it with job action=follow_job.
+    fn start_background_transfer(
+        &self,
+        args: &Value,
+        transfer: SftpTransfer,
+    ) -> Result<Value, ToolError> {
+        let service = self.job_service.clone().ok_or_else(|| {
+            ToolError::invalid_params("background transfers require the job store")
+                .with_hint("Rerun without background=true.")
+        })?;
+        let job = TransferJob::start(service, args, &transfer);
+        let job_id = job.job_id();
+        let manager = self.clone();
+        let args = args.clone();
+        let response = serde_json::json!({
+            "success": true,
+            "background": true,
+            "job_id": job_id,
+            "transfer_id": job_id,
+            "direction": transfer.direction,
+            "local_path": transfer.local_path.display().to_string(),
+            "remote_path": transfer.remote_path,
+        });
+        tokio::spawn(async move {
+            let outcome = if transfer.direction == "download" {
+                manager
+                    .run_download(&args, transfer, Some(job.clone()))
+                    .await
+            } else {
+                manager.run_upload(&args, transfer, Some(job.clone())).await
+            };
+            job.finish(&outcome);
+        });
+        Ok(response)
+    }
+
+    async fn sftp_upload(&self, args: &Value) -> Result<Value, ToolError> {
+        let transfer = self.transfer_request(args, "upload")?;
+        let attrs = FileAttrs::parse(args)?;
+        if args
+            .get("background")
             .and_then(|v| v.as_bool())
-            .unwrap_or(false);
-        let local_clone = local_path.clone();
-        let remote_clone = remote_path.clone();
-
-        self.with_sftp(args, move |sftp| {
-            if !overwrite && sftp.stat(Path::new(&remote_clone)).is_ok() {
-                return Err(ToolError::conflict(format!(
-                    "Remote path already exists: {}",
-                    remote_clone
-                ))
-                .with_hint("Set overwrite=true to replace it."));
-            }
-            if mkdirs {
-                ensure_remote_dir(sftp, &remote_clone)?;
-            }
-            let mut local_file = fs::File::open(&local_clone).map_err(|err| {
-                ToolError::invalid_params(format!("local_path must be readable: {}", err))
-            })?;
-            let mut remote_file = sftp
-                .open_mode(
-                    Path::new(&remote_clone),
-                    OpenFlags::WRITE | OpenFlags::CREATE | OpenFlags::TRUNCATE,
-                    0o600,
-                    OpenType::File,
+            .unwrap_or(false)
+        {
+            if attrs.is_some() {
+                return Err(ToolError::invalid_params(
+                    "owner, group, mode and selinux_context are not applied to background uploads",
                 )
-                .map_err(map_ssh_error)?;
-            std::io::copy(&mut local_file, &mut remote_file)
-                .map_err(|err| ToolError::internal(err.to_string()))?;
-            if preserve_mtime {
-                if let Ok(metadata) = fs::metadata(&local_clone) {
-                    let atime = metadata
-                        .accessed()
-                        .ok()
-                        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-                        .map(|d| d.as_secs());
-                    let mtime = metadata
-                        .modified()
-                        .ok()
-                        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-                        .map(|d| d.as_secs());
-                    let stat = FileStat {
-                        size: None,
-                        uid: None,
-                        gid: None,
-                        perm: None,
-                        atime,
-                        mtime,
-                    };
-                    let _ = sftp.setstat(Path::new(&remote_clone), stat);
+                .with_hint(
+                    "Upload in the foreground, or use deploy_file once the upload is done.",
+                ));
+            }
+            return self.start_background_transfer(args, transfer);
+        }
+        let remote_path = transfer.remote_path.clone();
+        let mut result = self.run_upload(args, transfer, None).await?;
+        if let Some(attrs) = attrs {
+            ensure_shell_arg(&remote_path, "remote_path")?;
+            match self.apply_file_attrs(args, &remote_path, &attrs).await? {
+                Ok(observed) => result["attributes"] = observed,
+                Err(failure) => {
+                    let message = failure["error"]
+                        .as_str()
+                        .unwrap_or("Applying file attributes failed")
+                        .to_string();
+                    return Err(ToolError::new(ToolErrorKind::Internal, "ATTRS_FAILED", message)
+                        .with_hint("The file was uploaded; fix the attributes (or pass sudo=true) and upload again.")
+                        .with_details(failure));
                 }
             }
-            Ok(())
-        })
-        .await?;
+        }
+        Ok(result)
+    }
 
-        Ok(
-            serde_json::json!({"success": true, "local_path": local_path.display().to_string(), "remote_path": remote_path}),
-        )
+    async fn run_upload(
+        &self,
+        args: &Value,
+        transfer: SftpTransfer,
+        job: Option<TransferJob>,
+    ) -> Result<Value, ToolError> {
+        let local_clone = transfer.local_path.clone();
+        let remote_clone = transfer.remote_path.clone();
+        let SftpTransfer {
+            overwrite,
+            mkdirs,
+            preserve_mtime,
+            max_rate_bps,
+            ..
+        } = transfer;
+
+        let stats = self
+            .with_sftp(args, move |sftp| {
+                if !overwrite && sftp.stat(Path::new(&remote_clone)).is_ok() {
+                    return Err(ToolError::conflict(format!(
+                        "Remote path already exists: {}",
+                        remote_clone
+                    ))
+                    .with_hint("Set overwrite=true to replace it."));
+                }
+                if mkdirs {
+                    ensure_remote_dir(sftp, &remote_clone)?;
+                }
+                let mut local_file = fs::File::open(&local_clone).map_err(|err| {
+                    ToolError::invalid_params(format!("local_path must be readable: {}", err))
+                })?;
+                let total_bytes = local_file.metadata().ok().map(|meta| meta.len());
+                let mut remote_file = sftp
+                    .open_mode(
+                        Path::new(&remote_clone),
+                        OpenFlags::WRITE | OpenFlags::CREATE | OpenFlags::TRUNCATE,
+                        0o600,
+                        OpenType::File,
+                    )
+                    .map_err(map_ssh_error)?;
+                let stats = copy_transfer(
+                    &mut local_file,
+                    &mut remote_file,
+                    max_rate_bps,
+                    total_bytes,
+                    job,
+                )?;
+                if preserve_mtime {
+                    if let Ok(metadata) = fs::metadata(&local_clone) {
+                        let atime = metadata
+                            .accessed()
+                            .ok()
+                            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                            .map(|d| d.as_secs());
+                        let mtime = metadata
+                            .modified()
+                            .ok()
+                            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                            .map(|d| d.as_secs());
+                        let stat = FileStat {
+                            size: None,
+                            uid: None,
+                            gid: None,
+                            perm: None,
+                            atime,
+                            mtime,
+                        };
+                        let _ = sftp.setstat(Path::new(&remote_clone), stat);
+                    }
+                }
+                Ok(stats)
+            })
+            .await?;
+
+        usage::record(Counter::SftpBytesWritten, stats.bytes);
+        Ok(serde_json::json!({
+            "success": true,
+            "local_path": transfer.local_path.display().to_string(),
+            "remote_path": transfer.remote_path,
+            "bytes": stats.bytes,
+            "duration_ms": stats.elapsed_ms,
+            "rate_bps": stats.rate_bps(),
+            "max_rate_bps": max_rate_bps,
+        }))
     }
 
     async fn sftp_download(&self, args: &Value) -> Result<Value, ToolError> {
-        let remote_path = self.validation.ensure_string(
-            args.get("remote_path").unwrap_or(&Value::Null),
-            "remote_path",
-            true,
-        )?;
-        let local_path = expand_home_path(self.validation.ensure_string(
-            args.get("local_path").unwrap_or(&Value::Null),
-            "local_path",
-            true,
-        )?);
-        let overwrite = args
-            .get("overwrite")
-            .and_then(|v| v.as_bool())
-            .unwrap_or(false);
-        let mkdirs = args
-            .get("mkdirs")
-            .and_then(|v| v.as_bool())
-            .unwrap_or(false);
-        let preserve_mtime = args
-            .get("preserve_mtime")
-            .and_then(|v| v.as_bool())
-            .unwrap_or(false);
-
-        if !overwrite && local_path.exists() {
+        let transfer = self.transfer_request(args, "download")?;
+        if !transfer.overwrite && transfer.local_path.exists() {
             return Err(ToolError::conflict(format!(
                 "Local path already exists: {}",
-                local_path.display()
+                transfer.local_path.display()
             ))
             .with_hint("Set overwrite=true to replace it."));
         }
-        if mkdirs {
+        if args
+            .get("background")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+        {
+            return self.start_background_transfer(args, transfer);
+        }
+        self.run_download(args, transfer, None).await
+    }
+
+    async fn run_download(
+        &self,
+        args: &Value,
+        transfer: SftpTransfer,
+        job: Option<TransferJob>,
+    ) -> Result<Value, ToolError> {
+        let local_path = transfer.local_path.clone();
+        if transfer.mkdirs {
             if let Some(parent) = local_path.parent() {
                 let _ = fs::create_dir_all(parent);
             }
         }
         let tmp_path = local_path.with_extension(format!("tmp-{}", rand::random::<u32>()));
         let tmp_clone = tmp_path.clone();
-        let remote_clone = remote_path.clone();
-        let local_clone = local_path.clone();
+        let remote_clone = transfer.remote_path.clone();
+        let preserve_mtime = transfer.preserve_mtime;
+        let max_rate_bps = transfer.max_rate_bps;
 
-        let remote_times = self
+        let outcome = self
             .with_sftp(args, move |sftp| {
+                let stat = sftp.stat(Path::new(&remote_clone)).ok();
                 let mut remote_file = sftp.open(Path::new(&remote_clone)).map_err(map_ssh_error)?;
                 let mut tmp_file = fs::File::create(&tmp_clone).map_err(|err| {
                     ToolError::internal(format!("Failed to create temp file: {}", err))
                 })?;
-                std::io::copy(&mut remote_file, &mut tmp_file)
-                    .map_err(|err| ToolError::internal(err.to_string()))?;
-                let stat = if preserve_mtime {
-                    sftp.stat(Path::new(&remote_clone)).ok()
-                } else {
-                    None
-                };
-                Ok(stat)
+                let total_bytes = stat.as_ref().and_then(|stat| stat.size);
+                let stats = copy_transfer(
+                    &mut remote_file,
+                    &mut tmp_file,
+                    max_rate_bps,
+                    total_bytes,
+                    job,
+                )?;
+                Ok((stats, if preserve_mtime { stat } else { None }))
             })
-            .await?;
+            .await;
+        let (stats, remote_times) = match outcome {
+            Ok(outcome) => outcome,
+            Err(err) => {
+                let _ = fs::remove_file(&tmp_path);
+                return Err(err);
+            }
+        };
 
-        fs::rename(&tmp_path, &local_clone)
+        fs::rename(&tmp_path, &local_path)
             .map_err(|err| ToolError::internal(format!("Failed to finalize download: {}", err)))?;
 
-        if preserve_mtime {
-            if let Some(stat) = remote_times {
-                if let (Some(atime), Some(mtime)) = (stat.atime, stat.mtime) {
-                    let atime = filetime::FileTime::from_unix_time(atime as i64, 0);
-                    let mtime = filetime::FileTime::from_unix_time(mtime as i64, 0);
-                    let _ = filetime::set_file_times(&local_clone, atime, mtime);
-                }
+        if let Some(stat) = remote_times {
+            if let (Some(atime), Some(mtime)) = (stat.atime, stat.mtime) {
+                let atime = filetime::FileTime::from_unix_time(atime as i64, 0);
+                let mtime = filetime::FileTime::from_unix_time(mtime as i64, 0);
+                let _ = filetime::set_file_times(&local_path, atime, mtime);
             }
         }
 
-        Ok(
-            serde_json::json!({"success": true, "remote_path": remote_path, "local_path": local_path.display().to_string()}),
-        )
+        usage::record(Counter::SftpBytesRead, stats.bytes);
+        Ok(serde_json::json!({
+            "success": true,
+            "remote_path": transfer.remote_path,
+            "local_path": local_path.display().to_string(),
+            "bytes": stats.bytes,
+            "duration_ms": stats.elapsed_ms,
+            "rate_bps": stats.rate_bps(),
+            "max_rate_bps": max_rate_bps,
+        }))
     }
 
     pub async fn with_sftp<F, T>(&self, args: &Value, handler: F) -> Result<T, ToolError>
@@ -1704,12 +3160,24 @@ impl SshManager {
         let resolved = self.resolve_connection(args).await?;
         let profile_name = resolved.profile_name.clone();
         let profile_service = self.profile_service.clone();
+        let usage_scope = usage::current();
         tokio::task::spawn_blocking(move || {
-            let (session, observed) = connect_session(&resolved.connection)?;
+            let (established, connect_attempts) =
+                connect_session(&resolved.connection, &profile_service)?;
+            usage::record_in(
+                usage_scope.as_ref(),
+                Counter::Retries,
+                u64::from(connect_attempts.saturating_sub(1)),
+            );
             if let Some(profile) = profile_name.as_deref() {
-                maybe_persist_tofu(&profile_service, profile, &resolved.connection, observed)?;
+                maybe_persist_tofu(
+                    &profile_service,
+                    profile,
+                    &resolved.connection,
+                    established.host_key,
+                )?;
             }
-            let sftp = session.sftp().map_err(map_ssh_error)?;
+            let sftp = established.session.sftp().map_err(map_ssh_error)?;
             handler(&sftp)
         })
         .await
@@ -1728,7 +3196,10 @@ impl SshManager {
             } else {
                 connection.clone()
             };
-            let connection = self.build_connection_from_value(&resolved, args)?;
+            let mut connection = self.build_connection_from_value(&resolved, args)?;
+            connection.jumps = self
+                .resolve_jumps(args.get("jump").or_else(|| resolved.get("jump")), args)
+                .await?;
             return Ok(ResolvedConnection {
                 connection,
                 profile_name: None,
@@ -1744,31 +3215,145 @@ impl SshManager {
         let profile = self
             .profile_service
             .get_profile(&profile_name, Some(SSH_PROFILE_TYPE))?;
-        let mut merged = profile
-            .get("data")
-            .cloned()
-            .unwrap_or(Value::Object(Default::default()));
-        if let Some(secrets) = profile.get("secrets") {
-            if let Some(obj) = secrets.as_object() {
-                if let Value::Object(map) = &mut merged {
-                    for (key, value) in obj {
-                        map.insert(key.clone(), value.clone());
-                    }
-                }
-            }
-        }
+        let merged = profile_connection_value(&profile);
         let resolved = if let Some(resolver) = &self.secret_ref_resolver {
             resolver.resolve_deep(&merged, args).await?
         } else {
             merged.clone()
         };
-        let connection = self.build_connection_from_value(&resolved, args)?;
+        let mut connection = self.build_connection_from_value(&resolved, args)?;
+        connection.jumps = self
+            .resolve_jumps(args.get("jump").or_else(|| resolved.get("jump")), args)
+            .await?;
         Ok(ResolvedConnection {
             connection,
             profile_name: Some(profile_name),
         })
     }
 
+    async fn resolve_exec_policy(
+        &self,
+        args: &Value,
+        origin: CommandOrigin,
+    ) -> Result<Option<(String, ExecPolicy)>, ToolError> {
+        if origin == CommandOrigin::Internal {
+            return Ok(None);
+        }
+        if let Some(connection) = args.get("connection") {
+            return Ok(ExecPolicy::from_value(connection.get("exec_policy"))?
+                .map(|policy| ("the inline connection".to_string(), policy)));
+        }
+        let Some(profile_name) = self.resolve_profile_name(args).await? else {
+            return Ok(None);
+        };
+        let profile = self
+            .profile_service
+            .get_profile(&profile_name, Some(SSH_PROFILE_TYPE))?;
+        let policy = ExecPolicy::from_value(profile.get("data").and_then(|v| v.get("exec_policy")))
+            .map_err(|err| prefix_error(err, &format!("ssh profile '{}'", profile_name)))?;
+        Ok(policy.map(|policy| (format!("profile '{}'", profile_name), policy)))
+    }
+
+    // Inline connection or profile `scratch_dir`, then INFRA_SSH_SCRATCH_DIR, then the default.
+    async fn resolve_scratch_dir(&self, args: &Value) -> Result<String, ToolError> {
+        let configured = if let Some(connection) = args.get("connection") {
+            connection.get("scratch_dir").cloned()
+        } else if let Some(profile_name) = self.resolve_profile_name(args).await? {
+            self.profile_service
+                .get_profile(&profile_name, Some(SSH_PROFILE_TYPE))?
+                .get("data")
+                .and_then(|v| v.get("scratch_dir"))
+                .cloned()
+        } else {
+            None
+        };
+        if let Some(value) = configured.filter(|v| !v.is_null()) {
+            return normalize_scratch_dir(&value, "scratch_dir");
+        }
+        match feature_flags::SSH_SCRATCH_DIR.raw() {
+            Some((_, value)) if !value.trim().is_empty() => {
+                normalize_scratch_dir(&Value::String(value), "INFRA_SSH_SCRATCH_DIR")
+            }
+            _ => Ok(DEFAULT_SCRATCH_DIR.to_string()),
+        }
+    }
+
+    async fn resolve_jumps(
+        &self,
+        spec: Option<&Value>,
+        args: &Value,
+    ) -> Result<Vec<JumpHop>, ToolError> {
+        let items = match spec {
+            None | Some(Value::Null) => return Ok(Vec::new()),
+            Some(Value::Array(items)) => items.clone(),
+            Some(value @ (Value::String(_) | Value::Object(_))) => vec![value.clone()],
+            Some(_) => {
+                return Err(ToolError::invalid_params(
+                    "jump must be an object, a profile name, or a list of them",
+                ))
+            }
+        };
+        if items.len() > MAX_JUMP_HOPS {
+            return Err(ToolError::invalid_params(format!(
+                "jump supports at most {} hops (got {})",
+                MAX_JUMP_HOPS,
+                items.len()
+            )));
+        }
+        let mut hops = Vec::new();
+        for (index, item) in items.iter().enumerate() {
+            let label = format!("jump[{}]", index);
+            let (value, profile_name) = match item {
+                Value::String(name) => (None, Some(name.as_str())),
+                Value::Object(map) => (
+                    map.get("connection").cloned(),
+                    map.get("profile_name").and_then(|v| v.as_str()),
+                ),
+                _ => (None, None),
+            };
+            let (value, profile_name) = match (value, profile_name) {
+                (_, Some(name)) => {
+                    let name = self
+                        .validation
+                        .ensure_identifier(name, &format!("{}.profile_name", label))?;
+                    let profile = self
+                        .profile_service
+                        .get_profile(&name, Some(SSH_PROFILE_TYPE))
+                        .map_err(|err| prefix_error(err, &label))?;
+                    (profile_connection_value(&profile), Some(name))
+                }
+                (Some(connection), None) => (connection, None),
+                (None, None) => {
+                    return Err(ToolError::invalid_params(format!(
+                        "{} requires profile_name or connection",
+                        label
+                    )))
+                }
+            };
+            if value.get("jump").is_some_and(|v| !v.is_null()) {
+                return Err(ToolError::invalid_params(format!(
+                    "{} has its own jump; nested jumps are not followed",
+                    label
+                ))
+                .with_hint("List every hop in order: jump: [bastion, inner, ...].".to_string()));
+            }
+            let resolved = if let Some(resolver) = &self.secret_ref_resolver {
+                resolver.resolve_deep(&value, args).await?
+            } else {
+                value
+            };
+            // Host key policy/fingerprint come from the hop itself, never from the target args.
+            let connection = self
+                .build_connection_from_value(&resolved, &Value::Object(Default::default()))
+                .map_err(|err| prefix_error(err, &label))?;
+            hops.push(JumpHop {
+                connection,
+                profile_name,
+            });
+        }
+        Ok(hops)
+    }
+
     async fn resolve_profile_name(&self, args: &Value) -> Result<Option<String>, ToolError> {
         if let Some(name) = args.get("profile_name").and_then(|v| v.as_str()) {
             return Ok(Some(
@@ -1971,10 +3556,35 @@ impl SshManager {
             .get("passphrase")
             .and_then(|v| v.as_str())
             .map(|s| s.to_string());
+        let agent_key_fingerprint = normalize_fingerprint_sha256(obj.get("agent_key_fingerprint"));
+
+        // Without explicit credentials the agent is used when one is configured (SSH_AUTH_SOCK)
+        // or an agent key is pinned.
+        let agent = match obj
+            .get("auth")
+            .and_then(|v| v.as_str())
+            .map(|s| s.trim().to_lowercase())
+            .as_deref()
+        {
+            Some("agent") => true,
+            None | Some("") => {
+                private_key.is_none()
+                    && password.is_none()
+                    && obj.get("private_key_path").is_none()
+                    && (agent_key_fingerprint.is_some() || agent_socket_configured())
+            }
+            Some(other) => {
+                return Err(ToolError::invalid_params(format!(
+                    "Unsupported connection.auth: {}",
+                    other
+                ))
+                .with_hint("Use auth: 'agent', or omit auth and set password/private_key."))
+            }
+        };
 
-        if private_key.is_none() && password.is_none() {
-            return Err(ToolError::invalid_params("Provide password or private_key for SSH connection")
-                .with_hint("Set connection.password, or connection.private_key/private_key_path (optionally passphrase)."));
+        if !agent && private_key.is_none() && password.is_none() {
+            return Err(ToolError::invalid_params("Provide password, private_key or auth: 'agent' for SSH connection")
+                .with_hint("Set connection.password, connection.private_key/private_key_path (optionally passphrase), or auth: 'agent' with SSH_AUTH_SOCK pointing at a running ssh-agent."));
         }
 
         let ready_timeout_ms = obj
@@ -2019,10 +3629,17 @@ impl SshManager {
             password,
             private_key,
             passphrase,
+            agent,
+            agent_key_fingerprint,
             ready_timeout_ms,
             keepalive_interval_ms,
             host_key_policy: policy,
             host_key_fingerprint: fingerprint,
+            connect_retry: parse_connect_retry(
+                args.get("connect_retry")
+                    .or_else(|| obj.get("connect_retry")),
+            )?,
+            jumps: Vec::new(),
         })
     }
 
@@ -2140,14 +3757,151 @@ impl SshManager {
             return Err(ToolError::invalid_params("log_path is required")
                 .with_hint("Example: { action: 'job_logs_tail', log_path: '/tmp/app.log' }"));
         }
-        Ok(JobSpec {
-            job_id: job_id.unwrap_or_default(),
-            not_found,
-            profile_name,
-            pid,
-            pid_path,
-            log_path,
-            exit_path,
+        Ok(JobSpec {
+            job_id: job_id.unwrap_or_default(),
+            not_found,
+            profile_name,
+            pid,
+            pid_path,
+            log_path,
+            exit_path,
+        })
+    }
+}
+
+fn read_log_offset(value: Option<&Value>) -> Result<u64, ToolError> {
+    match value {
+        None | Some(Value::Null) => Ok(0),
+        Some(value) => value
+            .as_u64()
+            .or_else(|| value.as_str().and_then(|s| s.trim().parse::<u64>().ok()))
+            .ok_or_else(|| {
+                ToolError::invalid_params("log_offset must be a non-negative integer")
+                    .with_hint("Pass the log_offset returned by the previous follow_job call.")
+            }),
+    }
+}
+
+// Local copy of a followed job log under artifact://runs/jobs/. Bytes are written at their log
+// offset, so a later call continuing from `log_offset` extends the same file; a call starting
+// past its end leaves a hole and marks the copy incomplete.
+struct LogMirror {
+    uri: String,
+    rel: String,
+    path: PathBuf,
+    complete: bool,
+    failed: bool,
+}
+
+impl LogMirror {
+    fn for_job(job_id: &str, profile_name: Option<&str>, log_path: &str) -> Option<Self> {
+        let context_root = resolve_context_root()?;
+        let key = if job_id.trim().is_empty() {
+            let digest = Sha256::digest(format!("{}\n{}", profile_name.unwrap_or(""), log_path));
+            hex::encode(digest)[..16].to_string()
+        } else {
+            job_id.to_string()
+        };
+        let reference =
+            build_run_file_ref(Some("jobs"), &format!("ssh-follow-{}.log", key)).ok()?;
+        let path = resolve_artifact_path(&context_root, &reference.rel).ok()?;
+        Some(Self {
+            uri: reference.uri,
+            rel: reference.rel,
+            path,
+            complete: true,
+            failed: false,
+        })
+    }
+
+    fn write_at(&mut self, offset: u64, bytes: &[u8]) -> std::io::Result<()> {
+        use std::io::{Seek, SeekFrom};
+        use std::os::unix::fs::OpenOptionsExt;
+        ensure_dir_for_file(&self.path)?;
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(false)
+            .mode(0o600)
+            .open(&self.path)?;
+        let len = file.metadata()?.len();
+        if offset <= len {
+            file.set_len(offset)?;
+            file.seek(SeekFrom::Start(offset))?;
+        } else {
+            self.complete = false;
+            file.seek(SeekFrom::End(0))?;
+        }
+        file.write_all(bytes)
+    }
+}
+
+struct LogFollow {
+    from_offset: u64,
+    offset: u64,
+    budget: u64,
+    read: u64,
+    inline: Vec<u8>,
+    inline_truncated: bool,
+    rewound: bool,
+    lines_fallback: bool,
+    mirror: Option<LogMirror>,
+}
+
+impl LogFollow {
+    fn new(from_offset: u64, budget: u64, mirror: Option<LogMirror>) -> Self {
+        Self {
+            from_offset,
+            offset: from_offset,
+            budget,
+            read: 0,
+            inline: Vec::new(),
+            inline_truncated: false,
+            rewound: false,
+            lines_fallback: false,
+            mirror,
+        }
+    }
+
+    fn push(&mut self, bytes: &[u8]) {
+        if let Some(mirror) = self.mirror.as_mut().filter(|mirror| !mirror.failed) {
+            if mirror.write_at(self.offset, bytes).is_err() {
+                mirror.failed = true;
+            }
+        }
+        self.offset += bytes.len() as u64;
+        self.read += bytes.len() as u64;
+        self.inline.extend_from_slice(bytes);
+        let max_inline = resolve_exec_max_inline_bytes();
+        if self.inline.len() > max_inline {
+            self.inline.drain(..self.inline.len() - max_inline);
+            self.inline_truncated = true;
+        }
+    }
+
+    fn view(&self, spec: &JobSpec, log_bytes: Option<u64>) -> Value {
+        let log_ref = match self.mirror.as_ref() {
+            Some(mirror) if !mirror.failed && mirror.path.exists() => serde_json::json!({
+                "uri": mirror.uri,
+                "rel": mirror.rel,
+                "complete": mirror.complete,
+            }),
+            _ => Value::Null,
+        };
+        serde_json::json!({
+            "success": true,
+            "job_id": spec.job_id,
+            "log_path": spec.log_path,
+            "mode": "bytes",
+            "from_offset": self.from_offset,
+            "log_offset": self.offset,
+            "bytes": self.read,
+            "pending_bytes": log_bytes.map(|total| total.saturating_sub(self.offset)),
+            "rewound": self.rewound,
+            "gaps_possible": false,
+            "text": String::from_utf8_lossy(&self.inline),
+            "text_truncated": self.inline_truncated,
+            "log_ref": log_ref,
         })
     }
 }
@@ -2182,147 +3936,29 @@ struct ExecResult {
     timed_out: bool,
     hard_timed_out: bool,
     duration_ms: u128,
-}
-
-struct CaptureState {
-    total: u64,
-    captured: usize,
-    truncated: bool,
-    inline_truncated: bool,
-    buffer: Vec<u8>,
-    inline: Vec<u8>,
-    writer: Option<ArtifactStream>,
-    writer_limit: usize,
-    writer_total: u64,
-    writer_truncated: bool,
-    max_capture: usize,
-    max_inline: usize,
-    filename: String,
-    trace_id: Option<String>,
-    span_id: Option<String>,
-}
-
-struct ArtifactStream {
-    rel: String,
-    uri: String,
-    path: PathBuf,
-    tmp_path: PathBuf,
-    file: fs::File,
-    bytes: u64,
-}
-
-impl ArtifactStream {
-    fn new(
-        filename: &str,
-        trace_id: Option<&str>,
-        span_id: Option<&str>,
-    ) -> Result<Self, ToolError> {
-        let context_root = resolve_context_root()
-            .ok_or_else(|| ToolError::internal("Context root not available"))?;
-        let reference = build_tool_call_file_ref(trace_id, span_id, filename)?;
-        let path = crate::utils::artifacts::resolve_artifact_path(&context_root, &reference.rel)?;
-        ensure_dir_for_file(&path).map_err(|err| {
-            ToolError::internal(format!("Failed to create artifact dir: {}", err))
-        })?;
-        let tmp_path = temp_sibling_path(&path);
-        let file = fs::OpenOptions::new()
-            .create(true)
-            .write(true)
-            .truncate(true)
-            .open(&tmp_path)
-            .map_err(|err| ToolError::internal(format!("Failed to create artifact: {}", err)))?;
-        Ok(Self {
-            rel: reference.rel,
-            uri: reference.uri,
-            path,
-            tmp_path,
-            file,
-            bytes: 0,
-        })
-    }
-
-    fn write(&mut self, chunk: &[u8]) -> Result<(), ToolError> {
-        self.file
-            .write_all(chunk)
-            .map_err(|err| ToolError::internal(format!("Failed to write artifact: {}", err)))?;
-        self.bytes += chunk.len() as u64;
-        Ok(())
-    }
-
-    fn finalize(mut self) -> Result<Value, ToolError> {
-        self.file
-            .flush()
-            .map_err(|err| ToolError::internal(format!("Failed to flush artifact: {}", err)))?;
-        drop(self.file);
-        fs::rename(&self.tmp_path, &self.path)
-            .map_err(|err| ToolError::internal(format!("Failed to finalize artifact: {}", err)))?;
-        Ok(serde_json::json!({
-            "uri": self.uri,
-            "rel": self.rel,
-            "bytes": self.bytes,
-        }))
-    }
-
-    fn abort(self) {
-        let _ = fs::remove_file(&self.tmp_path);
-    }
+    connect_attempts: u32,
+    // How `env` reached the command; None without env.
+    env: Option<Value>,
 }
 
 fn resolve_tool_call_budget_ms() -> u64 {
-    std::env::var("INFRA_TOOL_CALL_TIMEOUT_MS")
-        .ok()
-        .and_then(|v| v.parse::<u64>().ok())
-        .unwrap_or(network_constants::TIMEOUT_TOOL_CALL_MS)
+    feature_flags::TOOL_CALL_TIMEOUT_MS.number()
 }
 
 fn resolve_exec_default_timeout_ms() -> u64 {
-    std::env::var("INFRA_SSH_EXEC_DEFAULT_TIMEOUT_MS")
-        .ok()
-        .and_then(|v| v.parse::<u64>().ok())
-        .unwrap_or(network_constants::TIMEOUT_SSH_EXEC_DEFAULT_MS)
+    feature_flags::SSH_EXEC_DEFAULT_TIMEOUT_MS.number()
 }
 
 fn resolve_detached_start_timeout_ms() -> u64 {
-    std::env::var("INFRA_SSH_DETACHED_START_TIMEOUT_MS")
-        .ok()
-        .and_then(|v| v.parse::<u64>().ok())
-        .unwrap_or(network_constants::TIMEOUT_SSH_DETACHED_START_MS)
+    feature_flags::SSH_DETACHED_START_TIMEOUT_MS.number()
 }
 
 fn resolve_exec_max_capture_bytes() -> usize {
-    std::env::var("INFRA_SSH_MAX_CAPTURE_BYTES")
-        .or_else(|_| std::env::var("INFRA_MAX_CAPTURE_BYTES"))
-        .ok()
-        .and_then(|v| v.parse::<usize>().ok())
-        .unwrap_or(DEFAULT_MAX_CAPTURE_BYTES)
+    feature_flags::SSH_MAX_CAPTURE_BYTES.number() as usize
 }
 
 fn resolve_exec_max_inline_bytes() -> usize {
-    std::env::var("INFRA_SSH_MAX_INLINE_BYTES")
-        .or_else(|_| std::env::var("INFRA_MAX_INLINE_BYTES"))
-        .ok()
-        .and_then(|v| v.parse::<usize>().ok())
-        .unwrap_or(DEFAULT_MAX_INLINE_BYTES)
-}
-
-fn resolve_stream_to_artifact_mode() -> Option<String> {
-    let raw = std::env::var("INFRA_SSH_STREAM_TO_ARTIFACT")
-        .or_else(|_| std::env::var("INFRA_STREAM_TO_ARTIFACT"))
-        .ok()?;
-    let normalized = raw.trim().to_lowercase();
-    if normalized.is_empty() {
-        return None;
-    }
-    if normalized == "full" {
-        return Some("full".to_string());
-    }
-    if normalized == "capped" {
-        return Some("capped".to_string());
-    }
-    if normalized == "1" || normalized == "true" || normalized == "yes" {
-        return Some("capped".to_string());
-    }
-    None
+    feature_flags::SSH_MAX_INLINE_BYTES.number() as usize
 }
 
 fn ssh_stability_defaults() -> StabilityDefaults {
@@ -2359,6 +3995,19 @@ fn stability_debug_requested(args: &Value) -> bool {
         .unwrap_or(false)
 }
 
+fn normalize_scratch_dir(value: &Value, field: &str) -> Result<String, ToolError> {
+    let raw = value.as_str().unwrap_or("").trim();
+    let dir = raw.trim_end_matches('/');
+    if !raw.starts_with('/') || dir.is_empty() {
+        return Err(
+            ToolError::invalid_params(format!("{} must be an absolute directory", field))
+                .with_hint("Example: scratch_dir: '/var/tmp/infra'".to_string()),
+        );
+    }
+    ensure_shell_arg(dir, field)?;
+    Ok(dir.to_string())
+}
+
 fn read_positive_int(value: Option<&Value>) -> Option<u64> {
     let value = value?;
     if let Some(n) = value.as_i64() {
@@ -2415,17 +4064,6 @@ fn normalize_fingerprint_sha256(value: Option<&Value>) -> Option<String> {
     Some(format!("SHA256:{}", cleaned))
 }
 
-fn fingerprint_host_key_sha256(session: &Session) -> Option<String> {
-    let hash = session.host_key_hash(ssh2::HashType::Sha256)?;
-    let encoded = base64::engine::general_purpose::STANDARD_NO_PAD.encode(hash);
-    Some(format!("SHA256:{}", encoded))
-}
-
-fn escape_shell_value(value: &str) -> String {
-    let escaped = value.replace('"', "\\\"");
-    format!("'{}'", escaped.replace('\'', "'\\\''"))
-}
-
 fn resolve_public_key_line(args: &Value) -> Result<String, ToolError> {
     if let Some(key) = args.get("public_key").and_then(|v| v.as_str()) {
         return normalize_public_key_line(key);
@@ -2498,45 +4136,381 @@ fn build_command(
     security: &Security,
     command: &str,
     cwd: Option<&str>,
+    policy: Option<&(String, ExecPolicy)>,
 ) -> Result<String, ToolError> {
     let trimmed = security.clean_command(command)?;
+    if let Some((scope, policy)) = policy {
+        // The command itself is never echoed: it may carry inline secrets.
+        policy.check(&trimmed).map_err(|violation| {
+            let rule = match violation.pattern.as_deref() {
+                Some(pattern) => format!("{} `{}`", violation.rule, pattern),
+                None => violation.rule.clone(),
+            };
+            ToolError::denied(format!(
+                "exec_policy of {} denies this command ({}: {})",
+                scope, rule, violation.reason
+            ))
+            .with_hint("Ask an operator to adjust exec_policy for this profile.".to_string())
+            .with_details(serde_json::json!({
+                "exec_policy": scope,
+                "rule": violation.rule,
+                "pattern": violation.pattern,
+                "default": policy.default_label(),
+            }))
+        })?;
+    }
     if let Some(cwd) = cwd {
-        return Ok(format!("cd {} && {}", escape_shell_value(cwd), trimmed));
+        ensure_shell_arg(cwd, "cwd")?;
+        return Ok(format!("cd {} && {}", shell_quote(cwd), trimmed));
     }
     Ok(trimmed)
 }
 
-fn collect_secret_values(env: &Option<Value>) -> Option<Vec<String>> {
-    let env = env.as_ref()?;
-    let obj = env.as_object()?;
-    let mut out = Vec::new();
-    for raw in obj.values() {
-        if let Some(text) = raw.as_str() {
-            let trimmed = text.trim();
-            if trimmed.len() >= 6 {
-                out.push(trimmed.to_string());
+fn profile_connection_value(profile: &Value) -> Value {
+    let mut merged = profile
+        .get("data")
+        .cloned()
+        .unwrap_or(Value::Object(Default::default()));
+    if let Some(secrets) = profile.get("secrets").and_then(|v| v.as_object()) {
+        if let Value::Object(map) = &mut merged {
+            for (key, value) in secrets {
+                map.insert(key.clone(), value.clone());
             }
         }
-        if out.len() >= 32 {
-            break;
-        }
     }
-    if out.is_empty() {
-        None
+    merged
+}
+
+fn jump_has_inline_secrets(spec: Option<&Value>) -> bool {
+    let items = match spec {
+        Some(Value::Array(items)) => items.iter().collect::<Vec<_>>(),
+        Some(value) => vec![value],
+        None => Vec::new(),
+    };
+    items.iter().any(|item| {
+        item.get("connection").is_some_and(|connection| {
+            ["password", "private_key", "passphrase"]
+                .iter()
+                .any(|key| connection.get(*key).is_some_and(|v| !v.is_null()))
+        })
+    })
+}
+
+fn prefix_error(mut err: ToolError, label: &str) -> ToolError {
+    err.message = format!("{}: {}", label, err.message);
+    if err.details.is_none() {
+        err.details = Some(serde_json::json!({ "ssh_hop": label }));
+    }
+    err
+}
+
+// What a stored profile authenticates with; no password or key means the agent.
+fn stored_auth_method(connection: &Value) -> &'static str {
+    let present = |key: &str| {
+        connection
+            .get(key)
+            .and_then(|v| v.as_str())
+            .is_some_and(|s| !s.trim().is_empty())
+    };
+    if connection.get("auth").and_then(|v| v.as_str()) == Some("agent") {
+        "agent"
+    } else if present("private_key") || present("private_key_path") {
+        "private_key"
+    } else if present("password") {
+        "password"
+    } else {
+        "agent"
+    }
+}
+
+fn auth_method(connection: &SshConnection) -> &'static str {
+    if connection.agent {
+        "agent"
+    } else if connection.private_key.is_some() {
+        "private_key"
     } else {
-        Some(out)
+        "password"
+    }
+}
+
+fn hop_label(connection: &SshConnection) -> String {
+    format!(
+        "{}@{}:{}",
+        connection.username, connection.host, connection.port
+    )
+}
+
+fn describe_chain(connection: &SshConnection) -> Vec<Value> {
+    let policy = |connection: &SshConnection| match connection.host_key_policy {
+        HostKeyPolicy::Accept => "accept",
+        HostKeyPolicy::Tofu => "tofu",
+        HostKeyPolicy::Pin => "pin",
+    };
+    let mut chain: Vec<Value> = connection
+        .jumps
+        .iter()
+        .enumerate()
+        .map(|(index, hop)| {
+            serde_json::json!({
+                "role": "jump",
+                "index": index,
+                "endpoint": hop_label(&hop.connection),
+                "profile_name": hop.profile_name,
+                "host_key_policy": policy(&hop.connection),
+            })
+        })
+        .collect();
+    chain.push(serde_json::json!({
+        "role": "target",
+        "endpoint": hop_label(connection),
+        "host_key_policy": policy(connection),
+    }));
+    chain
+}
+
+// `connect_retry: {attempts, delay_ms}` on the call or connection, INFRA_SSH_CONNECT_ATTEMPTS /
+// INFRA_SSH_CONNECT_RETRY_DELAY_MS otherwise.
+fn parse_connect_retry(spec: Option<&Value>) -> Result<ConnectRetry, ToolError> {
+    let mut retry = ConnectRetry {
+        attempts: feature_flags::SSH_CONNECT_ATTEMPTS
+            .positive_number()
+            .clamp(1, 10) as u32,
+        delay_ms: feature_flags::SSH_CONNECT_RETRY_DELAY_MS.number(),
+    };
+    let spec = match spec {
+        None | Some(Value::Null) => return Ok(retry),
+        Some(Value::Object(spec)) => spec,
+        Some(_) => {
+            return Err(ToolError::invalid_params(
+                "connect_retry must be an object { attempts, delay_ms }",
+            ))
+        }
+    };
+    if let Some(value) = spec.get("attempts").filter(|v| !v.is_null()) {
+        retry.attempts = value
+            .as_u64()
+            .filter(|n| (1..=10).contains(n))
+            .ok_or_else(|| {
+                ToolError::invalid_params("connect_retry.attempts must be an integer 1..10")
+            })? as u32;
+    }
+    if let Some(value) = spec.get("delay_ms").filter(|v| !v.is_null()) {
+        retry.delay_ms = value.as_u64().filter(|n| *n <= 60_000).ok_or_else(|| {
+            ToolError::invalid_params("connect_retry.delay_ms must be an integer 0..60000")
+        })?;
+    }
+    Ok(retry)
+}
+
+// Returns the session with the number of attempts it took. Only transient failures (reset,
+// refused, timeouts, handshake) are retried; auth rejections and host key mismatches fail at
+// once. A persistent failure keeps its message and reports `connect_attempts` in details.
+fn connect_session(
+    connection: &SshConnection,
+    profile_service: &ProfileService,
+) -> Result<(Established, u32), ToolError> {
+    let retry = connection.connect_retry;
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        match connect_session_once(connection, profile_service) {
+            Ok(established) => return Ok((established, attempt)),
+            Err(err)
+                if attempt < retry.attempts
+                    && classify_tool_error(&err) == StabilityClassification::Transient =>
+            {
+                std::thread::sleep(Duration::from_millis(retry.delay_ms));
+            }
+            Err(mut err) => {
+                let mut details = match err.details.take() {
+                    Some(Value::Object(map)) => map,
+                    Some(other) => {
+                        let mut map = serde_json::Map::new();
+                        map.insert("cause".to_string(), other);
+                        map
+                    }
+                    None => serde_json::Map::new(),
+                };
+                details.insert("connect_attempts".to_string(), Value::from(attempt));
+                err.details = Some(Value::Object(details));
+                return Err(err);
+            }
+        }
+    }
+}
+
+fn connect_session_once(
+    connection: &SshConnection,
+    profile_service: &ProfileService,
+) -> Result<Established, ToolError> {
+    if connection.jumps.is_empty() {
+        let tcp = open_tcp_stream(connection)?;
+        return establish_session(tcp, connection);
+    }
+    let total = connection.jumps.len();
+    let first = &connection.jumps[0].connection;
+    let mut tcp = open_tcp_stream(first).map_err(|err| {
+        prefix_error(
+            err,
+            &format!("SSH jump hop 1/{} ({})", total, hop_label(first)),
+        )
+    })?;
+    for (index, hop) in connection.jumps.iter().enumerate() {
+        let label = format!(
+            "SSH jump hop {}/{} ({})",
+            index + 1,
+            total,
+            hop_label(&hop.connection)
+        );
+        let hop_session =
+            establish_session(tcp, &hop.connection).map_err(|err| prefix_error(err, &label))?;
+        let session = hop_session.session;
+        if let Some(profile) = hop.profile_name.as_deref() {
+            maybe_persist_tofu(
+                profile_service,
+                profile,
+                &hop.connection,
+                hop_session.host_key,
+            )?;
+        }
+        let next = connection
+            .jumps
+            .get(index + 1)
+            .map(|next| &next.connection)
+            .unwrap_or(connection);
+        tcp = forward_through_jump(session, &hop.connection, next).map_err(|err| {
+            prefix_error(
+                err,
+                &format!("{} forwarding to {}:{}", label, next.host, next.port),
+            )
+        })?;
     }
+    establish_session(tcp, connection).map_err(|err| {
+        prefix_error(
+            err,
+            &format!(
+                "SSH target ({}) via {} jump hop(s)",
+                hop_label(connection),
+                total
+            ),
+        )
+    })
 }
 
-fn connect_session(connection: &SshConnection) -> Result<(Session, Option<String>), ToolError> {
+fn open_tcp_stream(connection: &SshConnection) -> Result<TcpStream, ToolError> {
     let addr = format!("{}:{}", connection.host, connection.port);
-    let tcp = TcpStream::connect_timeout(
+    TcpStream::connect_timeout(
         &addr
             .parse()
             .map_err(|_| ToolError::invalid_params("Invalid SSH host/port"))?,
         Duration::from_millis(connection.ready_timeout_ms),
     )
-    .map_err(|err| ToolError::internal(format!("Failed to connect SSH: {}", err)))?;
+    .map_err(|err| ToolError::internal(format!("Failed to connect SSH: {}", err)))
+}
+
+// ssh2 needs a real socket for the next handshake, so the direct-tcpip channel is bridged
+// to a loopback TCP pair; the bridge thread owns the hop session and ends with either side.
+fn forward_through_jump(
+    session: Session,
+    hop: &SshConnection,
+    next: &SshConnection,
+) -> Result<TcpStream, ToolError> {
+    let channel = session
+        .channel_direct_tcpip(&next.host, next.port, None)
+        .map_err(map_ssh_error)?;
+    let bridge_error =
+        |err: std::io::Error| ToolError::internal(format!("Failed to bridge SSH jump: {}", err));
+    let listener = TcpListener::bind(("127.0.0.1", 0)).map_err(bridge_error)?;
+    let addr = listener.local_addr().map_err(bridge_error)?;
+    let client = TcpStream::connect_timeout(&addr, Duration::from_millis(next.ready_timeout_ms))
+        .map_err(bridge_error)?;
+    let (local, peer) = listener.accept().map_err(bridge_error)?;
+    if client.local_addr().ok() != Some(peer) {
+        return Err(ToolError::denied(
+            "Unexpected peer connected to the SSH jump bridge",
+        ));
+    }
+    let keepalive = Duration::from_millis(hop.keepalive_interval_ms.max(1000));
+    std::thread::spawn(move || pump_jump_channel(session, channel, local, keepalive));
+    Ok(client)
+}
+
+fn pump_jump_channel(
+    session: Session,
+    mut channel: ssh2::Channel,
+    mut local: TcpStream,
+    keepalive: Duration,
+) {
+    session.set_blocking(false);
+    if local.set_nonblocking(true).is_err() {
+        return;
+    }
+    let mut upstream = vec![0u8; 32 * 1024];
+    let mut downstream = vec![0u8; 32 * 1024];
+    let mut last_keepalive = Instant::now();
+    loop {
+        let mut progressed = false;
+        match local.read(&mut upstream) {
+            Ok(0) => break,
+            Ok(n) => {
+                if write_all_nonblocking(&mut channel, &upstream[..n]).is_err() {
+                    break;
+                }
+                progressed = true;
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {}
+            Err(_) => break,
+        }
+        match channel.read(&mut downstream) {
+            Ok(0) => {
+                if channel.eof() {
+                    break;
+                }
+            }
+            Ok(n) => {
+                if write_all_nonblocking(&mut local, &downstream[..n]).is_err() {
+                    break;
+                }
+                progressed = true;
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {}
+            Err(_) => break,
+        }
+        if last_keepalive.elapsed() >= keepalive {
+            let _ = session.keepalive_send();
+            last_keepalive = Instant::now();
+        }
+        if !progressed {
+            std::thread::sleep(Duration::from_millis(2));
+        }
+    }
+    let _ = local.shutdown(Shutdown::Both);
+    let _ = channel.close();
+}
+
+fn write_all_nonblocking<W: Write>(writer: &mut W, mut buf: &[u8]) -> std::io::Result<()> {
+    while !buf.is_empty() {
+        match writer.write(buf) {
+            Ok(0) => return Err(std::io::ErrorKind::WriteZero.into()),
+            Ok(n) => buf = &buf[n..],
+            Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(())
+}
+
+// An authenticated session with the host key it presented and, for agent auth, the identity the
+// server accepted.
+struct Established {
+    session: Session,
+    host_key: Option<String>,
+    agent_identity: Option<Value>,
+}
+
+fn establish_session(tcp: TcpStream, connection: &SshConnection) -> Result<Established, ToolError> {
     tcp.set_read_timeout(Some(Duration::from_millis(connection.ready_timeout_ms)))
         .ok();
     tcp.set_write_timeout(Some(Duration::from_millis(connection.ready_timeout_ms)))
@@ -2548,16 +4522,31 @@ fn connect_session(connection: &SshConnection) -> Result<(Session, Option<String
     // Hard-stop for blocking handshake/auth steps. Without this, slow/broken handshakes can
     // exceed the shared tool-call budget and stall the foreground command.
     session.set_timeout(connection.ready_timeout_ms as u32);
-    session.handshake().map_err(map_ssh_error)?;
+    // Named as a handshake failure so a peer dropping mid-handshake classifies as transient.
+    session
+        .handshake()
+        .map_err(|err| match map_ssh_error(err) {
+            mapped if mapped.kind == ToolErrorKind::Internal => ToolError::internal(
+                mapped
+                    .message
+                    .replacen("SSH error:", "SSH handshake failed:", 1),
+            ),
+            mapped => mapped,
+        })?;
 
     let observed = fingerprint_host_key_sha256(&session);
     if let Some(expected) = connection.host_key_fingerprint.as_ref() {
         if observed.as_ref() != Some(expected) {
-            return Err(ToolError::denied(format!(
-                "SSH host key mismatch (expected {}, got {})",
-                expected,
-                observed.clone().unwrap_or_else(|| "unknown".to_string())
-            )));
+            let observed = observed.clone().unwrap_or_else(|| "unknown".to_string());
+            return Err(ToolError::new(
+                ToolErrorKind::Denied,
+                "HOST_KEY_MISMATCH",
+                format!(
+                    "SSH host key mismatch (expected {}, got {})",
+                    expected, observed
+                ),
+            )
+            .with_details(serde_json::json!({"expected": expected, "observed": observed})));
         }
     } else if connection.host_key_policy == HostKeyPolicy::Pin {
         return Err(ToolError::invalid_params(
@@ -2565,7 +4554,10 @@ fn connect_session(connection: &SshConnection) -> Result<(Session, Option<String
         ));
     }
 
-    if let Some(key) = connection.private_key.as_ref() {
+    let mut agent_identity = None;
+    if connection.agent {
+        agent_identity = Some(userauth_agent(&session, connection)?);
+    } else if let Some(key) = connection.private_key.as_ref() {
         session
             .userauth_pubkey_memory(
                 &connection.username,
@@ -2586,7 +4578,81 @@ fn connect_session(connection: &SshConnection) -> Result<(Session, Option<String
     let interval = std::cmp::max(1, (connection.keepalive_interval_ms / 1000) as u32);
     session.set_keepalive(true, interval);
 
-    Ok((session, observed))
+    Ok(Established {
+        session,
+        host_key: observed,
+        agent_identity,
+    })
+}
+
+fn agent_socket_configured() -> bool {
+    std::env::var_os("SSH_AUTH_SOCK").is_some_and(|sock| !sock.is_empty())
+}
+
+fn agent_key_fingerprint(identity: &ssh2::PublicKey) -> String {
+    let hash = Sha256::digest(identity.blob());
+    format!(
+        "SHA256:{}",
+        base64::engine::general_purpose::STANDARD_NO_PAD.encode(hash)
+    )
+}
+
+// Offers agent identities one at a time (only the pinned one when agent_key_fingerprint is
+// set) and returns the one the server accepted. An agent that cannot be reached is NotFound;
+// an agent whose keys are all refused is Denied.
+fn userauth_agent(session: &Session, connection: &SshConnection) -> Result<Value, ToolError> {
+    let unreachable = |err: ssh2::Error| {
+        ToolError::not_found(format!("SSH agent unreachable: {}", err.message()))
+            .with_hint("Start ssh-agent (or the token's agent) and export SSH_AUTH_SOCK for the infra process.")
+            .with_details(serde_json::json!({"auth": "agent", "reason": "agent_unreachable"}))
+    };
+    let mut agent = session.agent().map_err(unreachable)?;
+    agent.connect().map_err(unreachable)?;
+    agent.list_identities().map_err(unreachable)?;
+    let identities = agent.identities().map_err(unreachable)?;
+
+    let offered: Vec<&ssh2::PublicKey> = identities
+        .iter()
+        .filter(|identity| {
+            connection
+                .agent_key_fingerprint
+                .as_ref()
+                .is_none_or(|pinned| *pinned == agent_key_fingerprint(identity))
+        })
+        .collect();
+    let mut tried = Vec::new();
+    for identity in offered {
+        let fingerprint = agent_key_fingerprint(identity);
+        if agent.userauth(&connection.username, identity).is_ok() && session.authenticated() {
+            let _ = agent.disconnect();
+            return Ok(serde_json::json!({
+                "comment": identity.comment(),
+                "fingerprint": fingerprint,
+            }));
+        }
+        tried.push(fingerprint);
+    }
+    let _ = agent.disconnect();
+
+    let message = match (&connection.agent_key_fingerprint, identities.is_empty()) {
+        (_, true) => "SSH agent has no identities".to_string(),
+        (Some(pinned), _) if tried.is_empty() => {
+            format!("SSH agent holds no identity matching {}", pinned)
+        }
+        _ => format!(
+            "SSH agent has no acceptable identities for {}",
+            hop_label(connection)
+        ),
+    };
+    Err(ToolError::denied(message)
+        .with_hint("Add the right key to the agent (ssh-add) or pin it with agent_key_fingerprint.")
+        .with_details(serde_json::json!({
+            "auth": "agent",
+            "reason": "no_acceptable_identity",
+            "agent_identities": identities.len(),
+            "tried": tried,
+            "agent_key_fingerprint": connection.agent_key_fingerprint,
+        })))
 }
 
 fn maybe_persist_tofu(
@@ -2615,16 +4681,20 @@ fn maybe_persist_tofu(
     Ok(())
 }
 
-fn test_connection(connection: &SshConnection) -> Result<(), ToolError> {
-    let (_session, _observed) = connect_session(connection)?;
-    Ok(())
+// The connect attempts it took and, for agent auth, the accepted identity.
+fn test_connection(
+    connection: &SshConnection,
+    profile_service: &ProfileService,
+) -> Result<(u32, Option<Value>), ToolError> {
+    let (established, connect_attempts) = connect_session(connection, profile_service)?;
+    Ok((connect_attempts, established.agent_identity))
 }
 
 fn exec_blocking(
     resolved: &ResolvedConnection,
     profile_service: Arc<ProfileService>,
     command: &str,
-    env: Option<Value>,
+    env: ExecEnv,
     pty: bool,
     stdin: Option<StdinSource>,
     stdin_eof: bool,
@@ -2639,7 +4709,12 @@ fn exec_blocking(
         connection.ready_timeout_ms = connection.ready_timeout_ms.min(timeout);
     }
 
-    let (session, observed) = connect_session(&connection)?;
+    let (established, connect_attempts) = connect_session(&connection, &profile_service)?;
+    let Established {
+        session,
+        host_key: observed,
+        ..
+    } = established;
     if let Some(profile) = resolved.profile_name.as_deref() {
         let _ = maybe_persist_tofu(&profile_service, profile, &connection, observed.clone());
     }
@@ -2648,16 +4723,28 @@ fn exec_blocking(
     if pty {
         let _ = channel.request_pty("xterm", None, None);
     }
-    if let Some(env_map) = env.as_ref().and_then(|v| v.as_object()) {
-        for (key, value) in env_map {
-            if let Some(text) = value.as_str() {
-                let _ = channel.setenv(key, text);
-            } else {
-                let _ = channel.setenv(key, &value.to_string());
+    // sshd answers every setenv request; one outside its AcceptEnv list is refused, and in
+    // auto mode that variable is passed in the command line instead.
+    let mut inlined = Vec::new();
+    for (key, value) in &env.vars {
+        if env.mode == EnvMode::Inline {
+            inlined.push(key.clone());
+            continue;
+        }
+        if channel.setenv(key, value).is_err() {
+            if env.mode == EnvMode::Setenv {
+                return Err(ToolError::denied(format!(
+                    "sshd rejected setenv for {} (not in its AcceptEnv list)",
+                    key
+                ))
+                .with_hint("Use env_mode=auto or env_mode=inline to pass it in the command."));
             }
+            inlined.push(key.clone());
         }
     }
-    channel.exec(command).map_err(map_ssh_error)?;
+    channel
+        .exec(&env.inline_command(command, &inlined))
+        .map_err(map_ssh_error)?;
     session.set_blocking(false);
 
     let mut stdin_bytes: Option<Vec<u8>> = None;
@@ -2843,7 +4930,7 @@ fn exec_blocking(
     let exit_code = i64::from(channel.exit_status().unwrap_or(-1));
     let signal = channel.exit_signal().ok().and_then(|sig| sig.exit_signal);
 
-    let extra_secrets = collect_secret_values(&env);
+    let extra_secrets = env.secret_values();
     let stdout = redact_text(
         &stdout_state.inline_string(),
         usize::MAX,
@@ -2876,150 +4963,42 @@ fn exec_blocking(
         timed_out,
         hard_timed_out,
         duration_ms: started.elapsed().as_millis(),
+        connect_attempts,
+        env: (!env.is_empty()).then(|| env.report(&inlined)),
     })
 }
 
-impl CaptureState {
-    fn new(
-        max_capture: usize,
-        max_inline: usize,
-        stream_mode: Option<&str>,
-        filename: &str,
-        trace_id: Option<&str>,
-        span_id: Option<&str>,
-    ) -> Result<Self, ToolError> {
-        let writer = if stream_mode.is_some() {
-            ArtifactStream::new(filename, trace_id, span_id).ok()
-        } else {
-            None
-        };
-        let writer_limit = if stream_mode == Some("full") {
-            usize::MAX
-        } else {
-            max_capture
-        };
-        Ok(Self {
-            total: 0,
-            captured: 0,
-            truncated: false,
-            inline_truncated: false,
-            buffer: Vec::new(),
-            inline: Vec::new(),
-            writer,
-            writer_limit,
-            writer_total: 0,
-            writer_truncated: false,
-            max_capture,
-            max_inline,
-            filename: filename.to_string(),
-            trace_id: trace_id.map(|s| s.to_string()),
-            span_id: span_id.map(|s| s.to_string()),
-        })
-    }
-
-    fn capture(&mut self, chunk: &[u8]) {
-        self.total += chunk.len() as u64;
-        if let Some(writer) = self.writer.as_mut() {
-            if self.writer_total < self.writer_limit as u64 {
-                let remaining =
-                    (self.writer_limit as u64).saturating_sub(self.writer_total) as usize;
-                let slice = if chunk.len() > remaining {
-                    &chunk[..remaining]
-                } else {
-                    chunk
-                };
-                let _ = writer.write(slice);
-                self.writer_total += slice.len() as u64;
-                if slice.len() < chunk.len() {
-                    self.writer_truncated = true;
-                }
-            } else {
-                self.writer_truncated = true;
-            }
-        }
-        if self.captured < self.max_capture {
-            let remaining = self.max_capture - self.captured;
-            let slice = if chunk.len() > remaining {
-                &chunk[..remaining]
-            } else {
-                chunk
-            };
-            self.buffer.extend_from_slice(slice);
-            self.captured += slice.len();
-            if slice.len() < chunk.len() {
-                self.truncated = true;
-            }
-        } else {
-            self.truncated = true;
+// Progress is only written for transfers at or above INFRA_SSH_PROGRESS_MIN_BYTES (or of
+// unknown size), so small files do not churn the job store.
+fn copy_transfer<R: Read, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+    max_rate_bps: Option<u64>,
+    total_bytes: Option<u64>,
+    job: Option<TransferJob>,
+) -> Result<TransferProgress, ToolError> {
+    let options = TransferOptions {
+        max_rate_bps,
+        total_bytes,
+        abort: job.as_ref().map(|job| job.abort_flag()),
+    };
+    let reporter = job.filter(|job| {
+        total_bytes
+            .map(|total| total >= job.min_progress_bytes)
+            .unwrap_or(true)
+    });
+    copy_with_progress(reader, writer, &options, &mut |progress| {
+        if let Some(job) = reporter.as_ref() {
+            job.report(progress);
         }
-        if self.inline.len() < self.max_inline {
-            let remaining = self.max_inline - self.inline.len();
-            let slice = if chunk.len() > remaining {
-                &chunk[..remaining]
-            } else {
-                chunk
-            };
-            self.inline.extend_from_slice(slice);
-            if slice.len() < chunk.len() {
-                self.inline_truncated = true;
-            }
+    })
+    .map_err(|err| {
+        if err.kind() == std::io::ErrorKind::Interrupted {
+            ToolError::internal("Transfer canceled")
         } else {
-            self.inline_truncated = true;
-        }
-    }
-
-    fn inline_string(&self) -> String {
-        String::from_utf8_lossy(&self.inline).to_string()
-    }
-
-    fn finalize_artifact(&mut self, extra_secrets: Option<&[String]>) -> Result<Value, ToolError> {
-        if let Some(writer) = self.writer.take() {
-            if self.writer_total == 0 {
-                writer.abort();
-                return Ok(Value::Null);
-            }
-            let mut payload = writer.finalize()?;
-            if let Value::Object(map) = &mut payload {
-                map.insert(
-                    "captured_bytes".to_string(),
-                    Value::Number(self.writer_total.into()),
-                );
-                map.insert("total_bytes".to_string(), Value::Number(self.total.into()));
-                map.insert("truncated".to_string(), Value::Bool(self.writer_truncated));
-            }
-            return Ok(payload);
-        }
-
-        if self.buffer.is_empty() {
-            return Ok(Value::Null);
-        }
-        let context_root = resolve_context_root();
-        if context_root.is_none() {
-            return Ok(Value::Null);
-        }
-        if !(self.truncated || self.inline_truncated) {
-            return Ok(Value::Null);
-        }
-        if let Ok(reference) = build_tool_call_file_ref(
-            self.trace_id.as_deref(),
-            self.span_id.as_deref(),
-            &self.filename,
-        ) {
-            let redacted = redact_text(
-                &String::from_utf8_lossy(&self.buffer),
-                usize::MAX,
-                extra_secrets,
-            );
-            let written =
-                write_text_artifact(context_root.as_ref().unwrap(), &reference, &redacted)?;
-            return Ok(serde_json::json!({
-                "uri": written.uri,
-                "rel": written.rel,
-                "bytes": written.bytes,
-            }));
+            ToolError::internal(err.to_string())
         }
-        Ok(Value::Null)
-    }
+    })
 }
 
 fn map_ssh_error(err: ssh2::Error) -> ToolError {
@@ -3049,7 +5028,7 @@ pub(crate) fn ensure_remote_dir(sftp: &ssh2::Sftp, remote_path: &str) -> Result<
     Ok(())
 }
 
-fn compute_local_sha256_hex(path: &Path) -> Result<String, ToolError> {
+pub(crate) fn compute_local_sha256_hex(path: &Path) -> Result<String, ToolError> {
     let mut file = fs::File::open(path).map_err(|err| {
         ToolError::invalid_params(format!("local_path must be readable: {}", err))
     })?;
@@ -3067,18 +5046,17 @@ fn compute_local_sha256_hex(path: &Path) -> Result<String, ToolError> {
     Ok(format!("{:x}", hasher.finalize()))
 }
 
-fn build_remote_sha256_command(remote_path: &str) -> String {
-    let quoted = escape_shell_value(remote_path);
-    [
-        "set -u".to_string(),
-        format!("PATH_ARG={}", quoted),
-        "if command -v sha256sum >/dev/null 2>&1; then sha256sum -- \"$PATH_ARG\" 2>/dev/null | awk '{print $1}'; exit 0; fi".to_string(),
-        "if command -v shasum >/dev/null 2>&1; then shasum -a 256 -- \"$PATH_ARG\" 2>/dev/null | awk '{print $1}'; exit 0; fi".to_string(),
-        "if command -v openssl >/dev/null 2>&1; then openssl dgst -sha256 -- \"$PATH_ARG\" 2>/dev/null | awk '{print $NF}'; exit 0; fi".to_string(),
-        "echo \"__INFRA_NO_SHA256__\"".to_string(),
-        "exit 127".to_string(),
-    ]
-    .join("\n")
+// deploy_file's ATTRS_FAILED payload: the failure report merged over the deploy context.
+fn attrs_failed_response(failure: Value, context: Value) -> Value {
+    let mut response = serde_json::json!({"success": false, "code": "ATTRS_FAILED"});
+    for part in [context, failure] {
+        if let Value::Object(map) = part {
+            for (key, value) in map {
+                response[key.as_str()] = value;
+            }
+        }
+    }
+    response
 }
 
 fn parse_sha256_from_output(text: &str) -> Option<String> {
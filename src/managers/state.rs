@@ -1,6 +1,8 @@
 use crate::errors::ToolError;
 use crate::services::logger::Logger;
+use crate::services::project_resolver::{project_state_namespace, ProjectResolver};
 use crate::services::state::StateService;
+use crate::utils::safe_name::ensure_safe_name;
 use crate::utils::tool_errors::unknown_action_error;
 use serde_json::Value;
 use std::sync::Arc;
@@ -11,6 +13,7 @@ pub(crate) const STATE_ACTIONS: &[&str] = &["set", "get", "list", "unset", "clea
 pub struct StateManager {
     logger: Logger,
     state_service: Arc<StateService>,
+    project_resolver: Option<Arc<ProjectResolver>>,
 }
 
 impl StateManager {
@@ -18,22 +21,80 @@ impl StateManager {
         Self {
             logger: logger.child("state"),
             state_service,
+            project_resolver: None,
         }
     }
 
+    pub fn with_project_resolver(mut self, project_resolver: Arc<ProjectResolver>) -> Self {
+        self.project_resolver = Some(project_resolver);
+        self
+    }
+
+    async fn project_namespace(&self, args: &Value) -> Result<String, ToolError> {
+        let Some(resolver) = self.project_resolver.as_ref() else {
+            return Err(ToolError::invalid_params(
+                "scope=project is not available without project resolution",
+            ));
+        };
+        resolver.state_namespace(args).await
+    }
+
+    // scope=project maps the key into the caller's project/<name>/<target>/ namespace of the
+    // persistent store; other scopes pass through unchanged.
+    async fn scoped_key(
+        &self,
+        args: &Value,
+        key: &str,
+        scope: Option<&str>,
+    ) -> Result<(String, Option<String>), ToolError> {
+        if !is_project_scope(scope) {
+            return Ok((key.to_string(), None));
+        }
+        if key.trim().is_empty() {
+            return Err(ToolError::invalid_params(
+                "State key must be a non-empty string",
+            ));
+        }
+        let namespace = self.project_namespace(args).await?;
+        Ok((format!("{}/{}", namespace, key.trim()), Some(namespace)))
+    }
+
     pub async fn handle_action(&self, args: Value) -> Result<Value, ToolError> {
         let action = args.get("action");
         match action.and_then(|v| v.as_str()).unwrap_or("") {
             "set" => {
                 let key = args.get("key").and_then(|v| v.as_str()).unwrap_or("");
+                let key = ensure_safe_name(key, "State key")?;
                 let value = args.get("value").cloned().unwrap_or(Value::Null);
                 let scope = args.get("scope").and_then(|v| v.as_str());
-                self.state_service.set(key, value, scope)
+                let (key, namespace) = self.scoped_key(&args, &key, scope).await?;
+                let scope = if namespace.is_some() {
+                    Some("persistent")
+                } else {
+                    scope
+                };
+                let sensitive = args
+                    .get("sensitive")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+                let result = if sensitive {
+                    self.state_service.set_sensitive(&key, value, scope)?
+                } else {
+                    self.state_service.set(&key, value, scope)?
+                };
+                Ok(tag_project_scope(result, namespace))
             }
             "get" => {
                 let key = args.get("key").and_then(|v| v.as_str()).unwrap_or("");
                 let scope = args.get("scope").and_then(|v| v.as_str());
-                self.state_service.get(key, scope)
+                let (key, namespace) = self.scoped_key(&args, key, scope).await?;
+                let scope = if namespace.is_some() {
+                    Some("persistent")
+                } else {
+                    scope
+                };
+                let result = self.state_service.get(&key, scope)?;
+                Ok(tag_project_scope(result, namespace))
             }
             "list" => {
                 let prefix = args.get("prefix").and_then(|v| v.as_str());
@@ -42,12 +103,51 @@ impl StateManager {
                     .get("include_values")
                     .and_then(|v| v.as_bool())
                     .unwrap_or(false);
-                self.state_service.list(prefix, scope, include_values)
+                let namespace = if is_project_scope(scope) {
+                    Some(self.project_namespace(&args).await?)
+                } else {
+                    args.get("project")
+                        .and_then(|v| v.as_str())
+                        .filter(|s| !s.trim().is_empty())
+                        .map(|project| {
+                            project_state_namespace(
+                                project.trim(),
+                                args.get("target")
+                                    .and_then(|v| v.as_str())
+                                    .map(|s| s.trim())
+                                    .filter(|s| !s.is_empty()),
+                            )
+                        })
+                };
+                let Some(namespace) = namespace else {
+                    return self.state_service.list(prefix, scope, include_values);
+                };
+                let scoped_prefix = format!("{}/{}", namespace, prefix.unwrap_or(""));
+                if !is_project_scope(scope) {
+                    let mut result =
+                        self.state_service
+                            .list(Some(&scoped_prefix), scope, include_values)?;
+                    result["namespace"] = Value::String(namespace);
+                    return Ok(result);
+                }
+                let result = self.state_service.list(
+                    Some(&scoped_prefix),
+                    Some("persistent"),
+                    include_values,
+                )?;
+                Ok(tag_project_scope(result, Some(namespace)))
             }
             "unset" => {
                 let key = args.get("key").and_then(|v| v.as_str()).unwrap_or("");
                 let scope = args.get("scope").and_then(|v| v.as_str());
-                self.state_service.unset(key, scope)
+                let (key, namespace) = self.scoped_key(&args, key, scope).await?;
+                let scope = if namespace.is_some() {
+                    Some("persistent")
+                } else {
+                    scope
+                };
+                let result = self.state_service.unset(&key, scope)?;
+                Ok(tag_project_scope(result, namespace))
             }
             "clear" => {
                 let scope = args.get("scope").and_then(|v| v.as_str());
@@ -62,6 +162,20 @@ impl StateManager {
     }
 }
 
+fn is_project_scope(scope: Option<&str>) -> bool {
+    scope
+        .map(|scope| scope.eq_ignore_ascii_case("project"))
+        .unwrap_or(false)
+}
+
+fn tag_project_scope(mut result: Value, namespace: Option<String>) -> Value {
+    if let (Some(namespace), Value::Object(map)) = (namespace, &mut result) {
+        map.insert("scope".to_string(), Value::String("project".to_string()));
+        map.insert("namespace".to_string(), Value::String(namespace));
+    }
+    result
+}
+
 #[async_trait::async_trait]
 impl crate::services::tool_executor::ToolHandler for StateManager {
     async fn handle(&self, args: Value) -> Result<Value, ToolError> {
@@ -2,10 +2,17 @@ use crate::errors::ToolError;
 use crate::managers::intent::IntentManager;
 use crate::managers::runbook::RunbookManager;
 use crate::managers::ssh::SshManager;
-use crate::services::logger::Logger;
+use crate::services::audit::AuditService;
+use crate::services::cache::CacheService;
+use crate::services::logger::{LogLevel, LogTailFilter, Logger};
 use crate::services::validation::Validation;
 use crate::services::workspace::WorkspaceService;
+use crate::utils::feature_flags::{describe_flags, is_resource_accounting_enabled};
+use crate::utils::http_tuning;
+use crate::utils::run_report;
 use crate::utils::tool_errors::unknown_action_error;
+use crate::utils::trace_context::TraceContext;
+use crate::utils::usage;
 use serde_json::Value;
 use std::sync::Arc;
 
@@ -13,12 +20,24 @@ pub(crate) const WORKSPACE_ACTIONS: &[&str] = &[
     "summary",
     "suggest",
     "diagnose",
+    "doctor",
     "store_status",
     "run",
     "cleanup",
     "stats",
+    "log_level_set",
+    "logs_tail",
+    "cache_stats",
+    "cache_invalidate",
+    "config",
+    "config_apply",
+    "config_export",
+    "metrics",
+    "secrets_rotate_key",
 ];
 
+const DEFAULT_LOGS_TAIL_LIMIT: usize = 100;
+
 #[derive(Clone)]
 pub struct WorkspaceManager {
     logger: Logger,
@@ -27,6 +46,8 @@ pub struct WorkspaceManager {
     runbook_manager: Arc<RunbookManager>,
     intent_manager: Option<Arc<IntentManager>>,
     ssh_manager: Option<Arc<SshManager>>,
+    audit_service: Option<Arc<AuditService>>,
+    cache_service: Option<Arc<CacheService>>,
 }
 
 impl WorkspaceManager {
@@ -45,9 +66,49 @@ impl WorkspaceManager {
             runbook_manager,
             intent_manager,
             ssh_manager,
+            audit_service: None,
+            cache_service: None,
         }
     }
 
+    pub fn with_audit_service(mut self, audit_service: Arc<AuditService>) -> Self {
+        self.audit_service = Some(audit_service);
+        self
+    }
+
+    pub fn with_cache_service(mut self, cache_service: Arc<CacheService>) -> Self {
+        self.cache_service = Some(cache_service);
+        self
+    }
+
+    // Each plan step runs in a child span of the workspace.run call.
+    async fn run_step(
+        &self,
+        trace: &TraceContext,
+        tool: &str,
+        args: Value,
+    ) -> Result<Value, ToolError> {
+        let span = trace.child();
+        let args = span.apply(args);
+        let action = args
+            .get("action")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string();
+        let started_at = chrono::Utc::now().timestamp_millis();
+        let result = match tool {
+            "intent" => match self.intent_manager.as_ref() {
+                Some(intent_manager) => intent_manager.handle_action(args).await,
+                None => Err(ToolError::internal("Intent manager is not available")),
+            },
+            _ => self.runbook_manager.handle_action(args).await,
+        };
+        if let Some(audit_service) = self.audit_service.as_ref() {
+            audit_service.record_span(&span, tool, &action, started_at, result.as_ref());
+        }
+        result.map(|value| span.tag(value))
+    }
+
     pub async fn handle_action(&self, args: Value) -> Result<Value, ToolError> {
         let action = args.get("action");
         match action.and_then(|v| v.as_str()).unwrap_or("") {
@@ -59,14 +120,56 @@ impl WorkspaceManager {
                 let normalized = self.normalize_args(&args)?;
                 self.workspace_service.suggest(&normalized).await
             }
+            "doctor" => self.workspace_service.doctor(&args),
             "diagnose" => {
                 let normalized = self.normalize_args(&args)?;
                 self.workspace_service.diagnose(&normalized).await
             }
             "store_status" => self.workspace_service.store_status(&args).await,
-            "run" => self.run(args).await,
+            "run" => {
+                run_report::requested(&args)?;
+                let started = std::time::Instant::now();
+                let result = self.run(args.clone()).await?;
+                Ok(run_report::attach(
+                    "Workspace run report",
+                    &args,
+                    result,
+                    started.elapsed().as_millis() as u64,
+                ))
+            }
             "cleanup" => self.cleanup().await,
             "stats" => self.workspace_service.stats(&args).await,
+            "log_level_set" => self.log_level_set(&args),
+            "logs_tail" => self.logs_tail(&args),
+            "cache_stats" => Ok({
+                let mut stats = self.cache()?.stats();
+                stats["success"] = Value::Bool(true);
+                stats
+            }),
+            "cache_invalidate" => {
+                let optional = |key: &str| {
+                    args.get(key)
+                        .and_then(|v| v.as_str())
+                        .map(str::trim)
+                        .filter(|s| !s.is_empty())
+                };
+                self.cache()?
+                    .invalidate(optional("namespace"), optional("key"))
+            }
+            "config" => Ok({
+                let mut config = describe_flags();
+                config["success"] = Value::Bool(true);
+                config
+            }),
+            "config_apply" => self.workspace_service.config_apply(&args),
+            "config_export" => self.workspace_service.config_export(&args),
+            "secrets_rotate_key" => self.workspace_service.secrets_rotate_key(&args),
+            "metrics" => Ok(serde_json::json!({
+                "success": true,
+                "accounting": is_resource_accounting_enabled(),
+                "tools": usage::totals_snapshot(),
+                "http_pool": http_tuning::pool_snapshot(),
+            })),
             _ => Err(unknown_action_error("workspace", action, WORKSPACE_ACTIONS)),
         }
     }
@@ -114,16 +217,19 @@ impl WorkspaceManager {
     }
 
     async fn run(&self, args: Value) -> Result<Value, ToolError> {
+        let trace = TraceContext::from_args(&args);
         let has_intent = args.get("intent").map(|v| !v.is_null()).unwrap_or(false)
             || args.get("intent_type").and_then(|v| v.as_str()).is_some()
             || args.get("type").and_then(|v| v.as_str()).is_some();
         if has_intent {
-            let intent_manager = self.intent_manager.as_ref().ok_or_else(|| {
-                ToolError::internal("Intent manager is not available").with_hint(
-                    "This is a server configuration error. Enable IntentManager in wiring."
-                        .to_string(),
-                )
-            })?;
+            if self.intent_manager.is_none() {
+                return Err(
+                    ToolError::internal("Intent manager is not available").with_hint(
+                        "This is a server configuration error. Enable IntentManager in wiring."
+                            .to_string(),
+                    ),
+                );
+            }
             let intent_type = args
                 .get("intent")
                 .and_then(|v| v.get("type"))
@@ -147,13 +253,17 @@ impl WorkspaceManager {
             let intent = serde_json::json!({ "type": intent_type, "inputs": inputs });
             let apply = args.get("apply").and_then(|v| v.as_bool()).unwrap_or(false);
             if apply {
-                return intent_manager
-                    .handle_action(with_action(&args, "execute", intent))
+                return self
+                    .run_step(&trace, "intent", with_action(&args, "execute", intent))
                     .await;
             }
 
-            let compiled = intent_manager
-                .handle_action(with_action(&args, "compile", intent.clone()))
+            let compiled = self
+                .run_step(
+                    &trace,
+                    "intent",
+                    with_action(&args, "compile", intent.clone()),
+                )
                 .await?;
             let requires_apply = compiled
                 .get("plan")
@@ -162,8 +272,8 @@ impl WorkspaceManager {
                 .and_then(|v| v.as_bool())
                 .unwrap_or(false);
             let action = if requires_apply { "dry_run" } else { "execute" };
-            return intent_manager
-                .handle_action(with_action(&args, action, intent))
+            return self
+                .run_step(&trace, "intent", with_action(&args, action, intent))
                 .await;
         }
 
@@ -174,7 +284,72 @@ impl WorkspaceManager {
                 Value::String("runbook_run".to_string()),
             );
         }
-        self.runbook_manager.handle_action(next).await
+        self.run_step(&trace, "runbook", next).await
+    }
+
+    fn cache(&self) -> Result<&CacheService, ToolError> {
+        self.cache_service
+            .as_deref()
+            .ok_or_else(|| ToolError::internal("cache service is not configured"))
+    }
+
+    fn log_level_set(&self, args: &Value) -> Result<Value, ToolError> {
+        let component = self.validation.ensure_string(
+            args.get("component").unwrap_or(&Value::Null),
+            "component",
+            true,
+        )?;
+        let raw = args.get("level").and_then(|v| v.as_str()).unwrap_or("");
+        let level = if raw == "default" {
+            None
+        } else {
+            Some(LogLevel::parse(raw).ok_or_else(|| {
+                ToolError::invalid_params("level must be one of: error, warn, info, debug, default")
+            })?)
+        };
+        self.logger.set_level_override(&component, level);
+        self.logger.info(
+            "log level override",
+            Some(&serde_json::json!({
+                "component": component,
+                "level": level.map(LogLevel::as_str).unwrap_or("default"),
+            })),
+        );
+        Ok(serde_json::json!({
+            "success": true,
+            "component": component,
+            "level": level.map(LogLevel::as_str),
+            "overrides": self.logger.level_overrides(),
+        }))
+    }
+
+    fn logs_tail(&self, args: &Value) -> Result<Value, ToolError> {
+        let optional = |key: &str| {
+            args.get(key)
+                .and_then(|v| v.as_str())
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+        };
+        let level = match optional("level") {
+            Some(raw) => Some(LogLevel::parse(&raw).ok_or_else(|| {
+                ToolError::invalid_params("level must be one of: error, warn, info, debug")
+            })?),
+            None => None,
+        };
+        let filter = LogTailFilter {
+            component: optional("component"),
+            level,
+            trace_id: optional("log_trace_id"),
+            limit: args
+                .get("limit")
+                .and_then(|v| v.as_u64())
+                .map(|v| v as usize)
+                .unwrap_or(DEFAULT_LOGS_TAIL_LIMIT),
+        };
+        let mut result = self.logger.tail(&filter);
+        result["success"] = Value::Bool(true);
+        result["overrides"] = self.logger.level_overrides();
+        Ok(result)
     }
 
     async fn cleanup(&self) -> Result<Value, ToolError> {
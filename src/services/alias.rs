@@ -1,11 +1,157 @@
 use crate::errors::ToolError;
 use crate::services::store_db::StoreDb;
+use crate::utils::data_path::{get_path_value, parse_path, PathSegment};
 use crate::utils::listing::ListFilters;
 use crate::utils::paths::resolve_aliases_path;
+use crate::utils::safe_name::ensure_safe_name;
+use crate::utils::template::{resolve_templates, template_variables};
 use serde_json::Value;
 
 const NAMESPACE: &str = "aliases";
 
+fn alias_positional(alias: &Value) -> Vec<String> {
+    alias
+        .get("positional")
+        .and_then(|v| v.as_array())
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| item.as_str().map(|s| s.trim().to_string()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn variable_path(expression: &str) -> (&str, bool) {
+    match expression.strip_prefix('?') {
+        Some(path) => (path.trim(), true),
+        None => (expression.trim(), false),
+    }
+}
+
+fn variable_root(path: &str) -> Option<String> {
+    match parse_path(path).ok()?.into_iter().next()? {
+        PathSegment::Key(key) => Some(key),
+        PathSegment::Index(_) => None,
+    }
+}
+
+fn alias_variables(alias: &Value) -> Vec<String> {
+    let mut out = Vec::new();
+    if let Some(args) = alias.get("args") {
+        for expression in template_variables(args) {
+            let (path, _) = variable_path(&expression);
+            if let Some(root) = variable_root(path) {
+                if !out.contains(&root) {
+                    out.push(root);
+                }
+            }
+        }
+    }
+    for name in alias_positional(alias) {
+        if !out.contains(&name) {
+            out.push(name);
+        }
+    }
+    out
+}
+
+fn collect_templates(value: &Value, pointer: &str, out: &mut Vec<String>) {
+    match value {
+        Value::Object(map) => {
+            for (key, entry) in map {
+                collect_templates(entry, &format!("{}.{}", pointer, key), out);
+            }
+        }
+        Value::Array(items) => {
+            for (idx, entry) in items.iter().enumerate() {
+                collect_templates(entry, &format!("{}[{}]", pointer, idx), out);
+            }
+        }
+        Value::String(text) if text.contains("{{") => {
+            out.push(format!("{}={}", pointer.trim_start_matches('.'), text));
+        }
+        _ => {}
+    }
+}
+
+pub fn expand_alias_call(
+    name: &str,
+    alias: &Value,
+    call_args: &Value,
+) -> Result<(Option<Value>, Value), ToolError> {
+    let mut call = call_args.as_object().cloned().unwrap_or_default();
+    let positional = alias_positional(alias);
+    if let Some(argv) = call.remove("argv") {
+        let items = argv
+            .as_array()
+            .ok_or_else(|| ToolError::invalid_params("argv must be an array"))?;
+        if items.len() > positional.len() {
+            return Err(ToolError::invalid_params(format!(
+                "alias '{}' accepts at most {} positional argument(s), got {}",
+                name,
+                positional.len(),
+                items.len()
+            ))
+            .with_details(serde_json::json!({ "alias": name, "positional": positional })));
+        }
+        for (key, value) in positional.iter().zip(items.iter()) {
+            if call.contains_key(key) {
+                return Err(ToolError::invalid_params(format!(
+                    "alias '{}' argument '{}' was passed both positionally and by name",
+                    name, key
+                )));
+            }
+            call.insert(key.clone(), value.clone());
+        }
+    }
+
+    let Some(template) = alias.get("args").filter(|v| v.is_object()) else {
+        return Ok((None, Value::Object(call)));
+    };
+    let context = Value::Object(call.clone());
+    let mut missing = Vec::new();
+    for expression in template_variables(template) {
+        let (path, optional) = variable_path(&expression);
+        if optional || path.is_empty() {
+            continue;
+        }
+        if get_path_value(&context, path, true, None).is_err()
+            && !missing.contains(&path.to_string())
+        {
+            missing.push(path.to_string());
+        }
+    }
+    if !missing.is_empty() {
+        let hint = if positional.is_empty() {
+            format!("Pass {} as call arguments.", missing.join(", "))
+        } else {
+            format!(
+                "Pass {} as call arguments or via argv in order [{}].",
+                missing.join(", "),
+                positional.join(", ")
+            )
+        };
+        return Err(ToolError::invalid_params(format!(
+            "alias '{}' is missing template variable(s): {}",
+            name,
+            missing.join(", ")
+        ))
+        .with_hint(hint)
+        .with_details(serde_json::json!({
+            "stage": "alias_template",
+            "alias": name,
+            "missing": missing,
+            "positional": positional,
+        })));
+    }
+    let resolved = resolve_templates(template, &context, "error")?;
+    for variable in alias_variables(alias) {
+        call.remove(&variable);
+    }
+    Ok((Some(resolved), Value::Object(call)))
+}
+
 #[derive(Clone)]
 pub struct AliasService {
     store: StoreDb,
@@ -55,15 +201,23 @@ impl AliasService {
                 return Err(ToolError::invalid_params("alias.args must be an object"));
             }
         }
+        if let Some(positional) = obj.get("positional") {
+            let valid = positional.as_array().is_some_and(|items| {
+                items
+                    .iter()
+                    .all(|item| item.as_str().is_some_and(|s| !s.trim().is_empty()))
+            });
+            if !valid {
+                return Err(ToolError::invalid_params(
+                    "alias.positional must be an array of argument names",
+                ));
+            }
+        }
         Ok(())
     }
 
     pub fn set_alias(&self, name: &str, alias: &Value) -> Result<Value, ToolError> {
-        if name.trim().is_empty() {
-            return Err(ToolError::invalid_params(
-                "alias name must be a non-empty string",
-            ));
-        }
+        let name = &ensure_safe_name(name, "alias name")?;
         self.validate_alias(alias)?;
         let existing = self.store.get(NAMESPACE, name)?;
         let now = chrono::Utc::now().to_rfc3339();
@@ -114,6 +268,19 @@ impl AliasService {
             if let Some(desc) = alias.get("description") {
                 map.insert("description".to_string(), desc.clone());
             }
+            let variables = alias_variables(&alias);
+            if !variables.is_empty() {
+                let mut templates = Vec::new();
+                if let Some(args) = alias.get("args") {
+                    collect_templates(args, "", &mut templates);
+                }
+                map.insert("variables".to_string(), serde_json::json!(variables));
+                map.insert(
+                    "positional".to_string(),
+                    serde_json::json!(alias_positional(&alias)),
+                );
+                map.insert("templates".to_string(), serde_json::json!(templates));
+            }
             map.insert(
                 "created_at".to_string(),
                 alias.get("created_at").cloned().unwrap_or(Value::Null),
@@ -124,7 +291,11 @@ impl AliasService {
             );
             items.push(Value::Object(map));
         }
-        let result = filters.apply(items, &["name", "tool", "description"], None);
+        let result = filters.apply(
+            items,
+            &["name", "tool", "description", "variables", "templates"],
+            None,
+        );
         Ok(serde_json::json!({
             "success": true,
             "aliases": result.items,
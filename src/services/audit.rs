@@ -1,16 +1,29 @@
 use crate::errors::ToolError;
+use crate::services::audit_forwarder::AuditForwarder;
 use crate::services::logger::Logger;
+use crate::utils::audit_chain::{seal, verify_link, ChainHead, GENESIS_HASH};
 use crate::utils::paths::resolve_audit_path;
+use crate::utils::redact::redact_text;
+use crate::utils::trace_context::{build_span_tree, TraceContext};
+use once_cell::sync::OnceCell;
 use serde_json::Value;
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const TAIL_CHUNK_BYTES: u64 = 64 * 1024;
 
 #[derive(Clone)]
 pub struct AuditService {
     logger: Logger,
     file_path: std::path::PathBuf,
-    queue: Arc<Mutex<()>>,
+    // Serializes appends and holds the chain head, so sealing an entry never rereads the file.
+    // Another process appending to the same path forks the chain; verify reports the break.
+    queue: Arc<Mutex<ChainHead>>,
     stats: Arc<Mutex<AuditStats>>,
+    // Optional external sink; entries are handed over after the local write, already redacted.
+    forwarder: Arc<OnceCell<AuditForwarder>>,
 }
 
 #[derive(Debug, Default, Clone)]
@@ -23,17 +36,25 @@ pub struct AuditStats {
 
 impl AuditService {
     pub fn new(logger: Logger) -> Self {
+        let file_path = resolve_audit_path();
+        let head = recover_head(&file_path);
         Self {
             logger: logger.child("audit"),
-            file_path: resolve_audit_path(),
-            queue: Arc::new(Mutex::new(())),
+            file_path,
+            queue: Arc::new(Mutex::new(head)),
             stats: Arc::new(Mutex::new(AuditStats::default())),
+            forwarder: Arc::new(OnceCell::new()),
         }
     }
 
+    pub fn set_forwarder(&self, forwarder: AuditForwarder) {
+        let _ = self.forwarder.set(forwarder);
+    }
+
     pub fn append(&self, entry: &Value) {
-        let payload = format!("{}\n", entry);
-        let _guard = self.queue.lock();
+        let mut head = self.queue.lock().unwrap_or_else(|err| err.into_inner());
+        let sealed = seal(entry, head.seq + 1, &head.hash);
+        let payload = format!("{}\n", sealed);
         if let Some(parent) = self.file_path.parent() {
             let _ = std::fs::create_dir_all(parent);
         }
@@ -53,12 +74,62 @@ impl AuditService {
                 "Audit write failed",
                 Some(&serde_json::json!({"error": err.to_string()})),
             );
-        } else if let Ok(mut stats) = self.stats.lock() {
-            stats.logged += 1;
+        } else {
+            if let Some(next) = ChainHead::from_entry(&sealed) {
+                *head = next;
+            }
+            if let Ok(mut stats) = self.stats.lock() {
+                stats.logged += 1;
+            }
+            if let Some(forwarder) = self.forwarder.get() {
+                forwarder.enqueue(sealed);
+            }
         }
     }
 
+    // Waits until entries appended so far have been handed to the sink (delivered or failed).
+    pub async fn flush(&self, timeout: Duration) -> Result<Value, ToolError> {
+        let Some(forwarder) = self.forwarder.get() else {
+            return Ok(serde_json::json!({"success": true, "forward": null}));
+        };
+        forwarder.flush(timeout).await?;
+        Ok(serde_json::json!({"success": true, "forward": forwarder.stats()}))
+    }
+
+    // Nested manager calls bypass the executor, so their spans are recorded here.
+    pub fn record_span(
+        &self,
+        span: &TraceContext,
+        tool: &str,
+        action: &str,
+        started_at: i64,
+        outcome: Result<&Value, &ToolError>,
+    ) {
+        let mut entry = serde_json::json!({
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+            "status": if outcome.is_ok() { "ok" } else { "error" },
+            "tool": tool,
+            "action": action,
+            "trace_id": span.trace_id,
+            "span_id": span.span_id,
+            "parent_span_id": span.parent_span_id,
+            "nested": true,
+            "duration_ms": chrono::Utc::now().timestamp_millis() - started_at,
+        });
+        if let Err(err) = outcome {
+            entry["error"] = serde_json::json!({
+                "kind": err.kind,
+                "code": err.code,
+                "message": redact_text(&err.message, 2048, None),
+            });
+        }
+        self.append(&entry);
+    }
+
+    // The chain head survives a clear, so the next entry still links to the deleted tail and
+    // verify reports where the retained history starts.
     pub fn clear(&self) -> Result<Value, ToolError> {
+        let _head = self.queue.lock().unwrap_or_else(|err| err.into_inner());
         if self.file_path.exists() {
             std::fs::remove_file(&self.file_path).map_err(|err| {
                 ToolError::internal(format!("Failed to clear audit file: {}", err))
@@ -133,7 +204,28 @@ impl AuditService {
         }))
     }
 
+    pub fn trace_tree(&self, trace_id: &str) -> Result<Value, ToolError> {
+        let listed = self.read_entries(
+            usize::MAX,
+            0,
+            false,
+            &serde_json::json!({"trace_id": trace_id}),
+        )?;
+        let entries = listed["entries"].as_array().cloned().unwrap_or_default();
+        Ok(serde_json::json!({
+            "success": true,
+            "trace_id": trace_id,
+            "entries": entries.len(),
+            "spans": build_span_tree(&entries),
+        }))
+    }
+
     pub fn stats(&self) -> Value {
+        let head = self
+            .queue
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .clone();
         let stats = self.stats.lock().unwrap_or_else(|err| err.into_inner());
         serde_json::json!({
             "logged": stats.logged,
@@ -141,8 +233,169 @@ impl AuditService {
             "reads": stats.reads,
             "cleared": stats.cleared,
             "path": self.file_path,
+            "head": {"seq": head.seq, "hash": head.hash},
+            "forward": self.forwarder.get().map(|forwarder| forwarder.stats()),
+        })
+    }
+
+    // Walks rotated files oldest-first, then the live file, stopping at the first broken link.
+    // Unchained entries before the chain starts are counted as legacy (written before chaining).
+    pub fn verify(&self) -> Result<Value, ToolError> {
+        let _guard = self.queue.lock().unwrap_or_else(|err| err.into_inner());
+        let mut paths = rotated_paths(&self.file_path);
+        if self.file_path.exists() {
+            paths.push(self.file_path.clone());
+        }
+
+        let mut head: Option<ChainHead> = None;
+        let mut first: Option<Value> = None;
+        let mut verified = 0u64;
+        let mut legacy = 0u64;
+        let mut files = Vec::new();
+        let mut broken = Value::Null;
+        'files: for path in &paths {
+            let file = std::fs::File::open(path).map_err(|err| {
+                ToolError::internal(format!("Failed to open audit file: {}", err))
+            })?;
+            let mut entries = 0u64;
+            for (idx, line) in BufReader::new(file).lines().enumerate() {
+                let line = line.map_err(|err| {
+                    ToolError::internal(format!("Failed to read audit file: {}", err))
+                })?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let location =
+                    |seq: Value| serde_json::json!({"file": path, "line": idx + 1, "seq": seq});
+                let Ok(entry) = serde_json::from_str::<Value>(&line) else {
+                    broken = location(Value::Null);
+                    broken["reason"] = Value::from("invalid_json");
+                    break 'files;
+                };
+                let seq = entry.get("seq").cloned().unwrap_or(Value::Null);
+                let current = match head.as_ref() {
+                    Some(current) => current.clone(),
+                    None if entry.get("entry_hash").is_none() => {
+                        legacy += 1;
+                        continue;
+                    }
+                    // The oldest retained entry anchors the walk; its link is reported below.
+                    None => ChainHead {
+                        seq: seq.as_u64().unwrap_or(1).saturating_sub(1),
+                        hash: entry
+                            .get("prev_hash")
+                            .and_then(|v| v.as_str())
+                            .unwrap_or_default()
+                            .to_string(),
+                    },
+                };
+                match verify_link(&current, &entry) {
+                    Ok(next) => {
+                        if first.is_none() {
+                            first = Some(serde_json::json!({
+                                "seq": next.seq,
+                                "prev_hash": current.hash,
+                            }));
+                        }
+                        head = Some(next);
+                        verified += 1;
+                        entries += 1;
+                    }
+                    Err(err) => {
+                        broken = location(seq);
+                        if let Value::Object(details) = err.to_json() {
+                            for (key, value) in details {
+                                broken[key] = value;
+                            }
+                        }
+                        break 'files;
+                    }
+                }
+            }
+            files.push(serde_json::json!({"path": path, "entries": entries}));
+        }
+
+        let starts_at_genesis = first
+            .as_ref()
+            .map(|first| first["seq"] == 1 && first["prev_hash"] == GENESIS_HASH)
+            .unwrap_or(true);
+        let head = head.unwrap_or_default();
+        Ok(serde_json::json!({
+            "success": true,
+            "valid": broken.is_null(),
+            "verified": verified,
+            "legacy_entries": legacy,
+            "starts_at_genesis": starts_at_genesis,
+            "first": first,
+            "files": files,
+            "head": {"seq": head.seq, "hash": head.hash},
+            "broken": broken,
+        }))
+    }
+}
+
+// Rotated siblings follow the logrotate naming (`audit.jsonl.1`, `audit.jsonl.2`, …); higher
+// numbers are older, so they are returned first.
+fn rotated_paths(file_path: &Path) -> Vec<PathBuf> {
+    let (Some(parent), Some(name)) = (
+        file_path.parent(),
+        file_path.file_name().and_then(|n| n.to_str()),
+    ) else {
+        return Vec::new();
+    };
+    let prefix = format!("{}.", name);
+    let mut rotated: Vec<(u64, PathBuf)> = std::fs::read_dir(parent)
+        .map(|dir| {
+            dir.filter_map(|item| item.ok())
+                .filter_map(|item| {
+                    let file_name = item.file_name();
+                    let index = file_name
+                        .to_str()?
+                        .strip_prefix(&prefix)?
+                        .parse::<u64>()
+                        .ok()?;
+                    Some((index, item.path()))
+                })
+                .collect()
         })
+        .unwrap_or_default();
+    rotated.sort_by_key(|item| std::cmp::Reverse(item.0));
+    rotated.into_iter().map(|(_, path)| path).collect()
+}
+
+fn last_line(path: &Path) -> Option<String> {
+    let mut file = std::fs::File::open(path).ok()?;
+    let len = file.metadata().ok()?.len();
+    let mut chunk = TAIL_CHUNK_BYTES;
+    loop {
+        let start = len.saturating_sub(chunk);
+        file.seek(SeekFrom::Start(start)).ok()?;
+        let mut buf = Vec::new();
+        file.read_to_end(&mut buf).ok()?;
+        let text = String::from_utf8_lossy(&buf);
+        let mut lines = text.lines().filter(|line| !line.trim().is_empty());
+        let last = lines.next_back().map(|line| line.to_string());
+        // Keep widening until the last line is known to start inside the window.
+        if start == 0 || lines.next_back().is_some() {
+            return last;
+        }
+        chunk = chunk.saturating_mul(2);
+    }
+}
+
+// Falls back to the newest rotated file when the live one is missing or empty.
+fn recover_head(file_path: &Path) -> ChainHead {
+    let mut candidates = vec![file_path.to_path_buf()];
+    candidates.extend(rotated_paths(file_path).into_iter().rev());
+    for path in candidates {
+        if let Some(line) = last_line(&path) {
+            return serde_json::from_str::<Value>(&line)
+                .ok()
+                .and_then(|entry| ChainHead::from_entry(&entry))
+                .unwrap_or_default();
+        }
     }
+    ChainHead::default()
 }
 
 fn build_filter(filters: &Value) -> impl Fn(&Value) -> bool {
@@ -0,0 +1,264 @@
+use crate::errors::{ToolError, ToolErrorKind};
+use crate::services::logger::Logger;
+use crate::utils::feature_flags::{
+    AUDIT_WEBHOOK_BATCH_SIZE, AUDIT_WEBHOOK_FLUSH_MS, AUDIT_WEBHOOK_PROFILE, AUDIT_WEBHOOK_QUEUE,
+    AUDIT_WEBHOOK_URL,
+};
+use crate::utils::redact::redact_text;
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::Instant;
+
+const DELIVERY_ATTEMPTS: u32 = 3;
+const RETRY_BASE_DELAY_MS: u64 = 250;
+
+// Where forwarded audit batches go; the api manager implements it for the webhook sink.
+#[async_trait::async_trait]
+pub trait AuditTransport: Send + Sync {
+    async fn deliver(&self, batch: &[Value]) -> Result<(), ToolError>;
+
+    // Shown in stats; must not include credentials.
+    fn describe(&self) -> Value;
+}
+
+#[derive(Clone, Debug)]
+pub struct ForwardConfig {
+    pub batch_size: usize,
+    pub flush_ms: u64,
+    pub queue_capacity: usize,
+}
+
+#[derive(Debug, Default)]
+struct ForwardStats {
+    sent: u64,
+    failed: u64,
+    dropped: u64,
+    batches: u64,
+    failed_batches: u64,
+    // Entries accepted but not yet delivered or given up on (channel plus the open batch).
+    pending: u64,
+    last_error: Option<String>,
+}
+
+enum Message {
+    Entry(Value),
+    Flush(oneshot::Sender<()>),
+}
+
+// Pushes sealed audit entries to an external sink off the tool-call path. Appends never wait:
+// a full queue drops the entry and counts it. A background task batches entries (batch_size
+// entries or flush_ms after the first one) and delivers each batch with bounded retries.
+#[derive(Clone)]
+pub struct AuditForwarder {
+    sender: mpsc::Sender<Message>,
+    stats: Arc<Mutex<ForwardStats>>,
+    config: ForwardConfig,
+    target: Value,
+}
+
+impl AuditForwarder {
+    // The INFRA_AUDIT_WEBHOOK_* setup: None when no URL is set. `webhook` builds the transport
+    // from the URL and the optional api profile name that supplies auth, TLS and proxy.
+    pub fn from_flags(
+        logger: &Logger,
+        webhook: impl FnOnce(String, Option<String>) -> Arc<dyn AuditTransport>,
+    ) -> Option<Self> {
+        let url = AUDIT_WEBHOOK_URL
+            .text()
+            .map(|url| url.trim().to_string())
+            .filter(|url| !url.is_empty())?;
+        let profile = AUDIT_WEBHOOK_PROFILE
+            .text()
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty());
+        let config = ForwardConfig {
+            batch_size: AUDIT_WEBHOOK_BATCH_SIZE.positive_number() as usize,
+            flush_ms: AUDIT_WEBHOOK_FLUSH_MS.number(),
+            queue_capacity: AUDIT_WEBHOOK_QUEUE.positive_number() as usize,
+        };
+        let forwarder = Self::spawn(logger.clone(), webhook(url, profile), config);
+        if forwarder.is_none() {
+            logger.warn(
+                "INFRA_AUDIT_WEBHOOK_URL is set outside an async runtime; audit forwarding is off",
+                None,
+            );
+        }
+        forwarder
+    }
+
+    // Needs a tokio runtime for the delivery task; returns None outside of one.
+    pub fn spawn(
+        logger: Logger,
+        transport: Arc<dyn AuditTransport>,
+        config: ForwardConfig,
+    ) -> Option<Self> {
+        let runtime = tokio::runtime::Handle::try_current().ok()?;
+        let config = ForwardConfig {
+            batch_size: config.batch_size.max(1),
+            flush_ms: config.flush_ms,
+            queue_capacity: config.queue_capacity.max(1),
+        };
+        let (sender, receiver) = mpsc::channel(config.queue_capacity);
+        let stats = Arc::new(Mutex::new(ForwardStats::default()));
+        let target = transport.describe();
+        runtime.spawn(run_delivery(
+            logger.child("audit_forward"),
+            receiver,
+            transport,
+            stats.clone(),
+            config.clone(),
+        ));
+        Some(Self {
+            sender,
+            stats,
+            config,
+            target,
+        })
+    }
+
+    pub fn enqueue(&self, entry: Value) {
+        let accepted = self.sender.try_send(Message::Entry(entry)).is_ok();
+        let mut stats = self.stats.lock().unwrap_or_else(|err| err.into_inner());
+        if accepted {
+            stats.pending += 1;
+        } else {
+            stats.dropped += 1;
+        }
+    }
+
+    // Delivers everything queued before the call; waits behind a full queue up to `timeout`.
+    pub async fn flush(&self, timeout: Duration) -> Result<(), ToolError> {
+        let (ack, done) = oneshot::channel();
+        let waited = tokio::time::timeout(timeout, async {
+            self.sender
+                .send(Message::Flush(ack))
+                .await
+                .map_err(|_| ToolError::internal("Audit forwarder has stopped"))?;
+            done.await
+                .map_err(|_| ToolError::internal("Audit forwarder has stopped"))
+        })
+        .await;
+        match waited {
+            Ok(result) => result,
+            Err(_) => Err(ToolError::timeout(format!(
+                "Audit flush did not finish within {}ms",
+                timeout.as_millis()
+            ))
+            .with_hint("The sink is slow or unreachable; check audit_stats forward.last_error.")),
+        }
+    }
+
+    pub fn stats(&self) -> Value {
+        let stats = self.stats.lock().unwrap_or_else(|err| err.into_inner());
+        serde_json::json!({
+            "target": self.target,
+            "sent": stats.sent,
+            "failed": stats.failed,
+            "dropped": stats.dropped,
+            "batches": stats.batches,
+            "failed_batches": stats.failed_batches,
+            "queue_depth": stats.pending,
+            "queue_capacity": self.config.queue_capacity,
+            "batch_size": self.config.batch_size,
+            "flush_ms": self.config.flush_ms,
+            "last_error": stats.last_error,
+        })
+    }
+}
+
+async fn run_delivery(
+    logger: Logger,
+    mut receiver: mpsc::Receiver<Message>,
+    transport: Arc<dyn AuditTransport>,
+    stats: Arc<Mutex<ForwardStats>>,
+    config: ForwardConfig,
+) {
+    let mut batch: Vec<Value> = Vec::new();
+    let mut deadline: Option<Instant> = None;
+    loop {
+        let message = match deadline {
+            Some(at) => tokio::select! {
+                message = receiver.recv() => message,
+                _ = tokio::time::sleep_until(at) => {
+                    deliver(&logger, transport.as_ref(), &stats, &mut batch).await;
+                    deadline = None;
+                    continue;
+                }
+            },
+            None => receiver.recv().await,
+        };
+        match message {
+            Some(Message::Entry(entry)) => {
+                batch.push(entry);
+                if batch.len() >= config.batch_size {
+                    deliver(&logger, transport.as_ref(), &stats, &mut batch).await;
+                    deadline = None;
+                } else if deadline.is_none() {
+                    deadline = Some(Instant::now() + Duration::from_millis(config.flush_ms));
+                }
+            }
+            Some(Message::Flush(ack)) => {
+                deliver(&logger, transport.as_ref(), &stats, &mut batch).await;
+                deadline = None;
+                let _ = ack.send(());
+            }
+            None => {
+                deliver(&logger, transport.as_ref(), &stats, &mut batch).await;
+                return;
+            }
+        }
+    }
+}
+
+async fn deliver(
+    logger: &Logger,
+    transport: &dyn AuditTransport,
+    stats: &Mutex<ForwardStats>,
+    batch: &mut Vec<Value>,
+) {
+    if batch.is_empty() {
+        return;
+    }
+    let mut attempt = 1;
+    let outcome = loop {
+        match transport.deliver(batch).await {
+            Ok(()) => break Ok(()),
+            Err(err)
+                if attempt < DELIVERY_ATTEMPTS
+                    && matches!(err.kind, ToolErrorKind::Retryable | ToolErrorKind::Timeout) =>
+            {
+                tokio::time::sleep(Duration::from_millis(RETRY_BASE_DELAY_MS << (attempt - 1)))
+                    .await;
+                attempt += 1;
+            }
+            Err(err) => break Err(err),
+        }
+    };
+    let count = batch.len() as u64;
+    batch.clear();
+    let mut stats = stats.lock().unwrap_or_else(|err| err.into_inner());
+    stats.pending = stats.pending.saturating_sub(count);
+    match outcome {
+        Ok(()) => {
+            stats.sent += count;
+            stats.batches += 1;
+        }
+        Err(err) => {
+            stats.failed += count;
+            stats.failed_batches += 1;
+            let message = redact_text(&err.message, 1024, None);
+            logger.warn(
+                "Audit forward failed",
+                Some(&serde_json::json!({
+                    "entries": count,
+                    "attempts": attempt,
+                    "code": err.code,
+                    "error": message,
+                })),
+            );
+            stats.last_error = Some(message);
+        }
+    }
+}
@@ -1,17 +1,89 @@
 use crate::errors::ToolError;
 use crate::services::logger::Logger;
+use crate::utils::feature_flags::{self, Flag};
 use crate::utils::fs_atomic::{atomic_write_text_file, temp_sibling_path};
 use crate::utils::paths::resolve_cache_dir;
+use crate::utils::usage::{self, Counter};
 use serde_json::Value;
 use sha2::{Digest, Sha256};
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 
-#[derive(Clone)]
-pub struct CacheService {
-    logger: Logger,
-    cache_dir: PathBuf,
-    stats: Arc<Mutex<CacheStats>>,
+const MIB: u64 = 1024 * 1024;
+const DEFAULT_NAMESPACE: &str = "api";
+
+#[derive(Clone, Copy, Debug)]
+struct NamespacePolicy {
+    name: &'static str,
+    ttl_ms: Option<u64>,
+    max_bytes: u64,
+    // Resolved secrets and resolver entries (keyed to this process's store generations) never
+    // reach the disk, whatever the backend.
+    persist: bool,
+}
+
+const NAMESPACES: &[NamespacePolicy] = &[
+    NamespacePolicy {
+        name: "api",
+        ttl_ms: Some(15 * 60 * 1000),
+        max_bytes: 64 * MIB,
+        persist: true,
+    },
+    NamespacePolicy {
+        name: "pipeline",
+        ttl_ms: Some(60 * 60 * 1000),
+        max_bytes: 512 * MIB,
+        persist: true,
+    },
+    NamespacePolicy {
+        name: "secret_refs",
+        ttl_ms: Some(60 * 1000),
+        max_bytes: MIB,
+        persist: false,
+    },
+    NamespacePolicy {
+        name: "project_resolver",
+        ttl_ms: Some(5 * 1000),
+        max_bytes: 4 * MIB,
+        persist: false,
+    },
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CacheBackend {
+    Memory,
+    Disk,
+}
+
+impl CacheBackend {
+    pub fn from_env() -> Self {
+        match feature_flags::CACHE_BACKEND
+            .text()
+            .unwrap_or_default()
+            .trim()
+            .to_lowercase()
+            .as_str()
+        {
+            "disk" => CacheBackend::Disk,
+            _ => CacheBackend::Memory,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CacheBackend::Memory => "memory",
+            CacheBackend::Disk => "disk",
+        }
+    }
+}
+
+// One index entry. `payload` holds the JSON entry for values kept in memory; entries on disk
+// (disk backend values, and file entries on every backend) leave it empty.
+struct Slot {
+    bytes: u64,
+    last_access_ms: i64,
+    payload: Option<Value>,
 }
 
 #[derive(Default)]
@@ -20,14 +92,197 @@ struct CacheStats {
     misses: u64,
     writes: u64,
     errors: u64,
+    evictions: u64,
+    corrupt_skipped: u64,
+}
+
+struct Store {
+    backend: CacheBackend,
+    policies: HashMap<String, NamespacePolicy>,
+    slots: HashMap<String, HashMap<String, Slot>>,
+    stats: HashMap<String, CacheStats>,
+}
+
+#[derive(Clone)]
+pub struct CacheService {
+    logger: Logger,
+    cache_dir: PathBuf,
+    namespace: String,
+    store: Arc<Mutex<Store>>,
+}
+
+fn now_ms() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+// `INFRA_CACHE_TTLS=api=60000,pipeline=0` / `INFRA_CACHE_BUDGETS=api=1048576`; 0 TTL disables it.
+fn parse_overrides(flag: &Flag) -> HashMap<String, u64> {
+    flag.text()
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|entry| {
+            let (name, value) = entry.split_once('=')?;
+            Some((name.trim().to_string(), value.trim().parse::<u64>().ok()?))
+        })
+        .collect()
+}
+
+fn resolve_policies() -> HashMap<String, NamespacePolicy> {
+    let ttls = parse_overrides(&feature_flags::CACHE_TTLS);
+    let budgets = parse_overrides(&feature_flags::CACHE_BUDGETS);
+    NAMESPACES
+        .iter()
+        .map(|policy| {
+            let mut policy = *policy;
+            if let Some(ttl) = ttls.get(policy.name) {
+                policy.ttl_ms = (*ttl > 0).then_some(*ttl);
+            }
+            if let Some(budget) = budgets.get(policy.name) {
+                policy.max_bytes = *budget;
+            }
+            (policy.name.to_string(), policy)
+        })
+        .collect()
+}
+
+fn file_len(path: &Path) -> u64 {
+    std::fs::metadata(path).map(|meta| meta.len()).unwrap_or(0)
 }
 
 impl CacheService {
     pub fn new(logger: Logger) -> Self {
-        Self {
+        Self::with_backend(logger, CacheBackend::from_env(), resolve_cache_dir())
+    }
+
+    pub fn with_backend(logger: Logger, backend: CacheBackend, cache_dir: PathBuf) -> Self {
+        let service = Self {
             logger: logger.child("cache"),
-            cache_dir: resolve_cache_dir(),
-            stats: Arc::new(Mutex::new(CacheStats::default())),
+            cache_dir,
+            namespace: DEFAULT_NAMESPACE.to_string(),
+            store: Arc::new(Mutex::new(Store {
+                backend,
+                policies: resolve_policies(),
+                slots: HashMap::new(),
+                stats: HashMap::new(),
+            })),
+        };
+        service.load_disk_entries();
+        service
+    }
+
+    // A handle sharing the backend whose keys live in `namespace`. Unknown namespaces are
+    // rejected so consumers cannot silently share key space.
+    pub fn namespaced(&self, namespace: &str) -> Result<Self, ToolError> {
+        self.ensure_namespace(namespace)?;
+        let mut service = self.clone();
+        service.namespace = namespace.to_string();
+        Ok(service)
+    }
+
+    pub fn namespace(&self) -> &str {
+        &self.namespace
+    }
+
+    fn ensure_namespace(&self, namespace: &str) -> Result<(), ToolError> {
+        if NAMESPACES.iter().any(|policy| policy.name == namespace) {
+            return Ok(());
+        }
+        let known: Vec<&str> = NAMESPACES.iter().map(|policy| policy.name).collect();
+        Err(
+            ToolError::invalid_params(format!("Unknown cache namespace: {}", namespace))
+                .with_hint(format!("Known namespaces: {}.", known.join(", ")))
+                .with_details(serde_json::json!({ "known_namespaces": known })),
+        )
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Store> {
+        self.store.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn policy(store: &Store, namespace: &str) -> NamespacePolicy {
+        store
+            .policies
+            .get(namespace)
+            .copied()
+            .unwrap_or(NAMESPACES[0])
+    }
+
+    fn namespace_dir(&self, namespace: &str) -> PathBuf {
+        self.cache_dir.join(namespace)
+    }
+
+    // Startup sanitation: index every entry already on disk and drop the ones that cannot be
+    // read back, counting them instead of failing.
+    fn load_disk_entries(&self) {
+        let mut store = self.lock();
+        for policy in NAMESPACES {
+            let dir = self.namespace_dir(policy.name);
+            let Ok(shards) = std::fs::read_dir(&dir) else {
+                continue;
+            };
+            let mut slots = HashMap::new();
+            let mut corrupt = 0u64;
+            for shard in shards.flatten() {
+                let Ok(files) = std::fs::read_dir(shard.path()) else {
+                    continue;
+                };
+                for file in files.flatten() {
+                    let path = file.path();
+                    if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                        continue;
+                    }
+                    let Some(key) = path
+                        .file_stem()
+                        .and_then(|stem| stem.to_str())
+                        .filter(|stem| stem.len() == 64)
+                        .map(str::to_string)
+                    else {
+                        continue;
+                    };
+                    let valid = std::fs::read_to_string(&path)
+                        .ok()
+                        .and_then(|raw| serde_json::from_str::<Value>(&raw).ok())
+                        .is_some_and(|payload| {
+                            matches!(
+                                payload.get("type").and_then(|v| v.as_str()),
+                                Some("json") | Some("file")
+                            )
+                        });
+                    if !valid {
+                        corrupt += 1;
+                        let _ = std::fs::remove_file(&path);
+                        let _ = std::fs::remove_file(path.with_extension("bin"));
+                        continue;
+                    }
+                    let last_access_ms = file
+                        .metadata()
+                        .ok()
+                        .and_then(|meta| meta.modified().ok())
+                        .map(|time| chrono::DateTime::<chrono::Utc>::from(time).timestamp_millis())
+                        .unwrap_or(0);
+                    let bytes = file_len(&path) + file_len(&path.with_extension("bin"));
+                    slots.insert(
+                        key,
+                        Slot {
+                            bytes,
+                            last_access_ms,
+                            payload: None,
+                        },
+                    );
+                }
+            }
+            if corrupt > 0 {
+                self.logger.warn(
+                    "Skipped corrupt cache entries",
+                    Some(&serde_json::json!({"namespace": policy.name, "count": corrupt})),
+                );
+            }
+            store
+                .stats
+                .entry(policy.name.to_string())
+                .or_default()
+                .corrupt_skipped += corrupt;
+            store.slots.insert(policy.name.to_string(), slots);
         }
     }
 
@@ -64,20 +319,21 @@ impl CacheService {
     }
 
     pub fn build_key(&self, input: &Value) -> String {
-        let payload = stable_stringify(input);
-        let mut hasher = Sha256::new();
-        hasher.update(payload.as_bytes());
-        hex::encode(hasher.finalize())
+        stable_key(input)
     }
 
+    // `<cache_dir>/<namespace>/<first two hex chars>/<key>.json`, sharded so no directory
+    // grows past a few thousand entries.
     fn entry_path(&self, key: &str) -> Result<PathBuf, ToolError> {
         let normalized = self.ensure_key(key)?;
-        Ok(self.cache_dir.join(format!("{}.json", normalized)))
+        Ok(self
+            .namespace_dir(&self.namespace)
+            .join(&normalized[..2])
+            .join(format!("{}.json", normalized)))
     }
 
     pub fn data_path(&self, key: &str) -> Result<PathBuf, ToolError> {
-        let normalized = self.ensure_key(key)?;
-        Ok(self.cache_dir.join(format!("{}.bin", normalized)))
+        Ok(self.entry_path(key)?.with_extension("bin"))
     }
 
     fn is_expired(meta: &Value, ttl_override: Option<u64>) -> bool {
@@ -90,90 +346,115 @@ impl CacheService {
         if created.is_none() {
             return false;
         }
-        let elapsed = chrono::Utc::now().timestamp_millis() - created.unwrap().timestamp_millis();
+        let elapsed = now_ms() - created.unwrap().timestamp_millis();
         elapsed > ttl.unwrap() as i64
     }
 
-    pub fn get_json(&self, key: &str, ttl_ms: Option<u64>) -> Result<Option<Value>, ToolError> {
-        let entry_path = self.entry_path(key)?;
+    fn stores_in_memory(&self, store: &Store) -> bool {
+        store.backend == CacheBackend::Memory || !Self::policy(store, &self.namespace).persist
+    }
+
+    fn read_entry(&self, key: &str) -> Result<Option<Value>, ToolError> {
+        let key = self.ensure_key(key)?;
+        {
+            let mut store = self.lock();
+            if let Some(slot) = store
+                .slots
+                .get_mut(&self.namespace)
+                .and_then(|slots| slots.get_mut(&key))
+            {
+                slot.last_access_ms = now_ms();
+                if let Some(payload) = slot.payload.as_ref() {
+                    return Ok(Some(payload.clone()));
+                }
+            }
+        }
+        let entry_path = self.entry_path(&key)?;
         let raw = match std::fs::read_to_string(&entry_path) {
             Ok(raw) => raw,
             Err(err) => {
                 if err.kind() != std::io::ErrorKind::NotFound {
-                    self.bump_errors();
+                    self.bump(|stats| stats.errors += 1);
                     self.logger.warn(
                         "Cache read failed",
                         Some(&serde_json::json!({"error": err.to_string()})),
                     );
                 }
-                self.bump_misses();
+                self.forget_slot(&key);
                 return Ok(None);
             }
         };
-        let payload: Value = match serde_json::from_str(&raw) {
-            Ok(val) => val,
+        match serde_json::from_str::<Value>(&raw) {
+            Ok(payload) => {
+                let bytes = raw.len() as u64 + file_len(&entry_path.with_extension("bin"));
+                let mut store = self.lock();
+                let slots = store.slots.entry(self.namespace.clone()).or_default();
+                slots.entry(key).or_insert(Slot {
+                    bytes,
+                    last_access_ms: now_ms(),
+                    payload: None,
+                });
+                Ok(Some(payload))
+            }
             Err(_) => {
-                self.bump_misses();
-                return Ok(None);
+                self.bump(|stats| stats.corrupt_skipped += 1);
+                let _ = self.remove(&key);
+                Ok(None)
             }
+        }
+    }
+
+    fn lookup(
+        &self,
+        key: &str,
+        kind: &str,
+        ttl_ms: Option<u64>,
+    ) -> Result<Option<Value>, ToolError> {
+        let Some(payload) = self.read_entry(key)? else {
+            self.miss();
+            return Ok(None);
         };
-        if payload.get("type").and_then(|v| v.as_str()) != Some("json") {
-            self.bump_misses();
+        if payload.get("type").and_then(|v| v.as_str()) != Some(kind) {
+            self.miss();
             return Ok(None);
         }
         if Self::is_expired(&payload, ttl_ms) {
             let _ = self.remove(key);
-            self.bump_misses();
+            self.miss();
             return Ok(None);
         }
-        self.bump_hits();
+        self.bump(|stats| stats.hits += 1);
+        usage::record(Counter::CacheHits, 1);
         Ok(Some(payload))
     }
 
+    fn miss(&self) {
+        self.bump(|stats| stats.misses += 1);
+        usage::record(Counter::CacheMisses, 1);
+    }
+
+    pub fn get_json(&self, key: &str, ttl_ms: Option<u64>) -> Result<Option<Value>, ToolError> {
+        self.lookup(key, "json", ttl_ms)
+    }
+
     pub fn get_file(&self, key: &str, ttl_ms: Option<u64>) -> Result<Option<Value>, ToolError> {
-        let entry_path = self.entry_path(key)?;
-        let raw = match std::fs::read_to_string(&entry_path) {
-            Ok(raw) => raw,
-            Err(err) => {
-                if err.kind() != std::io::ErrorKind::NotFound {
-                    self.bump_errors();
-                    self.logger.warn(
-                        "Cache read failed",
-                        Some(&serde_json::json!({"error": err.to_string()})),
-                    );
-                }
-                self.bump_misses();
-                return Ok(None);
-            }
-        };
-        let payload: Value = match serde_json::from_str(&raw) {
-            Ok(val) => val,
-            Err(_) => {
-                self.bump_misses();
-                return Ok(None);
-            }
-        };
-        if payload.get("type").and_then(|v| v.as_str()) != Some("file") {
-            self.bump_misses();
-            return Ok(None);
-        }
-        if Self::is_expired(&payload, ttl_ms) {
-            let _ = self.remove(key);
-            self.bump_misses();
+        let Some(mut entry) = self.lookup(key, "file", ttl_ms)? else {
             return Ok(None);
-        }
+        };
         let data_path = self.data_path(key)?;
-        let mut entry = payload.clone();
         if let Value::Object(map) = &mut entry {
             map.insert(
                 "file_path".to_string(),
                 Value::String(data_path.display().to_string()),
             );
         }
-        self.bump_hits();
         Ok(Some(entry))
     }
 
+    fn default_ttl(&self, ttl_ms: Option<u64>) -> Option<u64> {
+        ttl_ms.or_else(|| Self::policy(&self.lock(), &self.namespace).ttl_ms)
+    }
+
     pub fn set_json(
         &self,
         key: &str,
@@ -181,19 +462,30 @@ impl CacheService {
         ttl_ms: Option<u64>,
         meta: Option<Value>,
     ) -> Result<Value, ToolError> {
+        let key = self.ensure_key(key)?;
         let payload = serde_json::json!({
             "type": "json",
             "created_at": chrono::Utc::now().to_rfc3339(),
-            "ttl_ms": ttl_ms,
+            "ttl_ms": self.default_ttl(ttl_ms),
             "meta": meta,
             "value": value,
         });
         let serialized = serde_json::to_string_pretty(&payload).map_err(|err| {
             ToolError::internal(format!("Failed to serialize cache entry: {}", err))
         })?;
-        atomic_write_text_file(self.entry_path(key)?, &format!("{}\n", serialized), 0o600)
-            .map_err(|err| ToolError::internal(format!("Failed to write cache entry: {}", err)))?;
-        self.bump_writes();
+        let in_memory = self.stores_in_memory(&self.lock());
+        if !in_memory {
+            atomic_write_text_file(self.entry_path(&key)?, &format!("{}\n", serialized), 0o600)
+                .map_err(|err| {
+                    self.bump(|stats| stats.errors += 1);
+                    ToolError::internal(format!("Failed to write cache entry: {}", err))
+                })?;
+        }
+        self.record_write(
+            &key,
+            serialized.len() as u64 + 1,
+            in_memory.then(|| payload.clone()),
+        );
         Ok(payload)
     }
 
@@ -203,14 +495,17 @@ impl CacheService {
         _ttl_ms: Option<u64>,
         _meta: Option<Value>,
     ) -> Result<(PathBuf, PathBuf), ToolError> {
-        let key = self.ensure_key(key)?;
-        std::fs::create_dir_all(&self.cache_dir)
-            .map_err(|err| ToolError::internal(format!("Failed to create cache dir: {}", err)))?;
-        let data_path = self.cache_dir.join(format!("{}.bin", key));
+        let data_path = self.data_path(key)?;
+        if let Some(parent) = data_path.parent() {
+            std::fs::create_dir_all(parent).map_err(|err| {
+                ToolError::internal(format!("Failed to create cache dir: {}", err))
+            })?;
+        }
         let tmp_path = temp_sibling_path(&data_path);
         Ok((data_path, tmp_path))
     }
 
+    // File entries always live on disk: the payload is the downloaded file itself.
     pub fn finalize_file_writer(
         &self,
         key: &str,
@@ -218,58 +513,181 @@ impl CacheService {
         ttl_ms: Option<u64>,
         meta: Option<Value>,
     ) -> Result<Value, ToolError> {
-        let data_path = self.data_path(key)?;
+        let key = self.ensure_key(key)?;
+        let data_path = self.data_path(&key)?;
         std::fs::rename(tmp_path, &data_path).map_err(|err| {
             ToolError::internal(format!("Failed to finalize cache file: {}", err))
         })?;
         let payload = serde_json::json!({
             "type": "file",
             "created_at": chrono::Utc::now().to_rfc3339(),
-            "ttl_ms": ttl_ms,
+            "ttl_ms": self.default_ttl(ttl_ms),
             "meta": meta,
         });
         let serialized = serde_json::to_string_pretty(&payload).map_err(|err| {
             ToolError::internal(format!("Failed to serialize cache entry: {}", err))
         })?;
-        atomic_write_text_file(self.entry_path(key)?, &format!("{}\n", serialized), 0o600)
+        atomic_write_text_file(self.entry_path(&key)?, &format!("{}\n", serialized), 0o600)
             .map_err(|err| ToolError::internal(format!("Failed to write cache entry: {}", err)))?;
-        self.bump_writes();
+        self.record_write(
+            &key,
+            serialized.len() as u64 + 1 + file_len(&data_path),
+            None,
+        );
         Ok(payload)
     }
 
-    pub fn remove(&self, key: &str) -> Result<(), ToolError> {
-        if let Ok(path) = self.entry_path(key) {
-            let _ = std::fs::remove_file(path);
+    // Indexes the new entry, then evicts least recently used entries until the namespace fits
+    // its budget again. The entry just written is kept even when it alone exceeds the budget.
+    fn record_write(&self, key: &str, bytes: u64, payload: Option<Value>) {
+        let mut evicted = Vec::new();
+        {
+            let mut store = self.lock();
+            let budget = Self::policy(&store, &self.namespace).max_bytes;
+            let slots = store.slots.entry(self.namespace.clone()).or_default();
+            slots.insert(
+                key.to_string(),
+                Slot {
+                    bytes,
+                    last_access_ms: now_ms(),
+                    payload,
+                },
+            );
+            let mut total: u64 = slots.values().map(|slot| slot.bytes).sum();
+            while total > budget {
+                let Some((oldest, _)) = slots
+                    .iter()
+                    .filter(|(name, _)| name.as_str() != key)
+                    .min_by_key(|(_, slot)| slot.last_access_ms)
+                    .map(|(name, slot)| (name.clone(), slot.bytes))
+                else {
+                    break;
+                };
+                if let Some(slot) = slots.remove(&oldest) {
+                    total -= slot.bytes;
+                    evicted.push(oldest);
+                }
+            }
+            let stats = store.stats.entry(self.namespace.clone()).or_default();
+            stats.writes += 1;
+            stats.evictions += evicted.len() as u64;
         }
-        if let Ok(path) = self.data_path(key) {
-            let _ = std::fs::remove_file(path);
+        for key in evicted {
+            self.remove_files(&key);
         }
-        Ok(())
     }
 
-    fn bump_hits(&self) {
-        if let Ok(mut stats) = self.stats.lock() {
-            stats.hits += 1;
+    fn forget_slot(&self, key: &str) {
+        if let Some(slots) = self.lock().slots.get_mut(&self.namespace) {
+            slots.remove(key);
         }
     }
 
-    fn bump_misses(&self) {
-        if let Ok(mut stats) = self.stats.lock() {
-            stats.misses += 1;
+    fn remove_files(&self, key: &str) {
+        if let Ok(path) = self.entry_path(key) {
+            let _ = std::fs::remove_file(&path);
+            let _ = std::fs::remove_file(path.with_extension("bin"));
         }
     }
 
-    fn bump_writes(&self) {
-        if let Ok(mut stats) = self.stats.lock() {
-            stats.writes += 1;
+    pub fn remove(&self, key: &str) -> Result<(), ToolError> {
+        let key = self.ensure_key(key)?;
+        self.forget_slot(&key);
+        self.remove_files(&key);
+        Ok(())
+    }
+
+    // Drops one key, one namespace, or (with neither) everything, on either backend.
+    pub fn invalidate(
+        &self,
+        namespace: Option<&str>,
+        key: Option<&str>,
+    ) -> Result<Value, ToolError> {
+        if let Some(namespace) = namespace {
+            self.ensure_namespace(namespace)?;
+        }
+        if let Some(key) = key {
+            let namespace = namespace.unwrap_or(&self.namespace);
+            let scoped = self.namespaced(namespace)?;
+            let key = scoped.ensure_key(key)?;
+            let existed = scoped
+                .lock()
+                .slots
+                .get(namespace)
+                .is_some_and(|slots| slots.contains_key(&key))
+                || scoped.entry_path(&key)?.exists();
+            scoped.remove(&key)?;
+            return Ok(serde_json::json!({
+                "success": true,
+                "namespace": namespace,
+                "key": key,
+                "removed": u64::from(existed),
+            }));
         }
+        let targets: Vec<&str> = match namespace {
+            Some(namespace) => vec![namespace],
+            None => NAMESPACES.iter().map(|policy| policy.name).collect(),
+        };
+        let mut removed = 0usize;
+        for name in &targets {
+            if let Some(slots) = self.lock().slots.get_mut(*name) {
+                removed += slots.len();
+                slots.clear();
+            }
+            let _ = std::fs::remove_dir_all(self.namespace_dir(name));
+        }
+        Ok(serde_json::json!({
+            "success": true,
+            "namespaces": targets,
+            "removed": removed,
+        }))
     }
 
-    fn bump_errors(&self) {
-        if let Ok(mut stats) = self.stats.lock() {
-            stats.errors += 1;
+    pub fn stats(&self) -> Value {
+        let store = self.lock();
+        let mut namespaces = serde_json::Map::new();
+        for policy in NAMESPACES {
+            let policy = Self::policy(&store, policy.name);
+            let slots = store.slots.get(policy.name);
+            let stats = store.stats.get(policy.name);
+            let in_memory = store.backend == CacheBackend::Memory || !policy.persist;
+            namespaces.insert(
+                policy.name.to_string(),
+                serde_json::json!({
+                    "storage": if in_memory { "memory" } else { "disk" },
+                    "entries": slots.map(|slots| slots.len()).unwrap_or(0),
+                    "bytes": slots.map(|slots| slots.values().map(|s| s.bytes).sum::<u64>()).unwrap_or(0),
+                    "max_bytes": policy.max_bytes,
+                    "default_ttl_ms": policy.ttl_ms,
+                    "hits": stats.map(|s| s.hits).unwrap_or(0),
+                    "misses": stats.map(|s| s.misses).unwrap_or(0),
+                    "writes": stats.map(|s| s.writes).unwrap_or(0),
+                    "errors": stats.map(|s| s.errors).unwrap_or(0),
+                    "evictions": stats.map(|s| s.evictions).unwrap_or(0),
+                    "corrupt_skipped": stats.map(|s| s.corrupt_skipped).unwrap_or(0),
+                }),
+            );
         }
+        serde_json::json!({
+            "backend": store.backend.as_str(),
+            "cache_dir": self.cache_dir.display().to_string(),
+            "namespaces": namespaces,
+        })
     }
+
+    fn bump(&self, update: impl FnOnce(&mut CacheStats)) {
+        let mut store = self.lock();
+        update(store.stats.entry(self.namespace.clone()).or_default());
+    }
+}
+
+// sha256 of the value with object keys sorted, so equal requests hash equally whatever their
+// key order.
+pub fn stable_key(input: &Value) -> String {
+    let payload = stable_stringify(input);
+    let mut hasher = Sha256::new();
+    hasher.update(payload.as_bytes());
+    hex::encode(hasher.finalize())
 }
 
 fn stable_stringify(value: &Value) -> String {
@@ -3,6 +3,7 @@ use crate::services::security::Security;
 use crate::utils::bundled_manifests::{
     bundled_capabilities_json, BUNDLED_CAPABILITIES_MANIFEST_URI,
 };
+use crate::utils::capability_discovery::add_manifest_entries;
 use crate::utils::paths::{resolve_capabilities_path, resolve_default_capabilities_path};
 use crate::utils::when_matcher::matches_when;
 use serde_json::Value;
@@ -10,7 +11,7 @@ use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::path::Path;
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 
 const CAPABILITY_SOURCE: &str = "manifest";
 const FILE_BACKED_MANIFEST_SOURCE: &str = "file_backed_manifest";
@@ -20,8 +21,9 @@ const UNCONFIGURED_MANIFEST_SOURCE: &str = "unconfigured_manifest";
 #[derive(Clone)]
 pub struct CapabilityService {
     _security: Arc<Security>,
+    default_path: Option<PathBuf>,
     manifest_path: PathBuf,
-    manifest: Arc<CapabilityManifest>,
+    manifest: Arc<RwLock<Arc<CapabilityManifest>>>,
 }
 
 #[derive(Clone)]
@@ -43,22 +45,55 @@ impl CapabilityService {
         )?);
         Ok(Self {
             _security: security,
+            default_path,
             manifest_path,
-            manifest,
+            manifest: Arc::new(RwLock::new(manifest)),
         })
     }
 
+    fn manifest(&self) -> Arc<CapabilityManifest> {
+        self.manifest
+            .read()
+            .unwrap_or_else(|err| err.into_inner())
+            .clone()
+    }
+
+    // Adds `records` to the manifest at manifest_path (created when missing) and reloads it.
+    // Names any loaded manifest already defines are left untouched and returned as skipped.
+    pub fn add_capabilities(
+        &self,
+        records: &serde_json::Map<String, Value>,
+    ) -> Result<(Vec<String>, Vec<String>), ToolError> {
+        let current = self.manifest();
+        let (added, skipped) =
+            add_manifest_entries(&self.manifest_path, "capabilities", records, |name| {
+                current.capabilities.contains_key(name)
+            })?;
+        if !added.is_empty() {
+            let reloaded = load_capability_manifest(
+                self.default_path.as_deref(),
+                Some(self.manifest_path.as_path()),
+            )?;
+            *self.manifest.write().unwrap_or_else(|err| err.into_inner()) = Arc::new(reloaded);
+        }
+        Ok((added, skipped))
+    }
+
+    pub fn manifest_path(&self) -> &Path {
+        self.manifest_path.as_path()
+    }
+
     pub fn manifest_metadata(&self) -> Value {
         serde_json::json!({
             "manifest_path": self.manifest_path_value(),
-            "manifest_source": self.manifest.source.clone(),
-            "manifest_version": self.manifest.version.clone().unwrap_or(Value::Null),
+            "manifest_source": self.manifest().source.clone(),
+            "manifest_version": self.manifest().version.clone().unwrap_or(Value::Null),
             "manifest_sha256": self.manifest_sha256_value(),
         })
     }
 
     fn manifest_path_value(&self) -> Value {
-        self.manifest
+        self.manifest()
             .path
             .as_ref()
             .map(|path| Value::String(path.to_string_lossy().to_string()))
@@ -66,7 +101,7 @@ impl CapabilityService {
     }
 
     fn manifest_sha256_value(&self) -> Value {
-        self.manifest
+        self.manifest()
             .sha256
             .as_ref()
             .map(|sha| Value::String(sha.clone()))
@@ -79,9 +114,9 @@ impl CapabilityService {
         map.entry("manifest_path".to_string())
             .or_insert_with(|| self.manifest_path_value());
         map.entry("manifest_source".to_string())
-            .or_insert_with(|| Value::String(self.manifest.source.clone()));
+            .or_insert_with(|| Value::String(self.manifest().source.clone()));
         map.entry("manifest_version".to_string())
-            .or_insert_with(|| self.manifest.version.clone().unwrap_or(Value::Null));
+            .or_insert_with(|| self.manifest().version.clone().unwrap_or(Value::Null));
         map.entry("manifest_sha256".to_string())
             .or_insert_with(|| self.manifest_sha256_value());
     }
@@ -109,18 +144,19 @@ impl CapabilityService {
             "action": action,
             "name": name,
             "manifest_path": self.manifest_path.display().to_string(),
-            "manifest_source": self.manifest.source.clone(),
-            "manifest_version": self.manifest.version.clone().unwrap_or(Value::Null),
+            "manifest_source": self.manifest().source.clone(),
+            "manifest_version": self.manifest().version.clone().unwrap_or(Value::Null),
             "manifest_sha256": self.manifest_sha256_value(),
         }))
     }
 
     pub fn list_capabilities(&self) -> Result<Value, ToolError> {
+        let manifest = self.manifest();
         let mut out = Vec::new();
-        let mut names: Vec<String> = self.manifest.capabilities.keys().cloned().collect();
+        let mut names: Vec<String> = manifest.capabilities.keys().cloned().collect();
         names.sort();
         for name in names {
-            let cap = self.manifest.capabilities.get(&name).ok_or_else(|| {
+            let cap = manifest.capabilities.get(&name).ok_or_else(|| {
                 ToolError::internal("Capability disappeared while listing".to_string())
             })?;
             out.push(self.hydrate_capability(&name, cap));
@@ -134,7 +170,8 @@ impl CapabilityService {
                 "Capability name must be a non-empty string",
             ));
         }
-        let entry = self.manifest.capabilities.get(name).ok_or_else(|| {
+        let manifest = self.manifest();
+        let entry = manifest.capabilities.get(name).ok_or_else(|| {
             ToolError::not_found(format!("Capability '{}' not found", name))
                 .with_hint("Use action=capability_list to see known capabilities.".to_string())
         })?;
@@ -147,11 +184,12 @@ impl CapabilityService {
                 "Intent type must be a non-empty string",
             ));
         }
+        let manifest = self.manifest();
         let mut matches = Vec::new();
-        if let Some(entry) = self.manifest.capabilities.get(intent_type) {
+        if let Some(entry) = manifest.capabilities.get(intent_type) {
             matches.push(self.hydrate_capability(intent_type, entry));
         }
-        for (name, cap) in &self.manifest.capabilities {
+        for (name, cap) in &manifest.capabilities {
             if name == intent_type {
                 continue;
             }
@@ -256,7 +294,7 @@ impl CapabilityService {
             .cloned()
             .unwrap_or(Value::Object(Default::default()));
         let mut scored = Vec::new();
-        for (name, capability) in &self.manifest.capabilities {
+        for (name, capability) in &self.manifest().capabilities {
             if !matches_when(
                 capability.get("when").unwrap_or(&Value::Null),
                 &context_value,
@@ -321,7 +359,7 @@ impl CapabilityService {
     pub fn families_index(&self) -> Result<Value, ToolError> {
         let mut families: HashMap<String, serde_json::Map<String, Value>> = HashMap::new();
 
-        for (name, capability) in &self.manifest.capabilities {
+        for (name, capability) in &self.manifest().capabilities {
             let family = capability_family(capability);
             let verb = capability_verb(capability);
             let intent = capability
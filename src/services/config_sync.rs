@@ -0,0 +1,479 @@
+use crate::errors::ToolError;
+use crate::services::alias::AliasService;
+use crate::services::preset::PresetService;
+use crate::services::profile::ProfileService;
+use crate::services::project::ProjectService;
+use crate::utils::feature_flags::is_allow_secret_export_enabled;
+use crate::utils::listing::ListFilters;
+use crate::utils::redact::is_sensitive_key;
+use serde_json::{Map, Value};
+
+pub const CONFIG_VERSION: u64 = 1;
+// config_export writes this in place of stored secret values; on config_apply it keeps whatever
+// is stored at the same path, so an exported document applies back as unchanged.
+pub const STORED_SECRET: &str = "<stored-secret>";
+// Apply order: profiles before the projects that name them, presets before aliases.
+const SECTIONS: &[&str] = &["profiles", "projects", "presets", "aliases"];
+const MAX_APPLY_PASSES: usize = 8;
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ApplyOptions {
+    // Deletes items of the document's sections that the document does not list.
+    pub prune: bool,
+    pub plan_only: bool,
+}
+
+pub struct ConfigSync<'a> {
+    pub profiles: &'a ProfileService,
+    pub projects: &'a ProjectService,
+    pub presets: &'a PresetService,
+    pub aliases: &'a AliasService,
+}
+
+fn is_ref(text: &str) -> bool {
+    text.trim_start().starts_with("ref:")
+}
+
+// Alias and preset templates (`{{token}}`) are filled per call and hold no value yet.
+fn is_literal_secret(text: &str) -> bool {
+    !text.is_empty() && !is_ref(text) && text != STORED_SECRET && !text.contains("{{")
+}
+
+fn strip_timestamps(value: Value) -> Value {
+    let mut value = value;
+    if let Value::Object(map) = &mut value {
+        map.remove("name");
+        map.remove("created_at");
+        map.remove("updated_at");
+    }
+    value
+}
+
+// Profiles compare with empty `data` / `secrets` filled in, so `{}` and a missing section match.
+fn normalize(section: &str, value: Value) -> Value {
+    let mut value = strip_timestamps(value);
+    if section == "profiles" {
+        if let Value::Object(map) = &mut value {
+            for key in ["data", "secrets"] {
+                if !map.get(key).is_some_and(|v| v.is_object()) {
+                    map.insert(key.to_string(), Value::Object(Map::new()));
+                }
+            }
+        }
+    }
+    value
+}
+
+// Paths of literal values under sensitive keys (profile `secrets` is one, so all of it counts).
+fn literal_secret_paths(value: &Value, path: &str, in_secrets: bool, out: &mut Vec<String>) {
+    match value {
+        Value::String(text) if in_secrets && is_literal_secret(text) => out.push(path.to_string()),
+        Value::Object(map) => {
+            for (key, child) in map {
+                let child_path = format!("{}.{}", path, key);
+                let sensitive = in_secrets || is_sensitive_key(key);
+                literal_secret_paths(child, &child_path, sensitive, out);
+            }
+        }
+        Value::Array(items) => {
+            for (idx, item) in items.iter().enumerate() {
+                literal_secret_paths(item, &format!("{}[{}]", path, idx), in_secrets, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn mask_secrets(value: &Value, path: &str, in_secrets: bool, masked: &mut Vec<String>) -> Value {
+    match value {
+        Value::String(text) if in_secrets && is_literal_secret(text) => {
+            masked.push(path.to_string());
+            Value::String(STORED_SECRET.to_string())
+        }
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, child)| {
+                    let child_path = format!("{}.{}", path, key);
+                    let sensitive = in_secrets || is_sensitive_key(key);
+                    (
+                        key.clone(),
+                        mask_secrets(child, &child_path, sensitive, masked),
+                    )
+                })
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .enumerate()
+                .map(|(idx, item)| {
+                    mask_secrets(item, &format!("{}[{}]", path, idx), in_secrets, masked)
+                })
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+// Replaces STORED_SECRET with the stored value at the same place; Err names the path without one.
+fn fill_stored(desired: &Value, current: Option<&Value>, path: &str) -> Result<Value, String> {
+    match desired {
+        Value::String(text) if text == STORED_SECRET => match current {
+            Some(value) if !value.is_null() => Ok(value.clone()),
+            _ => Err(path.to_string()),
+        },
+        Value::Object(map) => {
+            let mut out = Map::new();
+            for (key, child) in map {
+                let stored = current.and_then(|value| value.get(key));
+                out.insert(
+                    key.clone(),
+                    fill_stored(child, stored, &format!("{}.{}", path, key))?,
+                );
+            }
+            Ok(Value::Object(out))
+        }
+        Value::Array(items) => items
+            .iter()
+            .enumerate()
+            .map(|(idx, item)| {
+                let stored = current.and_then(|value| value.get(idx));
+                fill_stored(item, stored, &format!("{}[{}]", path, idx))
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(Value::Array),
+        other => Ok(other.clone()),
+    }
+}
+
+// `{key: value}` for the incoming fields plus `{key: null}` for stored ones to drop, which is
+// how set_profile deletes fields.
+fn replacing(desired: &Value, current: Option<&Value>) -> Value {
+    let mut out = desired.as_object().cloned().unwrap_or_default();
+    if let Some(current) = current.and_then(|v| v.as_object()) {
+        for key in current.keys() {
+            out.entry(key.clone()).or_insert(Value::Null);
+        }
+    }
+    Value::Object(out)
+}
+
+pub fn parse_document(document: &Value) -> Result<&Map<String, Value>, ToolError> {
+    let map = document
+        .as_object()
+        .ok_or_else(|| ToolError::invalid_params("config document must be an object"))?;
+    for key in map.keys() {
+        if key != "version" && !SECTIONS.contains(&key.as_str()) {
+            return Err(
+                ToolError::invalid_params(format!("Unknown config section '{}'", key))
+                    .with_hint(format!("Known sections: version, {}.", SECTIONS.join(", "))),
+            );
+        }
+    }
+    if let Some(version) = map.get("version") {
+        if version.as_u64() != Some(CONFIG_VERSION) {
+            return Err(ToolError::invalid_params(format!(
+                "config version must be {}, got {}",
+                CONFIG_VERSION, version
+            )));
+        }
+    }
+    for section in SECTIONS {
+        let Some(items) = map.get(*section) else {
+            continue;
+        };
+        let items = items.as_object().ok_or_else(|| {
+            ToolError::invalid_params(format!("{} must be an object keyed by name", section))
+        })?;
+        for (name, item) in items {
+            if name.trim().is_empty() || !item.is_object() {
+                return Err(ToolError::invalid_params(format!(
+                    "{}.{} must be an object with a non-empty name",
+                    section, name
+                )));
+            }
+        }
+    }
+
+    let mut literal = Vec::new();
+    literal_secret_paths(document, "", false, &mut literal);
+    literal.sort();
+    if !literal.is_empty() && !is_allow_secret_export_enabled() {
+        let paths: Vec<String> = literal
+            .iter()
+            .map(|path| path.trim_start_matches('.').to_string())
+            .collect();
+        return Err(ToolError::new(
+            crate::errors::ToolErrorKind::InvalidParams,
+            "RAW_SECRET_IN_CONFIG",
+            format!("config document holds {} literal secret value(s)", paths.len()),
+        )
+        .with_hint(format!(
+            "Use ref:env:<VAR> or ref:vault:kv2:<mount>/<path>#<key>, or {} to keep a stored value. INFRA_ALLOW_SECRET_EXPORT=1 accepts literals.",
+            STORED_SECRET
+        ))
+        .with_details(serde_json::json!({"paths": paths})));
+    }
+    Ok(map)
+}
+
+impl ConfigSync<'_> {
+    fn names(&self, section: &str) -> Result<Vec<String>, ToolError> {
+        let all = ListFilters::default();
+        let (items, key) = match section {
+            "profiles" => (self.profiles.list_profiles(None)?, None),
+            "projects" => (self.projects.list_projects(&all)?, Some("projects")),
+            "presets" => (self.presets.list_presets(&all)?, Some("presets")),
+            _ => (self.aliases.list_aliases(&all)?, Some("aliases")),
+        };
+        let items = match key {
+            Some(key) => items.get(key).cloned().unwrap_or(Value::Null),
+            None => items,
+        };
+        let mut names: Vec<String> = items
+            .as_array()
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|item| item.get("name").and_then(|v| v.as_str()))
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+        names.sort();
+        Ok(names)
+    }
+
+    fn current(&self, section: &str, name: &str) -> Result<Option<Value>, ToolError> {
+        let found = match section {
+            "profiles" => self.profiles.get_profile(name, None),
+            "projects" => self
+                .projects
+                .get_project(name)
+                .map(|v| v.get("project").cloned().unwrap_or(Value::Null)),
+            "presets" => self
+                .presets
+                .get_preset(name)
+                .map(|v| v.get("preset").cloned().unwrap_or(Value::Null)),
+            _ => self
+                .aliases
+                .get_alias(name)
+                .map(|v| v.get("alias").cloned().unwrap_or(Value::Null)),
+        };
+        match found {
+            Ok(value) => Ok(Some(normalize(section, value))),
+            Err(err) if err.kind == crate::errors::ToolErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn write(
+        &self,
+        section: &str,
+        name: &str,
+        desired: &Value,
+        current: Option<&Value>,
+    ) -> Result<(), ToolError> {
+        match section {
+            "profiles" => {
+                let config = serde_json::json!({
+                    "type": desired.get("type").cloned().unwrap_or(Value::Null),
+                    "data": replacing(&desired["data"], current.map(|c| &c["data"])),
+                    "secrets": replacing(&desired["secrets"], current.map(|c| &c["secrets"])),
+                });
+                self.profiles.set_profile(name, &config).map(|_| ())
+            }
+            "projects" => self.projects.set_project(name, desired).map(|_| ()),
+            "presets" => self.presets.set_preset(name, desired).map(|_| ()),
+            _ => self.aliases.set_alias(name, desired).map(|_| ()),
+        }
+    }
+
+    fn remove(&self, section: &str, name: &str) -> Result<(), ToolError> {
+        match section {
+            "profiles" => self.profiles.delete_profile(name),
+            "projects" => self.projects.delete_project(name),
+            "presets" => self.presets.delete_preset(name),
+            _ => self.aliases.delete_alias(name),
+        }
+        .map(|_| ())
+    }
+
+    // The current setup as a document; stored secrets other than refs become STORED_SECRET.
+    pub fn export(&self) -> Result<(Value, Vec<String>), ToolError> {
+        let mut document = Map::new();
+        document.insert("version".to_string(), Value::from(CONFIG_VERSION));
+        let mut masked = Vec::new();
+        for section in SECTIONS {
+            let mut items = Map::new();
+            for name in self.names(section)? {
+                let Some(mut item) = self.current(section, &name)? else {
+                    continue;
+                };
+                if *section == "profiles"
+                    && item["secrets"].as_object().is_some_and(|s| s.is_empty())
+                {
+                    if let Value::Object(map) = &mut item {
+                        map.remove("secrets");
+                    }
+                }
+                let path = format!("{}.{}", section, name);
+                items.insert(name, mask_secrets(&item, &path, false, &mut masked));
+            }
+            document.insert(section.to_string(), Value::Object(items));
+        }
+        masked.sort();
+        Ok((Value::Object(document), masked))
+    }
+
+    pub fn apply(&self, document: &Value, options: ApplyOptions) -> Result<Value, ToolError> {
+        let sections = parse_document(document)?;
+        let mut items = Vec::new();
+        for section in SECTIONS {
+            let Some(desired) = sections.get(*section).and_then(|v| v.as_object()) else {
+                continue;
+            };
+            let mut pending = Vec::new();
+            for (name, wanted) in desired {
+                let current = self.current(section, name)?;
+                let mut entry = serde_json::json!({"section": section, "name": name});
+                let filled =
+                    match fill_stored(wanted, current.as_ref(), &format!("{}.{}", section, name)) {
+                        Ok(filled) => normalize(section, filled),
+                        Err(path) => {
+                            entry["change"] = Value::String(
+                                if current.is_some() {
+                                    "update"
+                                } else {
+                                    "create"
+                                }
+                                .to_string(),
+                            );
+                            entry["status"] = Value::String("failed".to_string());
+                            entry["error"] =
+                                Value::String(format!("{} has no stored value to keep", path));
+                            items.push(entry);
+                            continue;
+                        }
+                    };
+                let change = match current.as_ref() {
+                    None => "create",
+                    Some(current) if *current == filled => "unchanged",
+                    Some(_) => "update",
+                };
+                entry["change"] = Value::String(change.to_string());
+                if change == "unchanged" {
+                    entry["status"] = Value::String("unchanged".to_string());
+                    items.push(entry);
+                } else {
+                    pending.push((entry, Some(filled), current));
+                }
+            }
+            if options.prune {
+                for name in self.names(section)? {
+                    if !desired.contains_key(&name) {
+                        let entry = serde_json::json!({"section": section, "name": name, "change": "delete"});
+                        pending.push((entry, None, None));
+                    }
+                }
+            }
+
+            if options.plan_only {
+                for (mut entry, _, _) in pending {
+                    entry["status"] = Value::String("planned".to_string());
+                    items.push(entry);
+                }
+                continue;
+            }
+            // Items may depend on each other (preset extends); retry failures while any pass
+            // makes progress.
+            for pass in 0..MAX_APPLY_PASSES {
+                let mut failed = Vec::new();
+                let before = pending.len();
+                for (mut entry, desired, current) in pending {
+                    let name = entry["name"].as_str().unwrap_or("").to_string();
+                    let outcome = match desired.as_ref() {
+                        Some(desired) => self.write(section, &name, desired, current.as_ref()),
+                        None => self.remove(section, &name),
+                    };
+                    match outcome {
+                        Ok(()) => {
+                            entry["status"] = Value::String("applied".to_string());
+                            items.push(entry);
+                        }
+                        Err(err) => {
+                            entry["error"] = Value::String(err.message);
+                            failed.push((entry, desired, current));
+                        }
+                    }
+                }
+                let progressed = failed.len() < before;
+                pending = failed;
+                if pending.is_empty() || !progressed || pass + 1 == MAX_APPLY_PASSES {
+                    break;
+                }
+            }
+            for (mut entry, _, _) in pending {
+                entry["status"] = Value::String("failed".to_string());
+                items.push(entry);
+            }
+        }
+
+        let count =
+            |field: &str, value: &str| items.iter().filter(|item| item[field] == value).count();
+        let failed = count("status", "failed");
+        Ok(serde_json::json!({
+            "success": failed == 0,
+            "plan_only": options.plan_only,
+            "prune": options.prune,
+            "summary": {
+                "create": count("change", "create"),
+                "update": count("change", "update"),
+                "unchanged": count("change", "unchanged"),
+                "delete": count("change", "delete"),
+                "failed": failed,
+            },
+            "items": items,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn literal_secrets_are_found_outside_refs_and_templates() {
+        let document = json!({
+            "profiles": {"db": {"type": "postgres", "data": {"host": "db"}, "secrets": {"user": "app", "password": "ref:env:DB_PASSWORD"}}},
+            "aliases": {"deploy": {"tool": "api", "args": {"token": "{{token}}", "api_key": "abc"}}},
+        });
+        let mut paths = Vec::new();
+        literal_secret_paths(&document, "", false, &mut paths);
+        assert_eq!(
+            paths,
+            vec![".aliases.deploy.args.api_key", ".profiles.db.secrets.user"]
+        );
+    }
+
+    #[test]
+    fn stored_placeholders_take_the_current_value() {
+        let current = json!({"secrets": {"password": "s3cret"}});
+        let filled = fill_stored(
+            &json!({"secrets": {"password": STORED_SECRET}}),
+            Some(&current),
+            "p",
+        )
+        .expect("filled");
+        assert_eq!(filled, current);
+        assert_eq!(
+            fill_stored(
+                &json!({"secrets": {"token": STORED_SECRET}}),
+                Some(&current),
+                "p"
+            ),
+            Err("p.secrets.token".to_string())
+        );
+    }
+}
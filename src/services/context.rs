@@ -1,8 +1,19 @@
-use crate::errors::ToolError;
+use crate::errors::{ToolError, ToolErrorKind};
 use crate::utils::fs_atomic::path_exists;
 use serde_json::Value;
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+pub const CONTEXT_SOURCES: &[&str] = &[
+    "repo",
+    "profiles",
+    "projects",
+    "postgres",
+    "jobs",
+    "artifacts",
+];
 
 const MARKERS: &[(&str, &[&str])] = &[
     ("node", &["package.json", "pnpm-lock.yaml", "yarn.lock"]),
@@ -44,12 +55,145 @@ const MARKERS: &[(&str, &[&str])] = &[
     ("ci", &[".github/workflows", "gitlab-ci.yml", "Jenkinsfile"]),
 ];
 
+#[derive(Clone, Debug)]
+struct SourceSnapshot {
+    data: Value,
+    fingerprint: Option<String>,
+    refreshed_at: Option<chrono::DateTime<chrono::Utc>>,
+    status: String,
+    error: Option<String>,
+    duration_ms: u64,
+}
+
+fn fingerprint(data: &Value) -> String {
+    let raw = serde_json::to_vec(data).unwrap_or_default();
+    hex::encode(Sha256::digest(&raw))[..16].to_string()
+}
+
 #[derive(Clone, Default)]
-pub struct ContextService;
+pub struct ContextService {
+    sources: Arc<Mutex<HashMap<String, SourceSnapshot>>>,
+}
 
 impl ContextService {
     pub fn new() -> Result<Self, ToolError> {
-        Ok(Self)
+        Ok(Self::default())
+    }
+
+    pub fn record_source(
+        &self,
+        name: &str,
+        outcome: Result<Value, ToolError>,
+        duration_ms: u64,
+    ) -> Value {
+        let mut sources = self.sources.lock().unwrap_or_else(|err| err.into_inner());
+        let previous = sources.get(name).cloned();
+        let previous_fingerprint = previous.as_ref().and_then(|snap| snap.fingerprint.clone());
+        let snapshot = match outcome {
+            Ok(data) => SourceSnapshot {
+                fingerprint: Some(fingerprint(&data)),
+                data,
+                refreshed_at: Some(chrono::Utc::now()),
+                status: "ok".to_string(),
+                error: None,
+                duration_ms,
+            },
+            Err(err) => {
+                let status = match err.kind {
+                    ToolErrorKind::Timeout => "timeout",
+                    ToolErrorKind::NotFound => "unavailable",
+                    _ => "error",
+                };
+                let mut snapshot = previous.clone().unwrap_or(SourceSnapshot {
+                    data: Value::Null,
+                    fingerprint: None,
+                    refreshed_at: None,
+                    status: String::new(),
+                    error: None,
+                    duration_ms,
+                });
+                snapshot.status = status.to_string();
+                snapshot.error = Some(err.message);
+                snapshot.duration_ms = duration_ms;
+                snapshot
+            }
+        };
+        let changed = snapshot.status == "ok" && snapshot.fingerprint != previous_fingerprint;
+        let report = serde_json::json!({
+            "source": name,
+            "status": snapshot.status,
+            "changed": changed,
+            "fingerprint": snapshot.fingerprint,
+            "last_refreshed_at": snapshot.refreshed_at.map(|at| at.to_rfc3339()),
+            "duration_ms": duration_ms,
+            "error": snapshot.error,
+        });
+        sources.insert(name.to_string(), snapshot);
+        report
+    }
+
+    pub fn stale_sources(&self, names: &[String], max_age_ms: u64) -> Vec<String> {
+        let sources = self.sources.lock().unwrap_or_else(|err| err.into_inner());
+        let now = chrono::Utc::now();
+        names
+            .iter()
+            .filter(|name| {
+                match sources
+                    .get(name.as_str())
+                    .and_then(|snap| snap.refreshed_at)
+                {
+                    Some(at) => (now - at).num_milliseconds().max(0) as u64 > max_age_ms,
+                    None => true,
+                }
+            })
+            .cloned()
+            .collect()
+    }
+
+    pub fn source_freshness(
+        &self,
+        names: &[String],
+        max_age_ms: Option<u64>,
+        include_data: bool,
+    ) -> Value {
+        let sources = self.sources.lock().unwrap_or_else(|err| err.into_inner());
+        let now = chrono::Utc::now();
+        let mut out = serde_json::Map::new();
+        for name in names {
+            let entry = match sources.get(name.as_str()) {
+                Some(snap) => {
+                    let age_ms = snap
+                        .refreshed_at
+                        .map(|at| (now - at).num_milliseconds().max(0));
+                    let stale = match (age_ms, max_age_ms) {
+                        (None, _) => true,
+                        (Some(age), Some(max)) => age as u64 > max,
+                        (Some(_), None) => false,
+                    };
+                    let mut entry = serde_json::json!({
+                        "status": snap.status,
+                        "last_refreshed_at": snap.refreshed_at.map(|at| at.to_rfc3339()),
+                        "age_ms": age_ms,
+                        "stale": stale,
+                        "fingerprint": snap.fingerprint,
+                        "duration_ms": snap.duration_ms,
+                        "error": snap.error,
+                    });
+                    if include_data {
+                        entry["data"] = snap.data.clone();
+                    }
+                    entry
+                }
+                None => serde_json::json!({
+                    "status": "missing",
+                    "last_refreshed_at": Value::Null,
+                    "age_ms": Value::Null,
+                    "stale": true,
+                }),
+            };
+            out.insert(name.clone(), entry);
+        }
+        Value::Object(out)
     }
 
     async fn detect_markers(&self, root: &Path) -> (HashMap<String, bool>, HashMap<String, bool>) {
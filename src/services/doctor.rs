@@ -0,0 +1,535 @@
+use crate::errors::{ToolError, ToolErrorKind};
+use crate::services::logger::Logger;
+use crate::services::profile::ProfileService;
+use crate::utils::artifacts::resolve_context_root;
+use crate::utils::feature_flags::{
+    is_allow_secret_export_enabled, is_dry_run_enabled, is_dry_run_force_allowed,
+    is_readonly_enabled, is_unsafe_local_enabled, FlagKind, FLAGS,
+};
+use crate::utils::paths::{
+    resolve_audit_path, resolve_context_repo_root, resolve_profile_base_dir, resolve_profiles_path,
+    resolve_store_db_path,
+};
+use serde_json::Value;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::Path;
+use std::time::Duration;
+
+const PROBE_TIMEOUT_MS: u64 = 2_000;
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Ok,
+    Warn,
+    Error,
+    Fatal,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Ok => "ok",
+            Severity::Warn => "warn",
+            Severity::Error => "error",
+            Severity::Fatal => "fatal",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct DoctorCheck {
+    pub id: String,
+    pub severity: Severity,
+    pub message: String,
+    pub hint: Option<String>,
+    pub details: Value,
+}
+
+impl DoctorCheck {
+    fn ok(id: &str, message: impl Into<String>, details: Value) -> Self {
+        Self {
+            id: id.to_string(),
+            severity: Severity::Ok,
+            message: message.into(),
+            hint: None,
+            details,
+        }
+    }
+
+    fn problem(
+        id: &str,
+        severity: Severity,
+        message: impl Into<String>,
+        hint: impl Into<String>,
+        details: Value,
+    ) -> Self {
+        Self {
+            id: id.to_string(),
+            severity,
+            message: message.into(),
+            hint: Some(hint.into()),
+            details,
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        serde_json::json!({
+            "id": self.id,
+            "severity": self.severity.as_str(),
+            "message": self.message,
+            "hint": self.hint,
+            "details": self.details,
+        })
+    }
+}
+
+// Creates the directory if needed and round-trips a probe file, which is the only reliable
+// writability test (permission bits lie under ACLs, read-only mounts and foreign owners).
+fn probe_dir_writable(dir: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let probe = dir.join(format!(".infra-doctor-{}", uuid::Uuid::new_v4()));
+    std::fs::write(&probe, b"ok")?;
+    std::fs::remove_file(&probe)
+}
+
+fn check_state_dir() -> DoctorCheck {
+    let base = resolve_profile_base_dir();
+    let details = serde_json::json!({"path": base});
+    match probe_dir_writable(&base) {
+        Ok(()) => DoctorCheck::ok("state_dir", "State directory is writable", details),
+        Err(err) => DoctorCheck::problem(
+            "state_dir",
+            Severity::Fatal,
+            format!(
+                "State directory {} is not writable: {}",
+                base.display(),
+                err
+            ),
+            "Fix the directory permissions or point INFRA_PROFILES_DIR at a writable directory.",
+            details,
+        ),
+    }
+}
+
+fn check_profile_storage() -> DoctorCheck {
+    let db_path = resolve_store_db_path();
+    if db_path.exists() {
+        if let Err(err) = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&db_path)
+        {
+            return DoctorCheck::problem(
+                "profile_storage",
+                Severity::Fatal,
+                format!(
+                    "Store database {} is not readable and writable: {}",
+                    db_path.display(),
+                    err
+                ),
+                "Fix the file permissions or set INFRA_STORE_DB_PATH to a writable location.",
+                serde_json::json!({"path": db_path}),
+            );
+        }
+    }
+    let profiles_path = resolve_profiles_path();
+    let details = serde_json::json!({"store_db": db_path, "profiles_file": profiles_path});
+    if !profiles_path.exists() {
+        return DoctorCheck::ok("profile_storage", "Profile storage is accessible", details);
+    }
+    let parsed = std::fs::read_to_string(&profiles_path)
+        .map_err(|err| format!("cannot be read: {}", err))
+        .and_then(|raw| {
+            serde_json::from_str::<Value>(&raw).map_err(|err| format!("is not valid JSON: {}", err))
+        })
+        .and_then(|value| {
+            if value.is_object() {
+                Ok(())
+            } else {
+                Err("must be a JSON object".to_string())
+            }
+        });
+    match parsed {
+        Ok(()) => DoctorCheck::ok("profile_storage", "Profile storage is accessible", details),
+        Err(reason) => DoctorCheck::problem(
+            "profile_storage",
+            Severity::Fatal,
+            format!(
+                "Legacy profiles file {} {}",
+                profiles_path.display(),
+                reason
+            ),
+            "Repair or move the file away; it is imported into the store database once on startup.",
+            details,
+        ),
+    }
+}
+
+fn check_context_root() -> DoctorCheck {
+    let Some(root) = resolve_context_repo_root().or_else(resolve_context_root) else {
+        return DoctorCheck::ok(
+            "context_root",
+            "No context repo root configured; artifacts stay inline",
+            Value::Null,
+        );
+    };
+    let details = serde_json::json!({"path": root});
+    if !root.is_dir() {
+        return DoctorCheck::problem(
+            "context_root",
+            Severity::Error,
+            format!("Context repo root {} does not exist", root.display()),
+            "Create the directory or unset INFRA_CONTEXT_REPO_ROOT; artifact spills are skipped until then.",
+            details,
+        );
+    }
+    match probe_dir_writable(&root) {
+        Ok(()) => DoctorCheck::ok("context_root", "Context repo root is writable", details),
+        Err(err) => DoctorCheck::problem(
+            "context_root",
+            Severity::Error,
+            format!(
+                "Context repo root {} is not writable: {}",
+                root.display(),
+                err
+            ),
+            "Fix the directory permissions so artifacts can be written.",
+            details,
+        ),
+    }
+}
+
+fn check_audit_log() -> DoctorCheck {
+    let path = resolve_audit_path();
+    let details = serde_json::json!({"path": path});
+    let result = if path.exists() {
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .map(|_| ())
+    } else {
+        path.parent().map(probe_dir_writable).unwrap_or(Ok(()))
+    };
+    match result {
+        Ok(()) => DoctorCheck::ok("audit_log", "Audit log is appendable", details),
+        Err(err) => DoctorCheck::problem(
+            "audit_log",
+            Severity::Error,
+            format!("Audit log {} is not appendable: {}", path.display(), err),
+            "Fix the permissions or set INFRA_AUDIT_PATH; tool calls are not audited until then.",
+            details,
+        ),
+    }
+}
+
+fn check_feature_flags() -> Vec<DoctorCheck> {
+    let mut checks = Vec::new();
+    for flag in FLAGS {
+        let (Some(raw), Some((key, _))) = (flag.invalid_value(), flag.raw()) else {
+            continue;
+        };
+        let check = match flag.kind {
+            FlagKind::Bool(_) => DoctorCheck::problem(
+                "feature_flags",
+                Severity::Warn,
+                format!(
+                    "{}={} is not a recognized boolean and is treated as off",
+                    key, raw
+                ),
+                "Use 1/true/yes/on to enable or 0/false/no/off to disable.",
+                serde_json::json!({"flag": key}),
+            ),
+            _ => DoctorCheck::problem(
+                "feature_flags",
+                Severity::Warn,
+                format!(
+                    "{}={} is not a non-negative integer and the default is used",
+                    key, raw
+                ),
+                "Set a plain number of bytes, milliseconds or entries.",
+                serde_json::json!({"flag": key, "default": flag.number()}),
+            ),
+        };
+        checks.push(check);
+    }
+    if is_allow_secret_export_enabled() && !is_unsafe_local_enabled() {
+        checks.push(DoctorCheck::problem(
+            "feature_flags",
+            Severity::Warn,
+            "INFRA_ALLOW_SECRET_EXPORT is enabled without INFRA_UNSAFE_LOCAL; secret values can be returned to any caller",
+            "Enable secret export only for local unsafe sessions, or unset INFRA_ALLOW_SECRET_EXPORT.",
+            serde_json::json!({"flag": "INFRA_ALLOW_SECRET_EXPORT"}),
+        ));
+    }
+    if is_readonly_enabled() && is_unsafe_local_enabled() {
+        checks.push(DoctorCheck::problem(
+            "feature_flags",
+            Severity::Warn,
+            "INFRA_UNSAFE_LOCAL is enabled together with INFRA_READONLY; local writes stay denied",
+            "Drop one of the flags so the intended mode is explicit.",
+            serde_json::json!({"flag": "INFRA_UNSAFE_LOCAL"}),
+        ));
+    }
+    if is_dry_run_force_allowed() && !is_dry_run_enabled() {
+        checks.push(DoctorCheck::problem(
+            "feature_flags",
+            Severity::Warn,
+            "INFRA_DRY_RUN_ALLOW_FORCE is enabled without INFRA_DRY_RUN and has no effect",
+            "Enable INFRA_DRY_RUN, or unset INFRA_DRY_RUN_ALLOW_FORCE.",
+            serde_json::json!({"flag": "INFRA_DRY_RUN_ALLOW_FORCE"}),
+        ));
+    }
+    if checks.is_empty() {
+        checks.push(DoctorCheck::ok(
+            "feature_flags",
+            "Feature flags are consistent",
+            serde_json::json!({
+                "unsafe_local": is_unsafe_local_enabled(),
+                "allow_secret_export": is_allow_secret_export_enabled(),
+                "readonly": is_readonly_enabled(),
+                "dry_run": is_dry_run_enabled(),
+            }),
+        ));
+    }
+    checks
+}
+
+// Filesystem and flag checks; cheap enough to run on every startup.
+pub fn local_checks() -> Vec<DoctorCheck> {
+    let mut checks = vec![
+        check_state_dir(),
+        check_profile_storage(),
+        check_context_root(),
+        check_audit_log(),
+    ];
+    checks.extend(check_feature_flags());
+    checks
+}
+
+fn url_endpoint(raw: &str, default_port: u16) -> Option<(String, u16)> {
+    let parsed = url::Url::parse(raw).ok()?;
+    let host = parsed.host_str()?.to_string();
+    Some((host, parsed.port_or_known_default().unwrap_or(default_port)))
+}
+
+fn profile_endpoint(profile_type: &str, data: &Value) -> Option<(String, u16)> {
+    let text = |key: &str| data.get(key).and_then(|v| v.as_str()).map(str::trim);
+    let port = |default: u16| {
+        data.get("port")
+            .and_then(|v| {
+                v.as_u64()
+                    .or_else(|| v.as_str().and_then(|s| s.parse().ok()))
+            })
+            .and_then(|v| u16::try_from(v).ok())
+            .unwrap_or(default)
+    };
+    match profile_type {
+        "ssh" => text("host").map(|host| (host.to_string(), port(22))),
+        "postgresql" => text("connection_url")
+            .and_then(|url| url_endpoint(url, 5432))
+            .or_else(|| text("host").map(|host| (host.to_string(), port(5432)))),
+        "api" => text("base_url").and_then(|url| url_endpoint(url, 443)),
+        "vault" => text("addr").and_then(|url| url_endpoint(url, 8200)),
+        _ => None,
+    }
+    .filter(|(host, _)| !host.is_empty())
+}
+
+fn probe_tcp(host: &str, port: u16, timeout: Duration) -> Result<(), String> {
+    let addrs: Vec<_> = (host, port)
+        .to_socket_addrs()
+        .map_err(|err| format!("cannot resolve {}: {}", host, err))?
+        .collect();
+    let mut last = format!("no addresses for {}", host);
+    for addr in addrs {
+        match TcpStream::connect_timeout(&addr, timeout) {
+            Ok(_) => return Ok(()),
+            Err(err) => last = err.to_string(),
+        }
+    }
+    Err(last)
+}
+
+// Plain TCP reachability per stored profile; credentials are not exercised.
+pub fn profile_probes(profile_service: &ProfileService) -> Vec<DoctorCheck> {
+    let timeout = Duration::from_millis(PROBE_TIMEOUT_MS);
+    let profiles = match profile_service.list_profiles(None) {
+        Ok(Value::Array(items)) => items,
+        Ok(_) => Vec::new(),
+        Err(err) => {
+            return vec![DoctorCheck::problem(
+                "profile_probe",
+                Severity::Error,
+                format!("Failed to list profiles: {}", err.message),
+                "Run doctor again after fixing profile storage.",
+                Value::Null,
+            )]
+        }
+    };
+    let mut checks = Vec::new();
+    for profile in profiles {
+        let name = profile.get("name").and_then(|v| v.as_str()).unwrap_or("");
+        let profile_type = profile.get("type").and_then(|v| v.as_str()).unwrap_or("");
+        let Some((host, port)) = profile_endpoint(profile_type, &profile["data"]) else {
+            continue;
+        };
+        let details = serde_json::json!({
+            "profile": name,
+            "type": profile_type,
+            "host": host,
+            "port": port,
+        });
+        checks.push(match probe_tcp(&host, port, timeout) {
+            Ok(()) => DoctorCheck::ok(
+                "profile_probe",
+                format!("Profile '{}' is reachable", name),
+                details,
+            ),
+            Err(reason) => DoctorCheck::problem(
+                "profile_probe",
+                Severity::Warn,
+                format!(
+                    "Profile '{}' ({}:{}) is unreachable: {}",
+                    name, host, port, reason
+                ),
+                "Check network access and the profile host/port; calls using this profile will fail.",
+                details,
+            ),
+        });
+    }
+    checks
+}
+
+pub fn worst_severity(checks: &[DoctorCheck]) -> Severity {
+    checks
+        .iter()
+        .map(|check| check.severity)
+        .max()
+        .unwrap_or(Severity::Ok)
+}
+
+pub fn summary_line(checks: &[DoctorCheck]) -> String {
+    let count = |severity: Severity| checks.iter().filter(|c| c.severity == severity).count();
+    format!(
+        "startup check {}: {} ok, {} warn, {} error, {} fatal",
+        worst_severity(checks).as_str(),
+        count(Severity::Ok),
+        count(Severity::Warn),
+        count(Severity::Error),
+        count(Severity::Fatal)
+    )
+}
+
+pub fn build_report(checks: &[DoctorCheck]) -> Value {
+    let status = worst_severity(checks);
+    serde_json::json!({
+        "success": true,
+        "status": status.as_str(),
+        "healthy": status <= Severity::Warn,
+        "summary": summary_line(checks),
+        "checks": checks.iter().map(DoctorCheck::to_json).collect::<Vec<_>>(),
+    })
+}
+
+// Fatal startup problems stop initialization with the full doctor report attached, instead
+// of surfacing later as unrelated tool errors.
+pub fn ensure_startable(checks: &[DoctorCheck]) -> Result<(), ToolError> {
+    let Some(fatal) = checks
+        .iter()
+        .find(|check| check.severity == Severity::Fatal)
+    else {
+        return Ok(());
+    };
+    let mut err = ToolError::new(
+        ToolErrorKind::Internal,
+        "STARTUP_CHECK_FAILED",
+        format!("Startup check failed: {}", fatal.message),
+    )
+    .with_details(build_report(checks));
+    if let Some(hint) = fatal.hint.clone() {
+        err = err.with_hint(hint);
+    }
+    Err(err)
+}
+
+// One line per startup; the problems are listed only when something is not ok.
+pub fn log_startup(logger: &Logger, checks: &[DoctorCheck]) {
+    let summary = summary_line(checks);
+    if worst_severity(checks) == Severity::Ok {
+        logger.info(&summary, None);
+        return;
+    }
+    let problems: Vec<_> = checks
+        .iter()
+        .filter(|check| check.severity != Severity::Ok)
+        .map(|check| check.message.clone())
+        .collect();
+    logger.warn(&summary, Some(&serde_json::json!({"problems": problems})));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profile_endpoints_follow_each_profile_type() {
+        assert_eq!(
+            profile_endpoint("ssh", &serde_json::json!({"host": "web-1", "port": "2222"})),
+            Some(("web-1".to_string(), 2222))
+        );
+        assert_eq!(
+            profile_endpoint(
+                "postgresql",
+                &serde_json::json!({"connection_url": "postgres://app@db.internal/app"})
+            ),
+            Some(("db.internal".to_string(), 5432))
+        );
+        assert_eq!(
+            profile_endpoint(
+                "api",
+                &serde_json::json!({"base_url": "http://api.local:8080/v1"})
+            ),
+            Some(("api.local".to_string(), 8080))
+        );
+        assert_eq!(profile_endpoint("env", &serde_json::json!({})), None);
+    }
+
+    #[test]
+    fn report_status_is_the_worst_severity() {
+        let checks = vec![
+            DoctorCheck::ok("a", "fine", Value::Null),
+            DoctorCheck::problem("b", Severity::Warn, "meh", "fix it", Value::Null),
+        ];
+        let report = build_report(&checks);
+        assert_eq!(report["status"], "warn");
+        assert_eq!(report["healthy"], true);
+        assert_eq!(
+            report["summary"],
+            "startup check warn: 1 ok, 1 warn, 0 error, 0 fatal"
+        );
+        assert_eq!(report["checks"][1]["hint"], "fix it");
+    }
+
+    #[test]
+    fn only_fatal_checks_stop_startup() {
+        let mut checks = vec![DoctorCheck::problem(
+            "b",
+            Severity::Error,
+            "bad",
+            "fix it",
+            Value::Null,
+        )];
+        assert!(ensure_startable(&checks).is_ok());
+        checks.push(DoctorCheck::problem(
+            "c",
+            Severity::Fatal,
+            "store dir is read-only",
+            "make it writable",
+            Value::Null,
+        ));
+        let err = ensure_startable(&checks).expect_err("fatal");
+        assert_eq!(err.code, "STARTUP_CHECK_FAILED");
+        assert_eq!(err.message, "Startup check failed: store dir is read-only");
+        assert_eq!(err.hint.as_deref(), Some("make it writable"));
+        assert_eq!(err.details.as_ref().unwrap()["status"], "fatal");
+    }
+}
@@ -15,6 +15,118 @@ fn safe_timestamp() -> String {
     chrono::Utc::now().to_rfc3339().replace([':', '.'], "-")
 }
 
+fn optional_string(input: &Value, key: &str) -> Result<Option<String>, ToolError> {
+    match input.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(text)) if !text.trim().is_empty() => Ok(Some(text.trim().to_string())),
+        Some(_) => Err(ToolError::invalid_params(format!(
+            "{} must be a non-empty string",
+            key
+        ))),
+    }
+}
+
+fn normalize_artifact_ref(value: &Value) -> Result<Value, ToolError> {
+    let (raw, mut extra) = match value {
+        Value::String(text) => (text.trim().to_string(), serde_json::Map::new()),
+        Value::Object(map) => {
+            let raw = map
+                .get("uri")
+                .or_else(|| map.get("rel"))
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .trim()
+                .to_string();
+            (raw, map.clone())
+        }
+        _ => (String::new(), serde_json::Map::new()),
+    };
+    let rel = raw.trim_start_matches("artifact://").trim().to_string();
+    if rel.is_empty() || rel.starts_with('/') || rel.split('/').any(|segment| segment == "..") {
+        return Err(ToolError::invalid_params(
+            "artifacts entries must reference an artifact uri or rel path",
+        )
+        .with_hint(
+            "Example: { artifacts: ['artifact://runs/<trace>/tool_calls/<span>/result.json'] }"
+                .to_string(),
+        )
+        .with_details(serde_json::json!({ "artifact": value })));
+    }
+    extra.insert(
+        "uri".to_string(),
+        Value::String(format!("artifact://{}", rel)),
+    );
+    extra.insert("rel".to_string(), Value::String(rel));
+    Ok(Value::Object(extra))
+}
+
+fn normalize_assertion(value: &Value, index: usize) -> Result<Value, ToolError> {
+    let mut map = value.as_object().cloned().ok_or_else(|| {
+        ToolError::invalid_params(format!("assertions[{}] must be an object", index))
+    })?;
+    let check = map
+        .get("check")
+        .and_then(|v| v.as_str())
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .ok_or_else(|| {
+            ToolError::invalid_params(format!("assertions[{}].check is required", index)).with_hint(
+                "Example: { check: 'http_status', expected: 200, actual: 200, pass: true }"
+                    .to_string(),
+            )
+        })?
+        .to_string();
+    map.insert("check".to_string(), Value::String(check));
+    if !map.get("pass").map(|v| v.is_boolean()).unwrap_or(false) {
+        let pass = match (map.get("expected"), map.get("actual")) {
+            (Some(expected), Some(actual)) => expected == actual,
+            _ => false,
+        };
+        map.insert("pass".to_string(), Value::Bool(pass));
+    }
+    if let Some(target) = map.get("target") {
+        if !target.is_object() {
+            return Err(ToolError::invalid_params(format!(
+                "assertions[{}].target must be an object",
+                index
+            )));
+        }
+    }
+    Ok(Value::Object(map))
+}
+
+fn collect_artifact_uris(value: &Value, out: &mut Vec<Value>) {
+    match value {
+        Value::Object(map) => {
+            if let Some(uri) = map.get("uri").and_then(|v| v.as_str()) {
+                if uri.starts_with("artifact://") && !out.iter().any(|item| item["uri"] == uri) {
+                    let mut entry = serde_json::Map::new();
+                    entry.insert("uri".to_string(), Value::String(uri.to_string()));
+                    if let Some(bytes) = map.get("bytes") {
+                        entry.insert("bytes".to_string(), bytes.clone());
+                    }
+                    out.push(Value::Object(entry));
+                }
+            }
+            for entry in map.values() {
+                collect_artifact_uris(entry, out);
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                collect_artifact_uris(item, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+pub fn artifact_refs_in(value: &Value) -> Vec<Value> {
+    let mut out = Vec::new();
+    collect_artifact_uris(value, &mut out);
+    out
+}
+
 #[derive(Clone)]
 pub struct EvidenceService {
     logger: Logger,
@@ -51,6 +163,79 @@ impl EvidenceService {
         Ok(serde_json::json!({"id": filename, "path": full_path}))
     }
 
+    pub fn build_record(&self, input: &Value) -> Result<Value, ToolError> {
+        if !input.is_object() {
+            return Err(ToolError::invalid_params(
+                "evidence record must be an object",
+            ));
+        }
+        let artifacts = match input.get("artifacts") {
+            None | Some(Value::Null) => Vec::new(),
+            Some(Value::Array(items)) => items
+                .iter()
+                .map(normalize_artifact_ref)
+                .collect::<Result<Vec<_>, _>>()?,
+            Some(_) => return Err(ToolError::invalid_params("artifacts must be an array")),
+        };
+        let assertions = match input.get("assertions") {
+            None | Some(Value::Null) => Vec::new(),
+            Some(Value::Array(items)) => items
+                .iter()
+                .enumerate()
+                .map(|(index, item)| normalize_assertion(item, index))
+                .collect::<Result<Vec<_>, _>>()?,
+            Some(_) => return Err(ToolError::invalid_params("assertions must be an array")),
+        };
+        let mut trace_ids: Vec<String> = Vec::new();
+        if let Some(trace_id) = optional_string(input, "trace_id")? {
+            trace_ids.push(trace_id);
+        }
+        match input.get("trace_ids") {
+            None | Some(Value::Null) => {}
+            Some(Value::Array(items)) => {
+                for item in items {
+                    let text = item
+                        .as_str()
+                        .map(|s| s.trim())
+                        .filter(|s| !s.is_empty())
+                        .ok_or_else(|| {
+                            ToolError::invalid_params("trace_ids must be non-empty strings")
+                        })?;
+                    if !trace_ids.iter().any(|existing| existing == text) {
+                        trace_ids.push(text.to_string());
+                    }
+                }
+            }
+            Some(_) => return Err(ToolError::invalid_params("trace_ids must be an array")),
+        }
+        let passed = assertions
+            .iter()
+            .all(|item| item.get("pass").and_then(|v| v.as_bool()).unwrap_or(false));
+        Ok(serde_json::json!({
+            "kind": "record",
+            "capability": optional_string(input, "capability")?,
+            "intent": optional_string(input, "intent")?,
+            "summary": optional_string(input, "summary")?,
+            "trace_ids": trace_ids,
+            "artifacts": artifacts,
+            "assertions": assertions,
+            "passed": passed,
+            "data": input.get("data").cloned().unwrap_or(Value::Null),
+            "captured_at": chrono::Utc::now().to_rfc3339(),
+        }))
+    }
+
+    pub fn create_record(&self, input: &Value) -> Result<Value, ToolError> {
+        let record = self.build_record(input)?;
+        let saved = self.save_evidence(&record)?;
+        Ok(serde_json::json!({
+            "success": true,
+            "id": saved.get("id").cloned().unwrap_or(Value::Null),
+            "path": saved.get("path").cloned().unwrap_or(Value::Null),
+            "record": record,
+        }))
+    }
+
     pub fn list_evidence(&self) -> Result<Vec<String>, ToolError> {
         let mut entries = Vec::new();
         if !self.base_dir.exists() {
@@ -1,17 +1,101 @@
-use crate::errors::ToolError;
+use crate::errors::{ToolError, ToolErrorKind};
 use crate::services::logger::Logger;
 use crate::services::store_db::{StoreDb, StoreRecord};
+use crate::utils::artifacts::{build_run_file_ref, resolve_artifact_path, resolve_context_root};
+use crate::utils::feature_flags;
 use crate::utils::paths::resolve_jobs_path;
 use serde_json::Value;
 use std::cmp::Reverse;
 use std::collections::HashMap;
+use std::io::Write;
+use std::path::PathBuf;
 use std::sync::{
     atomic::{AtomicBool, Ordering},
-    Arc, RwLock,
+    Arc, Mutex, RwLock,
 };
 
 const NAMESPACE: &str = "jobs";
 
+// Tools whose jobs run inside this process (see `start_local`).
+pub const LOCAL_JOB_TOOLS: &[&str] = &["local", "pipeline", "ssh_watch"];
+
+// In-memory half of a local job: the store keeps its state, but stopping it needs the task
+// and, for commands, the child's process group.
+struct LocalTask {
+    abort: tokio::task::AbortHandle,
+    process_group: Option<u32>,
+}
+
+#[derive(Clone, Debug)]
+pub struct LocalJob {
+    pub job_id: String,
+    pub log_path: PathBuf,
+    pub log_uri: Option<String>,
+}
+
+impl LocalJob {
+    pub fn log(&self, line: &str) {
+        if let Ok(mut file) = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&self.log_path)
+        {
+            let _ = writeln!(file, "{}", line);
+        }
+    }
+}
+
+// Signal 0 only checks the pid: EPERM means it exists under another user. Pids that cannot
+// name a single process (0 and negatives address groups) count as gone.
+#[cfg(unix)]
+fn process_alive(pid: i64) -> bool {
+    let Some(pid) = libc::pid_t::try_from(pid).ok().filter(|pid| *pid > 0) else {
+        return false;
+    };
+    if unsafe { libc::kill(pid, 0) } == 0 {
+        return true;
+    }
+    std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(not(unix))]
+fn process_alive(_pid: i64) -> bool {
+    true
+}
+
+// A zero pgid would signal our own group, so it is ignored along with out-of-range ids.
+#[cfg(unix)]
+pub(crate) fn kill_process_group(pgid: u32) {
+    if let Some(pgid) = libc::pid_t::try_from(pgid).ok().filter(|pgid| *pgid > 0) {
+        unsafe {
+            libc::kill(-pgid, libc::SIGKILL);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+pub(crate) fn kill_process_group(_pgid: u32) {}
+
+// Context artifact when a context repo is configured, otherwise next to the job store.
+fn local_log_location(
+    job_id: &str,
+    trace_id: Option<&str>,
+) -> Result<(PathBuf, Option<String>), ToolError> {
+    if let Some(root) = resolve_context_root() {
+        let reference = build_run_file_ref(
+            Some(trace_id.unwrap_or("jobs")),
+            &format!("job-{}.log", job_id),
+        )?;
+        let path = resolve_artifact_path(&root, &reference.rel)?;
+        return Ok((path, Some(reference.uri)));
+    }
+    Ok((
+        resolve_jobs_path()
+            .with_file_name("job-logs")
+            .join(format!("{}.log", job_id)),
+        None,
+    ))
+}
+
 #[derive(Clone)]
 pub struct JobService {
     logger: Logger,
@@ -19,6 +103,10 @@ pub struct JobService {
     max_jobs: usize,
     ttl_ms: u64,
     abort_flags: Arc<RwLock<HashMap<String, Arc<AtomicBool>>>>,
+    local_tasks: Arc<Mutex<HashMap<String, LocalTask>>>,
+    // Set by hosts that exit as soon as the call returns (the one-shot CLI): a local job could
+    // only be interrupted at teardown there, so `start_local` refuses it up front.
+    one_shot: Arc<AtomicBool>,
 }
 
 impl JobService {
@@ -26,18 +114,15 @@ impl JobService {
         let service = Self {
             logger: logger.child("jobs"),
             store: StoreDb::new()?,
-            max_jobs: std::env::var("INFRA_JOBS_MAX")
-                .ok()
-                .and_then(|v| v.parse::<usize>().ok())
-                .unwrap_or(500),
-            ttl_ms: std::env::var("INFRA_JOBS_TTL_MS")
-                .ok()
-                .and_then(|v| v.parse::<u64>().ok())
-                .unwrap_or(6 * 60 * 60_000),
+            max_jobs: feature_flags::JOBS_MAX.number() as usize,
+            ttl_ms: feature_flags::JOBS_TTL_MS.number(),
             abort_flags: Arc::new(RwLock::new(HashMap::new())),
+            local_tasks: Arc::new(Mutex::new(HashMap::new())),
+            one_shot: Arc::new(AtomicBool::new(false)),
         };
         service.import_legacy_once()?;
         service.purge_expired()?;
+        service.interrupt_orphaned_local_jobs()?;
         service.enforce_max_jobs()?;
         Ok(service)
     }
@@ -319,4 +404,238 @@ impl JobService {
             )
         })
     }
+
+    // Shallow-merges `patch` into the stored record; the store itself replaces whole values.
+    fn patch_record(&self, job_id: &str, patch: Value) -> Option<Value> {
+        let mut record = self.get(job_id)?;
+        if let (Value::Object(map), Value::Object(patch)) = (&mut record, patch) {
+            map.extend(patch);
+        }
+        self.upsert(record)
+    }
+
+    fn is_running(&self, job_id: &str) -> bool {
+        self.get(job_id)
+            .and_then(|job| {
+                job.get("status")
+                    .and_then(|v| v.as_str())
+                    .map(|s| s == "running")
+            })
+            .unwrap_or(false)
+    }
+
+    pub fn mark_one_shot(&self) {
+        self.one_shot.store(true, Ordering::SeqCst);
+    }
+
+    // Registers a job that this process runs itself and creates its (owner-only) log file.
+    pub fn start_local(
+        &self,
+        kind: &str,
+        provider: Value,
+        trace_id: Option<&str>,
+    ) -> Result<LocalJob, ToolError> {
+        if self.one_shot.load(Ordering::SeqCst) {
+            return Err(ToolError::new(
+                ToolErrorKind::InvalidParams,
+                "LOCAL_JOB_UNSUPPORTED",
+                format!(
+                    "{} jobs run inside the calling process, and this one-shot process exits when the call returns",
+                    kind
+                ),
+            )
+            .with_hint(
+                "Run it in the foreground (drop detached/background, set timeout_ms), or start it on a host with ssh action=exec_detached.",
+            )
+            .with_details(serde_json::json!({"kind": kind})));
+        }
+        let job_id = uuid::Uuid::new_v4().to_string();
+        let (log_path, log_uri) = local_log_location(&job_id, trace_id)?;
+        if let Some(parent) = log_path.parent() {
+            std::fs::create_dir_all(parent).map_err(|err| {
+                ToolError::internal(format!("Failed to create job log dir: {}", err))
+            })?;
+        }
+        let mut options = std::fs::OpenOptions::new();
+        options.create(true).append(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        options
+            .open(&log_path)
+            .map_err(|err| ToolError::internal(format!("Failed to create job log: {}", err)))?;
+
+        let mut provider = provider;
+        if let Value::Object(map) = &mut provider {
+            map.insert(
+                "owner_pid".to_string(),
+                serde_json::json!(std::process::id()),
+            );
+            map.insert(
+                "log_path".to_string(),
+                Value::String(log_path.display().to_string()),
+            );
+        }
+        let now = Self::now_iso();
+        self.upsert(serde_json::json!({
+            "job_id": job_id,
+            "kind": kind,
+            "status": "running",
+            "trace_id": trace_id,
+            "created_at": now,
+            "started_at": now,
+            "ended_at": Value::Null,
+            "provider": provider,
+            "artifacts": {"log": {"uri": log_uri, "path": log_path.display().to_string()}},
+            "error": Value::Null,
+        }));
+        Ok(LocalJob {
+            job_id,
+            log_path,
+            log_uri,
+        })
+    }
+
+    // A task that already finished (or was killed) before it could be attached is not kept.
+    pub fn attach_local_task(
+        &self,
+        job_id: &str,
+        abort: tokio::task::AbortHandle,
+        process_group: Option<u32>,
+    ) {
+        let mut tasks = self.local_tasks.lock().unwrap();
+        if self.is_running(job_id) {
+            tasks.insert(
+                job_id.to_string(),
+                LocalTask {
+                    abort,
+                    process_group,
+                },
+            );
+        }
+    }
+
+    // Merges `progress` into a running job's progress object.
+    pub fn update_progress(&self, job_id: &str, progress: Value) {
+        let Some(mut record) = self.get(job_id) else {
+            return;
+        };
+        if record.get("status").and_then(|v| v.as_str()) != Some("running") {
+            return;
+        }
+        if !record["progress"].is_object() {
+            record["progress"] = serde_json::json!({});
+        }
+        if let (Some(current), Value::Object(update)) =
+            (record["progress"].as_object_mut(), progress)
+        {
+            current.extend(update);
+        }
+        record["updated_at"] = Value::String(chrono::Utc::now().to_rfc3339());
+        let _ = self.upsert(record);
+    }
+
+    pub fn finish_local(&self, job_id: &str, outcome: Result<Value, ToolError>) {
+        let mut tasks = self.local_tasks.lock().unwrap();
+        tasks.remove(job_id);
+        if !self.is_running(job_id) {
+            return;
+        }
+        let patch = match outcome {
+            Ok(result) => serde_json::json!({
+                "status": if result.get("success").and_then(|v| v.as_bool()).unwrap_or(true) {
+                    "succeeded"
+                } else {
+                    "failed"
+                },
+                "ended_at": Self::now_iso(),
+                "result": result,
+            }),
+            Err(err) => serde_json::json!({
+                "status": "failed",
+                "ended_at": Self::now_iso(),
+                "error": {"code": err.code, "message": err.message},
+            }),
+        };
+        let _ = self.patch_record(job_id, patch);
+    }
+
+    fn stop_local_task(task: LocalTask) {
+        task.abort.abort();
+        if let Some(pgid) = task.process_group {
+            kill_process_group(pgid);
+        }
+    }
+
+    // Returns the updated record and whether this process was running the job.
+    pub fn kill_local(&self, job_id: &str, reason: Option<&str>) -> Option<(Value, bool)> {
+        let mut tasks = self.local_tasks.lock().unwrap();
+        let job = self.get(job_id)?;
+        let Some(task) = tasks.remove(job_id) else {
+            return Some((job, false));
+        };
+        Self::stop_local_task(task);
+        let updated = self.patch_record(
+            job_id,
+            serde_json::json!({
+                "status": "canceled",
+                "ended_at": Self::now_iso(),
+                "error": reason.unwrap_or("killed"),
+            }),
+        )?;
+        Some((updated, true))
+    }
+
+    // Called when the hosting process shuts down: its tasks die with it.
+    pub fn interrupt_local_jobs(&self) -> usize {
+        let mut tasks = self.local_tasks.lock().unwrap();
+        let drained: Vec<(String, LocalTask)> = tasks.drain().collect();
+        let count = drained.len();
+        for (job_id, task) in drained {
+            Self::stop_local_task(task);
+            if self.is_running(&job_id) {
+                let _ = self.patch_record(
+                    &job_id,
+                    serde_json::json!({
+                        "status": "interrupted",
+                        "ended_at": Self::now_iso(),
+                        "error": "the process running this job shut down",
+                    }),
+                );
+            }
+        }
+        count
+    }
+
+    // Local jobs left `running` by a process that no longer exists.
+    fn interrupt_orphaned_local_jobs(&self) -> Result<(), ToolError> {
+        let own_pid = std::process::id() as i64;
+        for record in self.store.list(NAMESPACE)? {
+            let job = &record.value;
+            let provider = job.get("provider");
+            let local = provider
+                .and_then(|v| v.get("tool"))
+                .and_then(|v| v.as_str())
+                .is_some_and(|tool| LOCAL_JOB_TOOLS.contains(&tool));
+            let owner = provider
+                .and_then(|v| v.get("owner_pid"))
+                .and_then(|v| v.as_i64());
+            let running = job.get("status").and_then(|v| v.as_str()) == Some("running");
+            if let (true, true, Some(owner)) = (local, running, owner) {
+                if owner != own_pid && !process_alive(owner) {
+                    let _ = self.patch_record(
+                        &record.key,
+                        serde_json::json!({
+                            "status": "interrupted",
+                            "ended_at": Self::now_iso(),
+                            "error": "the process running this job exited",
+                        }),
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
 }
@@ -1,4 +1,23 @@
-use std::sync::Mutex;
+use crate::utils::feature_flags;
+use crate::utils::redact::{redact_object, redact_text};
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, RwLock};
+
+const MAX_LOG_BUFFER_SIZE: usize = 20_000;
+const LOG_RECORD_MAX_STRING: usize = 2000;
+
+tokio::task_local! {
+    static LOG_TRACE_ID: String;
+}
+
+pub async fn with_log_trace_id<F: std::future::Future>(trace_id: String, fut: F) -> F::Output {
+    LOG_TRACE_ID.scope(trace_id, fut).await
+}
+
+fn current_trace_id() -> Option<String> {
+    LOG_TRACE_ID.try_with(|trace_id| trace_id.clone()).ok()
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum LogLevel {
@@ -9,20 +28,33 @@ pub enum LogLevel {
 }
 
 impl LogLevel {
-    fn from_env() -> Self {
-        match std::env::var("LOG_LEVEL")
-            .unwrap_or_else(|_| "info".to_string())
-            .to_lowercase()
-            .as_str()
-        {
-            "error" => LogLevel::Error,
-            "warn" => LogLevel::Warn,
-            "debug" => LogLevel::Debug,
-            _ => LogLevel::Info,
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "error" => Some(LogLevel::Error),
+            "warn" | "warning" => Some(LogLevel::Warn),
+            "info" => Some(LogLevel::Info),
+            "debug" => Some(LogLevel::Debug),
+            _ => None,
         }
     }
 
-    fn allows(self, other: LogLevel) -> bool {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LogLevel::Error => "error",
+            LogLevel::Warn => "warn",
+            LogLevel::Info => "info",
+            LogLevel::Debug => "debug",
+        }
+    }
+
+    fn from_env() -> Self {
+        feature_flags::LOG_LEVEL
+            .text()
+            .and_then(|value| Self::parse(&value))
+            .unwrap_or(LogLevel::Info)
+    }
+
+    pub fn allows(self, other: LogLevel) -> bool {
         use LogLevel::*;
         let rank = match self {
             Error => 0,
@@ -48,11 +80,93 @@ struct Counters {
     debug: u64,
 }
 
+fn parse_level_overrides(raw: &str) -> HashMap<String, LogLevel> {
+    raw.split(',')
+        .filter_map(|entry| {
+            let (component, level) = entry.split_once('=')?;
+            let component = component.trim();
+            if component.is_empty() {
+                return None;
+            }
+            Some((component.to_string(), LogLevel::parse(level)?))
+        })
+        .collect()
+}
+
+// Overrides match a context or any of its prefixes, with or without the root segment,
+// so "ssh=debug" applies to "infra:ssh" and "infra:ssh:jobs"; the longest match wins.
+fn component_candidates(context: &str) -> Vec<String> {
+    let segments: Vec<&str> = context.split(':').collect();
+    let mut out = Vec::new();
+    for end in (1..=segments.len()).rev() {
+        out.push(segments[..end].join(":"));
+        if end > 1 {
+            out.push(segments[1..end].join(":"));
+        }
+    }
+    out
+}
+
+#[derive(Debug, Default)]
+pub struct LogTailFilter {
+    pub component: Option<String>,
+    pub level: Option<LogLevel>,
+    pub trace_id: Option<String>,
+    pub limit: usize,
+}
+
+#[derive(Debug)]
+struct LogHub {
+    overrides: RwLock<HashMap<String, LogLevel>>,
+    records: Mutex<VecDeque<Value>>,
+    capacity: usize,
+    dropped: Mutex<u64>,
+}
+
+impl LogHub {
+    fn from_env() -> Self {
+        let overrides = feature_flags::LOG_LEVELS
+            .text()
+            .map(|raw| parse_level_overrides(&raw))
+            .unwrap_or_default();
+        let capacity = (feature_flags::LOG_BUFFER_SIZE.number() as usize).min(MAX_LOG_BUFFER_SIZE);
+        Self {
+            overrides: RwLock::new(overrides),
+            records: Mutex::new(VecDeque::with_capacity(capacity.min(1024))),
+            capacity,
+            dropped: Mutex::new(0),
+        }
+    }
+
+    fn override_for(&self, context: &str) -> Option<LogLevel> {
+        let overrides = self.overrides.read().unwrap_or_else(|err| err.into_inner());
+        if overrides.is_empty() {
+            return None;
+        }
+        component_candidates(context)
+            .iter()
+            .find_map(|candidate| overrides.get(candidate).copied())
+    }
+
+    fn push(&self, record: Value) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut records = self.records.lock().unwrap_or_else(|err| err.into_inner());
+        while records.len() >= self.capacity {
+            records.pop_front();
+            *self.dropped.lock().unwrap_or_else(|err| err.into_inner()) += 1;
+        }
+        records.push_back(record);
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Logger {
     context: String,
     level: LogLevel,
     counters: std::sync::Arc<Mutex<Counters>>,
+    hub: Arc<LogHub>,
 }
 
 impl Logger {
@@ -61,6 +175,7 @@ impl Logger {
             context: context.to_string(),
             level: LogLevel::from_env(),
             counters: std::sync::Arc::new(Mutex::new(Counters::default())),
+            hub: Arc::new(LogHub::from_env()),
         }
     }
 
@@ -74,6 +189,7 @@ impl Logger {
             context,
             level: self.level,
             counters: self.counters.clone(),
+            hub: self.hub.clone(),
         }
     }
 
@@ -81,8 +197,99 @@ impl Logger {
         self.level = level;
     }
 
+    pub fn effective_level(&self) -> LogLevel {
+        self.hub.override_for(&self.context).unwrap_or(self.level)
+    }
+
+    pub fn set_level_override(&self, component: &str, level: Option<LogLevel>) {
+        let mut overrides = self
+            .hub
+            .overrides
+            .write()
+            .unwrap_or_else(|err| err.into_inner());
+        match level {
+            Some(level) => {
+                overrides.insert(component.to_string(), level);
+            }
+            None => {
+                overrides.remove(component);
+            }
+        }
+    }
+
+    pub fn level_overrides(&self) -> Value {
+        let overrides = self
+            .hub
+            .overrides
+            .read()
+            .unwrap_or_else(|err| err.into_inner());
+        let mut entries: Vec<(&String, &LogLevel)> = overrides.iter().collect();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+        let map: serde_json::Map<String, Value> = entries
+            .into_iter()
+            .map(|(component, level)| {
+                (component.clone(), Value::String(level.as_str().to_string()))
+            })
+            .collect();
+        Value::Object(map)
+    }
+
+    pub fn tail(&self, filter: &LogTailFilter) -> Value {
+        let records = self
+            .hub
+            .records
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        let matches = |record: &Value| {
+            if let Some(component) = filter.component.as_deref() {
+                let context = record
+                    .get("component")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("");
+                if !component_candidates(context).iter().any(|c| c == component) {
+                    return false;
+                }
+            }
+            if let Some(level) = filter.level {
+                let record_level = record
+                    .get("level")
+                    .and_then(|v| v.as_str())
+                    .and_then(LogLevel::parse)
+                    .unwrap_or(LogLevel::Debug);
+                if !level.allows(record_level) {
+                    return false;
+                }
+            }
+            if let Some(trace_id) = filter.trace_id.as_deref() {
+                if record.get("trace_id").and_then(|v| v.as_str()) != Some(trace_id) {
+                    return false;
+                }
+            }
+            true
+        };
+        let mut selected: Vec<Value> = records
+            .iter()
+            .rev()
+            .filter(|record| matches(record))
+            .take(filter.limit.max(1))
+            .cloned()
+            .collect();
+        selected.reverse();
+        let dropped = *self
+            .hub
+            .dropped
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        serde_json::json!({
+            "records": selected,
+            "buffered": records.len(),
+            "capacity": self.hub.capacity,
+            "dropped": dropped,
+        })
+    }
+
     fn log(&self, level: LogLevel, message: &str, meta: Option<&serde_json::Value>) {
-        if !self.level.allows(level) {
+        if !self.effective_level().allows(level) {
             return;
         }
         if let Ok(mut counters) = self.counters.lock() {
@@ -108,6 +315,20 @@ impl Logger {
             "[{}] {} [{}] {}{}",
             timestamp, level_str, self.context, message, meta_suffix
         );
+        let meta = meta.filter(|m| !m.is_null());
+        let trace_id = current_trace_id().or_else(|| {
+            meta.and_then(|m| m.get("trace_id"))
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string())
+        });
+        self.hub.push(serde_json::json!({
+            "timestamp": timestamp,
+            "level": level.as_str(),
+            "component": self.context,
+            "message": redact_text(message, LOG_RECORD_MAX_STRING, None),
+            "meta": meta.map(|m| redact_object(m, LOG_RECORD_MAX_STRING, None)),
+            "trace_id": trace_id,
+        }));
     }
 
     pub fn error(&self, message: &str, meta: Option<&serde_json::Value>) {
@@ -129,7 +350,7 @@ impl Logger {
     pub fn stats(&self) -> serde_json::Value {
         let counters = self.counters.lock().unwrap_or_else(|err| err.into_inner());
         serde_json::json!({
-            "level": format!("{:?}", self.level).to_lowercase(),
+            "level": self.effective_level().as_str(),
             "context": self.context,
             "error": counters.error,
             "warn": counters.warn,
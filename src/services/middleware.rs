@@ -0,0 +1,100 @@
+use async_trait::async_trait;
+use serde_json::{Map, Value};
+
+use crate::errors::ToolError;
+
+// Built-in stages of one tool call, in the order the executor runs them. Embedder middleware
+// runs its `before` hooks at BEFORE_SLOT (after aliases and presets shaped the args, so it sees
+// what the handler will get, and before validation and the effects gates, so a rewrite is still
+// checked) and its `after` hooks, in reverse registration order, at AFTER_SLOT.
+pub const BUILTIN_STAGES: &[&str] = &[
+    "alias",
+    "presets",
+    "validation",
+    "effects_gate",
+    "dry_run",
+    "handler",
+    "redaction",
+    "audit",
+];
+const BEFORE_SLOT: &str = "validation";
+const AFTER_SLOT: &str = "redaction";
+
+// What middleware sees of the call. `metadata` is shared along the chain: whatever one hook
+// puts there, later hooks read, and the call's audit entry records under `middleware`.
+#[derive(Clone, Debug)]
+pub struct MiddlewareCall {
+    pub tool: String,
+    pub action: Option<String>,
+    pub trace_id: String,
+    pub metadata: Map<String, Value>,
+}
+
+// Org-specific behavior for embedders: authorization, extra audit fields, argument rewrites.
+// An error from either hook fails the call with that error (use `ToolError::denied` to refuse)
+// and skips the remaining hooks.
+#[async_trait]
+pub trait ToolMiddleware: Send + Sync {
+    // Shown in `ToolExecutor::chain`.
+    fn name(&self) -> &str;
+
+    // Returns the args to continue with.
+    async fn before(&self, call: &mut MiddlewareCall, args: Value) -> Result<Value, ToolError> {
+        let _ = call;
+        Ok(args)
+    }
+
+    // Returns the result to continue with; runs for successful calls only.
+    async fn after(&self, call: &mut MiddlewareCall, result: Value) -> Result<Value, ToolError> {
+        let _ = call;
+        Ok(result)
+    }
+}
+
+// The full stage order with the given middleware slotted in.
+pub fn chain(middleware: &[String]) -> Vec<String> {
+    let mut stages = Vec::new();
+    for stage in BUILTIN_STAGES {
+        if *stage == BEFORE_SLOT {
+            stages.extend(middleware.iter().map(|name| format!("{}.before", name)));
+        }
+        if *stage == AFTER_SLOT {
+            stages.extend(
+                middleware
+                    .iter()
+                    .rev()
+                    .map(|name| format!("{}.after", name)),
+            );
+        }
+        stages.push(stage.to_string());
+    }
+    stages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn middleware_wraps_the_handler_in_registration_order() {
+        let names = vec!["authz".to_string(), "headers".to_string()];
+        assert_eq!(
+            chain(&names),
+            vec![
+                "alias",
+                "presets",
+                "authz.before",
+                "headers.before",
+                "validation",
+                "effects_gate",
+                "dry_run",
+                "handler",
+                "headers.after",
+                "authz.after",
+                "redaction",
+                "audit",
+            ]
+        );
+        assert_eq!(chain(&[]), BUILTIN_STAGES);
+    }
+}
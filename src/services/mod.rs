@@ -1,13 +1,17 @@
 pub mod alias;
 pub mod audit;
+pub mod audit_forwarder;
 pub mod cache;
 pub mod capability;
+pub mod config_sync;
 pub mod context;
 pub mod context_session;
 pub mod description;
+pub mod doctor;
 pub mod evidence;
 pub mod job;
 pub mod logger;
+pub mod middleware;
 pub mod operation;
 pub mod policy;
 pub mod preset;
@@ -20,6 +24,7 @@ pub mod security;
 pub mod state;
 pub mod store_db;
 pub mod tool_executor;
+pub mod trace_bundle;
 pub mod validation;
 pub mod vault_client;
 pub mod workspace;
@@ -1,6 +1,7 @@
 use crate::errors::ToolError;
 use crate::services::logger::Logger;
 use crate::services::state::StateService;
+use crate::utils::feature_flags;
 use chrono::{Datelike, Timelike};
 use serde_json::Value;
 use sha2::{Digest, Sha256};
@@ -298,7 +299,7 @@ impl PolicyService {
     }
 
     fn resolve_autonomy_policy(&self) -> Option<ResolvedPolicyEntry> {
-        let raw = std::env::var("INFRA_AUTONOMY_POLICY").ok();
+        let raw = feature_flags::AUTONOMY_POLICY.text();
         if let Some(raw) = raw {
             let trimmed = raw.trim();
             if trimmed == "operatorless" {
@@ -319,8 +320,7 @@ impl PolicyService {
             }
         }
 
-        let autonomy = std::env::var("INFRA_AUTONOMY").ok();
-        if autonomy.as_deref().map(read_truth_env).unwrap_or(false) {
+        if feature_flags::AUTONOMY.enabled() {
             return Some(ResolvedPolicyEntry {
                 source: "env.INFRA_AUTONOMY".to_string(),
                 value: serde_json::json!({"mode": "operatorless"}),
@@ -916,13 +916,6 @@ fn normalize_string_array(
     }
 }
 
-fn read_truth_env(value: &str) -> bool {
-    matches!(
-        value.trim().to_lowercase().as_str(),
-        "1" | "true" | "yes" | "on"
-    )
-}
-
 fn parse_time_minutes(raw: &str, label: &str) -> Result<u32, ToolError> {
     if raw == "24:00" {
         return Ok(24 * 60);
@@ -1,10 +1,21 @@
 use crate::errors::ToolError;
 use crate::services::store_db::StoreDb;
+use crate::tooling::names::canonical_tool_name;
 use crate::utils::listing::ListFilters;
+use crate::utils::merge::merge_deep;
 use crate::utils::paths::resolve_presets_path;
+use crate::utils::safe_name::ensure_safe_name;
 use serde_json::Value;
 
 const NAMESPACE: &str = "presets";
+const MAX_EXTENDS_DEPTH: usize = 16;
+
+#[derive(Debug, Clone)]
+pub struct PresetLayering {
+    pub args: Value,
+    pub presets: Vec<String>,
+    pub defaults: Value,
+}
 
 #[derive(Clone)]
 pub struct PresetService {
@@ -49,16 +60,184 @@ impl PresetService {
                 return Err(ToolError::invalid_params("preset.data must be an object"));
             }
         }
+        if let Some(extends) = obj.get("extends") {
+            let valid = extends.as_array().is_some_and(|items| {
+                items
+                    .iter()
+                    .all(|item| item.as_str().is_some_and(|s| !s.trim().is_empty()))
+            });
+            if !valid {
+                return Err(ToolError::invalid_params(
+                    "preset.extends must be an array of preset names",
+                ));
+            }
+        }
+        if let Some(bindings) = obj.get("default_for") {
+            let valid = bindings.as_array().is_some_and(|items| {
+                items.iter().all(|item| {
+                    let tool_ok = item
+                        .get("tool")
+                        .and_then(|v| v.as_str())
+                        .is_some_and(|s| !s.trim().is_empty());
+                    let action_ok = item
+                        .get("action")
+                        .map(|v| v.is_null() || v.as_str().is_some_and(|s| !s.trim().is_empty()))
+                        .unwrap_or(true);
+                    tool_ok && action_ok
+                })
+            });
+            if !valid {
+                return Err(ToolError::invalid_params(
+                    "preset.default_for must be an array of {tool, action?} objects",
+                ));
+            }
+        }
         Ok(())
     }
 
-    pub fn set_preset(&self, name: &str, preset: &Value) -> Result<Value, ToolError> {
+    fn extends_of(preset: &Value) -> Vec<String> {
+        preset
+            .get("extends")
+            .and_then(|v| v.as_array())
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|item| item.as_str().map(|s| s.trim().to_string()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn resolve_chain(&self, name: &str, stack: &mut Vec<String>) -> Result<Value, ToolError> {
+        if let Some(pos) = stack.iter().position(|item| item == name) {
+            let mut cycle: Vec<String> = stack[pos..].to_vec();
+            cycle.push(name.to_string());
+            return Err(ToolError::invalid_params(format!(
+                "preset extends cycle: {}",
+                cycle.join(" -> ")
+            ))
+            .with_hint("Remove one of the extends entries to break the cycle.".to_string())
+            .with_details(serde_json::json!({ "cycle": cycle })));
+        }
+        if stack.len() >= MAX_EXTENDS_DEPTH {
+            return Err(ToolError::invalid_params(format!(
+                "preset extends chain is deeper than {} levels",
+                MAX_EXTENDS_DEPTH
+            ))
+            .with_details(serde_json::json!({ "chain": stack })));
+        }
+        let entry = self.store.get(NAMESPACE, name)?.ok_or_else(|| {
+            let mut err = ToolError::not_found(format!("preset '{}' not found", name))
+                .with_hint("Use action=preset_list to see known presets.".to_string());
+            if let Some(parent) = stack.last() {
+                err = err.with_details(serde_json::json!({ "extended_by": parent }));
+            }
+            err
+        })?;
+        stack.push(name.to_string());
+        let mut merged = Value::Object(Default::default());
+        for parent in Self::extends_of(&entry.value) {
+            let parent_data = self.resolve_chain(&parent, stack)?;
+            merged = merge_deep(&merged, &parent_data);
+        }
+        stack.pop();
+        let own = entry
+            .value
+            .get("data")
+            .cloned()
+            .filter(|v| v.is_object())
+            .unwrap_or_else(|| Value::Object(Default::default()));
+        Ok(merge_deep(&merged, &own))
+    }
+
+    pub fn resolve_preset_data(&self, name: &str) -> Result<Value, ToolError> {
         if name.trim().is_empty() {
             return Err(ToolError::invalid_params(
                 "preset name must be a non-empty string",
             ));
         }
+        self.resolve_chain(name.trim(), &mut Vec::new())
+    }
+
+    pub fn default_presets_for(
+        &self,
+        tool: &str,
+        action: Option<&str>,
+    ) -> Result<Vec<String>, ToolError> {
+        let tool = canonical_tool_name(tool.trim());
+        let mut tool_wide = Vec::new();
+        let mut action_bound = Vec::new();
+        for entry in self.store.list(NAMESPACE)? {
+            let Some(bindings) = entry.value.get("default_for").and_then(|v| v.as_array()) else {
+                continue;
+            };
+            for binding in bindings {
+                let bound_tool = binding.get("tool").and_then(|v| v.as_str()).unwrap_or("");
+                if canonical_tool_name(bound_tool.trim()) != tool {
+                    continue;
+                }
+                match binding.get("action").and_then(|v| v.as_str()) {
+                    None => tool_wide.push(entry.key.clone()),
+                    Some(bound) if Some(bound.trim()) == action => {
+                        action_bound.push(entry.key.clone())
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+        tool_wide.sort();
+        tool_wide.dedup();
+        action_bound.sort();
+        action_bound.dedup();
+        action_bound.retain(|name| !tool_wide.contains(name));
+        tool_wide.extend(action_bound);
+        Ok(tool_wide)
+    }
+
+    pub fn layer_call_args(
+        &self,
+        tool: &str,
+        alias_args: Option<&Value>,
+        call_args: &Value,
+    ) -> Result<PresetLayering, ToolError> {
+        let mut explicit = Value::Object(Default::default());
+        if let Some(alias_args) = alias_args {
+            explicit = merge_deep(&explicit, alias_args);
+        }
+        explicit = merge_deep(&explicit, call_args);
+        let opted_out = call_args.get("preset") == Some(&Value::Bool(false))
+            || alias_args.and_then(|v| v.get("preset")) == Some(&Value::Bool(false));
+        if opted_out {
+            return Ok(PresetLayering {
+                args: explicit,
+                presets: Vec::new(),
+                defaults: Value::Object(Default::default()),
+            });
+        }
+        let action = explicit.get("action").and_then(|v| v.as_str());
+        let presets = self.default_presets_for(tool, action)?;
+        let mut defaults = Value::Object(Default::default());
+        for name in &presets {
+            let data = self.resolve_preset_data(name)?;
+            defaults = merge_deep(&defaults, &data);
+        }
+        if let Value::Object(map) = &mut defaults {
+            map.remove("apply");
+            map.remove("confirm");
+        }
+        Ok(PresetLayering {
+            args: merge_deep(&defaults, &explicit),
+            presets,
+            defaults,
+        })
+    }
+
+    pub fn set_preset(&self, name: &str, preset: &Value) -> Result<Value, ToolError> {
+        let name = &ensure_safe_name(name, "preset name")?;
         self.validate_preset(preset)?;
+        for parent in Self::extends_of(preset) {
+            self.resolve_chain(&parent, &mut vec![name.trim().to_string()])?;
+        }
         let existing = self.store.get(NAMESPACE, name)?;
         let now = chrono::Utc::now().to_rfc3339();
         let mut payload = preset.as_object().cloned().unwrap_or_default();
@@ -108,6 +287,12 @@ impl PresetService {
             if let Some(desc) = preset.get("description") {
                 map.insert("description".to_string(), desc.clone());
             }
+            if let Some(extends) = preset.get("extends") {
+                map.insert("extends".to_string(), extends.clone());
+            }
+            if let Some(bindings) = preset.get("default_for") {
+                map.insert("default_for".to_string(), bindings.clone());
+            }
             map.insert(
                 "created_at".to_string(),
                 preset.get("created_at").cloned().unwrap_or(Value::Null),
@@ -1,12 +1,113 @@
-use crate::errors::ToolError;
+use crate::errors::{ToolError, ToolErrorKind};
 use crate::services::security::Security;
-use crate::services::store_db::StoreDb;
+use crate::services::store_db::{namespace_generation, StoreDb};
 use crate::utils::paths::resolve_profiles_path;
+use crate::utils::safe_name::ensure_safe_name;
 use serde_json::Value;
 use std::sync::Arc;
 
 const NAMESPACE: &str = "profiles";
 
+// How `profile_upsert` treats an existing profile: fields are overlaid on the stored ones by
+// default (`merge: true`), `replace: true` starts from scratch, `unset` deletes named keys and
+// `skip_test` stores the profile without a connectivity check.
+#[derive(Clone, Debug, Default)]
+pub struct UpsertOptions {
+    pub replace: bool,
+    pub unset: Vec<String>,
+    pub skip_test: bool,
+}
+
+impl UpsertOptions {
+    pub fn from_args(args: &Value) -> Result<Self, ToolError> {
+        let flag = |key: &str| -> Result<Option<bool>, ToolError> {
+            match args.get(key) {
+                None | Some(Value::Null) => Ok(None),
+                Some(Value::Bool(value)) => Ok(Some(*value)),
+                Some(_) => Err(ToolError::invalid_params(format!(
+                    "{} must be a boolean",
+                    key
+                ))),
+            }
+        };
+        let replace = match (flag("merge")?, flag("replace")?) {
+            (Some(true), Some(true)) => {
+                return Err(ToolError::invalid_params(
+                    "merge and replace cannot both be true",
+                ))
+            }
+            (merge, replace) => replace.unwrap_or(false) || merge == Some(false),
+        };
+        let unset = match args.get("unset") {
+            None | Some(Value::Null) => Vec::new(),
+            Some(Value::Array(items)) => items
+                .iter()
+                .map(|item| {
+                    item.as_str()
+                        .filter(|key| !key.trim().is_empty())
+                        .map(|key| key.trim().to_string())
+                        .ok_or_else(|| {
+                            ToolError::invalid_params("unset must be an array of field names")
+                        })
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            Some(_) => {
+                return Err(
+                    ToolError::invalid_params("unset must be an array of field names")
+                        .with_hint("Example: unset: [\"password\"]."),
+                )
+            }
+        };
+        Ok(Self {
+            replace,
+            unset,
+            skip_test: flag("skip_test")?.unwrap_or(false),
+        })
+    }
+}
+
+// The profile an upsert is building: stored data and decrypted secrets to start from, with
+// incoming fields overlaid. Null removes a field, as with `set_profile`.
+#[derive(Clone, Debug, Default)]
+pub struct ProfileDraft {
+    pub data: serde_json::Map<String, Value>,
+    pub secrets: serde_json::Map<String, Value>,
+}
+
+impl ProfileDraft {
+    pub fn overlay_data(&mut self, key: &str, value: &Value) {
+        overlay(&mut self.data, key, value);
+    }
+
+    pub fn overlay_secret(&mut self, key: &str, value: &Value) {
+        overlay(&mut self.secrets, key, value);
+    }
+
+    // Data and secrets as one flat object, the shape connection builders expect.
+    pub fn connection(&self) -> Value {
+        let mut map = self.data.clone();
+        map.extend(self.secrets.clone());
+        Value::Object(map)
+    }
+}
+
+fn overlay(map: &mut serde_json::Map<String, Value>, key: &str, value: &Value) {
+    if value.is_null() {
+        map.remove(key);
+    } else {
+        map.insert(key.to_string(), value.clone());
+    }
+}
+
+fn profile_not_found(name: &str) -> ToolError {
+    ToolError::new(
+        ToolErrorKind::NotFound,
+        "PROFILE_NOT_FOUND",
+        format!("Profile '{}' not found", name),
+    )
+    .with_hint("Use action=profile_list to see known profiles.")
+}
+
 #[derive(Clone)]
 pub struct ProfileService {
     security: Arc<Security>,
@@ -20,9 +121,88 @@ impl ProfileService {
             store: StoreDb::new()?,
         };
         service.import_legacy_once()?;
+        service.ensure_readable()?;
         Ok(service)
     }
 
+    pub fn security(&self) -> &Arc<Security> {
+        &self.security
+    }
+
+    // Refuses to start over profiles the loaded key cannot open: carrying on would let the next
+    // write seal them under the wrong key for good.
+    fn ensure_readable(&self) -> Result<(), ToolError> {
+        let unreadable = self.unreadable_profiles()?;
+        if unreadable.is_empty() {
+            return Ok(());
+        }
+        Err(ToolError::new(
+            ToolErrorKind::Denied,
+            "SECRET_KEY_MISMATCH",
+            format!(
+                "Profile secrets cannot be decrypted with the loaded key ({}): {}",
+                self.security.key_id(),
+                unreadable.join(", ")
+            ),
+        )
+        .with_hint(
+            "Start infra with the INFRA_MASTER_KEY (or ENCRYPTION_KEY, or key file) these profiles were written with; nothing was modified.",
+        )
+        .with_details(serde_json::json!({
+            "profiles": unreadable,
+            "active_key_id": self.security.key_id(),
+            "key_source": self.security.key_source().as_str(),
+        })))
+    }
+
+    // Names of profiles holding a secret the loaded keys cannot decrypt.
+    pub fn unreadable_profiles(&self) -> Result<Vec<String>, ToolError> {
+        let mut out = Vec::new();
+        for entry in self.store.list(NAMESPACE)? {
+            let readable = entry
+                .value
+                .get("secrets")
+                .and_then(|v| v.as_object())
+                .into_iter()
+                .flatten()
+                .all(|(_, value)| self.security.reveal(value.as_str().unwrap_or("")).is_ok());
+            if !readable {
+                out.push(entry.key);
+            }
+        }
+        Ok(out)
+    }
+
+    // Seals every profile secret with the active key. All of them are decrypted before any is
+    // written, so a failure leaves the store as it was.
+    pub fn reseal_all(&self) -> Result<usize, ToolError> {
+        let mut updates = Vec::new();
+        for entry in self.store.list(NAMESPACE)? {
+            let mut profile = entry.value;
+            let Some(secrets) = profile.get_mut("secrets").and_then(|v| v.as_object_mut()) else {
+                continue;
+            };
+            let mut changed = false;
+            for value in secrets.values_mut() {
+                let stored = value.as_str().unwrap_or("");
+                let sealed = self.security.reseal(stored).map_err(|err| {
+                    err.with_details(serde_json::json!({"profile": entry.key.clone()}))
+                })?;
+                if sealed != stored {
+                    *value = Value::String(sealed);
+                    changed = true;
+                }
+            }
+            if changed {
+                updates.push((entry.key, profile));
+            }
+        }
+        for (name, profile) in &updates {
+            self.store.upsert(NAMESPACE, name, profile, Some("local"))?;
+        }
+        Ok(updates.len())
+    }
+
     fn import_legacy_once(&self) -> Result<(), ToolError> {
         let path = resolve_profiles_path();
         let import_key = format!("file:{}", path.display());
@@ -76,12 +256,13 @@ impl ProfileService {
         Ok(())
     }
 
+    // Changes whenever a profile is written or deleted in this process.
+    pub fn generation() -> u64 {
+        namespace_generation(NAMESPACE)
+    }
+
     pub fn set_profile(&self, name: &str, config: &Value) -> Result<Value, ToolError> {
-        if name.trim().is_empty() {
-            return Err(ToolError::invalid_params(
-                "Profile name must be a non-empty string",
-            ));
-        }
+        let name = &ensure_safe_name(name, "Profile name")?;
         let config_obj = config
             .as_object()
             .ok_or_else(|| ToolError::invalid_params("Profile config must be an object"))?;
@@ -119,11 +300,18 @@ impl ProfileService {
             }
         }
 
-        let mut secrets = existing_obj
+        // Secrets kept from the stored profile are sealed again with the active key, which
+        // migrates plaintext and older keys' values on the first write after an upgrade.
+        let mut secrets = serde_json::Map::new();
+        for (key, value) in existing_obj
             .get("secrets")
             .and_then(|v| v.as_object())
-            .cloned()
-            .unwrap_or_default();
+            .into_iter()
+            .flatten()
+        {
+            let sealed = self.security.reseal(value.as_str().unwrap_or(""))?;
+            secrets.insert(key.clone(), Value::String(sealed));
+        }
         if let Some(secrets_value) = config_obj.get("secrets") {
             if secrets_value.is_null() {
                 secrets.clear();
@@ -167,16 +355,66 @@ impl ProfileService {
         }))
     }
 
+    // Where an upsert starts: nothing for a new profile or `replace`, the stored profile
+    // otherwise; `unset` keys are dropped from both sections either way.
+    pub fn upsert_draft(
+        &self,
+        name: &str,
+        profile_type: &str,
+        options: &UpsertOptions,
+    ) -> Result<ProfileDraft, ToolError> {
+        let mut draft = ProfileDraft::default();
+        if !options.replace && self.has_profile(name) {
+            let stored = self.get_profile(name, Some(profile_type)).map_err(|err| {
+                err.with_hint("Pass replace: true to overwrite it with a new profile type.")
+            })?;
+            for (section, target) in [("data", &mut draft.data), ("secrets", &mut draft.secrets)] {
+                if let Some(map) = stored.get(section).and_then(|v| v.as_object()) {
+                    target.extend(map.clone());
+                }
+            }
+        }
+        for key in &options.unset {
+            draft.data.remove(key);
+            draft.secrets.remove(key);
+        }
+        Ok(draft)
+    }
+
+    // Stores exactly the draft, so keys it no longer holds are gone from the profile.
+    pub fn save_draft(
+        &self,
+        name: &str,
+        profile_type: &str,
+        draft: &ProfileDraft,
+    ) -> Result<Value, ToolError> {
+        let mut data = draft.data.clone();
+        let mut secrets = draft.secrets.clone();
+        if let Some(existing) = self.store.get(NAMESPACE, name)?.map(|record| record.value) {
+            for (section, target) in [("data", &mut data), ("secrets", &mut secrets)] {
+                if let Some(map) = existing.get(section).and_then(|v| v.as_object()) {
+                    for key in map.keys() {
+                        target.entry(key.clone()).or_insert(Value::Null);
+                    }
+                }
+            }
+        }
+        self.set_profile(
+            name,
+            &serde_json::json!({"type": profile_type, "data": data, "secrets": secrets}),
+        )
+    }
+
     pub fn get_profile(&self, name: &str, expected_type: Option<&str>) -> Result<Value, ToolError> {
         if name.trim().is_empty() {
             return Err(ToolError::invalid_params(
                 "Profile name must be a non-empty string",
             ));
         }
-        let entry = self.store.get(NAMESPACE, name)?.ok_or_else(|| {
-            ToolError::not_found(format!("Profile '{}' not found", name))
-                .with_hint("Use action=profile_list to see known profiles.".to_string())
-        })?;
+        let entry = self
+            .store
+            .get(NAMESPACE, name)?
+            .ok_or_else(|| profile_not_found(name))?;
         if let Some(expected) = expected_type {
             if entry.value.get("type").and_then(|v| v.as_str()) != Some(expected) {
                 return Err(ToolError::conflict(format!(
@@ -196,8 +434,8 @@ impl ProfileService {
         if let Some(secrets) = entry.value.get("secrets").and_then(|v| v.as_object()) {
             let mut decrypted = serde_json::Map::new();
             for (field, value) in secrets {
-                let cipher = value.as_str().unwrap_or("");
-                let plain = self.security.decrypt(cipher)?;
+                let stored = value.as_str().unwrap_or("");
+                let plain = self.security.reveal(stored)?;
                 decrypted.insert(field.clone(), Value::String(plain));
             }
             if let Value::Object(map) = &mut result {
@@ -229,10 +467,7 @@ impl ProfileService {
 
     pub fn delete_profile(&self, name: &str) -> Result<Value, ToolError> {
         if !self.store.delete(NAMESPACE, name)? {
-            return Err(
-                ToolError::not_found(format!("Profile '{}' not found", name))
-                    .with_hint("Use action=profile_list to see known profiles.".to_string()),
-            );
+            return Err(profile_not_found(name));
         }
         Ok(serde_json::json!({"success": true}))
     }
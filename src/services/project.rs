@@ -1,7 +1,8 @@
 use crate::errors::ToolError;
-use crate::services::store_db::StoreDb;
+use crate::services::store_db::{namespace_generation, StoreDb};
 use crate::utils::listing::ListFilters;
 use crate::utils::paths::resolve_projects_path;
+use crate::utils::safe_name::ensure_safe_name;
 use serde_json::Value;
 
 const NAMESPACE: &str = "projects";
@@ -84,6 +85,7 @@ impl ProjectService {
                         "project.targets keys must be non-empty strings",
                     ));
                 }
+                ensure_safe_name(name, "project target name")?;
                 self.validate_target(target)?;
             }
         }
@@ -116,12 +118,13 @@ impl ProjectService {
         Ok(())
     }
 
+    // Changes whenever a project is written or deleted in this process.
+    pub fn generation() -> u64 {
+        namespace_generation(NAMESPACE)
+    }
+
     pub fn set_project(&self, name: &str, project: &Value) -> Result<Value, ToolError> {
-        if name.trim().is_empty() {
-            return Err(ToolError::invalid_params(
-                "project name must be a non-empty string",
-            ));
-        }
+        let name = &ensure_safe_name(name, "project name")?;
         self.validate_project(project)?;
         self.store
             .upsert(NAMESPACE, name.trim(), project, Some("local"))?;
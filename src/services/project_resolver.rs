@@ -1,30 +1,135 @@
 use crate::errors::ToolError;
+use crate::services::cache::CacheService;
+use crate::services::profile::ProfileService;
+use crate::services::project::ProjectService;
 use crate::services::state::StateService;
 use crate::services::validation::Validation;
 use crate::utils::suggest::suggest;
 use serde_json::Value;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
 const ACTIVE_PROJECT_KEY: &str = "project.active";
 
+#[derive(Default)]
+struct CacheStats {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    stale: AtomicU64,
+}
+
+// Project and profile writes bump their store generation; an entry stored under older
+// generations is dropped on lookup. The generations are read before storage, so a write that
+// races a resolve leaves that entry already stale: at worst the in-flight call sees the old
+// value. The TTL bounds staleness from writers in other processes.
+fn current_generations() -> (u64, u64) {
+    (ProjectService::generation(), ProfileService::generation())
+}
+
 #[derive(Clone)]
 pub struct ProjectResolver {
     validation: Validation,
-    project_service: Arc<crate::services::project::ProjectService>,
+    project_service: Arc<ProjectService>,
     state_service: Option<Arc<StateService>>,
+    cache: Option<Arc<CacheService>>,
+    cache_ttl: Option<Duration>,
+    stats: Arc<CacheStats>,
 }
 
 impl ProjectResolver {
     pub fn new(
         validation: Validation,
-        project_service: Arc<crate::services::project::ProjectService>,
+        project_service: Arc<ProjectService>,
         state_service: Option<Arc<StateService>>,
     ) -> Self {
         Self {
             validation,
             project_service,
             state_service,
+            cache: None,
+            cache_ttl: None,
+            stats: Arc::new(CacheStats::default()),
+        }
+    }
+
+    // Resolved contexts live in the `project_resolver` namespace; without it every call reads
+    // the project store.
+    pub fn with_cache(mut self, cache: Arc<CacheService>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    // Overrides the namespace TTL; a zero TTL turns the cache off.
+    pub fn with_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.cache_ttl = Some(ttl);
+        self
+    }
+
+    fn active_cache(&self) -> Option<&CacheService> {
+        if self.cache_ttl.is_some_and(|ttl| ttl.is_zero()) {
+            return None;
+        }
+        self.cache.as_deref()
+    }
+
+    fn ttl_override_ms(&self) -> Option<u64> {
+        self.cache_ttl.map(|ttl| ttl.as_millis() as u64)
+    }
+
+    // (project, requested target) -> resolved context. The active project is looked up per
+    // call, so project_use/project_unuse change the key rather than the cached value.
+    fn cache_key(cache: &CacheService, project: &str, target: Option<&str>) -> String {
+        cache.build_key(&serde_json::json!({"project": project, "target": target}))
+    }
+
+    fn cached(&self, cache: &CacheService, key: &str, generations: (u64, u64)) -> Option<Value> {
+        let entry = cache
+            .get_json(key, self.ttl_override_ms())
+            .ok()
+            .flatten()
+            .and_then(|payload| payload.get("value").cloned());
+        let Some(entry) = entry else {
+            self.stats.misses.fetch_add(1, Ordering::Relaxed);
+            return None;
+        };
+        if entry.get("generations") != Some(&serde_json::json!([generations.0, generations.1])) {
+            let _ = cache.remove(key);
+            self.stats.stale.fetch_add(1, Ordering::Relaxed);
+            self.stats.misses.fetch_add(1, Ordering::Relaxed);
+            return None;
         }
+        self.stats.hits.fetch_add(1, Ordering::Relaxed);
+        entry.get("context").cloned()
+    }
+
+    fn store(&self, cache: &CacheService, key: &str, generations: (u64, u64), context: &Value) {
+        let entry = serde_json::json!({
+            "generations": [generations.0, generations.1],
+            "context": context,
+        });
+        let _ = cache.set_json(key, &entry, self.ttl_override_ms(), None);
+    }
+
+    // project resolver_stats: hit/miss counters since start, for checking the cache works.
+    pub fn stats(&self) -> Value {
+        let namespace = self
+            .active_cache()
+            .map(|cache| cache.stats()["namespaces"][cache.namespace()].clone())
+            .unwrap_or(Value::Null);
+        let ttl_ms = match self.cache_ttl {
+            Some(ttl) => Value::from(ttl.as_millis() as u64),
+            None => namespace["default_ttl_ms"].clone(),
+        };
+        let (projects, profiles) = current_generations();
+        serde_json::json!({
+            "hits": self.stats.hits.load(Ordering::Relaxed),
+            "misses": self.stats.misses.load(Ordering::Relaxed),
+            "stale": self.stats.stale.load(Ordering::Relaxed),
+            "entries": namespace["entries"].as_u64().unwrap_or(0),
+            "ttl_ms": ttl_ms,
+            "generations": {"projects": projects, "profiles": profiles},
+        })
     }
 
     async fn resolve_project_name(&self, args: &Value) -> Result<Option<String>, ToolError> {
@@ -46,17 +151,20 @@ impl ProjectResolver {
         Ok(None)
     }
 
+    fn requested_target(args: &Value) -> Option<&str> {
+        args.get("target")
+            .or_else(|| args.get("project_target"))
+            .or_else(|| args.get("environment"))
+            .and_then(|v| v.as_str())
+    }
+
     fn resolve_target(&self, project: &Value, args: &Value) -> Result<(String, Value), ToolError> {
         let targets = project
             .get("targets")
             .and_then(|v| v.as_object())
             .cloned()
             .unwrap_or_default();
-        let requested = args
-            .get("target")
-            .or_else(|| args.get("project_target"))
-            .or_else(|| args.get("environment"))
-            .and_then(|v| v.as_str());
+        let requested = Self::requested_target(args);
 
         if let Some(requested) = requested {
             let name = self.validation.ensure_identifier(requested, "target")?;
@@ -119,14 +227,74 @@ impl ProjectResolver {
         let Some(project_name) = project_name else {
             return Ok(None);
         };
+        let cache = self.active_cache();
+        let key =
+            cache.map(|cache| Self::cache_key(cache, &project_name, Self::requested_target(args)));
+        let generations = current_generations();
+        if let (Some(cache), Some(key)) = (cache, key.as_deref()) {
+            if let Some(context) = self.cached(cache, key, generations) {
+                return Ok(Some(context));
+            }
+        }
         let project = self.project_service.get_project(&project_name)?;
         let project_entry = project.get("project").cloned().unwrap_or(Value::Null);
         let (target_name, target_entry) = self.resolve_target(&project_entry, args)?;
-        Ok(Some(serde_json::json!({
+        let context = serde_json::json!({
             "projectName": project_name,
             "project": project_entry,
             "targetName": target_name,
             "target": target_entry,
-        })))
+        });
+        if let (Some(cache), Some(key)) = (cache, key.as_deref()) {
+            self.store(cache, key, generations, &context);
+        }
+        Ok(Some(context))
+    }
+
+    // Every target of the requested (or active) project, for fleet-wide actions that ignore
+    // target selection.
+    pub async fn project_targets(
+        &self,
+        args: &Value,
+    ) -> Result<Option<(String, serde_json::Map<String, Value>)>, ToolError> {
+        let Some(project_name) = self.resolve_project_name(args).await? else {
+            return Ok(None);
+        };
+        let project = self.project_service.get_project(&project_name)?;
+        let targets = project
+            .get("project")
+            .and_then(|v| v.get("targets"))
+            .and_then(|v| v.as_object())
+            .cloned()
+            .unwrap_or_default();
+        Ok(Some((project_name, targets)))
+    }
+
+    // Key prefix for project-scoped state (`project/<name>/<target>`), derived from the same
+    // project/target resolution tool calls use.
+    pub async fn state_namespace(&self, args: &Value) -> Result<String, ToolError> {
+        let context = self.resolve_context(args).await?.ok_or_else(|| {
+            ToolError::invalid_params("project scope requires a resolvable project/target")
+                .with_hint(
+                    "Pass project (and target when it has several), or set the active project."
+                        .to_string(),
+                )
+        })?;
+        let project = context
+            .get("projectName")
+            .and_then(|v| v.as_str())
+            .unwrap_or("");
+        let target = context
+            .get("targetName")
+            .and_then(|v| v.as_str())
+            .unwrap_or("");
+        Ok(project_state_namespace(project, Some(target)))
+    }
+}
+
+pub fn project_state_namespace(project: &str, target: Option<&str>) -> String {
+    match target {
+        Some(target) => format!("project/{}/{}", project, target),
+        None => format!("project/{}", project),
     }
 }
@@ -1,5 +1,6 @@
 use crate::errors::ToolError;
 use crate::utils::bundled_manifests::{bundled_runbooks_json, BUNDLED_RUNBOOKS_MANIFEST_URI};
+use crate::utils::capability_discovery::add_manifest_entries;
 use crate::utils::effects::resolve_effects;
 use crate::utils::listing::ListFilters;
 use crate::utils::paths::{resolve_default_runbooks_path, resolve_runbooks_path};
@@ -7,6 +8,7 @@ use serde_json::Value;
 use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
 
 const RUNBOOK_SOURCE: &str = "manifest";
 const FILE_BACKED_MANIFEST_SOURCE: &str = "file_backed_manifest";
@@ -17,7 +19,7 @@ const UNCONFIGURED_MANIFEST_SOURCE: &str = "unconfigured_manifest";
 pub struct RunbookService {
     default_manifest_path: Option<PathBuf>,
     manifest_path: PathBuf,
-    manifest: RunbookManifest,
+    manifest: Arc<RwLock<RunbookManifest>>,
 }
 
 #[derive(Clone)]
@@ -39,25 +41,59 @@ impl RunbookService {
         Ok(Self {
             default_manifest_path,
             manifest_path,
-            manifest,
+            manifest: Arc::new(RwLock::new(manifest)),
         })
     }
 
+    fn manifest(&self) -> RunbookManifest {
+        self.manifest
+            .read()
+            .unwrap_or_else(|err| err.into_inner())
+            .clone()
+    }
+
+    // Adds `records` to the manifest at manifest_path (created when missing); names any loaded
+    // manifest already defines are left untouched and returned as skipped.
+    pub fn add_runbooks(
+        &self,
+        records: &serde_json::Map<String, Value>,
+    ) -> Result<(Vec<String>, Vec<String>), ToolError> {
+        for (name, runbook) in records {
+            Self::validate_runbook(runbook).map_err(|err| {
+                ToolError::invalid_params(format!("runbook '{}': {}", name, err.message))
+            })?;
+        }
+        let current = self.manifest_runbooks()?;
+        let (added, skipped) =
+            add_manifest_entries(&self.manifest_path, "runbooks", records, |name| {
+                current.contains_key(name)
+            })?;
+        if !added.is_empty() {
+            let reloaded = load_runbook_manifest(
+                self.default_manifest_path.as_deref(),
+                Some(self.manifest_path.as_path()),
+            )?;
+            *self.manifest.write().unwrap_or_else(|err| err.into_inner()) = reloaded;
+        }
+        Ok((added, skipped))
+    }
+
     pub fn manifest_path(&self) -> &Path {
         self.manifest_path.as_path()
     }
 
     pub fn manifest_metadata(&self) -> Value {
+        let manifest = self.manifest();
         serde_json::json!({
             "manifest_path": self.manifest_path_value(),
-            "manifest_source": self.manifest.source.clone(),
-            "manifest_version": self.manifest.version.clone().unwrap_or(Value::Null),
+            "manifest_source": manifest.source,
+            "manifest_version": manifest.version.unwrap_or(Value::Null),
             "manifest_sha256": self.manifest_sha256_value(),
         })
     }
 
     fn manifest_path_value(&self) -> Value {
-        self.manifest
+        self.manifest()
             .path
             .as_ref()
             .map(|path| Value::String(path.to_string_lossy().to_string()))
@@ -65,7 +101,7 @@ impl RunbookService {
     }
 
     fn manifest_sha256_value(&self) -> Value {
-        self.manifest
+        self.manifest()
             .sha256
             .as_ref()
             .map(|sha| Value::String(sha.clone()))
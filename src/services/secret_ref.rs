@@ -1,15 +1,16 @@
-use crate::errors::ToolError;
+use crate::errors::{ToolError, ToolErrorKind};
+use crate::services::cache::CacheService;
 use crate::services::logger::Logger;
 use crate::services::profile::ProfileService;
 use crate::services::project_resolver::ProjectResolver;
 use crate::services::validation::Validation;
 use crate::services::vault_client::VaultClient;
-use futures::future::BoxFuture;
-use futures::FutureExt;
 use serde_json::Value;
 use std::collections::HashMap;
 use std::sync::Arc;
 
+const RESOLVE_CONCURRENCY: usize = 8;
+
 #[derive(Clone)]
 pub struct SecretRefResolver {
     logger: Logger,
@@ -17,6 +18,7 @@ pub struct SecretRefResolver {
     profile_service: Option<Arc<ProfileService>>,
     vault_client: Option<Arc<VaultClient>>,
     project_resolver: Option<Arc<ProjectResolver>>,
+    cache: Option<Arc<CacheService>>,
 }
 
 impl SecretRefResolver {
@@ -33,6 +35,35 @@ impl SecretRefResolver {
             profile_service,
             vault_client,
             project_resolver,
+            cache: None,
+        }
+    }
+
+    // Resolved vault values live in the memory-only `secret_refs` namespace until its TTL, so
+    // repeated calls skip the Vault round trip. Failures are never cached.
+    pub fn with_cache(mut self, cache: Arc<CacheService>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    fn cache_key(cache: &CacheService, profile_name: &str, reference: &str) -> String {
+        cache.build_key(&serde_json::json!({"vault_profile": profile_name, "ref": reference}))
+    }
+
+    fn cached_secret(&self, profile_name: &str, reference: &str) -> Option<String> {
+        let cache = self.cache.as_ref()?;
+        let key = Self::cache_key(cache, profile_name, reference);
+        let payload = cache.get_json(&key, None).ok().flatten()?;
+        payload
+            .get("value")
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+    }
+
+    fn store_secret(&self, profile_name: &str, reference: &str, secret: &str) {
+        if let Some(cache) = &self.cache {
+            let key = Self::cache_key(cache, profile_name, reference);
+            let _ = cache.set_json(&key, &Value::String(secret.to_string()), None, None);
         }
     }
 
@@ -77,90 +108,221 @@ impl SecretRefResolver {
             .with_hint("Pass args.vault_profile_name explicitly (or configure target.vault_profile in project).".to_string()))
     }
 
-    async fn resolve_ref_string(
+    // Env refs resolve inline; vault refs are batched per call so the profile and token
+    // are looked up once and secret paths are read concurrently.
+    async fn resolve_refs(
         &self,
-        value: &str,
+        refs: &[String],
         args: &Value,
-        cache: &mut HashMap<String, String>,
-    ) -> Result<String, ToolError> {
-        if let Some(existing) = cache.get(value) {
-            return Ok(existing.clone());
+    ) -> HashMap<String, Result<String, ToolError>> {
+        let mut resolved = HashMap::new();
+        let mut vault_refs = Vec::new();
+        for value in refs {
+            let spec = value.trim_start_matches("ref:");
+            if let Some(reference) = spec.strip_prefix("vault:kv2:") {
+                vault_refs.push((value.clone(), reference.to_string()));
+                continue;
+            }
+            resolved.insert(value.clone(), resolve_plain_ref(spec));
         }
-        let spec = value.trim_start_matches("ref:");
-        if spec.starts_with("vault:kv2:") {
-            let client = self.vault_client.as_ref().ok_or_else(|| {
-                ToolError::internal("vault refs require VaultClient (server misconfiguration)")
-                    .with_hint("Enable VaultClient in server bootstrap.")
-            })?;
-            let reference = spec.trim_start_matches("vault:kv2:");
-            let profile_name = self.resolve_vault_profile_name(args).await?;
-            let resolved = client.kv2_get(&profile_name, reference, Some(args)).await?;
-            cache.insert(value.to_string(), resolved.clone());
-            return Ok(resolved);
+        if vault_refs.is_empty() {
+            return resolved;
         }
-        if spec.starts_with("env:") {
-            let key = spec.trim_start_matches("env:").trim();
-            if key.is_empty() {
-                return Err(
-                    ToolError::invalid_params("ref:env requires a non-empty env var name")
-                        .with_hint("Example: \"ref:env:MY_TOKEN\"."),
-                );
+        let batch = match self.vault_client.as_ref() {
+            Some(client) => match self.resolve_vault_profile_name(args).await {
+                Ok(profile_name) => {
+                    vault_refs.retain(|(value, reference)| {
+                        match self.cached_secret(&profile_name, reference) {
+                            Some(secret) => {
+                                resolved.insert(value.clone(), Ok(secret));
+                                false
+                            }
+                            None => true,
+                        }
+                    });
+                    if vault_refs.is_empty() {
+                        return resolved;
+                    }
+                    let references: Vec<String> =
+                        vault_refs.iter().map(|(_, r)| r.clone()).collect();
+                    client
+                        .kv2_get_many(&profile_name, &references, Some(args), RESOLVE_CONCURRENCY)
+                        .await
+                        .inspect(|results| {
+                            for (reference, result) in references.iter().zip(results) {
+                                if let Ok(secret) = result {
+                                    self.store_secret(&profile_name, reference, secret);
+                                }
+                            }
+                        })
+                }
+                Err(err) => Err(err),
+            },
+            None => Err(ToolError::internal(
+                "vault refs require VaultClient (server misconfiguration)",
+            )
+            .with_hint("Enable VaultClient in server bootstrap.")),
+        };
+        match batch {
+            Ok(results) => {
+                for ((value, _), result) in vault_refs.into_iter().zip(results) {
+                    resolved.insert(value, result);
+                }
+            }
+            Err(err) => {
+                for (value, _) in vault_refs {
+                    resolved.insert(value, Err(err.clone()));
+                }
             }
-            let val = std::env::var(key).map_err(|_| {
-                ToolError::not_found(format!("ref:env var is not set: {}", key)).with_hint(
-                    "Set the env var in the server environment, or use ref:vault:kv2:<mount>/<path>#<key>.".to_string(),
-                )
-            })?;
-            cache.insert(value.to_string(), val.clone());
-            return Ok(val);
         }
-        let scheme = spec.split(':').next().unwrap_or("unknown");
-        Err(
-            ToolError::invalid_params(format!("Unknown secret ref scheme: {}", scheme)).with_hint(
-                "Supported schemes: ref:vault:kv2:<mount>/<path>#<key>, ref:env:<ENV_VAR>.",
-            ),
-        )
+        resolved
     }
 
     pub async fn resolve_deep(&self, input: &Value, args: &Value) -> Result<Value, ToolError> {
         self.logger.debug("resolve_deep", None);
-        let mut cache = HashMap::new();
-        self.walk_value(input, args, &mut cache).await
+        let resolution = self.resolve_deep_partial(input, args).await?;
+        if resolution.unresolved.is_empty() {
+            return Ok(resolution.value);
+        }
+        Err(batch_error(resolution.unresolved))
     }
 
-    fn walk_value<'a>(
-        &'a self,
-        value: &'a Value,
-        args: &'a Value,
-        cache: &'a mut HashMap<String, String>,
-    ) -> BoxFuture<'a, Result<Value, ToolError>> {
-        async move {
-            if value.is_null() {
-                return Ok(Value::Null);
-            }
-            if let Some(text) = value.as_str() {
-                if text.trim_start().starts_with("ref:") {
-                    let resolved = self.resolve_ref_string(text, args, cache).await?;
-                    return Ok(Value::String(resolved));
-                }
-                return Ok(Value::String(text.to_string()));
-            }
-            if let Some(arr) = value.as_array() {
-                let mut out = Vec::with_capacity(arr.len());
-                for item in arr {
-                    out.push(self.walk_value(item, args, cache).await?);
-                }
-                return Ok(Value::Array(out));
-            }
-            if let Some(obj) = value.as_object() {
-                let mut out = serde_json::Map::new();
-                for (key, val) in obj {
-                    out.insert(key.clone(), self.walk_value(val, args, cache).await?);
-                }
-                return Ok(Value::Object(out));
-            }
-            Ok(value.clone())
+    // Resolves what it can and leaves failing refs in place, reporting each one instead of
+    // failing the call; meant for diagnostics that want the whole picture.
+    pub async fn resolve_deep_partial(
+        &self,
+        input: &Value,
+        args: &Value,
+    ) -> Result<PartialResolution, ToolError> {
+        let mut refs = Vec::new();
+        collect_refs(input, &mut refs);
+        refs.sort();
+        refs.dedup();
+        let resolved = self.resolve_refs(&refs, args).await;
+        let value = substitute_refs(input, &resolved);
+        let unresolved = refs
+            .into_iter()
+            .filter_map(|reference| match resolved.get(&reference) {
+                Some(Err(err)) => Some(UnresolvedRef {
+                    reference,
+                    error: err.clone(),
+                }),
+                _ => None,
+            })
+            .collect();
+        Ok(PartialResolution { value, unresolved })
+    }
+}
+
+pub struct PartialResolution {
+    pub value: Value,
+    pub unresolved: Vec<UnresolvedRef>,
+}
+
+pub struct UnresolvedRef {
+    pub reference: String,
+    pub error: ToolError,
+}
+
+impl UnresolvedRef {
+    // Coarse failure class: a missing path or key, missing access, or an unreachable vault.
+    pub fn reason(&self) -> &'static str {
+        match self.error.kind {
+            ToolErrorKind::NotFound => "not_found",
+            ToolErrorKind::Denied => "permission",
+            ToolErrorKind::Timeout | ToolErrorKind::Retryable => "connection",
+            _ => "invalid",
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        serde_json::json!({
+            "ref": self.reference,
+            "reason": self.reason(),
+            "code": self.error.code,
+            "message": self.error.message,
+        })
+    }
+}
+
+fn batch_error(unresolved: Vec<UnresolvedRef>) -> ToolError {
+    let report: Vec<Value> = unresolved.iter().map(UnresolvedRef::to_json).collect();
+    let details = serde_json::json!({ "unresolved": report });
+    if unresolved.len() == 1 {
+        let mut err = unresolved.into_iter().next().unwrap().error;
+        err.details = Some(details);
+        return err;
+    }
+    let first = &unresolved[0].error;
+    let listed: Vec<String> = unresolved
+        .iter()
+        .map(|item| format!("{} ({})", item.reference, item.reason()))
+        .collect();
+    ToolError::new(
+        first.kind,
+        first.code.clone(),
+        format!(
+            "{} secret refs could not be resolved: {}",
+            unresolved.len(),
+            listed.join(", ")
+        ),
+    )
+    .with_hint("See details.unresolved for the error of each ref.")
+    .with_details(details)
+}
+
+fn is_ref(text: &str) -> bool {
+    text.trim_start().starts_with("ref:")
+}
+
+fn collect_refs(value: &Value, out: &mut Vec<String>) {
+    match value {
+        Value::String(text) if is_ref(text) => out.push(text.clone()),
+        Value::Array(items) => items.iter().for_each(|item| collect_refs(item, out)),
+        Value::Object(map) => map.values().for_each(|item| collect_refs(item, out)),
+        _ => {}
+    }
+}
+
+fn substitute_refs(value: &Value, resolved: &HashMap<String, Result<String, ToolError>>) -> Value {
+    match value {
+        Value::String(text) if is_ref(text) => match resolved.get(text) {
+            Some(Ok(secret)) => Value::String(secret.clone()),
+            _ => value.clone(),
+        },
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| substitute_refs(item, resolved))
+                .collect(),
+        ),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, item)| (key.clone(), substitute_refs(item, resolved)))
+                .collect(),
+        ),
+        _ => value.clone(),
+    }
+}
+
+fn resolve_plain_ref(spec: &str) -> Result<String, ToolError> {
+    if let Some(key) = spec.strip_prefix("env:") {
+        let key = key.trim();
+        if key.is_empty() {
+            return Err(
+                ToolError::invalid_params("ref:env requires a non-empty env var name")
+                    .with_hint("Example: \"ref:env:MY_TOKEN\"."),
+            );
         }
-        .boxed()
+        return std::env::var(key).map_err(|_| {
+            ToolError::not_found(format!("ref:env var is not set: {}", key)).with_hint(
+                "Set the env var in the server environment, or use ref:vault:kv2:<mount>/<path>#<key>.".to_string(),
+            )
+        });
     }
+    let scheme = spec.split(':').next().unwrap_or("unknown");
+    Err(
+        ToolError::invalid_params(format!("Unknown secret ref scheme: {}", scheme))
+            .with_hint("Supported schemes: ref:vault:kv2:<mount>/<path>#<key>, ref:env:<ENV_VAR>."),
+    )
 }
@@ -1,13 +1,22 @@
-use crate::constants::buffers::{CRYPTO_IV_SIZE, CRYPTO_KEY_SIZE, CRYPTO_TAG_SIZE, MAX_LOG_SIZE};
-use crate::errors::ToolError;
+use crate::constants::buffers::{CRYPTO_IV_SIZE, CRYPTO_KEY_SIZE, CRYPTO_TAG_SIZE};
+use crate::errors::{ToolError, ToolErrorKind};
+use crate::utils::feature_flags;
+use crate::utils::fs_atomic::atomic_write_text_file;
 use crate::utils::paths::resolve_profile_key_path;
 use aes_gcm::aead::{Aead, KeyInit, OsRng};
 use aes_gcm::Aes256Gcm;
 use base64::Engine;
 use rand::RngCore;
+use sha2::{Digest, Sha256};
 use std::fs;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+// Sealed values are `enc:v1:<key_id>:<iv_hex>:<tag_hex>:<data_hex>`; the key id names the key
+// that sealed them, so a wrong key is reported as such instead of as corrupt data. Values
+// written before the envelope existed are `<iv_hex>:<tag_hex>:<data_hex>`.
+const ENVELOPE_PREFIX: &str = "enc:v1:";
 
 fn decode_key(raw: &str) -> Option<Vec<u8>> {
     let trimmed = raw.trim();
@@ -19,23 +28,203 @@ fn decode_key(raw: &str) -> Option<Vec<u8>> {
     }
     if trimmed.len() > CRYPTO_KEY_SIZE * 2 {
         let engine = base64::engine::general_purpose::STANDARD;
-        return engine.decode(trimmed.as_bytes()).ok();
+        return engine
+            .decode(trimmed.as_bytes())
+            .ok()
+            .filter(|key| key.len() == CRYPTO_KEY_SIZE);
     }
     None
 }
 
+// Key material from a literal (hex, base64 or 32 raw characters), `ref:env:<VAR>` or, on macOS,
+// `ref:keychain:<service>` (a generic password in the login keychain).
+pub fn resolve_key_material(raw: &str, label: &str) -> Result<Vec<u8>, ToolError> {
+    let raw = raw.trim();
+    let material = if let Some(service) = raw.strip_prefix("ref:keychain:") {
+        keychain_secret(service.trim())?
+    } else if let Some(var) = raw.strip_prefix("ref:env:") {
+        std::env::var(var.trim()).map_err(|_| {
+            ToolError::invalid_params(format!("{} ref:env var is not set: {}", label, var.trim()))
+        })?
+    } else {
+        raw.to_string()
+    };
+    decode_key(&material).ok_or_else(|| {
+        ToolError::invalid_params(format!(
+            "{} must be a 32-byte key: 64 hex characters, base64, or 32 raw characters",
+            label
+        ))
+        .with_hint("Generate one with: openssl rand -hex 32")
+    })
+}
+
+#[cfg(target_os = "macos")]
+fn keychain_secret(service: &str) -> Result<String, ToolError> {
+    let output = std::process::Command::new("security")
+        .args(["find-generic-password", "-s", service, "-w"])
+        .output()
+        .map_err(|err| ToolError::internal(format!("Failed to run security: {}", err)))?;
+    if !output.status.success() {
+        return Err(
+            ToolError::not_found(format!("No keychain item for service {}", service)).with_hint(
+                format!(
+                    "Add it with: security add-generic-password -s {} -a infra -w <key>",
+                    service
+                ),
+            ),
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(not(target_os = "macos"))]
+fn keychain_secret(_service: &str) -> Result<String, ToolError> {
+    Err(
+        ToolError::invalid_params("ref:keychain is only supported on macOS")
+            .with_hint("Use ref:env:<VAR> or a literal key instead."),
+    )
+}
+
+pub fn generate_key() -> Vec<u8> {
+    let mut generated = vec![0u8; CRYPTO_KEY_SIZE];
+    OsRng.fill_bytes(&mut generated);
+    generated
+}
+
+fn key_id(key: &[u8]) -> String {
+    hex::encode(Sha256::digest(key))[..8].to_string()
+}
+
+fn is_legacy_payload(payload: &str) -> bool {
+    let parts: Vec<&str> = payload.split(':').collect();
+    parts.len() == 3
+        && parts[0].len() == CRYPTO_IV_SIZE * 2
+        && parts[1].len() == CRYPTO_TAG_SIZE * 2
+        && parts
+            .iter()
+            .all(|part| part.bytes().all(|b| b.is_ascii_hexdigit()))
+}
+
+// Where the active key came from; rotation can only persist a new key into the key file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeySource {
+    MasterKey,
+    EncryptionKey,
+    KeyFile,
+}
+
+impl KeySource {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::MasterKey => "INFRA_MASTER_KEY",
+            Self::EncryptionKey => "ENCRYPTION_KEY",
+            Self::KeyFile => "key_file",
+        }
+    }
+}
+
 #[derive(Clone)]
-pub struct Security {
+struct SealKey {
+    id: String,
     cipher: Aes256Gcm,
+    material: Vec<u8>,
+}
+
+impl SealKey {
+    fn new(key: &[u8]) -> Self {
+        Self {
+            id: key_id(key),
+            cipher: Aes256Gcm::new(aes_gcm::Key::<Aes256Gcm>::from_slice(key)),
+            material: key.to_vec(),
+        }
+    }
+}
+
+// Both keys of a rotation that has not finished resealing, next to the key file. Loaded as
+// readers on every start, so values sealed with either key open whichever one is active.
+fn rotation_path(key_path: &Path) -> PathBuf {
+    let mut path = key_path.as_os_str().to_owned();
+    path.push(".rotating");
+    PathBuf::from(path)
+}
+
+fn load_rotation_keys(key_path: &Path) -> Vec<Vec<u8>> {
+    fs::read_to_string(rotation_path(key_path))
+        .map(|text| text.lines().filter_map(decode_key).collect())
+        .unwrap_or_default()
+}
+
+// The key new values are sealed with, plus older keys that can still open what they sealed.
+struct Keyring {
+    active: SealKey,
+    readers: Vec<SealKey>,
+    source: KeySource,
+    key_path: PathBuf,
+}
+
+impl Keyring {
+    fn find(&self, id: &str) -> Option<&SealKey> {
+        std::iter::once(&self.active)
+            .chain(self.readers.iter())
+            .find(|key| key.id == id)
+    }
+}
+
+#[derive(Clone)]
+pub struct Security {
+    keys: Arc<RwLock<Keyring>>,
 }
 
 impl Security {
     pub fn new() -> Result<Self, ToolError> {
         let key_path = resolve_profile_key_path();
-        let secret_key = Self::load_or_create_secret(&key_path)?;
-        let key = aes_gcm::Key::<Aes256Gcm>::from_slice(&secret_key);
-        let cipher = Aes256Gcm::new(key);
-        Ok(Self { cipher })
+        let env_key = feature_flags::ENCRYPTION_KEY
+            .text()
+            .and_then(|raw| decode_key(&raw));
+        let keyring = match feature_flags::MASTER_KEY.text() {
+            Some(raw) => {
+                let master = resolve_key_material(&raw, "INFRA_MASTER_KEY")?;
+                // The keys used before the master key was set stay readable until rewritten.
+                let file_key = fs::read_to_string(&key_path)
+                    .ok()
+                    .and_then(|stored| decode_key(&stored));
+                let active = SealKey::new(&master);
+                let readers = env_key
+                    .into_iter()
+                    .chain(file_key)
+                    .map(|key| SealKey::new(&key))
+                    .filter(|key| key.id != active.id)
+                    .collect();
+                Keyring {
+                    active,
+                    readers,
+                    source: KeySource::MasterKey,
+                    key_path,
+                }
+            }
+            None => {
+                let (secret_key, source) = match env_key {
+                    Some(key) => (key, KeySource::EncryptionKey),
+                    None => (Self::load_or_create_secret(&key_path)?, KeySource::KeyFile),
+                };
+                Keyring {
+                    active: SealKey::new(&secret_key),
+                    readers: Vec::new(),
+                    source,
+                    key_path,
+                }
+            }
+        };
+        let mut keyring = keyring;
+        for key in load_rotation_keys(&keyring.key_path) {
+            let key = SealKey::new(&key);
+            if keyring.find(&key.id).is_none() {
+                keyring.readers.push(key);
+            }
+        }
+        Ok(Self {
+            keys: Arc::new(RwLock::new(keyring)),
+        })
     }
 
     pub fn ensure_size_fits(
@@ -43,10 +232,7 @@ impl Security {
         payload: &str,
         max_bytes: Option<usize>,
     ) -> Result<(), ToolError> {
-        let max_bytes_env = std::env::var("INFRA_MAX_PAYLOAD_BYTES")
-            .ok()
-            .and_then(|v| v.parse::<usize>().ok());
-        let limit = max_bytes.or(max_bytes_env).unwrap_or(MAX_LOG_SIZE);
+        let limit = max_bytes.unwrap_or(feature_flags::MAX_PAYLOAD_BYTES.number() as usize);
         if limit == 0 {
             return Ok(());
         }
@@ -63,12 +249,6 @@ impl Security {
     }
 
     fn load_or_create_secret(path: &PathBuf) -> Result<Vec<u8>, ToolError> {
-        if let Ok(raw) = std::env::var("ENCRYPTION_KEY") {
-            if let Some(decoded) = decode_key(&raw) {
-                return Ok(decoded);
-            }
-        }
-
         if path.exists() {
             if let Ok(stored) = fs::read_to_string(path) {
                 if let Some(decoded) = decode_key(&stored) {
@@ -77,8 +257,7 @@ impl Security {
             }
         }
 
-        let mut generated = vec![0u8; CRYPTO_KEY_SIZE];
-        OsRng.fill_bytes(&mut generated);
+        let generated = generate_key();
         if let Some(parent) = path.parent() {
             let _ = fs::create_dir_all(parent);
         }
@@ -98,11 +277,29 @@ impl Security {
         Ok(generated)
     }
 
+    fn keyring(&self) -> std::sync::RwLockReadGuard<'_, Keyring> {
+        self.keys.read().unwrap_or_else(|err| err.into_inner())
+    }
+
+    pub fn key_id(&self) -> String {
+        self.keyring().active.id.clone()
+    }
+
+    pub fn key_source(&self) -> KeySource {
+        self.keyring().source
+    }
+
+    pub fn key_path(&self) -> PathBuf {
+        self.keyring().key_path.clone()
+    }
+
     pub fn encrypt(&self, text: &str) -> Result<String, ToolError> {
+        let keys = self.keyring();
         let mut iv = [0u8; CRYPTO_IV_SIZE];
         OsRng.fill_bytes(&mut iv);
         let nonce = aes_gcm::Nonce::from_slice(&iv);
-        let mut ciphertext = self
+        let mut ciphertext = keys
+            .active
             .cipher
             .encrypt(nonce, text.as_bytes())
             .map_err(|_| ToolError::internal("Failed to encrypt secret payload"))?;
@@ -111,7 +308,9 @@ impl Security {
         }
         let tag = ciphertext.split_off(ciphertext.len() - CRYPTO_TAG_SIZE);
         Ok(format!(
-            "{}:{}:{}",
+            "{}{}:{}:{}:{}",
+            ENVELOPE_PREFIX,
+            keys.active.id,
             hex::encode(iv),
             hex::encode(tag),
             hex::encode(ciphertext)
@@ -119,35 +318,128 @@ impl Security {
     }
 
     pub fn decrypt(&self, payload: &str) -> Result<String, ToolError> {
-        let parts: Vec<&str> = payload.split(':').collect();
-        if parts.len() != 3 {
-            return Err(
-                ToolError::invalid_params("Invalid encrypted payload format")
-                    .with_hint("Expected format: \"<iv_hex>:<tag_hex>:<data_hex>\".".to_string()),
-            );
-        }
-        let iv = hex::decode(parts[0])
-            .map_err(|_| ToolError::invalid_params("Invalid encrypted payload format"))?;
-        let tag = hex::decode(parts[1])
-            .map_err(|_| ToolError::invalid_params("Invalid encrypted payload format"))?;
-        let data = hex::decode(parts[2])
-            .map_err(|_| ToolError::invalid_params("Invalid encrypted payload format"))?;
-        if tag.len() != CRYPTO_TAG_SIZE {
-            return Err(ToolError::invalid_params("Invalid auth tag length"));
-        }
-        let mut combined = Vec::with_capacity(data.len() + tag.len());
-        combined.extend_from_slice(&data);
-        combined.extend_from_slice(&tag);
-        let nonce = aes_gcm::Nonce::from_slice(&iv);
-        let decrypted = self
-            .cipher
-            .decrypt(nonce, combined.as_ref())
-            .map_err(|_| {
-                ToolError::internal("Failed to decrypt secret payload").with_hint(
-                    "Ensure ENCRYPTION_KEY (or the persisted key file) matches the one used to encrypt stored secrets. If keys were rotated, re-create the profile secrets.".to_string(),
+        let keys = self.keyring();
+        if let Some(rest) = payload.strip_prefix(ENVELOPE_PREFIX) {
+            let (id, sealed) = rest
+                .split_once(':')
+                .ok_or_else(|| ToolError::invalid_params("Invalid encrypted payload format"))?;
+            let key = keys.find(id).ok_or_else(|| {
+                ToolError::new(
+                    ToolErrorKind::Denied,
+                    "SECRET_KEY_MISMATCH",
+                    format!(
+                        "Secret was encrypted with key {}, but the loaded key is {}",
+                        id, keys.active.id
+                    ),
+                )
+                .with_hint(
+                    "Start infra with INFRA_MASTER_KEY (or ENCRYPTION_KEY, or the key file) set to the key that encrypted it.",
                 )
             })?;
-        Ok(String::from_utf8_lossy(&decrypted).to_string())
+            return open_payload(&key.cipher, sealed);
+        }
+        // Pre-envelope values carry no key id; whichever loaded key opens them sealed them.
+        let mut last = None;
+        for key in std::iter::once(&keys.active).chain(keys.readers.iter()) {
+            match open_payload(&key.cipher, payload) {
+                Ok(plain) => return Ok(plain),
+                Err(err) if err.kind == ToolErrorKind::InvalidParams => return Err(err),
+                Err(err) => last = Some(err),
+            }
+        }
+        Err(last.unwrap_or_else(|| ToolError::internal("Failed to decrypt secret payload")))
+    }
+
+    // Whether a stored value is ciphertext at all; anything else is a plaintext secret from
+    // before encryption at rest, returned as is and sealed on the next write.
+    pub fn is_sealed(&self, stored: &str) -> bool {
+        stored.starts_with(ENVELOPE_PREFIX) || is_legacy_payload(stored)
+    }
+
+    pub fn reveal(&self, stored: &str) -> Result<String, ToolError> {
+        if self.is_sealed(stored) {
+            self.decrypt(stored)
+        } else {
+            Ok(stored.to_string())
+        }
+    }
+
+    // The value sealed with the active key; values already sealed with it are kept as they are.
+    pub fn reseal(&self, stored: &str) -> Result<String, ToolError> {
+        let current = format!("{}{}:", ENVELOPE_PREFIX, self.key_id());
+        if stored.starts_with(&current) {
+            return Ok(stored.to_string());
+        }
+        self.encrypt(&self.reveal(stored)?)
+    }
+
+    // Makes `key` the active key; the previous one stays loaded to open what it sealed. A key
+    // that lives in the key file is replaced there too, so the next start loads the new one.
+    // Both keys are written to the rotation file first and stay there until
+    // `finish_rotation`, so a reseal that fails halfway loses nothing across a restart.
+    // Rotating again to the key of an unfinished rotation resumes it.
+    pub fn rotate(&self, key: &[u8]) -> Result<(String, String), ToolError> {
+        let mut keys = self.keys.write().unwrap_or_else(|err| err.into_inner());
+        let next = SealKey::new(key);
+        let rotation = rotation_path(&keys.key_path);
+        if next.id == keys.active.id {
+            if rotation.exists() {
+                let previous_id = keys
+                    .readers
+                    .first()
+                    .map(|reader| reader.id.clone())
+                    .unwrap_or_else(|| next.id.clone());
+                return Ok((previous_id, next.id));
+            }
+            return Err(ToolError::conflict(format!(
+                "New key {} is the key already in use",
+                next.id
+            )));
+        }
+        let mut pending = load_rotation_keys(&keys.key_path);
+        for material in [&keys.active.material, &next.material] {
+            if !pending.contains(material) {
+                pending.push(material.clone());
+            }
+        }
+        let text: Vec<String> = pending.iter().map(hex::encode).collect();
+        atomic_write_text_file(&rotation, &format!("{}\n", text.join("\n")), 0o600).map_err(
+            |err| {
+                ToolError::internal(format!(
+                    "Failed to write key rotation file {}: {}",
+                    rotation.display(),
+                    err
+                ))
+            },
+        )?;
+        if keys.source == KeySource::KeyFile {
+            persist_key_file(&keys.key_path, key)?;
+        }
+        let next_id = next.id.clone();
+        let previous = std::mem::replace(&mut keys.active, next);
+        let previous_id = previous.id.clone();
+        keys.readers.retain(|reader| reader.id != next_id);
+        keys.readers.insert(0, previous);
+        Ok((previous_id, next_id))
+    }
+
+    // Called once every stored value is sealed with the active key: older keys are no longer
+    // needed on disk.
+    pub fn finish_rotation(&self) -> Result<(), ToolError> {
+        let rotation = rotation_path(&self.keyring().key_path);
+        match fs::remove_file(&rotation) {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(ToolError::internal(format!(
+                "Failed to remove key rotation file {}: {}",
+                rotation.display(),
+                err
+            ))),
+        }
+    }
+
+    pub fn rotation_path(&self) -> PathBuf {
+        rotation_path(&self.keyring().key_path)
     }
 
     pub fn clean_command(&self, command: &str) -> Result<String, ToolError> {
@@ -165,3 +457,48 @@ impl Security {
         reqwest::Url::parse(url).map_err(|_| ToolError::invalid_params("Invalid URL"))
     }
 }
+
+fn persist_key_file(path: &Path, key: &[u8]) -> Result<(), ToolError> {
+    atomic_write_text_file(path, &hex::encode(key), 0o600).map_err(|err| {
+        ToolError::internal(format!(
+            "Failed to write key file {}: {}",
+            path.display(),
+            err
+        ))
+    })
+}
+
+fn open_payload(cipher: &Aes256Gcm, payload: &str) -> Result<String, ToolError> {
+    let parts: Vec<&str> = payload.split(':').collect();
+    if parts.len() != 3 {
+        return Err(
+            ToolError::invalid_params("Invalid encrypted payload format").with_hint(
+                "Expected format: \"enc:v1:<key_id>:<iv_hex>:<tag_hex>:<data_hex>\".".to_string(),
+            ),
+        );
+    }
+    let iv = hex::decode(parts[0])
+        .map_err(|_| ToolError::invalid_params("Invalid encrypted payload format"))?;
+    let tag = hex::decode(parts[1])
+        .map_err(|_| ToolError::invalid_params("Invalid encrypted payload format"))?;
+    let data = hex::decode(parts[2])
+        .map_err(|_| ToolError::invalid_params("Invalid encrypted payload format"))?;
+    if tag.len() != CRYPTO_TAG_SIZE {
+        return Err(ToolError::invalid_params("Invalid auth tag length"));
+    }
+    if iv.len() != CRYPTO_IV_SIZE {
+        return Err(ToolError::invalid_params(
+            "Invalid encrypted payload format",
+        ));
+    }
+    let mut combined = Vec::with_capacity(data.len() + tag.len());
+    combined.extend_from_slice(&data);
+    combined.extend_from_slice(&tag);
+    let nonce = aes_gcm::Nonce::from_slice(&iv);
+    let decrypted = cipher.decrypt(nonce, combined.as_ref()).map_err(|_| {
+        ToolError::internal("Failed to decrypt secret payload").with_hint(
+            "Ensure INFRA_MASTER_KEY, ENCRYPTION_KEY or the persisted key file matches the one used to encrypt stored secrets.".to_string(),
+        )
+    })?;
+    Ok(String::from_utf8_lossy(&decrypted).to_string())
+}
@@ -1,10 +1,13 @@
 use crate::errors::ToolError;
+use crate::services::security::Security;
 use crate::services::store_db::StoreDb;
 use serde_json::Value;
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
 
 const PERSISTENT_NAMESPACE: &str = "state:persistent";
+// Persistent values set with `sensitive: true` are stored as `{"$sealed": "<envelope>"}`.
+const SEALED_KEY: &str = "$sealed";
 
 pub type SessionState = Arc<RwLock<HashMap<String, Value>>>;
 
@@ -16,6 +19,7 @@ pub fn new_session_state() -> SessionState {
 pub struct StateService {
     store: StoreDb,
     session: SessionState,
+    security: Option<Arc<Security>>,
 }
 
 impl StateService {
@@ -27,11 +31,83 @@ impl StateService {
         let service = Self {
             store: StoreDb::new()?,
             session,
+            security: None,
         };
         service.import_legacy_once()?;
         Ok(service)
     }
 
+    pub fn with_security(mut self, security: Arc<Security>) -> Self {
+        self.security = Some(security);
+        self
+    }
+
+    // Always the app's shared instance: a private copy would not see a key rotation and could
+    // seal or open values with a stale key.
+    fn security(&self) -> Result<Arc<Security>, ToolError> {
+        self.security.clone().ok_or_else(|| {
+            ToolError::internal("Sensitive state needs the shared secrets key").with_hint(
+                "This is an app wiring bug: build StateService with with_security(...)."
+                    .to_string(),
+            )
+        })
+    }
+
+    fn seal(&self, value: &Value) -> Result<Value, ToolError> {
+        let sealed = self.security()?.encrypt(&value.to_string())?;
+        Ok(serde_json::json!({ SEALED_KEY: sealed }))
+    }
+
+    // The stored value with a sealed one decrypted; anything else comes back as is.
+    fn unseal(&self, stored: Value) -> Result<Value, ToolError> {
+        let Some(sealed) = sealed_payload(&stored) else {
+            return Ok(stored);
+        };
+        let plain = self.security()?.decrypt(sealed)?;
+        serde_json::from_str(&plain)
+            .map_err(|err| ToolError::internal(format!("Sealed state value is not JSON: {}", err)))
+    }
+
+    // Like `set`, but a persistent value is encrypted with the secrets key before it is stored.
+    pub fn set_sensitive(
+        &self,
+        key: &str,
+        value: Value,
+        scope: Option<&str>,
+    ) -> Result<Value, ToolError> {
+        let persistent = self.normalize_scope(scope)? != "session";
+        let value = if persistent {
+            self.seal(&value)?
+        } else {
+            value
+        };
+        let mut result = self.set(key, value, scope)?;
+        result["sensitive"] = Value::Bool(true);
+        Ok(result)
+    }
+
+    // Seals every sensitive persistent value with the active key of `security`; all are
+    // decrypted before any is written.
+    pub fn reseal_sensitive(&self, security: &Security) -> Result<usize, ToolError> {
+        let mut updates = Vec::new();
+        for record in self.store.list(PERSISTENT_NAMESPACE)? {
+            let Some(sealed) = sealed_payload(&record.value) else {
+                continue;
+            };
+            let resealed = security.reseal(sealed).map_err(|err| {
+                err.with_details(serde_json::json!({"state_key": record.key.clone()}))
+            })?;
+            if resealed != sealed {
+                updates.push((record.key, serde_json::json!({ SEALED_KEY: resealed })));
+            }
+        }
+        for (key, value) in &updates {
+            self.store
+                .upsert(PERSISTENT_NAMESPACE, key, value, Some("local"))?;
+        }
+        Ok(updates.len())
+    }
+
     fn import_legacy_once(&self) -> Result<(), ToolError> {
         let path = crate::utils::paths::resolve_state_path();
         let import_key = format!("file:{}", path.display());
@@ -94,6 +170,24 @@ impl StateService {
         }))
     }
 
+    // Atomic read-modify-write of a persistent key (see StoreDb::update). `update` sees Null for
+    // an unset key and the raw stored form of values written with set_sensitive.
+    pub fn update_persistent<T>(
+        &self,
+        key: &str,
+        update: impl FnOnce(Value) -> Result<(Option<Value>, T), ToolError>,
+    ) -> Result<T, ToolError> {
+        if key.trim().is_empty() {
+            return Err(ToolError::invalid_params(
+                "State key must be a non-empty string",
+            ));
+        }
+        self.store
+            .update(PERSISTENT_NAMESPACE, key.trim(), Some("local"), |current| {
+                update(current.unwrap_or(Value::Null))
+            })
+    }
+
     pub fn get(&self, key: &str, scope: Option<&str>) -> Result<Value, ToolError> {
         if key.trim().is_empty() {
             return Err(ToolError::invalid_params(
@@ -111,14 +205,14 @@ impl StateService {
             resolved_scope = "session";
         } else if normalized == "persistent" {
             if let Some(record) = self.store.get(PERSISTENT_NAMESPACE, trimmed)? {
-                value = record.value;
+                value = self.unseal(record.value)?;
             }
             resolved_scope = "persistent";
         } else if let Some(val) = self.session.read().unwrap().get(trimmed) {
             value = val.clone();
             resolved_scope = "session";
         } else if let Some(record) = self.store.get(PERSISTENT_NAMESPACE, trimmed)? {
-            value = record.value;
+            value = self.unseal(record.value)?;
             resolved_scope = "persistent";
         }
         Ok(
@@ -247,6 +341,14 @@ impl StateService {
     }
 }
 
+fn sealed_payload(value: &Value) -> Option<&str> {
+    let map = value.as_object()?;
+    if map.len() != 1 {
+        return None;
+    }
+    map.get(SEALED_KEY)?.as_str()
+}
+
 fn merge_maps(a: &HashMap<String, Value>, b: &HashMap<String, Value>) -> HashMap<String, Value> {
     let mut out = a.clone();
     for (k, v) in b.iter() {
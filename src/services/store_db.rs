@@ -1,10 +1,30 @@
 use crate::errors::ToolError;
 use crate::utils::paths::{ensure_dir_exists, resolve_store_db_path};
-use rusqlite::{params, Connection, OptionalExtension};
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use rusqlite::{params, Connection, OptionalExtension, TransactionBehavior};
 use serde_json::Value;
 use std::path::{Path, PathBuf};
 use std::time::Duration;
 
+// Bumped after every committed write to a namespace, so in-process caches can tell cheaply
+// whether anything they read may have changed. Process-wide rather than per DB path: a bump
+// from another store only costs a spurious cache miss.
+static NAMESPACE_GENERATIONS: Lazy<DashMap<String, u64>> = Lazy::new(DashMap::new);
+
+pub fn namespace_generation(namespace: &str) -> u64 {
+    NAMESPACE_GENERATIONS
+        .get(namespace)
+        .map(|entry| *entry)
+        .unwrap_or(0)
+}
+
+fn bump_generation(namespace: &str) {
+    *NAMESPACE_GENERATIONS
+        .entry(namespace.to_string())
+        .or_insert(0) += 1;
+}
+
 #[derive(Debug, Clone)]
 pub struct StoreRecord {
     pub key: String,
@@ -206,31 +226,53 @@ impl StoreDb {
         source: Option<&str>,
     ) -> Result<(), ToolError> {
         let conn = self.open()?;
-        let payload = serde_json::to_string(value).map_err(|err| {
-            ToolError::internal(format!("Failed to serialize store value: {}", err))
-        })?;
-        conn.execute(
-            r#"
-            INSERT INTO store_entries(namespace, key, payload, source, deleted, updated_at)
-            VALUES(?1, ?2, ?3, ?4, 0, ?5)
-            ON CONFLICT(namespace, key) DO UPDATE SET
-                payload = excluded.payload,
-                source = excluded.source,
-                deleted = 0,
-                updated_at = excluded.updated_at
-            "#,
-            params![
-                namespace,
-                key,
-                payload,
-                source.unwrap_or("local"),
-                chrono::Utc::now().to_rfc3339(),
-            ],
-        )
-        .map_err(|err| ToolError::internal(format!("Failed to upsert store entry: {}", err)))?;
+        write_entry(&conn, namespace, key, value, source)?;
+        bump_generation(namespace);
         Ok(())
     }
 
+    // Read-modify-write of one entry under the DB write lock, so concurrent updaters (in this
+    // or another process) see each other's writes. `update` gets the live value and returns
+    // the value to store (None leaves the entry as it is) plus its result; an error rolls back.
+    pub fn update<T>(
+        &self,
+        namespace: &str,
+        key: &str,
+        source: Option<&str>,
+        update: impl FnOnce(Option<Value>) -> Result<(Option<Value>, T), ToolError>,
+    ) -> Result<T, ToolError> {
+        let mut conn = self.open()?;
+        let tx = conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(|err| {
+                ToolError::internal(format!("Failed to begin store transaction: {}", err))
+            })?;
+        let current = tx
+            .query_row(
+                r#"
+                SELECT key, payload, source, deleted
+                FROM store_entries
+                WHERE namespace = ?1 AND key = ?2 AND deleted = 0
+                LIMIT 1
+                "#,
+                params![namespace, key],
+                row_to_record,
+            )
+            .optional()
+            .map_err(|err| ToolError::internal(format!("Failed to read store entry: {}", err)))?;
+        let (next, out) = update(current.map(|record| record.value))?;
+        if let Some(value) = next.as_ref() {
+            write_entry(&tx, namespace, key, value, source)?;
+        }
+        tx.commit().map_err(|err| {
+            ToolError::internal(format!("Failed to commit store transaction: {}", err))
+        })?;
+        if next.is_some() {
+            bump_generation(namespace);
+        }
+        Ok(out)
+    }
+
     pub fn delete(&self, namespace: &str, key: &str) -> Result<bool, ToolError> {
         let conn = self.open()?;
         let changed = conn
@@ -239,6 +281,7 @@ impl StoreDb {
                 params![namespace, key],
             )
             .map_err(|err| ToolError::internal(format!("Failed to delete store entry: {}", err)))?;
+        bump_generation(namespace);
         Ok(changed > 0)
     }
 
@@ -267,6 +310,7 @@ impl StoreDb {
             ],
         )
         .map_err(|err| ToolError::internal(format!("Failed to tombstone store entry: {}", err)))?;
+        bump_generation(namespace);
         Ok(())
     }
 
@@ -277,10 +321,42 @@ impl StoreDb {
             params![namespace],
         )
         .map_err(|err| ToolError::internal(format!("Failed to clear store namespace: {}", err)))?;
+        bump_generation(namespace);
         Ok(())
     }
 }
 
+fn write_entry(
+    conn: &Connection,
+    namespace: &str,
+    key: &str,
+    value: &Value,
+    source: Option<&str>,
+) -> Result<(), ToolError> {
+    let payload = serde_json::to_string(value)
+        .map_err(|err| ToolError::internal(format!("Failed to serialize store value: {}", err)))?;
+    conn.execute(
+        r#"
+        INSERT INTO store_entries(namespace, key, payload, source, deleted, updated_at)
+        VALUES(?1, ?2, ?3, ?4, 0, ?5)
+        ON CONFLICT(namespace, key) DO UPDATE SET
+            payload = excluded.payload,
+            source = excluded.source,
+            deleted = 0,
+            updated_at = excluded.updated_at
+        "#,
+        params![
+            namespace,
+            key,
+            payload,
+            source.unwrap_or("local"),
+            chrono::Utc::now().to_rfc3339(),
+        ],
+    )
+    .map_err(|err| ToolError::internal(format!("Failed to upsert store entry: {}", err)))?;
+    Ok(())
+}
+
 fn row_to_record(row: &rusqlite::Row<'_>) -> rusqlite::Result<StoreRecord> {
     let payload: Option<String> = row.get(1)?;
     let value = match payload {
@@ -1,25 +1,43 @@
 use async_trait::async_trait;
 use sha2::{Digest, Sha256};
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
 
-use crate::errors::ToolError;
-use crate::services::alias::AliasService;
+use crate::errors::{ToolError, ToolErrorKind};
+use crate::services::alias::{expand_alias_call, AliasService};
 use crate::services::audit::AuditService;
-use crate::services::logger::Logger;
+use crate::services::evidence::artifact_refs_in;
+use crate::services::logger::{with_log_trace_id, Logger};
+use crate::services::middleware::{self, MiddlewareCall, ToolMiddleware};
+use crate::services::preset::PresetService;
+use crate::services::project_resolver::ProjectResolver;
 use crate::services::state::StateService;
-use crate::tooling::catalog::validate_tool_args;
+use crate::tooling::catalog::{arg_typos, check_tool_args, deprecation_for, result_hint_fields};
+use crate::tooling::dry_run::build_dry_run_plan;
 use crate::tooling::effects;
 use crate::utils::artifacts::{
-    build_tool_call_file_ref, resolve_context_root, write_text_artifact,
+    build_run_file_ref, build_tool_call_file_ref, resolve_artifact_path, resolve_context_root,
+    write_text_artifact,
+};
+use crate::utils::feature_flags::{
+    self, is_dry_run_enabled, is_dry_run_force_allowed, is_readonly_enabled,
+    is_result_artifacts_enabled, is_strict_args_enabled,
 };
 use crate::utils::merge::merge_deep;
 use crate::utils::output::apply_output_transform;
+use crate::utils::pg_store::PostgresStore;
 use crate::utils::redact::{is_sensitive_key, redact_object, redact_text};
+use crate::utils::remediation::{self, FailedCall};
+use crate::utils::runbook_capture::{
+    append_step, captured_step, increment, is_capture_action, CAPTURE_STATE_KEY,
+};
+use crate::utils::safe_name::ensure_safe_name;
 use crate::utils::suggest::suggest;
 use crate::utils::text::{truncate_utf8_prefix, truncate_utf8_suffix};
+use crate::utils::usage::{self, with_usage_scope, UsageScope};
 
-use serde_json::Value;
+use serde_json::{Map, Value};
 
 #[async_trait]
 pub trait ToolHandler: Send + Sync {
@@ -32,8 +50,13 @@ pub struct ToolExecutor {
     state_service: Arc<StateService>,
     alias_service: Option<Arc<AliasService>>,
     audit_service: Option<Arc<AuditService>>,
+    preset_service: Option<Arc<PresetService>>,
+    project_resolver: Option<Arc<ProjectResolver>>,
     handlers: Arc<HashMap<String, Arc<dyn ToolHandler>>>,
     alias_map: HashMap<String, String>,
+    middleware: Arc<Vec<Arc<dyn ToolMiddleware>>>,
+    result_index_lock: Arc<Mutex<()>>,
+    capture_lock: Arc<Mutex<()>>,
 }
 
 #[derive(Clone)]
@@ -43,6 +66,11 @@ pub(crate) struct ToolCallMeta {
     pub span_id: String,
     pub parent_span_id: Option<String>,
     pub invoked_as: Option<String>,
+    pub warnings: Vec<Value>,
+    pub presets: Vec<String>,
+    pub store: Option<(String, String)>,
+    pub store_postgres: Option<PostgresStore>,
+    pub usage: Option<Value>,
 }
 
 impl ToolExecutor {
@@ -59,9 +87,64 @@ impl ToolExecutor {
             state_service,
             alias_service,
             audit_service,
+            preset_service: None,
+            project_resolver: None,
             handlers: Arc::new(handlers),
             alias_map,
+            middleware: Arc::new(Vec::new()),
+            result_index_lock: Arc::new(Mutex::new(())),
+            capture_lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    pub fn with_preset_service(mut self, preset_service: Arc<PresetService>) -> Self {
+        self.preset_service = Some(preset_service);
+        self
+    }
+
+    pub fn with_project_resolver(mut self, project_resolver: Arc<ProjectResolver>) -> Self {
+        self.project_resolver = Some(project_resolver);
+        self
+    }
+
+    // Embedder middleware, in the order its `before` hooks run.
+    pub fn with_middleware(mut self, middleware: Vec<Arc<dyn ToolMiddleware>>) -> Self {
+        self.middleware = Arc::new(middleware);
+        self
+    }
+
+    // Every stage a call passes through, built-ins and middleware hooks, in order.
+    pub fn chain(&self) -> Vec<String> {
+        let names: Vec<String> = self
+            .middleware
+            .iter()
+            .map(|middleware| middleware.name().to_string())
+            .collect();
+        middleware::chain(&names)
+    }
+
+    async fn run_before_hooks(
+        &self,
+        call: &mut MiddlewareCall,
+        args: Value,
+    ) -> Result<Value, ToolError> {
+        let mut args = args;
+        for middleware in self.middleware.iter() {
+            args = middleware.before(call, args).await?;
+        }
+        Ok(args)
+    }
+
+    async fn run_after_hooks(
+        &self,
+        call: &mut MiddlewareCall,
+        result: Value,
+    ) -> Result<Value, ToolError> {
+        let mut result = result;
+        for middleware in self.middleware.iter().rev() {
+            result = middleware.after(call, result).await?;
         }
+        Ok(result)
     }
 
     async fn resolve_alias(&self, tool: &str) -> (String, Option<Value>) {
@@ -126,18 +209,53 @@ impl ToolExecutor {
         None
     }
 
-    fn normalize_alias_args(&self, alias: Option<&Value>) -> Option<Value> {
-        let alias = alias?;
-        alias.get("args").cloned().filter(|v| v.is_object())
+    // store_scope=project keeps the key under project/<name>/<target>/ of the call's resolved
+    // context (persistent). Resolved before the handler runs so a missing project fails the
+    // call instead of silently storing a global key.
+    async fn resolve_store_target(
+        &self,
+        args: &Value,
+    ) -> Result<Option<(String, String)>, ToolError> {
+        let Some((key, scope)) =
+            self.normalize_store_target(args.get("store_as"), args.get("store_scope"))
+        else {
+            return Ok(None);
+        };
+        let key = ensure_safe_name(&key, "store_as")?;
+        if !scope.eq_ignore_ascii_case("project") {
+            return Ok(Some((key, scope)));
+        }
+        let Some(resolver) = self.project_resolver.as_ref() else {
+            return Err(ToolError::invalid_params(
+                "store_scope=project is not available without project resolution",
+            ));
+        };
+        let namespace = resolver.state_namespace(args).await.map_err(|err| {
+            let message = format!("store_scope=project: {}", err.message);
+            ToolError { message, ..err }
+        })?;
+        Ok(Some((
+            format!("{}/{}", namespace, key.trim()),
+            "persistent".to_string(),
+        )))
     }
 
-    fn merge_args(&self, alias_args: Option<&Value>, args: &Value) -> Value {
+    fn merge_args(
+        &self,
+        tool: &str,
+        alias_args: Option<&Value>,
+        args: &Value,
+    ) -> Result<(Value, Vec<String>), ToolError> {
+        if let Some(service) = &self.preset_service {
+            let layered = service.layer_call_args(tool, alias_args, args)?;
+            return Ok((layered.args, layered.presets));
+        }
         let mut merged = Value::Object(Default::default());
         if let Some(alias_args) = alias_args {
             merged = merge_deep(&merged, alias_args);
         }
         merged = merge_deep(&merged, args);
-        merged
+        Ok((merged, Vec::new()))
     }
 
     fn strip_args_for_handler(&self, args: &Value) -> Value {
@@ -148,6 +266,9 @@ impl ToolExecutor {
             map.remove("store_scope");
             map.remove("preset");
             map.remove("preset_name");
+            map.remove("force_execute");
+            map.remove("include_usage");
+            map.remove("capture");
         }
         cleaned
     }
@@ -162,14 +283,59 @@ impl ToolExecutor {
         cleaned
     }
 
+    // Handlers check required fields themselves; when one is missing, a misspelled key in the
+    // call is the usual cause, so name it next to the handler's message.
+    fn annotate_arg_typos(&self, tool: &str, args: &Value, err: ToolError) -> ToolError {
+        if err.kind != ToolErrorKind::InvalidParams
+            || !matches!(err.details, None | Some(Value::Object(_)))
+        {
+            return err;
+        }
+        let cleaned = self.strip_args_for_validation(args);
+        let typos = arg_typos(tool, &cleaned, &err.message);
+        if typos.unrecognized.is_empty() {
+            return err;
+        }
+        let mut err = err;
+        if !typos.did_you_mean.is_empty() {
+            let pairs: Vec<String> = typos
+                .did_you_mean
+                .iter()
+                .map(|(key, meant)| format!("{} (not {})", meant.as_str().unwrap_or(""), key))
+                .collect();
+            let hint = format!("Did you mean {}?", pairs.join(", "));
+            err.hint = Some(match err.hint.take() {
+                Some(existing) => format!("{} {}", hint, existing),
+                None => hint,
+            });
+        }
+        let mut details = match err.details.take() {
+            Some(Value::Object(map)) => map,
+            _ => serde_json::Map::new(),
+        };
+        if !typos.did_you_mean.is_empty() {
+            details.insert(
+                "did_you_mean".to_string(),
+                Value::Object(typos.did_you_mean),
+            );
+        }
+        details.insert(
+            "unrecognized_args".to_string(),
+            serde_json::json!(typos.unrecognized),
+        );
+        err.with_details(Value::Object(details))
+    }
+
     fn validate_effective_args(
         &self,
         tool: &str,
         args: &Value,
         invoked_as: Option<&str>,
-    ) -> Result<(), ToolError> {
+    ) -> Result<Vec<Value>, ToolError> {
         let cleaned = self.strip_args_for_validation(args);
-        validate_tool_args(tool, &cleaned).map_err(|err| {
+        let strict = is_strict_args_enabled();
+        let report = check_tool_args(tool, &cleaned);
+        if !report.is_valid(strict) {
             let mut details = serde_json::Map::new();
             details.insert(
                 "stage".to_string(),
@@ -179,8 +345,34 @@ impl ToolExecutor {
             if let Some(alias) = invoked_as {
                 details.insert("invoked_as".to_string(), Value::String(alias.to_string()));
             }
-            ToolError::invalid_params(err.message).with_details(Value::Object(details))
-        })
+            details.insert(
+                "violations".to_string(),
+                Value::Array(
+                    report
+                        .blocking(strict)
+                        .into_iter()
+                        .map(|violation| violation.to_value())
+                        .collect(),
+                ),
+            );
+            details.insert("strict".to_string(), Value::Bool(strict));
+            return Err(
+                ToolError::invalid_params(report.to_contract_error(strict).message)
+                    .with_details(Value::Object(details)),
+            );
+        }
+        let warnings: Vec<Value> = report
+            .unknown
+            .iter()
+            .map(|violation| violation.to_value())
+            .collect();
+        if !warnings.is_empty() {
+            self.logger.warn(
+                "Unknown arguments ignored",
+                Some(&serde_json::json!({ "tool": tool, "unknown": warnings })),
+            );
+        }
+        Ok(warnings)
     }
 
     fn reject_preset_compat(&self, args: &Value, alias: Option<&Value>) -> Result<(), ToolError> {
@@ -223,6 +415,167 @@ impl ToolExecutor {
         redact_object(&cleaned, 2048, None)
     }
 
+    // Failed handler calls are audited too so workspace.suggest can react to recent errors.
+    fn audit_failure(
+        &self,
+        tool: &str,
+        args: &Value,
+        err: &ToolError,
+        started_at: i64,
+        invoked_as: Option<&String>,
+        middleware: &Map<String, Value>,
+    ) {
+        self.record_result_artifact(
+            tool,
+            args,
+            started_at,
+            "error",
+            &serde_json::json!({
+                "ok": false,
+                "error": redact_object(
+                    &serde_json::to_value(err).unwrap_or(Value::Null),
+                    usize::MAX,
+                    None,
+                ),
+            }),
+            Vec::new(),
+        );
+        let Some(audit) = &self.audit_service else {
+            return;
+        };
+        audit.append(&serde_json::json!({
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+            "status": "error",
+            "tool": tool,
+            "action": args.get("action"),
+            "trace_id": args.get("trace_id"),
+            "span_id": args.get("span_id"),
+            "parent_span_id": args.get("parent_span_id"),
+            "invoked_as": invoked_as,
+            "input": self.build_audit_args(args),
+            "error": {
+                "kind": err.kind,
+                "code": err.code,
+                "message": redact_text(&err.message, 2048, None),
+                "details": err.details.as_ref().map(|details| redact_object(details, 2048, None)),
+            },
+            "middleware": middleware_audit(middleware),
+            "duration_ms": chrono::Utc::now().timestamp_millis() - started_at,
+        }));
+    }
+
+    // While runbook_capture_start is active, each call made directly by the client becomes a
+    // draft step; calls a tool makes on its own behalf (runbook steps, project fan-out) are part
+    // of the call that made them and are not recorded again.
+    fn capture_call(&self, tool: &str, args: &Value, error: Option<&ToolError>) {
+        let action = args.get("action").and_then(|v| v.as_str());
+        if is_capture_action(tool, action) {
+            return;
+        }
+        let _guard = self
+            .capture_lock
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        let Ok(current) = self.state_service.get(CAPTURE_STATE_KEY, Some("session")) else {
+            return;
+        };
+        let mut capture = match current.get("value") {
+            Some(Value::Object(_)) => current["value"].clone(),
+            _ => return,
+        };
+        if args.get("capture").and_then(|v| v.as_bool()) == Some(false) {
+            increment(&mut capture, "skipped");
+        } else {
+            append_step(&mut capture, captured_step(tool, args, error));
+        }
+        let _ = self
+            .state_service
+            .set(CAPTURE_STATE_KEY, capture, Some("session"));
+    }
+
+    // INFRA_RESULT_ARTIFACTS=1: every call writes its full (redacted, untruncated) result to
+    // runs/<trace>/tool_calls/<span>/result.json and is listed in runs/<trace>/index.json.
+    // Artifact failures are logged and never fail the call itself.
+    fn record_result_artifact(
+        &self,
+        tool: &str,
+        args: &Value,
+        started_at: i64,
+        status: &str,
+        body: &Value,
+        refs: Vec<Value>,
+    ) -> Option<String> {
+        if !is_result_artifacts_enabled() {
+            return None;
+        }
+        let context_root = resolve_context_root()?;
+        let trace_id = args.get("trace_id").and_then(|v| v.as_str());
+        let span_id = args.get("span_id").and_then(|v| v.as_str());
+        let entry = serde_json::json!({
+            "tool": tool,
+            "action": args.get("action").cloned().unwrap_or(Value::Null),
+            "span_id": span_id,
+            "parent_span_id": args.get("parent_span_id").cloned().unwrap_or(Value::Null),
+            "status": status,
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+            "duration_ms": chrono::Utc::now().timestamp_millis() - started_at,
+        });
+        let outcome =
+            build_tool_call_file_ref(trace_id, span_id, "result.json").and_then(|reference| {
+                let content = serde_json::to_string_pretty(body)
+                    .map_err(|err| ToolError::internal(err.to_string()))?;
+                let written = write_text_artifact(&context_root, &reference, &content)?;
+                let mut entry = entry;
+                let mut refs = refs;
+                refs.insert(
+                    0,
+                    serde_json::json!({"uri": written.uri, "bytes": written.bytes}),
+                );
+                entry["refs"] = Value::Array(refs);
+                self.append_result_index(&context_root, trace_id, entry)?;
+                Ok(written.uri)
+            });
+        match outcome {
+            Ok(uri) => Some(uri),
+            Err(err) => {
+                self.logger.warn(
+                    "Failed to write result artifact",
+                    Some(&serde_json::json!({ "tool": tool, "error": err.message })),
+                );
+                None
+            }
+        }
+    }
+
+    // Parallel calls of one trace finish concurrently; the lock keeps the read-modify-write of
+    // index.json from dropping entries, the atomic write keeps readers from seeing partial files.
+    fn append_result_index(
+        &self,
+        context_root: &Path,
+        trace_id: Option<&str>,
+        entry: Value,
+    ) -> Result<(), ToolError> {
+        let reference = build_run_file_ref(trace_id, "index.json")?;
+        let path = resolve_artifact_path(context_root, &reference.rel)?;
+        let _guard = self
+            .result_index_lock
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut index = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|raw| serde_json::from_str::<Value>(&raw).ok())
+            .filter(|value| value.get("calls").map(|v| v.is_array()).unwrap_or(false))
+            .unwrap_or_else(|| serde_json::json!({ "trace_id": trace_id, "calls": [] }));
+        if let Some(calls) = index.get_mut("calls").and_then(|v| v.as_array_mut()) {
+            calls.push(entry);
+        }
+        index["updated_at"] = Value::String(chrono::Utc::now().to_rfc3339());
+        let content = serde_json::to_string_pretty(&index)
+            .map_err(|err| ToolError::internal(err.to_string()))?;
+        write_text_artifact(context_root, &reference, &content)?;
+        Ok(())
+    }
+
     fn summarize_result(&self, result: &Value) -> Value {
         if result.is_null() {
             return serde_json::json!({"type": "null"});
@@ -335,6 +688,77 @@ impl ToolExecutor {
         Ok(value.clone())
     }
 
+    // INFRA_MAX_RESULT_BYTES caps the serialized result. The full result goes to
+    // result_full.json and the catalog's hinted list fields keep only the leading items that fit
+    // an equal share of the budget; a result still too large is replaced by its summary.
+    fn guard_result_size(
+        &self,
+        tool: &str,
+        args: &Value,
+        result: &Value,
+        ctx: &SpillContext,
+        max_bytes: usize,
+    ) -> Result<Option<(Value, Value)>, ToolError> {
+        let bytes = serialized_len(result);
+        if bytes <= max_bytes {
+            return Ok(None);
+        }
+        let mut artifact = Value::Null;
+        if let Some(context_root) = ctx.context_root.as_ref() {
+            let reference = build_tool_call_file_ref(
+                ctx.trace_id.as_deref(),
+                ctx.span_id.as_deref(),
+                "result_full.json",
+            )?;
+            let content = serde_json::to_string_pretty(result)
+                .map_err(|err| ToolError::internal(err.to_string()))?;
+            artifact = Value::String(write_text_artifact(context_root, &reference, &content)?.uri);
+        }
+
+        let action = args.get("action").and_then(|v| v.as_str());
+        let mut inline = result.clone();
+        let mut lists = Vec::new();
+        for field in result_hint_fields(tool, action) {
+            if let Some(Value::Array(items)) = value_at_path_mut(&mut inline, field) {
+                lists.push((field, std::mem::take(items)));
+            }
+        }
+        let mut fields = Vec::new();
+        if !lists.is_empty() {
+            let share = max_bytes.saturating_sub(serialized_len(&inline)) / lists.len();
+            for (field, items) in lists {
+                let mut used = 0;
+                let kept: Vec<Value> = items
+                    .iter()
+                    .take_while(|item| {
+                        used += serialized_len(item) + 1;
+                        used <= share
+                    })
+                    .cloned()
+                    .collect();
+                fields.push(serde_json::json!({
+                    "field": field,
+                    "total": items.len(),
+                    "kept": kept.len(),
+                }));
+                if let Some(slot) = value_at_path_mut(&mut inline, field) {
+                    *slot = Value::Array(kept);
+                }
+            }
+        }
+        if serialized_len(&inline) > max_bytes {
+            inline = serde_json::json!({ "summary": self.summarize_result(result) });
+        }
+        let truncation = serde_json::json!({
+            "bytes": bytes,
+            "inline_bytes": serialized_len(&inline),
+            "max_bytes": max_bytes,
+            "artifact": artifact,
+            "fields": fields,
+        });
+        Ok(Some((inline, truncation)))
+    }
+
     pub(crate) async fn wrap_result(
         &self,
         tool: &str,
@@ -348,15 +772,19 @@ impl ToolExecutor {
             span_id,
             parent_span_id,
             invoked_as,
+            warnings,
+            presets,
+            store,
+            store_postgres,
+            usage,
         } = meta;
         let output = args.get("output");
-        let store = self.normalize_store_target(args.get("store_as"), args.get("store_scope"));
         let shaped = apply_output_transform(result, output)?;
 
         let context_root = resolve_context_root();
-        let max_inline_bytes = env_u64("INFRA_MAX_INLINE_BYTES", 16 * 1024) as usize;
-        let max_capture_bytes = env_u64("INFRA_MAX_CAPTURE_BYTES", 256 * 1024) as usize;
-        let max_spills = env_u64("INFRA_MAX_SPILLS", 20) as usize;
+        let max_inline_bytes = feature_flags::MAX_INLINE_BYTES.number() as usize;
+        let max_capture_bytes = feature_flags::MAX_CAPTURE_BYTES.number() as usize;
+        let max_spills = feature_flags::MAX_SPILLS.number() as usize;
 
         let extra_secrets = collect_secret_values(args.get("env"));
         let ctx = SpillContext {
@@ -373,13 +801,49 @@ impl ToolExecutor {
             spilled: 0,
         };
         let spilled = Self::spill_large_values(&shaped, &[], &ctx, &mut state)?;
+        let max_result_bytes = feature_flags::MAX_RESULT_BYTES.number() as usize;
+        let guarded = self.guard_result_size(tool, args, &spilled, &ctx, max_result_bytes)?;
+        let artifact_uri_json = self.record_result_artifact(
+            tool,
+            args,
+            started_at,
+            "ok",
+            &redact_object(&shaped, usize::MAX, ctx.extra_secrets.as_deref()),
+            artifact_refs_in(&spilled),
+        );
 
+        // store_as keeps the full value unless it exceeds INFRA_MAX_STATE_VALUE_BYTES; then the
+        // artifact ref of the full result is stored (the truncated result without a context repo).
+        let stored_key = store.as_ref().map(|(key, _)| key.clone());
         if let Some((key, scope)) = store {
-            let _ = self.state_service.set(&key, spilled.clone(), Some(&scope));
+            let max_state_bytes = feature_flags::MAX_STATE_VALUE_BYTES.number() as usize;
+            let value = match guarded.as_ref() {
+                Some((inline, truncation)) if serialized_len(&spilled) > max_state_bytes => {
+                    if truncation["artifact"].is_string() {
+                        serde_json::json!({
+                            "result_truncated": true,
+                            "artifact": truncation["artifact"],
+                            "bytes": truncation["bytes"],
+                        })
+                    } else {
+                        inline.clone()
+                    }
+                }
+                _ => spilled.clone(),
+            };
+            let _ = self.state_service.set(&key, value, Some(&scope));
         }
 
+        let store_error = match store_postgres {
+            Some(spec) => self
+                .store_to_postgres(tool, args, &spec, &shaped, &trace_id, &span_id)
+                .await
+                .err(),
+            None => None,
+        };
+
         let resolved_effects = effects::resolve_tool_call_effects_for_result(tool, args, result);
-        let meta = serde_json::json!({
+        let mut meta = serde_json::json!({
             "tool": tool,
             "action": args.get("action").cloned().unwrap_or(Value::Null),
             "trace_id": trace_id,
@@ -390,15 +854,135 @@ impl ToolExecutor {
             "invoked_as": invoked_as,
             "effects": resolved_effects.to_value(),
         });
+        if !warnings.is_empty() {
+            meta["warnings"] = Value::Array(warnings);
+        }
+        if !presets.is_empty() {
+            meta["presets"] = serde_json::json!(presets);
+        }
+        if is_project_store_scope(args) {
+            meta["stored_key"] = stored_key.map(Value::String).unwrap_or(Value::Null);
+        }
+        if let Some(uri) = artifact_uri_json {
+            meta["artifact_uri_json"] = Value::String(uri);
+        }
+        if let Some(usage) = usage {
+            meta["resource_usage"] = usage;
+        }
+        let body = match guarded {
+            Some((inline, truncation)) => {
+                meta["result_truncated"] = Value::Bool(true);
+                meta["truncation"] = truncation;
+                inline
+            }
+            None => spilled,
+        };
 
-        Ok(serde_json::json!({
+        let mut payload = serde_json::json!({
             "ok": true,
-            "result": spilled,
+            "result": body,
             "meta": meta,
-        }))
+        });
+        if let Some(error) = store_error {
+            payload["store_error"] = error;
+        }
+        Ok(payload)
     }
 
-    pub async fn execute(&self, tool: &str, args: Value) -> Result<Value, ToolError> {
+    // store_as.postgres appends one row through the sql handler. The call already succeeded, so
+    // a failure here is audited and returned as `store_error` instead of failing it.
+    async fn store_to_postgres(
+        &self,
+        tool: &str,
+        args: &Value,
+        spec: &PostgresStore,
+        output: &Value,
+        trace_id: &str,
+        span_id: &str,
+    ) -> Result<(), Value> {
+        let outcome = async {
+            if is_readonly_enabled() {
+                return Err(ToolError::denied("INFRA_READONLY=1 denies store_as.postgres")
+                    .with_hint("Unset INFRA_READONLY to append results to Postgres."));
+            }
+            let handler = self.handlers.get("sql").ok_or_else(|| {
+                ToolError::internal("store_as.postgres requires the sql tool")
+            })?;
+            let row = spec.build_row(output, trace_id)?;
+            let mut insert = spec.insert_args(row, args);
+            insert["trace_id"] = Value::from(trace_id);
+            insert["parent_span_id"] = Value::from(span_id);
+            handler.handle(insert).await.map_err(|err| {
+                let sqlstate = err
+                    .details
+                    .as_ref()
+                    .and_then(|details| details.get("sqlstate"))
+                    .and_then(|v| v.as_str());
+                if sqlstate != Some("42P01") {
+                    return err;
+                }
+                ToolError::not_found(format!(
+                    "store_as.postgres table {} does not exist",
+                    spec.table
+                ))
+                .with_hint(
+                    "Create the table, or set store_as.postgres.create_table to \"if_missing\" to create it from the row.",
+                )
+            })
+        }
+        .await;
+        let Err(err) = outcome else {
+            return Ok(());
+        };
+        let error = serde_json::json!({
+            "kind": err.kind,
+            "code": err.code,
+            "message": redact_text(&err.message, 2048, None),
+            "hint": err.hint,
+        });
+        self.logger.warn(
+            "store_as.postgres failed",
+            Some(&serde_json::json!({
+                "tool": tool,
+                "table": spec.table,
+                "error": error,
+                "trace_id": trace_id,
+            })),
+        );
+        if let Some(audit) = &self.audit_service {
+            audit.append(&serde_json::json!({
+                "timestamp": chrono::Utc::now().to_rfc3339(),
+                "status": "error",
+                "tool": tool,
+                "action": args.get("action"),
+                "stage": "store_as.postgres",
+                "trace_id": trace_id,
+                "span_id": span_id,
+                "table": spec.table,
+                "error": error,
+            }));
+        }
+        Err(error)
+    }
+
+    // Errors leave with a catalog hint and next_actions built from this call; the trace id is
+    // fixed up front so those suggestions can point at the call's audit trace.
+    pub async fn execute(&self, tool: &str, mut args: Value) -> Result<Value, ToolError> {
+        if let Some(map) = args.as_object_mut() {
+            if !map.get("trace_id").is_some_and(|v| v.is_string()) {
+                map.insert(
+                    "trace_id".to_string(),
+                    Value::String(uuid::Uuid::new_v4().to_string()),
+                );
+            }
+        }
+        let call = FailedCall::new(tool, &args);
+        self.execute_call(tool, args)
+            .await
+            .map_err(|err| remediation::enrich(err, &call))
+    }
+
+    async fn execute_call(&self, tool: &str, args: Value) -> Result<Value, ToolError> {
         let started_at = chrono::Utc::now().timestamp_millis();
         let (resolved_tool, alias) = self.resolve_alias(tool).await;
         let handler = self.handlers.get(&resolved_tool);
@@ -434,10 +1018,14 @@ impl ToolExecutor {
             .get("parent_span_id")
             .and_then(|v| v.as_str())
             .map(|s| s.to_string());
+        let top_level = usage::current().is_none() && parent_span_id.is_none();
 
         self.reject_preset_compat(&args, alias.as_ref())?;
-        let alias_args = self.normalize_alias_args(alias.as_ref());
-        let merged_args = self.merge_args(alias_args.as_ref(), &args);
+        let (alias_args, args) = match alias.as_ref() {
+            Some(spec) => expand_alias_call(tool, spec, &args)?,
+            None => (None, args),
+        };
+        let (merged_args, presets) = self.merge_args(&resolved_tool, alias_args.as_ref(), &args)?;
 
         let mut merged_args = merged_args;
         if let Value::Object(map) = &mut merged_args {
@@ -448,17 +1036,58 @@ impl ToolExecutor {
             }
         }
 
-        let cleaned_args = self.strip_args_for_handler(&merged_args);
         let invoked_as = alias
             .as_ref()
             .and_then(|v| v.get("name"))
             .and_then(|v| v.as_str())
             .map(|s| s.to_string());
 
-        self.validate_effective_args(&resolved_tool, &merged_args, invoked_as.as_deref())?;
+        let mut call = MiddlewareCall {
+            tool: resolved_tool.clone(),
+            action: merged_args
+                .get("action")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string()),
+            trace_id: trace_id.clone(),
+            metadata: Map::new(),
+        };
+        let merged_args = if self.middleware.is_empty() {
+            merged_args
+        } else {
+            match self.run_before_hooks(&mut call, merged_args.clone()).await {
+                Ok(args) => args,
+                Err(err) => {
+                    self.audit_failure(
+                        &resolved_tool,
+                        &merged_args,
+                        &err,
+                        started_at,
+                        invoked_as.as_ref(),
+                        &call.metadata,
+                    );
+                    return Err(err);
+                }
+            }
+        };
 
-        self.logger
-            .debug(resolved_tool.as_str(), merged_args.get("action"));
+        let cleaned_args = self.strip_args_for_handler(&merged_args);
+
+        let mut warnings =
+            self.validate_effective_args(&resolved_tool, &merged_args, invoked_as.as_deref())?;
+        let action = merged_args.get("action").and_then(|v| v.as_str());
+        if let Some(deprecation) =
+            deprecation_for(tool, action).or_else(|| deprecation_for(&resolved_tool, action))
+        {
+            warnings.push(deprecation.warning());
+        }
+
+        self.logger.debug(
+            resolved_tool.as_str(),
+            Some(&serde_json::json!({
+                "action": merged_args.get("action"),
+                "trace_id": trace_id,
+            })),
+        );
 
         // Global effects enforcement (flagship safety): tools declare whether they are read/write/mixed
         // and whether they need explicit `apply` (opt-in) and/or `confirm` (irreversible).
@@ -471,20 +1100,38 @@ impl ToolExecutor {
             .get("confirm")
             .and_then(|v| v.as_bool())
             .unwrap_or(false);
+        if is_readonly_enabled() && effects.effects.is_write_classified() {
+            return Err(ToolError::denied(format!(
+                "INFRA_READONLY=1 denies {} effects{}",
+                effects.effects.class(),
+                effects.triggered_by()
+            ))
+            .with_hint(
+                "Unset INFRA_READONLY to allow write operations, or use a read-only action."
+                    .to_string(),
+            )
+            .with_details(serde_json::json!({
+                "tool": resolved_tool,
+                "action": merged_args.get("action"),
+                "effects": effects.to_value(),
+                "readonly": true,
+            })));
+        }
         if effects.effects.requires_apply && !apply {
-            return Err(
-                ToolError::denied("Action requires apply=true for write/mixed effects")
-                    .with_hint(
-                        "Rerun with apply=true if you intend to perform write operations."
-                            .to_string(),
-                    )
-                    .with_details(serde_json::json!({ "effects": effects.to_value() })),
-            );
+            return Err(ToolError::denied(format!(
+                "Action requires apply=true for write/mixed effects{}",
+                effects.triggered_by()
+            ))
+            .with_hint(
+                "Rerun with apply=true if you intend to perform write operations.".to_string(),
+            )
+            .with_details(serde_json::json!({ "effects": effects.to_value() })));
         }
         if effects.effects.irreversible && !confirm {
-            return Err(ToolError::denied(
-                "Action requires confirm=true for irreversible effects",
-            )
+            return Err(ToolError::denied(format!(
+                "Action requires confirm=true for irreversible effects{}",
+                effects.triggered_by()
+            ))
             .with_hint(
                 "Rerun with confirm=true if you understand this cannot be safely auto-rolled-back."
                     .to_string(),
@@ -492,22 +1139,138 @@ impl ToolExecutor {
             .with_details(serde_json::json!({ "effects": effects.to_value() })));
         }
 
-        let budget_ms = env_u64("INFRA_TOOL_CALL_TIMEOUT_MS", 55_000);
-        let result = match tokio::time::timeout(
+        // INFRA_DRY_RUN intercepts after the gates above, so the plan describes a call that
+        // would actually have been allowed to run.
+        if is_dry_run_enabled() && effects.effects.is_write_classified() {
+            let force = merged_args
+                .get("force_execute")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+            if !(force && is_dry_run_force_allowed()) {
+                let mut plan = build_dry_run_plan(&resolved_tool, &merged_args, &effects);
+                if force {
+                    plan["force_execute_ignored"] = Value::Bool(true);
+                }
+                let payload = self
+                    .wrap_result(
+                        &resolved_tool,
+                        &merged_args,
+                        &plan,
+                        ToolCallMeta {
+                            started_at,
+                            trace_id: trace_id.clone(),
+                            span_id: span_id.clone(),
+                            parent_span_id: parent_span_id.clone(),
+                            invoked_as: invoked_as.clone(),
+                            warnings,
+                            presets,
+                            store: None,
+                            store_postgres: None,
+                            usage: None,
+                        },
+                    )
+                    .await?;
+                if let Some(audit) = &self.audit_service {
+                    audit.append(&serde_json::json!({
+                        "timestamp": chrono::Utc::now().to_rfc3339(),
+                        "status": "dry_run",
+                        "tool": resolved_tool,
+                        "action": merged_args.get("action"),
+                        "trace_id": trace_id,
+                        "span_id": span_id,
+                        "parent_span_id": parent_span_id,
+                        "invoked_as": invoked_as,
+                        "input": self.build_audit_args(&merged_args),
+                        "effects": effects.to_value(),
+                        "plan": plan,
+                        "middleware": middleware_audit(&call.metadata),
+                        "duration_ms": chrono::Utc::now().timestamp_millis() - started_at,
+                    }));
+                }
+                return Ok(payload);
+            }
+        }
+
+        let store = self.resolve_store_target(&merged_args).await?;
+        let store_postgres = PostgresStore::parse(merged_args.get("store_as"))?;
+
+        let budget_ms = feature_flags::TOOL_CALL_TIMEOUT_MS.number();
+        let usage_scope = UsageScope::nested();
+        let outcome = tokio::time::timeout(
             std::time::Duration::from_millis(budget_ms),
-            handler.unwrap().handle(cleaned_args),
+            with_usage_scope(
+                usage_scope.clone(),
+                with_log_trace_id(trace_id.clone(), handler.unwrap().handle(cleaned_args)),
+            ),
         )
-        .await
-        {
-            Ok(result) => result?,
+        .await;
+        let handler_ms = chrono::Utc::now().timestamp_millis() - started_at;
+        usage::aggregate(
+            &resolved_tool,
+            &usage_scope,
+            handler_ms,
+            !matches!(outcome, Ok(Ok(_))),
+        );
+        let result = match outcome {
+            Ok(Ok(result)) => result,
+            Ok(Err(err)) => {
+                let err = self.annotate_arg_typos(&resolved_tool, &merged_args, err);
+                self.logger.warn(
+                    "Tool call failed",
+                    Some(&serde_json::json!({
+                        "tool": resolved_tool,
+                        "action": merged_args.get("action"),
+                        "code": err.code,
+                        "error": err.message,
+                        "trace_id": trace_id,
+                    })),
+                );
+                self.audit_failure(
+                    &resolved_tool,
+                    &merged_args,
+                    &err,
+                    started_at,
+                    invoked_as.as_ref(),
+                    &call.metadata,
+                );
+                if top_level {
+                    self.capture_call(&resolved_tool, &merged_args, Some(&err));
+                }
+                return Err(err);
+            }
             Err(_) => {
-                return Err(ToolError::timeout("Tool call timed out").with_details(
-                    serde_json::json!({
+                let err =
+                    ToolError::timeout("Tool call timed out").with_details(serde_json::json!({
                         "tool": resolved_tool,
                         "action": merged_args.get("action"),
                         "timeout_ms": budget_ms,
-                    }),
-                ));
+                    }));
+                self.audit_failure(
+                    &resolved_tool,
+                    &merged_args,
+                    &err,
+                    started_at,
+                    invoked_as.as_ref(),
+                    &call.metadata,
+                );
+                if top_level {
+                    self.capture_call(&resolved_tool, &merged_args, Some(&err));
+                }
+                return Err(err);
+            }
+        };
+        let result = match self.run_after_hooks(&mut call, result).await {
+            Ok(result) => result,
+            Err(err) => {
+                self.audit_failure(
+                    &resolved_tool,
+                    &merged_args,
+                    &err,
+                    started_at,
+                    invoked_as.as_ref(),
+                    &call.metadata,
+                );
+                return Err(err);
             }
         };
         let payload = self
@@ -521,12 +1284,17 @@ impl ToolExecutor {
                     span_id: span_id.clone(),
                     parent_span_id: parent_span_id.clone(),
                     invoked_as: invoked_as.clone(),
+                    warnings,
+                    presets,
+                    store,
+                    store_postgres,
+                    usage: include_usage(&merged_args).then(|| usage_scope.to_value(handler_ms)),
                 },
             )
             .await?;
 
         if let Some(audit) = &self.audit_service {
-            audit.append(&serde_json::json!({
+            let mut entry = serde_json::json!({
                 "timestamp": chrono::Utc::now().to_rfc3339(),
                 "status": "ok",
                 "tool": resolved_tool,
@@ -536,15 +1304,73 @@ impl ToolExecutor {
                 "parent_span_id": parent_span_id,
                 "invoked_as": invoked_as,
                 "input": self.build_audit_args(&merged_args),
+                "effects": payload.get("meta").and_then(|meta| meta.get("effects")).cloned().unwrap_or_else(|| effects.to_value()),
                 "result_summary": self.summarize_result(payload.get("result").unwrap_or(&Value::Null)),
+                "middleware": middleware_audit(&call.metadata),
                 "duration_ms": chrono::Utc::now().timestamp_millis() - started_at,
-            }));
+            });
+            // Intent applies name the preview they consumed and its project/target scope, so
+            // reviews can line approvals up with executions.
+            if let Some(record) = payload
+                .get("result")
+                .and_then(|result| result.get("intent_record"))
+            {
+                entry["intent"] = record.clone();
+            }
+            // Dry runs executed for real and were undone; say so instead of a bare "ok".
+            if payload
+                .get("result")
+                .and_then(|result| result.get("rolled_back"))
+                .and_then(|v| v.as_bool())
+                == Some(true)
+            {
+                entry["dry_run"] = Value::Bool(true);
+                entry["rolled_back"] = Value::Bool(true);
+            }
+            audit.append(&entry);
+        }
+        if top_level {
+            self.capture_call(&resolved_tool, &merged_args, None);
         }
 
         Ok(payload)
     }
 }
 
+// Metadata middleware attached to the call, redacted like the input; null when there is none.
+fn middleware_audit(metadata: &Map<String, Value>) -> Value {
+    if metadata.is_empty() {
+        return Value::Null;
+    }
+    redact_object(&Value::Object(metadata.clone()), 2048, None)
+}
+
+fn include_usage(args: &Value) -> bool {
+    args.get("include_usage")
+        .and_then(|v| v.as_bool())
+        .unwrap_or_else(feature_flags::is_resource_accounting_enabled)
+}
+
+fn is_project_store_scope(args: &Value) -> bool {
+    let scope = args
+        .get("store_as")
+        .and_then(|v| v.get("scope"))
+        .or_else(|| args.get("store_scope"))
+        .and_then(|v| v.as_str());
+    scope
+        .map(|scope| scope.eq_ignore_ascii_case("project"))
+        .unwrap_or(false)
+}
+
+fn serialized_len(value: &Value) -> usize {
+    serde_json::to_vec(value).map(|raw| raw.len()).unwrap_or(0)
+}
+
+fn value_at_path_mut<'a>(value: &'a mut Value, path: &str) -> Option<&'a mut Value> {
+    path.split('.')
+        .try_fold(value, |current, segment| current.get_mut(segment))
+}
+
 fn value_type_name(value: &Value) -> &'static str {
     match value {
         Value::Null => "null",
@@ -612,7 +1438,7 @@ fn safe_filename_segment(value: &str) -> String {
             }
         })
         .collect();
-    let cleaned = cleaned.trim_matches('_');
+    let cleaned = cleaned.trim_start_matches('.').trim_matches('_');
     if cleaned.is_empty() {
         "value".to_string()
     } else {
@@ -649,13 +1475,6 @@ fn buffer_bytes(value: &Value) -> Vec<u8> {
     Vec::new()
 }
 
-fn env_u64(key: &str, default: u64) -> u64 {
-    std::env::var(key)
-        .ok()
-        .and_then(|v| v.parse::<u64>().ok())
-        .unwrap_or(default)
-}
-
 fn collect_secret_values(value: Option<&Value>) -> Option<Vec<String>> {
     let Some(Value::Object(map)) = value else {
         return None;
@@ -0,0 +1,337 @@
+use crate::errors::ToolError;
+use crate::services::audit::AuditService;
+use crate::services::evidence::EvidenceService;
+use crate::utils::archive::tar_gz;
+use crate::utils::artifacts::{build_run_file_ref, resolve_artifact_path, write_binary_artifact};
+use crate::utils::redact::{redact_object, redact_text};
+use crate::utils::trace_context::build_span_tree;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::Path;
+
+// Bundle layout (stable; tooling may rely on it). Everything sits under `trace-<trace_id>/`:
+//
+//   manifest.json    format "infra.trace_bundle", version 1: trace_id, created_at, limits,
+//                    summary counts, `calls` (span tree in audit order; each node has span_id,
+//                    parent_span_id, tool, action, status, timestamp, duration_ms, error_code,
+//                    files, children) and `files` (every candidate file: path, source, bytes,
+//                    original_bytes, sha256, redacted, truncated, skipped)
+//   audit.jsonl      the trace's audit entries, oldest first, one JSON object per line
+//   artifacts/<rel>  files of artifact://runs/<trace_id>/..., kept at their artifact rel
+//   evidence/<id>    evidence records naming the trace (trace_id or trace_ids)
+//
+// Manifest paths are relative to the archive root; a skipped file has `path: null`. Later
+// versions may add fields but keep these.
+pub const BUNDLE_FORMAT: &str = "infra.trace_bundle";
+pub const BUNDLE_VERSION: u64 = 1;
+pub const DEFAULT_MAX_FILE_BYTES: usize = 1024 * 1024;
+pub const DEFAULT_MAX_TOTAL_BYTES: usize = 50 * 1024 * 1024;
+const BUNDLE_PREFIX: &str = "trace-bundle-";
+
+#[derive(Clone, Copy, Debug)]
+pub struct BundleLimits {
+    pub max_file_bytes: usize,
+    pub max_total_bytes: usize,
+}
+
+impl Default for BundleLimits {
+    fn default() -> Self {
+        Self {
+            max_file_bytes: DEFAULT_MAX_FILE_BYTES,
+            max_total_bytes: DEFAULT_MAX_TOTAL_BYTES,
+        }
+    }
+}
+
+struct Bundle {
+    root: String,
+    limits: BundleLimits,
+    total: usize,
+    entries: Vec<(String, Vec<u8>)>,
+    files: Vec<Value>,
+}
+
+fn truncation_marker(kept: usize, original: usize) -> String {
+    format!(
+        "\n[infra export_trace: truncated, kept {} of {} bytes]\n",
+        kept, original
+    )
+}
+
+// Redaction is applied again here: the bundle leaves the machine, whatever wrote the file.
+fn redact_content(name: &str, raw: Vec<u8>) -> (Vec<u8>, bool) {
+    let Ok(text) = String::from_utf8(raw) else {
+        return (Vec::new(), false);
+    };
+    if name.ends_with(".json") {
+        if let Ok(parsed) = serde_json::from_str::<Value>(&text) {
+            let redacted = redact_object(&parsed, usize::MAX, None);
+            let pretty = serde_json::to_string_pretty(&redacted).unwrap_or(text);
+            return (format!("{}\n", pretty).into_bytes(), true);
+        }
+    }
+    (redact_text(&text, usize::MAX, None).into_bytes(), true)
+}
+
+impl Bundle {
+    // Text gets a marker line where it was cut; binary content is cut silently (the manifest
+    // still says so).
+    fn add(
+        &mut self,
+        rel: &str,
+        source: Option<String>,
+        content: Vec<u8>,
+        redacted: bool,
+    ) -> Value {
+        let original = content.len();
+        let mut content = content;
+        let truncated = original > self.limits.max_file_bytes;
+        if truncated {
+            let mut keep = self.limits.max_file_bytes;
+            if redacted {
+                // Cut on a char boundary, never inside a multi-byte sequence.
+                while keep > 0 && content[keep] & 0xC0 == 0x80 {
+                    keep -= 1;
+                }
+            }
+            content.truncate(keep);
+            if redacted {
+                content.extend_from_slice(truncation_marker(keep, original).as_bytes());
+            }
+        }
+        let mut file = serde_json::json!({
+            "path": Value::Null,
+            "source": source,
+            "bytes": content.len(),
+            "original_bytes": original,
+            "redacted": redacted,
+            "truncated": truncated,
+            "skipped": false,
+        });
+        if self.total + content.len() > self.limits.max_total_bytes {
+            file["bytes"] = Value::from(0);
+            file["skipped"] = Value::Bool(true);
+            self.files.push(file.clone());
+            return file;
+        }
+        let path = format!("{}/{}", self.root, rel);
+        file["path"] = Value::String(path.clone());
+        file["sha256"] = Value::String(hex::encode(Sha256::digest(&content)));
+        self.total += content.len();
+        self.entries.push((path, content));
+        self.files.push(file.clone());
+        file
+    }
+}
+
+fn evidence_for_trace(evidence: &EvidenceService, trace_id: &str) -> Vec<(String, Value)> {
+    let mut out = Vec::new();
+    for id in evidence.list_evidence().unwrap_or_default() {
+        let Ok(record) = evidence.get_evidence(&id) else {
+            continue;
+        };
+        let payload = &record["payload"];
+        let names_trace = payload["trace_id"] == trace_id
+            || payload["trace_ids"]
+                .as_array()
+                .is_some_and(|ids| ids.iter().any(|id| id == trace_id));
+        if names_trace {
+            out.push((id, payload.clone()));
+        }
+    }
+    out.sort_by(|a, b| a.0.cmp(&b.0));
+    out
+}
+
+fn annotate_calls(
+    node: &mut Value,
+    errors: &HashMap<String, Value>,
+    files: &HashMap<String, Vec<String>>,
+) {
+    let span_id = node["span_id"].as_str().unwrap_or("").to_string();
+    node["error_code"] = errors.get(&span_id).cloned().unwrap_or(Value::Null);
+    node["files"] = serde_json::json!(files.get(&span_id).cloned().unwrap_or_default());
+    if let Some(children) = node["children"].as_array_mut() {
+        for child in children {
+            annotate_calls(child, errors, files);
+        }
+    }
+}
+
+// Writes the bundle as artifact://runs/<trace_id>/trace-bundle-<timestamp>.tar.gz.
+pub fn export_trace(
+    audit: &AuditService,
+    evidence: Option<&EvidenceService>,
+    context_root: &Path,
+    trace_id: &str,
+    limits: BundleLimits,
+) -> Result<Value, ToolError> {
+    let listed = audit.read_entries(
+        usize::MAX,
+        0,
+        false,
+        &serde_json::json!({"trace_id": trace_id}),
+    )?;
+    let audit_entries = listed["entries"].as_array().cloned().unwrap_or_default();
+
+    let now = chrono::Utc::now();
+    let bundle_ref = build_run_file_ref(
+        Some(trace_id),
+        &format!(
+            "{}{}.tar.gz",
+            BUNDLE_PREFIX,
+            now.format("%Y%m%dT%H%M%S%3fZ")
+        ),
+    )?;
+    let run_rel = bundle_ref
+        .rel
+        .rsplit_once('/')
+        .map(|(dir, _)| dir.to_string())
+        .unwrap_or_default();
+    let run_dir = resolve_artifact_path(context_root, &run_rel)?;
+    let evidence_records = evidence
+        .map(|service| evidence_for_trace(service, trace_id))
+        .unwrap_or_default();
+    if audit_entries.is_empty() && !run_dir.is_dir() && evidence_records.is_empty() {
+        return Err(
+            ToolError::not_found(format!("Nothing recorded for trace '{}'", trace_id))
+                .with_hint("Check the trace_id with audit action=audit_list."),
+        );
+    }
+
+    let mut bundle = Bundle {
+        root: format!("trace-{}", trace_id),
+        limits,
+        total: 0,
+        entries: Vec::new(),
+        files: Vec::new(),
+    };
+
+    let mut audit_lines = String::new();
+    for entry in &audit_entries {
+        audit_lines.push_str(&redact_object(entry, usize::MAX, None).to_string());
+        audit_lines.push('\n');
+    }
+    bundle.add("audit.jsonl", None, audit_lines.into_bytes(), true);
+
+    let mut span_files: HashMap<String, Vec<String>> = HashMap::new();
+    if run_dir.is_dir() {
+        let mut paths: Vec<_> = walkdir::WalkDir::new(&run_dir)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file())
+            .map(|entry| entry.into_path())
+            .collect();
+        paths.sort();
+        for path in paths {
+            let Ok(inner) = path.strip_prefix(&run_dir) else {
+                continue;
+            };
+            let inner = inner.to_string_lossy().replace('\\', "/");
+            if !inner.contains('/') && inner.starts_with(BUNDLE_PREFIX) {
+                continue;
+            }
+            let rel = format!("{}/{}", run_rel, inner);
+            let raw = std::fs::read(&path)?;
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+            let (content, redacted) = match redact_content(name, raw.clone()) {
+                (_, false) => (raw, false),
+                redacted => redacted,
+            };
+            let file = bundle.add(
+                &format!("artifacts/{}", rel),
+                Some(format!("artifact://{}", rel)),
+                content,
+                redacted,
+            );
+            let span = inner
+                .strip_prefix("tool_calls/")
+                .and_then(|rest| rest.split('/').next())
+                .map(|span| span.trim_end_matches(".context").to_string());
+            if let (Some(span), Some(path)) = (span, file["path"].as_str()) {
+                span_files.entry(span).or_default().push(path.to_string());
+            }
+        }
+    }
+
+    for (id, payload) in &evidence_records {
+        let content = serde_json::to_string_pretty(&redact_object(payload, usize::MAX, None))
+            .map_err(|err| ToolError::internal(err.to_string()))?;
+        bundle.add(
+            &format!("evidence/{}", id),
+            Some(format!("evidence:{}", id)),
+            format!("{}\n", content).into_bytes(),
+            true,
+        );
+    }
+
+    let errors: HashMap<String, Value> = audit_entries
+        .iter()
+        .filter_map(|entry| {
+            let span = entry["span_id"].as_str()?;
+            let code = entry["error"]["code"].clone();
+            (!code.is_null()).then(|| (span.to_string(), code))
+        })
+        .collect();
+    let mut calls = build_span_tree(&audit_entries);
+    if let Some(nodes) = calls.as_array_mut() {
+        for node in nodes {
+            annotate_calls(node, &errors, &span_files);
+        }
+    }
+
+    let count = |field: &str| {
+        bundle
+            .files
+            .iter()
+            .filter(|file| file[field] == true)
+            .count()
+    };
+    let summary = serde_json::json!({
+        "calls": audit_entries.len(),
+        "errors": audit_entries.iter().filter(|entry| entry["status"] == "error").count(),
+        "files": bundle.entries.len(),
+        "truncated_files": count("truncated"),
+        "skipped_files": count("skipped"),
+        "evidence": evidence_records.len(),
+        "bytes": bundle.total,
+    });
+    let manifest = serde_json::json!({
+        "format": BUNDLE_FORMAT,
+        "version": BUNDLE_VERSION,
+        "trace_id": trace_id,
+        "created_at": now.to_rfc3339(),
+        "limits": {
+            "max_file_bytes": limits.max_file_bytes,
+            "max_total_bytes": limits.max_total_bytes,
+        },
+        "summary": summary,
+        "calls": calls,
+        "files": bundle.files,
+    });
+    let manifest_text = serde_json::to_string_pretty(&manifest)
+        .map_err(|err| ToolError::internal(err.to_string()))?;
+    let mut entries = vec![(
+        format!("{}/manifest.json", bundle.root),
+        format!("{}\n", manifest_text).into_bytes(),
+    )];
+    entries.extend(bundle.entries);
+
+    let archive = tar_gz(&entries, now.timestamp().max(0) as u64)?;
+    let written = write_binary_artifact(context_root, &bundle_ref, &archive)?;
+    Ok(serde_json::json!({
+        "success": true,
+        "trace_id": trace_id,
+        "bundle": {
+            "uri": written.uri,
+            "rel": written.rel,
+            "bytes": written.bytes,
+            "sha256": hex::encode(Sha256::digest(&archive)),
+            "format": BUNDLE_FORMAT,
+            "version": BUNDLE_VERSION,
+        },
+        "summary": summary,
+    }))
+}
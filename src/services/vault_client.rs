@@ -2,9 +2,11 @@ use crate::errors::ToolError;
 use crate::services::logger::Logger;
 use crate::services::profile::ProfileService;
 use crate::services::validation::Validation;
+use futures::stream::{self, StreamExt};
 use reqwest::header::{HeaderMap, HeaderValue, ACCEPT};
 use reqwest::{Client, Method};
 use serde_json::Value;
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 use url::Url;
@@ -331,26 +333,71 @@ impl VaultClient {
         options: Option<&Value>,
     ) -> Result<String, ToolError> {
         let profile = self.load_profile(profile_name).await?;
-        let ref_value = reference.trim();
-        let (path_part, key) = ref_value.split_once('#').ok_or_else(|| {
-            ToolError::invalid_params("Vault kv2 ref must include #key (e.g. secret/app#TOKEN)")
-        })?;
-        let path_part = path_part.trim();
-        let key = key.trim();
-        if path_part.is_empty() || key.is_empty() {
-            return Err(ToolError::invalid_params(
-                "Vault kv2 ref must include mount/path and key",
-            ));
-        }
-        let mut pieces = path_part.splitn(2, '/');
-        let mount = pieces.next().unwrap_or("").trim();
-        let path = pieces.next().unwrap_or("").trim();
-        if mount.is_empty() || path.is_empty() {
-            return Err(ToolError::invalid_params(
-                "Vault kv2 ref must be <mount>/<path>#<key>",
-            ));
+        let (mount, path, key) = parse_kv2_ref(reference)?;
+        let token = self.ensure_token(&profile, options).await?;
+        let data = self
+            .kv2_read(&profile, &token, &mount, &path, options)
+            .await?;
+        kv2_key(&data, &key)
+    }
+
+    // Resolves several kv2 refs against one profile: the token is fetched once and each
+    // secret path is read once, however many of its keys are referenced. The outer error
+    // covers the profile and token; per-ref failures come back in input order.
+    pub async fn kv2_get_many(
+        &self,
+        profile_name: &str,
+        references: &[String],
+        options: Option<&Value>,
+        concurrency: usize,
+    ) -> Result<Vec<Result<String, ToolError>>, ToolError> {
+        let profile = self.load_profile(profile_name).await?;
+        let parsed: Vec<Result<(String, String, String), ToolError>> = references
+            .iter()
+            .map(|reference| parse_kv2_ref(reference))
+            .collect();
+        let mut paths: Vec<(String, String)> = parsed
+            .iter()
+            .filter_map(|item| item.as_ref().ok())
+            .map(|(mount, path, _)| (mount.clone(), path.clone()))
+            .collect();
+        paths.sort();
+        paths.dedup();
+        let mut reads: HashMap<(String, String), Result<Value, ToolError>> = HashMap::new();
+        if !paths.is_empty() {
+            let token = self.ensure_token(&profile, options).await?;
+            let profile = &profile;
+            let token = token.as_str();
+            reads = stream::iter(paths)
+                .map(|(mount, path)| async move {
+                    let read = self.kv2_read(profile, token, &mount, &path, options).await;
+                    ((mount, path), read)
+                })
+                .buffer_unordered(concurrency.max(1))
+                .collect()
+                .await;
         }
+        Ok(parsed
+            .into_iter()
+            .map(|item| {
+                let (mount, path, key) = item?;
+                match reads.get(&(mount, path)) {
+                    Some(Ok(data)) => kv2_key(data, &key),
+                    Some(Err(err)) => Err(err.clone()),
+                    None => Err(ToolError::internal("Vault kv2 path was not read")),
+                }
+            })
+            .collect())
+    }
 
+    async fn kv2_read(
+        &self,
+        profile: &VaultProfile,
+        token: &str,
+        mount: &str,
+        path: &str,
+        options: Option<&Value>,
+    ) -> Result<Value, ToolError> {
         let timeout_ms = options
             .and_then(|v| v.get("timeout_ms"))
             .and_then(|v| v.as_u64());
@@ -358,33 +405,56 @@ impl VaultClient {
             .and_then(|v| v.get("retries"))
             .and_then(|v| v.as_u64())
             .map(|v| v as u32);
-        let token = self.ensure_token(&profile, options).await?;
         let url = format!("{}/v1/{}/data/{}", profile.addr, mount, path);
         let response = self
             .request_json(
                 &url,
                 Method::GET,
-                self.build_headers(Some(&token), profile.namespace.as_deref()),
+                self.build_headers(Some(token), profile.namespace.as_deref()),
                 None,
                 timeout_ms,
                 retries,
             )
             .await?;
-        let value = response
+        Ok(response
             .get("data")
             .and_then(|v| v.get("data"))
-            .and_then(|v| v.get(key))
-            .ok_or_else(|| ToolError::not_found(format!("Vault kv2 key '{}' not found", key)))?;
-        if value.is_null() {
-            return Err(ToolError::not_found(format!(
-                "Vault kv2 key '{}' not found",
-                key
-            )));
-        }
-        Ok(value.as_str().unwrap_or(&value.to_string()).to_string())
+            .cloned()
+            .unwrap_or(Value::Null))
     }
 }
 
+fn parse_kv2_ref(reference: &str) -> Result<(String, String, String), ToolError> {
+    let ref_value = reference.trim();
+    let (path_part, key) = ref_value.split_once('#').ok_or_else(|| {
+        ToolError::invalid_params("Vault kv2 ref must include #key (e.g. secret/app#TOKEN)")
+    })?;
+    let path_part = path_part.trim();
+    let key = key.trim();
+    if path_part.is_empty() || key.is_empty() {
+        return Err(ToolError::invalid_params(
+            "Vault kv2 ref must include mount/path and key",
+        ));
+    }
+    let mut pieces = path_part.splitn(2, '/');
+    let mount = pieces.next().unwrap_or("").trim();
+    let path = pieces.next().unwrap_or("").trim();
+    if mount.is_empty() || path.is_empty() {
+        return Err(ToolError::invalid_params(
+            "Vault kv2 ref must be <mount>/<path>#<key>",
+        ));
+    }
+    Ok((mount.to_string(), path.to_string(), key.to_string()))
+}
+
+fn kv2_key(data: &Value, key: &str) -> Result<String, ToolError> {
+    let value = data
+        .get(key)
+        .filter(|value| !value.is_null())
+        .ok_or_else(|| ToolError::not_found(format!("Vault kv2 key '{}' not found", key)))?;
+    Ok(value.as_str().unwrap_or(&value.to_string()).to_string())
+}
+
 fn normalize_base_url(raw: Option<&str>) -> Result<String, ToolError> {
     let raw = raw.unwrap_or("").trim();
     if raw.is_empty() {
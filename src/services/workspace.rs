@@ -1,29 +1,48 @@
-use crate::errors::ToolError;
+use crate::errors::{ToolError, ToolErrorKind};
 use crate::services::alias::AliasService;
+use crate::services::audit::AuditService;
 use crate::services::capability::CapabilityService;
+use crate::services::config_sync::{ApplyOptions, ConfigSync};
 use crate::services::context::ContextService;
 use crate::services::context_session::ContextSessionService;
+use crate::services::doctor;
+use crate::services::job::JobService;
 use crate::services::logger::Logger;
 use crate::services::preset::PresetService;
 use crate::services::profile::ProfileService;
 use crate::services::project::ProjectService;
 use crate::services::project_resolver::ProjectResolver;
 use crate::services::runbook::RunbookService;
+use crate::services::security::{generate_key, resolve_key_material, KeySource};
 use crate::services::state::StateService;
+use crate::utils::artifacts::resolve_context_root;
 use crate::utils::data_path::get_path_value;
-use crate::utils::fs_atomic::path_exists;
+use crate::utils::feature_flags::is_startup_probe_enabled;
+use crate::utils::fs_atomic::{atomic_write_text_file, path_exists};
 use crate::utils::listing::ListFilters;
+use crate::utils::next_actions::{
+    next_actions_from_history, DEFAULT_NEXT_ACTIONS, DEFAULT_RECENT_ENTRIES, MAX_RECENT_ENTRIES,
+};
 use crate::utils::paths::{
     resolve_aliases_path, resolve_audit_path, resolve_cache_dir, resolve_capabilities_path,
-    resolve_context_path, resolve_evidence_dir, resolve_jobs_path, resolve_presets_path,
-    resolve_profile_key_path, resolve_profiles_path, resolve_projects_path, resolve_runbooks_path,
-    resolve_state_path, resolve_store_db_path, resolve_store_info,
+    resolve_context_path, resolve_evidence_dir, resolve_jobs_path,
+    resolve_pipeline_checkpoints_dir, resolve_presets_path, resolve_profile_key_path,
+    resolve_profiles_path, resolve_projects_path, resolve_runbooks_path, resolve_state_path,
+    resolve_store_db_path, resolve_store_info,
 };
+use crate::utils::user_paths::expand_home_path;
 use crate::utils::when_matcher::{match_tags, matches_when};
 use serde_json::Value;
 use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
+
+const OPERATIONS_SOURCE_TIMEOUT: Duration = Duration::from_millis(1500);
+const RECENT_FAILURES_LIMIT: usize = 5;
+const RUNNING_JOBS_LIMIT: usize = 20;
+const STALE_CHECKPOINT_SECS: u64 = 3600;
+const STORAGE_SCAN_LIMIT: usize = 20_000;
 
 #[derive(Clone)]
 pub struct WorkspaceService {
@@ -38,6 +57,8 @@ pub struct WorkspaceService {
     alias_service: Arc<AliasService>,
     preset_service: Arc<PresetService>,
     state_service: Arc<StateService>,
+    audit_service: Option<Arc<AuditService>>,
+    job_service: Option<Arc<JobService>>,
 }
 
 impl WorkspaceService {
@@ -66,9 +87,21 @@ impl WorkspaceService {
             alias_service,
             preset_service,
             state_service,
+            audit_service: None,
+            job_service: None,
         }
     }
 
+    pub fn with_history(
+        mut self,
+        audit_service: Arc<AuditService>,
+        job_service: Option<Arc<JobService>>,
+    ) -> Self {
+        self.audit_service = Some(audit_service);
+        self.job_service = job_service;
+        self
+    }
+
     async fn resolve_session(&self, args: &Value) -> Option<Value> {
         let session_service = self.context_session.as_ref()?;
         match session_service.resolve(args).await {
@@ -582,6 +615,7 @@ impl WorkspaceService {
         };
         let store = self.store_status(args).await?;
         let inventory = self.get_inventory().await?;
+        let operations = self.operations_overview(args).await;
 
         let limit = args
             .get("limit")
@@ -672,6 +706,7 @@ impl WorkspaceService {
             "bindings": session.as_ref().and_then(|s| s.get("bindings")).cloned().unwrap_or(Value::Null),
             "suggestions": suggestions.as_json(),
             "actions": actions,
+            "operations": operations,
             "view": view,
         });
 
@@ -708,6 +743,7 @@ impl WorkspaceService {
                 "bindings": base_workspace.get("bindings").cloned().unwrap_or(Value::Null),
                 "suggestions": base_workspace.get("suggestions").cloned().unwrap_or(Value::Null),
                 "actions": base_workspace.get("actions").cloned().unwrap_or(Value::Null),
+                "operations": base_workspace.get("operations").cloned().unwrap_or(Value::Null),
                 "view": view,
                 "store": store,
                 "inventory": inventory,
@@ -715,6 +751,67 @@ impl WorkspaceService {
         }))
     }
 
+    // What is currently going wrong or in flight: recent failed calls, running jobs, checkpoints
+    // that stopped progressing and local storage usage, each with a ready-made next action.
+    async fn operations_overview(&self, args: &Value) -> Value {
+        let window = args
+            .get("audit_limit")
+            .and_then(|v| v.as_u64())
+            .map(|v| (v as usize).clamp(1, MAX_RECENT_ENTRIES))
+            .unwrap_or(DEFAULT_RECENT_ENTRIES);
+        let audit = self.audit_service.clone();
+        let jobs = self.job_service.clone();
+        let checkpoints_dir = resolve_pipeline_checkpoints_dir();
+        let (failures, running, stale, storage) = tokio::join!(
+            bounded_source(move || recent_failures(audit.as_deref(), window)),
+            bounded_source(move || running_jobs(jobs.as_deref())),
+            bounded_source(move || stale_checkpoints(&checkpoints_dir)),
+            bounded_source(storage_usage),
+        );
+        serde_json::json!({
+            "recent_failures": failures,
+            "running_jobs": running,
+            "stale_checkpoints": stale,
+            "storage": storage,
+        })
+    }
+
+    fn suggest_next_actions(&self, args: &Value, limit: Option<usize>) -> Vec<Value> {
+        let window = args
+            .get("audit_limit")
+            .and_then(|v| v.as_u64())
+            .map(|v| (v as usize).clamp(1, MAX_RECENT_ENTRIES))
+            .unwrap_or(DEFAULT_RECENT_ENTRIES);
+        let entries = match self.audit_service.as_ref() {
+            Some(audit) => match audit.read_entries(window, 0, true, &Value::Null) {
+                Ok(result) => result
+                    .get("entries")
+                    .and_then(|v| v.as_array())
+                    .cloned()
+                    .unwrap_or_default(),
+                Err(err) => {
+                    self.logger.warn(
+                        "Audit read failed",
+                        Some(&serde_json::json!({"error": err.message})),
+                    );
+                    Vec::new()
+                }
+            },
+            None => Vec::new(),
+        };
+        let jobs = self
+            .job_service
+            .as_ref()
+            .map(|service| service.list(window, Some("failed")))
+            .unwrap_or_default();
+        next_actions_from_history(
+            &entries,
+            &jobs,
+            args.get("audit_trace_id").and_then(|v| v.as_str()),
+            limit.unwrap_or(DEFAULT_NEXT_ACTIONS),
+        )
+    }
+
     pub async fn suggest(&self, args: &Value) -> Result<Value, ToolError> {
         let session = self.resolve_session(args).await;
         let context_result = if let Some(session) = session.as_ref() {
@@ -751,6 +848,7 @@ impl WorkspaceService {
                 .await?,
         };
         let actions = self.build_action_hints(&suggestions, include_call, &context, None);
+        let next_actions = self.suggest_next_actions(args, limit);
 
         let view = serde_json::json!({
             "format": args.get("format").cloned().unwrap_or(Value::String("suggest".to_string())),
@@ -773,6 +871,7 @@ impl WorkspaceService {
                 "diagnostics": session.as_ref().and_then(|s| s.get("diagnostics")).cloned().unwrap_or(Value::Null),
                 "bindings": session.as_ref().and_then(|s| s.get("bindings")).cloned().unwrap_or(Value::Null),
                 "actions": actions,
+                "next_actions": next_actions,
                 "view": view,
             }));
         }
@@ -788,6 +887,7 @@ impl WorkspaceService {
             "bindings": session.as_ref().and_then(|s| s.get("bindings")).cloned().unwrap_or(Value::Null),
             "suggestions": suggestions.as_json(),
             "actions": actions,
+            "next_actions": next_actions,
             "view": view,
         }))
     }
@@ -893,6 +993,149 @@ impl WorkspaceService {
         }))
     }
 
+    pub fn doctor(&self, args: &Value) -> Result<Value, ToolError> {
+        let probe = args
+            .get("probe")
+            .and_then(|v| v.as_bool())
+            .unwrap_or_else(is_startup_probe_enabled);
+        let mut checks = doctor::local_checks();
+        if probe {
+            checks.extend(doctor::profile_probes(&self.profile_service));
+        }
+        Ok(doctor::build_report(&checks))
+    }
+
+    fn config_sync(&self) -> ConfigSync<'_> {
+        ConfigSync {
+            profiles: &self.profile_service,
+            projects: &self.project_service,
+            presets: &self.preset_service,
+            aliases: &self.alias_service,
+        }
+    }
+
+    pub fn config_apply(&self, args: &Value) -> Result<Value, ToolError> {
+        let document = match (args.get("document"), config_document_path(args)?) {
+            (Some(document), None) => document.clone(),
+            (None, Some(path)) => read_config_document(&path)?,
+            _ => {
+                return Err(ToolError::invalid_params(
+                    "config_apply requires exactly one of document or document_path",
+                ))
+            }
+        };
+        let options = ApplyOptions {
+            prune: args.get("prune").and_then(|v| v.as_bool()).unwrap_or(false),
+            plan_only: args
+                .get("plan_only")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false),
+        };
+        self.config_sync().apply(&document, options)
+    }
+
+    pub fn config_export(&self, args: &Value) -> Result<Value, ToolError> {
+        let (document, placeholders) = self.config_sync().export()?;
+        let mut out = serde_json::json!({
+            "success": true,
+            "placeholders": placeholders,
+        });
+        match config_document_path(args)? {
+            Some(path) => {
+                let text = serde_json::to_string_pretty(&document)
+                    .map_err(|err| ToolError::internal(err.to_string()))?;
+                atomic_write_text_file(&path, &format!("{}\n", text), 0o600)?;
+                out["document_path"] = Value::String(path.display().to_string());
+            }
+            None => out["document"] = document,
+        }
+        Ok(out)
+    }
+
+    // Re-encrypts every profile secret and sensitive state value under a new key. Everything is
+    // first sealed again under the current key, so nothing is rotated while any value is
+    // unreadable; after the switch the old key stays loaded until the process exits.
+    pub fn secrets_rotate_key(&self, args: &Value) -> Result<Value, ToolError> {
+        let security = self.profile_service.security().clone();
+        let source = security.key_source();
+        let new_key = match args
+            .get("new_key")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+        {
+            Some(raw) => resolve_key_material(raw, "new_key")?,
+            None if source == KeySource::KeyFile => generate_key(),
+            None => {
+                return Err(ToolError::invalid_params(format!(
+                    "new_key is required when the key comes from {}",
+                    source.as_str()
+                ))
+                .with_hint(
+                    "Pass new_key as a literal, ref:env:<VAR> or ref:keychain:<service>; it must be the INFRA_MASTER_KEY of the next start.",
+                ))
+            }
+        };
+
+        let unreadable = self.profile_service.unreadable_profiles()?;
+        if !unreadable.is_empty() {
+            return Err(ToolError::new(
+                ToolErrorKind::Denied,
+                "SECRET_KEY_MISMATCH",
+                format!(
+                    "Cannot rotate: profile secrets unreadable with the loaded key: {}",
+                    unreadable.join(", ")
+                ),
+            )
+            .with_details(serde_json::json!({"profiles": unreadable})));
+        }
+        self.profile_service.reseal_all()?;
+        self.state_service.reseal_sensitive(&security)?;
+
+        let (previous_key_id, key_id) = security.rotate(&new_key)?;
+        let resealed = self
+            .profile_service
+            .reseal_all()
+            .and_then(|profiles| Ok((profiles, self.state_service.reseal_sensitive(&security)?)));
+        let (profiles, state_values) = resealed.map_err(|err| {
+            err.with_hint(format!(
+                "Key {} is active; both keys are kept in {} until every value is resealed, so nothing is lost. Fix the cause and rerun secrets_rotate_key with the same new_key to finish.",
+                key_id,
+                security.rotation_path().display()
+            ))
+        })?;
+        security.finish_rotation()?;
+        self.logger.info(
+            "Secrets key rotated",
+            Some(&serde_json::json!({
+                "previous_key_id": previous_key_id,
+                "key_id": key_id,
+                "profiles": profiles,
+                "state_values": state_values,
+            })),
+        );
+        let mut out = serde_json::json!({
+            "success": true,
+            "previous_key_id": previous_key_id,
+            "key_id": key_id,
+            "key_source": source.as_str(),
+            "profiles_resealed": profiles,
+            "state_values_resealed": state_values,
+        });
+        match source {
+            KeySource::KeyFile => {
+                out["key_path"] = Value::String(security.key_path().display().to_string());
+            }
+            KeySource::MasterKey | KeySource::EncryptionKey => {
+                out["restart_note"] = Value::String(format!(
+                    "Set {} to the new key before the next start; stored secrets no longer open with the old one.",
+                    source.as_str()
+                ));
+            }
+        }
+        Ok(out)
+    }
+
     pub async fn stats(&self, args: &Value) -> Result<Value, ToolError> {
         Ok(serde_json::json!({
             "success": true,
@@ -963,6 +1206,35 @@ impl ResolvedInputs {
     }
 }
 
+fn config_document_path(args: &Value) -> Result<Option<PathBuf>, ToolError> {
+    let Some(raw) = args.get("document_path") else {
+        return Ok(None);
+    };
+    let path = raw
+        .as_str()
+        .filter(|s| !s.trim().is_empty())
+        .ok_or_else(|| ToolError::invalid_params("document_path must be a non-empty string"))?;
+    let path = expand_home_path(path.trim());
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    if extension.eq_ignore_ascii_case("yaml") || extension.eq_ignore_ascii_case("yml") {
+        return Err(ToolError::invalid_params(format!(
+            "{} is YAML; config documents are JSON",
+            path.display()
+        ))
+        .with_hint("Convert it first, e.g. `yq -o=json . file.yaml > file.json`.".to_string()));
+    }
+    Ok(Some(path))
+}
+
+fn read_config_document(path: &Path) -> Result<Value, ToolError> {
+    let raw = std::fs::read_to_string(path).map_err(|err| {
+        ToolError::invalid_params(format!("Cannot read {}: {}", path.display(), err))
+    })?;
+    serde_json::from_str(&raw).map_err(|err| {
+        ToolError::invalid_params(format!("{} is not valid JSON: {}", path.display(), err))
+    })
+}
+
 fn name_of(value: &Value) -> String {
     value
         .get("name")
@@ -1030,3 +1302,213 @@ fn find_git_root(start: &Path) -> Option<PathBuf> {
     }
     None
 }
+
+fn unavailable(error: impl std::fmt::Display) -> Value {
+    serde_json::json!({"status": "unavailable", "error": error.to_string()})
+}
+
+// Each operations source runs on the blocking pool under its own deadline, so a slow audit file
+// or job store degrades that one section instead of the whole summary.
+async fn bounded_source<F>(collect: F) -> Value
+where
+    F: FnOnce() -> Result<Value, String> + Send + 'static,
+{
+    match tokio::time::timeout(
+        OPERATIONS_SOURCE_TIMEOUT,
+        tokio::task::spawn_blocking(collect),
+    )
+    .await
+    {
+        Ok(Ok(Ok(value))) => value,
+        Ok(Ok(Err(error))) => unavailable(error),
+        Ok(Err(err)) => unavailable(err),
+        Err(_) => unavailable(format!(
+            "timed out after {}ms",
+            OPERATIONS_SOURCE_TIMEOUT.as_millis()
+        )),
+    }
+}
+
+fn next_action(tool: &str, action: &str, args: Value, reason: &str) -> Value {
+    serde_json::json!({"tool": tool, "action": action, "args": args, "reason": reason})
+}
+
+fn age_seconds(timestamp: Option<&str>, now: chrono::DateTime<chrono::Utc>) -> Option<i64> {
+    let parsed = chrono::DateTime::parse_from_rfc3339(timestamp?).ok()?;
+    Some(
+        (now - parsed.with_timezone(&chrono::Utc))
+            .num_seconds()
+            .max(0),
+    )
+}
+
+fn recent_failures(audit: Option<&AuditService>, window: usize) -> Result<Value, String> {
+    let audit = audit.ok_or("audit history is not available")?;
+    let result = audit
+        .read_entries(window, 0, true, &serde_json::json!({"status": "error"}))
+        .map_err(|err| err.message)?;
+    let items = result
+        .get("entries")
+        .and_then(|v| v.as_array())
+        .map(|entries| {
+            entries
+                .iter()
+                .take(RECENT_FAILURES_LIMIT)
+                .map(|entry| {
+                    let trace_id = entry.get("trace_id").cloned().unwrap_or(Value::Null);
+                    serde_json::json!({
+                        "timestamp": entry.get("timestamp").cloned().unwrap_or(Value::Null),
+                        "tool": entry.get("tool").cloned().unwrap_or(Value::Null),
+                        "action": entry.get("action").cloned().unwrap_or(Value::Null),
+                        "error_code": get_path_value(entry, "error.code", false, None).unwrap_or(Value::Null),
+                        "trace_id": trace_id,
+                        "next_action": next_action(
+                            "audit",
+                            "audit_trace",
+                            serde_json::json!({"trace_id": trace_id}),
+                            "Inspect the failed call and its nested spans.",
+                        ),
+                    })
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    Ok(serde_json::json!({"status": "ok", "items": items}))
+}
+
+fn running_jobs(jobs: Option<&JobService>) -> Result<Value, String> {
+    let jobs = jobs.ok_or("job registry is not available")?;
+    let now = chrono::Utc::now();
+    let items = jobs
+        .list(RUNNING_JOBS_LIMIT, Some("running"))
+        .iter()
+        .map(|job| {
+            let job_id = job.get("job_id").cloned().unwrap_or(Value::Null);
+            let started_at = job
+                .get("started_at")
+                .or_else(|| job.get("created_at"))
+                .and_then(|v| v.as_str());
+            serde_json::json!({
+                "job_id": job_id,
+                "kind": job.get("kind").cloned().unwrap_or(Value::Null),
+                "trace_id": job.get("trace_id").cloned().unwrap_or(Value::Null),
+                "started_at": started_at,
+                "age_seconds": age_seconds(started_at, now),
+                "next_action": next_action(
+                    "job",
+                    "follow_job",
+                    serde_json::json!({"job_id": job_id}),
+                    "Follow the job until it finishes.",
+                ),
+            })
+        })
+        .collect::<Vec<_>>();
+    Ok(serde_json::json!({"status": "ok", "items": items}))
+}
+
+// A checkpoint file is rewritten after every completed file, so an old mtime means the run that
+// owns it stopped making progress.
+fn stale_checkpoints(dir: &Path) -> Result<Value, String> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(serde_json::json!({"status": "ok", "items": []}));
+        }
+        Err(err) => return Err(err.to_string()),
+    };
+    let now = std::time::SystemTime::now();
+    let mut items = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Some(name) = path
+            .file_name()
+            .and_then(|v| v.to_str())
+            .and_then(|v| v.strip_suffix(".json"))
+        else {
+            continue;
+        };
+        let Some(modified) = entry.metadata().ok().and_then(|meta| meta.modified().ok()) else {
+            continue;
+        };
+        let age = now.duration_since(modified).unwrap_or_default().as_secs();
+        if age < STALE_CHECKPOINT_SECS {
+            continue;
+        }
+        let stored = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|raw| serde_json::from_str::<Value>(&raw).ok())
+            .unwrap_or(Value::Null);
+        let flow = stored.get("flow").cloned().unwrap_or(Value::Null);
+        items.push(serde_json::json!({
+            "checkpoint": name,
+            "flow": flow,
+            "files_done": stored.get("files").and_then(|v| v.as_object()).map(|v| v.len()),
+            "updated_at": chrono::DateTime::<chrono::Utc>::from(modified).to_rfc3339(),
+            "age_seconds": age,
+            "next_action": next_action(
+                "pipeline",
+                "run",
+                serde_json::json!({"flow": flow, "checkpoint": name}),
+                "Rerun the flow with its original source and sink to resume after the completed files.",
+            ),
+        }));
+    }
+    items.sort_by_key(|item| {
+        std::cmp::Reverse(
+            item.get("age_seconds")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(0),
+        )
+    });
+    Ok(serde_json::json!({"status": "ok", "items": items}))
+}
+
+fn dir_usage(path: &Path) -> Value {
+    if !path.is_dir() {
+        return serde_json::json!({"path": path.display().to_string(), "exists": false});
+    }
+    let mut bytes = 0u64;
+    let mut files = 0usize;
+    let mut pending = vec![path.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_dir() {
+                pending.push(entry.path());
+            } else if file_type.is_file() {
+                files += 1;
+                bytes += entry.metadata().map(|meta| meta.len()).unwrap_or(0);
+                if files >= STORAGE_SCAN_LIMIT {
+                    return serde_json::json!({
+                        "path": path.display().to_string(),
+                        "exists": true,
+                        "bytes": bytes,
+                        "files": files,
+                        "truncated": true,
+                    });
+                }
+            }
+        }
+    }
+    serde_json::json!({
+        "path": path.display().to_string(),
+        "exists": true,
+        "bytes": bytes,
+        "files": files,
+        "truncated": false,
+    })
+}
+
+fn storage_usage() -> Result<Value, String> {
+    Ok(serde_json::json!({
+        "status": "ok",
+        "cache": dir_usage(&resolve_cache_dir()),
+        "artifacts": resolve_context_root().map(|root| dir_usage(&root.join("artifacts"))),
+        "pipeline_checkpoints": dir_usage(&resolve_pipeline_checkpoints_dir()),
+    }))
+}
@@ -1,9 +1,14 @@
 use crate::errors::{ContractError, ErrorCode};
+use crate::tooling::effects::hint_effects_for_tool_action;
 use crate::tooling::names::canonical_tool_name;
+use crate::utils::suggest::suggest;
+use jsonschema::error::{TypeKind, ValidationErrorKind};
+use jsonschema::paths::JSONPointer;
 use jsonschema::JSONSchema;
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -37,6 +42,110 @@ static TOOL_VALIDATORS: Lazy<HashMap<String, JSONSchema>> = Lazy::new(|| {
     map
 });
 
+// Bump when the shape of tool_contracts.json or of the catalog payloads changes.
+pub const CATALOG_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Deprecation {
+    pub tool: &'static str,
+    // None deprecates every action of `tool` (use for retired tool aliases).
+    pub action: Option<&'static str>,
+    pub since: &'static str,
+    pub replacement: &'static str,
+    pub removal_planned: Option<&'static str>,
+}
+
+pub const DEPRECATIONS: &[Deprecation] = &[Deprecation {
+    tool: "ssh",
+    action: Some("connect"),
+    since: "7.1.0",
+    replacement: "ssh action=profile_test",
+    removal_planned: Some("8.0.0"),
+}];
+
+impl Deprecation {
+    pub fn to_value(&self) -> Value {
+        serde_json::json!({
+            "since": self.since,
+            "replacement": self.replacement,
+            "removal_planned": self.removal_planned,
+        })
+    }
+
+    pub fn warning(&self) -> Value {
+        let subject = match self.action {
+            Some(action) => format!("{} action={}", self.tool, action),
+            None => self.tool.to_string(),
+        };
+        serde_json::json!({
+            "code": "DEPRECATED_ACTION",
+            "message": format!(
+                "{} is deprecated since {}; use {} instead",
+                subject, self.since, self.replacement
+            ),
+            "replacement": self.replacement,
+            "since": self.since,
+            "removal_planned": self.removal_planned,
+        })
+    }
+}
+
+// `tool` is matched as given, so a deprecated alias name is caught before canonicalization.
+pub fn deprecation_for(tool: &str, action: Option<&str>) -> Option<&'static Deprecation> {
+    DEPRECATIONS.iter().find(|entry| {
+        entry.tool == tool
+            && match entry.action {
+                Some(deprecated) => action == Some(deprecated),
+                None => true,
+            }
+    })
+}
+
+// List fields that grow with the data an action returns; the executor's result size guard
+// cuts these to a preview first when a result exceeds INFRA_MAX_RESULT_BYTES.
+#[derive(Debug, Clone, Copy)]
+pub struct ResultHint {
+    pub tool: &'static str,
+    // None covers every action of `tool`.
+    pub action: Option<&'static str>,
+    // Dotted paths into the result object.
+    pub fields: &'static [&'static str],
+}
+
+pub const RESULT_HINTS: &[ResultHint] = &[
+    ResultHint {
+        tool: "sql",
+        action: None,
+        fields: &["rows"],
+    },
+    ResultHint {
+        tool: "ssh",
+        action: Some("sftp_list"),
+        fields: &["entries"],
+    },
+    ResultHint {
+        tool: "ssh",
+        action: Some("inventory"),
+        fields: &["hosts"],
+    },
+    ResultHint {
+        tool: "api",
+        action: Some("paginate"),
+        fields: &["pages", "items"],
+    },
+];
+
+pub fn result_hint_fields(tool: &str, action: Option<&str>) -> Vec<&'static str> {
+    let canonical = canonical_tool_name(tool);
+    RESULT_HINTS
+        .iter()
+        .filter(|hint| {
+            hint.tool == canonical && hint.action.is_none_or(|expected| action == Some(expected))
+        })
+        .flat_map(|hint| hint.fields.iter().copied())
+        .collect()
+}
+
 pub fn tool_contract_catalog() -> &'static [ToolDef] {
     TOOL_CONTRACT_CATALOG.as_slice()
 }
@@ -46,33 +155,321 @@ pub fn tool_by_name(name: &str) -> Option<&'static ToolDef> {
     TOOL_MAP.get(canonical)
 }
 
-pub fn validate_tool_args(tool_name: &str, args: &Value) -> Result<(), ContractError> {
+#[derive(Debug, Clone, Serialize)]
+pub struct ArgViolation {
+    pub pointer: String,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected: Option<Value>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub suggestions: Vec<String>,
+}
+
+impl ArgViolation {
+    fn render(&self) -> String {
+        let mut line = format!("- {}: {}", self.pointer, self.message);
+        if !self.suggestions.is_empty() {
+            line.push_str(&format!(" (did you mean: {})", self.suggestions.join(", ")));
+        }
+        line
+    }
+
+    pub fn to_value(&self) -> Value {
+        serde_json::to_value(self).unwrap_or(Value::Null)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ArgsReport {
+    pub tool: String,
+    pub action: Option<String>,
+    pub violations: Vec<ArgViolation>,
+    pub unknown: Vec<ArgViolation>,
+}
+
+impl ArgsReport {
+    pub fn is_valid(&self, strict: bool) -> bool {
+        self.violations.is_empty() && (!strict || self.unknown.is_empty())
+    }
+
+    pub fn blocking(&self, strict: bool) -> Vec<&ArgViolation> {
+        let mut out: Vec<&ArgViolation> = self.violations.iter().collect();
+        if strict {
+            out.extend(self.unknown.iter());
+        }
+        out
+    }
+
+    pub fn to_contract_error(&self, strict: bool) -> ContractError {
+        let mut lines = vec![match self.action.as_deref() {
+            Some(action) => format!("Invalid arguments for {}:{}", self.tool, action),
+            None => format!("Invalid arguments for {}", self.tool),
+        }];
+        for violation in self
+            .blocking(strict)
+            .into_iter()
+            .take(MAX_REPORTED_VIOLATIONS)
+        {
+            lines.push(violation.render());
+        }
+        ContractError::new(ErrorCode::InvalidParams, lines.join("\n"))
+    }
+}
+
+const MAX_REPORTED_VIOLATIONS: usize = 20;
+
+fn instance_pointer(pointer: &JSONPointer) -> String {
+    let rendered = pointer.to_string();
+    if rendered.is_empty() {
+        "(root)".to_string()
+    } else {
+        rendered
+    }
+}
+
+fn join_pointer(base: &str, key: &str) -> String {
+    let escaped = key.replace('~', "~0").replace('/', "~1");
+    if base == "(root)" {
+        format!("/{}", escaped)
+    } else {
+        format!("{}/{}", base, escaped)
+    }
+}
+
+fn sibling_properties(schema: &Value, schema_path: &JSONPointer) -> Vec<String> {
+    let mut chunks = schema_path.clone().into_vec();
+    chunks.pop();
+    let parent = if chunks.is_empty() {
+        String::new()
+    } else {
+        format!("/{}", chunks.join("/"))
+    };
+    schema
+        .pointer(&parent)
+        .and_then(|node| node.get("properties"))
+        .and_then(|props| props.as_object())
+        .map(|props| props.keys().cloned().collect())
+        .unwrap_or_default()
+}
+
+fn expected_type(kind: TypeKind) -> Value {
+    match kind {
+        TypeKind::Single(primitive) => Value::String(primitive.to_string()),
+        TypeKind::Multiple(types) => Value::Array(
+            types
+                .into_iter()
+                .map(|primitive| Value::String(primitive.to_string()))
+                .collect(),
+        ),
+    }
+}
+
+pub fn check_tool_args(tool_name: &str, args: &Value) -> ArgsReport {
     let canonical = canonical_tool_name(tool_name);
+    let mut report = ArgsReport {
+        tool: canonical.to_string(),
+        action: args
+            .get("action")
+            .and_then(|value| value.as_str())
+            .map(|value| value.to_string()),
+        ..Default::default()
+    };
     let Some(tool) = tool_by_name(canonical) else {
-        return Ok(());
+        return report;
     };
     let Some(schema) = TOOL_VALIDATORS.get(&tool.name) else {
-        return Ok(());
+        return report;
     };
-    if let Err(errors) = schema.validate(args) {
-        let action = args.get("action").and_then(|value| value.as_str());
-        let mut lines = vec![if let Some(action) = action {
-            format!("Invalid arguments for {}:{}", tool.name, action)
-        } else {
-            format!("Invalid arguments for {}", tool.name)
-        }];
-        for err in errors.take(10) {
-            let instance_path = if err.instance_path.to_string().is_empty() {
-                "(root)".to_string()
-            } else {
-                err.instance_path.to_string()
-            };
-            lines.push(format!("- {}: {}", instance_path, err));
+    report.tool = tool.name.clone();
+    let Err(errors) = schema.validate(args) else {
+        return report;
+    };
+    for err in errors {
+        let pointer = instance_pointer(&err.instance_path);
+        let message = err.to_string();
+        match err.kind {
+            ValidationErrorKind::AdditionalProperties { unexpected } => {
+                let known = sibling_properties(&tool.input_schema, &err.schema_path);
+                for key in unexpected {
+                    let violation = ArgViolation {
+                        pointer: join_pointer(&pointer, &key),
+                        message: format!("unknown property '{}'", key),
+                        expected: None,
+                        suggestions: suggest(&key, &known, 3),
+                    };
+                    if pointer == "(root)" {
+                        report.unknown.push(violation);
+                    } else {
+                        report.violations.push(violation);
+                    }
+                }
+            }
+            ValidationErrorKind::Type { kind } => report.violations.push(ArgViolation {
+                pointer,
+                message,
+                expected: Some(serde_json::json!({ "type": expected_type(kind) })),
+                suggestions: Vec::new(),
+            }),
+            ValidationErrorKind::Enum { options } => {
+                let candidates: Vec<String> = options
+                    .as_array()
+                    .map(|items| {
+                        items
+                            .iter()
+                            .filter_map(|item| item.as_str().map(|s| s.to_string()))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                let mut suggestions = err
+                    .instance
+                    .as_str()
+                    .map(|value| suggest(value, &candidates, 3))
+                    .unwrap_or_default();
+                // Deprecated actions stay valid but are offered last.
+                suggestions.sort_by_key(|candidate| {
+                    deprecation_for(&tool.name, Some(candidate.as_str())).is_some()
+                });
+                report.violations.push(ArgViolation {
+                    pointer,
+                    message,
+                    expected: Some(serde_json::json!({ "enum": options })),
+                    suggestions,
+                });
+            }
+            ValidationErrorKind::Required { property } => report.violations.push(ArgViolation {
+                pointer,
+                message,
+                expected: Some(serde_json::json!({ "required": property })),
+                suggestions: Vec::new(),
+            }),
+            _ => report.violations.push(ArgViolation {
+                pointer,
+                message,
+                expected: None,
+                suggestions: Vec::new(),
+            }),
         }
-        return Err(ContractError::new(
-            ErrorCode::InvalidParams,
-            lines.join("\n"),
-        ));
     }
-    Ok(())
+    report
+}
+
+pub fn validate_tool_args(tool_name: &str, args: &Value) -> Result<(), ContractError> {
+    let report = check_tool_args(tool_name, args);
+    if report.is_valid(true) {
+        return Ok(());
+    }
+    Err(report.to_contract_error(true))
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ArgTypos {
+    // Unknown top-level key -> the schema property it most likely meant.
+    pub did_you_mean: serde_json::Map<String, Value>,
+    pub unrecognized: Vec<String>,
+}
+
+// Explains a handler's invalid_params error by the call's unknown top-level keys. A key close to
+// a property the message names but the call lacks (usually the missing required field) wins over
+// the closest schema property overall.
+pub fn arg_typos(tool_name: &str, args: &Value, message: &str) -> ArgTypos {
+    let report = check_tool_args(tool_name, args);
+    let mut typos = ArgTypos::default();
+    if report.unknown.is_empty() {
+        return typos;
+    }
+    let known: Vec<String> = tool_by_name(tool_name)
+        .and_then(|tool| tool.input_schema.get("properties"))
+        .and_then(|props| props.as_object())
+        .map(|props| props.keys().cloned().collect())
+        .unwrap_or_default();
+    let missing: Vec<String> = message
+        .split(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .filter(|token| known.iter().any(|name| name == token) && args.get(*token).is_none())
+        .map(|token| token.to_string())
+        .collect();
+    for violation in &report.unknown {
+        let key = violation
+            .pointer
+            .trim_start_matches('/')
+            .replace("~1", "/")
+            .replace("~0", "~");
+        let meant = suggest(&key, &missing, 1)
+            .into_iter()
+            .next()
+            .or_else(|| violation.suggestions.first().cloned());
+        if let Some(meant) = meant {
+            typos.did_you_mean.insert(key.clone(), Value::String(meant));
+        }
+        typos.unrecognized.push(key);
+    }
+    typos
+}
+
+pub fn tool_action_effects(tool: &ToolDef) -> Vec<Value> {
+    tool.input_schema
+        .pointer("/properties/action/enum")
+        .and_then(|v| v.as_array())
+        .map(|actions| {
+            actions
+                .iter()
+                .filter_map(|v| v.as_str())
+                .map(|action| {
+                    let mut entry = hint_effects_for_tool_action(&tool.name, action).to_value();
+                    entry["action"] = Value::String(action.to_string());
+                    if let Some(deprecation) = deprecation_for(&tool.name, Some(action)) {
+                        entry["deprecated"] = deprecation.to_value();
+                    }
+                    entry
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub fn effects_catalog(tool_filter: Option<&str>) -> Value {
+    let filter = tool_filter.map(canonical_tool_name);
+    let tools: Vec<Value> = tool_contract_catalog()
+        .iter()
+        .filter(|tool| filter.map(|name| tool.name == name).unwrap_or(true))
+        .map(|tool| {
+            serde_json::json!({
+                "tool": tool.name,
+                "actions": tool_action_effects(tool),
+            })
+        })
+        .collect();
+    Value::Array(tools)
+}
+
+// Changes whenever the tool's input contract changes; clients can pin or gate on it.
+pub fn contract_version(tool: &ToolDef) -> String {
+    let rendered = serde_json::to_string(&tool.input_schema).unwrap_or_default();
+    hex::encode(Sha256::digest(rendered.as_bytes()))[..12].to_string()
+}
+
+pub fn versions_catalog() -> Value {
+    let tools: Vec<Value> = tool_contract_catalog()
+        .iter()
+        .map(|tool| {
+            let deprecated: Vec<Value> = DEPRECATIONS
+                .iter()
+                .filter(|entry| entry.tool == tool.name)
+                .map(|entry| {
+                    let mut value = entry.to_value();
+                    value["action"] = serde_json::json!(entry.action);
+                    value
+                })
+                .collect();
+            serde_json::json!({
+                "tool": tool.name,
+                "contract_version": contract_version(tool),
+                "deprecated": deprecated,
+            })
+        })
+        .collect();
+    serde_json::json!({
+        "crate_version": env!("CARGO_PKG_VERSION"),
+        "catalog_schema_version": CATALOG_SCHEMA_VERSION,
+        "tools": tools,
+    })
 }
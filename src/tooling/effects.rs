@@ -6,6 +6,7 @@ use serde_json::Value;
 pub struct ResolvedEffects {
     pub effects: Effects,
     pub reason: Option<String>,
+    pub trigger: Option<String>,
 }
 
 impl ResolvedEffects {
@@ -14,11 +15,25 @@ impl ResolvedEffects {
             Value::Object(map) => map,
             _ => serde_json::Map::new(),
         };
+        obj.insert(
+            "class".to_string(),
+            Value::String(self.effects.class().to_string()),
+        );
         if let Some(reason) = &self.reason {
             obj.insert("reason".to_string(), Value::String(reason.clone()));
         }
+        if let Some(trigger) = &self.trigger {
+            obj.insert("trigger".to_string(), Value::String(trigger.clone()));
+        }
         Value::Object(obj)
     }
+
+    pub fn triggered_by(&self) -> String {
+        match &self.trigger {
+            Some(trigger) => format!(" (triggered by {})", trigger),
+            None => String::new(),
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -43,6 +58,7 @@ fn effects(
             irreversible,
         },
         reason,
+        trigger: None,
     }
 }
 
@@ -438,6 +454,468 @@ fn classify_repo_exec(command: &str, argv: &[&str]) -> ResolvedEffects {
     )
 }
 
+const READ_ONLY_SHELL_COMMANDS: &[&str] = &[
+    "cat",
+    "ls",
+    "pwd",
+    "echo",
+    "printf",
+    "uname",
+    "uptime",
+    "whoami",
+    "id",
+    "hostname",
+    "date",
+    "df",
+    "du",
+    "free",
+    "ps",
+    "stat",
+    "file",
+    "head",
+    "tail",
+    "less",
+    "grep",
+    "egrep",
+    "zgrep",
+    "wc",
+    "sort",
+    "uniq",
+    "cut",
+    "tr",
+    "which",
+    "env",
+    "printenv",
+    "lsblk",
+    "lscpu",
+    "lsof",
+    "ss",
+    "netstat",
+    "ip",
+    "ping",
+    "dig",
+    "nslookup",
+    "curl",
+    "journalctl",
+    "test",
+    "true",
+    "sha256sum",
+    "md5sum",
+    "readlink",
+    "realpath",
+    "basename",
+    "dirname",
+    "nproc",
+    "last",
+    "w",
+    "who",
+];
+
+const WRITE_SHELL_COMMANDS: &[&str] = &[
+    "rm", "rmdir", "mv", "cp", "mkdir", "touch", "chmod", "chown", "chgrp", "ln", "tee", "dd",
+    "truncate", "shred", "mkfs", "wipefs", "kill", "pkill", "killall", "reboot", "shutdown",
+    "poweroff", "halt", "apt", "apt-get", "yum", "dnf", "apk", "pip", "npm", "service", "useradd",
+    "userdel", "usermod", "passwd", "crontab", "iptables", "sed", "install", "rsync", "scp", "tar",
+    "unzip",
+];
+
+const MAX_TRIGGER_CHARS: usize = 120;
+
+fn shorten(input: &str) -> String {
+    let trimmed = input.trim();
+    if trimmed.chars().count() <= MAX_TRIGGER_CHARS {
+        return trimmed.to_string();
+    }
+    let mut out: String = trimmed.chars().take(MAX_TRIGGER_CHARS).collect();
+    out.push('…');
+    out
+}
+
+fn effect_severity(resolved: &ResolvedEffects) -> u8 {
+    let effects = &resolved.effects;
+    if effects.irreversible {
+        3
+    } else if effects.requires_apply {
+        2
+    } else if effects.kind.as_deref() != Some("read") {
+        1
+    } else {
+        0
+    }
+}
+
+fn combine_nested_effects(label: &str, parts: Vec<(String, ResolvedEffects)>) -> ResolvedEffects {
+    if parts.is_empty() {
+        return effects(
+            "mixed",
+            true,
+            false,
+            Some(format!("{}: no nested steps to classify", label)),
+        );
+    }
+    let mut kind = "read";
+    let mut requires_apply = false;
+    let mut irreversible = false;
+    let mut worst: Option<&(String, ResolvedEffects)> = None;
+    for part in parts.iter() {
+        let nested = &part.1.effects;
+        match nested.kind.as_deref() {
+            Some("read") => {}
+            Some("write") if kind != "mixed" => kind = "write",
+            Some("write") => {}
+            _ => kind = "mixed",
+        }
+        requires_apply = requires_apply || nested.requires_apply;
+        irreversible = irreversible || nested.irreversible;
+        let severity = effect_severity(&part.1);
+        if severity > 0
+            && worst
+                .map(|w| effect_severity(&w.1) < severity)
+                .unwrap_or(true)
+        {
+            worst = Some(part);
+        }
+    }
+    let mut resolved = effects(kind, requires_apply, irreversible, None);
+    match worst {
+        Some((step, nested)) => {
+            resolved.reason = Some(match &nested.reason {
+                Some(reason) => format!("{}: {} ({})", label, step, reason),
+                None => format!("{}: {}", label, step),
+            });
+            resolved.trigger = Some(step.clone());
+        }
+        None => {
+            resolved.reason = Some(format!(
+                "{}: all {} nested step(s) are read-only",
+                label,
+                parts.len()
+            ));
+        }
+    }
+    resolved
+}
+
+fn strip_harmless_redirections(command: &str) -> String {
+    let mut cleaned = command.to_string();
+    for harmless in [
+        "2>&1",
+        ">&2",
+        "2>/dev/null",
+        "2> /dev/null",
+        ">/dev/null",
+        "> /dev/null",
+    ] {
+        cleaned = cleaned.replace(harmless, " ");
+    }
+    cleaned
+}
+
+fn classify_shell_segment(segment: &str) -> ResolvedEffects {
+    if segment.contains('>') {
+        return effects(
+            "write",
+            true,
+            false,
+            Some("shell output redirection".to_string()),
+        );
+    }
+    let tokens: Vec<&str> = segment
+        .split_whitespace()
+        .skip_while(|token| *token == "sudo" || (token.contains('=') && !token.starts_with('-')))
+        .collect();
+    let Some(first) = tokens.first() else {
+        return effects("read", false, false, None);
+    };
+    let cmd = first.rsplit('/').next().unwrap_or(first).to_lowercase();
+    let argv: Vec<&str> = tokens[1..].to_vec();
+    let sub = argv
+        .iter()
+        .find(|token| !token.starts_with('-'))
+        .map(|s| s.to_lowercase())
+        .unwrap_or_default();
+    match cmd.as_str() {
+        "git" | "kubectl" | "helm" | "kustomize" => classify_repo_exec(&cmd, &argv),
+        "find" => {
+            if argv
+                .iter()
+                .any(|t| matches!(*t, "-delete" | "-exec" | "-execdir"))
+            {
+                effects(
+                    "write",
+                    true,
+                    false,
+                    Some("find with -delete/-exec".to_string()),
+                )
+            } else {
+                effects("read", false, false, None)
+            }
+        }
+        "systemctl" => match sub.as_str() {
+            "" | "status" | "is-active" | "is-enabled" | "is-failed" | "show" | "cat"
+            | "list-units" | "list-unit-files" | "list-timers" => {
+                effects("read", false, false, Some(format!("systemctl {}", sub)))
+            }
+            _ => effects("write", true, false, Some(format!("systemctl {}", sub))),
+        },
+        "docker" | "podman" => match sub.as_str() {
+            "" | "ps" | "logs" | "inspect" | "images" | "version" | "info" | "stats" | "top" => {
+                effects("read", false, false, Some(format!("{} {}", cmd, sub)))
+            }
+            _ => effects("write", true, false, Some(format!("{} {}", cmd, sub))),
+        },
+        _ if READ_ONLY_SHELL_COMMANDS.contains(&cmd.as_str()) => {
+            effects("read", false, false, None)
+        }
+        _ if WRITE_SHELL_COMMANDS.contains(&cmd.as_str()) => effects(
+            "write",
+            true,
+            false,
+            Some(format!("{} modifies the host", cmd)),
+        ),
+        _ => effects(
+            "mixed",
+            true,
+            false,
+            Some(format!("{} (unknown command; treated as mixed)", cmd)),
+        ),
+    }
+}
+
+fn classify_shell_command(command: &str) -> ResolvedEffects {
+    let segments: Vec<(String, ResolvedEffects)> = strip_harmless_redirections(command)
+        .split(['\n', ';', '|', '&'])
+        .map(|segment| segment.trim())
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| (shorten(segment), classify_shell_segment(segment)))
+        .collect();
+    if segments.is_empty() {
+        return effects("mixed", true, false, Some("empty command".to_string()));
+    }
+    let mut resolved = combine_nested_effects("shell", segments);
+    resolved.reason = resolved.trigger.as_ref().map(|segment| segment.to_string());
+    resolved
+}
+
+fn batch_command_text(command: &Value) -> Option<String> {
+    let obj = command.as_object()?;
+    let head = obj
+        .get("command")
+        .and_then(|v| v.as_str())?
+        .trim()
+        .to_string();
+    let args: Vec<String> = obj
+        .get("args")
+        .and_then(|v| v.as_array())
+        .map(|arr| {
+            arr.iter()
+                .map(|v| match v {
+                    Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    if args.is_empty() {
+        Some(head)
+    } else {
+        Some(format!("{} {}", head, args.join(" ")))
+    }
+}
+
+fn classify_batch_commands(label: &str, args: &Value) -> ResolvedEffects {
+    let commands = args
+        .get("commands")
+        .and_then(|v| v.as_array())
+        .cloned()
+        .unwrap_or_default();
+    let parts = commands
+        .iter()
+        .enumerate()
+        .map(|(index, command)| match batch_command_text(command) {
+            Some(text) => (
+                format!("commands[{}]: {}", index, shorten(&text)),
+                classify_shell_command(&text),
+            ),
+            None => (
+                format!("commands[{}]", index),
+                effects(
+                    "mixed",
+                    true,
+                    false,
+                    Some("command is missing; treated as mixed".to_string()),
+                ),
+            ),
+        })
+        .collect();
+    combine_nested_effects(label, parts)
+}
+
+fn classify_pipeline_flow(args: &Value) -> ResolvedEffects {
+    let flow = string_arg(args, "flow").unwrap_or("");
+    if flow == "fan_out" {
+        return classify_pipeline_fan_out(args);
+    }
+    let Some((source, sink)) = flow.split_once("_to_") else {
+        return effects(
+            "mixed",
+            true,
+            false,
+            Some("pipeline flow is missing or unknown; treated as mixed".to_string()),
+        );
+    };
+    let dry_run = args.get("dry_run").and_then(|v| v.as_bool()) == Some(true);
+    combine_nested_effects(
+        &format!("pipeline flow={}", flow),
+        vec![
+            (
+                format!("source {}", source),
+                pipeline_source_effects(source, args.get(source)),
+            ),
+            (
+                format!("sink {}", sink),
+                pipeline_sink_effects(source, sink, args.get(sink), dry_run),
+            ),
+        ],
+    )
+}
+
+// One source, then each sink under its name.
+fn classify_pipeline_fan_out(args: &Value) -> ResolvedEffects {
+    let source = args.get("source");
+    let source_type = source.and_then(|v| string_arg(v, "type")).unwrap_or("");
+    let mut parts = vec![(
+        format!("source {}", source_type),
+        pipeline_source_effects(source_type, source),
+    )];
+    for (index, sink) in args
+        .get("sinks")
+        .and_then(|v| v.as_array())
+        .map(|sinks| sinks.iter().enumerate().collect::<Vec<_>>())
+        .unwrap_or_default()
+    {
+        let sink_type = string_arg(sink, "type").unwrap_or("");
+        let name = string_arg(sink, "name")
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("{}-{}", sink_type, index));
+        parts.push((
+            format!("sink {}", name),
+            pipeline_sink_effects("fan_out", sink_type, Some(sink), false),
+        ));
+    }
+    combine_nested_effects("pipeline flow=fan_out", parts)
+}
+
+fn pipeline_source_effects(source: &str, block: Option<&Value>) -> ResolvedEffects {
+    match source {
+        "http" => {
+            let method = block
+                .and_then(|v| v.get("method"))
+                .and_then(|v| v.as_str())
+                .unwrap_or("GET");
+            classify_http_method(method)
+        }
+        "sftp" | "postgres" => effects("read", false, false, Some(format!("{} read", source))),
+        _ => effects("mixed", true, false, None),
+    }
+}
+
+fn pipeline_sink_effects(
+    source: &str,
+    sink: &str,
+    block: Option<&Value>,
+    dry_run: bool,
+) -> ResolvedEffects {
+    match sink {
+        "sftp" => effects("write", true, false, Some("sftp upload".to_string())),
+        "postgres" => {
+            let replace = block
+                .map(|v| string_arg(v, "create_table") == Some("replace"))
+                .unwrap_or(false);
+            if replace {
+                effects(
+                    "write",
+                    true,
+                    true,
+                    Some(
+                        "postgres create_table=replace drops the table (irreversible)".to_string(),
+                    ),
+                )
+            } else {
+                effects("write", true, false, Some("postgres insert".to_string()))
+            }
+        }
+        "http"
+            if dry_run
+                && block.is_some_and(|http| {
+                    ["body_template", "path_template"]
+                        .iter()
+                        .any(|key| http.get(*key).is_some_and(|v| !v.is_null()))
+                }) =>
+        {
+            effects(
+                "read",
+                false,
+                false,
+                Some("dry_run renders templated requests without sending".to_string()),
+            )
+        }
+        "http" => {
+            let default_method = if source == "sftp" || source == "fan_out" {
+                "PUT"
+            } else {
+                "POST"
+            };
+            let method = block
+                .and_then(|v| v.get("method"))
+                .and_then(|v| v.as_str())
+                .unwrap_or(default_method);
+            classify_http_method(method)
+        }
+        _ => effects("mixed", true, false, None),
+    }
+}
+
+pub fn resolve_steps_effects(label: &str, steps: &[Value]) -> ResolvedEffects {
+    let parts = steps
+        .iter()
+        .enumerate()
+        .filter_map(|(index, step)| {
+            let tool = step
+                .get("tool")
+                .and_then(|v| v.as_str())
+                .map(|s| s.trim())
+                .filter(|s| !s.is_empty())?;
+            let args = step
+                .get("args")
+                .cloned()
+                .unwrap_or_else(|| Value::Object(Default::default()));
+            let id = step
+                .get("id")
+                .or_else(|| step.get("name"))
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string())
+                .unwrap_or_else(|| format!("steps[{}]", index));
+            let action = args.get("action").and_then(|v| v.as_str()).unwrap_or("");
+            let nested = resolve_tool_call_effects(tool, &args);
+            let step_label = match nested.trigger.as_ref() {
+                Some(inner) => format!("{} ({}.{} → {})", id, tool, action, inner),
+                None => format!("{} ({}.{})", id, tool, action),
+            };
+            Some((step_label, nested))
+        })
+        .collect::<Vec<_>>();
+    if parts.is_empty() {
+        return effects(
+            "read",
+            false,
+            false,
+            Some(format!("{}: no tool steps", label)),
+        );
+    }
+    combine_nested_effects(label, parts)
+}
+
 fn resolve_tool_action_effects(
     tool: &str,
     action: &str,
@@ -460,7 +938,7 @@ fn resolve_tool_action_effects(
         },
 
         "preset" => match action {
-            "preset_get" | "preset_list" => effects("read", false, false, None),
+            "preset_get" | "preset_list" | "preset_resolve" => effects("read", false, false, None),
             "preset_upsert" => effects("write", false, false, None),
             "preset_delete" => effects(
                 "write",
@@ -561,22 +1039,43 @@ fn resolve_tool_action_effects(
         },
 
         "audit" => match action {
-            "audit_list" | "audit_tail" | "audit_stats" => effects("read", false, false, None),
+            "audit_list" | "audit_tail" | "audit_stats" | "audit_verify" | "audit_trace"
+            | "audit_flush" => effects("read", false, false, None),
             "audit_clear" => effects(
                 "write",
                 false,
                 true,
                 Some("clears audit log (irreversible)".to_string()),
             ),
+            "export_trace" => effects(
+                "write",
+                false,
+                false,
+                Some("writes a trace bundle artifact".to_string()),
+            ),
             _ => effects("mixed", false, false, None),
         },
 
-        "artifacts" => effects("read", false, false, None),
+        "artifacts" => match action {
+            "delete" => effects(
+                "write",
+                false,
+                true,
+                Some("deletes an artifact ref (irreversible)".to_string()),
+            ),
+            "gc" => effects(
+                "write",
+                false,
+                false,
+                Some("removes artifact blobs no ref points at".to_string()),
+            ),
+            _ => effects("read", false, false, None),
+        },
 
         "context" => effects("read", false, false, None),
 
         "project" => match action {
-            "project_get" | "project_list" | "project_active" => {
+            "project_get" | "project_list" | "project_active" | "resolver_stats" => {
                 effects("read", false, false, None)
             }
             "project_upsert" | "project_use" | "project_unuse" => {
@@ -588,6 +1087,30 @@ fn resolve_tool_action_effects(
                 true,
                 Some("deletes project binding (irreversible)".to_string()),
             ),
+            "project_foreach" => match args.get("call") {
+                Some(call) if mode == ResolveMode::Runtime => {
+                    let mut call_args = call
+                        .get("args")
+                        .cloned()
+                        .filter(|v| v.is_object())
+                        .unwrap_or_else(|| Value::Object(Default::default()));
+                    call_args["action"] = call.get("action").cloned().unwrap_or(Value::Null);
+                    resolve_steps_effects(
+                        "project.foreach",
+                        &[serde_json::json!({
+                            "id": "call",
+                            "tool": call.get("tool").cloned().unwrap_or(Value::Null),
+                            "args": call_args,
+                        })],
+                    )
+                }
+                _ => effects(
+                    "mixed",
+                    false,
+                    false,
+                    Some("project_foreach effects follow the fanned-out call".to_string()),
+                ),
+            },
             _ => effects("mixed", false, false, None),
         },
 
@@ -604,12 +1127,32 @@ fn resolve_tool_action_effects(
                         .to_string(),
                 ),
             ),
+            "capability_discover" if bool_arg(args, "apply") => effects(
+                "write",
+                false,
+                false,
+                Some("adds discovered records to the capability and runbook manifests".to_string()),
+            ),
+            "capability_discover" => effects(
+                "read",
+                false,
+                false,
+                Some("probes project bindings read-only and only proposes records".to_string()),
+            ),
             _ => effects("mixed", false, false, None),
         },
 
         "receipt" | "profile" | "target" | "policy" => effects("read", false, false, None),
 
-        "evidence" => effects("read", false, false, None),
+        "evidence" => match action {
+            "create" => effects(
+                "write",
+                false,
+                false,
+                Some("writes a local evidence record".to_string()),
+            ),
+            _ => effects("read", false, false, None),
+        },
 
         // Orchestrators: they compute/enforce their own effects.
         "workspace" => match action {
@@ -619,17 +1162,74 @@ fn resolve_tool_action_effects(
                 false,
                 Some("workspace cleanup (in-memory)".to_string()),
             ),
-            "run" => effects(
-                "mixed",
+            "log_level_set" => effects(
+                "write",
+                false,
+                false,
+                Some("log level override (in-memory)".to_string()),
+            ),
+            "cache_invalidate" => effects(
+                "write",
+                false,
+                false,
+                Some("drops cached entries (refetched on next use)".to_string()),
+            ),
+            "config_apply" if args.get("plan_only").and_then(|v| v.as_bool()) == Some(true) => {
+                effects("read", false, false, None)
+            }
+            "config_apply" if args.get("prune").and_then(|v| v.as_bool()) == Some(true) => {
+                effects(
+                    "write",
+                    false,
+                    true,
+                    Some("prune deletes profiles/projects/presets/aliases missing from the document (irreversible)".to_string()),
+                )
+            }
+            "config_apply" => effects("write", false, false, None),
+            "secrets_rotate_key" => effects(
+                "write",
+                true,
                 false,
+                Some("re-encrypts stored secrets under a new key".to_string()),
+            ),
+            "config_export" if args.get("document_path").is_some() => effects(
+                "write",
                 false,
-                Some("workspace.run effects depend on chosen intent/runbook".to_string()),
+                false,
+                Some("writes the exported document to document_path".to_string()),
             ),
+            "run" => match args
+                .get("runbook")
+                .and_then(|v| v.get("steps"))
+                .and_then(|v| v.as_array())
+            {
+                Some(steps) if mode == ResolveMode::Runtime => {
+                    resolve_steps_effects("workspace.run", steps)
+                }
+                _ => effects(
+                    "mixed",
+                    false,
+                    false,
+                    Some("workspace.run effects depend on chosen intent/runbook".to_string()),
+                ),
+            },
             _ => effects("read", false, false, None),
         },
 
         "runbook" => match action {
-            "runbook_list" | "runbook_get" => effects("read", false, false, None),
+            "runbook_list" | "runbook_get" | "runbook_runs" => effects("read", false, false, None),
+            "runbook_capture_start" | "runbook_capture_stop" => effects(
+                "read",
+                false,
+                false,
+                Some("only records tool calls into session state".to_string()),
+            ),
+            "runbook_capture_save" => effects(
+                "write",
+                false,
+                false,
+                Some("adds the captured runbook to the runbook manifest".to_string()),
+            ),
             "runbook_compile" | "runbook_upsert" | "runbook_upsert_dsl" | "runbook_delete"
             | "runbook_run_dsl" => effects(
                 "read",
@@ -646,11 +1246,19 @@ fn resolve_tool_action_effects(
                 false,
                 Some("effects are determined by the runbook metadata".to_string()),
             ),
+            "runbook_resume" => effects(
+                "mixed",
+                false,
+                false,
+                Some("continues a paused run under the apply/confirm it started with".to_string()),
+            ),
             _ => effects("mixed", false, false, None),
         },
 
         "intent" => match action {
-            "compile" | "dry_run" | "explain" => effects("read", false, false, None),
+            "compile" | "dry_run" | "explain" | "preview" | "list" => {
+                effects("read", false, false, None)
+            }
             "execute" => effects(
                 "mixed",
                 false,
@@ -669,7 +1277,8 @@ fn resolve_tool_action_effects(
                 true,
                 Some("deletes env profile (irreversible)".to_string()),
             ),
-            "write_remote" => effects("write", true, false, None),
+            "env_get" | "env_diff" | "env_validate" => effects("read", false, false, None),
+            "write_remote" | "env_set" => effects("write", true, false, None),
             "run_remote" => effects("mixed", true, false, None),
             _ => effects("mixed", false, false, None),
         },
@@ -693,21 +1302,50 @@ fn resolve_tool_action_effects(
                 true,
                 Some("cancels a job (irreversible)".to_string()),
             ),
-            "job_forget" => effects(
+            "job_kill" => effects(
                 "write",
+                true,
+                true,
+                Some("kills a running job (irreversible)".to_string()),
+            ),
+            "job_forget" => match mode {
+                ResolveMode::Runtime if bool_arg(args, "cleanup") => effects(
+                    "write",
+                    true,
+                    false,
+                    Some("cleanup=true removes the job's remote log/pid/exit files".to_string()),
+                ),
+                _ => effects(
+                    "write",
+                    false,
+                    false,
+                    Some("forgets a job locally".to_string()),
+                ),
+            },
+            "jobs_gc" => effects(
+                "write",
+                true,
                 false,
-                false,
-                Some("forgets a job locally".to_string()),
+                Some("removes stale scratch files on the remote host".to_string()),
             ),
             _ => effects("read", false, false, None),
         },
 
         "ssh" => match action {
             "profile_get" | "profile_list" | "profile_test" | "connect" | "system_info"
-            | "check_host" | "sftp_list" | "sftp_exists" | "sftp_download" | "job_status"
-            | "job_wait" | "job_logs_tail" | "tail_job" | "follow_job" => {
+            | "inventory" | "check_host" | "probe" | "watch_path" | "sftp_list" | "sftp_exists"
+            | "sftp_download" | "job_wait" | "job_logs_tail" | "tail_job" | "follow_job" => {
                 effects("read", false, false, None)
             }
+            "job_status" => match mode {
+                ResolveMode::Runtime if bool_arg(args, "cleanup") => effects(
+                    "write",
+                    true,
+                    false,
+                    Some("cleanup=true removes the exited job's remote files".to_string()),
+                ),
+                _ => effects("read", false, false, None),
+            },
             "profile_upsert" => effects("write", false, false, None),
             "profile_delete" => effects(
                 "write",
@@ -722,29 +1360,55 @@ fn resolve_tool_action_effects(
                 Some("adds authorized key (treated as irreversible)".to_string()),
             ),
             "deploy_file" | "sftp_upload" => effects("write", true, false, None),
-            "exec" | "exec_detached" | "exec_follow" | "batch" => {
-                effects("mixed", true, false, None)
-            }
+            "exec" | "exec_detached" | "exec_follow" => effects("mixed", true, false, None),
+            "batch" => match mode {
+                ResolveMode::Hint => effects(
+                    "mixed",
+                    true,
+                    false,
+                    Some("classified from the batch commands".to_string()),
+                ),
+                ResolveMode::Runtime => classify_batch_commands("ssh batch", args),
+            },
             "job_kill" => effects(
                 "write",
                 true,
                 true,
                 Some("kills a job (irreversible)".to_string()),
             ),
-            "job_forget" => effects(
+            "job_forget" => match mode {
+                ResolveMode::Runtime if bool_arg(args, "cleanup") => effects(
+                    "write",
+                    true,
+                    false,
+                    Some("cleanup=true removes the job's remote log/pid/exit files".to_string()),
+                ),
+                _ => effects(
+                    "write",
+                    false,
+                    false,
+                    Some("forgets a job locally".to_string()),
+                ),
+            },
+            "jobs_gc" => effects(
                 "write",
+                true,
                 false,
-                false,
-                Some("forgets a job locally".to_string()),
+                Some("removes stale scratch files on the remote host".to_string()),
             ),
             _ => effects("mixed", false, false, None),
         },
 
         "api" => match action {
-            "profile_get" | "profile_list" | "check" | "smoke_http" => {
-                effects("read", false, false, None)
-            }
+            "profile_get" | "profile_list" | "check" | "smoke_http" | "recording_get"
+            | "fixtures_list" | "cert_check" => effects("read", false, false, None),
             "profile_upsert" => effects("write", false, false, None),
+            "fixtures_clear" => effects(
+                "write",
+                false,
+                false,
+                Some("removes recorded api fixtures".to_string()),
+            ),
             "profile_delete" => effects(
                 "write",
                 false,
@@ -805,7 +1469,7 @@ fn resolve_tool_action_effects(
                 Some("deletes postgres profile (irreversible)".to_string()),
             ),
             "select" | "count" | "exists" | "catalog_tables" | "catalog_columns"
-            | "database_info" => effects("read", false, false, None),
+            | "catalog_diff" | "database_info" => effects("read", false, false, None),
             "export" => match mode {
                 ResolveMode::Hint => effects(
                     "write",
@@ -835,6 +1499,18 @@ fn resolve_tool_action_effects(
                     }
                 }
             },
+            "insert_bulk" if string_arg(args, "create_table") == Some("replace") => effects(
+                "write",
+                true,
+                true,
+                Some("insert_bulk create_table=replace drops the table (irreversible)".to_string()),
+            ),
+            "update" | "delete" | "query" if bool_arg(args, "dry_run") => effects(
+                "read",
+                false,
+                false,
+                Some("dry_run executes in a transaction that is always rolled back".to_string()),
+            ),
             "insert" | "insert_bulk" | "update" | "delete" => effects("write", true, false, None),
             "query" | "batch" | "transaction" => match mode {
                 ResolveMode::Hint => effects(
@@ -857,7 +1533,10 @@ fn resolve_tool_action_effects(
                     let mut any_write = false;
                     let mut irreversible = false;
                     for statement in statements.iter() {
-                        let sql = statement.get("sql").and_then(|v| v.as_str()).unwrap_or("");
+                        let sql = statement
+                            .as_str()
+                            .or_else(|| statement.get("sql").and_then(|v| v.as_str()))
+                            .unwrap_or("");
                         let resolved = classify_sql(sql);
                         match resolved.effects.kind.as_deref() {
                             Some("read") => any_read = true,
@@ -959,13 +1638,49 @@ fn resolve_tool_action_effects(
                 true,
                 Some("filesystem delete is treated as irreversible".to_string()),
             ),
-            "exec" | "batch" => effects("mixed", true, false, None),
+            "batch" => match mode {
+                ResolveMode::Hint => effects(
+                    "mixed",
+                    true,
+                    false,
+                    Some("classified from the batch commands".to_string()),
+                ),
+                ResolveMode::Runtime => classify_batch_commands("local batch", args),
+            },
             _ => effects("mixed", true, false, None),
         },
 
         "pipeline" => match action {
             "describe" => effects("read", false, false, None),
-            "run" | "deploy_smoke" => effects("mixed", true, false, None),
+            "run" => match mode {
+                ResolveMode::Hint => effects(
+                    "write",
+                    true,
+                    false,
+                    Some(
+                        "writes to the flow sink (sftp upload, http request, postgres insert)"
+                            .to_string(),
+                    ),
+                ),
+                ResolveMode::Runtime => classify_pipeline_flow(args),
+            },
+            "deploy_smoke" => {
+                let mut resolved = effects(
+                    "write",
+                    true,
+                    false,
+                    Some(
+                        "uploads local_path, optionally restarts, then smoke-tests url".to_string(),
+                    ),
+                );
+                if mode == ResolveMode::Runtime {
+                    resolved.trigger = Some(match string_arg(args, "remote_path") {
+                        Some(path) => format!("deploy sftp upload to {}", path),
+                        None => "deploy sftp upload".to_string(),
+                    });
+                }
+                resolved
+            }
             _ => effects("mixed", true, false, None),
         },
 
@@ -1052,6 +1767,13 @@ fn parse_effects_object(obj: &serde_json::Map<String, Value>) -> Option<Resolved
             requires_apply,
             irreversible,
         },
-        reason: None,
+        reason: obj
+            .get("reason")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()),
+        trigger: obj
+            .get("trigger")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()),
     })
 }
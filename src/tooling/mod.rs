@@ -1,3 +1,5 @@
 pub mod catalog;
+pub mod dry_run;
 pub mod effects;
 pub mod names;
+pub mod topics;
@@ -0,0 +1,311 @@
+use crate::errors::ToolError;
+use crate::tooling::catalog::deprecation_for;
+use crate::tooling::effects::hint_effects_for_tool_action;
+use crate::utils::feature_flags::is_readonly_enabled;
+use crate::utils::suggest::suggest;
+use serde_json::Value;
+
+// `infra describe help topic=<system>`: one page answering "what can infra do with X" across
+// tools. Every tool, action and flow named here must exist in tool_contracts.json and every
+// section in docs/RUNBOOK.md; tests/help_topics.rs fails otherwise.
+pub struct TopicTool {
+    pub tool: &'static str,
+    pub actions: &'static [&'static str],
+}
+
+pub struct TopicProfile {
+    pub tool: &'static str,
+    pub profile_type: &'static str,
+    // Key of a project target that points at a profile of this type.
+    pub binding: &'static str,
+    // Arguments of a `profile_upsert` call, as JSON.
+    pub example: &'static str,
+}
+
+pub struct Topic {
+    pub name: &'static str,
+    pub summary: &'static str,
+    pub tools: &'static [TopicTool],
+    pub flows: &'static [&'static str],
+    pub profile: Option<TopicProfile>,
+    // Leading words of docs/RUNBOOK.md bullets, up to the colon.
+    pub runbook_sections: &'static [&'static str],
+}
+
+pub const TOPICS: &[Topic] = &[
+    Topic {
+        name: "postgres",
+        summary: "Query, write and export PostgreSQL tables; move rows to and from HTTP and SFTP.",
+        tools: &[
+            TopicTool {
+                tool: "sql",
+                actions: &[
+                    "profile_upsert",
+                    "profile_test",
+                    "query",
+                    "select",
+                    "insert_bulk",
+                    "update",
+                    "transaction",
+                    "export",
+                    "catalog_tables",
+                    "catalog_diff",
+                    "database_info",
+                ],
+            },
+            TopicTool {
+                tool: "pipeline",
+                actions: &["run", "describe"],
+            },
+        ],
+        flows: &[
+            "http_to_postgres",
+            "sftp_to_postgres",
+            "postgres_to_sftp",
+            "postgres_to_http",
+            "fan_out",
+        ],
+        profile: Some(TopicProfile {
+            tool: "sql",
+            profile_type: "postgresql",
+            binding: "postgres_profile",
+            example: r#"{"action": "profile_upsert", "profile_name": "app-db", "connection_url": "postgres://app@db.internal:5432/app"}"#,
+        }),
+        runbook_sections: &[
+            "PostgreSQL incidents",
+            "Schema drift",
+            "PostgreSQL TLS",
+            "Postgres sink tables",
+            "Bulk inserts",
+            "Sensitive columns",
+        ],
+    },
+    Topic {
+        name: "ssh",
+        summary: "Run commands on hosts, ship files over SFTP and inspect fleets.",
+        tools: &[TopicTool {
+            tool: "ssh",
+            actions: &[
+                "profile_upsert",
+                "profile_test",
+                "exec",
+                "exec_detached",
+                "deploy_file",
+                "batch",
+                "system_info",
+                "inventory",
+                "check_host",
+                "sftp_upload",
+                "sftp_download",
+            ],
+        }],
+        flows: &["http_to_sftp", "sftp_to_http", "sftp_to_postgres"],
+        profile: Some(TopicProfile {
+            tool: "ssh",
+            profile_type: "ssh",
+            binding: "ssh_profile",
+            example: r#"{"action": "profile_upsert", "profile_name": "web-1", "connection": {"host": "web-1.internal", "username": "deploy"}}"#,
+        }),
+        runbook_sections: &[
+            "SSH connect retry",
+            "SSH agent auth",
+            "New hosts before credentials",
+            "Fleet overview",
+            "Deploy preflight",
+        ],
+    },
+    Topic {
+        name: "http",
+        summary: "Call HTTP APIs, page through them, download files and smoke-test endpoints.",
+        tools: &[
+            TopicTool {
+                tool: "api",
+                actions: &[
+                    "profile_upsert",
+                    "request",
+                    "paginate",
+                    "download",
+                    "check",
+                    "smoke_http",
+                    "cert_check",
+                ],
+            },
+            TopicTool {
+                tool: "pipeline",
+                actions: &["run", "deploy_smoke"],
+            },
+        ],
+        flows: &[
+            "http_to_sftp",
+            "http_to_postgres",
+            "sftp_to_http",
+            "postgres_to_http",
+            "fan_out",
+        ],
+        profile: Some(TopicProfile {
+            tool: "api",
+            profile_type: "api",
+            binding: "api_profile",
+            example: r#"{"action": "profile_upsert", "profile_name": "billing", "base_url": "https://billing.example.com/v1"}"#,
+        }),
+        runbook_sections: &[
+            "HTTP traffic",
+            "Offline API fixtures",
+            "Mutual TLS APIs",
+            "Proxied egress",
+            "Shaping API responses",
+            "Certificate expiry",
+            "Smoke SLAs",
+        ],
+    },
+    Topic {
+        name: "files",
+        summary: "Read and write files locally, on hosts over SFTP, and as run artifacts.",
+        tools: &[
+            TopicTool {
+                tool: "local",
+                actions: &["fs_read", "fs_write", "fs_list", "fs_stat"],
+            },
+            TopicTool {
+                tool: "ssh",
+                actions: &[
+                    "sftp_list",
+                    "sftp_exists",
+                    "watch_path",
+                    "sftp_upload",
+                    "sftp_download",
+                    "deploy_file",
+                ],
+            },
+            TopicTool {
+                tool: "artifacts",
+                actions: &["get", "tail", "list", "gc"],
+            },
+        ],
+        flows: &["http_to_sftp", "sftp_to_http", "postgres_to_sftp"],
+        profile: None,
+        runbook_sections: &[
+            "Local files (`INFRA_UNSAFE_LOCAL=1`)",
+            "Large SFTP transfers",
+            "Large remote directories",
+            "Artifact dedup",
+            "Safe names",
+        ],
+    },
+    Topic {
+        name: "jobs",
+        summary: "Start long-running commands in the background and follow, wait for or stop them.",
+        tools: &[
+            TopicTool {
+                tool: "job",
+                actions: &[
+                    "job_list",
+                    "job_status",
+                    "job_wait",
+                    "job_wait_all",
+                    "follow_job",
+                    "job_cancel",
+                    "job_forget",
+                ],
+            },
+            TopicTool {
+                tool: "ssh",
+                actions: &["exec_detached", "exec_follow", "jobs_gc"],
+            },
+            TopicTool {
+                tool: "local",
+                actions: &["exec"],
+            },
+        ],
+        flows: &[],
+        profile: None,
+        runbook_sections: &[
+            "Following detached jobs",
+            "Local background jobs",
+            "Remote scratch",
+        ],
+    },
+];
+
+pub fn topic_by_name(name: &str) -> Option<&'static Topic> {
+    let name = name.trim().to_ascii_lowercase();
+    TOPICS.iter().find(|topic| topic.name == name)
+}
+
+fn unknown_topic(name: &str) -> ToolError {
+    let names: Vec<String> = TOPICS.iter().map(|t| t.name.to_string()).collect();
+    let mut err = ToolError::invalid_params(format!("unknown help topic '{}'", name))
+        .with_hint(format!("Use one of: {}", names.join(", ")));
+    let suggestions = suggest(name, &names, 1);
+    if !suggestions.is_empty() {
+        err = err.with_details(serde_json::json!({"did_you_mean": suggestions[0]}));
+    }
+    err
+}
+
+pub fn topic_index() -> Value {
+    Value::Array(
+        TOPICS
+            .iter()
+            .map(|topic| serde_json::json!({"topic": topic.name, "summary": topic.summary}))
+            .collect(),
+    )
+}
+
+// Under INFRA_READONLY write-classified actions are refused, so they are left out of the
+// matrix and only named under `hidden_by_readonly`.
+pub fn capability_matrix(name: &str) -> Result<Value, ToolError> {
+    let topic = topic_by_name(name).ok_or_else(|| unknown_topic(name))?;
+    let readonly = is_readonly_enabled();
+    let mut hidden = Vec::new();
+    let tools: Vec<Value> = topic
+        .tools
+        .iter()
+        .map(|entry| {
+            let actions: Vec<Value> = entry
+                .actions
+                .iter()
+                .filter_map(|action| {
+                    let effects = hint_effects_for_tool_action(entry.tool, action);
+                    if readonly && effects.effects.is_write_classified() {
+                        hidden.push(format!("{}.{}", entry.tool, action));
+                        return None;
+                    }
+                    let mut item = serde_json::json!({
+                        "action": action,
+                        "effects": effects.effects.class(),
+                    });
+                    if let Some(deprecation) = deprecation_for(entry.tool, Some(action)) {
+                        item["deprecated"] = deprecation.to_value();
+                    }
+                    Some(item)
+                })
+                .collect();
+            serde_json::json!({"tool": entry.tool, "actions": actions})
+        })
+        .collect();
+    let profile = topic.profile.as_ref().map(|profile| {
+        serde_json::json!({
+            "tool": profile.tool,
+            "type": profile.profile_type,
+            "project_binding": profile.binding,
+            "upsert_example": serde_json::from_str::<Value>(profile.example)
+                .unwrap_or(Value::Null),
+        })
+    });
+    Ok(serde_json::json!({
+        "topic": topic.name,
+        "summary": topic.summary,
+        "readonly": readonly,
+        "tools": tools,
+        "hidden_by_readonly": hidden,
+        "pipeline_flows": topic.flows,
+        "profile": profile,
+        "project_binding": topic.profile.as_ref().map(|profile| profile.binding),
+        "runbook_sections": topic
+            .runbook_sections
+            .iter()
+            .map(|section| format!("docs/RUNBOOK.md: {}", section))
+            .collect::<Vec<_>>(),
+    }))
+}
@@ -0,0 +1,112 @@
+// Removes terminal control sequences from pty output: CSI (`ESC [ ... final`), OSC (`ESC ] ...`
+// up to BEL or `ESC \`), two-byte escapes such as charset selection, and BEL. `\r\n` becomes
+// `\n`; a lone `\r` is kept. State carries over between chunks, so a sequence split across two
+// reads is still removed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum State {
+    #[default]
+    Ground,
+    Escape,
+    EscapeIntermediate,
+    Csi,
+    Osc,
+    OscEscape,
+}
+
+#[derive(Debug, Default)]
+pub struct AnsiStripper {
+    state: State,
+    pending_cr: bool,
+}
+
+impl AnsiStripper {
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(chunk.len());
+        for &byte in chunk {
+            match self.state {
+                State::Ground => {
+                    if self.pending_cr {
+                        self.pending_cr = false;
+                        if byte == b'\n' {
+                            out.push(b'\n');
+                            continue;
+                        }
+                        out.push(b'\r');
+                    }
+                    match byte {
+                        0x1b => self.state = State::Escape,
+                        b'\r' => self.pending_cr = true,
+                        0x07 => {}
+                        _ => out.push(byte),
+                    }
+                }
+                State::Escape => {
+                    self.state = match byte {
+                        b'[' => State::Csi,
+                        b']' => State::Osc,
+                        0x20..=0x2f => State::EscapeIntermediate,
+                        _ => State::Ground,
+                    }
+                }
+                State::EscapeIntermediate => {
+                    if (0x30..=0x7e).contains(&byte) {
+                        self.state = State::Ground;
+                    }
+                }
+                State::Csi => {
+                    if (0x40..=0x7e).contains(&byte) {
+                        self.state = State::Ground;
+                    }
+                }
+                State::Osc => match byte {
+                    0x07 => self.state = State::Ground,
+                    0x1b => self.state = State::OscEscape,
+                    _ => {}
+                },
+                State::OscEscape => self.state = State::Ground,
+            }
+        }
+        out
+    }
+
+    // A `\r` held back at the end of the last chunk.
+    pub fn finish(&mut self) -> Vec<u8> {
+        if std::mem::take(&mut self.pending_cr) {
+            vec![b'\r']
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+pub fn strip_ansi(text: &str) -> String {
+    let mut stripper = AnsiStripper::default();
+    let mut out = stripper.feed(text.as_bytes());
+    out.extend(stripper.finish());
+    String::from_utf8_lossy(&out).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_sequences_and_normalizes_line_endings() {
+        assert_eq!(
+            strip_ansi("\x1b[1;32mok\x1b[0m\r\n\x1b]0;title\x07done\x1b(B\r"),
+            "ok\ndone\r"
+        );
+        assert_eq!(strip_ansi("50%\r100%\r\n"), "50%\r100%\n");
+        assert_eq!(strip_ansi("\x1b]8;;https://x\x1b\\link"), "link");
+    }
+
+    #[test]
+    fn sequences_split_across_chunks_are_removed() {
+        let mut stripper = AnsiStripper::default();
+        let mut out = stripper.feed(b"Are you sure? \x1b[");
+        out.extend(stripper.feed(b"33m[y/N]\x1b[0m\r"));
+        out.extend(stripper.feed(b"\n"));
+        out.extend(stripper.finish());
+        assert_eq!(String::from_utf8(out).unwrap(), "Are you sure? [y/N]\n");
+    }
+}
@@ -0,0 +1,317 @@
+use crate::errors::{ToolError, ToolErrorKind};
+use crate::services::cache::stable_key;
+use crate::utils::feature_flags::{
+    is_allow_secret_export_enabled, API_FIXTURES, API_FIXTURES_MISS,
+};
+use crate::utils::fs_atomic::atomic_write_text_file;
+use crate::utils::paths::resolve_api_fixtures_dir;
+use crate::utils::redact::{redact_object, redact_text};
+use serde_json::Value;
+use std::path::PathBuf;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FixtureMode {
+    Record,
+    // `fallback`: a miss sends the real request instead of failing.
+    Replay { fallback: bool },
+}
+
+pub fn mode() -> Result<Option<FixtureMode>, ToolError> {
+    let raw = API_FIXTURES
+        .text()
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase();
+    match raw.as_str() {
+        "" | "off" | "0" | "false" => Ok(None),
+        "record" => Ok(Some(FixtureMode::Record)),
+        "replay" => {
+            let miss = API_FIXTURES_MISS
+                .text()
+                .unwrap_or_default()
+                .trim()
+                .to_lowercase();
+            match miss.as_str() {
+                "" | "strict" => Ok(Some(FixtureMode::Replay { fallback: false })),
+                "fallback" => Ok(Some(FixtureMode::Replay { fallback: true })),
+                _ => Err(ToolError::invalid_params(format!(
+                    "INFRA_API_FIXTURES_MISS must be strict or fallback, got '{}'",
+                    miss
+                ))),
+            }
+        }
+        _ => Err(ToolError::invalid_params(format!(
+            "INFRA_API_FIXTURES must be record or replay, got '{}'",
+            raw
+        ))),
+    }
+}
+
+// JSON bodies key by their parsed form, so field order does not change the fixture.
+pub fn body_value(bytes: Option<&[u8]>) -> Value {
+    match bytes {
+        None => Value::Null,
+        Some(bytes) => serde_json::from_slice::<Value>(bytes)
+            .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(bytes).to_string())),
+    }
+}
+
+// Same keying as the response cache: the sha256 of the normalized request.
+pub fn fixture_key(method: &str, url: &str, body: &Value) -> String {
+    stable_key(&serde_json::json!({
+        "method": method.to_uppercase(),
+        "url": url,
+        "body": body,
+    }))
+}
+
+fn fixture_path(key: &str) -> PathBuf {
+    resolve_api_fixtures_dir().join(format!("{}.json", key))
+}
+
+fn is_fixture_key(key: &str) -> bool {
+    key.len() == 64 && key.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+pub fn load(key: &str) -> Result<Option<Value>, ToolError> {
+    let path = fixture_path(key);
+    let raw = match std::fs::read_to_string(&path) {
+        Ok(raw) => raw,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => {
+            return Err(ToolError::internal(format!(
+                "Failed to read api fixture {}: {}",
+                path.display(),
+                err
+            )))
+        }
+    };
+    serde_json::from_str(&raw).map(Some).map_err(|err| {
+        ToolError::internal(format!("Corrupt api fixture {}: {}", path.display(), err))
+            .with_hint("Remove it with api action=fixtures_clear and record again.".to_string())
+    })
+}
+
+pub fn miss_error(key: &str, method: &str, url: &str) -> ToolError {
+    ToolError::new(
+        ToolErrorKind::NotFound,
+        "FIXTURE_MISS",
+        format!("No api fixture for {} {}", method, url),
+    )
+    .with_hint(
+        "Record it with INFRA_API_FIXTURES=record, or set INFRA_API_FIXTURES_MISS=fallback to send unrecorded requests."
+            .to_string(),
+    )
+    .with_details(serde_json::json!({"key": key, "method": method, "url": url}))
+}
+
+pub fn replay_response(fixture: &Value, key: &str) -> Value {
+    let mut response = fixture
+        .get("response")
+        .cloned()
+        .unwrap_or_else(|| serde_json::json!({}));
+    response["fixture"] = serde_json::json!({
+        "mode": "replay",
+        "key": key,
+        "recorded_at": fixture.get("recorded_at").cloned().unwrap_or(Value::Null),
+        "body_withheld": fixture.pointer("/response_body/withheld").cloned().unwrap_or(Value::Bool(false)),
+    });
+    response
+}
+
+// A body that redaction would change carries secrets: only its hash is kept unless
+// INFRA_ALLOW_SECRET_EXPORT=1.
+fn guard_body(body: &Value, secrets: &[String]) -> (Value, Value) {
+    let sensitive = !body.is_null() && redact_object(body, usize::MAX, Some(secrets)) != *body;
+    let withheld = sensitive && !is_allow_secret_export_enabled();
+    let meta = serde_json::json!({
+        "sha256": stable_key(body),
+        "withheld": withheld,
+    });
+    if withheld {
+        (Value::Null, meta)
+    } else {
+        (body.clone(), meta)
+    }
+}
+
+fn redact_headers(headers: &Value, secrets: &[String]) -> Value {
+    redact_object(
+        &serde_json::json!({ "headers": headers }),
+        usize::MAX,
+        Some(secrets),
+    )
+    .get("headers")
+    .cloned()
+    .unwrap_or(Value::Null)
+}
+
+pub struct RecordedRequest<'a> {
+    pub method: &'a str,
+    pub url: &'a str,
+    pub headers: Value,
+    pub body: &'a Value,
+}
+
+pub fn store(
+    key: &str,
+    request: RecordedRequest<'_>,
+    response: &Value,
+    secrets: &[String],
+) -> Result<PathBuf, ToolError> {
+    let (request_body, request_body_meta) = guard_body(request.body, secrets);
+    let mut stored = response.clone();
+    let (data, response_body_meta) =
+        guard_body(response.get("data").unwrap_or(&Value::Null), secrets);
+    if let Value::Object(map) = &mut stored {
+        map.insert("data".to_string(), data);
+        if let Some(headers) = map.get("headers").cloned() {
+            map.insert("headers".to_string(), redact_headers(&headers, secrets));
+        }
+        if let Some(url) = map.get("url").and_then(|v| v.as_str()) {
+            let url = redact_text(url, usize::MAX, Some(secrets));
+            map.insert("url".to_string(), Value::String(url));
+        }
+        map.remove("fixture");
+    }
+    let fixture = serde_json::json!({
+        "key": key,
+        "recorded_at": chrono::Utc::now().to_rfc3339(),
+        "request": {
+            "method": request.method,
+            "url": redact_text(request.url, usize::MAX, Some(secrets)),
+            "headers": redact_headers(&request.headers, secrets),
+            "body": request_body,
+        },
+        "request_body": request_body_meta,
+        "response": stored,
+        "response_body": response_body_meta,
+    });
+    let path = fixture_path(key);
+    let content = serde_json::to_string_pretty(&fixture)
+        .map_err(|err| ToolError::internal(format!("Failed to encode api fixture: {}", err)))?;
+    atomic_write_text_file(&path, &content, 0o600)
+        .map_err(|err| ToolError::internal(format!("Failed to write api fixture: {}", err)))?;
+    Ok(path)
+}
+
+fn summary(fixture: &Value) -> Value {
+    serde_json::json!({
+        "key": fixture.get("key").cloned().unwrap_or(Value::Null),
+        "method": fixture.pointer("/request/method").cloned().unwrap_or(Value::Null),
+        "url": fixture.pointer("/request/url").cloned().unwrap_or(Value::Null),
+        "status": fixture.pointer("/response/status").cloned().unwrap_or(Value::Null),
+        "recorded_at": fixture.get("recorded_at").cloned().unwrap_or(Value::Null),
+        "body_withheld": fixture.pointer("/response_body/withheld").cloned().unwrap_or(Value::Bool(false)),
+    })
+}
+
+fn fixture_keys() -> Result<Vec<String>, ToolError> {
+    let dir = resolve_api_fixtures_dir();
+    let entries = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => {
+            return Err(ToolError::internal(format!(
+                "Failed to list api fixtures: {}",
+                err
+            )))
+        }
+    };
+    let mut keys: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            name.strip_suffix(".json")
+                .filter(|key| is_fixture_key(key))
+                .map(str::to_string)
+        })
+        .collect();
+    keys.sort();
+    Ok(keys)
+}
+
+// Newest first; `url_contains` filters on the recorded url.
+pub fn list(url_contains: Option<&str>, limit: usize) -> Result<Value, ToolError> {
+    let mut fixtures = Vec::new();
+    for key in fixture_keys()? {
+        let Ok(Some(fixture)) = load(&key) else {
+            continue;
+        };
+        let item = summary(&fixture);
+        let url = item.get("url").and_then(|v| v.as_str()).unwrap_or("");
+        if url_contains.is_some_and(|needle| !url.contains(needle)) {
+            continue;
+        }
+        fixtures.push(item);
+    }
+    fixtures.sort_by(|a, b| b["recorded_at"].as_str().cmp(&a["recorded_at"].as_str()));
+    let total = fixtures.len();
+    fixtures.truncate(limit);
+    Ok(serde_json::json!({
+        "dir": resolve_api_fixtures_dir().display().to_string(),
+        "total": total,
+        "fixtures": fixtures,
+    }))
+}
+
+// Removes the given fixtures, or all of them.
+pub fn clear(keys: Option<&[String]>) -> Result<Vec<String>, ToolError> {
+    let targets = match keys {
+        Some(keys) => {
+            for key in keys {
+                if !is_fixture_key(key) {
+                    return Err(ToolError::invalid_params(format!(
+                        "'{}' is not a fixture key (64 hex chars)",
+                        key
+                    )));
+                }
+            }
+            keys.to_vec()
+        }
+        None => fixture_keys()?,
+    };
+    let mut removed = Vec::new();
+    for key in targets {
+        match std::fs::remove_file(fixture_path(&key)) {
+            Ok(()) => removed.push(key),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => {
+                return Err(ToolError::internal(format!(
+                    "Failed to remove api fixture {}: {}",
+                    key, err
+                )))
+            }
+        }
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn keys_ignore_json_field_order_and_method_case() {
+        let a = fixture_key("get", "https://x/a", &body_value(Some(br#"{"b":1,"a":2}"#)));
+        let b = fixture_key("GET", "https://x/a", &body_value(Some(br#"{"a":2,"b":1}"#)));
+        assert_eq!(a, b);
+        assert_ne!(a, fixture_key("POST", "https://x/a", &Value::Null));
+        assert_eq!(body_value(Some(b"plain")), json!("plain"));
+    }
+
+    #[test]
+    fn sensitive_bodies_keep_only_their_hash() {
+        let secrets = vec!["s3cr3t-token-value".to_string()];
+        let (kept, meta) = guard_body(&json!({"name": "app"}), &secrets);
+        assert_eq!(kept, json!({"name": "app"}));
+        assert_eq!(meta["withheld"], false);
+        let (kept, meta) = guard_body(&json!({"password": "hunter2"}), &secrets);
+        assert!(kept.is_null());
+        assert_eq!(meta["withheld"], true);
+        assert_eq!(meta["sha256"].as_str().map(str::len), Some(64));
+        let (kept, _) = guard_body(&json!("bearer s3cr3t-token-value"), &secrets);
+        assert!(kept.is_null());
+    }
+}
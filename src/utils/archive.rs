@@ -0,0 +1,432 @@
+use crate::errors::ToolError;
+use flate2::read::{DeflateDecoder, MultiGzDecoder};
+use serde_json::Value;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const EOCD_SIGNATURE: &[u8] = b"PK\x05\x06";
+const CENTRAL_SIGNATURE: &[u8] = b"PK\x01\x02";
+const LOCAL_SIGNATURE: &[u8] = b"PK\x03\x04";
+const EOCD_LEN: usize = 22;
+const CENTRAL_LEN: usize = 46;
+const LOCAL_LEN: usize = 30;
+// The end record sits within its own size plus the longest archive comment from the end.
+const EOCD_SEARCH_BYTES: u64 = EOCD_LEN as u64 + 65_535;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Decompress {
+    None,
+    Gzip,
+    // Gzip when the stream starts with the gzip magic bytes, untouched otherwise.
+    Auto,
+}
+
+impl Decompress {
+    pub fn parse(value: Option<&Value>) -> Result<Self, ToolError> {
+        match value
+            .and_then(|v| v.as_str())
+            .map(|s| s.trim().to_lowercase())
+            .as_deref()
+        {
+            None | Some("") | Some("none") => match value {
+                Some(v) if !v.is_null() && !v.is_string() => Err(ToolError::invalid_params(
+                    "decompress must be one of: gzip, auto, none",
+                )),
+                _ => Ok(Decompress::None),
+            },
+            Some("gzip") | Some("gz") => Ok(Decompress::Gzip),
+            Some("auto") => Ok(Decompress::Auto),
+            Some(other) => Err(ToolError::invalid_params(format!(
+                "decompress must be one of: gzip, auto, none (got {})",
+                other
+            ))),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Decompress::None => "none",
+            Decompress::Gzip => "gzip",
+            Decompress::Auto => "auto",
+        }
+    }
+}
+
+// Wraps `reader` so reads yield decompressed bytes; concatenated gzip members decode as one stream.
+pub fn decode_reader<'a, R: Read + 'a>(
+    reader: R,
+    mode: Decompress,
+) -> Result<Box<dyn Read + 'a>, ToolError> {
+    match mode {
+        Decompress::None => Ok(Box::new(reader)),
+        Decompress::Gzip => Ok(Box::new(MultiGzDecoder::new(reader))),
+        Decompress::Auto => {
+            let mut buffered = BufReader::with_capacity(64 * 1024, reader);
+            let head = buffered
+                .fill_buf()
+                .map_err(|err| ToolError::internal(err.to_string()))?;
+            if head.starts_with(&GZIP_MAGIC) {
+                Ok(Box::new(MultiGzDecoder::new(buffered)))
+            } else {
+                Ok(Box::new(buffered))
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ZipMember {
+    pub name: String,
+    pub size: u64,
+    pub compressed_size: u64,
+    method: u16,
+    encrypted: bool,
+    header_offset: u64,
+}
+
+impl ZipMember {
+    // The part after the last `/`, which member globs without a `/` match against.
+    pub fn file_name(&self) -> &str {
+        self.name.rsplit('/').next().unwrap_or(&self.name)
+    }
+}
+
+fn zip_error(message: impl Into<String>) -> ToolError {
+    ToolError::invalid_params(format!("zip archive: {}", message.into()))
+}
+
+fn io_error(err: std::io::Error) -> ToolError {
+    if err.kind() == std::io::ErrorKind::UnexpectedEof {
+        return zip_error("truncated");
+    }
+    ToolError::internal(err.to_string())
+}
+
+fn le16(buf: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([buf[at], buf[at + 1]])
+}
+
+fn le32(buf: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([buf[at], buf[at + 1], buf[at + 2], buf[at + 3]])
+}
+
+// File entries from the central directory, in archive order; directory entries are left out.
+pub fn zip_members<R: Read + Seek>(reader: &mut R) -> Result<Vec<ZipMember>, ToolError> {
+    let len = reader.seek(SeekFrom::End(0)).map_err(io_error)?;
+    let start = len.saturating_sub(EOCD_SEARCH_BYTES);
+    reader.seek(SeekFrom::Start(start)).map_err(io_error)?;
+    let mut tail = Vec::with_capacity((len - start) as usize);
+    reader.read_to_end(&mut tail).map_err(io_error)?;
+    let eocd = tail
+        .windows(EOCD_SIGNATURE.len())
+        .rposition(|w| w == EOCD_SIGNATURE)
+        .filter(|pos| pos + EOCD_LEN <= tail.len())
+        .ok_or_else(|| zip_error("end of central directory not found"))?;
+    let record = &tail[eocd..eocd + EOCD_LEN];
+    let entries = le16(record, 10);
+    let dir_size = le32(record, 12);
+    let dir_offset = le32(record, 16);
+    if entries == u16::MAX || dir_size == u32::MAX || dir_offset == u32::MAX {
+        return Err(zip_error("zip64 archives are not supported"));
+    }
+
+    reader
+        .seek(SeekFrom::Start(dir_offset as u64))
+        .map_err(io_error)?;
+    let mut dir = vec![0u8; dir_size as usize];
+    reader.read_exact(&mut dir).map_err(io_error)?;
+
+    let mut members = Vec::new();
+    let mut at = 0usize;
+    for _ in 0..entries {
+        let header = dir
+            .get(at..at + CENTRAL_LEN)
+            .filter(|h| h.starts_with(CENTRAL_SIGNATURE))
+            .ok_or_else(|| zip_error("corrupt central directory"))?;
+        let name_len = le16(header, 28) as usize;
+        let extra_len = le16(header, 30) as usize;
+        let comment_len = le16(header, 32) as usize;
+        let name = dir
+            .get(at + CENTRAL_LEN..at + CENTRAL_LEN + name_len)
+            .ok_or_else(|| zip_error("corrupt central directory"))?;
+        let name = String::from_utf8_lossy(name).to_string();
+        if !name.ends_with('/') {
+            members.push(ZipMember {
+                size: le32(header, 24) as u64,
+                compressed_size: le32(header, 20) as u64,
+                method: le16(header, 10),
+                encrypted: le16(header, 8) & 1 == 1,
+                header_offset: le32(header, 42) as u64,
+                name,
+            });
+        }
+        at += CENTRAL_LEN + name_len + extra_len + comment_len;
+    }
+    Ok(members)
+}
+
+// A reader over the uncompressed bytes of one member (stored or deflated).
+pub fn open_member<'a, R: Read + Seek + 'a>(
+    mut reader: R,
+    member: &ZipMember,
+) -> Result<Box<dyn Read + 'a>, ToolError> {
+    if member.encrypted {
+        return Err(zip_error(format!("{} is encrypted", member.name)));
+    }
+    reader
+        .seek(SeekFrom::Start(member.header_offset))
+        .map_err(io_error)?;
+    let mut header = [0u8; LOCAL_LEN];
+    reader.read_exact(&mut header).map_err(io_error)?;
+    if !header.starts_with(LOCAL_SIGNATURE) {
+        return Err(zip_error(format!(
+            "corrupt local header for {}",
+            member.name
+        )));
+    }
+    let skip = le16(&header, 26) as i64 + le16(&header, 28) as i64;
+    reader.seek(SeekFrom::Current(skip)).map_err(io_error)?;
+    let data = reader.take(member.compressed_size);
+    match member.method {
+        0 => Ok(Box::new(data)),
+        8 => Ok(Box::new(DeflateDecoder::new(data))),
+        method => Err(zip_error(format!(
+            "{} uses unsupported compression method {}",
+            member.name, method
+        ))),
+    }
+}
+
+const TAR_BLOCK: usize = 512;
+
+fn tar_octal(field: &mut [u8], value: u64) {
+    let text = format!("{:0width$o}\0", value, width = field.len() - 1);
+    field.copy_from_slice(text.as_bytes());
+}
+
+// Splits a path into ustar's prefix (155 bytes) and name (100 bytes) fields at a '/'.
+fn tar_name(path: &str) -> Result<(&str, &str), ToolError> {
+    if path.len() <= 100 {
+        return Ok(("", path));
+    }
+    path.char_indices()
+        .filter(|(_, c)| *c == '/')
+        .map(|(at, _)| (&path[..at], &path[at + 1..]))
+        .find(|(prefix, name)| prefix.len() <= 155 && name.len() <= 100 && !name.is_empty())
+        .ok_or_else(|| ToolError::internal(format!("path too long for a tar entry: {}", path)))
+}
+
+// A gzip-compressed ustar archive of regular files (mode 0644), in the given order.
+pub fn tar_gz(entries: &[(String, Vec<u8>)], mtime: u64) -> Result<Vec<u8>, ToolError> {
+    use flate2::write::GzEncoder;
+    use std::io::Write;
+
+    let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+    for (path, data) in entries {
+        let (prefix, name) = tar_name(path)?;
+        let mut header = [0u8; TAR_BLOCK];
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        tar_octal(&mut header[100..108], 0o644);
+        tar_octal(&mut header[108..116], 0);
+        tar_octal(&mut header[116..124], 0);
+        tar_octal(&mut header[124..136], data.len() as u64);
+        tar_octal(&mut header[136..148], mtime);
+        header[148..156].copy_from_slice(b"        ");
+        header[156] = b'0';
+        header[257..263].copy_from_slice(b"ustar\0");
+        header[263..265].copy_from_slice(b"00");
+        header[345..345 + prefix.len()].copy_from_slice(prefix.as_bytes());
+        let checksum: u64 = header.iter().map(|b| u64::from(*b)).sum();
+        let text = format!("{:06o}\0 ", checksum);
+        header[148..156].copy_from_slice(text.as_bytes());
+
+        encoder.write_all(&header)?;
+        encoder.write_all(data)?;
+        let padding = (TAR_BLOCK - data.len() % TAR_BLOCK) % TAR_BLOCK;
+        encoder.write_all(&vec![0u8; padding])?;
+    }
+    encoder.write_all(&[0u8; TAR_BLOCK * 2])?;
+    Ok(encoder.finish()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::{DeflateEncoder, GzEncoder};
+    use flate2::Compression;
+    use std::io::{Cursor, Write};
+
+    fn deflate(data: &[u8]) -> Vec<u8> {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    // (name, method, stored bytes, uncompressed size); CRCs are left zero since reads skip them.
+    fn build_zip(entries: &[(&str, u16, Vec<u8>, usize)]) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut central = Vec::new();
+        for (name, method, data, size) in entries {
+            let offset = out.len() as u32;
+            let fields = |buf: &mut Vec<u8>| {
+                buf.extend_from_slice(&method.to_le_bytes());
+                buf.extend_from_slice(&[0u8; 8]);
+                buf.extend_from_slice(&(data.len() as u32).to_le_bytes());
+                buf.extend_from_slice(&(*size as u32).to_le_bytes());
+                buf.extend_from_slice(&(name.len() as u16).to_le_bytes());
+                buf.extend_from_slice(&0u16.to_le_bytes());
+            };
+            out.extend_from_slice(LOCAL_SIGNATURE);
+            out.extend_from_slice(&[20, 0, 0, 0]);
+            fields(&mut out);
+            out.extend_from_slice(name.as_bytes());
+            out.extend_from_slice(data);
+
+            central.extend_from_slice(CENTRAL_SIGNATURE);
+            central.extend_from_slice(&[20, 0, 20, 0, 0, 0]);
+            fields(&mut central);
+            central.extend_from_slice(&[0u8; 10]);
+            central.extend_from_slice(&offset.to_le_bytes());
+            central.extend_from_slice(name.as_bytes());
+        }
+        let dir_offset = out.len() as u32;
+        out.extend_from_slice(&central);
+        out.extend_from_slice(EOCD_SIGNATURE);
+        out.extend_from_slice(&[0u8; 4]);
+        out.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(central.len() as u32).to_le_bytes());
+        out.extend_from_slice(&dir_offset.to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes());
+        out
+    }
+
+    fn read_all(mut reader: Box<dyn Read + '_>) -> String {
+        let mut out = String::new();
+        reader.read_to_string(&mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn zip_members_list_and_read_stored_and_deflated_entries() {
+        let csv = "id,name\n1,a\n2,b\n".repeat(50);
+        let archive = build_zip(&[
+            ("reports/", 0, Vec::new(), 0),
+            ("reports/orders.csv", 8, deflate(csv.as_bytes()), csv.len()),
+            ("README", 0, b"hello".to_vec(), 5),
+        ]);
+        let mut cursor = Cursor::new(archive.clone());
+        let members = zip_members(&mut cursor).unwrap();
+        let names: Vec<&str> = members.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, ["reports/orders.csv", "README"]);
+        assert_eq!(members[0].file_name(), "orders.csv");
+        assert_eq!(members[0].size, csv.len() as u64);
+
+        assert_eq!(
+            read_all(open_member(Cursor::new(archive.clone()), &members[0]).unwrap()),
+            csv
+        );
+        assert_eq!(
+            read_all(open_member(Cursor::new(archive), &members[1]).unwrap()),
+            "hello"
+        );
+
+        let err = zip_members(&mut Cursor::new(b"not a zip".to_vec())).unwrap_err();
+        assert_eq!(
+            err.message,
+            "zip archive: end of central directory not found"
+        );
+        let odd = build_zip(&[("x.bin", 12, b"BZh".to_vec(), 3)]);
+        let member = zip_members(&mut Cursor::new(odd.clone()))
+            .unwrap()
+            .remove(0);
+        assert_eq!(
+            open_member(Cursor::new(odd), &member)
+                .err()
+                .unwrap()
+                .message,
+            "zip archive: x.bin uses unsupported compression method 12"
+        );
+    }
+
+    #[test]
+    fn auto_decompress_follows_the_gzip_magic() {
+        let mut stream = gzip(b"{\"id\":1}\n");
+        stream.extend(gzip(b"{\"id\":2}\n"));
+        assert_eq!(
+            read_all(decode_reader(Cursor::new(stream.clone()), Decompress::Auto).unwrap()),
+            "{\"id\":1}\n{\"id\":2}\n"
+        );
+        assert_eq!(
+            read_all(decode_reader(Cursor::new(stream), Decompress::Gzip).unwrap()),
+            "{\"id\":1}\n{\"id\":2}\n"
+        );
+        assert_eq!(
+            read_all(decode_reader(Cursor::new(b"plain\n".to_vec()), Decompress::Auto).unwrap()),
+            "plain\n"
+        );
+    }
+
+    #[test]
+    fn decompress_modes_parse_strictly() {
+        assert_eq!(Decompress::parse(None).unwrap(), Decompress::None);
+        assert_eq!(
+            Decompress::parse(Some(&Value::from(" GZIP "))).unwrap(),
+            Decompress::Gzip
+        );
+        assert_eq!(
+            Decompress::parse(Some(&Value::from("auto"))).unwrap(),
+            Decompress::Auto
+        );
+        assert_eq!(
+            Decompress::parse(Some(&Value::from("bzip2")))
+                .unwrap_err()
+                .message,
+            "decompress must be one of: gzip, auto, none (got bzip2)"
+        );
+        assert!(Decompress::parse(Some(&Value::Bool(true))).is_err());
+    }
+
+    #[test]
+    fn tar_gz_writes_ustar_entries_with_long_paths() {
+        let long = format!("bundle/{}/result.json", "d".repeat(120));
+        let entries = vec![
+            ("bundle/manifest.json".to_string(), b"{}".to_vec()),
+            (long.clone(), vec![b'x'; 700]),
+        ];
+        let mut tar = Vec::new();
+        decode_reader(
+            Cursor::new(tar_gz(&entries, 1_700_000_000).unwrap()),
+            Decompress::Gzip,
+        )
+        .unwrap()
+        .read_to_end(&mut tar)
+        .unwrap();
+        assert_eq!(tar.len(), TAR_BLOCK * 2 + TAR_BLOCK * 3 + TAR_BLOCK * 2);
+        assert_eq!(&tar[..20], b"bundle/manifest.json");
+        assert_eq!(&tar[257..262], b"ustar");
+        let second = &tar[TAR_BLOCK * 2..];
+        assert_eq!(&second[..11], b"result.json");
+        assert!(second[345..].starts_with(format!("bundle/{}", "d".repeat(120)).as_bytes()));
+        assert_eq!(&second[124..135], b"00000001274");
+        let checksum: u64 = second[..TAR_BLOCK]
+            .iter()
+            .enumerate()
+            .map(|(at, b)| {
+                if (148..156).contains(&at) {
+                    32
+                } else {
+                    u64::from(*b)
+                }
+            })
+            .sum();
+        assert_eq!(&second[148..154], format!("{:06o}", checksum).as_bytes());
+        assert!(tar_name(&"e".repeat(300)).is_err());
+    }
+}
@@ -1,8 +1,11 @@
 use crate::errors::ToolError;
+use crate::utils::feature_flags::is_artifact_dedup_enabled;
 use crate::utils::fs_atomic::{
     atomic_write_binary_file, atomic_write_text_file, ensure_dir_for_file, temp_sibling_path,
 };
 use crate::utils::paths::resolve_context_repo_root;
+use crate::utils::safe_name::{ensure_safe_filename, join_within};
+use crate::utils::user_paths::expand_home_path;
 use rand::{distributions::Alphanumeric, Rng};
 use std::path::{Path, PathBuf};
 use tokio::fs::{self, File};
@@ -10,6 +13,7 @@ use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
 const DEFAULT_CONTEXT_REPO_ROOT: &str = "/home/amir/Документы/projects/context";
 const DEFAULT_FILE_MODE: u32 = 0o600;
+pub const BLOBS_DIR: &str = "blobs";
 
 #[derive(Debug, Clone)]
 pub struct ArtifactRef {
@@ -28,6 +32,7 @@ pub struct ArtifactInfo {
 
 #[derive(Debug)]
 pub struct ArtifactWriter {
+    pub context_root: PathBuf,
     pub uri: String,
     pub rel: String,
     pub path: PathBuf,
@@ -79,17 +84,7 @@ fn normalize_segment(value: &str, label: &str) -> Result<String, ToolError> {
 }
 
 fn normalize_filename(value: &str) -> Result<String, ToolError> {
-    let trimmed = normalize_segment(value, "filename")?;
-    let base = Path::new(&trimmed)
-        .file_name()
-        .and_then(|s| s.to_str())
-        .unwrap_or("");
-    if base != trimmed {
-        return Err(ToolError::invalid_params(
-            "filename must be a basename only",
-        ));
-    }
-    Ok(trimmed)
+    ensure_safe_filename(value, "filename")
 }
 
 pub fn build_tool_call_context_ref(
@@ -120,19 +115,94 @@ pub fn build_tool_call_file_ref(
     })
 }
 
+pub fn build_run_file_ref(
+    trace_id: Option<&str>,
+    filename: &str,
+) -> Result<ArtifactRef, ToolError> {
+    let run_id = normalize_segment(trace_id.unwrap_or("run"), "trace_id")?;
+    let safe_name = normalize_filename(filename)?;
+    let rel = format!("runs/{}/{}", run_id, safe_name);
+    Ok(ArtifactRef {
+        uri: format!("artifact://{}", rel),
+        rel,
+    })
+}
+
 pub fn resolve_artifact_path(context_root: &Path, rel: &str) -> Result<PathBuf, ToolError> {
     if rel.trim().is_empty() {
         return Err(ToolError::invalid_params(
             "artifact rel must be a non-empty string",
         ));
     }
-    let base = context_root.join("artifacts");
-    let resolved = base.join(rel);
-    if resolved != base && !resolved.starts_with(&base) {
+    join_within(&context_root.join("artifacts"), rel.trim()).ok_or_else(|| {
+        ToolError::denied("Artifact path escapes context root")
+            .with_hint("Use a rel path within the artifacts root.")
+    })
+}
+
+// The rel of an `artifact://<rel>` path argument (the uri form refs carry); None for
+// filesystem paths.
+pub fn artifact_uri_rel(raw: &str) -> Option<&str> {
+    raw.trim().strip_prefix("artifact://")
+}
+
+fn artifact_context_root(label: &str) -> Result<PathBuf, ToolError> {
+    resolve_context_root().ok_or_else(|| {
+        ToolError::denied(format!("{} requires context repo root", label)).with_hint(
+            "Set INFRA_CONTEXT_REPO_ROOT to the repo root that owns artifacts.".to_string(),
+        )
+    })
+}
+
+// An existing artifact named by `rel`, as its canonical path. Symlinks that lead out of the
+// artifacts dir are refused like `..` segments.
+pub fn resolve_artifact_input(rel: &str, label: &str) -> Result<PathBuf, ToolError> {
+    let rel = rel.trim();
+    if rel.is_empty() {
+        return Err(ToolError::invalid_params(format!(
+            "{} must include a path",
+            label
+        )));
+    }
+    let context_root = artifact_context_root(label)?;
+    let path = resolve_artifact_path(&context_root, rel)?;
+    let real = path
+        .canonicalize()
+        .map_err(|_| ToolError::not_found(format!("{} does not exist: {}", label, rel)))?;
+    let real_root = context_root
+        .join("artifacts")
+        .canonicalize()
+        .map_err(|_| ToolError::not_found(format!("{} does not exist: {}", label, rel)))?;
+    if !real.starts_with(&real_root) {
         return Err(ToolError::denied("Artifact path escapes context root")
             .with_hint("Use a rel path within the artifacts root."));
     }
-    Ok(resolved)
+    Ok(real)
+}
+
+// A local path argument that is read from: `artifact://<rel>` resolves to the artifact (which
+// must exist), anything else is a filesystem path with `~` expanded.
+pub fn resolve_input_path(raw: &str, label: &str) -> Result<PathBuf, ToolError> {
+    match artifact_uri_rel(raw) {
+        Some(rel) => resolve_artifact_input(rel, label),
+        None => Ok(expand_home_path(raw.trim())),
+    }
+}
+
+// A local path argument that is written to: `artifact://<rel>` names a file under the
+// artifacts dir that does not have to exist yet.
+pub fn resolve_output_path(raw: &str, label: &str) -> Result<PathBuf, ToolError> {
+    let Some(rel) = artifact_uri_rel(raw) else {
+        return Ok(expand_home_path(raw.trim()));
+    };
+    if rel.trim().is_empty() {
+        return Err(ToolError::invalid_params(format!(
+            "{} must include a path",
+            label
+        )));
+    }
+    let context_root = artifact_context_root(label)?;
+    resolve_artifact_path(&context_root, rel)
 }
 
 pub fn write_text_artifact(
@@ -143,6 +213,7 @@ pub fn write_text_artifact(
     let path = resolve_artifact_path(context_root, &reference.rel)?;
     atomic_write_text_file(&path, content, DEFAULT_FILE_MODE)
         .map_err(|err| ToolError::internal(format!("Failed to write artifact: {}", err)))?;
+    dedup_artifact(context_root, &path);
     Ok(ArtifactInfo {
         uri: reference.uri.clone(),
         rel: reference.rel.clone(),
@@ -160,6 +231,7 @@ pub fn write_binary_artifact(
     let path = resolve_artifact_path(context_root, &reference.rel)?;
     atomic_write_binary_file(&path, content, DEFAULT_FILE_MODE)
         .map_err(|err| ToolError::internal(format!("Failed to write artifact: {}", err)))?;
+    dedup_artifact(context_root, &path);
     Ok(ArtifactInfo {
         uri: reference.uri.clone(),
         rel: reference.rel.clone(),
@@ -181,6 +253,7 @@ pub async fn create_artifact_write_stream(
         .await
         .map_err(|err| ToolError::internal(format!("Failed to create artifact: {}", err)))?;
     Ok(ArtifactWriter {
+        context_root: context_root.to_path_buf(),
         uri: reference.uri.clone(),
         rel: reference.rel.clone(),
         path,
@@ -208,6 +281,7 @@ impl ArtifactWriter {
         fs::rename(&self.tmp_path, &self.path)
             .await
             .map_err(|err| ToolError::internal(format!("Failed to finalize artifact: {}", err)))?;
+        dedup_artifact_async(&self.context_root, &self.path).await;
         Ok(ArtifactInfo {
             uri: self.uri,
             rel: self.rel,
@@ -276,6 +350,7 @@ pub async fn copy_file_artifact(
     fs::rename(&tmp_path, &path)
         .await
         .map_err(|err| ToolError::internal(format!("Failed to finalize artifact: {}", err)))?;
+    dedup_artifact_async(context_root, &path).await;
 
     Ok(ArtifactInfo {
         uri: reference.uri.clone(),
@@ -286,6 +361,102 @@ pub async fn copy_file_artifact(
     })
 }
 
+pub fn resolve_blob_path(context_root: &Path, hash: &str) -> PathBuf {
+    context_root.join("artifacts").join(BLOBS_DIR).join(hash)
+}
+
+// Other artifact paths sharing the blob's inode; the blob itself is the remaining link.
+#[cfg(unix)]
+pub fn blob_refs(metadata: &std::fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    metadata.nlink().saturating_sub(1)
+}
+
+#[cfg(not(unix))]
+pub fn blob_refs(_metadata: &std::fs::Metadata) -> u64 {
+    1
+}
+
+#[cfg(unix)]
+fn same_inode(a: &std::fs::Metadata, b: &std::fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    a.dev() == b.dev() && a.ino() == b.ino()
+}
+
+#[cfg(not(unix))]
+fn same_inode(_a: &std::fs::Metadata, _b: &std::fs::Metadata) -> bool {
+    false
+}
+
+pub fn file_sha256(path: &Path) -> std::io::Result<(String, u64)> {
+    use sha2::Digest;
+    use std::io::Read;
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = sha2::Sha256::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut total = 0u64;
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        total += n as u64;
+    }
+    Ok((hex::encode(hasher.finalize()), total))
+}
+
+fn link_to_blob(context_root: &Path, path: &Path) -> std::io::Result<Option<String>> {
+    let (hash, bytes) = file_sha256(path)?;
+    let blob = resolve_blob_path(context_root, &hash);
+    match std::fs::metadata(&blob) {
+        Ok(existing) => {
+            // Equal hashes with different lengths mean a corrupt blob or a collision: keep the
+            // plain file rather than point at the wrong content.
+            if existing.len() != bytes {
+                return Ok(None);
+            }
+            if same_inode(&existing, &std::fs::metadata(path)?) {
+                return Ok(Some(hash));
+            }
+            let tmp = temp_sibling_path(path);
+            std::fs::hard_link(&blob, &tmp)?;
+            if let Err(err) = std::fs::rename(&tmp, path) {
+                let _ = std::fs::remove_file(&tmp);
+                return Err(err);
+            }
+            Ok(Some(hash))
+        }
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            ensure_dir_for_file(&blob)?;
+            std::fs::hard_link(path, &blob)?;
+            Ok(Some(hash))
+        }
+        Err(err) => Err(err),
+    }
+}
+
+// With INFRA_ARTIFACT_DEDUP=1 a finished artifact is backed by artifacts/blobs/<sha256>: the
+// first copy becomes the blob and identical later ones are hardlinked to it, so refs and readers
+// are unchanged. Any failure (no hardlink support, a race) leaves the plain file in place.
+pub fn dedup_artifact(context_root: &Path, path: &Path) -> Option<String> {
+    if !is_artifact_dedup_enabled() {
+        return None;
+    }
+    link_to_blob(context_root, path).ok().flatten()
+}
+
+async fn dedup_artifact_async(context_root: &Path, path: &Path) -> Option<String> {
+    if !is_artifact_dedup_enabled() {
+        return None;
+    }
+    let (context_root, path) = (context_root.to_path_buf(), path.to_path_buf());
+    tokio::task::spawn_blocking(move || link_to_blob(&context_root, &path).ok().flatten())
+        .await
+        .ok()
+        .flatten()
+}
+
 fn random_id() -> String {
     rand::thread_rng()
         .sample_iter(&Alphanumeric)
@@ -0,0 +1,224 @@
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+pub const CHAIN_FIELDS: &[&str] = &["seq", "prev_hash", "entry_hash"];
+
+// Canonical form (stable across versions; changing it invalidates every stored chain):
+// - the entry object minus its `entry_hash` field (`seq` and `prev_hash` are included);
+// - object keys sorted by their UTF-8 bytes, at every nesting level;
+// - no whitespace between tokens;
+// - strings escaped as serde_json writes them (`"`, `\`, and control chars; non-ASCII stays raw);
+// - integers in plain decimal, floats in serde_json's shortest round-trip form;
+// - `entry_hash` = lowercase hex sha256 of the canonical UTF-8 bytes.
+pub fn canonicalize(entry: &Value) -> String {
+    let mut out = String::new();
+    match entry {
+        Value::Object(map) => {
+            let mut trimmed = map.clone();
+            trimmed.remove("entry_hash");
+            write_canonical(&Value::Object(trimmed), &mut out);
+        }
+        other => write_canonical(other, &mut out),
+    }
+    out
+}
+
+fn write_canonical(value: &Value, out: &mut String) {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort_by(|a, b| a.as_bytes().cmp(b.as_bytes()));
+            out.push('{');
+            for (idx, key) in keys.iter().enumerate() {
+                if idx > 0 {
+                    out.push(',');
+                }
+                out.push_str(&Value::String((*key).clone()).to_string());
+                out.push(':');
+                write_canonical(&map[key.as_str()], out);
+            }
+            out.push('}');
+        }
+        Value::Array(items) => {
+            out.push('[');
+            for (idx, item) in items.iter().enumerate() {
+                if idx > 0 {
+                    out.push(',');
+                }
+                write_canonical(item, out);
+            }
+            out.push(']');
+        }
+        scalar => out.push_str(&scalar.to_string()),
+    }
+}
+
+pub fn entry_hash(entry: &Value) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(canonicalize(entry).as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+// Stamps `seq` and `prev_hash`, then `entry_hash` over the result. Existing chain fields are
+// replaced so callers cannot forge links.
+pub fn seal(entry: &Value, seq: u64, prev_hash: &str) -> Value {
+    let mut map = match entry {
+        Value::Object(map) => map.clone(),
+        other => {
+            let mut map = serde_json::Map::new();
+            map.insert("value".to_string(), other.clone());
+            map
+        }
+    };
+    for field in CHAIN_FIELDS {
+        map.remove(*field);
+    }
+    map.insert("seq".to_string(), Value::from(seq));
+    map.insert(
+        "prev_hash".to_string(),
+        Value::String(prev_hash.to_string()),
+    );
+    let mut sealed = Value::Object(map);
+    let hash = entry_hash(&sealed);
+    sealed["entry_hash"] = Value::String(hash);
+    sealed
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChainHead {
+    pub seq: u64,
+    pub hash: String,
+}
+
+impl Default for ChainHead {
+    fn default() -> Self {
+        Self {
+            seq: 0,
+            hash: GENESIS_HASH.to_string(),
+        }
+    }
+}
+
+impl ChainHead {
+    pub fn from_entry(entry: &Value) -> Option<Self> {
+        Some(Self {
+            seq: entry.get("seq")?.as_u64()?,
+            hash: entry.get("entry_hash")?.as_str()?.to_string(),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LinkError {
+    Unchained,
+    HashMismatch { expected: String, found: String },
+    PrevMismatch { expected: String, found: String },
+    SeqGap { expected: u64, found: u64 },
+}
+
+impl LinkError {
+    pub fn reason(&self) -> &'static str {
+        match self {
+            LinkError::Unchained => "missing_chain_fields",
+            LinkError::HashMismatch { .. } => "entry_hash_mismatch",
+            LinkError::PrevMismatch { .. } => "prev_hash_mismatch",
+            LinkError::SeqGap { .. } => "seq_gap",
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        match self {
+            LinkError::Unchained => serde_json::json!({"reason": self.reason()}),
+            LinkError::HashMismatch { expected, found }
+            | LinkError::PrevMismatch { expected, found } => serde_json::json!({
+                "reason": self.reason(),
+                "expected": expected,
+                "found": found,
+            }),
+            LinkError::SeqGap { expected, found } => serde_json::json!({
+                "reason": self.reason(),
+                "expected": expected,
+                "found": found,
+            }),
+        }
+    }
+}
+
+// Checks one entry against the head it should extend and returns the new head.
+pub fn verify_link(head: &ChainHead, entry: &Value) -> Result<ChainHead, LinkError> {
+    let (Some(seq), Some(prev), Some(found)) = (
+        entry.get("seq").and_then(|v| v.as_u64()),
+        entry.get("prev_hash").and_then(|v| v.as_str()),
+        entry.get("entry_hash").and_then(|v| v.as_str()),
+    ) else {
+        return Err(LinkError::Unchained);
+    };
+    let expected = entry_hash(entry);
+    if expected != found {
+        return Err(LinkError::HashMismatch {
+            expected,
+            found: found.to_string(),
+        });
+    }
+    if seq != head.seq + 1 {
+        return Err(LinkError::SeqGap {
+            expected: head.seq + 1,
+            found: seq,
+        });
+    }
+    if prev != head.hash {
+        return Err(LinkError::PrevMismatch {
+            expected: head.hash.clone(),
+            found: prev.to_string(),
+        });
+    }
+    Ok(ChainHead {
+        seq,
+        hash: found.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn canonical_form_sorts_keys_and_drops_entry_hash() {
+        let entry = serde_json::json!({
+            "tool": "ssh",
+            "b": {"z": 1, "a": [true, null, 1.5, "é\n"]},
+            "entry_hash": "ignored",
+            "A": -3,
+        });
+        assert_eq!(
+            canonicalize(&entry),
+            "{\"A\":-3,\"b\":{\"a\":[true,null,1.5,\"é\\n\"],\"z\":1},\"tool\":\"ssh\"}"
+        );
+    }
+
+    #[test]
+    fn sealed_entries_link_and_detect_edits() {
+        let head = ChainHead::default();
+        let first = seal(
+            &serde_json::json!({"tool": "sql", "seq": 99}),
+            1,
+            &head.hash,
+        );
+        let head = verify_link(&head, &first).expect("first link");
+        assert_eq!(head.seq, 1);
+        let second = seal(&serde_json::json!({"tool": "api"}), 2, &head.hash);
+        let head = verify_link(&head, &second).expect("second link");
+        assert_eq!(head.hash, second["entry_hash"].as_str().unwrap());
+
+        let mut edited = second.clone();
+        edited["tool"] = Value::from("ssh");
+        let err = verify_link(&ChainHead::from_entry(&first).unwrap(), &edited).unwrap_err();
+        assert_eq!(err.reason(), "entry_hash_mismatch");
+
+        let replayed = verify_link(&head, &second).unwrap_err();
+        assert_eq!(replayed.reason(), "seq_gap");
+        let unchained = verify_link(&head, &serde_json::json!({"tool": "api"})).unwrap_err();
+        assert_eq!(unchained, LinkError::Unchained);
+    }
+}
@@ -0,0 +1,590 @@
+use crate::errors::ToolError;
+use crate::utils::fs_atomic::atomic_write_text_file;
+use crate::utils::shell::{restart_service_command, shell_quote};
+use serde_json::{Map, Value};
+use std::path::Path;
+
+pub const DISCOVERY_KINDS: &[&str] = &["ssh", "postgres", "api"];
+pub const DEFAULT_UNIT_FILTER: &str = "*.service";
+const MAX_API_OPERATIONS: usize = 200;
+const HTTP_METHODS: &[&str] = &["get", "put", "post", "delete", "patch", "head", "options"];
+
+// Tables (not partitions) the connected role may INSERT into, with their primary key columns.
+pub const WRITABLE_TABLES_SQL: &str = "SELECT n.nspname AS schema, c.relname AS table, \
+     (SELECT string_agg(a.attname::text, ',' ORDER BY array_position(k.conkey, a.attnum)) \
+        FROM pg_constraint k JOIN pg_attribute a ON a.attrelid = k.conrelid AND a.attnum = ANY (k.conkey) \
+       WHERE k.conrelid = c.oid AND k.contype = 'p') AS primary_key \
+     FROM pg_class c JOIN pg_namespace n ON n.oid = c.relnamespace \
+     WHERE c.relkind IN ('r', 'p') AND NOT c.relispartition \
+       AND n.nspname NOT IN ('pg_catalog', 'information_schema') AND n.nspname NOT LIKE 'pg\\_toast%' \
+       AND has_schema_privilege(n.oid, 'USAGE') AND has_table_privilege(c.oid, 'INSERT') \
+     ORDER BY 1, 2 LIMIT 500";
+
+// Lists units without touching them; `--plain` drops the status bullet column.
+pub fn list_units_command(filter: &str) -> String {
+    format!(
+        "systemctl list-units --type=service --all --no-legend --plain --no-pager -- {}",
+        shell_quote(filter)
+    )
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SystemdUnit {
+    pub name: String,
+    pub load: String,
+    pub active: String,
+    pub sub: String,
+    pub description: String,
+}
+
+// `list-units` rows: `UNIT LOAD ACTIVE SUB DESCRIPTION...`. Older systemd still prints a `●`
+// before failed units even with --plain.
+pub fn parse_units(stdout: &str) -> Vec<SystemdUnit> {
+    stdout
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim().trim_start_matches(['●', '*']).trim_start();
+            let mut parts = line.split_whitespace();
+            let name = parts.next()?;
+            if !name.contains('.') {
+                return None;
+            }
+            let load = parts.next()?;
+            let active = parts.next()?;
+            let sub = parts.next()?;
+            Some(SystemdUnit {
+                name: name.to_string(),
+                load: load.to_string(),
+                active: active.to_string(),
+                sub: sub.to_string(),
+                description: parts.collect::<Vec<_>>().join(" "),
+            })
+        })
+        .collect()
+}
+
+// A loaded, running unit is certainly restartable; a loaded one that is stopped or failed
+// probably is; anything not loaded (masked, not-found) is only a guess.
+fn unit_confidence(unit: &SystemdUnit) -> &'static str {
+    match (unit.load.as_str(), unit.active.as_str()) {
+        ("loaded", "active") => "high",
+        ("loaded", _) => "medium",
+        _ => "low",
+    }
+}
+
+// `<project>.<target>.<rest>` with every piece reduced to `[A-Za-z0-9_-]`.
+pub fn proposal_name(project: &str, target: &str, rest: &[&str]) -> String {
+    let clean = |part: &str| {
+        let cleaned: String = part
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        cleaned.trim_matches('_').to_string()
+    };
+    [project, target]
+        .into_iter()
+        .chain(rest.iter().copied())
+        .map(clean)
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+fn effects(kind: &str, irreversible: bool) -> Value {
+    if kind == "read" {
+        serde_json::json!({"kind": "read", "requires_apply": false})
+    } else {
+        serde_json::json!({"kind": "write", "requires_apply": true, "irreversible": irreversible})
+    }
+}
+
+struct Draft<'a> {
+    name: String,
+    kind: &'a str,
+    target: &'a str,
+    confidence: &'a str,
+    evidence: Value,
+    description: String,
+    tags: Vec<&'a str>,
+    required: Vec<String>,
+    effect: Value,
+    steps: Value,
+}
+
+impl Draft<'_> {
+    // The capability and the runbook it points at share the proposal name.
+    fn into_proposal(self) -> Value {
+        let mut tags: Vec<Value> = vec![Value::from("discovered")];
+        tags.extend(self.tags.iter().map(|tag| Value::from(*tag)));
+        if self.effect["kind"] == "write" {
+            tags.push(Value::from("write"));
+        } else {
+            tags.push(Value::from("read"));
+        }
+        serde_json::json!({
+            "name": self.name,
+            "kind": self.kind,
+            "target": self.target,
+            "confidence": self.confidence,
+            "evidence": self.evidence,
+            "capability": {
+                "intent": self.name,
+                "description": self.description,
+                "runbook": self.name,
+                "tags": tags,
+                "inputs": {"required": self.required, "defaults": {}, "map": {}},
+                "effects": self.effect,
+            },
+            "runbook": {
+                "description": self.description,
+                "tags": tags,
+                "inputs": self.required,
+                "steps": self.steps,
+            },
+        })
+    }
+}
+
+pub fn unit_proposals(
+    project: &str,
+    target: &str,
+    profile: &str,
+    units: &[SystemdUnit],
+) -> Vec<Value> {
+    let mut out = Vec::new();
+    for unit in units {
+        let short = unit.name.strip_suffix(".service").unwrap_or(&unit.name);
+        let evidence = serde_json::json!({
+            "ssh_profile": profile,
+            "unit": unit.name,
+            "load": unit.load,
+            "active": unit.active,
+            "sub": unit.sub,
+            "description": unit.description,
+        });
+        let confidence = unit_confidence(unit);
+        out.push(
+            Draft {
+                name: proposal_name(project, target, &[short, "restart"]),
+                kind: "ssh.systemd.restart",
+                target,
+                confidence,
+                evidence: evidence.clone(),
+                description: format!("Restart {} on {} ({}).", unit.name, target, profile),
+                tags: vec!["ssh", "systemd"],
+                required: Vec::new(),
+                effect: effects("write", false),
+                steps: serde_json::json!([{
+                    "id": "restart",
+                    "tool": "ssh",
+                    "args": {
+                        "action": "exec",
+                        "profile_name": profile,
+                        "command": restart_service_command(&unit.name),
+                    },
+                }]),
+            }
+            .into_proposal(),
+        );
+        out.push(
+            Draft {
+                name: proposal_name(project, target, &[short, "status"]),
+                kind: "ssh.systemd.status",
+                target,
+                confidence,
+                evidence,
+                description: format!("Show the state of {} on {} ({}).", unit.name, target, profile),
+                tags: vec!["ssh", "systemd"],
+                required: Vec::new(),
+                effect: effects("read", false),
+                steps: serde_json::json!([{
+                    "id": "status",
+                    "tool": "ssh",
+                    "args": {
+                        "action": "exec",
+                        "profile_name": profile,
+                        "command": format!(
+                            "systemctl show --no-pager -p LoadState,ActiveState,SubState,MainPID -- {}",
+                            shell_quote(&unit.name)
+                        ),
+                    },
+                }]),
+            }
+            .into_proposal(),
+        );
+    }
+    out
+}
+
+// One load capability per writable table: an upsert on the primary key when there is one,
+// plain inserts (less certain to be what a reload wants) otherwise.
+pub fn table_proposals(project: &str, target: &str, profile: &str, rows: &[Value]) -> Vec<Value> {
+    let mut out = Vec::new();
+    for row in rows {
+        let (Some(schema), Some(table)) = (
+            row.get("schema").and_then(|v| v.as_str()),
+            row.get("table").and_then(|v| v.as_str()),
+        ) else {
+            continue;
+        };
+        let primary_key: Vec<String> = row
+            .get("primary_key")
+            .and_then(|v| v.as_str())
+            .map(|keys| {
+                keys.split(',')
+                    .filter(|k| !k.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let mut args = serde_json::json!({
+            "action": "insert_bulk",
+            "profile_name": profile,
+            "schema": schema,
+            "table": table,
+            "rows": "{{ input.rows }}",
+        });
+        if !primary_key.is_empty() {
+            args["on_conflict"] = Value::from("upsert");
+            args["conflict_columns"] = Value::from(primary_key.clone());
+        }
+        out.push(
+            Draft {
+                name: proposal_name(project, target, &[schema, table, "load"]),
+                kind: "postgres.load",
+                target,
+                confidence: if primary_key.is_empty() {
+                    "medium"
+                } else {
+                    "high"
+                },
+                evidence: serde_json::json!({
+                    "postgres_profile": profile,
+                    "schema": schema,
+                    "table": table,
+                    "privilege": "INSERT",
+                    "primary_key": primary_key,
+                }),
+                description: if primary_key.is_empty() {
+                    format!("Insert rows into {}.{} on {}.", schema, table, target)
+                } else {
+                    format!(
+                        "Upsert rows into {}.{} on {} by ({}).",
+                        schema,
+                        table,
+                        target,
+                        primary_key.join(", ")
+                    )
+                },
+                tags: vec!["db", "postgres"],
+                required: vec!["rows".to_string()],
+                effect: effects("write", false),
+                steps: serde_json::json!([{"id": "load", "tool": "sql", "args": args}]),
+            }
+            .into_proposal(),
+        );
+    }
+    out
+}
+
+// `{param}` path segments become required inputs.
+fn path_params(path: &str) -> Vec<String> {
+    let mut params = Vec::new();
+    let mut rest = path;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        let name = rest[start + 1..start + len].trim();
+        if !name.is_empty() && !params.iter().any(|p| p == name) {
+            params.push(name.to_string());
+        }
+        rest = &rest[start + len + 1..];
+    }
+    params
+}
+
+// One capability per OpenAPI operation (capped); true in the second slot when some were left out.
+pub fn openapi_proposals(
+    project: &str,
+    target: &str,
+    profile: Option<&str>,
+    base_url: &str,
+    openapi_url: &str,
+    doc: &Value,
+) -> Result<(Vec<Value>, bool), String> {
+    let paths = doc
+        .get("paths")
+        .and_then(|v| v.as_object())
+        .ok_or_else(|| {
+            "document has no paths object; is it an OpenAPI/Swagger spec?".to_string()
+        })?;
+    let spec_version = doc
+        .get("openapi")
+        .or_else(|| doc.get("swagger"))
+        .cloned()
+        .unwrap_or(Value::Null);
+    let base = base_url.trim_end_matches('/');
+    let mut out = Vec::new();
+    let mut truncated = false;
+    for (path, item) in paths {
+        let Some(item) = item.as_object() else {
+            continue;
+        };
+        for method in HTTP_METHODS {
+            let Some(operation) = item.get(*method) else {
+                continue;
+            };
+            if out.len() == MAX_API_OPERATIONS {
+                truncated = true;
+                break;
+            }
+            let operation_id = operation.get("operationId").and_then(|v| v.as_str());
+            let slug = format!("{}_{}", method, path.replace(['{', '}'], ""));
+            let params = path_params(path);
+            let mut templated = path.clone();
+            for param in &params {
+                templated = templated.replace(
+                    &format!("{{{}}}", param),
+                    &format!("{{{{ input.{} }}}}", param),
+                );
+            }
+            let mut required = params.clone();
+            let mut args = serde_json::json!({
+                "action": "request",
+                "method": method.to_ascii_uppercase(),
+                "url": format!("{}{}", base, templated),
+            });
+            if let Some(profile) = profile {
+                args["profile_name"] = Value::from(profile);
+            }
+            if operation.get("requestBody").is_some() {
+                required.push("body".to_string());
+                args["body"] = Value::from("{{ input.body }}");
+            }
+            let read = matches!(*method, "get" | "head" | "options");
+            let summary = operation
+                .get("summary")
+                .and_then(|v| v.as_str())
+                .unwrap_or("");
+            out.push(
+                Draft {
+                    name: proposal_name(project, target, &["api", operation_id.unwrap_or(&slug)]),
+                    kind: "api.endpoint",
+                    target,
+                    confidence: if operation_id.is_some() {
+                        "high"
+                    } else {
+                        "medium"
+                    },
+                    evidence: serde_json::json!({
+                        "api_profile": profile,
+                        "base_url": base_url,
+                        "openapi_url": openapi_url,
+                        "openapi_version": spec_version,
+                        "method": method.to_ascii_uppercase(),
+                        "path": path,
+                        "operation_id": operation_id,
+                    }),
+                    description: if summary.is_empty() {
+                        format!("{} {} on {}.", method.to_ascii_uppercase(), path, target)
+                    } else {
+                        format!(
+                            "{} ({} {} on {}).",
+                            summary.trim_end_matches('.'),
+                            method.to_ascii_uppercase(),
+                            path,
+                            target
+                        )
+                    },
+                    tags: vec!["api"],
+                    required,
+                    effect: if read {
+                        effects("read", false)
+                    } else {
+                        effects("write", *method == "delete")
+                    },
+                    steps: serde_json::json!([{"id": "request", "tool": "api", "args": args}]),
+                }
+                .into_proposal(),
+            );
+        }
+    }
+    Ok((out, truncated))
+}
+
+// Without a spec all that is known is that the base URL is configured.
+pub fn api_check_proposal(
+    project: &str,
+    target: &str,
+    profile: Option<&str>,
+    base_url: &str,
+) -> Value {
+    let mut args = serde_json::json!({"action": "request", "method": "GET", "url": base_url});
+    if let Some(profile) = profile {
+        args["profile_name"] = Value::from(profile);
+    }
+    Draft {
+        name: proposal_name(project, target, &["api", "check"]),
+        kind: "api.check",
+        target,
+        confidence: "low",
+        evidence: serde_json::json!({
+            "api_profile": profile,
+            "base_url": base_url,
+            "openapi_url": Value::Null,
+        }),
+        description: format!("GET the API base URL of {}.", target),
+        tags: vec!["api"],
+        required: Vec::new(),
+        effect: effects("read", false),
+        steps: serde_json::json!([{"id": "request", "tool": "api", "args": args}]),
+    }
+    .into_proposal()
+}
+
+// Adds `records` under `key` of the JSON manifest at `path` (created when missing) with an
+// atomic rewrite. Names for which `exists` is true are not written and come back as skipped.
+pub fn add_manifest_entries(
+    path: &Path,
+    key: &str,
+    records: &Map<String, Value>,
+    exists: impl Fn(&str) -> bool,
+) -> Result<(Vec<String>, Vec<String>), ToolError> {
+    let mut doc = if path.exists() {
+        let raw = std::fs::read_to_string(path).map_err(|err| {
+            ToolError::internal(format!("Failed to read {}: {}", path.display(), err))
+        })?;
+        serde_json::from_str::<Value>(&raw).map_err(|err| {
+            ToolError::internal(format!("Failed to parse {}: {}", path.display(), err))
+        })?
+    } else {
+        serde_json::json!({"version": 1, key: {}})
+    };
+    let mut added = Vec::new();
+    let mut skipped = Vec::new();
+    for (name, record) in records {
+        if exists(name) {
+            skipped.push(name.clone());
+            continue;
+        }
+        match doc.get_mut(key) {
+            Some(Value::Object(map)) => {
+                map.insert(name.clone(), record.clone());
+            }
+            Some(Value::Array(list)) => {
+                let mut entry = record.clone();
+                entry["name"] = Value::String(name.clone());
+                list.push(entry);
+            }
+            _ => match doc.as_object_mut() {
+                Some(map) => {
+                    map.insert(name.clone(), record.clone());
+                }
+                None => {
+                    return Err(ToolError::internal(format!(
+                        "{} is not a {} manifest",
+                        path.display(),
+                        key
+                    )))
+                }
+            },
+        }
+        added.push(name.clone());
+    }
+    if added.is_empty() {
+        return Ok((added, skipped));
+    }
+    let content = serde_json::to_string_pretty(&doc).unwrap_or_default();
+    atomic_write_text_file(path, &format!("{}\n", content), 0o600).map_err(|err| {
+        ToolError::internal(format!("Failed to write {}: {}", path.display(), err))
+    })?;
+    Ok((added, skipped))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn units_become_restart_and_status_proposals() {
+        let units = parse_units(
+            "nginx.service loaded active running A high performance web server\n\
+             ● worker@1.service loaded failed failed Queue worker\n\
+             ghost.service not-found inactive dead ghost.service\n",
+        );
+        assert_eq!(units.len(), 3);
+        assert_eq!(units[1].name, "worker@1.service");
+        assert_eq!(units[0].description, "A high performance web server");
+        let proposals = unit_proposals("shop", "prod", "web-1", &units);
+        let names: Vec<&str> = proposals
+            .iter()
+            .map(|p| p["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names[0], "shop.prod.nginx.restart");
+        assert_eq!(names[3], "shop.prod.worker_1.status");
+        let confidence: Vec<&Value> = proposals
+            .iter()
+            .step_by(2)
+            .map(|p| &p["confidence"])
+            .collect();
+        assert_eq!(
+            confidence,
+            [&json!("high"), &json!("medium"), &json!("low")]
+        );
+        assert_eq!(
+            proposals[0]["capability"]["effects"]["requires_apply"],
+            true
+        );
+        assert_eq!(proposals[1]["capability"]["effects"]["kind"], "read");
+        assert_eq!(
+            proposals[0]["runbook"]["steps"][0]["args"]["command"],
+            "systemctl restart -- 'nginx.service' && systemctl is-active -- 'nginx.service'"
+        );
+    }
+
+    #[test]
+    fn openapi_operations_template_their_path_params() {
+        let doc = json!({
+            "openapi": "3.0.0",
+            "paths": {
+                "/users/{id}": {
+                    "get": {"operationId": "getUser"},
+                    "delete": {},
+                },
+            },
+        });
+        let (proposals, truncated) = openapi_proposals(
+            "shop",
+            "prod",
+            Some("shop-api"),
+            "http://api.internal/v1/",
+            "/openapi.json",
+            &doc,
+        )
+        .unwrap();
+        assert!(!truncated);
+        assert_eq!(proposals[0]["name"], "shop.prod.api.getUser");
+        assert_eq!(proposals[0]["confidence"], "high");
+        assert_eq!(
+            proposals[0]["runbook"]["steps"][0]["args"]["url"],
+            "http://api.internal/v1/users/{{ input.id }}"
+        );
+        assert_eq!(
+            proposals[0]["capability"]["inputs"]["required"],
+            json!(["id"])
+        );
+        assert_eq!(proposals[1]["name"], "shop.prod.api.delete__users_id");
+        assert_eq!(proposals[1]["confidence"], "medium");
+        assert_eq!(proposals[1]["capability"]["effects"]["irreversible"], true);
+        assert!(openapi_proposals("p", "t", None, "http://x", "u", &json!({})).is_err());
+    }
+}
@@ -0,0 +1,431 @@
+use crate::errors::{ToolError, ToolErrorKind};
+use crate::utils::http_tls::HttpTlsConfig;
+use crate::utils::ssrf::SsrfPolicy;
+use openssl::asn1::{Asn1Time, Asn1TimeRef};
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+use openssl::ssl::{SslConnector, SslMethod, SslVerifyMode};
+use openssl::x509::store::X509StoreBuilder;
+use openssl::x509::{X509NameRef, X509Ref, X509VerifyResult, X509};
+use serde_json::Value;
+use std::net::{IpAddr, SocketAddr, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+use url::{Host, Url};
+
+const DEFAULT_TLS_PORT: u16 = 443;
+
+// One endpoint to handshake with. `servername` is what goes into SNI and what the certificate
+// is checked against; it defaults to the host and is never sent for IP literals.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CertTarget {
+    pub target: String,
+    pub host: String,
+    pub port: u16,
+    pub servername: Option<String>,
+}
+
+impl CertTarget {
+    pub fn verify_name(&self) -> &str {
+        self.servername.as_deref().unwrap_or(&self.host)
+    }
+
+    fn endpoint(&self) -> String {
+        match self.host.parse::<IpAddr>() {
+            Ok(IpAddr::V6(_)) => format!("[{}]:{}", self.host, self.port),
+            _ => format!("{}:{}", self.host, self.port),
+        }
+    }
+}
+
+fn host_text(host: Host<&str>) -> String {
+    match host {
+        Host::Domain(name) => name.to_lowercase(),
+        Host::Ipv4(ip) => ip.to_string(),
+        Host::Ipv6(ip) => ip.to_string(),
+    }
+}
+
+fn invalid_target(raw: &str) -> ToolError {
+    ToolError::invalid_params(format!(
+        "cert_check target {} must be an https URL or host[:port]",
+        raw
+    ))
+    .with_hint("Examples: https://api.internal:8443/health, db.internal:5433, [::1]:443.")
+}
+
+// Accepts `https://host[:port]/...`, `host`, `host:port` and `[v6]:port`.
+pub fn parse_target_str(raw: &str) -> Result<CertTarget, ToolError> {
+    let trimmed = raw.trim();
+    if trimmed.is_empty() {
+        return Err(invalid_target(raw));
+    }
+    let url = if trimmed.contains("://") {
+        let url = Url::parse(trimmed).map_err(|_| invalid_target(raw))?;
+        if url.scheme() == "http" || url.scheme() == "ws" {
+            return Err(ToolError::invalid_params(format!(
+                "cert_check target {} does not use TLS",
+                raw
+            ))
+            .with_hint("Use the https:// URL of the endpoint."));
+        }
+        url
+    } else {
+        Url::parse(&format!("tls://{}", trimmed)).map_err(|_| invalid_target(raw))?
+    };
+    let host = url
+        .host()
+        .map(host_text)
+        .ok_or_else(|| invalid_target(raw))?;
+    let port = url.port().unwrap_or(DEFAULT_TLS_PORT);
+    Ok(CertTarget {
+        target: trimmed.to_string(),
+        host,
+        port,
+        servername: None,
+    })
+}
+
+// A string, or `{ target|url|host, port?, servername? }`.
+pub fn parse_target(value: &Value) -> Result<CertTarget, ToolError> {
+    match value {
+        Value::String(raw) => parse_target_str(raw),
+        Value::Object(map) => {
+            let text = |key: &str| map.get(key).and_then(|v| v.as_str()).map(str::trim);
+            let raw = text("target")
+                .or_else(|| text("url"))
+                .or_else(|| text("host"))
+                .ok_or_else(|| {
+                    ToolError::invalid_params("cert_check target objects need target, url or host")
+                })?;
+            let mut target = parse_target_str(raw)?;
+            match map.get("port") {
+                None | Some(Value::Null) => {}
+                Some(port) => {
+                    target.port = port
+                        .as_u64()
+                        .and_then(|p| u16::try_from(p).ok())
+                        .filter(|p| *p > 0)
+                        .ok_or_else(|| {
+                            ToolError::invalid_params("cert_check target port must be 1-65535")
+                        })?;
+                }
+            }
+            if let Some(name) = text("servername").filter(|s| !s.is_empty()) {
+                target.servername = Some(name.to_lowercase());
+            }
+            Ok(target)
+        }
+        _ => Err(ToolError::invalid_params(
+            "cert_check targets must be strings or { target, port, servername } objects",
+        )),
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CertCheckOptions {
+    pub insecure_ok: bool,
+    pub warn_days: i64,
+    pub timeout: Duration,
+    pub ssrf: Option<SsrfPolicy>,
+}
+
+fn name_text(name: &X509NameRef) -> String {
+    name.entries()
+        .map(|entry| {
+            let key = entry.object().nid().short_name().unwrap_or("?");
+            let value = entry
+                .data()
+                .as_utf8()
+                .map(|v| v.to_string())
+                .unwrap_or_default();
+            format!("{}={}", key, value)
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn unix_seconds(time: &Asn1TimeRef) -> Result<i64, ToolError> {
+    let epoch = Asn1Time::from_unix(0).map_err(|err| ToolError::internal(err.to_string()))?;
+    let diff = epoch
+        .diff(time)
+        .map_err(|err| ToolError::internal(err.to_string()))?;
+    Ok(i64::from(diff.days) * 86_400 + i64::from(diff.secs))
+}
+
+fn rfc3339(seconds: i64) -> String {
+    chrono::DateTime::from_timestamp(seconds, 0)
+        .map(|t| t.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+        .unwrap_or_default()
+}
+
+fn subject_alt_names(cert: &X509Ref) -> Vec<String> {
+    let Some(names) = cert.subject_alt_names() else {
+        return Vec::new();
+    };
+    names
+        .iter()
+        .filter_map(|name| {
+            if let Some(dns) = name.dnsname() {
+                return Some(dns.to_string());
+            }
+            match name.ipaddress()? {
+                [a, b, c, d] => Some(IpAddr::from([*a, *b, *c, *d]).to_string()),
+                bytes => <[u8; 16]>::try_from(bytes)
+                    .ok()
+                    .map(|octets| IpAddr::from(octets).to_string()),
+            }
+        })
+        .collect()
+}
+
+// Leaf certificate facts. `days_until_expiry` rounds down, so it is negative once expired.
+pub fn describe_certificate(cert: &X509Ref, now: i64, warn_days: i64) -> Result<Value, ToolError> {
+    let internal = |err: openssl::error::ErrorStack| ToolError::internal(err.to_string());
+    let not_before = unix_seconds(cert.not_before())?;
+    let not_after = unix_seconds(cert.not_after())?;
+    let days_until_expiry = (not_after - now).div_euclid(86_400);
+    let serial = cert
+        .serial_number()
+        .to_bn()
+        .and_then(|bn| bn.to_hex_str().map(|hex| hex.to_string()))
+        .map_err(internal)?;
+    let fingerprint = cert.digest(MessageDigest::sha256()).map_err(internal)?;
+    Ok(serde_json::json!({
+        "subject": name_text(cert.subject_name()),
+        "issuer": name_text(cert.issuer_name()),
+        "sans": subject_alt_names(cert),
+        "serial": serial.to_lowercase(),
+        "sha256_fingerprint": hex::encode(fingerprint),
+        "not_before": rfc3339(not_before),
+        "not_after": rfc3339(not_after),
+        "days_until_expiry": days_until_expiry,
+        "expired": not_after <= now,
+        "warning": days_until_expiry < warn_days,
+    }))
+}
+
+fn connect(target: &CertTarget, options: &CertCheckOptions) -> Result<TcpStream, ToolError> {
+    let addrs: Vec<SocketAddr> = (target.host.as_str(), target.port)
+        .to_socket_addrs()
+        .map_err(|err| {
+            ToolError::new(
+                ToolErrorKind::Retryable,
+                "CERT_CHECK_DNS_FAILED",
+                format!("{} did not resolve: {}", target.host, err),
+            )
+        })?
+        .collect();
+    if let Some(policy) = options.ssrf.as_ref() {
+        for addr in &addrs {
+            policy
+                .check_ip(&target.host, addr.ip())
+                .map_err(|denied| denied.to_tool_error())?;
+        }
+    }
+    let mut last_error = None;
+    for addr in &addrs {
+        match TcpStream::connect_timeout(addr, options.timeout) {
+            Ok(stream) => {
+                stream.set_read_timeout(Some(options.timeout)).ok();
+                stream.set_write_timeout(Some(options.timeout)).ok();
+                return Ok(stream);
+            }
+            Err(err) => last_error = Some(err),
+        }
+    }
+    Err(ToolError::new(
+        ToolErrorKind::Retryable,
+        "CERT_CHECK_CONNECT_FAILED",
+        format!(
+            "could not connect to {}: {}",
+            target.endpoint(),
+            last_error
+                .map(|err| err.to_string())
+                .unwrap_or_else(|| "no addresses".to_string())
+        ),
+    ))
+}
+
+fn connector(tls: Option<&HttpTlsConfig>) -> Result<SslConnector, ToolError> {
+    let internal = |err: openssl::error::ErrorStack| ToolError::internal(err.to_string());
+    let mut builder = SslConnector::builder(SslMethod::tls_client()).map_err(internal)?;
+    // The handshake always completes; chain validity is read from the verify result afterwards.
+    builder.set_verify(SslVerifyMode::NONE);
+    if let Some(bundle) = tls.and_then(|tls| tls.ca_cert_pem()) {
+        // Same rule as api requests: a configured CA replaces the system roots.
+        let certs = X509::stack_from_pem(bundle).map_err(|err| {
+            ToolError::invalid_params(format!("tls.ca_cert is not a valid PEM bundle: {}", err))
+        })?;
+        if certs.is_empty() {
+            return Err(ToolError::invalid_params(
+                "tls.ca_cert does not contain any PEM certificates",
+            ));
+        }
+        let mut store = X509StoreBuilder::new().map_err(internal)?;
+        for cert in certs {
+            store.add_cert(cert).map_err(internal)?;
+        }
+        builder
+            .set_verify_cert_store(store.build())
+            .map_err(internal)?;
+    }
+    if let Some((cert, key)) = tls.and_then(|tls| tls.client_identity_pem()) {
+        let load_error = |_| {
+            ToolError::invalid_params("tls.client_cert/tls.client_key could not be loaded")
+                .with_hint(
+                    "Use a PEM certificate chain and an unencrypted PEM private key (PKCS#8, RSA or EC).",
+                )
+        };
+        let chain = X509::stack_from_pem(cert).map_err(load_error)?;
+        let key = PKey::private_key_from_pem(key.unwrap_or(cert)).map_err(load_error)?;
+        let mut chain = chain.into_iter();
+        let leaf = chain
+            .next()
+            .ok_or_else(|| ToolError::invalid_params("tls.client_cert has no certificate"))?;
+        builder.set_certificate(&leaf).map_err(internal)?;
+        for extra in chain {
+            builder.add_extra_chain_cert(extra).map_err(internal)?;
+        }
+        builder.set_private_key(&key).map_err(internal)?;
+    }
+    Ok(builder.build())
+}
+
+// Blocking: TCP connect, TLS handshake, read the peer chain, close. No application data is sent.
+pub fn check_certificate(
+    target: &CertTarget,
+    tls: Option<&HttpTlsConfig>,
+    options: &CertCheckOptions,
+    now: i64,
+) -> Result<Value, ToolError> {
+    let connector = connector(tls)?;
+    let stream = connect(target, options)?;
+    let address = stream.peer_addr().map(|addr| addr.to_string()).ok();
+    let name = target.verify_name().to_string();
+    let config = connector
+        .configure()
+        .map_err(|err| ToolError::internal(err.to_string()))?;
+    let mut tls_stream = config.connect(&name, stream).map_err(|err| {
+        ToolError::new(
+            ToolErrorKind::Retryable,
+            "CERT_CHECK_HANDSHAKE_FAILED",
+            format!("TLS handshake with {} failed: {}", target.endpoint(), err),
+        )
+    })?;
+    let ssl = tls_stream.ssl();
+    let leaf = ssl.peer_certificate().ok_or_else(|| {
+        ToolError::new(
+            ToolErrorKind::Retryable,
+            "CERT_CHECK_HANDSHAKE_FAILED",
+            format!("{} did not present a certificate", target.endpoint()),
+        )
+    })?;
+    let verify = ssl.verify_result();
+    let chain_valid = verify == X509VerifyResult::OK;
+    if !chain_valid && !options.insecure_ok {
+        let _ = tls_stream.shutdown();
+        return Err(ToolError::new(
+            ToolErrorKind::Denied,
+            "CERT_CHECK_VERIFY_FAILED",
+            format!(
+                "certificate of {} is not valid for {}: {}",
+                target.endpoint(),
+                name,
+                verify.error_string()
+            ),
+        )
+        .with_hint("Set tls.ca_cert_path to the issuing CA, or insecure_ok=true to report the certificate anyway."));
+    }
+    let mut entry = describe_certificate(&leaf, now, options.warn_days)?;
+    entry["chain_length"] = Value::from(ssl.peer_cert_chain().map(|c| c.len()).unwrap_or(1));
+    entry["chain_valid"] = Value::Bool(chain_valid);
+    if !chain_valid {
+        entry["verify_error"] = Value::String(verify.error_string().to_string());
+    }
+    entry["tls_version"] = Value::String(ssl.version_str().to_string());
+    entry["address"] = Value::from(address);
+    let _ = tls_stream.shutdown();
+    Ok(entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openssl::asn1::Asn1Integer;
+    use openssl::bn::BigNum;
+    use openssl::rsa::Rsa;
+    use openssl::x509::extension::SubjectAlternativeName;
+    use openssl::x509::X509NameBuilder;
+
+    #[test]
+    fn parses_urls_host_ports_and_objects() {
+        let target = parse_target_str("https://API.example.com/health").unwrap();
+        assert_eq!(
+            (target.host.as_str(), target.port),
+            ("api.example.com", 443)
+        );
+        let target = parse_target_str("db.internal:5433").unwrap();
+        assert_eq!((target.host.as_str(), target.port), ("db.internal", 5433));
+        let target = parse_target_str("[::1]:8443").unwrap();
+        assert_eq!((target.host.as_str(), target.port), ("::1", 8443));
+        assert_eq!(target.endpoint(), "[::1]:8443");
+        assert_eq!(parse_target_str("ldaps://dir.internal").unwrap().port, 443);
+        assert!(parse_target_str("http://plain.example.com").is_err());
+        assert!(parse_target_str(" ").is_err());
+
+        let target = parse_target(&serde_json::json!({
+            "host": "10.0.0.5", "port": 8443, "servername": "Api.Internal"
+        }))
+        .unwrap();
+        assert_eq!(target.port, 8443);
+        assert_eq!(target.verify_name(), "api.internal");
+        assert!(parse_target(&serde_json::json!({"host": "x", "port": 0})).is_err());
+    }
+
+    #[test]
+    fn describes_leaf_certificate_fields() {
+        let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_text("CN", "svc.internal").unwrap();
+        let name = name.build();
+        let mut builder = X509::builder().unwrap();
+        builder.set_version(2).unwrap();
+        let serial = Asn1Integer::from_bn(&BigNum::from_u32(0xBEEF).unwrap()).unwrap();
+        builder.set_serial_number(&serial).unwrap();
+        builder.set_subject_name(&name).unwrap();
+        builder.set_issuer_name(&name).unwrap();
+        builder.set_pubkey(&key).unwrap();
+        builder
+            .set_not_before(&Asn1Time::from_unix(1_700_000_000).unwrap())
+            .unwrap();
+        builder
+            .set_not_after(&Asn1Time::from_unix(1_700_000_000 + 40 * 86_400).unwrap())
+            .unwrap();
+        let san = SubjectAlternativeName::new()
+            .dns("svc.internal")
+            .ip("10.1.2.3")
+            .build(&builder.x509v3_context(None, None))
+            .unwrap();
+        builder.append_extension(san).unwrap();
+        builder.sign(&key, MessageDigest::sha256()).unwrap();
+        let cert = builder.build();
+
+        let now = 1_700_000_000 + 15 * 86_400 + 60;
+        let described = describe_certificate(&cert, now, 30).unwrap();
+        assert_eq!(described["subject"], "CN=svc.internal");
+        assert_eq!(described["issuer"], "CN=svc.internal");
+        assert_eq!(
+            described["sans"],
+            serde_json::json!(["svc.internal", "10.1.2.3"])
+        );
+        assert_eq!(described["serial"], "beef");
+        assert_eq!(described["not_after"], "2023-12-24T22:13:20Z");
+        assert_eq!(described["days_until_expiry"], 24);
+        assert_eq!(described["warning"], true);
+        assert_eq!(described["expired"], false);
+
+        let after = describe_certificate(&cert, 1_700_000_000 + 41 * 86_400, 30).unwrap();
+        assert_eq!(after["days_until_expiry"], -1);
+        assert_eq!(after["expired"], true);
+    }
+}
@@ -0,0 +1,181 @@
+use crate::errors::{ToolError, ToolErrorKind};
+use flate2::write::{DeflateDecoder, MultiGzDecoder, ZlibDecoder};
+use std::io::Write;
+
+// What the api tool advertises when it decodes bodies itself; the shared clients for pipelines
+// still let reqwest decode, the api tool's wire client does not.
+pub const ACCEPT_ENCODING: &str = "gzip, deflate, br";
+const BROTLI_BUFFER_BYTES: usize = 16 * 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContentEncoding {
+    Gzip,
+    Deflate,
+    Brotli,
+}
+
+impl ContentEncoding {
+    // None for identity, a missing header, or codings this tool cannot decode (zstd, stacked ones).
+    pub fn parse(header: Option<&str>) -> Option<Self> {
+        match header?.trim().to_ascii_lowercase().as_str() {
+            "gzip" | "x-gzip" => Some(ContentEncoding::Gzip),
+            "deflate" => Some(ContentEncoding::Deflate),
+            "br" => Some(ContentEncoding::Brotli),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContentEncoding::Gzip => "gzip",
+            ContentEncoding::Deflate => "deflate",
+            ContentEncoding::Brotli => "br",
+        }
+    }
+}
+
+enum Inner {
+    Gzip(MultiGzDecoder<Vec<u8>>),
+    // HTTP "deflate" is meant to be zlib-wrapped, but servers send raw deflate too; the first
+    // two bytes decide which one this is.
+    DeflatePending(Vec<u8>),
+    Zlib(ZlibDecoder<Vec<u8>>),
+    RawDeflate(DeflateDecoder<Vec<u8>>),
+    Brotli(Box<brotli_decompressor::DecompressorWriter<Vec<u8>>>),
+}
+
+// Decodes a body chunk by chunk: `push` returns whatever decoded bytes each chunk completes.
+pub struct BodyDecoder {
+    encoding: ContentEncoding,
+    inner: Inner,
+}
+
+fn decode_error(encoding: ContentEncoding, err: std::io::Error) -> ToolError {
+    ToolError::new(
+        ToolErrorKind::Retryable,
+        "CONTENT_DECODING_FAILED",
+        format!(
+            "Failed to decode {} response body: {}",
+            encoding.as_str(),
+            err
+        ),
+    )
+    .with_hint("Retry, or pass decompress=false to keep the body as sent.")
+}
+
+fn is_zlib_header(head: &[u8]) -> bool {
+    head.len() >= 2
+        && head[0] & 0x0f == 8
+        && (u16::from(head[0]) << 8 | u16::from(head[1])) % 31 == 0
+}
+
+impl BodyDecoder {
+    pub fn new(encoding: ContentEncoding) -> Self {
+        let inner = match encoding {
+            ContentEncoding::Gzip => Inner::Gzip(MultiGzDecoder::new(Vec::new())),
+            ContentEncoding::Deflate => Inner::DeflatePending(Vec::new()),
+            ContentEncoding::Brotli => Inner::Brotli(Box::new(
+                brotli_decompressor::DecompressorWriter::new(Vec::new(), BROTLI_BUFFER_BYTES),
+            )),
+        };
+        Self { encoding, inner }
+    }
+
+    pub fn push(&mut self, chunk: &[u8]) -> Result<Vec<u8>, ToolError> {
+        let encoding = self.encoding;
+        let written = match &mut self.inner {
+            Inner::Gzip(decoder) => decoder.write_all(chunk).map(|_| decoder.get_mut()),
+            Inner::Zlib(decoder) => decoder.write_all(chunk).map(|_| decoder.get_mut()),
+            Inner::RawDeflate(decoder) => decoder.write_all(chunk).map(|_| decoder.get_mut()),
+            Inner::Brotli(decoder) => decoder.write_all(chunk).map(|_| decoder.get_mut()),
+            Inner::DeflatePending(head) => {
+                head.extend_from_slice(chunk);
+                if head.len() < 2 {
+                    return Ok(Vec::new());
+                }
+                let head = std::mem::take(head);
+                self.inner = if is_zlib_header(&head) {
+                    Inner::Zlib(ZlibDecoder::new(Vec::new()))
+                } else {
+                    Inner::RawDeflate(DeflateDecoder::new(Vec::new()))
+                };
+                return self.push(&head);
+            }
+        };
+        written
+            .map(std::mem::take)
+            .map_err(|err| decode_error(encoding, err))
+    }
+
+    // Flushes the decoder; errors when the body ended mid-stream.
+    pub fn finish(self) -> Result<Vec<u8>, ToolError> {
+        let encoding = self.encoding;
+        let result = match self.inner {
+            Inner::Gzip(decoder) => decoder.finish(),
+            Inner::Zlib(decoder) => decoder.finish(),
+            Inner::RawDeflate(decoder) => decoder.finish(),
+            Inner::Brotli(mut decoder) => {
+                decoder.close().map(|_| std::mem::take(decoder.get_mut()))
+            }
+            Inner::DeflatePending(head) if head.is_empty() => Ok(Vec::new()),
+            Inner::DeflatePending(head) => {
+                let mut decoder = DeflateDecoder::new(Vec::new());
+                decoder.write_all(&head).and_then(|_| decoder.finish())
+            }
+        };
+        result.map_err(|err| decode_error(encoding, err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::{DeflateEncoder, GzEncoder, ZlibEncoder};
+    use flate2::Compression;
+
+    fn decode_in_chunks(encoding: ContentEncoding, encoded: &[u8], chunk: usize) -> Vec<u8> {
+        let mut decoder = BodyDecoder::new(encoding);
+        let mut out = Vec::new();
+        for piece in encoded.chunks(chunk) {
+            out.extend(decoder.push(piece).expect("push"));
+        }
+        out.extend(decoder.finish().expect("finish"));
+        out
+    }
+
+    #[test]
+    fn decodes_gzip_and_both_deflate_flavours_in_any_chunking() {
+        let body = b"{\"items\": [1, 2, 3]}\n".repeat(200);
+        let mut gzip = GzEncoder::new(Vec::new(), Compression::default());
+        gzip.write_all(&body).unwrap();
+        let mut zlib = ZlibEncoder::new(Vec::new(), Compression::default());
+        zlib.write_all(&body).unwrap();
+        let mut raw = DeflateEncoder::new(Vec::new(), Compression::default());
+        raw.write_all(&body).unwrap();
+        for (encoding, encoded) in [
+            (ContentEncoding::Gzip, gzip.finish().unwrap()),
+            (ContentEncoding::Deflate, zlib.finish().unwrap()),
+            (ContentEncoding::Deflate, raw.finish().unwrap()),
+        ] {
+            for chunk in [1, 7, 4096] {
+                assert_eq!(decode_in_chunks(encoding, &encoded, chunk), body);
+            }
+        }
+    }
+
+    #[test]
+    fn truncated_bodies_fail_to_decode() {
+        let mut gzip = GzEncoder::new(Vec::new(), Compression::default());
+        gzip.write_all(&[7u8; 4096]).unwrap();
+        let encoded = gzip.finish().unwrap();
+        let mut decoder = BodyDecoder::new(ContentEncoding::Gzip);
+        decoder.push(&encoded[..encoded.len() / 2]).expect("push");
+        let err = decoder.finish().expect_err("truncated");
+        assert_eq!(err.code, "CONTENT_DECODING_FAILED");
+        assert_eq!(
+            ContentEncoding::parse(Some(" GZIP ")),
+            Some(ContentEncoding::Gzip)
+        );
+        assert_eq!(ContentEncoding::parse(Some("zstd")), None);
+    }
+}
@@ -0,0 +1,336 @@
+use std::collections::BTreeMap;
+
+#[derive(Clone, Debug, PartialEq)]
+enum DotenvLine {
+    Entry {
+        key: String,
+        value: String,
+        export: bool,
+        raw: String,
+    },
+    Other(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SetOutcome {
+    Added,
+    Updated,
+    Unchanged,
+}
+
+// Keeps every original line (comments, blanks, quoting) so untouched entries render verbatim.
+#[derive(Clone, Debug, Default)]
+pub struct DotenvFile {
+    lines: Vec<DotenvLine>,
+    trailing_newline: bool,
+}
+
+pub fn escape_env_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for ch in value.chars() {
+        match ch {
+            '\\' => escaped.push_str("\\\\"),
+            '\r' => escaped.push_str("\\r"),
+            '\n' => escaped.push_str("\\n"),
+            '"' => escaped.push_str("\\\""),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+fn is_env_key(key: &str) -> bool {
+    let mut chars = key.chars();
+    matches!(chars.next(), Some(ch) if ch == '_' || ch.is_ascii_alphabetic())
+        && chars.all(|ch| ch == '_' || ch.is_ascii_alphanumeric())
+}
+
+fn unescape_double_quoted(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            out.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some('t') => out.push('\t'),
+            Some('"') => out.push('"'),
+            Some('\\') => out.push('\\'),
+            Some(other) => {
+                out.push('\\');
+                out.push(other);
+            }
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+fn closing_quote(text: &str, quote: char) -> Option<usize> {
+    let mut escaped = false;
+    for (idx, ch) in text.char_indices() {
+        if quote == '"' && escaped {
+            escaped = false;
+            continue;
+        }
+        if quote == '"' && ch == '\\' {
+            escaped = true;
+            continue;
+        }
+        if ch == quote {
+            return Some(idx);
+        }
+    }
+    None
+}
+
+fn strip_inline_comment(value: &str) -> &str {
+    let bytes = value.as_bytes();
+    for idx in 0..bytes.len() {
+        if bytes[idx] == b'#' && (idx == 0 || bytes[idx - 1].is_ascii_whitespace()) {
+            return value[..idx].trim_end();
+        }
+    }
+    value.trim_end()
+}
+
+impl DotenvFile {
+    pub fn parse(content: &str) -> Self {
+        let trailing_newline = content.ends_with('\n');
+        let body = content.strip_suffix('\n').unwrap_or(content);
+        let raw_lines: Vec<&str> = if content.is_empty() {
+            Vec::new()
+        } else {
+            body.split('\n').collect()
+        };
+
+        let mut lines = Vec::new();
+        let mut idx = 0;
+        while idx < raw_lines.len() {
+            let line = raw_lines[idx];
+            idx += 1;
+            let trimmed = line.trim_start();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                lines.push(DotenvLine::Other(line.to_string()));
+                continue;
+            }
+            let (export, rest) = match trimmed.strip_prefix("export") {
+                Some(rest) if rest.starts_with([' ', '\t']) => (true, rest.trim_start()),
+                _ => (false, trimmed),
+            };
+            let Some((key, value)) = rest.split_once('=') else {
+                lines.push(DotenvLine::Other(line.to_string()));
+                continue;
+            };
+            let key = key.trim();
+            if !is_env_key(key) {
+                lines.push(DotenvLine::Other(line.to_string()));
+                continue;
+            }
+
+            let value = value.trim_start().trim_end_matches('\r');
+            let mut raw = line.to_string();
+            let parsed = match value.chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    // Quoted values may span lines; an unterminated quote keeps the rest as-is.
+                    let mut text = value[1..].to_string();
+                    loop {
+                        if let Some(end) = closing_quote(&text, quote) {
+                            text.truncate(end);
+                            break;
+                        }
+                        if idx >= raw_lines.len() {
+                            break;
+                        }
+                        text.push('\n');
+                        text.push_str(raw_lines[idx].trim_end_matches('\r'));
+                        raw.push('\n');
+                        raw.push_str(raw_lines[idx]);
+                        idx += 1;
+                    }
+                    if quote == '"' {
+                        unescape_double_quoted(&text)
+                    } else {
+                        text
+                    }
+                }
+                _ => strip_inline_comment(value).to_string(),
+            };
+            lines.push(DotenvLine::Entry {
+                key: key.to_string(),
+                value: parsed,
+                export,
+                raw,
+            });
+        }
+
+        Self {
+            lines,
+            trailing_newline,
+        }
+    }
+
+    fn entries(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.lines.iter().filter_map(|line| match line {
+            DotenvLine::Entry { key, value, .. } => Some((key.as_str(), value.as_str())),
+            DotenvLine::Other(_) => None,
+        })
+    }
+
+    pub fn keys(&self) -> Vec<String> {
+        let mut out: Vec<String> = Vec::new();
+        for (key, _) in self.entries() {
+            if !out.iter().any(|existing| existing == key) {
+                out.push(key.to_string());
+            }
+        }
+        out
+    }
+
+    pub fn duplicate_keys(&self) -> Vec<String> {
+        let mut seen: Vec<&str> = Vec::new();
+        let mut out: Vec<String> = Vec::new();
+        for (key, _) in self.entries() {
+            if seen.contains(&key) {
+                if !out.iter().any(|existing| existing == key) {
+                    out.push(key.to_string());
+                }
+            } else {
+                seen.push(key);
+            }
+        }
+        out
+    }
+
+    // Last assignment wins, matching how shells and dotenv loaders read the file.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries()
+            .filter(|(entry_key, _)| *entry_key == key)
+            .map(|(_, value)| value)
+            .last()
+    }
+
+    pub fn to_map(&self) -> BTreeMap<String, String> {
+        self.entries()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    pub fn set(&mut self, key: &str, value: &str) -> SetOutcome {
+        if self.get(key) == Some(value) {
+            return SetOutcome::Unchanged;
+        }
+        let mut found = false;
+        for line in self.lines.iter_mut() {
+            if let DotenvLine::Entry {
+                key: entry_key,
+                value: entry_value,
+                export,
+                raw,
+            } = line
+            {
+                if entry_key != key {
+                    continue;
+                }
+                found = true;
+                *entry_value = value.to_string();
+                *raw = format!(
+                    "{}{}={}",
+                    if *export { "export " } else { "" },
+                    key,
+                    escape_env_value(value)
+                );
+            }
+        }
+        if found {
+            return SetOutcome::Updated;
+        }
+        self.lines.push(DotenvLine::Entry {
+            key: key.to_string(),
+            value: value.to_string(),
+            export: false,
+            raw: format!("{}={}", key, escape_env_value(value)),
+        });
+        self.trailing_newline = true;
+        SetOutcome::Added
+    }
+
+    pub fn remove(&mut self, key: &str) -> bool {
+        let before = self.lines.len();
+        self.lines.retain(
+            |line| !matches!(line, DotenvLine::Entry { key: entry_key, .. } if entry_key == key),
+        );
+        self.lines.len() != before
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = self
+            .lines
+            .iter()
+            .map(|line| match line {
+                DotenvLine::Entry { raw, .. } => raw.as_str(),
+                DotenvLine::Other(raw) => raw.as_str(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        if self.trailing_newline && !self.lines.is_empty() {
+            out.push('\n');
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "# app settings\nexport APP_ENV=production\nDB_URL=\"postgres://db/app\" # primary\n\nGREETING='hello # not a comment'\nMULTI=\"line one\nline two\"\nPLAIN=value # trailing\nnot a pair\n";
+
+    #[test]
+    fn parse_handles_quotes_exports_comments_and_multiline_values() {
+        let file = DotenvFile::parse(SAMPLE);
+        assert_eq!(
+            file.keys(),
+            vec!["APP_ENV", "DB_URL", "GREETING", "MULTI", "PLAIN"]
+        );
+        assert_eq!(file.get("APP_ENV"), Some("production"));
+        assert_eq!(file.get("DB_URL"), Some("postgres://db/app"));
+        assert_eq!(file.get("GREETING"), Some("hello # not a comment"));
+        assert_eq!(file.get("MULTI"), Some("line one\nline two"));
+        assert_eq!(file.get("PLAIN"), Some("value"));
+        assert_eq!(file.render(), SAMPLE);
+    }
+
+    #[test]
+    fn set_and_remove_preserve_unrelated_lines() {
+        let mut file = DotenvFile::parse(SAMPLE);
+        assert_eq!(file.set("APP_ENV", "production"), SetOutcome::Unchanged);
+        assert_eq!(file.set("APP_ENV", "staging"), SetOutcome::Updated);
+        assert_eq!(file.set("NEW_KEY", "a \"quoted\" value"), SetOutcome::Added);
+        assert!(file.remove("MULTI"));
+        assert!(!file.remove("MISSING"));
+        let rendered = file.render();
+        assert!(rendered.starts_with("# app settings\nexport APP_ENV=\"staging\"\nDB_URL="));
+        assert!(rendered.contains("GREETING='hello # not a comment'\n"));
+        assert!(!rendered.contains("line two"));
+        assert!(rendered.ends_with("not a pair\nNEW_KEY=\"a \\\"quoted\\\" value\"\n"));
+        let reparsed = DotenvFile::parse(&rendered);
+        assert_eq!(reparsed.get("NEW_KEY"), Some("a \"quoted\" value"));
+    }
+
+    #[test]
+    fn duplicate_keys_resolve_to_the_last_assignment() {
+        let mut file = DotenvFile::parse("A=1\nB=2\nA=3");
+        assert_eq!(file.get("A"), Some("3"));
+        assert_eq!(file.duplicate_keys(), vec!["A"]);
+        assert_eq!(file.set("A", "4"), SetOutcome::Updated);
+        assert_eq!(file.render(), "A=\"4\"\nB=2\nA=\"4\"");
+        assert_eq!(file.set("C", "5"), SetOutcome::Added);
+        assert_eq!(file.render(), "A=\"4\"\nB=2\nA=\"4\"\nC=\"5\"\n");
+    }
+}
@@ -0,0 +1,178 @@
+use crate::errors::ToolError;
+use crate::utils::trace_context::TraceContext;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::Value;
+
+const SUPPORTED: &str = "${uuid}, ${now_iso}, ${now_ms}, ${trace_id}, ${span_id}, ${env:NAME}";
+
+#[derive(Clone, Debug)]
+struct Fixed {
+    uuid: String,
+    now: DateTime<Utc>,
+    trace_id: String,
+    span_id: String,
+}
+
+// Values for `${...}` placeholders in header and query values. One instance covers one
+// request attempt, so every `${uuid}` in it expands to the same id.
+#[derive(Clone, Debug)]
+pub struct DynamicValues {
+    fixed: Option<Fixed>,
+}
+
+impl DynamicValues {
+    pub fn from_args(args: &Value) -> Self {
+        let trace = TraceContext::from_args(args);
+        Self {
+            fixed: Some(Fixed {
+                uuid: uuid::Uuid::new_v4().to_string(),
+                now: Utc::now(),
+                trace_id: trace.trace_id,
+                span_id: trace.span_id,
+            }),
+        }
+    }
+
+    // Checks placeholders but keeps them as written; used where a stable key is needed.
+    pub fn verbatim() -> Self {
+        Self { fixed: None }
+    }
+
+    pub fn substitute(&self, template: &str, field: &str) -> Result<String, ToolError> {
+        let mut out = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(start) = rest.find("${") {
+            out.push_str(&rest[..start]);
+            let after = &rest[start + 2..];
+            let end = after.find('}').ok_or_else(|| {
+                ToolError::invalid_params(format!("{}: unterminated placeholder in value", field))
+                    .with_hint(format!("Supported placeholders: {}.", SUPPORTED))
+            })?;
+            let name = after[..end].trim();
+            let value = self.resolve(name, field)?;
+            match value {
+                Some(value) => out.push_str(&value),
+                None => out.push_str(&rest[start..start + end + 3]),
+            }
+            rest = &after[end + 1..];
+        }
+        out.push_str(rest);
+        Ok(out)
+    }
+
+    fn resolve(&self, name: &str, field: &str) -> Result<Option<String>, ToolError> {
+        if let Some(var) = name.strip_prefix("env:") {
+            let var = var.trim();
+            if var.is_empty() {
+                return Err(ToolError::invalid_params(format!(
+                    "{}: ${{env:NAME}} needs a variable name",
+                    field
+                )));
+            }
+            if self.fixed.is_none() {
+                return Ok(None);
+            }
+            return std::env::var(var).map(Some).map_err(|_| {
+                ToolError::invalid_params(format!(
+                    "{}: environment variable {} is not set",
+                    field, var
+                ))
+            });
+        }
+        if !matches!(name, "uuid" | "now_iso" | "now_ms" | "trace_id" | "span_id") {
+            return Err(ToolError::invalid_params(format!(
+                "{}: unknown placeholder ${{{}}}",
+                field, name
+            ))
+            .with_hint(format!("Supported placeholders: {}.", SUPPORTED)));
+        }
+        let Some(fixed) = self.fixed.as_ref() else {
+            return Ok(None);
+        };
+        Ok(Some(match name {
+            "uuid" => fixed.uuid.clone(),
+            "now_iso" => fixed.now.to_rfc3339_opts(SecondsFormat::Millis, true),
+            "now_ms" => fixed.now.timestamp_millis().to_string(),
+            "trace_id" => fixed.trace_id.clone(),
+            _ => fixed.span_id.clone(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn substitutes_known_placeholders() {
+        std::env::set_var("INFRA_DYNAMIC_VALUES_TEST", "7.2.0");
+        let values = DynamicValues::from_args(&json!({"trace_id": "t-1", "span_id": "s-1"}));
+        let out = values
+            .substitute(
+                "${uuid}|${uuid}|${trace_id}/${span_id}|v${env:INFRA_DYNAMIC_VALUES_TEST}",
+                "header X",
+            )
+            .unwrap();
+        let parts: Vec<&str> = out.split('|').collect();
+        assert_eq!(parts[0], parts[1]);
+        assert!(uuid::Uuid::parse_str(parts[0]).is_ok());
+        assert_eq!(parts[2], "t-1/s-1");
+        assert_eq!(parts[3], "v7.2.0");
+
+        let now_ms: i64 = values
+            .substitute("${now_ms}", "header X")
+            .unwrap()
+            .parse()
+            .unwrap();
+        let now_iso = values.substitute("${now_iso}", "header X").unwrap();
+        assert_eq!(
+            DateTime::parse_from_rfc3339(&now_iso)
+                .unwrap()
+                .timestamp_millis(),
+            now_ms
+        );
+        assert_ne!(
+            DynamicValues::from_args(&json!({}))
+                .substitute("${uuid}", "h")
+                .unwrap(),
+            parts[0]
+        );
+        assert_eq!(
+            values.substitute("plain $value", "h").unwrap(),
+            "plain $value"
+        );
+    }
+
+    #[test]
+    fn verbatim_keeps_placeholders_and_errors_name_the_field() {
+        let verbatim = DynamicValues::verbatim();
+        assert_eq!(
+            verbatim
+                .substitute("id-${uuid}-${env:UNSET_VAR}", "h")
+                .unwrap(),
+            "id-${uuid}-${env:UNSET_VAR}"
+        );
+        let values = DynamicValues::from_args(&json!({}));
+        for (template, message) in [
+            (
+                "${nonce}",
+                "header X-Request-Id: unknown placeholder ${nonce}",
+            ),
+            (
+                "${uuid",
+                "header X-Request-Id: unterminated placeholder in value",
+            ),
+            (
+                "${env:INFRA_DYNAMIC_VALUES_UNSET}",
+                "header X-Request-Id: environment variable INFRA_DYNAMIC_VALUES_UNSET is not set",
+            ),
+        ] {
+            let err = values
+                .substitute(template, "header X-Request-Id")
+                .unwrap_err();
+            assert_eq!(err.message, message);
+        }
+        assert!(verbatim.substitute("${nonce}", "h").is_err());
+    }
+}
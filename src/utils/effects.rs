@@ -24,6 +24,21 @@ impl Effects {
         map.insert("irreversible".to_string(), Value::Bool(self.irreversible));
         Value::Object(map)
     }
+
+    pub fn class(&self) -> &'static str {
+        if self.irreversible {
+            return "destructive";
+        }
+        match self.kind.as_deref() {
+            Some("read") => "read",
+            Some("write") => "write",
+            _ => "mixed",
+        }
+    }
+
+    pub fn is_write_classified(&self) -> bool {
+        self.requires_apply || self.irreversible || self.kind.as_deref() == Some("write")
+    }
 }
 
 pub fn resolve_effects(meta: &Value) -> Effects {
@@ -0,0 +1,268 @@
+use crate::errors::ToolError;
+use crate::utils::artifacts::{
+    build_tool_call_file_ref, dedup_artifact, resolve_context_root, write_text_artifact,
+};
+use crate::utils::feature_flags;
+use crate::utils::fs_atomic::{ensure_dir_for_file, temp_sibling_path};
+use crate::utils::redact::redact_text;
+use serde_json::Value;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+
+pub(crate) fn resolve_stream_to_artifact_mode() -> Option<String> {
+    let raw = feature_flags::SSH_STREAM_TO_ARTIFACT.text()?;
+    let normalized = raw.trim().to_lowercase();
+    if normalized.is_empty() {
+        return None;
+    }
+    if normalized == "full" {
+        return Some("full".to_string());
+    }
+    if normalized == "capped" {
+        return Some("capped".to_string());
+    }
+    if normalized == "1" || normalized == "true" || normalized == "yes" {
+        return Some("capped".to_string());
+    }
+    None
+}
+
+// Output capture shared by ssh exec and local pty exec: `max_capture` bytes are kept for the
+// artifact written when the output was truncated, `max_inline` bytes go into the response, and
+// INFRA_STREAM_TO_ARTIFACT streams the whole stream (or its capped prefix) to an artifact.
+pub(crate) struct CaptureState {
+    pub(crate) total: u64,
+    pub(crate) captured: usize,
+    pub(crate) truncated: bool,
+    pub(crate) inline_truncated: bool,
+    buffer: Vec<u8>,
+    inline: Vec<u8>,
+    writer: Option<ArtifactStream>,
+    writer_limit: usize,
+    writer_total: u64,
+    writer_truncated: bool,
+    max_capture: usize,
+    max_inline: usize,
+    filename: String,
+    trace_id: Option<String>,
+    span_id: Option<String>,
+}
+
+struct ArtifactStream {
+    context_root: PathBuf,
+    rel: String,
+    uri: String,
+    path: PathBuf,
+    tmp_path: PathBuf,
+    file: fs::File,
+    bytes: u64,
+}
+
+impl ArtifactStream {
+    fn new(
+        filename: &str,
+        trace_id: Option<&str>,
+        span_id: Option<&str>,
+    ) -> Result<Self, ToolError> {
+        let context_root = resolve_context_root()
+            .ok_or_else(|| ToolError::internal("Context root not available"))?;
+        let reference = build_tool_call_file_ref(trace_id, span_id, filename)?;
+        let path = crate::utils::artifacts::resolve_artifact_path(&context_root, &reference.rel)?;
+        ensure_dir_for_file(&path).map_err(|err| {
+            ToolError::internal(format!("Failed to create artifact dir: {}", err))
+        })?;
+        let tmp_path = temp_sibling_path(&path);
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&tmp_path)
+            .map_err(|err| ToolError::internal(format!("Failed to create artifact: {}", err)))?;
+        Ok(Self {
+            context_root,
+            rel: reference.rel,
+            uri: reference.uri,
+            path,
+            tmp_path,
+            file,
+            bytes: 0,
+        })
+    }
+
+    fn write(&mut self, chunk: &[u8]) -> Result<(), ToolError> {
+        self.file
+            .write_all(chunk)
+            .map_err(|err| ToolError::internal(format!("Failed to write artifact: {}", err)))?;
+        self.bytes += chunk.len() as u64;
+        Ok(())
+    }
+
+    fn finalize(mut self) -> Result<Value, ToolError> {
+        self.file
+            .flush()
+            .map_err(|err| ToolError::internal(format!("Failed to flush artifact: {}", err)))?;
+        fs::rename(&self.tmp_path, &self.path)
+            .map_err(|err| ToolError::internal(format!("Failed to finalize artifact: {}", err)))?;
+        dedup_artifact(&self.context_root, &self.path);
+        Ok(serde_json::json!({
+            "uri": self.uri,
+            "rel": self.rel,
+            "bytes": self.bytes,
+        }))
+    }
+}
+
+// A stream dropped before `finalize` (an error, or a call dropped at shutdown) removes its temp
+// file; after a rename there is nothing left to remove.
+impl Drop for ArtifactStream {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.tmp_path);
+    }
+}
+
+impl CaptureState {
+    pub(crate) fn new(
+        max_capture: usize,
+        max_inline: usize,
+        stream_mode: Option<&str>,
+        filename: &str,
+        trace_id: Option<&str>,
+        span_id: Option<&str>,
+    ) -> Result<Self, ToolError> {
+        let writer = if stream_mode.is_some() {
+            ArtifactStream::new(filename, trace_id, span_id).ok()
+        } else {
+            None
+        };
+        let writer_limit = if stream_mode == Some("full") {
+            usize::MAX
+        } else {
+            max_capture
+        };
+        Ok(Self {
+            total: 0,
+            captured: 0,
+            truncated: false,
+            inline_truncated: false,
+            buffer: Vec::new(),
+            inline: Vec::new(),
+            writer,
+            writer_limit,
+            writer_total: 0,
+            writer_truncated: false,
+            max_capture,
+            max_inline,
+            filename: filename.to_string(),
+            trace_id: trace_id.map(|s| s.to_string()),
+            span_id: span_id.map(|s| s.to_string()),
+        })
+    }
+
+    pub(crate) fn capture(&mut self, chunk: &[u8]) {
+        self.total += chunk.len() as u64;
+        if let Some(writer) = self.writer.as_mut() {
+            if self.writer_total < self.writer_limit as u64 {
+                let remaining =
+                    (self.writer_limit as u64).saturating_sub(self.writer_total) as usize;
+                let slice = if chunk.len() > remaining {
+                    &chunk[..remaining]
+                } else {
+                    chunk
+                };
+                let _ = writer.write(slice);
+                self.writer_total += slice.len() as u64;
+                if slice.len() < chunk.len() {
+                    self.writer_truncated = true;
+                }
+            } else {
+                self.writer_truncated = true;
+            }
+        }
+        if self.captured < self.max_capture {
+            let remaining = self.max_capture - self.captured;
+            let slice = if chunk.len() > remaining {
+                &chunk[..remaining]
+            } else {
+                chunk
+            };
+            self.buffer.extend_from_slice(slice);
+            self.captured += slice.len();
+            if slice.len() < chunk.len() {
+                self.truncated = true;
+            }
+        } else {
+            self.truncated = true;
+        }
+        if self.inline.len() < self.max_inline {
+            let remaining = self.max_inline - self.inline.len();
+            let slice = if chunk.len() > remaining {
+                &chunk[..remaining]
+            } else {
+                chunk
+            };
+            self.inline.extend_from_slice(slice);
+            if slice.len() < chunk.len() {
+                self.inline_truncated = true;
+            }
+        } else {
+            self.inline_truncated = true;
+        }
+    }
+
+    pub(crate) fn inline_string(&self) -> String {
+        String::from_utf8_lossy(&self.inline).to_string()
+    }
+
+    pub(crate) fn finalize_artifact(
+        &mut self,
+        extra_secrets: Option<&[String]>,
+    ) -> Result<Value, ToolError> {
+        if let Some(writer) = self.writer.take() {
+            if self.writer_total == 0 {
+                drop(writer);
+                return Ok(Value::Null);
+            }
+            let mut payload = writer.finalize()?;
+            if let Value::Object(map) = &mut payload {
+                map.insert(
+                    "captured_bytes".to_string(),
+                    Value::Number(self.writer_total.into()),
+                );
+                map.insert("total_bytes".to_string(), Value::Number(self.total.into()));
+                map.insert("truncated".to_string(), Value::Bool(self.writer_truncated));
+            }
+            return Ok(payload);
+        }
+
+        if self.buffer.is_empty() {
+            return Ok(Value::Null);
+        }
+        let context_root = resolve_context_root();
+        if context_root.is_none() {
+            return Ok(Value::Null);
+        }
+        if !(self.truncated || self.inline_truncated) {
+            return Ok(Value::Null);
+        }
+        if let Ok(reference) = build_tool_call_file_ref(
+            self.trace_id.as_deref(),
+            self.span_id.as_deref(),
+            &self.filename,
+        ) {
+            let redacted = redact_text(
+                &String::from_utf8_lossy(&self.buffer),
+                usize::MAX,
+                extra_secrets,
+            );
+            let written =
+                write_text_artifact(context_root.as_ref().unwrap(), &reference, &redacted)?;
+            return Ok(serde_json::json!({
+                "uri": written.uri,
+                "rel": written.rel,
+                "bytes": written.bytes,
+            }));
+        }
+        Ok(Value::Null)
+    }
+}
@@ -0,0 +1,186 @@
+use crate::errors::ToolError;
+use regex::Regex;
+use serde_json::Value;
+
+#[derive(Clone, Debug)]
+pub struct ExecPolicy {
+    allow: Vec<Regex>,
+    deny: Vec<Regex>,
+    default_allow: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExecPolicyViolation {
+    pub rule: String,
+    pub pattern: Option<String>,
+    pub reason: String,
+}
+
+fn compile_rules(value: Option<&Value>, label: &str) -> Result<Vec<Regex>, ToolError> {
+    let items = match value {
+        None | Some(Value::Null) => return Ok(Vec::new()),
+        Some(Value::String(pattern)) => vec![Value::String(pattern.clone())],
+        Some(Value::Array(items)) => items.clone(),
+        Some(_) => {
+            return Err(ToolError::invalid_params(format!(
+                "exec_policy.{} must be an array of regex strings",
+                label
+            )))
+        }
+    };
+    items
+        .iter()
+        .enumerate()
+        .map(|(index, item)| {
+            let pattern = item.as_str().ok_or_else(|| {
+                ToolError::invalid_params(format!(
+                    "exec_policy.{}[{}] must be a string",
+                    label, index
+                ))
+            })?;
+            Regex::new(pattern).map_err(|err| {
+                ToolError::invalid_params(format!(
+                    "exec_policy.{}[{}] is not a valid regex: {}",
+                    label, index, err
+                ))
+            })
+        })
+        .collect()
+}
+
+// Compound commands are checked segment by segment so an allowed prefix cannot smuggle
+// a second command (`df; rm -rf /`).
+fn command_segments(command: &str) -> Vec<String> {
+    let mut cleaned = command.to_string();
+    for harmless in ["2>&1", ">&2"] {
+        cleaned = cleaned.replace(harmless, " ");
+    }
+    cleaned
+        .split(['\n', ';', '|', '&'])
+        .map(|segment| segment.trim().to_string())
+        .filter(|segment| !segment.is_empty())
+        .collect()
+}
+
+impl ExecPolicy {
+    pub fn from_value(value: Option<&Value>) -> Result<Option<Self>, ToolError> {
+        let obj = match value {
+            None | Some(Value::Null) => return Ok(None),
+            Some(Value::Object(obj)) => obj,
+            Some(_) => return Err(ToolError::invalid_params("exec_policy must be an object")),
+        };
+        let allow = compile_rules(obj.get("allow"), "allow")?;
+        let deny = compile_rules(obj.get("deny"), "deny")?;
+        let default_allow = match obj.get("default").and_then(|v| v.as_str()) {
+            Some("allow") => true,
+            Some("deny") => false,
+            Some(other) => {
+                return Err(ToolError::invalid_params(format!(
+                    "exec_policy.default must be 'allow' or 'deny' (got '{}')",
+                    other
+                )))
+            }
+            None => allow.is_empty(),
+        };
+        Ok(Some(Self {
+            allow,
+            deny,
+            default_allow,
+        }))
+    }
+
+    pub fn default_label(&self) -> &'static str {
+        if self.default_allow {
+            "allow"
+        } else {
+            "deny"
+        }
+    }
+
+    pub fn check(&self, command: &str) -> Result<(), ExecPolicyViolation> {
+        let segments = command_segments(command);
+        for (index, rule) in self.deny.iter().enumerate() {
+            let hit = rule.is_match(command) || segments.iter().any(|s| rule.is_match(s));
+            if hit {
+                return Err(ExecPolicyViolation {
+                    rule: format!("deny[{}]", index),
+                    pattern: Some(rule.as_str().to_string()),
+                    reason: "matched a deny rule".to_string(),
+                });
+            }
+        }
+        if self.default_allow {
+            return Ok(());
+        }
+        if command.contains("$(") || command.contains('`') {
+            return Err(ExecPolicyViolation {
+                rule: "default".to_string(),
+                pattern: None,
+                reason: "command substitution is not allowed under default deny".to_string(),
+            });
+        }
+        for segment in &segments {
+            if !self.allow.iter().any(|rule| rule.is_match(segment)) {
+                return Err(ExecPolicyViolation {
+                    rule: "default".to_string(),
+                    pattern: None,
+                    reason: "no allow rule matched (default deny)".to_string(),
+                });
+            }
+        }
+        if segments.is_empty() {
+            return Err(ExecPolicyViolation {
+                rule: "default".to_string(),
+                pattern: None,
+                reason: "empty command under default deny".to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ExecPolicy;
+
+    fn policy(value: serde_json::Value) -> ExecPolicy {
+        ExecPolicy::from_value(Some(&value))
+            .expect("valid policy")
+            .expect("policy present")
+    }
+
+    #[test]
+    fn allowlist_defaults_to_deny_and_checks_every_segment() {
+        let policy = policy(serde_json::json!({
+            "allow": ["^systemctl (status|restart) app$", "^journalctl\\b", "^df\\b"]
+        }));
+        assert!(policy.check("systemctl restart app").is_ok());
+        assert!(policy.check("journalctl -u app -n 50 2>&1").is_ok());
+        assert!(policy.check("df -h && journalctl -u app").is_ok());
+        assert_eq!(
+            policy.check("df -h; cat /etc/shadow").unwrap_err().rule,
+            "default"
+        );
+        assert!(policy.check("df $(cat /etc/shadow)").is_err());
+    }
+
+    #[test]
+    fn deny_takes_precedence_over_allow() {
+        let policy = policy(serde_json::json!({
+            "allow": [".*"],
+            "deny": ["rm\\s+-rf"],
+            "default": "allow"
+        }));
+        let violation = policy.check("echo ok && rm -rf /srv").unwrap_err();
+        assert_eq!(violation.rule, "deny[0]");
+        assert_eq!(violation.pattern.as_deref(), Some("rm\\s+-rf"));
+        assert!(policy.check("ls -la").is_ok());
+    }
+
+    #[test]
+    fn invalid_rules_are_rejected() {
+        assert!(ExecPolicy::from_value(Some(&serde_json::json!({ "allow": ["("] }))).is_err());
+        assert!(ExecPolicy::from_value(Some(&serde_json::json!({ "default": "maybe" }))).is_err());
+        assert!(ExecPolicy::from_value(None).expect("absent").is_none());
+    }
+}
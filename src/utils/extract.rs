@@ -0,0 +1,524 @@
+use crate::errors::ToolError;
+use crate::utils::data_path::get_path_value;
+use serde_json::Value;
+
+const MAX_EXPRESSION_LEN: usize = 2048;
+const MAX_NODES: usize = 64;
+
+#[derive(Clone, Debug, PartialEq)]
+enum Op {
+    Eq,
+    Ne,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Condition {
+    path: String,
+    op: Op,
+    value: Value,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Stage {
+    Path(String),
+    Select(Vec<Condition>),
+    Map(Vec<(String, String)>),
+    Flatten,
+    First,
+    Last,
+    Count,
+}
+
+// A pipeline of stages separated by `|`, e.g.
+// `items | select(status == "active") | map(id, owner: owner.name)`.
+// The grammar has no nesting, so evaluation is a single pass bounded by MAX_NODES.
+#[derive(Clone, Debug)]
+pub struct Extract {
+    source: String,
+    stages: Vec<(String, Stage)>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Literal(Value),
+    Eq,
+    Ne,
+    Comma,
+    Colon,
+}
+
+fn stage_error(index: usize, text: &str, message: impl std::fmt::Display) -> ToolError {
+    ToolError::invalid_params(format!(
+        "extract stage {} (`{}`): {}",
+        index + 1,
+        text,
+        message
+    ))
+    .with_details(serde_json::json!({ "stage": index + 1, "expression": text }))
+}
+
+// Splits on `|` outside string literals and parentheses.
+fn split_stages(expr: &str) -> Result<Vec<String>, ToolError> {
+    let mut stages = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0i32;
+    let mut in_string = false;
+    let mut escaped = false;
+    for ch in expr.chars() {
+        if in_string {
+            current.push(ch);
+            if escaped {
+                escaped = false;
+            } else if ch == '\\' {
+                escaped = true;
+            } else if ch == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+        match ch {
+            '"' => {
+                in_string = true;
+                current.push(ch);
+            }
+            '(' => {
+                depth += 1;
+                current.push(ch);
+            }
+            ')' => {
+                depth -= 1;
+                current.push(ch);
+            }
+            '|' if depth == 0 => {
+                stages.push(current.trim().to_string());
+                current.clear();
+            }
+            _ => current.push(ch),
+        }
+    }
+    if in_string {
+        return Err(ToolError::invalid_params(
+            "extract has an unterminated string literal",
+        ));
+    }
+    if depth != 0 {
+        return Err(ToolError::invalid_params(
+            "extract has unbalanced parentheses",
+        ));
+    }
+    stages.push(current.trim().to_string());
+    Ok(stages)
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut idx = 0;
+    while idx < chars.len() {
+        let ch = chars[idx];
+        if ch.is_whitespace() {
+            idx += 1;
+            continue;
+        }
+        match ch {
+            ',' => {
+                tokens.push(Token::Comma);
+                idx += 1;
+            }
+            ':' => {
+                tokens.push(Token::Colon);
+                idx += 1;
+            }
+            '=' | '!' => {
+                if chars.get(idx + 1) != Some(&'=') {
+                    return Err(format!("expected `{}=` at column {}", ch, idx + 1));
+                }
+                tokens.push(if ch == '=' { Token::Eq } else { Token::Ne });
+                idx += 2;
+            }
+            '"' => {
+                let start = idx;
+                idx += 1;
+                let mut escaped = false;
+                while idx < chars.len() && (escaped || chars[idx] != '"') {
+                    escaped = !escaped && chars[idx] == '\\';
+                    idx += 1;
+                }
+                let raw: String = chars[start..=idx.min(chars.len() - 1)].iter().collect();
+                let parsed: String = serde_json::from_str(&raw)
+                    .map_err(|_| format!("invalid string literal {}", raw))?;
+                tokens.push(Token::Literal(Value::String(parsed)));
+                idx += 1;
+            }
+            _ => {
+                let start = idx;
+                while idx < chars.len()
+                    && !chars[idx].is_whitespace()
+                    && !matches!(chars[idx], ',' | ':' | '=' | '!' | '"')
+                {
+                    idx += 1;
+                }
+                let word: String = chars[start..idx].iter().collect();
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+fn literal(token: &Token) -> Option<Value> {
+    match token {
+        Token::Literal(value) => Some(value.clone()),
+        Token::Word(word) => match word.as_str() {
+            "true" => Some(Value::Bool(true)),
+            "false" => Some(Value::Bool(false)),
+            "null" => Some(Value::Null),
+            _ => serde_json::from_str::<serde_json::Number>(word)
+                .ok()
+                .map(Value::Number),
+        },
+        _ => None,
+    }
+}
+
+fn field_path(word: &str) -> String {
+    word.trim_start_matches('.').to_string()
+}
+
+fn parse_select(inner: &str) -> Result<Vec<Condition>, String> {
+    let tokens = tokenize(inner)?;
+    let mut conditions = Vec::new();
+    let mut parts = tokens.split(|t| *t == Token::Word("and".to_string()));
+    for part in parts.by_ref() {
+        let [Token::Word(path), op, value] = part else {
+            return Err("expected `field == value` (conditions join with `and`)".to_string());
+        };
+        let op = match op {
+            Token::Eq => Op::Eq,
+            Token::Ne => Op::Ne,
+            _ => return Err(format!("expected == or != after `{}`", path)),
+        };
+        let value = literal(value).ok_or_else(|| format!("`{}` needs a literal value", path))?;
+        conditions.push(Condition {
+            path: field_path(path),
+            op,
+            value,
+        });
+    }
+    Ok(conditions)
+}
+
+fn parse_map(inner: &str) -> Result<Vec<(String, String)>, String> {
+    let tokens = tokenize(inner)?;
+    let mut fields = Vec::new();
+    for part in tokens.split(|t| *t == Token::Comma) {
+        let (key, path) = match part {
+            [Token::Word(path)] => {
+                let path = field_path(path);
+                let key = path
+                    .rsplit(['.', '['])
+                    .next()
+                    .unwrap_or(&path)
+                    .trim_end_matches(']')
+                    .to_string();
+                (key, path)
+            }
+            [Token::Word(key), Token::Colon, Token::Word(path)] => (key.clone(), field_path(path)),
+            _ => return Err("expected `field` or `name: field` entries".to_string()),
+        };
+        if key.is_empty() || path.is_empty() {
+            return Err("map fields must not be empty".to_string());
+        }
+        fields.push((key, path));
+    }
+    Ok(fields)
+}
+
+fn parse_stage(text: &str) -> Result<(Stage, usize), String> {
+    if let Some((name, rest)) = text.split_once('(') {
+        let inner = rest
+            .strip_suffix(')')
+            .ok_or_else(|| "expected `)` at the end of the stage".to_string())?;
+        return match name.trim() {
+            "select" => {
+                let conditions = parse_select(inner)?;
+                let nodes = conditions.len();
+                Ok((Stage::Select(conditions), nodes))
+            }
+            "map" => {
+                let fields = parse_map(inner)?;
+                let nodes = fields.len();
+                Ok((Stage::Map(fields), nodes))
+            }
+            other => Err(format!(
+                "unknown function `{}` (use select, map, flatten, first, last, count)",
+                other
+            )),
+        };
+    }
+    let stage = match text {
+        "flatten" => Stage::Flatten,
+        "first" => Stage::First,
+        "last" => Stage::Last,
+        "count" => Stage::Count,
+        path if !path.contains(char::is_whitespace) => Stage::Path(field_path(path)),
+        _ => {
+            return Err(
+                "expected a path or one of select, map, flatten, first, last, count".to_string(),
+            )
+        }
+    };
+    Ok((stage, 1))
+}
+
+pub fn parse_extract(expr: &str) -> Result<Extract, ToolError> {
+    let source = expr.trim();
+    if source.is_empty() {
+        return Err(ToolError::invalid_params(
+            "extract must be a non-empty expression",
+        ));
+    }
+    if source.len() > MAX_EXPRESSION_LEN {
+        return Err(ToolError::invalid_params(format!(
+            "extract is longer than {} bytes",
+            MAX_EXPRESSION_LEN
+        )));
+    }
+    let mut stages = Vec::new();
+    let mut nodes = 0usize;
+    for (index, text) in split_stages(source)?.into_iter().enumerate() {
+        if text.is_empty() {
+            return Err(stage_error(index, &text, "empty stage"));
+        }
+        let (stage, weight) = parse_stage(&text).map_err(|msg| stage_error(index, &text, msg))?;
+        nodes += weight.max(1);
+        if nodes > MAX_NODES {
+            return Err(stage_error(
+                index,
+                &text,
+                format!("expression exceeds {} nodes", MAX_NODES),
+            ));
+        }
+        stages.push((text, stage));
+    }
+    Ok(Extract {
+        source: source.to_string(),
+        stages,
+    })
+}
+
+// `extract` is optional; anything other than a string is rejected.
+pub fn parse_extract_arg(value: Option<&Value>) -> Result<Option<Extract>, ToolError> {
+    match value.filter(|v| !v.is_null()) {
+        None => Ok(None),
+        Some(Value::String(expr)) => parse_extract(expr).map(Some),
+        Some(_) => Err(
+            ToolError::invalid_params("extract must be a string expression").with_hint(
+                "Example: extract: \"items | select(status == \\\"active\\\") | map(id, name)\""
+                    .to_string(),
+            ),
+        ),
+    }
+}
+
+fn kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn field(value: &Value, path: &str) -> Value {
+    get_path_value(value, path, false, None).unwrap_or(Value::Null)
+}
+
+fn map_one(value: &Value, fields: &[(String, String)]) -> Value {
+    Value::Object(
+        fields
+            .iter()
+            .map(|(key, path)| (key.clone(), field(value, path)))
+            .collect(),
+    )
+}
+
+impl Extract {
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn apply(&self, input: &Value) -> Result<Value, ToolError> {
+        let mut current = input.clone();
+        for (index, (text, stage)) in self.stages.iter().enumerate() {
+            let fail = |message: String| stage_error(index, text, message);
+            current = match stage {
+                Stage::Path(path) if path.is_empty() => current,
+                Stage::Path(path) => get_path_value(&current, path, true, None)
+                    .map_err(|_| fail(format!("path not found in {}", kind(&current))))?,
+                Stage::Select(conditions) => {
+                    let Value::Array(items) = current else {
+                        return Err(fail(format!("expected an array, got {}", kind(&current))));
+                    };
+                    Value::Array(
+                        items
+                            .into_iter()
+                            .filter(|item| {
+                                conditions.iter().all(|cond| {
+                                    let matches = field(item, &cond.path) == cond.value;
+                                    matches == (cond.op == Op::Eq)
+                                })
+                            })
+                            .collect(),
+                    )
+                }
+                Stage::Map(fields) => match &current {
+                    Value::Array(items) => {
+                        Value::Array(items.iter().map(|item| map_one(item, fields)).collect())
+                    }
+                    Value::Object(_) => map_one(&current, fields),
+                    other => return Err(fail(format!("cannot map over {}", kind(other)))),
+                },
+                Stage::Flatten => {
+                    let Value::Array(items) = current else {
+                        return Err(fail(format!("expected an array, got {}", kind(&current))));
+                    };
+                    let mut out = Vec::new();
+                    for item in items {
+                        match item {
+                            Value::Array(inner) => out.extend(inner),
+                            other => out.push(other),
+                        }
+                    }
+                    Value::Array(out)
+                }
+                Stage::First | Stage::Last => {
+                    let Value::Array(items) = current else {
+                        return Err(fail(format!("expected an array, got {}", kind(&current))));
+                    };
+                    let picked = if *stage == Stage::First {
+                        items.into_iter().next()
+                    } else {
+                        items.into_iter().last()
+                    };
+                    picked.unwrap_or(Value::Null)
+                }
+                Stage::Count => match &current {
+                    Value::Array(items) => Value::from(items.len()),
+                    Value::Object(map) => Value::from(map.len()),
+                    Value::Null => Value::from(0),
+                    other => return Err(fail(format!("cannot count {}", kind(other)))),
+                },
+            };
+        }
+        Ok(current)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Value {
+        serde_json::json!({
+            "items": [
+                {"id": 1, "name": "web", "status": "active", "owner": {"name": "ops"}, "tags": ["a", "b"]},
+                {"id": 2, "name": "db", "status": "paused", "owner": {"name": "dba"}, "tags": ["c"]},
+                {"id": 3, "name": "cache", "status": "active", "owner": null, "tags": []},
+            ]
+        })
+    }
+
+    fn run(expr: &str) -> Value {
+        parse_extract(expr).unwrap().apply(&sample()).unwrap()
+    }
+
+    #[test]
+    fn select_and_map_shape_items() {
+        assert_eq!(
+            run("items | select(status == \"active\") | map(id, name)"),
+            serde_json::json!([{"id": 1, "name": "web"}, {"id": 3, "name": "cache"}])
+        );
+        assert_eq!(
+            run(".items | select(status != \"active\" and id == 2) | map(id, team: owner.name)"),
+            serde_json::json!([{"id": 2, "team": "dba"}])
+        );
+        assert_eq!(
+            run("items | map(owner.name)"),
+            serde_json::json!([{"name": "ops"}, {"name": "dba"}, {"name": null}])
+        );
+    }
+
+    #[test]
+    fn flatten_first_last_and_count() {
+        let tags = parse_extract("items[0].tags")
+            .unwrap()
+            .apply(&sample())
+            .unwrap();
+        assert_eq!(tags, serde_json::json!(["a", "b"]));
+        let nested = serde_json::json!([[1, 2], [3], 4]);
+        let flat = parse_extract("flatten").unwrap().apply(&nested).unwrap();
+        assert_eq!(flat, serde_json::json!([1, 2, 3, 4]));
+        assert_eq!(
+            run("items | first | map(name)"),
+            serde_json::json!({"name": "web"})
+        );
+        assert_eq!(run("items | last | map(id)"), serde_json::json!({"id": 3}));
+        assert_eq!(run("items | select(status == \"active\") | count"), 2);
+        assert_eq!(run("items | select(id == 9) | first"), Value::Null);
+    }
+
+    #[test]
+    fn errors_point_at_the_failing_stage() {
+        let err = parse_extract("items | select(status = \"x\")").unwrap_err();
+        assert!(
+            err.message.starts_with("extract stage 2"),
+            "{}",
+            err.message
+        );
+        let err = parse_extract("items | sort(id)").unwrap_err();
+        assert!(
+            err.message.contains("unknown function `sort`"),
+            "{}",
+            err.message
+        );
+        let err = parse_extract("items | select(status == \"x)").unwrap_err();
+        assert!(err.message.contains("unterminated"), "{}", err.message);
+
+        let err = parse_extract("items | first | select(id == 1)")
+            .unwrap()
+            .apply(&sample())
+            .unwrap_err();
+        assert!(
+            err.message.starts_with("extract stage 3"),
+            "{}",
+            err.message
+        );
+        assert!(
+            err.message.contains("expected an array, got object"),
+            "{}",
+            err.message
+        );
+        let err = parse_extract("missing | count")
+            .unwrap()
+            .apply(&sample())
+            .unwrap_err();
+        assert!(
+            err.message.starts_with("extract stage 1"),
+            "{}",
+            err.message
+        );
+    }
+
+    #[test]
+    fn expressions_are_bounded() {
+        let fields: Vec<String> = (0..MAX_NODES + 1).map(|i| format!("f{}", i)).collect();
+        let err = parse_extract(&format!("map({})", fields.join(", "))).unwrap_err();
+        assert!(err.message.contains("nodes"), "{}", err.message);
+        assert!(parse_extract(&"x".repeat(MAX_EXPRESSION_LEN + 1)).is_err());
+        assert!(parse_extract_arg(Some(&serde_json::json!({"path": "x"}))).is_err());
+    }
+}
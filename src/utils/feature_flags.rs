@@ -1,3 +1,887 @@
+use crate::constants::buffers::MAX_LOG_SIZE;
+use crate::constants::network::{
+    TIMEOUT_SSH_DETACHED_START_MS, TIMEOUT_SSH_EXEC_DEFAULT_MS, TIMEOUT_TOOL_CALL_MS,
+};
+use crate::utils::paths::normalize_env_path;
+use crate::utils::transfer::DEFAULT_PROGRESS_MIN_BYTES;
+use serde_json::Value;
+use std::path::PathBuf;
+
+// Every environment setting the server reads, declared once. Accessors read the environment on
+// each call; `startup_only` flags are consumed once while the app is built, so changing them
+// needs a restart.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FlagKind {
+    Bool(bool),
+    Number(Option<u64>),
+    Text(Option<&'static str>),
+    // Comma-separated entries.
+    List(&'static [&'static str]),
+    // The default is derived at runtime; the text only documents it.
+    Path(&'static str),
+}
+
+impl FlagKind {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            FlagKind::Bool(_) => "bool",
+            FlagKind::Number(_) => "number",
+            FlagKind::Text(_) => "text",
+            FlagKind::List(_) => "list",
+            FlagKind::Path(_) => "path",
+        }
+    }
+
+    fn default_json(&self) -> Value {
+        match self {
+            FlagKind::Bool(value) => Value::Bool(*value),
+            FlagKind::Number(value) => Value::from(*value),
+            FlagKind::Text(value) => Value::from(*value),
+            FlagKind::List(items) => Value::from(items.to_vec()),
+            FlagKind::Path(doc) => Value::from(*doc),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Flag {
+    pub name: &'static str,
+    // Checked in order; the first one that is set wins, even when its value is unusable.
+    pub env: &'static [&'static str],
+    pub kind: FlagKind,
+    pub description: &'static str,
+    // Loosens or tightens what tools may do.
+    pub sensitive: bool,
+    // The value is never reported, only whether it is set.
+    pub secret: bool,
+    pub startup_only: bool,
+}
+
+const fn flag(
+    name: &'static str,
+    env: &'static [&'static str],
+    kind: FlagKind,
+    description: &'static str,
+) -> Flag {
+    Flag {
+        name,
+        env,
+        kind,
+        description,
+        sensitive: false,
+        secret: false,
+        startup_only: false,
+    }
+}
+
+impl Flag {
+    const fn sensitive(mut self) -> Self {
+        self.sensitive = true;
+        self
+    }
+
+    const fn secret(mut self) -> Self {
+        self.secret = true;
+        self.sensitive = true;
+        self
+    }
+
+    const fn startup_only(mut self) -> Self {
+        self.startup_only = true;
+        self
+    }
+
+    // The first env var that is set, with its name.
+    pub fn raw(&self) -> Option<(&'static str, String)> {
+        self.env
+            .iter()
+            .find_map(|key| std::env::var(key).ok().map(|value| (*key, value)))
+    }
+
+    // The env value when it parses as a non-negative integer.
+    pub fn env_number(&self) -> Option<u64> {
+        self.raw()
+            .and_then(|(_, raw)| raw.trim().parse::<u64>().ok())
+    }
+
+    pub fn enabled(&self) -> bool {
+        match self.raw() {
+            Some((_, raw)) => is_truthy(raw),
+            None => matches!(self.kind, FlagKind::Bool(true)),
+        }
+    }
+
+    // The env value when it parses, the declared default (or 0) otherwise.
+    pub fn number(&self) -> u64 {
+        self.env_number().or(self.default_number()).unwrap_or(0)
+    }
+
+    // Like `number`, but 0 also means the default.
+    pub fn positive_number(&self) -> u64 {
+        self.env_number()
+            .filter(|value| *value > 0)
+            .or(self.default_number())
+            .unwrap_or(0)
+    }
+
+    fn default_number(&self) -> Option<u64> {
+        match self.kind {
+            FlagKind::Number(default) => default,
+            _ => None,
+        }
+    }
+
+    pub fn text(&self) -> Option<String> {
+        match self.raw() {
+            Some((_, raw)) => Some(raw),
+            None => match self.kind {
+                FlagKind::Text(default) => default.map(str::to_string),
+                _ => None,
+            },
+        }
+    }
+
+    // Entries of a comma-separated value; the declared default when none are set.
+    pub fn list(&self) -> Vec<String> {
+        let entries: Vec<String> = self
+            .raw()
+            .map(|(_, raw)| {
+                raw.split(',')
+                    .map(str::trim)
+                    .filter(|entry| !entry.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+        match self.kind {
+            FlagKind::List(default) if entries.is_empty() => {
+                default.iter().map(|entry| entry.to_string()).collect()
+            }
+            _ => entries,
+        }
+    }
+
+    // Blank values and the literal strings "undefined" / "null" count as unset.
+    pub fn path(&self) -> Option<PathBuf> {
+        normalize_env_path(self.raw().map(|(_, raw)| raw))
+    }
+
+    // Whether the env value is usable for this kind; unusable values fall back to the default.
+    pub fn invalid_value(&self) -> Option<String> {
+        let (_, raw) = self.raw()?;
+        let valid = match self.kind {
+            FlagKind::Bool(_) => is_boolean(&raw),
+            FlagKind::Number(_) => raw.trim().is_empty() || self.env_number().is_some(),
+            _ => true,
+        };
+        (!valid).then_some(raw)
+    }
+
+    // Effective value and where it comes from; secret values are never included.
+    pub fn describe(&self) -> Value {
+        let raw = self.raw();
+        let invalid = self.invalid_value();
+        let from_env = raw.is_some() && invalid.is_none();
+        let value = if self.secret {
+            Value::Null
+        } else {
+            match self.kind {
+                FlagKind::Bool(_) => Value::Bool(self.enabled() && invalid.is_none()),
+                FlagKind::Number(_) => Value::from(self.env_number().or(self.default_number())),
+                FlagKind::Text(_) => Value::from(self.text()),
+                FlagKind::List(_) => Value::from(self.list()),
+                FlagKind::Path(doc) => match self.path() {
+                    Some(path) => Value::from(path.display().to_string()),
+                    None => Value::from(doc),
+                },
+            }
+        };
+        let mut out = serde_json::json!({
+            "name": self.name,
+            "env": self.env,
+            "type": self.kind.type_name(),
+            "default": if self.secret { Value::Null } else { self.kind.default_json() },
+            "value": value,
+            "source": match raw.as_ref() {
+                Some((key, _)) if from_env => format!("env:{}", key),
+                _ => "default".to_string(),
+            },
+            "sensitive": self.sensitive,
+            "startup_only": self.startup_only,
+            "description": self.description,
+        });
+        if self.secret {
+            out["set"] = Value::Bool(raw.is_some());
+        }
+        if let (Some(raw), false) = (invalid, self.secret) {
+            out["invalid"] = Value::String(raw);
+        }
+        out
+    }
+}
+
+pub const UNSAFE_LOCAL: Flag = flag(
+    "unsafe_local",
+    &["INFRA_UNSAFE_LOCAL"],
+    FlagKind::Bool(false),
+    "Enables the local tool (exec, fs writes) on this machine.",
+)
+.sensitive();
+pub const ALLOW_SECRET_EXPORT: Flag = flag(
+    "allow_secret_export",
+    &["INFRA_ALLOW_SECRET_EXPORT"],
+    FlagKind::Bool(false),
+    "Lets profile, env and vault reads return secret values when include_secrets is set.",
+)
+.sensitive();
+pub const STRICT_ARGS: Flag = flag(
+    "strict_args",
+    &["INFRA_STRICT_ARGS"],
+    FlagKind::Bool(false),
+    "Rejects unknown tool arguments instead of ignoring them.",
+);
+pub const READONLY: Flag = flag(
+    "readonly",
+    &["INFRA_READONLY"],
+    FlagKind::Bool(false),
+    "Denies every action whose effects are not read-only.",
+)
+.sensitive();
+pub const STARTUP_PROBE: Flag = flag(
+    "startup_probe",
+    &["INFRA_STARTUP_PROBE"],
+    FlagKind::Bool(false),
+    "Runs the doctor checks once at startup and logs problems.",
+)
+.startup_only();
+pub const API_RECORD: Flag = flag(
+    "api_record",
+    &["INFRA_API_RECORD"],
+    FlagKind::Bool(false),
+    "Records api requests and responses by default.",
+)
+.sensitive();
+pub const API_RECORD_BODY_BYTES: Flag = flag(
+    "api_record_body_bytes",
+    &["INFRA_API_RECORD_BODY_BYTES"],
+    FlagKind::Number(Some(64 * 1024)),
+    "Bytes of each body kept in an api recording.",
+);
+pub const API_FIXTURES: Flag = flag(
+    "api_fixtures",
+    &["INFRA_API_FIXTURES"],
+    FlagKind::Text(None),
+    "api request/paginate fixtures: record writes one per request, replay serves from them.",
+)
+.sensitive();
+pub const API_FIXTURES_MISS: Flag = flag(
+    "api_fixtures_miss",
+    &["INFRA_API_FIXTURES_MISS"],
+    FlagKind::Text(Some("strict")),
+    "Replay without a fixture: strict fails, fallback sends the real request.",
+);
+pub const RESULT_ARTIFACTS: Flag = flag(
+    "result_artifacts",
+    &["INFRA_RESULT_ARTIFACTS"],
+    FlagKind::Bool(false),
+    "Stores oversized tool results as artifacts instead of truncating them.",
+);
+pub const ARTIFACT_DEDUP: Flag = flag(
+    "artifact_dedup",
+    &["INFRA_ARTIFACT_DEDUP"],
+    FlagKind::Bool(false),
+    "Stores identical artifacts once under artifacts/blobs and hardlinks each ref to it.",
+);
+pub const RESOURCE_ACCOUNTING: Flag = flag(
+    "resource_accounting",
+    &["INFRA_RESOURCE_ACCOUNTING"],
+    FlagKind::Bool(false),
+    "Adds meta.resource_usage (bytes, rows, retries, cache hits) to every tool response.",
+);
+pub const DRY_RUN: Flag = flag(
+    "dry_run",
+    &["INFRA_DRY_RUN"],
+    FlagKind::Bool(false),
+    "Plans write actions without running them.",
+)
+.sensitive();
+pub const DRY_RUN_ALLOW_FORCE: Flag = flag(
+    "dry_run_allow_force",
+    &["INFRA_DRY_RUN_ALLOW_FORCE"],
+    FlagKind::Bool(false),
+    "Lets force_execute bypass dry_run.",
+)
+.sensitive();
+pub const HTTP_DENY_PRIVATE: Flag = flag(
+    "http_deny_private",
+    &["INFRA_HTTP_DENY_PRIVATE"],
+    FlagKind::Bool(false),
+    "Blocks HTTP requests to private, loopback and link-local addresses unless allow-listed.",
+)
+.sensitive();
+pub const HTTP_ALLOW_HOSTS: Flag = flag(
+    "http_allow_hosts",
+    &["INFRA_HTTP_ALLOW_HOSTS"],
+    FlagKind::List(&[]),
+    "Hosts, IPs or CIDRs exempt from http_deny_private.",
+)
+.sensitive();
+pub const REPO_ALLOWED_COMMANDS: Flag = flag(
+    "repo_allowed_commands",
+    &["INFRA_REPO_ALLOWED_COMMANDS"],
+    FlagKind::List(&["git"]),
+    "Commands the repo tool may run.",
+)
+.sensitive();
+pub const AUTONOMY: Flag = flag(
+    "autonomy",
+    &["INFRA_AUTONOMY"],
+    FlagKind::Bool(false),
+    "Applies the operatorless autonomy policy.",
+)
+.sensitive();
+pub const AUTONOMY_POLICY: Flag = flag(
+    "autonomy_policy",
+    &["INFRA_AUTONOMY_POLICY"],
+    FlagKind::Text(None),
+    "Autonomy policy: operatorless, or a JSON policy object.",
+)
+.sensitive();
+pub const ENCRYPTION_KEY: Flag = flag(
+    "encryption_key",
+    &["ENCRYPTION_KEY"],
+    FlagKind::Text(None),
+    "Profile encryption key (hex or base64); overrides the key file.",
+)
+.secret()
+.startup_only();
+pub const MASTER_KEY: Flag = flag(
+    "master_key",
+    &["INFRA_MASTER_KEY"],
+    FlagKind::Text(None),
+    "Master key for secrets at rest (hex, base64, ref:env:<VAR> or ref:keychain:<service>); takes precedence over ENCRYPTION_KEY and the key file.",
+)
+.secret()
+.startup_only();
+
+pub const MAX_PAYLOAD_BYTES: Flag = flag(
+    "max_payload_bytes",
+    &["INFRA_MAX_PAYLOAD_BYTES"],
+    FlagKind::Number(Some(MAX_LOG_SIZE as u64)),
+    "Largest accepted payload; 0 disables the check.",
+);
+pub const MAX_INLINE_BYTES: Flag = flag(
+    "max_inline_bytes",
+    &["INFRA_MAX_INLINE_BYTES"],
+    FlagKind::Number(Some(16 * 1024)),
+    "Tool output kept inline before it is spilled to an artifact.",
+);
+pub const MAX_CAPTURE_BYTES: Flag = flag(
+    "max_capture_bytes",
+    &["INFRA_MAX_CAPTURE_BYTES"],
+    FlagKind::Number(Some(256 * 1024)),
+    "Tool output captured per stream.",
+);
+pub const MAX_SPILLS: Flag = flag(
+    "max_spills",
+    &["INFRA_MAX_SPILLS"],
+    FlagKind::Number(Some(20)),
+    "Artifacts one tool result may spill into.",
+);
+pub const MAX_RESULT_BYTES: Flag = flag(
+    "max_result_bytes",
+    &["INFRA_MAX_RESULT_BYTES"],
+    FlagKind::Number(Some(1024 * 1024)),
+    "Largest tool result returned inline.",
+);
+pub const MAX_STATE_VALUE_BYTES: Flag = flag(
+    "max_state_value_bytes",
+    &["INFRA_MAX_STATE_VALUE_BYTES"],
+    FlagKind::Number(Some(8 * 1024 * 1024)),
+    "Largest value store_as may save.",
+);
+pub const TOOL_CALL_TIMEOUT_MS: Flag = flag(
+    "tool_call_timeout_ms",
+    &["INFRA_TOOL_CALL_TIMEOUT_MS"],
+    FlagKind::Number(Some(TIMEOUT_TOOL_CALL_MS)),
+    "Time budget of one tool call; longer work moves to jobs.",
+);
+pub const SHUTDOWN_DRAIN_MS: Flag = flag(
+    "shutdown_drain_ms",
+    &["INFRA_SHUTDOWN_DRAIN_MS"],
+    FlagKind::Number(Some(10_000)),
+    "Time an in-flight call gets to finish after SIGINT/SIGTERM.",
+);
+
+pub const SSH_EXEC_DEFAULT_TIMEOUT_MS: Flag = flag(
+    "ssh_exec_default_timeout_ms",
+    &["INFRA_SSH_EXEC_DEFAULT_TIMEOUT_MS"],
+    FlagKind::Number(Some(TIMEOUT_SSH_EXEC_DEFAULT_MS)),
+    "Timeout of ssh exec without timeout_ms.",
+);
+pub const SSH_DETACHED_START_TIMEOUT_MS: Flag = flag(
+    "ssh_detached_start_timeout_ms",
+    &["INFRA_SSH_DETACHED_START_TIMEOUT_MS"],
+    FlagKind::Number(Some(TIMEOUT_SSH_DETACHED_START_MS)),
+    "Time allowed for a detached ssh command to start.",
+);
+pub const SSH_CONNECT_ATTEMPTS: Flag = flag(
+    "ssh_connect_attempts",
+    &["INFRA_SSH_CONNECT_ATTEMPTS"],
+    FlagKind::Number(Some(3)),
+    "ssh connect/handshake attempts before a command runs.",
+);
+pub const SSH_CONNECT_RETRY_DELAY_MS: Flag = flag(
+    "ssh_connect_retry_delay_ms",
+    &["INFRA_SSH_CONNECT_RETRY_DELAY_MS"],
+    FlagKind::Number(Some(500)),
+    "Pause between ssh connect attempts.",
+);
+pub const SSH_MAX_CAPTURE_BYTES: Flag = flag(
+    "ssh_max_capture_bytes",
+    &["INFRA_SSH_MAX_CAPTURE_BYTES", "INFRA_MAX_CAPTURE_BYTES"],
+    FlagKind::Number(Some(256 * 1024)),
+    "ssh exec output captured per stream.",
+);
+pub const SSH_MAX_INLINE_BYTES: Flag = flag(
+    "ssh_max_inline_bytes",
+    &["INFRA_SSH_MAX_INLINE_BYTES", "INFRA_MAX_INLINE_BYTES"],
+    FlagKind::Number(Some(16 * 1024)),
+    "ssh exec output kept inline.",
+);
+pub const SSH_STREAM_TO_ARTIFACT: Flag = flag(
+    "ssh_stream_to_artifact",
+    &["INFRA_SSH_STREAM_TO_ARTIFACT", "INFRA_STREAM_TO_ARTIFACT"],
+    FlagKind::Text(None),
+    "Streams ssh output to artifacts: full, capped (or 1/true/yes).",
+);
+pub const SSH_MAX_JOBS: Flag = flag(
+    "ssh_max_jobs",
+    &["INFRA_SSH_MAX_JOBS"],
+    FlagKind::Number(Some(200)),
+    "Detached ssh jobs kept in memory.",
+)
+.startup_only();
+pub const SSH_PROGRESS_MIN_BYTES: Flag = flag(
+    "ssh_progress_min_bytes",
+    &["INFRA_SSH_PROGRESS_MIN_BYTES"],
+    FlagKind::Number(Some(DEFAULT_PROGRESS_MIN_BYTES)),
+    "Bytes between transfer progress updates.",
+);
+pub const SSH_SCRATCH_DIR: Flag = flag(
+    "ssh_scratch_dir",
+    &["INFRA_SSH_SCRATCH_DIR"],
+    FlagKind::Text(Some("/tmp/infra-scratch")),
+    "Remote directory for ssh scratch files.",
+);
+
+pub const API_MAX_CAPTURE_BYTES: Flag = flag(
+    "api_max_capture_bytes",
+    &["INFRA_API_MAX_CAPTURE_BYTES", "INFRA_MAX_CAPTURE_BYTES"],
+    FlagKind::Number(Some(256 * 1024)),
+    "api response bytes captured.",
+);
+pub const API_STREAM_TO_ARTIFACT: Flag = flag(
+    "api_stream_to_artifact",
+    &["INFRA_API_STREAM_TO_ARTIFACT", "INFRA_STREAM_TO_ARTIFACT"],
+    FlagKind::Text(None),
+    "Streams api responses to artifacts: full, capped (or 1/true/yes).",
+);
+pub const API_MAX_CONNECTIONS_PER_HOST: Flag = flag(
+    "api_max_connections_per_host",
+    &["INFRA_API_MAX_CONNECTIONS_PER_HOST"],
+    FlagKind::Number(Some(4)),
+    "Segmented api downloads in flight per host.",
+);
+pub const HTTP2_PRIOR_KNOWLEDGE: Flag = flag(
+    "http2_prior_knowledge",
+    &["INFRA_HTTP2_PRIOR_KNOWLEDGE"],
+    FlagKind::Bool(false),
+    "api/pipeline clients speak HTTP/2 without negotiating it (profile/request http settings win).",
+);
+pub const HTTP_POOL_MAX_IDLE_PER_HOST: Flag = flag(
+    "http_pool_max_idle_per_host",
+    &["INFRA_HTTP_POOL_MAX_IDLE_PER_HOST"],
+    FlagKind::Number(None),
+    "Idle connections an api/pipeline client keeps per host (reqwest default when unset).",
+);
+pub const HTTP_POOL_IDLE_TIMEOUT_MS: Flag = flag(
+    "http_pool_idle_timeout_ms",
+    &["INFRA_HTTP_POOL_IDLE_TIMEOUT_MS"],
+    FlagKind::Number(None),
+    "How long an idle api/pipeline connection is kept (reqwest default when unset).",
+);
+pub const HTTP_TCP_KEEPALIVE_MS: Flag = flag(
+    "http_tcp_keepalive_ms",
+    &["INFRA_HTTP_TCP_KEEPALIVE_MS"],
+    FlagKind::Number(None),
+    "TCP keepalive interval for api/pipeline connections (off when unset).",
+);
+pub const HTTP_CONNECT_TIMEOUT_MS: Flag = flag(
+    "http_connect_timeout_ms",
+    &["INFRA_HTTP_CONNECT_TIMEOUT_MS"],
+    FlagKind::Number(None),
+    "Connect timeout for api/pipeline connections (none beyond the request timeout when unset).",
+);
+pub const PIPELINE_MAX_CAPTURE_BYTES: Flag = flag(
+    "pipeline_max_capture_bytes",
+    &[
+        "INFRA_PIPELINE_MAX_CAPTURE_BYTES",
+        "INFRA_MAX_CAPTURE_BYTES",
+    ],
+    FlagKind::Number(Some(256 * 1024)),
+    "Pipeline stream bytes captured into artifacts.",
+);
+pub const PIPELINE_STREAM_TO_ARTIFACT: Flag = flag(
+    "pipeline_stream_to_artifact",
+    &[
+        "INFRA_PIPELINE_STREAM_TO_ARTIFACT",
+        "INFRA_STREAM_TO_ARTIFACT",
+    ],
+    FlagKind::Text(None),
+    "Captures pipeline streams as artifacts: full, capped (or a truthy value).",
+);
+pub const LOCAL_EXEC_MAX_STDOUT_INLINE_BYTES: Flag = flag(
+    "local_exec_max_stdout_inline_bytes",
+    &["INFRA_LOCAL_EXEC_MAX_STDOUT_INLINE_BYTES"],
+    FlagKind::Number(Some(32 * 1024)),
+    "local exec stdout kept inline (at most 256 KiB).",
+);
+pub const LOCAL_EXEC_MAX_STDERR_INLINE_BYTES: Flag = flag(
+    "local_exec_max_stderr_inline_bytes",
+    &["INFRA_LOCAL_EXEC_MAX_STDERR_INLINE_BYTES"],
+    FlagKind::Number(Some(16 * 1024)),
+    "local exec stderr kept inline (at most 256 KiB).",
+);
+
+pub const JOBS_MAX: Flag = flag(
+    "jobs_max",
+    &["INFRA_JOBS_MAX"],
+    FlagKind::Number(Some(500)),
+    "Jobs kept in the job store.",
+)
+.startup_only();
+pub const JOBS_TTL_MS: Flag = flag(
+    "jobs_ttl_ms",
+    &["INFRA_JOBS_TTL_MS"],
+    FlagKind::Number(Some(6 * 60 * 60_000)),
+    "Age after which finished jobs are purged.",
+)
+.startup_only();
+pub const LOG_LEVEL: Flag = flag(
+    "log_level",
+    &["LOG_LEVEL"],
+    FlagKind::Text(Some("info")),
+    "Base log level: error, warn, info or debug.",
+)
+.startup_only();
+pub const LOG_LEVELS: Flag = flag(
+    "log_levels",
+    &["INFRA_LOG_LEVELS"],
+    FlagKind::Text(None),
+    "Per-context log levels, e.g. ssh=debug,api=warn.",
+)
+.startup_only();
+pub const LOG_BUFFER_SIZE: Flag = flag(
+    "log_buffer_size",
+    &["INFRA_LOG_BUFFER_SIZE"],
+    FlagKind::Number(Some(1000)),
+    "Log records kept for logs_tail.",
+)
+.startup_only();
+pub const CACHE_BACKEND: Flag = flag(
+    "cache_backend",
+    &["INFRA_CACHE_BACKEND"],
+    FlagKind::Text(Some("memory")),
+    "Response cache backend: memory or disk.",
+)
+.startup_only();
+pub const CACHE_TTLS: Flag = flag(
+    "cache_ttls",
+    &["INFRA_CACHE_TTLS"],
+    FlagKind::Text(None),
+    "Per-namespace cache TTLs in ms, e.g. api=60000,pipeline=0.",
+)
+.startup_only();
+pub const CACHE_BUDGETS: Flag = flag(
+    "cache_budgets",
+    &["INFRA_CACHE_BUDGETS"],
+    FlagKind::Text(None),
+    "Per-namespace cache size budgets in bytes.",
+)
+.startup_only();
+
+pub const PROFILES_DIR: Flag = flag(
+    "profiles_dir",
+    &["INFRA_PROFILES_DIR"],
+    FlagKind::Path("$XDG_STATE_HOME/infra (~/.local/state/infra)"),
+    "Base directory of all local state.",
+);
+pub const PROFILE_KEY_PATH: Flag = flag(
+    "profile_key_path",
+    &["INFRA_PROFILE_KEY_PATH"],
+    FlagKind::Path("<profiles_dir>/.infra.key"),
+    "Key file that encrypts profile secrets.",
+)
+.sensitive();
+pub const PROFILES_PATH: Flag = flag(
+    "profiles_path",
+    &["INFRA_PROFILES_PATH"],
+    FlagKind::Path("<profiles_dir>/profiles.json"),
+    "Profile store.",
+);
+pub const STATE_PATH: Flag = flag(
+    "state_path",
+    &["INFRA_STATE_PATH"],
+    FlagKind::Path("<profiles_dir>/state.json"),
+    "Persistent state store.",
+);
+pub const PROJECTS_PATH: Flag = flag(
+    "projects_path",
+    &["INFRA_PROJECTS_PATH"],
+    FlagKind::Path("<profiles_dir>/projects.json"),
+    "Project store.",
+);
+pub const RUNBOOKS_PATH: Flag = flag(
+    "runbooks_path",
+    &["INFRA_RUNBOOKS_PATH"],
+    FlagKind::Path("<profiles_dir>/runbooks.json"),
+    "User runbooks.",
+);
+pub const DEFAULT_RUNBOOKS_PATH: Flag = flag(
+    "default_runbooks_path",
+    &["INFRA_DEFAULT_RUNBOOKS_PATH"],
+    FlagKind::Path("runbooks.json next to the binary"),
+    "Bundled runbooks.",
+);
+pub const CAPABILITIES_PATH: Flag = flag(
+    "capabilities_path",
+    &["INFRA_CAPABILITIES_PATH"],
+    FlagKind::Path("<profiles_dir>/capabilities.json"),
+    "User capabilities.",
+);
+pub const DEFAULT_CAPABILITIES_PATH: Flag = flag(
+    "default_capabilities_path",
+    &["INFRA_DEFAULT_CAPABILITIES_PATH"],
+    FlagKind::Path("capabilities.json next to the binary"),
+    "Bundled capabilities.",
+);
+pub const CONTEXT_PATH: Flag = flag(
+    "context_path",
+    &["INFRA_CONTEXT_PATH"],
+    FlagKind::Path("<profiles_dir>/context.json"),
+    "Context store.",
+);
+pub const EVIDENCE_DIR: Flag = flag(
+    "evidence_dir",
+    &["INFRA_EVIDENCE_DIR"],
+    FlagKind::Path("<profiles_dir>/.infra/evidence"),
+    "Evidence records.",
+);
+pub const ALIASES_PATH: Flag = flag(
+    "aliases_path",
+    &["INFRA_ALIASES_PATH"],
+    FlagKind::Path("<profiles_dir>/aliases.json"),
+    "Alias store.",
+);
+pub const PRESETS_PATH: Flag = flag(
+    "presets_path",
+    &["INFRA_PRESETS_PATH"],
+    FlagKind::Path("<profiles_dir>/presets.json"),
+    "Preset store.",
+);
+pub const AUDIT_PATH: Flag = flag(
+    "audit_path",
+    &["INFRA_AUDIT_PATH"],
+    FlagKind::Path("<profiles_dir>/audit.jsonl"),
+    "Audit log.",
+)
+.sensitive();
+pub const AUDIT_WEBHOOK_URL: Flag = flag(
+    "audit_webhook_url",
+    &["INFRA_AUDIT_WEBHOOK_URL"],
+    FlagKind::Text(None),
+    "Also POSTs audit entries, batched as JSON arrays, to this URL.",
+)
+.secret()
+.startup_only();
+pub const AUDIT_WEBHOOK_PROFILE: Flag = flag(
+    "audit_webhook_profile",
+    &["INFRA_AUDIT_WEBHOOK_PROFILE"],
+    FlagKind::Text(None),
+    "Api profile whose auth, headers, TLS and proxy the audit webhook uses.",
+)
+.startup_only();
+pub const AUDIT_WEBHOOK_BATCH_SIZE: Flag = flag(
+    "audit_webhook_batch_size",
+    &["INFRA_AUDIT_WEBHOOK_BATCH_SIZE"],
+    FlagKind::Number(Some(100)),
+    "Entries per audit webhook POST.",
+)
+.startup_only();
+pub const AUDIT_WEBHOOK_FLUSH_MS: Flag = flag(
+    "audit_webhook_flush_ms",
+    &["INFRA_AUDIT_WEBHOOK_FLUSH_MS"],
+    FlagKind::Number(Some(1_000)),
+    "Longest an audit entry waits for its batch to fill.",
+)
+.startup_only();
+pub const AUDIT_WEBHOOK_QUEUE: Flag = flag(
+    "audit_webhook_queue",
+    &["INFRA_AUDIT_WEBHOOK_QUEUE"],
+    FlagKind::Number(Some(10_000)),
+    "Audit entries held for the webhook before new ones are dropped.",
+)
+.startup_only();
+pub const JOBS_PATH: Flag = flag(
+    "jobs_path",
+    &["INFRA_JOBS_PATH"],
+    FlagKind::Path("<profiles_dir>/jobs.json"),
+    "Legacy job store, imported once.",
+);
+pub const API_FIXTURES_DIR: Flag = flag(
+    "api_fixtures_dir",
+    &["INFRA_API_FIXTURES_DIR"],
+    FlagKind::Path("<profiles_dir>/api-fixtures"),
+    "Recorded api fixtures.",
+);
+pub const CACHE_DIR: Flag = flag(
+    "cache_dir",
+    &["INFRA_CACHE_DIR"],
+    FlagKind::Path("<profiles_dir>/cache"),
+    "Response cache and downloads.",
+);
+pub const STORE_DB_PATH: Flag = flag(
+    "store_db_path",
+    &["INFRA_STORE_DB_PATH"],
+    FlagKind::Path("<profiles_dir>/infra.db"),
+    "SQLite store.",
+);
+pub const PIPELINE_CHECKPOINTS_DIR: Flag = flag(
+    "pipeline_checkpoints_dir",
+    &["INFRA_PIPELINE_CHECKPOINTS_DIR"],
+    FlagKind::Path("<profiles_dir>/pipeline-checkpoints"),
+    "Completed-file checkpoints of sftp pipeline runs.",
+);
+pub const CONTEXT_REPO_ROOT: Flag = flag(
+    "context_repo_root",
+    &["INFRA_CONTEXT_REPO_ROOT"],
+    FlagKind::Path("unset (artifacts stay inline)"),
+    "Repo root that owns artifacts.",
+);
+
+pub const FLAGS: &[Flag] = &[
+    UNSAFE_LOCAL,
+    ALLOW_SECRET_EXPORT,
+    STRICT_ARGS,
+    READONLY,
+    STARTUP_PROBE,
+    API_RECORD,
+    API_RECORD_BODY_BYTES,
+    API_FIXTURES,
+    API_FIXTURES_MISS,
+    RESULT_ARTIFACTS,
+    ARTIFACT_DEDUP,
+    RESOURCE_ACCOUNTING,
+    DRY_RUN,
+    DRY_RUN_ALLOW_FORCE,
+    HTTP_DENY_PRIVATE,
+    HTTP_ALLOW_HOSTS,
+    REPO_ALLOWED_COMMANDS,
+    AUTONOMY,
+    AUTONOMY_POLICY,
+    ENCRYPTION_KEY,
+    MASTER_KEY,
+    MAX_PAYLOAD_BYTES,
+    MAX_INLINE_BYTES,
+    MAX_CAPTURE_BYTES,
+    MAX_SPILLS,
+    MAX_RESULT_BYTES,
+    MAX_STATE_VALUE_BYTES,
+    TOOL_CALL_TIMEOUT_MS,
+    SHUTDOWN_DRAIN_MS,
+    SSH_EXEC_DEFAULT_TIMEOUT_MS,
+    SSH_DETACHED_START_TIMEOUT_MS,
+    SSH_CONNECT_ATTEMPTS,
+    SSH_CONNECT_RETRY_DELAY_MS,
+    SSH_MAX_CAPTURE_BYTES,
+    SSH_MAX_INLINE_BYTES,
+    SSH_STREAM_TO_ARTIFACT,
+    SSH_MAX_JOBS,
+    SSH_PROGRESS_MIN_BYTES,
+    SSH_SCRATCH_DIR,
+    API_MAX_CAPTURE_BYTES,
+    API_STREAM_TO_ARTIFACT,
+    API_MAX_CONNECTIONS_PER_HOST,
+    HTTP2_PRIOR_KNOWLEDGE,
+    HTTP_POOL_MAX_IDLE_PER_HOST,
+    HTTP_POOL_IDLE_TIMEOUT_MS,
+    HTTP_TCP_KEEPALIVE_MS,
+    HTTP_CONNECT_TIMEOUT_MS,
+    PIPELINE_MAX_CAPTURE_BYTES,
+    PIPELINE_STREAM_TO_ARTIFACT,
+    LOCAL_EXEC_MAX_STDOUT_INLINE_BYTES,
+    LOCAL_EXEC_MAX_STDERR_INLINE_BYTES,
+    JOBS_MAX,
+    JOBS_TTL_MS,
+    LOG_LEVEL,
+    LOG_LEVELS,
+    LOG_BUFFER_SIZE,
+    CACHE_BACKEND,
+    CACHE_TTLS,
+    CACHE_BUDGETS,
+    PROFILES_DIR,
+    PROFILE_KEY_PATH,
+    PROFILES_PATH,
+    STATE_PATH,
+    PROJECTS_PATH,
+    RUNBOOKS_PATH,
+    DEFAULT_RUNBOOKS_PATH,
+    CAPABILITIES_PATH,
+    DEFAULT_CAPABILITIES_PATH,
+    CONTEXT_PATH,
+    EVIDENCE_DIR,
+    ALIASES_PATH,
+    PRESETS_PATH,
+    AUDIT_PATH,
+    AUDIT_WEBHOOK_URL,
+    AUDIT_WEBHOOK_PROFILE,
+    AUDIT_WEBHOOK_BATCH_SIZE,
+    AUDIT_WEBHOOK_FLUSH_MS,
+    AUDIT_WEBHOOK_QUEUE,
+    JOBS_PATH,
+    API_FIXTURES_DIR,
+    CACHE_DIR,
+    STORE_DB_PATH,
+    PIPELINE_CHECKPOINTS_DIR,
+    CONTEXT_REPO_ROOT,
+];
+
+pub fn find_flag(name: &str) -> Option<&'static Flag> {
+    FLAGS.iter().find(|flag| flag.name == name)
+}
+
+// The registry as reported by `workspace action=config`.
+pub fn describe_flags() -> Value {
+    let flags: Vec<Value> = FLAGS.iter().map(Flag::describe).collect();
+    let overridden: Vec<&str> = FLAGS
+        .iter()
+        .filter(|flag| flag.raw().is_some())
+        .map(|flag| flag.name)
+        .collect();
+    let sensitive_active: Vec<&str> = FLAGS
+        .iter()
+        .filter(|flag| flag.sensitive && flag.raw().is_some())
+        .map(|flag| flag.name)
+        .collect();
+    serde_json::json!({
+        "flags": flags,
+        "overridden": overridden,
+        "sensitive_overridden": sensitive_active,
+    })
+}
+
 pub fn is_truthy(value: impl AsRef<str>) -> bool {
     matches!(
         value.as_ref().trim().to_lowercase().as_str(),
@@ -5,15 +889,253 @@ pub fn is_truthy(value: impl AsRef<str>) -> bool {
     )
 }
 
-pub fn is_truthy_any_env(keys: &[&str]) -> bool {
-    keys.iter()
-        .any(|key| std::env::var(key).ok().map(is_truthy).unwrap_or(false))
+fn is_boolean(value: &str) -> bool {
+    matches!(
+        value.trim().to_lowercase().as_str(),
+        "" | "1" | "true" | "yes" | "on" | "0" | "false" | "no" | "off"
+    )
 }
 
 pub fn is_unsafe_local_enabled() -> bool {
-    is_truthy_any_env(&["INFRA_UNSAFE_LOCAL"])
+    UNSAFE_LOCAL.enabled()
 }
 
 pub fn is_allow_secret_export_enabled() -> bool {
-    is_truthy_any_env(&["INFRA_ALLOW_SECRET_EXPORT"])
+    ALLOW_SECRET_EXPORT.enabled()
+}
+
+pub fn is_strict_args_enabled() -> bool {
+    STRICT_ARGS.enabled()
+}
+
+pub fn is_readonly_enabled() -> bool {
+    READONLY.enabled()
+}
+
+pub fn is_startup_probe_enabled() -> bool {
+    STARTUP_PROBE.enabled()
+}
+
+pub fn is_api_record_enabled() -> bool {
+    API_RECORD.enabled()
+}
+
+pub fn is_artifact_dedup_enabled() -> bool {
+    ARTIFACT_DEDUP.enabled()
+}
+
+pub fn is_result_artifacts_enabled() -> bool {
+    RESULT_ARTIFACTS.enabled()
+}
+
+pub fn is_resource_accounting_enabled() -> bool {
+    RESOURCE_ACCOUNTING.enabled()
+}
+
+pub fn is_dry_run_enabled() -> bool {
+    DRY_RUN.enabled()
+}
+
+pub fn is_dry_run_force_allowed() -> bool {
+    DRY_RUN_ALLOW_FORCE.enabled()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    fn registered_env_vars() -> BTreeSet<&'static str> {
+        FLAGS
+            .iter()
+            .flat_map(|flag| flag.env.iter().copied())
+            .collect()
+    }
+
+    #[test]
+    fn registry_covers_every_env_var_the_source_mentions() {
+        let pattern = regex::Regex::new(r"\bINFRA_[A-Z0-9_]*[A-Z0-9]\b").unwrap();
+        let root = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+        let mut mentioned = BTreeSet::new();
+        for entry in walkdir::WalkDir::new(&root) {
+            let entry = entry.unwrap();
+            if entry.path().extension().and_then(|e| e.to_str()) != Some("rs")
+                || entry.path().ends_with("utils/feature_flags.rs")
+            {
+                continue;
+            }
+            let source = std::fs::read_to_string(entry.path()).unwrap();
+            for found in pattern.find_iter(&source) {
+                mentioned.insert(found.as_str().to_string());
+            }
+            // Reads go through the registry; only dynamic names may reach std::env directly.
+            assert!(
+                !source.contains("env::var(\"INFRA_"),
+                "{} reads an INFRA_ variable directly",
+                entry.path().display()
+            );
+        }
+        // Names used only by unit tests of other modules.
+        let test_only = ["INFRA_DYNAMIC_VALUES_TEST", "INFRA_DYNAMIC_VALUES_UNSET"];
+        let registered = registered_env_vars();
+        let missing: Vec<&String> = mentioned
+            .iter()
+            .filter(|name| !registered.contains(name.as_str()))
+            .filter(|name| !test_only.contains(&name.as_str()))
+            .collect();
+        assert!(missing.is_empty(), "unregistered env vars: {:?}", missing);
+    }
+
+    #[test]
+    fn registry_matches_the_expected_flag_set() {
+        let expected: BTreeSet<&str> = [
+            "ENCRYPTION_KEY",
+            "INFRA_ALIASES_PATH",
+            "INFRA_ALLOW_SECRET_EXPORT",
+            "INFRA_API_FIXTURES",
+            "INFRA_API_FIXTURES_DIR",
+            "INFRA_API_FIXTURES_MISS",
+            "INFRA_API_MAX_CAPTURE_BYTES",
+            "INFRA_API_MAX_CONNECTIONS_PER_HOST",
+            "INFRA_API_RECORD",
+            "INFRA_API_RECORD_BODY_BYTES",
+            "INFRA_API_STREAM_TO_ARTIFACT",
+            "INFRA_ARTIFACT_DEDUP",
+            "INFRA_AUDIT_PATH",
+            "INFRA_AUDIT_WEBHOOK_BATCH_SIZE",
+            "INFRA_AUDIT_WEBHOOK_FLUSH_MS",
+            "INFRA_AUDIT_WEBHOOK_PROFILE",
+            "INFRA_AUDIT_WEBHOOK_QUEUE",
+            "INFRA_AUDIT_WEBHOOK_URL",
+            "INFRA_AUTONOMY",
+            "INFRA_AUTONOMY_POLICY",
+            "INFRA_CACHE_BACKEND",
+            "INFRA_CACHE_BUDGETS",
+            "INFRA_CACHE_DIR",
+            "INFRA_CACHE_TTLS",
+            "INFRA_CAPABILITIES_PATH",
+            "INFRA_CONTEXT_PATH",
+            "INFRA_CONTEXT_REPO_ROOT",
+            "INFRA_DEFAULT_CAPABILITIES_PATH",
+            "INFRA_DEFAULT_RUNBOOKS_PATH",
+            "INFRA_DRY_RUN",
+            "INFRA_DRY_RUN_ALLOW_FORCE",
+            "INFRA_EVIDENCE_DIR",
+            "INFRA_HTTP2_PRIOR_KNOWLEDGE",
+            "INFRA_HTTP_ALLOW_HOSTS",
+            "INFRA_HTTP_CONNECT_TIMEOUT_MS",
+            "INFRA_HTTP_DENY_PRIVATE",
+            "INFRA_HTTP_POOL_IDLE_TIMEOUT_MS",
+            "INFRA_HTTP_POOL_MAX_IDLE_PER_HOST",
+            "INFRA_HTTP_TCP_KEEPALIVE_MS",
+            "INFRA_JOBS_MAX",
+            "INFRA_JOBS_PATH",
+            "INFRA_JOBS_TTL_MS",
+            "INFRA_LOCAL_EXEC_MAX_STDERR_INLINE_BYTES",
+            "INFRA_LOCAL_EXEC_MAX_STDOUT_INLINE_BYTES",
+            "INFRA_LOG_BUFFER_SIZE",
+            "INFRA_LOG_LEVELS",
+            "INFRA_MASTER_KEY",
+            "INFRA_MAX_CAPTURE_BYTES",
+            "INFRA_MAX_INLINE_BYTES",
+            "INFRA_MAX_PAYLOAD_BYTES",
+            "INFRA_MAX_RESULT_BYTES",
+            "INFRA_MAX_SPILLS",
+            "INFRA_MAX_STATE_VALUE_BYTES",
+            "INFRA_PIPELINE_CHECKPOINTS_DIR",
+            "INFRA_PIPELINE_MAX_CAPTURE_BYTES",
+            "INFRA_PIPELINE_STREAM_TO_ARTIFACT",
+            "INFRA_PRESETS_PATH",
+            "INFRA_PROFILES_DIR",
+            "INFRA_PROFILES_PATH",
+            "INFRA_PROFILE_KEY_PATH",
+            "INFRA_PROJECTS_PATH",
+            "INFRA_READONLY",
+            "INFRA_REPO_ALLOWED_COMMANDS",
+            "INFRA_RESOURCE_ACCOUNTING",
+            "INFRA_RESULT_ARTIFACTS",
+            "INFRA_RUNBOOKS_PATH",
+            "INFRA_SHUTDOWN_DRAIN_MS",
+            "INFRA_SSH_CONNECT_ATTEMPTS",
+            "INFRA_SSH_CONNECT_RETRY_DELAY_MS",
+            "INFRA_SSH_DETACHED_START_TIMEOUT_MS",
+            "INFRA_SSH_EXEC_DEFAULT_TIMEOUT_MS",
+            "INFRA_SSH_MAX_CAPTURE_BYTES",
+            "INFRA_SSH_MAX_INLINE_BYTES",
+            "INFRA_SSH_MAX_JOBS",
+            "INFRA_SSH_PROGRESS_MIN_BYTES",
+            "INFRA_SSH_SCRATCH_DIR",
+            "INFRA_SSH_STREAM_TO_ARTIFACT",
+            "INFRA_STARTUP_PROBE",
+            "INFRA_STATE_PATH",
+            "INFRA_STORE_DB_PATH",
+            "INFRA_STREAM_TO_ARTIFACT",
+            "INFRA_STRICT_ARGS",
+            "INFRA_TOOL_CALL_TIMEOUT_MS",
+            "INFRA_UNSAFE_LOCAL",
+            "LOG_LEVEL",
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(registered_env_vars(), expected);
+
+        let mut names = BTreeSet::new();
+        for flag in FLAGS {
+            assert!(names.insert(flag.name), "duplicate flag {}", flag.name);
+            assert!(!flag.env.is_empty() && !flag.description.is_empty());
+        }
+        assert_eq!(
+            find_flag("unsafe_local").unwrap().env,
+            ["INFRA_UNSAFE_LOCAL"]
+        );
+        assert!(find_flag("encryption_key").unwrap().secret);
+    }
+
+    #[test]
+    fn accessors_parse_values_and_fall_back_to_defaults() {
+        const NUMBER: Flag = flag(
+            "n",
+            &["INFRA_FLAG_TEST_PRIMARY", "INFRA_FLAG_TEST_FALLBACK"],
+            FlagKind::Number(Some(7)),
+            "test",
+        );
+        const LIST: Flag = flag(
+            "l",
+            &["INFRA_FLAG_TEST_LIST"],
+            FlagKind::List(&["git"]),
+            "test",
+        );
+        const SECRET: Flag = flag(
+            "s",
+            &["INFRA_FLAG_TEST_SECRET"],
+            FlagKind::Text(None),
+            "test",
+        )
+        .secret();
+
+        assert_eq!(NUMBER.number(), 7);
+        assert_eq!(NUMBER.describe()["source"], "default");
+        std::env::set_var("INFRA_FLAG_TEST_FALLBACK", "12");
+        assert_eq!(NUMBER.number(), 12);
+        assert_eq!(NUMBER.describe()["source"], "env:INFRA_FLAG_TEST_FALLBACK");
+        std::env::set_var("INFRA_FLAG_TEST_PRIMARY", "lots");
+        assert_eq!(NUMBER.number(), 7);
+        let described = NUMBER.describe();
+        assert_eq!(described["invalid"], "lots");
+        assert_eq!(described["source"], "default");
+        std::env::remove_var("INFRA_FLAG_TEST_PRIMARY");
+        std::env::remove_var("INFRA_FLAG_TEST_FALLBACK");
+
+        assert_eq!(LIST.list(), ["git"]);
+        std::env::set_var("INFRA_FLAG_TEST_LIST", " git, make ,,");
+        assert_eq!(LIST.list(), ["git", "make"]);
+        std::env::remove_var("INFRA_FLAG_TEST_LIST");
+
+        std::env::set_var("INFRA_FLAG_TEST_SECRET", "hunter2");
+        let described = SECRET.describe();
+        assert_eq!(described["value"], Value::Null);
+        assert_eq!(described["set"], true);
+        assert!(!described.to_string().contains("hunter2"));
+        std::env::remove_var("INFRA_FLAG_TEST_SECRET");
+    }
 }
@@ -0,0 +1,282 @@
+use crate::errors::ToolError;
+use crate::utils::shell::shell_quote;
+use serde_json::Value;
+
+const ATTRS_MARKER: &str = "__INFRA_ATTRS__=";
+const CONTEXT_MARKER: &str = "__INFRA_ATTRS_CONTEXT__=";
+
+// Ownership, permissions and SELinux context applied to an uploaded file (deploy_file and
+// sftp_upload `owner`, `group`, `mode`, `selinux_context`). `sudo` runs the commands through
+// `sudo -n`, so a password prompt fails instead of hanging.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FileAttrs {
+    pub owner: Option<String>,
+    pub group: Option<String>,
+    pub mode: Option<String>,
+    pub selinux_context: Option<String>,
+    pub sudo: bool,
+}
+
+fn optional_string(args: &Value, key: &str) -> Result<Option<String>, ToolError> {
+    match args.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(text)) if !text.trim().is_empty() => Ok(Some(text.trim().to_string())),
+        Some(Value::Number(number)) if key != "mode" => Ok(Some(number.to_string())),
+        Some(_) => Err(
+            ToolError::invalid_params(format!("{} must be a non-empty string", key)).with_hint(
+                if key == "mode" {
+                    "Quote the octal mode, e.g. mode: \"0755\"."
+                } else {
+                    "Pass a name or a numeric id."
+                },
+            ),
+        ),
+    }
+}
+
+// User and group names as useradd accepts them, or numeric ids.
+fn valid_principal(value: &str) -> bool {
+    value.len() <= 32
+        && value
+            .chars()
+            .next()
+            .is_some_and(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+        && value
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '_' | '.' | '-'))
+}
+
+fn valid_context(value: &str) -> bool {
+    value
+        .chars()
+        .all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '_' | '.' | ':' | ',' | '-'))
+}
+
+impl FileAttrs {
+    // None when no attribute was asked for: the upload then behaves exactly as before.
+    pub fn parse(args: &Value) -> Result<Option<Self>, ToolError> {
+        let attrs = Self {
+            owner: optional_string(args, "owner")?,
+            group: optional_string(args, "group")?,
+            mode: optional_string(args, "mode")?,
+            selinux_context: optional_string(args, "selinux_context")?,
+            sudo: args.get("sudo").and_then(|v| v.as_bool()).unwrap_or(false),
+        };
+        if attrs.owner.is_none()
+            && attrs.group.is_none()
+            && attrs.mode.is_none()
+            && attrs.selinux_context.is_none()
+        {
+            return Ok(None);
+        }
+        for (key, value) in [("owner", &attrs.owner), ("group", &attrs.group)] {
+            if let Some(value) = value.as_deref().filter(|v| !valid_principal(v)) {
+                return Err(ToolError::invalid_params(format!(
+                    "{} '{}' is not a valid user or group name",
+                    key, value
+                )));
+            }
+        }
+        if let Some(mode) = attrs.mode.as_deref() {
+            if mode.is_empty()
+                || mode.len() > 4
+                || !mode.chars().all(|ch| ('0'..='7').contains(&ch))
+            {
+                return Err(ToolError::invalid_params(format!(
+                    "mode '{}' must be an octal permission such as 0644 or 755",
+                    mode
+                )));
+            }
+        }
+        if let Some(context) = attrs
+            .selinux_context
+            .as_deref()
+            .filter(|v| !valid_context(v))
+        {
+            return Err(ToolError::invalid_params(format!(
+                "selinux_context '{}' must look like user:role:type:level or a bare type",
+                context
+            )));
+        }
+        Ok(Some(attrs))
+    }
+
+    // Applies the attributes in chown, chmod, chcon order (chown clears setuid bits, so the mode
+    // goes after it), then prints what stat reports. A bare type goes through `chcon -t`.
+    pub fn script(&self, path: &str) -> String {
+        let sudo = if self.sudo { "sudo -n " } else { "" };
+        let mut lines = vec![
+            "set -eu".to_string(),
+            format!("TARGET={}", shell_quote(path)),
+        ];
+        match (self.owner.as_deref(), self.group.as_deref()) {
+            (Some(owner), Some(group)) => lines.push(format!(
+                "{}chown -- {} \"$TARGET\"",
+                sudo,
+                shell_quote(&format!("{}:{}", owner, group))
+            )),
+            (Some(owner), None) => lines.push(format!(
+                "{}chown -- {} \"$TARGET\"",
+                sudo,
+                shell_quote(owner)
+            )),
+            (None, Some(group)) => lines.push(format!(
+                "{}chgrp -- {} \"$TARGET\"",
+                sudo,
+                shell_quote(group)
+            )),
+            (None, None) => {}
+        }
+        if let Some(mode) = self.mode.as_deref() {
+            lines.push(format!("{}chmod -- {} \"$TARGET\"", sudo, mode));
+        }
+        if let Some(context) = self.selinux_context.as_deref() {
+            let flag = if context.contains(':') { "" } else { "-t " };
+            lines.push(format!(
+                "{}chcon {}-- {} \"$TARGET\"",
+                sudo,
+                flag,
+                shell_quote(context)
+            ));
+        }
+        lines.push(format!(
+            "echo \"{}$({}stat -c '%u:%U:%g:%G:%a' -- \"$TARGET\")\"",
+            ATTRS_MARKER, sudo
+        ));
+        if self.selinux_context.is_some() {
+            lines.push(format!(
+                "echo \"{}$({}stat -c '%C' -- \"$TARGET\")\"",
+                CONTEXT_MARKER, sudo
+            ));
+        }
+        lines.join("\n")
+    }
+
+    // The attributes stat reported, plus `mismatches` naming every requested attribute the file
+    // does not carry. None when the script printed nothing parseable.
+    pub fn verify(&self, stdout: &str) -> Option<(Value, Vec<String>)> {
+        let line = stdout
+            .lines()
+            .find_map(|line| line.strip_prefix(ATTRS_MARKER))?;
+        let parts: Vec<&str> = line.trim().splitn(5, ':').collect();
+        let [uid, user, gid, group, mode] = parts.as_slice() else {
+            return None;
+        };
+        let context = stdout
+            .lines()
+            .find_map(|line| line.strip_prefix(CONTEXT_MARKER))
+            .map(|line| line.trim().to_string());
+        let mut mismatches = Vec::new();
+        if let Some(owner) = self.owner.as_deref() {
+            if owner != *user && owner != *uid {
+                mismatches.push("owner".to_string());
+            }
+        }
+        if let Some(wanted) = self.group.as_deref() {
+            if wanted != *group && wanted != *gid {
+                mismatches.push("group".to_string());
+            }
+        }
+        if let Some(wanted) = self.mode.as_deref() {
+            if normalize_mode(wanted) != normalize_mode(mode) {
+                mismatches.push("mode".to_string());
+            }
+        }
+        if let Some(wanted) = self.selinux_context.as_deref() {
+            let matches = context.as_deref().is_some_and(|actual| {
+                if wanted.contains(':') {
+                    actual == wanted
+                } else {
+                    actual.split(':').nth(2) == Some(wanted)
+                }
+            });
+            if !matches {
+                mismatches.push("selinux_context".to_string());
+            }
+        }
+        let observed = serde_json::json!({
+            "owner": user,
+            "uid": uid.parse::<u64>().ok(),
+            "group": group,
+            "gid": gid.parse::<u64>().ok(),
+            "mode": format!("{:0>4}", normalize_mode(mode)),
+            "selinux_context": context,
+            "sudo": self.sudo,
+        });
+        Some((observed, mismatches))
+    }
+}
+
+fn normalize_mode(mode: &str) -> &str {
+    let trimmed = mode.trim().trim_start_matches('0');
+    if trimmed.is_empty() {
+        "0"
+    } else {
+        trimmed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::process::Command;
+
+    fn run(script: &str) -> std::process::Output {
+        Command::new("sh")
+            .arg("-c")
+            .arg(script)
+            .output()
+            .expect("run sh")
+    }
+
+    #[test]
+    fn parse_ignores_absent_attributes_and_rejects_unsafe_values() {
+        assert_eq!(FileAttrs::parse(&json!({"sudo": true})).unwrap(), None);
+        let attrs = FileAttrs::parse(&json!({"owner": "app", "group": 1000, "mode": "0755"}))
+            .unwrap()
+            .unwrap();
+        assert_eq!(attrs.group.as_deref(), Some("1000"));
+        for bad in [
+            json!({"mode": 755}),
+            json!({"mode": "0899"}),
+            json!({"mode": "u+x"}),
+            json!({"owner": "app; rm -rf /"}),
+            json!({"group": "-g"}),
+            json!({"selinux_context": "$(id)"}),
+        ] {
+            assert!(FileAttrs::parse(&bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn script_applies_mode_and_reports_it() {
+        let dir = std::env::temp_dir().join(format!("infra-test-attrs-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("it's a file");
+        std::fs::write(&path, "x").unwrap();
+        let uid = String::from_utf8(run("id -u").stdout).unwrap();
+        let attrs = FileAttrs {
+            owner: Some(uid.trim().to_string()),
+            mode: Some("0750".to_string()),
+            ..Default::default()
+        };
+        let out = run(&attrs.script(path.to_str().unwrap()));
+        assert!(out.status.success(), "{:?}", out);
+        let stdout = String::from_utf8(out.stdout).unwrap();
+        let (observed, mismatches) = attrs.verify(&stdout).expect("stat line");
+        assert_eq!(mismatches, Vec::<String>::new());
+        assert_eq!(observed["mode"], "0750");
+        assert_eq!(observed["uid"], json!(uid.trim().parse::<u64>().unwrap()));
+
+        let wanted = FileAttrs {
+            mode: Some("644".to_string()),
+            selinux_context: Some("bin_t".to_string()),
+            ..Default::default()
+        };
+        let (_, mismatches) = wanted.verify(&stdout).unwrap();
+        assert_eq!(mismatches, ["mode", "selinux_context"]);
+        assert!(wanted.verify("no marker").is_none());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
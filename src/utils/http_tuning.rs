@@ -0,0 +1,264 @@
+use crate::errors::ToolError;
+use crate::utils::feature_flags;
+use once_cell::sync::Lazy;
+use reqwest::ClientBuilder;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::net::SocketAddr;
+use std::sync::Mutex;
+use std::time::Duration;
+
+const TUNING_KEYS: &[&str] = &[
+    "http2_prior_knowledge",
+    "pool_max_idle_per_host",
+    "pool_idle_timeout_ms",
+    "tcp_keepalive_ms",
+    "connect_timeout_ms",
+];
+// Connections remembered to tell a reused one from a new one; the set starts over past this.
+const MAX_TRACKED_CONNECTIONS: usize = 4096;
+
+// Connection settings for api/pipeline clients. Each field is taken from the request's `http`
+// object, else the profile's, else the HTTP_* env flags; a field set nowhere keeps the
+// reqwest default, and nothing set at all resolves to None (the untuned client).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HttpTuning {
+    pub http2_prior_knowledge: Option<bool>,
+    pub pool_max_idle_per_host: Option<u64>,
+    pub pool_idle_timeout_ms: Option<u64>,
+    pub tcp_keepalive_ms: Option<u64>,
+    pub connect_timeout_ms: Option<u64>,
+}
+
+impl HttpTuning {
+    pub fn resolve(
+        profile: Option<&Value>,
+        request: Option<&Value>,
+    ) -> Result<Option<Self>, ToolError> {
+        let layers = [section(request, "http")?, section(profile, "profile http")?];
+        let number = |key: &str, flag: &feature_flags::Flag| -> Result<Option<u64>, ToolError> {
+            for layer in layers.iter().flatten() {
+                match layer.get(key) {
+                    None | Some(Value::Null) => continue,
+                    Some(value) => {
+                        return value.as_u64().map(Some).ok_or_else(|| {
+                            ToolError::invalid_params(format!(
+                                "http.{} must be a non-negative integer",
+                                key
+                            ))
+                        })
+                    }
+                }
+            }
+            Ok(flag.env_number())
+        };
+        let mut http2_prior_knowledge = None;
+        for layer in layers.iter().flatten() {
+            match layer.get("http2_prior_knowledge") {
+                None | Some(Value::Null) => continue,
+                Some(Value::Bool(value)) => {
+                    http2_prior_knowledge = Some(*value);
+                    break;
+                }
+                Some(_) => {
+                    return Err(ToolError::invalid_params(
+                        "http.http2_prior_knowledge must be a boolean",
+                    ))
+                }
+            }
+        }
+        if http2_prior_knowledge.is_none() && feature_flags::HTTP2_PRIOR_KNOWLEDGE.raw().is_some() {
+            http2_prior_knowledge = Some(feature_flags::HTTP2_PRIOR_KNOWLEDGE.enabled());
+        }
+        let tuning = Self {
+            http2_prior_knowledge,
+            pool_max_idle_per_host: number(
+                "pool_max_idle_per_host",
+                &feature_flags::HTTP_POOL_MAX_IDLE_PER_HOST,
+            )?,
+            pool_idle_timeout_ms: number(
+                "pool_idle_timeout_ms",
+                &feature_flags::HTTP_POOL_IDLE_TIMEOUT_MS,
+            )?,
+            tcp_keepalive_ms: number("tcp_keepalive_ms", &feature_flags::HTTP_TCP_KEEPALIVE_MS)?,
+            connect_timeout_ms: number(
+                "connect_timeout_ms",
+                &feature_flags::HTTP_CONNECT_TIMEOUT_MS,
+            )?,
+        };
+        Ok((tuning != Self::default()).then_some(tuning))
+    }
+
+    // Client cache key: clients with different settings are never shared.
+    pub fn fingerprint(&self) -> String {
+        let part = |value: Option<u64>| value.map(|v| v.to_string()).unwrap_or_default();
+        format!(
+            "h2={};idle={};idle_ms={};ka_ms={};connect_ms={}",
+            self.http2_prior_knowledge
+                .map(|v| v.to_string())
+                .unwrap_or_default(),
+            part(self.pool_max_idle_per_host),
+            part(self.pool_idle_timeout_ms),
+            part(self.tcp_keepalive_ms),
+            part(self.connect_timeout_ms),
+        )
+    }
+
+    pub fn apply(&self, mut builder: ClientBuilder) -> ClientBuilder {
+        if self.http2_prior_knowledge == Some(true) {
+            builder = builder.http2_prior_knowledge();
+        }
+        if let Some(max) = self.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max as usize);
+        }
+        if let Some(ms) = self.pool_idle_timeout_ms {
+            builder = builder.pool_idle_timeout(Duration::from_millis(ms));
+        }
+        if let Some(ms) = self.tcp_keepalive_ms {
+            builder = builder.tcp_keepalive(Duration::from_millis(ms));
+        }
+        if let Some(ms) = self.connect_timeout_ms {
+            builder = builder.connect_timeout(Duration::from_millis(ms));
+        }
+        builder
+    }
+}
+
+fn section<'a>(
+    value: Option<&'a Value>,
+    label: &str,
+) -> Result<Option<&'a serde_json::Map<String, Value>>, ToolError> {
+    match value {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::Object(map)) => {
+            if let Some(unknown) = map.keys().find(|key| !TUNING_KEYS.contains(&key.as_str())) {
+                return Err(ToolError::invalid_params(format!(
+                    "Unknown {} setting: {}",
+                    label, unknown
+                ))
+                .with_hint(format!("Known settings: {}", TUNING_KEYS.join(", "))));
+            }
+            Ok(Some(map))
+        }
+        Some(_) => Err(ToolError::invalid_params(format!(
+            "{} must be an object",
+            label
+        ))),
+    }
+}
+
+#[derive(Default)]
+struct PoolStats {
+    clients: u64,
+    tuned_clients: u64,
+    responses: u64,
+    new_connections: u64,
+    reused_connections: u64,
+    versions: BTreeMap<String, u64>,
+    seen: HashSet<(SocketAddr, SocketAddr)>,
+}
+
+static POOL_STATS: Lazy<Mutex<PoolStats>> = Lazy::new(|| Mutex::new(PoolStats::default()));
+
+pub fn record_client_built(tuned: bool) {
+    let mut stats = POOL_STATS.lock().unwrap_or_else(|err| err.into_inner());
+    stats.clients += 1;
+    if tuned {
+        stats.tuned_clients += 1;
+    }
+}
+
+// `{http_version, connection_reused}` for a response. reqwest does not say whether a pooled
+// connection was used, so reuse is inferred from the socket's local/remote address pair having
+// served an earlier response; it is null when the transport exposes no addresses.
+pub fn observe_response(response: &reqwest::Response) -> Value {
+    let version = format!("{:?}", response.version());
+    let addrs = response
+        .extensions()
+        .get::<hyper::client::connect::HttpInfo>()
+        .map(|info| (info.local_addr(), info.remote_addr()));
+    let mut stats = POOL_STATS.lock().unwrap_or_else(|err| err.into_inner());
+    stats.responses += 1;
+    *stats.versions.entry(version.clone()).or_insert(0) += 1;
+    let reused = addrs.map(|pair| {
+        if stats.seen.contains(&pair) {
+            stats.reused_connections += 1;
+            true
+        } else {
+            if stats.seen.len() >= MAX_TRACKED_CONNECTIONS {
+                stats.seen.clear();
+            }
+            stats.seen.insert(pair);
+            stats.new_connections += 1;
+            false
+        }
+    });
+    serde_json::json!({
+        "http_version": version,
+        "connection_reused": reused,
+    })
+}
+
+pub fn pool_snapshot() -> Value {
+    let stats = POOL_STATS.lock().unwrap_or_else(|err| err.into_inner());
+    let reuse_ratio = if stats.responses == 0 {
+        Value::Null
+    } else {
+        Value::from(stats.reused_connections as f64 / stats.responses as f64)
+    };
+    serde_json::json!({
+        "clients": stats.clients,
+        "tuned_clients": stats.tuned_clients,
+        "responses": stats.responses,
+        "new_connections": stats.new_connections,
+        "reused_connections": stats.reused_connections,
+        "reuse_ratio": reuse_ratio,
+        "http_versions": stats.versions,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn request_settings_override_the_profile_and_unset_means_untuned() {
+        assert_eq!(HttpTuning::resolve(None, None).expect("resolve"), None);
+        assert_eq!(
+            HttpTuning::resolve(Some(&json!({})), Some(&json!({}))).expect("resolve"),
+            None
+        );
+
+        let profile = json!({"pool_max_idle_per_host": 8, "http2_prior_knowledge": true});
+        let tuning = HttpTuning::resolve(
+            Some(&profile),
+            Some(&json!({"pool_max_idle_per_host": 2, "connect_timeout_ms": 1500})),
+        )
+        .expect("resolve")
+        .expect("tuned");
+        assert_eq!(tuning.pool_max_idle_per_host, Some(2));
+        assert_eq!(tuning.http2_prior_knowledge, Some(true));
+        assert_eq!(tuning.connect_timeout_ms, Some(1500));
+        let profile_only = HttpTuning::resolve(Some(&profile), None)
+            .expect("resolve")
+            .expect("tuned");
+        assert_ne!(tuning.fingerprint(), profile_only.fingerprint());
+
+        for (request, message) in [
+            (json!({"keepalive": 1}), "Unknown http setting: keepalive"),
+            (
+                json!({"tcp_keepalive_ms": "30s"}),
+                "http.tcp_keepalive_ms must be a non-negative integer",
+            ),
+            (
+                json!({"http2_prior_knowledge": "yes"}),
+                "http.http2_prior_knowledge must be a boolean",
+            ),
+            (json!(true), "http must be an object"),
+        ] {
+            let err = HttpTuning::resolve(None, Some(&request)).unwrap_err();
+            assert_eq!(err.message, message);
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use serde_json::Value;
+
+// One round trip per host; every section is optional so busybox and macOS hosts still report
+// what they have. Sections are separated by `__INFRA_<NAME>__` marker lines.
+pub const INVENTORY_SCRIPT: &str = "\
+echo __INFRA_KERNEL__; uname -srm 2>/dev/null
+echo __INFRA_OS__; ( . /etc/os-release 2>/dev/null && echo \"$PRETTY_NAME\" ) || sw_vers -productVersion 2>/dev/null
+echo __INFRA_DISK__; df -P 2>/dev/null
+echo __INFRA_MEMORY__; grep -E '^(MemTotal|MemAvailable):' /proc/meminfo 2>/dev/null
+echo __INFRA_UPTIME__; cat /proc/uptime 2>/dev/null
+echo __INFRA_LOAD__; cat /proc/loadavg 2>/dev/null || uptime
+true";
+
+pub const DEFAULT_DISK_WARN_PCT: u64 = 90;
+
+fn sections(stdout: &str) -> Vec<(&str, Vec<&str>)> {
+    let mut out: Vec<(&str, Vec<&str>)> = Vec::new();
+    for line in stdout.lines() {
+        let trimmed = line.trim();
+        if let Some(name) = trimmed
+            .strip_prefix("__INFRA_")
+            .and_then(|rest| rest.strip_suffix("__"))
+        {
+            out.push((name, Vec::new()));
+        } else if let Some((_, lines)) = out.last_mut() {
+            if !trimmed.is_empty() {
+                lines.push(trimmed);
+            }
+        }
+    }
+    out
+}
+
+// `df -P` rows: filesystem, blocks, used, available, capacity%, mount point (may contain spaces).
+fn disk_warnings(lines: &[&str], warn_pct: u64) -> Vec<Value> {
+    lines
+        .iter()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 6 {
+                return None;
+            }
+            let use_pct = fields[4].trim_end_matches('%').parse::<u64>().ok()?;
+            (use_pct >= warn_pct).then(|| {
+                serde_json::json!({
+                    "mount": fields[5..].join(" "),
+                    "use_pct": use_pct,
+                })
+            })
+        })
+        .collect()
+}
+
+fn meminfo_mb(lines: &[&str], key: &str) -> Option<u64> {
+    lines.iter().find_map(|line| {
+        let rest = line.strip_prefix(key)?.strip_prefix(':')?;
+        let kb = rest.split_whitespace().next()?.parse::<u64>().ok()?;
+        Some(kb / 1024)
+    })
+}
+
+// `/proc/loadavg` starts with the three averages; `uptime` ends with "load average(s): a, b, c".
+fn load_averages(lines: &[&str]) -> Option<Vec<f64>> {
+    let line = lines.first()?;
+    let tail = line
+        .rsplit_once("load average")
+        .map(|(_, rest)| rest.trim_start_matches(['s', ':', ' ']))
+        .unwrap_or(line);
+    let values: Vec<f64> = tail
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+        .take(3)
+        .map(|s| s.parse::<f64>())
+        .collect::<Result<_, _>>()
+        .ok()?;
+    (values.len() == 3).then_some(values)
+}
+
+// Compact per-host facts from INVENTORY_SCRIPT output.
+pub fn parse_inventory(stdout: &str, disk_warn_pct: u64) -> Value {
+    let sections = sections(stdout);
+    let section = |name: &str| -> Vec<&str> {
+        sections
+            .iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, lines)| lines.clone())
+            .unwrap_or_default()
+    };
+    let first = |name: &str| section(name).first().map(|s| s.to_string());
+    let memory = section("MEMORY");
+    let uptime_s = section("UPTIME")
+        .first()
+        .and_then(|line| line.split_whitespace().next())
+        .and_then(|s| s.parse::<f64>().ok())
+        .map(|secs| secs as u64);
+    serde_json::json!({
+        "os": first("OS"),
+        "kernel": first("KERNEL"),
+        "uptime_s": uptime_s,
+        "load": load_averages(&section("LOAD")),
+        "memory": {
+            "total_mb": meminfo_mb(&memory, "MemTotal"),
+            "available_mb": meminfo_mb(&memory, "MemAvailable"),
+        },
+        "disk_warnings": disk_warnings(&section("DISK"), disk_warn_pct),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_linux_output() {
+        let stdout = "__INFRA_KERNEL__\nLinux 6.1.0-18-amd64 x86_64\n__INFRA_OS__\nDebian GNU/Linux 12 (bookworm)\n__INFRA_DISK__\nFilesystem 1024-blocks Used Available Capacity Mounted on\n/dev/sda1 100 95 5 95% /\n/dev/sdb1 100 10 90 10% /data\n/dev/sdc1 100 91 9 91% /mnt/My Disk\n__INFRA_MEMORY__\nMemTotal:        8388608 kB\nMemAvailable:    2097152 kB\n__INFRA_UPTIME__\n3600.52 7000.10\n__INFRA_LOAD__\n0.52 0.41 0.30 1/123 4567\n";
+        let parsed = parse_inventory(stdout, DEFAULT_DISK_WARN_PCT);
+        assert_eq!(parsed["os"], "Debian GNU/Linux 12 (bookworm)");
+        assert_eq!(parsed["kernel"], "Linux 6.1.0-18-amd64 x86_64");
+        assert_eq!(parsed["uptime_s"], 3600);
+        assert_eq!(parsed["load"], serde_json::json!([0.52, 0.41, 0.3]));
+        assert_eq!(parsed["memory"]["total_mb"], 8192);
+        assert_eq!(parsed["memory"]["available_mb"], 2048);
+        assert_eq!(
+            parsed["disk_warnings"],
+            serde_json::json!([
+                {"mount": "/", "use_pct": 95},
+                {"mount": "/mnt/My Disk", "use_pct": 91},
+            ])
+        );
+    }
+
+    #[test]
+    fn tolerates_missing_sections_and_uptime_fallback() {
+        let stdout = "__INFRA_KERNEL__\nDarwin 23.1.0 arm64\n__INFRA_OS__\n14.1\n__INFRA_DISK__\n__INFRA_MEMORY__\n__INFRA_UPTIME__\n__INFRA_LOAD__\n10:00  up 3 days, 2 users, load averages: 1.50 1.20 1.00\n";
+        let parsed = parse_inventory(stdout, DEFAULT_DISK_WARN_PCT);
+        assert_eq!(parsed["os"], "14.1");
+        assert_eq!(parsed["load"], serde_json::json!([1.5, 1.2, 1.0]));
+        assert!(parsed["uptime_s"].is_null());
+        assert!(parsed["memory"]["total_mb"].is_null());
+        assert_eq!(parsed["disk_warnings"], serde_json::json!([]));
+        assert!(parse_inventory("", 90)["kernel"].is_null());
+    }
+
+    #[test]
+    fn script_runs_locally() {
+        let out = std::process::Command::new("sh")
+            .arg("-c")
+            .arg(INVENTORY_SCRIPT)
+            .output()
+            .expect("run sh");
+        assert!(out.status.success());
+        let parsed = parse_inventory(&String::from_utf8_lossy(&out.stdout), 101);
+        assert!(parsed["kernel"].is_string(), "{}", parsed);
+        assert_eq!(parsed["disk_warnings"], serde_json::json!([]));
+    }
+}
@@ -1,25 +1,72 @@
+pub mod ansi;
+pub mod api_fixtures;
+pub mod archive;
 pub mod artifacts;
+pub mod audit_chain;
 pub mod bundled_manifests;
+pub mod capability_discovery;
+pub mod cert_check;
 pub mod checks;
+pub mod content_encoding;
 pub mod data_path;
+pub mod dotenv;
+pub mod dynamic_values;
 pub mod effects;
+pub mod exec_capture;
+pub mod exec_policy;
+pub mod extract;
 pub mod feature_flags;
+pub mod file_attrs;
 pub mod fs_atomic;
+pub mod http_proxy;
+pub mod http_tls;
+pub mod http_tuning;
+pub mod inventory;
 pub mod listing;
 pub mod manifests;
 pub mod merge;
+pub mod next_actions;
 pub mod operation_view;
 pub mod output;
+pub mod path_watch;
 pub mod paths;
+pub mod pg_bulk;
+pub mod pg_catalog_diff;
+pub mod pg_keyset;
+pub mod pg_params;
+pub mod pg_redaction;
+pub mod pg_reports;
+pub mod pg_retry;
+pub mod pg_schema;
+pub mod pg_store;
+pub mod pg_tls;
+pub mod pg_values;
 pub mod redact;
+pub mod remediation;
+pub mod run_report;
+pub mod runbook_capture;
 pub mod runbook_dsl;
+pub mod safe_name;
 pub mod sandbox;
+pub mod segmented_download;
+pub mod sftp_listing;
+pub mod shell;
+pub mod shutdown;
+pub mod smoke_sla;
 pub mod sql;
+pub mod ssh_env;
+pub mod ssh_probe;
+pub mod ssrf;
 pub mod stability;
 pub mod stdin;
 pub mod suggest;
 pub mod template;
 pub mod text;
+pub mod text_parse;
+pub mod text_patch;
 pub mod tool_errors;
+pub mod trace_context;
+pub mod transfer;
+pub mod usage;
 pub mod user_paths;
 pub mod when_matcher;
@@ -0,0 +1,420 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_json::Value;
+
+pub const DEFAULT_RECENT_ENTRIES: usize = 50;
+pub const MAX_RECENT_ENTRIES: usize = 500;
+pub const DEFAULT_NEXT_ACTIONS: usize = 5;
+const SAME_TRACE_BONUS: i64 = 50;
+const SELECTOR_KEYS: &[&str] = &[
+    "profile_name",
+    "project",
+    "project_name",
+    "target",
+    "project_target",
+    "environment",
+];
+
+static MISSING_RELATION_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"relation "([^"]+)" does not exist"#).unwrap());
+static SERVICE_NAME_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[A-Za-z0-9@._:-]+$").unwrap());
+
+struct Suggestion {
+    rule: &'static str,
+    score: i64,
+    tool: &'static str,
+    action: &'static str,
+    args: Value,
+    reason: String,
+}
+
+type AuditRule = fn(&Value) -> Vec<Suggestion>;
+
+const AUDIT_RULES: &[AuditRule] = &[
+    deploy_smoke_failed,
+    sql_missing_relation,
+    ssh_host_key_mismatch,
+];
+
+fn str_at<'a>(value: &'a Value, path: &[&str]) -> Option<&'a str> {
+    let mut current = value;
+    for key in path {
+        current = current.get(*key)?;
+    }
+    current.as_str().filter(|s| !s.trim().is_empty())
+}
+
+fn is_error(entry: &Value, tool: &str) -> bool {
+    str_at(entry, &["tool"]) == Some(tool) && str_at(entry, &["status"]) == Some("error")
+}
+
+fn error_message(entry: &Value) -> &str {
+    str_at(entry, &["error", "message"])
+        .or_else(|| str_at(entry, &["error"]))
+        .unwrap_or("")
+}
+
+// Only selector keys are copied: connection objects/urls are redacted in the audit log.
+fn selectors(source: Option<&Value>) -> serde_json::Map<String, Value> {
+    let mut out = serde_json::Map::new();
+    let Some(source) = source else {
+        return out;
+    };
+    for key in SELECTOR_KEYS {
+        if let Some(value) = source.get(*key).filter(|v| v.is_string()) {
+            out.insert((*key).to_string(), value.clone());
+        }
+    }
+    out
+}
+
+fn deploy_smoke_failed(entry: &Value) -> Vec<Suggestion> {
+    if str_at(entry, &["tool"]) != Some("pipeline")
+        || str_at(entry, &["action"]) != Some("deploy_smoke.failed")
+        || str_at(entry, &["details", "stage"]) != Some("smoke")
+    {
+        return Vec::new();
+    }
+    let details = entry.get("details").unwrap_or(&Value::Null);
+    let Some(url) = str_at(details, &["url"]) else {
+        return Vec::new();
+    };
+    let status = details.get("status").cloned().unwrap_or(Value::Null);
+    let expect_code = details
+        .get("expect_code")
+        .filter(|v| !v.is_null())
+        .cloned()
+        .unwrap_or_else(|| Value::from(200));
+
+    let mut out = vec![Suggestion {
+        rule: "deploy_smoke_failed",
+        score: 100,
+        tool: "api",
+        action: "smoke_http",
+        args: serde_json::json!({"url": url, "expect_code": expect_code}),
+        reason: format!(
+            "deploy_smoke failed at the smoke stage (status {}); retry the smoke check",
+            status
+        ),
+    }];
+    if let Some(service) = str_at(details, &["restart"]).filter(|s| SERVICE_NAME_RE.is_match(s)) {
+        let mut args = selectors(Some(details));
+        args.insert(
+            "command".to_string(),
+            Value::String(format!("journalctl -u {} -n 200 --no-pager", service)),
+        );
+        args.insert("apply".to_string(), Value::Bool(true));
+        out.push(Suggestion {
+            rule: "deploy_smoke_failed",
+            score: 90,
+            tool: "ssh",
+            action: "exec",
+            args: Value::Object(args),
+            reason: format!("inspect recent logs of the restarted service {}", service),
+        });
+    }
+    out
+}
+
+fn sql_missing_relation(entry: &Value) -> Vec<Suggestion> {
+    if !is_error(entry, "sql") {
+        return Vec::new();
+    }
+    let message = error_message(entry);
+    let relation = MISSING_RELATION_RE
+        .captures(message)
+        .and_then(|caps| caps.get(1))
+        .map(|m| m.as_str().to_string());
+    let sqlstate = str_at(entry, &["error", "details", "sqlstate"]);
+    if relation.is_none() && sqlstate != Some("42P01") {
+        return Vec::new();
+    }
+    let input = entry.get("input");
+    let schema = relation
+        .as_deref()
+        .and_then(|name| name.rsplit_once('.').map(|(schema, _)| schema.to_string()))
+        .or_else(|| {
+            input
+                .and_then(|v| str_at(v, &["schema"]))
+                .map(str::to_string)
+        })
+        .unwrap_or_else(|| "public".to_string());
+
+    let mut args = selectors(input);
+    args.insert("schema".to_string(), Value::String(schema.clone()));
+    vec![Suggestion {
+        rule: "sql_missing_relation",
+        score: 80,
+        tool: "sql",
+        action: "catalog_tables",
+        args: Value::Object(args),
+        reason: format!(
+            "relation {} does not exist; list the tables in schema {}",
+            relation.unwrap_or_else(|| "(unknown)".to_string()),
+            schema
+        ),
+    }]
+}
+
+fn ssh_host_key_mismatch(entry: &Value) -> Vec<Suggestion> {
+    if !is_error(entry, "ssh") || !error_message(entry).contains("host key mismatch") {
+        return Vec::new();
+    }
+    let Some(profile_name) = entry
+        .get("input")
+        .and_then(|v| str_at(v, &["profile_name"]))
+    else {
+        return Vec::new();
+    };
+    vec![Suggestion {
+        rule: "ssh_host_key_mismatch",
+        score: 85,
+        tool: "ssh",
+        action: "profile_upsert",
+        args: serde_json::json!({
+            "profile_name": profile_name,
+            "connection": {
+                "host_key_policy": "tofu",
+                "host_key_fingerprint_sha256": Value::Null,
+            },
+        }),
+        reason: format!(
+            "host key for profile {} changed; verify the new key out-of-band, then re-pin it via tofu",
+            profile_name
+        ),
+    }]
+}
+
+fn failed_job(job: &Value) -> Option<Suggestion> {
+    if str_at(job, &["status"]) != Some("failed") {
+        return None;
+    }
+    let job_id = str_at(job, &["job_id"])?;
+    Some(Suggestion {
+        rule: "job_failed",
+        score: 70,
+        tool: "job",
+        action: "job_logs_tail",
+        args: serde_json::json!({"job_id": job_id}),
+        reason: format!("job {} failed; tail its logs", job_id),
+    })
+}
+
+// `entries` and `jobs` are newest first. Suggestions from the preferred trace (explicit, or the
+// newest audit entry's) outrank older noise; within a trace, newer entries rank higher.
+pub fn next_actions_from_history(
+    entries: &[Value],
+    jobs: &[Value],
+    trace_id: Option<&str>,
+    limit: usize,
+) -> Vec<Value> {
+    let preferred = trace_id.or_else(|| entries.first().and_then(|e| str_at(e, &["trace_id"])));
+    let rank = |base: i64, trace: Option<&str>, index: usize| {
+        let bonus = if trace.is_some() && trace == preferred {
+            SAME_TRACE_BONUS
+        } else {
+            0
+        };
+        base + bonus - index as i64
+    };
+
+    let mut ranked: Vec<(i64, Value)> = Vec::new();
+    for (index, entry) in entries.iter().enumerate() {
+        let trace = str_at(entry, &["trace_id"]);
+        for rule in AUDIT_RULES {
+            for suggestion in rule(entry) {
+                let source = serde_json::json!({
+                    "trace_id": trace,
+                    "span_id": entry.get("span_id").cloned().unwrap_or(Value::Null),
+                    "timestamp": entry.get("timestamp").cloned().unwrap_or(Value::Null),
+                    "tool": entry.get("tool").cloned().unwrap_or(Value::Null),
+                    "action": entry.get("action").cloned().unwrap_or(Value::Null),
+                });
+                ranked.push((
+                    rank(suggestion.score, trace, index),
+                    render(suggestion, source),
+                ));
+            }
+        }
+    }
+    for (index, job) in jobs.iter().enumerate() {
+        if let Some(suggestion) = failed_job(job) {
+            let trace = str_at(job, &["trace_id"]);
+            let source = serde_json::json!({
+                "trace_id": trace,
+                "job_id": job.get("job_id").cloned().unwrap_or(Value::Null),
+            });
+            ranked.push((
+                rank(suggestion.score, trace, index),
+                render(suggestion, source),
+            ));
+        }
+    }
+
+    ranked.sort_by_key(|item| std::cmp::Reverse(item.0));
+    let mut seen: Vec<String> = Vec::new();
+    let mut out = Vec::new();
+    for (score, mut item) in ranked {
+        let key = format!("{}.{}:{}", item["tool"], item["action"], item["args"]);
+        if seen.contains(&key) {
+            continue;
+        }
+        seen.push(key);
+        item["score"] = Value::from(score);
+        out.push(item);
+        if out.len() >= limit.max(1) {
+            break;
+        }
+    }
+    out
+}
+
+fn render(suggestion: Suggestion, source: Value) -> Value {
+    serde_json::json!({
+        "rule": suggestion.rule,
+        "tool": suggestion.tool,
+        "action": suggestion.action,
+        "args": suggestion.args,
+        "reason": suggestion.reason,
+        "source": source,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn smoke_failure(trace: &str, restart: Value) -> Value {
+        serde_json::json!({
+            "timestamp": "2026-10-14T10:00:00Z",
+            "status": "ok",
+            "tool": "pipeline",
+            "action": "deploy_smoke.failed",
+            "trace_id": trace,
+            "details": {
+                "stage": "smoke",
+                "url": "https://app.example.com/health",
+                "status": 502,
+                "expect_code": 200,
+                "restart": restart,
+                "profile_name": "prod-web",
+            },
+        })
+    }
+
+    fn tool_error(tool: &str, trace: &str, message: &str, input: Value) -> Value {
+        serde_json::json!({
+            "status": "error",
+            "tool": tool,
+            "action": "query",
+            "trace_id": trace,
+            "input": input,
+            "error": {"kind": "internal", "code": "INTERNAL", "message": message},
+        })
+    }
+
+    #[test]
+    fn smoke_stage_failure_suggests_retry_and_service_logs() {
+        let entries = vec![smoke_failure("t1", serde_json::json!("app.service"))];
+        let actions = next_actions_from_history(&entries, &[], None, 5);
+        assert_eq!(actions.len(), 2);
+        assert_eq!(actions[0]["tool"], "api");
+        assert_eq!(actions[0]["action"], "smoke_http");
+        assert_eq!(actions[0]["args"]["url"], "https://app.example.com/health");
+        assert_eq!(actions[1]["action"], "exec");
+        assert_eq!(
+            actions[1]["args"]["command"],
+            "journalctl -u app.service -n 200 --no-pager"
+        );
+        assert_eq!(actions[1]["args"]["profile_name"], "prod-web");
+
+        let unsafe_service = vec![smoke_failure("t1", serde_json::json!("app; rm -rf /"))];
+        let actions = next_actions_from_history(&unsafe_service, &[], None, 5);
+        assert_eq!(actions.len(), 1);
+    }
+
+    #[test]
+    fn missing_relation_suggests_catalog_tables_for_its_schema() {
+        let entries = vec![
+            tool_error(
+                "sql",
+                "t1",
+                "PostgreSQL error: db error: ERROR: relation \"billing.invoices\" does not exist",
+                serde_json::json!({"profile_name": "prod-db", "connection_url": "[REDACTED]"}),
+            ),
+            tool_error(
+                "sql",
+                "t0",
+                "PostgreSQL error: db error: ERROR: relation \"orders\" does not exist",
+                serde_json::json!({"schema": "shop"}),
+            ),
+        ];
+        let actions = next_actions_from_history(&entries, &[], None, 5);
+        assert_eq!(actions[0]["action"], "catalog_tables");
+        assert_eq!(
+            actions[0]["args"],
+            serde_json::json!({"profile_name": "prod-db", "schema": "billing"})
+        );
+        assert_eq!(actions[1]["args"], serde_json::json!({"schema": "shop"}));
+
+        let other = vec![tool_error(
+            "sql",
+            "t1",
+            "syntax error at or near",
+            Value::Null,
+        )];
+        assert!(next_actions_from_history(&other, &[], None, 5).is_empty());
+    }
+
+    #[test]
+    fn host_key_mismatch_suggests_tofu_profile_upsert() {
+        let entries = vec![tool_error(
+            "ssh",
+            "t1",
+            "SSH host key mismatch (expected SHA256:old, got SHA256:new)",
+            serde_json::json!({"action": "exec", "profile_name": "prod-web"}),
+        )];
+        let actions = next_actions_from_history(&entries, &[], None, 5);
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0]["action"], "profile_upsert");
+        assert_eq!(actions[0]["args"]["profile_name"], "prod-web");
+        assert_eq!(actions[0]["args"]["connection"]["host_key_policy"], "tofu");
+        assert!(actions[0]["args"]["connection"]["host_key_fingerprint_sha256"].is_null());
+    }
+
+    #[test]
+    fn failed_jobs_suggest_log_tail() {
+        let jobs = vec![
+            serde_json::json!({"job_id": "job-1", "status": "failed"}),
+            serde_json::json!({"job_id": "job-2", "status": "succeeded"}),
+        ];
+        let actions = next_actions_from_history(&[], &jobs, None, 5);
+        assert_eq!(actions.len(), 1);
+        assert_eq!(actions[0]["args"]["job_id"], "job-1");
+    }
+
+    #[test]
+    fn preferred_trace_outranks_other_failures_and_duplicates_collapse() {
+        let mismatch = |trace: &str| {
+            tool_error(
+                "ssh",
+                trace,
+                "SSH host key mismatch (expected a, got b)",
+                serde_json::json!({"profile_name": "edge"}),
+            )
+        };
+        let entries = vec![
+            mismatch("recent"),
+            smoke_failure("older", Value::Null),
+            mismatch("older"),
+        ];
+        let actions = next_actions_from_history(&entries, &[], None, 5);
+        assert_eq!(actions.len(), 2);
+        assert_eq!(actions[0]["rule"], "ssh_host_key_mismatch");
+        assert_eq!(actions[0]["source"]["trace_id"], "recent");
+
+        let focused = next_actions_from_history(&entries, &[], Some("older"), 1);
+        assert_eq!(focused.len(), 1);
+        assert_eq!(focused[0]["rule"], "deploy_smoke_failed");
+    }
+}
@@ -0,0 +1,321 @@
+use crate::errors::ToolError;
+use crate::utils::shell::{ensure_shell_arg, shell_quote};
+use serde_json::Value;
+
+pub const DEFAULT_POLL_INTERVAL_MS: u64 = 2_000;
+pub const MIN_POLL_INTERVAL_MS: u64 = 250;
+// Background watches without timeout_ms give up after an hour.
+pub const DEFAULT_BACKGROUND_TIMEOUT_MS: u64 = 60 * 60 * 1000;
+const MAX_INLINE_MATCHES: usize = 50;
+const CONDITIONS: &[&str] = &["exists", "mtime_after", "size_stable_for_ms"];
+
+// One exec per poll: `$WATCH_PATH` is expanded unquoted with an empty IFS, so a glob matches
+// files but the value is never split or evaluated. GNU/busybox `stat -c` first, BSD `stat -f`
+// as the fallback; each match prints `<size> <mtime> <path>`.
+pub fn watch_stat_script(path: &str) -> String {
+    [
+        "IFS=".to_string(),
+        format!("WATCH_PATH={}", shell_quote(path)),
+        "for f in $WATCH_PATH; do".to_string(),
+        "  [ -e \"$f\" ] || continue".to_string(),
+        "  stat -c '%s %Y %n' -- \"$f\" 2>/dev/null || stat -f '%z %m %N' -- \"$f\" 2>/dev/null"
+            .to_string(),
+        "done".to_string(),
+        "true".to_string(),
+    ]
+    .join("\n")
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WatchMatch {
+    pub path: String,
+    pub size: u64,
+    pub mtime: i64,
+}
+
+// What one poll saw: every match of the path or glob, in the order the shell listed them.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WatchStat {
+    pub matches: Vec<WatchMatch>,
+}
+
+impl WatchStat {
+    pub fn parse(stdout: &str) -> Self {
+        let matches = stdout
+            .lines()
+            .filter_map(|line| {
+                let mut parts = line.splitn(3, ' ');
+                let size = parts.next()?.trim().parse::<u64>().ok()?;
+                let mtime = parts.next()?.trim().parse::<i64>().ok()?;
+                let path = parts.next()?.to_string();
+                Some(WatchMatch { path, size, mtime })
+            })
+            .collect();
+        Self { matches }
+    }
+
+    pub fn exists(&self) -> bool {
+        !self.matches.is_empty()
+    }
+
+    pub fn total_size(&self) -> u64 {
+        self.matches.iter().map(|m| m.size).sum()
+    }
+
+    pub fn latest_mtime(&self) -> Option<i64> {
+        self.matches.iter().map(|m| m.mtime).max()
+    }
+
+    pub fn to_json(&self) -> Value {
+        let mtime = self.latest_mtime();
+        serde_json::json!({
+            "exists": self.exists(),
+            "count": self.matches.len(),
+            "size": self.total_size(),
+            "mtime": mtime,
+            "mtime_iso": mtime
+                .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+                .map(|at| at.to_rfc3339()),
+            "matches": self
+                .matches
+                .iter()
+                .take(MAX_INLINE_MATCHES)
+                .map(|m| serde_json::json!({"path": m.path, "size": m.size, "mtime": m.mtime}))
+                .collect::<Vec<_>>(),
+            "matches_truncated": self.matches.len() > MAX_INLINE_MATCHES,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WatchCondition {
+    Exists,
+    // Unix seconds; met once any match was modified later.
+    MtimeAfter(i64),
+    // Met once the matches and their sizes stayed the same for this long.
+    SizeStableFor(u64),
+}
+
+impl WatchCondition {
+    // `condition` may be omitted when exactly one of mtime_after / size_stable_for_ms is set.
+    pub fn parse(args: &Value) -> Result<Self, ToolError> {
+        let mtime_after = match args.get("mtime_after") {
+            None | Some(Value::Null) => None,
+            Some(value) => Some(parse_mtime_after(value)?),
+        };
+        let stable_for = match args.get("size_stable_for_ms") {
+            None | Some(Value::Null) => None,
+            Some(value) => Some(value.as_u64().filter(|ms| *ms > 0).ok_or_else(|| {
+                ToolError::invalid_params("size_stable_for_ms must be a positive integer")
+            })?),
+        };
+        let named = args
+            .get("condition")
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|s| !s.is_empty());
+        match (named, mtime_after, stable_for) {
+            (_, Some(_), Some(_)) => Err(ToolError::invalid_params(
+                "watch_path takes one condition: mtime_after or size_stable_for_ms",
+            )),
+            (None | Some("exists"), None, None) => Ok(WatchCondition::Exists),
+            (None | Some("mtime_after"), Some(after), None) => {
+                Ok(WatchCondition::MtimeAfter(after))
+            }
+            (None | Some("size_stable_for_ms"), None, Some(ms)) => {
+                Ok(WatchCondition::SizeStableFor(ms))
+            }
+            (Some("exists"), _, _) => Err(ToolError::invalid_params(
+                "condition exists takes no mtime_after or size_stable_for_ms",
+            )),
+            (Some(name), _, _) if CONDITIONS.contains(&name) => Err(ToolError::invalid_params(
+                format!("condition {} requires {}", name, name),
+            )
+            .with_hint(
+                "Example: {\"condition\": \"size_stable_for_ms\", \"size_stable_for_ms\": 10000}",
+            )),
+            (Some(name), _, _) => Err(ToolError::invalid_params(format!(
+                "Unknown watch condition: {}",
+                name
+            ))
+            .with_hint(format!("Known conditions: {}", CONDITIONS.join(", ")))),
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        match self {
+            WatchCondition::Exists => serde_json::json!({"type": "exists"}),
+            WatchCondition::MtimeAfter(after) => {
+                serde_json::json!({"type": "mtime_after", "mtime_after": after})
+            }
+            WatchCondition::SizeStableFor(ms) => {
+                serde_json::json!({"type": "size_stable_for_ms", "size_stable_for_ms": ms})
+            }
+        }
+    }
+}
+
+fn parse_mtime_after(value: &Value) -> Result<i64, ToolError> {
+    if let Some(secs) = value.as_i64() {
+        return Ok(secs);
+    }
+    value
+        .as_str()
+        .and_then(|text| chrono::DateTime::parse_from_rfc3339(text.trim()).ok())
+        .map(|at| at.timestamp())
+        .ok_or_else(|| {
+            ToolError::invalid_params("mtime_after must be unix seconds or an RFC3339 timestamp")
+        })
+}
+
+#[derive(Clone, Debug)]
+pub struct WatchSpec {
+    pub remote_path: String,
+    pub condition: WatchCondition,
+    pub poll_interval_ms: u64,
+}
+
+impl WatchSpec {
+    pub fn parse(args: &Value) -> Result<Self, ToolError> {
+        let remote_path = args
+            .get("remote_path")
+            .or_else(|| args.get("path"))
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .ok_or_else(|| ToolError::invalid_params("remote_path is required"))?
+            .to_string();
+        ensure_shell_arg(&remote_path, "remote_path")?;
+        let poll_interval_ms = match args.get("poll_interval_ms") {
+            None | Some(Value::Null) => DEFAULT_POLL_INTERVAL_MS,
+            Some(value) => value
+                .as_u64()
+                .filter(|ms| *ms >= MIN_POLL_INTERVAL_MS)
+                .ok_or_else(|| {
+                    ToolError::invalid_params(format!(
+                        "poll_interval_ms must be an integer >= {}",
+                        MIN_POLL_INTERVAL_MS
+                    ))
+                })?,
+        };
+        Ok(Self {
+            remote_path,
+            condition: WatchCondition::parse(args)?,
+            poll_interval_ms,
+        })
+    }
+}
+
+// Carries what earlier polls saw, which size_stable_for_ms needs.
+#[derive(Debug, Default)]
+pub struct WatchState {
+    last: Option<Vec<(String, u64)>>,
+    stable_since_ms: u64,
+}
+
+impl WatchState {
+    // Records a poll taken `elapsed_ms` into the watch and says whether the condition holds.
+    pub fn observe(
+        &mut self,
+        condition: &WatchCondition,
+        stat: &WatchStat,
+        elapsed_ms: u64,
+    ) -> bool {
+        match condition {
+            WatchCondition::Exists => stat.exists(),
+            WatchCondition::MtimeAfter(after) => stat.latest_mtime().is_some_and(|m| m > *after),
+            WatchCondition::SizeStableFor(ms) => {
+                let current: Vec<(String, u64)> = stat
+                    .matches
+                    .iter()
+                    .map(|m| (m.path.clone(), m.size))
+                    .collect();
+                if self.last.as_ref() != Some(&current) {
+                    self.last = Some(current);
+                    self.stable_since_ms = elapsed_ms;
+                    return false;
+                }
+                stat.exists() && elapsed_ms.saturating_sub(self.stable_since_ms) >= *ms
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn conditions_parse_from_args() {
+        let parse = |args: Value| WatchCondition::parse(&args);
+        assert_eq!(parse(json!({})).unwrap(), WatchCondition::Exists);
+        assert_eq!(
+            parse(json!({"mtime_after": "2026-01-01T00:00:00Z"})).unwrap(),
+            WatchCondition::MtimeAfter(1_767_225_600)
+        );
+        assert_eq!(
+            parse(json!({"condition": "size_stable_for_ms", "size_stable_for_ms": 5000})).unwrap(),
+            WatchCondition::SizeStableFor(5000)
+        );
+        for (args, message) in [
+            (
+                json!({"mtime_after": 1, "size_stable_for_ms": 1}),
+                "watch_path takes one condition: mtime_after or size_stable_for_ms",
+            ),
+            (
+                json!({"condition": "mtime_after"}),
+                "condition mtime_after requires mtime_after",
+            ),
+            (
+                json!({"condition": "gone"}),
+                "Unknown watch condition: gone",
+            ),
+            (
+                json!({"mtime_after": "yesterday"}),
+                "mtime_after must be unix seconds or an RFC3339 timestamp",
+            ),
+        ] {
+            assert_eq!(parse(args).unwrap_err().message, message);
+        }
+    }
+
+    #[test]
+    fn size_stable_waits_for_an_unchanged_listing() {
+        let stat = |size: u64| WatchStat::parse(&format!("{} 1700000000 /in/a b.csv\n", size));
+        assert_eq!(stat(10).matches[0].path, "/in/a b.csv");
+        let condition = WatchCondition::SizeStableFor(1000);
+        let mut state = WatchState::default();
+        assert!(!state.observe(&condition, &stat(10), 0));
+        assert!(!state.observe(&condition, &stat(20), 500));
+        assert!(!state.observe(&condition, &stat(20), 1200));
+        assert!(state.observe(&condition, &stat(20), 1500));
+        assert!(!state.observe(&condition, &WatchStat::default(), 3000));
+        assert!(!state.observe(&condition, &WatchStat::default(), 9000));
+    }
+
+    #[test]
+    fn script_lists_glob_matches_locally() {
+        let dir = std::env::temp_dir().join(format!("infra-watch-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).expect("create dir");
+        std::fs::write(dir.join("drop 1.csv"), b"abc").expect("write");
+        std::fs::write(dir.join("drop2.csv"), b"hello").expect("write");
+        std::fs::write(dir.join("$(touch pwned).csv"), b"").expect("write");
+        let run = |path: String| {
+            let out = std::process::Command::new("sh")
+                .arg("-c")
+                .arg(watch_stat_script(&path))
+                .output()
+                .expect("run sh");
+            assert!(out.status.success());
+            WatchStat::parse(&String::from_utf8_lossy(&out.stdout))
+        };
+        let stat = run(format!("{}/*.csv", dir.display()));
+        assert_eq!(stat.matches.len(), 3);
+        assert_eq!(stat.total_size(), 8);
+        assert!(!dir.join("pwned").exists());
+        assert!(!run(format!("{}/missing-*", dir.display())).exists());
+        assert_eq!(run(format!("{}/drop 1.csv", dir.display())).total_size(), 3);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -1,7 +1,8 @@
+use crate::utils::feature_flags;
 use std::env;
 use std::path::{Path, PathBuf};
 
-fn normalize_env_path(value: Option<String>) -> Option<PathBuf> {
+pub(crate) fn normalize_env_path(value: Option<String>) -> Option<PathBuf> {
     let raw = value?;
     let trimmed = raw.trim();
     if trimmed.is_empty() {
@@ -14,10 +15,6 @@ fn normalize_env_path(value: Option<String>) -> Option<PathBuf> {
     Some(PathBuf::from(trimmed))
 }
 
-fn infra_env_path(key: &str) -> Option<PathBuf> {
-    normalize_env_path(env::var(key).ok())
-}
-
 fn resolve_home_dir() -> Option<PathBuf> {
     env::var("HOME").ok().map(PathBuf::from)
 }
@@ -36,7 +33,7 @@ fn resolve_entry_dir() -> Option<PathBuf> {
 }
 
 pub fn resolve_profile_base_dir() -> PathBuf {
-    if let Some(path) = infra_env_path("INFRA_PROFILES_DIR") {
+    if let Some(path) = feature_flags::PROFILES_DIR.path() {
         return path;
     }
     if let Some(path) = resolve_xdg_state_dir() {
@@ -46,14 +43,14 @@ pub fn resolve_profile_base_dir() -> PathBuf {
 }
 
 pub fn resolve_profile_key_path() -> PathBuf {
-    if let Some(path) = infra_env_path("INFRA_PROFILE_KEY_PATH") {
+    if let Some(path) = feature_flags::PROFILE_KEY_PATH.path() {
         return path;
     }
     resolve_profile_base_dir().join(".infra.key")
 }
 
 pub fn resolve_store_mode() -> &'static str {
-    if infra_env_path("INFRA_PROFILES_DIR").is_some() {
+    if feature_flags::PROFILES_DIR.path().is_some() {
         return "custom";
     }
     if resolve_xdg_state_dir().is_some() {
@@ -72,35 +69,35 @@ pub fn resolve_store_info() -> serde_json::Value {
 }
 
 pub fn resolve_profiles_path() -> PathBuf {
-    if let Some(path) = infra_env_path("INFRA_PROFILES_PATH") {
+    if let Some(path) = feature_flags::PROFILES_PATH.path() {
         return path;
     }
     resolve_profile_base_dir().join("profiles.json")
 }
 
 pub fn resolve_state_path() -> PathBuf {
-    if let Some(path) = infra_env_path("INFRA_STATE_PATH") {
+    if let Some(path) = feature_flags::STATE_PATH.path() {
         return path;
     }
     resolve_profile_base_dir().join("state.json")
 }
 
 pub fn resolve_projects_path() -> PathBuf {
-    if let Some(path) = infra_env_path("INFRA_PROJECTS_PATH") {
+    if let Some(path) = feature_flags::PROJECTS_PATH.path() {
         return path;
     }
     resolve_profile_base_dir().join("projects.json")
 }
 
 pub fn resolve_runbooks_path() -> PathBuf {
-    if let Some(path) = infra_env_path("INFRA_RUNBOOKS_PATH") {
+    if let Some(path) = feature_flags::RUNBOOKS_PATH.path() {
         return path;
     }
     resolve_profile_base_dir().join("runbooks.json")
 }
 
 pub fn resolve_default_runbooks_path() -> Option<PathBuf> {
-    if let Some(path) = infra_env_path("INFRA_DEFAULT_RUNBOOKS_PATH") {
+    if let Some(path) = feature_flags::DEFAULT_RUNBOOKS_PATH.path() {
         return Some(path);
     }
     let entry_dir = resolve_entry_dir();
@@ -119,14 +116,14 @@ pub fn resolve_default_runbooks_path() -> Option<PathBuf> {
 }
 
 pub fn resolve_capabilities_path() -> PathBuf {
-    if let Some(path) = infra_env_path("INFRA_CAPABILITIES_PATH") {
+    if let Some(path) = feature_flags::CAPABILITIES_PATH.path() {
         return path;
     }
     resolve_profile_base_dir().join("capabilities.json")
 }
 
 pub fn resolve_default_capabilities_path() -> Option<PathBuf> {
-    if let Some(path) = infra_env_path("INFRA_DEFAULT_CAPABILITIES_PATH") {
+    if let Some(path) = feature_flags::DEFAULT_CAPABILITIES_PATH.path() {
         return Some(path);
     }
     let entry_dir = resolve_entry_dir();
@@ -145,63 +142,77 @@ pub fn resolve_default_capabilities_path() -> Option<PathBuf> {
 }
 
 pub fn resolve_context_path() -> PathBuf {
-    if let Some(path) = infra_env_path("INFRA_CONTEXT_PATH") {
+    if let Some(path) = feature_flags::CONTEXT_PATH.path() {
         return path;
     }
     resolve_profile_base_dir().join("context.json")
 }
 
 pub fn resolve_evidence_dir() -> PathBuf {
-    if let Some(path) = infra_env_path("INFRA_EVIDENCE_DIR") {
+    if let Some(path) = feature_flags::EVIDENCE_DIR.path() {
         return path;
     }
     resolve_profile_base_dir().join(".infra").join("evidence")
 }
 
 pub fn resolve_aliases_path() -> PathBuf {
-    if let Some(path) = infra_env_path("INFRA_ALIASES_PATH") {
+    if let Some(path) = feature_flags::ALIASES_PATH.path() {
         return path;
     }
     resolve_profile_base_dir().join("aliases.json")
 }
 
 pub fn resolve_presets_path() -> PathBuf {
-    if let Some(path) = infra_env_path("INFRA_PRESETS_PATH") {
+    if let Some(path) = feature_flags::PRESETS_PATH.path() {
         return path;
     }
     resolve_profile_base_dir().join("presets.json")
 }
 
 pub fn resolve_audit_path() -> PathBuf {
-    if let Some(path) = infra_env_path("INFRA_AUDIT_PATH") {
+    if let Some(path) = feature_flags::AUDIT_PATH.path() {
         return path;
     }
     resolve_profile_base_dir().join("audit.jsonl")
 }
 
 pub fn resolve_jobs_path() -> PathBuf {
-    if let Some(path) = infra_env_path("INFRA_JOBS_PATH") {
+    if let Some(path) = feature_flags::JOBS_PATH.path() {
         return path;
     }
     resolve_profile_base_dir().join("jobs.json")
 }
 
+pub fn resolve_api_fixtures_dir() -> PathBuf {
+    if let Some(path) = feature_flags::API_FIXTURES_DIR.path() {
+        return path;
+    }
+    resolve_profile_base_dir().join("api-fixtures")
+}
+
 pub fn resolve_cache_dir() -> PathBuf {
-    if let Some(path) = infra_env_path("INFRA_CACHE_DIR") {
+    if let Some(path) = feature_flags::CACHE_DIR.path() {
         return path;
     }
     resolve_profile_base_dir().join("cache")
 }
 
+pub fn resolve_pipeline_checkpoints_dir() -> PathBuf {
+    if let Some(path) = feature_flags::PIPELINE_CHECKPOINTS_DIR.path() {
+        return path;
+    }
+    resolve_profile_base_dir().join("pipeline-checkpoints")
+}
+
 pub fn resolve_store_db_path() -> PathBuf {
-    if let Some(path) = infra_env_path("INFRA_STORE_DB_PATH") {
+    if let Some(path) = feature_flags::STORE_DB_PATH.path() {
         return path;
     }
     resolve_profile_base_dir().join("infra.db")
 }
 
 pub fn resolve_context_repo_root() -> Option<PathBuf> {
-    infra_env_path("INFRA_CONTEXT_REPO_ROOT")
+    feature_flags::CONTEXT_REPO_ROOT.path()
 }
 
 pub fn ensure_dir_exists(path: &Path) -> std::io::Result<()> {
@@ -0,0 +1,240 @@
+use crate::errors::ToolError;
+use crate::utils::sql::quote_qualified_identifier;
+use serde_json::Value;
+
+// Rows of a failed insert_bulk chunk kept (redacted) in its report.
+pub const MAX_FAILED_SAMPLE_ROWS: usize = 3;
+
+// Extra column an upsert returns per row: true when the row was inserted, false when an
+// existing row was updated (only the updated version carries the old transaction in xmax).
+pub const INSERTED_FLAG: &str = "__infra_inserted";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OnConflict {
+    Error,
+    Ignore,
+    Upsert,
+}
+
+impl OnConflict {
+    pub fn parse(value: Option<&Value>) -> Result<Self, ToolError> {
+        let raw = match value {
+            None | Some(Value::Null) => return Ok(OnConflict::Error),
+            Some(Value::String(raw)) => raw.trim(),
+            Some(_) => "",
+        };
+        match raw {
+            "error" => Ok(OnConflict::Error),
+            "ignore" => Ok(OnConflict::Ignore),
+            "upsert" => Ok(OnConflict::Upsert),
+            _ => Err(ToolError::invalid_params(
+                "on_conflict must be one of: error, ignore, upsert",
+            )),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OnConflict::Error => "error",
+            OnConflict::Ignore => "ignore",
+            OnConflict::Upsert => "upsert",
+        }
+    }
+}
+
+fn quoted_list(columns: &[String]) -> Result<String, ToolError> {
+    Ok(columns
+        .iter()
+        .map(|col| quote_qualified_identifier(col))
+        .collect::<Result<Vec<_>, _>>()?
+        .join(", "))
+}
+
+// ` ON CONFLICT ...` for an INSERT of `columns`, empty for `error`. `ignore` without a target
+// skips rows hitting any unique constraint; `upsert` needs the target and by default updates
+// every inserted column outside it.
+pub fn conflict_clause(
+    mode: OnConflict,
+    columns: &[String],
+    target: &[String],
+    update: Option<&[String]>,
+) -> Result<String, ToolError> {
+    match mode {
+        OnConflict::Error => Ok(String::new()),
+        OnConflict::Ignore if target.is_empty() => Ok(" ON CONFLICT DO NOTHING".to_string()),
+        OnConflict::Ignore => Ok(format!(
+            " ON CONFLICT ({}) DO NOTHING",
+            quoted_list(target)?
+        )),
+        OnConflict::Upsert => {
+            if target.is_empty() {
+                return Err(ToolError::invalid_params(
+                    "on_conflict=upsert needs conflict_columns (or primary_key)",
+                )
+                .with_hint(
+                    "Name the unique key the rows collide on, e.g. conflict_columns: [\"id\"].",
+                ));
+            }
+            let update: Vec<String> = match update {
+                Some(update) => update.to_vec(),
+                None => columns
+                    .iter()
+                    .filter(|col| !target.contains(col))
+                    .cloned()
+                    .collect(),
+            };
+            if let Some(unknown) = update.iter().find(|col| !columns.contains(col)) {
+                return Err(ToolError::invalid_params(format!(
+                    "update_columns lists '{}', which is not an inserted column",
+                    unknown
+                )));
+            }
+            if update.is_empty() {
+                return Err(ToolError::invalid_params(
+                    "on_conflict=upsert has no columns to update",
+                )
+                .with_hint("Every inserted column is a conflict column; use on_conflict=ignore to skip existing rows."));
+            }
+            let assignments = update
+                .iter()
+                .map(|col| {
+                    let quoted = quote_qualified_identifier(col)?;
+                    Ok(format!("{} = EXCLUDED.{}", quoted, quoted))
+                })
+                .collect::<Result<Vec<_>, ToolError>>()?;
+            Ok(format!(
+                " ON CONFLICT ({}) DO UPDATE SET {}",
+                quoted_list(target)?,
+                assignments.join(", ")
+            ))
+        }
+    }
+}
+
+// The caller's RETURNING clause plus the inserted/updated flag.
+pub fn upsert_returning(returning: &str) -> String {
+    let flag = format!("(xmax = 0) AS \"{}\"", INSERTED_FLAG);
+    if returning.is_empty() {
+        format!(" RETURNING {}", flag)
+    } else {
+        format!("{}, {}", returning, flag)
+    }
+}
+
+pub fn ensure_row_shape(row: &Value) -> Result<(), ToolError> {
+    if row.is_object() || row.is_array() {
+        Ok(())
+    } else {
+        Err(ToolError::invalid_params(
+            "Each row must be an object or array",
+        ))
+    }
+}
+
+// One row's parameters in column order; arrays are positional. Built per chunk, so only the
+// chunk in flight is ever held as parameters.
+pub fn row_values(row: &Value, columns: &[String]) -> Vec<Value> {
+    match row {
+        Value::Object(obj) => columns
+            .iter()
+            .map(|col| obj.get(col).cloned().unwrap_or(Value::Null))
+            .collect(),
+        Value::Array(arr) => (0..columns.len())
+            .map(|idx| arr.get(idx).cloned().unwrap_or(Value::Null))
+            .collect(),
+        _ => vec![Value::Null; columns.len()],
+    }
+}
+
+// A row as a column-keyed object, ready for a table redaction plan.
+pub fn sample_row(row: &Value, columns: &[String]) -> Value {
+    Value::Object(
+        columns
+            .iter()
+            .cloned()
+            .zip(row_values(row, columns))
+            .collect(),
+    )
+}
+
+// Postgres puts the offending key values in DETAIL ("Key (email)=(a@example.com) already
+// exists"); they are dropped when the table has redacted columns.
+pub fn failure_message(message: &str, redacting: bool) -> String {
+    if !redacting {
+        return message.to_string();
+    }
+    message
+        .lines()
+        .filter(|line| !line.starts_with("DETAIL:"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn cols(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn conflict_clauses_quote_targets_and_default_the_update_set() {
+        let columns = cols(&["id", "order", "name"]);
+        assert_eq!(
+            conflict_clause(OnConflict::Error, &columns, &[], None).unwrap(),
+            ""
+        );
+        assert_eq!(
+            conflict_clause(OnConflict::Ignore, &columns, &[], None).unwrap(),
+            " ON CONFLICT DO NOTHING"
+        );
+        assert_eq!(
+            conflict_clause(OnConflict::Upsert, &columns, &cols(&["id"]), None).unwrap(),
+            " ON CONFLICT (\"id\") DO UPDATE SET \"order\" = EXCLUDED.\"order\", \"name\" = EXCLUDED.\"name\""
+        );
+        assert_eq!(
+            conflict_clause(
+                OnConflict::Upsert,
+                &columns,
+                &cols(&["id"]),
+                Some(&cols(&["name"]))
+            )
+            .unwrap(),
+            " ON CONFLICT (\"id\") DO UPDATE SET \"name\" = EXCLUDED.\"name\""
+        );
+        for (target, update) in [
+            (cols(&[]), None),
+            (cols(&["id", "order", "name"]), None),
+            (cols(&["id"]), Some(cols(&["missing"]))),
+        ] {
+            assert!(
+                conflict_clause(OnConflict::Upsert, &columns, &target, update.as_deref()).is_err()
+            );
+        }
+        assert!(OnConflict::parse(Some(&json!("merge"))).is_err());
+        assert_eq!(OnConflict::parse(None).unwrap(), OnConflict::Error);
+    }
+
+    #[test]
+    fn rows_map_to_columns_and_details_drop_when_redacting() {
+        let columns = cols(&["id", "email"]);
+        assert_eq!(row_values(&json!([1]), &columns), [json!(1), Value::Null]);
+        assert_eq!(
+            sample_row(&json!({"email": "a@example.com", "extra": 1}), &columns),
+            json!({"id": null, "email": "a@example.com"})
+        );
+        assert!(ensure_row_shape(&json!("row")).is_err());
+        assert_eq!(
+            upsert_returning(""),
+            " RETURNING (xmax = 0) AS \"__infra_inserted\""
+        );
+        let message = "PostgreSQL error: db error: ERROR: duplicate key\nDETAIL: Key (email)=(a@example.com) already exists.";
+        assert_eq!(
+            failure_message(message, true),
+            "PostgreSQL error: db error: ERROR: duplicate key"
+        );
+        assert_eq!(failure_message(message, false), message);
+    }
+}
@@ -0,0 +1,703 @@
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+pub const DEFAULT_DIFF_SCHEMAS: &[&str] = &["public"];
+// Items (tables, columns, indexes, constraints) kept inline; the full diff goes to an artifact.
+pub const MAX_INLINE_DIFF_ITEMS: usize = 200;
+
+const COLUMNS_SQL: &str = "SELECT n.nspname AS schema, c.relname AS table, a.attname AS column, format_type(a.atttypid, a.atttypmod) AS type, NOT a.attnotnull AS nullable, pg_get_expr(d.adbin, d.adrelid) AS default FROM pg_attribute a JOIN pg_class c ON c.oid = a.attrelid JOIN pg_namespace n ON n.oid = c.relnamespace LEFT JOIN pg_attrdef d ON d.adrelid = a.attrelid AND d.adnum = a.attnum WHERE c.relkind IN ('r', 'p') AND a.attnum > 0 AND NOT a.attisdropped AND n.nspname IN ({schemas}) ORDER BY n.nspname, c.relname, a.attnum";
+// Indexes backing primary key, unique and exclusion constraints are reported as constraints.
+const INDEXES_SQL: &str = "SELECT n.nspname AS schema, t.relname AS table, i.relname AS name, pg_get_indexdef(i.oid) AS definition FROM pg_index x JOIN pg_class i ON i.oid = x.indexrelid JOIN pg_class t ON t.oid = x.indrelid JOIN pg_namespace n ON n.oid = t.relnamespace WHERE t.relkind IN ('r', 'p') AND n.nspname IN ({schemas}) AND NOT EXISTS (SELECT 1 FROM pg_constraint k WHERE k.conindid = i.oid AND k.conrelid = t.oid AND k.contype IN ('p', 'u', 'x')) ORDER BY 1, 2, 3";
+const CONSTRAINTS_SQL: &str = "SELECT n.nspname AS schema, t.relname AS table, k.conname AS name, k.contype::text AS type, pg_get_constraintdef(k.oid) AS definition FROM pg_constraint k JOIN pg_class t ON t.oid = k.conrelid JOIN pg_namespace n ON n.oid = t.relnamespace WHERE t.relkind IN ('r', 'p') AND n.nspname IN ({schemas}) ORDER BY 1, 2, 3";
+
+// The three catalog reads for `schema_count` schemas bound as $1..$n.
+pub fn catalog_queries(schema_count: usize) -> [String; 3] {
+    let placeholders = (1..=schema_count.max(1))
+        .map(|idx| format!("${}", idx))
+        .collect::<Vec<_>>()
+        .join(", ");
+    [COLUMNS_SQL, INDEXES_SQL, CONSTRAINTS_SQL].map(|sql| sql.replace("{schemas}", &placeholders))
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColumnDef {
+    pub data_type: String,
+    pub nullable: bool,
+    pub default: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConstraintDef {
+    pub kind: String,
+    pub definition: String,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct TableDef {
+    pub schema: String,
+    pub name: String,
+    // In ordinal order.
+    pub columns: Vec<(String, ColumnDef)>,
+    pub indexes: BTreeMap<String, String>,
+    pub constraints: BTreeMap<String, ConstraintDef>,
+}
+
+impl TableDef {
+    fn key(&self) -> String {
+        format!("{}.{}", self.schema, self.name)
+    }
+
+    fn qualified(&self) -> String {
+        format!("{}.{}", quote_ident(&self.schema), quote_ident(&self.name))
+    }
+
+    fn column(&self, name: &str) -> Option<&ColumnDef> {
+        self.columns
+            .iter()
+            .find(|(column, _)| column == name)
+            .map(|(_, def)| def)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SchemaCatalog {
+    pub tables: BTreeMap<String, TableDef>,
+}
+
+fn text(row: &Value, key: &str) -> String {
+    row.get(key)
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .to_string()
+}
+
+// `ignore` entries match a table name or `schema.table`.
+fn is_ignored(ignore: &[String], schema: &str, table: &str) -> bool {
+    ignore
+        .iter()
+        .any(|entry| entry == table || *entry == format!("{}.{}", schema, table))
+}
+
+impl SchemaCatalog {
+    fn table_for(&mut self, row: &Value, ignore: &[String]) -> Option<&mut TableDef> {
+        let (schema, name) = (text(row, "schema"), text(row, "table"));
+        if is_ignored(ignore, &schema, &name) {
+            return None;
+        }
+        Some(
+            self.tables
+                .entry(format!("{}.{}", schema, name))
+                .or_insert_with(|| TableDef {
+                    schema,
+                    name,
+                    ..Default::default()
+                }),
+        )
+    }
+
+    pub fn from_rows(
+        columns: &[Value],
+        indexes: &[Value],
+        constraints: &[Value],
+        ignore: &[String],
+    ) -> Self {
+        let mut catalog = Self::default();
+        for row in columns {
+            if let Some(table) = catalog.table_for(row, ignore) {
+                table.columns.push((
+                    text(row, "column"),
+                    ColumnDef {
+                        data_type: text(row, "type"),
+                        nullable: row
+                            .get("nullable")
+                            .and_then(|v| v.as_bool())
+                            .unwrap_or(true),
+                        default: row
+                            .get("default")
+                            .and_then(|v| v.as_str())
+                            .map(|s| s.to_string()),
+                    },
+                ));
+            }
+        }
+        for row in indexes {
+            if let Some(table) = catalog.table_for(row, ignore) {
+                table
+                    .indexes
+                    .insert(text(row, "name"), text(row, "definition"));
+            }
+        }
+        for row in constraints {
+            if let Some(table) = catalog.table_for(row, ignore) {
+                table.constraints.insert(
+                    text(row, "name"),
+                    ConstraintDef {
+                        kind: text(row, "type"),
+                        definition: text(row, "definition"),
+                    },
+                );
+            }
+        }
+        catalog
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Side {
+    A,
+    B,
+}
+
+enum Change<'a> {
+    Table(Side, &'a TableDef),
+    Column(Side, &'a TableDef, &'a str, &'a ColumnDef),
+    ColumnChanged(&'a TableDef, &'a str, &'a ColumnDef, &'a ColumnDef),
+    Index(Side, &'a TableDef, &'a str, &'a str),
+    IndexChanged(&'a TableDef, &'a str, &'a str, &'a str),
+    Constraint(Side, &'a TableDef, &'a str, &'a ConstraintDef),
+    ConstraintChanged(&'a TableDef, &'a str, &'a ConstraintDef, &'a ConstraintDef),
+}
+
+fn one_sided<'a, T>(
+    a: &'a BTreeMap<String, T>,
+    b: &'a BTreeMap<String, T>,
+) -> impl Iterator<Item = (Side, &'a String, &'a T)> {
+    let only_a = a
+        .iter()
+        .filter(|(key, _)| !b.contains_key(*key))
+        .map(|(key, value)| (Side::A, key, value));
+    let only_b = b
+        .iter()
+        .filter(|(key, _)| !a.contains_key(*key))
+        .map(|(key, value)| (Side::B, key, value));
+    only_a.chain(only_b)
+}
+
+fn changes<'a>(a: &'a SchemaCatalog, b: &'a SchemaCatalog) -> Vec<Change<'a>> {
+    let mut out = Vec::new();
+    for (side, _, table) in one_sided(&a.tables, &b.tables) {
+        out.push(Change::Table(side, table));
+    }
+    for (key, table_a) in &a.tables {
+        let Some(table_b) = b.tables.get(key) else {
+            continue;
+        };
+        for (name, column) in &table_a.columns {
+            match table_b.column(name) {
+                None => out.push(Change::Column(Side::A, table_a, name, column)),
+                Some(other) if other != column => {
+                    out.push(Change::ColumnChanged(table_a, name, column, other))
+                }
+                Some(_) => {}
+            }
+        }
+        for (name, column) in &table_b.columns {
+            if table_a.column(name).is_none() {
+                out.push(Change::Column(Side::B, table_b, name, column));
+            }
+        }
+        for (side, name, definition) in one_sided(&table_a.indexes, &table_b.indexes) {
+            let table = if side == Side::A { table_a } else { table_b };
+            out.push(Change::Index(side, table, name, definition));
+        }
+        for (name, definition) in &table_a.indexes {
+            if let Some(other) = table_b
+                .indexes
+                .get(name)
+                .filter(|other| *other != definition)
+            {
+                out.push(Change::IndexChanged(table_a, name, definition, other));
+            }
+        }
+        for (side, name, constraint) in one_sided(&table_a.constraints, &table_b.constraints) {
+            let table = if side == Side::A { table_a } else { table_b };
+            out.push(Change::Constraint(side, table, name, constraint));
+        }
+        for (name, constraint) in &table_a.constraints {
+            if let Some(other) = table_b
+                .constraints
+                .get(name)
+                .filter(|other| *other != constraint)
+            {
+                out.push(Change::ConstraintChanged(table_a, name, constraint, other));
+            }
+        }
+    }
+    out
+}
+
+fn column_json(column: &ColumnDef) -> Value {
+    serde_json::json!({
+        "type": column.data_type,
+        "nullable": column.nullable,
+        "default": column.default,
+    })
+}
+
+fn before_after(before: Value, after: Value) -> Value {
+    serde_json::json!({"before": before, "after": after})
+}
+
+const GROUPS: [&str; 4] = ["tables", "columns", "indexes", "constraints"];
+
+// `before` is side a and `after` side b. Tables present on one side only are listed once, not
+// per column/index/constraint.
+pub fn diff_catalogs(a: &SchemaCatalog, b: &SchemaCatalog) -> Value {
+    let mut only_in_a: BTreeMap<&str, Vec<Value>> = BTreeMap::new();
+    let mut only_in_b: BTreeMap<&str, Vec<Value>> = BTreeMap::new();
+    let mut changed: BTreeMap<&str, Vec<Value>> = BTreeMap::new();
+    for change in changes(a, b) {
+        let (side, group, item) = match change {
+            Change::Table(side, table) => (
+                Some(side),
+                "tables",
+                serde_json::json!({"table": table.key(), "columns": table.columns.len()}),
+            ),
+            Change::Column(side, table, name, column) => {
+                let mut item = column_json(column);
+                item["table"] = Value::String(table.key());
+                item["column"] = Value::String(name.to_string());
+                (Some(side), "columns", item)
+            }
+            Change::ColumnChanged(table, name, before, after) => {
+                let mut attributes = serde_json::Map::new();
+                if before.data_type != after.data_type {
+                    attributes.insert(
+                        "type".to_string(),
+                        before_after(
+                            Value::from(before.data_type.as_str()),
+                            Value::from(after.data_type.as_str()),
+                        ),
+                    );
+                }
+                if before.nullable != after.nullable {
+                    attributes.insert(
+                        "nullable".to_string(),
+                        before_after(Value::from(before.nullable), Value::from(after.nullable)),
+                    );
+                }
+                if before.default != after.default {
+                    attributes.insert(
+                        "default".to_string(),
+                        before_after(
+                            Value::from(before.default.clone()),
+                            Value::from(after.default.clone()),
+                        ),
+                    );
+                }
+                (
+                    None,
+                    "columns",
+                    serde_json::json!({"table": table.key(), "column": name, "changes": attributes}),
+                )
+            }
+            Change::Index(side, table, name, definition) => (
+                Some(side),
+                "indexes",
+                serde_json::json!({"table": table.key(), "name": name, "definition": definition}),
+            ),
+            Change::IndexChanged(table, name, before, after) => (
+                None,
+                "indexes",
+                serde_json::json!({
+                    "table": table.key(),
+                    "name": name,
+                    "changes": {"definition": before_after(Value::from(before), Value::from(after))},
+                }),
+            ),
+            Change::Constraint(side, table, name, constraint) => (
+                Some(side),
+                "constraints",
+                serde_json::json!({
+                    "table": table.key(),
+                    "name": name,
+                    "type": constraint_type_name(&constraint.kind),
+                    "definition": constraint.definition,
+                }),
+            ),
+            Change::ConstraintChanged(table, name, before, after) => (
+                None,
+                "constraints",
+                serde_json::json!({
+                    "table": table.key(),
+                    "name": name,
+                    "changes": {"definition": before_after(
+                        Value::from(before.definition.as_str()),
+                        Value::from(after.definition.as_str()),
+                    )},
+                }),
+            ),
+        };
+        let target = match side {
+            Some(Side::A) => &mut only_in_a,
+            Some(Side::B) => &mut only_in_b,
+            None => &mut changed,
+        };
+        target.entry(group).or_default().push(item);
+    }
+
+    let render = |mut groups: BTreeMap<&str, Vec<Value>>| {
+        let map: serde_json::Map<String, Value> = GROUPS
+            .iter()
+            .map(|group| {
+                (
+                    group.to_string(),
+                    Value::Array(groups.remove(group).unwrap_or_default()),
+                )
+            })
+            .collect();
+        Value::Object(map)
+    };
+    let only_in_a = render(only_in_a);
+    let only_in_b = render(only_in_b);
+    let changed = render(changed);
+    let count = |section: &Value| -> usize {
+        GROUPS
+            .iter()
+            .map(|group| section[*group].as_array().map_or(0, Vec::len))
+            .sum()
+    };
+    let summary = serde_json::json!({
+        "only_in_a": count(&only_in_a),
+        "only_in_b": count(&only_in_b),
+        "changed": count(&changed),
+        "tables_a": a.tables.len(),
+        "tables_b": b.tables.len(),
+    });
+    serde_json::json!({
+        "identical": count(&only_in_a) + count(&only_in_b) + count(&changed) == 0,
+        "summary": summary,
+        "only_in_a": only_in_a,
+        "only_in_b": only_in_b,
+        "changed": changed,
+    })
+}
+
+// Keeps at most `max_items` entries across the diff sections, in section order.
+pub fn truncate_diff(diff: &Value, max_items: usize) -> Value {
+    let mut out = diff.clone();
+    let mut budget = max_items;
+    for section in ["only_in_a", "only_in_b", "changed"] {
+        for group in GROUPS {
+            if let Some(items) = out[section][group].as_array_mut() {
+                let keep = items.len().min(budget);
+                items.truncate(keep);
+                budget -= keep;
+            }
+        }
+    }
+    out
+}
+
+pub fn diff_item_count(diff: &Value) -> usize {
+    ["only_in_a", "only_in_b", "changed"]
+        .iter()
+        .map(|section| diff["summary"][*section].as_u64().unwrap_or(0) as usize)
+        .sum()
+}
+
+fn constraint_type_name(kind: &str) -> &str {
+    match kind {
+        "p" => "primary_key",
+        "u" => "unique",
+        "f" => "foreign_key",
+        "c" => "check",
+        "x" => "exclusion",
+        "t" => "trigger",
+        other => other,
+    }
+}
+
+fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+fn column_sql(name: &str, column: &ColumnDef) -> String {
+    let mut sql = format!("{} {}", quote_ident(name), column.data_type);
+    if !column.nullable {
+        sql.push_str(" NOT NULL");
+    }
+    if let Some(default) = column.default.as_deref() {
+        sql.push_str(&format!(" DEFAULT {}", default));
+    }
+    sql
+}
+
+fn add_constraint_sql(table: &TableDef, name: &str, constraint: &ConstraintDef) -> String {
+    format!(
+        "ALTER TABLE {} ADD CONSTRAINT {} {};",
+        table.qualified(),
+        quote_ident(name),
+        constraint.definition
+    )
+}
+
+fn drop_constraint_sql(table: &TableDef, name: &str) -> String {
+    format!(
+        "ALTER TABLE {} DROP CONSTRAINT {};",
+        table.qualified(),
+        quote_ident(name)
+    )
+}
+
+fn drop_index_sql(table: &TableDef, name: &str) -> String {
+    format!(
+        "DROP INDEX {}.{};",
+        quote_ident(&table.schema),
+        quote_ident(name)
+    )
+}
+
+// Foreign keys go last so the keys they reference exist first.
+fn constraint_rank(constraint: &ConstraintDef) -> u8 {
+    match constraint.kind.as_str() {
+        "p" => 0,
+        "u" => 1,
+        "f" => 3,
+        _ => 2,
+    }
+}
+
+// Best-effort DDL that would turn schema a into schema b, ordered in phases (drop constraints
+// and indexes, create tables, add/alter columns, add constraints and indexes, drop columns and
+// tables). Suggestions only: type changes, NOT NULL and drops can fail, lock or lose data.
+pub fn sql_hints(a: &SchemaCatalog, b: &SchemaCatalog) -> Vec<Value> {
+    let mut phases: [Vec<(String, bool)>; 7] = Default::default();
+    let mut constraints_to_add: Vec<(u8, String)> = Vec::new();
+    for change in changes(a, b) {
+        match change {
+            Change::Table(Side::B, table) => {
+                let columns: Vec<String> = table
+                    .columns
+                    .iter()
+                    .map(|(name, column)| format!("  {}", column_sql(name, column)))
+                    .collect();
+                phases[1].push((
+                    format!(
+                        "CREATE TABLE {} (\n{}\n);",
+                        table.qualified(),
+                        columns.join(",\n")
+                    ),
+                    false,
+                ));
+                for (name, constraint) in &table.constraints {
+                    constraints_to_add.push((
+                        constraint_rank(constraint),
+                        add_constraint_sql(table, name, constraint),
+                    ));
+                }
+                for definition in table.indexes.values() {
+                    phases[4].push((format!("{};", definition), false));
+                }
+            }
+            Change::Table(Side::A, table) => {
+                phases[6].push((format!("DROP TABLE {};", table.qualified()), true));
+            }
+            Change::Column(Side::B, table, name, column) => phases[2].push((
+                format!(
+                    "ALTER TABLE {} ADD COLUMN {};",
+                    table.qualified(),
+                    column_sql(name, column)
+                ),
+                false,
+            )),
+            Change::Column(Side::A, table, name, _) => phases[5].push((
+                format!(
+                    "ALTER TABLE {} DROP COLUMN {};",
+                    table.qualified(),
+                    quote_ident(name)
+                ),
+                true,
+            )),
+            Change::ColumnChanged(table, name, before, after) => {
+                let alter = format!(
+                    "ALTER TABLE {} ALTER COLUMN {}",
+                    table.qualified(),
+                    quote_ident(name)
+                );
+                if before.data_type != after.data_type {
+                    phases[2].push((
+                        format!(
+                            "{} TYPE {} USING {}::{};",
+                            alter,
+                            after.data_type,
+                            quote_ident(name),
+                            after.data_type
+                        ),
+                        true,
+                    ));
+                }
+                if before.default != after.default {
+                    let sql = match after.default.as_deref() {
+                        Some(default) => format!("{} SET DEFAULT {};", alter, default),
+                        None => format!("{} DROP DEFAULT;", alter),
+                    };
+                    phases[2].push((sql, false));
+                }
+                if before.nullable != after.nullable {
+                    let sql = if after.nullable {
+                        format!("{} DROP NOT NULL;", alter)
+                    } else {
+                        format!("{} SET NOT NULL;", alter)
+                    };
+                    phases[2].push((sql, !after.nullable));
+                }
+            }
+            Change::Index(Side::B, _, _, definition) => {
+                phases[4].push((format!("{};", definition), false))
+            }
+            Change::Index(Side::A, table, name, _) => {
+                phases[0].push((drop_index_sql(table, name), false))
+            }
+            Change::IndexChanged(table, name, _, after) => {
+                phases[0].push((drop_index_sql(table, name), false));
+                phases[4].push((format!("{};", after), false));
+            }
+            Change::Constraint(Side::B, table, name, constraint) => constraints_to_add.push((
+                constraint_rank(constraint),
+                add_constraint_sql(table, name, constraint),
+            )),
+            Change::Constraint(Side::A, table, name, _) => {
+                phases[0].push((drop_constraint_sql(table, name), false))
+            }
+            Change::ConstraintChanged(table, name, _, after) => {
+                phases[0].push((drop_constraint_sql(table, name), false));
+                constraints_to_add.push((
+                    constraint_rank(after),
+                    add_constraint_sql(table, name, after),
+                ));
+            }
+        }
+    }
+    constraints_to_add.sort_by_key(|(rank, _)| *rank);
+    phases[3] = constraints_to_add
+        .into_iter()
+        .map(|(_, sql)| (sql, false))
+        .collect();
+    phases
+        .into_iter()
+        .flatten()
+        .map(|(sql, destructive)| serde_json::json!({"sql": sql, "destructive": destructive}))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn catalog(columns: Value, indexes: Value, constraints: Value) -> SchemaCatalog {
+        SchemaCatalog::from_rows(
+            columns.as_array().unwrap(),
+            indexes.as_array().unwrap(),
+            constraints.as_array().unwrap(),
+            &["_infra_migrations".to_string()],
+        )
+    }
+
+    fn col(table: &str, column: &str, ty: &str, nullable: bool) -> Value {
+        json!({"schema": "public", "table": table, "column": column, "type": ty, "nullable": nullable, "default": null})
+    }
+
+    #[test]
+    fn diff_reports_one_sided_and_changed_items_with_sql_hints() {
+        let a = catalog(
+            json!([
+                col("users", "id", "integer", false),
+                col("users", "email", "character varying(100)", true),
+                col("users", "legacy", "text", true),
+                col("audit", "id", "bigint", false),
+                col("_infra_migrations", "id", "integer", false),
+            ]),
+            json!([{"schema": "public", "table": "users", "name": "users_email_idx", "definition": "CREATE INDEX users_email_idx ON public.users USING btree (email)"}]),
+            json!([{"schema": "public", "table": "users", "name": "users_pkey", "type": "p", "definition": "PRIMARY KEY (id)"}]),
+        );
+        let b = catalog(
+            json!([
+                col("users", "id", "integer", false),
+                col("users", "email", "text", false),
+                col("users", "plan", "text", true),
+                col("orders", "id", "bigint", false),
+                col("orders", "user_id", "integer", false),
+            ]),
+            json!([{"schema": "public", "table": "users", "name": "users_email_idx", "definition": "CREATE UNIQUE INDEX users_email_idx ON public.users USING btree (email)"}]),
+            json!([
+                {"schema": "public", "table": "users", "name": "users_pkey", "type": "p", "definition": "PRIMARY KEY (id)"},
+                {"schema": "public", "table": "orders", "name": "orders_user_fk", "type": "f", "definition": "FOREIGN KEY (user_id) REFERENCES users(id)"},
+                {"schema": "public", "table": "orders", "name": "orders_pkey", "type": "p", "definition": "PRIMARY KEY (id)"},
+            ]),
+        );
+
+        let diff = diff_catalogs(&a, &b);
+        assert_eq!(diff["identical"], false);
+        assert_eq!(diff["only_in_a"]["tables"][0]["table"], "public.audit");
+        assert_eq!(diff["only_in_a"]["columns"][0]["column"], "legacy");
+        assert_eq!(diff["only_in_b"]["tables"][0]["table"], "public.orders");
+        assert_eq!(diff["only_in_b"]["columns"][0]["column"], "plan");
+        assert!(diff["only_in_b"]["constraints"]
+            .as_array()
+            .unwrap()
+            .is_empty());
+        let email = &diff["changed"]["columns"][0];
+        assert_eq!(email["changes"]["type"]["before"], "character varying(100)");
+        assert_eq!(email["changes"]["nullable"]["after"], false);
+        assert!(email["changes"].get("default").is_none());
+        assert_eq!(
+            diff["changed"]["indexes"][0]["changes"]["definition"]["after"],
+            "CREATE UNIQUE INDEX users_email_idx ON public.users USING btree (email)"
+        );
+        assert_eq!(diff_item_count(&diff), 6);
+        assert_eq!(diff["summary"]["tables_a"], 2);
+
+        let truncated = truncate_diff(&diff, 2);
+        assert_eq!(
+            truncated["only_in_a"]["columns"].as_array().unwrap().len(),
+            1
+        );
+        assert!(truncated["only_in_b"]["tables"]
+            .as_array()
+            .unwrap()
+            .is_empty());
+
+        let hints: Vec<String> = sql_hints(&a, &b)
+            .iter()
+            .map(|hint| hint["sql"].as_str().unwrap().to_string())
+            .collect();
+        let position = |needle: &str| {
+            hints
+                .iter()
+                .position(|sql| sql.contains(needle))
+                .unwrap_or_else(|| panic!("{} missing from {:?}", needle, hints))
+        };
+        assert!(
+            position("DROP INDEX \"public\".\"users_email_idx\"")
+                < position("CREATE TABLE \"public\".\"orders\"")
+        );
+        assert!(
+            position("ADD CONSTRAINT \"orders_pkey\"")
+                < position("ADD CONSTRAINT \"orders_user_fk\"")
+        );
+        assert!(
+            position("ALTER COLUMN \"email\" TYPE text USING \"email\"::text")
+                < position("CREATE UNIQUE INDEX")
+        );
+        assert!(position("DROP COLUMN \"legacy\"") < position("DROP TABLE \"public\".\"audit\""));
+        assert!(hints
+            .iter()
+            .any(|sql| sql.ends_with("ADD COLUMN \"plan\" text;")));
+        assert!(!hints.iter().any(|sql| sql.contains("_infra_migrations")));
+
+        let same = diff_catalogs(&a, &a);
+        assert_eq!(same["identical"], true);
+        assert!(sql_hints(&a, &a).is_empty());
+    }
+
+    #[test]
+    fn catalog_queries_bind_one_placeholder_per_schema() {
+        let [columns, indexes, constraints] = catalog_queries(2);
+        for sql in [&columns, &indexes, &constraints] {
+            assert!(sql.contains("n.nspname IN ($1, $2)"), "{}", sql);
+        }
+        assert!(catalog_queries(0)[0].contains("IN ($1)"));
+    }
+}
@@ -0,0 +1,343 @@
+use crate::errors::ToolError;
+use crate::utils::sql::quote_qualified_identifier;
+use serde_json::Value;
+
+const DEFAULT_PAGE_SIZE: usize = 100;
+const MAX_PAGE_SIZE: usize = 100_000;
+
+// Keyset (seek) pagination: each page continues strictly after the key values of the previous
+// page's last row, so rows inserted or deleted elsewhere never shift later pages the way OFFSET
+// does. Keys compare as one row value, `(a, b) > ($1, $2)`, which only matches an ORDER BY where
+// every key runs the same direction; mixed ASC/DESC keys are rejected rather than emulated.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Keyset {
+    pub keys: Vec<String>,
+    pub descending: bool,
+    pub page_size: usize,
+    pub after: Option<Vec<Value>>,
+}
+
+fn key_direction(value: Option<&Value>, label: &str) -> Result<Option<bool>, ToolError> {
+    match value {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(dir)) if dir.eq_ignore_ascii_case("asc") => Ok(Some(false)),
+        Some(Value::String(dir)) if dir.eq_ignore_ascii_case("desc") => Ok(Some(true)),
+        Some(_) => Err(ToolError::invalid_params(format!(
+            "{} must be asc or desc",
+            label
+        ))),
+    }
+}
+
+// Result rows are keyed by the bare column name, whatever qualification the key was given with.
+fn column_name(key: &str) -> &str {
+    let last = key.rsplit('.').next().unwrap_or(key).trim();
+    last.strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .unwrap_or(last)
+}
+
+impl Keyset {
+    pub fn new(keys: Vec<String>, descending: bool) -> Self {
+        Self {
+            keys,
+            descending,
+            page_size: DEFAULT_PAGE_SIZE,
+            after: None,
+        }
+    }
+
+    // `paginate`: `{keys, after?, page_size?, direction?}`. A key is a column name or
+    // `{column, direction}`; `after` is the `next_after` object of the previous page (or its
+    // values in key order).
+    pub fn parse(value: &Value) -> Result<Self, ToolError> {
+        let map = value
+            .as_object()
+            .ok_or_else(|| ToolError::invalid_params("paginate must be an object"))?;
+        let entries = match map.get("keys") {
+            Some(Value::String(key)) => vec![Value::String(key.clone())],
+            Some(Value::Array(keys)) if !keys.is_empty() => keys.clone(),
+            _ => {
+                return Err(ToolError::invalid_params(
+                    "paginate.keys must be a column name or a non-empty array of column names",
+                ))
+            }
+        };
+        let default_direction = key_direction(map.get("direction"), "paginate.direction")?;
+        let mut keys = Vec::new();
+        let mut directions = Vec::new();
+        for entry in &entries {
+            let (column, direction) = match entry {
+                Value::String(column) => (column.as_str(), default_direction),
+                Value::Object(obj) => (
+                    obj.get("column").and_then(|v| v.as_str()).unwrap_or(""),
+                    key_direction(obj.get("direction"), "paginate.keys[].direction")?
+                        .or(default_direction),
+                ),
+                _ => ("", None),
+            };
+            let column = column.trim();
+            if column.is_empty() {
+                return Err(ToolError::invalid_params(
+                    "paginate.keys entries must be column names or {column, direction}",
+                ));
+            }
+            quote_qualified_identifier(column)?;
+            if keys.iter().any(|k| k == column) {
+                return Err(ToolError::invalid_params(format!(
+                    "paginate.keys lists {} twice",
+                    column
+                )));
+            }
+            keys.push(column.to_string());
+            directions.push(direction.unwrap_or(false));
+        }
+        if directions.windows(2).any(|pair| pair[0] != pair[1]) {
+            return Err(ToolError::invalid_params(
+                "paginate.keys must all sort in the same direction; mixed ASC/DESC keys cannot be compared as one row value",
+            )
+            .with_hint("Order every key ASC or every key DESC, or page through with limit/offset instead."));
+        }
+        let mut keyset = Self::new(keys, directions[0]);
+        if let Some(size) = map.get("page_size").filter(|v| !v.is_null()) {
+            keyset.page_size = size
+                .as_u64()
+                .filter(|n| (1..=MAX_PAGE_SIZE as u64).contains(n))
+                .ok_or_else(|| {
+                    ToolError::invalid_params(format!(
+                        "paginate.page_size must be an integer between 1 and {}",
+                        MAX_PAGE_SIZE
+                    ))
+                })? as usize;
+        }
+        keyset.after = match map.get("after") {
+            None | Some(Value::Null) => None,
+            Some(after) => Some(keyset.parse_after(after)?),
+        };
+        Ok(keyset)
+    }
+
+    fn parse_after(&self, after: &Value) -> Result<Vec<Value>, ToolError> {
+        let values: Vec<Value> = match after {
+            Value::Object(map) => self
+                .keys
+                .iter()
+                .map(|key| {
+                    map.get(key)
+                        .or_else(|| map.get(column_name(key)))
+                        .cloned()
+                        .ok_or_else(|| {
+                            ToolError::invalid_params(format!(
+                                "paginate.after is missing key {}",
+                                key
+                            ))
+                        })
+                })
+                .collect::<Result<_, _>>()?,
+            Value::Array(items) if items.len() == self.keys.len() => items.clone(),
+            _ => {
+                return Err(ToolError::invalid_params(
+                    "paginate.after must be an object with a value for every key",
+                ))
+            }
+        };
+        if values.iter().any(Value::is_null) {
+            return Err(ToolError::invalid_params(
+                "paginate.after values must not be null",
+            ));
+        }
+        Ok(values)
+    }
+
+    // `SELECT ... FROM ...` plus the caller's filter, the seek predicate (its values bound after
+    // `params`), the key ORDER BY and one extra row to tell whether another page follows.
+    pub fn page_sql(
+        &self,
+        select_from: &str,
+        where_sql: &str,
+        params: &mut Vec<Value>,
+        offset: Option<usize>,
+    ) -> Result<String, ToolError> {
+        let columns = self
+            .keys
+            .iter()
+            .map(|key| quote_qualified_identifier(key))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut conditions = Vec::new();
+        if !where_sql.is_empty() {
+            conditions.push(format!("({})", where_sql));
+        }
+        if let Some(after) = &self.after {
+            let placeholders: Vec<String> = (1..=after.len())
+                .map(|idx| format!("${}", params.len() + idx))
+                .collect();
+            params.extend(after.iter().cloned());
+            conditions.push(format!(
+                "({}) {} ({})",
+                columns.join(", "),
+                if self.descending { "<" } else { ">" },
+                placeholders.join(", ")
+            ));
+        }
+        let dir = if self.descending { "DESC" } else { "ASC" };
+        let mut sql = select_from.to_string();
+        if !conditions.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&conditions.join(" AND "));
+        }
+        sql.push_str(&format!(
+            " ORDER BY {} LIMIT {}",
+            columns
+                .iter()
+                .map(|col| format!("{} {}", col, dir))
+                .collect::<Vec<_>>()
+                .join(", "),
+            self.page_size + 1
+        ));
+        if let Some(offset) = offset.filter(|n| *n > 0) {
+            sql.push_str(&format!(" OFFSET {}", offset));
+        }
+        Ok(sql)
+    }
+
+    // Drops the look-ahead row; true when it was there.
+    pub fn trim_page(&self, rows: &mut Vec<Value>) -> bool {
+        if rows.len() > self.page_size {
+            rows.truncate(self.page_size);
+            true
+        } else {
+            false
+        }
+    }
+
+    // The key values of `row`, to continue after it.
+    pub fn cursor(&self, row: &Value) -> Result<Vec<Value>, ToolError> {
+        self.keys
+            .iter()
+            .map(|key| match row.get(column_name(key)) {
+                None => Err(ToolError::invalid_params(format!(
+                    "paginate key {} is not among the selected columns",
+                    key
+                ))),
+                Some(Value::Null) => Err(ToolError::invalid_params(format!(
+                    "paginate key {} is NULL in a fetched row; keyset pagination needs non-null keys",
+                    key
+                ))),
+                Some(value) => Ok(value.clone()),
+            })
+            .collect()
+    }
+
+    pub fn after_value(&self, cursor: &[Value]) -> Value {
+        Value::Object(
+            self.keys
+                .iter()
+                .cloned()
+                .zip(cursor.iter().cloned())
+                .collect(),
+        )
+    }
+
+    pub fn to_value(&self) -> Value {
+        serde_json::json!({
+            "keys": self.keys,
+            "direction": if self.descending { "desc" } else { "asc" },
+            "page_size": self.page_size,
+        })
+    }
+}
+
+// The columns and shared direction of an `order_by` that keyset pagination can follow; None for
+// expressions, mixed directions or forms it cannot read.
+pub fn ordering(order_by: &Value) -> Option<(Vec<String>, bool)> {
+    let mut keys = Vec::new();
+    let mut directions = Vec::new();
+    let mut push = |column: &str, direction: Option<&Value>| -> Option<()> {
+        keys.push(column.trim().to_string());
+        directions.push(key_direction(direction, "order_by").ok()?.unwrap_or(false));
+        Some(())
+    };
+    match order_by {
+        Value::String(column) => push(column, None)?,
+        Value::Array(entries) => {
+            for entry in entries {
+                match entry {
+                    Value::String(column) => push(column, None)?,
+                    Value::Object(obj) => push(
+                        obj.get("column")
+                            .or_else(|| obj.get("field"))
+                            .and_then(|v| v.as_str())?,
+                        obj.get("direction"),
+                    )?,
+                    _ => return None,
+                }
+            }
+        }
+        Value::Object(map) => {
+            for (column, direction) in map {
+                push(column, Some(direction))?;
+            }
+        }
+        _ => return None,
+    }
+    if keys.is_empty()
+        || keys.iter().any(|k| k.is_empty())
+        || directions.windows(2).any(|pair| pair[0] != pair[1])
+    {
+        return None;
+    }
+    Some((keys, directions[0]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn page_sql_seeks_after_the_previous_row() {
+        let mut keyset = Keyset::parse(&json!({
+            "keys": ["created_at", "id"],
+            "after": {"created_at": "2024-01-01T00:00:00+00:00", "id": 7},
+            "page_size": 2,
+        }))
+        .unwrap();
+        let mut params = vec![json!("active")];
+        let sql = keyset
+            .page_sql(
+                "SELECT * FROM \"events\"",
+                "\"status\" = $1",
+                &mut params,
+                None,
+            )
+            .unwrap();
+        assert_eq!(
+            sql,
+            "SELECT * FROM \"events\" WHERE (\"status\" = $1) AND (\"created_at\", \"id\") > ($2, $3) \
+             ORDER BY \"created_at\" ASC, \"id\" ASC LIMIT 3"
+        );
+        assert_eq!(params.len(), 3);
+
+        keyset.descending = true;
+        keyset.after = None;
+        let sql = keyset.page_sql("SELECT id FROM t", "", &mut Vec::new(), Some(4));
+        assert_eq!(
+            sql.unwrap(),
+            "SELECT id FROM t ORDER BY \"created_at\" DESC, \"id\" DESC LIMIT 3 OFFSET 4"
+        );
+    }
+
+    #[test]
+    fn mixed_directions_are_rejected() {
+        let err = Keyset::parse(&json!({
+            "keys": [{"column": "created_at", "direction": "desc"}, "id"],
+        }))
+        .unwrap_err();
+        assert!(err.message.contains("same direction"), "{}", err.message);
+        assert_eq!(
+            ordering(&json!([{"column": "id", "direction": "desc"}])),
+            Some((vec!["id".to_string()], true))
+        );
+        assert_eq!(ordering(&json!({"a": "asc", "b": "desc"})), None);
+    }
+}
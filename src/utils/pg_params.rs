@@ -0,0 +1,398 @@
+use bytes::BytesMut;
+use serde_json::Value;
+use std::error::Error;
+use tokio_postgres::types::{to_sql_checked, IsNull, Json, Kind, ToSql, Type};
+
+// A JSON value already converted for the parameter type the server inferred at prepare time.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PgParam {
+    Null,
+    Bool(bool),
+    Int2(i16),
+    Int4(i32),
+    Int8(i64),
+    Float4(f32),
+    Float8(f64),
+    // Binary NUMERIC wire encoding, built up front so malformed input fails before execution.
+    Numeric(Vec<u8>),
+    Text(String),
+    Json(Value),
+    Uuid(uuid::Uuid),
+    Timestamptz(chrono::DateTime<chrono::Utc>),
+    Timestamp(chrono::NaiveDateTime),
+    Date(chrono::NaiveDate),
+    Array(Vec<PgParam>),
+}
+
+impl ToSql for PgParam {
+    fn to_sql(
+        &self,
+        ty: &Type,
+        out: &mut BytesMut,
+    ) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        match self {
+            PgParam::Null => Ok(IsNull::Yes),
+            PgParam::Bool(value) => value.to_sql(ty, out),
+            PgParam::Int2(value) => value.to_sql(ty, out),
+            PgParam::Int4(value) => value.to_sql(ty, out),
+            PgParam::Int8(value) => value.to_sql(ty, out),
+            PgParam::Float4(value) => value.to_sql(ty, out),
+            PgParam::Float8(value) => value.to_sql(ty, out),
+            PgParam::Numeric(encoded) => {
+                out.extend_from_slice(encoded);
+                Ok(IsNull::No)
+            }
+            PgParam::Text(value) => value.to_sql(ty, out),
+            PgParam::Json(value) => Json(value).to_sql(ty, out),
+            PgParam::Uuid(value) => value.to_sql(ty, out),
+            PgParam::Timestamptz(value) => value.to_sql(ty, out),
+            PgParam::Timestamp(value) => value.to_sql(ty, out),
+            PgParam::Date(value) => value.to_sql(ty, out),
+            PgParam::Array(items) => items.to_sql(ty, out),
+        }
+    }
+
+    fn accepts(_ty: &Type) -> bool {
+        true
+    }
+
+    to_sql_checked!();
+}
+
+fn is_text_type(ty: &Type) -> bool {
+    matches!(
+        *ty,
+        Type::TEXT | Type::VARCHAR | Type::BPCHAR | Type::NAME | Type::UNKNOWN
+    ) || matches!(ty.name(), "citext" | "ltree" | "lquery" | "ltxtquery")
+}
+
+fn is_scalar_type(ty: &Type) -> bool {
+    is_text_type(ty)
+        || matches!(
+            *ty,
+            Type::BOOL
+                | Type::INT2
+                | Type::INT4
+                | Type::INT8
+                | Type::FLOAT4
+                | Type::FLOAT8
+                | Type::NUMERIC
+                | Type::JSON
+                | Type::JSONB
+                | Type::UUID
+                | Type::TIMESTAMPTZ
+                | Type::TIMESTAMP
+                | Type::DATE
+        )
+}
+
+// Types outside this set are bound as text and cast server-side by the caller.
+pub fn binds_natively(ty: &Type) -> bool {
+    match ty.kind() {
+        Kind::Array(member) => is_scalar_type(member),
+        _ => is_scalar_type(ty),
+    }
+}
+
+fn parse_integer(value: &Value) -> Result<i64, String> {
+    match value {
+        Value::Number(num) => num
+            .as_i64()
+            .or_else(|| {
+                num.as_f64()
+                    .filter(|f| f.is_finite() && f.fract() == 0.0 && f.abs() < 9.2e18)
+                    .map(|f| f as i64)
+            })
+            .ok_or_else(|| format!("{} is not an integer", num)),
+        Value::String(text) => text
+            .trim()
+            .parse::<i64>()
+            .map_err(|_| format!("{:?} is not an integer", text)),
+        other => Err(format!("expected an integer, got {}", json_kind(other))),
+    }
+}
+
+fn parse_float(value: &Value) -> Result<f64, String> {
+    match value {
+        Value::Number(num) => num
+            .as_f64()
+            .ok_or_else(|| format!("{} is not a number", num)),
+        Value::String(text) => text
+            .trim()
+            .parse::<f64>()
+            .map_err(|_| format!("{:?} is not a number", text)),
+        other => Err(format!("expected a number, got {}", json_kind(other))),
+    }
+}
+
+fn expect_str<'a>(value: &'a Value, what: &str) -> Result<&'a str, String> {
+    value
+        .as_str()
+        .map(str::trim)
+        .ok_or_else(|| format!("expected {} string, got {}", what, json_kind(value)))
+}
+
+fn parse_naive_datetime(text: &str) -> Option<chrono::NaiveDateTime> {
+    ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"]
+        .iter()
+        .find_map(|fmt| chrono::NaiveDateTime::parse_from_str(text, fmt).ok())
+        .or_else(|| {
+            chrono::NaiveDate::parse_from_str(text, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })
+}
+
+// RFC3339 first; offset-less timestamps and bare dates are read as UTC.
+fn parse_timestamptz(text: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    chrono::DateTime::parse_from_rfc3339(text)
+        .ok()
+        .map(|dt| dt.with_timezone(&chrono::Utc))
+        .or_else(|| parse_naive_datetime(text).map(|naive| naive.and_utc()))
+}
+
+fn json_kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn narrow<T: TryFrom<i64>>(value: i64, ty: &Type) -> Result<T, String> {
+    T::try_from(value).map_err(|_| format!("{} is out of range for {}", value, ty.name()))
+}
+
+pub fn to_pg_param(value: &Value, ty: &Type) -> Result<PgParam, String> {
+    if value.is_null() {
+        return Ok(PgParam::Null);
+    }
+    if let Kind::Array(member) = ty.kind() {
+        let Value::Array(items) = value else {
+            return Err(format!("expected an array, got {}", json_kind(value)));
+        };
+        return items
+            .iter()
+            .enumerate()
+            .map(|(idx, item)| {
+                to_pg_param(item, member).map_err(|err| format!("element {}: {}", idx, err))
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(PgParam::Array);
+    }
+    if is_text_type(ty) {
+        return Ok(PgParam::Text(match value {
+            Value::String(text) => text.clone(),
+            other => other.to_string(),
+        }));
+    }
+    match *ty {
+        Type::BOOL => match value {
+            Value::Bool(flag) => Ok(PgParam::Bool(*flag)),
+            Value::String(text) => match text.trim().to_lowercase().as_str() {
+                "true" => Ok(PgParam::Bool(true)),
+                "false" => Ok(PgParam::Bool(false)),
+                _ => Err(format!("{:?} is not a boolean", text)),
+            },
+            other => Err(format!("expected a boolean, got {}", json_kind(other))),
+        },
+        Type::INT2 => Ok(PgParam::Int2(narrow(parse_integer(value)?, ty)?)),
+        Type::INT4 => Ok(PgParam::Int4(narrow(parse_integer(value)?, ty)?)),
+        Type::INT8 => Ok(PgParam::Int8(parse_integer(value)?)),
+        Type::FLOAT4 => Ok(PgParam::Float4(parse_float(value)? as f32)),
+        Type::FLOAT8 => Ok(PgParam::Float8(parse_float(value)?)),
+        Type::NUMERIC => {
+            let text = match value {
+                Value::Number(num) => num.to_string(),
+                Value::String(text) => text.clone(),
+                other => return Err(format!("expected a number, got {}", json_kind(other))),
+            };
+            encode_numeric(&text).map(PgParam::Numeric)
+        }
+        Type::JSON | Type::JSONB => Ok(PgParam::Json(value.clone())),
+        Type::UUID => {
+            let text = expect_str(value, "a uuid")?;
+            uuid::Uuid::parse_str(text)
+                .map(PgParam::Uuid)
+                .map_err(|_| format!("{:?} is not a uuid", text))
+        }
+        Type::TIMESTAMPTZ => {
+            let text = expect_str(value, "an RFC3339 timestamp")?;
+            parse_timestamptz(text)
+                .map(PgParam::Timestamptz)
+                .ok_or_else(|| format!("{:?} is not an RFC3339 timestamp", text))
+        }
+        Type::TIMESTAMP => {
+            let text = expect_str(value, "a timestamp")?;
+            chrono::DateTime::parse_from_rfc3339(text)
+                .ok()
+                .map(|dt| dt.naive_utc())
+                .or_else(|| parse_naive_datetime(text))
+                .map(PgParam::Timestamp)
+                .ok_or_else(|| format!("{:?} is not a timestamp", text))
+        }
+        Type::DATE => {
+            let text = expect_str(value, "a date")?;
+            chrono::NaiveDate::parse_from_str(text, "%Y-%m-%d")
+                .ok()
+                .or_else(|| parse_timestamptz(text).map(|dt| dt.date_naive()))
+                .map(PgParam::Date)
+                .ok_or_else(|| format!("{:?} is not a date (YYYY-MM-DD)", text))
+        }
+        _ => Err(format!("type {} cannot be bound directly", ty.name())),
+    }
+}
+
+// NUMERIC binary format: ndigits, weight, sign, dscale, then base-10000 digit groups.
+pub fn encode_numeric(raw: &str) -> Result<Vec<u8>, String> {
+    let text = raw.trim();
+    let invalid = || format!("{:?} is not a decimal number", raw);
+    let mut out = Vec::new();
+    let mut header = |ndigits: i16, weight: i16, sign: u16, dscale: u16| {
+        out.extend_from_slice(&ndigits.to_be_bytes());
+        out.extend_from_slice(&weight.to_be_bytes());
+        out.extend_from_slice(&sign.to_be_bytes());
+        out.extend_from_slice(&dscale.to_be_bytes());
+    };
+    if text.eq_ignore_ascii_case("nan") {
+        header(0, 0, 0xC000, 0);
+        return Ok(out);
+    }
+    let (negative, unsigned) = match text.as_bytes().first() {
+        Some(b'-') => (true, &text[1..]),
+        Some(b'+') => (false, &text[1..]),
+        _ => (false, text),
+    };
+    let (mantissa, exponent) = match unsigned.find(['e', 'E']) {
+        Some(pos) => (
+            &unsigned[..pos],
+            unsigned[pos + 1..].parse::<i32>().map_err(|_| invalid())?,
+        ),
+        None => (unsigned, 0),
+    };
+    let (int_part, frac_part) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    if (int_part.is_empty() && frac_part.is_empty())
+        || !int_part
+            .bytes()
+            .chain(frac_part.bytes())
+            .all(|b| b.is_ascii_digit())
+    {
+        return Err(invalid());
+    }
+    if exponent.abs() > 100_000 {
+        return Err(format!("{:?} is out of range for numeric", raw));
+    }
+
+    let mut digits: Vec<u8> = int_part
+        .bytes()
+        .chain(frac_part.bytes())
+        .map(|b| b - b'0')
+        .collect();
+    let mut point = int_part.len() as i32 + exponent;
+    let dscale = (frac_part.len() as i32 - exponent).max(0);
+    let leading = digits.iter().take_while(|d| **d == 0).count();
+    digits.drain(..leading);
+    point -= leading as i32;
+    while digits.last() == Some(&0) {
+        digits.pop();
+    }
+    let dscale = u16::try_from(dscale)
+        .ok()
+        .filter(|scale| *scale <= 0x3FFF)
+        .ok_or_else(|| format!("{:?} has too many fractional digits", raw))?;
+    if digits.is_empty() {
+        header(0, 0, 0, dscale);
+        return Ok(out);
+    }
+
+    let pad = (4 - point.rem_euclid(4)) % 4;
+    let mut padded = vec![0u8; pad as usize];
+    padded.extend(digits);
+    point += pad;
+    while !padded.len().is_multiple_of(4) {
+        padded.push(0);
+    }
+    let groups: Vec<i16> = padded
+        .chunks(4)
+        .map(|chunk| chunk.iter().fold(0i16, |acc, d| acc * 10 + *d as i16))
+        .collect();
+    let weight = i16::try_from(point / 4 - 1)
+        .map_err(|_| format!("{:?} is out of range for numeric", raw))?;
+    let ndigits = i16::try_from(groups.len())
+        .map_err(|_| format!("{:?} is out of range for numeric", raw))?;
+    header(ndigits, weight, if negative { 0x4000 } else { 0 }, dscale);
+    for group in groups {
+        out.extend_from_slice(&group.to_be_bytes());
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numeric_words(raw: &str) -> Vec<i32> {
+        let bytes = encode_numeric(raw).unwrap();
+        bytes
+            .chunks(2)
+            .enumerate()
+            .map(|(idx, pair)| {
+                if idx == 2 || idx == 3 {
+                    u16::from_be_bytes([pair[0], pair[1]]) as i32
+                } else {
+                    i16::from_be_bytes([pair[0], pair[1]]) as i32
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn numeric_encoding_matches_postgres_layout() {
+        assert_eq!(numeric_words("12345.678"), vec![3, 1, 0, 3, 1, 2345, 6780]);
+        assert_eq!(numeric_words("-0.001"), vec![1, -1, 0x4000, 3, 10]);
+        assert_eq!(numeric_words("0.00"), vec![0, 0, 0, 2]);
+        assert_eq!(numeric_words("10000"), vec![1, 1, 0, 0, 1]);
+        assert_eq!(numeric_words("1.5e3"), vec![1, 0, 0, 0, 1500]);
+        assert_eq!(numeric_words("NaN"), vec![0, 0, 0xC000, 0]);
+        assert!(encode_numeric("12a").is_err());
+        assert!(encode_numeric(".").is_err());
+    }
+
+    #[test]
+    fn values_convert_to_the_inferred_type() {
+        assert_eq!(
+            to_pg_param(&serde_json::json!("42"), &Type::INT4).unwrap(),
+            PgParam::Int4(42)
+        );
+        assert!(to_pg_param(&serde_json::json!(70000), &Type::INT2).is_err());
+        assert_eq!(
+            to_pg_param(&serde_json::json!(7), &Type::TEXT).unwrap(),
+            PgParam::Text("7".to_string())
+        );
+        match to_pg_param(
+            &serde_json::json!("2024-01-01T03:00:00+03:00"),
+            &Type::TIMESTAMPTZ,
+        ) {
+            Ok(PgParam::Timestamptz(dt)) => {
+                assert_eq!(dt.to_rfc3339(), "2024-01-01T00:00:00+00:00")
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(to_pg_param(&serde_json::json!("yesterday"), &Type::TIMESTAMPTZ).is_err());
+        assert_eq!(
+            to_pg_param(&serde_json::json!("plain"), &Type::JSONB).unwrap(),
+            PgParam::Json(serde_json::json!("plain"))
+        );
+        assert_eq!(
+            to_pg_param(&serde_json::json!([1, null, "3"]), &Type::INT8_ARRAY).unwrap(),
+            PgParam::Array(vec![PgParam::Int8(1), PgParam::Null, PgParam::Int8(3)])
+        );
+        let err = to_pg_param(&serde_json::json!(["a"]), &Type::INT8_ARRAY).unwrap_err();
+        assert!(err.contains("element 0"), "{}", err);
+        assert!(!binds_natively(&Type::INET));
+        assert!(binds_natively(&Type::TEXT_ARRAY));
+    }
+}
@@ -0,0 +1,424 @@
+use crate::errors::ToolError;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+pub const MASK: &str = "***";
+const HASH_PREFIX_CHARS: usize = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RedactionMode {
+    Mask,
+    Drop,
+    Hash,
+}
+
+impl RedactionMode {
+    fn parse(value: Option<&Value>, table: &str) -> Result<Self, ToolError> {
+        match value.and_then(|v| v.as_str()).map(|s| s.trim()) {
+            None | Some("mask") => Ok(Self::Mask),
+            Some("drop") => Ok(Self::Drop),
+            Some("hash") => Ok(Self::Hash),
+            Some(other) => Err(ToolError::invalid_params(format!(
+                "redaction.{}.mode must be one of: mask, drop, hash (got {})",
+                table, other
+            ))),
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Mask => "masked",
+            Self::Drop => "dropped",
+            Self::Hash => "hashed",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct TableRule {
+    // None matches the table in any schema.
+    schema: Option<String>,
+    table: String,
+    columns: Vec<String>,
+    mode: RedactionMode,
+}
+
+// Where a result column comes from, as reported by the row description and pg_attribute.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SourceColumn {
+    pub schema: String,
+    pub table: String,
+    pub column: String,
+}
+
+// Profile `redaction: { "schema.table": { columns: [...], mode: mask|drop|hash } }`.
+#[derive(Clone, Debug, Default)]
+pub struct RedactionPolicy {
+    rules: Vec<TableRule>,
+}
+
+impl RedactionPolicy {
+    pub fn parse(value: Option<&Value>) -> Result<Self, ToolError> {
+        let Some(value) = value.filter(|v| !v.is_null()) else {
+            return Ok(Self::default());
+        };
+        let map = value.as_object().ok_or_else(|| {
+            ToolError::invalid_params("redaction must be an object keyed by schema.table")
+        })?;
+        let mut rules = Vec::new();
+        for (key, spec) in map {
+            let (schema, table) = match key.trim().split_once('.') {
+                Some((schema, table)) => (Some(schema.trim().to_string()), table.trim()),
+                None => (None, key.trim()),
+            };
+            let columns: Vec<String> = spec
+                .get("columns")
+                .and_then(|v| v.as_array())
+                .map(|items| {
+                    items
+                        .iter()
+                        .filter_map(|v| v.as_str())
+                        .map(|s| s.trim().to_string())
+                        .filter(|s| !s.is_empty())
+                        .collect()
+                })
+                .unwrap_or_default();
+            if table.is_empty() || columns.is_empty() {
+                return Err(ToolError::invalid_params(format!(
+                    "redaction.{} needs a table name and a non-empty columns list",
+                    key
+                )));
+            }
+            rules.push(TableRule {
+                schema,
+                table: table.to_string(),
+                columns,
+                mode: RedactionMode::parse(spec.get("mode"), key)?,
+            });
+        }
+        Ok(Self { rules })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    fn rule_for_source(&self, source: &SourceColumn) -> Option<RedactionMode> {
+        self.rules
+            .iter()
+            .find(|rule| {
+                rule.table.eq_ignore_ascii_case(&source.table)
+                    && rule
+                        .schema
+                        .as_deref()
+                        .is_none_or(|schema| schema.eq_ignore_ascii_case(&source.schema))
+                    && rule
+                        .columns
+                        .iter()
+                        .any(|column| column.eq_ignore_ascii_case(&source.column))
+            })
+            .map(|rule| rule.mode)
+    }
+
+    fn rule_for_name(&self, name: &str) -> Option<RedactionMode> {
+        self.rules
+            .iter()
+            .find(|rule| {
+                rule.columns
+                    .iter()
+                    .any(|column| column.eq_ignore_ascii_case(name))
+            })
+            .map(|rule| rule.mode)
+    }
+
+    // Columns traced to a table column (aliases included) follow that column's rule. Computed
+    // columns carry no source, so they fall back to matching the result name against every
+    // listed column: `lower(email) AS email` is caught, `lower(email) AS contact` is not.
+    pub fn plan(
+        &self,
+        fields: &Value,
+        sources: &HashMap<(u32, i16), SourceColumn>,
+    ) -> RedactionPlan {
+        let mut rules = Vec::new();
+        for field in fields.as_array().into_iter().flatten() {
+            let Some(name) = field.get("name").and_then(|v| v.as_str()) else {
+                continue;
+            };
+            let source = field_source(field).and_then(|key| sources.get(&key));
+            let mode = match source {
+                Some(source) => self.rule_for_source(source),
+                None => self.rule_for_name(name),
+            };
+            if let Some(mode) = mode {
+                rules.push((name.to_string(), mode));
+            }
+        }
+        RedactionPlan { rules }
+    }
+
+    // Rows headed into a known table need no field tracing: the table's own rules apply by
+    // column name. An unqualified table is matched in `public`.
+    pub fn plan_for_table(
+        &self,
+        schema: Option<&str>,
+        table: &str,
+        columns: &[String],
+    ) -> RedactionPlan {
+        let (schema, table) = match (schema, table.split_once('.')) {
+            (Some(schema), _) => (schema, table),
+            (None, Some((schema, table))) => (schema, table),
+            (None, None) => ("public", table),
+        };
+        let mut rules = Vec::new();
+        for column in columns {
+            let source = SourceColumn {
+                schema: schema.to_string(),
+                table: table.to_string(),
+                column: column.clone(),
+            };
+            if let Some(mode) = self.rule_for_source(&source) {
+                rules.push((column.clone(), mode));
+            }
+        }
+        RedactionPlan { rules }
+    }
+}
+
+fn field_source(field: &Value) -> Option<(u32, i16)> {
+    let table = field.get("tableId").and_then(|v| v.as_u64())?;
+    let column = field.get("columnId").and_then(|v| v.as_i64())?;
+    (table != 0 && column > 0).then_some((table as u32, column as i16))
+}
+
+// Distinct, non-zero table oids referenced by the result fields.
+pub fn source_table_oids(fields: &Value) -> Vec<u32> {
+    let mut oids: Vec<u32> = fields
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(field_source)
+        .map(|(table, _)| table)
+        .collect();
+    oids.sort_unstable();
+    oids.dedup();
+    oids
+}
+
+fn hash_value(value: &Value) -> Value {
+    let raw = match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    };
+    let digest = hex::encode(Sha256::digest(raw.as_bytes()));
+    Value::String(digest[..HASH_PREFIX_CHARS].to_string())
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct RedactionPlan {
+    rules: Vec<(String, RedactionMode)>,
+}
+
+impl RedactionPlan {
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    // Nulls stay null in mask and hash mode.
+    pub fn apply_row(&self, row: &mut Value) {
+        let Some(map) = row.as_object_mut() else {
+            return;
+        };
+        for (name, mode) in &self.rules {
+            match mode {
+                RedactionMode::Drop => {
+                    map.remove(name);
+                }
+                RedactionMode::Mask => {
+                    if let Some(value) = map.get_mut(name).filter(|v| !v.is_null()) {
+                        *value = Value::String(MASK.to_string());
+                    }
+                }
+                RedactionMode::Hash => {
+                    if let Some(value) = map.get_mut(name).filter(|v| !v.is_null()) {
+                        *value = hash_value(value);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn note(&self) -> Value {
+        let mut note = serde_json::Map::new();
+        for (name, mode) in &self.rules {
+            note.entry(mode.as_str())
+                .or_insert_with(|| Value::Array(Vec::new()))
+                .as_array_mut()
+                .unwrap()
+                .push(Value::String(name.clone()));
+        }
+        Value::Object(note)
+    }
+
+    // Redacts a query payload (`rows`, `row` or `value` mode) and records what was changed.
+    pub fn apply(&self, payload: &mut Value) {
+        if self.is_empty() {
+            return;
+        }
+        // `value` mode returns the first column of the (sorted) row object.
+        let value_column = payload
+            .get("fields")
+            .and_then(|v| v.as_array())
+            .and_then(|fields| {
+                fields
+                    .iter()
+                    .filter_map(|f| f.get("name").and_then(|v| v.as_str()))
+                    .min()
+                    .map(str::to_string)
+            });
+        let Some(map) = payload.as_object_mut() else {
+            return;
+        };
+        if let Some(rows) = map.get_mut("rows").and_then(|v| v.as_array_mut()) {
+            rows.iter_mut().for_each(|row| self.apply_row(row));
+        }
+        if let Some(row) = map.get_mut("row") {
+            self.apply_row(row);
+        }
+        if let (Some(value), Some(column)) = (map.get_mut("value"), value_column) {
+            let mut wrapped = serde_json::json!({ column.as_str(): value.take() });
+            self.apply_row(&mut wrapped);
+            *value = wrapped.get(column.as_str()).cloned().unwrap_or(Value::Null);
+        }
+        if let Some(fields) = map.get_mut("fields").and_then(|v| v.as_array_mut()) {
+            fields.retain(|field| {
+                let name = field.get("name").and_then(|v| v.as_str()).unwrap_or("");
+                !self
+                    .rules
+                    .iter()
+                    .any(|(rule, mode)| *mode == RedactionMode::Drop && rule == name)
+            });
+        }
+        map.insert("redaction".to_string(), self.note());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn policy() -> RedactionPolicy {
+        RedactionPolicy::parse(Some(&json!({
+            "public.users": {"columns": ["email", "ssn"], "mode": "mask"},
+            "tokens": {"columns": ["token"], "mode": "drop"},
+            "billing.cards": {"columns": ["number"], "mode": "hash"},
+        })))
+        .unwrap()
+    }
+
+    fn sources() -> HashMap<(u32, i16), SourceColumn> {
+        let source = |schema: &str, table: &str, column: &str| SourceColumn {
+            schema: schema.to_string(),
+            table: table.to_string(),
+            column: column.to_string(),
+        };
+        HashMap::from([
+            ((100, 2), source("public", "users", "email")),
+            ((100, 1), source("public", "users", "id")),
+            ((200, 3), source("auth", "tokens", "token")),
+            ((300, 1), source("billing", "cards", "number")),
+            ((400, 1), source("crm", "users", "email")),
+        ])
+    }
+
+    #[test]
+    fn aliases_follow_the_source_column() {
+        let fields = json!([
+            {"name": "id", "tableId": 100, "columnId": 1},
+            {"name": "e", "tableId": 100, "columnId": 2},
+            {"name": "token", "tableId": 200, "columnId": 3},
+            {"name": "n", "tableId": 300, "columnId": 1},
+            {"name": "email", "tableId": 400, "columnId": 1},
+        ]);
+        assert_eq!(source_table_oids(&fields), [100, 200, 300, 400]);
+        let plan = policy().plan(&fields, &sources());
+        let mut payload = json!({
+            "fields": fields,
+            "rows": [
+                {"id": 1, "e": "a@example.com", "token": "t-1", "n": "4111", "email": "crm@example.com"},
+                {"id": 2, "e": null, "token": "t-2", "n": 4111, "email": null},
+            ],
+        });
+        plan.apply(&mut payload);
+        let rows = payload["rows"].as_array().unwrap();
+        // crm.users is not listed: the schema-qualified key only covers public.users.
+        assert_eq!(
+            rows[0],
+            json!({"id": 1, "e": "***", "n": hash_value(&json!("4111")), "email": "crm@example.com"})
+        );
+        assert_eq!(rows[1]["e"], Value::Null);
+        assert_eq!(rows[1]["n"], rows[0]["n"]);
+        assert_eq!(rows[0]["n"].as_str().unwrap().len(), HASH_PREFIX_CHARS);
+        assert_eq!(
+            payload["redaction"],
+            json!({"masked": ["e"], "dropped": ["token"], "hashed": ["n"]})
+        );
+        let names: Vec<&str> = payload["fields"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|f| f["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["id", "e", "n", "email"]);
+    }
+
+    #[test]
+    fn computed_columns_match_by_result_name() {
+        let fields = json!([
+            {"name": "email", "tableId": 0, "columnId": 0},
+            {"name": "contact"},
+            {"name": "token"},
+        ]);
+        let mut payload =
+            json!({"fields": fields, "row": {"email": "x", "contact": "y", "token": "z"}});
+        policy().plan(&fields, &HashMap::new()).apply(&mut payload);
+        assert_eq!(payload["row"], json!({"email": "***", "contact": "y"}));
+
+        let fields = json!([{"name": "token", "tableId": 200, "columnId": 3}]);
+        let mut payload = json!({"fields": fields, "value": "secret"});
+        policy().plan(&fields, &sources()).apply(&mut payload);
+        assert_eq!(payload["value"], Value::Null);
+        assert!(RedactionPlan::default().is_empty());
+    }
+
+    #[test]
+    fn table_plans_match_written_columns() {
+        let columns = vec!["id".to_string(), "email".to_string(), "token".to_string()];
+        let mut row = json!({"id": 1, "email": "a@example.com", "token": "t-1"});
+        policy()
+            .plan_for_table(None, "users", &columns)
+            .apply_row(&mut row);
+        assert_eq!(row, json!({"id": 1, "email": "***", "token": "t-1"}));
+        let mut row = json!({"id": 1, "email": "a@example.com", "token": "t-1"});
+        policy()
+            .plan_for_table(Some("auth"), "tokens", &columns)
+            .apply_row(&mut row);
+        assert_eq!(row, json!({"id": 1, "email": "a@example.com"}));
+        assert!(policy()
+            .plan_for_table(None, "crm.users", &columns)
+            .is_empty());
+    }
+
+    #[test]
+    fn rejects_malformed_policies() {
+        for bad in [
+            json!(["users"]),
+            json!({"users": {"columns": []}}),
+            json!({"users": {"columns": ["email"], "mode": "blur"}}),
+        ] {
+            assert!(RedactionPolicy::parse(Some(&bad)).is_err(), "{}", bad);
+        }
+        assert!(RedactionPolicy::parse(None).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,214 @@
+use crate::errors::ToolError;
+use serde_json::Value;
+
+pub const PG_REPORTS: &[&str] = &["activity", "locks", "replication"];
+pub const PG_REPORTS_MIN_VERSION: i64 = 120_000;
+pub const DEFAULT_REPORT_LIMIT: usize = 100;
+pub const MAX_REPORT_LIMIT: usize = 1_000;
+pub const MAX_QUERY_CHARS: usize = 1_024;
+
+#[derive(Clone, Copy, Debug)]
+pub struct PgReportOptions {
+    pub version_num: i64,
+    pub include_queries: bool,
+}
+
+pub fn parse_reports(value: Option<&Value>) -> Result<Vec<&'static str>, ToolError> {
+    let requested: Vec<String> = match value {
+        None | Some(Value::Null) => return Ok(Vec::new()),
+        Some(Value::String(name)) => vec![name.clone()],
+        Some(Value::Array(items)) => items
+            .iter()
+            .map(|item| {
+                item.as_str()
+                    .map(|s| s.to_string())
+                    .ok_or_else(|| ToolError::invalid_params("reports must be an array of strings"))
+            })
+            .collect::<Result<_, _>>()?,
+        Some(_) => {
+            return Err(ToolError::invalid_params(
+                "reports must be a string or an array of strings",
+            ))
+        }
+    };
+    let mut out = Vec::new();
+    for name in requested {
+        let normalized = name.trim().to_lowercase();
+        if normalized == "all" {
+            return Ok(PG_REPORTS.to_vec());
+        }
+        let known = PG_REPORTS
+            .iter()
+            .find(|report| **report == normalized)
+            .ok_or_else(|| {
+                ToolError::invalid_params(format!("Unknown database_info report: {}", name))
+                    .with_hint(format!("Use one of: {}, all.", PG_REPORTS.join(", ")))
+            })?;
+        if !out.contains(known) {
+            out.push(*known);
+        }
+    }
+    Ok(out)
+}
+
+fn query_column(alias: &str, source: &str, options: &PgReportOptions) -> String {
+    if options.include_queries {
+        format!(", left({}.query, {}) AS {}", source, MAX_QUERY_CHARS, alias)
+    } else {
+        String::new()
+    }
+}
+
+// $1 = min_duration_ms, $2 = limit. Ages are in ms; the oldest transaction sorts first.
+// int4 columns are widened to bigint because rows are decoded as i64.
+pub fn activity_sql(options: &PgReportOptions) -> String {
+    let mut columns = String::from(
+        "a.pid::bigint AS pid, a.usename AS user_name, a.datname AS database_name, a.application_name, \
+         a.client_addr::text AS client_addr, a.backend_type, a.state, a.wait_event_type, \
+         a.wait_event, \
+         (extract(epoch FROM now() - a.xact_start) * 1000)::bigint AS xact_age_ms, \
+         (extract(epoch FROM now() - a.query_start) * 1000)::bigint AS query_age_ms, \
+         a.backend_start",
+    );
+    if options.version_num >= 130_000 {
+        columns.push_str(", a.leader_pid::bigint AS leader_pid");
+    }
+    if options.version_num >= 140_000 {
+        columns.push_str(", a.query_id");
+    }
+    columns.push_str(&query_column("query", "a", options));
+    format!(
+        "SELECT {} FROM pg_stat_activity a \
+         WHERE a.pid <> pg_backend_pid() AND a.backend_type = 'client backend' \
+         AND ($1::bigint = 0 OR (extract(epoch FROM now() - coalesce(a.xact_start, a.query_start)) * 1000) >= $1::bigint) \
+         ORDER BY coalesce(a.xact_start, a.query_start) ASC NULLS LAST \
+         LIMIT $2::bigint",
+        columns
+    )
+}
+
+// $1 = limit. One row per (blocked, blocker) pair, with the lock the blocked backend waits on.
+pub fn locks_sql(options: &PgReportOptions) -> String {
+    format!(
+        "SELECT blocked.pid::bigint AS blocked_pid, blocked.usename AS blocked_user, \
+         blocked.wait_event_type, blocked.wait_event, \
+         (extract(epoch FROM now() - blocked.query_start) * 1000)::bigint AS blocked_ms, \
+         waiting.locktype, waiting.mode, waiting.relation::regclass::text AS relation, \
+         blocker.pid::bigint AS blocking_pid, blocker.usename AS blocking_user, \
+         blocker.state AS blocking_state, \
+         (extract(epoch FROM now() - blocker.xact_start) * 1000)::bigint AS blocking_xact_age_ms, \
+         cardinality(pg_blocking_pids(blocker.pid)) > 0 AS blocking_is_blocked{}{} \
+         FROM pg_stat_activity blocked \
+         CROSS JOIN LATERAL unnest(pg_blocking_pids(blocked.pid)) AS b(pid) \
+         JOIN pg_stat_activity blocker ON blocker.pid = b.pid \
+         LEFT JOIN LATERAL (SELECT l.locktype, l.mode, l.relation FROM pg_locks l \
+           WHERE l.pid = blocked.pid AND NOT l.granted LIMIT 1) waiting ON true \
+         ORDER BY blocked_ms DESC NULLS LAST, blocked.pid, blocker.pid \
+         LIMIT $1::bigint",
+        query_column("blocked_query", "blocked", options),
+        query_column("blocking_query", "blocker", options),
+    )
+}
+
+pub fn recovery_status_sql() -> &'static str {
+    "SELECT current_setting('server_version_num')::bigint AS version_num, pg_is_in_recovery() AS in_recovery"
+}
+
+// $1 = limit. Primary side: connected standbys with lag in ms and bytes.
+pub fn replication_senders_sql() -> String {
+    "SELECT r.pid::bigint AS pid, r.usename AS user_name, r.application_name, r.client_addr::text AS client_addr, \
+     r.state, r.sync_state, r.sent_lsn::text AS sent_lsn, r.write_lsn::text AS write_lsn, \
+     r.flush_lsn::text AS flush_lsn, r.replay_lsn::text AS replay_lsn, \
+     pg_wal_lsn_diff(pg_current_wal_lsn(), r.replay_lsn)::bigint AS replay_lag_bytes, \
+     (extract(epoch FROM r.write_lag) * 1000)::bigint AS write_lag_ms, \
+     (extract(epoch FROM r.flush_lag) * 1000)::bigint AS flush_lag_ms, \
+     (extract(epoch FROM r.replay_lag) * 1000)::bigint AS replay_lag_ms, \
+     r.reply_time \
+     FROM pg_stat_replication r ORDER BY r.application_name, r.pid LIMIT $1::bigint"
+        .to_string()
+}
+
+pub fn replication_slots_sql(options: &PgReportOptions) -> String {
+    let retained = "pg_wal_lsn_diff(CASE WHEN pg_is_in_recovery() THEN pg_last_wal_receive_lsn() \
+                    ELSE pg_current_wal_lsn() END, s.restart_lsn)::bigint AS retained_bytes";
+    let extra = if options.version_num >= 130_000 {
+        ", s.wal_status, s.safe_wal_size"
+    } else {
+        ""
+    };
+    format!(
+        "SELECT s.slot_name, s.slot_type, s.database, s.active, s.active_pid::bigint AS active_pid, \
+         s.restart_lsn::text AS restart_lsn, {}{} \
+         FROM pg_replication_slots s ORDER BY s.slot_name LIMIT $1::bigint",
+        retained, extra
+    )
+}
+
+// Replica side: WAL receiver state plus how far replay trails receipt.
+pub fn replica_status_sql(options: &PgReportOptions) -> String {
+    let received = if options.version_num >= 130_000 {
+        "w.written_lsn::text AS received_lsn, w.flushed_lsn::text AS flushed_lsn"
+    } else {
+        "w.received_lsn::text AS received_lsn, NULL::text AS flushed_lsn"
+    };
+    format!(
+        "SELECT pg_last_wal_receive_lsn()::text AS last_receive_lsn, \
+         pg_last_wal_replay_lsn()::text AS last_replay_lsn, \
+         pg_wal_lsn_diff(pg_last_wal_receive_lsn(), pg_last_wal_replay_lsn())::bigint AS replay_lag_bytes, \
+         pg_last_xact_replay_timestamp() AS last_replay_at, \
+         (extract(epoch FROM now() - pg_last_xact_replay_timestamp()) * 1000)::bigint AS replay_delay_ms, \
+         w.status AS receiver_status, w.sender_host, w.sender_port::bigint AS sender_port, w.slot_name, {} \
+         FROM (SELECT 1) one LEFT JOIN pg_stat_wal_receiver w ON true",
+        received
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PG12: PgReportOptions = PgReportOptions {
+        version_num: 120_015,
+        include_queries: false,
+    };
+    const PG15: PgReportOptions = PgReportOptions {
+        version_num: 150_006,
+        include_queries: true,
+    };
+
+    #[test]
+    fn activity_columns_follow_server_version_and_privacy() {
+        let pg12 = activity_sql(&PG12);
+        assert!(!pg12.contains("leader_pid"));
+        assert!(!pg12.contains("query_id"));
+        assert!(!pg12.contains("a.query,"));
+        let pg15 = activity_sql(&PG15);
+        assert!(pg15.contains("a.leader_pid"));
+        assert!(pg15.contains("a.query_id"));
+        assert!(pg15.contains("left(a.query, 1024) AS query"));
+    }
+
+    #[test]
+    fn replication_sql_uses_version_specific_receiver_and_slot_columns() {
+        assert!(replica_status_sql(&PG12).contains("w.received_lsn"));
+        assert!(replica_status_sql(&PG15).contains("w.written_lsn"));
+        assert!(!replication_slots_sql(&PG12).contains("wal_status"));
+        assert!(replication_slots_sql(&PG15).contains("s.wal_status"));
+        assert!(!locks_sql(&PG12).contains("blocking_query"));
+        assert!(locks_sql(&PG15).contains("blocking_query"));
+    }
+
+    #[test]
+    fn reports_parse_aliases_and_reject_unknown_names() {
+        assert_eq!(
+            parse_reports(Some(&serde_json::json!("all"))).expect("all"),
+            PG_REPORTS.to_vec()
+        );
+        assert_eq!(
+            parse_reports(Some(&serde_json::json!(["locks", "locks"]))).expect("dedupe"),
+            vec!["locks"]
+        );
+        assert!(parse_reports(Some(&serde_json::json!(["lock"]))).is_err());
+        assert!(parse_reports(None).expect("none").is_empty());
+    }
+}
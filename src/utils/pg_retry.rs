@@ -0,0 +1,211 @@
+use crate::errors::ToolError;
+use crate::utils::stability::compute_backoff_delay_ms;
+use crate::utils::usage::{self, Counter};
+use serde_json::Value;
+use std::future::Future;
+use std::time::Duration;
+
+// serialization_failure and deadlock_detected: Postgres expects the client to re-run the
+// statement (or the whole transaction).
+pub const RETRYABLE_SQLSTATES: &[&str] = &["40001", "40P01"];
+
+const DEFAULT_MAX_ATTEMPTS: u32 = 4;
+const DEFAULT_BASE_DELAY_MS: u64 = 50;
+const DEFAULT_MAX_DELAY_MS: u64 = 1000;
+const BACKOFF_JITTER: f64 = 0.5;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SerializationRetry {
+    pub max_attempts: u32,
+    pub base_delay_ms: u64,
+    pub max_delay_ms: u64,
+}
+
+impl Default for SerializationRetry {
+    fn default() -> Self {
+        Self {
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            base_delay_ms: DEFAULT_BASE_DELAY_MS,
+            max_delay_ms: DEFAULT_MAX_DELAY_MS,
+        }
+    }
+}
+
+fn positive(value: Option<&Value>, label: &str) -> Result<Option<u64>, ToolError> {
+    match value {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => value.as_u64().map(Some).ok_or_else(|| {
+            ToolError::invalid_params(format!(
+                "retry_serialization.{} must be a non-negative integer",
+                label
+            ))
+        }),
+    }
+}
+
+impl SerializationRetry {
+    // `retry_serialization`: true/false or `{max_attempts, base_delay_ms, max_delay_ms}`;
+    // absent means `default_on`.
+    pub fn parse(value: Option<&Value>, default_on: bool) -> Result<Option<Self>, ToolError> {
+        match value {
+            None | Some(Value::Null) => Ok(default_on.then(Self::default)),
+            Some(Value::Bool(enabled)) => Ok(enabled.then(Self::default)),
+            Some(Value::Object(map)) => {
+                if map.get("enabled").and_then(|v| v.as_bool()) == Some(false) {
+                    return Ok(None);
+                }
+                let defaults = Self::default();
+                let max_attempts = positive(map.get("max_attempts"), "max_attempts")?
+                    .map_or(defaults.max_attempts, |n| n.clamp(1, 20) as u32);
+                Ok(Some(Self {
+                    max_attempts,
+                    base_delay_ms: positive(map.get("base_delay_ms"), "base_delay_ms")?
+                        .unwrap_or(defaults.base_delay_ms),
+                    max_delay_ms: positive(map.get("max_delay_ms"), "max_delay_ms")?
+                        .unwrap_or(defaults.max_delay_ms),
+                }))
+            }
+            Some(_) => Err(ToolError::invalid_params(
+                "retry_serialization must be a boolean or an object",
+            )),
+        }
+    }
+
+    fn delay(&self, retry: u32) -> Duration {
+        Duration::from_millis(compute_backoff_delay_ms(
+            retry as usize,
+            self.base_delay_ms,
+            self.max_delay_ms,
+            BACKOFF_JITTER,
+        ))
+    }
+}
+
+pub fn sqlstate(err: &ToolError) -> Option<&str> {
+    err.details
+        .as_ref()
+        .and_then(|details| details.get("sqlstate"))
+        .and_then(|v| v.as_str())
+}
+
+pub fn is_retryable_sqlstate(err: &ToolError) -> bool {
+    sqlstate(err).is_some_and(|code| RETRYABLE_SQLSTATES.contains(&code))
+}
+
+// The original error, marked retryable with the number of attempts made.
+pub fn exhausted(mut err: ToolError, attempts: u32) -> ToolError {
+    err.retryable = true;
+    let mut details = err.details.take().unwrap_or_else(|| serde_json::json!({}));
+    details["attempts"] = Value::from(attempts);
+    err.details = Some(details);
+    if err.hint.is_none() {
+        err.hint = Some(format!(
+            "Gave up after {} attempt(s); the conflict is transient, so the call can be retried (raise retry_serialization.max_attempts to retry longer).",
+            attempts
+        ));
+    }
+    err
+}
+
+// Runs `attempt` until it succeeds, fails with a non-retryable error, or the policy runs out of
+// attempts. Returns the value with the number of retries made.
+pub async fn run_with_retry<T, F, Fut>(
+    policy: Option<&SerializationRetry>,
+    mut attempt: F,
+) -> Result<(T, u32), ToolError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, ToolError>>,
+{
+    let mut retries = 0u32;
+    loop {
+        match attempt().await {
+            Ok(value) => return Ok((value, retries)),
+            Err(err) => {
+                let Some(policy) = policy.filter(|_| is_retryable_sqlstate(&err)) else {
+                    return Err(err);
+                };
+                if retries + 1 >= policy.max_attempts {
+                    return Err(exhausted(err, retries + 1));
+                }
+                retries += 1;
+                usage::record(Counter::Retries, 1);
+                tokio::time::sleep(policy.delay(retries)).await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn pg_error(code: &str) -> ToolError {
+        ToolError::internal("PostgreSQL error").with_details(json!({"sqlstate": code}))
+    }
+
+    #[test]
+    fn parse_defaults_and_overrides() {
+        assert_eq!(SerializationRetry::parse(None, false).unwrap(), None);
+        assert_eq!(
+            SerializationRetry::parse(None, true).unwrap(),
+            Some(SerializationRetry::default())
+        );
+        assert_eq!(
+            SerializationRetry::parse(Some(&json!(false)), true).unwrap(),
+            None
+        );
+        let custom =
+            SerializationRetry::parse(Some(&json!({"max_attempts": 0, "base_delay_ms": 5})), false)
+                .unwrap()
+                .unwrap();
+        assert_eq!(custom.max_attempts, 1);
+        assert_eq!(custom.base_delay_ms, 5);
+        assert!(SerializationRetry::parse(Some(&json!("yes")), false).is_err());
+    }
+
+    #[tokio::test]
+    async fn retries_only_serialization_errors_and_marks_exhaustion() {
+        let policy = SerializationRetry {
+            max_attempts: 3,
+            base_delay_ms: 1,
+            max_delay_ms: 1,
+        };
+        let mut calls = 0;
+        let (value, retries) = run_with_retry(Some(&policy), || {
+            calls += 1;
+            let outcome = if calls < 3 {
+                Err(pg_error("40P01"))
+            } else {
+                Ok(calls)
+            };
+            async move { outcome }
+        })
+        .await
+        .unwrap();
+        assert_eq!((value, retries), (3, 2));
+
+        let err = run_with_retry(Some(&policy), || async { Err::<(), _>(pg_error("40001")) })
+            .await
+            .unwrap_err();
+        assert!(err.retryable);
+        assert_eq!(err.details.as_ref().unwrap()["attempts"], 3);
+        assert_eq!(sqlstate(&err), Some("40001"));
+
+        let mut calls = 0;
+        let err = run_with_retry(Some(&policy), || {
+            calls += 1;
+            async { Err::<(), _>(pg_error("23505")) }
+        })
+        .await
+        .unwrap_err();
+        assert_eq!(calls, 1);
+        assert!(!err.retryable);
+
+        let err = run_with_retry(None, || async { Err::<(), _>(pg_error("40001")) })
+            .await
+            .unwrap_err();
+        assert!(!err.retryable);
+    }
+}
@@ -0,0 +1,400 @@
+use crate::errors::ToolError;
+use crate::utils::sql::quote_qualified_identifier;
+use serde_json::Value;
+
+// Rows inspected when inferring column types for `create_table`.
+pub const DEFAULT_INFER_SAMPLE_ROWS: usize = 1000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CreateTable {
+    Never,
+    IfMissing,
+    Replace,
+}
+
+impl CreateTable {
+    pub fn parse(value: Option<&Value>) -> Result<Self, ToolError> {
+        let raw = match value {
+            None | Some(Value::Null) => return Ok(CreateTable::Never),
+            Some(Value::String(raw)) => raw.trim(),
+            Some(_) => "",
+        };
+        match raw {
+            "never" => Ok(CreateTable::Never),
+            "if_missing" => Ok(CreateTable::IfMissing),
+            "replace" => Ok(CreateTable::Replace),
+            _ => Err(ToolError::invalid_params(
+                "create_table must be one of: never, if_missing, replace",
+            )),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CreateTable::Never => "never",
+            CreateTable::IfMissing => "if_missing",
+            CreateTable::Replace => "replace",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Kind {
+    Bool,
+    Int,
+    Float,
+    Date,
+    Timestamp,
+    Text,
+    Json,
+}
+
+impl Kind {
+    fn pg_type(self) -> &'static str {
+        match self {
+            Kind::Bool => "boolean",
+            Kind::Int => "bigint",
+            Kind::Float => "double precision",
+            Kind::Date => "date",
+            Kind::Timestamp => "timestamptz",
+            Kind::Text => "text",
+            Kind::Json => "jsonb",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Kind::Bool => "boolean",
+            Kind::Int => "integer",
+            Kind::Float => "float",
+            Kind::Date => "date",
+            Kind::Timestamp => "timestamp",
+            Kind::Text => "string",
+            Kind::Json => "json",
+        }
+    }
+
+    // Numeric and temporal kinds widen; any other mix is a conflict that lands on jsonb when
+    // structured values are involved and text otherwise.
+    fn merge(self, other: Kind) -> (Kind, bool) {
+        match (self, other) {
+            (a, b) if a == b => (a, false),
+            (Kind::Int, Kind::Float) | (Kind::Float, Kind::Int) => (Kind::Float, false),
+            (Kind::Date, Kind::Timestamp) | (Kind::Timestamp, Kind::Date) => {
+                (Kind::Timestamp, false)
+            }
+            (Kind::Json, _) | (_, Kind::Json) => (Kind::Json, true),
+            _ => (Kind::Text, true),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct InferredColumn {
+    pub name: String,
+    pub pg_type: &'static str,
+    pub nullable: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct InferredSchema {
+    pub columns: Vec<InferredColumn>,
+    pub warnings: Vec<String>,
+    pub sampled_rows: usize,
+}
+
+impl InferredSchema {
+    pub fn describe(&self) -> Value {
+        serde_json::json!({
+            "columns": self
+                .columns
+                .iter()
+                .map(|column| serde_json::json!({
+                    "name": column.name,
+                    "type": column.pg_type,
+                    "nullable": column.nullable,
+                }))
+                .collect::<Vec<_>>(),
+            "sampled_rows": self.sampled_rows,
+            "warnings": self.warnings,
+        })
+    }
+}
+
+fn is_timestamp(text: &str) -> bool {
+    chrono::DateTime::parse_from_rfc3339(text).is_ok()
+        || ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"]
+            .iter()
+            .any(|fmt| chrono::NaiveDateTime::parse_from_str(text, fmt).is_ok())
+}
+
+// With `sniff`, strings are read the way CSV cells arrive ("42", "true"); numbers with a
+// leading zero stay text so identifiers like zip codes keep their digits.
+fn classify_string(text: &str, sniff: bool) -> Kind {
+    let trimmed = text.trim();
+    if sniff && !trimmed.is_empty() {
+        let lower = trimmed.to_lowercase();
+        if lower == "true" || lower == "false" {
+            return Kind::Bool;
+        }
+        let digits = trimmed.strip_prefix('-').unwrap_or(trimmed);
+        let leading_zero = digits.len() > 1 && digits.starts_with('0') && !digits.starts_with("0.");
+        if !leading_zero
+            && digits.chars().all(|c| c.is_ascii_digit())
+            && trimmed.parse::<i64>().is_ok()
+        {
+            return Kind::Int;
+        }
+        if !leading_zero
+            && digits.chars().next().is_some_and(|c| c.is_ascii_digit())
+            && trimmed.parse::<f64>().is_ok_and(f64::is_finite)
+        {
+            return Kind::Float;
+        }
+    }
+    if chrono::NaiveDate::parse_from_str(trimmed, "%Y-%m-%d").is_ok() {
+        return Kind::Date;
+    }
+    if is_timestamp(trimmed) {
+        return Kind::Timestamp;
+    }
+    Kind::Text
+}
+
+fn classify(value: &Value, sniff: bool) -> Option<Kind> {
+    match value {
+        Value::Null => None,
+        Value::Bool(_) => Some(Kind::Bool),
+        Value::Number(num) if num.is_i64() => Some(Kind::Int),
+        Value::Number(_) => Some(Kind::Float),
+        Value::String(text) => Some(classify_string(text, sniff)),
+        Value::Array(_) | Value::Object(_) => Some(Kind::Json),
+    }
+}
+
+// Column types for `columns` from the first `sample` rows (objects, or arrays in column order).
+pub fn infer_schema(
+    rows: &[Value],
+    columns: &[String],
+    sniff_strings: bool,
+    sample: usize,
+) -> InferredSchema {
+    let sampled = &rows[..rows.len().min(sample.max(1))];
+    let mut out = Vec::with_capacity(columns.len());
+    let mut warnings = Vec::new();
+    for (idx, name) in columns.iter().enumerate() {
+        let mut kind: Option<Kind> = None;
+        let mut seen: Vec<Kind> = Vec::new();
+        let mut conflict = false;
+        let mut nullable = false;
+        for row in sampled {
+            let value = match row {
+                Value::Object(map) => map.get(name),
+                Value::Array(items) => items.get(idx),
+                _ => None,
+            };
+            let Some(next) = value.and_then(|v| classify(v, sniff_strings)) else {
+                nullable = true;
+                continue;
+            };
+            if !seen.contains(&next) {
+                seen.push(next);
+            }
+            kind = Some(match kind {
+                None => next,
+                Some(current) => {
+                    let (merged, clash) = current.merge(next);
+                    conflict |= clash;
+                    merged
+                }
+            });
+        }
+        let kind = match kind {
+            Some(kind) => kind,
+            None => {
+                warnings.push(format!(
+                    "column {}: only nulls in the sample; created as text",
+                    name
+                ));
+                Kind::Text
+            }
+        };
+        if conflict {
+            let labels: Vec<&str> = seen.iter().map(|kind| kind.label()).collect();
+            warnings.push(format!(
+                "column {}: mixed {} values in the sample; created as {}",
+                name,
+                labels.join(", "),
+                kind.pg_type()
+            ));
+        }
+        out.push(InferredColumn {
+            name: name.clone(),
+            pg_type: kind.pg_type(),
+            nullable,
+        });
+    }
+    InferredSchema {
+        columns: out,
+        warnings,
+        sampled_rows: sampled.len(),
+    }
+}
+
+pub fn create_table_sql(
+    qualified: &str,
+    schema: &InferredSchema,
+    primary_key: &[String],
+    if_not_exists: bool,
+) -> Result<String, ToolError> {
+    for key in primary_key {
+        if !schema.columns.iter().any(|column| &column.name == key) {
+            return Err(ToolError::invalid_params(format!(
+                "primary_key column {} is not among the inserted columns",
+                key
+            ))
+            .with_details(serde_json::json!({
+                "columns": schema.columns.iter().map(|c| c.name.clone()).collect::<Vec<_>>(),
+            })));
+        }
+    }
+    let mut parts = Vec::with_capacity(schema.columns.len() + 1);
+    for column in &schema.columns {
+        let not_null = !column.nullable || primary_key.contains(&column.name);
+        parts.push(format!(
+            "{} {}{}",
+            quote_qualified_identifier(&column.name)?,
+            column.pg_type,
+            if not_null { " NOT NULL" } else { "" }
+        ));
+    }
+    if !primary_key.is_empty() {
+        let keys = primary_key
+            .iter()
+            .map(|key| quote_qualified_identifier(key))
+            .collect::<Result<Vec<_>, _>>()?;
+        parts.push(format!("PRIMARY KEY ({})", keys.join(", ")));
+    }
+    Ok(format!(
+        "CREATE TABLE {}{} ({})",
+        if if_not_exists { "IF NOT EXISTS " } else { "" },
+        qualified,
+        parts.join(", ")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn names(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn types(schema: &InferredSchema) -> Vec<(&str, &str, bool)> {
+        schema
+            .columns
+            .iter()
+            .map(|c| (c.name.as_str(), c.pg_type, c.nullable))
+            .collect()
+    }
+
+    #[test]
+    fn infers_json_types_with_widening_and_nulls() {
+        let rows = vec![
+            json!({"id": 1, "score": 1, "ok": true, "at": "2024-05-01T10:00:00Z", "day": "2024-05-01", "meta": {"a": 1}, "zip": "01234"}),
+            json!({"id": 2, "score": 2.5, "ok": false, "at": "2024-05-02 11:00:00", "day": null, "meta": [1], "zip": "99501"}),
+        ];
+        let schema = infer_schema(
+            &rows,
+            &names(&["id", "score", "ok", "at", "day", "meta", "zip", "gone"]),
+            false,
+            DEFAULT_INFER_SAMPLE_ROWS,
+        );
+        assert_eq!(
+            types(&schema),
+            vec![
+                ("id", "bigint", false),
+                ("score", "double precision", false),
+                ("ok", "boolean", false),
+                ("at", "timestamptz", false),
+                ("day", "date", true),
+                ("meta", "jsonb", false),
+                ("zip", "text", false),
+                ("gone", "text", true),
+            ]
+        );
+        assert_eq!(
+            schema.warnings,
+            vec!["column gone: only nulls in the sample; created as text"]
+        );
+    }
+
+    #[test]
+    fn conflicts_fall_back_to_text_or_jsonb() {
+        let rows = vec![
+            json!({"v": 1, "doc": {"a": 1}}),
+            json!({"v": "one", "doc": "plain"}),
+        ];
+        let schema = infer_schema(&rows, &names(&["v", "doc"]), false, 10);
+        assert_eq!(
+            types(&schema),
+            vec![("v", "text", false), ("doc", "jsonb", false)]
+        );
+        assert_eq!(
+            schema.warnings,
+            vec![
+                "column v: mixed integer, string values in the sample; created as text",
+                "column doc: mixed json, string values in the sample; created as jsonb",
+            ]
+        );
+    }
+
+    #[test]
+    fn sniffs_csv_strings_and_respects_the_sample() {
+        let rows = vec![
+            json!(["1", "2.5", "TRUE", "007", "", "x"]),
+            json!(["-20", "3", "false", "008", "", "y"]),
+            json!(["oops", "oops", "oops", "oops", "oops", "oops"]),
+        ];
+        let cols = names(&["n", "f", "b", "code", "blank", "s"]);
+        let schema = infer_schema(&rows, &cols, true, 2);
+        assert_eq!(schema.sampled_rows, 2);
+        assert_eq!(
+            types(&schema),
+            vec![
+                ("n", "bigint", false),
+                ("f", "double precision", false),
+                ("b", "boolean", false),
+                ("code", "text", false),
+                ("blank", "text", false),
+                ("s", "text", false),
+            ]
+        );
+        assert!(schema.warnings.is_empty());
+        let unsniffed = infer_schema(&rows, &cols, false, 2);
+        assert_eq!(unsniffed.columns[0].pg_type, "text");
+    }
+
+    #[test]
+    fn ddl_quotes_identifiers_and_validates_primary_key() {
+        let rows = vec![json!({"id": 1, "Na\"me": "a", "note": null})];
+        let schema = infer_schema(&rows, &names(&["id", "Na\"me", "note"]), false, 10);
+        assert_eq!(
+            create_table_sql("\"raw\".\"events\"", &schema, &names(&["id"]), true).unwrap(),
+            "CREATE TABLE IF NOT EXISTS \"raw\".\"events\" (\"id\" bigint NOT NULL, \"Na\"\"me\" text NOT NULL, \"note\" text, PRIMARY KEY (\"id\"))"
+        );
+        let err = create_table_sql("\"events\"", &schema, &names(&["missing"]), false).unwrap_err();
+        assert_eq!(
+            err.message,
+            "primary_key column missing is not among the inserted columns"
+        );
+        assert!(CreateTable::parse(Some(&json!("drop"))).is_err());
+        assert_eq!(CreateTable::parse(None).unwrap(), CreateTable::Never);
+        assert_eq!(
+            CreateTable::parse(Some(&json!("replace"))).unwrap(),
+            CreateTable::Replace
+        );
+    }
+}
@@ -0,0 +1,176 @@
+use crate::errors::ToolError;
+use crate::utils::data_path::get_path_value;
+use crate::utils::pg_schema::CreateTable;
+use serde_json::{Map, Value};
+
+const CONNECTION_KEYS: &[&str] = &[
+    "profile_name",
+    "project",
+    "target",
+    "connection",
+    "connection_url",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MissingField {
+    Error,
+    Skip,
+    Null,
+}
+
+impl MissingField {
+    fn parse(value: Option<&Value>) -> Result<Self, ToolError> {
+        match value.and_then(|v| v.as_str()).map(str::trim) {
+            None | Some("error") => Ok(MissingField::Error),
+            Some("skip") => Ok(MissingField::Skip),
+            Some("null") => Ok(MissingField::Null),
+            Some(_) => Err(ToolError::invalid_params(
+                "store_as.postgres.missing must be error, skip or null",
+            )),
+        }
+    }
+}
+
+// `store_as: { postgres: {...} }` appends one row per call to a table: each `mapping` column
+// takes the value at its path in the shaped output, plus `created_at` and `trace_id`.
+#[derive(Clone, Debug)]
+pub struct PostgresStore {
+    pub table: String,
+    pub schema: Option<String>,
+    pub mapping: Vec<(String, String)>,
+    pub missing: MissingField,
+    pub create_table: CreateTable,
+    connection: Map<String, Value>,
+}
+
+impl PostgresStore {
+    pub fn parse(store_as: Option<&Value>) -> Result<Option<Self>, ToolError> {
+        let Some(spec) = store_as.and_then(|v| v.get("postgres")) else {
+            return Ok(None);
+        };
+        if store_as.and_then(|v| v.get("key")).is_some() {
+            return Err(ToolError::invalid_params(
+                "store_as.postgres cannot be combined with store_as.key",
+            )
+            .with_hint("Use one store_as per call: a state key or a postgres table."));
+        }
+        let spec = spec
+            .as_object()
+            .ok_or_else(|| ToolError::invalid_params("store_as.postgres must be an object"))?;
+        let text = |key: &str| {
+            spec.get(key)
+                .and_then(|v| v.as_str())
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+        };
+        let table = text("table")
+            .ok_or_else(|| ToolError::invalid_params("store_as.postgres.table is required"))?;
+        let mapping = parse_mapping(spec.get("mapping"))?;
+        if text("profile_name").is_none()
+            && text("target").is_none()
+            && !spec.contains_key("connection")
+            && !spec.contains_key("connection_url")
+        {
+            return Err(ToolError::invalid_params(
+                "store_as.postgres needs profile_name or target",
+            ));
+        }
+        let connection = CONNECTION_KEYS
+            .iter()
+            .filter_map(|key| spec.get(*key).map(|value| (key.to_string(), value.clone())))
+            .collect();
+        Ok(Some(Self {
+            table,
+            schema: text("schema"),
+            mapping,
+            missing: MissingField::parse(spec.get("missing"))?,
+            create_table: CreateTable::parse(spec.get("create_table")).map_err(|err| {
+                ToolError::invalid_params(format!("store_as.postgres.{}", err.message))
+            })?,
+            connection,
+        }))
+    }
+
+    // The row for one call. `created_at` and `trace_id` are added unless the mapping sets them.
+    pub fn build_row(
+        &self,
+        output: &Value,
+        trace_id: &str,
+    ) -> Result<Map<String, Value>, ToolError> {
+        let mut row = Map::new();
+        for (column, path) in &self.mapping {
+            match get_path_value(output, path, true, None) {
+                Ok(value) => {
+                    row.insert(column.clone(), value);
+                }
+                Err(_) if self.missing == MissingField::Error => {
+                    return Err(ToolError::invalid_params(format!(
+                        "store_as.postgres.mapping.{}: path '{}' not found in the result",
+                        column, path
+                    ))
+                    .with_hint(
+                        "Set store_as.postgres.missing to skip or null to store the row anyway.",
+                    ));
+                }
+                Err(_) if self.missing == MissingField::Null => {
+                    row.insert(column.clone(), Value::Null);
+                }
+                Err(_) => {}
+            }
+        }
+        row.entry("created_at")
+            .or_insert_with(|| Value::String(chrono::Utc::now().to_rfc3339()));
+        row.entry("trace_id")
+            .or_insert_with(|| Value::String(trace_id.to_string()));
+        Ok(row)
+    }
+
+    // sql insert_bulk args for `row`. A call-level project scopes a target the spec names
+    // without one.
+    pub fn insert_args(&self, row: Map<String, Value>, call_args: &Value) -> Value {
+        let mut args = self.connection.clone();
+        if args.contains_key("target") && !args.contains_key("project") {
+            if let Some(project) = call_args
+                .get("project")
+                .or_else(|| call_args.get("project_name"))
+            {
+                args.insert("project".to_string(), project.clone());
+            }
+        }
+        args.insert("action".to_string(), Value::from("insert_bulk"));
+        args.insert("table".to_string(), Value::from(self.table.clone()));
+        if let Some(schema) = &self.schema {
+            args.insert("schema".to_string(), Value::from(schema.clone()));
+        }
+        args.insert("rows".to_string(), Value::Array(vec![Value::Object(row)]));
+        args.insert(
+            "create_table".to_string(),
+            Value::from(self.create_table.as_str()),
+        );
+        Value::Object(args)
+    }
+}
+
+fn parse_mapping(value: Option<&Value>) -> Result<Vec<(String, String)>, ToolError> {
+    let Some(map) = value
+        .and_then(|v| v.as_object())
+        .filter(|map| !map.is_empty())
+    else {
+        return Err(ToolError::invalid_params(
+            "store_as.postgres.mapping must be a non-empty object",
+        )
+        .with_hint(
+            "Map each column to a path in the result, e.g. {\"status\": \"smoke.status\"}.",
+        ));
+    };
+    map.iter()
+        .map(|(column, path)| match path.as_str().map(str::trim) {
+            Some(path) if !path.is_empty() => Ok((column.clone(), path.to_string())),
+            _ => Err(ToolError::invalid_params(format!(
+                "store_as.postgres.mapping.{} must be a path string",
+                column
+            ))),
+        })
+        .collect()
+}
@@ -0,0 +1,419 @@
+use crate::errors::{ToolError, ToolErrorKind};
+use crate::utils::user_paths::expand_home_path;
+use native_tls::{Certificate, Identity, TlsConnector};
+use postgres_native_tls::MakeTlsConnector;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use tokio_postgres::config::SslMode as SessionSslMode;
+use tokio_postgres::Config;
+
+const URL_TLS_KEYS: &[(&str, &str)] = &[
+    ("sslmode", "sslmode"),
+    ("sslrootcert", "ssl_root_cert"),
+    ("sslcert", "ssl_cert"),
+    ("sslkey", "ssl_key"),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SslMode {
+    Disable,
+    Prefer,
+    Require,
+    VerifyCa,
+    VerifyFull,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Verification {
+    None,
+    CaOnly,
+    Full,
+}
+
+impl SslMode {
+    pub fn parse(raw: &str) -> Result<Self, ToolError> {
+        match raw.trim().to_lowercase().as_str() {
+            "disable" => Ok(Self::Disable),
+            // libpq's "allow" tries plaintext first; tokio-postgres cannot, so it upgrades to prefer.
+            "allow" | "prefer" => Ok(Self::Prefer),
+            "require" => Ok(Self::Require),
+            "verify-ca" | "verify_ca" => Ok(Self::VerifyCa),
+            "verify-full" | "verify_full" => Ok(Self::VerifyFull),
+            other => Err(
+                ToolError::invalid_params(format!("Unsupported sslmode: {}", other))
+                    .with_hint("Use one of: disable, prefer, require, verify-ca, verify-full."),
+            ),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Disable => "disable",
+            Self::Prefer => "prefer",
+            Self::Require => "require",
+            Self::VerifyCa => "verify-ca",
+            Self::VerifyFull => "verify-full",
+        }
+    }
+
+    fn session_mode(&self) -> SessionSslMode {
+        match self {
+            Self::Disable => SessionSslMode::Disable,
+            Self::Prefer => SessionSslMode::Prefer,
+            _ => SessionSslMode::Require,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct PgTlsConfig {
+    pub mode: SslMode,
+    root_cert: Option<Vec<u8>>,
+    client_cert: Option<Vec<u8>>,
+    client_key: Option<Vec<u8>>,
+}
+
+impl std::fmt::Debug for PgTlsConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "PgTlsConfig({})", self.describe())
+    }
+}
+
+// tokio-postgres rejects verify-ca/verify-full and the sslrootcert/sslcert/sslkey keys, so
+// they are lifted out of the connection string (URL or keyword form) before parsing.
+pub fn split_tls_params(connection_url: &str) -> (String, HashMap<String, String>) {
+    let mut params = HashMap::new();
+    let lookup = |key: &str| {
+        URL_TLS_KEYS
+            .iter()
+            .find(|(url_key, _)| url_key.eq_ignore_ascii_case(key))
+            .map(|(_, name)| name.to_string())
+    };
+    if !connection_url.contains("://") {
+        let kept = connection_url
+            .split_whitespace()
+            .filter(|token| {
+                let Some((key, value)) = token.split_once('=') else {
+                    return true;
+                };
+                match lookup(key.trim()) {
+                    Some(name) => {
+                        params.insert(name, value.trim_matches('\'').to_string());
+                        false
+                    }
+                    None => true,
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        return (kept, params);
+    }
+    let Some((base, query)) = connection_url.split_once('?') else {
+        return (connection_url.to_string(), params);
+    };
+    let mut serializer = url::form_urlencoded::Serializer::new(String::new());
+    let mut kept = 0;
+    for (key, value) in url::form_urlencoded::parse(query.as_bytes()) {
+        match lookup(&key) {
+            Some(name) => {
+                params.insert(name, value.to_string());
+            }
+            None => {
+                serializer.append_pair(&key, &value);
+                kept += 1;
+            }
+        }
+    }
+    if kept == 0 {
+        return (base.to_string(), params);
+    }
+    (format!("{}?{}", base, serializer.finish()), params)
+}
+
+pub fn load_pem(value: &str, label: &str) -> Result<Vec<u8>, ToolError> {
+    let trimmed = value.trim();
+    if trimmed.starts_with("-----BEGIN") {
+        return Ok(trimmed.as_bytes().to_vec());
+    }
+    let path = expand_home_path(trimmed);
+    std::fs::read(&path).map_err(|err| {
+        ToolError::invalid_params(format!(
+            "{} could not be read from {}: {}",
+            label,
+            path.display(),
+            err
+        ))
+        .with_hint(
+            "Pass a readable PEM file path, or the PEM contents (for example via a secret ref).",
+        )
+    })
+}
+
+fn pem_certificates(bundle: &[u8]) -> Result<Vec<Certificate>, ToolError> {
+    let text = String::from_utf8_lossy(bundle);
+    let end_marker = "-----END CERTIFICATE-----";
+    let mut certs = Vec::new();
+    for block in text.split_inclusive(end_marker) {
+        let Some(start) = block.find("-----BEGIN CERTIFICATE-----") else {
+            continue;
+        };
+        let cert = Certificate::from_pem(&block.as_bytes()[start..]).map_err(|err| {
+            ToolError::invalid_params(format!(
+                "ssl_root_cert is not a valid PEM certificate: {}",
+                err
+            ))
+        })?;
+        certs.push(cert);
+    }
+    if certs.is_empty() {
+        return Err(ToolError::invalid_params(
+            "ssl_root_cert does not contain any PEM certificates",
+        ));
+    }
+    Ok(certs)
+}
+
+impl PgTlsConfig {
+    pub fn resolve(
+        connection: &Value,
+        url_params: &HashMap<String, String>,
+    ) -> Result<Self, ToolError> {
+        let setting = |names: &[&str]| -> Option<String> {
+            names
+                .iter()
+                .find_map(|name| connection.get(*name).and_then(|v| v.as_str()))
+                .map(|s| s.to_string())
+                .or_else(|| names.iter().find_map(|name| url_params.get(*name).cloned()))
+                .filter(|s| !s.trim().is_empty())
+        };
+        let mode = match setting(&["sslmode", "ssl_mode"]) {
+            Some(raw) => SslMode::parse(&raw)?,
+            None => SslMode::Prefer,
+        };
+        let root_cert = setting(&["ssl_root_cert", "sslrootcert"])
+            .map(|v| load_pem(&v, "ssl_root_cert"))
+            .transpose()?;
+        let client_cert = setting(&["ssl_cert", "sslcert"])
+            .map(|v| load_pem(&v, "ssl_cert"))
+            .transpose()?;
+        let client_key = setting(&["ssl_key", "sslkey"])
+            .map(|v| load_pem(&v, "ssl_key"))
+            .transpose()?;
+        if client_cert.is_some() != client_key.is_some() {
+            return Err(ToolError::invalid_params(
+                "ssl_cert and ssl_key must be provided together",
+            ));
+        }
+        Ok(Self {
+            mode,
+            root_cert,
+            client_cert,
+            client_key,
+        })
+    }
+
+    // libpq treats require + a root certificate as verify-ca.
+    fn verification(&self) -> Verification {
+        match self.mode {
+            SslMode::Disable | SslMode::Prefer => Verification::None,
+            SslMode::Require if self.root_cert.is_some() => Verification::CaOnly,
+            SslMode::Require => Verification::None,
+            SslMode::VerifyCa => Verification::CaOnly,
+            SslMode::VerifyFull => Verification::Full,
+        }
+    }
+
+    pub fn apply(&self, config: &mut Config) {
+        config.ssl_mode(self.mode.session_mode());
+    }
+
+    pub fn fingerprint(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.mode.as_str().as_bytes());
+        for material in [&self.root_cert, &self.client_cert, &self.client_key] {
+            hasher.update([0u8]);
+            if let Some(bytes) = material {
+                hasher.update(Sha256::digest(bytes));
+            }
+        }
+        hex::encode(hasher.finalize())[..16].to_string()
+    }
+
+    pub fn describe(&self) -> Value {
+        serde_json::json!({
+            "sslmode": self.mode.as_str(),
+            "verify": match self.verification() {
+                Verification::None => "none",
+                Verification::CaOnly => "ca",
+                Verification::Full => "full",
+            },
+            "root_cert": self.root_cert.is_some(),
+            "client_cert": self.client_cert.is_some(),
+        })
+    }
+
+    // Without ssl_root_cert, verify-ca/verify-full fall back to the system trust store.
+    pub fn connector(&self) -> Result<MakeTlsConnector, ToolError> {
+        let mut builder = TlsConnector::builder();
+        if let Some(bundle) = self.root_cert.as_ref() {
+            builder.disable_built_in_roots(true);
+            for cert in pem_certificates(bundle)? {
+                builder.add_root_certificate(cert);
+            }
+        }
+        if let (Some(cert), Some(key)) = (self.client_cert.as_ref(), self.client_key.as_ref()) {
+            let identity = Identity::from_pkcs8(cert, key).map_err(|err| {
+                ToolError::invalid_params(format!("ssl_cert/ssl_key could not be loaded: {}", err))
+                    .with_hint("Use a PEM certificate and an unencrypted PEM private key.")
+            })?;
+            builder.identity(identity);
+        }
+        match self.verification() {
+            Verification::None => {
+                builder.danger_accept_invalid_certs(true);
+                builder.danger_accept_invalid_hostnames(true);
+            }
+            Verification::CaOnly => {
+                builder.danger_accept_invalid_hostnames(true);
+            }
+            Verification::Full => {}
+        }
+        let connector = builder.build().map_err(|err| {
+            ToolError::internal(format!("Failed to initialize TLS connector: {}", err))
+        })?;
+        Ok(MakeTlsConnector::new(connector))
+    }
+}
+
+pub fn error_chain(err: &(dyn std::error::Error + 'static)) -> String {
+    let mut parts = vec![err.to_string()];
+    let mut source = err.source();
+    while let Some(next) = source {
+        let text = next.to_string();
+        if !parts.iter().any(|part| part.contains(&text)) {
+            parts.push(text);
+        }
+        source = next.source();
+    }
+    parts.join(": ")
+}
+
+pub fn map_connect_error(err: &tokio_postgres::Error, tls: &PgTlsConfig) -> ToolError {
+    if let Some(db) = err.as_db_error() {
+        let code = db.code().code();
+        if code == "28P01" || code == "28000" {
+            return ToolError::new(
+                ToolErrorKind::Denied,
+                "PG_AUTH_FAILED",
+                format!("PostgreSQL authentication failed: {}", db.message()),
+            )
+            .with_hint("Check the user/password, and that pg_hba.conf allows this user over this transport (TLS or plaintext).")
+            .with_details(serde_json::json!({ "sqlstate": code, "tls": tls.describe() }));
+        }
+        return ToolError::internal(format!("PostgreSQL error: {}", db.message()))
+            .with_details(serde_json::json!({ "sqlstate": code }));
+    }
+    let chain = error_chain(err);
+    let lower = chain.to_lowercase();
+    if lower.contains("tls handshake") {
+        let verification = [
+            "certificate",
+            "verify",
+            "hostname",
+            "self signed",
+            "self-signed",
+        ]
+        .iter()
+        .any(|needle| lower.contains(needle));
+        if verification {
+            return ToolError::new(
+                ToolErrorKind::Denied,
+                "PG_TLS_VERIFY_FAILED",
+                format!(
+                    "PostgreSQL TLS certificate validation failed (sslmode={}): {}",
+                    tls.mode.as_str(),
+                    chain
+                ),
+            )
+            .with_hint("Set ssl_root_cert to the CA that signed the server certificate; verify-full also requires the host to match the certificate (verify-ca skips that check).")
+            .with_details(serde_json::json!({ "tls": tls.describe() }));
+        }
+        return ToolError::new(
+            ToolErrorKind::Denied,
+            "PG_TLS_FAILED",
+            format!(
+                "PostgreSQL TLS negotiation failed (sslmode={}): {}",
+                tls.mode.as_str(),
+                chain
+            ),
+        )
+        .with_hint(
+            "Check that the server has ssl=on, or relax sslmode if the server does not offer TLS.",
+        )
+        .with_details(serde_json::json!({ "tls": tls.describe() }));
+    }
+    ToolError::retryable(format!("PostgreSQL connection failed: {}", chain))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tls_params_are_lifted_out_of_urls_and_keyword_strings() {
+        let (url, params) = split_tls_params(
+            "postgres://app@db.internal:5432/app?sslmode=verify-full&application_name=infra&sslrootcert=/etc/ca.pem",
+        );
+        assert_eq!(
+            url,
+            "postgres://app@db.internal:5432/app?application_name=infra"
+        );
+        assert_eq!(params["sslmode"], "verify-full");
+        assert_eq!(params["ssl_root_cert"], "/etc/ca.pem");
+
+        let (url, params) = split_tls_params("postgres://app@db/app?sslmode=require");
+        assert_eq!(url, "postgres://app@db/app");
+        assert_eq!(params["sslmode"], "require");
+
+        let (kv, params) = split_tls_params("host=db user=app sslmode=verify-ca sslcert=~/c.pem");
+        assert_eq!(kv, "host=db user=app");
+        assert_eq!(params["sslmode"], "verify-ca");
+        assert_eq!(params["ssl_cert"], "~/c.pem");
+    }
+
+    #[test]
+    fn profile_settings_override_url_params_and_change_the_fingerprint() {
+        let mut url_params = HashMap::new();
+        url_params.insert("sslmode".to_string(), "require".to_string());
+        let from_url = PgTlsConfig::resolve(&serde_json::json!({}), &url_params).expect("url");
+        assert_eq!(from_url.mode, SslMode::Require);
+
+        let explicit = PgTlsConfig::resolve(
+            &serde_json::json!({ "sslmode": "verify-full" }),
+            &url_params,
+        )
+        .expect("explicit");
+        assert_eq!(explicit.mode, SslMode::VerifyFull);
+        assert_ne!(explicit.fingerprint(), from_url.fingerprint());
+
+        let default =
+            PgTlsConfig::resolve(&serde_json::json!({}), &HashMap::new()).expect("default");
+        assert_eq!(default.mode, SslMode::Prefer);
+    }
+
+    #[test]
+    fn invalid_tls_settings_are_rejected() {
+        assert!(SslMode::parse("verify").is_err());
+        let lonely_cert = PgTlsConfig::resolve(
+            &serde_json::json!({ "ssl_cert": "-----BEGIN CERTIFICATE-----\nAA==\n-----END CERTIFICATE-----" }),
+            &HashMap::new(),
+        );
+        assert!(lonely_cert.is_err());
+        let missing = PgTlsConfig::resolve(
+            &serde_json::json!({ "ssl_root_cert": "/nonexistent/infra-ca.pem" }),
+            &HashMap::new(),
+        );
+        assert!(missing.unwrap_err().message.contains("ssl_root_cert"));
+    }
+}
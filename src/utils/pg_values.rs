@@ -0,0 +1,468 @@
+use base64::Engine;
+use serde_json::Value;
+use std::error::Error;
+use tokio_postgres::types::{FromSql, Kind, Type};
+use tokio_postgres::Row;
+
+type DecodeError = Box<dyn Error + Sync + Send>;
+
+// Larger bytea values are cut to this many bytes before base64; `bytes` keeps the real size.
+pub const MAX_BYTEA_BYTES: usize = 64 * 1024;
+
+// The untouched wire bytes of a column, whatever its type.
+struct RawValue<'a>(&'a [u8]);
+
+impl<'a> FromSql<'a> for RawValue<'a> {
+    fn from_sql(_ty: &Type, raw: &'a [u8]) -> Result<Self, DecodeError> {
+        Ok(RawValue(raw))
+    }
+
+    fn accepts(_ty: &Type) -> bool {
+        true
+    }
+}
+
+// Whether `decode` understands the binary format of `ty`. Columns of other types are
+// cast to text in the query itself, so one odd column never costs the whole row.
+pub fn decodes(ty: &Type) -> bool {
+    match ty.kind() {
+        Kind::Enum(_) => true,
+        Kind::Array(inner) | Kind::Range(inner) | Kind::Domain(inner) => decodes(inner),
+        Kind::Simple => matches!(
+            *ty,
+            Type::BOOL
+                | Type::CHAR
+                | Type::INT2
+                | Type::INT4
+                | Type::INT8
+                | Type::OID
+                | Type::FLOAT4
+                | Type::FLOAT8
+                | Type::NUMERIC
+                | Type::TEXT
+                | Type::VARCHAR
+                | Type::BPCHAR
+                | Type::NAME
+                | Type::UNKNOWN
+                | Type::JSON
+                | Type::JSONB
+                | Type::UUID
+                | Type::BYTEA
+                | Type::TIMESTAMP
+                | Type::TIMESTAMPTZ
+                | Type::DATE
+                | Type::TIME
+                | Type::INTERVAL
+        ),
+        _ => false,
+    }
+}
+
+// The type name reported in result `fields` and by `catalog_columns`: the server's
+// type name, with array types (`_uuid`) spelled `uuid[]`.
+pub fn type_label(name: &str) -> String {
+    match name.strip_prefix('_') {
+        Some(element) if !element.is_empty() => format!("{}[]", element),
+        _ => name.to_string(),
+    }
+}
+
+pub fn row_to_value(row: &Row) -> Value {
+    let mut map = serde_json::Map::new();
+    for (idx, col) in row.columns().iter().enumerate() {
+        let value = match row.try_get::<usize, Option<RawValue>>(idx) {
+            Ok(Some(RawValue(raw))) => decode_or_text(col.type_(), raw),
+            _ => Value::Null,
+        };
+        map.insert(col.name().to_string(), value);
+    }
+    Value::Object(map)
+}
+
+fn decode_or_text(ty: &Type, raw: &[u8]) -> Value {
+    decode(ty, raw).unwrap_or_else(|_| {
+        std::str::from_utf8(raw)
+            .map(|text| Value::String(text.to_string()))
+            .unwrap_or(Value::Null)
+    })
+}
+
+fn decode(ty: &Type, raw: &[u8]) -> Result<Value, DecodeError> {
+    match ty.kind() {
+        Kind::Enum(_) => return Ok(Value::String(String::from_utf8(raw.to_vec())?)),
+        Kind::Array(inner) => return decode_array(inner, raw),
+        Kind::Range(inner) => return decode_range(inner, raw),
+        Kind::Domain(inner) => return decode(inner, raw),
+        _ => {}
+    }
+    Ok(match *ty {
+        Type::BOOL => Value::Bool(bool::from_sql(ty, raw)?),
+        Type::CHAR => Value::String(((i8::from_sql(ty, raw)? as u8) as char).to_string()),
+        Type::INT2 => i16::from_sql(ty, raw)?.into(),
+        Type::INT4 => i32::from_sql(ty, raw)?.into(),
+        Type::INT8 => i64::from_sql(ty, raw)?.into(),
+        Type::OID => u32::from_sql(ty, raw)?.into(),
+        // Through its shortest text form, so 0.1::real stays 0.1 instead of 0.10000000149.
+        Type::FLOAT4 => float_value(f32::from_sql(ty, raw)?.to_string().parse()?),
+        Type::FLOAT8 => float_value(f64::from_sql(ty, raw)?),
+        Type::NUMERIC => Value::String(decode_numeric(raw)?),
+        Type::JSON | Type::JSONB => Value::from_sql(ty, raw)?,
+        Type::UUID => Value::String(uuid::Uuid::from_sql(ty, raw)?.to_string()),
+        Type::BYTEA => bytea_value(raw),
+        Type::TIMESTAMP => Value::String(chrono::NaiveDateTime::from_sql(ty, raw)?.to_string()),
+        Type::TIMESTAMPTZ => {
+            Value::String(chrono::DateTime::<chrono::Utc>::from_sql(ty, raw)?.to_rfc3339())
+        }
+        Type::DATE => Value::String(chrono::NaiveDate::from_sql(ty, raw)?.to_string()),
+        Type::TIME => Value::String(chrono::NaiveTime::from_sql(ty, raw)?.to_string()),
+        Type::INTERVAL => Value::String(decode_interval(raw)?),
+        _ => Value::String(String::from_utf8(raw.to_vec())?),
+    })
+}
+
+fn float_value(value: f64) -> Value {
+    serde_json::Number::from_f64(value)
+        .map(Value::Number)
+        .unwrap_or_else(|| Value::String(value.to_string()))
+}
+
+fn bytea_value(raw: &[u8]) -> Value {
+    let kept = &raw[..raw.len().min(MAX_BYTEA_BYTES)];
+    let mut out = serde_json::json!({
+        "base64": base64::engine::general_purpose::STANDARD.encode(kept),
+        "bytes": raw.len(),
+    });
+    if kept.len() < raw.len() {
+        out["truncated"] = Value::Bool(true);
+    }
+    out
+}
+
+struct Reader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], DecodeError> {
+        if self.buf.len() < len {
+            return Err("value is shorter than its header claims".into());
+        }
+        let (head, rest) = self.buf.split_at(len);
+        self.buf = rest;
+        Ok(head)
+    }
+
+    fn i16(&mut self) -> Result<i16, DecodeError> {
+        Ok(i16::from_be_bytes(self.take(2)?.try_into()?))
+    }
+
+    fn u16(&mut self) -> Result<u16, DecodeError> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into()?))
+    }
+
+    fn i32(&mut self) -> Result<i32, DecodeError> {
+        Ok(i32::from_be_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn i64(&mut self) -> Result<i64, DecodeError> {
+        Ok(i64::from_be_bytes(self.take(8)?.try_into()?))
+    }
+
+    // A length-prefixed element as used by arrays and ranges; -1 means NULL.
+    fn element(&mut self) -> Result<Option<&'a [u8]>, DecodeError> {
+        let len = self.i32()?;
+        if len < 0 {
+            return Ok(None);
+        }
+        Ok(Some(self.take(len as usize)?))
+    }
+}
+
+// The inverse of `pg_params::encode_numeric`, printed with exactly `dscale` fraction digits.
+pub fn decode_numeric(raw: &[u8]) -> Result<String, DecodeError> {
+    let mut reader = Reader { buf: raw };
+    let ndigits = reader.i16()?;
+    let weight = reader.i16()? as i32;
+    let sign = reader.u16()?;
+    let dscale = reader.u16()? as usize;
+    match sign {
+        0xC000 => return Ok("NaN".to_string()),
+        0xD000 => return Ok("Infinity".to_string()),
+        0xF000 => return Ok("-Infinity".to_string()),
+        _ => {}
+    }
+    let digits = (0..ndigits.max(0))
+        .map(|_| reader.i16())
+        .collect::<Result<Vec<_>, _>>()?;
+    let group = |idx: i32| -> i16 {
+        usize::try_from(idx)
+            .ok()
+            .and_then(|idx| digits.get(idx).copied())
+            .unwrap_or(0)
+    };
+    let mut out = String::new();
+    if sign == 0x4000 && digits.iter().any(|d| *d != 0) {
+        out.push('-');
+    }
+    if weight < 0 {
+        out.push('0');
+    } else {
+        out.push_str(&group(0).to_string());
+        for idx in 1..=weight {
+            out.push_str(&format!("{:04}", group(idx)));
+        }
+    }
+    if dscale > 0 {
+        let mut fraction = String::new();
+        let mut idx = weight + 1;
+        while fraction.len() < dscale {
+            fraction.push_str(&format!("{:04}", group(idx)));
+            idx += 1;
+        }
+        fraction.truncate(dscale);
+        out.push('.');
+        out.push_str(&fraction);
+    }
+    Ok(out)
+}
+
+// ISO 8601 duration in the layout of PostgreSQL's `intervalstyle = iso_8601`.
+pub fn decode_interval(raw: &[u8]) -> Result<String, DecodeError> {
+    let mut reader = Reader { buf: raw };
+    let micros = reader.i64()?;
+    let days = reader.i32()?;
+    let months = reader.i32()?;
+    Ok(iso_duration(months, days, micros))
+}
+
+fn iso_duration(months: i32, days: i32, micros: i64) -> String {
+    if months == 0 && days == 0 && micros == 0 {
+        return "PT0S".to_string();
+    }
+    let mut out = String::from("P");
+    for (amount, unit) in [(months / 12, 'Y'), (months % 12, 'M'), (days, 'D')] {
+        if amount != 0 {
+            out.push_str(&format!("{}{}", amount, unit));
+        }
+    }
+    if micros != 0 {
+        out.push('T');
+        let hours = micros / 3_600_000_000;
+        let minutes = micros % 3_600_000_000 / 60_000_000;
+        let rest = micros % 60_000_000;
+        for (amount, unit) in [(hours, 'H'), (minutes, 'M')] {
+            if amount != 0 {
+                out.push_str(&format!("{}{}", amount, unit));
+            }
+        }
+        if rest != 0 {
+            let sign = if rest < 0 { "-" } else { "" };
+            let whole = (rest / 1_000_000).abs();
+            let fraction = format!("{:06}", (rest % 1_000_000).abs());
+            let fraction = fraction.trim_end_matches('0');
+            out.push_str(sign);
+            out.push_str(&whole.to_string());
+            if !fraction.is_empty() {
+                out.push('.');
+                out.push_str(fraction);
+            }
+            out.push('S');
+        }
+    }
+    out
+}
+
+const RANGE_EMPTY: u8 = 0x01;
+const RANGE_LB_INC: u8 = 0x02;
+const RANGE_UB_INC: u8 = 0x04;
+const RANGE_LB_INF: u8 = 0x08;
+const RANGE_UB_INF: u8 = 0x10;
+
+// `{lower, upper, bounds}` with unbounded ends as null; the empty range is `{empty: true}`.
+fn decode_range(inner: &Type, raw: &[u8]) -> Result<Value, DecodeError> {
+    let mut reader = Reader { buf: raw };
+    let flags = reader.take(1)?[0];
+    if flags & RANGE_EMPTY != 0 {
+        return Ok(serde_json::json!({"empty": true}));
+    }
+    let mut bound = |infinite: u8| -> Result<Value, DecodeError> {
+        if flags & infinite != 0 {
+            return Ok(Value::Null);
+        }
+        Ok(reader
+            .element()?
+            .map(|raw| decode_or_text(inner, raw))
+            .unwrap_or(Value::Null))
+    };
+    let lower = bound(RANGE_LB_INF)?;
+    let upper = bound(RANGE_UB_INF)?;
+    let bounds = format!(
+        "{}{}",
+        if flags & RANGE_LB_INC != 0 { '[' } else { '(' },
+        if flags & RANGE_UB_INC != 0 { ']' } else { ')' }
+    );
+    Ok(serde_json::json!({"lower": lower, "upper": upper, "bounds": bounds}))
+}
+
+// Multi-dimensional arrays become nested JSON arrays.
+fn decode_array(inner: &Type, raw: &[u8]) -> Result<Value, DecodeError> {
+    let mut reader = Reader { buf: raw };
+    let ndim = reader.i32()?;
+    let _has_nulls = reader.i32()?;
+    let _element_oid = reader.i32()?;
+    let mut dims = Vec::new();
+    for _ in 0..ndim.max(0) {
+        let len = reader.i32()?;
+        let _lower_bound = reader.i32()?;
+        dims.push(len.max(0) as usize);
+    }
+    if dims.is_empty() {
+        return Ok(Value::Array(Vec::new()));
+    }
+    let total: usize = dims.iter().product();
+    let mut items = Vec::with_capacity(total);
+    for _ in 0..total {
+        items.push(
+            reader
+                .element()?
+                .map(|raw| decode_or_text(inner, raw))
+                .unwrap_or(Value::Null),
+        );
+    }
+    Ok(nest(&mut items.into_iter(), &dims))
+}
+
+fn nest(items: &mut impl Iterator<Item = Value>, dims: &[usize]) -> Value {
+    match dims.split_first() {
+        Some((len, [])) => Value::Array(items.take(*len).collect()),
+        Some((len, rest)) => Value::Array((0..*len).map(|_| nest(items, rest)).collect()),
+        None => Value::Array(Vec::new()),
+    }
+}
+
+// `numeric: "float"` on a read: NUMERIC columns (and arrays of them) become JSON numbers,
+// accepting the precision loss; values that do not parse stay strings.
+pub fn numeric_as_float(payload: &mut Value) {
+    let columns: Vec<String> = payload
+        .get("fields")
+        .and_then(|v| v.as_array())
+        .map(|fields| {
+            fields
+                .iter()
+                .filter(|field| {
+                    let oid = field.get("dataTypeId").and_then(|v| v.as_u64());
+                    oid == Some(Type::NUMERIC.oid() as u64)
+                        || oid == Some(Type::NUMERIC_ARRAY.oid() as u64)
+                })
+                .filter_map(|field| field.get("name").and_then(|v| v.as_str()))
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default();
+    if columns.is_empty() {
+        return;
+    }
+    let Some(map) = payload.as_object_mut() else {
+        return;
+    };
+    let mut convert_row = |row: &mut Value| {
+        for column in &columns {
+            if let Some(value) = row.get_mut(column) {
+                to_float(value);
+            }
+        }
+    };
+    if let Some(rows) = map.get_mut("rows").and_then(|v| v.as_array_mut()) {
+        rows.iter_mut().for_each(&mut convert_row);
+    }
+    if let Some(row) = map.get_mut("row") {
+        convert_row(row);
+    }
+    let first_is_numeric = map
+        .get("fields")
+        .and_then(|v| v.get(0))
+        .and_then(|field| field.get("name"))
+        .and_then(|v| v.as_str())
+        .is_some_and(|name| columns.iter().any(|column| column == name));
+    if first_is_numeric {
+        if let Some(value) = map.get_mut("value") {
+            to_float(value);
+        }
+    }
+}
+
+fn to_float(value: &mut Value) {
+    match value {
+        Value::String(text) => {
+            if let Some(number) = text
+                .parse::<f64>()
+                .ok()
+                .and_then(serde_json::Number::from_f64)
+            {
+                *value = Value::Number(number);
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(to_float),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::pg_params::encode_numeric;
+    use serde_json::json;
+
+    #[test]
+    fn numeric_round_trips_through_the_wire_format() {
+        for text in [
+            "12345.678",
+            "-0.001",
+            "0.00",
+            "10000",
+            "1500",
+            "0",
+            "123456789012345678901234567890.000000000000000001",
+            "NaN",
+        ] {
+            let encoded = encode_numeric(text).unwrap();
+            assert_eq!(decode_numeric(&encoded).unwrap(), text);
+        }
+        assert_eq!(
+            decode_numeric(&encode_numeric("1.5e3").unwrap()).unwrap(),
+            "1500"
+        );
+        assert!(decode_numeric(&[0, 1]).is_err());
+    }
+
+    #[test]
+    fn intervals_print_as_iso_durations() {
+        assert_eq!(iso_duration(0, 0, 0), "PT0S");
+        assert_eq!(
+            iso_duration(14, 3, 4 * 3_600_000_000 + 5 * 60_000_000 + 6_500_000),
+            "P1Y2M3DT4H5M6.5S"
+        );
+        assert_eq!(iso_duration(0, 1, 0), "P1D");
+        assert_eq!(iso_duration(-1, 0, -1_500_000), "P-1MT-1.5S");
+        assert_eq!(iso_duration(0, 0, 90 * 60_000_000), "PT1H30M");
+    }
+
+    #[test]
+    fn float_mode_converts_only_numeric_columns() {
+        let mut payload = json!({
+            "fields": [
+                {"name": "total", "dataTypeId": 1700},
+                {"name": "code", "dataTypeId": 25},
+                {"name": "parts", "dataTypeId": 1231},
+            ],
+            "rows": [{"total": "12.50", "code": "7", "parts": ["1.5", null]}],
+        });
+        numeric_as_float(&mut payload);
+        assert_eq!(
+            payload["rows"][0],
+            json!({"total": 12.5, "code": "7", "parts": [1.5, null]})
+        );
+        assert_eq!(type_label("_uuid"), "uuid[]");
+        assert_eq!(type_label("int4"), "int4");
+    }
+}
@@ -19,6 +19,7 @@ static SENSITIVE_KEYS: Lazy<HashSet<&'static str>> = Lazy::new(|| {
         "auth_token",
         "auth_password",
         "client_secret",
+        "client_key_pem",
         "refresh_token",
         "header_value",
         "authorization",
@@ -35,6 +36,8 @@ static SENSITIVE_HEADER_KEYS: Lazy<HashSet<&'static str>> = Lazy::new(|| {
         "x-api-key",
         "x-auth-token",
         "x-access-token",
+        "cookie",
+        "set-cookie",
     ]
     .into_iter()
     .collect()
@@ -0,0 +1,804 @@
+use crate::errors::ToolError;
+use crate::tooling::catalog::validate_tool_args;
+use crate::tooling::names::canonical_tool_name;
+use crate::utils::redact::redact_object;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_json::{Map, Value};
+
+const MAX_NEXT_ACTIONS: usize = 3;
+const MAX_ARG_STRING: usize = 2048;
+const SELECTOR_KEYS: &[&str] = &[
+    "profile_name",
+    "project",
+    "project_name",
+    "target",
+    "project_target",
+    "environment",
+];
+// A suggested rerun is a new call with its own trace.
+const CALL_ONLY_KEYS: &[&str] = &["action", "trace_id", "span_id", "parent_span_id"];
+// Tools that store their own profiles, and the profile type they store.
+const PROFILE_TOOLS: &[(&str, &str)] = &[
+    ("api", "api"),
+    ("env", "env"),
+    ("sql", "postgresql"),
+    ("ssh", "ssh"),
+    ("vault", "vault"),
+];
+// Where a NOT_FOUND from each tool can be looked up.
+const LIST_ACTIONS: &[(&str, &str)] = &[
+    ("alias", "alias_list"),
+    ("artifacts", "list"),
+    ("capability", "list"),
+    ("evidence", "list"),
+    ("intent", "list"),
+    ("job", "job_list"),
+    ("operation", "list"),
+    ("preset", "preset_list"),
+    ("project", "project_list"),
+    ("receipt", "list"),
+    ("runbook", "runbook_list"),
+    ("state", "list"),
+    ("target", "list"),
+];
+
+static PROFILE_NAME_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"[Pp]rofile '([^']+)' not found").unwrap());
+
+// The failing call as the catalog sees it: canonical tool, action and the args it was made with.
+pub struct FailedCall {
+    pub tool: String,
+    pub action: Option<String>,
+    args: Value,
+}
+
+impl FailedCall {
+    pub fn new(tool: &str, args: &Value) -> Self {
+        Self {
+            tool: canonical_tool_name(tool).to_string(),
+            action: args
+                .get("action")
+                .and_then(|v| v.as_str())
+                .map(str::to_string),
+            args: args.clone(),
+        }
+    }
+
+    fn text(&self, key: &str) -> Option<&str> {
+        self.args
+            .get(key)
+            .and_then(|v| v.as_str())
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+    }
+
+    fn selectors(&self) -> Map<String, Value> {
+        SELECTOR_KEYS
+            .iter()
+            .filter_map(|key| {
+                self.text(key)
+                    .map(|value| (key.to_string(), Value::from(value)))
+            })
+            .collect()
+    }
+
+    // The same call, secrets redacted, as the args of a rerun suggestion.
+    fn rerun_args(&self) -> Map<String, Value> {
+        let mut args = match redact_object(&self.args, MAX_ARG_STRING, None) {
+            Value::Object(map) => map,
+            _ => Map::new(),
+        };
+        for key in CALL_ONLY_KEYS {
+            args.remove(*key);
+        }
+        args
+    }
+
+    fn rerun_with(&self, key: &str, value: Value, reason: &str) -> NextAction {
+        let mut args = self.rerun_args();
+        args.insert(key.to_string(), value);
+        let action = self.action.clone().unwrap_or_default();
+        NextAction::new(&self.tool, &action, args, reason)
+    }
+
+    fn profile_name(&self, err: &ToolError) -> Option<String> {
+        self.text("profile_name").map(str::to_string).or_else(|| {
+            PROFILE_NAME_RE
+                .captures(&err.message)
+                .and_then(|caps| caps.get(1))
+                .map(|m| m.as_str().to_string())
+        })
+    }
+}
+
+struct NextAction {
+    tool: String,
+    action: String,
+    args: Map<String, Value>,
+    reason: String,
+}
+
+impl NextAction {
+    fn new(tool: &str, action: &str, args: Map<String, Value>, reason: &str) -> Self {
+        Self {
+            tool: tool.to_string(),
+            action: action.to_string(),
+            args,
+            reason: reason.to_string(),
+        }
+    }
+
+    fn from_json(tool: &str, action: &str, args: Value, reason: &str) -> Self {
+        let args = match args {
+            Value::Object(map) => map,
+            _ => Map::new(),
+        };
+        Self::new(tool, action, args, reason)
+    }
+
+    // Only suggestions the tool's contract accepts as written are offered.
+    fn is_valid(&self) -> bool {
+        let mut call = self.args.clone();
+        call.insert("action".to_string(), Value::from(self.action.clone()));
+        validate_tool_args(&self.tool, &Value::Object(call)).is_ok()
+    }
+
+    fn render(self) -> Value {
+        serde_json::json!({
+            "tool": self.tool,
+            "action": self.action,
+            "args": self.args,
+            "reason": self.reason,
+        })
+    }
+}
+
+type Suggest = fn(&FailedCall, &ToolError) -> Vec<NextAction>;
+
+// One catalog row: the remediation for `code`, optionally narrowed to a tool and action. The
+// hint only fills in for errors raised without one.
+struct Remediation {
+    code: &'static str,
+    tool: Option<&'static str>,
+    action: Option<&'static str>,
+    hint: &'static str,
+    suggest: Suggest,
+}
+
+const CATALOG: &[Remediation] = &[
+    Remediation {
+        code: "INVALID_PARAMS",
+        tool: None,
+        action: None,
+        hint: "Check the call against the tool contract; next_actions holds the corrected call when the mistake is a misspelled argument or action.",
+        suggest: corrected_call,
+    },
+    Remediation {
+        code: "NOT_FOUND",
+        tool: None,
+        action: None,
+        hint: "The referenced item does not exist; list what is there and retry with an existing name or id.",
+        suggest: list_existing,
+    },
+    Remediation {
+        code: "NOT_FOUND",
+        tool: Some("sql"),
+        action: None,
+        hint: "The table or row does not exist; list the tables in the schema.",
+        suggest: sql_catalog_tables,
+    },
+    Remediation {
+        code: "DENIED",
+        tool: None,
+        action: None,
+        hint: "The call was refused by a safety gate; read the error details before retrying.",
+        suggest: confirm_gate,
+    },
+    Remediation {
+        code: "CONFLICT",
+        tool: None,
+        action: None,
+        hint: "The call conflicts with the current state; inspect the existing item first.",
+        suggest: inspect_conflict,
+    },
+    Remediation {
+        code: "TIMEOUT",
+        tool: None,
+        action: None,
+        hint: "The call ran out of time; run it in the background if the tool supports it, or check the trace for where it stalled.",
+        suggest: background_rerun,
+    },
+    Remediation {
+        code: "TIMEOUT",
+        tool: Some("ssh"),
+        action: Some("exec"),
+        hint: "The command outlived the call budget; run it with exec_detached and follow the job.",
+        suggest: ssh_exec_detached,
+    },
+    Remediation {
+        code: "RETRYABLE",
+        tool: None,
+        action: None,
+        hint: "The failure looks transient; retry after a short delay.",
+        suggest: retry_call,
+    },
+    Remediation {
+        code: "RETRYABLE",
+        tool: Some("ssh"),
+        action: None,
+        hint: "The host could not be reached reliably; probe it before retrying.",
+        suggest: ssh_probe,
+    },
+    Remediation {
+        code: "INTERNAL",
+        tool: None,
+        action: None,
+        hint: "Unexpected failure; the call's audit trace shows what ran before it failed.",
+        suggest: audit_trace,
+    },
+    Remediation {
+        code: "INTERNAL",
+        tool: Some("sql"),
+        action: None,
+        hint: "PostgreSQL rejected the statement; see details.sqlstate.",
+        suggest: sql_catalog_tables,
+    },
+    Remediation {
+        code: "PROFILE_NOT_FOUND",
+        tool: None,
+        action: None,
+        hint: "No profile by that name; list the stored profiles or create it with profile_upsert.",
+        suggest: create_profile,
+    },
+    Remediation {
+        code: "HOST_KEY_MISMATCH",
+        tool: Some("ssh"),
+        action: None,
+        hint: "The host presented a different key than the pinned one; confirm the new fingerprint out-of-band before re-pinning it.",
+        suggest: repin_host_key,
+    },
+    Remediation {
+        code: "PG_AUTH_FAILED",
+        tool: Some("sql"),
+        action: None,
+        hint: "Check the profile's user and password, and that pg_hba.conf admits this user.",
+        suggest: pg_credentials,
+    },
+    Remediation {
+        code: "PG_TLS_VERIFY_FAILED",
+        tool: Some("sql"),
+        action: None,
+        hint: "Point ssl_root_cert at the CA that signed the server certificate.",
+        suggest: pg_root_cert,
+    },
+    Remediation {
+        code: "HTTP_TLS_VERIFY_FAILED",
+        tool: Some("api"),
+        action: None,
+        hint: "Inspect the server certificate, then set tls.ca_cert_path to the CA that signed it.",
+        suggest: http_ca_cert,
+    },
+    Remediation {
+        code: "HTTP_TLS_CLIENT_CERT_REJECTED",
+        tool: Some("api"),
+        action: None,
+        hint: "Set tls.client_cert_path and tls.client_key_path to a certificate the server accepts.",
+        suggest: http_client_cert,
+    },
+    Remediation {
+        code: "HTTP_TARGET_DENIED",
+        tool: Some("api"),
+        action: None,
+        hint: "The target resolves to a private address; allow the host on the profile only if it is an intended target.",
+        suggest: http_allow_host,
+    },
+    Remediation {
+        code: "AMBIGUOUS_CAPABILITY",
+        tool: None,
+        action: None,
+        hint: "Several capabilities match; pick one of the candidates explicitly.",
+        suggest: capability_candidates,
+    },
+    Remediation {
+        code: "INTENT_APPLY_IN_PROGRESS",
+        tool: Some("intent"),
+        action: None,
+        hint: "Another execute is applying this preview; wait for it to finish.",
+        suggest: intent_list,
+    },
+    Remediation {
+        code: "SECRET_KEY_MISMATCH",
+        tool: None,
+        action: None,
+        hint: "Stored secrets were encrypted with a different key; run doctor to see which key is loaded.",
+        suggest: workspace_doctor,
+    },
+];
+
+fn lookup(code: &str, tool: &str, action: Option<&str>) -> Option<&'static Remediation> {
+    CATALOG
+        .iter()
+        .filter(|entry| {
+            entry.code == code
+                && entry.tool.is_none_or(|t| t == tool)
+                && entry.action.is_none_or(|a| Some(a) == action)
+        })
+        .max_by_key(|entry| entry.tool.is_some() as u8 + entry.action.is_some() as u8)
+}
+
+// Fills in the catalog hint when the error has none and attaches next_actions, each a call the
+// tool contract accepts, built from the failing call's own values.
+pub fn enrich(mut err: ToolError, call: &FailedCall) -> ToolError {
+    let Some(entry) = lookup(&err.code, &call.tool, call.action.as_deref()) else {
+        return err;
+    };
+    if err.hint.is_none() {
+        err.hint = Some(entry.hint.to_string());
+    }
+    if err.next_actions.is_none() {
+        let next_actions: Vec<Value> = (entry.suggest)(call, &err)
+            .into_iter()
+            .filter(NextAction::is_valid)
+            .take(MAX_NEXT_ACTIONS)
+            .map(NextAction::render)
+            .collect();
+        if !next_actions.is_empty() {
+            err.next_actions = Some(Box::new(Value::Array(next_actions)));
+        }
+    }
+    err
+}
+
+fn detail<'a>(err: &'a ToolError, key: &str) -> Option<&'a Value> {
+    err.details.as_ref().and_then(|details| details.get(key))
+}
+
+fn corrected_call(call: &FailedCall, err: &ToolError) -> Vec<NextAction> {
+    let mut args = call.rerun_args();
+    let mut action = call.action.clone();
+    let mut changed = false;
+    // Misspelled argument names, as annotated by the executor.
+    if let Some(Value::Object(typos)) = detail(err, "did_you_mean") {
+        for (key, meant) in typos {
+            if let (Some(value), Some(meant)) = (args.remove(key), meant.as_str()) {
+                args.insert(meant.to_string(), value);
+                changed = true;
+            }
+        }
+    }
+    // An unknown action, from the contract check or the handler.
+    let suggested_action = detail(err, "violations")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter(|violation| violation.get("pointer").and_then(|v| v.as_str()) == Some("/action"))
+        .find_map(|violation| violation.pointer("/suggestions/0"))
+        .or_else(|| {
+            detail(err, "known_actions")
+                .and(detail(err, "did_you_mean"))
+                .and_then(|v| v.get(0))
+        })
+        .and_then(|v| v.as_str());
+    if let Some(suggested) = suggested_action {
+        action = Some(suggested.to_string());
+        changed = true;
+    }
+    match action.filter(|_| changed) {
+        Some(action) => vec![NextAction::new(
+            &call.tool,
+            &action,
+            args,
+            "the same call with the suggested argument names",
+        )],
+        None => Vec::new(),
+    }
+}
+
+fn list_existing(call: &FailedCall, err: &ToolError) -> Vec<NextAction> {
+    if PROFILE_NAME_RE.is_match(&err.message) {
+        return create_profile(call, err);
+    }
+    if let Some((_, action)) = LIST_ACTIONS.iter().find(|(tool, _)| *tool == call.tool) {
+        return vec![NextAction::new(
+            &call.tool,
+            action,
+            Map::new(),
+            &format!("list the existing {} entries", call.tool),
+        )];
+    }
+    audit_trace(call, err)
+}
+
+fn sql_catalog_tables(call: &FailedCall, err: &ToolError) -> Vec<NextAction> {
+    let sqlstate = detail(err, "sqlstate").and_then(|v| v.as_str());
+    if sqlstate != Some("42P01") && !err.message.contains("does not exist") {
+        return match err.kind {
+            crate::errors::ToolErrorKind::NotFound => list_existing(call, err),
+            _ => audit_trace(call, err),
+        };
+    }
+    let mut args = call.selectors();
+    args.insert(
+        "schema".to_string(),
+        Value::from(call.text("schema").unwrap_or("public")),
+    );
+    vec![NextAction::new(
+        "sql",
+        "catalog_tables",
+        args,
+        "list the tables that do exist in the schema",
+    )]
+}
+
+fn confirm_gate(call: &FailedCall, err: &ToolError) -> Vec<NextAction> {
+    if detail(err, "effects").is_none() {
+        return Vec::new();
+    }
+    if err.message.contains("apply=true") {
+        return vec![call.rerun_with(
+            "apply",
+            Value::Bool(true),
+            "rerun with apply=true once the write is intended",
+        )];
+    }
+    if err.message.contains("confirm=true") {
+        return vec![call.rerun_with(
+            "confirm",
+            Value::Bool(true),
+            "rerun with confirm=true once the irreversible change is intended",
+        )];
+    }
+    Vec::new()
+}
+
+fn inspect_conflict(call: &FailedCall, err: &ToolError) -> Vec<NextAction> {
+    if let Some(name) = call.text("profile_name") {
+        return vec![NextAction::from_json(
+            "profile",
+            "get",
+            serde_json::json!({"profile_name": name}),
+            &format!("see the type and settings of profile {}", name),
+        )];
+    }
+    list_existing(call, err)
+}
+
+fn background_rerun(call: &FailedCall, err: &ToolError) -> Vec<NextAction> {
+    let mut out = vec![call.rerun_with(
+        "background",
+        Value::Bool(true),
+        "run the same call as a background job and follow it",
+    )];
+    out.extend(audit_trace(call, err));
+    out
+}
+
+fn ssh_exec_detached(call: &FailedCall, _err: &ToolError) -> Vec<NextAction> {
+    let Some(command) = call.text("command") else {
+        return Vec::new();
+    };
+    let mut args = call.selectors();
+    args.insert("command".to_string(), Value::from(command));
+    vec![NextAction::new(
+        "ssh",
+        "exec_detached",
+        args,
+        "run the command detached, then follow_job on the returned job_id",
+    )]
+}
+
+fn retry_call(call: &FailedCall, err: &ToolError) -> Vec<NextAction> {
+    let action = call.action.clone().unwrap_or_default();
+    let mut out = vec![NextAction::new(
+        &call.tool,
+        &action,
+        call.rerun_args(),
+        "retry the same call after a short delay",
+    )];
+    out.extend(audit_trace(call, err));
+    out
+}
+
+fn ssh_probe(call: &FailedCall, err: &ToolError) -> Vec<NextAction> {
+    let mut args = call.selectors();
+    if args.is_empty() {
+        // Only the address of an inline connection: probing never authenticates.
+        let address: Map<String, Value> = ["host", "port"]
+            .iter()
+            .filter_map(|key| {
+                call.args
+                    .pointer(&format!("/connection/{}", key))
+                    .map(|value| (key.to_string(), value.clone()))
+            })
+            .collect();
+        if address.is_empty() {
+            return retry_call(call, err);
+        }
+        args.insert("connection".to_string(), Value::Object(address));
+    }
+    let mut out = vec![NextAction::new(
+        "ssh",
+        "probe",
+        args,
+        "check DNS, TCP and the SSH banner without authenticating",
+    )];
+    out.extend(retry_call(call, err));
+    out
+}
+
+fn audit_trace(call: &FailedCall, _err: &ToolError) -> Vec<NextAction> {
+    let Some(trace_id) = call.text("trace_id") else {
+        return Vec::new();
+    };
+    vec![NextAction::from_json(
+        "audit",
+        "audit_trace",
+        serde_json::json!({"trace_id": trace_id}),
+        "see every span the failed call ran",
+    )]
+}
+
+fn profile_skeleton(tool: &str) -> Option<Value> {
+    match tool {
+        "ssh" => Some(serde_json::json!({
+            "connection": {"host": "<host>", "port": 22, "username": "<user>"},
+        })),
+        "sql" => Some(serde_json::json!({
+            "connection": {
+                "host": "<host>",
+                "port": 5432,
+                "user": "<user>",
+                "database": "<database>",
+            },
+        })),
+        "api" => Some(serde_json::json!({"base_url": "https://<host>"})),
+        "vault" => Some(serde_json::json!({"addr": "https://<vault-host>:8200"})),
+        _ => None,
+    }
+}
+
+fn create_profile(call: &FailedCall, err: &ToolError) -> Vec<NextAction> {
+    let name = call.profile_name(err);
+    let Some((tool, profile_type)) = PROFILE_TOOLS.iter().find(|(tool, _)| *tool == call.tool)
+    else {
+        return vec![NextAction::new(
+            "profile",
+            "list",
+            Map::new(),
+            "list the stored profiles",
+        )];
+    };
+    let mut out = vec![NextAction::from_json(
+        "profile",
+        "list",
+        serde_json::json!({"type": profile_type}),
+        &format!("list the stored {} profiles", profile_type),
+    )];
+    if let (Some(name), Some(Value::Object(mut args))) = (name, profile_skeleton(tool)) {
+        args.insert("profile_name".to_string(), Value::from(name.clone()));
+        out.push(NextAction::new(
+            tool,
+            "profile_upsert",
+            args,
+            &format!("create profile {} (replace the <placeholders> first)", name),
+        ));
+    }
+    out
+}
+
+fn repin_host_key(call: &FailedCall, err: &ToolError) -> Vec<NextAction> {
+    let mut out = ssh_probe(call, err);
+    out.retain(|next| next.action == "probe");
+    if let Some(name) = call.text("profile_name") {
+        out.push(NextAction::from_json(
+            "ssh",
+            "profile_upsert",
+            serde_json::json!({
+                "profile_name": name,
+                "connection": {
+                    "host_key_policy": "tofu",
+                    "host_key_fingerprint_sha256": Value::Null,
+                },
+            }),
+            &format!(
+                "once the new key is confirmed, re-pin profile {} on the next connect",
+                name
+            ),
+        ));
+    }
+    out
+}
+
+fn pg_credentials(call: &FailedCall, _err: &ToolError) -> Vec<NextAction> {
+    let Some(name) = call.text("profile_name") else {
+        return vec![NextAction::new(
+            "sql",
+            "profile_list",
+            Map::new(),
+            "list the stored PostgreSQL profiles",
+        )];
+    };
+    vec![
+        NextAction::from_json(
+            "sql",
+            "profile_get",
+            serde_json::json!({"profile_name": name}),
+            &format!("see which user and database profile {} connects with", name),
+        ),
+        NextAction::from_json(
+            "sql",
+            "profile_upsert",
+            serde_json::json!({
+                "profile_name": name,
+                "connection": {"user": "<user>", "password": "<password>"},
+            }),
+            &format!("update the credentials of profile {}", name),
+        ),
+    ]
+}
+
+fn pg_root_cert(call: &FailedCall, err: &ToolError) -> Vec<NextAction> {
+    let Some(name) = call.text("profile_name") else {
+        return pg_credentials(call, err);
+    };
+    vec![
+        NextAction::from_json(
+            "sql",
+            "profile_upsert",
+            serde_json::json!({
+                "profile_name": name,
+                "connection": {"ssl_root_cert": "<path to CA certificate>"},
+            }),
+            &format!("trust the server's CA for profile {}", name),
+        ),
+        NextAction::from_json(
+            "sql",
+            "profile_test",
+            serde_json::json!({"profile_name": name}),
+            "re-test the connection",
+        ),
+    ]
+}
+
+fn http_ca_cert(call: &FailedCall, _err: &ToolError) -> Vec<NextAction> {
+    let name = call.text("profile_name");
+    let mut out = Vec::new();
+    match (call.text("url"), name) {
+        (Some(url), _) if url.starts_with("https://") => out.push(NextAction::from_json(
+            "api",
+            "cert_check",
+            serde_json::json!({"url": url}),
+            "inspect the certificate chain the server presents",
+        )),
+        (_, Some(name)) => out.push(NextAction::from_json(
+            "api",
+            "cert_check",
+            serde_json::json!({"profiles": [name]}),
+            "inspect the certificate chain the server presents",
+        )),
+        _ => {}
+    }
+    if let Some(name) = name {
+        out.push(NextAction::from_json(
+            "api",
+            "profile_upsert",
+            serde_json::json!({
+                "profile_name": name,
+                "tls": {"ca_cert_path": "<path to CA certificate>"},
+            }),
+            &format!("trust the server's CA for profile {}", name),
+        ));
+    }
+    out
+}
+
+fn http_client_cert(call: &FailedCall, _err: &ToolError) -> Vec<NextAction> {
+    let Some(name) = call.text("profile_name") else {
+        return Vec::new();
+    };
+    vec![NextAction::from_json(
+        "api",
+        "profile_upsert",
+        serde_json::json!({
+            "profile_name": name,
+            "tls": {
+                "client_cert_path": "<path to client certificate>",
+                "client_key_path": "<path to client key>",
+            },
+        }),
+        &format!(
+            "give profile {} a client certificate the server accepts",
+            name
+        ),
+    )]
+}
+
+fn http_allow_host(call: &FailedCall, err: &ToolError) -> Vec<NextAction> {
+    let (Some(name), Some(host)) = (
+        call.text("profile_name"),
+        detail(err, "host").and_then(|v| v.as_str()),
+    ) else {
+        return Vec::new();
+    };
+    vec![NextAction::from_json(
+        "api",
+        "profile_upsert",
+        serde_json::json!({
+            "profile_name": name,
+            "ssrf": {"allow_hosts": [host]},
+        }),
+        &format!(
+            "allow {} for profile {} if it is an intended internal target",
+            host, name
+        ),
+    )]
+}
+
+fn capability_candidates(_call: &FailedCall, err: &ToolError) -> Vec<NextAction> {
+    detail(err, "candidates")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|candidate| candidate.get("name").and_then(|v| v.as_str()))
+        .map(|name| {
+            NextAction::from_json(
+                "capability",
+                "get",
+                serde_json::json!({"name": name}),
+                &format!("review candidate {}", name),
+            )
+        })
+        .collect()
+}
+
+fn intent_list(_call: &FailedCall, _err: &ToolError) -> Vec<NextAction> {
+    vec![NextAction::new(
+        "intent",
+        "list",
+        Map::new(),
+        "see the preview's apply status",
+    )]
+}
+
+fn workspace_doctor(_call: &FailedCall, _err: &ToolError) -> Vec<NextAction> {
+    vec![NextAction::new(
+        "workspace",
+        "doctor",
+        Map::new(),
+        "check which secret key is loaded and whether it opens the stored profiles",
+    )]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::ToolErrorKind;
+
+    #[test]
+    fn the_most_specific_entry_wins_and_explicit_hints_stay() {
+        let exec = lookup("TIMEOUT", "ssh", Some("exec")).expect("entry");
+        assert_eq!(exec.action, Some("exec"));
+        let other = lookup("TIMEOUT", "ssh", Some("batch")).expect("entry");
+        assert_eq!(other.tool, None);
+        assert!(lookup("NO_SUCH_CODE", "ssh", None).is_none());
+
+        let call = FailedCall::new(
+            "ssh",
+            &serde_json::json!({"action": "exec", "profile_name": "web", "command": "make", "trace_id": "t1"}),
+        );
+        let err = enrich(
+            ToolError::timeout("Tool call timed out").with_hint("keep me"),
+            &call,
+        );
+        assert_eq!(err.hint.as_deref(), Some("keep me"));
+        assert_eq!(err.next_actions()[0]["action"], "exec_detached");
+        assert_eq!(err.next_actions()[0]["args"]["command"], "make");
+
+        let untouched = enrich(
+            ToolError::new(ToolErrorKind::Internal, "SHUTDOWN_FORCED", "stop"),
+            &call,
+        );
+        assert!(untouched.hint.is_none() && untouched.next_actions.is_none());
+    }
+}
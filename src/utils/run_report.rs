@@ -0,0 +1,311 @@
+use crate::errors::ToolError;
+use crate::services::evidence::artifact_refs_in;
+use crate::utils::artifacts::{
+    artifact_uri_rel, build_run_file_ref, resolve_context_root, write_text_artifact,
+};
+use crate::utils::text::truncate_utf8_prefix;
+use serde_json::Value;
+
+pub const REPORT_FILENAME: &str = "report.md";
+const MAX_ERROR_SAMPLES: usize = 5;
+const MAX_ERROR_BYTES: usize = 240;
+
+// Blocks a two-block flow or deploy_smoke reports under its own key, in stage order.
+const STAGE_KEYS: &[&str] = &["deploy", "smoke", "http", "sftp", "postgres"];
+const ROWS_KEYS: &[&str] = &["rows", "rows_written", "inserted"];
+const BYTES_KEYS: &[&str] = &["bytes", "bytes_uploaded", "bytes_written"];
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReportStage {
+    pub name: String,
+    pub status: String,
+    pub duration_ms: Option<u64>,
+    pub rows: Option<u64>,
+    pub bytes: Option<u64>,
+    pub retries: Option<u64>,
+}
+
+// What a pipeline run, deploy_smoke or workspace run report shows, pulled out of the call's
+// args and result so every caller renders the same layout.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RunReport {
+    pub title: String,
+    pub flow: Option<String>,
+    pub project: Option<String>,
+    pub target: Option<String>,
+    pub trace_id: Option<String>,
+    pub duration_ms: u64,
+    pub success: bool,
+    pub stages: Vec<ReportStage>,
+    pub rows: Option<u64>,
+    pub bytes: Option<u64>,
+    pub retries: Option<u64>,
+    pub errors: Vec<String>,
+    pub artifacts: Vec<String>,
+}
+
+// `report: true` asks for the Markdown report; anything but a boolean is rejected before the
+// run starts.
+pub fn requested(args: &Value) -> Result<bool, ToolError> {
+    match args.get("report") {
+        None | Some(Value::Null) => Ok(false),
+        Some(Value::Bool(value)) => Ok(*value),
+        Some(_) => Err(ToolError::invalid_params("report must be a boolean")),
+    }
+}
+
+impl RunReport {
+    pub fn from_result(title: &str, args: &Value, result: &Value, duration_ms: u64) -> Self {
+        let text = |value: Option<&Value>| {
+            value
+                .and_then(|v| v.as_str())
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+        };
+        let first = |keys: &[&str]| keys.iter().find_map(|key| text(args.get(*key)));
+        let flow = text(result.get("flow"))
+            .or_else(|| text(args.get("flow")))
+            .or_else(|| text(result.get("runbook")))
+            .or_else(|| text(args.get("intent").and_then(|v| v.get("type"))))
+            .or_else(|| first(&["intent_type", "name"]));
+
+        let stages = collect_stages(result);
+        let mut errors = Vec::new();
+        if let Some(message) = error_message(result.get("error")) {
+            errors.push(message);
+        } else if result.get("success").and_then(|v| v.as_bool()) == Some(false) {
+            if let Some(code) = text(result.get("code")) {
+                errors.push(code);
+            }
+        }
+        for (name, block) in stage_blocks(result) {
+            if let Some(message) = error_message(block.get("error")) {
+                errors.push(format!("{}: {}", name, message));
+            }
+        }
+        let errors = errors
+            .into_iter()
+            .take(MAX_ERROR_SAMPLES)
+            .map(|message| truncate_error(&message))
+            .collect();
+
+        let artifacts = artifact_refs_in(result)
+            .iter()
+            .filter_map(|entry| entry.get("uri").and_then(|v| v.as_str()))
+            .filter_map(artifact_uri_rel)
+            .map(str::to_string)
+            .collect();
+
+        // Every stage carries the same data, so the largest count is what moved; retries add up.
+        let top = |keys: &[&str]| lookup_u64(result, keys);
+        Self {
+            title: title.to_string(),
+            flow,
+            project: first(&["project", "project_name"]),
+            target: first(&["target", "project_target", "environment"]),
+            trace_id: text(args.get("trace_id")).or_else(|| text(result.get("trace_id"))),
+            duration_ms,
+            success: result
+                .get("success")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false),
+            rows: top(ROWS_KEYS).or_else(|| stages.iter().filter_map(|s| s.rows).max()),
+            bytes: top(BYTES_KEYS).or_else(|| stages.iter().filter_map(|s| s.bytes).max()),
+            retries: top(&["retries"]).or_else(|| {
+                let counted: Vec<u64> = stages.iter().filter_map(|s| s.retries).collect();
+                (!counted.is_empty()).then(|| counted.iter().sum())
+            }),
+            stages,
+            errors,
+            artifacts,
+        }
+    }
+
+    pub fn render_markdown(&self) -> String {
+        let dash = |value: Option<u64>| value.map(|v| v.to_string()).unwrap_or("-".to_string());
+        let text = |value: &Option<String>| cell(value.as_deref().unwrap_or("-"));
+        let mut out = format!("# {}\n\n", self.title);
+        out.push_str("| Field | Value |\n|---|---|\n");
+        out.push_str(&format!("| Flow | {} |\n", text(&self.flow)));
+        out.push_str(&format!("| Project | {} |\n", text(&self.project)));
+        out.push_str(&format!("| Target | {} |\n", text(&self.target)));
+        out.push_str(&format!("| Trace | {} |\n", text(&self.trace_id)));
+        out.push_str(&format!("| Duration | {} ms |\n", self.duration_ms));
+        out.push_str(&format!(
+            "| Success | {} |\n",
+            if self.success { "yes" } else { "no" }
+        ));
+
+        out.push_str("\n## Stages\n\n");
+        if self.stages.is_empty() {
+            out.push_str("_none_\n");
+        } else {
+            out.push_str("| Stage | Status | Duration (ms) | Rows | Bytes | Retries |\n");
+            out.push_str("|---|---|---|---|---|---|\n");
+            for stage in &self.stages {
+                out.push_str(&format!(
+                    "| {} | {} | {} | {} | {} | {} |\n",
+                    cell(&stage.name),
+                    cell(&stage.status),
+                    dash(stage.duration_ms),
+                    dash(stage.rows),
+                    dash(stage.bytes),
+                    dash(stage.retries),
+                ));
+            }
+        }
+
+        out.push_str("\n## Metrics\n\n");
+        out.push_str(&format!("- Rows moved: {}\n", dash(self.rows)));
+        out.push_str(&format!("- Bytes: {}\n", dash(self.bytes)));
+        out.push_str(&format!("- Retries: {}\n", dash(self.retries)));
+
+        out.push_str("\n## Errors\n\n");
+        if self.errors.is_empty() {
+            out.push_str("_none_\n");
+        }
+        for error in &self.errors {
+            out.push_str(&format!("- {}\n", error.replace('\n', " ")));
+        }
+
+        out.push_str("\n## Artifacts\n\n");
+        if self.artifacts.is_empty() {
+            out.push_str("_none_\n");
+        }
+        for rel in &self.artifacts {
+            out.push_str(&format!("- [{}]({})\n", rel, rel));
+        }
+        out
+    }
+
+    // Writes the report to runs/<trace_id>/report.md and returns its ref.
+    pub fn write(&self) -> Result<Value, ToolError> {
+        let context_root = resolve_context_root().ok_or_else(|| {
+            ToolError::denied("report requires context repo root").with_hint(
+                "Set INFRA_CONTEXT_REPO_ROOT to the repo root that owns artifacts.".to_string(),
+            )
+        })?;
+        let reference = build_run_file_ref(self.trace_id.as_deref(), REPORT_FILENAME)?;
+        let written = write_text_artifact(&context_root, &reference, &self.render_markdown())?;
+        Ok(serde_json::json!({
+            "uri": written.uri,
+            "rel": written.rel,
+            "bytes": written.bytes,
+        }))
+    }
+}
+
+// Adds `report` to the result when the call asked for one. A report that cannot be written
+// does not fail the run it describes; the error takes the ref's place.
+pub fn attach(title: &str, args: &Value, mut result: Value, duration_ms: u64) -> Value {
+    if !matches!(args.get("report"), Some(Value::Bool(true))) {
+        return result;
+    }
+    let report = match RunReport::from_result(title, args, &result, duration_ms).write() {
+        Ok(reference) => reference,
+        Err(err) => serde_json::json!({ "error": err.message }),
+    };
+    if let Value::Object(map) = &mut result {
+        map.insert("report".to_string(), report);
+    }
+    result
+}
+
+fn stage_blocks(result: &Value) -> Vec<(String, &Value)> {
+    let mut out = Vec::new();
+    // Workspace runs: runbook steps, or the runbooks an intent ran.
+    for item in result
+        .get("steps")
+        .or_else(|| result.get("results"))
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+    {
+        let name = ["id", "runbook", "tool"]
+            .iter()
+            .find_map(|key| item.get(*key).and_then(|v| v.as_str()))
+            .unwrap_or("step");
+        out.push((name.to_string(), item));
+    }
+    if let Some(sinks) = result.get("sinks").and_then(|v| v.as_array()) {
+        if let Some(source) = result.get("source").filter(|v| v.is_object()) {
+            out.push(("source".to_string(), source));
+        }
+        for sink in sinks {
+            let name = sink.get("name").and_then(|v| v.as_str()).unwrap_or("sink");
+            out.push((format!("sink:{}", name), sink));
+        }
+        return out;
+    }
+    for key in STAGE_KEYS {
+        if let Some(block) = result.get(*key).filter(|v| v.is_object()) {
+            out.push((key.to_string(), block));
+        }
+    }
+    out
+}
+
+fn collect_stages(result: &Value) -> Vec<ReportStage> {
+    stage_blocks(result)
+        .into_iter()
+        .map(|(name, block)| {
+            let nested = block.get("result").filter(|v| v.is_object());
+            let number = |keys: &[&str]| {
+                lookup_u64(block, keys).or_else(|| nested.and_then(|n| lookup_u64(n, keys)))
+            };
+            let status = match block.get("status").and_then(|v| v.as_str()) {
+                Some(status @ ("ok" | "failed" | "skipped")) => status.to_string(),
+                _ => {
+                    let success = block
+                        .get("success")
+                        .or_else(|| nested.and_then(|n| n.get("success")))
+                        .and_then(|v| v.as_bool());
+                    match success {
+                        Some(false) => "failed",
+                        _ if block.get("error").is_some_and(|v| !v.is_null()) => "failed",
+                        _ => "ok",
+                    }
+                    .to_string()
+                }
+            };
+            ReportStage {
+                name,
+                status,
+                duration_ms: number(&["duration_ms"]),
+                rows: number(ROWS_KEYS),
+                bytes: number(BYTES_KEYS),
+                retries: number(&["retries"]),
+            }
+        })
+        .collect()
+}
+
+fn lookup_u64(value: &Value, keys: &[&str]) -> Option<u64> {
+    keys.iter()
+        .find_map(|key| value.get(*key).and_then(|v| v.as_u64()))
+}
+
+fn error_message(value: Option<&Value>) -> Option<String> {
+    match value? {
+        Value::String(message) if !message.trim().is_empty() => Some(message.clone()),
+        Value::Object(map) => map
+            .get("message")
+            .and_then(|v| v.as_str())
+            .map(str::to_string),
+        _ => None,
+    }
+}
+
+fn truncate_error(message: &str) -> String {
+    let cut = truncate_utf8_prefix(message, MAX_ERROR_BYTES);
+    if cut.len() < message.len() {
+        format!("{}…", cut)
+    } else {
+        cut
+    }
+}
+
+fn cell(value: &str) -> String {
+    value.replace('|', "\\|").replace('\n', " ")
+}
@@ -0,0 +1,359 @@
+use crate::errors::ToolError;
+use crate::utils::redact::{redact_object, redact_text};
+use serde_json::{Map, Value};
+
+// Session state keys: the capture in progress, and the draft runbook_capture_stop produced.
+pub const CAPTURE_STATE_KEY: &str = "runbook_capture/active";
+pub const DRAFT_STATE_KEY: &str = "runbook_capture/draft";
+const MAX_CAPTURED_STEPS: usize = 200;
+
+// Executor-level keys that describe one call rather than what it does.
+const CALL_ONLY_KEYS: &[&str] = &[
+    "trace_id",
+    "span_id",
+    "parent_span_id",
+    "capture",
+    "include_usage",
+    "force_execute",
+];
+
+pub fn is_capture_action(tool: &str, action: Option<&str>) -> bool {
+    tool == "runbook" && action.is_some_and(|action| action.starts_with("runbook_capture"))
+}
+
+pub fn new_capture(name: Option<&str>) -> Value {
+    serde_json::json!({
+        "name": name,
+        "started_at": chrono::Utc::now().to_rfc3339(),
+        "steps": [],
+        "skipped": 0,
+        "dropped": 0,
+    })
+}
+
+// A call as a draft step. Every value redaction would touch is removed rather than masked, so
+// a saved runbook never carries a placeholder where a secret was; `stripped` lists them.
+pub fn captured_step(tool: &str, args: &Value, error: Option<&ToolError>) -> Value {
+    let mut cleaned = args.clone();
+    if let Value::Object(map) = &mut cleaned {
+        for key in CALL_ONLY_KEYS {
+            map.remove(*key);
+        }
+    }
+    let redacted = redact_object(&cleaned, usize::MAX, None);
+    let mut stripped = Vec::new();
+    let args = strip_redacted(&cleaned, &redacted, "args", &mut stripped)
+        .unwrap_or_else(|| Value::Object(Map::new()));
+    let mut step = serde_json::json!({"tool": tool, "args": args, "stripped": stripped});
+    if let Some(err) = error {
+        step["error"] = serde_json::json!({
+            "code": err.code,
+            "message": redact_text(&err.message, 512, None),
+        });
+    }
+    step
+}
+
+fn strip_redacted(
+    original: &Value,
+    redacted: &Value,
+    path: &str,
+    stripped: &mut Vec<String>,
+) -> Option<Value> {
+    match (original, redacted) {
+        (Value::Object(original), Value::Object(redacted)) => {
+            let mut out = Map::new();
+            for (key, value) in original {
+                let child = format!("{}.{}", path, key);
+                let kept = match redacted.get(key) {
+                    Some(redacted) => strip_redacted(value, redacted, &child, stripped),
+                    None => Some(value.clone()),
+                };
+                if let Some(value) = kept {
+                    out.insert(key.clone(), value);
+                }
+            }
+            Some(Value::Object(out))
+        }
+        // A list goes as a whole: dropping one element would shift the rest (argv, for one).
+        _ if original != redacted => {
+            stripped.push(path.to_string());
+            None
+        }
+        _ => Some(original.clone()),
+    }
+}
+
+pub fn append_step(capture: &mut Value, step: Value) {
+    let Some(steps) = capture.get_mut("steps").and_then(|v| v.as_array_mut()) else {
+        return;
+    };
+    if steps.len() >= MAX_CAPTURED_STEPS {
+        increment(capture, "dropped");
+    } else {
+        steps.push(step);
+    }
+}
+
+pub fn increment(capture: &mut Value, key: &str) {
+    let count = capture.get(key).and_then(|v| v.as_u64()).unwrap_or(0);
+    capture[key] = Value::from(count + 1);
+}
+
+// Keys whose values differ between environments; they become runbook inputs.
+fn is_environment_key(key: &str) -> bool {
+    matches!(
+        key,
+        "profile_name"
+            | "host"
+            | "hostname"
+            | "url"
+            | "base_url"
+            | "connection_url"
+            | "path"
+            | "cwd"
+            | "repo_root"
+            | "project"
+            | "project_name"
+            | "target"
+            | "target_name"
+    ) || key.ends_with("_profile")
+        || key.ends_with("_path")
+        || key.ends_with("_host")
+}
+
+struct Params {
+    names: Vec<String>,
+    defaults: Map<String, Value>,
+    used_in: Map<String, Value>,
+}
+
+impl Params {
+    // One input per distinct value, named after the first key it appeared under.
+    fn name_for(&mut self, key: &str, value: &str) -> String {
+        if let Some(name) = self
+            .names
+            .iter()
+            .find(|name| self.defaults.get(*name).and_then(|v| v.as_str()) == Some(value))
+        {
+            return name.clone();
+        }
+        let mut name = key.to_string();
+        let mut suffix = 2;
+        while self.defaults.contains_key(&name) {
+            name = format!("{}_{}", key, suffix);
+            suffix += 1;
+        }
+        self.names.push(name.clone());
+        self.defaults
+            .insert(name.clone(), Value::String(value.to_string()));
+        name
+    }
+
+    fn parameterize(&mut self, value: &mut Value, key: Option<&str>, path: &str) {
+        match value {
+            Value::Object(map) => {
+                for (child_key, child) in map.iter_mut() {
+                    let child_path = format!("{}.{}", path, child_key);
+                    self.parameterize(child, Some(child_key), &child_path);
+                }
+            }
+            Value::Array(items) => {
+                for (index, item) in items.iter_mut().enumerate() {
+                    self.parameterize(item, None, &format!("{}[{}]", path, index));
+                }
+            }
+            Value::String(text) => {
+                let Some(key) = key.filter(|key| is_environment_key(key)) else {
+                    return;
+                };
+                if text.trim().is_empty() || text.contains("{{") {
+                    return;
+                }
+                let name = self.name_for(key, text);
+                let used_in = self
+                    .used_in
+                    .entry(name.clone())
+                    .or_insert_with(|| Value::Array(Vec::new()));
+                if let Some(paths) = used_in.as_array_mut() {
+                    paths.push(Value::String(path.to_string()));
+                }
+                *value = Value::String(format!("{{{{ input.{} }}}}", name));
+            }
+            _ => {}
+        }
+    }
+}
+
+// The captured steps as a runbook. Failed calls stay in place behind `when: false` with the
+// error as a comment, so the draft shows what was tried without running it again.
+pub fn build_draft(capture: &Value, parameterize: bool) -> Value {
+    let captured = capture
+        .get("steps")
+        .and_then(|v| v.as_array())
+        .cloned()
+        .unwrap_or_default();
+    let mut params = Params {
+        names: Vec::new(),
+        defaults: Map::new(),
+        used_in: Map::new(),
+    };
+    let mut steps = Vec::new();
+    let mut secrets_stripped = Vec::new();
+    let mut failed = 0;
+    for (index, entry) in captured.iter().enumerate() {
+        let id = format!("step_{}", index + 1);
+        let mut args = entry.get("args").cloned().unwrap_or(Value::Null);
+        if parameterize {
+            params.parameterize(&mut args, None, &format!("{}.args", id));
+        }
+        let mut step = serde_json::json!({
+            "id": id,
+            "tool": entry.get("tool").cloned().unwrap_or(Value::Null),
+            "args": args,
+        });
+        if let Some(error) = entry.get("error") {
+            failed += 1;
+            step["when"] = Value::Bool(false);
+            step["comment"] = Value::String(format!(
+                "failed during capture ({}): {}",
+                error
+                    .get("code")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("ERROR"),
+                error.get("message").and_then(|v| v.as_str()).unwrap_or("")
+            ));
+        } else if entry.get("tool").and_then(|v| v.as_str()) == Some("runbook") {
+            // Runbook steps may not call the runbook tool.
+            step["when"] = Value::Bool(false);
+            step["comment"] = Value::String(format!(
+                "runbooks cannot run runbooks; copy the steps of '{}' here instead",
+                step["args"]
+                    .get("name")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("")
+            ));
+        }
+        for path in entry
+            .get("stripped")
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|v| v.as_str())
+        {
+            secrets_stripped.push(Value::String(format!("{}.{}", id, path)));
+        }
+        steps.push(step);
+    }
+    let runbook = serde_json::json!({
+        "description": format!(
+            "Captured from {} tool call(s) starting {}.",
+            steps.len(),
+            capture.get("started_at").and_then(|v| v.as_str()).unwrap_or("")
+        ),
+        "tags": ["captured"],
+        "inputs": params.names,
+        "defaults": params.defaults,
+        "steps": steps,
+    });
+    let params: Vec<Value> = params
+        .names
+        .iter()
+        .map(|name| {
+            serde_json::json!({
+                "name": name,
+                "default": params.defaults.get(name).cloned().unwrap_or(Value::Null),
+                "used_in": params.used_in.get(name).cloned().unwrap_or(Value::Null),
+            })
+        })
+        .collect();
+    serde_json::json!({
+        "runbook": runbook,
+        "params": params,
+        "secrets_stripped": secrets_stripped,
+        "recorded": captured.len(),
+        "failed": failed,
+        "skipped": capture.get("skipped").cloned().unwrap_or(Value::from(0)),
+        "dropped": capture.get("dropped").cloned().unwrap_or(Value::from(0)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn drafts_turn_environment_values_into_inputs_and_drop_secrets() {
+        let mut capture = new_capture(Some("rotate"));
+        append_step(
+            &mut capture,
+            captured_step(
+                "ssh",
+                &json!({
+                    "action": "exec",
+                    "trace_id": "t-1",
+                    "connection": {"host": "db-1.prod", "username": "ops", "password": "hunter22"},
+                    "command": "systemctl restart app",
+                }),
+                None,
+            ),
+        );
+        append_step(
+            &mut capture,
+            captured_step(
+                "local",
+                &json!({"action": "fs_read", "path": "/srv/app/.env", "capture": true}),
+                Some(&ToolError::not_found("path does not exist: /srv/app/.env")),
+            ),
+        );
+        append_step(
+            &mut capture,
+            captured_step(
+                "sql",
+                &json!({"action": "query", "profile_name": "prod-db", "sql": "SELECT 1"}),
+                None,
+            ),
+        );
+        append_step(
+            &mut capture,
+            captured_step(
+                "ssh",
+                &json!({"action": "exec", "host": "db-1.prod", "command": "uptime"}),
+                None,
+            ),
+        );
+
+        let draft = build_draft(&capture, true);
+        let runbook = &draft["runbook"];
+        assert_eq!(runbook["inputs"], json!(["host", "path", "profile_name"]));
+        assert_eq!(runbook["defaults"]["host"], "db-1.prod");
+        assert_eq!(
+            runbook["steps"][0]["args"],
+            json!({
+                "action": "exec",
+                "connection": {"host": "{{ input.host }}", "username": "ops"},
+                "command": "systemctl restart app",
+            })
+        );
+        assert_eq!(runbook["steps"][3]["args"]["host"], "{{ input.host }}");
+        assert_eq!(runbook["steps"][1]["when"], false);
+        assert_eq!(
+            runbook["steps"][1]["comment"],
+            "failed during capture (NOT_FOUND): path does not exist: /srv/app/.env"
+        );
+        assert_eq!(
+            draft["secrets_stripped"],
+            json!(["step_1.args.connection.password"])
+        );
+        assert_eq!(draft["params"][0]["used_in"].as_array().unwrap().len(), 2);
+        assert_eq!(draft["failed"], 1);
+
+        let literal = build_draft(&capture, false);
+        assert_eq!(literal["runbook"]["inputs"], json!([]));
+        assert_eq!(
+            literal["runbook"]["steps"][2]["args"]["profile_name"],
+            "prod-db"
+        );
+    }
+}
@@ -0,0 +1,120 @@
+use crate::errors::ToolError;
+use std::path::{Component, Path, PathBuf};
+
+// Names that become store keys, namespace segments or file names (profiles, presets, aliases,
+// projects and their targets, state keys, checkpoints, artifact filenames): ASCII letters,
+// digits, '.', '_' and '-', at most MAX_NAME_LEN bytes and never starting with '.', so a name
+// can neither walk out of its directory nor hide as a dotfile.
+pub const MAX_NAME_LEN: usize = 128;
+pub const MAX_FILENAME_LEN: usize = 255;
+
+// '/' and '\' plus the characters that render like them; the lookalikes are not separators to
+// the file system, but a name that reads as a path is refused all the same.
+const SEPARATORS: &[char] = &[
+    '/', '\\', '\u{2044}', '\u{2215}', '\u{2216}', '\u{29F8}', '\u{29F9}', '\u{FF0F}', '\u{FF3C}',
+];
+
+fn is_allowed(ch: char) -> bool {
+    ch.is_ascii_alphanumeric() || matches!(ch, '.' | '_' | '-')
+}
+
+fn problem(name: &str, max_len: usize) -> Option<String> {
+    if name.len() > max_len {
+        return Some(format!("is longer than {} bytes", max_len));
+    }
+    if name.starts_with('.') {
+        return Some("must not start with '.'".to_string());
+    }
+    let ch = name.chars().find(|ch| !is_allowed(*ch))?;
+    Some(if SEPARATORS.contains(&ch) {
+        format!("must not contain path separators (found {:?})", ch)
+    } else {
+        format!("must not contain {:?}", ch)
+    })
+}
+
+// The closest name that passes: every other character becomes '-', leading dots go.
+pub fn suggest_safe_name(value: &str) -> String {
+    let mapped: String = value
+        .trim()
+        .chars()
+        .map(|ch| if is_allowed(ch) { ch } else { '-' })
+        .collect();
+    let mut out = String::new();
+    for ch in mapped.trim_start_matches(['.', '-']).chars() {
+        if ch == '-' && out.ends_with('-') {
+            continue;
+        }
+        out.push(ch);
+    }
+    let out: String = out
+        .trim_end_matches('-')
+        .chars()
+        .take(MAX_NAME_LEN)
+        .collect();
+    if out.is_empty() {
+        "name".to_string()
+    } else {
+        out
+    }
+}
+
+fn ensure_with_limit(value: &str, label: &str, max_len: usize) -> Result<String, ToolError> {
+    let trimmed = value.trim();
+    if trimmed.is_empty() {
+        return Err(ToolError::invalid_params(format!(
+            "{} must be a non-empty string",
+            label
+        )));
+    }
+    let Some(problem) = problem(trimmed, max_len) else {
+        return Ok(trimmed.to_string());
+    };
+    let suggested = suggest_safe_name(trimmed);
+    Err(ToolError::invalid_params(format!(
+        "{} {:?} is not a safe name: it {}",
+        label, trimmed, problem
+    ))
+    .with_hint(format!(
+        "Use letters, digits, '.', '_' or '-', e.g. '{}'. Entries stored under an older name stay readable: read it, write it again under the new name and delete the old one.",
+        suggested
+    ))
+    .with_details(serde_json::json!({"suggested": suggested})))
+}
+
+// Only writes go through this; reads and deletes keep accepting names stored before the rule
+// existed, so they can be migrated.
+pub fn ensure_safe_name(value: &str, label: &str) -> Result<String, ToolError> {
+    ensure_with_limit(value, label, MAX_NAME_LEN)
+}
+
+pub fn ensure_safe_filename(value: &str, label: &str) -> Result<String, ToolError> {
+    ensure_with_limit(value, label, MAX_FILENAME_LEN)
+}
+
+// `base` joined with a caller-supplied relative path, or None when the result could land
+// outside `base`: absolute paths and '..' are refused outright, and a symlink on the way out is
+// caught by canonicalizing the deepest part of the path that already exists.
+pub fn join_within(base: &Path, rel: &str) -> Option<PathBuf> {
+    let rel_path = Path::new(rel);
+    if !rel_path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+    {
+        return None;
+    }
+    let joined = base.join(rel_path);
+    let Ok(real_base) = base.canonicalize() else {
+        return Some(joined);
+    };
+    let mut existing = joined.as_path();
+    while existing.symlink_metadata().is_err() {
+        existing = existing.parent()?;
+    }
+    match existing.canonicalize() {
+        Ok(real) if !real.starts_with(&real_base) => None,
+        // A dangling symlink resolves nowhere yet; writing through it could still escape.
+        Err(_) if existing.is_symlink() => None,
+        _ => Some(joined),
+    }
+}
@@ -0,0 +1,134 @@
+use crate::errors::ToolError;
+use reqwest::header::HeaderMap;
+use serde_json::Value;
+
+pub const MAX_SEGMENTS: usize = 16;
+
+// `segments` of a download: 1 (the default) keeps the single-stream path.
+pub fn parse_segments(value: Option<&Value>) -> Result<usize, ToolError> {
+    match value {
+        None | Some(Value::Null) => Ok(1),
+        Some(value) => value
+            .as_u64()
+            .filter(|n| (1..=MAX_SEGMENTS as u64).contains(n))
+            .map(|n| n as usize)
+            .ok_or_else(|| {
+                ToolError::invalid_params(format!(
+                    "segments must be an integer between 1 and {}",
+                    MAX_SEGMENTS
+                ))
+            }),
+    }
+}
+
+// `expect_sha256` as lowercase hex.
+pub fn parse_expected_sha256(value: Option<&Value>) -> Result<Option<String>, ToolError> {
+    match value {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::String(hex)) => {
+            let hex = hex.trim().to_ascii_lowercase();
+            if hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                Ok(Some(hex))
+            } else {
+                Err(ToolError::invalid_params(
+                    "expect_sha256 must be a 64-character hex digest",
+                ))
+            }
+        }
+        Some(_) => Err(ToolError::invalid_params(
+            "expect_sha256 must be a 64-character hex digest",
+        )),
+    }
+}
+
+// The full size of a resource whose HEAD response says ranges can be fetched, or why the
+// single-stream path has to be used instead.
+pub fn ranged_length(status: u16, headers: &HeaderMap) -> Result<u64, String> {
+    if !(200..300).contains(&status) {
+        return Err(format!("HEAD returned status {}", status));
+    }
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.trim().to_ascii_lowercase())
+    };
+    let ranges = header("accept-ranges").unwrap_or_default();
+    if !ranges.split(',').any(|unit| unit.trim() == "bytes") {
+        return Err("server does not advertise accept-ranges: bytes".to_string());
+    }
+    if let Some(encoding) = header("content-encoding").filter(|e| !e.is_empty() && e != "identity")
+    {
+        return Err(format!(
+            "response is {} encoded; byte ranges would split the encoded stream",
+            encoding
+        ));
+    }
+    match header("content-length").and_then(|v| v.parse::<u64>().ok()) {
+        Some(0) => Err("content-length is 0".to_string()),
+        Some(length) => Ok(length),
+        None => Err("HEAD response has no content-length".to_string()),
+    }
+}
+
+// `segments` contiguous inclusive byte ranges covering `total` bytes; earlier ranges take the
+// remainder, and there are never more ranges than bytes.
+pub fn plan_ranges(total: u64, segments: usize) -> Vec<(u64, u64)> {
+    let count = (segments as u64).clamp(1, total.max(1));
+    let base = total / count;
+    let extra = total % count;
+    let mut start = 0;
+    (0..count)
+        .map(|index| {
+            let len = base + u64::from(index < extra);
+            let range = (start, start + len - 1);
+            start += len;
+            range
+        })
+        .collect()
+}
+
+// The first byte of a 206 response's `content-range: bytes <first>-<last>/<total>`.
+pub fn content_range_start(headers: &HeaderMap) -> Option<u64> {
+    let value = headers.get("content-range")?.to_str().ok()?;
+    let range = value.trim().strip_prefix("bytes")?.trim();
+    range.split(['-', '/']).next()?.trim().parse().ok()
+}
+
+// Bytes per second over `duration_ms` (at least 1 ms), rounded down.
+pub fn throughput(bytes: u64, duration_ms: u64) -> u64 {
+    bytes.saturating_mul(1000) / duration_ms.max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn ranges_cover_every_byte_once() {
+        assert_eq!(plan_ranges(10, 3), [(0, 3), (4, 6), (7, 9)]);
+        assert_eq!(plan_ranges(2, 4), [(0, 0), (1, 1)]);
+        assert_eq!(plan_ranges(7, 1), [(0, 6)]);
+    }
+
+    #[test]
+    fn head_probe_needs_byte_ranges_and_a_length() {
+        let mut headers = HeaderMap::new();
+        headers.insert("content-length", HeaderValue::from_static("4096"));
+        assert!(ranged_length(200, &headers)
+            .unwrap_err()
+            .contains("accept-ranges"));
+        headers.insert("accept-ranges", HeaderValue::from_static("bytes"));
+        assert_eq!(ranged_length(200, &headers), Ok(4096));
+        headers.insert("content-encoding", HeaderValue::from_static("gzip"));
+        assert!(ranged_length(200, &headers).unwrap_err().contains("gzip"));
+
+        let mut partial = HeaderMap::new();
+        partial.insert(
+            "content-range",
+            HeaderValue::from_static("bytes 100-199/4096"),
+        );
+        assert_eq!(content_range_start(&partial), Some(100));
+    }
+}
@@ -0,0 +1,375 @@
+use crate::errors::ToolError;
+use regex::Regex;
+use serde_json::Value;
+use std::cmp::Ordering;
+
+// Entries returned inline; larger matches go to an artifact when a context repo is configured.
+pub const MAX_INLINE_ENTRIES: usize = 500;
+pub const DEFAULT_MAX_ENTRIES: usize = 100_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortKey {
+    Name,
+    Mtime,
+    Size,
+}
+
+#[derive(Clone, Debug)]
+pub struct ListedEntry {
+    pub path: String,
+    pub filename: String,
+    pub is_dir: bool,
+    pub size: Option<u64>,
+    pub mode: Option<u32>,
+    pub mtime: Option<u64>,
+    pub atime: Option<u64>,
+}
+
+impl ListedEntry {
+    pub fn to_json(&self) -> Value {
+        serde_json::json!({
+            "path": self.path,
+            "filename": self.filename,
+            "type": if self.is_dir { "dir" } else { "file" },
+            "size": self.size,
+            "mode": self.mode,
+            "mtime": self.mtime,
+            "atime": self.atime,
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ListingQuery {
+    name: Option<Regex>,
+    pattern: Option<Regex>,
+    kind: Option<bool>,
+    min_mtime: Option<u64>,
+    max_mtime: Option<u64>,
+    min_size: Option<u64>,
+    sort: Option<SortKey>,
+    descending: bool,
+    pub limit: usize,
+    pub offset: usize,
+    pub max_entries: usize,
+}
+
+// `*` and `?` stay within one name; `[...]` classes pass through (`[!x]` negates).
+pub fn glob_to_regex(glob: &str) -> Result<Regex, ToolError> {
+    let mut out = String::from("^");
+    let mut chars = glob.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '*' => out.push_str("[^/]*"),
+            '?' => out.push_str("[^/]"),
+            '[' => {
+                let mut class = String::new();
+                let mut closed = false;
+                for next in chars.by_ref() {
+                    if next == ']' {
+                        closed = true;
+                        break;
+                    }
+                    class.push(next);
+                }
+                if !closed || class.is_empty() {
+                    return Err(ToolError::invalid_params(format!(
+                        "glob has an unterminated character class: {}",
+                        glob
+                    )));
+                }
+                let class = match class.strip_prefix('!') {
+                    Some(rest) => format!("^{}", rest),
+                    None => class,
+                };
+                out.push('[');
+                out.push_str(&class.replace('\\', "\\\\"));
+                out.push(']');
+            }
+            other => out.push_str(&regex::escape(&other.to_string())),
+        }
+    }
+    out.push('$');
+    Regex::new(&out).map_err(|err| ToolError::invalid_params(format!("invalid glob: {}", err)))
+}
+
+fn non_negative(args: &Value, key: &str) -> Result<Option<u64>, ToolError> {
+    match args.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => value.as_u64().map(Some).ok_or_else(|| {
+            ToolError::invalid_params(format!("{} must be a non-negative integer", key))
+        }),
+    }
+}
+
+// Unix seconds, or an RFC 3339 timestamp.
+fn timestamp(args: &Value, key: &str) -> Result<Option<u64>, ToolError> {
+    match args.get(key) {
+        Some(Value::String(text)) => chrono::DateTime::parse_from_rfc3339(text.trim())
+            .ok()
+            .and_then(|dt| u64::try_from(dt.timestamp()).ok())
+            .map(Some)
+            .ok_or_else(|| {
+                ToolError::invalid_params(format!(
+                    "{} must be unix seconds or an RFC 3339 timestamp",
+                    key
+                ))
+            }),
+        _ => non_negative(args, key),
+    }
+}
+
+fn text<'a>(args: &'a Value, key: &str) -> Option<&'a str> {
+    args.get(key)
+        .and_then(|v| v.as_str())
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+}
+
+impl ListingQuery {
+    pub fn from_args(args: &Value) -> Result<Self, ToolError> {
+        let name = text(args, "glob").map(glob_to_regex).transpose()?;
+        let pattern = text(args, "regex")
+            .map(|raw| {
+                Regex::new(raw)
+                    .map_err(|err| ToolError::invalid_params(format!("invalid regex: {}", err)))
+            })
+            .transpose()?;
+        let kind = match text(args, "type") {
+            None => None,
+            Some("dir") => Some(true),
+            Some("file") => Some(false),
+            Some(_) => return Err(ToolError::invalid_params("type must be file or dir")),
+        };
+        let sort = match text(args, "sort") {
+            None => None,
+            Some("name") => Some(SortKey::Name),
+            Some("mtime") => Some(SortKey::Mtime),
+            Some("size") => Some(SortKey::Size),
+            Some(_) => {
+                return Err(ToolError::invalid_params(
+                    "sort must be one of: name, mtime, size",
+                ))
+            }
+        };
+        let descending = match text(args, "order") {
+            None | Some("asc") => false,
+            Some("desc") => true,
+            Some(_) => return Err(ToolError::invalid_params("order must be asc or desc")),
+        };
+        let limit = match non_negative(args, "limit")? {
+            Some(0) => return Err(ToolError::invalid_params("limit must be positive")),
+            Some(limit) => (limit as usize).min(MAX_INLINE_ENTRIES),
+            None => MAX_INLINE_ENTRIES,
+        };
+        let max_entries = match non_negative(args, "max_entries")? {
+            Some(0) => return Err(ToolError::invalid_params("max_entries must be positive")),
+            Some(max) => max as usize,
+            None => DEFAULT_MAX_ENTRIES,
+        };
+        Ok(Self {
+            name,
+            pattern,
+            kind,
+            min_mtime: timestamp(args, "min_mtime")?,
+            max_mtime: timestamp(args, "max_mtime")?,
+            min_size: non_negative(args, "min_size")?,
+            sort,
+            descending,
+            limit,
+            offset: non_negative(args, "offset")?.unwrap_or(0) as usize,
+            max_entries,
+        })
+    }
+
+    // Entries without an mtime/size never pass a bound on that field.
+    pub fn accepts(&self, entry: &ListedEntry) -> bool {
+        if self.kind.is_some_and(|want_dir| want_dir != entry.is_dir) {
+            return false;
+        }
+        if self
+            .name
+            .as_ref()
+            .is_some_and(|re| !re.is_match(&entry.filename))
+        {
+            return false;
+        }
+        if self
+            .pattern
+            .as_ref()
+            .is_some_and(|re| !re.is_match(&entry.filename))
+        {
+            return false;
+        }
+        let within = |value: Option<u64>, min: Option<u64>, max: Option<u64>| match value {
+            Some(value) => min.is_none_or(|min| value >= min) && max.is_none_or(|max| value <= max),
+            None => min.is_none() && max.is_none(),
+        };
+        within(entry.mtime, self.min_mtime, self.max_mtime)
+            && within(entry.size, self.min_size, None)
+    }
+
+    pub fn sort(&self, entries: &mut [ListedEntry]) {
+        let Some(key) = self.sort else {
+            return;
+        };
+        entries.sort_by(|a, b| {
+            let ordering = match key {
+                SortKey::Name => a.filename.cmp(&b.filename),
+                SortKey::Mtime => a.mtime.cmp(&b.mtime),
+                SortKey::Size => a.size.cmp(&b.size),
+            };
+            let ordering = if ordering == Ordering::Equal {
+                a.path.cmp(&b.path)
+            } else {
+                ordering
+            };
+            if self.descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+    }
+
+    pub fn page<'a>(&self, entries: &'a [ListedEntry]) -> &'a [ListedEntry] {
+        let start = self.offset.min(entries.len());
+        let end = (start + self.limit).min(entries.len());
+        &entries[start..end]
+    }
+}
+
+// Collects matches while walking; `visit` returns false once `max_entries` were scanned.
+#[derive(Debug, Default)]
+pub struct ListingWalk {
+    pub matched: Vec<ListedEntry>,
+    pub scanned: usize,
+    pub stopped_at: Option<String>,
+}
+
+impl ListingWalk {
+    pub fn visit(&mut self, query: &ListingQuery, entry: ListedEntry) -> bool {
+        if self.scanned >= query.max_entries {
+            self.stopped_at.get_or_insert(entry.path);
+            return false;
+        }
+        self.scanned += 1;
+        if query.accepts(&entry) {
+            self.matched.push(entry);
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn entry(path: &str, is_dir: bool, size: u64, mtime: u64) -> ListedEntry {
+        ListedEntry {
+            path: path.to_string(),
+            filename: path.rsplit('/').next().unwrap().to_string(),
+            is_dir,
+            size: Some(size),
+            mode: None,
+            mtime: Some(mtime),
+            atime: None,
+        }
+    }
+
+    fn names(entries: &[ListedEntry]) -> Vec<&str> {
+        entries.iter().map(|e| e.filename.as_str()).collect()
+    }
+
+    fn fixture() -> Vec<ListedEntry> {
+        vec![
+            entry("/d/app.log", false, 300, 30),
+            entry("/d/app.log.1", false, 100, 10),
+            entry("/d/logs", true, 0, 40),
+            entry("/d/b.log", false, 200, 20),
+            entry("/d/notes.txt", false, 50, 50),
+        ]
+    }
+
+    #[test]
+    fn filters_by_glob_regex_type_and_bounds() {
+        let select = |args: Value| {
+            let query = ListingQuery::from_args(&args).unwrap();
+            fixture()
+                .into_iter()
+                .filter(|e| query.accepts(e))
+                .map(|e| e.filename)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(select(json!({"glob": "*.log"})), ["app.log", "b.log"]);
+        assert_eq!(select(json!({"glob": "[ab]*.log.?"})), ["app.log.1"]);
+        assert_eq!(
+            select(json!({"glob": "[!a]*"})),
+            ["logs", "b.log", "notes.txt"]
+        );
+        assert_eq!(select(json!({"regex": "\\.log(\\.\\d+)?$"})).len(), 3);
+        assert_eq!(select(json!({"type": "dir"})), ["logs"]);
+        assert_eq!(
+            select(json!({"type": "file", "min_size": 150, "max_mtime": 25})),
+            ["b.log"]
+        );
+        assert_eq!(
+            select(json!({"min_mtime": "1970-01-01T00:00:40Z"})),
+            ["logs", "notes.txt"]
+        );
+        for bad in [
+            json!({"type": "link"}),
+            json!({"sort": "owner"}),
+            json!({"regex": "("}),
+            json!({"glob": "[abc"}),
+            json!({"min_size": -1}),
+            json!({"limit": 0}),
+        ] {
+            assert!(ListingQuery::from_args(&bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn sorts_and_paginates() {
+        let query = ListingQuery::from_args(
+            &json!({"sort": "size", "order": "desc", "limit": 2, "offset": 1}),
+        )
+        .unwrap();
+        let mut entries = fixture();
+        query.sort(&mut entries);
+        assert_eq!(
+            names(&entries),
+            ["app.log", "b.log", "app.log.1", "notes.txt", "logs"]
+        );
+        assert_eq!(names(query.page(&entries)), ["b.log", "app.log.1"]);
+
+        let query = ListingQuery::from_args(&json!({"sort": "mtime", "offset": 9})).unwrap();
+        query.sort(&mut entries);
+        assert_eq!(names(&entries)[0], "app.log.1");
+        assert!(query.page(&entries).is_empty());
+        assert_eq!(
+            ListingQuery::from_args(&json!({"limit": 100000}))
+                .unwrap()
+                .limit,
+            MAX_INLINE_ENTRIES
+        );
+    }
+
+    #[test]
+    fn walk_stops_at_max_entries() {
+        let query = ListingQuery::from_args(&json!({"glob": "*.log", "max_entries": 3})).unwrap();
+        let mut walk = ListingWalk::default();
+        let mut visited = 0;
+        for item in fixture() {
+            if !walk.visit(&query, item) {
+                break;
+            }
+            visited += 1;
+        }
+        assert_eq!(visited, 3);
+        assert_eq!(walk.scanned, 3);
+        assert_eq!(names(&walk.matched), ["app.log"]);
+        assert_eq!(walk.stopped_at.as_deref(), Some("/d/b.log"));
+    }
+}
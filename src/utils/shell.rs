@@ -0,0 +1,664 @@
+use crate::errors::ToolError;
+
+// POSIX single-quoting: everything between quotes is literal, and an embedded `'` closes the
+// quote, adds an escaped quote and reopens it. Newlines, `$`, backticks, `"` and `\` need no
+// further treatment. Values are Rust strings (JSON input), so non-UTF-8 bytes never reach here;
+// NUL cannot be passed through a shell at all and is rejected by `ensure_shell_arg`.
+pub fn shell_quote(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('\'');
+    for ch in value.chars() {
+        if ch == '\'' {
+            out.push_str("'\\''");
+        } else {
+            out.push(ch);
+        }
+    }
+    out.push('\'');
+    out
+}
+
+pub fn ensure_shell_arg(value: &str, field: &str) -> Result<(), ToolError> {
+    if value.contains('\0') {
+        return Err(
+            ToolError::invalid_params(format!("{} must not contain NUL bytes", field))
+                .with_hint("Shell arguments cannot carry NUL; rename the path.".to_string()),
+        );
+    }
+    Ok(())
+}
+
+// Creates the exec_detached scratch dir owner-only. Runs in a subshell so the umask does not
+// leak into the user's command; chmod fails on a dir someone else pre-created.
+pub fn scratch_dir_command(dir: &str) -> String {
+    let dir = shell_quote(dir);
+    format!("(umask 077 && mkdir -p -- {dir} && chmod 700 -- {dir})")
+}
+
+// Writes the channel's stdin to `path` inside the scratch dir with mode 600.
+pub fn stdin_upload_command(scratch_dir: &str, path: &str) -> String {
+    let file = shell_quote(path);
+    format!(
+        "{} && (umask 077 && cat > {file}) && chmod 600 -- {file}",
+        scratch_dir_command(scratch_dir)
+    )
+}
+
+pub fn remove_files_command(paths: &[&str]) -> String {
+    let quoted: Vec<String> = paths.iter().map(|path| shell_quote(path)).collect();
+    format!("rm -f -- {}", quoted.join(" "))
+}
+
+// `nohup sh -lc` wrapper for exec_detached: records the pid, waits for the command and writes
+// its exit code. With an uploaded stdin file the command runs in the background under `wait`
+// so a TERM/INT/HUP to the wrapper (job_kill) reaches the EXIT trap that removes the file.
+pub fn detached_script(
+    command: &str,
+    stdin_path: Option<&str>,
+    pid_path: &str,
+    exit_path: &str,
+    log_path: &str,
+) -> String {
+    let exit = shell_quote(exit_path);
+    let inner = match stdin_path {
+        Some(path) => {
+            let stdin = shell_quote(path);
+            format!(
+                "trap {} EXIT\ntrap 'exit 129' HUP\ntrap 'exit 130' INT\ntrap 'exit 143' TERM\n({}) < {stdin} &\nwait $!\nrc=$?\necho \"$rc\" > {exit}\nexit \"$rc\"",
+                shell_quote(&format!("rm -f -- {}", stdin)),
+                command
+            )
+        }
+        None => format!("({})\nrc=$?\necho \"$rc\" > {exit}\nexit \"$rc\"", command),
+    };
+    let pid = shell_quote(pid_path);
+    format!(
+        "rm -f -- {pid} {exit} 2>/dev/null || true; nohup sh -lc {inner} > {log} 2>&1 < /dev/null & echo $! > {pid}; cat {pid}",
+        inner = shell_quote(&inner),
+        log = shell_quote(log_path)
+    )
+}
+
+pub fn job_status_script(pid: &str, pid_path: &str, exit_path: &str, log_path: &str) -> String {
+    [
+        "set -u",
+        &format!("PID_VALUE={}", shell_quote(pid)),
+        &format!("PID_PATH={}", shell_quote(pid_path)),
+        &format!("EXIT_PATH={}", shell_quote(exit_path)),
+        &format!("LOG_PATH={}", shell_quote(log_path)),
+        "pid=\"$PID_VALUE\"",
+        "if [ -z \"$pid\" ] && [ -n \"$PID_PATH\" ] && [ -f \"$PID_PATH\" ]; then pid=\"$(cat \"$PID_PATH\" 2>/dev/null | tr -dc '0-9' | head -c 32)\"; fi",
+        "running=0",
+        "if [ -n \"$pid\" ] && kill -0 \"$pid\" 2>/dev/null; then running=1; fi",
+        "exit_code=\"\"",
+        "if [ -n \"$EXIT_PATH\" ] && [ -f \"$EXIT_PATH\" ]; then exit_code=\"$(cat \"$EXIT_PATH\" 2>/dev/null | tr -d '\\r\\n' | head -c 64)\"; fi",
+        "log_bytes=\"\"",
+        "if [ -n \"$LOG_PATH\" ] && [ -f \"$LOG_PATH\" ]; then log_bytes=\"$(wc -c < \"$LOG_PATH\" 2>/dev/null | tr -d ' ')\"; fi",
+        "echo \"__INFRA_PID__=$pid\"",
+        "echo \"__INFRA_RUNNING__=$running\"",
+        "echo \"__INFRA_EXIT_CODE__=$exit_code\"",
+        "echo \"__INFRA_LOG_BYTES__=$log_bytes\"",
+    ]
+    .join("\n")
+}
+
+// job_status_script for several jobs in one exec. Each job is `[pid, pid_path, exit_path,
+// log_path]` and prints `__INFRA_JOB__=<index>|<pid>|<running>|<log_bytes>|<exit_code>`, exit
+// code last since it is the only free-form field.
+pub fn job_status_batch_script(jobs: &[[&str; 4]]) -> String {
+    let mut lines: Vec<String> = [
+        "set -u",
+        "probe() {",
+        "  pid=\"$2\"",
+        "  if [ -z \"$pid\" ] && [ -n \"$3\" ] && [ -f \"$3\" ]; then pid=\"$(cat \"$3\" 2>/dev/null | tr -dc '0-9' | head -c 32)\"; fi",
+        "  running=0",
+        "  if [ -n \"$pid\" ] && kill -0 \"$pid\" 2>/dev/null; then running=1; fi",
+        "  exit_code=\"\"",
+        "  if [ -n \"$4\" ] && [ -f \"$4\" ]; then exit_code=\"$(cat \"$4\" 2>/dev/null | tr -d '\\r\\n' | head -c 64)\"; fi",
+        "  log_bytes=\"\"",
+        "  if [ -n \"$5\" ] && [ -f \"$5\" ]; then log_bytes=\"$(wc -c < \"$5\" 2>/dev/null | tr -d ' ')\"; fi",
+        "  echo \"__INFRA_JOB__=$1|$pid|$running|$log_bytes|$exit_code\"",
+        "}",
+    ]
+    .iter()
+    .map(|line| line.to_string())
+    .collect();
+    for (index, job) in jobs.iter().enumerate() {
+        let args: Vec<String> = job.iter().map(|value| shell_quote(value)).collect();
+        lines.push(format!("probe {} {}", index, args.join(" ")));
+    }
+    lines.join("\n")
+}
+
+// Exit status of log_delta_script when the remote tail/head cannot address bytes (or base64 is
+// missing); the caller falls back to a line tail.
+pub const LOG_DELTA_UNSUPPORTED_EXIT: i64 = 97;
+
+// Bytes [offset, offset + len) of the log, base64-encoded so a chunk cut inside a multi-byte
+// character or holding binary output survives the trip intact.
+pub fn log_delta_script(log_path: &str, offset: u64, len: u64) -> String {
+    [
+        "set -u".to_string(),
+        format!("LOG_PATH={}", shell_quote(log_path)),
+        format!(
+            "command -v base64 >/dev/null 2>&1 || exit {}",
+            LOG_DELTA_UNSUPPORTED_EXIT
+        ),
+        format!(
+            "[ \"$(printf 'ab' | tail -c +2 2>/dev/null | head -c 1 2>/dev/null)\" = b ] || exit {}",
+            LOG_DELTA_UNSUPPORTED_EXIT
+        ),
+        format!(
+            "tail -c +{} -- \"$LOG_PATH\" 2>/dev/null | head -c {} | base64",
+            offset + 1,
+            len
+        ),
+    ]
+    .join("\n")
+}
+
+// Sweeps exec_detached scratch files older than `max_age_minutes`: orphaned stdin uploads and
+// finished jobs' log/pid/exit sets. A job whose pid is still alive and has no exit file is kept.
+pub fn jobs_gc_script(scratch_dir: &str, max_age_minutes: u64) -> String {
+    [
+        "set -u".to_string(),
+        format!("DIR={}", shell_quote(scratch_dir)),
+        format!("AGE={}", max_age_minutes),
+        "[ -d \"$DIR\" ] || { echo \"__INFRA_GC_NO_DIR__\"; exit 0; }".to_string(),
+        "stale() { [ -n \"$(find \"$1\" -mmin +\"$AGE\" 2>/dev/null)\" ]; }".to_string(),
+        "for f in \"$DIR\"/infra-stdin-*; do".to_string(),
+        "  [ -f \"$f\" ] && stale \"$f\" || continue".to_string(),
+        "  rm -f -- \"$f\" && echo \"__INFRA_GC_REMOVED__=$f\"".to_string(),
+        "done".to_string(),
+        "for log in \"$DIR\"/infra-detached-*.log; do".to_string(),
+        "  [ -f \"$log\" ] && stale \"$log\" || continue".to_string(),
+        "  pid=\"$(cat \"$log.pid\" 2>/dev/null | tr -dc '0-9' | head -c 32)\"".to_string(),
+        "  if [ ! -f \"$log.exit\" ] && [ -n \"$pid\" ] && kill -0 \"$pid\" 2>/dev/null; then echo \"__INFRA_GC_RUNNING__=$log\"; continue; fi".to_string(),
+        "  rm -f -- \"$log\" \"$log.pid\" \"$log.exit\" && echo \"__INFRA_GC_REMOVED__=$log\"".to_string(),
+        "done".to_string(),
+    ]
+    .join("\n")
+}
+
+// The file is read through stdin so the output never echoes the name: coreutils prefixes the
+// line with `\` for names containing a newline or backslash, which broke hash parsing.
+pub fn sha256_script(path: &str) -> String {
+    [
+        "set -u".to_string(),
+        format!("PATH_ARG={}", shell_quote(path)),
+        "if command -v sha256sum >/dev/null 2>&1; then sha256sum 2>/dev/null < \"$PATH_ARG\" | awk '{print $1}'; exit 0; fi".to_string(),
+        "if command -v shasum >/dev/null 2>&1; then shasum -a 256 2>/dev/null < \"$PATH_ARG\" | awk '{print $1}'; exit 0; fi".to_string(),
+        "if command -v openssl >/dev/null 2>&1; then openssl dgst -sha256 2>/dev/null < \"$PATH_ARG\" | awk '{print $NF}'; exit 0; fi".to_string(),
+        "echo \"__INFRA_NO_SHA256__\"".to_string(),
+        "exit 127".to_string(),
+    ]
+    .join("\n")
+}
+
+// Read-only checks run before deploy_file uploads anything. Free space and writability are
+// probed on the nearest existing ancestor, since mkdirs may create the target directory. The
+// current file's hash is printed only when `with_hash` is set and the file exists.
+pub fn deploy_preflight_script(path: &str, unit: Option<&str>, with_hash: bool) -> String {
+    let mut lines = vec![
+        "set -u".to_string(),
+        format!("TARGET={}", shell_quote(path)),
+        "DIR=$(dirname -- \"$TARGET\")".to_string(),
+        "if [ -d \"$DIR\" ]; then echo \"__INFRA_PF_DIR__=1\"; else echo \"__INFRA_PF_DIR__=0\"; fi".to_string(),
+        "PROBE=\"$DIR\"".to_string(),
+        "while [ ! -d \"$PROBE\" ]; do PROBE=$(dirname -- \"$PROBE\"); done".to_string(),
+        "if [ -w \"$PROBE\" ]; then echo \"__INFRA_PF_WRITABLE__=1\"; else echo \"__INFRA_PF_WRITABLE__=0\"; fi".to_string(),
+        "echo \"__INFRA_PF_FREE_KB__=$(df -Pk -- \"$PROBE\" 2>/dev/null | awk 'NR==2 {print $4}')\"".to_string(),
+    ];
+    if let Some(unit) = unit {
+        lines.push(format!("UNIT={}", shell_quote(unit)));
+        lines.push("if ! command -v systemctl >/dev/null 2>&1; then echo \"__INFRA_PF_UNIT__=no_systemctl\"; elif systemctl cat -- \"$UNIT\" >/dev/null 2>&1; then echo \"__INFRA_PF_UNIT__=1\"; else echo \"__INFRA_PF_UNIT__=0\"; fi".to_string());
+    }
+    if with_hash {
+        lines.push("if [ -f \"$TARGET\" ]; then echo \"__INFRA_PF_SHA256__=$( (sha256sum || shasum -a 256) < \"$TARGET\" 2>/dev/null | awk '{print $1}')\"; fi".to_string());
+    }
+    lines.join("\n")
+}
+
+// `--` keeps a unit name starting with `-` from being read as a systemctl option.
+pub fn restart_service_command(service: &str) -> String {
+    let unit = shell_quote(service);
+    format!(
+        "systemctl restart -- {} && systemctl is-active -- {}",
+        unit, unit
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use std::process::Command;
+
+    const HOSTILE: &[&str] = &[
+        "",
+        "plain",
+        "it's",
+        "say \"hi\"",
+        "$(touch pwned)",
+        "`touch pwned`",
+        "${HOME}",
+        "a\\b",
+        "line1\nline2\n",
+        "-rf",
+        "; rm -rf / #",
+        "'\"'\"'",
+        "tab\there",
+        "ünïcødé ✓",
+    ];
+
+    fn sh(script: &str) -> std::process::Output {
+        Command::new("sh")
+            .arg("-c")
+            .arg(script)
+            .output()
+            .expect("run sh")
+    }
+
+    fn printf_round_trip(value: &str) -> String {
+        let out = sh(&format!("printf %s {}", shell_quote(value)));
+        assert!(out.status.success(), "sh failed for {:?}", value);
+        String::from_utf8(out.stdout).expect("utf8 output")
+    }
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("infra-test-{}-{}", name, uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).expect("create temp dir");
+        dir
+    }
+
+    #[test]
+    fn quote_round_trips_hostile_values() {
+        for value in HOSTILE {
+            assert_eq!(printf_round_trip(value), *value);
+        }
+        // The old escaper backslash-escaped `"` inside single quotes and corrupted the value.
+        assert_eq!(shell_quote("say \"hi\""), "'say \"hi\"'");
+    }
+
+    #[test]
+    fn quote_round_trips_random_strings() {
+        let alphabet: Vec<char> = "ab '\"$`\\\n\r\t;&|<>(){}*?!#~%-=é".chars().collect();
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0x5eed);
+        for _ in 0..200 {
+            let len = rng.gen_range(0..24);
+            let value: String = (0..len)
+                .map(|_| alphabet[rng.gen_range(0..alphabet.len())])
+                .collect();
+            assert_eq!(printf_round_trip(&value), value, "value {:?}", value);
+        }
+    }
+
+    #[test]
+    fn nul_bytes_are_rejected() {
+        assert!(ensure_shell_arg("/tmp/a\0b", "remote_path").is_err());
+        assert!(ensure_shell_arg("/tmp/a\nb", "remote_path").is_ok());
+    }
+
+    #[test]
+    fn detached_and_status_scripts_survive_hostile_paths() {
+        let dir = temp_dir("detached");
+        let base = dir.join("log 'x' \"y\" $(touch pwned) `id`\nz");
+        let base = base.to_str().expect("utf8 path").to_string();
+        let stdin_path = format!("{}.in", base);
+        std::fs::write(&stdin_path, "from stdin").expect("write stdin");
+        let (pid, exit, log) = (
+            format!("{}.pid", base),
+            format!("{}.exit", base),
+            format!("{}.log", base),
+        );
+        let command = "cat; printf ' %s' \"it's \\\"quoted\\\" \\$HOME\"";
+        let out = sh(&detached_script(
+            command,
+            Some(&stdin_path),
+            &pid,
+            &exit,
+            &log,
+        ));
+        assert!(out.status.success());
+
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+        while !std::path::Path::new(&exit).exists() && std::time::Instant::now() < deadline {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        assert_eq!(
+            std::fs::read_to_string(&exit).expect("exit file").trim(),
+            "0"
+        );
+        assert_eq!(
+            std::fs::read_to_string(&log).expect("log file"),
+            "from stdin it's \"quoted\" $HOME"
+        );
+        assert!(!std::path::Path::new(&stdin_path).exists(), "stdin removed");
+
+        let status = sh(&job_status_script("", &pid, &exit, &log));
+        let stdout = String::from_utf8_lossy(&status.stdout);
+        assert!(stdout.contains("__INFRA_EXIT_CODE__=0"), "{}", stdout);
+        let log_bytes = std::fs::metadata(&log).expect("log meta").len();
+        assert!(
+            stdout.contains(&format!("__INFRA_LOG_BYTES__={}", log_bytes)),
+            "{}",
+            stdout
+        );
+        assert!(!std::path::Path::new("pwned").exists());
+        assert!(!dir.join("pwned").exists());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn batch_status_script_probes_every_job_in_order() {
+        let dir = temp_dir("batch");
+        let base = dir.join("job 'a' $(touch pwned)");
+        let base = base.to_str().expect("utf8 path").to_string();
+        let (exit, log) = (format!("{}.exit", base), format!("{}.log", base));
+        std::fs::write(&exit, "3\n").expect("write exit");
+        std::fs::write(&log, "12345").expect("write log");
+        let own_pid = std::process::id().to_string();
+        let pid_path = dir.join("own.pid");
+        std::fs::write(&pid_path, &own_pid).expect("write pid");
+        let pid_path = pid_path.to_str().unwrap();
+
+        let out = sh(&job_status_batch_script(&[
+            ["", "", &exit, &log],
+            ["", pid_path, "", ""],
+            ["", "/nonexistent/job.pid", "", ""],
+        ]));
+        let stdout = String::from_utf8_lossy(&out.stdout);
+        let lines: Vec<&str> = stdout.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "__INFRA_JOB__=0||0|5|3".to_string(),
+                format!("__INFRA_JOB__=1|{}|1||", own_pid),
+                "__INFRA_JOB__=2||0||".to_string(),
+            ]
+        );
+        assert!(!dir.join("pwned").exists());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    fn decode_base64(out: &std::process::Output) -> Vec<u8> {
+        use base64::Engine;
+        let text: String = String::from_utf8_lossy(&out.stdout)
+            .chars()
+            .filter(|ch| !ch.is_whitespace())
+            .collect();
+        base64::engine::general_purpose::STANDARD
+            .decode(text)
+            .expect("base64")
+    }
+
+    #[test]
+    fn log_delta_reads_exact_byte_ranges() {
+        let dir = temp_dir("delta");
+        let log = dir.join("job 'x'.log");
+        let content = "ünïcødé line 1\nline 2\n".repeat(40).into_bytes();
+        std::fs::write(&log, &content).expect("write log");
+        let log = log.to_str().expect("utf8 path");
+
+        let mut rebuilt = Vec::new();
+        let mut offset = 0u64;
+        while offset < content.len() as u64 {
+            let out = sh(&log_delta_script(log, offset, 37));
+            assert!(out.status.success());
+            let chunk = decode_base64(&out);
+            assert_eq!(chunk, content[offset as usize..][..chunk.len()]);
+            offset += chunk.len() as u64;
+            rebuilt.extend(chunk);
+        }
+        assert_eq!(rebuilt, content);
+        assert!(decode_base64(&sh(&log_delta_script(log, offset, 37))).is_empty());
+
+        // A tail without `-c +N` makes the script bail out with the fallback status.
+        let bin = dir.join("bin");
+        std::fs::create_dir_all(&bin).expect("bin dir");
+        let fake = bin.join("tail");
+        std::fs::write(
+            &fake,
+            "#!/bin/sh\necho 'tail: invalid option -- c' >&2\nexit 1\n",
+        )
+        .expect("fake tail");
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&fake, std::fs::Permissions::from_mode(0o755)).expect("chmod");
+        let out = Command::new("sh")
+            .arg("-c")
+            .arg(log_delta_script(log, 0, 37))
+            .env(
+                "PATH",
+                format!(
+                    "{}:{}",
+                    bin.display(),
+                    std::env::var("PATH").unwrap_or_default()
+                ),
+            )
+            .output()
+            .expect("run sh");
+        assert_eq!(out.status.code(), Some(LOG_DELTA_UNSUPPORTED_EXIT as i32));
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    fn wait_until(mut done: impl FnMut() -> bool) -> bool {
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+        while !done() {
+            if std::time::Instant::now() >= deadline {
+                return false;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        true
+    }
+
+    #[test]
+    fn killed_wrapper_still_removes_stdin_file() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = temp_dir("scratch");
+        let scratch = dir.join("nested 'scratch'");
+        let scratch = scratch.to_str().expect("utf8 path").to_string();
+        let stdin_path = format!("{}/infra-stdin-1.txt", scratch);
+        let mut upload = Command::new("sh")
+            .arg("-c")
+            .arg(stdin_upload_command(&scratch, &stdin_path))
+            .stdin(std::process::Stdio::piped())
+            .spawn()
+            .expect("spawn upload");
+        std::io::Write::write_all(upload.stdin.as_mut().unwrap(), b"secret").expect("write");
+        drop(upload.stdin.take());
+        assert!(upload.wait().expect("upload").success());
+        let mode = |path: &str| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(&scratch), 0o700);
+        assert_eq!(mode(&stdin_path), 0o600);
+
+        let log = format!("{}/infra-detached-1.log", scratch);
+        let (pid, exit) = (format!("{}.pid", log), format!("{}.exit", log));
+        let out = sh(&detached_script(
+            "echo started; sleep 30",
+            Some(&stdin_path),
+            &pid,
+            &exit,
+            &log,
+        ));
+        assert!(out.status.success());
+        let wrapper = String::from_utf8_lossy(&out.stdout).trim().to_string();
+        // The traps are installed before the command starts writing to the log.
+        assert!(wait_until(|| std::fs::read_to_string(&log)
+            .map(|text| text.contains("started"))
+            .unwrap_or(false)));
+        assert!(sh(&format!("kill {}", wrapper)).status.success());
+        assert!(wait_until(|| !std::path::Path::new(&stdin_path).exists()));
+        assert!(!std::path::Path::new(&exit).exists());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn jobs_gc_removes_stale_files_and_keeps_running_jobs() {
+        let dir = temp_dir("gc");
+        let path = |name: &str| dir.join(name).to_str().unwrap().to_string();
+        for name in [
+            "infra-stdin-old.txt",
+            "infra-stdin-new.txt",
+            "infra-detached-done.log",
+            "infra-detached-done.log.pid",
+            "infra-detached-done.log.exit",
+            "infra-detached-live.log",
+            "other.log",
+        ] {
+            std::fs::write(path(name), "x").expect("write");
+        }
+        std::fs::write(
+            path("infra-detached-live.log.pid"),
+            std::process::id().to_string(),
+        )
+        .expect("write pid");
+        for name in [
+            "infra-stdin-old.txt",
+            "infra-detached-done.log",
+            "infra-detached-live.log",
+            "other.log",
+        ] {
+            let status = Command::new("touch")
+                .args(["-d", "2 days ago"])
+                .arg(path(name))
+                .status()
+                .expect("touch");
+            assert!(status.success());
+        }
+        let out = sh(&jobs_gc_script(dir.to_str().unwrap(), 60));
+        assert!(out.status.success());
+        let stdout = String::from_utf8_lossy(&out.stdout).to_string();
+        assert!(stdout.contains(&format!(
+            "__INFRA_GC_REMOVED__={}",
+            path("infra-stdin-old.txt")
+        )));
+        assert!(stdout.contains(&format!(
+            "__INFRA_GC_RUNNING__={}",
+            path("infra-detached-live.log")
+        )));
+        let mut left: Vec<String> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        left.sort();
+        assert_eq!(
+            left,
+            [
+                "infra-detached-live.log",
+                "infra-detached-live.log.pid",
+                "infra-stdin-new.txt",
+                "other.log",
+            ]
+        );
+        let missing = sh(&jobs_gc_script(&path("missing"), 60));
+        assert_eq!(
+            String::from_utf8_lossy(&missing.stdout).trim(),
+            "__INFRA_GC_NO_DIR__"
+        );
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn sha256_script_hashes_hostile_paths() {
+        use sha2::Digest;
+        let dir = temp_dir("sha");
+        let path = dir.join("-n 'a' \"b\" $(id)\n`x`.bin");
+        std::fs::write(&path, b"payload").expect("write file");
+        let out = sh(&sha256_script(path.to_str().expect("utf8 path")));
+        assert!(out.status.success());
+        let expected = format!("{:x}", sha2::Sha256::digest(b"payload"));
+        assert_eq!(String::from_utf8_lossy(&out.stdout).trim(), expected);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn restart_command_passes_unit_as_single_argument() {
+        let dir = temp_dir("restart");
+        let record = dir.join("calls");
+        let fake = dir.join("systemctl");
+        std::fs::write(
+            &fake,
+            format!(
+                "#!/bin/sh\nfor arg in \"$@\"; do printf '[%s]' \"$arg\" >> {}; done\necho >> {}\n",
+                shell_quote(record.to_str().unwrap()),
+                shell_quote(record.to_str().unwrap())
+            ),
+        )
+        .expect("write fake systemctl");
+        Command::new("chmod")
+            .arg("+x")
+            .arg(&fake)
+            .status()
+            .expect("chmod");
+        let script = format!(
+            "PATH={}:\"$PATH\"; {}",
+            shell_quote(dir.to_str().unwrap()),
+            restart_service_command("--now app's $(id).service")
+        );
+        assert!(sh(&script).status.success());
+        assert_eq!(
+            std::fs::read_to_string(&record).expect("calls"),
+            "[restart][--][--now app's $(id).service]\n[is-active][--][--now app's $(id).service]\n"
+        );
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn deploy_preflight_reports_dir_space_unit_and_hash() {
+        use sha2::Digest;
+        let dir = temp_dir("preflight");
+        let fake = dir.join("systemctl");
+        std::fs::write(
+            &fake,
+            "#!/bin/sh\n[ \"$1\" = cat ] && [ \"$3\" = \"app's.service\" ]\n",
+        )
+        .expect("write fake systemctl");
+        Command::new("chmod")
+            .arg("+x")
+            .arg(&fake)
+            .status()
+            .expect("chmod");
+        let run = |path: &std::path::Path, unit: &str| {
+            let script = format!(
+                "PATH={}:\"$PATH\"; {}",
+                shell_quote(dir.to_str().unwrap()),
+                deploy_preflight_script(path.to_str().unwrap(), Some(unit), true)
+            );
+            let out = sh(&script);
+            assert!(out.status.success());
+            String::from_utf8_lossy(&out.stdout).to_string()
+        };
+
+        let existing = dir.join("app $(id).bin");
+        std::fs::write(&existing, b"payload").expect("write file");
+        let stdout = run(&existing, "app's.service");
+        assert!(stdout.contains("__INFRA_PF_DIR__=1\n"), "{}", stdout);
+        assert!(stdout.contains("__INFRA_PF_WRITABLE__=1\n"), "{}", stdout);
+        assert!(stdout.contains("__INFRA_PF_UNIT__=1\n"), "{}", stdout);
+        let expected = format!("{:x}", sha2::Sha256::digest(b"payload"));
+        assert!(
+            stdout.contains(&format!("__INFRA_PF_SHA256__={}", expected)),
+            "{}",
+            stdout
+        );
+        let free_kb = stdout
+            .lines()
+            .find_map(|line| line.strip_prefix("__INFRA_PF_FREE_KB__="))
+            .and_then(|value| value.parse::<u64>().ok());
+        assert!(free_kb.is_some(), "{}", stdout);
+
+        let missing = dir.join("new").join("deeper").join("app.bin");
+        let stdout = run(&missing, "other.service");
+        assert!(stdout.contains("__INFRA_PF_DIR__=0\n"), "{}", stdout);
+        assert!(stdout.contains("__INFRA_PF_WRITABLE__=1\n"), "{}", stdout);
+        assert!(stdout.contains("__INFRA_PF_UNIT__=0\n"), "{}", stdout);
+        assert!(!stdout.contains("__INFRA_PF_SHA256__"), "{}", stdout);
+        assert!(
+            !dir.join("new").exists(),
+            "preflight never creates anything"
+        );
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,243 @@
+use crate::services::audit::AuditService;
+use crate::services::job::JobService;
+use crate::services::logger::Logger;
+use serde_json::Value;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+
+// Exit codes after SIGINT/SIGTERM: the in-flight call finished inside the drain window, or it
+// was dropped when the window ran out.
+pub const EXIT_SHUTDOWN_DRAINED: i32 = 60;
+pub const EXIT_SHUTDOWN_FORCED: i32 = 61;
+
+// How long teardown waits for queued audit entries to reach the webhook.
+const AUDIT_FLUSH_TIMEOUT_MS: u64 = 5_000;
+
+// Cancellation shared by every handler of one call; cloning keeps the same signal.
+#[derive(Clone, Debug)]
+pub struct ShutdownToken {
+    sender: Arc<watch::Sender<bool>>,
+}
+
+impl Default for ShutdownToken {
+    fn default() -> Self {
+        Self {
+            sender: Arc::new(watch::channel(false).0),
+        }
+    }
+}
+
+impl ShutdownToken {
+    pub fn cancel(&self) {
+        self.sender.send_replace(true);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        *self.sender.borrow()
+    }
+
+    pub async fn cancelled(&self) {
+        let mut receiver = self.sender.subscribe();
+        let _ = receiver.wait_for(|cancelled| *cancelled).await;
+    }
+}
+
+tokio::task_local! {
+    static TOKEN: ShutdownToken;
+}
+
+pub async fn with_token<F: Future>(token: ShutdownToken, fut: F) -> F::Output {
+    TOKEN.scope(token, fut).await
+}
+
+pub fn current() -> Option<ShutdownToken> {
+    TOKEN.try_with(|token| token.clone()).ok()
+}
+
+pub fn is_shutting_down() -> bool {
+    TOKEN
+        .try_with(|token| token.is_cancelled())
+        .unwrap_or(false)
+}
+
+// Resolves once shutdown starts; never resolves outside a supervised call, so it can sit in a
+// `select!` next to the real work.
+pub async fn cancelled() {
+    match current() {
+        Some(token) => token.cancelled().await,
+        None => std::future::pending().await,
+    }
+}
+
+// Carries the current token into a future handed to tokio::spawn.
+pub fn in_current_scope<F: Future>(fut: F) -> impl Future<Output = F::Output> {
+    let token = current();
+    async move {
+        match token {
+            Some(token) => TOKEN.scope(token, fut).await,
+            None => fut.await,
+        }
+    }
+}
+
+// Resolves with the signal name on SIGINT or SIGTERM. Handlers are installed before this
+// returns, so a signal that arrives while the call is starting is not lost.
+#[cfg(unix)]
+pub fn listen() -> std::io::Result<impl Future<Output = &'static str>> {
+    use tokio::signal::unix::{signal, SignalKind};
+    let mut interrupt = signal(SignalKind::interrupt())?;
+    let mut terminate = signal(SignalKind::terminate())?;
+    Ok(async move {
+        tokio::select! {
+            _ = interrupt.recv() => "SIGINT",
+            _ = terminate.recv() => "SIGTERM",
+        }
+    })
+}
+
+#[cfg(not(unix))]
+pub fn listen() -> std::io::Result<impl Future<Output = &'static str>> {
+    Ok(async {
+        let _ = tokio::signal::ctrl_c().await;
+        "SIGINT"
+    })
+}
+
+#[derive(Debug)]
+pub struct Supervised<T> {
+    // None when the drain window ran out and the call was dropped.
+    pub outcome: Option<T>,
+    pub signal: Option<&'static str>,
+    pub drain_ms: u64,
+    pub elapsed_ms: u64,
+}
+
+impl<T> Supervised<T> {
+    pub fn forced(&self) -> bool {
+        self.outcome.is_none()
+    }
+
+    // None when no signal arrived: the call's own exit code applies.
+    pub fn exit_code(&self) -> Option<i32> {
+        self.signal.map(|_| {
+            if self.forced() {
+                EXIT_SHUTDOWN_FORCED
+            } else {
+                EXIT_SHUTDOWN_DRAINED
+            }
+        })
+    }
+
+    pub fn audit_entry(&self, interrupted_jobs: usize, closed_pools: usize) -> Value {
+        serde_json::json!({
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+            "status": if self.forced() { "forced" } else { "drained" },
+            "tool": "server",
+            "action": "shutdown",
+            "signal": self.signal,
+            "drain_ms": self.drain_ms,
+            "elapsed_ms": self.elapsed_ms,
+            "interrupted_jobs": interrupted_jobs,
+            "closed_pools": closed_pools,
+        })
+    }
+}
+
+// Process teardown after the last call, in order: local jobs (which die with this process) are
+// marked interrupted, pools closed (`close_pools` returns how many), a signal-driven stop is
+// audited, and queued audit entries are flushed to the webhook.
+pub async fn teardown<T>(
+    supervised: &Supervised<T>,
+    logger: &Logger,
+    job_service: &JobService,
+    audit_service: &AuditService,
+    close_pools: impl FnOnce() -> usize,
+) {
+    let interrupted_jobs = job_service.interrupt_local_jobs();
+    let closed_pools = close_pools();
+    if supervised.signal.is_some() {
+        logger.warn(
+            "Shutting down on signal",
+            Some(&serde_json::json!({
+                "signal": supervised.signal,
+                "forced": supervised.forced(),
+                "interrupted_jobs": interrupted_jobs,
+            })),
+        );
+        audit_service.append(&supervised.audit_entry(interrupted_jobs, closed_pools));
+    }
+    if let Err(err) = audit_service
+        .flush(Duration::from_millis(AUDIT_FLUSH_TIMEOUT_MS))
+        .await
+    {
+        logger.warn(
+            "Audit flush failed",
+            Some(&serde_json::json!({"error": err.message})),
+        );
+    }
+}
+
+// Runs `call` until it completes. When `signal` fires first, the call's token is cancelled
+// and it gets `drain` to finish before it is dropped.
+pub async fn supervise<F, S>(call: F, signal: S, drain: Duration) -> Supervised<F::Output>
+where
+    F: Future,
+    S: Future<Output = &'static str>,
+{
+    let token = ShutdownToken::default();
+    let call = with_token(token.clone(), call);
+    tokio::pin!(call);
+    let drain_ms = drain.as_millis() as u64;
+    let signal = tokio::select! {
+        outcome = &mut call => {
+            return Supervised { outcome: Some(outcome), signal: None, drain_ms, elapsed_ms: 0 };
+        }
+        signal = signal => signal,
+    };
+    token.cancel();
+    let started = std::time::Instant::now();
+    let outcome = tokio::time::timeout(drain, &mut call).await.ok();
+    Supervised {
+        outcome,
+        signal: Some(signal),
+        drain_ms,
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn token_reaches_the_call_and_the_window_bounds_it() {
+        assert!(!is_shutting_down());
+        let quick = supervise(async { 7 }, std::future::pending(), Duration::ZERO).await;
+        assert_eq!(quick.outcome, Some(7));
+        assert_eq!(quick.exit_code(), None);
+
+        let drained = supervise(
+            async {
+                cancelled().await;
+                is_shutting_down()
+            },
+            async { "SIGTERM" },
+            Duration::from_secs(5),
+        )
+        .await;
+        assert_eq!(drained.outcome, Some(true));
+        assert_eq!(drained.exit_code(), Some(EXIT_SHUTDOWN_DRAINED));
+
+        let forced = supervise(
+            std::future::pending::<()>(),
+            async { "SIGINT" },
+            Duration::from_millis(10),
+        )
+        .await;
+        assert!(forced.forced());
+        assert_eq!(forced.exit_code(), Some(EXIT_SHUTDOWN_FORCED));
+        assert_eq!(forced.audit_entry(1, 2)["status"], "forced");
+    }
+}
@@ -0,0 +1,266 @@
+use crate::errors::ToolError;
+use serde_json::Value;
+
+const MAX_SAMPLES: u64 = 100;
+const DEFAULT_INTERVAL_MS: u64 = 500;
+const MAX_INTERVAL_MS: u64 = 60_000;
+pub const MAX_SAMPLE_RESULTS: usize = 20;
+const SLA_KEYS: &[&str] = &["max_p95_ms", "max_avg_ms", "min_success_ratio"];
+
+// Thresholds a sampled smoke_http run must meet. Without any, every sample has to pass.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SmokeSla {
+    pub max_p95_ms: Option<u64>,
+    pub max_avg_ms: Option<u64>,
+    pub min_success_ratio: Option<f64>,
+}
+
+// `samples` sequential probes `interval_ms` apart, judged against `sla`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SmokeSampling {
+    pub samples: u64,
+    pub interval_ms: u64,
+    pub sla: SmokeSla,
+}
+
+// One probe: `ok` when it got the expected status, `latency_ms` only when it got a response.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SmokeSample {
+    pub ok: bool,
+    pub latency_ms: Option<u64>,
+}
+
+fn bounded_int(args: &Value, key: &str, min: u64, max: u64) -> Result<Option<u64>, ToolError> {
+    match args.get(key) {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => value
+            .as_u64()
+            .filter(|n| (min..=max).contains(n))
+            .map(Some)
+            .ok_or_else(|| {
+                ToolError::invalid_params(format!(
+                    "{} must be an integer between {} and {}",
+                    key, min, max
+                ))
+            }),
+    }
+}
+
+impl SmokeSla {
+    fn parse(value: &Value) -> Result<Self, ToolError> {
+        let obj = value.as_object().ok_or_else(|| {
+            ToolError::invalid_params("sla must be an object")
+                .with_hint("Example: sla: { max_p95_ms: 800, min_success_ratio: 0.95 }")
+        })?;
+        if let Some(unknown) = obj.keys().find(|key| !SLA_KEYS.contains(&key.as_str())) {
+            return Err(
+                ToolError::invalid_params(format!("unknown sla key '{}'", unknown))
+                    .with_hint(format!("Use any of: {}", SLA_KEYS.join(", "))),
+            );
+        }
+        let min_success_ratio = match obj.get("min_success_ratio") {
+            None | Some(Value::Null) => None,
+            Some(value) => Some(
+                value
+                    .as_f64()
+                    .filter(|ratio| (0.0..=1.0).contains(ratio))
+                    .ok_or_else(|| {
+                        ToolError::invalid_params(
+                            "sla.min_success_ratio must be a number between 0 and 1",
+                        )
+                    })?,
+            ),
+        };
+        let limit_ms = |key: &str| match obj.get(key) {
+            None | Some(Value::Null) => Ok(None),
+            Some(value) => value
+                .as_u64()
+                .filter(|ms| *ms > 0)
+                .map(Some)
+                .ok_or_else(|| {
+                    ToolError::invalid_params(format!("sla.{} must be a positive integer", key))
+                }),
+        };
+        Ok(Self {
+            max_p95_ms: limit_ms("max_p95_ms")?,
+            max_avg_ms: limit_ms("max_avg_ms")?,
+            min_success_ratio,
+        })
+    }
+
+    // Names every threshold the summary misses. Latency limits fail when no sample got a
+    // response at all.
+    pub fn violations(&self, summary: &SampleSummary) -> Vec<String> {
+        let mut out = Vec::new();
+        let required = self.min_success_ratio.unwrap_or(1.0);
+        if summary.success_ratio < required {
+            out.push(format!(
+                "success_ratio {:.3} < min_success_ratio {}",
+                summary.success_ratio, required
+            ));
+        }
+        for (key, limit, observed) in [
+            ("p95_ms", self.max_p95_ms, summary.p95_ms),
+            ("avg_ms", self.max_avg_ms, summary.avg_ms),
+        ] {
+            let Some(limit) = limit else { continue };
+            match observed {
+                Some(observed) if observed > limit => {
+                    out.push(format!("{} {} > max_{} {}", key, observed, key, limit))
+                }
+                Some(_) => {}
+                None => out.push(format!("max_{} {}: no sample got a response", key, limit)),
+            }
+        }
+        out
+    }
+
+    pub fn to_value(&self) -> Value {
+        serde_json::json!({
+            "max_p95_ms": self.max_p95_ms,
+            "max_avg_ms": self.max_avg_ms,
+            "min_success_ratio": self.min_success_ratio.unwrap_or(1.0),
+        })
+    }
+}
+
+impl SmokeSampling {
+    // None when neither `samples` nor `sla` is given: smoke_http then runs one probe as before.
+    pub fn parse(args: &Value) -> Result<Option<Self>, ToolError> {
+        let samples = bounded_int(args, "samples", 1, MAX_SAMPLES)?;
+        let sla = match args.get("sla") {
+            None | Some(Value::Null) => None,
+            Some(value) => Some(SmokeSla::parse(value)?),
+        };
+        if samples.is_none() && sla.is_none() {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            samples: samples.unwrap_or(1),
+            interval_ms: bounded_int(args, "interval_ms", 0, MAX_INTERVAL_MS)?
+                .unwrap_or(DEFAULT_INTERVAL_MS),
+            sla: sla.unwrap_or_default(),
+        }))
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SampleSummary {
+    pub ok: usize,
+    pub failed: usize,
+    pub success_ratio: f64,
+    pub min_ms: Option<u64>,
+    pub avg_ms: Option<u64>,
+    pub p95_ms: Option<u64>,
+    pub max_ms: Option<u64>,
+}
+
+impl SampleSummary {
+    // p95 is nearest-rank over the samples that got a response; the ratio counts every sample.
+    pub fn from_samples(samples: &[SmokeSample]) -> Self {
+        let ok = samples.iter().filter(|sample| sample.ok).count();
+        let mut latencies: Vec<u64> = samples.iter().filter_map(|s| s.latency_ms).collect();
+        latencies.sort_unstable();
+        let success_ratio = if samples.is_empty() {
+            0.0
+        } else {
+            ok as f64 / samples.len() as f64
+        };
+        let p95_ms = if latencies.is_empty() {
+            None
+        } else {
+            let rank = (latencies.len() * 95).div_ceil(100);
+            latencies.get(rank.saturating_sub(1)).copied()
+        };
+        Self {
+            ok,
+            failed: samples.len() - ok,
+            success_ratio,
+            min_ms: latencies.first().copied(),
+            avg_ms: (!latencies.is_empty())
+                .then(|| latencies.iter().sum::<u64>() / latencies.len() as u64),
+            p95_ms,
+            max_ms: latencies.last().copied(),
+        }
+    }
+
+    pub fn to_value(&self) -> Value {
+        serde_json::json!({
+            "ok": self.ok,
+            "failed": self.failed,
+            "success_ratio": self.success_ratio,
+            "min_ms": self.min_ms,
+            "avg_ms": self.avg_ms,
+            "p95_ms": self.p95_ms,
+            "max_ms": self.max_ms,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sample(ok: bool, latency_ms: Option<u64>) -> SmokeSample {
+        SmokeSample { ok, latency_ms }
+    }
+
+    #[test]
+    fn parse_only_switches_on_for_samples_or_sla() {
+        assert_eq!(
+            SmokeSampling::parse(&json!({"interval_ms": 10})).unwrap(),
+            None
+        );
+        let sampling = SmokeSampling::parse(&json!({"sla": {"max_p95_ms": 800}}))
+            .unwrap()
+            .unwrap();
+        assert_eq!((sampling.samples, sampling.interval_ms), (1, 500));
+        for bad in [
+            json!({"samples": 0}),
+            json!({"samples": 101}),
+            json!({"samples": 3, "interval_ms": -1}),
+            json!({"sla": {"max_p99_ms": 10}}),
+            json!({"sla": {"min_success_ratio": 1.5}}),
+            json!({"sla": "fast"}),
+            json!({"sla": {"max_avg_ms": 0}}),
+        ] {
+            assert!(SmokeSampling::parse(&bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn summary_and_violations() {
+        let mut samples: Vec<SmokeSample> =
+            (1..=19).map(|ms| sample(true, Some(ms * 10))).collect();
+        samples.push(sample(false, Some(8_000)));
+        samples.push(sample(false, None));
+        let summary = SampleSummary::from_samples(&samples);
+        assert_eq!((summary.ok, summary.failed), (19, 2));
+        assert_eq!((summary.min_ms, summary.max_ms), (Some(10), Some(8_000)));
+        assert_eq!(summary.p95_ms, Some(190));
+        assert_eq!(summary.avg_ms, Some(495));
+
+        assert_eq!(
+            SmokeSla::default().violations(&summary),
+            ["success_ratio 0.905 < min_success_ratio 1"]
+        );
+        let sla = SmokeSla {
+            max_p95_ms: Some(100),
+            max_avg_ms: Some(1_000),
+            min_success_ratio: Some(0.9),
+        };
+        assert_eq!(sla.violations(&summary), ["p95_ms 190 > max_p95_ms 100"]);
+
+        let unreachable = SampleSummary::from_samples(&[sample(false, None)]);
+        assert_eq!(unreachable.p95_ms, None);
+        assert_eq!(
+            sla.violations(&unreachable),
+            [
+                "success_ratio 0.000 < min_success_ratio 0.9",
+                "max_p95_ms 100: no sample got a response",
+                "max_avg_ms 1000: no sample got a response",
+            ]
+        );
+    }
+}
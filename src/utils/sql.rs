@@ -1,5 +1,6 @@
 use crate::errors::ToolError;
 use serde_json::Value;
+use std::collections::BTreeMap;
 
 pub fn normalize_identifier_part(value: &str) -> Result<String, ToolError> {
     let trimmed = value.trim();
@@ -84,6 +85,59 @@ pub fn build_where_clause(
     Ok((String::new(), Vec::new(), start_index))
 }
 
+// `returning: true | "*" | "col" | ["col", ...]` for write statements. Columns are quoted like
+// every other identifier, so reserved words such as "order" work as-is.
+pub fn build_returning_clause(returning: Option<&Value>) -> Result<String, ToolError> {
+    let items: Vec<Value> = match returning {
+        None | Some(Value::Null) | Some(Value::Bool(false)) => return Ok(String::new()),
+        Some(Value::Bool(true)) => vec![Value::String("*".to_string())],
+        Some(Value::String(text)) if text.trim().is_empty() => return Ok(String::new()),
+        Some(Value::String(text)) => vec![Value::String(text.clone())],
+        Some(Value::Array(items)) if items.is_empty() => return Ok(String::new()),
+        Some(Value::Array(items)) => items.clone(),
+        Some(_) => {
+            return Err(ToolError::invalid_params(
+                "returning must be a boolean, a column name or an array of column names",
+            ))
+        }
+    };
+    let mut columns = Vec::new();
+    for item in &items {
+        let column = item
+            .as_str()
+            .ok_or_else(|| ToolError::invalid_params("returning must contain only column names"))?;
+        if column.trim() == "*" {
+            columns.push("*".to_string());
+        } else {
+            columns.push(quote_qualified_identifier(column)?);
+        }
+    }
+    Ok(format!(" RETURNING {}", columns.join(", ")))
+}
+
+// Optimistic-lock guard: `expect: {column: value, ...}` becomes extra equality conditions
+// (null means IS NULL), so an update only applies while every column still holds its value.
+pub fn build_expect_clause(
+    expect: Option<&Value>,
+    start_index: i64,
+) -> Result<(String, Vec<Value>, i64), ToolError> {
+    let Some(expect) = expect.filter(|v| !v.is_null()) else {
+        return Ok((String::new(), Vec::new(), start_index));
+    };
+    let valid = expect
+        .as_object()
+        .map(|map| !map.is_empty())
+        .unwrap_or(false);
+    if !valid {
+        return Err(
+            ToolError::invalid_params("expect must be a non-empty object").with_hint(
+                "Example: { action: 'update', expect: { version: 3 }, data: { version: 4 } }",
+            ),
+        );
+    }
+    build_filters_clause(expect, start_index)
+}
+
 fn build_filters_clause(
     filters: &Value,
     start_index: i64,
@@ -162,3 +216,643 @@ fn build_filters_clause(
 
     Ok((clauses.join(" AND "), values, index))
 }
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Placeholder {
+    Positional(usize),
+    Named(String),
+}
+
+fn is_word_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_' || b == b'$' || b >= 0x80
+}
+
+fn name_end(bytes: &[u8], start: usize) -> usize {
+    bytes[start..]
+        .iter()
+        .position(|b| !(b.is_ascii_alphanumeric() || *b == b'_'))
+        .map(|pos| start + pos)
+        .unwrap_or(bytes.len())
+}
+
+fn skip_quoted(bytes: &[u8], start: usize, quote: u8, backslash_escapes: bool) -> usize {
+    let mut idx = start + 1;
+    while idx < bytes.len() {
+        let b = bytes[idx];
+        if backslash_escapes && b == b'\\' {
+            idx += 2;
+            continue;
+        }
+        if b == quote {
+            if bytes.get(idx + 1) == Some(&quote) {
+                idx += 2;
+                continue;
+            }
+            return idx + 1;
+        }
+        idx += 1;
+    }
+    bytes.len()
+}
+
+fn skip_block_comment(bytes: &[u8], start: usize) -> usize {
+    let mut depth = 0usize;
+    let mut idx = start;
+    while idx + 1 < bytes.len() {
+        match (bytes[idx], bytes[idx + 1]) {
+            (b'/', b'*') => {
+                depth += 1;
+                idx += 2;
+            }
+            (b'*', b'/') => {
+                depth -= 1;
+                idx += 2;
+                if depth == 0 {
+                    return idx;
+                }
+            }
+            _ => idx += 1,
+        }
+    }
+    bytes.len()
+}
+
+// `$tag$ ... $tag$` (or `$$ ... $$`); None when `$` does not open a dollar quote.
+fn skip_dollar_quote(bytes: &[u8], start: usize) -> Option<usize> {
+    let tag_end = name_end(bytes, start + 1);
+    if bytes.get(tag_end) != Some(&b'$') {
+        return None;
+    }
+    let tag = &bytes[start..=tag_end];
+    let body = tag_end + 1;
+    Some(
+        bytes[body..]
+            .windows(tag.len())
+            .position(|window| window == tag)
+            .map(|pos| body + pos + tag.len())
+            .unwrap_or(bytes.len()),
+    )
+}
+
+// Byte ranges of `$n` and `:name` placeholders, skipping string literals (including E'' and
+// dollar quotes), quoted identifiers, comments and `::` casts.
+fn scan_placeholders(sql: &str) -> Vec<(usize, usize, Placeholder)> {
+    let bytes = sql.as_bytes();
+    let mut out = Vec::new();
+    let mut idx = 0usize;
+    while idx < bytes.len() {
+        let prev_word = idx > 0 && is_word_byte(bytes[idx - 1]);
+        match bytes[idx] {
+            b'\'' => {
+                let escape_string = idx > 0
+                    && matches!(bytes[idx - 1], b'e' | b'E')
+                    && !(idx > 1 && is_word_byte(bytes[idx - 2]));
+                idx = skip_quoted(bytes, idx, b'\'', escape_string);
+            }
+            b'"' => idx = skip_quoted(bytes, idx, b'"', false),
+            b'-' if bytes.get(idx + 1) == Some(&b'-') => {
+                idx = bytes[idx..]
+                    .iter()
+                    .position(|b| *b == b'\n')
+                    .map(|pos| idx + pos + 1)
+                    .unwrap_or(bytes.len());
+            }
+            b'/' if bytes.get(idx + 1) == Some(&b'*') => idx = skip_block_comment(bytes, idx),
+            b':' if bytes.get(idx + 1) == Some(&b':') => idx += 2,
+            // After a word or `]` the colon is an array slice bound (`arr[a:b]`, `m[1][lo:hi]`).
+            b':' if prev_word || (idx > 0 && bytes[idx - 1] == b']') => idx += 1,
+            b':' => {
+                let end = name_end(bytes, idx + 1);
+                if end > idx + 1 && !bytes[idx + 1].is_ascii_digit() {
+                    out.push((idx, end, Placeholder::Named(sql[idx + 1..end].to_string())));
+                    idx = end;
+                } else {
+                    idx += 1;
+                }
+            }
+            b'$' if !prev_word => {
+                let digits_end = bytes[idx + 1..]
+                    .iter()
+                    .position(|b| !b.is_ascii_digit())
+                    .map(|pos| idx + 1 + pos)
+                    .unwrap_or(bytes.len());
+                if digits_end > idx + 1 {
+                    if let Ok(index) = sql[idx + 1..digits_end].parse::<usize>() {
+                        out.push((idx, digits_end, Placeholder::Positional(index)));
+                    }
+                    idx = digits_end;
+                } else {
+                    idx = skip_dollar_quote(bytes, idx).unwrap_or(idx + 1);
+                }
+            }
+            _ => idx += 1,
+        }
+    }
+    out
+}
+
+// Postgres type names that contain spaces; any other type is one identifier, optionally
+// schema-qualified.
+const MULTI_WORD_TYPES: &[&str] = &[
+    "double precision",
+    "character varying",
+    "timestamp with time zone",
+    "timestamp without time zone",
+    "time with time zone",
+    "time without time zone",
+    "bit varying",
+];
+
+// The value is spliced after `::` verbatim, so anything beyond a type name (`text into t`)
+// would change what the statement does behind the read/write classification.
+fn is_type_name(value: &str) -> bool {
+    let mut base = value;
+    while let Some(inner) = base.strip_suffix("[]") {
+        base = inner;
+    }
+    let is_ident = |part: &str| {
+        let mut chars = part.chars();
+        chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+    };
+    let mut parts = base.splitn(2, '.');
+    let qualified = parts.next().is_some_and(is_ident) && parts.next().is_none_or(is_ident);
+    qualified
+        || MULTI_WORD_TYPES
+            .iter()
+            .any(|ty| ty.eq_ignore_ascii_case(base))
+}
+
+// Appends `::<type>` to every occurrence of the given 1-based positional placeholders.
+pub fn cast_placeholders(sql: &str, casts: &BTreeMap<usize, String>) -> String {
+    let mut out = String::with_capacity(sql.len());
+    let mut last = 0usize;
+    for (_, end, placeholder) in scan_placeholders(sql) {
+        if let Placeholder::Positional(index) = placeholder {
+            if let Some(cast) = casts.get(&index) {
+                out.push_str(&sql[last..end]);
+                out.push_str("::");
+                out.push_str(cast);
+                last = end;
+            }
+        }
+    }
+    out.push_str(&sql[last..]);
+    out
+}
+
+// `sql` with string literals, quoted identifiers and comments blanked out, so keywords and `;`
+// separators can be found without tripping over quoted text.
+fn code_only(sql: &str) -> String {
+    let bytes = sql.as_bytes();
+    let mut out = String::with_capacity(sql.len());
+    let mut idx = 0usize;
+    while idx < bytes.len() {
+        let prev_word = idx > 0 && is_word_byte(bytes[idx - 1]);
+        let skipped_to = match bytes[idx] {
+            b'\'' => {
+                let escape_string = idx > 0
+                    && matches!(bytes[idx - 1], b'e' | b'E')
+                    && !(idx > 1 && is_word_byte(bytes[idx - 2]));
+                Some(skip_quoted(bytes, idx, b'\'', escape_string))
+            }
+            b'"' => Some(skip_quoted(bytes, idx, b'"', false)),
+            b'-' if bytes.get(idx + 1) == Some(&b'-') => Some(
+                bytes[idx..]
+                    .iter()
+                    .position(|b| *b == b'\n')
+                    .map(|pos| idx + pos + 1)
+                    .unwrap_or(bytes.len()),
+            ),
+            b'/' if bytes.get(idx + 1) == Some(&b'*') => Some(skip_block_comment(bytes, idx)),
+            b'$' if !prev_word => skip_dollar_quote(bytes, idx),
+            _ => None,
+        };
+        match skipped_to {
+            Some(end) => {
+                out.push(' ');
+                idx = end;
+            }
+            None => {
+                let ch = sql[idx..].chars().next().unwrap_or(' ');
+                out.push(ch);
+                idx += ch.len_utf8();
+            }
+        }
+    }
+    out
+}
+
+pub const DEFAULT_DRY_RUN_SAMPLE: usize = 10;
+pub const MAX_DRY_RUN_SAMPLE: usize = 100;
+
+// `sample_size` of a dry run: how many `RETURNING` rows come back (all of them are counted).
+pub fn parse_sample_size(value: Option<&Value>) -> Result<usize, ToolError> {
+    match value {
+        None | Some(Value::Null) => Ok(DEFAULT_DRY_RUN_SAMPLE),
+        Some(value) => value
+            .as_u64()
+            .filter(|n| *n <= MAX_DRY_RUN_SAMPLE as u64)
+            .map(|n| n as usize)
+            .ok_or_else(|| {
+                ToolError::invalid_params(format!(
+                    "sample_size must be an integer between 0 and {}",
+                    MAX_DRY_RUN_SAMPLE
+                ))
+            }),
+    }
+}
+
+// A statement `dry_run` can preview: exactly one INSERT, UPDATE, DELETE or MERGE (or a WITH
+// whose CTEs modify data), never DDL or several statements.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DryRunStatement {
+    pub keyword: String,
+    pub returning: bool,
+}
+
+impl DryRunStatement {
+    pub fn parse(sql: &str) -> Result<Self, ToolError> {
+        let code = code_only(sql).to_ascii_lowercase();
+        let statements = code.split(';').filter(|s| !s.trim().is_empty()).count();
+        if statements != 1 {
+            return Err(ToolError::invalid_params(
+                "dry_run takes exactly one statement; multiple statements cannot be previewed",
+            )
+            .with_hint("Preview each statement in its own call."));
+        }
+        let words: Vec<&str> = code
+            .split(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .filter(|w| !w.is_empty())
+            .collect();
+        let keyword = words.first().copied().unwrap_or("").to_string();
+        let modifies = |word: &&str| matches!(*word, "insert" | "update" | "delete" | "merge");
+        match keyword.as_str() {
+            "insert" | "update" | "delete" | "merge" => {}
+            "with" if words.iter().any(modifies) => {}
+            "create" | "alter" | "drop" | "truncate" | "grant" | "revoke" | "comment"
+            | "reindex" | "cluster" | "vacuum" | "refresh" => {
+                return Err(ToolError::invalid_params(format!(
+                    "dry_run cannot preview DDL ({}); only INSERT, UPDATE, DELETE and MERGE run in a rolled-back transaction",
+                    keyword.to_ascii_uppercase()
+                ))
+                .with_hint(
+                    "Review schema changes with sql action=catalog_diff or in a migration on a scratch database.",
+                ))
+            }
+            _ => {
+                return Err(ToolError::invalid_params(format!(
+                    "dry_run previews data-modifying statements; {} does not modify rows",
+                    if keyword.is_empty() {
+                        "an empty statement".to_string()
+                    } else {
+                        keyword.to_ascii_uppercase()
+                    }
+                ))
+                .with_hint("Run it without dry_run."))
+            }
+        }
+        Ok(Self {
+            keyword,
+            returning: words.contains(&"returning"),
+        })
+    }
+
+    // The statement with `RETURNING *` appended when it has no RETURNING of its own and the
+    // form allows one (MERGE only gained RETURNING in Postgres 17, WITH bodies are left alone).
+    pub fn sample_sql(&self, sql: &str) -> String {
+        if self.returning || !matches!(self.keyword.as_str(), "insert" | "update" | "delete") {
+            return sql.to_string();
+        }
+        format!(
+            "{} RETURNING *",
+            sql.trim_end().trim_end_matches(';').trim_end()
+        )
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BoundParams {
+    pub sql: String,
+    pub values: Vec<Value>,
+    // Display name per placeholder (`:email` or `$1`), used in binding errors.
+    pub names: Vec<String>,
+}
+
+// `params` is either an array for `$1..$n` or an object for `:name` placeholders, which are
+// rewritten to positional ones in order of first use. `param_types` keys are param names (or
+// positions for arrays) and become explicit casts on the placeholder.
+pub fn bind_named_params(
+    sql: &str,
+    params: Option<&Value>,
+    param_types: Option<&Value>,
+) -> Result<BoundParams, ToolError> {
+    let mut bound = match params.filter(|v| !v.is_null()) {
+        None => BoundParams {
+            sql: sql.to_string(),
+            ..BoundParams::default()
+        },
+        Some(Value::Array(items)) => BoundParams {
+            sql: sql.to_string(),
+            values: items.clone(),
+            names: (1..=items.len()).map(|idx| format!("${}", idx)).collect(),
+        },
+        Some(Value::Object(map)) => {
+            let mut rewritten = String::with_capacity(sql.len());
+            let mut order: Vec<String> = Vec::new();
+            let mut last = 0usize;
+            for (start, end, placeholder) in scan_placeholders(sql) {
+                let name = match placeholder {
+                    Placeholder::Named(name) => name,
+                    Placeholder::Positional(_) => {
+                        return Err(ToolError::invalid_params(
+                            "sql mixes $n placeholders with named params",
+                        )
+                        .with_hint(
+                            "Use :name placeholders with a params object, or $1..$n with a params array.".to_string(),
+                        ))
+                    }
+                };
+                if !map.contains_key(&name) {
+                    return Err(ToolError::invalid_params(format!(
+                        "named parameter :{} has no value in params",
+                        name
+                    )));
+                }
+                let index = match order.iter().position(|known| *known == name) {
+                    Some(pos) => pos + 1,
+                    None => {
+                        order.push(name);
+                        order.len()
+                    }
+                };
+                rewritten.push_str(&sql[last..start]);
+                rewritten.push_str(&format!("${}", index));
+                last = end;
+            }
+            rewritten.push_str(&sql[last..]);
+            if let Some(unused) = map.keys().find(|key| !order.contains(key)) {
+                return Err(ToolError::invalid_params(format!(
+                    "params.{} is not referenced in sql",
+                    unused
+                ))
+                .with_hint(
+                    "Named placeholders look like :name outside quotes and casts.".to_string(),
+                ));
+            }
+            BoundParams {
+                sql: rewritten,
+                values: order.iter().map(|name| map[name].clone()).collect(),
+                names: order.iter().map(|name| format!(":{}", name)).collect(),
+            }
+        }
+        Some(_) => {
+            return Err(ToolError::invalid_params(
+                "params must be an array ($1..$n) or an object (:name)",
+            ))
+        }
+    };
+
+    let Some(types) = param_types.filter(|v| !v.is_null()) else {
+        return Ok(bound);
+    };
+    let Some(types) = types.as_object() else {
+        return Err(ToolError::invalid_params(
+            "param_types must be an object of param name to type",
+        ));
+    };
+    let mut casts = BTreeMap::new();
+    for (key, ty) in types {
+        let ty = ty.as_str().map(str::trim).filter(|ty| is_type_name(ty));
+        let Some(ty) = ty else {
+            return Err(ToolError::invalid_params(format!(
+                "param_types.{} must be a type name like timestamptz or int8[]",
+                key
+            )));
+        };
+        let label = if key.starts_with('$') {
+            key.clone()
+        } else if key.parse::<usize>().is_ok() {
+            format!("${}", key)
+        } else {
+            format!(":{}", key)
+        };
+        let Some(pos) = bound.names.iter().position(|name| *name == label) else {
+            return Err(ToolError::invalid_params(format!(
+                "param_types.{} does not match any parameter",
+                key
+            )));
+        };
+        casts.insert(pos + 1, ty.to_string());
+    }
+    bound.sql = cast_placeholders(&bound.sql, &casts);
+    Ok(bound)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dry_run_accepts_one_data_modifying_statement() {
+        let update =
+            DryRunStatement::parse("UPDATE t SET note = 'a;b' -- ; DROP\n WHERE id = 1;").unwrap();
+        assert_eq!(update.keyword, "update");
+        assert_eq!(
+            update.sample_sql("UPDATE t SET x = 1 ;"),
+            "UPDATE t SET x = 1 RETURNING *"
+        );
+        let cte = DryRunStatement::parse(
+            "WITH moved AS (DELETE FROM a RETURNING *) INSERT INTO b SELECT * FROM moved",
+        )
+        .unwrap();
+        assert!(cte.returning);
+        assert_eq!(cte.sample_sql("WITH x"), "WITH x");
+
+        let multi = DryRunStatement::parse("DELETE FROM a; DELETE FROM b").unwrap_err();
+        assert!(
+            multi.message.contains("exactly one statement"),
+            "{}",
+            multi.message
+        );
+        let ddl = DryRunStatement::parse("ALTER TABLE t ADD COLUMN x int").unwrap_err();
+        assert!(ddl.message.contains("DDL (ALTER)"), "{}", ddl.message);
+        let read = DryRunStatement::parse("SELECT 'update'").unwrap_err();
+        assert!(
+            read.message.contains("SELECT does not modify"),
+            "{}",
+            read.message
+        );
+    }
+
+    #[test]
+    fn returning_quotes_reserved_words_and_keeps_star() {
+        assert_eq!(build_returning_clause(None).unwrap(), "");
+        assert_eq!(
+            build_returning_clause(Some(&Value::Bool(true))).unwrap(),
+            " RETURNING *"
+        );
+        assert_eq!(
+            build_returning_clause(Some(&serde_json::json!("*"))).unwrap(),
+            " RETURNING *"
+        );
+        assert_eq!(
+            build_returning_clause(Some(&serde_json::json!(["id", "order", "user"]))).unwrap(),
+            " RETURNING \"id\", \"order\", \"user\""
+        );
+        assert_eq!(
+            build_returning_clause(Some(&serde_json::json!("id; DROP TABLE t"))).unwrap(),
+            " RETURNING \"id; DROP TABLE t\""
+        );
+        assert!(build_returning_clause(Some(&serde_json::json!([1]))).is_err());
+        assert!(build_returning_clause(Some(&serde_json::json!({"id": true}))).is_err());
+    }
+
+    #[test]
+    fn expect_clause_covers_every_column_after_existing_params() {
+        let (sql, params, next) = build_expect_clause(
+            Some(&serde_json::json!({"version": 3, "order": "a", "locked_by": null})),
+            4,
+        )
+        .unwrap();
+        assert_eq!(
+            sql,
+            "\"locked_by\" IS NULL AND \"order\" = $4 AND \"version\" = $5"
+        );
+        assert_eq!(params, vec![serde_json::json!("a"), serde_json::json!(3)]);
+        assert_eq!(next, 6);
+        assert!(build_expect_clause(Some(&serde_json::json!({})), 1).is_err());
+        assert!(build_expect_clause(Some(&serde_json::json!([1])), 1).is_err());
+        assert_eq!(build_expect_clause(None, 2).unwrap().2, 2);
+    }
+
+    #[test]
+    fn named_params_skip_literals_identifiers_comments_and_casts() {
+        let bound = bind_named_params(
+            "select ':skip', E'it\\'s :esc', $q$ :dollar $q$, \"col:name\", created_at::date -- :comment\n\
+             from t /* :block /* nested */ */ where email = :email and created_at > :since and owner = :email",
+            Some(&serde_json::json!({"email": "a@b.c", "since": "2024-01-01T00:00:00Z"})),
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            bound.sql,
+            "select ':skip', E'it\\'s :esc', $q$ :dollar $q$, \"col:name\", created_at::date -- :comment\n\
+             from t /* :block /* nested */ */ where email = $1 and created_at > $2 and owner = $1"
+        );
+        assert_eq!(
+            bound.values,
+            vec![
+                serde_json::json!("a@b.c"),
+                serde_json::json!("2024-01-01T00:00:00Z")
+            ]
+        );
+        assert_eq!(bound.names, vec![":email", ":since"]);
+    }
+
+    #[test]
+    fn array_slices_are_not_named_params() {
+        let bound = bind_named_params(
+            "select arr[lo:hi], m[1][a:b], arr[ :n] from t where id = :id",
+            Some(&serde_json::json!({"id": 7, "n": 2})),
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            bound.sql,
+            "select arr[lo:hi], m[1][a:b], arr[ $1] from t where id = $2"
+        );
+        assert_eq!(bound.names, vec![":n", ":id"]);
+    }
+
+    #[test]
+    fn named_params_report_the_offending_name() {
+        let err = bind_named_params("select :a, :b", Some(&serde_json::json!({"a": 1})), None)
+            .unwrap_err();
+        assert!(err.message.contains(":b"), "{}", err.message);
+        let err = bind_named_params(
+            "select :a",
+            Some(&serde_json::json!({"a": 1, "extra": 2})),
+            None,
+        )
+        .unwrap_err();
+        assert!(err.message.contains("params.extra"), "{}", err.message);
+        assert!(
+            bind_named_params("select $1, :a", Some(&serde_json::json!({"a": 1})), None).is_err()
+        );
+        assert!(bind_named_params("select 1", Some(&serde_json::json!("x")), None).is_err());
+    }
+
+    #[test]
+    fn param_types_cast_named_and_positional_placeholders() {
+        let bound = bind_named_params(
+            "select :ids, :at, :at",
+            Some(&serde_json::json!({"ids": [1, 2], "at": "2024-01-01"})),
+            Some(&serde_json::json!({"ids": "int8[]", "at": "timestamptz"})),
+        )
+        .unwrap();
+        assert_eq!(
+            bound.sql,
+            "select $1::int8[], $2::timestamptz, $2::timestamptz"
+        );
+
+        let bound = bind_named_params(
+            "select $1, '$1', $2",
+            Some(&serde_json::json!(["1.5", 2])),
+            Some(&serde_json::json!({"1": "numeric", "$2": "text"})),
+        )
+        .unwrap();
+        assert_eq!(bound.sql, "select $1::numeric, '$1', $2::text");
+        assert_eq!(bound.names, vec!["$1", "$2"]);
+
+        let bound = bind_named_params(
+            "select $1, $2",
+            Some(&serde_json::json!(["1.5", []])),
+            Some(
+                &serde_json::json!({"1": "Double Precision", "2": "public.timestamp with time zone[][]"}),
+            ),
+        );
+        assert!(bound.is_err(), "only bare multi-word types are allowed");
+        let bound = bind_named_params(
+            "select $1, $2",
+            Some(&serde_json::json!(["1.5", []])),
+            Some(&serde_json::json!({"1": "Double Precision", "2": "timestamp with time zone[]"})),
+        )
+        .unwrap();
+        assert_eq!(
+            bound.sql,
+            "select $1::Double Precision, $2::timestamp with time zone[]"
+        );
+
+        for types in [
+            serde_json::json!({"9": "int"}),
+            serde_json::json!({"1": "int; drop table t"}),
+            serde_json::json!({"1": "text into t"}),
+            serde_json::json!({"1": "double  precision"}),
+            serde_json::json!({"1": "public.\"t\""}),
+            serde_json::json!({"1": "a.b.c"}),
+            serde_json::json!({"1": "int[]x"}),
+            serde_json::json!(["int"]),
+        ] {
+            assert!(
+                bind_named_params("select $1", Some(&serde_json::json!([1])), Some(&types))
+                    .is_err()
+            );
+        }
+    }
+
+    #[test]
+    fn sql_without_params_is_left_untouched() {
+        let sql = "select now()::text, $tag$:x$tag$";
+        assert_eq!(bind_named_params(sql, None, None).unwrap().sql, sql);
+        assert_eq!(
+            cast_placeholders(
+                "select $1, $10, x$1",
+                &BTreeMap::from([(1, "text".to_string())])
+            ),
+            "select $1::text, $10, x$1"
+        );
+    }
+}
@@ -0,0 +1,188 @@
+use crate::errors::ToolError;
+use crate::utils::shell::{ensure_shell_arg, shell_quote};
+use serde_json::Value;
+
+// Values shorter than this are too likely to occur by chance to be redacted from output.
+const MIN_SECRET_LEN: usize = 6;
+const MAX_SECRET_VALUES: usize = 32;
+
+// How `env` reaches a remote command. Most sshd only accept the names in their AcceptEnv
+// list, so `auto` asks for each variable with setenv and inlines the ones refused.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EnvMode {
+    #[default]
+    Auto,
+    Setenv,
+    Inline,
+}
+
+impl EnvMode {
+    pub fn parse(value: Option<&Value>) -> Result<Self, ToolError> {
+        match value {
+            None | Some(Value::Null) => Ok(Self::Auto),
+            Some(Value::String(mode)) => match mode.trim() {
+                "auto" => Ok(Self::Auto),
+                "setenv" => Ok(Self::Setenv),
+                "inline" => Ok(Self::Inline),
+                other => Err(ToolError::invalid_params(format!(
+                    "env_mode must be one of: auto, setenv, inline (got {})",
+                    other
+                ))),
+            },
+            Some(other) => Err(ToolError::invalid_params(format!(
+                "env_mode must be one of: auto, setenv, inline (got {})",
+                other
+            ))),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::Setenv => "setenv",
+            Self::Inline => "inline",
+        }
+    }
+}
+
+// The `env` object of an exec as name/value pairs sorted by name; non-string values are
+// passed as their JSON text.
+#[derive(Clone, Debug, Default)]
+pub struct ExecEnv {
+    pub vars: Vec<(String, String)>,
+    pub mode: EnvMode,
+}
+
+impl ExecEnv {
+    pub fn parse(args: &Value) -> Result<Self, ToolError> {
+        let mode = EnvMode::parse(args.get("env_mode"))?;
+        let vars = match args.get("env") {
+            None | Some(Value::Null) => Vec::new(),
+            Some(Value::Object(map)) => map
+                .iter()
+                .map(|(key, value)| {
+                    ensure_env_name(key)?;
+                    let value = value
+                        .as_str()
+                        .map(str::to_string)
+                        .unwrap_or_else(|| value.to_string());
+                    ensure_shell_arg(&value, &format!("env.{}", key))?;
+                    Ok((key.clone(), value))
+                })
+                .collect::<Result<Vec<_>, ToolError>>()?,
+            Some(_) => {
+                return Err(ToolError::invalid_params(
+                    "env must be an object of NAME: value pairs",
+                ))
+            }
+        };
+        Ok(Self { vars, mode })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vars.is_empty()
+    }
+
+    // Values long enough to redact from stdout, stderr and their artifacts.
+    pub fn secret_values(&self) -> Option<Vec<String>> {
+        let out: Vec<String> = self
+            .vars
+            .iter()
+            .map(|(_, value)| value.trim())
+            .filter(|value| value.len() >= MIN_SECRET_LEN)
+            .take(MAX_SECRET_VALUES)
+            .map(str::to_string)
+            .collect();
+        (!out.is_empty()).then_some(out)
+    }
+
+    // `command` run through `env NAME='value' ... sh -c '<command>'` for the `inlined` names.
+    pub fn inline_command(&self, command: &str, inlined: &[String]) -> String {
+        if inlined.is_empty() {
+            return command.to_string();
+        }
+        let assignments = self
+            .vars
+            .iter()
+            .filter(|(key, _)| inlined.contains(key))
+            .map(|(key, value)| format!("{}={}", key, shell_quote(value)))
+            .collect::<Vec<_>>()
+            .join(" ");
+        format!("env {} sh -c {}", assignments, shell_quote(command))
+    }
+
+    // `{mode, effective, inlined}` for the response; `effective` is `mixed` when auto had to
+    // inline only some of the variables.
+    pub fn report(&self, inlined: &[String]) -> Value {
+        let effective = if inlined.is_empty() {
+            "setenv"
+        } else if inlined.len() == self.vars.len() {
+            "inline"
+        } else {
+            "mixed"
+        };
+        serde_json::json!({
+            "mode": self.mode.as_str(),
+            "effective": effective,
+            "inlined": inlined,
+        })
+    }
+}
+
+// Portable variable names only, so an inlined assignment can never be read as an option or
+// split by the shell.
+fn ensure_env_name(name: &str) -> Result<(), ToolError> {
+    let mut chars = name.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(ToolError::invalid_params(format!(
+            "env name {:?} must match [A-Za-z_][A-Za-z0-9_]*",
+            name
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn inlined_variables_wrap_the_command_in_env_and_sh() {
+        let env = ExecEnv::parse(&json!({
+            "env": {"TOKEN": "it's secret", "PORT": 8080, "MODE": "prod"},
+        }))
+        .unwrap();
+        assert_eq!(env.mode, EnvMode::Auto);
+        let inlined = vec!["TOKEN".to_string(), "PORT".to_string()];
+        assert_eq!(
+            env.inline_command("echo \"$TOKEN\" $PORT", &inlined),
+            "env PORT='8080' TOKEN='it'\\''s secret' sh -c 'echo \"$TOKEN\" $PORT'"
+        );
+        assert_eq!(env.inline_command("true", &[]), "true");
+        assert_eq!(env.report(&inlined)["effective"], "mixed");
+        assert_eq!(env.secret_values(), Some(vec!["it's secret".to_string()]));
+
+        for (args, message) in [
+            (
+                json!({"env": {"-u": "x"}}),
+                "env name \"-u\" must match [A-Za-z_][A-Za-z0-9_]*",
+            ),
+            (
+                json!({"env": ["A=1"]}),
+                "env must be an object of NAME: value pairs",
+            ),
+            (
+                json!({"env_mode": "sendenv"}),
+                "env_mode must be one of: auto, setenv, inline (got sendenv)",
+            ),
+        ] {
+            assert_eq!(ExecEnv::parse(&args).unwrap_err().message, message);
+        }
+    }
+}
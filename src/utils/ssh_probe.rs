@@ -0,0 +1,246 @@
+use base64::Engine;
+use serde_json::Value;
+use ssh2::{HostKeyType, Session};
+use std::io::Read;
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_LATENCY_SAMPLES: usize = 3;
+pub const MAX_LATENCY_SAMPLES: usize = 20;
+// RFC 4253 lets servers send other lines before the identification string; stop looking after this.
+const MAX_BANNER_BYTES: usize = 8 * 1024;
+
+#[derive(Clone, Debug)]
+pub struct ProbeTarget {
+    pub host: String,
+    pub port: u16,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct ProbeOptions {
+    pub timeout: Duration,
+    pub latency_samples: usize,
+}
+
+pub(crate) fn fingerprint_host_key_sha256(session: &Session) -> Option<String> {
+    let hash = session.host_key_hash(ssh2::HashType::Sha256)?;
+    let encoded = base64::engine::general_purpose::STANDARD_NO_PAD.encode(hash);
+    Some(format!("SHA256:{}", encoded))
+}
+
+fn host_key_type_name(kind: HostKeyType) -> &'static str {
+    match kind {
+        HostKeyType::Rsa => "ssh-rsa",
+        HostKeyType::Dss => "ssh-dss",
+        HostKeyType::Ecdsa256 => "ecdsa-sha2-nistp256",
+        HostKeyType::Ecdsa384 => "ecdsa-sha2-nistp384",
+        HostKeyType::Ecdsa521 => "ecdsa-sha2-nistp521",
+        HostKeyType::Ed25519 => "ssh-ed25519",
+        _ => "unknown",
+    }
+}
+
+fn elapsed_ms(started: Instant) -> f64 {
+    (started.elapsed().as_secs_f64() * 1000.0 * 10.0).round() / 10.0
+}
+
+fn connect(addr: &SocketAddr, timeout: Duration) -> std::io::Result<(TcpStream, f64)> {
+    let started = Instant::now();
+    let stream = TcpStream::connect_timeout(addr, timeout)?;
+    let ms = elapsed_ms(started);
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+    Ok((stream, ms))
+}
+
+// Reads lines until the `SSH-protoversion-softwareversion comments` identification string.
+fn read_banner(stream: &mut TcpStream) -> Result<String, String> {
+    let mut line = Vec::new();
+    let mut seen = 0usize;
+    let mut byte = [0u8; 1];
+    loop {
+        match stream.read(&mut byte) {
+            Ok(0) => return Err("connection closed before the SSH banner".to_string()),
+            Ok(_) => {}
+            Err(err) => return Err(format!("no SSH banner: {}", err)),
+        }
+        seen += 1;
+        if byte[0] == b'\n' {
+            let text = String::from_utf8_lossy(&line).trim_end().to_string();
+            if text.starts_with("SSH-") {
+                return Ok(text);
+            }
+            line.clear();
+        } else {
+            line.push(byte[0]);
+        }
+        if seen >= MAX_BANNER_BYTES {
+            return Err(format!(
+                "no SSH banner in the first {} bytes",
+                MAX_BANNER_BYTES
+            ));
+        }
+    }
+}
+
+fn describe_banner(banner: &str) -> Value {
+    let mut parts = banner.splitn(3, '-');
+    let _ = parts.next();
+    let protocol = parts.next().unwrap_or("");
+    let rest = parts.next().unwrap_or("");
+    let (software, comments) = match rest.split_once(' ') {
+        Some((software, comments)) => (software, Some(comments)),
+        None => (rest, None),
+    };
+    serde_json::json!({
+        "ok": true,
+        "banner": banner,
+        "protocol_version": protocol,
+        "software": software,
+        "comments": comments,
+    })
+}
+
+// Key exchange only: the session is dropped right after the server proved its host key.
+fn observe_host_key(addr: &SocketAddr, timeout: Duration) -> Result<Value, String> {
+    let (stream, _) = connect(addr, timeout).map_err(|err| err.to_string())?;
+    let mut session = Session::new().map_err(|err| err.to_string())?;
+    session.set_timeout(timeout.as_millis().min(u32::MAX as u128) as u32);
+    session.set_tcp_stream(stream);
+    session
+        .handshake()
+        .map_err(|err| format!("handshake failed: {}", err))?;
+    let fingerprint = fingerprint_host_key_sha256(&session)
+        .ok_or_else(|| "server sent no host key".to_string())?;
+    let key_type = session
+        .host_key()
+        .map(|(_, kind)| host_key_type_name(kind))
+        .unwrap_or("unknown");
+    let _ = session.disconnect(None, "probe", None);
+    Ok(serde_json::json!({
+        "ok": true,
+        "type": key_type,
+        "fingerprint_sha256": fingerprint,
+    }))
+}
+
+fn latency(addr: &SocketAddr, options: &ProbeOptions) -> Value {
+    let mut samples = Vec::new();
+    let mut failures = 0usize;
+    for _ in 0..options.latency_samples {
+        match connect(addr, options.timeout) {
+            Ok((_, ms)) => samples.push(ms),
+            Err(_) => failures += 1,
+        }
+    }
+    if samples.is_empty() {
+        return serde_json::json!({"samples": 0, "failures": failures});
+    }
+    let min = samples.iter().cloned().fold(f64::INFINITY, f64::min);
+    let max = samples.iter().cloned().fold(0.0, f64::max);
+    let avg = samples.iter().sum::<f64>() / samples.len() as f64;
+    serde_json::json!({
+        "samples": samples.len(),
+        "failures": failures,
+        "min_ms": min,
+        "avg_ms": (avg * 10.0).round() / 10.0,
+        "max_ms": max,
+    })
+}
+
+fn failed(stage: &str, error: String) -> Value {
+    serde_json::json!({"ok": false, "stage": stage, "error": error})
+}
+
+// DNS, TCP connect, banner, host key and connect latency, in that order; a failed stage ends
+// the probe and is reported under `failed`. Never authenticates.
+pub fn probe(target: &ProbeTarget, options: &ProbeOptions) -> Value {
+    let mut out = serde_json::json!({
+        "success": false,
+        "host": target.host,
+        "port": target.port,
+    });
+
+    let started = Instant::now();
+    let addrs: Vec<SocketAddr> = match (target.host.as_str(), target.port).to_socket_addrs() {
+        Ok(addrs) => addrs.collect(),
+        Err(err) => {
+            out["failed"] = failed("dns", format!("{} did not resolve: {}", target.host, err));
+            return out;
+        }
+    };
+    out["dns"] = serde_json::json!({
+        "ok": !addrs.is_empty(),
+        "addresses": addrs.iter().map(|addr| addr.ip().to_string()).collect::<Vec<_>>(),
+        "ms": elapsed_ms(started),
+    });
+    if addrs.is_empty() {
+        out["failed"] = failed("dns", format!("{} has no addresses", target.host));
+        return out;
+    }
+
+    let mut attempts = Vec::new();
+    let mut connected = None;
+    for addr in &addrs {
+        match connect(addr, options.timeout) {
+            Ok((stream, ms)) => {
+                attempts.push(serde_json::json!({"address": addr.to_string(), "ok": true, "ms": ms}));
+                connected = Some((*addr, stream));
+                break;
+            }
+            Err(err) => attempts.push(
+                serde_json::json!({"address": addr.to_string(), "ok": false, "error": err.to_string()}),
+            ),
+        }
+    }
+    let Some((addr, mut stream)) = connected else {
+        out["tcp"] = serde_json::json!({"ok": false, "attempts": attempts});
+        out["failed"] = failed("tcp", format!("port {} is not reachable", target.port));
+        return out;
+    };
+    out["tcp"] = serde_json::json!({
+        "ok": true,
+        "address": addr.to_string(),
+        "connect_ms": attempts.last().and_then(|a| a.get("ms")).cloned(),
+        "attempts": attempts,
+    });
+
+    match read_banner(&mut stream) {
+        Ok(banner) => out["banner"] = describe_banner(&banner),
+        Err(error) => {
+            out["failed"] = failed("banner", error);
+            return out;
+        }
+    }
+    drop(stream);
+
+    match observe_host_key(&addr, options.timeout) {
+        Ok(host_key) => out["host_key"] = host_key,
+        Err(error) => {
+            out["failed"] = failed("host_key", error);
+            return out;
+        }
+    }
+
+    if options.latency_samples > 0 {
+        out["latency"] = latency(&addr, options);
+    }
+    out["success"] = Value::Bool(true);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn banners_split_into_protocol_software_and_comments() {
+        let parsed = describe_banner("SSH-2.0-OpenSSH_9.6p1 Ubuntu-3ubuntu13");
+        assert_eq!(parsed["protocol_version"], "2.0");
+        assert_eq!(parsed["software"], "OpenSSH_9.6p1");
+        assert_eq!(parsed["comments"], "Ubuntu-3ubuntu13");
+        let bare = describe_banner("SSH-2.0-dropbear");
+        assert_eq!(bare["software"], "dropbear");
+        assert!(bare["comments"].is_null());
+    }
+}
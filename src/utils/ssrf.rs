@@ -0,0 +1,450 @@
+use crate::errors::{ToolError, ToolErrorKind};
+use crate::utils::feature_flags::{HTTP_ALLOW_HOSTS, HTTP_DENY_PRIVATE};
+use crate::utils::http_proxy::HttpProxyConfig;
+use hyper::client::connect::dns::Name;
+use reqwest::dns::{Addrs, Resolve, Resolving};
+use reqwest::ClientBuilder;
+use serde_json::Value;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs};
+use std::sync::Arc;
+use url::{Host, Url};
+
+const MAX_REDIRECTS: usize = 10;
+
+// Opt-in guard against api/pipeline targets in internal address ranges. On direct connections
+// hostnames are checked in the client's DNS resolver, so the addresses that were checked are the
+// ones connected to (no rebinding window between check and connect); IP literals skip DNS and
+// are checked on the URL before the request and on every redirect hop. Through a proxy the proxy
+// resolves the target, so each proxied target is resolved and checked when the proxy is chosen
+// (see HttpProxyConfig::apply); the proxy may still resolve it differently.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SsrfPolicy {
+    allow_hosts: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SsrfDenied {
+    pub host: String,
+    pub ip: IpAddr,
+    pub rule: &'static str,
+}
+
+impl std::fmt::Display for SsrfDenied {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} resolves to {}, which is in a denied {} range",
+            self.host, self.ip, self.rule
+        )
+    }
+}
+
+impl std::error::Error for SsrfDenied {}
+
+impl SsrfDenied {
+    pub fn to_tool_error(&self) -> ToolError {
+        ToolError::new(
+            ToolErrorKind::Denied,
+            "HTTP_TARGET_DENIED",
+            format!("HTTP target denied: {}", self),
+        )
+        .with_hint(
+            "Add the host to INFRA_HTTP_ALLOW_HOSTS or the profile's ssrf.allow_hosts if it is an intended internal target.",
+        )
+        .with_details(serde_json::json!({
+            "host": self.host,
+            "ip": self.ip.to_string(),
+            "rule": self.rule,
+        }))
+    }
+}
+
+impl SsrfPolicy {
+    // INFRA_HTTP_DENY_PRIVATE turns the guard on; a profile's `ssrf.deny_private` overrides it
+    // either way. Allow entries come from INFRA_HTTP_ALLOW_HOSTS plus `ssrf.allow_hosts`.
+    pub fn resolve(profile: Option<&Value>) -> Result<Option<Self>, ToolError> {
+        let profile = profile.filter(|v| !v.is_null());
+        if let Some(value) = profile {
+            if !value.is_object() {
+                return Err(ToolError::invalid_params(
+                    "ssrf must be an object { deny_private, allow_hosts }",
+                ));
+            }
+        }
+        let deny_private = match profile.and_then(|v| v.get("deny_private")) {
+            None | Some(Value::Null) => HTTP_DENY_PRIVATE.enabled(),
+            Some(Value::Bool(flag)) => *flag,
+            Some(_) => {
+                return Err(ToolError::invalid_params(
+                    "ssrf.deny_private must be a boolean",
+                ))
+            }
+        };
+        let mut allow_hosts: Vec<String> = HTTP_ALLOW_HOSTS
+            .list()
+            .iter()
+            .map(|entry| normalize_entry(entry))
+            .filter(|entry| !entry.is_empty())
+            .collect();
+        match profile.and_then(|v| v.get("allow_hosts")) {
+            None | Some(Value::Null) => {}
+            Some(Value::Array(items)) => {
+                for item in items {
+                    let entry = item.as_str().map(normalize_entry).unwrap_or_default();
+                    if entry.is_empty() {
+                        return Err(ToolError::invalid_params(
+                            "ssrf.allow_hosts must be an array of host names, IPs or CIDRs",
+                        ));
+                    }
+                    allow_hosts.push(entry);
+                }
+            }
+            Some(_) => {
+                return Err(ToolError::invalid_params(
+                    "ssrf.allow_hosts must be an array of host names, IPs or CIDRs",
+                ))
+            }
+        }
+        for entry in &allow_hosts {
+            if entry.contains('/') && parse_cidr(entry).is_none() {
+                return Err(ToolError::invalid_params(format!(
+                    "Invalid CIDR in HTTP allow list: {}",
+                    entry
+                )));
+            }
+        }
+        allow_hosts.sort();
+        allow_hosts.dedup();
+        Ok(deny_private.then_some(Self { allow_hosts }))
+    }
+
+    pub fn fingerprint(&self) -> String {
+        self.allow_hosts.join(",")
+    }
+
+    // Exact names, `*.suffix` / `.suffix` for subdomains, and IP literals match the URL host.
+    fn host_allowed(&self, host: &str) -> bool {
+        let host = normalize_entry(host);
+        self.allow_hosts.iter().any(|entry| {
+            if let Some(suffix) = entry.strip_prefix("*.").or_else(|| entry.strip_prefix('.')) {
+                host.len() > suffix.len()
+                    && host.ends_with(suffix)
+                    && host.as_bytes()[host.len() - suffix.len() - 1] == b'.'
+            } else {
+                *entry == host
+            }
+        })
+    }
+
+    // IP and CIDR entries also match the resolved address.
+    fn ip_allowed(&self, ip: IpAddr) -> bool {
+        self.allow_hosts.iter().any(|entry| {
+            if let Some((net, bits)) = parse_cidr(entry) {
+                cidr_contains(net, bits, ip)
+            } else {
+                entry.parse::<IpAddr>().map(|v| v == ip).unwrap_or(false)
+            }
+        })
+    }
+
+    pub fn check_ip(&self, host: &str, ip: IpAddr) -> Result<(), SsrfDenied> {
+        if self.host_allowed(host) || self.ip_allowed(ip) {
+            return Ok(());
+        }
+        match denied_rule(ip) {
+            Some(rule) => Err(SsrfDenied {
+                host: host.to_string(),
+                ip,
+                rule,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    // Only IP-literal hosts can be judged without DNS; names are checked by the resolver.
+    pub fn check_url_literal(&self, url: &Url) -> Result<(), SsrfDenied> {
+        let ip = match url.host() {
+            Some(Host::Ipv4(ip)) => IpAddr::V4(ip),
+            Some(Host::Ipv6(ip)) => IpAddr::V6(ip),
+            _ => return Ok(()),
+        };
+        self.check_ip(url.host_str().unwrap_or_default(), ip)
+    }
+
+    pub fn check_url(&self, raw: &str) -> Result<(), ToolError> {
+        match Url::parse(raw) {
+            Ok(url) => self
+                .check_url_literal(&url)
+                .map_err(|denied| denied.to_tool_error()),
+            Err(_) => Ok(()),
+        }
+    }
+
+    // Decides, per request and redirect hop, whether a target may go through the proxy. The
+    // lookup blocks, as proxy selection is synchronous; names that do not resolve here fail
+    // closed.
+    pub fn proxied_target_allowed(&self, target: &Url) -> bool {
+        let Some(host) = target.host_str() else {
+            return false;
+        };
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        let port = target.port_or_known_default().unwrap_or(0);
+        let addrs: Vec<SocketAddr> = match (host, port).to_socket_addrs() {
+            Ok(addrs) => addrs.collect(),
+            Err(_) => return false,
+        };
+        !addrs.is_empty()
+            && addrs
+                .iter()
+                .all(|addr| self.check_ip(host, addr.ip()).is_ok())
+    }
+
+    // `proxy` is the configured proxy, if any: its host is exempt from the guard. Without one,
+    // HTTP(S)_PROXY from the environment is ignored, since the guard never sees targets sent
+    // through it.
+    pub fn apply(
+        &self,
+        builder: ClientBuilder,
+        follow_redirects: bool,
+        proxy: Option<&HttpProxyConfig>,
+    ) -> ClientBuilder {
+        let mut builder = builder.dns_resolver(Arc::new(GuardedResolver {
+            policy: self.clone(),
+            proxy_host: proxy.and_then(|proxy| proxy.host()),
+        }));
+        if proxy.is_none() {
+            builder = builder.no_proxy();
+        }
+        if !follow_redirects {
+            return builder;
+        }
+        let policy = self.clone();
+        builder.redirect(reqwest::redirect::Policy::custom(move |attempt| {
+            if attempt.previous().len() >= MAX_REDIRECTS {
+                return attempt.error("too many redirects");
+            }
+            match policy.check_url_literal(attempt.url()) {
+                Ok(()) => attempt.follow(),
+                Err(denied) => attempt.error(denied),
+            }
+        }))
+    }
+}
+
+struct GuardedResolver {
+    policy: SsrfPolicy,
+    proxy_host: Option<String>,
+}
+
+impl Resolve for GuardedResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let policy = self.policy.clone();
+        let host = name.as_str().to_string();
+        let exempt = self
+            .proxy_host
+            .as_ref()
+            .is_some_and(|proxy| *proxy == normalize_entry(&host));
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> =
+                tokio::net::lookup_host((host.as_str(), 0)).await?.collect();
+            // Any denied address fails the name: connecting to "the public one" would still let
+            // a rebinding server pick which address wins.
+            for addr in addrs.iter().filter(|_| !exempt) {
+                policy.check_ip(&host, addr.ip())?;
+            }
+            let addrs: Addrs = Box::new(addrs.into_iter());
+            Ok(addrs)
+        })
+    }
+}
+
+// Finds a policy denial anywhere in a reqwest error's source chain (resolver or redirect).
+pub fn denial_from_error(err: &reqwest::Error) -> Option<ToolError> {
+    let mut source: Option<&(dyn std::error::Error + 'static)> = std::error::Error::source(err);
+    while let Some(current) = source {
+        if let Some(denied) = current.downcast_ref::<SsrfDenied>() {
+            return Some(denied.to_tool_error());
+        }
+        source = current.source();
+    }
+    None
+}
+
+pub(crate) fn normalize_entry(raw: &str) -> String {
+    raw.trim()
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .trim_end_matches('.')
+        .to_lowercase()
+}
+
+pub(crate) fn parse_cidr(entry: &str) -> Option<(IpAddr, u8)> {
+    let (net, bits) = entry.split_once('/')?;
+    let net = net.parse::<IpAddr>().ok()?;
+    let bits = bits.parse::<u8>().ok()?;
+    let max = if net.is_ipv4() { 32 } else { 128 };
+    (bits <= max).then_some((net, bits))
+}
+
+pub(crate) fn cidr_contains(net: IpAddr, bits: u8, ip: IpAddr) -> bool {
+    match (net, canonical(ip)) {
+        (IpAddr::V4(net), IpAddr::V4(ip)) => {
+            let mask = u32::MAX.checked_shl(32 - bits as u32).unwrap_or(0);
+            u32::from(net) & mask == u32::from(ip) & mask
+        }
+        (IpAddr::V6(net), IpAddr::V6(ip)) => {
+            let mask = u128::MAX.checked_shl(128 - bits as u32).unwrap_or(0);
+            u128::from(net) & mask == u128::from(ip) & mask
+        }
+        _ => false,
+    }
+}
+
+// IPv4-mapped IPv6 (`::ffff:10.0.0.1`) is judged as the IPv4 address it reaches.
+fn canonical(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(v6) => v6
+            .to_ipv4_mapped()
+            .map(IpAddr::V4)
+            .unwrap_or(IpAddr::V6(v6)),
+        v4 => v4,
+    }
+}
+
+fn denied_rule(ip: IpAddr) -> Option<&'static str> {
+    match canonical(ip) {
+        IpAddr::V4(ip) => denied_rule_v4(ip),
+        IpAddr::V6(ip) => denied_rule_v6(ip),
+    }
+}
+
+fn denied_rule_v4(ip: Ipv4Addr) -> Option<&'static str> {
+    let [a, b, _, _] = ip.octets();
+    if ip == Ipv4Addr::new(169, 254, 169, 254) || ip == Ipv4Addr::new(100, 100, 100, 200) {
+        Some("metadata")
+    } else if ip.is_loopback() {
+        Some("loopback")
+    } else if ip.is_private() {
+        Some("private")
+    } else if ip.is_link_local() {
+        Some("link_local")
+    } else if a == 100 && (64..128).contains(&b) {
+        Some("shared")
+    } else if ip.is_unspecified() || ip.is_broadcast() || a == 0 {
+        Some("unspecified")
+    } else {
+        None
+    }
+}
+
+fn denied_rule_v6(ip: Ipv6Addr) -> Option<&'static str> {
+    let first = ip.segments()[0];
+    if ip == Ipv6Addr::new(0xfd00, 0x0ec2, 0, 0, 0, 0, 0, 0x0254) {
+        Some("metadata")
+    } else if ip.is_loopback() {
+        Some("loopback")
+    } else if ip.is_unspecified() {
+        Some("unspecified")
+    } else if first & 0xfe00 == 0xfc00 {
+        Some("private")
+    } else if first & 0xffc0 == 0xfe80 {
+        Some("link_local")
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(allow: &[&str]) -> SsrfPolicy {
+        SsrfPolicy {
+            allow_hosts: allow.iter().map(|s| normalize_entry(s)).collect(),
+        }
+    }
+
+    fn rule(raw: &str) -> Option<&'static str> {
+        denied_rule(raw.parse().unwrap())
+    }
+
+    #[test]
+    fn classifies_internal_ranges() {
+        assert_eq!(rule("169.254.169.254"), Some("metadata"));
+        assert_eq!(rule("fd00:ec2::254"), Some("metadata"));
+        assert_eq!(rule("127.8.0.1"), Some("loopback"));
+        assert_eq!(rule("::1"), Some("loopback"));
+        assert_eq!(rule("10.1.2.3"), Some("private"));
+        assert_eq!(rule("172.31.0.1"), Some("private"));
+        assert_eq!(rule("192.168.1.1"), Some("private"));
+        assert_eq!(rule("fd12::1"), Some("private"));
+        assert_eq!(rule("169.254.1.1"), Some("link_local"));
+        assert_eq!(rule("fe80::1"), Some("link_local"));
+        assert_eq!(rule("100.64.0.1"), Some("shared"));
+        assert_eq!(rule("0.0.0.0"), Some("unspecified"));
+        assert_eq!(rule("::ffff:10.0.0.1"), Some("private"));
+        assert_eq!(rule("172.32.0.1"), None);
+        assert_eq!(rule("8.8.8.8"), None);
+        assert_eq!(rule("2606:4700::1111"), None);
+    }
+
+    #[test]
+    fn allow_list_matches_names_ips_and_cidrs() {
+        let policy = policy(&["*.corp.example", "api.internal", "10.0.0.0/8", "[::1]"]);
+        let ip = |raw: &str| raw.parse::<IpAddr>().unwrap();
+        assert!(policy
+            .check_ip("git.corp.example", ip("192.168.0.1"))
+            .is_ok());
+        assert!(policy.check_ip("corp.example", ip("192.168.0.1")).is_err());
+        assert!(policy
+            .check_ip("evilcorp.example", ip("192.168.0.1"))
+            .is_err());
+        assert!(policy.check_ip("API.internal.", ip("192.168.0.1")).is_ok());
+        assert!(policy.check_ip("db", ip("10.9.8.7")).is_ok());
+        assert!(policy.check_ip("db", ip("::1")).is_ok());
+        let denied = policy.check_ip("db", ip("127.0.0.1")).unwrap_err();
+        assert_eq!(denied.rule, "loopback");
+        assert_eq!(
+            denied.to_string(),
+            "db resolves to 127.0.0.1, which is in a denied loopback range"
+        );
+        assert!(policy.check_ip("example.com", ip("93.184.216.34")).is_ok());
+    }
+
+    #[test]
+    fn url_literals_are_checked_without_dns() {
+        let policy = policy(&[]);
+        let url = Url::parse("http://169.254.169.254/latest/meta-data/").unwrap();
+        assert_eq!(policy.check_url_literal(&url).unwrap_err().rule, "metadata");
+        let url = Url::parse("http://[::ffff:7f00:1]:8080/").unwrap();
+        assert_eq!(policy.check_url_literal(&url).unwrap_err().rule, "loopback");
+        let url = Url::parse("http://localhost/").unwrap();
+        assert!(policy.check_url_literal(&url).is_ok());
+        let err = policy.check_url("http://10.0.0.5/").unwrap_err();
+        assert_eq!(err.code, "HTTP_TARGET_DENIED");
+        assert_eq!(err.details.as_ref().unwrap()["rule"], "private");
+    }
+
+    #[test]
+    fn resolve_validates_profile_overrides() {
+        assert!(SsrfPolicy::resolve(Some(&serde_json::json!({"deny_private": "yes"}))).is_err());
+        assert!(SsrfPolicy::resolve(Some(&serde_json::json!({"allow_hosts": "a"}))).is_err());
+        assert!(SsrfPolicy::resolve(Some(&serde_json::json!({
+            "deny_private": true,
+            "allow_hosts": ["10.0.0.0/33"]
+        })))
+        .is_err());
+        let policy = SsrfPolicy::resolve(Some(&serde_json::json!({
+            "deny_private": true,
+            "allow_hosts": ["B.example", "a.example", "b.example"]
+        })))
+        .expect("valid")
+        .expect("enabled");
+        assert!(policy.fingerprint().contains("a.example,b.example"));
+        assert!(
+            SsrfPolicy::resolve(Some(&serde_json::json!({"deny_private": false})))
+                .expect("valid")
+                .is_none()
+        );
+    }
+}
@@ -1,6 +1,5 @@
 use crate::errors::ToolError;
-use crate::utils::artifacts::{resolve_artifact_path, resolve_context_root};
-use crate::utils::user_paths::expand_home_path;
+use crate::utils::artifacts::{resolve_artifact_input, resolve_input_path};
 use base64::Engine;
 use serde_json::Value;
 use std::path::PathBuf;
@@ -17,7 +16,10 @@ pub fn resolve_stdin_source(args: &Value) -> Result<Option<StdinSource>, ToolErr
         if trimmed.is_empty() {
             return Err(ToolError::invalid_params("stdin_file must not be empty"));
         }
-        return Ok(Some(StdinSource::File(expand_home_path(trimmed))));
+        return Ok(Some(StdinSource::File(resolve_input_path(
+            trimmed,
+            "stdin_file",
+        )?)));
     }
 
     if let Some(raw) = args.get("stdin_ref").and_then(|v| v.as_str()) {
@@ -26,22 +28,10 @@ pub fn resolve_stdin_source(args: &Value) -> Result<Option<StdinSource>, ToolErr
             return Err(ToolError::invalid_params("stdin_ref must not be empty"));
         }
         let rel = trimmed.trim_start_matches("artifact://");
-        if rel.trim().is_empty() {
-            return Err(ToolError::invalid_params("stdin_ref must include a path"));
-        }
-        let context_root = resolve_context_root().ok_or_else(|| {
-            ToolError::denied("stdin_ref requires context repo root").with_hint(
-                "Set INFRA_CONTEXT_REPO_ROOT to the repo root that owns artifacts.".to_string(),
-            )
-        })?;
-        let path = resolve_artifact_path(&context_root, rel)?;
-        if !path.exists() {
-            return Err(ToolError::not_found(format!(
-                "stdin_ref does not exist: {}",
-                rel
-            )));
-        }
-        return Ok(Some(StdinSource::File(path)));
+        return Ok(Some(StdinSource::File(resolve_artifact_input(
+            rel,
+            "stdin_ref",
+        )?)));
     }
 
     if let Some(raw) = args.get("stdin_base64").and_then(|v| v.as_str()) {
@@ -101,3 +101,39 @@ pub fn resolve_templates(
         _ => Ok(value.clone()),
     }
 }
+
+fn collect_template_variables(value: &Value, out: &mut Vec<String>) {
+    match value {
+        Value::Array(items) => {
+            for item in items {
+                collect_template_variables(item, out);
+            }
+        }
+        Value::Object(map) => {
+            for entry in map.values() {
+                collect_template_variables(entry, out);
+            }
+        }
+        Value::String(text) => {
+            let mut rest = text.as_str();
+            while let Some(start) = rest.find("{{") {
+                let tail = &rest[start + 2..];
+                let Some(end) = tail.find("}}") else {
+                    break;
+                };
+                let expression = tail[..end].trim();
+                if !expression.is_empty() && !out.iter().any(|item| item == expression) {
+                    out.push(expression.to_string());
+                }
+                rest = &tail[end + 2..];
+            }
+        }
+        _ => {}
+    }
+}
+
+pub fn template_variables(value: &Value) -> Vec<String> {
+    let mut out = Vec::new();
+    collect_template_variables(value, &mut out);
+    out
+}
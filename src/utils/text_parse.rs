@@ -0,0 +1,296 @@
+use crate::errors::ToolError;
+use serde_json::Value;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseSpec {
+    Json,
+    Lines,
+    Kv,
+    Csv { headers: bool, delimiter: char },
+}
+
+impl ParseSpec {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ParseSpec::Json => "json",
+            ParseSpec::Lines => "lines",
+            ParseSpec::Kv => "kv",
+            ParseSpec::Csv { .. } => "csv",
+        }
+    }
+}
+
+// Accepts `"json" | "lines" | "kv" | "csv"` or `{ csv: { headers, delimiter } }`.
+pub fn parse_spec(value: Option<&Value>) -> Result<Option<ParseSpec>, ToolError> {
+    let Some(value) = value.filter(|v| !v.is_null()) else {
+        return Ok(None);
+    };
+    let invalid = || {
+        ToolError::invalid_params("parse must be one of: json, lines, kv, csv, or { csv: {...} }")
+            .with_hint("Example: parse: { csv: { headers: true, delimiter: \",\" } }".to_string())
+    };
+    match value {
+        Value::String(name) => match name.trim().to_lowercase().as_str() {
+            "json" => Ok(Some(ParseSpec::Json)),
+            "lines" => Ok(Some(ParseSpec::Lines)),
+            "kv" => Ok(Some(ParseSpec::Kv)),
+            "csv" => Ok(Some(ParseSpec::Csv {
+                headers: true,
+                delimiter: ',',
+            })),
+            _ => Err(invalid()),
+        },
+        Value::Object(map) => {
+            let Some(csv) = map.get("csv") else {
+                return Err(invalid());
+            };
+            if map.len() != 1 || !(csv.is_object() || csv.as_bool() == Some(true)) {
+                return Err(invalid());
+            }
+            let headers = csv.get("headers").and_then(|v| v.as_bool()).unwrap_or(true);
+            let delimiter = match csv.get("delimiter").and_then(|v| v.as_str()) {
+                None => ',',
+                Some(raw) => {
+                    let mut chars = raw.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(ch), None) if ch != '"' && ch != '\n' => ch,
+                        _ => {
+                            return Err(ToolError::invalid_params(
+                                "parse.csv.delimiter must be a single character",
+                            ))
+                        }
+                    }
+                }
+            };
+            Ok(Some(ParseSpec::Csv { headers, delimiter }))
+        }
+        _ => Err(invalid()),
+    }
+}
+
+pub fn split_csv_line(line: &str, delimiter: char) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+
+    let chars: Vec<char> = line.chars().collect();
+    let mut idx = 0usize;
+    while idx < chars.len() {
+        let ch = chars[idx];
+        if ch == '"' {
+            if in_quotes && idx + 1 < chars.len() && chars[idx + 1] == '"' {
+                current.push('"');
+                idx += 2;
+                continue;
+            }
+            in_quotes = !in_quotes;
+            idx += 1;
+            continue;
+        }
+        if ch == delimiter && !in_quotes {
+            out.push(current.clone());
+            current.clear();
+            idx += 1;
+            continue;
+        }
+        current.push(ch);
+        idx += 1;
+    }
+    out.push(current);
+    out
+}
+
+fn non_empty_lines(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.lines()
+        .enumerate()
+        .map(|(idx, line)| (idx + 1, line.trim_end_matches('\r')))
+        .filter(|(_, line)| !line.trim().is_empty())
+}
+
+fn unquote(value: &str) -> &str {
+    let bytes = value.as_bytes();
+    if bytes.len() >= 2
+        && (bytes[0] == b'"' || bytes[0] == b'\'')
+        && bytes[bytes.len() - 1] == bytes[0]
+    {
+        return &value[1..value.len() - 1];
+    }
+    value
+}
+
+fn parse_kv(text: &str) -> Result<Value, String> {
+    let mut out = serde_json::Map::new();
+    for (line_no, line) in non_empty_lines(text) {
+        let trimmed = line.trim();
+        if trimmed.starts_with('#') {
+            continue;
+        }
+        let trimmed = trimmed.strip_prefix("export ").unwrap_or(trimmed);
+        let Some((key, value)) = trimmed.split_once('=') else {
+            return Err(format!("line {} is not KEY=VALUE", line_no));
+        };
+        let key = key.trim();
+        if key.is_empty() {
+            return Err(format!("line {} has an empty key", line_no));
+        }
+        out.insert(
+            key.to_string(),
+            Value::String(unquote(value.trim()).to_string()),
+        );
+    }
+    Ok(Value::Object(out))
+}
+
+fn parse_csv(text: &str, headers: bool, delimiter: char) -> Result<Value, String> {
+    let mut rows =
+        non_empty_lines(text).map(|(line_no, line)| (line_no, split_csv_line(line, delimiter)));
+    if !headers {
+        return Ok(Value::Array(
+            rows.map(|(_, cells)| Value::Array(cells.into_iter().map(Value::String).collect()))
+                .collect(),
+        ));
+    }
+    let Some((_, names)) = rows.next() else {
+        return Ok(Value::Array(Vec::new()));
+    };
+    let names: Vec<String> = names.into_iter().map(|n| n.trim().to_string()).collect();
+    let mut out = Vec::new();
+    for (line_no, cells) in rows {
+        if cells.len() > names.len() {
+            return Err(format!(
+                "line {} has {} columns, header has {}",
+                line_no,
+                cells.len(),
+                names.len()
+            ));
+        }
+        let mut row = serde_json::Map::new();
+        let mut cells = cells.into_iter();
+        for name in &names {
+            row.insert(
+                name.clone(),
+                cells.next().map(Value::String).unwrap_or(Value::Null),
+            );
+        }
+        out.push(Value::Object(row));
+    }
+    Ok(Value::Array(out))
+}
+
+// Err carries the parse_error message; callers keep the raw text either way.
+pub fn parse_text(spec: &ParseSpec, text: &str) -> Result<Value, String> {
+    match spec {
+        ParseSpec::Json => serde_json::from_str(text.trim()).map_err(|err| err.to_string()),
+        ParseSpec::Lines => Ok(Value::Array(
+            non_empty_lines(text)
+                .map(|(_, line)| Value::String(line.to_string()))
+                .collect(),
+        )),
+        ParseSpec::Kv => parse_kv(text),
+        ParseSpec::Csv { headers, delimiter } => parse_csv(text, *headers, *delimiter),
+    }
+}
+
+// Adds `parsed` (or `parse_error`), `parse` and `parsed_truncated` next to the raw text field.
+pub fn attach_parsed(result: &mut Value, spec: &ParseSpec, text: &str, truncated: bool) {
+    let Value::Object(map) = result else {
+        return;
+    };
+    map.insert("parse".to_string(), Value::String(spec.name().to_string()));
+    match parse_text(spec, text) {
+        Ok(parsed) => {
+            map.insert("parsed".to_string(), parsed);
+        }
+        Err(message) => {
+            map.insert("parsed".to_string(), Value::Null);
+            map.insert("parse_error".to_string(), Value::String(message));
+        }
+    }
+    map.insert("parsed_truncated".to_string(), Value::Bool(truncated));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_spec_accepts_names_and_csv_options() {
+        assert_eq!(parse_spec(None).unwrap(), None);
+        assert_eq!(
+            parse_spec(Some(&serde_json::json!("JSON"))).unwrap(),
+            Some(ParseSpec::Json)
+        );
+        assert_eq!(
+            parse_spec(Some(
+                &serde_json::json!({"csv": {"headers": false, "delimiter": ";"}})
+            ))
+            .unwrap(),
+            Some(ParseSpec::Csv {
+                headers: false,
+                delimiter: ';'
+            })
+        );
+        assert!(parse_spec(Some(&serde_json::json!("yaml"))).is_err());
+        assert!(parse_spec(Some(&serde_json::json!({"csv": {"delimiter": ",,"}}))).is_err());
+        assert!(parse_spec(Some(&serde_json::json!({"tsv": {}}))).is_err());
+    }
+
+    #[test]
+    fn json_and_lines_parse_stdout() {
+        assert_eq!(
+            parse_text(&ParseSpec::Json, " {\"pods\": 3}\n").unwrap(),
+            serde_json::json!({"pods": 3})
+        );
+        assert!(parse_text(&ParseSpec::Json, "{\"pods\": ").is_err());
+        assert_eq!(
+            parse_text(&ParseSpec::Lines, "a\r\n\n  \nb\n").unwrap(),
+            serde_json::json!(["a", "b"])
+        );
+    }
+
+    #[test]
+    fn kv_parses_env_style_output() {
+        let parsed = parse_text(
+            &ParseSpec::Kv,
+            "# os-release\nNAME=\"Ubuntu\"\nexport VERSION_ID='22.04'\nURL=https://x?a=b\n",
+        )
+        .unwrap();
+        assert_eq!(parsed["NAME"], "Ubuntu");
+        assert_eq!(parsed["VERSION_ID"], "22.04");
+        assert_eq!(parsed["URL"], "https://x?a=b");
+        let err = parse_text(&ParseSpec::Kv, "A=1\nnot a pair\n").unwrap_err();
+        assert!(err.contains("line 2"), "{}", err);
+    }
+
+    #[test]
+    fn csv_parses_rows_with_quotes_and_short_lines() {
+        let spec = ParseSpec::Csv {
+            headers: true,
+            delimiter: ',',
+        };
+        let parsed = parse_text(&spec, "name,note\nweb,\"a, \"\"b\"\"\"\ndb\n").unwrap();
+        assert_eq!(parsed[0]["name"], "web");
+        assert_eq!(parsed[0]["note"], "a, \"b\"");
+        assert!(parsed[1]["note"].is_null());
+        assert!(parse_text(&spec, "a\n1,2\n").is_err());
+
+        let raw = ParseSpec::Csv {
+            headers: false,
+            delimiter: '\t',
+        };
+        assert_eq!(
+            parse_text(&raw, "1\t2\n").unwrap(),
+            serde_json::json!([["1", "2"]])
+        );
+    }
+
+    #[test]
+    fn attach_parsed_keeps_errors_and_truncation_explicit() {
+        let mut result = serde_json::json!({"stdout": "{"});
+        attach_parsed(&mut result, &ParseSpec::Json, "{", true);
+        assert!(result["parsed"].is_null());
+        assert!(result["parse_error"].is_string());
+        assert_eq!(result["parsed_truncated"], true);
+        assert_eq!(result["stdout"], "{");
+    }
+}
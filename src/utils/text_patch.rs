@@ -0,0 +1,406 @@
+use crate::errors::ToolError;
+use serde_json::Value;
+
+// Middle sections (after trimming the common prefix/suffix) larger than this many line pairs are
+// diffed as one replaced block instead of running the quadratic LCS.
+const MAX_LCS_CELLS: usize = 4_000_000;
+const DIFF_CONTEXT_LINES: usize = 3;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum EditCount {
+    First(usize),
+    All,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TextEdit {
+    // Replaces the first `count` occurrences of `find`; fewer matches is a conflict.
+    Replace {
+        find: String,
+        replace: String,
+        count: EditCount,
+    },
+    // Replaces lines `start..=end` (1-based) with `replace`.
+    Lines {
+        start: usize,
+        end: usize,
+        replace: String,
+    },
+}
+
+fn line_number(value: Option<&Value>, label: &str) -> Result<Option<usize>, ToolError> {
+    match value {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => match value.as_u64() {
+            Some(n) if n >= 1 => Ok(Some(n as usize)),
+            _ => Err(ToolError::invalid_params(format!(
+                "{} must be a positive line number",
+                label
+            ))),
+        },
+    }
+}
+
+// Parses `{start, end}` (1-based, inclusive; `end` defaults to `start` or, when `open_end`, to
+// the last line).
+pub fn parse_line_range(
+    value: &Value,
+    label: &str,
+    open_end: bool,
+) -> Result<(usize, Option<usize>), ToolError> {
+    if !value.is_object() {
+        return Err(ToolError::invalid_params(format!(
+            "{} must be an object {{start, end}}",
+            label
+        )));
+    }
+    let start = line_number(value.get("start"), &format!("{}.start", label))?.unwrap_or(1);
+    let end = line_number(value.get("end"), &format!("{}.end", label))?;
+    let end = if open_end {
+        end
+    } else {
+        Some(end.unwrap_or(start))
+    };
+    if end.is_some_and(|end| end < start) {
+        return Err(ToolError::invalid_params(format!(
+            "{}.end must not be before {}.start",
+            label, label
+        )));
+    }
+    Ok((start, end))
+}
+
+pub fn parse_edits(value: &Value) -> Result<Vec<TextEdit>, ToolError> {
+    let items = value
+        .as_array()
+        .filter(|items| !items.is_empty())
+        .ok_or_else(|| {
+            ToolError::invalid_params("patch must be a non-empty array of edits")
+                .with_hint("Use [{find, replace, count}] or [{lines: {start, end}, replace}].")
+        })?;
+    items
+        .iter()
+        .enumerate()
+        .map(|(idx, item)| {
+            let label = format!("patch[{}]", idx);
+            let replace = match item.get("replace") {
+                Some(Value::String(text)) => text.clone(),
+                None | Some(Value::Null) => String::new(),
+                Some(_) => {
+                    return Err(ToolError::invalid_params(format!(
+                        "{}.replace must be a string",
+                        label
+                    )))
+                }
+            };
+            if let Some(range) = item.get("lines") {
+                if item.get("find").is_some() {
+                    return Err(ToolError::invalid_params(format!(
+                        "{} takes either find or lines, not both",
+                        label
+                    )));
+                }
+                let (start, end) = parse_line_range(range, &format!("{}.lines", label), false)?;
+                return Ok(TextEdit::Lines {
+                    start,
+                    end: end.unwrap_or(start),
+                    replace,
+                });
+            }
+            let find = item
+                .get("find")
+                .and_then(|v| v.as_str())
+                .filter(|find| !find.is_empty())
+                .ok_or_else(|| {
+                    ToolError::invalid_params(format!(
+                        "{} needs a non-empty find string or a lines range",
+                        label
+                    ))
+                })?;
+            let count = match item.get("count") {
+                None | Some(Value::Null) => EditCount::First(1),
+                Some(Value::String(all)) if all == "all" => EditCount::All,
+                Some(value) => match value.as_u64() {
+                    Some(n) if n >= 1 => EditCount::First(n as usize),
+                    _ => {
+                        return Err(ToolError::invalid_params(format!(
+                            "{}.count must be a positive integer or \"all\"",
+                            label
+                        )))
+                    }
+                },
+            };
+            Ok(TextEdit::Replace {
+                find: find.to_string(),
+                replace,
+                count,
+            })
+        })
+        .collect()
+}
+
+// Byte offsets of each line start, plus the content length as a final sentinel.
+fn line_starts(content: &str) -> Vec<usize> {
+    if content.is_empty() {
+        return vec![0];
+    }
+    let mut starts = vec![0];
+    starts.extend(
+        content
+            .match_indices('\n')
+            .map(|(idx, _)| idx + 1)
+            .filter(|idx| *idx < content.len()),
+    );
+    starts.push(content.len());
+    starts
+}
+
+pub fn line_count(content: &str) -> usize {
+    line_starts(content).len() - 1
+}
+
+// Applies the edits in order; each one sees the result of the previous ones.
+pub fn apply_edits(content: &str, edits: &[TextEdit]) -> Result<(String, usize), ToolError> {
+    let mut current = content.to_string();
+    let mut replacements = 0usize;
+    for (idx, edit) in edits.iter().enumerate() {
+        match edit {
+            TextEdit::Replace {
+                find,
+                replace,
+                count,
+            } => {
+                let found = current.matches(find.as_str()).count();
+                let wanted = match count {
+                    EditCount::All => found.max(1),
+                    EditCount::First(n) => *n,
+                };
+                if found < wanted {
+                    return Err(ToolError::conflict(format!(
+                        "patch[{}]: find matched {} time(s), expected at least {}",
+                        idx, found, wanted
+                    ))
+                    .with_hint("Re-read the file; the text may have changed since it was read."));
+                }
+                current = current.replacen(find.as_str(), replace, wanted);
+                replacements += wanted;
+            }
+            TextEdit::Lines {
+                start,
+                end,
+                replace,
+            } => {
+                let starts = line_starts(&current);
+                let total = starts.len() - 1;
+                if *end > total {
+                    return Err(ToolError::conflict(format!(
+                        "patch[{}]: lines {}..{} are past the end of the file ({} lines)",
+                        idx, start, end, total
+                    )));
+                }
+                let (from, to) = (starts[start - 1], starts[*end]);
+                let mut text = replace.clone();
+                if current[..to].ends_with('\n') && !text.is_empty() && !text.ends_with('\n') {
+                    text.push('\n');
+                }
+                current.replace_range(from..to, &text);
+                replacements += 1;
+            }
+        }
+    }
+    Ok((current, replacements))
+}
+
+fn split_lines(content: &str) -> Vec<&str> {
+    content.split_inclusive('\n').collect()
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Op {
+    Keep,
+    Delete,
+    Insert,
+}
+
+fn diff_ops(before: &[&str], after: &[&str]) -> Vec<Op> {
+    let prefix = before.iter().zip(after).take_while(|(a, b)| a == b).count();
+    let suffix = before[prefix..]
+        .iter()
+        .rev()
+        .zip(after[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old = &before[prefix..before.len() - suffix];
+    let new = &after[prefix..after.len() - suffix];
+
+    let mut ops = vec![Op::Keep; prefix];
+    if old.len().saturating_mul(new.len()) > MAX_LCS_CELLS {
+        ops.extend(std::iter::repeat_n(Op::Delete, old.len()));
+        ops.extend(std::iter::repeat_n(Op::Insert, new.len()));
+    } else {
+        // lcs[i][j] = LCS length of old[i..] and new[j..].
+        let width = new.len() + 1;
+        let mut lcs = vec![0u32; (old.len() + 1) * width];
+        for i in (0..old.len()).rev() {
+            for j in (0..new.len()).rev() {
+                lcs[i * width + j] = if old[i] == new[j] {
+                    lcs[(i + 1) * width + j + 1] + 1
+                } else {
+                    lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < old.len() || j < new.len() {
+            if i < old.len() && j < new.len() && old[i] == new[j] {
+                ops.push(Op::Keep);
+                i += 1;
+                j += 1;
+            } else if i < old.len()
+                && (j == new.len() || lcs[(i + 1) * width + j] >= lcs[i * width + j + 1])
+            {
+                ops.push(Op::Delete);
+                i += 1;
+            } else {
+                ops.push(Op::Insert);
+                j += 1;
+            }
+        }
+    }
+    ops.extend(std::iter::repeat_n(Op::Keep, suffix));
+    ops
+}
+
+fn push_line(out: &mut String, marker: char, line: &str) {
+    out.push(marker);
+    out.push_str(line);
+    if !line.ends_with('\n') {
+        out.push_str("\n\\ No newline at end of file\n");
+    }
+}
+
+// Unified diff (`--- a/<label>` / `+++ b/<label>`) with three lines of context; empty when the
+// texts are equal.
+pub fn unified_diff(label: &str, before: &str, after: &str) -> String {
+    if before == after {
+        return String::new();
+    }
+    let old = split_lines(before);
+    let new = split_lines(after);
+    let ops = diff_ops(&old, &new);
+
+    // Positions (in ops) of changed lines, grouped into hunks that share context.
+    let changed: Vec<usize> = ops
+        .iter()
+        .enumerate()
+        .filter(|(_, op)| **op != Op::Keep)
+        .map(|(idx, _)| idx)
+        .collect();
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for idx in changed {
+        let start = idx.saturating_sub(DIFF_CONTEXT_LINES);
+        let end = (idx + DIFF_CONTEXT_LINES + 1).min(ops.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    let mut out = format!("--- a/{}\n+++ b/{}\n", label, label);
+    // Old/new line numbers (0-based) at the start of each op.
+    let mut positions = Vec::with_capacity(ops.len() + 1);
+    let (mut old_line, mut new_line) = (0usize, 0usize);
+    for op in &ops {
+        positions.push((old_line, new_line));
+        match op {
+            Op::Keep => {
+                old_line += 1;
+                new_line += 1;
+            }
+            Op::Delete => old_line += 1,
+            Op::Insert => new_line += 1,
+        }
+    }
+    positions.push((old_line, new_line));
+
+    for (start, end) in hunks {
+        let (old_start, new_start) = positions[start];
+        let (old_end, new_end) = positions[end];
+        let (old_len, new_len) = (old_end - old_start, new_end - new_start);
+        out.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            if old_len == 0 {
+                old_start
+            } else {
+                old_start + 1
+            },
+            old_len,
+            if new_len == 0 {
+                new_start
+            } else {
+                new_start + 1
+            },
+            new_len
+        ));
+        for idx in start..end {
+            let (old_idx, new_idx) = positions[idx];
+            match ops[idx] {
+                Op::Keep => push_line(&mut out, ' ', old[old_idx]),
+                Op::Delete => push_line(&mut out, '-', old[old_idx]),
+                Op::Insert => push_line(&mut out, '+', new[new_idx]),
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn edits_apply_in_order_and_reject_stale_finds() {
+        let content = "alpha\nbeta\ngamma\nbeta\n";
+        let edits = parse_edits(&json!([
+            {"find": "beta", "replace": "BETA", "count": "all"},
+            {"lines": {"start": 1}, "replace": "first"},
+            {"lines": {"start": 3, "end": 4}, "replace": "tail\nend"},
+        ]))
+        .unwrap();
+        let (patched, replacements) = apply_edits(content, &edits).unwrap();
+        assert_eq!(patched, "first\nBETA\ntail\nend\n");
+        assert_eq!(replacements, 4);
+
+        let stale = parse_edits(&json!([{"find": "beta", "replace": "x", "count": 3}])).unwrap();
+        assert!(apply_edits(content, &stale)
+            .unwrap_err()
+            .message
+            .contains("matched 2 time(s)"));
+        let past_end = parse_edits(&json!([{"lines": {"start": 5}, "replace": ""}])).unwrap();
+        assert!(apply_edits(content, &past_end).is_err());
+        assert!(parse_edits(&json!([{"find": ""}])).is_err());
+        assert!(parse_edits(&json!([{"lines": {"start": 3, "end": 2}}])).is_err());
+        assert!(parse_edits(&json!([])).is_err());
+    }
+
+    #[test]
+    fn unified_diff_emits_hunks_with_context() {
+        let before: String = (1..=20).map(|n| format!("line {}\n", n)).collect();
+        let after = before
+            .replace("line 2\n", "line two\n")
+            .replace("line 18\n", "");
+        let diff = unified_diff("app.conf", &before, &after);
+        assert!(diff.starts_with("--- a/app.conf\n+++ b/app.conf\n"));
+        assert!(diff.contains("@@ -1,5 +1,5 @@\n line 1\n-line 2\n+line two\n line 3\n"));
+        assert!(diff.contains("@@ -15,6 +15,5 @@\n line 15\n line 16\n line 17\n-line 18\n"));
+
+        let no_newline = unified_diff("x", "a\nb", "a\nc");
+        assert!(no_newline
+            .ends_with("-b\n\\ No newline at end of file\n+c\n\\ No newline at end of file\n"));
+        assert_eq!(unified_diff("x", "same", "same"), "");
+        assert_eq!(line_count("a\nb\n"), 2);
+        assert_eq!(line_count("a\nb"), 2);
+        assert_eq!(line_count(""), 0);
+    }
+}
@@ -0,0 +1,188 @@
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TraceContext {
+    pub trace_id: String,
+    pub span_id: String,
+    pub parent_span_id: Option<String>,
+}
+
+fn new_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+fn string_arg(args: &Value, key: &str) -> Option<String> {
+    args.get(key)
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.trim().is_empty())
+        .map(|s| s.to_string())
+}
+
+impl TraceContext {
+    // The span a handler runs in. The executor always stamps trace_id/span_id; direct manager
+    // calls without them start a fresh trace.
+    pub fn from_args(args: &Value) -> Self {
+        Self {
+            trace_id: string_arg(args, "trace_id").unwrap_or_else(new_id),
+            span_id: string_arg(args, "span_id").unwrap_or_else(new_id),
+            parent_span_id: string_arg(args, "parent_span_id"),
+        }
+    }
+
+    pub fn child(&self) -> Self {
+        Self {
+            trace_id: self.trace_id.clone(),
+            span_id: new_id(),
+            parent_span_id: Some(self.span_id.clone()),
+        }
+    }
+
+    // Stamps the span onto nested call args, replacing whatever the caller forwarded.
+    pub fn apply(&self, mut args: Value) -> Value {
+        if let Value::Object(map) = &mut args {
+            map.insert("trace_id".to_string(), Value::String(self.trace_id.clone()));
+            map.insert("span_id".to_string(), Value::String(self.span_id.clone()));
+            match self.parent_span_id.as_ref() {
+                Some(parent) => {
+                    map.insert("parent_span_id".to_string(), Value::String(parent.clone()));
+                }
+                None => {
+                    map.remove("parent_span_id");
+                }
+            }
+        }
+        args
+    }
+
+    // Adds span_id to an object result so callers can find the nested call in audit/artifacts.
+    pub fn tag(&self, mut result: Value) -> Value {
+        if let Value::Object(map) = &mut result {
+            map.insert("span_id".to_string(), Value::String(self.span_id.clone()));
+        }
+        result
+    }
+}
+
+fn span_node(entry: &Value) -> Value {
+    serde_json::json!({
+        "span_id": entry.get("span_id").cloned().unwrap_or(Value::Null),
+        "parent_span_id": entry.get("parent_span_id").cloned().unwrap_or(Value::Null),
+        "tool": entry.get("tool").cloned().unwrap_or(Value::Null),
+        "action": entry.get("action").cloned().unwrap_or(Value::Null),
+        "status": entry.get("status").cloned().unwrap_or(Value::Null),
+        "timestamp": entry.get("timestamp").cloned().unwrap_or(Value::Null),
+        "duration_ms": entry.get("duration_ms").cloned().unwrap_or(Value::Null),
+    })
+}
+
+fn attach_children(
+    node: &mut Value,
+    span_id: &str,
+    nodes: &HashMap<String, Value>,
+    children: &HashMap<String, Vec<String>>,
+    visited: &mut HashSet<String>,
+) {
+    let mut out = Vec::new();
+    for child_id in children.get(span_id).into_iter().flatten() {
+        if !visited.insert(child_id.clone()) {
+            continue;
+        }
+        let mut child = nodes[child_id].clone();
+        attach_children(&mut child, child_id, nodes, children, visited);
+        out.push(child);
+    }
+    node["children"] = Value::Array(out);
+}
+
+// Rebuilds the span tree of one trace from its audit entries (in audit order). The first entry
+// per span_id wins; spans whose parent is not in the trace become roots.
+pub fn build_span_tree(entries: &[Value]) -> Value {
+    let mut order: Vec<String> = Vec::new();
+    let mut nodes: HashMap<String, Value> = HashMap::new();
+    for entry in entries {
+        let Some(span_id) = entry.get("span_id").and_then(|v| v.as_str()) else {
+            continue;
+        };
+        if nodes.contains_key(span_id) {
+            continue;
+        }
+        order.push(span_id.to_string());
+        nodes.insert(span_id.to_string(), span_node(entry));
+    }
+
+    let mut children: HashMap<String, Vec<String>> = HashMap::new();
+    let mut roots = Vec::new();
+    for span_id in &order {
+        match nodes[span_id]["parent_span_id"].as_str() {
+            Some(parent) if parent != span_id && nodes.contains_key(parent) => {
+                children
+                    .entry(parent.to_string())
+                    .or_default()
+                    .push(span_id.clone());
+            }
+            _ => roots.push(span_id.clone()),
+        }
+    }
+
+    let mut visited = HashSet::new();
+    let mut tree = Vec::new();
+    for span_id in roots {
+        visited.insert(span_id.clone());
+        let mut node = nodes[&span_id].clone();
+        attach_children(&mut node, &span_id, &nodes, &children, &mut visited);
+        tree.push(node);
+    }
+    Value::Array(tree)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn child_spans_keep_the_trace_and_point_at_the_caller() {
+        let root = TraceContext::from_args(&serde_json::json!({
+            "trace_id": "t1",
+            "span_id": "s1",
+        }));
+        let child = root.child();
+        assert_eq!(child.trace_id, "t1");
+        assert_eq!(child.parent_span_id.as_deref(), Some("s1"));
+        assert_ne!(child.span_id, "s1");
+
+        let args = child.apply(serde_json::json!({"action": "exec", "span_id": "stale"}));
+        assert_eq!(args["span_id"], child.span_id.as_str());
+        assert_eq!(args["parent_span_id"], "s1");
+        assert_eq!(
+            child.tag(serde_json::json!({"ok": true}))["span_id"],
+            args["span_id"]
+        );
+
+        let fresh = TraceContext::from_args(&serde_json::json!({}));
+        assert!(fresh.parent_span_id.is_none());
+        assert!(
+            !fresh.apply(serde_json::json!({"parent_span_id": "x"}))["parent_span_id"].is_string()
+        );
+    }
+
+    #[test]
+    fn span_tree_nests_children_under_their_parents() {
+        let entries = vec![
+            serde_json::json!({"span_id": "c1", "parent_span_id": "root", "tool": "ssh"}),
+            serde_json::json!({"span_id": "g1", "parent_span_id": "c1", "tool": "ssh"}),
+            serde_json::json!({"span_id": "c2", "parent_span_id": "root", "tool": "api"}),
+            serde_json::json!({"span_id": "root", "parent_span_id": "caller", "tool": "pipeline"}),
+            serde_json::json!({"span_id": "c2", "parent_span_id": "root", "tool": "dup"}),
+            serde_json::json!({"tool": "no-span"}),
+        ];
+        let tree = build_span_tree(&entries);
+        let roots = tree.as_array().expect("roots");
+        assert_eq!(roots.len(), 1);
+        assert_eq!(roots[0]["span_id"], "root");
+        let children = roots[0]["children"].as_array().expect("children");
+        assert_eq!(children.len(), 2);
+        assert_eq!(children[0]["children"][0]["span_id"], "g1");
+        assert_eq!(children[1]["tool"], "api");
+    }
+}
@@ -0,0 +1,181 @@
+use serde_json::Value;
+use std::io::{Read, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+pub const CHUNK_BYTES: usize = 64 * 1024;
+pub const PROGRESS_INTERVAL_MS: u64 = 1_000;
+pub const DEFAULT_PROGRESS_MIN_BYTES: u64 = 8 * 1024 * 1024;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TransferProgress {
+    pub bytes: u64,
+    pub total_bytes: Option<u64>,
+    pub elapsed_ms: u64,
+}
+
+impl TransferProgress {
+    pub fn rate_bps(&self) -> u64 {
+        if self.elapsed_ms == 0 {
+            return self.bytes;
+        }
+        self.bytes.saturating_mul(1000) / self.elapsed_ms
+    }
+
+    pub fn percent(&self) -> Option<f64> {
+        let total = self.total_bytes.filter(|total| *total > 0)?;
+        Some(((self.bytes as f64 / total as f64) * 1000.0).round() / 10.0)
+    }
+
+    pub fn to_json(&self) -> Value {
+        serde_json::json!({
+            "bytes": self.bytes,
+            "total_bytes": self.total_bytes,
+            "percent": self.percent(),
+            "rate_bps": self.rate_bps(),
+            "duration_ms": self.elapsed_ms,
+        })
+    }
+}
+
+// Token bucket holding at most one second of budget, so a limit never bursts above ~1s of
+// traffic after an idle period.
+struct TokenBucket {
+    rate_bps: u64,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate_bps: u64) -> Self {
+        Self {
+            rate_bps,
+            tokens: rate_bps as f64,
+            refilled_at: Instant::now(),
+        }
+    }
+
+    fn take(&mut self, bytes: usize) {
+        let now = Instant::now();
+        let capacity = self.rate_bps as f64;
+        self.tokens = (self.tokens + now.duration_since(self.refilled_at).as_secs_f64() * capacity)
+            .min(capacity);
+        self.refilled_at = now;
+        self.tokens -= bytes as f64;
+        if self.tokens < 0.0 {
+            std::thread::sleep(Duration::from_secs_f64(-self.tokens / capacity));
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct TransferOptions {
+    pub max_rate_bps: Option<u64>,
+    pub total_bytes: Option<u64>,
+    pub abort: Option<Arc<AtomicBool>>,
+}
+
+// Blocking chunked copy used by the SFTP transfers. `on_progress` runs at most once per
+// PROGRESS_INTERVAL_MS and once more with the final totals.
+pub fn copy_with_progress<R: Read, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+    options: &TransferOptions,
+    on_progress: &mut dyn FnMut(&TransferProgress),
+) -> std::io::Result<TransferProgress> {
+    let started = Instant::now();
+    let chunk = match options.max_rate_bps {
+        Some(rate) => CHUNK_BYTES.min(rate.max(1) as usize),
+        None => CHUNK_BYTES,
+    };
+    let mut buf = vec![0u8; chunk];
+    let mut bucket = options.max_rate_bps.map(TokenBucket::new);
+    let mut progress = TransferProgress {
+        bytes: 0,
+        total_bytes: options.total_bytes,
+        elapsed_ms: 0,
+    };
+    let mut reported_at = started;
+    loop {
+        if options
+            .abort
+            .as_ref()
+            .map(|flag| flag.load(Ordering::SeqCst))
+            .unwrap_or(false)
+        {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Interrupted,
+                "transfer canceled",
+            ));
+        }
+        let read = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+        if let Some(bucket) = bucket.as_mut() {
+            bucket.take(read);
+        }
+        writer.write_all(&buf[..read])?;
+        progress.bytes += read as u64;
+        if reported_at.elapsed() >= Duration::from_millis(PROGRESS_INTERVAL_MS) {
+            progress.elapsed_ms = started.elapsed().as_millis() as u64;
+            on_progress(&progress);
+            reported_at = Instant::now();
+        }
+    }
+    writer.flush()?;
+    progress.elapsed_ms = started.elapsed().as_millis() as u64;
+    on_progress(&progress);
+    Ok(progress)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn copy_reports_totals_and_percent() {
+        let data = vec![7u8; 200_000];
+        let mut out = Vec::new();
+        let mut calls = Vec::new();
+        let options = TransferOptions {
+            total_bytes: Some(data.len() as u64),
+            ..Default::default()
+        };
+        let done = copy_with_progress(&mut data.as_slice(), &mut out, &options, &mut |p| {
+            calls.push(p.clone())
+        })
+        .expect("copy");
+        assert_eq!(out, data);
+        assert_eq!(done.bytes, 200_000);
+        assert_eq!(done.percent(), Some(100.0));
+        assert_eq!(calls.last(), Some(&done));
+    }
+
+    #[test]
+    fn rate_limit_throttles_and_abort_stops_the_loop() {
+        let data = vec![1u8; 3_000];
+        let mut out = Vec::new();
+        let options = TransferOptions {
+            max_rate_bps: Some(2_000),
+            ..Default::default()
+        };
+        let started = Instant::now();
+        copy_with_progress(&mut data.as_slice(), &mut out, &options, &mut |_| {}).expect("copy");
+        // 2000 bytes of initial budget, then 1000 more bytes at 2000 B/s.
+        assert!(started.elapsed() >= Duration::from_millis(450));
+        assert_eq!(out.len(), 3_000);
+
+        let flag = Arc::new(AtomicBool::new(true));
+        let options = TransferOptions {
+            abort: Some(flag),
+            ..Default::default()
+        };
+        let err = copy_with_progress(&mut data.as_slice(), &mut Vec::new(), &options, &mut |_| {})
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::Interrupted);
+    }
+}
@@ -0,0 +1,231 @@
+use once_cell::sync::Lazy;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+// Per-call resource accounting. The executor opens a scope around every handler; managers add to
+// whatever scope is current, and a nested call's scope also feeds the scopes of its callers.
+// Blocking threads and spawned tasks do not inherit task-locals: capture `current()` before
+// spawn_blocking, and wrap spawned futures in `in_current_scope`.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Counter {
+    SshStdoutBytes,
+    SshStderrBytes,
+    SftpBytesRead,
+    SftpBytesWritten,
+    HttpBodyReadBytes,
+    HttpBodySentBytes,
+    PostgresRows,
+    Retries,
+    CacheHits,
+    CacheMisses,
+}
+
+const COUNTERS: [Counter; 10] = [
+    Counter::SshStdoutBytes,
+    Counter::SshStderrBytes,
+    Counter::SftpBytesRead,
+    Counter::SftpBytesWritten,
+    Counter::HttpBodyReadBytes,
+    Counter::HttpBodySentBytes,
+    Counter::PostgresRows,
+    Counter::Retries,
+    Counter::CacheHits,
+    Counter::CacheMisses,
+];
+
+impl Counter {
+    pub fn key(self) -> &'static str {
+        match self {
+            Counter::SshStdoutBytes => "ssh_stdout_bytes",
+            Counter::SshStderrBytes => "ssh_stderr_bytes",
+            Counter::SftpBytesRead => "sftp_bytes_read",
+            Counter::SftpBytesWritten => "sftp_bytes_written",
+            Counter::HttpBodyReadBytes => "http_body_read_bytes",
+            Counter::HttpBodySentBytes => "http_body_sent_bytes",
+            Counter::PostgresRows => "postgres_rows",
+            Counter::Retries => "retries",
+            Counter::CacheHits => "cache_hits",
+            Counter::CacheMisses => "cache_misses",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+tokio::task_local! {
+    static USAGE: Arc<UsageScope>;
+}
+
+#[derive(Debug, Default)]
+pub struct UsageScope {
+    counters: [AtomicU64; COUNTERS.len()],
+    parent: Option<Arc<UsageScope>>,
+}
+
+impl UsageScope {
+    // A scope nested under the current one (if any), so its counts also reach the caller.
+    pub fn nested() -> Arc<Self> {
+        Arc::new(Self {
+            counters: Default::default(),
+            parent: current(),
+        })
+    }
+
+    pub fn add(&self, counter: Counter, amount: u64) {
+        if amount == 0 {
+            return;
+        }
+        let mut scope = Some(self);
+        while let Some(current) = scope {
+            current.counters[counter.index()].fetch_add(amount, Ordering::Relaxed);
+            scope = current.parent.as_deref();
+        }
+    }
+
+    pub fn get(&self, counter: Counter) -> u64 {
+        self.counters[counter.index()].load(Ordering::Relaxed)
+    }
+
+    pub fn to_value(&self, duration_ms: i64) -> Value {
+        let mut map = serde_json::Map::new();
+        map.insert("duration_ms".to_string(), Value::from(duration_ms));
+        for counter in COUNTERS {
+            map.insert(counter.key().to_string(), Value::from(self.get(counter)));
+        }
+        Value::Object(map)
+    }
+}
+
+pub async fn with_usage_scope<F: Future>(scope: Arc<UsageScope>, fut: F) -> F::Output {
+    USAGE.scope(scope, fut).await
+}
+
+pub fn current() -> Option<Arc<UsageScope>> {
+    USAGE.try_with(|scope| scope.clone()).ok()
+}
+
+// Adds to the current task's scope; a no-op outside tool calls.
+pub fn record(counter: Counter, amount: u64) {
+    let _ = USAGE.try_with(|scope| scope.add(counter, amount));
+}
+
+// For work that runs off the task (spawn_blocking): `scope` is what `current()` returned before.
+pub fn record_in(scope: Option<&Arc<UsageScope>>, counter: Counter, amount: u64) {
+    if let Some(scope) = scope {
+        scope.add(counter, amount);
+    }
+}
+
+// Carries the current scope into a future handed to tokio::spawn.
+pub fn in_current_scope<F: Future>(fut: F) -> impl Future<Output = F::Output> {
+    let scope = current();
+    async move {
+        match scope {
+            Some(scope) => USAGE.scope(scope, fut).await,
+            None => fut.await,
+        }
+    }
+}
+
+#[derive(Default)]
+struct ToolTotals {
+    calls: u64,
+    errors: u64,
+    duration_ms: u64,
+    counters: [u64; COUNTERS.len()],
+}
+
+// Process-wide totals per tool, inclusive of nested calls.
+static TOTALS: Lazy<Mutex<BTreeMap<String, ToolTotals>>> =
+    Lazy::new(|| Mutex::new(BTreeMap::new()));
+
+pub fn aggregate(tool: &str, scope: &UsageScope, duration_ms: i64, failed: bool) {
+    let mut totals = TOTALS.lock().unwrap_or_else(|err| err.into_inner());
+    let entry = totals.entry(tool.to_string()).or_default();
+    entry.calls += 1;
+    if failed {
+        entry.errors += 1;
+    }
+    entry.duration_ms += duration_ms.max(0) as u64;
+    for counter in COUNTERS {
+        entry.counters[counter.index()] += scope.get(counter);
+    }
+}
+
+pub fn totals_snapshot() -> Value {
+    let totals = TOTALS.lock().unwrap_or_else(|err| err.into_inner());
+    let tools: serde_json::Map<String, Value> = totals
+        .iter()
+        .map(|(tool, entry)| {
+            let mut map = serde_json::Map::new();
+            map.insert("calls".to_string(), Value::from(entry.calls));
+            map.insert("errors".to_string(), Value::from(entry.errors));
+            map.insert("duration_ms".to_string(), Value::from(entry.duration_ms));
+            for counter in COUNTERS {
+                map.insert(
+                    counter.key().to_string(),
+                    Value::from(entry.counters[counter.index()]),
+                );
+            }
+            (tool.clone(), Value::Object(map))
+        })
+        .collect();
+    Value::Object(tools)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn nested_scopes_feed_their_callers_and_spawned_tasks() {
+        record(Counter::Retries, 5);
+        assert!(current().is_none());
+
+        let outer = UsageScope::nested();
+        let inner_counts = with_usage_scope(outer.clone(), async {
+            record(Counter::PostgresRows, 3);
+            let inner = UsageScope::nested();
+            with_usage_scope(inner.clone(), async {
+                record(Counter::SshStdoutBytes, 10);
+                let captured = current();
+                std::thread::spawn(move || {
+                    record_in(captured.as_ref(), Counter::SftpBytesRead, 7);
+                })
+                .join()
+                .unwrap();
+                tokio::spawn(in_current_scope(async {
+                    record(Counter::HttpBodyReadBytes, 2);
+                }))
+                .await
+                .unwrap();
+            })
+            .await;
+            inner.to_value(4)
+        })
+        .await;
+
+        assert_eq!(inner_counts["ssh_stdout_bytes"], 10);
+        assert_eq!(inner_counts["postgres_rows"], 0);
+        assert_eq!(inner_counts["duration_ms"], 4);
+        assert_eq!(outer.get(Counter::PostgresRows), 3);
+        assert_eq!(outer.get(Counter::SshStdoutBytes), 10);
+        assert_eq!(outer.get(Counter::SftpBytesRead), 7);
+        assert_eq!(outer.get(Counter::HttpBodyReadBytes), 2);
+        assert_eq!(outer.get(Counter::Retries), 0);
+
+        aggregate("usage-test", &outer, 12, false);
+        aggregate("usage-test", &UsageScope::default(), 3, true);
+        let totals = &totals_snapshot()["usage-test"];
+        assert_eq!(totals["calls"], 2);
+        assert_eq!(totals["errors"], 1);
+        assert_eq!(totals["duration_ms"], 15);
+        assert_eq!(totals["sftp_bytes_read"], 7);
+    }
+}
@@ -0,0 +1,192 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::api::ApiManager;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use openssl::asn1::Asn1Time;
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+use openssl::rsa::Rsa;
+use openssl::ssl::{NameType, SslAcceptor, SslMethod};
+use openssl::x509::extension::SubjectAlternativeName;
+use openssl::x509::{X509NameBuilder, X509};
+use serde_json::json;
+use std::io::Read;
+use std::sync::{Arc, Mutex};
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+fn closed_local_port() -> u16 {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+    listener.local_addr().expect("addr").port()
+}
+
+// Self-signed "localhost" certificate (SANs localhost and 127.0.0.1) expiring in ten days.
+fn self_signed() -> (X509, PKey<openssl::pkey::Private>) {
+    let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+    let mut name = X509NameBuilder::new().unwrap();
+    name.append_entry_by_text("CN", "localhost").unwrap();
+    let name = name.build();
+    let mut builder = X509::builder().unwrap();
+    builder.set_version(2).unwrap();
+    builder.set_subject_name(&name).unwrap();
+    builder.set_issuer_name(&name).unwrap();
+    builder.set_pubkey(&key).unwrap();
+    builder
+        .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+        .unwrap();
+    builder
+        .set_not_after(&Asn1Time::days_from_now(10).unwrap())
+        .unwrap();
+    let san = SubjectAlternativeName::new()
+        .dns("localhost")
+        .ip("127.0.0.1")
+        .build(&builder.x509v3_context(None, None))
+        .unwrap();
+    builder.append_extension(san).unwrap();
+    builder.sign(&key, MessageDigest::sha256()).unwrap();
+    (builder.build(), key)
+}
+
+// Completes TLS handshakes and records the SNI name each client sent.
+fn spawn_tls_stub(
+    cert: &X509,
+    key: &PKey<openssl::pkey::Private>,
+) -> (u16, Arc<Mutex<Vec<Option<String>>>>) {
+    let names = Arc::new(Mutex::new(Vec::new()));
+    let mut acceptor = SslAcceptor::mozilla_intermediate(SslMethod::tls()).unwrap();
+    acceptor.set_certificate(cert).unwrap();
+    acceptor.set_private_key(key).unwrap();
+    let seen = names.clone();
+    acceptor.set_servername_callback(move |ssl, _| {
+        seen.lock()
+            .unwrap()
+            .push(ssl.servername(NameType::HOST_NAME).map(str::to_string));
+        Ok(())
+    });
+    let acceptor = Arc::new(acceptor.build());
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind stub");
+    let port = listener.local_addr().expect("stub addr").port();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else { continue };
+            let acceptor = acceptor.clone();
+            std::thread::spawn(move || {
+                if let Ok(mut tls) = acceptor.accept(stream) {
+                    let mut buf = [0u8; 64];
+                    let _ = tls.read(&mut buf);
+                }
+            });
+        }
+    });
+    (port, names)
+}
+
+#[tokio::test]
+async fn cert_check_reports_leaf_details_validity_and_expiry() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+
+    let security = Arc::new(Security::new().expect("security"));
+    let profile_service = Arc::new(ProfileService::new(security).expect("profile service"));
+    let manager = ApiManager::new(
+        Logger::new("test"),
+        Validation::new(),
+        profile_service,
+        None,
+        None,
+        None,
+    );
+    let (cert, key) = self_signed();
+    let ca_path = tmp_dir.join("ca.pem");
+    std::fs::write(&ca_path, cert.to_pem().unwrap()).expect("ca file");
+    let (port, sni) = spawn_tls_stub(&cert, &key);
+    let closed = closed_local_port();
+
+    // Self-signed chains are errors unless insecure_ok asks for the details anyway.
+    let strict = manager
+        .handle_action(json!({
+            "action": "cert_check",
+            "targets": [format!("127.0.0.1:{}", port), format!("127.0.0.1:{}", closed)],
+        }))
+        .await
+        .expect("strict check");
+    let certs = strict["certificates"].as_array().unwrap();
+    assert_eq!(certs[0]["status"], "error");
+    assert_eq!(certs[0]["code"], "CERT_CHECK_VERIFY_FAILED");
+    assert_eq!(certs[1]["code"], "CERT_CHECK_CONNECT_FAILED");
+    assert_eq!(certs[1]["port"], closed);
+    assert_eq!(strict["stats"]["error"], 2);
+
+    let insecure = manager
+        .handle_action(json!({
+            "action": "cert_check",
+            "url": format!("https://localhost:{}/health", port),
+            "insecure_ok": true,
+        }))
+        .await
+        .expect("insecure check");
+    let entry = &insecure["certificates"][0];
+    assert_eq!(entry["status"], "invalid");
+    assert_eq!(entry["chain_valid"], false);
+    assert!(entry["verify_error"].as_str().unwrap().contains("self"));
+    assert_eq!(entry["subject"], "CN=localhost");
+    assert_eq!(entry["issuer"], "CN=localhost");
+    assert_eq!(entry["sans"], json!(["localhost", "127.0.0.1"]));
+    assert_eq!(entry["chain_length"], 1);
+    assert_eq!(entry["warning"], true);
+    assert!(matches!(entry["days_until_expiry"].as_i64(), Some(9..=10)));
+    assert_eq!(entry["host"], "localhost");
+
+    // Trusting the CA validates the chain; SNI carries the servername but never an IP literal.
+    let trusted = manager
+        .handle_action(json!({
+            "action": "cert_check",
+            "targets": [
+                {"host": "127.0.0.1", "port": port, "servername": "localhost"},
+                format!("127.0.0.1:{}", port),
+            ],
+            "tls": {"ca_cert_path": ca_path.display().to_string()},
+            "warn_days": 5,
+            "concurrency": 1,
+        }))
+        .await
+        .expect("trusted check");
+    let certs = trusted["certificates"].as_array().unwrap();
+    assert_eq!(certs[0]["status"], "ok");
+    assert_eq!(certs[0]["chain_valid"], true);
+    assert_eq!(certs[0]["servername"], "localhost");
+    assert_eq!(certs[1]["status"], "ok");
+    assert_eq!(trusted["stats"]["ok"], 2);
+    assert_eq!(trusted["source"], "targets");
+    let names = sni.lock().unwrap().clone();
+    assert_eq!(names.last(), Some(&None));
+    assert!(names.contains(&Some("localhost".to_string())));
+
+    let err = manager
+        .handle_action(json!({"action": "cert_check", "targets": ["http://plain.internal"]}))
+        .await
+        .expect_err("plain http");
+    assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+    let err = manager
+        .handle_action(json!({"action": "cert_check"}))
+        .await
+        .expect_err("no targets");
+    assert!(err.message.contains("needs targets"));
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    let _ = std::fs::remove_dir_all(&tmp_dir);
+}
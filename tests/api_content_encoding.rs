@@ -0,0 +1,182 @@
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use infra::managers::api::ApiManager;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use infra::utils::artifacts::resolve_artifact_path;
+use serde_json::json;
+use std::io::{Read, Write};
+use std::sync::{Arc, Mutex};
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+const BODY: &str =
+    r#"{"items":[{"id":1,"name":"alpha"},{"id":2,"name":"beta"}],"note":"gzip on the wire"}"#;
+
+fn gzipped(body: &str) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(body.as_bytes()).expect("gzip");
+    encoder.finish().expect("gzip finish")
+}
+
+// Always answers gzip-encoded and records each request's accept-encoding header.
+fn spawn_gzip_stub(seen: Arc<Mutex<Vec<String>>>) -> u16 {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind stub");
+    let port = listener.local_addr().expect("stub addr").port();
+    std::thread::spawn(move || {
+        let encoded = gzipped(BODY);
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { continue };
+            let mut buf = [0u8; 8192];
+            let read = stream.read(&mut buf).unwrap_or(0);
+            let head = String::from_utf8_lossy(&buf[..read]).to_string();
+            let accept = head
+                .lines()
+                .find_map(|line| {
+                    let (name, value) = line.split_once(':')?;
+                    name.eq_ignore_ascii_case("accept-encoding")
+                        .then(|| value.trim().to_string())
+                })
+                .unwrap_or_default();
+            seen.lock().unwrap().push(accept);
+            let mut response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Encoding: gzip\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                encoded.len()
+            )
+            .into_bytes();
+            response.extend_from_slice(&encoded);
+            let _ = stream.write_all(&response);
+        }
+    });
+    port
+}
+
+#[tokio::test]
+async fn gzip_bodies_decode_the_same_way_on_every_path() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let prev_context = std::env::var("INFRA_CONTEXT_REPO_ROOT").ok();
+    let prev_stream = std::env::var("INFRA_API_STREAM_TO_ARTIFACT").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    let context_root = tmp_dir.join("context");
+    std::fs::create_dir_all(&context_root).expect("create context dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    std::env::set_var("INFRA_CONTEXT_REPO_ROOT", &context_root);
+    std::env::set_var("INFRA_API_STREAM_TO_ARTIFACT", "full");
+
+    let security = Arc::new(Security::new().expect("security"));
+    let profile_service = Arc::new(ProfileService::new(security).expect("profile service"));
+    let manager = ApiManager::new(
+        Logger::new("test"),
+        Validation::new(),
+        profile_service,
+        None,
+        None,
+        None,
+    );
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let port = spawn_gzip_stub(seen.clone());
+    let url = format!("http://127.0.0.1:{}/items", port);
+    let wire = gzipped(BODY).len() as u64;
+
+    for response_type in ["auto", "json", "text", "bytes"] {
+        let result = manager
+            .handle_action(json!({
+                "action": "request",
+                "method": "GET",
+                "url": url,
+                "response_type": response_type,
+            }))
+            .await
+            .expect("request");
+        assert_eq!(result["body_read_bytes"], BODY.len(), "{}", result);
+        assert_eq!(result["wire_bytes"], wire);
+        assert_eq!(result["content_encoding"], "gzip");
+        assert_eq!(result["body_decoded"], true);
+        match response_type {
+            "auto" | "json" => assert_eq!(result["data"]["items"][1]["name"], "beta"),
+            "text" => assert_eq!(result["data"], BODY),
+            _ => {
+                use base64::Engine;
+                let bytes = base64::engine::general_purpose::STANDARD
+                    .decode(result["body_base64"].as_str().expect("base64"))
+                    .expect("decode");
+                assert_eq!(bytes, BODY.as_bytes());
+            }
+        }
+        let body_ref = &result["body_ref"];
+        assert_eq!(body_ref["content_encoding"], "gzip");
+        assert_eq!(body_ref["decoded"], true);
+        let stored = resolve_artifact_path(&context_root, body_ref["rel"].as_str().expect("rel"))
+            .expect("artifact path");
+        assert_eq!(std::fs::read(stored).expect("artifact"), BODY.as_bytes());
+    }
+
+    // decompress=false keeps the bytes as sent, in the preview and the artifact alike.
+    let raw = manager
+        .handle_action(json!({
+            "action": "request",
+            "method": "GET",
+            "url": url,
+            "response_type": "bytes",
+            "decompress": false,
+        }))
+        .await
+        .expect("raw request");
+    assert_eq!(raw["body_decoded"], false);
+    assert_eq!(raw["body_read_bytes"], wire);
+    assert_eq!(raw["body_ref"]["decoded"], false);
+    let stored = resolve_artifact_path(&context_root, raw["body_ref"]["rel"].as_str().unwrap())
+        .expect("artifact path");
+    assert_eq!(std::fs::read(stored).expect("artifact"), gzipped(BODY));
+
+    let smoke = manager
+        .handle_action(json!({"action": "smoke_http", "url": url}))
+        .await
+        .expect("smoke");
+    assert_eq!(smoke["bytes"], BODY.len(), "{}", smoke);
+    assert_eq!(smoke["wire_bytes"], wire);
+    assert_eq!(smoke["body_preview"], BODY);
+
+    let download_path = tmp_dir.join("items.json");
+    let downloaded = manager
+        .handle_action(json!({
+            "action": "download",
+            "method": "GET",
+            "url": url,
+            "download_path": download_path.display().to_string(),
+        }))
+        .await
+        .expect("download");
+    assert_eq!(downloaded["bytes"], BODY.len(), "{}", downloaded);
+    assert_eq!(downloaded["wire_bytes"], wire);
+    assert_eq!(std::fs::read_to_string(&download_path).unwrap(), BODY);
+
+    let accepted = seen.lock().unwrap().clone();
+    assert_eq!(accepted[0], "gzip, deflate, br");
+    assert_eq!(accepted[4], "", "decompress=false advertises nothing");
+
+    let err = manager
+        .handle_action(
+            json!({"action": "request", "method": "GET", "url": url, "decompress": "yes"}),
+        )
+        .await
+        .expect_err("decompress is a boolean");
+    assert_eq!(err.message, "decompress must be a boolean (got \"yes\")");
+
+    restore_env("INFRA_API_STREAM_TO_ARTIFACT", prev_stream);
+    restore_env("INFRA_CONTEXT_REPO_ROOT", prev_context);
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
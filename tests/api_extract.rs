@@ -0,0 +1,127 @@
+use infra::managers::api::ApiManager;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use std::io::{Read, Write};
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+fn spawn_json_stub(bodies: Vec<&'static str>) -> u16 {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind stub");
+    let port = listener.local_addr().expect("stub addr").port();
+    std::thread::spawn(move || {
+        for (body, stream) in bodies.into_iter().zip(listener.incoming()) {
+            let Ok(mut stream) = stream else { continue };
+            let mut buf = [0u8; 8192];
+            let _ = stream.read(&mut buf);
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+    port
+}
+
+const USERS: &str = r#"{"users":[{"id":1,"name":"ana","role":"admin","team":{"name":"ops"}},{"id":2,"name":"bo","role":"dev","team":{"name":"web"}}]}"#;
+
+#[tokio::test]
+async fn extract_shapes_request_and_paginate_results() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+
+    let security = Arc::new(Security::new().expect("security"));
+    let profile_service = Arc::new(ProfileService::new(security).expect("profile service"));
+    let manager = ApiManager::new(
+        Logger::new("test"),
+        Validation::new(),
+        profile_service,
+        None,
+        None,
+        None,
+    );
+
+    let port = spawn_json_stub(vec![USERS, USERS]);
+    let base_url = format!("http://127.0.0.1:{}", port);
+    let result = manager
+        .handle_action(serde_json::json!({
+            "action": "request",
+            "base_url": base_url,
+            "path": "/users",
+            "extract": "users | select(role == \"admin\") | map(id, team: team.name)",
+        }))
+        .await
+        .expect("request");
+    assert_eq!(
+        result["data"],
+        serde_json::json!([{"id": 1, "team": "ops"}])
+    );
+    assert!(result.get("extracted").is_none());
+    assert_eq!(
+        result["extract"],
+        "users | select(role == \"admin\") | map(id, team: team.name)"
+    );
+
+    let result = manager
+        .handle_action(serde_json::json!({
+            "action": "request",
+            "base_url": base_url,
+            "path": "/users",
+            "extract": "users | count",
+            "keep_raw": true,
+        }))
+        .await
+        .expect("request keep_raw");
+    assert_eq!(result["extracted"], 2);
+    assert_eq!(result["data"]["users"][1]["name"], "bo");
+
+    let port = spawn_json_stub(vec![USERS, r#"{"users":[]}"#]);
+    let result = manager
+        .handle_action(serde_json::json!({
+            "action": "paginate",
+            "base_url": format!("http://127.0.0.1:{}", port),
+            "path": "/users",
+            "pagination": {"type": "page", "max_pages": 5, "item_path": "data.users"},
+            "extract": "map(name) | last",
+        }))
+        .await
+        .expect("paginate");
+    assert_eq!(result["page_count"], 2, "{}", result);
+    assert_eq!(result["items"], serde_json::json!({"name": "bo"}));
+    assert!(result["pages"][0].get("data").is_none(), "{}", result);
+
+    // The expression is rejected before anything is sent; the port has no listener.
+    let err = manager
+        .handle_action(serde_json::json!({
+            "action": "request",
+            "base_url": "http://127.0.0.1:9",
+            "path": "/users",
+            "extract": "users | select(role => \"admin\")",
+        }))
+        .await
+        .expect_err("bad extract");
+    assert!(
+        err.message.starts_with("extract stage 2"),
+        "{}",
+        err.message
+    );
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    let _ = std::fs::remove_dir_all(&tmp_dir);
+}
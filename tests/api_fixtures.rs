@@ -0,0 +1,183 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::api::ApiManager;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use serde_json::json;
+use std::io::{Read, Write};
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+// Answers `responses` requests, echoing the request target (POSTs also get a secret), then
+// stops listening.
+fn spawn_http_stub(responses: usize) -> u16 {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind stub");
+    let port = listener.local_addr().expect("stub addr").port();
+    std::thread::spawn(move || {
+        for stream in listener.incoming().take(responses) {
+            let Ok(mut stream) = stream else { continue };
+            let mut buf = [0u8; 8192];
+            let read = stream.read(&mut buf).unwrap_or(0);
+            let head = String::from_utf8_lossy(&buf[..read]).to_string();
+            let target = head.split_whitespace().nth(1).unwrap_or("").to_string();
+            let mut body = json!({"items": [1], "target": target});
+            if head.starts_with("POST") {
+                body["password"] = json!("server-side-secret");
+            }
+            let body = body.to_string();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nSet-Cookie: session=abcdef123456\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+    port
+}
+
+#[tokio::test]
+async fn recorded_fixtures_replay_offline() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let prev_fixtures = std::env::var("INFRA_API_FIXTURES").ok();
+    let prev_miss = std::env::var("INFRA_API_FIXTURES_MISS").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    std::env::remove_var("INFRA_API_FIXTURES_MISS");
+
+    let security = Arc::new(Security::new().expect("security"));
+    let profile_service = Arc::new(ProfileService::new(security).expect("profile service"));
+    let manager = ApiManager::new(
+        Logger::new("test"),
+        Validation::new(),
+        profile_service,
+        None,
+        None,
+        None,
+    );
+    let port = spawn_http_stub(3);
+    let base_url = format!("http://127.0.0.1:{}", port);
+    let post = json!({
+        "action": "request",
+        "base_url": base_url,
+        "path": "/items",
+        "method": "POST",
+        "auth": { "type": "bearer", "token": "bearer-token-value" },
+        "body": { "name": "demo", "api_key": "body-secret-value" },
+    });
+    let paginate = json!({
+        "action": "paginate",
+        "base_url": base_url,
+        "path": "/items",
+        "pagination": { "type": "page", "max_pages": 2 },
+    });
+
+    std::env::set_var("INFRA_API_FIXTURES", "record");
+    let recorded = manager
+        .handle_action(post.clone())
+        .await
+        .expect("recorded request");
+    assert_eq!(recorded["status"], 200);
+    assert_eq!(recorded["fixture"]["mode"], "record");
+    let recorded_pages = manager
+        .handle_action(paginate.clone())
+        .await
+        .expect("recorded pagination");
+
+    let fixtures_dir = tmp_dir.join("api-fixtures");
+    let files: Vec<_> = std::fs::read_dir(&fixtures_dir)
+        .expect("fixtures dir")
+        .filter_map(|entry| entry.ok())
+        .collect();
+    assert_eq!(files.len(), 3, "one fixture per request and page");
+    for file in &files {
+        let raw = std::fs::read_to_string(file.path()).expect("read fixture");
+        for secret in [
+            "bearer-token-value",
+            "body-secret-value",
+            "server-side-secret",
+            "abcdef123456",
+        ] {
+            assert!(!raw.contains(secret), "fixture leaks {}", secret);
+        }
+    }
+
+    // The stub has stopped answering: everything below is served from disk.
+    std::env::set_var("INFRA_API_FIXTURES", "replay");
+    let replayed = manager
+        .handle_action(post.clone())
+        .await
+        .expect("replayed request");
+    assert_eq!(replayed["fixture"]["mode"], "replay");
+    assert_eq!(replayed["fixture"]["key"], recorded["fixture"]["key"]);
+    assert_eq!(replayed["fixture"]["body_withheld"], true);
+    assert_eq!(replayed["status"], 200);
+    assert_eq!(replayed["attempts"], 1);
+    let replayed_pages = manager
+        .handle_action(paginate.clone())
+        .await
+        .expect("replayed pagination");
+    let pages = replayed_pages["pages"].as_array().expect("pages");
+    assert_eq!(pages.len(), 2);
+    for (replayed, recorded) in pages
+        .iter()
+        .zip(recorded_pages["pages"].as_array().unwrap())
+    {
+        assert_eq!(replayed["data"], recorded["data"]);
+        assert_eq!(replayed["fixture"]["key"], recorded["fixture"]["key"]);
+        assert_eq!(replayed["fixture"]["body_withheld"], false);
+    }
+
+    let mut unknown = post.clone();
+    unknown["path"] = json!("/other");
+    let err = manager
+        .handle_action(unknown.clone())
+        .await
+        .expect_err("strict miss");
+    assert_eq!(err.kind, ToolErrorKind::NotFound);
+    assert_eq!(err.code, "FIXTURE_MISS");
+    assert!(err.details.as_ref().unwrap()["key"].is_string());
+
+    std::env::set_var("INFRA_API_FIXTURES_MISS", "fallback");
+    let err = manager
+        .handle_action(unknown)
+        .await
+        .expect_err("fallback reaches the closed port");
+    assert_ne!(err.code, "FIXTURE_MISS");
+
+    let listed = manager
+        .handle_action(json!({"action": "fixtures_list", "url_contains": "page="}))
+        .await
+        .expect("fixtures_list");
+    assert_eq!(listed["mode"], "replay");
+    assert_eq!(listed["total"], 2, "{}", listed);
+    let first = listed["fixtures"][0]["key"].clone();
+    let cleared = manager
+        .handle_action(json!({"action": "fixtures_clear", "fixture_keys": [first]}))
+        .await
+        .expect("clear one");
+    assert_eq!(cleared["removed"], 1);
+    let cleared = manager
+        .handle_action(json!({"action": "fixtures_clear"}))
+        .await
+        .expect("clear all");
+    assert_eq!(cleared["removed"], 2);
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    restore_env("INFRA_API_FIXTURES", prev_fixtures);
+    restore_env("INFRA_API_FIXTURES_MISS", prev_miss);
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
@@ -0,0 +1,174 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::api::ApiManager;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use serde_json::json;
+use std::io::{Read, Write};
+use std::sync::{Arc, Mutex};
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+// Answers with the scripted statuses in order and records each raw request head.
+fn spawn_scripted_stub(statuses: Vec<&'static str>) -> (u16, Arc<Mutex<Vec<String>>>) {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind stub");
+    let port = listener.local_addr().expect("stub addr").port();
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let log = seen.clone();
+    std::thread::spawn(move || {
+        for (status, stream) in statuses.into_iter().zip(listener.incoming()) {
+            let Ok(mut stream) = stream else { continue };
+            let mut buf = [0u8; 8192];
+            let read = stream.read(&mut buf).unwrap_or(0);
+            log.lock()
+                .unwrap()
+                .push(String::from_utf8_lossy(&buf[..read]).to_string());
+            let body = "{}";
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+    (port, seen)
+}
+
+fn header(request: &str, name: &str) -> String {
+    let prefix = format!("{}: ", name.to_ascii_lowercase());
+    request
+        .lines()
+        .find_map(|line| {
+            line.to_ascii_lowercase()
+                .starts_with(&prefix)
+                .then(|| line[prefix.len()..].trim().to_string())
+        })
+        .unwrap_or_default()
+}
+
+#[tokio::test]
+async fn header_templates_expand_per_attempt() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let prev_version = std::env::var("INFRA_TEST_CLIENT_VERSION").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    std::env::set_var("INFRA_TEST_CLIENT_VERSION", "7.2.0");
+
+    let security = Arc::new(Security::new().expect("security"));
+    let profile_service = Arc::new(ProfileService::new(security).expect("profile service"));
+    let manager = ApiManager::new(
+        Logger::new("test"),
+        Validation::new(),
+        profile_service,
+        None,
+        None,
+        None,
+    );
+
+    let (port, seen) = spawn_scripted_stub(vec![
+        "503 Service Unavailable",
+        "503 Service Unavailable",
+        "200 OK",
+    ]);
+    manager
+        .handle_action(json!({
+            "action": "profile_upsert",
+            "profile_name": "templated",
+            "base_url": format!("http://127.0.0.1:{}", port),
+            "headers": {
+                "X-Request-Id": "${uuid}",
+                "X-Client-Version": "${env:INFRA_TEST_CLIENT_VERSION}",
+            },
+        }))
+        .await
+        .expect("profile upsert");
+    let retry = json!({"max_attempts": 3, "base_delay_ms": 1, "max_delay_ms": 1, "jitter": 0});
+    let result = manager
+        .handle_action(json!({
+            "action": "request",
+            "profile_name": "templated",
+            "path": "/items",
+            "trace_id": "trace-tpl",
+            "headers": {"X-Trace": "${trace_id}"},
+            "query": {"ts": "${now_ms}", "tag": ["a", "${trace_id}"]},
+            "retry": retry,
+        }))
+        .await
+        .expect("request");
+    assert_eq!(result["status"], 200, "{}", result);
+    let requests = seen.lock().unwrap().clone();
+    assert_eq!(requests.len(), 3);
+    let ids: Vec<String> = requests.iter().map(|r| header(r, "X-Request-Id")).collect();
+    assert!(uuid::Uuid::parse_str(&ids[0]).is_ok(), "{:?}", ids);
+    assert_ne!(ids[0], ids[1]);
+    assert_ne!(ids[1], ids[2]);
+    assert_eq!(header(&requests[0], "X-Client-Version"), "7.2.0");
+    assert_eq!(header(&requests[0], "X-Trace"), "trace-tpl");
+    let line = requests[0].lines().next().unwrap();
+    assert!(line.contains("tag=a&tag=trace-tpl"), "{}", line);
+    assert!(!line.contains("now_ms"), "{}", line);
+
+    let (port, seen) = spawn_scripted_stub(vec!["503 Service Unavailable", "200 OK"]);
+    let mut pinned = retry.clone();
+    pinned["regenerate_on_retry"] = json!(false);
+    manager
+        .handle_action(json!({
+            "action": "request",
+            "base_url": format!("http://127.0.0.1:{}", port),
+            "path": "/items",
+            "headers": {"X-Request-Id": "${uuid}"},
+            "retry": pinned,
+        }))
+        .await
+        .expect("pinned request");
+    let requests = seen.lock().unwrap().clone();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(
+        header(&requests[0], "X-Request-Id"),
+        header(&requests[1], "X-Request-Id")
+    );
+
+    let err = manager
+        .handle_action(json!({
+            "action": "request",
+            "url": "http://127.0.0.1:9/items",
+            "headers": {"X-Request-Id": "${nonce}"},
+        }))
+        .await
+        .expect_err("unknown placeholder");
+    assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+    assert_eq!(
+        err.message,
+        "header X-Request-Id: unknown placeholder ${nonce}"
+    );
+    let err = manager
+        .handle_action(json!({
+            "action": "request",
+            "url": "http://127.0.0.1:9/items",
+            "query": {"since": "${yesterday}"},
+        }))
+        .await
+        .expect_err("unknown query placeholder");
+    assert_eq!(
+        err.message,
+        "query parameter since: unknown placeholder ${yesterday}"
+    );
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    restore_env("INFRA_TEST_CLIENT_VERSION", prev_version);
+    let _ = std::fs::remove_dir_all(&tmp_dir);
+}
@@ -0,0 +1,180 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::api::ApiManager;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use infra::utils::http_tuning::pool_snapshot;
+use serde_json::json;
+use std::io::{Read, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+// HTTP/1.1 with keep-alive: every accepted connection serves requests until the client closes it.
+fn spawn_keepalive_stub(connections: Arc<AtomicUsize>) -> u16 {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind stub");
+    let port = listener.local_addr().expect("stub addr").port();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { continue };
+            connections.fetch_add(1, Ordering::SeqCst);
+            std::thread::spawn(move || {
+                let mut pending = Vec::new();
+                let mut buf = [0u8; 4096];
+                loop {
+                    let read = match stream.read(&mut buf) {
+                        Ok(0) | Err(_) => return,
+                        Ok(read) => read,
+                    };
+                    pending.extend_from_slice(&buf[..read]);
+                    while let Some(end) = pending.windows(4).position(|w| w == b"\r\n\r\n") {
+                        pending.drain(..end + 4);
+                        let body = r#"{"ok":true}"#;
+                        let response = format!(
+                            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                            body.len(),
+                            body
+                        );
+                        if stream.write_all(response.as_bytes()).is_err() {
+                            return;
+                        }
+                    }
+                }
+            });
+        }
+    });
+    port
+}
+
+#[tokio::test]
+async fn tuned_profiles_get_their_own_clients_and_responses_report_reuse() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let keys = [
+        "INFRA_PROFILES_DIR",
+        "INFRA_HTTP2_PRIOR_KNOWLEDGE",
+        "INFRA_HTTP_POOL_MAX_IDLE_PER_HOST",
+        "INFRA_HTTP_CONNECT_TIMEOUT_MS",
+    ];
+    let previous: Vec<Option<String>> = keys.iter().map(|key| std::env::var(key).ok()).collect();
+    for key in &keys[1..] {
+        std::env::remove_var(key);
+    }
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+
+    let security = Arc::new(Security::new().expect("security"));
+    let manager = ApiManager::new(
+        Logger::new("test"),
+        Validation::new(),
+        Arc::new(ProfileService::new(security).expect("profile service")),
+        None,
+        None,
+        None,
+    );
+    let connections = Arc::new(AtomicUsize::new(0));
+    let port = spawn_keepalive_stub(connections.clone());
+    let base_url = format!("http://127.0.0.1:{}", port);
+    for (name, http) in [
+        (
+            "tuned",
+            json!({"pool_max_idle_per_host": 2, "connect_timeout_ms": 2000}),
+        ),
+        ("retuned", json!({"pool_max_idle_per_host": 4})),
+    ] {
+        manager
+            .handle_action(json!({
+                "action": "profile_upsert",
+                "profile_name": name,
+                "base_url": base_url,
+                "http": http,
+            }))
+            .await
+            .expect("profile_upsert");
+    }
+    let err = manager
+        .handle_action(json!({
+            "action": "profile_upsert",
+            "profile_name": "broken",
+            "http": {"keepalive": true},
+        }))
+        .await
+        .expect_err("unknown setting");
+    assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+
+    let request = |profile: Option<&str>| {
+        let mut args = json!({"action": "request", "url": format!("{}/ping", base_url)});
+        if let Some(profile) = profile {
+            args["profile_name"] = json!(profile);
+        }
+        manager.handle_action(args)
+    };
+    let clients = || pool_snapshot()["clients"].as_u64().unwrap();
+
+    let before = clients();
+    let first = request(None).await.expect("untuned request");
+    assert_eq!(first["data"]["ok"], true, "{}", first);
+    assert_eq!(first["http_version"], "HTTP/1.1");
+    assert_eq!(first["connection_reused"], false);
+    let second = request(None).await.expect("untuned request");
+    assert_eq!(second["connection_reused"], true);
+    assert_eq!(clients(), before + 1);
+    assert_eq!(connections.load(Ordering::SeqCst), 1);
+
+    // Each tuning set builds (and pools through) its own client; the same set reuses it.
+    let tuned = request(Some("tuned")).await.expect("tuned request");
+    assert_eq!(tuned["connection_reused"], false);
+    assert_eq!(clients(), before + 2);
+    let tuned = request(Some("tuned")).await.expect("tuned request");
+    assert_eq!(tuned["connection_reused"], true);
+    request(Some("retuned")).await.expect("retuned request");
+    assert_eq!(clients(), before + 3);
+    assert_eq!(connections.load(Ordering::SeqCst), 3);
+
+    // A per-call setting overrides the profile's and so is yet another client.
+    let mut args = json!({
+        "action": "request",
+        "profile_name": "tuned",
+        "url": format!("{}/ping", base_url),
+        "http": {"pool_max_idle_per_host": 1},
+    });
+    manager
+        .handle_action(args.clone())
+        .await
+        .expect("override request");
+    assert_eq!(clients(), before + 4);
+    args["http"] = json!({"tcp_keepalive_ms": "soon"});
+    let err = manager
+        .handle_action(args)
+        .await
+        .expect_err("invalid setting");
+    assert_eq!(
+        err.message,
+        "http.tcp_keepalive_ms must be a non-negative integer"
+    );
+
+    // Env settings apply to calls that set nothing themselves.
+    std::env::set_var("INFRA_HTTP_POOL_MAX_IDLE_PER_HOST", "3");
+    request(None).await.expect("env tuned request");
+    assert_eq!(clients(), before + 5);
+    let snapshot = pool_snapshot();
+    assert!(snapshot["tuned_clients"].as_u64().unwrap() >= 4);
+    assert!(snapshot["reused_connections"].as_u64().unwrap() >= 2);
+    assert!(snapshot["http_versions"]["HTTP/1.1"].as_u64().unwrap() >= 7);
+
+    for (key, value) in keys.iter().zip(previous) {
+        restore_env(key, value);
+    }
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
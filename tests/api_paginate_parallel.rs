@@ -0,0 +1,175 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::api::ApiManager;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use serde_json::{json, Value};
+use std::io::{Read, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+const PAGE_DELAY_MS: u64 = 150;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+fn query_param(target: &str, key: &str) -> Option<usize> {
+    let query = target.split_once('?')?.1;
+    query.split('&').find_map(|pair| {
+        let (k, v) = pair.split_once('=')?;
+        (k == key).then(|| v.parse().ok()).flatten()
+    })
+}
+
+// `/items/<total>` serves items 1..=total as `{items, total}`, paged by `page`+`limit` or
+// `offset`+`limit`; every response takes PAGE_DELAY_MS and requests are served concurrently.
+fn spawn_paged_stub() -> (u16, Arc<AtomicUsize>) {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind stub");
+    let port = listener.local_addr().expect("stub addr").port();
+    let requests = Arc::new(AtomicUsize::new(0));
+    let counter = requests.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { continue };
+            let counter = counter.clone();
+            std::thread::spawn(move || {
+                let mut buf = [0u8; 8192];
+                let read = stream.read(&mut buf).unwrap_or(0);
+                let head = String::from_utf8_lossy(&buf[..read]).to_string();
+                let target = head.split_whitespace().nth(1).unwrap_or("/").to_string();
+                counter.fetch_add(1, Ordering::SeqCst);
+                let path = target.split('?').next().unwrap_or("");
+                let total: usize = path.rsplit('/').next().unwrap_or("0").parse().unwrap_or(0);
+                let limit = query_param(&target, "limit").unwrap_or(10);
+                let start = match query_param(&target, "offset") {
+                    Some(offset) => offset,
+                    None => (query_param(&target, "page").unwrap_or(1) - 1) * limit,
+                };
+                let items: Vec<usize> = (start + 1..=total.min(start + limit)).collect();
+                std::thread::sleep(std::time::Duration::from_millis(PAGE_DELAY_MS));
+                let body = json!({"items": items, "total": total}).to_string();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes());
+            });
+        }
+    });
+    (port, requests)
+}
+
+fn numbers(to: u64) -> Value {
+    Value::Array((1..=to).map(Value::from).collect())
+}
+
+#[tokio::test]
+async fn paginate_fetches_page_and_offset_pages_in_parallel() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+
+    let security = Arc::new(Security::new().expect("security"));
+    let profile_service = Arc::new(ProfileService::new(security).expect("profile service"));
+    let manager = ApiManager::new(
+        Logger::new("test"),
+        Validation::new(),
+        profile_service,
+        None,
+        None,
+        None,
+    );
+    let (port, requests) = spawn_paged_stub();
+    let paginate = |total: usize, pagination: Value| {
+        manager.handle_action(json!({
+            "action": "paginate",
+            "base_url": format!("http://127.0.0.1:{}", port),
+            "path": format!("/items/{}", total),
+            "pagination": pagination,
+        }))
+    };
+
+    let sequential = paginate(
+        16,
+        json!({"type": "page", "size": 2, "max_pages": 8, "item_path": "data.items"}),
+    )
+    .await
+    .expect("sequential");
+    let parallel = paginate(
+        16,
+        json!({"type": "page", "size": 2, "max_pages": 8, "item_path": "data.items", "parallel": 4}),
+    )
+    .await
+    .expect("parallel");
+    assert_eq!(parallel["success"], true, "{}", parallel);
+    assert_eq!(parallel["items"], numbers(16));
+    assert_eq!(parallel["items"], sequential["items"]);
+    assert_eq!(parallel["page_count"], 8);
+    assert_eq!(parallel["concurrency"]["requested"], 4);
+    assert!(parallel["concurrency"]["effective"].as_u64().unwrap() > 1);
+    assert_eq!(sequential["concurrency"]["effective"], 1);
+    assert!(
+        parallel["duration_ms"].as_u64().unwrap() < sequential["duration_ms"].as_u64().unwrap(),
+        "parallel {} vs sequential {}",
+        parallel["duration_ms"],
+        sequential["duration_ms"]
+    );
+
+    // The first response's total plans the remaining pages.
+    let planned = paginate(
+        7,
+        json!({"type": "offset", "param": "offset", "size": 2, "total_path": "data.total", "item_path": "data.items", "parallel": 3}),
+    )
+    .await
+    .expect("total_path");
+    assert_eq!(planned["total"], 7);
+    assert_eq!(planned["planned_pages"], 4);
+    assert_eq!(planned["items"], numbers(7));
+
+    // Only 5 pages hold items: nothing past the empty 6th page is merged, and requests beyond
+    // it stop once it has been seen.
+    requests.store(0, Ordering::SeqCst);
+    let stopped = paginate(
+        10,
+        json!({"type": "page", "size": 2, "max_pages": 40, "item_path": "data.items", "parallel": 3}),
+    )
+    .await
+    .expect("stop on empty");
+    assert_eq!(stopped["items"], numbers(10));
+    assert_eq!(stopped["page_count"], 6);
+    assert!(requests.load(Ordering::SeqCst) < 12, "{:?}", requests);
+
+    for (pagination, message) in [
+        (
+            json!({"type": "cursor", "cursor_path": "data.next", "max_pages": 3, "parallel": 2}),
+            "pagination.parallel is only supported for page and offset pagination",
+        ),
+        (
+            json!({"type": "page", "parallel": 2}),
+            "pagination.parallel needs max_pages or total_path",
+        ),
+        (
+            json!({"type": "page", "max_pages": 3, "parallel": 0}),
+            "pagination.parallel must be an integer between 1 and 16",
+        ),
+    ] {
+        let err = paginate(4, pagination).await.expect_err(message);
+        assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+        assert_eq!(err.message, message);
+    }
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
@@ -0,0 +1,133 @@
+use infra::managers::api::ApiManager;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use std::io::{Read, Write};
+use std::sync::{Arc, Mutex};
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+// Serves the scripted (status line, extra headers, body) responses in order and records the
+// request line of every call.
+fn spawn_scripted_stub(
+    script: Vec<(&'static str, &'static str, &'static str)>,
+) -> (u16, Arc<Mutex<Vec<String>>>) {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind stub");
+    let port = listener.local_addr().expect("stub addr").port();
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let log = seen.clone();
+    std::thread::spawn(move || {
+        for ((status, headers, body), stream) in script.into_iter().zip(listener.incoming()) {
+            let Ok(mut stream) = stream else { continue };
+            let mut buf = [0u8; 8192];
+            let read = stream.read(&mut buf).unwrap_or(0);
+            let request = String::from_utf8_lossy(&buf[..read]);
+            log.lock()
+                .unwrap()
+                .push(request.lines().next().unwrap_or("").to_string());
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Type: application/json\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                headers,
+                body.len(),
+                body
+            );
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+    (port, seen)
+}
+
+#[tokio::test]
+async fn pagination_waits_on_rate_limits_without_consuming_throttled_pages() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+
+    let security = Arc::new(Security::new().expect("security"));
+    let profile_service = Arc::new(ProfileService::new(security).expect("profile service"));
+    let manager = ApiManager::new(
+        Logger::new("test"),
+        Validation::new(),
+        profile_service,
+        None,
+        None,
+        None,
+    );
+
+    let (port, seen) = spawn_scripted_stub(vec![
+        (
+            "200 OK",
+            "Quota-Left: 0\r\nQuota-Reset: 30\r\n",
+            r#"{"items":[1]}"#,
+        ),
+        (
+            "429 Too Many Requests",
+            "Retry-After: 30\r\n",
+            r#"{"error":"slow down"}"#,
+        ),
+        ("200 OK", "Quota-Left: 9\r\n", r#"{"items":[2]}"#),
+    ]);
+    let result = manager
+        .handle_action(serde_json::json!({
+            "action": "paginate",
+            "base_url": format!("http://127.0.0.1:{}", port),
+            "path": "/items",
+            "retry": {"enabled": false},
+            "pagination": {
+                "type": "page",
+                "max_pages": 2,
+                "item_path": "data.items",
+                "rate_limit": {
+                    "remaining_header": "Quota-Left",
+                    "reset_header": "Quota-Reset",
+                    "max_wait_ms": 20,
+                },
+            },
+        }))
+        .await
+        .expect("paginate");
+    assert_eq!(result["success"], true, "{}", result);
+    assert_eq!(result["page_count"], 2, "{}", result);
+    assert_eq!(result["items"], serde_json::json!([1, 2]));
+    assert_eq!(result["pacing"]["waits"], 2);
+    assert_eq!(result["pacing"]["wait_ms_total"], 40);
+    assert_eq!(result["pacing"]["rate_limited"], true);
+    let requests = seen.lock().unwrap().clone();
+    assert_eq!(requests.len(), 3);
+    assert!(requests[0].contains("page=1"), "{:?}", requests);
+    assert!(requests[1].contains("page=2"), "{:?}", requests);
+    assert!(requests[2].contains("page=2"), "{:?}", requests);
+
+    let (port, _) = spawn_scripted_stub(vec![
+        ("200 OK", "", r#"{"items":[1]}"#),
+        ("200 OK", "", r#"{"items":[2]}"#),
+    ]);
+    let result = manager
+        .handle_action(serde_json::json!({
+            "action": "paginate",
+            "base_url": format!("http://127.0.0.1:{}", port),
+            "path": "/items",
+            "pagination": {"type": "page", "max_pages": 2, "item_path": "data.items", "min_interval_ms": 30},
+        }))
+        .await
+        .expect("paced paginate");
+    assert_eq!(result["page_count"], 2);
+    assert_eq!(result["pacing"]["waits"], 1);
+    assert_eq!(result["pacing"]["rate_limited"], false);
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
@@ -0,0 +1,142 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::api::ApiManager;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use std::io::{Read, Write};
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+fn spawn_http_stub(responses: usize) -> u16 {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind stub");
+    let port = listener.local_addr().expect("stub addr").port();
+    std::thread::spawn(move || {
+        for stream in listener.incoming().take(responses) {
+            let Ok(mut stream) = stream else { continue };
+            let mut buf = [0u8; 8192];
+            let _ = stream.read(&mut buf);
+            let body = r#"{"items":[1],"password":"server-side-secret"}"#;
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nSet-Cookie: session=abcdef123456\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+    port
+}
+
+#[tokio::test]
+async fn recorded_requests_append_redacted_entries_per_trace() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let prev_context = std::env::var("INFRA_CONTEXT_REPO_ROOT").ok();
+    let prev_record = std::env::var("INFRA_API_RECORD").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    std::env::set_var("INFRA_CONTEXT_REPO_ROOT", &tmp_dir);
+    std::env::remove_var("INFRA_API_RECORD");
+
+    let security = Arc::new(Security::new().expect("security"));
+    let profile_service = Arc::new(ProfileService::new(security).expect("profile service"));
+    let manager = ApiManager::new(
+        Logger::new("test"),
+        Validation::new(),
+        profile_service,
+        None,
+        None,
+        None,
+    );
+    let port = spawn_http_stub(3);
+    let base_url = format!("http://127.0.0.1:{}", port);
+
+    let response = manager
+        .handle_action(serde_json::json!({
+            "action": "request",
+            "base_url": base_url,
+            "path": "/items",
+            "method": "POST",
+            "headers": { "Cookie": "sid=browser-cookie-value" },
+            "auth": { "type": "bearer", "token": "bearer-token-value" },
+            "body": { "name": "demo", "api_key": "body-secret-value" },
+            "record": true,
+            "trace_id": "trace-rec-1",
+        }))
+        .await
+        .expect("recorded request");
+    assert_eq!(response["status"], 200);
+    assert_eq!(response["recording"]["entries"], 1);
+
+    manager
+        .handle_action(serde_json::json!({
+            "action": "paginate",
+            "base_url": base_url,
+            "path": "/items",
+            "pagination": { "type": "page", "max_pages": 2 },
+            "record": true,
+            "trace_id": "trace-rec-1",
+        }))
+        .await
+        .expect("recorded pagination");
+
+    let recording = manager
+        .handle_action(serde_json::json!({
+            "action": "recording_get",
+            "recording_trace_id": "trace-rec-1",
+        }))
+        .await
+        .expect("recording ref");
+    assert_eq!(
+        recording["recording"]["uri"],
+        "artifact://runs/trace-rec-1/api_recording.har.json"
+    );
+    assert_eq!(recording["recording"]["entries"], 3);
+
+    let raw =
+        std::fs::read_to_string(tmp_dir.join("artifacts/runs/trace-rec-1/api_recording.har.json"))
+            .expect("read recording");
+    for secret in [
+        "bearer-token-value",
+        "browser-cookie-value",
+        "body-secret-value",
+        "server-side-secret",
+        "abcdef123456",
+    ] {
+        assert!(!raw.contains(secret), "recording leaks {}", secret);
+    }
+    let har: serde_json::Value = serde_json::from_str(&raw).expect("parse recording");
+    let entries = har["log"]["entries"].as_array().expect("entries");
+    assert_eq!(entries[0]["request"]["method"], "POST");
+    assert!(entries[0]["request"]["postData"]["text"]
+        .as_str()
+        .unwrap_or("")
+        .contains("demo"));
+    assert_eq!(entries[1]["_action"], "paginate");
+    assert_eq!(entries[2]["response"]["status"], 200);
+
+    let err = manager
+        .handle_action(serde_json::json!({
+            "action": "recording_get",
+            "recording_trace_id": "trace-missing",
+        }))
+        .await
+        .expect_err("no recording for unknown trace");
+    assert_eq!(err.kind, ToolErrorKind::NotFound);
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    restore_env("INFRA_CONTEXT_REPO_ROOT", prev_context);
+    restore_env("INFRA_API_RECORD", prev_record);
+}
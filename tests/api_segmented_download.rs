@@ -0,0 +1,217 @@
+use infra::managers::api::ApiManager;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::io::{Read, Write};
+use std::sync::{Arc, Mutex};
+
+mod common;
+use common::ENV_LOCK;
+
+const SIZE: usize = 100_000;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+fn payload() -> Vec<u8> {
+    (0..SIZE).map(|i| (i % 251) as u8).collect()
+}
+
+fn read_head(stream: &mut std::net::TcpStream) -> String {
+    let mut head = Vec::new();
+    let mut byte = [0u8; 1];
+    while !head.ends_with(b"\r\n\r\n") {
+        match stream.read(&mut byte) {
+            Ok(1) => head.push(byte[0]),
+            _ => break,
+        }
+    }
+    String::from_utf8_lossy(&head).to_string()
+}
+
+// `/ranged` and `/flaky` serve byte ranges (`/flaky` cuts off the first range that starts at 0
+// half way); `/plain` ignores Range and never advertises it. Every Range header is recorded.
+fn spawn_range_stub() -> (u16, Arc<Mutex<Vec<String>>>) {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind stub");
+    let port = listener.local_addr().expect("stub addr").port();
+    let ranges = Arc::new(Mutex::new(Vec::new()));
+    let seen = ranges.clone();
+    let cut_once = Arc::new(Mutex::new(false));
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { continue };
+            let seen = seen.clone();
+            let cut_once = cut_once.clone();
+            std::thread::spawn(move || {
+                let head = read_head(&mut stream);
+                let mut words = head.split_whitespace();
+                let method = words.next().unwrap_or("").to_string();
+                let path = words.next().unwrap_or("/").to_string();
+                let range = head.lines().find_map(|line| {
+                    let (name, value) = line.split_once(':')?;
+                    name.eq_ignore_ascii_case("range")
+                        .then(|| value.trim().to_string())
+                });
+                let body = payload();
+                let ranged = path != "/plain";
+                let accept = if ranged {
+                    "Accept-Ranges: bytes\r\n"
+                } else {
+                    ""
+                };
+                if method == "HEAD" {
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n",
+                        accept, SIZE
+                    );
+                    let _ = stream.write_all(response.as_bytes());
+                    return;
+                }
+                let (status, slice, extra) = match range.filter(|_| ranged) {
+                    Some(range) => {
+                        seen.lock().unwrap().push(format!("{} {}", path, range));
+                        let (start, end) = range
+                            .trim_start_matches("bytes=")
+                            .split_once('-')
+                            .map(|(a, b)| {
+                                (a.parse::<usize>().unwrap(), b.parse::<usize>().unwrap())
+                            })
+                            .unwrap();
+                        (
+                            "206 Partial Content",
+                            body[start..=end].to_vec(),
+                            format!("Content-Range: bytes {}-{}/{}\r\n", start, end, SIZE),
+                        )
+                    }
+                    None => ("200 OK", body, String::new()),
+                };
+                let response = format!(
+                    "HTTP/1.1 {}\r\n{}{}Content-Length: {}\r\nConnection: close\r\n\r\n",
+                    status,
+                    accept,
+                    extra,
+                    slice.len()
+                );
+                let _ = stream.write_all(response.as_bytes());
+                let mut cut = cut_once.lock().unwrap();
+                if path == "/flaky" && extra.contains("bytes 0-") && !*cut {
+                    *cut = true;
+                    let _ = stream.write_all(&slice[..slice.len() / 2]);
+                    return;
+                }
+                drop(cut);
+                let _ = stream.write_all(&slice);
+            });
+        }
+    });
+    (port, ranges)
+}
+
+#[tokio::test]
+async fn download_fetches_ranges_in_parallel_and_retries_only_the_failed_one() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let prev_limit = std::env::var("INFRA_API_MAX_CONNECTIONS_PER_HOST").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    std::env::set_var("INFRA_API_MAX_CONNECTIONS_PER_HOST", "2");
+
+    let security = Arc::new(Security::new().expect("security"));
+    let manager = ApiManager::new(
+        Logger::new("test"),
+        Validation::new(),
+        Arc::new(ProfileService::new(security).expect("profile service")),
+        None,
+        None,
+        None,
+    );
+    let (port, ranges) = spawn_range_stub();
+    let expected_sha = hex::encode(Sha256::digest(payload()));
+    let download = |path: &str, name: &str, extra: Value| {
+        let mut args = json!({
+            "action": "download",
+            "url": format!("http://127.0.0.1:{}{}", port, path),
+            "download_path": tmp_dir.join(name),
+            "segments": 4,
+            "retry": {"max_attempts": 3, "base_delay_ms": 1, "max_delay_ms": 2, "jitter": 0},
+        });
+        args.as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        manager.handle_action(args)
+    };
+
+    let result = download(
+        "/ranged",
+        "ranged.bin",
+        json!({"expect_sha256": expected_sha}),
+    )
+    .await
+    .expect("segmented download");
+    assert_eq!(result["bytes"], SIZE as u64, "{}", result);
+    assert_eq!(result["sha256"], expected_sha);
+    assert_eq!(result["segmented"]["used"], 4);
+    assert_eq!(result["segmented"]["per_host_limit"], 2);
+    assert!(result["segmented"]["peak_in_flight"].as_u64().unwrap() <= 2);
+    let segments = result["segments"].as_array().unwrap();
+    assert_eq!(segments[0]["start"], 0);
+    assert_eq!(segments[3]["end"], SIZE as u64 - 1);
+    assert!(segments
+        .iter()
+        .all(|s| s["throughput_bytes_per_s"].is_u64()));
+    assert_eq!(
+        std::fs::read(tmp_dir.join("ranged.bin")).unwrap(),
+        payload()
+    );
+    assert!(!tmp_dir.join("ranged.part").exists());
+
+    // Only the cut-off range is fetched again, starting after the bytes it already wrote.
+    let flaky = download("/flaky", "flaky.bin", json!({}))
+        .await
+        .expect("flaky download");
+    assert_eq!(flaky["segments"][0]["attempts"], 2, "{}", flaky);
+    assert_eq!(flaky["segments"][1]["attempts"], 1);
+    let flaky_ranges: Vec<String> = ranges
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|r| r.starts_with("/flaky"))
+        .cloned()
+        .collect();
+    assert_eq!(flaky_ranges.len(), 5, "{:?}", flaky_ranges);
+    assert!(flaky_ranges.contains(&"/flaky bytes=12500-24999".to_string()));
+    assert_eq!(std::fs::read(tmp_dir.join("flaky.bin")).unwrap(), payload());
+
+    let plain = download("/plain", "plain.bin", json!({}))
+        .await
+        .expect("single-stream fallback");
+    assert_eq!(plain["segmented"]["used"], false);
+    assert!(plain["segments"].is_null());
+    assert_eq!(std::fs::read(tmp_dir.join("plain.bin")).unwrap(), payload());
+
+    for (path, name) in [("/ranged", "bad-ranged.bin"), ("/plain", "bad-plain.bin")] {
+        let err = download(path, name, json!({"expect_sha256": "0".repeat(64)}))
+            .await
+            .expect_err("checksum mismatch");
+        assert_eq!(err.code, "CHECKSUM_MISMATCH");
+        assert_eq!(err.details.unwrap()["actual"], expected_sha);
+        assert!(!tmp_dir.join(name).exists());
+    }
+    let err = download("/ranged", "x.bin", json!({"segments": 17}))
+        .await
+        .expect_err("too many segments");
+    assert_eq!(err.message, "segments must be an integer between 1 and 16");
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    restore_env("INFRA_API_MAX_CONNECTIONS_PER_HOST", prev_limit);
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
@@ -0,0 +1,227 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::api::ApiManager;
+use infra::managers::pipeline::PipelineManager;
+use infra::managers::postgres::PostgresManager;
+use infra::managers::ssh::SshManager;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use serde_json::{json, Value};
+use std::io::{Read, Write};
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+// `/fast` answers at once, `/slow` holds every second response for 400 ms and `/flaky`
+// alternates 200 and 503.
+fn spawn_stub() -> u16 {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind stub");
+    let port = listener.local_addr().expect("stub addr").port();
+    std::thread::spawn(move || {
+        let mut served = 0usize;
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { continue };
+            let mut buf = [0u8; 4096];
+            let read = stream.read(&mut buf).unwrap_or(0);
+            let head = String::from_utf8_lossy(&buf[..read]).to_string();
+            let path = head.split_whitespace().nth(1).unwrap_or("/").to_string();
+            served += 1;
+            let status = match path.as_str() {
+                "/slow" if served.is_multiple_of(2) => {
+                    std::thread::sleep(std::time::Duration::from_millis(400));
+                    "200 OK"
+                }
+                "/flaky" if served.is_multiple_of(2) => "503 Service Unavailable",
+                _ => "200 OK",
+            };
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok",
+                status
+            );
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+    port
+}
+
+fn pipeline(api: ApiManager, profile_service: Arc<ProfileService>) -> PipelineManager {
+    let logger = Logger::new("test");
+    let security = Arc::new(Security::new().expect("security"));
+    let ssh = SshManager::new(
+        logger.clone(),
+        security,
+        Validation::new(),
+        profile_service.clone(),
+        None,
+        None,
+        None,
+    );
+    let postgres = PostgresManager::new(
+        logger.clone(),
+        Validation::new(),
+        profile_service,
+        None,
+        None,
+    );
+    PipelineManager::new(
+        logger,
+        Validation::new(),
+        Arc::new(api),
+        Arc::new(ssh),
+        Arc::new(postgres),
+        None,
+        None,
+        None,
+        None,
+    )
+}
+
+fn violations(result: &Value) -> Vec<String> {
+    result["sla"]["violations"]
+        .as_array()
+        .expect("violations")
+        .iter()
+        .map(|v| v.as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn smoke_http_samples_and_judges_against_the_sla() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+
+    let security = Arc::new(Security::new().expect("security"));
+    let profile_service = Arc::new(ProfileService::new(security).expect("profile service"));
+    let api = ApiManager::new(
+        Logger::new("test"),
+        Validation::new(),
+        profile_service.clone(),
+        None,
+        None,
+        None,
+    );
+    let base = format!("http://127.0.0.1:{}", spawn_stub());
+    let smoke = |path: &str, extra: Value| {
+        let mut args = json!({"action": "smoke_http", "url": format!("{}{}", base, path)});
+        args.as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        api.handle_action(args)
+    };
+
+    // Without samples/sla the single-probe result is unchanged.
+    let single = smoke("/fast", json!({})).await.expect("single probe");
+    assert_eq!(single["ok"], true);
+    assert!(single.get("samples_run").is_none());
+
+    let fast = smoke("/fast", json!({"samples": 4, "interval_ms": 0}))
+        .await
+        .expect("fast samples");
+    assert_eq!(fast["ok"], true, "{}", fast);
+    assert_eq!(
+        (&fast["samples_planned"], &fast["samples_run"]),
+        (&json!(4), &json!(4))
+    );
+    assert_eq!(fast["stats"]["ok"], 4);
+    assert_eq!(fast["stats"]["success_ratio"], 1.0);
+    assert_eq!(fast["results"].as_array().unwrap().len(), 4);
+    for key in ["min_ms", "avg_ms", "p95_ms", "max_ms"] {
+        assert!(fast["stats"][key].is_u64(), "{}", key);
+    }
+
+    let slow = smoke(
+        "/slow",
+        json!({"samples": 4, "interval_ms": 0, "sla": {"max_p95_ms": 300}}),
+    )
+    .await
+    .expect("slow samples");
+    assert_eq!(slow["ok"], false);
+    assert_eq!(slow["stats"]["ok"], 4, "every probe answered 200");
+    assert!(slow["stats"]["p95_ms"].as_u64().unwrap() >= 400);
+    assert!(violations(&slow)[0].starts_with("p95_ms "));
+
+    let flaky = smoke("/flaky", json!({"samples": 4, "interval_ms": 0}))
+        .await
+        .expect("flaky samples");
+    assert_eq!(flaky["ok"], false);
+    assert_eq!(flaky["stats"]["failed"], 2);
+    assert_eq!(
+        violations(&flaky),
+        ["success_ratio 0.500 < min_success_ratio 1"]
+    );
+    let tolerated = smoke(
+        "/flaky",
+        json!({"samples": 4, "interval_ms": 0, "sla": {"min_success_ratio": 0.5}}),
+    )
+    .await
+    .expect("tolerated flakiness");
+    assert_eq!(tolerated["ok"], true, "{}", tolerated);
+
+    // A sample only starts when it can finish, at its timeout, inside the budget.
+    let limited = smoke(
+        "/fast",
+        json!({"samples": 10, "interval_ms": 200, "timeout_ms": 1000, "budget_ms": 1500}),
+    )
+    .await
+    .expect("budget limited");
+    assert_eq!(limited["samples_planned"], 10);
+    assert!(limited["samples_run"].as_u64().unwrap() < 10);
+    assert_eq!(limited["budget_limited"], true);
+    assert_eq!(limited["ok"], true);
+
+    for (extra, message) in [
+        (
+            json!({"samples": 0}),
+            "samples must be an integer between 1 and 100",
+        ),
+        (json!({"sla": {"p95": 10}}), "unknown sla key 'p95'"),
+        (
+            json!({"sla": {"min_success_ratio": 2}}),
+            "sla.min_success_ratio must be a number between 0 and 1",
+        ),
+    ] {
+        let err = smoke("/fast", extra.clone()).await.expect_err(message);
+        assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+        assert_eq!(err.message, message);
+
+        // deploy_smoke checks the same options before it deploys anything.
+        let mut args = json!({
+            "action": "deploy_smoke",
+            "local_path": tmp_dir.join("missing.bin"),
+            "remote_path": "/srv/app/app.bin",
+            "url": format!("{}/fast", base),
+        });
+        args.as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        let api = ApiManager::new(
+            Logger::new("test"),
+            Validation::new(),
+            profile_service.clone(),
+            None,
+            None,
+            None,
+        );
+        let err = pipeline(api, profile_service.clone())
+            .handle_action(args)
+            .await
+            .expect_err(message);
+        assert_eq!(err.message, message);
+    }
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
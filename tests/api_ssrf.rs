@@ -0,0 +1,271 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::api::ApiManager;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use serde_json::json;
+use std::io::{Read, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+// reqwest honors these unless the client opts out; cleared so no CI proxy decides the outcome.
+const PROXY_VARS: &[&str] = &[
+    "HTTP_PROXY",
+    "HTTPS_PROXY",
+    "ALL_PROXY",
+    "NO_PROXY",
+    "http_proxy",
+    "https_proxy",
+    "all_proxy",
+    "no_proxy",
+];
+
+fn clear_proxy_env() -> Vec<Option<String>> {
+    PROXY_VARS
+        .iter()
+        .map(|key| {
+            let previous = std::env::var(key).ok();
+            std::env::remove_var(key);
+            previous
+        })
+        .collect()
+}
+
+fn restore_proxy_env(previous: Vec<Option<String>>) {
+    for (key, value) in PROXY_VARS.iter().zip(previous) {
+        restore_env(key, value);
+    }
+}
+
+fn manager() -> ApiManager {
+    let security = Arc::new(Security::new().expect("security"));
+    let profile_service = Arc::new(ProfileService::new(security).expect("profile service"));
+    ApiManager::new(
+        Logger::new("test"),
+        Validation::new(),
+        profile_service,
+        None,
+        None,
+        None,
+    )
+}
+
+// Answers every request with `head` (status line plus headers) and counts the hits.
+fn spawn_stub(ip: &str, head: impl Fn(u16) -> String + Send + 'static) -> (u16, Arc<AtomicUsize>) {
+    let listener = std::net::TcpListener::bind((ip, 0)).expect("bind stub");
+    let port = listener.local_addr().expect("stub addr").port();
+    let hits = Arc::new(AtomicUsize::new(0));
+    let counter = hits.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { continue };
+            counter.fetch_add(1, Ordering::SeqCst);
+            let mut buf = [0u8; 8192];
+            let _ = stream.read(&mut buf);
+            let response = format!(
+                "{}\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok",
+                head(port)
+            );
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+    (port, hits)
+}
+
+#[tokio::test]
+async fn private_targets_are_denied_directly_and_after_redirects() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let prev_deny = std::env::var("INFRA_HTTP_DENY_PRIVATE").ok();
+    let prev_allow = std::env::var("INFRA_HTTP_ALLOW_HOSTS").ok();
+    let prev_proxy = clear_proxy_env();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    std::env::set_var("INFRA_HTTP_DENY_PRIVATE", "1");
+    // 127.0.0.2 stands in for a public host; the rest of 127/8 stays internal.
+    std::env::set_var("INFRA_HTTP_ALLOW_HOSTS", "127.0.0.2");
+
+    let manager = manager();
+
+    let (private_port, private_hits) = spawn_stub("127.0.0.1", |_| "HTTP/1.1 200 OK".to_string());
+    let (public_port, public_hits) = spawn_stub("127.0.0.2", move |_| {
+        format!(
+            "HTTP/1.1 302 Found\r\nLocation: http://127.0.0.1:{}/secret",
+            private_port
+        )
+    });
+    let (named_port, _) = spawn_stub("127.0.0.2", move |_| {
+        format!(
+            "HTTP/1.1 302 Found\r\nLocation: http://localhost:{}/secret",
+            private_port
+        )
+    });
+
+    let err = manager
+        .handle_action(json!({
+            "action": "request",
+            "url": format!("http://127.0.0.2:{}/start", public_port),
+        }))
+        .await
+        .expect_err("redirect into loopback literal");
+    assert_eq!(err.kind, ToolErrorKind::Denied);
+    assert_eq!(err.code, "HTTP_TARGET_DENIED");
+    let details = err.details.as_ref().expect("details");
+    assert_eq!(details["ip"], "127.0.0.1");
+    assert_eq!(details["rule"], "loopback");
+    assert_eq!(public_hits.load(Ordering::SeqCst), 1);
+
+    // A redirect to a name is checked on the addresses the resolver returns.
+    let err = manager
+        .handle_action(json!({
+            "action": "request",
+            "url": format!("http://127.0.0.2:{}/start", named_port),
+        }))
+        .await
+        .expect_err("redirect into a name resolving to loopback");
+    assert_eq!(err.code, "HTTP_TARGET_DENIED");
+    assert_eq!(err.details.as_ref().unwrap()["host"], "localhost");
+    assert_eq!(err.details.as_ref().unwrap()["rule"], "loopback");
+
+    let err = manager
+        .handle_action(json!({
+            "action": "smoke_http",
+            "url": format!("http://127.0.0.2:{}/start", public_port),
+        }))
+        .await
+        .expect_err("smoke_http redirect into loopback");
+    assert_eq!(err.code, "HTTP_TARGET_DENIED");
+
+    let err = manager
+        .handle_action(json!({
+            "action": "request",
+            "url": "http://169.254.169.254/latest/meta-data/",
+        }))
+        .await
+        .expect_err("metadata literal");
+    assert_eq!(err.details.as_ref().unwrap()["rule"], "metadata");
+    assert_eq!(private_hits.load(Ordering::SeqCst), 0);
+
+    // A profile can opt out of the env default.
+    manager
+        .handle_action(json!({
+            "action": "profile_upsert",
+            "profile_name": "internal",
+            "base_url": format!("http://127.0.0.1:{}", private_port),
+            "ssrf": { "deny_private": false },
+        }))
+        .await
+        .expect("profile upsert");
+    let result = manager
+        .handle_action(json!({
+            "action": "request",
+            "profile_name": "internal",
+            "path": "/secret",
+        }))
+        .await
+        .expect("profile override");
+    assert_eq!(result["status"], 200);
+    assert_eq!(private_hits.load(Ordering::SeqCst), 1);
+
+    restore_proxy_env(prev_proxy);
+    restore_env("INFRA_HTTP_ALLOW_HOSTS", prev_allow);
+    restore_env("INFRA_HTTP_DENY_PRIVATE", prev_deny);
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    let _ = std::fs::remove_dir_all(&tmp_dir);
+}
+
+#[tokio::test]
+async fn proxied_targets_are_resolved_and_checked_before_they_reach_the_proxy() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let prev_deny = std::env::var("INFRA_HTTP_DENY_PRIVATE").ok();
+    let prev_allow = std::env::var("INFRA_HTTP_ALLOW_HOSTS").ok();
+    let prev_proxy = clear_proxy_env();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    std::env::set_var("INFRA_HTTP_DENY_PRIVATE", "1");
+    std::env::set_var("INFRA_HTTP_ALLOW_HOSTS", "127.0.0.2");
+    let manager = manager();
+
+    let (private_port, private_hits) = spawn_stub("127.0.0.1", |_| "HTTP/1.1 200 OK".to_string());
+    let (proxy_port, proxy_hits) = spawn_stub("127.0.0.1", |_| "HTTP/1.1 200 OK".to_string());
+    let proxy = format!("http://127.0.0.1:{}", proxy_port);
+    let (redirecting_port, redirecting_hits) = spawn_stub("127.0.0.1", move |_| {
+        format!(
+            "HTTP/1.1 302 Found\r\nLocation: http://localhost:{}/secret",
+            private_port
+        )
+    });
+
+    // The proxy sits on loopback and is reached by name: its endpoint is exempt from the guard.
+    let result = manager
+        .handle_action(json!({
+            "action": "request",
+            "url": "http://127.0.0.2:9/public",
+            "proxy": format!("http://localhost:{}", proxy_port),
+        }))
+        .await
+        .expect("allowed target through the proxy");
+    assert_eq!(result["status"], 200);
+    assert_eq!(proxy_hits.load(Ordering::SeqCst), 1);
+
+    // A name resolving to loopback never reaches the proxy.
+    let err = manager
+        .handle_action(json!({
+            "action": "request",
+            "url": format!("http://localhost:{}/secret", private_port),
+            "proxy": proxy,
+        }))
+        .await
+        .expect_err("proxied name resolving to loopback");
+    assert_eq!(err.code, "HTTP_TARGET_DENIED");
+    assert_eq!(err.details.as_ref().unwrap()["host"], "localhost");
+    assert_eq!(err.details.as_ref().unwrap()["rule"], "loopback");
+    assert_eq!(proxy_hits.load(Ordering::SeqCst), 1);
+
+    // Nor does a redirect hop to one.
+    let err = manager
+        .handle_action(json!({
+            "action": "request",
+            "url": "http://127.0.0.2:9/start",
+            "proxy": format!("http://127.0.0.1:{}", redirecting_port),
+        }))
+        .await
+        .expect_err("proxied redirect into loopback");
+    assert_eq!(err.code, "HTTP_TARGET_DENIED");
+    assert_eq!(redirecting_hits.load(Ordering::SeqCst), 1);
+
+    // With the guard on, a proxy from the environment is not used at all.
+    std::env::set_var("HTTP_PROXY", &proxy);
+    let err = manager
+        .handle_action(json!({
+            "action": "request",
+            "url": format!("http://localhost:{}/secret", private_port),
+        }))
+        .await
+        .expect_err("environment proxy");
+    assert_eq!(err.code, "HTTP_TARGET_DENIED");
+    assert_eq!(proxy_hits.load(Ordering::SeqCst), 1);
+    assert_eq!(private_hits.load(Ordering::SeqCst), 0);
+
+    restore_proxy_env(prev_proxy);
+    restore_env("INFRA_HTTP_ALLOW_HOSTS", prev_allow);
+    restore_env("INFRA_HTTP_DENY_PRIVATE", prev_deny);
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    let _ = std::fs::remove_dir_all(&tmp_dir);
+}
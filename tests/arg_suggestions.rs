@@ -0,0 +1,116 @@
+use infra::app::App;
+use infra::errors::ToolErrorKind;
+use serde_json::{json, Value};
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+async fn rejected(app: &App, tool: &str, args: Value) -> infra::errors::ToolError {
+    let err = app
+        .tool_executor
+        .execute(tool, args)
+        .await
+        .expect_err("typo should fail");
+    assert_eq!(err.kind, ToolErrorKind::InvalidParams, "{}", err.message);
+    err
+}
+
+#[tokio::test]
+async fn misspelled_required_args_are_named_in_the_error() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let prev_strict = std::env::var("INFRA_STRICT_ARGS").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    std::env::remove_var("INFRA_STRICT_ARGS");
+    let app = App::initialize().expect("app");
+    let local = tmp_dir.join("app.bin");
+    std::fs::write(&local, b"bin").expect("write local file");
+
+    let err = rejected(
+        &app,
+        "ssh",
+        json!({
+            "action": "deploy_file",
+            "apply": true,
+            "connection": {"host": "127.0.0.1", "port": 1, "username": "deploy", "password": "x"},
+            "local_path": local.to_string_lossy(),
+            "remot_path": "/srv/app/app.bin",
+            "colour": "blue",
+        }),
+    )
+    .await;
+    let details = err.details.as_ref().expect("details");
+    assert_eq!(
+        details["did_you_mean"],
+        json!({"remot_path": "remote_path"})
+    );
+    assert_eq!(
+        details["unrecognized_args"],
+        json!(["colour", "remot_path"])
+    );
+    assert!(
+        err.hint
+            .as_deref()
+            .unwrap_or("")
+            .contains("remote_path (not remot_path)"),
+        "{:?}",
+        err.hint
+    );
+
+    let err = rejected(
+        &app,
+        "api",
+        json!({"action": "request", "method": "GET", "uri": "http://127.0.0.1:1/health"}),
+    )
+    .await;
+    assert_eq!(
+        err.details.as_ref().expect("details")["did_you_mean"],
+        json!({"uri": "url"}),
+        "{}",
+        err.message
+    );
+
+    let err = rejected(
+        &app,
+        "psql",
+        json!({
+            "action": "select",
+            "connection_url": "postgres://app@127.0.0.1:1/app",
+            "tabel": "users",
+        }),
+    )
+    .await;
+    assert_eq!(
+        err.details.as_ref().expect("details")["did_you_mean"],
+        json!({"tabel": "table"}),
+        "{}",
+        err.message
+    );
+
+    // Failures without unknown keys are left as the handler reported them.
+    let err = rejected(
+        &app,
+        "psql",
+        json!({"action": "select", "connection_url": "postgres://app@127.0.0.1:1/app"}),
+    )
+    .await;
+    assert!(err
+        .details
+        .as_ref()
+        .and_then(|details| details.get("unrecognized_args"))
+        .is_none());
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    restore_env("INFRA_STRICT_ARGS", prev_strict);
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
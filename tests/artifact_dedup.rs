@@ -0,0 +1,124 @@
+use infra::managers::artifacts::ArtifactManager;
+use infra::services::logger::Logger;
+use infra::utils::artifacts::{
+    build_run_file_ref, create_artifact_write_stream, resolve_blob_path, write_text_artifact,
+};
+use serde_json::json;
+use sha2::Digest;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+fn sha256(content: &str) -> String {
+    hex::encode(sha2::Sha256::digest(content.as_bytes()))
+}
+
+fn write(root: &Path, trace: &str, content: &str) -> std::path::PathBuf {
+    let reference = build_run_file_ref(Some(trace), "stdout.log").expect("ref");
+    write_text_artifact(root, &reference, content)
+        .expect("write artifact")
+        .path
+}
+
+#[tokio::test]
+async fn identical_artifacts_share_one_refcounted_blob() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_context = std::env::var("INFRA_CONTEXT_REPO_ROOT").ok();
+    let prev_dedup = std::env::var("INFRA_ARTIFACT_DEDUP").ok();
+    let root = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&root).expect("create temp dir");
+    std::env::set_var("INFRA_CONTEXT_REPO_ROOT", &root);
+    let manager = ArtifactManager::new(Logger::new("test"));
+    let output = "line\n".repeat(10_000);
+    let blob = resolve_blob_path(&root, &sha256(&output));
+
+    // Off by default: plain files, no blob store.
+    std::env::remove_var("INFRA_ARTIFACT_DEDUP");
+    let plain = write(&root, "plain", &output);
+    assert_eq!(std::fs::metadata(&plain).unwrap().nlink(), 1);
+    assert!(!blob.exists());
+
+    std::env::set_var("INFRA_ARTIFACT_DEDUP", "1");
+    let first = write(&root, "run-1", &output);
+    let second = write(&root, "run-2", &output);
+    let reference = build_run_file_ref(Some("run-3"), "stdout.log").expect("ref");
+    let mut stream = create_artifact_write_stream(&root, &reference)
+        .await
+        .expect("stream");
+    stream.write(output.as_bytes()).await.expect("chunk");
+    let third = stream.finalize().await.expect("finalize").path;
+    let blob_meta = std::fs::metadata(&blob).expect("blob stored once");
+    assert_eq!(blob_meta.nlink(), 4, "blob + three refs");
+    for path in [&first, &second, &third] {
+        assert_eq!(std::fs::metadata(path).unwrap().ino(), blob_meta.ino());
+    }
+
+    // Refs read as before, and the blob store stays out of listings.
+    let got = manager
+        .handle_action(
+            json!({"action": "get", "uri": "artifact://runs/run-2/stdout.log", "max_bytes": 5}),
+        )
+        .await
+        .expect("get");
+    assert_eq!(got["content"], "line\n");
+    assert_eq!(got["file_bytes"], output.len());
+    let listed = manager
+        .handle_action(json!({"action": "list"}))
+        .await
+        .expect("list");
+    assert_eq!(listed["total"], 4, "{}", listed);
+
+    // Deleting refs counts down; the last one takes the blob with it.
+    for (trace, refs) in [("run-1", 2), ("run-2", 1), ("run-3", 0)] {
+        let deleted = manager
+            .handle_action(json!({"action": "delete", "rel": format!("runs/{}/stdout.log", trace)}))
+            .await
+            .expect("delete");
+        assert_eq!(deleted["blob"]["refs"], refs, "{}", deleted);
+        assert_eq!(deleted["blob"]["removed"], refs == 0);
+    }
+    assert!(!blob.exists());
+    let deleted = manager
+        .handle_action(json!({"action": "delete", "rel": "runs/plain/stdout.log"}))
+        .await
+        .expect("delete plain");
+    assert!(deleted.get("blob").is_none());
+
+    // A blob whose length disagrees with its name is never linked to.
+    let suspicious = "different content";
+    let clash = resolve_blob_path(&root, &sha256("clash\n"));
+    std::fs::write(&clash, suspicious).expect("plant blob");
+    let kept = write(&root, "clash", "clash\n");
+    assert_eq!(std::fs::metadata(&kept).unwrap().nlink(), 1);
+    assert_eq!(std::fs::read_to_string(&clash).unwrap(), suspicious);
+
+    // Rewriting a ref orphans its old blob; gc sweeps only blobs without refs.
+    write(&root, "rewrite", "v1\n");
+    write(&root, "rewrite", "v2\n");
+    let collected = manager
+        .handle_action(json!({"action": "gc"}))
+        .await
+        .expect("gc");
+    assert_eq!(
+        collected["removed"], 2,
+        "v1 and the planted blob: {}",
+        collected
+    );
+    assert_eq!(collected["kept"], 1);
+    assert!(resolve_blob_path(&root, &sha256("v2\n")).exists());
+    assert!(!resolve_blob_path(&root, &sha256("v1\n")).exists());
+
+    restore_env("INFRA_CONTEXT_REPO_ROOT", prev_context);
+    restore_env("INFRA_ARTIFACT_DEDUP", prev_dedup);
+    std::fs::remove_dir_all(&root).ok();
+}
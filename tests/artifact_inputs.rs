@@ -0,0 +1,186 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::api::ApiManager;
+use infra::managers::local::LocalManager;
+use infra::managers::ssh::SshManager;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use serde_json::{json, Value};
+use std::io::{Read, Write};
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+const BODY: &[u8] = b"release-2024.10 build artifact";
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+// Answers every request with BODY.
+fn spawn_file_stub() -> u16 {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind stub");
+    let port = listener.local_addr().expect("stub addr").port();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { continue };
+            let mut head = Vec::new();
+            let mut byte = [0u8; 1];
+            while !head.ends_with(b"\r\n\r\n") {
+                match stream.read(&mut byte) {
+                    Ok(1) => head.push(byte[0]),
+                    _ => break,
+                }
+            }
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/octet-stream\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                BODY.len()
+            );
+            let _ = stream.write_all(response.as_bytes());
+            let _ = stream.write_all(BODY);
+        }
+    });
+    port
+}
+
+#[tokio::test]
+async fn artifact_uris_resolve_wherever_local_paths_are_read() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let keys = [
+        "INFRA_PROFILES_DIR",
+        "INFRA_CONTEXT_REPO_ROOT",
+        "INFRA_API_STREAM_TO_ARTIFACT",
+    ];
+    let previous: Vec<Option<String>> = keys.iter().map(|key| std::env::var(key).ok()).collect();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    let context_root = tmp_dir.join("context");
+    std::fs::create_dir_all(context_root.join("artifacts")).expect("create context root");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    std::env::set_var("INFRA_CONTEXT_REPO_ROOT", &context_root);
+    std::env::remove_var("INFRA_API_STREAM_TO_ARTIFACT");
+
+    let security = Arc::new(Security::new().expect("security"));
+    let profiles = Arc::new(ProfileService::new(security.clone()).expect("profile service"));
+    let api = ApiManager::new(
+        Logger::new("test"),
+        Validation::new(),
+        profiles.clone(),
+        None,
+        None,
+        None,
+    );
+    let ssh = SshManager::new(
+        Logger::new("test"),
+        security,
+        Validation::new(),
+        profiles,
+        None,
+        None,
+        None,
+    );
+    let local = LocalManager::new(Logger::new("test"), Validation::new(), Some(true));
+    let port = spawn_file_stub();
+    let url = format!("http://127.0.0.1:{}/release.bin", port);
+
+    let downloaded = api
+        .handle_action(json!({
+            "action": "download",
+            "url": url,
+            "download_path": "artifact://downloads/release.bin",
+        }))
+        .await
+        .expect("download into an artifact");
+    let stored = context_root.join("artifacts/downloads/release.bin");
+    assert_eq!(std::fs::read(&stored).expect("artifact"), BODY);
+    assert_eq!(downloaded["file_path"], stored.display().to_string());
+
+    // Nothing listens on port 1; preflight stops after the local check, before connecting.
+    let connection = json!({"host": "127.0.0.1", "port": 1, "username": "deploy", "password": "x"});
+    let deploy = |local_path: Value| {
+        ssh.handle_action(json!({
+            "action": "deploy_file",
+            "connection": connection,
+            "local_path": local_path,
+            "remote_path": "/srv/app/release.bin",
+            "preflight": true,
+            "min_bytes": 1_000_000,
+        }))
+    };
+    let result = deploy(json!("artifact://downloads/release.bin"))
+        .await
+        .expect("deploy_file result");
+    assert_eq!(result["code"], "PREFLIGHT_FAILED", "{}", result);
+    let check = &result["preflight"]["checks"][0];
+    assert_eq!(check["exists"], true);
+    assert_eq!(check["bytes"], BODY.len());
+    assert_eq!(
+        result["local_path"],
+        stored.canonicalize().unwrap().display().to_string()
+    );
+
+    // Response bodies streamed to artifacts chain the same way through their ref uri.
+    std::env::set_var("INFRA_API_STREAM_TO_ARTIFACT", "full");
+    let requested = api
+        .handle_action(json!({"action": "request", "method": "GET", "url": url}))
+        .await
+        .expect("request");
+    let body_uri = requested["body_ref"]["uri"].clone();
+    assert!(body_uri.as_str().unwrap().starts_with("artifact://runs/"));
+    let result = deploy(body_uri.clone()).await.expect("deploy body ref");
+    assert_eq!(result["preflight"]["checks"][0]["bytes"], BODY.len());
+
+    let read = local
+        .handle_action(json!({"action": "fs_read", "path": body_uri}))
+        .await
+        .expect("fs_read artifact");
+    assert_eq!(read["content"], String::from_utf8_lossy(BODY).as_ref());
+
+    std::fs::write(tmp_dir.join("outside.bin"), b"secret").expect("outside file");
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(
+        tmp_dir.join("outside.bin"),
+        context_root.join("artifacts/leak.bin"),
+    )
+    .expect("symlink");
+    for (uri, kind) in [
+        ("artifact://../outside.bin", ToolErrorKind::Denied),
+        ("artifact://leak.bin", ToolErrorKind::Denied),
+        ("artifact://downloads/missing.bin", ToolErrorKind::NotFound),
+    ] {
+        let err = deploy(json!(uri)).await.expect_err(uri);
+        assert_eq!(err.kind, kind, "{}", uri);
+        let err = local
+            .handle_action(json!({"action": "fs_read", "path": uri}))
+            .await
+            .expect_err(uri);
+        assert_eq!(err.kind, kind, "{}", uri);
+    }
+    assert_eq!(
+        deploy(json!("artifact://downloads/missing.bin"))
+            .await
+            .expect_err("missing")
+            .message,
+        "local_path does not exist: downloads/missing.bin"
+    );
+    let err = api
+        .handle_action(json!({
+            "action": "download",
+            "url": url,
+            "download_path": "artifact://../escape.bin",
+        }))
+        .await
+        .expect_err("download outside artifacts");
+    assert_eq!(err.kind, ToolErrorKind::Denied);
+    assert!(!tmp_dir.join("context/escape.bin").exists());
+
+    for (key, value) in keys.iter().zip(previous) {
+        restore_env(key, value);
+    }
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
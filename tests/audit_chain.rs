@@ -0,0 +1,72 @@
+use infra::services::audit::AuditService;
+use infra::services::logger::Logger;
+use serde_json::Value;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+#[tokio::test]
+async fn audit_entries_are_hash_chained_across_restarts_and_rotation() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let tmp_dir = std::env::temp_dir().join(format!("infra-audit-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    let audit_path = tmp_dir.join("audit.jsonl");
+    let prev_audit = std::env::var("INFRA_AUDIT_PATH").ok();
+    std::env::set_var("INFRA_AUDIT_PATH", &audit_path);
+
+    std::fs::write(&audit_path, "{\"tool\":\"legacy\"}\n").expect("legacy entry");
+    let audit = AuditService::new(Logger::new("test"));
+    audit.append(&serde_json::json!({"tool": "sql", "status": "ok"}));
+    audit.append(&serde_json::json!({"tool": "ssh", "status": "error"}));
+
+    // Rotate like logrotate would, then restart: the new service resumes from the rotated tail.
+    std::fs::rename(&audit_path, tmp_dir.join("audit.jsonl.1")).expect("rotate");
+    let audit = AuditService::new(Logger::new("test"));
+    assert_eq!(audit.stats()["head"]["seq"], 2);
+    audit.append(&serde_json::json!({"tool": "api", "status": "ok", "seq": 1}));
+
+    let report = audit.verify().expect("verify");
+    assert_eq!(report["valid"], true, "{}", report);
+    assert_eq!(report["verified"], 3);
+    assert_eq!(report["legacy_entries"], 1);
+    assert_eq!(report["starts_at_genesis"], true);
+    assert_eq!(report["files"].as_array().expect("files").len(), 2);
+    assert_eq!(report["head"]["seq"], 3);
+    let head_hash = report["head"]["hash"]
+        .as_str()
+        .expect("head hash")
+        .to_string();
+    assert_eq!(audit.stats()["head"]["hash"], head_hash.as_str());
+
+    let rotated = tmp_dir.join("audit.jsonl.1");
+    let text = std::fs::read_to_string(&rotated).expect("read rotated");
+    let mut lines: Vec<Value> = text
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("entry"))
+        .collect();
+    lines[2]["status"] = Value::from("ok");
+    let tampered = lines
+        .iter()
+        .map(|line| line.to_string())
+        .collect::<Vec<_>>()
+        .join("\n");
+    std::fs::write(&rotated, format!("{}\n", tampered)).expect("tamper");
+
+    let report = audit.verify().expect("verify tampered");
+    assert_eq!(report["valid"], false);
+    assert_eq!(report["verified"], 1);
+    assert_eq!(report["broken"]["reason"], "entry_hash_mismatch");
+    assert_eq!(report["broken"]["line"], 3);
+    assert_eq!(report["broken"]["seq"], 2);
+
+    restore_env("INFRA_AUDIT_PATH", prev_audit);
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
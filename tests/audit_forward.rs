@@ -0,0 +1,237 @@
+use infra::errors::ToolError;
+use infra::managers::api::{ApiManager, AuditWebhook};
+use infra::managers::audit::AuditManager;
+use infra::services::audit::AuditService;
+use infra::services::audit_forwarder::{AuditForwarder, AuditTransport, ForwardConfig};
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+struct Received {
+    authorization: Option<String>,
+    body: Value,
+}
+
+// Answers with the given statuses in order (200 once they run out) and keeps each POST.
+fn spawn_sink(statuses: Vec<u16>) -> (u16, Arc<Mutex<Vec<Received>>>) {
+    let listener = std::net::TcpListener::bind(("127.0.0.1", 0)).expect("bind sink");
+    let port = listener.local_addr().expect("sink addr").port();
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let seen = received.clone();
+    std::thread::spawn(move || {
+        let mut statuses = statuses.into_iter();
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { continue };
+            let mut reader = BufReader::new(stream.try_clone().expect("clone stream"));
+            let mut length = 0usize;
+            let mut authorization = None;
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap_or(0) == 0 || line == "\r\n" {
+                    break;
+                }
+                let lower = line.to_lowercase();
+                if let Some(value) = lower.strip_prefix("content-length:") {
+                    length = value.trim().parse().unwrap_or(0);
+                }
+                if lower.starts_with("authorization:") {
+                    authorization = Some(line["authorization:".len()..].trim().to_string());
+                }
+            }
+            let mut body = vec![0u8; length];
+            let _ = reader.read_exact(&mut body);
+            let status = statuses.next().unwrap_or(200);
+            if status == 200 {
+                seen.lock().unwrap().push(Received {
+                    authorization,
+                    body: serde_json::from_slice(&body).unwrap_or(Value::Null),
+                });
+            }
+            let response = format!(
+                "HTTP/1.1 {} X\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                status
+            );
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+    (port, received)
+}
+
+#[tokio::test]
+async fn audit_entries_are_batched_to_the_webhook_with_profile_auth_and_retries() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let prev_audit = std::env::var("INFRA_AUDIT_PATH").ok();
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    std::env::set_var("INFRA_AUDIT_PATH", tmp_dir.join("audit.jsonl"));
+
+    let logger = Logger::new("test");
+    let security = Arc::new(Security::new().expect("security"));
+    let profile_service = Arc::new(ProfileService::new(security).expect("profile service"));
+    let api = Arc::new(ApiManager::new(
+        logger.clone(),
+        Validation::new(),
+        profile_service,
+        None,
+        None,
+        None,
+    ));
+    api.handle_action(json!({
+        "action": "profile_upsert",
+        "profile_name": "siem",
+        "auth": {"type": "bearer", "token": "siem-token"},
+    }))
+    .await
+    .expect("profile_upsert");
+
+    // The first POST gets a 503, so the first batch only lands on the retry.
+    let (port, received) = spawn_sink(vec![503]);
+    let audit_service = Arc::new(AuditService::new(logger.clone()));
+    let forwarder = AuditForwarder::spawn(
+        logger.clone(),
+        Arc::new(AuditWebhook::new(
+            api,
+            format!("http://127.0.0.1:{}/ingest", port),
+            Some("siem".to_string()),
+        )),
+        ForwardConfig {
+            batch_size: 2,
+            flush_ms: 60_000,
+            queue_capacity: 16,
+        },
+    )
+    .expect("runtime available");
+    audit_service.set_forwarder(forwarder);
+
+    for idx in 0..3 {
+        audit_service.append(&json!({"tool": "sql", "action": "query", "idx": idx}));
+    }
+    let manager = AuditManager::new(logger, audit_service.clone());
+    let flushed = manager
+        .handle_action(json!({"action": "audit_flush"}))
+        .await
+        .expect("audit_flush");
+    assert_eq!(flushed["forward"]["sent"], 3, "{}", flushed);
+    assert_eq!(flushed["forward"]["batches"], 2);
+    assert_eq!(flushed["forward"]["failed"], 0);
+    assert_eq!(flushed["forward"]["queue_depth"], 0);
+    assert_eq!(flushed["forward"]["target"]["profile"], "siem");
+
+    let local = audit_service
+        .read_entries(10, 0, false, &json!({}))
+        .expect("read local");
+    let received = std::mem::take(&mut *received.lock().unwrap());
+    assert_eq!(received.len(), 2);
+    assert_eq!(received[0].body.as_array().map(Vec::len), Some(2));
+    assert_eq!(received[1].body.as_array().map(Vec::len), Some(1));
+    assert_eq!(
+        received[0].authorization.as_deref(),
+        Some("Bearer siem-token")
+    );
+    // Forwarded entries are the sealed ones written locally.
+    assert_eq!(received[0].body[0], local["entries"][0]);
+    assert_eq!(received[1].body[0], local["entries"][2]);
+
+    let stats = manager
+        .handle_action(json!({"action": "audit_stats"}))
+        .await
+        .expect("audit_stats");
+    assert_eq!(stats["stats"]["forward"]["sent"], 3);
+    assert!(!stats.to_string().contains("siem-token"));
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    restore_env("INFRA_AUDIT_PATH", prev_audit);
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
+
+// Holds every delivery until released, so the queue can be filled deterministically.
+struct GatedTransport {
+    gate: Arc<tokio::sync::Semaphore>,
+    delivered: Arc<Mutex<usize>>,
+}
+
+#[async_trait::async_trait]
+impl AuditTransport for GatedTransport {
+    async fn deliver(&self, batch: &[Value]) -> Result<(), ToolError> {
+        let _permit = self.gate.acquire().await.expect("gate");
+        *self.delivered.lock().unwrap() += batch.len();
+        Ok(())
+    }
+
+    fn describe(&self) -> Value {
+        json!({"kind": "gated"})
+    }
+}
+
+#[tokio::test]
+async fn a_full_queue_drops_entries_instead_of_blocking_appends() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    let prev_audit = std::env::var("INFRA_AUDIT_PATH").ok();
+    std::env::set_var("INFRA_AUDIT_PATH", tmp_dir.join("audit.jsonl"));
+
+    let logger = Logger::new("test");
+    let gate = Arc::new(tokio::sync::Semaphore::new(0));
+    let delivered = Arc::new(Mutex::new(0));
+    let audit_service = AuditService::new(logger.clone());
+    audit_service.set_forwarder(
+        AuditForwarder::spawn(
+            logger,
+            Arc::new(GatedTransport {
+                gate: gate.clone(),
+                delivered: delivered.clone(),
+            }),
+            ForwardConfig {
+                batch_size: 1,
+                flush_ms: 0,
+                queue_capacity: 2,
+            },
+        )
+        .expect("runtime available"),
+    );
+
+    for idx in 0..6 {
+        audit_service.append(&json!({"tool": "local", "idx": idx}));
+        tokio::task::yield_now().await;
+    }
+    let stats = audit_service.stats();
+    assert_eq!(stats["logged"], 6);
+    let dropped = stats["forward"]["dropped"].as_u64().unwrap();
+    assert!(dropped >= 3, "{}", stats);
+    assert_eq!(
+        stats["forward"]["queue_depth"].as_u64().unwrap() + dropped,
+        6
+    );
+
+    gate.add_permits(16);
+    let flushed = audit_service
+        .flush(Duration::from_secs(5))
+        .await
+        .expect("flush");
+    assert_eq!(flushed["forward"]["sent"].as_u64().unwrap() + dropped, 6);
+    assert_eq!(*delivered.lock().unwrap() as u64 + dropped, 6);
+    assert_eq!(flushed["forward"]["queue_depth"], 0);
+
+    restore_env("INFRA_AUDIT_PATH", prev_audit);
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
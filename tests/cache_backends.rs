@@ -0,0 +1,156 @@
+use infra::services::cache::{CacheBackend, CacheService};
+use infra::services::logger::Logger;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+fn disk_service(dir: &std::path::Path) -> CacheService {
+    CacheService::with_backend(Logger::new("test"), CacheBackend::Disk, dir.to_path_buf())
+}
+
+#[tokio::test]
+async fn disk_cache_persists_per_namespace_and_sanitizes_corrupt_entries() {
+    let _guard = ENV_LOCK.lock().await;
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+
+    let root = disk_service(&tmp_dir);
+    let api = root.namespaced("api").expect("api namespace");
+    let pipeline = root.namespaced("pipeline").expect("pipeline namespace");
+    assert!(root.namespaced("nope").is_err());
+    let key = api.build_key(&serde_json::json!({"url": "https://example.test/a"}));
+    api.set_json(&key, &serde_json::json!({"from": "api"}), None, None)
+        .expect("set api");
+    pipeline
+        .set_json(&key, &serde_json::json!({"from": "pipeline"}), None, None)
+        .expect("set pipeline");
+    let secrets = root.namespaced("secret_refs").expect("secret namespace");
+    secrets
+        .set_json(&key, &serde_json::json!("s3cr3t"), None, None)
+        .expect("set secret");
+    assert!(
+        !tmp_dir.join("secret_refs").exists(),
+        "secrets stay in memory"
+    );
+
+    // A fresh service (a restart) reads the same entries back, each in its own namespace.
+    let restarted = disk_service(&tmp_dir);
+    let api = restarted.namespaced("api").unwrap();
+    let hit = api.get_json(&key, None).expect("get").expect("api hit");
+    assert_eq!(hit["value"]["from"], "api");
+    assert_eq!(hit["ttl_ms"], 15 * 60 * 1000, "namespace default ttl");
+    let hit = restarted
+        .namespaced("pipeline")
+        .unwrap()
+        .get_json(&key, None)
+        .unwrap()
+        .expect("pipeline hit");
+    assert_eq!(hit["value"]["from"], "pipeline");
+    assert!(restarted
+        .namespaced("secret_refs")
+        .unwrap()
+        .get_json(&key, None)
+        .unwrap()
+        .is_none());
+
+    // Corrupt entries are dropped and counted at startup instead of failing.
+    let corrupt_key = "a".repeat(64);
+    let shard = tmp_dir.join("api").join("aa");
+    std::fs::create_dir_all(&shard).unwrap();
+    std::fs::write(shard.join(format!("{}.json", corrupt_key)), "{not json").unwrap();
+    let sanitized = disk_service(&tmp_dir);
+    let stats = sanitized.stats();
+    assert_eq!(stats["backend"], "disk");
+    assert_eq!(
+        stats["namespaces"]["api"]["corrupt_skipped"], 1,
+        "{}",
+        stats
+    );
+    assert_eq!(stats["namespaces"]["api"]["entries"], 1, "{}", stats);
+    assert_eq!(stats["namespaces"]["secret_refs"]["storage"], "memory");
+    assert!(!shard.join(format!("{}.json", corrupt_key)).exists());
+
+    let result = sanitized.invalidate(Some("api"), None).expect("invalidate");
+    assert_eq!(result["removed"], 1);
+    assert!(sanitized
+        .namespaced("api")
+        .unwrap()
+        .get_json(&key, None)
+        .unwrap()
+        .is_none());
+    assert!(sanitized
+        .namespaced("pipeline")
+        .unwrap()
+        .get_json(&key, None)
+        .unwrap()
+        .is_some());
+
+    let _ = std::fs::remove_dir_all(&tmp_dir);
+}
+
+#[tokio::test]
+async fn namespaces_evict_least_recently_used_entries_over_budget() {
+    let _guard = ENV_LOCK.lock().await;
+    let prev_budgets = std::env::var("INFRA_CACHE_BUDGETS").ok();
+    std::env::set_var("INFRA_CACHE_BUDGETS", "api=700");
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+
+    for backend in [CacheBackend::Memory, CacheBackend::Disk] {
+        let dir = tmp_dir.join(backend.as_str());
+        let api = CacheService::with_backend(Logger::new("test"), backend, dir.clone())
+            .namespaced("api")
+            .unwrap();
+        let keys: Vec<String> = (0..3)
+            .map(|i| api.build_key(&serde_json::json!(i)))
+            .collect();
+        let body = serde_json::json!("x".repeat(150));
+        api.set_json(&keys[0], &body, None, None).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        api.set_json(&keys[1], &body, None, None).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        // Reading the first entry makes the second one the least recently used.
+        assert!(api.get_json(&keys[0], None).unwrap().is_some());
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        api.set_json(&keys[2], &body, None, None).unwrap();
+
+        assert!(
+            api.get_json(&keys[0], None).unwrap().is_some(),
+            "{:?}",
+            backend
+        );
+        assert!(
+            api.get_json(&keys[1], None).unwrap().is_none(),
+            "{:?}",
+            backend
+        );
+        assert!(
+            api.get_json(&keys[2], None).unwrap().is_some(),
+            "{:?}",
+            backend
+        );
+        let stats = api.stats();
+        assert_eq!(stats["backend"], backend.as_str());
+        assert_eq!(stats["namespaces"]["api"]["evictions"], 1, "{}", stats);
+        assert_eq!(stats["namespaces"]["api"]["max_bytes"], 700);
+
+        let removed = api.invalidate(None, Some(&keys[0])).unwrap();
+        assert_eq!(removed["removed"], 1);
+        assert!(api.get_json(&keys[0], None).unwrap().is_none());
+        let cleared = api.invalidate(None, None).unwrap();
+        assert_eq!(cleared["removed"], 1);
+    }
+    assert!(
+        !tmp_dir.join("memory").join("api").exists(),
+        "memory backend writes nothing"
+    );
+
+    restore_env("INFRA_CACHE_BUDGETS", prev_budgets);
+    let _ = std::fs::remove_dir_all(&tmp_dir);
+}
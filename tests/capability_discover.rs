@@ -0,0 +1,317 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::api::ApiManager;
+use infra::managers::capability::CapabilityManager;
+use infra::managers::postgres::PostgresManager;
+use infra::managers::ssh::SshManager;
+use infra::services::capability::CapabilityService;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::project::ProjectService;
+use infra::services::project_resolver::ProjectResolver;
+use infra::services::runbook::RunbookService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use serde_json::{json, Value};
+use std::io::{Read, Write};
+use std::sync::{Arc, Mutex};
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+// Serves an OpenAPI document at `/v1/openapi.json` and never answers `/slow`; records every
+// request line it sees.
+fn spawn_openapi_stub() -> (u16, Arc<Mutex<Vec<String>>>) {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind stub");
+    let port = listener.local_addr().expect("stub addr").port();
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let seen = requests.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { continue };
+            let seen = seen.clone();
+            std::thread::spawn(move || {
+                let mut head = Vec::new();
+                let mut byte = [0u8; 1];
+                while !head.ends_with(b"\r\n\r\n") {
+                    match stream.read(&mut byte) {
+                        Ok(1) => head.push(byte[0]),
+                        _ => break,
+                    }
+                }
+                let head = String::from_utf8_lossy(&head).to_string();
+                let line = head.lines().next().unwrap_or("").to_string();
+                seen.lock().unwrap().push(line.clone());
+                if line.contains("/slow") {
+                    std::thread::sleep(std::time::Duration::from_secs(5));
+                    return;
+                }
+                let body = json!({
+                    "openapi": "3.0.3",
+                    "paths": {
+                        "/orders": {
+                            "get": {"operationId": "listOrders", "summary": "List orders"},
+                            "post": {"operationId": "createOrder", "requestBody": {}},
+                        },
+                        "/orders/{id}": {"delete": {}},
+                    },
+                })
+                .to_string();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes());
+            });
+        }
+    });
+    (port, requests)
+}
+
+#[tokio::test]
+async fn discover_proposes_records_and_persists_only_on_apply() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+
+    let security = Arc::new(Security::new().expect("security"));
+    let profiles = Arc::new(ProfileService::new(security.clone()).expect("profile service"));
+    let project_service = Arc::new(ProjectService::new().expect("project service"));
+    let resolver = Arc::new(ProjectResolver::new(
+        Validation::new(),
+        project_service.clone(),
+        None,
+    ));
+    let api = Arc::new(ApiManager::new(
+        Logger::new("test"),
+        Validation::new(),
+        profiles.clone(),
+        None,
+        None,
+        None,
+    ));
+    let ssh = Arc::new(SshManager::new(
+        Logger::new("test"),
+        security.clone(),
+        Validation::new(),
+        profiles.clone(),
+        None,
+        None,
+        None,
+    ));
+    let postgres = Arc::new(PostgresManager::new(
+        Logger::new("test"),
+        Validation::new(),
+        profiles.clone(),
+        None,
+        None,
+    ));
+    let capability_service =
+        Arc::new(CapabilityService::new(security.clone()).expect("capability service"));
+    let runbook_service = Arc::new(RunbookService::new().expect("runbook service"));
+    let manager = CapabilityManager::new(
+        Logger::new("test"),
+        Validation::new(),
+        capability_service.clone(),
+        None,
+    )
+    .with_project_resolver(resolver)
+    .with_runbook_service(runbook_service.clone())
+    .with_ssh_manager(ssh)
+    .with_postgres_manager(postgres.clone())
+    .with_api_manager(api.clone());
+
+    let (port, requests) = spawn_openapi_stub();
+    api.handle_action(json!({
+        "action": "profile_upsert",
+        "profile_name": "shop-api",
+        "base_url": format!("http://127.0.0.1:{}/v1/", port),
+        "openapi_url": "openapi.json",
+    }))
+    .await
+    .expect("api profile");
+    project_service
+        .set_project(
+            "shop",
+            &json!({"targets": {
+                "prod": {"ssh_profile": "web-missing", "postgres_profile": "db-missing", "api_profile": "shop-api"},
+                "stage": {"api_base_url": format!("http://127.0.0.1:{}", port), "openapi_url": "/slow"},
+                "dev": {"api_base_url": format!("http://127.0.0.1:{}/", port)},
+            }}),
+        )
+        .expect("project");
+
+    let discover = |extra: Value| {
+        let mut args = json!({
+            "action": "capability_discover",
+            "project": "shop",
+            "probe_timeout_ms": 1000,
+        });
+        args.as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        manager.handle_action(args)
+    };
+    let started = std::time::Instant::now();
+    let result = discover(json!({})).await.expect("discover");
+    assert!(
+        started.elapsed() < std::time::Duration::from_secs(3),
+        "probes run concurrently"
+    );
+    assert_eq!(result["dry_run"], true, "{}", result);
+    let status = |target: &str, kind: &str| {
+        result["probes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|p| p["target"] == target && p["kind"] == kind)
+            .map(|p| p["status"].clone())
+            .unwrap_or(Value::Null)
+    };
+    assert_eq!(status("prod", "api"), "ok");
+    assert_eq!(status("prod", "ssh"), "error");
+    assert_eq!(status("prod", "postgres"), "error");
+    assert_eq!(status("stage", "api"), "timeout");
+    assert_eq!(status("dev", "api"), "ok");
+
+    let proposals = result["proposals"].as_array().unwrap();
+    let find = |name: &str| proposals.iter().find(|p| p["name"] == name).cloned();
+    let list = find("shop.prod.api.listOrders").expect("listOrders proposal");
+    assert_eq!(list["confidence"], "high");
+    assert_eq!(
+        list["evidence"]["openapi_url"],
+        format!("http://127.0.0.1:{}/v1/openapi.json", port)
+    );
+    assert_eq!(list["capability"]["effects"]["kind"], "read");
+    assert_eq!(
+        list["runbook"]["steps"][0]["args"]["url"],
+        format!("http://127.0.0.1:{}/v1/orders", port)
+    );
+    let delete = find("shop.prod.api.delete__orders_id").expect("delete proposal");
+    assert_eq!(delete["confidence"], "medium");
+    assert_eq!(delete["capability"]["inputs"]["required"], json!(["id"]));
+    assert_eq!(delete["capability"]["effects"]["irreversible"], true);
+    let check = find("shop.dev.api.check").expect("check proposal");
+    assert_eq!(check["confidence"], "low");
+    assert!(requests
+        .lock()
+        .unwrap()
+        .iter()
+        .all(|line| line.starts_with("GET ")));
+    assert!(!tmp_dir.join("capabilities.json").exists());
+    assert!(!tmp_dir.join("runbooks.json").exists());
+
+    let applied = discover(json!({
+        "target": "prod",
+        "kinds": ["api"],
+        "apply": true,
+        "names": ["shop.prod.api.listOrders", "shop.prod.api.createOrder"],
+    }))
+    .await
+    .expect("apply");
+    assert_eq!(applied["dry_run"], false);
+    assert_eq!(applied["probes"].as_array().unwrap().len(), 1);
+    assert_eq!(
+        applied["applied"]["capabilities"]["added"],
+        json!(["shop.prod.api.createOrder", "shop.prod.api.listOrders"])
+    );
+    let capability = manager
+        .handle_action(json!({"action": "get", "name": "shop.prod.api.createOrder"}))
+        .await
+        .expect("persisted capability");
+    assert_eq!(
+        capability["capability"]["inputs"]["required"],
+        json!(["body"])
+    );
+    let runbook = runbook_service
+        .get_runbook("shop.prod.api.listOrders")
+        .expect("persisted runbook");
+    assert_eq!(runbook["runbook"]["steps"][0]["tool"], "api");
+    assert!(capability_service
+        .get_capability("shop.prod.api.delete__orders_id")
+        .is_err());
+
+    let again = discover(json!({"target": "prod", "kinds": "api", "apply": true}))
+        .await
+        .expect("apply again");
+    assert_eq!(
+        again["applied"]["capabilities"]["skipped"],
+        json!(["shop.prod.api.createOrder", "shop.prod.api.listOrders"])
+    );
+    assert_eq!(
+        again["applied"]["capabilities"]["added"],
+        json!(["shop.prod.api.delete__orders_id"])
+    );
+
+    // Set INFRA_TEST_POSTGRES_URLS (comma-separated) to list writable tables on live servers.
+    let urls = std::env::var("INFRA_TEST_POSTGRES_URLS").unwrap_or_default();
+    if let Some(url) = urls.split(',').map(str::trim).find(|url| !url.is_empty()) {
+        let table = format!("infra_discover_{}", uuid::Uuid::new_v4().simple());
+        let query = |sql: String| {
+            postgres.handle_action(json!({"action": "query", "connection_url": url, "sql": sql}))
+        };
+        query(format!(
+            "CREATE TABLE \"{}\" (tenant int4, id int4, body text, PRIMARY KEY (tenant, id))",
+            table
+        ))
+        .await
+        .expect("create table");
+        postgres
+            .handle_action(
+                json!({"action": "profile_upsert", "profile_name": "db", "connection_url": url}),
+            )
+            .await
+            .expect("postgres profile");
+        project_service
+            .set_project(
+                "data",
+                &json!({"targets": {"prod": {"postgres_profile": "db"}}}),
+            )
+            .expect("data project");
+        let found = discover(json!({"project": "data"}))
+            .await
+            .expect("discover tables");
+        let load = found["proposals"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|p| p["name"] == format!("data.prod.public.{}.load", table))
+            .cloned()
+            .expect("load proposal");
+        assert_eq!(load["confidence"], "high");
+        assert_eq!(load["evidence"]["primary_key"], json!(["tenant", "id"]));
+        assert_eq!(load["runbook"]["steps"][0]["args"]["on_conflict"], "upsert");
+        query(format!("DROP TABLE \"{}\"", table))
+            .await
+            .expect("drop table");
+    }
+
+    for (extra, message) in [
+        (
+            json!({"kinds": ["ssh", "dns"]}),
+            "kinds must be a non-empty subset of ssh, postgres, api",
+        ),
+        (
+            json!({"apply": true, "names": ["shop.prod.nope"]}),
+            "names lists shop.prod.nope, which is not among the proposals",
+        ),
+        (json!({"target": "qa"}), "Unknown project target: qa."),
+    ] {
+        let err = discover(extra).await.expect_err(message);
+        assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+        assert_eq!(err.message, message);
+    }
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
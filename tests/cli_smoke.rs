@@ -440,3 +440,87 @@ fn cli_ambiguous_capability_resolution_fails_loudly() {
         Some(2)
     );
 }
+
+#[test]
+fn cli_startup_check_stops_on_fatal_storage_and_doctor_reports_problems() {
+    let _guard = ENV_LOCK.blocking_lock();
+    let tmp_root = std::env::temp_dir().join(format!("infra-cli-doctor-{}", uuid::Uuid::new_v4()));
+    let cwd = tmp_root.join("cwd");
+    let profiles = tmp_root.join("profiles");
+    std::fs::create_dir_all(&cwd).expect("create cwd");
+    std::fs::create_dir_all(&profiles).expect("create profiles");
+
+    let not_a_dir = tmp_root.join("state-file");
+    std::fs::write(&not_a_dir, "x").expect("write state file");
+    let (status, output, _stderr) = run_cli(&cwd, &not_a_dir, &[], &["describe", "status"]);
+    assert_ne!(status, 0);
+    assert_eq!(
+        output.pointer("/error/code").and_then(|v| v.as_str()),
+        Some("STARTUP_CHECK_FAILED")
+    );
+    assert_eq!(
+        output
+            .pointer("/error/details/status")
+            .and_then(|v| v.as_str()),
+        Some("fatal")
+    );
+
+    let probe_port = std::net::TcpListener::bind("127.0.0.1:0")
+        .expect("bind probe port")
+        .local_addr()
+        .expect("probe addr")
+        .port();
+    write_json(
+        &profiles.join("profiles.json"),
+        &serde_json::json!({
+            "web": {"type": "ssh", "data": {"host": "127.0.0.1", "port": probe_port}},
+        }),
+    );
+    let env = vec![
+        (
+            "INFRA_CONTEXT_REPO_ROOT",
+            tmp_root
+                .join("missing-context")
+                .to_string_lossy()
+                .to_string(),
+        ),
+        ("INFRA_ALLOW_SECRET_EXPORT", "1".to_string()),
+        ("INFRA_UNSAFE_LOCAL", "0".to_string()),
+    ];
+    let (status, output, stderr) = run_cli(
+        &cwd,
+        &profiles,
+        &env,
+        &["describe", "doctor", "--arg", "probe=true"],
+    );
+    assert_eq!(status, 0, "stderr: {stderr}");
+    assert!(stderr.contains("startup check error"), "stderr: {stderr}");
+    let report = output.get("result").expect("report");
+    assert_eq!(report["status"], "error");
+    assert_eq!(report["healthy"], false);
+    let severity = |id: &str| -> Vec<String> {
+        report["checks"]
+            .as_array()
+            .expect("checks")
+            .iter()
+            .filter(|check| check["id"] == id)
+            .map(|check| check["severity"].as_str().unwrap_or("").to_string())
+            .collect()
+    };
+    assert_eq!(severity("state_dir"), vec!["ok"]);
+    assert_eq!(severity("context_root"), vec!["error"]);
+    assert_eq!(severity("feature_flags"), vec!["warn"]);
+    assert_eq!(severity("profile_probe"), vec!["warn"]);
+
+    std::fs::write(profiles.join("profiles.json"), "{not json").expect("corrupt profiles");
+    std::fs::remove_file(profiles.join("infra.db")).ok();
+    let (status, output, _stderr) = run_cli(&cwd, &profiles, &[], &["describe", "status"]);
+    assert_ne!(status, 0);
+    assert!(output
+        .pointer("/error/message")
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .contains("profiles.json"));
+
+    std::fs::remove_dir_all(&tmp_root).ok();
+}
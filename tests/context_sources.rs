@@ -0,0 +1,135 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::context::ContextManager;
+use infra::services::context::ContextService;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+#[tokio::test]
+async fn context_sources_refresh_selectively_and_report_staleness() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+
+    let security = Arc::new(Security::new().expect("security"));
+    let profile_service = Arc::new(ProfileService::new(security).expect("profile service"));
+    profile_service
+        .set_profile(
+            "prod-ssh",
+            &serde_json::json!({ "type": "ssh", "data": { "host": "prod" } }),
+        )
+        .expect("seed profile");
+    let manager = ContextManager::new(
+        Logger::new("test"),
+        Arc::new(ContextService::new().expect("context service")),
+        Some(profile_service.clone()),
+        None,
+        None,
+        None,
+    );
+
+    let refreshed = manager
+        .handle_action(serde_json::json!({
+            "action": "refresh",
+            "cwd": tmp_dir,
+            "sources": ["profiles"],
+        }))
+        .await
+        .expect("selective refresh");
+    let reports = refreshed["refreshed"].as_array().expect("reports");
+    assert_eq!(reports.len(), 1);
+    assert_eq!(reports[0]["source"], "profiles");
+    assert_eq!(reports[0]["status"], "ok");
+    assert_eq!(reports[0]["changed"], true);
+    assert!(refreshed.get("context").is_none());
+    let first_fingerprint = reports[0]["fingerprint"].clone();
+
+    let again = manager
+        .handle_action(serde_json::json!({
+            "action": "refresh",
+            "cwd": tmp_dir,
+            "sources": ["profiles", "projects"],
+        }))
+        .await
+        .expect("second refresh");
+    assert_eq!(again["refreshed"][0]["changed"], false);
+    assert_eq!(again["refreshed"][0]["fingerprint"], first_fingerprint);
+    assert_eq!(again["refreshed"][1]["status"], "unavailable");
+
+    let summary = manager
+        .handle_action(serde_json::json!({
+            "action": "summary",
+            "cwd": tmp_dir,
+            "sources": ["profiles", "jobs"],
+        }))
+        .await
+        .expect("summary");
+    let sources = &summary["summary"]["sources"];
+    assert_eq!(sources["profiles"]["stale"], false);
+    assert_eq!(sources["profiles"]["data"]["count"], 1);
+    assert_eq!(sources["jobs"]["status"], "missing");
+    assert_eq!(
+        summary["summary"]["stale_sources"],
+        serde_json::json!(["jobs"])
+    );
+    assert_eq!(summary["refreshed"], serde_json::json!([]));
+
+    profile_service
+        .set_profile(
+            "staging-ssh",
+            &serde_json::json!({ "type": "ssh", "data": { "host": "staging" } }),
+        )
+        .expect("seed second profile");
+    tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+
+    let auto = manager
+        .handle_action(serde_json::json!({
+            "action": "summary",
+            "cwd": tmp_dir,
+            "sources": ["profiles", "repo"],
+            "max_age_ms": 5,
+        }))
+        .await
+        .expect("auto refresh");
+    assert_eq!(auto["refreshed"], serde_json::json!(["profiles", "repo"]));
+    assert_eq!(auto["summary"]["sources"]["profiles"]["data"]["count"], 2);
+    assert_ne!(
+        auto["summary"]["sources"]["profiles"]["fingerprint"],
+        first_fingerprint
+    );
+    assert_eq!(auto["summary"]["sources"]["repo"]["status"], "ok");
+
+    let fresh = manager
+        .handle_action(serde_json::json!({
+            "action": "summary",
+            "cwd": tmp_dir,
+            "sources": ["profiles"],
+            "max_age_ms": 60_000,
+        }))
+        .await
+        .expect("fresh summary");
+    assert_eq!(fresh["refreshed"], serde_json::json!([]));
+
+    let err = manager
+        .handle_action(serde_json::json!({ "action": "refresh", "sources": ["profile"] }))
+        .await
+        .expect_err("unknown source");
+    assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+    assert!(err.hint.unwrap_or_default().contains("profiles"));
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+}
@@ -0,0 +1,106 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::ssh::SshManager;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use serde_json::json;
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+fn manager() -> SshManager {
+    let security = Arc::new(Security::new().expect("security"));
+    let profile_service = Arc::new(ProfileService::new(security.clone()).expect("profile service"));
+    SshManager::new(
+        Logger::new("test"),
+        security,
+        Validation::new(),
+        profile_service,
+        None,
+        None,
+        None,
+    )
+}
+
+#[tokio::test]
+async fn file_attributes_are_validated_before_connecting() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    let ssh = manager();
+
+    let binary = tmp_dir.join("app.bin");
+    std::fs::write(&binary, b"binary").expect("write binary");
+    // Nothing listens on port 1: any remote step would fail with a connection error instead.
+    let connection = json!({"host": "127.0.0.1", "port": 1, "username": "deploy", "password": "x"});
+    let call = |action: &str, extra: serde_json::Value| {
+        let mut args = json!({
+            "action": action,
+            "connection": connection,
+            "local_path": binary.to_string_lossy(),
+            "remote_path": "/srv/app/app.bin",
+        });
+        args.as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        ssh.handle_action(args)
+    };
+
+    for action in ["deploy_file", "sftp_upload"] {
+        for (extra, message) in [
+            (json!({"mode": 755}), "mode must be a non-empty string"),
+            (
+                json!({"mode": "0955"}),
+                "mode '0955' must be an octal permission such as 0644 or 755",
+            ),
+            (
+                json!({"owner": "app:app"}),
+                "owner 'app:app' is not a valid user or group name",
+            ),
+            (
+                json!({"selinux_context": "bin_t; id"}),
+                "selinux_context 'bin_t; id' must look like user:role:type:level or a bare type",
+            ),
+        ] {
+            let err = call(action, extra).await.expect_err(message);
+            assert_eq!(err.kind, ToolErrorKind::InvalidParams, "{}", action);
+            assert_eq!(err.message, message, "{}", action);
+        }
+    }
+    let err = call(
+        "sftp_upload",
+        json!({"background": true, "owner": "app", "mode": "0755"}),
+    )
+    .await
+    .expect_err("background upload");
+    assert_eq!(
+        err.message,
+        "owner, group, mode and selinux_context are not applied to background uploads"
+    );
+
+    // Valid attributes pass validation and the deploy fails at the upload as before, with no
+    // attributes reported.
+    let result = call(
+        "deploy_file",
+        json!({"owner": "app", "group": "app", "mode": "0755", "selinux_context": "bin_t", "sudo": true}),
+    )
+    .await
+    .expect("deploy_file result");
+    assert_eq!(result["code"], "UPLOAD_FAILED", "{}", result);
+    assert!(result.get("attributes").is_none());
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
@@ -0,0 +1,138 @@
+use infra::managers::api::ApiManager;
+use infra::managers::pipeline::PipelineManager;
+use infra::managers::postgres::PostgresManager;
+use infra::managers::ssh::SshManager;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use serde_json::json;
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+fn managers() -> (Arc<SshManager>, PipelineManager) {
+    let logger = Logger::new("test");
+    let security = Arc::new(Security::new().expect("security"));
+    let profile_service = Arc::new(ProfileService::new(security.clone()).expect("profile service"));
+    let api = ApiManager::new(
+        logger.clone(),
+        Validation::new(),
+        profile_service.clone(),
+        None,
+        None,
+        None,
+    );
+    let ssh = Arc::new(SshManager::new(
+        logger.clone(),
+        security,
+        Validation::new(),
+        profile_service.clone(),
+        None,
+        None,
+        None,
+    ));
+    let postgres = PostgresManager::new(
+        logger.clone(),
+        Validation::new(),
+        profile_service,
+        None,
+        None,
+    );
+    let pipeline = PipelineManager::new(
+        logger,
+        Validation::new(),
+        Arc::new(api),
+        ssh.clone(),
+        Arc::new(postgres),
+        None,
+        None,
+        None,
+        None,
+    );
+    (ssh, pipeline)
+}
+
+#[tokio::test]
+async fn preflight_rejects_bad_local_files_without_connecting() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    let (ssh, pipeline) = managers();
+
+    let empty = tmp_dir.join("empty.bin");
+    std::fs::write(&empty, b"").expect("write empty");
+    let small = tmp_dir.join("small.bin");
+    std::fs::write(&small, b"12345").expect("write small");
+    // Nothing listens on port 1: a remote probe would fail with a connection error instead.
+    let connection = json!({"host": "127.0.0.1", "port": 1, "username": "deploy", "password": "x"});
+
+    for (local_path, min_bytes, exists, bytes) in [
+        (tmp_dir.join("missing.bin"), None, false, json!(null)),
+        (empty.clone(), None, true, json!(0)),
+        (small.clone(), Some(10), true, json!(5)),
+    ] {
+        let result = ssh
+            .handle_action(json!({
+                "action": "deploy_file",
+                "connection": connection,
+                "local_path": local_path.to_string_lossy(),
+                "remote_path": "/srv/app/app.bin",
+                "restart": "app.service",
+                "preflight": true,
+                "min_bytes": min_bytes,
+            }))
+            .await
+            .expect("deploy_file result");
+        assert_eq!(result["success"], false);
+        assert_eq!(result["code"], "PREFLIGHT_FAILED", "{}", result);
+        let preflight = &result["preflight"];
+        assert_eq!(preflight["passed"], false);
+        assert_eq!(preflight["remote_checked"], false);
+        assert_eq!(preflight["checks"][0]["check"], "local_file");
+        assert_eq!(preflight["checks"][0]["exists"], exists);
+        assert_eq!(preflight["checks"][0]["bytes"], bytes);
+    }
+
+    let smoke = pipeline
+        .handle_action(json!({
+            "action": "deploy_smoke",
+            "connection": connection,
+            "local_path": empty.to_string_lossy(),
+            "remote_path": "/srv/app/app.bin",
+            "url": "http://127.0.0.1:1/health",
+            "preflight": true,
+        }))
+        .await
+        .expect("deploy_smoke result");
+    assert_eq!(smoke["success"], false);
+    assert_eq!(smoke["code"], "PREFLIGHT_FAILED", "{}", smoke);
+    assert_eq!(smoke["preflight"]["checks"][0]["ok"], false);
+    assert!(smoke["smoke"].is_null());
+
+    // Without preflight the empty file goes on to the upload, which fails on the connection.
+    let plain = ssh
+        .handle_action(json!({
+            "action": "deploy_file",
+            "connection": connection,
+            "local_path": empty.to_string_lossy(),
+            "remote_path": "/srv/app/app.bin",
+        }))
+        .await
+        .expect("deploy_file result");
+    assert_eq!(plain["code"], "UPLOAD_FAILED", "{}", plain);
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
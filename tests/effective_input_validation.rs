@@ -33,9 +33,11 @@ async fn alias_injected_fields_are_revalidated_after_merge() {
     let _guard = ENV_LOCK.lock().await;
 
     let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let prev_strict = std::env::var("INFRA_STRICT_ARGS").ok();
     let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
     std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
     std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    std::env::set_var("INFRA_STRICT_ARGS", "1");
 
     let logger = Logger::new("test");
     let state_service = Arc::new(StateService::new().expect("state"));
@@ -86,6 +88,7 @@ async fn alias_injected_fields_are_revalidated_after_merge() {
         Some("effective_args")
     );
 
+    restore_env("INFRA_STRICT_ARGS", prev_strict);
     restore_env("INFRA_PROFILES_DIR", prev_profiles);
 }
 
@@ -244,3 +247,192 @@ async fn alias_inherited_preset_is_rejected_as_compatibility_only() {
 
     restore_env("INFRA_PROFILES_DIR", prev_profiles);
 }
+
+fn schema_executor() -> ToolExecutor {
+    let logger = Logger::new("test");
+    let state_service = Arc::new(StateService::new().expect("state"));
+    let mut handlers: HashMap<String, Arc<dyn ToolHandler>> = HashMap::new();
+    for tool in ["ssh", "sql", "api", "pipeline"] {
+        handlers.insert(tool.to_string(), Arc::new(DummyHandler));
+    }
+    let alias_map = HashMap::from([("psql".to_string(), "sql".to_string())]);
+    ToolExecutor::new(logger, state_service, None, None, handlers, alias_map)
+}
+
+fn violations(err: &infra::errors::ToolError) -> Vec<Value> {
+    err.details
+        .as_ref()
+        .and_then(|v| v.get("violations"))
+        .and_then(|v| v.as_array())
+        .cloned()
+        .unwrap_or_default()
+}
+
+#[tokio::test]
+async fn ssh_exec_type_violations_list_pointer_and_expected_type() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+
+    let err = schema_executor()
+        .execute(
+            "ssh",
+            serde_json::json!({
+                "action": "exec",
+                "profile_name": "web",
+                "command": ["uptime"],
+                "timeout_ms": "5000"
+            }),
+        )
+        .await
+        .expect_err("mistyped ssh exec args should fail before dispatch");
+
+    assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+    assert!(err.message.starts_with("Invalid arguments for ssh:exec"));
+    let items = violations(&err);
+    let timeout = items
+        .iter()
+        .find(|item| item["pointer"] == "/timeout_ms")
+        .expect("timeout_ms violation");
+    assert_eq!(
+        timeout["expected"],
+        serde_json::json!({ "type": "integer" })
+    );
+    assert!(items.iter().any(|item| item["pointer"] == "/command"));
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+}
+
+#[tokio::test]
+async fn psql_select_unknown_property_warns_with_suggestion() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let prev_strict = std::env::var("INFRA_STRICT_ARGS").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    std::env::remove_var("INFRA_STRICT_ARGS");
+
+    let args = serde_json::json!({
+        "action": "select",
+        "profile_name": "db",
+        "tabel": "users",
+        "limit": 10
+    });
+    let result = schema_executor()
+        .execute("psql", args.clone())
+        .await
+        .expect("unknown properties only warn by default");
+    let warnings = result["meta"]["warnings"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0]["pointer"], "/tabel");
+    assert_eq!(warnings[0]["suggestions"], serde_json::json!(["table"]));
+
+    std::env::set_var("INFRA_STRICT_ARGS", "1");
+    let err = schema_executor()
+        .execute("psql", args)
+        .await
+        .expect_err("strict mode should reject unknown properties");
+    assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+    assert!(err.message.contains("did you mean: table"));
+    assert_eq!(
+        err.details.as_ref().and_then(|v| v.get("strict")),
+        Some(&Value::Bool(true))
+    );
+
+    restore_env("INFRA_STRICT_ARGS", prev_strict);
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+}
+
+#[tokio::test]
+async fn api_request_enum_violation_suggests_nearest_value() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+
+    let err = schema_executor()
+        .execute(
+            "api",
+            serde_json::json!({
+                "action": "reqest",
+                "url": "https://example.com",
+                "follow_redirects": "yes"
+            }),
+        )
+        .await
+        .expect_err("unknown api action should fail");
+
+    let items = violations(&err);
+    let action = items
+        .iter()
+        .find(|item| item["pointer"] == "/action")
+        .expect("action violation");
+    assert!(action["expected"]["enum"]
+        .as_array()
+        .is_some_and(|options| options.contains(&Value::String("request".to_string()))));
+    assert_eq!(action["suggestions"], serde_json::json!(["request"]));
+    let redirects = items
+        .iter()
+        .find(|item| item["pointer"] == "/follow_redirects")
+        .expect("follow_redirects violation");
+    assert_eq!(
+        redirects["expected"],
+        serde_json::json!({ "type": "boolean" })
+    );
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+}
+
+#[tokio::test]
+async fn pipeline_run_violations_fail_even_without_strict_mode() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let prev_strict = std::env::var("INFRA_STRICT_ARGS").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    std::env::remove_var("INFRA_STRICT_ARGS");
+
+    let err = schema_executor()
+        .execute(
+            "pipeline",
+            serde_json::json!({
+                "action": "run",
+                "flow": "http_to_sftpp",
+                "batch_size": 10.5,
+                "where_params": [{ "id": 1 }]
+            }),
+        )
+        .await
+        .expect_err("bad pipeline args should fail");
+
+    let items = violations(&err);
+    let flow = items
+        .iter()
+        .find(|item| item["pointer"] == "/flow")
+        .expect("flow violation");
+    assert_eq!(flow["suggestions"], serde_json::json!(["http_to_sftp"]));
+    assert!(items.iter().any(|item| item["pointer"] == "/batch_size"));
+    let param = items
+        .iter()
+        .find(|item| item["pointer"] == "/where_params/0")
+        .expect("nested item violation");
+    assert_eq!(
+        param["expected"],
+        serde_json::json!({ "type": ["boolean", "null", "number", "string"] })
+    );
+
+    restore_env("INFRA_STRICT_ARGS", prev_strict);
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+}
@@ -0,0 +1,109 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::env::EnvManager;
+use infra::managers::ssh::SshManager;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+#[tokio::test]
+async fn env_file_actions_validate_arguments_before_connecting() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+
+    let security = Arc::new(Security::new().expect("security"));
+    let profile_service = Arc::new(ProfileService::new(security.clone()).expect("profile service"));
+    let ssh_manager = Arc::new(SshManager::new(
+        Logger::new("test"),
+        security,
+        Validation::new(),
+        profile_service.clone(),
+        None,
+        None,
+        None,
+    ));
+    let manager = EnvManager::new(
+        Logger::new("test"),
+        Validation::new(),
+        profile_service,
+        ssh_manager,
+        None,
+        None,
+    );
+
+    let err = manager
+        .handle_action(serde_json::json!({
+            "action": "env_get",
+            "ssh_profile_name": "prod-web",
+        }))
+        .await
+        .expect_err("remote path is required without a project target");
+    assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+    assert!(err.hint.unwrap_or_default().contains("target.env_path"));
+
+    let remote = serde_json::json!({
+        "ssh_profile_name": "prod-web",
+        "remote_path": "/srv/app/.env",
+    });
+    let cases = [
+        (serde_json::json!({"action": "env_set"}), "set and/or unset"),
+        (
+            serde_json::json!({"action": "env_set", "set": {"BAD-KEY": "x"}}),
+            "Invalid env var key",
+        ),
+        (
+            serde_json::json!({"action": "env_set", "unset": "A"}),
+            "unset must be an array",
+        ),
+        (
+            serde_json::json!({
+                "action": "env_set",
+                "set": {"A": "1"},
+                "restart": "app",
+                "restart_command": "systemctl reload app",
+            }),
+            "only one of restart",
+        ),
+        (
+            serde_json::json!({"action": "env_diff"}),
+            "requires expected or compare_target",
+        ),
+        (
+            serde_json::json!({"action": "env_validate", "required": []}),
+            "non-empty array",
+        ),
+    ];
+    for (mut args, expected) in cases {
+        for (key, value) in remote.as_object().expect("remote args") {
+            args[key] = value.clone();
+        }
+        let err = manager
+            .handle_action(args.clone())
+            .await
+            .expect_err("invalid env file args");
+        assert_eq!(err.kind, ToolErrorKind::InvalidParams, "{}", args);
+        assert!(
+            err.message.contains(expected),
+            "{} -> {}",
+            args,
+            err.message
+        );
+    }
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+}
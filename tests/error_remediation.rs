@@ -0,0 +1,309 @@
+use infra::errors::{ToolError, ToolErrorKind};
+use infra::managers::postgres::PostgresManager;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::state::StateService;
+use infra::services::tool_executor::{ToolExecutor, ToolHandler};
+use infra::services::validation::Validation;
+use infra::tooling::catalog::validate_tool_args;
+use infra::utils::remediation::{enrich, FailedCall};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+fn assert_valid(next_actions: &[Value], context: &str) {
+    assert!(!next_actions.is_empty(), "{}: no next_actions", context);
+    for next in next_actions {
+        let tool = next["tool"].as_str().expect("tool");
+        let mut call = next["args"].clone();
+        call["action"] = next["action"].clone();
+        assert!(
+            validate_tool_args(tool, &call).is_ok(),
+            "{}: {} rejected by the contract",
+            context,
+            next
+        );
+        assert!(next["reason"].as_str().is_some_and(|r| !r.is_empty()));
+    }
+}
+
+#[test]
+fn common_error_codes_get_valid_next_actions_from_the_failing_call() {
+    let cases: Vec<(&str, Value, ToolError, &[&str])> = vec![
+        (
+            "ssh",
+            json!({"action": "exec", "profile_name": "web-1", "comand": "uptime -p"}),
+            ToolError::invalid_params("Unknown argument: comand")
+                .with_details(json!({"did_you_mean": {"comand": "command"}})),
+            &["/0/args/command=uptime -p", "/0/args/profile_name=web-1"],
+        ),
+        (
+            "psql",
+            json!({"action": "select", "profile_name": "orders-db", "schema": "sales", "table": "ordrs"}),
+            ToolError::not_found("relation \"sales.ordrs\" does not exist")
+                .with_details(json!({"sqlstate": "42P01"})),
+            &[
+                "/0/action=catalog_tables",
+                "/0/args/profile_name=orders-db",
+                "/0/args/schema=sales",
+            ],
+        ),
+        (
+            "sql",
+            json!({
+                "action": "query",
+                "connection": {"host": "db.internal", "password": "hunter2"},
+                "sql": "DELETE FROM orders WHERE id = 7",
+            }),
+            ToolError::denied("Action requires apply=true for write/mixed effects")
+                .with_details(json!({"effects": {"kind": "write"}})),
+            &[
+                "/0/args/apply=true",
+                "/0/args/sql=DELETE FROM orders WHERE id = 7",
+                "/0/args/connection/host=db.internal",
+            ],
+        ),
+        (
+            "ssh",
+            json!({"action": "exec", "profile_name": "orders-db", "command": "uptime"}),
+            ToolError::conflict("Profile 'orders-db' is of type 'postgresql', expected 'ssh'"),
+            &["/0/tool=profile", "/0/args/profile_name=orders-db"],
+        ),
+        (
+            "ssh",
+            json!({"action": "exec", "profile_name": "web-1", "command": "make release"}),
+            ToolError::timeout("Tool call timed out after 30000ms"),
+            &[
+                "/0/action=exec_detached",
+                "/0/args/command=make release",
+                "/0/args/profile_name=web-1",
+            ],
+        ),
+        (
+            "ssh",
+            json!({"action": "exec", "profile_name": "web-1", "command": "uptime"}),
+            ToolError::retryable("Connection reset by peer"),
+            &[
+                "/0/action=probe",
+                "/0/args/profile_name=web-1",
+                "/1/args/command=uptime",
+            ],
+        ),
+        (
+            "api",
+            json!({"action": "request", "profile_name": "billing", "path": "/v1/invoices", "trace_id": "trace-7"}),
+            ToolError::internal("Response decoding failed"),
+            &["/0/action=audit_trace", "/0/args/trace_id=trace-7"],
+        ),
+        (
+            "ssh",
+            json!({"action": "exec", "profile_name": "bastion", "command": "uptime"}),
+            ToolError::new(
+                ToolErrorKind::NotFound,
+                "PROFILE_NOT_FOUND",
+                "Profile 'bastion' not found",
+            ),
+            &[
+                "/0/args/type=ssh",
+                "/1/action=profile_upsert",
+                "/1/args/profile_name=bastion",
+            ],
+        ),
+        (
+            "ssh",
+            json!({"action": "exec", "profile_name": "web-1", "command": "uptime"}),
+            ToolError::new(
+                ToolErrorKind::Denied,
+                "HOST_KEY_MISMATCH",
+                "SSH host key mismatch (expected a, got b)",
+            ),
+            &[
+                "/0/action=probe",
+                "/1/args/profile_name=web-1",
+                "/1/args/connection/host_key_policy=tofu",
+            ],
+        ),
+        (
+            "sql",
+            json!({"action": "query", "profile_name": "orders-db", "sql": "SELECT 1"}),
+            ToolError::new(
+                ToolErrorKind::Denied,
+                "PG_AUTH_FAILED",
+                "password authentication failed",
+            ),
+            &[
+                "/0/action=profile_get",
+                "/0/args/profile_name=orders-db",
+                "/1/args/profile_name=orders-db",
+            ],
+        ),
+        (
+            "sql",
+            json!({"action": "query", "profile_name": "orders-db", "sql": "SELECT 1"}),
+            ToolError::new(
+                ToolErrorKind::Denied,
+                "PG_TLS_VERIFY_FAILED",
+                "certificate verify failed",
+            ),
+            &["/0/args/profile_name=orders-db", "/1/action=profile_test"],
+        ),
+        (
+            "api",
+            json!({"action": "request", "profile_name": "billing", "path": "/health"}),
+            ToolError::new(
+                ToolErrorKind::Denied,
+                "HTTP_TLS_VERIFY_FAILED",
+                "Server TLS certificate validation failed",
+            ),
+            &[
+                "/0/action=cert_check",
+                "/0/args/profiles/0=billing",
+                "/1/args/profile_name=billing",
+            ],
+        ),
+        (
+            "api",
+            json!({"action": "request", "profile_name": "billing", "path": "/health"}),
+            ToolError::new(
+                ToolErrorKind::Denied,
+                "HTTP_TLS_CLIENT_CERT_REJECTED",
+                "Server rejected the TLS client certificate",
+            ),
+            &["/0/action=profile_upsert", "/0/args/profile_name=billing"],
+        ),
+        (
+            "api",
+            json!({"action": "request", "profile_name": "metadata", "path": "/latest"}),
+            ToolError::new(
+                ToolErrorKind::Denied,
+                "HTTP_TARGET_DENIED",
+                "Target resolves to a private address",
+            )
+            .with_details(json!({"host": "10.0.0.5", "ip": "10.0.0.5", "rule": "private"})),
+            &[
+                "/0/args/profile_name=metadata",
+                "/0/args/ssrf/allow_hosts/0=10.0.0.5",
+            ],
+        ),
+        (
+            "capability",
+            json!({"action": "resolve", "intent": "deploy"}),
+            ToolError::new(
+                ToolErrorKind::Conflict,
+                "AMBIGUOUS_CAPABILITY",
+                "Several capabilities match intent 'deploy'",
+            )
+            .with_details(json!({"candidates": [{"name": "deploy.web"}, {"name": "deploy.api"}]})),
+            &["/0/args/name=deploy.web", "/1/args/name=deploy.api"],
+        ),
+    ];
+    assert_eq!(cases.len(), 15);
+
+    for (tool, args, err, expected) in cases {
+        let code = err.code.clone();
+        let enriched = enrich(err, &FailedCall::new(tool, &args));
+        assert!(enriched.hint.is_some(), "{}: no hint", code);
+        assert_valid(enriched.next_actions(), &code);
+        let rendered = Value::Array(enriched.next_actions().to_vec());
+        for check in expected {
+            let (pointer, value) = check.split_once('=').expect("pointer=value");
+            let actual = rendered.pointer(pointer).unwrap_or(&Value::Null);
+            // `apply=true` means the boolean; anything that is not JSON is a string.
+            let expected = serde_json::from_str(value).unwrap_or_else(|_| Value::from(value));
+            assert!(
+                *actual == expected,
+                "{}: {} is {} in {}",
+                code,
+                pointer,
+                actual,
+                rendered
+            );
+        }
+        assert!(!rendered.to_string().contains("hunter2"), "{}", rendered);
+    }
+}
+
+#[tokio::test]
+async fn executor_enriches_errors_without_overwriting_hints() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let keys = ["INFRA_PROFILES_DIR"];
+    let previous: Vec<Option<String>> = keys.iter().map(|key| std::env::var(key).ok()).collect();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+
+    let logger = Logger::new("test");
+    let security = Arc::new(Security::new().expect("security"));
+    let profile_service = Arc::new(ProfileService::new(security).expect("profile service"));
+    let postgres = Arc::new(PostgresManager::new(
+        logger.clone(),
+        Validation::new(),
+        profile_service,
+        None,
+        None,
+    ));
+    let mut handlers: HashMap<String, Arc<dyn ToolHandler>> = HashMap::new();
+    handlers.insert("sql".to_string(), postgres);
+    let executor = ToolExecutor::new(
+        logger,
+        Arc::new(StateService::new().expect("state")),
+        None,
+        None,
+        handlers,
+        HashMap::new(),
+    );
+
+    let err = executor
+        .execute(
+            "sql",
+            json!({"action": "query", "profile_name": "orders-db", "sql": "SELECT 1"}),
+        )
+        .await
+        .expect_err("missing profile");
+    assert_eq!(err.code, "PROFILE_NOT_FOUND");
+    assert_eq!(
+        err.hint.as_deref(),
+        Some("Use action=profile_list to see known profiles.")
+    );
+    assert_valid(err.next_actions(), "executor");
+    let upsert = err
+        .next_actions()
+        .iter()
+        .find(|next| next["action"] == "profile_upsert")
+        .expect("upsert skeleton");
+    assert_eq!(upsert["tool"], "sql");
+    assert_eq!(upsert["args"]["profile_name"], "orders-db");
+    let serialized = serde_json::to_value(&err).expect("serialize");
+    assert_eq!(serialized["next_actions"], json!(err.next_actions()));
+
+    // A misspelled action comes back as the corrected call.
+    let err = executor
+        .execute(
+            "sql",
+            json!({"action": "qurey", "profile_name": "orders-db", "sql": "SELECT 1"}),
+        )
+        .await
+        .expect_err("unknown action");
+    assert_eq!(err.code, "INVALID_PARAMS");
+    assert_valid(err.next_actions(), "executor");
+    assert_eq!(err.next_actions()[0]["action"], "query");
+    assert_eq!(err.next_actions()[0]["args"]["sql"], "SELECT 1");
+    assert!(err.next_actions()[0]["args"].get("trace_id").is_none());
+
+    for (key, value) in keys.iter().zip(previous) {
+        restore_env(key, value);
+    }
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
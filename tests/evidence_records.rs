@@ -0,0 +1,99 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::evidence::EvidenceManager;
+use infra::services::evidence::EvidenceService;
+use infra::services::logger::Logger;
+use infra::services::security::Security;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+#[tokio::test]
+async fn evidence_records_store_assertions_and_verify_reports_drift() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let prev_evidence = std::env::var("INFRA_EVIDENCE_DIR").ok();
+    let prev_unsafe = std::env::var("INFRA_UNSAFE_LOCAL").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    std::env::set_var("INFRA_EVIDENCE_DIR", tmp_dir.join("evidence"));
+    std::env::set_var("INFRA_UNSAFE_LOCAL", "1");
+
+    let artifact_path = tmp_dir.join("release.tar");
+    std::fs::write(&artifact_path, b"v1").expect("write artifact");
+    let captured_hash = hex::encode(Sha256::digest(b"v1"));
+
+    let logger = Logger::new("test");
+    let security = Security::new().expect("security");
+    let service = Arc::new(EvidenceService::new(logger.clone(), security));
+    let manager = EvidenceManager::new(logger, service, None, None, None);
+
+    let created = manager
+        .handle_action(serde_json::json!({
+            "action": "create",
+            "capability": "deploy.release",
+            "trace_ids": ["trace-1"],
+            "artifacts": ["artifact://runs/trace-1/tool_calls/span-1/result.json"],
+            "assertions": [
+                {
+                    "check": "file_hash",
+                    "target": { "via": "local", "path": artifact_path.to_string_lossy() },
+                    "expected": captured_hash,
+                    "actual": captured_hash
+                },
+                { "check": "http_status", "expected": 200, "actual": 200 }
+            ]
+        }))
+        .await
+        .expect("create evidence");
+    let id = created["id"].as_str().expect("evidence id").to_string();
+    let record = &created["record"];
+    assert_eq!(record["kind"], "record");
+    assert_eq!(record["trace_ids"], serde_json::json!(["trace-1"]));
+    assert_eq!(
+        record["artifacts"][0]["rel"],
+        "runs/trace-1/tool_calls/span-1/result.json"
+    );
+    assert_eq!(record["assertions"][1]["pass"], true);
+
+    let verified = manager
+        .handle_action(serde_json::json!({ "action": "evidence_verify", "id": id }))
+        .await
+        .expect("verify unchanged");
+    assert_eq!(verified["drift"], false);
+    assert_eq!(verified["passed"], true);
+    assert_eq!(verified["counts"]["verified"], 1);
+    assert_eq!(verified["counts"]["unverifiable"], 1);
+
+    std::fs::write(&artifact_path, b"v2").expect("rewrite artifact");
+    let drifted = manager
+        .handle_action(serde_json::json!({ "action": "evidence_verify", "id": id }))
+        .await
+        .expect("verify drifted");
+    assert_eq!(drifted["drift"], true);
+    assert_eq!(drifted["passed"], false);
+    assert_eq!(drifted["assertions"][0]["drift"], true);
+
+    let err = manager
+        .handle_action(serde_json::json!({
+            "action": "create",
+            "artifacts": ["artifact://../outside"]
+        }))
+        .await
+        .expect_err("escaping artifact refs are rejected");
+    assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+
+    restore_env("INFRA_UNSAFE_LOCAL", prev_unsafe);
+    restore_env("INFRA_EVIDENCE_DIR", prev_evidence);
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+}
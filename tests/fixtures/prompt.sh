@@ -0,0 +1,17 @@
+#!/bin/sh
+# Interactive prompt used by tests/local_pty.rs: refuses to run without a terminal, reports the
+# window size, then asks for confirmation and a name.
+if [ ! -t 0 ]; then
+    echo "stdin is not a terminal" >&2
+    exit 3
+fi
+echo "size: $(stty size)"
+printf '\033[1;33mAre you sure? [y/N]\033[0m '
+read -r answer
+if [ "$answer" != "y" ]; then
+    echo "aborted"
+    exit 1
+fi
+printf 'Name: '
+read -r name
+printf '\033[32mhello, %s\033[0m\n' "$name"
@@ -0,0 +1,126 @@
+use infra::app::App;
+use infra::utils::shutdown::{self, EXIT_SHUTDOWN_DRAINED, EXIT_SHUTDOWN_FORCED};
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+fn shutdown_entries(app: &App) -> Vec<Value> {
+    let read = app
+        .audit_service
+        .read_entries(
+            10,
+            0,
+            false,
+            &json!({"tool": "server", "action": "shutdown"}),
+        )
+        .expect("read audit");
+    read["entries"].as_array().cloned().unwrap_or_default()
+}
+
+#[tokio::test]
+async fn sigterm_drains_the_call_marks_jobs_and_audits_shutdown() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let prev_unsafe = std::env::var("INFRA_UNSAFE_LOCAL").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    std::env::set_var("INFRA_UNSAFE_LOCAL", "1");
+
+    let app = App::initialize().expect("app");
+    let started = app
+        .tool_executor
+        .execute(
+            "local",
+            json!({"action": "exec", "command": "sleep 30", "detached": true, "apply": true}),
+        )
+        .await
+        .expect("detached job");
+    let job_id = started["result"]["job_id"]
+        .as_str()
+        .expect("job id")
+        .to_string();
+
+    // A slow handler that only stops when told to, next to a real job_wait poll.
+    let observed = AtomicBool::new(false);
+    let call = async {
+        let slow = async {
+            tokio::select! {
+                _ = shutdown::cancelled() => observed.store(true, Ordering::SeqCst),
+                _ = tokio::time::sleep(Duration::from_secs(20)) => {}
+            }
+        };
+        let wait = app.job_manager.handle_action(
+            json!({"action": "job_wait", "job_id": job_id, "timeout_ms": 20_000, "poll_interval_ms": 100}),
+        );
+        let (_, wait) = tokio::join!(slow, wait);
+        wait
+    };
+    let signal = shutdown::listen().expect("signal handlers");
+    let pid = std::process::id().to_string();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        std::process::Command::new("kill")
+            .args(["-TERM", &pid])
+            .status()
+            .expect("send SIGTERM");
+    });
+    let supervised = shutdown::supervise(call, signal, Duration::from_secs(5)).await;
+    assert!(observed.load(Ordering::SeqCst), "handler saw cancellation");
+    assert_eq!(supervised.signal, Some("SIGTERM"));
+    assert_eq!(supervised.exit_code(), Some(EXIT_SHUTDOWN_DRAINED));
+    let wait = supervised
+        .outcome
+        .as_ref()
+        .expect("drained")
+        .as_ref()
+        .expect("job_wait result");
+    assert_eq!(wait["wait"]["interrupted"], true, "{}", wait);
+    assert_eq!(wait["wait"]["timed_out"], false);
+
+    app.close(&supervised).await;
+    assert_eq!(
+        app.job_service.get(&job_id).unwrap()["status"],
+        "interrupted"
+    );
+    let entries = shutdown_entries(&app);
+    assert_eq!(entries.len(), 1, "{:?}", entries);
+    assert_eq!(entries[0]["status"], "drained");
+    assert_eq!(entries[0]["signal"], "SIGTERM");
+    assert_eq!(entries[0]["interrupted_jobs"], 1);
+
+    // A handler that ignores cancellation is dropped once the window runs out.
+    let forced = shutdown::supervise(
+        tokio::time::sleep(Duration::from_secs(20)),
+        async { "SIGINT" },
+        Duration::from_millis(50),
+    )
+    .await;
+    assert!(forced.forced());
+    assert_eq!(forced.exit_code(), Some(EXIT_SHUTDOWN_FORCED));
+    app.close(&forced).await;
+    let entries = shutdown_entries(&app);
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[1]["status"], "forced");
+    assert_eq!(entries[1]["interrupted_jobs"], 0);
+
+    // Calls that finish before any signal leave no shutdown entry.
+    let quiet = shutdown::supervise(async {}, std::future::pending(), Duration::ZERO).await;
+    app.close(&quiet).await;
+    assert_eq!(shutdown_entries(&app).len(), 2);
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    restore_env("INFRA_UNSAFE_LOCAL", prev_unsafe);
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
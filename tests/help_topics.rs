@@ -0,0 +1,179 @@
+use infra::services::project::ProjectService;
+use infra::tooling::catalog::{check_tool_args, tool_by_name};
+use infra::tooling::topics::{capability_matrix, TOPICS};
+use serde_json::{json, Value};
+use std::process::Command;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+fn enum_values(tool: &str, property: &str) -> Vec<String> {
+    tool_by_name(tool)
+        .unwrap_or_else(|| panic!("tool {} is not in the catalog", tool))
+        .input_schema
+        .pointer(&format!("/properties/{}/enum", property))
+        .and_then(|v| v.as_array())
+        .map(|values| {
+            values
+                .iter()
+                .filter_map(|v| v.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn action_names(matrix: &Value) -> Vec<String> {
+    matrix["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .flat_map(|tool| {
+            let name = tool["tool"].as_str().unwrap().to_string();
+            tool["actions"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(move |action| format!("{}.{}", name, action["action"].as_str().unwrap()))
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn topic_registry_matches_the_catalog_and_runbook() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    let projects = ProjectService::new().expect("project service");
+
+    let runbook = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/docs/RUNBOOK.md"))
+        .expect("read RUNBOOK.md");
+    let flows = enum_values("pipeline", "flow");
+    for topic in TOPICS {
+        for entry in topic.tools {
+            let actions = enum_values(entry.tool, "action");
+            for action in entry.actions {
+                assert!(
+                    actions.iter().any(|known| known == action),
+                    "topic {}: {}.{} is not in the catalog",
+                    topic.name,
+                    entry.tool,
+                    action
+                );
+            }
+        }
+        for flow in topic.flows {
+            assert!(
+                flows.iter().any(|known| known == flow),
+                "topic {}: unknown pipeline flow {}",
+                topic.name,
+                flow
+            );
+        }
+        for section in topic.runbook_sections {
+            assert!(
+                runbook.contains(&format!("\n- {}:", section)),
+                "topic {}: RUNBOOK.md has no '{}' section",
+                topic.name,
+                section
+            );
+        }
+        if let Some(profile) = &topic.profile {
+            let example: Value = serde_json::from_str(profile.example).expect("example is JSON");
+            let report = check_tool_args(profile.tool, &example);
+            assert!(
+                report.is_valid(true),
+                "topic {}: upsert example fails the {} contract: {}",
+                topic.name,
+                profile.tool,
+                report.to_contract_error(true).message
+            );
+            // Known binding keys are checked on write: an empty value is refused.
+            projects
+                .set_project(
+                    "help-topics",
+                    &json!({"targets": {"prod": {profile.binding: ""}}}),
+                )
+                .expect_err(profile.binding);
+        }
+        capability_matrix(topic.name).expect("matrix renders");
+    }
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
+
+#[tokio::test]
+async fn capability_matrix_follows_readonly_mode() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_readonly = std::env::var("INFRA_READONLY").ok();
+    std::env::remove_var("INFRA_READONLY");
+    let full = capability_matrix("Postgres").expect("postgres");
+    assert_eq!(full["profile"]["type"], "postgresql");
+    assert_eq!(full["project_binding"], "postgres_profile");
+    assert!(full["pipeline_flows"]
+        .as_array()
+        .unwrap()
+        .contains(&json!("http_to_postgres")));
+    assert!(action_names(&full).contains(&"sql.insert_bulk".to_string()));
+    assert_eq!(full["hidden_by_readonly"], json!([]));
+
+    std::env::set_var("INFRA_READONLY", "1");
+    let readonly = capability_matrix("postgres").expect("postgres readonly");
+    assert_eq!(readonly["readonly"], true);
+    let visible = action_names(&readonly);
+    assert!(visible.contains(&"sql.select".to_string()));
+    assert!(!visible.contains(&"sql.insert_bulk".to_string()));
+    assert!(readonly["hidden_by_readonly"]
+        .as_array()
+        .unwrap()
+        .contains(&json!("sql.insert_bulk")));
+    restore_env("INFRA_READONLY", prev_readonly);
+
+    let err = capability_matrix("postgers").expect_err("unknown topic");
+    assert_eq!(err.message, "unknown help topic 'postgers'");
+    assert_eq!(err.details.unwrap()["did_you_mean"], "postgres");
+}
+
+#[test]
+fn describe_help_lists_topics_and_renders_one() {
+    let _guard = ENV_LOCK.blocking_lock();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    let run = |args: &[&str]| -> Value {
+        let output = Command::new(env!("CARGO_BIN_EXE_infra"))
+            .current_dir(&tmp_dir)
+            .env("INFRA_PROFILES_DIR", &tmp_dir)
+            .env_remove("INFRA_READONLY")
+            .args(args)
+            .output()
+            .expect("run infra");
+        serde_json::from_slice(&output.stdout).expect("parse cli json")
+    };
+
+    let index = run(&["describe", "help"]);
+    let topics: Vec<&str> = index["result"]["topics"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|topic| topic["topic"].as_str().unwrap())
+        .collect();
+    assert_eq!(topics, ["postgres", "ssh", "http", "files", "jobs"]);
+
+    let jobs = run(&["describe", "help", "--arg", "topic=jobs"]);
+    assert_eq!(jobs["ok"], true, "{}", jobs);
+    assert!(action_names(&jobs["result"]).contains(&"job.follow_job".to_string()));
+    assert_eq!(jobs["result"]["profile"], Value::Null);
+
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
@@ -0,0 +1,233 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::intent::IntentManager;
+use infra::services::capability::CapabilityService;
+use infra::services::evidence::EvidenceService;
+use infra::services::logger::Logger;
+use infra::services::runbook::RunbookService;
+use infra::services::security::Security;
+use infra::services::state::StateService;
+use infra::services::tool_executor::{ToolExecutor, ToolHandler};
+use infra::services::validation::Validation;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+mod common;
+use common::ENV_LOCK;
+
+// Stands in for the sql tool: answers count/select probes and records every action.
+#[derive(Clone)]
+struct FakeSql {
+    actions: Arc<Mutex<Vec<String>>>,
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for FakeSql {
+    async fn handle(&self, args: Value) -> Result<Value, infra::errors::ToolError> {
+        let action = args
+            .get("action")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string();
+        self.actions.lock().unwrap().push(action.clone());
+        Ok(match action.as_str() {
+            "count" => serde_json::json!({"success": true, "count": 2}),
+            "select" => serde_json::json!({
+                "success": true,
+                "result": {"rows": [{"status": "active"}, {"status": "trial"}]},
+                "columns": args.get("columns").cloned().unwrap_or(Value::Null),
+            }),
+            _ => serde_json::json!({"success": true, "rowCount": 2}),
+        })
+    }
+}
+
+fn write_json(path: &std::path::Path, value: &Value) {
+    let payload = serde_json::to_string_pretty(value).expect("serialize json");
+    std::fs::write(path, format!("{}\n", payload)).expect("write file");
+}
+
+fn set_env(key: &str, value: &std::path::Path) {
+    std::env::set_var(key, value.to_string_lossy().as_ref());
+}
+
+#[tokio::test]
+async fn preview_probes_write_steps_read_only_and_links_the_apply() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+
+    let runbooks_path = tmp_dir.join("runbooks.json");
+    write_json(
+        &runbooks_path,
+        &serde_json::json!({
+            "users.suspend": {
+                "tags": ["write"],
+                "steps": [
+                    {
+                        "id": "suspend",
+                        "tool": "sql",
+                        "args": {
+                            "action": "update",
+                            "table": "users",
+                            "data": { "status": "{{input.status}}" },
+                            "filters": { "plan": "{{input.plan}}" }
+                        }
+                    },
+                    {
+                        "id": "audit_row",
+                        "tool": "sql",
+                        "args": { "action": "insert", "table": "audit", "data": { "what": "suspend" } }
+                    }
+                ]
+            }
+        }),
+    );
+    let capabilities_path = tmp_dir.join("capabilities.json");
+    write_json(
+        &capabilities_path,
+        &serde_json::json!({
+            "version": 1,
+            "capabilities": {
+                "users.suspend": {
+                    "intent": "users.suspend",
+                    "description": "suspend users of a plan",
+                    "runbook": "users.suspend",
+                    "inputs": { "required": ["plan", "status"], "defaults": {}, "map": {} },
+                    "when": {},
+                    "effects": { "kind": "write", "requires_apply": true, "irreversible": false }
+                }
+            }
+        }),
+    );
+
+    set_env("INFRA_PROFILES_DIR", &tmp_dir);
+    set_env("INFRA_DEFAULT_RUNBOOKS_PATH", &runbooks_path);
+    set_env("INFRA_DEFAULT_CAPABILITIES_PATH", &capabilities_path);
+
+    let logger = Logger::new("test");
+    let security = Arc::new(Security::new().expect("security"));
+    let state_service = Arc::new(StateService::new().expect("state"));
+    let intent_manager = Arc::new(IntentManager::new(
+        logger.clone(),
+        security.clone(),
+        Validation::new(),
+        Arc::new(CapabilityService::new(security.clone()).expect("capability service")),
+        Arc::new(RunbookService::new().expect("runbook service")),
+        Arc::new(EvidenceService::new(
+            logger.clone(),
+            security.as_ref().clone(),
+        )),
+        state_service.clone(),
+        None,
+        None,
+        None,
+    ));
+
+    let actions = Arc::new(Mutex::new(Vec::new()));
+    let mut handlers: HashMap<String, Arc<dyn ToolHandler>> = HashMap::new();
+    handlers.insert(
+        "sql".to_string(),
+        Arc::new(FakeSql {
+            actions: actions.clone(),
+        }),
+    );
+    let tool_executor = Arc::new(ToolExecutor::new(
+        logger,
+        state_service.clone(),
+        None,
+        None,
+        handlers,
+        HashMap::new(),
+    ));
+    intent_manager.set_tool_executor(tool_executor.clone());
+
+    let intent = serde_json::json!({
+        "type": "users.suspend",
+        "inputs": { "plan": "legacy", "status": "suspended" }
+    });
+    let preview = intent_manager
+        .handle_action(serde_json::json!({"action": "preview", "intent": intent}))
+        .await
+        .expect("preview");
+    assert_eq!(*actions.lock().unwrap(), vec!["count", "select"]);
+    let findings = preview["findings"].as_array().expect("findings");
+    assert_eq!(findings.len(), 2);
+    assert_eq!(findings[0]["step"], "users.suspend/suspend");
+    assert_eq!(findings[0]["kind"], "sql_rows");
+    assert_eq!(findings[0]["affected_rows"], 2);
+    assert_eq!(findings[0]["set_columns"], serde_json::json!(["status"]));
+    assert_eq!(findings[0]["sample"][0]["status"], "active");
+    assert_eq!(findings[1]["status"], "unsupported");
+
+    let preview_id = preview["preview_id"].as_str().expect("preview_id");
+    let stored = state_service
+        .get(
+            &format!("intent_preview/{}", preview_id),
+            Some("persistent"),
+        )
+        .expect("stored preview");
+    assert_eq!(stored["value"]["findings"], preview["findings"]);
+
+    let applied = intent_manager
+        .handle_action(serde_json::json!({
+            "action": "execute",
+            "apply": true,
+            "preview_id": preview_id,
+            "intent": intent,
+        }))
+        .await
+        .expect("execute");
+    assert_eq!(applied["preview"]["matched"], true);
+    assert_eq!(applied["evidence"]["preview"]["preview_id"], preview_id);
+    assert_eq!(
+        *actions.lock().unwrap(),
+        vec!["count", "select", "update", "insert"]
+    );
+
+    // The preview was consumed: applying it again runs nothing and points at the first apply.
+    let again = intent_manager
+        .handle_action(serde_json::json!({
+            "action": "execute",
+            "apply": true,
+            "preview_id": preview_id,
+            "intent": intent,
+        }))
+        .await
+        .expect("execute again");
+    assert_eq!(again["already_applied"], true);
+    assert_eq!(
+        again["applied"]["trace_id"],
+        applied["intent_record"]["applied"]["trace_id"]
+    );
+    assert_eq!(actions.lock().unwrap().len(), 4);
+
+    let second = intent_manager
+        .handle_action(serde_json::json!({"action": "preview", "intent": intent}))
+        .await
+        .expect("second preview");
+    let drifted = intent_manager
+        .handle_action(serde_json::json!({
+            "action": "execute",
+            "apply": true,
+            "preview_id": second["preview_id"],
+            "intent": { "type": "users.suspend", "inputs": { "plan": "pro", "status": "suspended" } },
+        }))
+        .await
+        .expect("execute with other inputs");
+    assert_eq!(drifted["preview"]["matched"], false);
+
+    let err = intent_manager
+        .handle_action(serde_json::json!({
+            "action": "execute",
+            "apply": true,
+            "preview_id": "missing",
+            "intent": intent,
+        }))
+        .await
+        .expect_err("unknown preview");
+    assert_eq!(err.kind, ToolErrorKind::NotFound);
+
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
@@ -0,0 +1,364 @@
+use infra::errors::{ToolError, ToolErrorKind};
+use infra::managers::intent::IntentManager;
+use infra::services::audit::AuditService;
+use infra::services::capability::CapabilityService;
+use infra::services::evidence::EvidenceService;
+use infra::services::logger::Logger;
+use infra::services::runbook::RunbookService;
+use infra::services::security::Security;
+use infra::services::state::StateService;
+use infra::services::tool_executor::{ToolExecutor, ToolHandler};
+use infra::services::validation::Validation;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+mod common;
+use common::ENV_LOCK;
+
+// Stands in for the sql tool: an update to status "broken" fails, one to "slow" takes a while,
+// everything else succeeds.
+#[derive(Clone)]
+struct FakeSql {
+    updates: Arc<Mutex<usize>>,
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for FakeSql {
+    async fn handle(&self, args: Value) -> Result<Value, ToolError> {
+        match args.get("action").and_then(|v| v.as_str()).unwrap_or("") {
+            "update" => {
+                *self.updates.lock().unwrap() += 1;
+                if args["data"]["status"] == "slow" {
+                    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+                }
+                if args["data"]["status"] == "broken" {
+                    return Err(ToolError::internal("update failed"));
+                }
+                Ok(json!({"success": true, "rowCount": 1}))
+            }
+            "count" => Ok(json!({"success": true, "count": 1})),
+            _ => Ok(json!({"success": true, "result": {"rows": []}})),
+        }
+    }
+}
+
+fn write_json(path: &std::path::Path, value: &Value) {
+    let payload = serde_json::to_string_pretty(value).expect("serialize json");
+    std::fs::write(path, format!("{}\n", payload)).expect("write file");
+}
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+fn intent(status: &str) -> Value {
+    json!({"type": "users.suspend", "inputs": {"plan": "legacy", "status": status}})
+}
+
+const ENV_KEYS: [&str; 4] = [
+    "INFRA_PROFILES_DIR",
+    "INFRA_DEFAULT_RUNBOOKS_PATH",
+    "INFRA_DEFAULT_CAPABILITIES_PATH",
+    "INFRA_AUDIT_PATH",
+];
+
+// Writes the users.suspend runbook and capability into `tmp_dir` and points the env at them.
+fn prepare_env(tmp_dir: &std::path::Path) -> Vec<Option<String>> {
+    std::fs::create_dir_all(tmp_dir).expect("create temp dir");
+    let runbooks_path = tmp_dir.join("runbooks.json");
+    write_json(
+        &runbooks_path,
+        &json!({
+            "users.suspend": {
+                "tags": ["write"],
+                "steps": [{
+                    "id": "suspend",
+                    "tool": "sql",
+                    "args": {
+                        "action": "update",
+                        "table": "users",
+                        "data": {"status": "{{input.status}}"},
+                        "filters": {"plan": "{{input.plan}}"}
+                    }
+                }]
+            }
+        }),
+    );
+    let capabilities_path = tmp_dir.join("capabilities.json");
+    write_json(
+        &capabilities_path,
+        &json!({
+            "version": 1,
+            "capabilities": {
+                "users.suspend": {
+                    "intent": "users.suspend",
+                    "description": "suspend users of a plan",
+                    "runbook": "users.suspend",
+                    "inputs": {"required": ["plan", "status"], "defaults": {}, "map": {}},
+                    "when": {},
+                    "effects": {"kind": "write", "requires_apply": true, "irreversible": false}
+                }
+            }
+        }),
+    );
+    let previous = ENV_KEYS.iter().map(|key| std::env::var(key).ok()).collect();
+    let audit_path = tmp_dir.join("audit.jsonl");
+    for (key, path) in ENV_KEYS.iter().zip([
+        tmp_dir,
+        runbooks_path.as_path(),
+        capabilities_path.as_path(),
+        audit_path.as_path(),
+    ]) {
+        std::env::set_var(key, path);
+    }
+    previous
+}
+
+// One intent manager wired to its own executor and state service, as a separate process would be.
+fn build(updates: &Arc<Mutex<usize>>) -> (Arc<IntentManager>, Arc<ToolExecutor>) {
+    let logger = Logger::new("test");
+    let security = Arc::new(Security::new().expect("security"));
+    let state_service = Arc::new(StateService::new().expect("state"));
+    let intent_manager = Arc::new(IntentManager::new(
+        logger.clone(),
+        security.clone(),
+        Validation::new(),
+        Arc::new(CapabilityService::new(security.clone()).expect("capability service")),
+        Arc::new(RunbookService::new().expect("runbook service")),
+        Arc::new(EvidenceService::new(
+            logger.clone(),
+            security.as_ref().clone(),
+        )),
+        state_service.clone(),
+        None,
+        None,
+        None,
+    ));
+    let mut handlers: HashMap<String, Arc<dyn ToolHandler>> = HashMap::new();
+    handlers.insert(
+        "sql".to_string(),
+        Arc::new(FakeSql {
+            updates: updates.clone(),
+        }),
+    );
+    handlers.insert("intent".to_string(), intent_manager.clone());
+    let tool_executor = Arc::new(ToolExecutor::new(
+        logger.clone(),
+        state_service,
+        None,
+        Some(Arc::new(AuditService::new(logger))),
+        handlers,
+        HashMap::new(),
+    ));
+    intent_manager.set_tool_executor(tool_executor.clone());
+    (intent_manager, tool_executor)
+}
+
+#[tokio::test]
+async fn previews_expire_bind_scope_and_apply_once() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    let previous = prepare_env(&tmp_dir);
+    let audit_path = tmp_dir.join("audit.jsonl");
+    let updates = Arc::new(Mutex::new(0usize));
+    let (intent_manager, tool_executor) = build(&updates);
+    let call = |args: Value| intent_manager.handle_action(args);
+    let preview = |extra: Value| {
+        let mut args = json!({"action": "preview", "intent": intent("suspended")});
+        args.as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        call(args)
+    };
+    let execute = |preview_id: &Value, intent: Value, extra: Value| {
+        let mut args = json!({
+            "action": "execute",
+            "apply": true,
+            "preview_id": preview_id,
+            "intent": intent,
+        });
+        args.as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        call(args)
+    };
+
+    // Bound to the project/target it was taken for.
+    let scoped = preview(json!({"project": "shop", "target": "prod"}))
+        .await
+        .expect("scoped preview");
+    assert_eq!(
+        scoped["scope"],
+        json!({"project": "shop", "target": "prod"})
+    );
+    assert!(scoped["expires_at"].is_string());
+    let err = execute(
+        &scoped["preview_id"],
+        intent("suspended"),
+        json!({"project": "shop", "target": "stage"}),
+    )
+    .await
+    .expect_err("other target");
+    assert_eq!(err.code, "INTENT_SCOPE_MISMATCH");
+    assert_eq!(
+        err.details.as_ref().unwrap()["resolved_scope"]["target"],
+        "stage"
+    );
+    assert_eq!(*updates.lock().unwrap(), 0);
+
+    // Applied through the executor: the audit entry names the preview and its scope.
+    let applied = tool_executor
+        .execute(
+            "intent",
+            json!({
+                "action": "execute",
+                "apply": true,
+                "preview_id": scoped["preview_id"],
+                "intent": intent("suspended"),
+                "project": "shop",
+                "target": "prod",
+            }),
+        )
+        .await
+        .expect("apply");
+    let record = &applied["result"]["intent_record"];
+    assert_eq!(record["status"], "applied", "{}", applied);
+    assert_eq!(record["attempts"], 1);
+    let audit = std::fs::read_to_string(&audit_path).expect("audit file");
+    let entry: Value = audit
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .find(|entry| entry["tool"] == "intent")
+        .expect("intent audit entry");
+    assert_eq!(entry["intent"]["preview_id"], scoped["preview_id"]);
+    assert_eq!(entry["intent"]["scope"]["project"], "shop");
+
+    // Failed applies leave the preview applicable until max_attempts is used up.
+    let flaky = preview(json!({"max_attempts": 2}))
+        .await
+        .expect("flaky preview");
+    for attempt in 1..=2 {
+        let outcome = execute(&flaky["preview_id"], intent("broken"), json!({})).await;
+        let record = match outcome {
+            Ok(result) => {
+                assert_eq!(result["success"], false);
+                result["intent_record"].clone()
+            }
+            Err(err) => err.details.expect("details")["intent_record"].clone(),
+        };
+        assert_eq!(record["attempts"], attempt);
+        assert_eq!(
+            record["status"],
+            if attempt == 2 { "exhausted" } else { "pending" }
+        );
+    }
+    let err = execute(&flaky["preview_id"], intent("broken"), json!({}))
+        .await
+        .expect_err("exhausted");
+    assert_eq!(err.code, "INTENT_EXHAUSTED");
+    assert_eq!(*updates.lock().unwrap(), 3);
+
+    let short = preview(json!({"ttl_ms": 50})).await.expect("short preview");
+    tokio::time::sleep(std::time::Duration::from_millis(120)).await;
+    let err = execute(&short["preview_id"], intent("suspended"), json!({}))
+        .await
+        .expect_err("expired");
+    assert_eq!(err.code, "INTENT_EXPIRED");
+    assert_eq!(err.kind, ToolErrorKind::Denied);
+
+    let listed = call(json!({"action": "list"})).await.expect("list");
+    let statuses: HashMap<String, String> = listed["previews"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|p| {
+            (
+                p["preview_id"].as_str().unwrap().to_string(),
+                p["status"].as_str().unwrap().to_string(),
+            )
+        })
+        .collect();
+    for (preview, status) in [
+        (&scoped, "applied"),
+        (&flaky, "exhausted"),
+        (&short, "expired"),
+    ] {
+        assert_eq!(statuses[preview["preview_id"].as_str().unwrap()], status);
+    }
+    let applied_only = call(json!({"action": "list", "status": "applied"}))
+        .await
+        .expect("list applied");
+    assert_eq!(applied_only["count"], 1);
+
+    for (extra, message) in [
+        (
+            json!({"ttl_ms": 1000, "expires_at": "2099-01-01T00:00:00Z"}),
+            "Pass either ttl_ms or expires_at, not both",
+        ),
+        (
+            json!({"expires_at": "2001-01-01T00:00:00Z"}),
+            "expires_at must be in the future",
+        ),
+    ] {
+        let err = preview(extra).await.expect_err(message);
+        assert_eq!(err.message, message);
+    }
+
+    for (key, value) in ENV_KEYS.iter().zip(previous) {
+        restore_env(key, value);
+    }
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
+
+#[tokio::test]
+async fn concurrent_executes_from_separate_executors_apply_a_preview_once() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    let previous = prepare_env(&tmp_dir);
+    let updates = Arc::new(Mutex::new(0usize));
+    let (first, first_executor) = build(&updates);
+    let (_second, second_executor) = build(&updates);
+
+    let preview = first
+        .handle_action(json!({"action": "preview", "intent": intent("slow")}))
+        .await
+        .expect("preview");
+    let execute = json!({
+        "action": "execute",
+        "apply": true,
+        "preview_id": preview["preview_id"],
+        "intent": intent("slow"),
+    });
+    let (a, b) = tokio::join!(
+        first_executor.execute("intent", execute.clone()),
+        second_executor.execute("intent", execute.clone()),
+    );
+    let (applied, refused) = match (a, b) {
+        (Ok(applied), Err(refused)) | (Err(refused), Ok(applied)) => (applied, refused),
+        other => panic!("expected one apply and one refusal: {:?}", other),
+    };
+    assert_eq!(applied["result"]["intent_record"]["status"], "applied");
+    assert_eq!(applied["result"]["intent_record"]["attempts"], 1);
+    assert_eq!(refused.code, "INTENT_APPLY_IN_PROGRESS");
+    assert_eq!(*updates.lock().unwrap(), 1);
+
+    // The claim is released once the outcome is recorded; a later execute sees the apply.
+    let again = second_executor
+        .execute("intent", execute)
+        .await
+        .expect("execute again");
+    assert_eq!(again["result"]["already_applied"], true);
+    assert_eq!(again["result"]["intent_record"]["attempts"], 1);
+    assert_eq!(*updates.lock().unwrap(), 1);
+
+    for (key, value) in ENV_KEYS.iter().zip(previous) {
+        restore_env(key, value);
+    }
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
@@ -0,0 +1,132 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::jobs::JobManager;
+use infra::managers::local::LocalManager;
+use infra::services::job::JobService;
+use infra::services::logger::Logger;
+use infra::services::validation::Validation;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+#[tokio::test]
+async fn wait_all_aggregates_job_outcomes_and_can_be_reissued() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let prev_context = std::env::var("INFRA_CONTEXT_REPO_ROOT").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    let context_root = tmp_dir.join("context");
+    std::fs::create_dir_all(&context_root).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    std::env::set_var("INFRA_CONTEXT_REPO_ROOT", &context_root);
+
+    let job_service = Arc::new(JobService::new(Logger::new("test")).expect("job service"));
+    let local = LocalManager::new(Logger::new("test"), Validation::new(), Some(true))
+        .with_job_service(job_service.clone());
+    let jobs = JobManager::new(
+        Logger::new("test"),
+        Validation::new(),
+        job_service.clone(),
+        None,
+    );
+    let start = |command: &str| {
+        local.handle_action(json!({"action": "exec", "command": command, "detached": true}))
+    };
+    let ok = start("echo fine").await.expect("ok job")["job_id"].clone();
+    let failing = start("echo step one; echo boom >&2; exit 4")
+        .await
+        .expect("failing job")["job_id"]
+        .clone();
+    let slow = start("sleep 30").await.expect("slow job")["job_id"].clone();
+
+    let wait_all = |jobs_arg: Value, extra: Value| {
+        let mut args = json!({
+            "action": "job_wait_all",
+            "jobs": jobs_arg,
+            "timeout_ms": 1500,
+            "poll_interval_ms": 50,
+        });
+        args.as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        jobs.handle_action(args)
+    };
+    let first = wait_all(
+        json!([ok, {"job_id": failing}, slow, "missing-job"]),
+        json!({"lines": 1}),
+    )
+    .await
+    .expect("wait all");
+    assert_eq!(first["all_succeeded"], false, "{}", first);
+    assert_eq!(first["succeeded"], json!([ok]));
+    assert_eq!(first["failed"], json!([failing]));
+    assert_eq!(first["still_running"], json!([slow]));
+    assert_eq!(first["unknown"], json!(["missing-job"]));
+    assert_eq!(first["wait"]["timed_out"], true);
+    assert_eq!(first["wait"]["remote_probes"], 0);
+    let entries = first["jobs"].as_array().unwrap();
+    assert_eq!(entries[1]["exit_code"], 4);
+    assert_eq!(entries[1]["logs"]["text"], "boom");
+    assert!(
+        entries[0].get("logs").is_none(),
+        "only failed jobs get a tail"
+    );
+    assert_eq!(entries[3]["code"], "NOT_FOUND");
+    assert_eq!(first["next"]["jobs"], json!([{"job_id": slow}]));
+    assert_eq!(first["next"]["lines"], 1);
+
+    // Re-issuing changes nothing; `next` continues with only the unfinished job.
+    let again = jobs
+        .handle_action(first["next"].clone())
+        .await
+        .expect("continue waiting");
+    assert_eq!(again["still_running"], json!([slow]));
+    assert_eq!(again["succeeded"], json!([]));
+    jobs.handle_action(json!({"action": "job_kill", "job_id": slow}))
+        .await
+        .expect("kill slow job");
+    let done = wait_all(json!([ok, slow]), json!({}))
+        .await
+        .expect("wait after kill");
+    assert_eq!(done["wait"]["completed"], true);
+    assert_eq!(done["failed"], json!([slow]));
+    assert!(done.get("next").is_none());
+    assert!(done["wait"]["waited_ms"].as_u64().unwrap() < 1500);
+
+    // ssh specs need a host to probe; without the ssh manager they are reported, not waited on.
+    let orphan = wait_all(
+        json!([{"pid_path": "/tmp/x.pid", "profile_name": "web"}]),
+        json!({}),
+    )
+    .await
+    .expect("ssh spec");
+    assert_eq!(orphan["unknown"], json!(["web:/tmp/x.pid"]));
+
+    for (jobs_arg, message) in [
+        (
+            json!([]),
+            "jobs must be a non-empty array of job ids or {pid|pid_path, exit_path, log_path, profile_name} specs",
+        ),
+        (
+            json!([{"log_path": "/tmp/x.log"}]),
+            "jobs[0] needs job_id, pid or pid_path",
+        ),
+    ] {
+        let err = wait_all(jobs_arg, json!({})).await.expect_err(message);
+        assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+        assert_eq!(err.message, message);
+    }
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    restore_env("INFRA_CONTEXT_REPO_ROOT", prev_context);
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
@@ -0,0 +1,299 @@
+use infra::managers::api::ApiManager;
+use infra::managers::jobs::JobManager;
+use infra::managers::local::LocalManager;
+use infra::managers::pipeline::PipelineManager;
+use infra::managers::postgres::PostgresManager;
+use infra::managers::ssh::SshManager;
+use infra::services::job::JobService;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+fn pipeline(job_service: Arc<JobService>) -> PipelineManager {
+    let logger = Logger::new("test");
+    let security = Arc::new(Security::new().expect("security"));
+    let profile_service = Arc::new(ProfileService::new(security.clone()).expect("profile service"));
+    let api = ApiManager::new(
+        logger.clone(),
+        Validation::new(),
+        profile_service.clone(),
+        None,
+        None,
+        None,
+    );
+    let ssh = SshManager::new(
+        logger.clone(),
+        security,
+        Validation::new(),
+        profile_service.clone(),
+        None,
+        None,
+        None,
+    );
+    let postgres = PostgresManager::new(
+        logger.clone(),
+        Validation::new(),
+        profile_service,
+        None,
+        None,
+    );
+    PipelineManager::new(
+        logger,
+        Validation::new(),
+        Arc::new(api),
+        Arc::new(ssh),
+        Arc::new(postgres),
+        None,
+        None,
+        None,
+        None,
+    )
+    .with_job_service(job_service)
+}
+
+// Killed processes reparented to a non-reaping init linger as zombies; those count as gone.
+fn pid_alive(pid: &str) -> bool {
+    std::process::Command::new("ps")
+        .args(["-o", "stat=", "-p", pid])
+        .output()
+        .map(|out| {
+            let stat = String::from_utf8_lossy(&out.stdout).trim().to_string();
+            !stat.is_empty() && !stat.starts_with('Z')
+        })
+        .unwrap_or(false)
+}
+
+async fn follow(jobs: &JobManager, job_id: &Value) -> Value {
+    jobs.handle_action(json!({
+        "action": "follow_job",
+        "job_id": job_id,
+        "timeout_ms": 10_000,
+        "poll_interval_ms": 50,
+    }))
+    .await
+    .expect("follow job")
+}
+
+#[tokio::test]
+async fn local_and_pipeline_jobs_run_in_the_background() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let prev_context = std::env::var("INFRA_CONTEXT_REPO_ROOT").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    let context_root = tmp_dir.join("context");
+    std::fs::create_dir_all(&context_root).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    std::env::set_var("INFRA_CONTEXT_REPO_ROOT", &context_root);
+
+    let job_service = Arc::new(JobService::new(Logger::new("test")).expect("job service"));
+    let local = LocalManager::new(Logger::new("test"), Validation::new(), Some(true))
+        .with_job_service(job_service.clone());
+    let jobs = JobManager::new(
+        Logger::new("test"),
+        Validation::new(),
+        job_service.clone(),
+        None,
+    );
+
+    let started = local
+        .handle_action(json!({
+            "action": "exec",
+            "command": "echo hello; sleep 0.2; echo done >&2",
+            "detached": true,
+            "trace_id": "trace-jobs",
+        }))
+        .await
+        .expect("detached exec");
+    assert_eq!(started["detached"], true);
+    assert!(started["log_uri"]
+        .as_str()
+        .unwrap()
+        .starts_with("artifact://runs/trace-jobs/job-"));
+    let followed = follow(&jobs, &started["job_id"]).await;
+    assert_eq!(followed["wait"]["completed"], true);
+    assert_eq!(followed["job"]["raw_status"], "succeeded");
+    assert_eq!(followed["job"]["result"]["exit_code"], 0);
+    assert!(followed["job"]["ended_at"].is_string());
+    let tail = jobs
+        .handle_action(json!({"action": "tail_job", "job_id": started["job_id"]}))
+        .await
+        .expect("tail job");
+    assert_eq!(tail["logs"]["text"], "hello\ndone");
+
+    let failing = local
+        .handle_action(json!({"action": "exec", "command": "exit 3", "detached": true}))
+        .await
+        .expect("detached exec");
+    let followed = follow(&jobs, &failing["job_id"]).await;
+    assert_eq!(followed["job"]["raw_status"], "failed");
+    assert_eq!(followed["job"]["result"]["exit_code"], 3);
+
+    // job_kill takes the whole process group, including the backgrounded grandchild.
+    let pid_file = tmp_dir.join("grandchild.pid");
+    let sleeper = local
+        .handle_action(json!({
+            "action": "exec",
+            "command": format!("sleep 30 & echo $! > {}; wait", pid_file.display()),
+            "detached": true,
+        }))
+        .await
+        .expect("detached sleeper");
+    let mut grandchild = String::new();
+    for _ in 0..100 {
+        grandchild = std::fs::read_to_string(&pid_file)
+            .unwrap_or_default()
+            .trim()
+            .to_string();
+        if !grandchild.is_empty() {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+    assert!(pid_alive(&grandchild));
+    let killed = jobs
+        .handle_action(json!({"action": "job_kill", "job_id": sleeper["job_id"]}))
+        .await
+        .expect("kill job");
+    assert_eq!(killed["killed"], true);
+    assert_eq!(killed["job"]["raw_status"], "canceled");
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+    assert!(!pid_alive(&grandchild));
+
+    let pending = local
+        .handle_action(json!({"action": "exec", "command": "sleep 30", "detached": true}))
+        .await
+        .expect("detached exec");
+    assert_eq!(job_service.interrupt_local_jobs(), 1);
+    let status = jobs
+        .handle_action(json!({"action": "job_status", "job_id": pending["job_id"]}))
+        .await
+        .expect("job status");
+    assert_eq!(status["job"]["raw_status"], "interrupted");
+
+    // A record left running by a process that is gone is interrupted on the next start.
+    let mut exited = std::process::Command::new("true").spawn().expect("spawn");
+    let dead_pid = exited.id();
+    exited.wait().expect("wait");
+    job_service.upsert(json!({
+        "job_id": "orphan",
+        "kind": "local_exec",
+        "status": "running",
+        "provider": {"tool": "local", "owner_pid": dead_pid},
+    }));
+    // One whose owner is still alive is left alone.
+    let mut owner = std::process::Command::new("sleep")
+        .arg("30")
+        .spawn()
+        .expect("spawn");
+    job_service.upsert(json!({
+        "job_id": "owned",
+        "kind": "local_exec",
+        "status": "running",
+        "provider": {"tool": "local", "owner_pid": owner.id()},
+    }));
+    let restarted = JobService::new(Logger::new("test")).expect("job service");
+    assert_eq!(
+        restarted.get("orphan").unwrap()["status"],
+        json!("interrupted")
+    );
+    assert_eq!(restarted.get("owned").unwrap()["status"], json!("running"));
+    owner.kill().expect("kill owner");
+    owner.wait().expect("wait owner");
+
+    let pipeline = pipeline(job_service.clone());
+    let err = pipeline
+        .handle_action(json!({"action": "run", "flow": "http_to_nowhere", "background": true}))
+        .await
+        .expect_err("flow validated before the job starts");
+    assert!(err.message.contains("http_to_nowhere"), "{}", err.message);
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+    let closed_port = listener.local_addr().unwrap().port();
+    drop(listener);
+    let run = pipeline
+        .handle_action(json!({
+            "action": "run",
+            "flow": "http_to_postgres",
+            "background": true,
+            "http": {"url": format!("http://127.0.0.1:{}/rows.jsonl", closed_port)},
+            "postgres": {"connection_url": "postgres://app@127.0.0.1:1/app", "table": "events"},
+        }))
+        .await
+        .expect("background run");
+    assert_eq!(run["background"], true);
+    let followed = follow(&jobs, &run["job_id"]).await;
+    assert_eq!(followed["job"]["raw_status"], "failed");
+    assert_eq!(followed["job"]["provider"]["flow"], "http_to_postgres");
+    let tail = jobs
+        .handle_action(json!({"action": "job_logs_tail", "job_id": run["job_id"]}))
+        .await
+        .expect("tail pipeline job");
+    let text = tail["logs"]["text"].as_str().unwrap();
+    assert!(
+        text.contains("pipeline flow=http_to_postgres started"),
+        "{}",
+        text
+    );
+    assert!(text.contains(" failed: "), "{}", text);
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    restore_env("INFRA_CONTEXT_REPO_ROOT", prev_context);
+    let _ = std::fs::remove_dir_all(&tmp_dir);
+}
+
+#[tokio::test]
+async fn one_shot_hosts_refuse_local_jobs() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+
+    let job_service = Arc::new(JobService::new(Logger::new("test")).expect("job service"));
+    job_service.mark_one_shot();
+    let local = LocalManager::new(Logger::new("test"), Validation::new(), Some(true))
+        .with_job_service(job_service.clone());
+    let err = local
+        .handle_action(json!({"action": "exec", "command": "sleep 30", "detached": true}))
+        .await
+        .expect_err("detached exec refused");
+    assert_eq!(err.code, "LOCAL_JOB_UNSUPPORTED");
+    assert!(err.hint.is_some());
+
+    let err = pipeline(job_service.clone())
+        .handle_action(json!({
+            "action": "run",
+            "flow": "http_to_postgres",
+            "background": true,
+            "http": {"url": "http://127.0.0.1:1/rows.jsonl"},
+            "postgres": {"connection_url": "postgres://app@127.0.0.1:1/app", "table": "events"},
+        }))
+        .await
+        .expect_err("background run refused");
+    assert_eq!(err.code, "LOCAL_JOB_UNSUPPORTED");
+    assert!(job_service.list(10, None).is_empty());
+
+    // Foreground calls are unaffected.
+    let ran = local
+        .handle_action(json!({"action": "exec", "command": "echo hi"}))
+        .await
+        .expect("foreground exec");
+    assert_eq!(ran["exit_code"], 0);
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    let _ = std::fs::remove_dir_all(&tmp_dir);
+}
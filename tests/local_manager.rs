@@ -165,3 +165,223 @@ async fn local_fs_rm_force_ignores_missing() {
 
     assert_eq!(result.get("success").and_then(Value::as_bool), Some(true));
 }
+
+#[tokio::test]
+async fn local_fs_read_ranges_lines_and_binary_content() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let root = tmp_dir("infra-local-read");
+    std::fs::create_dir_all(&root).expect("create dir");
+    let manager = LocalManager::new(Logger::new("test"), Validation::new(), Some(true));
+
+    let binary = root.join("blob.bin");
+    let bytes: Vec<u8> = vec![0xff, 0xfe, 0x00, 0x80, b'a', b'b', 0xc3];
+    std::fs::write(&binary, &bytes).expect("write blob");
+    let read = manager
+        .handle_action(serde_json::json!({
+            "action": "fs_read",
+            "path": binary.to_string_lossy(),
+            "encoding": "base64",
+            "offset": 1,
+            "max_bytes": 4,
+        }))
+        .await
+        .expect("fs_read base64");
+    let decoded = base64::Engine::decode(
+        &base64::engine::general_purpose::STANDARD,
+        read["content"].as_str().unwrap(),
+    )
+    .unwrap();
+    assert_eq!(decoded, bytes[1..5]);
+    assert_eq!(read["inline_truncated"], true);
+    assert_eq!(read["truncated"], true);
+    assert_eq!(read["file_bytes"], 7);
+
+    let lossy = manager
+        .handle_action(serde_json::json!({"action": "fs_read", "path": binary.to_string_lossy()}))
+        .await
+        .expect("fs_read utf8");
+    assert_eq!(lossy["lossy"], true);
+    assert_eq!(lossy["truncated"], false);
+
+    let text = root.join("lines.txt");
+    std::fs::write(&text, "one\ntwo\nthree\nfour\nfünf\n").expect("write lines");
+    let middle = manager
+        .handle_action(serde_json::json!({
+            "action": "fs_read",
+            "path": text.to_string_lossy(),
+            "lines": {"start": 2, "end": 3},
+        }))
+        .await
+        .expect("fs_read lines");
+    assert_eq!(middle["content"], "two\nthree\n");
+    assert_eq!(middle["offset"], 4);
+    assert_eq!(middle["lines"], serde_json::json!({"start": 2, "end": 3}));
+    assert_eq!(middle["truncated"], true);
+
+    // The cap lands inside "ü": the partial character is dropped, not replaced.
+    let capped = manager
+        .handle_action(serde_json::json!({
+            "action": "fs_read",
+            "path": text.to_string_lossy(),
+            "lines": {"start": 4},
+            "max_bytes": 7,
+        }))
+        .await
+        .expect("fs_read capped lines");
+    assert_eq!(capped["content"], "four\nf");
+    assert_eq!(capped["length"], 6);
+    assert_eq!(capped["inline_truncated"], true);
+    assert_eq!(capped["lines"]["end"], 4);
+
+    let err = manager
+        .handle_action(serde_json::json!({
+            "action": "fs_read",
+            "path": text.to_string_lossy(),
+            "lines": {"start": 1},
+            "offset": 3,
+        }))
+        .await
+        .expect_err("lines with offset");
+    assert!(err.message.contains("lines cannot be combined"));
+    let err = manager
+        .handle_action(serde_json::json!({
+            "action": "fs_read",
+            "path": text.to_string_lossy(),
+            "encoding": "latin1",
+        }))
+        .await
+        .expect_err("unknown encoding");
+    assert!(err.message.contains("utf8 or base64"));
+
+    std::fs::remove_dir_all(&root).ok();
+}
+
+#[tokio::test]
+async fn local_fs_write_appends_patches_and_stays_atomic() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let root = tmp_dir("infra-local-write");
+    std::fs::create_dir_all(&root).expect("create dir");
+    let manager = LocalManager::new(Logger::new("test"), Validation::new(), Some(true));
+
+    let nested = root.join("missing").join("app.log");
+    let err = manager
+        .handle_action(serde_json::json!({
+            "action": "fs_write",
+            "path": nested.to_string_lossy(),
+            "content": "x",
+            "create_dirs": false,
+        }))
+        .await
+        .expect_err("parent missing");
+    assert!(err.message.contains("parent directory does not exist"));
+
+    for chunk in ["first\n", "second\n"] {
+        manager
+            .handle_action(serde_json::json!({
+                "action": "fs_write",
+                "path": nested.to_string_lossy(),
+                "content": chunk,
+                "append": true,
+            }))
+            .await
+            .expect("append");
+    }
+    assert_eq!(std::fs::read_to_string(&nested).unwrap(), "first\nsecond\n");
+
+    let config = root.join("app.conf");
+    std::fs::write(
+        &config,
+        "port = 80\nhost = a\nmode = dev\nmode_extra = dev\n",
+    )
+    .unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&config, std::fs::Permissions::from_mode(0o640)).unwrap();
+    }
+    let patched = manager
+        .handle_action(serde_json::json!({
+            "action": "fs_write",
+            "path": config.to_string_lossy(),
+            "patch": [
+                {"find": "port = 80", "replace": "port = 8080"},
+                {"find": "dev", "replace": "prod", "count": "all"},
+                {"lines": {"start": 2}, "replace": "host = b"},
+            ],
+        }))
+        .await
+        .expect("patch");
+    assert_eq!(patched["changed"], true);
+    assert_eq!(patched["replacements"], 4);
+    assert_eq!(
+        std::fs::read_to_string(&config).unwrap(),
+        "port = 8080\nhost = b\nmode = prod\nmode_extra = prod\n"
+    );
+    let diff = patched["diff"].as_str().unwrap();
+    assert!(diff.contains("-port = 80\n"), "{}", diff);
+    assert!(diff.contains("+host = b\n"), "{}", diff);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&config).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode, 0o640, "patch keeps the file mode");
+    }
+
+    let err = manager
+        .handle_action(serde_json::json!({
+            "action": "fs_write",
+            "path": config.to_string_lossy(),
+            "patch": [{"find": "port = 80\n", "replace": "port = 81\n"}],
+        }))
+        .await
+        .expect_err("stale patch");
+    assert_eq!(err.kind, infra::errors::ToolErrorKind::Conflict);
+
+    let blob = root.join("blob.bin");
+    std::fs::write(&blob, [0xffu8, 0xfe]).unwrap();
+    let err = manager
+        .handle_action(serde_json::json!({
+            "action": "fs_write",
+            "path": blob.to_string_lossy(),
+            "patch": [{"find": "a", "replace": "b"}],
+        }))
+        .await
+        .expect_err("binary patch");
+    assert!(err.message.contains("UTF-8"));
+
+    // Concurrent overwrites: the file always holds one complete payload, never a mix.
+    let target = root.join("race.txt");
+    let payloads: Vec<String> = (0..16)
+        .map(|n| format!("{}", n % 10).repeat(64 * 1024))
+        .collect();
+    let writes = payloads.iter().map(|payload| {
+        manager.handle_action(serde_json::json!({
+            "action": "fs_write",
+            "path": target.to_string_lossy(),
+            "content": payload,
+            "overwrite": true,
+        }))
+    });
+    let reader = async {
+        for _ in 0..50 {
+            if let Ok(seen) = std::fs::read_to_string(&target) {
+                assert!(payloads.contains(&seen), "observed a partial write");
+            }
+            tokio::task::yield_now().await;
+        }
+    };
+    let (results, _) = tokio::join!(futures::future::join_all(writes), reader);
+    assert!(results.iter().all(|r| r.is_ok()));
+    let final_content = std::fs::read_to_string(&target).unwrap();
+    assert!(payloads.contains(&final_content));
+    let leftovers = std::fs::read_dir(&root)
+        .unwrap()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_name().to_string_lossy().contains(".part-"))
+        .count();
+    assert_eq!(leftovers, 0);
+
+    std::fs::remove_dir_all(&root).ok();
+}
@@ -0,0 +1,150 @@
+#![cfg(unix)]
+
+mod common;
+use common::ENV_LOCK;
+
+use infra::errors::ToolErrorKind;
+use infra::managers::local::LocalManager;
+use infra::services::logger::Logger;
+use infra::services::validation::Validation;
+use serde_json::{json, Value};
+
+fn prompt_script() -> String {
+    format!("{}/tests/fixtures/prompt.sh", env!("CARGO_MANIFEST_DIR"))
+}
+
+fn exec_args(extra: Value) -> Value {
+    let mut args = json!({"action": "exec", "command": "sh", "args": [prompt_script()]});
+    args.as_object_mut()
+        .unwrap()
+        .extend(extra.as_object().unwrap().clone());
+    args
+}
+
+#[tokio::test]
+async fn local_exec_pty_answers_prompts() {
+    let _guard = ENV_LOCK.lock().await;
+    let manager = LocalManager::new(Logger::new("test"), Validation::new(), Some(true));
+
+    // Without a terminal the fixture refuses to run.
+    let piped = manager
+        .handle_action(exec_args(json!({})))
+        .await
+        .expect("piped exec");
+    assert_eq!(piped["exit_code"], 3);
+
+    let answered = manager
+        .handle_action(exec_args(json!({
+            "pty": true,
+            "pty_rows": 30,
+            "pty_cols": 100,
+            "strip_ansi": true,
+            "timeout_ms": 10000,
+            "expect": [
+                {"pattern": "Are you sure\\? \\[y/N\\]", "send": "y\n"},
+                {"pattern": "Name:", "send": "ada\n", "timeout_ms": 5000},
+            ],
+        })))
+        .await
+        .expect("pty exec");
+    assert_eq!(answered["success"], true, "{}", answered);
+    assert_eq!(answered["exit_code"], 0);
+    assert_eq!(answered["stderr_merged"], true);
+    let stdout = answered["stdout"].as_str().unwrap();
+    assert!(stdout.contains("size: 30 100"), "{}", stdout);
+    assert!(stdout.ends_with("hello, ada"), "{}", stdout);
+    assert!(!stdout.contains('\x1b'), "{}", stdout);
+    let steps = answered["expect"].as_array().unwrap();
+    assert_eq!(steps.len(), 2);
+    assert_eq!(steps[1]["sent"], true);
+    assert!(steps[1].get("send").is_none(), "answers are never echoed");
+
+    // Matching ignores colors even when the output keeps them.
+    let declined = manager
+        .handle_action(exec_args(json!({
+            "pty": true,
+            "expect": [{"pattern": "\\[y/N\\] $", "send": "n\n"}],
+        })))
+        .await
+        .expect("declined exec");
+    assert_eq!(declined["success"], false);
+    assert_eq!(declined["exit_code"], 1);
+    assert!(declined["stdout"].as_str().unwrap().contains("\x1b[1;33m"));
+    assert!(declined["expect_failed"].is_null());
+
+    let stuck = manager
+        .handle_action(exec_args(json!({
+            "pty": true,
+            "expect": [{"pattern": "Password:", "send": "secret\n", "timeout_ms": 300}],
+        })))
+        .await
+        .expect("stuck exec");
+    assert_eq!(stuck["success"], false);
+    assert_eq!(stuck["expect_failed"]["step"], 1);
+    assert_eq!(stuck["expect_failed"]["pattern"], "Password:");
+    assert!(stuck["duration_ms"].as_u64().unwrap() < 5000);
+
+    let exited = manager
+        .handle_action(json!({
+            "action": "exec",
+            "command": "echo done",
+            "pty": true,
+            "expect": [{"pattern": "never"}],
+        }))
+        .await
+        .expect("early exit");
+    assert_eq!(exited["exit_code"], 0);
+    assert_eq!(exited["success"], false);
+    assert_eq!(
+        exited["expect_failed"]["reason"],
+        "command exited before the pattern appeared"
+    );
+}
+
+#[tokio::test]
+async fn local_exec_pty_validates_options() {
+    let _guard = ENV_LOCK.lock().await;
+    let manager = LocalManager::new(Logger::new("test"), Validation::new(), Some(true));
+
+    for (extra, message) in [
+        (
+            json!({"expect": [{"pattern": "x"}]}),
+            "expect needs pty: true",
+        ),
+        (
+            json!({"pty": true, "detached": true}),
+            "pty cannot be combined with detached",
+        ),
+        (
+            json!({"pty": true, "stdin": "y\n"}),
+            "stdin, stdin_base64, stdin_file and stdin_ref are not supported with pty",
+        ),
+        (
+            json!({"pty": true, "pty_cols": 0}),
+            "pty_cols must be an integer between 1 and 1000",
+        ),
+    ] {
+        let err = manager
+            .handle_action(exec_args(extra))
+            .await
+            .expect_err(message);
+        assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+        assert_eq!(err.message, message);
+    }
+    let err = manager
+        .handle_action(exec_args(
+            json!({"pty": true, "expect": [{"pattern": "[y/N"}]}),
+        ))
+        .await
+        .expect_err("bad regex");
+    assert!(err
+        .message
+        .starts_with("expect[0].pattern is not a valid regex"));
+
+    let disabled = LocalManager::new(Logger::new("test"), Validation::new(), Some(false));
+    let err = disabled
+        .handle_action(exec_args(json!({"pty": true})))
+        .await
+        .expect_err("unsafe gate");
+    assert_eq!(err.kind, ToolErrorKind::Denied);
+}
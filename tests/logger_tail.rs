@@ -0,0 +1,105 @@
+mod common;
+use common::ENV_LOCK;
+
+use infra::services::logger::{LogLevel, LogTailFilter, Logger};
+use infra::services::state::StateService;
+use infra::services::tool_executor::{ToolExecutor, ToolHandler};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+#[derive(Clone)]
+struct FailingHandler {
+    logger: Logger,
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for FailingHandler {
+    async fn handle(&self, args: Value) -> Result<Value, infra::errors::ToolError> {
+        self.logger.debug(
+            "connecting",
+            Some(&serde_json::json!({ "host": args.get("host"), "password": "hunter2-secret" })),
+        );
+        Err(infra::errors::ToolError::retryable("connection reset"))
+    }
+}
+
+#[tokio::test]
+async fn log_overrides_and_tail_capture_redacted_records_per_trace() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let mut logger = Logger::new("infra");
+    logger.set_level(LogLevel::Info);
+    let ssh_logger = logger.child("ssh");
+
+    ssh_logger.debug("suppressed before override", None);
+    logger.set_level_override("ssh", Some(LogLevel::Debug));
+    assert_eq!(ssh_logger.effective_level(), LogLevel::Debug);
+    assert_eq!(logger.child("api").effective_level(), LogLevel::Info);
+
+    let mut handlers: HashMap<String, Arc<dyn ToolHandler>> = HashMap::new();
+    handlers.insert(
+        "state".to_string(),
+        Arc::new(FailingHandler {
+            logger: ssh_logger.clone(),
+        }),
+    );
+    let executor = ToolExecutor::new(
+        logger.clone(),
+        Arc::new(StateService::new().expect("state")),
+        None,
+        None,
+        handlers,
+        HashMap::new(),
+    );
+    executor
+        .execute(
+            "state",
+            serde_json::json!({ "action": "get", "key": "k", "trace_id": "trace-logs-1" }),
+        )
+        .await
+        .expect_err("handler fails");
+
+    let tail = logger.tail(&LogTailFilter {
+        trace_id: Some("trace-logs-1".to_string()),
+        limit: 50,
+        ..Default::default()
+    });
+    let records = tail["records"].as_array().expect("records");
+    let connecting = records
+        .iter()
+        .find(|record| record["message"] == "connecting")
+        .expect("handler debug record is attributed to the trace");
+    assert_eq!(connecting["component"], "infra:ssh");
+    assert_eq!(connecting["meta"]["password"], "[REDACTED]");
+    assert!(records
+        .iter()
+        .any(|record| record["message"] == "Tool call failed" && record["level"] == "warn"));
+    assert!(!tail.to_string().contains("suppressed before override"));
+
+    let warnings = logger.tail(&LogTailFilter {
+        level: Some(LogLevel::Warn),
+        limit: 50,
+        ..Default::default()
+    });
+    assert!(warnings["records"]
+        .as_array()
+        .expect("records")
+        .iter()
+        .all(|record| record["level"] == "warn" || record["level"] == "error"));
+
+    let ssh_only = logger.tail(&LogTailFilter {
+        component: Some("ssh".to_string()),
+        limit: 50,
+        ..Default::default()
+    });
+    assert!(ssh_only["records"]
+        .as_array()
+        .expect("records")
+        .iter()
+        .all(|record| record["component"] == "infra:ssh"));
+
+    logger.set_level_override("ssh", None);
+    assert_eq!(ssh_logger.effective_level(), LogLevel::Info);
+    assert_eq!(logger.level_overrides(), serde_json::json!({}));
+}
@@ -0,0 +1,189 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::postgres::PostgresManager;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+fn names(items: &Value, key: &str) -> Vec<String> {
+    items
+        .as_array()
+        .expect("diff items")
+        .iter()
+        .map(|item| item[key].as_str().unwrap_or_default().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn catalog_diff_compares_two_databases() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+
+    let security = Arc::new(Security::new().expect("security"));
+    let profile_service = Arc::new(ProfileService::new(security).expect("profile service"));
+    let postgres = PostgresManager::new(
+        Logger::new("test"),
+        Validation::new(),
+        profile_service,
+        None,
+        None,
+    );
+
+    let offline = "postgres://app@127.0.0.1:1/app";
+    for (args, needle) in [
+        (
+            json!({"side_a": {"connection_url": offline}}),
+            "requires side_b",
+        ),
+        (json!({"side_a": {}, "side_b": "prod"}), "requires side_a"),
+        (
+            json!({"side_a": "prod", "side_b": "stage", "format": "yaml"}),
+            "json or sql_hint",
+        ),
+        (
+            json!({"side_a": "prod", "side_b": "stage", "schemas": [1]}),
+            "schemas",
+        ),
+        (
+            json!({"side_a": {"connection_url": offline}, "side_b": "missing_profile"}),
+            "catalog_diff side_",
+        ),
+    ] {
+        let mut args = args;
+        args["action"] = json!("catalog_diff");
+        let err = postgres
+            .handle_action(args)
+            .await
+            .expect_err("invalid catalog_diff");
+        assert!(err.message.contains(needle), "{}", err.message);
+        if needle != "catalog_diff side_" {
+            assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+        }
+    }
+
+    // Set INFRA_TEST_POSTGRES_URLS (comma-separated) to diff catalogs on live servers.
+    let urls = std::env::var("INFRA_TEST_POSTGRES_URLS").unwrap_or_default();
+    for url in urls.split(',').map(str::trim).filter(|url| !url.is_empty()) {
+        let database = format!("infra_diff_{}", uuid::Uuid::new_v4().simple());
+        let schema = format!("diff_{}", uuid::Uuid::new_v4().simple());
+        let query = |target: Option<&str>, sql: String| {
+            let mut args = json!({"action": "query", "connection_url": url, "sql": sql});
+            if let Some(target) = target {
+                args["connection"] = json!({"database": target});
+            }
+            postgres.handle_action(args)
+        };
+        query(None, format!("CREATE DATABASE \"{}\"", database))
+            .await
+            .expect("create database");
+        let side_a = [
+            "CREATE TABLE {s}.users (id int4 PRIMARY KEY, email varchar(100), legacy text)",
+            "CREATE INDEX users_email_idx ON {s}.users (email)",
+            "CREATE TABLE {s}.audit (id int8 PRIMARY KEY)",
+            "CREATE TABLE {s}._migrations (version int4)",
+        ];
+        let side_b = [
+            "CREATE TABLE {s}.users (id int4 PRIMARY KEY, email text NOT NULL DEFAULT '', plan text)",
+            "CREATE UNIQUE INDEX users_email_idx ON {s}.users (email)",
+            "CREATE TABLE {s}.orders (id int8 PRIMARY KEY, user_id int4 REFERENCES {s}.users(id))",
+        ];
+        for (target, statements) in [(None, &side_a[..]), (Some(database.as_str()), &side_b[..])] {
+            query(target, format!("CREATE SCHEMA \"{}\"", schema))
+                .await
+                .expect("create schema");
+            for sql in statements {
+                query(target, sql.replace("{s}", &format!("\"{}\"", schema)))
+                    .await
+                    .expect("seed catalog");
+            }
+        }
+
+        let diff = postgres
+            .handle_action(json!({
+                "action": "catalog_diff",
+                "side_a": {"connection_url": url},
+                "side_b": {"connection_url": url, "connection": {"database": database}},
+                "schemas": [schema],
+                "ignore": ["_migrations"],
+                "format": "sql_hint",
+            }))
+            .await
+            .expect("catalog diff");
+        assert_eq!(diff["success"], true);
+        assert_eq!(diff["identical"], false, "{}", diff);
+        let qualified = |table: &str| format!("{}.{}", schema, table);
+        assert_eq!(
+            names(&diff["only_in_a"]["tables"], "table"),
+            [qualified("audit")]
+        );
+        assert_eq!(
+            names(&diff["only_in_b"]["tables"], "table"),
+            [qualified("orders")]
+        );
+        assert_eq!(names(&diff["only_in_a"]["columns"], "column"), ["legacy"]);
+        assert_eq!(names(&diff["only_in_b"]["columns"], "column"), ["plan"]);
+        let email = &diff["changed"]["columns"][0]["changes"];
+        assert_eq!(email["type"]["before"], "character varying(100)");
+        assert_eq!(email["type"]["after"], "text");
+        assert_eq!(email["nullable"], json!({"before": true, "after": false}));
+        assert_eq!(email["default"]["after"], "''::text");
+        assert_eq!(
+            names(&diff["changed"]["indexes"], "name"),
+            ["users_email_idx"]
+        );
+        assert!(diff["truncated"].is_null());
+
+        let hints = diff["sql_hints"].as_array().expect("sql hints");
+        assert!(diff["sql_hints_note"]
+            .as_str()
+            .unwrap()
+            .contains("not guaranteed safe"));
+        let position = |needle: &str| {
+            hints
+                .iter()
+                .position(|hint| hint["sql"].as_str().unwrap().contains(needle))
+                .unwrap_or_else(|| panic!("{} missing from {:?}", needle, hints))
+        };
+        assert!(position("CREATE TABLE") < position("FOREIGN KEY (user_id)"));
+        assert!(position("DROP TABLE") > position("CREATE UNIQUE INDEX"));
+        assert_eq!(hints[position("DROP TABLE")]["destructive"], true);
+
+        let same = postgres
+            .handle_action(json!({
+                "action": "catalog_diff",
+                "side_a": {"connection_url": url},
+                "side_b": {"connection_url": url},
+                "schemas": schema,
+            }))
+            .await
+            .expect("self diff");
+        assert_eq!(same["identical"], true, "{}", same);
+        assert!(same.get("sql_hints").is_none());
+
+        query(None, format!("DROP SCHEMA \"{}\" CASCADE", schema))
+            .await
+            .expect("drop schema");
+        query(None, format!("DROP DATABASE \"{}\" WITH (FORCE)", database))
+            .await
+            .expect("drop database");
+    }
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
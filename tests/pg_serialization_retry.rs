@@ -0,0 +1,180 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::postgres::PostgresManager;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+// Two transactions that lock the same rows in opposite order; the pause makes both hold their
+// first lock before asking for the second, so Postgres must pick a deadlock victim.
+fn crossing_transaction(url: &str, table: &str, first: i32, second: i32, retry: Value) -> Value {
+    json!({
+        "action": "transaction",
+        "connection_url": url,
+        "statements": [
+            format!("UPDATE \"{}\" SET v = v + 1 WHERE id = {}", table, first),
+            "SELECT pg_sleep(0.3)",
+            format!("UPDATE \"{}\" SET v = v + 1 WHERE id = {}", table, second),
+        ],
+        "retry_serialization": retry,
+    })
+}
+
+#[tokio::test]
+async fn deadlocks_and_serialization_failures_are_retried() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+
+    let security = Arc::new(Security::new().expect("security"));
+    let profile_service = Arc::new(ProfileService::new(security).expect("profile service"));
+    let postgres = PostgresManager::new(
+        Logger::new("test"),
+        Validation::new(),
+        profile_service,
+        None,
+        None,
+    );
+
+    let err = postgres
+        .handle_action(json!({
+            "action": "query",
+            "connection_url": "postgres://app@127.0.0.1:1/app",
+            "sql": "SELECT 1",
+            "retry_serialization": "always",
+        }))
+        .await
+        .expect_err("invalid retry option");
+    assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+    assert!(
+        err.message.contains("retry_serialization"),
+        "{}",
+        err.message
+    );
+
+    // Set INFRA_TEST_POSTGRES_URLS (comma-separated) to provoke real deadlocks on live servers.
+    let urls = std::env::var("INFRA_TEST_POSTGRES_URLS").unwrap_or_default();
+    for url in urls.split(',').map(str::trim).filter(|url| !url.is_empty()) {
+        let table = format!("infra_retry_{}", uuid::Uuid::new_v4().simple());
+        let sequence = format!("{}_seq", table);
+        let query = |sql: String| {
+            postgres.handle_action(json!({"action": "query", "connection_url": url, "sql": sql}))
+        };
+        for sql in [
+            format!("CREATE TABLE \"{}\" (id int4 PRIMARY KEY, v int4)", table),
+            format!("INSERT INTO \"{}\" VALUES (1, 0), (2, 0)", table),
+            format!("CREATE SEQUENCE \"{}\"", sequence),
+        ] {
+            query(sql).await.expect("seed");
+        }
+
+        // Default for transactions: the victim re-runs its whole body and both commit.
+        let quick = json!({"base_delay_ms": 10, "max_delay_ms": 50});
+        let (left, right) = tokio::join!(
+            postgres.handle_action(crossing_transaction(url, &table, 1, 2, Value::Null)),
+            postgres.handle_action(crossing_transaction(url, &table, 2, 1, quick.clone())),
+        );
+        let (left, right) = (left.expect("left"), right.expect("right"));
+        assert_eq!(left["committed"], true, "{}", left);
+        assert_eq!(right["committed"], true, "{}", right);
+        let retries = left["retries"].as_u64().unwrap() + right["retries"].as_u64().unwrap();
+        assert!(retries >= 1, "{} / {}", left, right);
+        let values = query(format!("SELECT id, v FROM \"{}\" ORDER BY id", table))
+            .await
+            .expect("read values");
+        assert_eq!(
+            values["rows"],
+            json!([{"id": 1, "v": 2}, {"id": 2, "v": 2}]),
+            "re-run bodies must not apply twice"
+        );
+
+        // One attempt only: the victim's deadlock comes back as a retryable error.
+        let once = json!({"max_attempts": 1});
+        let (left, right) = tokio::join!(
+            postgres.handle_action(crossing_transaction(url, &table, 1, 2, once.clone())),
+            postgres.handle_action(crossing_transaction(url, &table, 2, 1, once)),
+        );
+        let outcomes = [left, right];
+        let victim = outcomes
+            .iter()
+            .find_map(|outcome| outcome.as_ref().err())
+            .expect("one transaction is the deadlock victim");
+        assert!(victim.retryable, "{}", victim.message);
+        let details = victim.details.as_ref().expect("details");
+        assert_eq!(details["sqlstate"], "40P01");
+        assert_eq!(details["attempts"], 1);
+        assert_eq!(outcomes.iter().filter(|outcome| outcome.is_ok()).count(), 1);
+
+        // Disabled: the failure is reported in the batch results as before.
+        let (left, right) = tokio::join!(
+            postgres.handle_action(crossing_transaction(url, &table, 1, 2, json!(false))),
+            postgres.handle_action(crossing_transaction(url, &table, 2, 1, json!(false))),
+        );
+        let payloads = [left.expect("left"), right.expect("right")];
+        let failed = payloads
+            .iter()
+            .find(|payload| payload["committed"] == false)
+            .expect("one transaction rolled back");
+        assert_eq!(failed["results"][2]["details"]["sqlstate"], "40P01");
+        assert!(failed.get("retries").is_none());
+
+        // Raw queries retry only when asked; the statement fails until the third attempt.
+        let flaky = format!(
+            "DO $$ BEGIN IF nextval('\"{}\"') < 3 THEN RAISE EXCEPTION 'conflict' USING ERRCODE = 'serialization_failure'; END IF; END $$",
+            sequence
+        );
+        let err = query(flaky.clone()).await.expect_err("no retry by default");
+        assert!(!err.retryable);
+        assert_eq!(err.details.as_ref().unwrap()["sqlstate"], "40001");
+        let ok = postgres
+            .handle_action(json!({
+                "action": "query",
+                "connection_url": url,
+                "sql": flaky,
+                "retry_serialization": quick,
+            }))
+            .await
+            .expect("retried query");
+        assert_eq!(ok["retries"], 1, "{}", ok);
+
+        // Writes opt in the same way.
+        let updated = postgres
+            .handle_action(json!({
+                "action": "update",
+                "connection_url": url,
+                "table": table,
+                "data": {"v": 10},
+                "filters": {"id": 1},
+                "retry_serialization": true,
+            }))
+            .await
+            .expect("update");
+        assert_eq!(updated["affected"], 1);
+        assert_eq!(updated["retries"], 0);
+
+        query(format!("DROP TABLE \"{}\"", table))
+            .await
+            .expect("drop table");
+        query(format!("DROP SEQUENCE \"{}\"", sequence))
+            .await
+            .expect("drop sequence");
+    }
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
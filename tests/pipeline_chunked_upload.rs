@@ -0,0 +1,298 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::api::ApiManager;
+use infra::managers::pipeline::PipelineManager;
+use infra::managers::postgres::PostgresManager;
+use infra::managers::ssh::SshManager;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use serde_json::{json, Value};
+use std::io::{Read, Write};
+use std::sync::{Arc, Mutex};
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+fn manager() -> (PipelineManager, Arc<PostgresManager>) {
+    let logger = Logger::new("test");
+    let security = Arc::new(Security::new().expect("security"));
+    let profile_service = Arc::new(ProfileService::new(security.clone()).expect("profile service"));
+    let api = ApiManager::new(
+        logger.clone(),
+        Validation::new(),
+        profile_service.clone(),
+        None,
+        None,
+        None,
+    );
+    let ssh = SshManager::new(
+        logger.clone(),
+        security,
+        Validation::new(),
+        profile_service.clone(),
+        None,
+        None,
+        None,
+    );
+    let postgres = Arc::new(PostgresManager::new(
+        logger.clone(),
+        Validation::new(),
+        profile_service,
+        None,
+        None,
+    ));
+    let pipeline = PipelineManager::new(
+        logger,
+        Validation::new(),
+        Arc::new(api),
+        Arc::new(ssh),
+        postgres.clone(),
+        None,
+        None,
+        None,
+        None,
+    );
+    (pipeline, postgres)
+}
+
+#[derive(Clone, Debug)]
+struct SeenRequest {
+    line: String,
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+impl SeenRequest {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+// Answers each request with the next scripted status and records request line, headers and body.
+fn spawn_sink(script: Vec<&'static str>) -> (u16, Arc<Mutex<Vec<SeenRequest>>>) {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind sink");
+    let port = listener.local_addr().expect("sink addr").port();
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let log = seen.clone();
+    std::thread::spawn(move || {
+        for (status, stream) in script.into_iter().zip(listener.incoming()) {
+            let Ok(mut stream) = stream else { continue };
+            let mut raw = Vec::new();
+            let mut buf = [0u8; 8192];
+            let head_end = loop {
+                let read = stream.read(&mut buf).unwrap_or(0);
+                if read == 0 {
+                    break raw.len();
+                }
+                raw.extend_from_slice(&buf[..read]);
+                if let Some(pos) = raw.windows(4).position(|w| w == b"\r\n\r\n") {
+                    break pos + 4;
+                }
+            };
+            let head = String::from_utf8_lossy(&raw[..head_end]).to_string();
+            let mut lines = head.lines();
+            let line = lines.next().unwrap_or("").to_string();
+            let headers: Vec<(String, String)> = lines
+                .filter_map(|l| l.split_once(':'))
+                .map(|(k, v)| (k.trim().to_lowercase(), v.trim().to_string()))
+                .collect();
+            let length = headers
+                .iter()
+                .find(|(k, _)| k == "content-length")
+                .and_then(|(_, v)| v.parse::<usize>().ok())
+                .unwrap_or(0);
+            while raw.len() < head_end + length {
+                let read = stream.read(&mut buf).unwrap_or(0);
+                if read == 0 {
+                    break;
+                }
+                raw.extend_from_slice(&buf[..read]);
+            }
+            let body = String::from_utf8_lossy(&raw[head_end..]).to_string();
+            log.lock().unwrap().push(SeenRequest {
+                line,
+                headers,
+                body,
+            });
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{{}}",
+                status
+            );
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+    (port, seen)
+}
+
+fn ids(body: &str) -> Vec<i64> {
+    body.lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .filter_map(|row| row["id"].as_i64())
+        .collect()
+}
+
+#[tokio::test]
+async fn postgres_to_http_uploads_chunks_with_retry_finalize_and_resume() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    let (manager, postgres) = manager();
+
+    for (extra, needle) in [
+        (
+            json!({"finalize": {"path": "commit"}}),
+            "requires chunk_rows",
+        ),
+        (json!({"chunk_rows": 2, "format": "csv"}), "format=csv"),
+        (
+            json!({"chunk_rows": 2, "chunk_format": "xml"}),
+            "chunk_format",
+        ),
+        (
+            json!({"chunk_rows": 2, "finalize": {"method": "PUT"}}),
+            "finalize.path",
+        ),
+    ] {
+        let mut args = json!({
+            "action": "run",
+            "flow": "postgres_to_http",
+            "postgres": {"connection_url": "postgres://app@127.0.0.1:1/app", "table": "events"},
+            "http": {"url": "http://127.0.0.1:1/ingest"},
+        });
+        args.as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        let err = manager
+            .handle_action(args)
+            .await
+            .expect_err("invalid chunk options");
+        assert_eq!(err.kind, ToolErrorKind::InvalidParams, "{}", err.message);
+        assert!(err.message.contains(needle), "{}", err.message);
+    }
+
+    // Set INFRA_TEST_POSTGRES_URLS (comma-separated) to stream chunks from live servers.
+    let urls = std::env::var("INFRA_TEST_POSTGRES_URLS").unwrap_or_default();
+    for url in urls.split(',').map(str::trim).filter(|url| !url.is_empty()) {
+        let table = format!("infra_chunks_{}", uuid::Uuid::new_v4().simple());
+        for sql in [
+            format!(
+                "CREATE TABLE \"{}\" (id int4 PRIMARY KEY, name text)",
+                table
+            ),
+            format!(
+                "INSERT INTO \"{}\" SELECT g, 'row ' || g FROM generate_series(1, 5) g",
+                table
+            ),
+        ] {
+            postgres
+                .handle_action(json!({"action": "query", "connection_url": url, "sql": sql}))
+                .await
+                .expect("seed table");
+        }
+        let run = |port: u16, extra: Value| {
+            let mut args = json!({
+                "action": "run",
+                "flow": "postgres_to_http",
+                "postgres": {"connection_url": url, "table": table},
+                "order_by": ["id"],
+                "chunk_rows": 2,
+                "http": {
+                    "url": format!("http://127.0.0.1:{}/ingest/", port),
+                    "retry": {"max_attempts": 3, "base_delay_ms": 1, "max_delay_ms": 2, "jitter": 0},
+                },
+            });
+            args.as_object_mut()
+                .unwrap()
+                .extend(extra.as_object().unwrap().clone());
+            manager.handle_action(args)
+        };
+
+        let (port, seen) = spawn_sink(vec![
+            "200 OK",
+            "503 Service Unavailable",
+            "200 OK",
+            "200 OK",
+            "202 Accepted",
+        ]);
+        let result = run(
+            port,
+            json!({"chunk_headers": true, "finalize": {"path": "commit", "method": "POST"}}),
+        )
+        .await
+        .expect("chunked upload");
+        assert_eq!(result["success"], true, "{}", result);
+        assert_eq!(result["chunks"]["delivered"], 3);
+        assert_eq!(result["chunks"]["total"], 3);
+        assert_eq!(result["chunks"]["last_delivered"], 2);
+        assert_eq!(result["rows"], 5);
+        assert_eq!(result["retries"], 1);
+        assert_eq!(result["finalize"]["status"], 202);
+        assert!(result["bytes"].as_u64().unwrap_or(0) > 0);
+        let seen = seen.lock().unwrap().clone();
+        assert_eq!(seen.len(), 5);
+        let indexes: Vec<_> = seen[..4]
+            .iter()
+            .map(|req| req.header("x-chunk-index").unwrap_or("").to_string())
+            .collect();
+        assert_eq!(indexes, ["0", "1", "1", "2"]);
+        assert_eq!(seen[0].header("x-chunk-total"), Some("3"));
+        assert_eq!(seen[0].header("content-type"), Some("application/x-ndjson"));
+        assert_eq!(ids(&seen[2].body), [3, 4]);
+        assert_eq!(ids(&seen[3].body), [5]);
+        assert!(
+            seen[4].line.starts_with("POST /ingest/commit"),
+            "{}",
+            seen[4].line
+        );
+
+        let (port, _) = spawn_sink(vec!["200 OK", "400 Bad Request"]);
+        let failed = run(port, json!({})).await.expect("failure is reported");
+        assert_eq!(failed["success"], false, "{}", failed);
+        assert_eq!(failed["failed"]["chunk"], 1);
+        assert_eq!(failed["failed"]["status"], 400);
+        assert_eq!(failed["chunks"]["last_delivered"], 0);
+        assert_eq!(failed["chunks"]["resume_from_chunk"], 1);
+
+        let (port, seen) = spawn_sink(vec!["200 OK", "200 OK"]);
+        let resumed = run(
+            port,
+            json!({"resume_from_chunk": failed["chunks"]["resume_from_chunk"], "chunk_format": "json"}),
+        )
+        .await
+        .expect("resumed upload");
+        assert_eq!(resumed["success"], true, "{}", resumed);
+        assert_eq!(resumed["chunks"]["skipped"], 1);
+        assert_eq!(resumed["chunks"]["delivered"], 2);
+        assert_eq!(resumed["rows"], 3);
+        let seen = seen.lock().unwrap().clone();
+        let first: Value = serde_json::from_str(&seen[0].body).expect("json array chunk");
+        assert_eq!(first[0]["id"], 3);
+        assert_eq!(seen[0].header("content-type"), Some("application/json"));
+
+        postgres
+            .handle_action(json!({
+                "action": "query",
+                "connection_url": url,
+                "sql": format!("DROP TABLE \"{}\"", table),
+            }))
+            .await
+            .expect("drop table");
+    }
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
@@ -0,0 +1,158 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::api::ApiManager;
+use infra::managers::pipeline::PipelineManager;
+use infra::managers::postgres::PostgresManager;
+use infra::managers::ssh::SshManager;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use serde_json::json;
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+fn manager() -> PipelineManager {
+    let logger = Logger::new("test");
+    let security = Arc::new(Security::new().expect("security"));
+    let profile_service = Arc::new(ProfileService::new(security.clone()).expect("profile service"));
+    let api = ApiManager::new(
+        logger.clone(),
+        Validation::new(),
+        profile_service.clone(),
+        None,
+        None,
+        None,
+    );
+    let ssh = SshManager::new(
+        logger.clone(),
+        security,
+        Validation::new(),
+        profile_service.clone(),
+        None,
+        None,
+        None,
+    );
+    let postgres = PostgresManager::new(
+        logger.clone(),
+        Validation::new(),
+        profile_service,
+        None,
+        None,
+    );
+    PipelineManager::new(
+        logger,
+        Validation::new(),
+        Arc::new(api),
+        Arc::new(ssh),
+        Arc::new(postgres),
+        None,
+        None,
+        None,
+        None,
+    )
+}
+
+#[tokio::test]
+async fn describe_documents_every_flow_and_run_enforces_it() {
+    let _guard = ENV_LOCK.lock().await;
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    let manager = manager();
+
+    let all = manager
+        .handle_action(json!({"action": "describe"}))
+        .await
+        .expect("describe");
+    let flows = all["flows"].as_array().expect("flows");
+    assert_eq!(flows.len(), 6);
+    assert_eq!(all["names"].as_array().map(|n| n.len()), Some(6));
+    for flow in flows {
+        let name = flow["flow"].as_str().expect("flow name");
+        let example = &flow["example"];
+        assert_eq!(example["flow"], name);
+        assert!(flow.get("extended_example").is_none());
+        // Every documented required field is present in the example payload.
+        for role in ["source", "sink"] {
+            let block = flow[role]["block"].as_str().expect("block");
+            assert!(example[block].is_object(), "{} example has {}", name, block);
+            for field in flow[role]["required"].as_array().expect("required") {
+                let field = field.as_str().unwrap();
+                assert!(
+                    !example[block][field].is_null(),
+                    "{}.{}.{}",
+                    name,
+                    block,
+                    field
+                );
+            }
+            assert!(flow[role]["help"]["tool"].is_string());
+        }
+    }
+
+    let one = manager
+        .handle_action(json!({"action": "describe", "flow": "sftp_to_postgres"}))
+        .await
+        .expect("describe one");
+    let flow = &one["flow"];
+    assert_eq!(flow["flow"], "sftp_to_postgres");
+    assert_eq!(flow["source"]["block"], "sftp");
+    assert_eq!(flow["sink"]["required"], json!(["table"]));
+    assert_eq!(flow["extended_example"]["project"], "shop");
+    assert!(flow["extended_example"]["sftp"]
+        .get("profile_name")
+        .is_none());
+    assert!(flow["project_shorthand"]
+        .as_str()
+        .unwrap()
+        .contains("ssh_profile"));
+
+    let err = manager
+        .handle_action(json!({"action": "describe", "flow": "sftp_to_postgress"}))
+        .await
+        .expect_err("unknown flow");
+    assert_eq!(
+        err.details.as_ref().unwrap()["did_you_mean"][0],
+        "sftp_to_postgres"
+    );
+
+    let err = manager
+        .handle_action(json!({
+            "action": "run",
+            "flow": "sftp_to_postgres",
+            "sftp": {"profile_name": "etl"},
+            "postgres": {"table": "orders"},
+        }))
+        .await
+        .expect_err("missing remote_path");
+    assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+    assert_eq!(
+        err.message,
+        "sftp.remote_path is required for sftp_to_postgres"
+    );
+    let err = manager
+        .handle_action(json!({
+            "action": "run",
+            "flow": "postgres_to_sftp",
+            "postgres": {"table": "orders"},
+        }))
+        .await
+        .expect_err("missing sink block");
+    assert_eq!(
+        err.message,
+        "postgres_to_sftp requires a `sftp` object (sink)"
+    );
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    let _ = std::fs::remove_dir_all(&tmp_dir);
+}
@@ -0,0 +1,95 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::api::ApiManager;
+use infra::managers::pipeline::PipelineManager;
+use infra::managers::postgres::PostgresManager;
+use infra::managers::ssh::SshManager;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+fn manager() -> PipelineManager {
+    let logger = Logger::new("test");
+    let security = Arc::new(Security::new().expect("security"));
+    let profile_service = Arc::new(ProfileService::new(security.clone()).expect("profile service"));
+    let api = ApiManager::new(
+        logger.clone(),
+        Validation::new(),
+        profile_service.clone(),
+        None,
+        None,
+        None,
+    );
+    let ssh = SshManager::new(
+        logger.clone(),
+        security,
+        Validation::new(),
+        profile_service.clone(),
+        None,
+        None,
+        None,
+    );
+    let postgres = PostgresManager::new(
+        logger.clone(),
+        Validation::new(),
+        profile_service,
+        None,
+        None,
+    );
+    PipelineManager::new(
+        logger,
+        Validation::new(),
+        Arc::new(api),
+        Arc::new(ssh),
+        Arc::new(postgres),
+        None,
+        None,
+        None,
+        None,
+    )
+}
+
+#[tokio::test]
+async fn deploy_smoke_rejects_malformed_log_collection_before_deploying() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    let manager = manager();
+
+    for on_failure in [
+        serde_json::json!({"collect_logs": "journalctl"}),
+        serde_json::json!({"collect_logs": {"lines": 50}}),
+        serde_json::json!({"collect_logs": {"journalctl_unit": "app; rm -rf /"}}),
+        serde_json::json!({"collect_logs": {"journalctl_unit": "app", "command": "cat /var/log/app.log"}}),
+    ] {
+        let err = manager
+            .handle_action(serde_json::json!({
+                "action": "deploy_smoke",
+                "local_path": tmp_dir.join("missing.bin"),
+                "remote_path": "/srv/app/app.bin",
+                "url": "http://127.0.0.1:1/health",
+                "on_failure": on_failure,
+            }))
+            .await
+            .expect_err("invalid collect_logs");
+        assert_eq!(err.kind, ToolErrorKind::InvalidParams, "{}", err.message);
+        assert!(err.message.contains("collect_logs"), "{}", err.message);
+    }
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
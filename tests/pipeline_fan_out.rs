@@ -0,0 +1,361 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::api::ApiManager;
+use infra::managers::pipeline::PipelineManager;
+use infra::managers::postgres::PostgresManager;
+use infra::managers::ssh::SshManager;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use serde_json::{json, Value};
+use std::io::{Read, Write};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+fn manager() -> (PipelineManager, Arc<PostgresManager>) {
+    let logger = Logger::new("test");
+    let security = Arc::new(Security::new().expect("security"));
+    let profile_service = Arc::new(ProfileService::new(security.clone()).expect("profile service"));
+    let api = ApiManager::new(
+        logger.clone(),
+        Validation::new(),
+        profile_service.clone(),
+        None,
+        None,
+        None,
+    );
+    let ssh = SshManager::new(
+        logger.clone(),
+        security,
+        Validation::new(),
+        profile_service.clone(),
+        None,
+        None,
+        None,
+    );
+    let postgres = Arc::new(PostgresManager::new(
+        logger.clone(),
+        Validation::new(),
+        profile_service,
+        None,
+        None,
+    ));
+    let pipeline = PipelineManager::new(
+        logger,
+        Validation::new(),
+        Arc::new(api),
+        Arc::new(ssh),
+        postgres.clone(),
+        None,
+        None,
+        None,
+        None,
+    );
+    (pipeline, postgres)
+}
+
+const FEED: &str = "{\"id\":1,\"kind\":\"signup\"}\n{\"id\":2,\"kind\":\"login\"}\n{\"id\":3,\"kind\":\"logout\"}\n";
+
+struct Stub {
+    port: u16,
+    source_hits: Arc<AtomicUsize>,
+    // (path, body) of every upload.
+    uploads: Arc<Mutex<Vec<(String, String)>>>,
+    // /flaky answers 500 until this is set.
+    healthy: Arc<AtomicBool>,
+}
+
+fn dechunk(raw: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut rest = raw;
+    while let Some(pos) = rest.windows(2).position(|w| w == b"\r\n") {
+        let size =
+            usize::from_str_radix(String::from_utf8_lossy(&rest[..pos]).trim(), 16).unwrap_or(0);
+        if size == 0 {
+            break;
+        }
+        out.extend_from_slice(&rest[pos + 2..pos + 2 + size]);
+        rest = &rest[pos + 2 + size + 2..];
+    }
+    out
+}
+
+// GET /events.jsonl serves the feed; any other request is an upload.
+fn spawn_stub() -> Stub {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind stub");
+    let stub = Stub {
+        port: listener.local_addr().expect("stub addr").port(),
+        source_hits: Arc::new(AtomicUsize::new(0)),
+        uploads: Arc::new(Mutex::new(Vec::new())),
+        healthy: Arc::new(AtomicBool::new(false)),
+    };
+    let (hits, uploads, healthy) = (
+        stub.source_hits.clone(),
+        stub.uploads.clone(),
+        stub.healthy.clone(),
+    );
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { continue };
+            let mut raw = Vec::new();
+            let mut buf = [0u8; 8192];
+            let head_end = loop {
+                let read = stream.read(&mut buf).unwrap_or(0);
+                if read == 0 {
+                    break raw.len();
+                }
+                raw.extend_from_slice(&buf[..read]);
+                if let Some(pos) = raw.windows(4).position(|w| w == b"\r\n\r\n") {
+                    break pos + 4;
+                }
+            };
+            let head = String::from_utf8_lossy(&raw[..head_end]).to_lowercase();
+            let line = head.lines().next().unwrap_or("").to_string();
+            let path = line.split(' ').nth(1).unwrap_or("").to_string();
+            if line.starts_with("get /events.jsonl") {
+                hits.fetch_add(1, Ordering::SeqCst);
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/x-ndjson\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    FEED.len(),
+                    FEED
+                );
+                let _ = stream.write_all(response.as_bytes());
+                continue;
+            }
+            let chunked = head.contains("transfer-encoding: chunked");
+            let length = head
+                .lines()
+                .filter_map(|l| l.split_once(':'))
+                .find(|(k, _)| k.trim() == "content-length")
+                .and_then(|(_, v)| v.trim().parse::<usize>().ok())
+                .unwrap_or(0);
+            loop {
+                let done = if chunked {
+                    raw[head_end..].ends_with(b"0\r\n\r\n")
+                } else {
+                    raw.len() >= head_end + length
+                };
+                if done {
+                    break;
+                }
+                let read = stream.read(&mut buf).unwrap_or(0);
+                if read == 0 {
+                    break;
+                }
+                raw.extend_from_slice(&buf[..read]);
+            }
+            let body = if chunked {
+                dechunk(&raw[head_end..])
+            } else {
+                raw[head_end..].to_vec()
+            };
+            let status = if path == "/flaky" && !healthy.load(Ordering::SeqCst) {
+                "500 Internal Server Error"
+            } else {
+                "200 OK"
+            };
+            uploads
+                .lock()
+                .unwrap()
+                .push((path, String::from_utf8_lossy(&body).to_string()));
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{{}}",
+                status
+            );
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+    stub
+}
+
+fn statuses(result: &Value) -> Vec<(String, String)> {
+    result["sinks"]
+        .as_array()
+        .expect("sinks")
+        .iter()
+        .map(|sink| {
+            (
+                sink["name"].as_str().unwrap().to_string(),
+                sink["status"].as_str().unwrap().to_string(),
+            )
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn fan_out_fetches_once_and_retries_only_failed_sinks() {
+    let _guard = ENV_LOCK.lock().await;
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let prev_checkpoints = std::env::var("INFRA_PIPELINE_CHECKPOINTS_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    std::env::set_var(
+        "INFRA_PIPELINE_CHECKPOINTS_DIR",
+        tmp_dir.join("checkpoints"),
+    );
+    let (manager, _) = manager();
+    let stub = spawn_stub();
+    let base = format!("http://127.0.0.1:{}", stub.port);
+    // Nothing listens here: the sftp sink fails to connect.
+    let closed_port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+
+    let sinks = json!([
+        {"type": "http", "name": "archive", "url": format!("{}/archive", base)},
+        {"type": "http", "name": "ingest", "url": format!("{}/flaky", base), "method": "POST"},
+        {"type": "sftp", "name": "mirror", "remote_path": "/mirror/events.jsonl",
+         "connection": {"host": "127.0.0.1", "port": closed_port, "username": "etl", "password": "pw"}},
+    ]);
+    let first = manager
+        .handle_action(json!({
+            "action": "run",
+            "flow": "fan_out",
+            "source": {"type": "http", "url": format!("{}/events.jsonl", base)},
+            "sinks": sinks,
+            "parallel": true,
+            "checkpoint": "events-daily",
+        }))
+        .await
+        .expect("fan out");
+    assert_eq!(first["success"], false, "{}", first);
+    assert_eq!(
+        statuses(&first),
+        vec![
+            ("archive".to_string(), "ok".to_string()),
+            ("ingest".to_string(), "failed".to_string()),
+            ("mirror".to_string(), "failed".to_string()),
+        ]
+    );
+    assert_eq!(first["source"]["bytes"], FEED.len());
+    assert_eq!(first["source"]["staged"], "file");
+    assert!(first["sinks"][2]["error"]["message"]
+        .as_str()
+        .unwrap()
+        .contains("SSH"));
+    assert!(first["sinks"][0]["span_id"].is_string());
+    assert_ne!(first["sinks"][0]["span_id"], first["sinks"][1]["span_id"]);
+    assert_eq!(stub.source_hits.load(Ordering::SeqCst), 1);
+    let staged = tmp_dir.join("checkpoints").join("events-daily.source");
+    assert_eq!(std::fs::read_to_string(&staged).unwrap(), FEED);
+
+    // The retry reads the staged copy: no second fetch, archive is not sent again.
+    stub.healthy.store(true, Ordering::SeqCst);
+    let retry = manager
+        .handle_action(json!({
+            "action": "run",
+            "flow": "fan_out",
+            "source": {"type": "http", "url": format!("{}/events.jsonl", base)},
+            "sinks": [sinks[0].clone(), sinks[1].clone()],
+            "checkpoint": "events-daily",
+        }))
+        .await
+        .expect("retry");
+    assert_eq!(retry["success"], true, "{}", retry);
+    assert_eq!(
+        statuses(&retry),
+        vec![
+            ("archive".to_string(), "skipped".to_string()),
+            ("ingest".to_string(), "ok".to_string()),
+        ]
+    );
+    assert_eq!(retry["source"]["reused"], true);
+    assert_eq!(stub.source_hits.load(Ordering::SeqCst), 1);
+    assert!(
+        !staged.exists(),
+        "staged copy is released once all sinks are done"
+    );
+    let uploads = stub.uploads.lock().unwrap().clone();
+    assert_eq!(
+        uploads
+            .iter()
+            .filter(|(path, _)| path == "/archive")
+            .count(),
+        1
+    );
+    let ingested: Vec<_> = uploads
+        .iter()
+        .filter(|(path, _)| path == "/flaky")
+        .collect();
+    let first_attempts = first["sinks"][1]["result"]["attempts"].as_u64().unwrap();
+    assert_eq!(ingested.len() as u64, first_attempts + 1);
+    assert!(ingested.iter().all(|(_, body)| body == FEED));
+
+    // require=any tolerates a failed sink; a tiny memory limit spills to a temp file.
+    let any = manager
+        .handle_action(json!({
+            "action": "run",
+            "flow": "fan_out",
+            "source": {"type": "http", "url": format!("{}/events.jsonl", base)},
+            "sinks": [sinks[0].clone(), sinks[2].clone()],
+            "require": "any",
+            "stage_memory_bytes": 16,
+        }))
+        .await
+        .expect("require any");
+    assert_eq!(any["success"], true, "{}", any);
+    assert_eq!(any["summary"]["failed"], 1);
+    assert_eq!(any["source"]["staged"], "file");
+    assert!(any["hint"].as_str().unwrap().contains("checkpoint"));
+
+    // Set INFRA_TEST_POSTGRES_URLS (comma-separated) to land the feed in live servers too.
+    let urls = std::env::var("INFRA_TEST_POSTGRES_URLS").unwrap_or_default();
+    for url in urls.split(',').map(str::trim).filter(|u| !u.is_empty()) {
+        let table = format!("fan_out_{}", uuid::Uuid::new_v4().simple());
+        let landed = manager
+            .handle_action(json!({
+                "action": "run",
+                "flow": "fan_out",
+                "source": {"type": "http", "url": format!("{}/events.jsonl", base)},
+                "sinks": [
+                    {"type": "postgres", "name": "warehouse", "connection_url": url, "table": table, "create_table": "if_missing"},
+                    sinks[0].clone(),
+                ],
+                "parallel": true,
+            }))
+            .await
+            .expect("postgres fan out");
+        assert_eq!(landed["success"], true, "{}", landed);
+        assert_eq!(landed["sinks"][0]["result"]["inserted"], 3, "{}", landed);
+        assert_eq!(landed["source"]["staged"], "memory");
+    }
+
+    let err = manager
+        .handle_action(json!({
+            "action": "run",
+            "flow": "fan_out",
+            "source": {"type": "http", "url": format!("{}/events.jsonl", base)},
+            "sinks": [{"type": "postgres", "name": "a"}],
+        }))
+        .await
+        .expect_err("missing table");
+    assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+    assert_eq!(err.message, "sinks[0].table is required for fan_out");
+    let err = manager
+        .handle_action(json!({
+            "action": "run",
+            "flow": "fan_out",
+            "source": {"type": "http", "url": format!("{}/events.jsonl", base)},
+            "sinks": [sinks[0].clone(), sinks[0].clone()],
+        }))
+        .await
+        .expect_err("duplicate name");
+    assert_eq!(err.message, "sink name 'archive' is used twice");
+
+    restore_env("INFRA_PIPELINE_CHECKPOINTS_DIR", prev_checkpoints);
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    let _ = std::fs::remove_dir_all(&tmp_dir);
+}
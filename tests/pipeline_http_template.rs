@@ -0,0 +1,320 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::api::ApiManager;
+use infra::managers::pipeline::PipelineManager;
+use infra::managers::postgres::PostgresManager;
+use infra::managers::ssh::SshManager;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use serde_json::{json, Value};
+use std::io::{Read, Write};
+use std::sync::{Arc, Mutex};
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+fn manager() -> (PipelineManager, Arc<PostgresManager>) {
+    let logger = Logger::new("test");
+    let security = Arc::new(Security::new().expect("security"));
+    let profile_service = Arc::new(ProfileService::new(security.clone()).expect("profile service"));
+    let api = ApiManager::new(
+        logger.clone(),
+        Validation::new(),
+        profile_service.clone(),
+        None,
+        None,
+        None,
+    );
+    let ssh = SshManager::new(
+        logger.clone(),
+        security,
+        Validation::new(),
+        profile_service.clone(),
+        None,
+        None,
+        None,
+    );
+    let postgres = Arc::new(PostgresManager::new(
+        logger.clone(),
+        Validation::new(),
+        profile_service,
+        None,
+        None,
+    ));
+    let pipeline = PipelineManager::new(
+        logger,
+        Validation::new(),
+        Arc::new(api),
+        Arc::new(ssh),
+        postgres.clone(),
+        None,
+        None,
+        None,
+        None,
+    );
+    (pipeline, postgres)
+}
+
+// (request line, body) of every request the sink saw.
+type Seen = Arc<Mutex<Vec<(String, String)>>>;
+
+// Paths containing `reject` get a 500, everything else a 200.
+fn spawn_sink() -> (u16, Seen) {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind sink");
+    let port = listener.local_addr().expect("sink addr").port();
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let log = seen.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { continue };
+            let mut raw = Vec::new();
+            let mut buf = [0u8; 8192];
+            let head_end = loop {
+                let read = stream.read(&mut buf).unwrap_or(0);
+                if read == 0 {
+                    break raw.len();
+                }
+                raw.extend_from_slice(&buf[..read]);
+                if let Some(pos) = raw.windows(4).position(|w| w == b"\r\n\r\n") {
+                    break pos + 4;
+                }
+            };
+            let head = String::from_utf8_lossy(&raw[..head_end]).to_string();
+            let line = head.lines().next().unwrap_or("").to_string();
+            let length = head
+                .lines()
+                .filter_map(|l| l.split_once(':'))
+                .find(|(k, _)| k.trim().eq_ignore_ascii_case("content-length"))
+                .and_then(|(_, v)| v.trim().parse::<usize>().ok())
+                .unwrap_or(0);
+            while raw.len() < head_end + length {
+                let read = stream.read(&mut buf).unwrap_or(0);
+                if read == 0 {
+                    break;
+                }
+                raw.extend_from_slice(&buf[..read]);
+            }
+            let body = String::from_utf8_lossy(&raw[head_end..]).to_string();
+            let status = if line.contains("reject") {
+                "500 Internal Server Error"
+            } else {
+                "200 OK"
+            };
+            log.lock().unwrap().push((line, body));
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{{}}",
+                status
+            );
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+    (port, seen)
+}
+
+#[tokio::test]
+async fn templated_http_sinks_render_per_record_and_per_batch() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    let (manager, postgres) = manager();
+
+    for (flow, extra, needle) in [
+        (
+            "postgres_to_http",
+            json!({"dry_run": true}),
+            "dry_run requires",
+        ),
+        (
+            "postgres_to_http",
+            json!({"http": {"url": "http://127.0.0.1:1/", "per": "batch"}}),
+            "http.per requires",
+        ),
+        (
+            "postgres_to_http",
+            json!({"http": {"url": "http://127.0.0.1:1/", "body_template": {}, "per": "row"}}),
+            "record or batch",
+        ),
+        (
+            "postgres_to_http",
+            json!({"http": {"url": "http://127.0.0.1:1/", "body_template": {}, "batch_size": 5}}),
+            "per=batch",
+        ),
+        (
+            "postgres_to_http",
+            json!({"http": {"url": "http://127.0.0.1:1/", "body_template": {}, "missing": "ignore"}}),
+            "error, skip or null",
+        ),
+        (
+            "postgres_to_http",
+            json!({"http": {"url": "http://127.0.0.1:1/", "body_template": {}}, "chunk_rows": 10}),
+            "chunk_rows cannot be combined",
+        ),
+        (
+            "sftp_to_http",
+            json!({"http": {"url": "http://127.0.0.1:1/", "path_template": "/{{record.id}}"}, "sftp": {"profile_name": "etl", "remote_glob": "/in/*.jsonl"}}),
+            "single sftp.remote_path",
+        ),
+    ] {
+        let mut args = json!({
+            "action": "run",
+            "flow": flow,
+            "postgres": {"connection_url": "postgres://app@127.0.0.1:1/app", "table": "events"},
+            "sftp": {"profile_name": "etl", "remote_path": "/in/events.jsonl"},
+            "http": {"url": "http://127.0.0.1:1/ingest"},
+        });
+        args.as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        let err = manager
+            .handle_action(args)
+            .await
+            .expect_err("invalid template options");
+        assert_eq!(err.kind, ToolErrorKind::InvalidParams, "{}", err.message);
+        assert!(err.message.contains(needle), "{}", err.message);
+    }
+
+    // Set INFRA_TEST_POSTGRES_URLS (comma-separated) to render rows from live servers.
+    let urls = std::env::var("INFRA_TEST_POSTGRES_URLS").unwrap_or_default();
+    for url in urls.split(',').map(str::trim).filter(|url| !url.is_empty()) {
+        let table = format!("infra_templated_{}", uuid::Uuid::new_v4().simple());
+        for sql in [
+            format!(
+                "CREATE TABLE \"{}\" (id int4 PRIMARY KEY, account text, kind text, api_token text)",
+                table
+            ),
+            format!(
+                "INSERT INTO \"{}\" SELECT g, CASE WHEN g = 3 THEN 'reject me' ELSE 'acct-' || g END, 'signup', 'tok-' || g FROM generate_series(1, 5) g",
+                table
+            ),
+        ] {
+            postgres
+                .handle_action(json!({"action": "query", "connection_url": url, "sql": sql}))
+                .await
+                .expect("seed table");
+        }
+        let (port, seen) = spawn_sink();
+        let run = |http: Value, extra: Value| {
+            let mut http = http;
+            http["url"] = Value::String(format!("http://127.0.0.1:{}/", port));
+            http["retry"] = json!({"max_attempts": 1});
+            let mut args = json!({
+                "action": "run",
+                "flow": "postgres_to_http",
+                "postgres": {"connection_url": url, "table": table},
+                "order_by": ["id"],
+                "http": http,
+            });
+            args.as_object_mut()
+                .unwrap()
+                .extend(extra.as_object().unwrap().clone());
+            manager.handle_action(args)
+        };
+        let per_record = json!({
+            "path_template": "/v1/accounts/{{record.account}}/events",
+            "body_template": {"event": "{{record.kind}}", "id": "{{record.id}}", "source": "infra"},
+            "concurrency": 3,
+        });
+
+        let preview = run(per_record.clone(), json!({"dry_run": true}))
+            .await
+            .expect("dry run");
+        assert_eq!(preview["dry_run"], true, "{}", preview);
+        let examples = preview["examples"].as_array().expect("examples");
+        assert_eq!(examples.len(), 2);
+        assert_eq!(
+            examples[0]["url"],
+            format!("http://127.0.0.1:{}/v1/accounts/acct-1/events", port)
+        );
+        assert_eq!(
+            examples[1]["body"],
+            json!({"event": "signup", "id": 2, "source": "infra"})
+        );
+        assert!(seen.lock().unwrap().is_empty());
+
+        let result = run(per_record, json!({})).await.expect("per record");
+        assert_eq!(result["success"], false, "{}", result);
+        assert_eq!(result["records"]["read"], 5);
+        assert_eq!(result["records"]["delivered"], 4);
+        assert_eq!(result["records"]["failed"], 1);
+        assert_eq!(result["requests"]["sent"], 5);
+        assert_eq!(result["postgres"]["rows_written"], 5);
+        let failure = &result["failures"][0];
+        assert_eq!(failure["status"], 500);
+        assert_eq!(failure["record"]["id"], 3);
+        assert_ne!(failure["record"]["api_token"], "tok-3");
+        let mut lines: Vec<String> = std::mem::take(&mut *seen.lock().unwrap())
+            .into_iter()
+            .map(|(line, _)| line)
+            .collect();
+        lines.sort();
+        assert!(
+            lines[0].starts_with("POST /v1/accounts/acct-1/events"),
+            "{:?}",
+            lines
+        );
+        assert!(lines
+            .iter()
+            .any(|line| line.starts_with("POST /v1/accounts/reject%20me/events")));
+
+        let batched = run(
+            json!({
+                "body_template": {"events": "{{batch}}", "count": "{{count}}"},
+                "per": "batch",
+                "batch_size": 2,
+            }),
+            json!({"columns": ["id", "kind"]}),
+        )
+        .await
+        .expect("per batch");
+        assert_eq!(batched["success"], true, "{}", batched);
+        assert_eq!(batched["requests"]["sent"], 3);
+        let bodies: Vec<Value> = std::mem::take(&mut *seen.lock().unwrap())
+            .into_iter()
+            .map(|(_, body)| serde_json::from_str(&body).expect("json body"))
+            .collect();
+        let counts: Vec<_> = bodies.iter().map(|body| body["count"].clone()).collect();
+        assert_eq!(counts, [json!(2), json!(2), json!(1)]);
+        assert_eq!(bodies[1]["events"][0], json!({"id": 3, "kind": "signup"}));
+
+        let missing = |policy: &str| {
+            run(
+                json!({"body_template": {"tier": "{{record.tier}}"}, "missing": policy}),
+                json!({}),
+            )
+        };
+        let stopped = missing("error").await.expect("missing=error");
+        assert_eq!(stopped["success"], false);
+        assert_eq!(stopped["stopped"]["stage"], "render");
+        assert_eq!(stopped["requests"]["sent"], 0);
+        let skipped = missing("skip").await.expect("missing=skip");
+        assert_eq!(skipped["records"]["skipped"], 5);
+        assert_eq!(skipped["requests"]["sent"], 0);
+        let nulls = missing("null").await.expect("missing=null");
+        assert_eq!(nulls["records"]["delivered"], 5, "{}", nulls);
+        let bodies = std::mem::take(&mut *seen.lock().unwrap());
+        assert!(bodies.iter().all(|(_, body)| body == r#"{"tier":null}"#));
+
+        postgres
+            .handle_action(json!({
+                "action": "query",
+                "connection_url": url,
+                "sql": format!("DROP TABLE \"{}\"", table),
+            }))
+            .await
+            .expect("drop table");
+    }
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
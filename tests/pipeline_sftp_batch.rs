@@ -0,0 +1,185 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::api::ApiManager;
+use infra::managers::pipeline::PipelineManager;
+use infra::managers::postgres::PostgresManager;
+use infra::managers::ssh::SshManager;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use serde_json::json;
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+fn manager() -> PipelineManager {
+    let logger = Logger::new("test");
+    let security = Arc::new(Security::new().expect("security"));
+    let profile_service = Arc::new(ProfileService::new(security.clone()).expect("profile service"));
+    let api = ApiManager::new(
+        logger.clone(),
+        Validation::new(),
+        profile_service.clone(),
+        None,
+        None,
+        None,
+    );
+    let ssh = SshManager::new(
+        logger.clone(),
+        security,
+        Validation::new(),
+        profile_service.clone(),
+        None,
+        None,
+        None,
+    );
+    let postgres = PostgresManager::new(
+        logger.clone(),
+        Validation::new(),
+        profile_service,
+        None,
+        None,
+    );
+    PipelineManager::new(
+        logger,
+        Validation::new(),
+        Arc::new(api),
+        Arc::new(ssh),
+        Arc::new(postgres),
+        None,
+        None,
+        None,
+        None,
+    )
+}
+
+fn closed_local_port() -> u16 {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind probe port");
+    listener.local_addr().expect("probe addr").port()
+}
+
+#[tokio::test]
+async fn sftp_batch_sources_validate_options_and_checkpoints() {
+    let _guard = ENV_LOCK.lock().await;
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let prev_checkpoints = std::env::var("INFRA_PIPELINE_CHECKPOINTS_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    std::env::remove_var("INFRA_PIPELINE_CHECKPOINTS_DIR");
+    let manager = manager();
+    let connection = json!({
+        "host": "127.0.0.1",
+        "port": closed_local_port(),
+        "username": "etl",
+        "password": "pw",
+    });
+    let run = |sftp: serde_json::Value, extra: serde_json::Value| {
+        let mut args = json!({
+            "action": "run",
+            "flow": "sftp_to_postgres",
+            "sftp": sftp,
+            "postgres": {"connection_url": "postgres://app@127.0.0.1:1/app", "table": "orders"},
+        });
+        for (key, value) in extra.as_object().cloned().unwrap_or_default() {
+            args[key] = value;
+        }
+        manager.handle_action(args)
+    };
+
+    let cases = [
+        (
+            json!({"remote_glob": "/inbox/*.csv", "done_dir": "/inbox/done"}),
+            "sftp.done_dir requires sftp.post_process=move",
+        ),
+        (
+            json!({"remote_glob": "/inbox/*.csv", "post_process": "move"}),
+            "sftp.post_process=move requires sftp.done_dir",
+        ),
+        (
+            json!({"remote_glob": "/inbox/*.zip", "archive_member_glob": "*.csv"}),
+            "sftp.archive_member_glob requires sftp.archive=zip",
+        ),
+        (
+            json!({"remote_glob": "/inbox/*/data.csv"}),
+            "sftp.remote_glob wildcards are only supported in the file name",
+        ),
+        (
+            json!({"remote_glob": "/inbox/*.csv", "decompress": "bzip2"}),
+            "sftp.decompress must be one of: gzip, auto, none (got bzip2)",
+        ),
+        (
+            json!({"remote_glob": "/inbox/*.csv", "remote_path": "/inbox/a.csv"}),
+            "sftp.remote_path and sftp.remote_glob are mutually exclusive",
+        ),
+        (
+            json!({"remote_path": "/inbox/a.zip", "archive": "tar"}),
+            "sftp.archive must be zip or none (got tar)",
+        ),
+    ];
+    for (sftp, message) in cases {
+        let mut sftp = sftp;
+        sftp["connection"] = connection.clone();
+        let err = run(sftp, json!({})).await.expect_err(message);
+        assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+        assert_eq!(err.message, message);
+    }
+
+    // remote_glob stands in for remote_path, so the run gets as far as the (closed) server.
+    let err = run(
+        json!({"connection": connection, "remote_glob": "/inbox/orders-*.csv.gz", "decompress": "auto"}),
+        json!({}),
+    )
+    .await
+    .expect_err("unreachable server");
+    assert!(!err.message.contains("required"), "{}", err.message);
+
+    let err = run(
+        json!({"connection": connection, "remote_glob": "/inbox/*.csv"}),
+        json!({"checkpoint": "../escape"}),
+    )
+    .await
+    .expect_err("bad checkpoint name");
+    assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+
+    let checkpoints = tmp_dir.join("pipeline-checkpoints");
+    std::fs::create_dir_all(&checkpoints).expect("checkpoint dir");
+    std::fs::write(
+        checkpoints.join("inbox.json"),
+        json!({"checkpoint": "inbox", "flow": "sftp_to_http", "files": {}}).to_string(),
+    )
+    .expect("seed checkpoint");
+    let err = run(
+        json!({"connection": connection, "remote_glob": "/inbox/*.csv"}),
+        json!({"checkpoint": "inbox"}),
+    )
+    .await
+    .expect_err("checkpoint of another flow");
+    assert_eq!(err.kind, ToolErrorKind::Conflict);
+    assert_eq!(err.message, "checkpoint inbox belongs to flow sftp_to_http");
+
+    let described = manager
+        .handle_action(json!({"action": "describe", "flow": "sftp_to_http"}))
+        .await
+        .expect("describe");
+    assert_eq!(
+        described["flow"]["source"]["alternatives"],
+        json!({"remote_path": "remote_glob"})
+    );
+    assert!(described["flow"]["options"]
+        .as_array()
+        .unwrap()
+        .contains(&json!("checkpoint")));
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    restore_env("INFRA_PIPELINE_CHECKPOINTS_DIR", prev_checkpoints);
+    let _ = std::fs::remove_dir_all(&tmp_dir);
+}
@@ -0,0 +1,188 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::api::ApiManager;
+use infra::managers::pipeline::PipelineManager;
+use infra::managers::postgres::PostgresManager;
+use infra::managers::ssh::SshManager;
+use infra::services::job::JobService;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use serde_json::json;
+use std::io::{Read, Write};
+use std::sync::Arc;
+use std::time::Duration;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+fn manager() -> PipelineManager {
+    let logger = Logger::new("test");
+    let security = Arc::new(Security::new().expect("security"));
+    let profile_service = Arc::new(ProfileService::new(security.clone()).expect("profile service"));
+    let api = ApiManager::new(
+        logger.clone(),
+        Validation::new(),
+        profile_service.clone(),
+        None,
+        None,
+        None,
+    );
+    let ssh = SshManager::new(
+        logger.clone(),
+        security,
+        Validation::new(),
+        profile_service.clone(),
+        None,
+        None,
+        None,
+    );
+    let postgres = PostgresManager::new(
+        logger.clone(),
+        Validation::new(),
+        profile_service,
+        None,
+        None,
+    );
+    PipelineManager::new(
+        logger,
+        Validation::new(),
+        Arc::new(api),
+        Arc::new(ssh),
+        Arc::new(postgres),
+        None,
+        None,
+        None,
+        None,
+    )
+}
+
+fn closed_local_port() -> u16 {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind probe port");
+    listener.local_addr().expect("probe addr").port()
+}
+
+// Serves a body far larger than the upload buffers to every connection.
+fn spawn_large_body(bytes: usize) -> u16 {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind stub");
+    let port = listener.local_addr().expect("stub addr").port();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { continue };
+            let mut buf = [0u8; 8192];
+            let _ = stream.read(&mut buf);
+            let head = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/csv\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                bytes
+            );
+            if stream.write_all(head.as_bytes()).is_err() {
+                continue;
+            }
+            let chunk = vec![b'x'; 64 * 1024];
+            let mut sent = 0;
+            while sent < bytes {
+                let n = chunk.len().min(bytes - sent);
+                if stream.write_all(&chunk[..n]).is_err() {
+                    break;
+                }
+                sent += n;
+            }
+        }
+    });
+    port
+}
+
+#[tokio::test]
+async fn streamed_uploads_fail_fast_and_validate_options() {
+    let _guard = ENV_LOCK.lock().await;
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    let manager = manager();
+    let port = spawn_large_body(16 * 1024 * 1024);
+    let run = |sftp: serde_json::Value| {
+        manager.handle_action(json!({
+            "action": "run",
+            "flow": "http_to_sftp",
+            "http": {"url": format!("http://127.0.0.1:{}/export.csv", port)},
+            "sftp": sftp,
+        }))
+    };
+    let connection = json!({
+        "host": "127.0.0.1",
+        "port": closed_local_port(),
+        "username": "etl",
+        "password": "pw",
+    });
+
+    let err = run(json!({
+        "connection": connection,
+        "remote_path": "/exports/report.csv",
+        "verify": "md5",
+    }))
+    .await
+    .expect_err("unknown verify mode");
+    assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+    assert_eq!(
+        err.message,
+        "sftp.verify must be one of: none, size, sha256 (got md5)"
+    );
+
+    // The producer has far more to send than the pipe holds; a dead sink must not leave it
+    // waiting on a reader that stopped.
+    let err = tokio::time::timeout(
+        Duration::from_secs(30),
+        run(json!({
+            "connection": connection,
+            "remote_path": "/exports/report.csv",
+            "keep_partial": true,
+        })),
+    )
+    .await
+    .expect("upload failure returns instead of hanging")
+    .expect_err("sftp host is unreachable");
+    assert_ne!(err.kind, ToolErrorKind::InvalidParams, "{}", err.message);
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
+
+#[tokio::test]
+async fn job_progress_merges_while_running_only() {
+    let _guard = ENV_LOCK.lock().await;
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    let jobs = JobService::new(Logger::new("test")).expect("jobs");
+
+    let job = jobs
+        .start_local("pipeline", json!({"flow": "postgres_to_sftp"}), None)
+        .expect("start job");
+    jobs.update_progress(
+        &job.job_id,
+        json!({"stage": "sftp_upload", "bytes_uploaded": 1}),
+    );
+    jobs.update_progress(&job.job_id, json!({"bytes_uploaded": 2}));
+    let record = jobs.get(&job.job_id).expect("record");
+    assert_eq!(
+        record["progress"],
+        json!({"stage": "sftp_upload", "bytes_uploaded": 2})
+    );
+
+    jobs.finish_local(&job.job_id, Ok(json!({"success": true})));
+    jobs.update_progress(&job.job_id, json!({"bytes_uploaded": 3}));
+    let record = jobs.get(&job.job_id).expect("record");
+    assert_eq!(record["progress"]["bytes_uploaded"], 2);
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
@@ -0,0 +1,142 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::postgres::PostgresManager;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+fn manager() -> PostgresManager {
+    let security = Arc::new(Security::new().expect("security"));
+    PostgresManager::new(
+        Logger::new("test"),
+        Validation::new(),
+        Arc::new(ProfileService::new(security).expect("profile service")),
+        None,
+        None,
+    )
+}
+
+#[tokio::test]
+async fn batch_items_are_validated_before_anything_runs() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    let manager = manager();
+
+    let batch = |statements: serde_json::Value| {
+        manager.handle_action(serde_json::json!({
+            "action": "batch",
+            "connection_url": "postgres://app@127.0.0.1:1/app",
+            "statements": statements,
+        }))
+    };
+
+    let err = batch(serde_json::json!(["SELECT 1", {"sql": "SELECT :id", "params": {"other": 1}}]))
+        .await
+        .expect_err("missing param");
+    assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+    assert!(err.message.contains("statements[1]"), "{}", err.message);
+    assert!(err.message.contains(":id"), "{}", err.message);
+
+    let err = batch(serde_json::json!([
+        {"sql": "SELECT 1", "name": "a"},
+        {"sql": "SELECT 2", "name": "a"},
+    ]))
+    .await
+    .expect_err("duplicate name");
+    assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+    assert!(err.message.contains("more than once"), "{}", err.message);
+
+    let err = batch(serde_json::json!(["SELECT 1", "  "]))
+        .await
+        .expect_err("blank item");
+    assert!(err.message.contains("statements[1].sql"), "{}", err.message);
+
+    let err = batch(serde_json::json!([{"sql": "SELECT 1", "timeout_ms": 0}]))
+        .await
+        .expect_err("zero timeout");
+    assert!(err.message.contains("timeout_ms"), "{}", err.message);
+
+    let err = batch(serde_json::json!([42]))
+        .await
+        .expect_err("number item");
+    assert!(err.message.contains("statements[0]"), "{}", err.message);
+
+    // Set INFRA_TEST_POSTGRES_URLS (comma-separated) to run against live servers.
+    let urls = std::env::var("INFRA_TEST_POSTGRES_URLS").unwrap_or_default();
+    for url in urls.split(',').map(str::trim).filter(|url| !url.is_empty()) {
+        let table = format!("infra_batch_{}", uuid::Uuid::new_v4().simple());
+        let run = |args: serde_json::Value| {
+            let mut call = serde_json::json!({"action": "batch", "connection_url": url});
+            for (key, value) in args.as_object().expect("args object") {
+                call[key] = value.clone();
+            }
+            manager.handle_action(call)
+        };
+
+        let created = run(serde_json::json!({
+            "statements": [
+                format!("CREATE TABLE \"{}\" (id int4 PRIMARY KEY, v int4)", table),
+                {"sql": format!("INSERT INTO \"{}\" VALUES (:id, 0), (:id + 1, 0)", table), "params": {"id": 1}, "name": "seed"},
+            ],
+        }))
+        .await
+        .expect("plain and parameterized items");
+        assert_eq!(created["success"], true);
+        assert_eq!(created["results"][1]["affected_rows"], 2);
+        assert_eq!(created["by_name"]["seed"], 1);
+
+        let atomic = run(serde_json::json!({
+            "transaction": true,
+            "statements": [
+                {"sql": format!("UPDATE \"{}\" SET v = 1", table), "name": "bump"},
+                {"sql": "SELECT pg_sleep(2)", "timeout_ms": 100, "name": "slow"},
+            ],
+        }))
+        .await
+        .expect("transaction with a timed-out statement");
+        assert_eq!(atomic["success"], false);
+        assert_eq!(atomic["committed"], false);
+        assert_eq!(atomic["results"][1]["code"], "TIMEOUT");
+
+        let isolated = run(serde_json::json!({
+            "transaction": true,
+            "stop_on_error": false,
+            "statements": [
+                format!("INSERT INTO \"{}\" VALUES (1, 9)", table),
+                format!("UPDATE \"{}\" SET v = 2 WHERE id = 2", table),
+            ],
+        }))
+        .await
+        .expect("savepoint per statement");
+        assert_eq!(isolated["failed"], 1);
+        assert_eq!(isolated["committed"], true);
+
+        let check = run(serde_json::json!({
+            "statements": [
+                {"sql": format!("SELECT sum(v) AS total FROM \"{}\"", table), "mode": "value"},
+                format!("DROP TABLE \"{}\"", table),
+            ],
+        }))
+        .await
+        .expect("verify and drop");
+        assert_eq!(check["results"][0]["value"], 2);
+    }
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
@@ -0,0 +1,219 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::postgres::PostgresManager;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+fn manager() -> PostgresManager {
+    let security = Arc::new(Security::new().expect("security"));
+    PostgresManager::new(
+        Logger::new("test"),
+        Validation::new(),
+        Arc::new(ProfileService::new(security).expect("profile service")),
+        None,
+        None,
+    )
+}
+
+fn chunk_statuses(result: &Value) -> Vec<String> {
+    result["chunks"]
+        .as_array()
+        .expect("chunks")
+        .iter()
+        .map(|chunk| chunk["status"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn insert_bulk_chunks_resolves_conflicts_and_isolates_failures() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    let postgres = manager();
+
+    for (extra, message) in [
+        (
+            json!({"on_conflict": "merge"}),
+            "on_conflict must be one of: error, ignore, upsert",
+        ),
+        (
+            json!({"on_conflict": "upsert"}),
+            "on_conflict=upsert needs conflict_columns (or primary_key)",
+        ),
+        (
+            json!({"on_conflict": "upsert", "conflict_columns": ["id", "email"]}),
+            "on_conflict=upsert has no columns to update",
+        ),
+        (
+            json!({"rows": [{"id": 1, "email": "a"}, "oops"]}),
+            "Each row must be an object or array",
+        ),
+    ] {
+        let mut args = json!({
+            "action": "insert_bulk",
+            "connection_url": "postgres://app@127.0.0.1:1/app",
+            "table": "users",
+            "rows": [{"id": 1, "email": "a"}],
+        });
+        args.as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        let err = postgres.handle_action(args).await.expect_err("rejected");
+        assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+        assert_eq!(err.message, message);
+    }
+
+    // Set INFRA_TEST_POSTGRES_URLS (comma-separated) to run against live servers.
+    let urls = std::env::var("INFRA_TEST_POSTGRES_URLS").unwrap_or_default();
+    for url in urls.split(',').map(str::trim).filter(|url| !url.is_empty()) {
+        let table = format!("infra_bulk_{}", uuid::Uuid::new_v4().simple());
+        postgres
+            .handle_action(json!({
+                "action": "query",
+                "connection_url": url,
+                "sql": format!(
+                    "CREATE TABLE {} (id bigint PRIMARY KEY, email text UNIQUE, score int NOT NULL)",
+                    table
+                ),
+            }))
+            .await
+            .expect("create table");
+        postgres
+            .handle_action(json!({
+                "action": "profile_upsert",
+                "profile_name": "bulk",
+                "connection_url": url,
+                "redaction": {table.clone(): {"columns": ["email"], "mode": "mask"}},
+            }))
+            .await
+            .expect("profile upsert");
+        let insert = |extra: Value| {
+            let mut args = json!({
+                "action": "insert_bulk",
+                "profile_name": "bulk",
+                "table": table,
+            });
+            args.as_object_mut()
+                .unwrap()
+                .extend(extra.as_object().unwrap().clone());
+            postgres.handle_action(args)
+        };
+        let user = |id: i64, score: Value| json!({"id": id, "email": format!("u{}@example.com", id), "score": score});
+
+        let first = insert(json!({
+            "chunk_size": 2,
+            "rows": (1..=5).map(|id| user(id, json!(id))).collect::<Vec<_>>(),
+        }))
+        .await
+        .expect("chunked insert");
+        assert_eq!(first["inserted"], 5);
+        assert_eq!(first["failed"], 0);
+        assert_eq!(first["atomic"], false);
+        assert_eq!(first["batches"], 3);
+        assert_eq!(chunk_statuses(&first), ["ok", "ok", "ok"]);
+        assert!(first["chunks"][2]["duration_ms"].is_u64());
+        assert_eq!(first["chunks"][2]["rows"], 1);
+
+        let ignored = insert(json!({
+            "on_conflict": "ignore",
+            "rows": [user(4, json!(0)), user(5, json!(0)), user(6, json!(6))],
+        }))
+        .await
+        .expect("ignore conflicts");
+        assert_eq!(
+            (
+                &ignored["inserted"],
+                &ignored["ignored"],
+                &ignored["atomic"]
+            ),
+            (&json!(1), &json!(2), &json!(true))
+        );
+
+        let upserted = insert(json!({
+            "on_conflict": "upsert",
+            "conflict_columns": "id",
+            "update_columns": ["score"],
+            "returning": ["id", "score"],
+            "rows": [user(1, json!(100)), user(7, json!(7))],
+        }))
+        .await
+        .expect("upsert");
+        assert_eq!(upserted["inserted"], 1);
+        assert_eq!(upserted["updated"], 1);
+        assert_eq!(upserted["affected"], 2);
+        assert_eq!(
+            upserted["rows"],
+            json!([{"id": 1, "score": 100}, {"id": 7, "score": 7}])
+        );
+
+        // score is NOT NULL: the middle chunk fails, the others still land.
+        let partial = insert(json!({
+            "chunk_size": 1,
+            "continue_on_error": true,
+            "rows": [user(8, json!(8)), user(9, Value::Null), user(10, json!(10))],
+        }))
+        .await
+        .expect("continue on error");
+        assert_eq!(partial["success"], false);
+        assert_eq!(partial["inserted"], 2);
+        assert_eq!(partial["failed"], 1);
+        assert_eq!(chunk_statuses(&partial), ["ok", "failed", "ok"]);
+        let failed = &partial["chunks"][1];
+        assert_eq!(failed["offset"], 1);
+        assert_eq!(failed["error"]["sqlstate"], "23502");
+        assert_eq!(
+            failed["sample_rows"],
+            json!([{"id": 9, "email": "***", "score": null}])
+        );
+
+        let err = insert(json!({
+            "chunk_size": 1,
+            "rows": [user(11, json!(11)), user(12, Value::Null)],
+        }))
+        .await
+        .expect_err("stops at the failing chunk");
+        assert!(err
+            .hint
+            .as_deref()
+            .unwrap()
+            .starts_with("Rows before offset 1 were already committed (1 inserted)."));
+
+        let stored = postgres
+            .handle_action(json!({
+                "action": "query",
+                "connection_url": url,
+                "sql": format!("SELECT count(*)::int AS n, sum(score)::int AS total FROM {}", table),
+            }))
+            .await
+            .expect("count rows");
+        assert_eq!(stored["rows"][0], json!({"n": 10, "total": 156}));
+
+        postgres
+            .handle_action(json!({
+                "action": "query",
+                "connection_url": url,
+                "sql": format!("DROP TABLE {}", table),
+            }))
+            .await
+            .expect("drop table");
+    }
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
@@ -0,0 +1,258 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::api::ApiManager;
+use infra::managers::pipeline::PipelineManager;
+use infra::managers::postgres::PostgresManager;
+use infra::managers::ssh::SshManager;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use serde_json::{json, Value};
+use std::io::{Read, Write};
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+fn manager() -> (PipelineManager, Arc<PostgresManager>) {
+    let logger = Logger::new("test");
+    let security = Arc::new(Security::new().expect("security"));
+    let profile_service = Arc::new(ProfileService::new(security.clone()).expect("profile service"));
+    let api = ApiManager::new(
+        logger.clone(),
+        Validation::new(),
+        profile_service.clone(),
+        None,
+        None,
+        None,
+    );
+    let ssh = SshManager::new(
+        logger.clone(),
+        security,
+        Validation::new(),
+        profile_service.clone(),
+        None,
+        None,
+        None,
+    );
+    let postgres = Arc::new(PostgresManager::new(
+        logger.clone(),
+        Validation::new(),
+        profile_service,
+        None,
+        None,
+    ));
+    let pipeline = PipelineManager::new(
+        logger,
+        Validation::new(),
+        Arc::new(api),
+        Arc::new(ssh),
+        postgres.clone(),
+        None,
+        None,
+        None,
+        None,
+    );
+    (pipeline, postgres)
+}
+
+// Serves `body` as text/csv to every request.
+fn spawn_csv(body: &'static str) -> u16 {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+    let port = listener.local_addr().unwrap().port();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { break };
+            let mut buf = [0u8; 4096];
+            let _ = stream.read(&mut buf);
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/csv\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+    port
+}
+
+fn column_types(setup: &Value) -> Vec<(String, String)> {
+    setup["columns"]
+        .as_array()
+        .expect("columns")
+        .iter()
+        .map(|c| {
+            (
+                c["name"].as_str().unwrap().to_string(),
+                c["type"].as_str().unwrap().to_string(),
+            )
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn insert_bulk_creates_tables_from_inferred_schema() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    let (pipeline, postgres) = manager();
+
+    for (extra, message) in [
+        (
+            json!({"create_table": "drop"}),
+            "create_table must be one of: never, if_missing, replace",
+        ),
+        (
+            json!({"create_table": "if_missing", "primary_key": "missing"}),
+            "primary_key column missing is not among the inserted columns",
+        ),
+    ] {
+        let mut args = json!({
+            "action": "insert_bulk",
+            "connection_url": "postgres://app@127.0.0.1:1/app",
+            "table": "events",
+            "rows": [{"id": 1}],
+        });
+        args.as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        let err = postgres.handle_action(args).await.expect_err("rejected");
+        assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+        assert_eq!(err.message, message);
+    }
+
+    // Set INFRA_TEST_POSTGRES_URLS (comma-separated) to create tables on live servers.
+    let urls = std::env::var("INFRA_TEST_POSTGRES_URLS").unwrap_or_default();
+    for url in urls.split(',').map(str::trim).filter(|url| !url.is_empty()) {
+        let table = format!("infra_create_{}", uuid::Uuid::new_v4().simple());
+        let insert = |create_table: &str, rows: Value| {
+            postgres.handle_action(json!({
+                "action": "insert_bulk",
+                "connection_url": url,
+                "table": table,
+                "rows": rows,
+                "create_table": create_table,
+                "primary_key": "id",
+            }))
+        };
+        let first = insert(
+            "if_missing",
+            json!([
+                {"id": 1, "price": 2, "at": "2024-05-01T10:00:00Z", "tags": ["a"], "note": null, "code": 7},
+                {"id": 2, "price": 2.5, "at": "2024-05-02T10:00:00Z", "tags": {"k": 1}, "note": "x", "code": "seven"},
+            ]),
+        )
+        .await
+        .expect("create and insert");
+        let setup = &first["table_setup"];
+        assert_eq!(first["inserted"], 2);
+        assert_eq!(setup["created"], true);
+        assert_eq!(
+            column_types(setup),
+            [
+                ("at", "timestamptz"),
+                ("code", "text"),
+                ("id", "bigint"),
+                ("note", "text"),
+                ("price", "double precision"),
+                ("tags", "jsonb"),
+            ]
+            .map(|(n, t)| (n.to_string(), t.to_string()))
+        );
+        assert_eq!(
+            setup["warnings"],
+            json!(["column code: mixed integer, string values in the sample; created as text"])
+        );
+        assert!(setup["ddl"]
+            .as_str()
+            .unwrap()
+            .ends_with("\"note\" text, \"price\" double precision NOT NULL, \"tags\" jsonb NOT NULL, PRIMARY KEY (\"id\"))"));
+
+        let again = insert(
+            "if_missing",
+            json!([{"id": 3, "price": 1, "at": "2024-05-03T10:00:00Z", "tags": [], "note": null, "code": "8"}]),
+        )
+        .await
+        .expect("insert into existing table");
+        assert_eq!(
+            again["table_setup"],
+            json!({"mode": "if_missing", "created": false})
+        );
+
+        let stored = postgres
+            .handle_action(json!({
+                "action": "query",
+                "connection_url": url,
+                "sql": format!("SELECT count(*)::int AS n, sum(price) AS total FROM \"{}\"", table),
+            }))
+            .await
+            .expect("count rows");
+        assert_eq!(stored["rows"][0]["n"], 3);
+        assert_eq!(stored["rows"][0]["total"], 5.5);
+
+        let replaced = insert("replace", json!([{"id": 9, "flag": true}]))
+            .await
+            .expect("replace table");
+        assert_eq!(replaced["table_setup"]["created"], true);
+        let stored = postgres
+            .handle_action(json!({
+                "action": "query",
+                "connection_url": url,
+                "sql": format!("SELECT id, flag FROM \"{}\"", table),
+            }))
+            .await
+            .expect("read replaced table");
+        assert_eq!(stored["rows"], json!([{"id": 9, "flag": true}]));
+
+        let csv_table = format!("infra_create_csv_{}", uuid::Uuid::new_v4().simple());
+        let port = spawn_csv("id,price,day,zip\n1,2.50,2024-05-01,01234\n2,3,2024-05-02,99501\n3,4,2024-05-03,10001\n");
+        let run = pipeline
+            .handle_action(json!({
+                "action": "run",
+                "flow": "http_to_postgres",
+                "http": {"url": format!("http://127.0.0.1:{}/rows.csv", port)},
+                "postgres": {"connection_url": url, "table": csv_table, "create_table": "if_missing"},
+                "format": "csv",
+                "batch_size": 2,
+            }))
+            .await
+            .expect("pipeline with create_table");
+        assert_eq!(run["postgres"]["inserted"], 3);
+        let setup = &run["postgres"]["table_setup"];
+        assert_eq!(setup["sampled_rows"], 2);
+        assert_eq!(
+            column_types(setup),
+            [
+                ("id", "bigint"),
+                ("price", "double precision"),
+                ("day", "date"),
+                ("zip", "text"),
+            ]
+            .map(|(n, t)| (n.to_string(), t.to_string()))
+        );
+
+        for name in [&table, &csv_table] {
+            postgres
+                .handle_action(json!({
+                    "action": "query",
+                    "connection_url": url,
+                    "sql": format!("DROP TABLE \"{}\"", name),
+                }))
+                .await
+                .expect("drop table");
+        }
+    }
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    let _ = std::fs::remove_dir_all(&tmp_dir);
+}
@@ -0,0 +1,198 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::postgres::PostgresManager;
+use infra::services::audit::AuditService;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::state::StateService;
+use infra::services::tool_executor::{ToolExecutor, ToolHandler};
+use infra::services::validation::Validation;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+#[tokio::test]
+async fn dry_run_reports_would_affect_and_always_rolls_back() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    let keys = ["INFRA_PROFILES_DIR", "INFRA_AUDIT_PATH", "INFRA_READONLY"];
+    let previous: Vec<Option<String>> = keys.iter().map(|key| std::env::var(key).ok()).collect();
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    std::env::set_var("INFRA_AUDIT_PATH", tmp_dir.join("audit.jsonl"));
+    std::env::set_var("INFRA_READONLY", "1");
+
+    let security = Arc::new(Security::new().expect("security"));
+    let postgres = Arc::new(PostgresManager::new(
+        Logger::new("test"),
+        Validation::new(),
+        Arc::new(ProfileService::new(security).expect("profile service")),
+        None,
+        None,
+    ));
+    let audit = Arc::new(AuditService::new(Logger::new("test")));
+    let mut handlers: HashMap<String, Arc<dyn ToolHandler>> = HashMap::new();
+    handlers.insert("sql".to_string(), postgres.clone());
+    let executor = ToolExecutor::new(
+        Logger::new("test"),
+        Arc::new(StateService::new().expect("state")),
+        None,
+        Some(audit.clone()),
+        handlers,
+        HashMap::new(),
+    );
+
+    let offline = "postgres://app@127.0.0.1:1/app";
+    for (args, message) in [
+        (
+            json!({"action": "query", "sql": "DELETE FROM a; DELETE FROM b"}),
+            "dry_run takes exactly one statement; multiple statements cannot be previewed",
+        ),
+        (
+            json!({"action": "query", "sql": "DROP TABLE a"}),
+            "dry_run cannot preview DDL (DROP); only INSERT, UPDATE, DELETE and MERGE run in a rolled-back transaction",
+        ),
+        (
+            json!({"action": "query", "sql": "SELECT * FROM a"}),
+            "dry_run previews data-modifying statements; SELECT does not modify rows",
+        ),
+        (
+            json!({"action": "delete", "table": "a", "sample_size": 101}),
+            "sample_size must be an integer between 0 and 100",
+        ),
+    ] {
+        let mut args = args;
+        args["dry_run"] = json!(true);
+        args["connection_url"] = json!(offline);
+        let err = postgres.handle_action(args).await.expect_err(message);
+        assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+        assert_eq!(err.message, message);
+    }
+    let err = executor
+        .execute(
+            "sql",
+            json!({"action": "delete", "table": "a", "connection_url": offline, "apply": true}),
+        )
+        .await
+        .expect_err("readonly denies the real delete");
+    assert_eq!(err.kind, ToolErrorKind::Denied);
+
+    // Set INFRA_TEST_POSTGRES_URLS (comma-separated) to run against live servers.
+    let urls = std::env::var("INFRA_TEST_POSTGRES_URLS").unwrap_or_default();
+    for url in urls.split(',').map(str::trim).filter(|url| !url.is_empty()) {
+        let table = format!("infra_dry_run_{}", uuid::Uuid::new_v4().simple());
+        let run = |sql: String| {
+            postgres.handle_action(json!({"action": "query", "connection_url": url, "sql": sql}))
+        };
+        run(format!(
+            "CREATE TABLE {table} (id serial PRIMARY KEY, email text, amount numeric)"
+        ))
+        .await
+        .expect("create table");
+        run(format!(
+            "INSERT INTO {table} (email, amount) VALUES ('a@example.com', 1), ('b@example.com', 2), ('c@example.com', 3)"
+        ))
+        .await
+        .expect("seed rows");
+        postgres
+            .handle_action(json!({
+                "action": "profile_upsert",
+                "profile_name": "db",
+                "connection_url": url,
+                "redaction": {table.clone(): {"columns": ["email"], "mode": "mask"}},
+            }))
+            .await
+            .expect("profile upsert");
+        let dry_run = |extra: Value| {
+            let mut args = json!({"profile_name": "db", "dry_run": true});
+            args.as_object_mut()
+                .unwrap()
+                .extend(extra.as_object().unwrap().clone());
+            executor.execute("sql", args)
+        };
+
+        let deleted = dry_run(json!({"action": "delete", "table": table, "sample_size": 2}))
+            .await
+            .expect("delete dry run");
+        let result = &deleted["result"];
+        assert_eq!(result["rolled_back"], true, "{}", result);
+        assert_eq!(result["would_affect"], 3);
+        assert_eq!(result["sample"].as_array().unwrap().len(), 2);
+        assert_eq!(result["sample_truncated"], true);
+        assert_eq!(result["sample"][0]["email"], "***");
+        assert_eq!(result["table"], table.as_str());
+        assert_eq!(deleted["meta"]["effects"]["kind"], "read");
+
+        let updated = dry_run(json!({
+            "action": "update",
+            "table": table,
+            "data": {"amount": 10},
+            "filters": {"id": 2},
+            "returning": ["id"],
+            "numeric": "float",
+        }))
+        .await
+        .expect("update dry run");
+        assert_eq!(updated["result"]["would_affect"], 1);
+        assert_eq!(updated["result"]["sample"][0]["amount"], 10.0);
+        assert_eq!(updated["result"]["sample_truncated"], false);
+
+        let inserted = dry_run(json!({
+            "action": "query",
+            "sql": format!("INSERT INTO {table} (email, amount) VALUES (:email, 4);"),
+            "params": {"email": "d@example.com"},
+        }))
+        .await
+        .expect("query dry run");
+        assert_eq!(inserted["result"]["command"], "INSERT");
+        assert_eq!(inserted["result"]["would_affect"], 1);
+        assert_eq!(inserted["result"]["sample"][0]["email"], "***");
+
+        let err = dry_run(json!({
+            "action": "query",
+            "sql": format!("UPDATE {table} SET amount = 0 WHERE id IN (SELECT 1 FROM pg_sleep(1))"),
+            "timeout_ms": 100,
+        }))
+        .await
+        .expect_err("statement timeout");
+        assert_eq!(err.kind, ToolErrorKind::Timeout);
+
+        let unchanged = run(format!(
+            "SELECT count(*)::int AS rows, sum(amount)::int AS total FROM {table}"
+        ))
+        .await
+        .expect("count rows");
+        assert_eq!(unchanged["rows"][0], json!({"rows": 3, "total": 6}));
+
+        let entries = audit
+            .read_entries(50, 0, false, &json!({"status": "ok"}))
+            .expect("audit entries");
+        let marked = entries["entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|entry| entry["rolled_back"] == true && entry["dry_run"] == true)
+            .count();
+        assert_eq!(marked, 3);
+
+        run(format!("DROP TABLE {table}"))
+            .await
+            .expect("drop table");
+    }
+
+    for (key, value) in keys.iter().zip(previous) {
+        restore_env(key, value);
+    }
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
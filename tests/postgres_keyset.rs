@@ -0,0 +1,229 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::postgres::PostgresManager;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+fn manager() -> PostgresManager {
+    let security = Arc::new(Security::new().expect("security"));
+    PostgresManager::new(
+        Logger::new("test"),
+        Validation::new(),
+        Arc::new(ProfileService::new(security).expect("profile service")),
+        None,
+        None,
+    )
+}
+
+fn ids(result: &Value) -> Vec<i64> {
+    result["result"]["rows"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|row| row["id"].as_i64().unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn select_paginates_by_keyset_without_gaps_or_duplicates() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    let manager = manager();
+
+    for (paginate, extra, message) in [
+        (
+            json!({"keys": [{"column": "created_at", "direction": "desc"}, "id"]}),
+            json!({}),
+            "paginate.keys must all sort in the same direction; mixed ASC/DESC keys cannot be compared as one row value",
+        ),
+        (
+            json!({"keys": ["id"]}),
+            json!({"order_by": ["name"]}),
+            "order_by cannot be combined with paginate; rows are ordered by paginate.keys and sized by paginate.page_size",
+        ),
+        (
+            json!({"keys": ["created_at", "id"], "after": {"id": 3}}),
+            json!({}),
+            "paginate.after is missing key created_at",
+        ),
+    ] {
+        let mut args = json!({
+            "action": "select",
+            "connection_url": "postgres://app@127.0.0.1:1/app",
+            "table": "events",
+            "paginate": paginate,
+        });
+        args.as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        let err = manager.handle_action(args).await.expect_err(message);
+        assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+        assert_eq!(err.message, message);
+    }
+
+    // Set INFRA_TEST_POSTGRES_URLS (comma-separated) to page through live servers.
+    let urls = std::env::var("INFRA_TEST_POSTGRES_URLS").unwrap_or_default();
+    for url in urls.split(',').map(str::trim).filter(|url| !url.is_empty()) {
+        let table = format!("infra_keyset_{}", uuid::Uuid::new_v4().simple());
+        let heap = format!("{}_heap", table);
+        let query = |sql: String| {
+            manager.handle_action(json!({"action": "query", "connection_url": url, "sql": sql}))
+        };
+        // created_at repeats every two rows, so the id tie-breaker decides page boundaries.
+        for sql in [
+            format!(
+                "CREATE TABLE \"{}\" (id int4 PRIMARY KEY, created_at timestamptz NOT NULL, kind text)",
+                table
+            ),
+            format!(
+                "INSERT INTO \"{}\" SELECT g * 10, timestamptz '2024-01-01' + ((g + 1) / 2) * interval '1 minute', \
+                 CASE WHEN g % 3 = 0 THEN 'skip' ELSE 'keep' END FROM generate_series(1, 9) g",
+                table
+            ),
+            format!("CREATE TABLE \"{}\" (id int4, name text)", heap),
+            format!(
+                "INSERT INTO \"{}\" SELECT g, 'row ' || g FROM generate_series(1, 7) g",
+                heap
+            ),
+        ] {
+            query(sql).await.expect("seed tables");
+        }
+
+        let page = |after: Value, extra: Value| {
+            let mut args = json!({
+                "action": "select",
+                "connection_url": url,
+                "table": table,
+                "paginate": {"keys": ["created_at", "id"], "page_size": 3, "after": after},
+            });
+            args.as_object_mut()
+                .unwrap()
+                .extend(extra.as_object().unwrap().clone());
+            manager.handle_action(args)
+        };
+        let mut seen = Vec::new();
+        let mut after = Value::Null;
+        let mut pages = 0;
+        loop {
+            let result = page(after.clone(), json!({})).await.expect("keyset page");
+            seen.extend(ids(&result));
+            pages += 1;
+            if pages == 1 {
+                // One row lands before the cursor and one after it: the first is never
+                // returned, the second shows up exactly once, and nothing shifts.
+                query(format!(
+                    "INSERT INTO \"{}\" VALUES (5, '2024-01-01 00:00:30+00', 'keep'), \
+                     (95, '2024-01-01 00:04:00+00', 'keep')",
+                    table
+                ))
+                .await
+                .expect("insert between pages");
+            }
+            if result["has_more"] == false {
+                assert!(result["next_after"]["id"].is_i64(), "{}", result);
+                break;
+            }
+            after = result["next_after"].clone();
+            assert!(pages < 10, "pagination does not terminate");
+        }
+        assert_eq!(seen, [10, 20, 30, 40, 50, 60, 70, 80, 95, 90]);
+        assert_eq!(pages, 4);
+
+        let desc = manager
+            .handle_action(json!({
+                "action": "select",
+                "connection_url": url,
+                "table": table,
+                "filters": {"kind": "keep"},
+                "paginate": {"keys": ["id"], "direction": "desc", "page_size": 2, "after": {"id": 70}},
+            }))
+            .await
+            .expect("descending page");
+        assert_eq!(ids(&desc), [50, 40]);
+        assert_eq!(desc["next_after"], json!({"id": 40}));
+        assert_eq!(desc["has_more"], true);
+        assert_eq!(desc["paginate"]["direction"], "desc");
+
+        // Export reads by the primary key when there is one, through a cursor otherwise.
+        for (source, mode, expected) in [(&table, "keyset", 11), (&heap, "cursor", 7)] {
+            let file_path = tmp_dir.join(format!("{}.jsonl", source));
+            let exported = manager
+                .handle_action(json!({
+                    "action": "export",
+                    "connection_url": url,
+                    "table": source,
+                    "format": "jsonl",
+                    "batch_size": 3,
+                    "where_sql": "id > $1",
+                    "where_params": [0],
+                    "file_path": file_path,
+                }))
+                .await
+                .expect("export");
+            assert_eq!(exported["pagination"]["mode"], mode, "{}", exported);
+            assert_eq!(exported["rows_written"], expected);
+            let lines = std::fs::read_to_string(&file_path).expect("export file");
+            let mut exported_ids: Vec<i64> = lines
+                .lines()
+                .map(|line| {
+                    serde_json::from_str::<Value>(line).unwrap()["id"]
+                        .as_i64()
+                        .unwrap()
+                })
+                .collect();
+            let total = exported_ids.len();
+            exported_ids.sort();
+            exported_ids.dedup();
+            assert_eq!(exported_ids.len(), total);
+        }
+        let limited = manager
+            .handle_action(json!({
+                "action": "export",
+                "connection_url": url,
+                "table": table,
+                "format": "jsonl",
+                "batch_size": 2,
+                "offset": 1,
+                "limit": 5,
+                "order_by": [{"column": "id", "direction": "desc"}],
+                "file_path": tmp_dir.join("limited.jsonl"),
+            }))
+            .await
+            .expect("limited export");
+        assert_eq!(limited["pagination"]["keys"], json!(["id"]));
+        let lines = std::fs::read_to_string(tmp_dir.join("limited.jsonl")).expect("export file");
+        let limited_ids: Vec<i64> = lines
+            .lines()
+            .map(|line| {
+                serde_json::from_str::<Value>(line).unwrap()["id"]
+                    .as_i64()
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(limited_ids, [90, 80, 70, 60, 50]);
+
+        query(format!("DROP TABLE \"{}\", \"{}\"", table, heap))
+            .await
+            .expect("drop tables");
+    }
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
@@ -0,0 +1,157 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::postgres::PostgresManager;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+fn manager() -> PostgresManager {
+    let security = Arc::new(Security::new().expect("security"));
+    PostgresManager::new(
+        Logger::new("test"),
+        Validation::new(),
+        Arc::new(ProfileService::new(security).expect("profile service")),
+        None,
+        None,
+    )
+}
+
+#[tokio::test]
+async fn named_params_bind_with_inferred_types() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    let manager = manager();
+
+    let err = manager
+        .handle_action(serde_json::json!({
+            "action": "query",
+            "connection_url": "postgres://app@127.0.0.1:1/app",
+            "sql": "select * from users where email = :email and created_at > :since",
+            "params": {"email": "a@b.c"},
+        }))
+        .await
+        .expect_err("missing named param");
+    assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+    assert!(err.message.contains(":since"), "{}", err.message);
+
+    // Set INFRA_TEST_POSTGRES_URLS (comma-separated) to round-trip against live servers.
+    let urls = std::env::var("INFRA_TEST_POSTGRES_URLS").unwrap_or_default();
+    for url in urls.split(',').map(str::trim).filter(|url| !url.is_empty()) {
+        let table = format!("infra_params_{}", uuid::Uuid::new_v4().simple());
+        let query = |sql: String, params: serde_json::Value, param_types: serde_json::Value| {
+            manager.handle_action(serde_json::json!({
+                "action": "query",
+                "connection_url": url,
+                "sql": sql,
+                "params": params,
+                "param_types": param_types,
+            }))
+        };
+        query(
+            format!(
+                "CREATE TABLE \"{}\" (id int4 PRIMARY KEY, amount numeric(12,3), created_at timestamptz, \
+                 tags text[], scores int4[], addr inet, meta jsonb, ref uuid, day date)",
+                table
+            ),
+            serde_json::Value::Null,
+            serde_json::Value::Null,
+        )
+        .await
+        .expect("create table");
+
+        let inserted = query(
+            format!(
+                "INSERT INTO \"{}\" VALUES (:id, :amount, :since, :tags, :scores, :addr, :meta, :ref, :day) \
+                 RETURNING id, amount::text AS amount, created_at, tags, host(addr) AS addr, meta",
+                table
+            ),
+            serde_json::json!({
+                "id": "7",
+                "amount": "12345.678",
+                "since": "2024-01-01T03:00:00+03:00",
+                "tags": ["a", "b:c"],
+                "scores": [1, null, 3],
+                "addr": "10.0.0.1",
+                "meta": {"k": ":not_a_param"},
+                "ref": "6f1c0c1e-2b4e-4b7b-9c55-0d4f8f4e2b11",
+                "day": "2024-02-29",
+            }),
+            serde_json::Value::Null,
+        )
+        .await
+        .expect("insert with named params");
+        let row = &inserted["rows"][0];
+        assert_eq!(row["id"], 7);
+        assert_eq!(row["amount"], "12345.678");
+        assert_eq!(row["created_at"], "2024-01-01T00:00:00+00:00");
+        assert_eq!(row["addr"], "10.0.0.1");
+        assert_eq!(row["meta"]["k"], ":not_a_param");
+
+        let selected = query(
+            format!(
+                "SELECT count(*) AS n FROM \"{}\" WHERE created_at >= :since AND 'x:y' <> :label \
+                 AND tags @> :tags AND day::text = :day AND ref = :ref AND amount > :min",
+                table
+            ),
+            serde_json::json!({
+                "since": "2024-01-01T00:00:00Z",
+                "label": "z",
+                "tags": ["b:c"],
+                "day": "2024-02-29",
+                "ref": "6f1c0c1e-2b4e-4b7b-9c55-0d4f8f4e2b11",
+                "min": 100.5,
+            }),
+            serde_json::Value::Null,
+        )
+        .await
+        .expect("select with named params");
+        assert_eq!(selected["rows"][0]["n"], 1);
+
+        let typed = query(
+            "SELECT :n AS n, :at AS at".to_string(),
+            serde_json::json!({"n": "41", "at": "2024-05-01T12:00:00Z"}),
+            serde_json::json!({"n": "int8", "at": "timestamptz"}),
+        )
+        .await
+        .expect("param_types override");
+        assert_eq!(typed["rows"][0]["n"], 41);
+        assert_eq!(typed["rows"][0]["at"], "2024-05-01T12:00:00+00:00");
+
+        let err = query(
+            format!("SELECT id FROM \"{}\" WHERE created_at > :since", table),
+            serde_json::json!({"since": "last tuesday"}),
+            serde_json::Value::Null,
+        )
+        .await
+        .expect_err("bad timestamp");
+        assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+        assert!(err.message.contains(":since"), "{}", err.message);
+        assert!(err.message.contains("timestamptz"), "{}", err.message);
+
+        query(
+            format!("DROP TABLE \"{}\"", table),
+            serde_json::Value::Null,
+            serde_json::Value::Null,
+        )
+        .await
+        .expect("drop table");
+    }
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
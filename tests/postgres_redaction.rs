@@ -0,0 +1,212 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::postgres::PostgresManager;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+fn manager() -> PostgresManager {
+    let security = Arc::new(Security::new().expect("security"));
+    let profile_service = Arc::new(ProfileService::new(security).expect("profile service"));
+    PostgresManager::new(
+        Logger::new("test"),
+        Validation::new(),
+        profile_service,
+        None,
+        None,
+    )
+}
+
+fn field_names(result: &Value) -> Vec<String> {
+    result["fields"]
+        .as_array()
+        .expect("fields")
+        .iter()
+        .map(|f| f["name"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn profile_redaction_covers_queries_selects_and_exports() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let prev_allow = std::env::var("INFRA_ALLOW_SECRET_EXPORT").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    std::env::remove_var("INFRA_ALLOW_SECRET_EXPORT");
+    let postgres = manager();
+
+    let err = postgres
+        .handle_action(json!({
+            "action": "query",
+            "connection_url": "postgres://app@127.0.0.1:1/app",
+            "sql": "SELECT 1",
+            "redaction": "off",
+        }))
+        .await
+        .expect_err("break-glass flag required");
+    assert_eq!(err.kind, ToolErrorKind::Denied);
+    assert_eq!(
+        err.message,
+        "redaction=off requires INFRA_ALLOW_SECRET_EXPORT=1"
+    );
+    let err = postgres
+        .handle_action(json!({
+            "action": "profile_upsert",
+            "profile_name": "replica",
+            "connection_url": "postgres://app@127.0.0.1:1/app",
+            "redaction": {"users": {"columns": ["email"], "mode": "blur"}},
+        }))
+        .await
+        .expect_err("invalid policy");
+    assert_eq!(
+        err.message,
+        "redaction.users.mode must be one of: mask, drop, hash (got blur)"
+    );
+
+    // Set INFRA_TEST_POSTGRES_URLS (comma-separated) to run against live servers.
+    let urls = std::env::var("INFRA_TEST_POSTGRES_URLS").unwrap_or_default();
+    for url in urls.split(',').map(str::trim).filter(|url| !url.is_empty()) {
+        let users = format!("infra_redact_users_{}", uuid::Uuid::new_v4().simple());
+        let tokens = format!("infra_redact_tokens_{}", uuid::Uuid::new_v4().simple());
+        let run = |sql: String| {
+            postgres.handle_action(json!({
+                "action": "query",
+                "connection_url": url,
+                "sql": sql,
+            }))
+        };
+        run(format!(
+            "CREATE TABLE {users} (id int, email text, ssn text)"
+        ))
+        .await
+        .expect("create users");
+        run(format!(
+            "INSERT INTO {users} VALUES (1, 'a@example.com', '111'), (2, 'b@example.com', '111')"
+        ))
+        .await
+        .expect("insert users");
+        run(format!("CREATE TABLE {tokens} (id int, token text)"))
+            .await
+            .expect("create tokens");
+        run(format!("INSERT INTO {tokens} VALUES (1, 't-1')"))
+            .await
+            .expect("insert tokens");
+        postgres
+            .handle_action(json!({
+                "action": "profile_upsert",
+                "profile_name": "replica",
+                "connection_url": url,
+                "redaction": {
+                    format!("public.{users}"): {"columns": ["email"], "mode": "mask"},
+                    tokens.clone(): {"columns": ["token"], "mode": "drop"},
+                },
+            }))
+            .await
+            .expect("profile upsert");
+        let query = |sql: String| {
+            postgres.handle_action(json!({
+                "action": "query",
+                "profile_name": "replica",
+                "sql": sql,
+            }))
+        };
+
+        let aliased = query(format!(
+            "SELECT id, email AS e, ssn FROM {users} ORDER BY id"
+        ))
+        .await
+        .expect("aliased select");
+        assert_eq!(
+            aliased["rows"][0],
+            json!({"id": 1, "e": "***", "ssn": "111"})
+        );
+        assert_eq!(aliased["redaction"], json!({"masked": ["e"]}));
+
+        let joined = query(format!(
+            "SELECT u.id, t.token, u.email FROM {users} u JOIN {tokens} t ON t.id = u.id"
+        ))
+        .await
+        .expect("joined select");
+        assert_eq!(joined["rows"], json!([{"id": 1, "email": "***"}]));
+        assert_eq!(field_names(&joined), ["id", "email"]);
+        assert_eq!(joined["redaction"]["dropped"], json!(["token"]));
+
+        // Computed columns are only caught when their result name is a listed column.
+        let computed = query(format!(
+            "SELECT upper(email) AS email, upper(email) AS contact FROM {users} WHERE id = 1"
+        ))
+        .await
+        .expect("computed select");
+        assert_eq!(
+            computed["rows"][0],
+            json!({"email": "***", "contact": "A@EXAMPLE.COM"})
+        );
+
+        let selected = postgres
+            .handle_action(json!({
+                "action": "select",
+                "profile_name": "replica",
+                "table": users,
+                "columns": ["id", "email"],
+                "order_by": "id",
+            }))
+            .await
+            .expect("select action");
+        assert_eq!(
+            selected["result"]["rows"][1],
+            json!({"id": 2, "email": "***"})
+        );
+
+        let export_path = tmp_dir.join("users.jsonl");
+        postgres
+            .handle_action(json!({
+                "action": "export",
+                "profile_name": "replica",
+                "table": users,
+                "format": "jsonl",
+                "file_path": export_path.display().to_string(),
+            }))
+            .await
+            .expect("export");
+        let exported = std::fs::read_to_string(&export_path).expect("read export");
+        assert!(!exported.contains("@example.com"), "{}", exported);
+        assert_eq!(exported.matches("\"***\"").count(), 2);
+
+        std::env::set_var("INFRA_ALLOW_SECRET_EXPORT", "1");
+        let raw = postgres
+            .handle_action(json!({
+                "action": "query",
+                "profile_name": "replica",
+                "sql": format!("SELECT email FROM {users} WHERE id = 1"),
+                "redaction": "off",
+            }))
+            .await
+            .expect("break-glass query");
+        std::env::remove_var("INFRA_ALLOW_SECRET_EXPORT");
+        assert_eq!(raw["rows"][0]["email"], "a@example.com");
+        assert!(raw.get("redaction").is_none());
+
+        run(format!("DROP TABLE {users}, {tokens}"))
+            .await
+            .expect("drop tables");
+    }
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    restore_env("INFRA_ALLOW_SECRET_EXPORT", prev_allow);
+    let _ = std::fs::remove_dir_all(&tmp_dir);
+}
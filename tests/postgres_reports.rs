@@ -0,0 +1,82 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::postgres::PostgresManager;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+fn manager() -> PostgresManager {
+    let security = Arc::new(Security::new().expect("security"));
+    PostgresManager::new(
+        Logger::new("test"),
+        Validation::new(),
+        Arc::new(ProfileService::new(security).expect("profile service")),
+        None,
+        None,
+    )
+}
+
+#[tokio::test]
+async fn database_info_reports_validate_before_connecting_and_report_live_state() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    let manager = manager();
+
+    let err = manager
+        .handle_action(serde_json::json!({
+            "action": "database_info",
+            "connection_url": "postgres://app@127.0.0.1:1/app",
+            "reports": ["activity", "lock"],
+        }))
+        .await
+        .expect_err("unknown report");
+    assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+    assert!(err.hint.unwrap_or_default().contains("replication"));
+
+    // Set INFRA_TEST_POSTGRES_URLS (comma-separated) to run the reports against live servers,
+    // e.g. one PG12 and one PG16 instance.
+    let urls = std::env::var("INFRA_TEST_POSTGRES_URLS").unwrap_or_default();
+    for url in urls.split(',').map(str::trim).filter(|url| !url.is_empty()) {
+        let info = manager
+            .handle_action(serde_json::json!({
+                "action": "database_info",
+                "connection_url": url,
+                "reports": "all",
+                "limit": 5,
+                "min_duration_ms": 0,
+            }))
+            .await
+            .unwrap_or_else(|err| panic!("database_info against {}: {}", url, err.message));
+        assert!(info["server"]["version_num"].as_i64().unwrap_or(0) >= 120_000);
+        assert_eq!(info["include_queries"], false);
+        let activity = &info["reports"]["activity"];
+        assert!(activity["count"].as_u64().unwrap_or(99) <= 5);
+        assert!(activity["rows"]
+            .as_array()
+            .expect("activity rows")
+            .iter()
+            .all(|row| row.get("query").is_none()));
+        assert!(info["reports"]["locks"]["rows"].is_array());
+        let role = info["reports"]["replication"]["role"]
+            .as_str()
+            .unwrap_or("");
+        assert!(role == "primary" || role == "replica");
+    }
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+}
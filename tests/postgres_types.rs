@@ -0,0 +1,195 @@
+use infra::managers::postgres::PostgresManager;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use serde_json::json;
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+fn manager() -> PostgresManager {
+    let security = Arc::new(Security::new().expect("security"));
+    let profile_service = Arc::new(ProfileService::new(security).expect("profile service"));
+    PostgresManager::new(
+        Logger::new("test"),
+        Validation::new(),
+        profile_service,
+        None,
+        None,
+    )
+}
+
+#[tokio::test]
+async fn query_rows_serialize_special_types_stably() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    let postgres = manager();
+
+    let err = postgres
+        .handle_action(json!({
+            "action": "query",
+            "connection_url": "postgres://app@127.0.0.1:1/app",
+            "sql": "SELECT 1",
+            "numeric": "decimal",
+        }))
+        .await
+        .expect_err("unknown numeric mode");
+    assert_eq!(
+        err.message,
+        "numeric must be one of: string, float (got decimal)"
+    );
+
+    // Set INFRA_TEST_POSTGRES_URLS (comma-separated) to run against live servers.
+    let urls = std::env::var("INFRA_TEST_POSTGRES_URLS").unwrap_or_default();
+    for url in urls.split(',').map(str::trim).filter(|url| !url.is_empty()) {
+        let suffix = uuid::Uuid::new_v4().simple().to_string();
+        let table = format!("infra_types_{}", suffix);
+        let mood = format!("infra_mood_{}", suffix);
+        let run = |sql: String| {
+            postgres.handle_action(json!({
+                "action": "query",
+                "connection_url": url,
+                "sql": sql,
+            }))
+        };
+        run(format!("CREATE TYPE {mood} AS ENUM ('sad', 'happy')"))
+            .await
+            .expect("create enum");
+        run(format!(
+            "CREATE TABLE {table} (id int, b bytea, u uuid[], n numeric, m {mood}, dr daterange, \
+             j jsonb[], iv interval, addr inet, ir int4range, grid int[], f4 real, pt point)"
+        ))
+        .await
+        .expect("create table");
+        run(format!(
+            "INSERT INTO {table} VALUES (1, '\\xdeadbeef', \
+             ARRAY['00000000-0000-0000-0000-000000000001', NULL]::uuid[], \
+             12345678901234567890.1230, 'happy', '[2024-01-01,2024-02-01)', \
+             ARRAY['{{\"a\": 1}}', '[]']::jsonb[], '1 year 2 months 3 days 04:05:06.5', \
+             '10.0.0.1', 'empty', '{{{{1,2}},{{3,4}}}}', 0.1, '(1,2)'), \
+             (2, NULL, '{{}}', NULL, NULL, '(,2024-01-01]', NULL, '-1 day', NULL, '[1,5)', NULL, NULL, NULL)"
+        ))
+        .await
+        .expect("insert rows");
+
+        let result = run(format!("SELECT * FROM {table} ORDER BY id"))
+            .await
+            .expect("select all");
+        assert_eq!(
+            result["rows"],
+            json!([
+                {
+                    "id": 1,
+                    "b": {"base64": "3q2+7w==", "bytes": 4},
+                    "u": ["00000000-0000-0000-0000-000000000001", null],
+                    "n": "12345678901234567890.1230",
+                    "m": "happy",
+                    "dr": {"lower": "2024-01-01", "upper": "2024-02-01", "bounds": "[)"},
+                    "j": [{"a": 1}, []],
+                    "iv": "P1Y2M3DT4H5M6.5S",
+                    "addr": "10.0.0.1/32",
+                    "ir": {"empty": true},
+                    "grid": [[1, 2], [3, 4]],
+                    "f4": 0.1,
+                    "pt": "(1,2)",
+                },
+                {
+                    "id": 2,
+                    "b": null,
+                    "u": [],
+                    "n": null,
+                    "m": null,
+                    "dr": {"lower": null, "upper": "2024-01-02", "bounds": "()"},
+                    "j": null,
+                    "iv": "P-1D",
+                    "addr": null,
+                    "ir": {"lower": 1, "upper": 5, "bounds": "[)"},
+                    "grid": null,
+                    "f4": null,
+                    "pt": null,
+                },
+            ])
+        );
+
+        let labels: Vec<(String, String)> = result["fields"]
+            .as_array()
+            .expect("fields")
+            .iter()
+            .map(|f| {
+                (
+                    f["name"].as_str().unwrap().to_string(),
+                    f["dataType"].as_str().unwrap().to_string(),
+                )
+            })
+            .collect();
+        let catalog = postgres
+            .handle_action(json!({
+                "action": "catalog_columns",
+                "connection_url": url,
+                "table": table,
+            }))
+            .await
+            .expect("catalog columns");
+        let catalog_labels: Vec<(String, String)> = catalog["columns"]
+            .as_array()
+            .expect("columns")
+            .iter()
+            .map(|c| {
+                (
+                    c["column_name"].as_str().unwrap().to_string(),
+                    c["type"].as_str().unwrap().to_string(),
+                )
+            })
+            .collect();
+        assert_eq!(labels, catalog_labels);
+        assert_eq!(labels[2].1, "uuid[]");
+        assert_eq!(labels[4].1, mood);
+        assert_eq!(labels[8].1, "inet");
+
+        let floats = postgres
+            .handle_action(json!({
+                "action": "query",
+                "connection_url": url,
+                "sql": format!("SELECT n, n::text AS raw FROM {table} WHERE id = 1"),
+                "numeric": "float",
+            }))
+            .await
+            .expect("float numeric");
+        assert_eq!(
+            floats["rows"][0],
+            json!({"n": 12345678901234567890.123, "raw": "12345678901234567890.1230"})
+        );
+
+        // Writes returning an undecodable column go through the same text cast.
+        let returned = run(format!(
+            "INSERT INTO {table} (id, addr) VALUES (3, '192.168.0.0/16') RETURNING id, addr;"
+        ))
+        .await
+        .expect("insert returning");
+        assert_eq!(
+            returned["rows"],
+            json!([{"id": 3, "addr": "192.168.0.0/16"}])
+        );
+
+        run(format!("DROP TABLE {table}"))
+            .await
+            .expect("drop table");
+        run(format!("DROP TYPE {mood}")).await.expect("drop enum");
+    }
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    let _ = std::fs::remove_dir_all(&tmp_dir);
+}
@@ -0,0 +1,142 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::postgres::PostgresManager;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+fn manager() -> PostgresManager {
+    let security = Arc::new(Security::new().expect("security"));
+    PostgresManager::new(
+        Logger::new("test"),
+        Validation::new(),
+        Arc::new(ProfileService::new(security).expect("profile service")),
+        None,
+        None,
+    )
+}
+
+#[tokio::test]
+async fn returning_and_expect_guard_writes_with_quoted_identifiers() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    let manager = manager();
+
+    let err = manager
+        .handle_action(serde_json::json!({
+            "action": "update",
+            "connection_url": "postgres://app@127.0.0.1:1/app",
+            "table": "items",
+            "data": {"name": "x"},
+            "expect": {},
+        }))
+        .await
+        .expect_err("empty expect");
+    assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+    let err = manager
+        .handle_action(serde_json::json!({
+            "action": "insert",
+            "connection_url": "postgres://app@127.0.0.1:1/app",
+            "table": "items",
+            "data": {"name": "x"},
+            "returning": [{"column": "id"}],
+        }))
+        .await
+        .expect_err("returning must list names");
+    assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+
+    // Set INFRA_TEST_POSTGRES_URLS (comma-separated) to run the writes against live servers.
+    let urls = std::env::var("INFRA_TEST_POSTGRES_URLS").unwrap_or_default();
+    for url in urls.split(',').map(str::trim).filter(|url| !url.is_empty()) {
+        let table = format!("infra_writes_{}", uuid::Uuid::new_v4().simple());
+        let call = |args: serde_json::Value| {
+            let mut args = args;
+            args["connection_url"] = serde_json::json!(url);
+            args["table"] = serde_json::json!(table);
+            manager.handle_action(args)
+        };
+        manager
+            .handle_action(serde_json::json!({
+                "action": "query",
+                "connection_url": url,
+                "sql": format!(
+                    "CREATE TABLE \"{}\" (id bigserial PRIMARY KEY, \"order\" bigint NOT NULL, \"user\" text, version bigint NOT NULL DEFAULT 1)",
+                    table
+                ),
+            }))
+            .await
+            .expect("create table");
+
+        let inserted = call(serde_json::json!({
+            "action": "insert",
+            "data": {"order": 1, "user": "ann"},
+            "returning": ["id", "order", "user"],
+        }))
+        .await
+        .expect("insert returning");
+        assert_eq!(inserted["affected"], 1);
+        assert_eq!(inserted["rows"][0]["order"], 1);
+        assert_eq!(inserted["rows"][0]["user"], "ann");
+        let id = inserted["rows"][0]["id"].clone();
+
+        let updated = call(serde_json::json!({
+            "action": "update",
+            "filters": {"id": id},
+            "expect": {"version": 1, "user": "ann"},
+            "data": {"order": 2, "version": 2},
+            "returning": "*",
+        }))
+        .await
+        .expect("guarded update");
+        assert_eq!(updated["success"], true);
+        assert_eq!(updated["rows"][0]["version"], 2);
+
+        let stale = call(serde_json::json!({
+            "action": "update",
+            "filters": {"id": id},
+            "expect": {"version": 1, "user": "ann"},
+            "data": {"order": 3, "version": 2},
+        }))
+        .await
+        .expect("stale update");
+        assert_eq!(stale["success"], false);
+        assert_eq!(stale["conflict"], true);
+        assert_eq!(stale["affected"], 0);
+
+        let deleted = call(serde_json::json!({
+            "action": "delete",
+            "filters": {"id": id},
+            "returning": ["order"],
+        }))
+        .await
+        .expect("delete returning");
+        assert_eq!(deleted["rows"][0]["order"], 2);
+
+        manager
+            .handle_action(serde_json::json!({
+                "action": "query",
+                "connection_url": url,
+                "sql": format!("DROP TABLE \"{}\"", table),
+            }))
+            .await
+            .expect("drop table");
+    }
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
@@ -0,0 +1,179 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::preset::PresetManager;
+use infra::services::alias::AliasService;
+use infra::services::logger::Logger;
+use infra::services::preset::PresetService;
+use infra::services::state::StateService;
+use infra::services::tool_executor::{ToolExecutor, ToolHandler};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+#[derive(Clone)]
+struct EchoHandler;
+
+#[async_trait::async_trait]
+impl ToolHandler for EchoHandler {
+    async fn handle(&self, args: Value) -> Result<Value, infra::errors::ToolError> {
+        Ok(serde_json::json!({ "success": true, "args": args }))
+    }
+}
+
+#[tokio::test]
+async fn preset_extends_layers_parents_and_rejects_cycles() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+
+    let service = PresetService::new().expect("preset service");
+    service
+        .set_preset(
+            "base",
+            &serde_json::json!({ "data": { "timeout_ms": 1000, "env": { "A": "1", "B": "1" } } }),
+        )
+        .expect("base");
+    service
+        .set_preset(
+            "web",
+            &serde_json::json!({
+                "extends": ["base"],
+                "data": { "profile_name": "web", "env": { "B": "2" } }
+            }),
+        )
+        .expect("web");
+
+    let data = service.resolve_preset_data("web").expect("resolve web");
+    assert_eq!(
+        data,
+        serde_json::json!({
+            "timeout_ms": 1000,
+            "profile_name": "web",
+            "env": { "A": "1", "B": "2" }
+        })
+    );
+
+    let err = service
+        .set_preset(
+            "base",
+            &serde_json::json!({ "extends": ["web"], "data": {} }),
+        )
+        .expect_err("cycle should be rejected");
+    assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+    assert!(err.message.contains("base -> web -> base"));
+
+    let err = service
+        .set_preset(
+            "orphan",
+            &serde_json::json!({ "extends": ["missing"], "data": {} }),
+        )
+        .expect_err("unknown parent should be rejected");
+    assert_eq!(err.kind, ToolErrorKind::NotFound);
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+}
+
+#[tokio::test]
+async fn default_presets_apply_below_alias_and_call_args() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+
+    let preset_service = Arc::new(PresetService::new().expect("preset service"));
+    preset_service
+        .set_preset(
+            "workstation_ssh",
+            &serde_json::json!({
+                "default_for": [{ "tool": "ssh", "action": "exec" }],
+                "data": { "timeout_ms": 5000, "profile_name": "default-box", "cwd": "/srv" }
+            }),
+        )
+        .expect("default preset");
+    let alias_service = Arc::new(AliasService::new().expect("alias service"));
+    alias_service
+        .set_alias(
+            "uptime_web",
+            &serde_json::json!({
+                "tool": "ssh",
+                "args": { "action": "exec", "command": "uptime", "profile_name": "web" }
+            }),
+        )
+        .expect("alias");
+
+    let mut handlers: HashMap<String, Arc<dyn ToolHandler>> = HashMap::new();
+    handlers.insert("ssh".to_string(), Arc::new(EchoHandler));
+    let executor = ToolExecutor::new(
+        Logger::new("test"),
+        Arc::new(StateService::new().expect("state")),
+        Some(alias_service.clone()),
+        None,
+        handlers,
+        HashMap::new(),
+    )
+    .with_preset_service(preset_service.clone());
+
+    let result = executor
+        .execute(
+            "uptime_web",
+            serde_json::json!({ "cwd": "/tmp", "apply": true }),
+        )
+        .await
+        .expect("alias call");
+    let args = &result["result"]["args"];
+    assert_eq!(args["timeout_ms"], 5000);
+    assert_eq!(args["profile_name"], "web");
+    assert_eq!(args["cwd"], "/tmp");
+    assert_eq!(
+        result["meta"]["presets"],
+        serde_json::json!(["workstation_ssh"])
+    );
+
+    let result = executor
+        .execute(
+            "ssh",
+            serde_json::json!({
+                "action": "exec",
+                "command": "id",
+                "preset": false,
+                "apply": true
+            }),
+        )
+        .await
+        .expect("opt-out call");
+    let args = &result["result"]["args"];
+    assert!(args.get("timeout_ms").is_none());
+    assert!(result["meta"].get("presets").is_none());
+
+    let manager = PresetManager::new(Logger::new("test"), preset_service, Some(alias_service));
+    let resolved = manager
+        .handle_action(serde_json::json!({
+            "action": "preset_resolve",
+            "tool": "uptime_web",
+            "args": { "timeout_ms": 100 }
+        }))
+        .await
+        .expect("preset_resolve");
+    assert_eq!(resolved["tool"], "ssh");
+    assert_eq!(resolved["invoked_as"], "uptime_web");
+    assert_eq!(resolved["presets"], serde_json::json!(["workstation_ssh"]));
+    assert_eq!(resolved["args"]["timeout_ms"], 100);
+    assert_eq!(resolved["args"]["profile_name"], "web");
+    assert_eq!(resolved["args"]["cwd"], "/srv");
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+}
@@ -0,0 +1,251 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::api::ApiManager;
+use infra::managers::postgres::PostgresManager;
+use infra::managers::ssh::SshManager;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+fn stored(profiles: &ProfileService, name: &str) -> (Value, Vec<String>) {
+    let profile = profiles.get_profile(name, None).expect("stored profile");
+    let mut secrets: Vec<String> = profile
+        .get("secrets")
+        .and_then(|v| v.as_object())
+        .map(|map| map.keys().cloned().collect())
+        .unwrap_or_default();
+    secrets.sort();
+    (profile["data"].clone(), secrets)
+}
+
+#[tokio::test]
+async fn profile_upserts_merge_replace_and_unset_fields() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+
+    let logger = Logger::new("test");
+    let security = Arc::new(Security::new().expect("security"));
+    let profiles = Arc::new(ProfileService::new(security.clone()).expect("profile service"));
+    let ssh = SshManager::new(
+        logger.clone(),
+        security,
+        Validation::new(),
+        profiles.clone(),
+        None,
+        None,
+        None,
+    );
+    let api = ApiManager::new(
+        logger.clone(),
+        Validation::new(),
+        profiles.clone(),
+        None,
+        None,
+        None,
+    );
+    let postgres = PostgresManager::new(logger, Validation::new(), profiles.clone(), None, None);
+
+    // A host that is down can still be stored, and partial updates keep the rest.
+    let created = ssh
+        .handle_action(json!({
+            "action": "profile_upsert",
+            "profile_name": "web",
+            "connection": {"host": "127.0.0.1", "port": 1, "username": "deploy", "password": "pw-1"},
+            "skip_test": true,
+        }))
+        .await
+        .expect("create without test");
+    assert_eq!(created["tested"], false);
+    ssh.handle_action(json!({
+        "action": "profile_upsert",
+        "profile_name": "web",
+        "connection": {"description": "edge node"},
+        "skip_test": true,
+    }))
+    .await
+    .expect("patch description");
+    let (data, secrets) = stored(&profiles, "web");
+    assert_eq!(
+        data,
+        json!({"host": "127.0.0.1", "port": 1, "username": "deploy", "description": "edge node"})
+    );
+    assert_eq!(secrets, ["password"]);
+    assert_eq!(
+        profiles.get_profile("web", None).unwrap()["secrets"]["password"],
+        "pw-1"
+    );
+
+    // Without skip_test the connectivity check still runs and nothing is written on failure.
+    ssh.handle_action(json!({
+        "action": "profile_upsert",
+        "profile_name": "web",
+        "connection": {"username": "root"},
+    }))
+    .await
+    .expect_err("unreachable host");
+    assert_eq!(stored(&profiles, "web").0["username"], "deploy");
+
+    let switched = ssh
+        .handle_action(json!({
+            "action": "profile_upsert",
+            "profile_name": "web",
+            "connection": {"private_key": "KEY"},
+            "unset": ["password"],
+            "skip_test": true,
+        }))
+        .await
+        .expect("swap auth");
+    assert_eq!(switched["profile"]["auth"], "private_key");
+    assert_eq!(stored(&profiles, "web").1, ["private_key"]);
+
+    ssh.handle_action(json!({
+        "action": "profile_upsert",
+        "profile_name": "web",
+        "connection": {"host": "10.0.0.9", "username": "ops", "password": "pw-2"},
+        "replace": true,
+        "skip_test": true,
+    }))
+    .await
+    .expect("replace");
+    let (data, secrets) = stored(&profiles, "web");
+    assert_eq!(data, json!({"host": "10.0.0.9", "username": "ops"}));
+    assert_eq!(secrets, ["password"]);
+
+    let err = ssh
+        .handle_action(json!({
+            "action": "profile_upsert",
+            "profile_name": "web",
+            "merge": true,
+            "replace": true,
+        }))
+        .await
+        .expect_err("conflicting flags");
+    assert_eq!(err.message, "merge and replace cannot both be true");
+    let err = ssh
+        .handle_action(json!({
+            "action": "profile_upsert",
+            "profile_name": "web",
+            "unset": "password",
+        }))
+        .await
+        .expect_err("unset must be a list");
+    assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+
+    // The api manager shares the same semantics.
+    api.handle_action(json!({
+        "action": "profile_upsert",
+        "profile_name": "billing",
+        "base_url": "https://billing.example.com",
+        "auth": "token-1",
+        "timeout_ms": 5000,
+    }))
+    .await
+    .expect("api create");
+    let patched = api
+        .handle_action(json!({
+            "action": "profile_upsert",
+            "profile_name": "billing",
+            "headers": {"X-Team": "payments"},
+        }))
+        .await
+        .expect("api patch");
+    assert_eq!(patched["tested"], false);
+    let (data, secrets) = stored(&profiles, "billing");
+    assert_eq!(data["base_url"], "https://billing.example.com");
+    assert_eq!(data["headers"], json!({"X-Team": "payments"}));
+    assert_eq!(secrets, ["auth_token"]);
+    api.handle_action(json!({
+        "action": "profile_upsert",
+        "profile_name": "billing",
+        "unset": ["auth_token", "auth", "timeout_ms"],
+    }))
+    .await
+    .expect("api unset");
+    let (data, secrets) = stored(&profiles, "billing");
+    assert!(secrets.is_empty());
+    assert_eq!(
+        data,
+        json!({"base_url": "https://billing.example.com", "headers": {"X-Team": "payments"}})
+    );
+    api.handle_action(json!({
+        "action": "profile_upsert",
+        "profile_name": "billing",
+        "base_url": "https://billing-v2.example.com",
+        "merge": false,
+    }))
+    .await
+    .expect("api replace");
+    assert_eq!(
+        stored(&profiles, "billing").0,
+        json!({"base_url": "https://billing-v2.example.com"})
+    );
+
+    // A profile of another type is only overwritten on explicit replace.
+    let err = postgres
+        .handle_action(json!({
+            "action": "profile_upsert",
+            "profile_name": "web",
+            "connection_url": "postgres://app@127.0.0.1:1/app",
+            "skip_test": true,
+        }))
+        .await
+        .expect_err("type conflict");
+    assert_eq!(err.kind, ToolErrorKind::Conflict);
+    let replaced = postgres
+        .handle_action(json!({
+            "action": "profile_upsert",
+            "profile_name": "web",
+            "connection_url": "postgres://app@127.0.0.1:1/app",
+            "replace": true,
+            "skip_test": true,
+        }))
+        .await
+        .expect("replace with another type");
+    assert_eq!(replaced["tested"], false);
+    assert_eq!(replaced["profile"]["type"], "postgresql");
+
+    // Set INFRA_TEST_POSTGRES_URLS (comma-separated) to run against live servers.
+    let urls = std::env::var("INFRA_TEST_POSTGRES_URLS").unwrap_or_default();
+    for url in urls.split(',').map(str::trim).filter(|url| !url.is_empty()) {
+        let first = postgres
+            .handle_action(json!({
+                "action": "profile_upsert",
+                "profile_name": "replica",
+                "connection_url": url,
+            }))
+            .await
+            .expect("tested create");
+        assert_eq!(first["tested"], true);
+        // The connection test runs against the merged profile, not just the patch.
+        postgres
+            .handle_action(json!({
+                "action": "profile_upsert",
+                "profile_name": "replica",
+                "options": {"application_name": "infra-tests"},
+            }))
+            .await
+            .expect("patch options");
+        let (data, _) = stored(&profiles, "replica");
+        assert_eq!(data["connection_url"], url);
+        assert_eq!(data["options"], json!({"application_name": "infra-tests"}));
+    }
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    let _ = std::fs::remove_dir_all(&tmp_dir);
+}
@@ -0,0 +1,171 @@
+use infra::errors::{ToolError, ToolErrorKind};
+use infra::managers::project::ProjectManager;
+use infra::services::audit::AuditService;
+use infra::services::logger::Logger;
+use infra::services::project::ProjectService;
+use infra::services::project_resolver::ProjectResolver;
+use infra::services::state::StateService;
+use infra::services::tool_executor::{ToolExecutor, ToolHandler};
+use infra::services::validation::Validation;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+mod common;
+use common::ENV_LOCK;
+
+#[derive(Clone, Default)]
+struct RecordingHandler {
+    calls: Arc<Mutex<Vec<Value>>>,
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for RecordingHandler {
+    async fn handle(&self, args: Value) -> Result<Value, ToolError> {
+        self.calls.lock().unwrap().push(args.clone());
+        if args["project"] == "beta" {
+            return Err(ToolError::internal("beta is down"));
+        }
+        Ok(serde_json::json!({"success": true, "project": args["project"]}))
+    }
+}
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+#[tokio::test]
+async fn project_foreach_fans_out_with_child_spans_and_isolated_failures() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let prev_audit = std::env::var("INFRA_AUDIT_PATH").ok();
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    std::env::set_var("INFRA_AUDIT_PATH", tmp_dir.join("audit.jsonl"));
+
+    let logger = Logger::new("test");
+    let validation = Validation::new();
+    let state_service = Arc::new(StateService::new().expect("state"));
+    let project_service = Arc::new(ProjectService::new().expect("project"));
+    for name in ["alpha", "beta", "gamma"] {
+        project_service
+            .set_project(
+                name,
+                &serde_json::json!({
+                    "description": format!("{} shop", name),
+                    "default_target": "staging",
+                    "targets": {"staging": {}, "prod": {}},
+                }),
+            )
+            .expect("project");
+    }
+    let audit_service = Arc::new(AuditService::new(logger.clone()));
+    let handler = RecordingHandler::default();
+    let mut handlers: HashMap<String, Arc<dyn ToolHandler>> = HashMap::new();
+    handlers.insert("dummy".to_string(), Arc::new(handler.clone()));
+    let tool_executor = Arc::new(ToolExecutor::new(
+        logger.clone(),
+        state_service.clone(),
+        None,
+        Some(audit_service.clone()),
+        handlers,
+        HashMap::new(),
+    ));
+    let resolver = Arc::new(ProjectResolver::new(
+        validation.clone(),
+        project_service.clone(),
+        Some(state_service.clone()),
+    ));
+    let manager = ProjectManager::new(
+        logger,
+        validation,
+        project_service,
+        state_service,
+        Some(resolver),
+    );
+    manager.set_tool_executor(tool_executor.clone());
+
+    let err = manager
+        .handle_action(serde_json::json!({
+            "action": "project_foreach",
+            "call": {"tool": "dummy", "action": "ping"},
+        }))
+        .await
+        .expect_err("projects required");
+    assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+
+    let result = manager
+        .handle_action(serde_json::json!({
+            "action": "project_foreach",
+            "projects": ["alpha", "beta", "gamma", "missing"],
+            "target": "prod",
+            "call": {"tool": "dummy", "action": "ping", "args": {"project": "ignored", "n": 1}},
+            "concurrency": 2,
+            "trace_id": "trace-fanout",
+            "span_id": "span-root",
+        }))
+        .await
+        .expect("foreach");
+    assert_eq!(result["success"], false);
+    assert_eq!(result["succeeded"], 2);
+    assert_eq!(result["failed"], 2);
+    let results = result["results"].as_array().expect("results");
+    let statuses: Vec<&str> = results
+        .iter()
+        .map(|item| item["status"].as_str().unwrap_or(""))
+        .collect();
+    assert_eq!(statuses, vec!["ok", "error", "ok", "error"]);
+    assert_eq!(results[0]["target"], "prod");
+    assert_eq!(results[0]["result"]["project"], "alpha");
+    assert_eq!(results[1]["stage"], "call");
+    assert_eq!(results[3]["stage"], "resolve");
+    assert_eq!(results[3]["error"]["kind"], "not_found");
+
+    let calls = handler.calls.lock().unwrap().clone();
+    assert_eq!(calls.len(), 3);
+    for call in &calls {
+        assert_eq!(call["trace_id"], "trace-fanout");
+        assert_eq!(call["parent_span_id"], "span-root");
+        assert_eq!(call["target"], "prod");
+        assert_eq!(call["n"], 1);
+    }
+    let audited = audit_service
+        .read_entries(
+            10,
+            0,
+            false,
+            &serde_json::json!({"trace_id": "trace-fanout"}),
+        )
+        .expect("audit");
+    let spans: Vec<&Value> = audited["entries"]
+        .as_array()
+        .expect("entries")
+        .iter()
+        .filter(|entry| entry["parent_span_id"] == "span-root")
+        .collect();
+    assert_eq!(spans.len(), 3);
+
+    let stopped = manager
+        .handle_action(serde_json::json!({
+            "action": "project_foreach",
+            "project_filter": {"query": "shop"},
+            "call": {"tool": "dummy", "action": "ping"},
+            "concurrency": 1,
+            "stop_on_error": true,
+        }))
+        .await
+        .expect("foreach stop_on_error");
+    assert_eq!(stopped["total"], 3);
+    assert_eq!(stopped["skipped"], 1);
+    assert_eq!(stopped["results"][0]["target"], "staging");
+    assert_eq!(stopped["results"][2]["status"], "skipped");
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    restore_env("INFRA_AUDIT_PATH", prev_audit);
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
@@ -0,0 +1,196 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::project::ProjectManager;
+use infra::services::cache::{CacheBackend, CacheService};
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::project::ProjectService;
+use infra::services::project_resolver::ProjectResolver;
+use infra::services::security::Security;
+use infra::services::state::StateService;
+use infra::services::validation::Validation;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+fn counts(stats: &Value) -> (u64, u64, u64) {
+    let resolver = &stats["resolver"];
+    (
+        resolver["hits"].as_u64().unwrap(),
+        resolver["misses"].as_u64().unwrap(),
+        resolver["stale"].as_u64().unwrap(),
+    )
+}
+
+#[tokio::test]
+async fn resolver_cache_follows_project_and_profile_writes() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+
+    let state_service = Arc::new(StateService::new().expect("state"));
+    let project_service = Arc::new(ProjectService::new().expect("project"));
+    let profiles =
+        ProfileService::new(Arc::new(Security::new().expect("security"))).expect("profile service");
+    let cache = Arc::new(
+        CacheService::with_backend(
+            Logger::new("test"),
+            CacheBackend::Memory,
+            tmp_dir.join("cache"),
+        )
+        .namespaced("project_resolver")
+        .expect("resolver namespace"),
+    );
+    let resolver = Arc::new(
+        ProjectResolver::new(
+            Validation::new(),
+            project_service.clone(),
+            Some(state_service.clone()),
+        )
+        .with_cache(cache.clone()),
+    );
+    let projects = ProjectManager::new(
+        Logger::new("test"),
+        Validation::new(),
+        project_service.clone(),
+        state_service,
+        Some(resolver.clone()),
+    );
+    let call = |args: Value| projects.handle_action(args);
+    let stats = || call(json!({"action": "resolver_stats"}));
+    let resolve = |target: &'static str| {
+        let resolver = resolver.clone();
+        async move {
+            resolver
+                .resolve_context(&json!({"project": "shop", "target": target}))
+                .await
+        }
+    };
+
+    call(json!({
+        "action": "project_upsert",
+        "name": "shop",
+        "project": {"targets": {"prod": {"ssh_profile": "web-1"}, "stage": {"ssh_profile": "web-2"}}},
+    }))
+    .await
+    .expect("upsert shop");
+
+    let first = resolve("prod").await.expect("resolve").expect("context");
+    let second = resolve("prod").await.expect("resolve").expect("context");
+    assert_eq!(first, second);
+    assert_eq!(second["target"]["ssh_profile"], "web-1");
+    resolve("stage").await.expect("resolve stage");
+    let after_reads = stats().await.expect("stats");
+    assert_eq!(counts(&after_reads), (1, 2, 0));
+    assert_eq!(after_reads["resolver"]["entries"], 2);
+    assert_eq!(after_reads["resolver"]["ttl_ms"], 5000);
+    assert_eq!(
+        cache.stats()["namespaces"]["project_resolver"]["storage"],
+        "memory"
+    );
+
+    call(json!({
+        "action": "project_upsert",
+        "name": "shop",
+        "project": {"targets": {"prod": {"ssh_profile": "web-3"}}},
+    }))
+    .await
+    .expect("rebind prod");
+    let rebound = resolve("prod").await.expect("resolve").expect("context");
+    assert_eq!(rebound["target"]["ssh_profile"], "web-3");
+    assert_eq!(counts(&stats().await.expect("stats")), (1, 3, 1));
+
+    // A profile write drops entries too: targets name profiles the caller resolves next.
+    profiles
+        .set_profile("web-3", &json!({"type": "ssh", "data": {"host": "web-3"}}))
+        .expect("profile upsert");
+    resolve("prod").await.expect("resolve after profile write");
+    assert_eq!(counts(&stats().await.expect("stats")), (1, 4, 2));
+
+    // The active project is read per call, so switching it never serves the old project.
+    call(json!({
+        "action": "project_upsert",
+        "name": "blog",
+        "project": {"targets": {"prod": {"ssh_profile": "blog-1"}}},
+    }))
+    .await
+    .expect("upsert blog");
+    for (name, profile) in [("shop", "web-3"), ("blog", "blog-1")] {
+        call(json!({"action": "project_use", "name": name, "scope": "session"}))
+            .await
+            .expect("project_use");
+        let active = resolver
+            .resolve_context(&json!({"target": "prod"}))
+            .await
+            .expect("resolve active")
+            .expect("context");
+        assert_eq!(active["target"]["ssh_profile"], profile);
+    }
+
+    call(json!({"action": "project_delete", "name": "shop"}))
+        .await
+        .expect("delete shop");
+    let err = resolve("prod").await.expect_err("deleted project");
+    assert_eq!(err.kind, ToolErrorKind::NotFound);
+
+    // Concurrent writers and readers: once the writes settle, the next resolve sees the last one.
+    project_service
+        .set_project("shop", &json!({"targets": {"prod": {"ssh_profile": "v0"}}}))
+        .expect("recreate shop");
+    let mut tasks = Vec::new();
+    for round in 1..=8 {
+        let service = project_service.clone();
+        tasks.push(tokio::task::spawn_blocking(move || {
+            service
+                .set_project(
+                    "shop",
+                    &json!({"targets": {"prod": {"ssh_profile": format!("v{}", round)}}}),
+                )
+                .expect("concurrent write");
+        }));
+        let resolver = resolver.clone();
+        tasks.push(tokio::spawn(async move {
+            resolver
+                .resolve_context(&json!({"project": "shop", "target": "prod"}))
+                .await
+                .expect("concurrent resolve");
+        }));
+    }
+    for task in tasks {
+        task.await.expect("task");
+    }
+    let stored = project_service.get_project("shop").expect("stored");
+    let settled = resolve("prod").await.expect("resolve").expect("context");
+    assert_eq!(
+        settled["target"]["ssh_profile"],
+        stored["project"]["targets"]["prod"]["ssh_profile"]
+    );
+
+    // With a zero TTL nothing is kept.
+    let uncached = ProjectResolver::new(Validation::new(), project_service.clone(), None)
+        .with_cache(cache)
+        .with_cache_ttl(Duration::ZERO);
+    for _ in 0..2 {
+        uncached
+            .resolve_context(&json!({"project": "shop", "target": "prod"}))
+            .await
+            .expect("uncached resolve");
+    }
+    assert_eq!(uncached.stats()["entries"], 0);
+    assert_eq!(uncached.stats()["hits"], 0);
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
@@ -0,0 +1,274 @@
+use infra::managers::api::ApiManager;
+use infra::managers::pipeline::PipelineManager;
+use infra::managers::postgres::PostgresManager;
+use infra::managers::ssh::SshManager;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::state::StateService;
+use infra::services::tool_executor::{ToolExecutor, ToolHandler};
+use infra::services::validation::Validation;
+use infra::utils::usage::{self, Counter};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::sync::{Arc, Mutex};
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+// Counts like a manager would: inline, from a spawned task and from a blocking thread.
+struct FakeTransfer;
+
+#[async_trait::async_trait]
+impl ToolHandler for FakeTransfer {
+    async fn handle(&self, _args: Value) -> Result<Value, infra::errors::ToolError> {
+        usage::record(Counter::PostgresRows, 3);
+        tokio::spawn(usage::in_current_scope(async {
+            usage::record(Counter::HttpBodyReadBytes, 100);
+        }))
+        .await
+        .unwrap();
+        let scope = usage::current();
+        tokio::task::spawn_blocking(move || {
+            usage::record_in(scope.as_ref(), Counter::SftpBytesWritten, 40);
+        })
+        .await
+        .unwrap();
+        Ok(json!({"success": true}))
+    }
+}
+
+fn executor(handlers: Vec<(&str, Arc<dyn ToolHandler>)>) -> ToolExecutor {
+    ToolExecutor::new(
+        Logger::new("test"),
+        Arc::new(StateService::new().expect("state")),
+        None,
+        None,
+        handlers
+            .into_iter()
+            .map(|(name, handler)| (name.to_string(), handler))
+            .collect::<HashMap<_, _>>(),
+        HashMap::new(),
+    )
+}
+
+fn pipeline() -> (PipelineManager, Arc<PostgresManager>) {
+    let logger = Logger::new("test");
+    let security = Arc::new(Security::new().expect("security"));
+    let profile_service = Arc::new(ProfileService::new(security.clone()).expect("profile service"));
+    let api = ApiManager::new(
+        logger.clone(),
+        Validation::new(),
+        profile_service.clone(),
+        None,
+        None,
+        None,
+    );
+    let ssh = SshManager::new(
+        logger.clone(),
+        security,
+        Validation::new(),
+        profile_service.clone(),
+        None,
+        None,
+        None,
+    );
+    let postgres = Arc::new(PostgresManager::new(
+        logger.clone(),
+        Validation::new(),
+        profile_service,
+        None,
+        None,
+    ));
+    let pipeline = PipelineManager::new(
+        logger,
+        Validation::new(),
+        Arc::new(api),
+        Arc::new(ssh),
+        postgres.clone(),
+        None,
+        None,
+        None,
+        None,
+    );
+    (pipeline, postgres)
+}
+
+// Answers 500 to paths containing `reject`, 200 otherwise; records every request body length.
+fn spawn_sink() -> (u16, Arc<Mutex<Vec<usize>>>) {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind sink");
+    let port = listener.local_addr().expect("sink addr").port();
+    let bodies = Arc::new(Mutex::new(Vec::new()));
+    let log = bodies.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { continue };
+            let mut raw = Vec::new();
+            let mut buf = [0u8; 8192];
+            let head_end = loop {
+                let read = stream.read(&mut buf).unwrap_or(0);
+                if read == 0 {
+                    break raw.len();
+                }
+                raw.extend_from_slice(&buf[..read]);
+                if let Some(pos) = raw.windows(4).position(|w| w == b"\r\n\r\n") {
+                    break pos + 4;
+                }
+            };
+            let head = String::from_utf8_lossy(&raw[..head_end]).to_string();
+            let length = head
+                .lines()
+                .filter_map(|l| l.split_once(':'))
+                .find(|(k, _)| k.trim().eq_ignore_ascii_case("content-length"))
+                .and_then(|(_, v)| v.trim().parse::<usize>().ok())
+                .unwrap_or(0);
+            while raw.len() < head_end + length {
+                let read = stream.read(&mut buf).unwrap_or(0);
+                if read == 0 {
+                    break;
+                }
+                raw.extend_from_slice(&buf[..read]);
+            }
+            log.lock().unwrap().push(length);
+            let status = if head.lines().next().unwrap_or("").contains("reject") {
+                "500 Internal Server Error"
+            } else {
+                "200 OK"
+            };
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{{}}",
+                status
+            );
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+    (port, bodies)
+}
+
+#[tokio::test]
+async fn responses_report_resource_usage_and_metrics_aggregate_it() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_accounting = std::env::var("INFRA_RESOURCE_ACCOUNTING").ok();
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    std::env::remove_var("INFRA_RESOURCE_ACCOUNTING");
+
+    let tool = format!("usage_{}", uuid::Uuid::new_v4().simple());
+    let fake = executor(vec![(tool.as_str(), Arc::new(FakeTransfer))]);
+
+    let plain = fake
+        .execute(&tool, json!({"action": "run"}))
+        .await
+        .expect("plain call");
+    assert!(plain["meta"].get("resource_usage").is_none());
+
+    let counted = fake
+        .execute(&tool, json!({"action": "run", "include_usage": true}))
+        .await
+        .expect("counted call");
+    let usage = &counted["meta"]["resource_usage"];
+    assert_eq!(usage["postgres_rows"], 3, "{}", usage);
+    assert_eq!(usage["http_body_read_bytes"], 100);
+    assert_eq!(usage["sftp_bytes_written"], 40);
+    assert_eq!(usage["retries"], 0);
+    assert!(usage["duration_ms"].is_i64());
+
+    std::env::set_var("INFRA_RESOURCE_ACCOUNTING", "1");
+    let flagged = fake
+        .execute(&tool, json!({"action": "run"}))
+        .await
+        .expect("flagged call");
+    assert_eq!(flagged["meta"]["resource_usage"]["postgres_rows"], 3);
+    let opted_out = fake
+        .execute(&tool, json!({"action": "run", "include_usage": false}))
+        .await
+        .expect("opted out call");
+    assert!(opted_out["meta"].get("resource_usage").is_none());
+
+    let totals = &usage::totals_snapshot()[tool.as_str()];
+    assert_eq!(totals["calls"], 4);
+    assert_eq!(totals["postgres_rows"], 12);
+    assert_eq!(totals["sftp_bytes_written"], 160);
+
+    // Set INFRA_TEST_POSTGRES_URLS (comma-separated) to account a pipeline run against live servers.
+    let urls = std::env::var("INFRA_TEST_POSTGRES_URLS").unwrap_or_default();
+    for url in urls.split(',').map(str::trim).filter(|url| !url.is_empty()) {
+        let (manager, postgres) = pipeline();
+        let table = format!("infra_usage_{}", uuid::Uuid::new_v4().simple());
+        for sql in [
+            format!("CREATE TABLE \"{}\" (id int4 PRIMARY KEY, name text)", table),
+            format!(
+                "INSERT INTO \"{}\" SELECT g, CASE WHEN g = 2 THEN 'reject' ELSE 'row-' || g END FROM generate_series(1, 4) g",
+                table
+            ),
+        ] {
+            postgres
+                .handle_action(json!({"action": "query", "connection_url": url, "sql": sql}))
+                .await
+                .expect("seed table");
+        }
+        let (port, bodies) = spawn_sink();
+        let executor = executor(vec![("pipeline", Arc::new(manager))]);
+        let run = executor
+            .execute(
+                "pipeline",
+                json!({
+                    "action": "run",
+                    "flow": "postgres_to_http",
+                    "postgres": {"connection_url": url, "table": table},
+                    "order_by": ["id"],
+                    "http": {
+                        "url": format!("http://127.0.0.1:{}/", port),
+                        "path_template": "/items/{{record.name}}",
+                        "body_template": {"id": "{{record.id}}"},
+                        "concurrency": 1,
+                        "retry": {
+                            "enabled": true,
+                            "max_attempts": 2,
+                            "base_delay_ms": 1,
+                            "max_delay_ms": 1,
+                            "methods": ["POST"],
+                            "status_codes": [500],
+                        },
+                    },
+                    "include_usage": true,
+                    "apply": true,
+                }),
+            )
+            .await
+            .expect("pipeline run");
+        assert_eq!(run["result"]["records"]["failed"], 1, "{}", run["result"]);
+        let usage = &run["meta"]["resource_usage"];
+        let sent: usize = bodies.lock().unwrap().iter().sum();
+        assert_eq!(bodies.lock().unwrap().len(), 5);
+        assert_eq!(usage["http_body_sent_bytes"], sent as u64, "{}", usage);
+        assert_eq!(usage["retries"], 1);
+        assert!(usage["postgres_rows"].as_u64().unwrap() >= 4, "{}", usage);
+        let totals = &usage::totals_snapshot()["pipeline"];
+        assert!(totals["http_body_sent_bytes"].as_u64().unwrap() >= sent as u64);
+
+        postgres
+            .handle_action(json!({
+                "action": "query",
+                "connection_url": url,
+                "sql": format!("DROP TABLE \"{}\"", table),
+            }))
+            .await
+            .expect("drop table");
+    }
+
+    restore_env("INFRA_RESOURCE_ACCOUNTING", prev_accounting);
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
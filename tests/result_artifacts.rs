@@ -0,0 +1,126 @@
+mod common;
+use common::ENV_LOCK;
+
+use infra::services::logger::Logger;
+use infra::services::state::StateService;
+use infra::services::tool_executor::{ToolExecutor, ToolHandler};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+struct LargeValueHandler;
+
+#[async_trait::async_trait]
+impl ToolHandler for LargeValueHandler {
+    async fn handle(&self, args: Value) -> Result<Value, infra::errors::ToolError> {
+        let key = args.get("key").and_then(|v| v.as_str()).unwrap_or("");
+        if key == "missing" {
+            return Err(infra::errors::ToolError::not_found("no such key"));
+        }
+        Ok(serde_json::json!({ "key": key, "value": "x".repeat(4096) }))
+    }
+}
+
+#[tokio::test]
+async fn result_artifacts_capture_full_results_and_index_parallel_calls() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    let keys = [
+        "INFRA_CONTEXT_REPO_ROOT",
+        "INFRA_RESULT_ARTIFACTS",
+        "INFRA_MAX_INLINE_BYTES",
+    ];
+    let previous: Vec<Option<String>> = keys.iter().map(|key| std::env::var(key).ok()).collect();
+    std::env::set_var("INFRA_CONTEXT_REPO_ROOT", &tmp_dir);
+    std::env::set_var("INFRA_RESULT_ARTIFACTS", "1");
+    std::env::set_var("INFRA_MAX_INLINE_BYTES", "256");
+
+    let mut handlers: HashMap<String, Arc<dyn ToolHandler>> = HashMap::new();
+    handlers.insert("state".to_string(), Arc::new(LargeValueHandler));
+    let executor = ToolExecutor::new(
+        Logger::new("test"),
+        Arc::new(StateService::new().expect("state")),
+        None,
+        None,
+        handlers,
+        HashMap::new(),
+    );
+
+    let calls = (0..8).map(|idx| {
+        executor.execute(
+            "state",
+            serde_json::json!({
+                "action": "get",
+                "key": format!("k{}", idx),
+                "trace_id": "trace-results",
+                "span_id": format!("span-{}", idx),
+            }),
+        )
+    });
+    let payloads = futures::future::join_all(calls).await;
+    let first = payloads[0].as_ref().expect("call succeeds");
+    assert_eq!(first["result"]["value"]["truncated"], true);
+    assert_eq!(
+        first["meta"]["artifact_uri_json"],
+        "artifact://runs/trace-results/tool_calls/span-0/result.json"
+    );
+    let raw = std::fs::read_to_string(
+        tmp_dir.join("artifacts/runs/trace-results/tool_calls/span-0/result.json"),
+    )
+    .expect("read result artifact");
+    let stored: Value = serde_json::from_str(&raw).expect("result json");
+    assert_eq!(stored["value"].as_str().map(|s| s.len()), Some(4096));
+
+    executor
+        .execute(
+            "state",
+            serde_json::json!({
+                "action": "get",
+                "key": "missing",
+                "trace_id": "trace-results",
+                "span_id": "span-missing",
+            }),
+        )
+        .await
+        .expect_err("handler fails");
+
+    let raw = std::fs::read_to_string(tmp_dir.join("artifacts/runs/trace-results/index.json"))
+        .expect("read index");
+    let index: Value = serde_json::from_str(&raw).expect("index json");
+    let calls = index["calls"].as_array().expect("calls");
+    assert_eq!(calls.len(), 9);
+    for idx in 0..8 {
+        let span = format!("span-{}", idx);
+        let call = calls
+            .iter()
+            .find(|call| call["span_id"] == span.as_str())
+            .expect("every parallel call is indexed");
+        assert_eq!(call["tool"], "state");
+        assert_eq!(call["status"], "ok");
+        assert!(call["refs"].as_array().expect("refs").len() >= 2);
+    }
+    let failed = calls
+        .iter()
+        .find(|call| call["span_id"] == "span-missing")
+        .expect("failed call indexed");
+    assert_eq!(failed["status"], "error");
+    let raw = std::fs::read_to_string(
+        tmp_dir.join("artifacts/runs/trace-results/tool_calls/span-missing/result.json"),
+    )
+    .expect("read error artifact");
+    assert!(raw.contains("no such key"));
+
+    for (key, value) in keys.iter().zip(previous) {
+        restore_env(key, value);
+    }
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
@@ -0,0 +1,160 @@
+mod common;
+use common::ENV_LOCK;
+
+use infra::services::logger::Logger;
+use infra::services::state::StateService;
+use infra::services::tool_executor::{ToolExecutor, ToolHandler};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+// Shapes like a large `sql action=query` select and a large `ssh action=sftp_list`.
+struct BulkHandler;
+
+#[async_trait::async_trait]
+impl ToolHandler for BulkHandler {
+    async fn handle(&self, args: Value) -> Result<Value, infra::errors::ToolError> {
+        let count = args.get("count").and_then(|v| v.as_u64()).unwrap_or(0);
+        if args.get("action").and_then(|v| v.as_str()) == Some("sftp_list") {
+            let entries: Vec<Value> = (0..count)
+                .map(|idx| json!({"path": format!("/var/log/app-{:05}.log", idx), "type": "file", "size": idx}))
+                .collect();
+            return Ok(
+                json!({"success": true, "path": "/var/log", "entries": entries, "total_matched": count}),
+            );
+        }
+        let rows: Vec<Value> = (0..count)
+            .map(|idx| json!({"id": idx, "email": format!("user-{:05}@example.com", idx)}))
+            .collect();
+        Ok(json!({"success": true, "rows": rows, "row_count": count}))
+    }
+}
+
+#[tokio::test]
+async fn oversized_results_are_cut_to_previews_with_the_full_result_offloaded() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    let keys = [
+        "INFRA_CONTEXT_REPO_ROOT",
+        "INFRA_MAX_RESULT_BYTES",
+        "INFRA_MAX_STATE_VALUE_BYTES",
+    ];
+    let previous: Vec<Option<String>> = keys.iter().map(|key| std::env::var(key).ok()).collect();
+    std::env::set_var("INFRA_CONTEXT_REPO_ROOT", &tmp_dir);
+    std::env::set_var("INFRA_MAX_RESULT_BYTES", "4096");
+    std::env::remove_var("INFRA_MAX_STATE_VALUE_BYTES");
+
+    let state = Arc::new(StateService::new().expect("state"));
+    let mut handlers: HashMap<String, Arc<dyn ToolHandler>> = HashMap::new();
+    handlers.insert("sql".to_string(), Arc::new(BulkHandler));
+    handlers.insert("ssh".to_string(), Arc::new(BulkHandler));
+    let executor = ToolExecutor::new(
+        Logger::new("test"),
+        state.clone(),
+        None,
+        None,
+        handlers,
+        HashMap::new(),
+    );
+
+    let small = executor
+        .execute(
+            "sql",
+            json!({"action": "query", "sql": "SELECT 1", "count": 3}),
+        )
+        .await
+        .expect("small select");
+    assert!(small["meta"].get("result_truncated").is_none());
+    assert_eq!(small["result"]["rows"].as_array().unwrap().len(), 3);
+
+    let select = || {
+        executor.execute(
+            "sql",
+            json!({
+                "action": "query",
+                "sql": "SELECT * FROM users",
+                "count": 500,
+                "trace_id": "trace-guard",
+                "span_id": "span-select",
+                "store_as": "users",
+            }),
+        )
+    };
+    let payload = select().await.expect("large select");
+    let meta = &payload["meta"];
+    assert_eq!(meta["result_truncated"], true);
+    let truncation = &meta["truncation"];
+    assert_eq!(truncation["max_bytes"], 4096);
+    assert!(truncation["bytes"].as_u64().unwrap() > 4096);
+    assert!(truncation["inline_bytes"].as_u64().unwrap() <= 4096);
+    assert_eq!(
+        truncation["artifact"],
+        "artifact://runs/trace-guard/tool_calls/span-select/result_full.json"
+    );
+    let kept = truncation["fields"][0]["kept"].as_u64().unwrap();
+    assert_eq!(truncation["fields"][0]["field"], "rows");
+    assert_eq!(truncation["fields"][0]["total"], 500);
+    assert!(kept > 0 && kept < 500);
+    let rows = payload["result"]["rows"].as_array().unwrap();
+    assert_eq!(rows.len() as u64, kept);
+    assert_eq!(rows[0]["id"], 0);
+    assert_eq!(payload["result"]["row_count"], 500);
+
+    let raw = std::fs::read_to_string(
+        tmp_dir.join("artifacts/runs/trace-guard/tool_calls/span-select/result_full.json"),
+    )
+    .expect("full result artifact");
+    let full: Value = serde_json::from_str(&raw).expect("full result json");
+    assert_eq!(full["rows"].as_array().unwrap().len(), 500);
+
+    // The same call truncates the same way.
+    let again = select().await.expect("repeated select");
+    assert_eq!(again["result"], payload["result"]);
+
+    // store_as keeps the full value while it fits the state backend, the ref otherwise.
+    let stored = state.get("users", Some("session")).expect("stored");
+    assert_eq!(stored["value"]["rows"].as_array().unwrap().len(), 500);
+    std::env::set_var("INFRA_MAX_STATE_VALUE_BYTES", "1024");
+    select().await.expect("select stored as ref");
+    let stored = state.get("users", Some("session")).expect("stored");
+    assert_eq!(
+        stored["value"],
+        json!({
+            "result_truncated": true,
+            "artifact": "artifact://runs/trace-guard/tool_calls/span-select/result_full.json",
+            "bytes": truncation["bytes"],
+        })
+    );
+
+    let listing = executor
+        .execute(
+            "ssh",
+            json!({"action": "sftp_list", "path": "/var/log", "count": 400, "trace_id": "trace-guard", "span_id": "span-list"}),
+        )
+        .await
+        .expect("large listing");
+    let truncation = &listing["meta"]["truncation"];
+    assert_eq!(truncation["fields"][0]["field"], "entries");
+    assert_eq!(truncation["fields"][0]["total"], 400);
+    let entries = listing["result"]["entries"].as_array().unwrap();
+    assert_eq!(
+        entries.len() as u64,
+        truncation["fields"][0]["kept"].as_u64().unwrap()
+    );
+    assert_eq!(entries[0]["path"], "/var/log/app-00000.log");
+    assert_eq!(listing["result"]["total_matched"], 400);
+
+    for (key, value) in keys.iter().zip(previous) {
+        restore_env(key, value);
+    }
+    let _ = std::fs::remove_dir_all(&tmp_dir);
+}
@@ -0,0 +1,113 @@
+use infra::errors::ToolErrorKind;
+use infra::utils::run_report::{attach, requested, RunReport};
+use serde_json::{json, Value};
+
+mod common;
+use common::ENV_LOCK;
+
+const GOLDEN: &str = include_str!("fixtures/run_report.golden");
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+fn fan_out_run() -> (Value, Value) {
+    let args = json!({
+        "action": "run",
+        "flow": "fan_out",
+        "project": "shop",
+        "target": "prod",
+        "trace_id": "trace-42",
+        "report": true,
+    });
+    let result = json!({
+        "success": false,
+        "flow": "fan_out",
+        "source": {
+            "type": "http",
+            "bytes": 5120,
+            "http": {"body_ref": {"uri": "artifact://runs/trace-42/source.jsonl", "rel": "runs/trace-42/source.jsonl"}},
+        },
+        "sinks": [
+            {
+                "name": "warehouse",
+                "type": "postgres",
+                "status": "ok",
+                "duration_ms": 310,
+                "result": {"inserted": 40, "retries": 1},
+            },
+            {
+                "name": "archive|cold",
+                "type": "sftp",
+                "status": "failed",
+                "duration_ms": 95,
+                "error": {"code": "CONNECTION_FAILED", "message": format!("connect to archive-1 refused {}", "x".repeat(300))},
+            },
+        ],
+        "failure_logs": {"artifact": {"uri": "artifact://runs/trace-42/tool_calls/s1/failure_logs.txt"}},
+    });
+    (args, result)
+}
+
+#[test]
+fn report_layout_matches_the_golden_file() {
+    let (args, result) = fan_out_run();
+    let report = RunReport::from_result("Pipeline run report", &args, &result, 1234);
+    assert_eq!(report.rows, Some(40));
+    assert_eq!(report.bytes, Some(5120));
+    assert_eq!(report.retries, Some(1));
+    assert_eq!(report.render_markdown(), GOLDEN);
+}
+
+#[test]
+fn report_flag_must_be_a_boolean() {
+    assert!(!requested(&json!({})).expect("absent"));
+    assert!(requested(&json!({"report": true})).expect("true"));
+    let err = requested(&json!({"report": "yes"})).expect_err("string");
+    assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+}
+
+#[tokio::test]
+async fn attach_writes_the_report_under_the_run_trace() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let keys = ["INFRA_CONTEXT_REPO_ROOT"];
+    let previous: Vec<Option<String>> = keys.iter().map(|key| std::env::var(key).ok()).collect();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_CONTEXT_REPO_ROOT", &tmp_dir);
+
+    let (mut args, result) = fan_out_run();
+    let attached = attach("Pipeline run report", &args, result.clone(), 1234);
+    assert_eq!(attached["report"]["rel"], "runs/trace-42/report.md");
+    assert_eq!(
+        attached["report"]["uri"],
+        "artifact://runs/trace-42/report.md"
+    );
+    let written = std::fs::read_to_string(tmp_dir.join("artifacts/runs/trace-42/report.md"))
+        .expect("read report");
+    assert_eq!(written, GOLDEN);
+    assert_eq!(attached["report"]["bytes"], written.len());
+
+    args["report"] = json!(false);
+    let untouched = attach("Pipeline run report", &args, result.clone(), 1234);
+    assert_eq!(untouched, result);
+
+    // Without an artifacts root the run still returns, with the write error in place of the ref.
+    std::env::set_var("INFRA_CONTEXT_REPO_ROOT", tmp_dir.join("missing"));
+    args["report"] = json!(true);
+    let attached = attach("Pipeline run report", &args, result, 1234);
+    assert_eq!(attached["success"], false);
+    assert_eq!(
+        attached["report"]["error"],
+        "report requires context repo root"
+    );
+
+    for (key, value) in keys.iter().zip(previous) {
+        restore_env(key, value);
+    }
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
@@ -0,0 +1,224 @@
+use infra::errors::{ToolError, ToolErrorKind};
+use infra::managers::runbook::RunbookManager;
+use infra::services::logger::Logger;
+use infra::services::runbook::RunbookService;
+use infra::services::state::StateService;
+use infra::services::tool_executor::{ToolExecutor, ToolHandler};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+mod common;
+use common::ENV_LOCK;
+
+#[derive(Clone, Default)]
+struct DummyHandler {
+    calls: Arc<Mutex<Vec<Value>>>,
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for DummyHandler {
+    async fn handle(&self, args: Value) -> Result<Value, ToolError> {
+        self.calls.lock().unwrap().push(args.clone());
+        if args["action"] == "fail" {
+            return Err(ToolError::not_found("no such release"));
+        }
+        Ok(json!({ "success": true }))
+    }
+}
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+#[tokio::test]
+async fn capture_records_top_level_calls_into_a_parameterized_runbook() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let keys = [
+        "INFRA_PROFILES_DIR",
+        "INFRA_DEFAULT_RUNBOOKS_PATH",
+        "INFRA_RUNBOOKS_PATH",
+    ];
+    let previous: Vec<Option<String>> = keys.iter().map(|key| std::env::var(key).ok()).collect();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    let runbooks_path = tmp_dir.join("runbooks.json");
+    std::fs::write(
+        &runbooks_path,
+        json!({
+            "web.diag": {
+                "steps": [
+                    { "id": "one", "tool": "dummy", "args": { "action": "ping" } },
+                    { "id": "two", "tool": "dummy", "args": { "action": "ping" } }
+                ]
+            }
+        })
+        .to_string(),
+    )
+    .expect("write runbooks");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    std::env::set_var("INFRA_DEFAULT_RUNBOOKS_PATH", &runbooks_path);
+    std::env::set_var("INFRA_RUNBOOKS_PATH", &runbooks_path);
+
+    let logger = Logger::new("test");
+    let state_service = Arc::new(StateService::new().expect("state"));
+    let runbook_manager = RunbookManager::new(
+        logger.clone(),
+        Arc::new(RunbookService::new().expect("runbook service")),
+        state_service.clone(),
+    );
+    let dummy = DummyHandler::default();
+    let mut handlers: HashMap<String, Arc<dyn ToolHandler>> = HashMap::new();
+    handlers.insert("dummy".to_string(), Arc::new(dummy.clone()));
+    handlers.insert("runbook".to_string(), Arc::new(runbook_manager.clone()));
+    let executor = Arc::new(ToolExecutor::new(
+        logger,
+        state_service,
+        None,
+        None,
+        handlers,
+        HashMap::new(),
+    ));
+    runbook_manager.set_tool_executor(executor.clone());
+    let runbook = |args: Value| executor.execute("runbook", args);
+
+    let err = runbook(json!({"action": "runbook_capture_stop"}))
+        .await
+        .expect_err("nothing to stop");
+    assert_eq!(err.message, "no runbook capture is active");
+
+    runbook(json!({"action": "runbook_capture_start", "name": "web.release"}))
+        .await
+        .expect("capture start");
+    let err = runbook(json!({"action": "runbook_capture_start"}))
+        .await
+        .expect_err("already capturing");
+    assert_eq!(err.kind, ToolErrorKind::Conflict);
+
+    executor
+        .execute(
+            "dummy",
+            json!({
+                "action": "deploy",
+                "connection": {"host": "web-1.prod", "password": "hunter22"},
+                "release_path": "/srv/releases/42",
+            }),
+        )
+        .await
+        .expect("deploy");
+    executor
+        .execute(
+            "dummy",
+            json!({"action": "fail", "release_path": "/srv/releases/41"}),
+        )
+        .await
+        .expect_err("failed call");
+    executor
+        .execute("dummy", json!({"action": "ping", "capture": false}))
+        .await
+        .expect("uncaptured call");
+    // The runbook's own two steps run nested and stay out of the draft.
+    runbook(json!({"action": "runbook_run", "name": "web.diag"}))
+        .await
+        .expect("nested run");
+    executor
+        .execute("dummy", json!({"action": "ping", "host": "web-1.prod"}))
+        .await
+        .expect("ping");
+    assert!(dummy.calls.lock().unwrap()[2].get("capture").is_none());
+
+    let stopped = runbook(json!({"action": "runbook_capture_stop"}))
+        .await
+        .expect("capture stop");
+    let draft = &stopped["result"];
+    assert_eq!(draft["recorded"], 4, "{}", draft);
+    assert_eq!(draft["failed"], 1);
+    assert_eq!(draft["skipped"], 1);
+    assert_eq!(draft["name"], "web.release");
+    assert_eq!(
+        draft["secrets_stripped"],
+        json!(["step_1.args.connection.password"])
+    );
+    let steps = draft["runbook"]["steps"].as_array().unwrap();
+    let tools: Vec<_> = steps
+        .iter()
+        .map(|step| format!("{}:{}", step["tool"], step["args"]["action"]))
+        .collect();
+    assert_eq!(
+        tools,
+        [
+            "\"dummy\":\"deploy\"",
+            "\"dummy\":\"fail\"",
+            "\"runbook\":\"runbook_run\"",
+            "\"dummy\":\"ping\""
+        ]
+    );
+    assert_eq!(
+        draft["runbook"]["defaults"],
+        json!({
+            "host": "web-1.prod",
+            "release_path": "/srv/releases/42",
+            "release_path_2": "/srv/releases/41",
+        })
+    );
+    assert_eq!(
+        steps[0]["args"]["connection"],
+        json!({"host": "{{ input.host }}"})
+    );
+    assert_eq!(steps[3]["args"]["host"], "{{ input.host }}");
+    assert_eq!(steps[1]["when"], false);
+    assert_eq!(
+        steps[2]["comment"],
+        "runbooks cannot run runbooks; copy the steps of 'web.diag' here instead"
+    );
+    assert_eq!(
+        steps[1]["comment"],
+        "failed during capture (NOT_FOUND): no such release"
+    );
+    assert!(steps[0]["args"].get("trace_id").is_none());
+
+    let saved = runbook(json!({"action": "runbook_capture_save"}))
+        .await
+        .expect("capture save");
+    assert_eq!(saved["result"]["name"], "web.release");
+    let manifest: Value =
+        serde_json::from_str(&std::fs::read_to_string(&runbooks_path).expect("read manifest"))
+            .expect("manifest json");
+    assert_eq!(
+        manifest["web.release"]["steps"].as_array().unwrap().len(),
+        4
+    );
+
+    dummy.calls.lock().unwrap().clear();
+    let replayed = runbook(json!({
+        "action": "runbook_run",
+        "name": "web.release",
+        "input": {"release_path": "/srv/releases/43"},
+    }))
+    .await
+    .expect("replay");
+    assert_eq!(replayed["result"]["success"], true, "{}", replayed);
+    let calls = dummy.calls.lock().unwrap().clone();
+    let actions: Vec<_> = calls.iter().map(|call| call["action"].clone()).collect();
+    assert_eq!(actions, ["deploy", "ping"]);
+    assert_eq!(calls[0]["connection"]["host"], "web-1.prod");
+    assert_eq!(calls[0]["release_path"], "/srv/releases/43");
+
+    let err = runbook(json!({
+        "action": "runbook_capture_save",
+        "name": "web.release",
+        "runbook": draft["runbook"],
+    }))
+    .await
+    .expect_err("existing name");
+    assert_eq!(err.kind, ToolErrorKind::Conflict);
+
+    for (key, value) in keys.iter().zip(previous) {
+        restore_env(key, value);
+    }
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
@@ -0,0 +1,200 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::runbook::RunbookManager;
+use infra::services::logger::Logger;
+use infra::services::runbook::RunbookService;
+use infra::services::state::StateService;
+use infra::services::tool_executor::{ToolExecutor, ToolHandler};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+#[derive(Clone)]
+struct DummyHandler {
+    calls: Arc<AtomicUsize>,
+}
+
+#[async_trait::async_trait]
+impl ToolHandler for DummyHandler {
+    async fn handle(&self, args: Value) -> Result<Value, infra::errors::ToolError> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        Ok(json!({ "success": true, "args": args }))
+    }
+}
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+fn run_status(runs: &Value, run_id: &str) -> String {
+    runs["runs"]
+        .as_array()
+        .and_then(|runs| runs.iter().find(|run| run["run_id"] == run_id))
+        .and_then(|run| run["status"].as_str())
+        .unwrap_or("missing")
+        .to_string()
+}
+
+#[tokio::test]
+async fn checkpoint_pauses_and_resume_approves_aborts_or_expires() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let keys = [
+        "INFRA_PROFILES_DIR",
+        "INFRA_DEFAULT_RUNBOOKS_PATH",
+        "INFRA_RUNBOOKS_PATH",
+    ];
+    let previous: Vec<Option<String>> = keys.iter().map(|key| std::env::var(key).ok()).collect();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    let runbooks_path = tmp_dir.join("runbooks.json");
+    std::fs::write(
+        &runbooks_path,
+        json!({
+            "db.failover": {
+                "steps": [
+                    { "id": "diag", "tool": "dummy", "args": { "action": "ping" } },
+                    {
+                        "id": "failover",
+                        "tool": "dummy",
+                        "checkpoint": true,
+                        "args": { "action": "failover", "target": "{{ input.replica }}", "mode": "safe" }
+                    },
+                    { "id": "verify", "tool": "dummy", "args": { "action": "ping" } }
+                ]
+            }
+        })
+        .to_string(),
+    )
+    .expect("write runbooks");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    std::env::set_var("INFRA_DEFAULT_RUNBOOKS_PATH", &runbooks_path);
+    std::env::set_var("INFRA_RUNBOOKS_PATH", &runbooks_path);
+
+    let logger = Logger::new("test");
+    let state_service = Arc::new(StateService::new().expect("state"));
+    let runbook_manager = RunbookManager::new(
+        logger.clone(),
+        Arc::new(RunbookService::new().expect("runbook service")),
+        state_service.clone(),
+    );
+    let calls = Arc::new(AtomicUsize::new(0));
+    let mut handlers: HashMap<String, Arc<dyn ToolHandler>> = HashMap::new();
+    handlers.insert(
+        "dummy".to_string(),
+        Arc::new(DummyHandler {
+            calls: calls.clone(),
+        }),
+    );
+    handlers.insert("runbook".to_string(), Arc::new(runbook_manager.clone()));
+    let executor = Arc::new(ToolExecutor::new(
+        logger,
+        state_service,
+        None,
+        None,
+        handlers,
+        HashMap::new(),
+    ));
+    runbook_manager.set_tool_executor(executor.clone());
+    let start = |extra: Value| {
+        let mut args = json!({
+            "action": "runbook_run",
+            "name": "db.failover",
+            "input": { "replica": "db-2" },
+        });
+        if let (Some(args), Some(extra)) = (args.as_object_mut(), extra.as_object()) {
+            args.extend(extra.clone());
+        }
+        runbook_manager.handle_action(args)
+    };
+
+    let paused = start(json!({})).await.expect("run pauses");
+    assert_eq!(paused["paused"], true);
+    assert_eq!(paused["awaiting"]["id"], "failover");
+    assert_eq!(paused["awaiting"]["args"]["target"], "db-2");
+    assert_eq!(paused["steps"].as_array().map(|s| s.len()), Some(1));
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    let run_id = paused["run_id"].as_str().expect("run_id").to_string();
+    let runs = runbook_manager
+        .handle_action(json!({ "action": "runbook_runs" }))
+        .await
+        .expect("runs");
+    assert_eq!(run_status(&runs, &run_id), "paused");
+
+    let resumed = executor
+        .execute(
+            "runbook",
+            json!({
+                "action": "runbook_resume",
+                "run_id": run_id,
+                "approve": true,
+                "approved_by": "oncall@example.com",
+                "override_args": { "mode": "fast" },
+                "trace_id": "trace-approval",
+                "span_id": "span-approval",
+            }),
+        )
+        .await
+        .expect("resume");
+    let resumed = &resumed["result"];
+    assert_eq!(resumed["success"], true, "{}", resumed);
+    assert_eq!(resumed["approval"]["approved_by"], "oncall@example.com");
+    let steps = resumed["steps"].as_array().expect("steps");
+    assert_eq!(steps.len(), 3);
+    assert_eq!(steps[1]["result"]["args"]["mode"], "fast");
+    assert_eq!(steps[1]["result"]["args"]["target"], "db-2");
+    assert_eq!(
+        steps[1]["result"]["args"]["parent_span_id"],
+        "span-approval"
+    );
+    assert_eq!(steps[1]["meta"]["trace_id"], "trace-approval");
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+    let err = runbook_manager
+        .handle_action(json!({ "action": "runbook_resume", "run_id": run_id, "approve": true }))
+        .await
+        .expect_err("completed runs cannot resume");
+    assert_eq!(err.kind, ToolErrorKind::Conflict);
+
+    let rejected = start(json!({})).await.expect("second run pauses");
+    let rejected_id = rejected["run_id"].as_str().expect("run_id").to_string();
+    let aborted = runbook_manager
+        .handle_action(
+            json!({ "action": "runbook_resume", "run_id": rejected_id, "approve": false }),
+        )
+        .await
+        .expect("abort");
+    assert_eq!(aborted["aborted"], true);
+    assert_eq!(aborted["skipped"]["id"], "failover");
+    assert_eq!(calls.load(Ordering::SeqCst), 4);
+
+    let stale = start(json!({ "checkpoint_ttl_ms": 1 }))
+        .await
+        .expect("third run pauses");
+    let stale_id = stale["run_id"].as_str().expect("run_id").to_string();
+    tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    let runs = runbook_manager
+        .handle_action(json!({ "action": "runbook_runs" }))
+        .await
+        .expect("runs");
+    assert_eq!(run_status(&runs, &run_id), "completed");
+    assert_eq!(run_status(&runs, &rejected_id), "aborted");
+    assert_eq!(run_status(&runs, &stale_id), "expired");
+    let err = runbook_manager
+        .handle_action(json!({ "action": "runbook_resume", "run_id": stale_id, "approve": true }))
+        .await
+        .expect_err("expired runs cannot resume");
+    assert_eq!(err.kind, ToolErrorKind::Conflict);
+    assert_eq!(calls.load(Ordering::SeqCst), 5);
+
+    for (key, value) in keys.iter().zip(previous) {
+        restore_env(key, value);
+    }
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
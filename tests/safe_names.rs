@@ -0,0 +1,189 @@
+use infra::app::App;
+use infra::errors::{ToolError, ToolErrorKind};
+use infra::services::alias::AliasService;
+use infra::services::preset::PresetService;
+use infra::services::profile::ProfileService;
+use infra::services::project::ProjectService;
+use infra::services::security::Security;
+use infra::utils::artifacts::{build_run_file_ref, resolve_artifact_path};
+use serde_json::json;
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+fn unsafe_names() -> Vec<String> {
+    vec![
+        "../../etc/passwd".to_string(),
+        "/etc/passwd".to_string(),
+        "..\\..\\windows".to_string(),
+        "ops\u{2215}prod".to_string(),
+        "ops\u{FF0F}prod".to_string(),
+        ".hidden".to_string(),
+        "prod db".to_string(),
+        "a".repeat(256),
+    ]
+}
+
+fn assert_unsafe(result: Result<serde_json::Value, ToolError>, name: &str) {
+    let err = result.expect_err(name);
+    assert_eq!(
+        err.kind,
+        ToolErrorKind::InvalidParams,
+        "{}: {:?}",
+        name,
+        err
+    );
+    assert!(
+        err.message.contains("is not a safe name"),
+        "{}: {}",
+        name,
+        err.message
+    );
+}
+
+#[tokio::test]
+async fn storage_services_refuse_names_that_read_as_paths() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let keys = ["INFRA_PROFILES_DIR", "INFRA_CONTEXT_REPO_ROOT"];
+    let previous: Vec<Option<String>> = keys.iter().map(|key| std::env::var(key).ok()).collect();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    let context_root = tmp_dir.join("context");
+    std::fs::create_dir_all(&context_root).expect("create context dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    std::env::set_var("INFRA_CONTEXT_REPO_ROOT", &context_root);
+
+    // An alias stored before the rule existed.
+    std::fs::write(
+        tmp_dir.join("aliases.json"),
+        json!({"ops/restart": {"tool": "state", "args": {"action": "get", "key": "x"}}})
+            .to_string(),
+    )
+    .unwrap();
+
+    let profiles = ProfileService::new(Arc::new(Security::new().expect("security"))).unwrap();
+    let aliases = AliasService::new().unwrap();
+    let presets = PresetService::new().unwrap();
+    let projects = ProjectService::new().unwrap();
+    for name in unsafe_names() {
+        assert_unsafe(
+            profiles.set_profile(&name, &json!({"type": "api", "data": {}})),
+            &name,
+        );
+        assert_unsafe(aliases.set_alias(&name, &json!({"tool": "state"})), &name);
+        assert_unsafe(presets.set_preset(&name, &json!({"data": {}})), &name);
+        assert_unsafe(projects.set_project(&name, &json!({})), &name);
+        assert_unsafe(
+            projects.set_project("shop", &json!({"targets": {name.clone(): {}}})),
+            &name,
+        );
+    }
+    profiles
+        .set_profile("prod-api.v2", &json!({"type": "api", "data": {}}))
+        .expect("safe name");
+
+    // The legacy entry stays readable and deletable; writing it again points at a safe name.
+    let legacy = aliases.get_alias("ops/restart").expect("legacy alias");
+    assert_eq!(legacy["alias"]["tool"], "state", "{}", legacy);
+    let err = aliases
+        .set_alias("ops/restart", &json!({"tool": "state"}))
+        .expect_err("legacy write");
+    assert_eq!(
+        err.message,
+        "alias name \"ops/restart\" is not a safe name: it must not contain path separators (found '/')"
+    );
+    assert_eq!(err.details.as_ref().unwrap()["suggested"], "ops-restart");
+    assert!(err.hint.unwrap().contains("'ops-restart'"));
+    aliases.delete_alias("ops/restart").expect("legacy delete");
+
+    let app = App::initialize().expect("app");
+    for name in unsafe_names() {
+        assert_unsafe(
+            app.tool_executor
+                .execute(
+                    "state",
+                    json!({"action": "set", "key": name, "value": 1, "scope": "session"}),
+                )
+                .await,
+            &name,
+        );
+        assert_unsafe(
+            app.tool_executor
+                .execute(
+                    "state",
+                    json!({"action": "get", "key": "k", "store_as": name}),
+                )
+                .await,
+            &name,
+        );
+        assert_unsafe(
+            app.tool_executor
+                .execute(
+                    "pipeline",
+                    json!({
+                        "action": "run",
+                        "flow": "fan_out",
+                        "checkpoint": name,
+                        "source": {"type": "http", "url": "http://127.0.0.1:9/feed"},
+                        "sinks": [{"type": "http", "url": "http://127.0.0.1:9/in"}],
+                        "apply": true,
+                    }),
+                )
+                .await,
+            &name,
+        );
+        assert_unsafe(
+            build_run_file_ref(Some("run"), &name).map(|_| json!(null)),
+            &name,
+        );
+    }
+
+    // Artifact paths: '..' and absolute paths never leave the artifacts root, and neither does
+    // a symlink placed inside it.
+    let outside = tmp_dir.join("outside");
+    std::fs::create_dir_all(&outside).unwrap();
+    std::fs::write(outside.join("secret.txt"), "secret").unwrap();
+    std::fs::create_dir_all(context_root.join("artifacts")).unwrap();
+    std::os::unix::fs::symlink(&outside, context_root.join("artifacts").join("link")).unwrap();
+    for rel in [
+        "../../etc/passwd",
+        "runs/../../../outside/secret.txt",
+        "/etc/passwd",
+        "link/secret.txt",
+        "link/new.txt",
+    ] {
+        let err = resolve_artifact_path(&context_root, rel).expect_err(rel);
+        assert_eq!(err.kind, ToolErrorKind::Denied, "{}", rel);
+        let err = app
+            .tool_executor
+            .execute(
+                "artifacts",
+                json!({"action": "get", "uri": format!("artifact://{}", rel)}),
+            )
+            .await
+            .expect_err(rel);
+        assert_eq!(err.kind, ToolErrorKind::Denied, "{}", rel);
+    }
+    let err = app
+        .tool_executor
+        .execute("artifacts", json!({"action": "list", "prefix": "../.."}))
+        .await
+        .expect_err("list prefix");
+    assert_eq!(err.kind, ToolErrorKind::Denied);
+    // A lookalike separator is an ordinary character to the file system: it stays inside.
+    let inside = resolve_artifact_path(&context_root, "..\u{2215}..\u{2215}etc").unwrap();
+    assert!(inside.starts_with(context_root.join("artifacts")));
+
+    for (key, value) in keys.iter().zip(previous) {
+        restore_env(key, value);
+    }
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
@@ -0,0 +1,288 @@
+use infra::errors::ToolErrorKind;
+use infra::services::cache::{CacheBackend, CacheService};
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::secret_ref::SecretRefResolver;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use infra::services::vault_client::VaultClient;
+use serde_json::json;
+use std::io::{Read, Write};
+use std::sync::{Arc, Mutex};
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+// Minimal Vault: AppRole login plus kv2 reads, where `moved` is missing and `locked` is
+// forbidden. Records the request line of every call.
+fn spawn_vault_stub() -> (u16, Arc<Mutex<Vec<String>>>) {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind stub");
+    let port = listener.local_addr().expect("stub addr").port();
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let log = seen.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { continue };
+            let mut buf = [0u8; 8192];
+            let read = stream.read(&mut buf).unwrap_or(0);
+            let request = String::from_utf8_lossy(&buf[..read]).to_string();
+            let line = request.lines().next().unwrap_or_default().to_string();
+            log.lock().unwrap().push(line.clone());
+            let (status, body) = if line.starts_with("POST /v1/auth/approle/login") {
+                ("200 OK", json!({"auth": {"client_token": "tok-1"}}))
+            } else if line.starts_with("GET /v1/secret/data/app ") {
+                (
+                    "200 OK",
+                    json!({"data": {"data": {"user": "svc", "password": "pw"}}}),
+                )
+            } else if line.starts_with("GET /v1/secret/data/locked ") {
+                ("403 Forbidden", json!({"errors": ["permission denied"]}))
+            } else {
+                ("404 Not Found", json!({"errors": []}))
+            };
+            let body = body.to_string();
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+    (port, seen)
+}
+
+#[tokio::test]
+async fn refs_resolve_in_one_batch_and_report_every_failure() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let prev_secret = std::env::var("INFRA_TEST_SECRET_REF").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    std::env::set_var("INFRA_TEST_SECRET_REF", "from-env");
+    std::env::remove_var("INFRA_TEST_SECRET_REF_UNSET");
+
+    let (port, seen) = spawn_vault_stub();
+    let security = Arc::new(Security::new().expect("security"));
+    let profile_service = Arc::new(ProfileService::new(security).expect("profile service"));
+    profile_service
+        .set_profile(
+            "vault-main",
+            &json!({
+                "type": "vault",
+                "data": {"addr": format!("http://127.0.0.1:{}", port)},
+                "secrets": {"role_id": "role", "secret_id": "secret"},
+            }),
+        )
+        .expect("vault profile");
+    let vault = Arc::new(VaultClient::new(
+        Logger::new("test"),
+        Validation::new(),
+        profile_service.clone(),
+    ));
+    let resolver = SecretRefResolver::new(
+        Logger::new("test"),
+        Validation::new(),
+        Some(profile_service),
+        Some(vault),
+        None,
+    );
+    let args = json!({"vault_profile_name": "vault-main"});
+
+    let healthy = json!({
+        "user": "ref:vault:kv2:secret/app#user",
+        "password": "ref:vault:kv2:secret/app#password",
+        "replicas": ["ref:vault:kv2:secret/app#user", "ref:env:INFRA_TEST_SECRET_REF"],
+        "port": 5432,
+    });
+    let resolved = resolver
+        .resolve_deep(&healthy, &args)
+        .await
+        .expect("resolve");
+    assert_eq!(
+        resolved,
+        json!({"user": "svc", "password": "pw", "replicas": ["svc", "from-env"], "port": 5432})
+    );
+    assert_eq!(
+        *seen.lock().unwrap(),
+        [
+            "POST /v1/auth/approle/login HTTP/1.1",
+            "GET /v1/secret/data/app HTTP/1.1"
+        ]
+    );
+
+    seen.lock().unwrap().clear();
+    let broken = json!({
+        "user": "ref:vault:kv2:secret/app#user",
+        "password": "ref:vault:kv2:secret/moved#password",
+        "token": "ref:vault:kv2:secret/locked#token",
+        "extra": "ref:env:INFRA_TEST_SECRET_REF_UNSET",
+    });
+    let err = resolver
+        .resolve_deep(&broken, &args)
+        .await
+        .expect_err("broken refs");
+    assert_eq!(
+        err.message,
+        "3 secret refs could not be resolved: ref:env:INFRA_TEST_SECRET_REF_UNSET (not_found), ref:vault:kv2:secret/locked#token (permission), ref:vault:kv2:secret/moved#password (not_found)"
+    );
+    let unresolved = err.details.as_ref().unwrap()["unresolved"]
+        .as_array()
+        .unwrap();
+    assert_eq!(unresolved.len(), 3);
+    assert_eq!(unresolved[0]["reason"], "not_found");
+    assert_eq!(
+        unresolved[1]["message"],
+        "Vault request failed (403): permission denied"
+    );
+    let mut calls = seen.lock().unwrap().clone();
+    calls.sort();
+    assert_eq!(
+        calls,
+        [
+            "GET /v1/secret/data/app HTTP/1.1",
+            "GET /v1/secret/data/locked HTTP/1.1",
+            "GET /v1/secret/data/moved HTTP/1.1",
+            "POST /v1/auth/approle/login HTTP/1.1",
+        ]
+    );
+
+    let partial = resolver
+        .resolve_deep_partial(&broken, &args)
+        .await
+        .expect("partial resolve");
+    assert_eq!(
+        partial.value,
+        json!({
+            "user": "svc",
+            "password": "ref:vault:kv2:secret/moved#password",
+            "token": "ref:vault:kv2:secret/locked#token",
+            "extra": "ref:env:INFRA_TEST_SECRET_REF_UNSET",
+        })
+    );
+    let report: Vec<&str> = partial.unresolved.iter().map(|r| r.reason()).collect();
+    assert_eq!(report, ["not_found", "permission", "not_found"]);
+
+    let err = resolver
+        .resolve_deep(
+            &json!({"password": "ref:vault:kv2:secret/moved#password"}),
+            &args,
+        )
+        .await
+        .expect_err("single broken ref");
+    assert_eq!(err.kind, ToolErrorKind::NotFound);
+    assert_eq!(err.message, "Vault request failed (404)");
+    assert_eq!(
+        err.details.unwrap()["unresolved"][0]["ref"],
+        "ref:vault:kv2:secret/moved#password"
+    );
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    restore_env("INFRA_TEST_SECRET_REF", prev_secret);
+    let _ = std::fs::remove_dir_all(&tmp_dir);
+}
+
+#[tokio::test]
+async fn resolved_vault_refs_are_served_from_the_secret_refs_namespace() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+
+    let (port, seen) = spawn_vault_stub();
+    let security = Arc::new(Security::new().expect("security"));
+    let profile_service = Arc::new(ProfileService::new(security).expect("profile service"));
+    profile_service
+        .set_profile(
+            "vault-main",
+            &json!({
+                "type": "vault",
+                "data": {"addr": format!("http://127.0.0.1:{}", port)},
+                "secrets": {"role_id": "role", "secret_id": "secret"},
+            }),
+        )
+        .expect("vault profile");
+    let vault = Arc::new(VaultClient::new(
+        Logger::new("test"),
+        Validation::new(),
+        profile_service.clone(),
+    ));
+    let cache = CacheService::with_backend(
+        Logger::new("test"),
+        CacheBackend::Disk,
+        tmp_dir.join("cache"),
+    );
+    let resolver = SecretRefResolver::new(
+        Logger::new("test"),
+        Validation::new(),
+        Some(profile_service),
+        Some(vault),
+        None,
+    )
+    .with_cache(Arc::new(
+        cache.namespaced("secret_refs").expect("secret namespace"),
+    ));
+    let args = json!({"vault_profile_name": "vault-main"});
+    let input = json!({
+        "user": "ref:vault:kv2:secret/app#user",
+        "password": "ref:vault:kv2:secret/moved#password",
+    });
+
+    for _ in 0..2 {
+        let partial = resolver
+            .resolve_deep_partial(&input, &args)
+            .await
+            .expect("partial resolve");
+        assert_eq!(partial.value["user"], "svc");
+        assert_eq!(partial.unresolved.len(), 1);
+    }
+    // The second pass only retries the failed ref; the resolved one never touches Vault again.
+    let calls: Vec<String> = seen
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|line| line.starts_with("GET"))
+        .cloned()
+        .collect();
+    assert_eq!(
+        calls,
+        [
+            "GET /v1/secret/data/app HTTP/1.1",
+            "GET /v1/secret/data/moved HTTP/1.1",
+            "GET /v1/secret/data/moved HTTP/1.1",
+        ]
+    );
+    let stats = cache.stats();
+    assert_eq!(stats["namespaces"]["secret_refs"]["entries"], 1);
+    assert_eq!(stats["namespaces"]["secret_refs"]["storage"], "memory");
+    assert!(!tmp_dir.join("cache").join("secret_refs").exists());
+
+    cache
+        .invalidate(Some("secret_refs"), None)
+        .expect("invalidate");
+    seen.lock().unwrap().clear();
+    let resolved = resolver
+        .resolve_deep(&json!({"user": "ref:vault:kv2:secret/app#user"}), &args)
+        .await
+        .expect("resolve");
+    assert_eq!(resolved, json!({"user": "svc"}));
+    assert!(seen
+        .lock()
+        .unwrap()
+        .contains(&"GET /v1/secret/data/app HTTP/1.1".to_string()));
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    let _ = std::fs::remove_dir_all(&tmp_dir);
+}
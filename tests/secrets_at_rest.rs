@@ -0,0 +1,351 @@
+use infra::errors::ToolErrorKind;
+use infra::services::alias::AliasService;
+use infra::services::capability::CapabilityService;
+use infra::services::context::ContextService;
+use infra::services::logger::Logger;
+use infra::services::preset::PresetService;
+use infra::services::profile::ProfileService;
+use infra::services::project::ProjectService;
+use infra::services::runbook::RunbookService;
+use infra::services::security::Security;
+use infra::services::state::StateService;
+use infra::services::store_db::StoreDb;
+use infra::services::workspace::WorkspaceService;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+const KEY_A: &str = "0101010101010101010101010101010101010101010101010101010101010101";
+const KEY_B: &str = "0202020202020202020202020202020202020202020202020202020202020202";
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+fn stored_secret(name: &str) -> String {
+    let record = StoreDb::new()
+        .expect("store")
+        .get("profiles", name)
+        .expect("store get")
+        .expect("stored profile");
+    record.value["secrets"]["password"]
+        .as_str()
+        .unwrap_or("")
+        .to_string()
+}
+
+fn stored_state(key: &str) -> Value {
+    StoreDb::new()
+        .expect("store")
+        .get("state:persistent", key)
+        .expect("store get")
+        .expect("stored state")
+        .value
+}
+
+fn workspace(security: Arc<Security>, state_service: Arc<StateService>) -> WorkspaceService {
+    WorkspaceService::new(
+        Logger::new("test"),
+        Arc::new(ContextService::new().expect("context")),
+        None,
+        None,
+        Arc::new(ProfileService::new(security.clone()).expect("profile")),
+        Arc::new(RunbookService::new().expect("runbook")),
+        Arc::new(CapabilityService::new(security).expect("capability")),
+        Arc::new(ProjectService::new().expect("project")),
+        Arc::new(AliasService::new().expect("alias")),
+        Arc::new(PresetService::new().expect("preset")),
+        state_service,
+    )
+}
+
+#[tokio::test]
+async fn profile_secrets_are_sealed_migrated_and_rotated() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let keys = [
+        "INFRA_PROFILES_DIR",
+        "INFRA_MASTER_KEY",
+        "ENCRYPTION_KEY",
+        "INFRA_TEST_NEXT_KEY",
+    ];
+    let previous: Vec<Option<String>> = keys.iter().map(|key| std::env::var(key).ok()).collect();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    // A profiles file from before encryption at rest, secrets in plaintext.
+    std::fs::write(
+        tmp_dir.join("profiles.json"),
+        json!({
+            "legacy": {"type": "ssh", "data": {"host": "web-1"}, "secrets": {"password": "plain-pass"}}
+        })
+        .to_string(),
+    )
+    .expect("write profiles");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    std::env::remove_var("ENCRYPTION_KEY");
+    std::env::set_var("INFRA_MASTER_KEY", KEY_A);
+
+    let security = Arc::new(Security::new().expect("security"));
+    let key_a = security.key_id();
+    let profiles = ProfileService::new(security.clone()).expect("profile service");
+    let legacy = profiles.get_profile("legacy", None).expect("legacy");
+    assert_eq!(legacy["secrets"]["password"], "plain-pass");
+    assert_eq!(stored_secret("legacy"), "plain-pass");
+
+    // The first write seals the secrets it keeps.
+    profiles
+        .set_profile("legacy", &json!({"data": {"port": 22}}))
+        .expect("update legacy");
+    assert!(stored_secret("legacy").starts_with(&format!("enc:v1:{}:", key_a)));
+    profiles
+        .set_profile(
+            "db",
+            &json!({"type": "postgres", "data": {}, "secrets": {"password": "s3cret"}}),
+        )
+        .expect("create db");
+    assert!(!stored_secret("db").contains("s3cret"));
+    let legacy = profiles.get_profile("legacy", None).expect("legacy");
+    assert_eq!(legacy["secrets"]["password"], "plain-pass");
+    assert_eq!(legacy["data"]["port"], 22);
+
+    let state = StateService::new()
+        .expect("state")
+        .with_security(security.clone());
+    let result = state
+        .set_sensitive("deploy/token", json!({"token": "t0k-value"}), None)
+        .expect("sensitive set");
+    assert_eq!(result["sensitive"], true);
+    assert!(!stored_state("deploy/token")
+        .to_string()
+        .contains("t0k-value"));
+    let value = state.get("deploy/token", None).expect("sensitive get");
+    assert_eq!(value["value"]["token"], "t0k-value");
+
+    // A different key, or none at all, refuses to start and leaves the store untouched.
+    let sealed_db = stored_secret("db");
+    for master in [Some(KEY_B), None] {
+        match master {
+            Some(key) => std::env::set_var("INFRA_MASTER_KEY", key),
+            None => std::env::remove_var("INFRA_MASTER_KEY"),
+        }
+        let wrong = Arc::new(Security::new().expect("security"));
+        let err = ProfileService::new(wrong.clone())
+            .err()
+            .expect("unreadable profiles");
+        assert_eq!(err.kind, ToolErrorKind::Denied);
+        assert_eq!(err.code, "SECRET_KEY_MISMATCH");
+        assert_eq!(
+            err.details.as_ref().unwrap()["profiles"],
+            json!(["db", "legacy"])
+        );
+        assert!(err.message.ends_with("db, legacy"), "{}", err.message);
+        let err = StateService::new()
+            .expect("state")
+            .with_security(wrong)
+            .get("deploy/token", None)
+            .expect_err("sealed state");
+        assert_eq!(err.code, "SECRET_KEY_MISMATCH");
+        assert!(err.message.contains(&key_a), "{}", err.message);
+    }
+    assert_eq!(stored_secret("db"), sealed_db);
+
+    std::env::set_var("INFRA_MASTER_KEY", KEY_A);
+    let security = Arc::new(Security::new().expect("security"));
+    let state = Arc::new(
+        StateService::new()
+            .expect("state")
+            .with_security(security.clone()),
+    );
+    let workspace = workspace(security.clone(), state.clone());
+    let err = workspace
+        .secrets_rotate_key(&json!({}))
+        .expect_err("master key needs new_key");
+    assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+    let err = workspace
+        .secrets_rotate_key(&json!({"new_key": KEY_A}))
+        .expect_err("same key");
+    assert_eq!(err.kind, ToolErrorKind::Conflict);
+
+    std::env::set_var("INFRA_TEST_NEXT_KEY", KEY_B);
+    let rotated = workspace
+        .secrets_rotate_key(&json!({"new_key": "ref:env:INFRA_TEST_NEXT_KEY"}))
+        .expect("rotate");
+    assert_eq!(rotated["previous_key_id"], key_a.as_str());
+    assert_eq!(rotated["key_id"], security.key_id().as_str());
+    assert_eq!(rotated["profiles_resealed"], 2);
+    assert_eq!(rotated["state_values_resealed"], 1);
+    assert_eq!(rotated["key_source"], "INFRA_MASTER_KEY");
+    assert!(rotated["restart_note"].is_string());
+
+    std::env::set_var("INFRA_MASTER_KEY", KEY_B);
+    let security = Arc::new(Security::new().expect("security"));
+    let profiles = ProfileService::new(security.clone()).expect("restart with new key");
+    let db = profiles.get_profile("db", None).expect("db");
+    assert_eq!(db["secrets"]["password"], "s3cret");
+    let value = StateService::new()
+        .expect("state")
+        .with_security(security)
+        .get("deploy/token", None)
+        .expect("sensitive get");
+    assert_eq!(value["value"]["token"], "t0k-value");
+
+    std::env::set_var("INFRA_MASTER_KEY", KEY_A);
+    let err = ProfileService::new(Arc::new(Security::new().expect("security")))
+        .err()
+        .expect("old key no longer opens");
+    assert_eq!(err.code, "SECRET_KEY_MISMATCH");
+
+    for (key, value) in keys.iter().zip(previous) {
+        restore_env(key, value);
+    }
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
+
+#[tokio::test]
+async fn a_rotation_that_fails_while_resealing_keeps_every_value_readable() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let keys = ["INFRA_PROFILES_DIR", "INFRA_MASTER_KEY", "ENCRYPTION_KEY"];
+    let previous: Vec<Option<String>> = keys.iter().map(|key| std::env::var(key).ok()).collect();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    std::env::set_var("ENCRYPTION_KEY", KEY_B);
+    let key_b = Security::new().expect("security").key_id();
+    std::env::remove_var("ENCRYPTION_KEY");
+    std::env::remove_var("INFRA_MASTER_KEY");
+
+    // The key file source: rotate replaces the key file itself.
+    let security = Arc::new(Security::new().expect("security"));
+    let key_a = security.key_id();
+    let profiles = ProfileService::new(security.clone()).expect("profile service");
+    profiles
+        .set_profile(
+            "db",
+            &json!({"type": "postgres", "data": {}, "secrets": {"password": "s3cret"}}),
+        )
+        .expect("create db");
+    let state = Arc::new(
+        StateService::new()
+            .expect("state")
+            .with_security(security.clone()),
+    );
+    state
+        .set_sensitive("deploy/token", json!({"token": "t0k-value"}), None)
+        .expect("sensitive set");
+
+    // The store refuses state values sealed with the new key: profiles reseal, state does not.
+    let store_path = StoreDb::new().expect("store").path().to_path_buf();
+    let conn = rusqlite::Connection::open(&store_path).expect("open store");
+    conn.execute_batch(&format!(
+        "CREATE TRIGGER fail_reseal BEFORE UPDATE ON store_entries \
+         WHEN NEW.namespace = 'state:persistent' AND NEW.payload LIKE '%enc:v1:{}:%' \
+         BEGIN SELECT RAISE(ABORT, 'injected store failure'); END;",
+        key_b
+    ))
+    .expect("install trigger");
+
+    let err = workspace(security.clone(), state.clone())
+        .secrets_rotate_key(&json!({"new_key": KEY_B}))
+        .expect_err("reseal fails");
+    assert!(
+        err.message.contains("injected store failure"),
+        "{}",
+        err.message
+    );
+    assert!(err.hint.as_deref().unwrap_or("").contains(".rotating"));
+    assert!(security.rotation_path().exists());
+    assert!(stored_secret("db").starts_with(&format!("enc:v1:{}:", key_b)));
+    assert!(stored_state("deploy/token")["$sealed"]
+        .as_str()
+        .unwrap()
+        .starts_with(&format!("enc:v1:{}:", key_a)));
+
+    // A restart loads the new key from the key file and still opens both generations.
+    let restarted = Arc::new(Security::new().expect("security"));
+    assert_eq!(restarted.key_id(), key_b);
+    let db = ProfileService::new(restarted.clone())
+        .expect("profiles readable after restart")
+        .get_profile("db", None)
+        .expect("db");
+    assert_eq!(db["secrets"]["password"], "s3cret");
+    let state = Arc::new(
+        StateService::new()
+            .expect("state")
+            .with_security(restarted.clone()),
+    );
+    let value = state.get("deploy/token", None).expect("sensitive get");
+    assert_eq!(value["value"]["token"], "t0k-value");
+
+    // Once the store accepts writes again, the same new_key finishes the rotation.
+    conn.execute_batch("DROP TRIGGER fail_reseal;")
+        .expect("drop trigger");
+    let finished = workspace(restarted.clone(), state)
+        .secrets_rotate_key(&json!({"new_key": KEY_B}))
+        .expect("resume rotation");
+    assert_eq!(finished["key_id"], key_b.as_str());
+    assert!(!restarted.rotation_path().exists());
+    assert!(stored_state("deploy/token")["$sealed"]
+        .as_str()
+        .unwrap()
+        .starts_with(&format!("enc:v1:{}:", key_b)));
+
+    for (key, value) in keys.iter().zip(previous) {
+        restore_env(key, value);
+    }
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
+
+#[tokio::test]
+async fn sensitive_state_uses_the_shared_keyring() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let keys = ["INFRA_PROFILES_DIR", "INFRA_MASTER_KEY", "ENCRYPTION_KEY"];
+    let previous: Vec<Option<String>> = keys.iter().map(|key| std::env::var(key).ok()).collect();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    std::env::remove_var("ENCRYPTION_KEY");
+    std::env::set_var("INFRA_MASTER_KEY", KEY_A);
+
+    let err = StateService::new()
+        .expect("state")
+        .set_sensitive("deploy/token", json!("t0k"), None)
+        .expect_err("no shared keyring");
+    assert_eq!(err.kind, ToolErrorKind::Internal);
+    assert!(StoreDb::new()
+        .expect("store")
+        .get("state:persistent", "deploy/token")
+        .expect("store get")
+        .is_none());
+
+    // A rotation through the shared instance is what the next seal uses.
+    let security = Arc::new(Security::new().expect("security"));
+    let state = StateService::new()
+        .expect("state")
+        .with_security(security.clone());
+    security
+        .rotate(&hex::decode(KEY_B).expect("hex"))
+        .expect("rotate");
+    state
+        .set_sensitive("deploy/token", json!("t0k"), None)
+        .expect("sensitive set");
+    assert!(stored_state("deploy/token")["$sealed"]
+        .as_str()
+        .unwrap()
+        .starts_with(&format!("enc:v1:{}:", security.key_id())));
+    assert_eq!(
+        state.get("deploy/token", None).expect("get")["value"],
+        "t0k"
+    );
+
+    for (key, value) in keys.iter().zip(previous) {
+        restore_env(key, value);
+    }
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
@@ -0,0 +1,137 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::ssh::SshManager;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use serde_json::json;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+// Accepts and drops every connection; reaching it proves the credentials were accepted.
+fn dropping_listener() -> (u16, Arc<AtomicUsize>) {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind listener");
+    let port = listener.local_addr().expect("listener addr").port();
+    let accepted = Arc::new(AtomicUsize::new(0));
+    let counter = accepted.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            counter.fetch_add(1, Ordering::SeqCst);
+            drop(stream);
+        }
+    });
+    (port, accepted)
+}
+
+#[tokio::test]
+async fn agent_auth_is_selected_explicitly_or_from_ssh_auth_sock() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let prev_sock = std::env::var("SSH_AUTH_SOCK").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    std::env::remove_var("SSH_AUTH_SOCK");
+
+    let security = Arc::new(Security::new().expect("security"));
+    let profile_service = Arc::new(ProfileService::new(security.clone()).expect("profile service"));
+    let manager = SshManager::new(
+        Logger::new("test"),
+        security,
+        Validation::new(),
+        profile_service.clone(),
+        None,
+        None,
+        None,
+    );
+
+    let (port, accepted) = dropping_listener();
+    let test = |connection: serde_json::Value| {
+        manager.handle_action(json!({
+            "action": "profile_test",
+            "connection": connection,
+            "connect_retry": { "attempts": 1 },
+        }))
+    };
+    let bare = json!({"host": "127.0.0.1", "port": port, "username": "app"});
+
+    let err = test(bare.clone()).await.expect_err("no credentials");
+    assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+    assert!(err.message.contains("auth: 'agent'"), "{}", err.message);
+
+    let mut unsupported = bare.clone();
+    unsupported["auth"] = json!("kerberos");
+    let err = test(unsupported).await.expect_err("unknown auth");
+    assert!(err.message.contains("connection.auth"), "{}", err.message);
+    assert_eq!(accepted.load(Ordering::SeqCst), 0);
+
+    let mut explicit = bare.clone();
+    explicit["auth"] = json!("agent");
+    let err = test(explicit).await.expect_err("peer drops the handshake");
+    assert_ne!(err.kind, ToolErrorKind::InvalidParams, "{}", err.message);
+    assert_eq!(accepted.load(Ordering::SeqCst), 1);
+
+    std::env::set_var("SSH_AUTH_SOCK", tmp_dir.join("agent.sock"));
+    let err = test(bare.clone())
+        .await
+        .expect_err("peer drops the handshake");
+    assert_ne!(err.kind, ToolErrorKind::InvalidParams, "{}", err.message);
+    assert_eq!(accepted.load(Ordering::SeqCst), 2);
+
+    // An unreadable key path is not silently replaced by the agent.
+    let mut key_path = bare.clone();
+    key_path["private_key_path"] = json!(tmp_dir.join("missing_key").to_string_lossy());
+    let err = test(key_path).await.expect_err("key path unreadable");
+    assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+    std::env::remove_var("SSH_AUTH_SOCK");
+
+    let stored = manager
+        .handle_action(json!({
+            "action": "profile_upsert",
+            "profile_name": "bastion",
+            "connection": {
+                "host": "127.0.0.1",
+                "port": port,
+                "username": "app",
+                "agent_key_fingerprint": "SHA256:AbCdEf0123==",
+            },
+            "skip_test": true,
+        }))
+        .await
+        .expect("store agent profile");
+    assert_eq!(stored["profile"]["auth"], "agent");
+    let profile = profile_service
+        .get_profile("bastion", None)
+        .expect("stored profile");
+    assert_eq!(
+        profile["data"]["agent_key_fingerprint"],
+        "SHA256:AbCdEf0123=="
+    );
+
+    // A pinned agent key selects agent auth even without SSH_AUTH_SOCK.
+    let err = manager
+        .handle_action(json!({
+            "action": "profile_test",
+            "profile_name": "bastion",
+            "connect_retry": { "attempts": 1 },
+        }))
+        .await
+        .expect_err("peer drops the handshake");
+    assert_ne!(err.kind, ToolErrorKind::InvalidParams, "{}", err.message);
+    assert_eq!(accepted.load(Ordering::SeqCst), 3);
+
+    restore_env("SSH_AUTH_SOCK", prev_sock);
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
@@ -0,0 +1,107 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::ssh::SshManager;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+// Accepts and immediately drops every connection, so each attempt fails mid-handshake.
+fn dropping_listener() -> (u16, Arc<AtomicUsize>) {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind listener");
+    let port = listener.local_addr().expect("listener addr").port();
+    let accepted = Arc::new(AtomicUsize::new(0));
+    let counter = accepted.clone();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            counter.fetch_add(1, Ordering::SeqCst);
+            drop(stream);
+        }
+    });
+    (port, accepted)
+}
+
+#[tokio::test]
+async fn connect_phase_failures_are_retried_and_counted() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+
+    let security = Arc::new(Security::new().expect("security"));
+    let profile_service = Arc::new(ProfileService::new(security.clone()).expect("profile service"));
+    let manager = SshManager::new(
+        Logger::new("test"),
+        security,
+        Validation::new(),
+        profile_service,
+        None,
+        None,
+        None,
+    );
+
+    let (port, accepted) = dropping_listener();
+    let connection = serde_json::json!({
+        "host": "127.0.0.1",
+        "port": port,
+        "username": "app",
+        "password": "secret",
+        "ready_timeout_ms": 2000,
+    });
+    let err = manager
+        .handle_action(serde_json::json!({
+            "action": "exec",
+            "command": "uptime",
+            "connection": connection,
+            "connect_retry": { "attempts": 3, "delay_ms": 10 },
+        }))
+        .await
+        .expect_err("peer drops every handshake");
+    assert!(err.message.contains("handshake"), "{}", err.message);
+    assert_eq!(
+        err.details.as_ref().and_then(|d| d.get("connect_attempts")),
+        Some(&serde_json::json!(3))
+    );
+    assert_eq!(accepted.load(Ordering::SeqCst), 3);
+
+    let err = manager
+        .handle_action(serde_json::json!({
+            "action": "profile_test",
+            "connection": connection,
+            "connect_retry": { "attempts": 1 },
+        }))
+        .await
+        .expect_err("single attempt");
+    assert_eq!(
+        err.details.as_ref().and_then(|d| d.get("connect_attempts")),
+        Some(&serde_json::json!(1))
+    );
+    assert_eq!(accepted.load(Ordering::SeqCst), 4);
+
+    let err = manager
+        .handle_action(serde_json::json!({
+            "action": "profile_test",
+            "connection": connection,
+            "connect_retry": { "attempts": 0 },
+        }))
+        .await
+        .expect_err("attempts out of range");
+    assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+    assert_eq!(accepted.load(Ordering::SeqCst), 4);
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
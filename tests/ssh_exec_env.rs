@@ -0,0 +1,113 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::ssh::SshManager;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+#[tokio::test]
+async fn exec_env_reaches_the_command_even_when_sshd_refuses_setenv() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+
+    let security = Arc::new(Security::new().expect("security"));
+    let manager = SshManager::new(
+        Logger::new("test"),
+        security.clone(),
+        Validation::new(),
+        Arc::new(ProfileService::new(security).expect("profile service")),
+        None,
+        None,
+        None,
+    );
+    let exec = |connection: Value, extra: Value| {
+        let mut args = json!({
+            "action": "exec",
+            "connection": connection,
+            "command": "printf '%s|%s' \"$INFRA_ENV_TOKEN\" \"$INFRA_ENV_PORT\"",
+        });
+        args.as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        manager.handle_action(args)
+    };
+
+    // Nothing listens on port 1: these fail on their arguments before connecting.
+    let offline = json!({"host": "127.0.0.1", "port": 1, "username": "ops", "password": "x"});
+    for (extra, message) in [
+        (
+            json!({"env": {"A": "1"}, "env_mode": "sendenv"}),
+            "env_mode must be one of: auto, setenv, inline (got sendenv)",
+        ),
+        (
+            json!({"env": {"A B": "1"}}),
+            "env name \"A B\" must match [A-Za-z_][A-Za-z0-9_]*",
+        ),
+    ] {
+        let err = exec(offline.clone(), extra).await.expect_err(message);
+        assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+        assert_eq!(err.message, message);
+    }
+
+    // Set INFRA_TEST_SSH_CONNECTION to a connection object (host, port, username, password or
+    // private_key_path) of a real sshd with the default AcceptEnv (LANG LC_*).
+    if let Some(connection) = std::env::var("INFRA_TEST_SSH_CONNECTION")
+        .ok()
+        .and_then(|raw| serde_json::from_str::<Value>(&raw).ok())
+    {
+        let env = json!({"INFRA_ENV_TOKEN": "tok'en-s3cret", "INFRA_ENV_PORT": 8443});
+        let auto = exec(connection.clone(), json!({"env": env}))
+            .await
+            .expect("auto exec");
+        assert_eq!(auto["exitCode"], 0, "{}", auto);
+        assert_eq!(auto["stdout"], "[REDACTED]|8443");
+        assert_eq!(auto["env"]["mode"], "auto");
+        assert_eq!(auto["env"]["effective"], "inline");
+        assert_eq!(
+            auto["env"]["inlined"],
+            json!(["INFRA_ENV_PORT", "INFRA_ENV_TOKEN"])
+        );
+        assert!(!auto["command"].as_str().unwrap().contains("s3cret"));
+
+        let accepted = manager
+            .handle_action(json!({
+                "action": "exec",
+                "connection": connection,
+                "command": "printf '%s' \"$LC_INFRA_TEST\"",
+                "env": {"LC_INFRA_TEST": "via-setenv", "INFRA_ENV_PORT": 1},
+            }))
+            .await
+            .expect("mixed exec");
+        assert_eq!(accepted["stdout"], "[REDACTED]");
+        assert_eq!(accepted["env"]["effective"], "mixed");
+        assert_eq!(accepted["env"]["inlined"], json!(["INFRA_ENV_PORT"]));
+
+        let err = exec(
+            connection.clone(),
+            json!({"env": env, "env_mode": "setenv"}),
+        )
+        .await
+        .expect_err("strict setenv");
+        assert_eq!(err.kind, ToolErrorKind::Denied);
+        assert!(err.message.contains("AcceptEnv"), "{}", err.message);
+    }
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
@@ -0,0 +1,136 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::ssh::SshManager;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+fn closed_local_port() -> u16 {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind probe port");
+    listener.local_addr().expect("probe addr").port()
+}
+
+#[tokio::test]
+async fn profile_exec_policy_gates_user_commands_but_not_internal_ones() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+
+    let security = Arc::new(Security::new().expect("security"));
+    let profile_service = Arc::new(ProfileService::new(security.clone()).expect("profile service"));
+    profile_service
+        .set_profile(
+            "prod-web",
+            &serde_json::json!({
+                "type": "ssh",
+                "data": {
+                    "host": "127.0.0.1",
+                    "port": closed_local_port(),
+                    "username": "deploy",
+                    "exec_policy": {
+                        "allow": ["^systemctl (status|restart) app$", "^journalctl\\b", "^df\\b"],
+                        "deny": ["rm\\s+-rf"]
+                    }
+                },
+                "secrets": { "password": "pw" }
+            }),
+        )
+        .expect("seed profile");
+    let manager = SshManager::new(
+        Logger::new("test"),
+        security,
+        Validation::new(),
+        profile_service,
+        None,
+        None,
+        None,
+    );
+
+    let err = manager
+        .handle_action(serde_json::json!({
+            "action": "exec",
+            "profile_name": "prod-web",
+            "command": "df -h && rm -rf /srv/app TOKEN=abc123",
+        }))
+        .await
+        .expect_err("deny rule wins");
+    assert_eq!(err.kind, ToolErrorKind::Denied);
+    assert!(err.message.contains("deny[0]"));
+    assert!(!err.message.contains("TOKEN=abc123"));
+    assert_eq!(err.details.as_ref().expect("details")["rule"], "deny[0]");
+
+    let err = manager
+        .handle_action(serde_json::json!({
+            "action": "exec",
+            "profile_name": "prod-web",
+            "command": "cat /etc/passwd",
+        }))
+        .await
+        .expect_err("default deny");
+    assert_eq!(err.kind, ToolErrorKind::Denied);
+    assert!(err.message.contains("profile 'prod-web'"));
+
+    let batch = manager
+        .handle_action(serde_json::json!({
+            "action": "batch",
+            "profile_name": "prod-web",
+            "stop_on_error": false,
+            "commands": [{ "command": "uptime" }],
+        }))
+        .await
+        .expect("batch reports per-command errors");
+    assert!(batch["results"][0]["error"]
+        .as_str()
+        .unwrap_or("")
+        .contains("exec_policy"));
+
+    let allowed = manager
+        .handle_action(serde_json::json!({
+            "action": "exec",
+            "profile_name": "prod-web",
+            "command": "systemctl status app",
+        }))
+        .await
+        .expect_err("allowed command reaches the (closed) host");
+    assert_ne!(allowed.kind, ToolErrorKind::Denied);
+
+    let internal = manager
+        .handle_action(serde_json::json!({ "action": "check_host", "profile_name": "prod-web" }))
+        .await;
+    let internal_error = match internal {
+        Ok(result) => result.to_string(),
+        Err(err) => err.message,
+    };
+    assert!(!internal_error.contains("exec_policy"));
+
+    let err = manager
+        .handle_action(serde_json::json!({
+            "action": "profile_upsert",
+            "profile_name": "broken",
+            "connection": {
+                "host": "127.0.0.1",
+                "username": "deploy",
+                "password": "pw",
+                "exec_policy": { "allow": ["("] }
+            },
+        }))
+        .await
+        .expect_err("invalid regex rejected on upsert");
+    assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+}
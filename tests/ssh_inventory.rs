@@ -0,0 +1,154 @@
+use infra::managers::ssh::SshManager;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use std::sync::Arc;
+use std::time::Instant;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+fn closed_local_port() -> u16 {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind probe port");
+    listener.local_addr().expect("probe addr").port()
+}
+
+// Accepts connections and never speaks, like a host whose sshd hangs.
+fn silent_port() -> u16 {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind silent port");
+    let port = listener.local_addr().expect("silent addr").port();
+    std::thread::spawn(move || {
+        let mut held = Vec::new();
+        for stream in listener.incoming().flatten() {
+            held.push(stream);
+        }
+    });
+    port
+}
+
+#[tokio::test]
+async fn inventory_reports_unreachable_hosts_without_failing() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+
+    let security = Arc::new(Security::new().expect("security"));
+    let profile_service = Arc::new(ProfileService::new(security.clone()).expect("profile service"));
+    for (name, port) in [("refused", closed_local_port()), ("hung", silent_port())] {
+        profile_service
+            .set_profile(
+                name,
+                &serde_json::json!({
+                    "type": "ssh",
+                    "data": { "host": "127.0.0.1", "port": port, "username": "ops" },
+                    "secrets": { "password": "pw" }
+                }),
+            )
+            .expect("seed profile");
+    }
+    let manager = SshManager::new(
+        Logger::new("test"),
+        security,
+        Validation::new(),
+        profile_service,
+        None,
+        None,
+        None,
+    );
+
+    let started = Instant::now();
+    let result = manager
+        .handle_action(serde_json::json!({
+            "action": "inventory",
+            "profiles": "all",
+            "host_timeout_ms": 1500,
+            "concurrency": 2,
+            "trace_id": "inv-trace",
+        }))
+        .await
+        .expect("inventory");
+    assert!(
+        started.elapsed().as_millis() < 10_000,
+        "{:?}",
+        started.elapsed()
+    );
+    assert_eq!(result["source"], "all");
+    assert_eq!(
+        result["stats"],
+        serde_json::json!({"hosts": 2, "reachable": 0, "unreachable": 2, "warning": 0})
+    );
+    let hosts = result["hosts"].as_array().expect("hosts");
+    let by_name = |name: &str| {
+        hosts
+            .iter()
+            .find(|h| h["profile"] == name)
+            .cloned()
+            .expect("host entry")
+    };
+    let refused = by_name("refused");
+    assert_eq!(refused["reachable"], false);
+    assert!(refused["error"].is_string(), "{}", refused);
+    let hung = by_name("hung");
+    assert_eq!(hung["reachable"], false);
+    assert!(hung["error"].is_string(), "{}", hung);
+    assert!(result["details_ref"].is_null());
+
+    let result = manager
+        .handle_action(serde_json::json!({
+            "action": "inventory",
+            "profiles": ["refused", "refused"],
+            "host_timeout_ms": 1500,
+        }))
+        .await
+        .expect("explicit inventory");
+    assert_eq!(result["stats"]["hosts"], 1);
+
+    // A large fleet keeps only summaries inline and writes full host results to an artifact.
+    let prev_context = std::env::var("INFRA_CONTEXT_REPO_ROOT").ok();
+    std::env::set_var("INFRA_CONTEXT_REPO_ROOT", &tmp_dir);
+    let fleet: Vec<String> = (0..21).map(|i| format!("refused-{}", i)).collect();
+    let result = manager
+        .handle_action(serde_json::json!({
+            "action": "inventory",
+            "profiles": fleet,
+            "host_timeout_ms": 1500,
+            "concurrency": 32,
+            "trace_id": "inv-fleet",
+        }))
+        .await
+        .expect("fleet inventory");
+    restore_env("INFRA_CONTEXT_REPO_ROOT", prev_context);
+    assert_eq!(result["stats"]["hosts"], 21);
+    assert_eq!(result["stats"]["unreachable"], 21);
+    assert_eq!(result["hosts"][0]["profile"], "refused-0");
+    assert_eq!(result["hosts"][20]["profile"], "refused-20");
+    assert!(result["hosts"][0].get("duration_ms").is_none());
+    let uri = result["details_ref"].as_str().expect("details ref");
+    let rel = uri.strip_prefix("artifact://").expect("artifact uri");
+    let details: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(tmp_dir.join("artifacts").join(rel)).expect("read details"),
+    )
+    .expect("details json");
+    assert_eq!(details.as_array().map(|hosts| hosts.len()), Some(21));
+    assert!(details[0]["error"].is_string());
+
+    let err = manager
+        .handle_action(serde_json::json!({"action": "inventory"}))
+        .await
+        .expect_err("selection required");
+    assert!(err.message.contains("profiles"), "{}", err.message);
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    let _ = std::fs::remove_dir_all(&tmp_dir);
+}
@@ -0,0 +1,116 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::ssh::SshManager;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+fn closed_local_port() -> u16 {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind probe port");
+    listener.local_addr().expect("probe addr").port()
+}
+
+#[tokio::test]
+async fn jump_hops_resolve_per_hop_and_report_the_failing_hop() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+
+    let security = Arc::new(Security::new().expect("security"));
+    let profile_service = Arc::new(ProfileService::new(security.clone()).expect("profile service"));
+    let bastion_port = closed_local_port();
+    profile_service
+        .set_profile(
+            "bastion",
+            &serde_json::json!({
+                "type": "ssh",
+                "data": { "host": "127.0.0.1", "port": bastion_port, "username": "jump" },
+                "secrets": { "password": "bastion-pass" }
+            }),
+        )
+        .expect("seed bastion profile");
+    let manager = SshManager::new(
+        Logger::new("test"),
+        security,
+        Validation::new(),
+        profile_service,
+        None,
+        None,
+        None,
+    );
+    let target = serde_json::json!({
+        "host": "10.0.0.5",
+        "username": "app",
+        "password": "target-pass",
+    });
+
+    let err = manager
+        .handle_action(serde_json::json!({
+            "action": "profile_test",
+            "connection": target,
+            "jump": { "profile_name": "bastion" },
+        }))
+        .await
+        .expect_err("closed bastion port fails on the first hop");
+    assert!(
+        err.message.starts_with(&format!(
+            "SSH jump hop 1/1 (jump@127.0.0.1:{})",
+            bastion_port
+        )),
+        "unexpected message: {}",
+        err.message
+    );
+
+    let err = manager
+        .handle_action(serde_json::json!({
+            "action": "profile_test",
+            "connection": target,
+            "jump": ["bastion", { "profile_name": "missing-hop" }],
+        }))
+        .await
+        .expect_err("unknown hop profile");
+    assert_eq!(err.kind, ToolErrorKind::NotFound);
+    assert!(err.message.starts_with("jump[1]:"));
+
+    let err = manager
+        .handle_action(serde_json::json!({
+            "action": "profile_test",
+            "connection": target,
+            "jump": { "connection": { "host": "127.0.0.1", "username": "jump" } },
+        }))
+        .await
+        .expect_err("hop without credentials");
+    assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+    assert!(err.message.starts_with("jump[0]:"));
+
+    let err = manager
+        .handle_action(serde_json::json!({
+            "action": "profile_upsert",
+            "profile_name": "inner",
+            "connection": {
+                "host": "10.0.0.5",
+                "username": "app",
+                "password": "target-pass",
+                "jump": { "connection": { "host": "127.0.0.1", "username": "jump", "password": "x" } }
+            },
+        }))
+        .await
+        .expect_err("inline hop secrets are not stored");
+    assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+}
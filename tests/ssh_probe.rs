@@ -0,0 +1,114 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::ssh::SshManager;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use serde_json::json;
+use std::io::{Read, Write};
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+fn closed_local_port() -> u16 {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind probe port");
+    listener.local_addr().expect("probe addr").port()
+}
+
+// Sends a pre-banner line and an identification string, then hangs up on the key exchange.
+fn spawn_banner_only_server() -> u16 {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind stub");
+    let port = listener.local_addr().expect("stub addr").port();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { continue };
+            let _ = stream.write_all(b"Authorized use only\r\nSSH-2.0-StubSSH_1.2 probe-test\r\n");
+            let mut buf = [0u8; 1024];
+            let _ = stream.read(&mut buf);
+        }
+    });
+    port
+}
+
+#[tokio::test]
+async fn probe_reports_each_stage_without_credentials() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+
+    let security = Arc::new(Security::new().expect("security"));
+    let profile_service = Arc::new(ProfileService::new(security.clone()).expect("profile service"));
+    let manager = SshManager::new(
+        Logger::new("test"),
+        security,
+        Validation::new(),
+        profile_service,
+        None,
+        None,
+        None,
+    );
+
+    // No username or key: the probe never gets to authentication.
+    let port = spawn_banner_only_server();
+    let probed = manager
+        .handle_action(json!({
+            "action": "probe",
+            "connection": {"host": "localhost", "port": port},
+            "timeout_ms": 2000,
+        }))
+        .await
+        .expect("probe");
+    assert_eq!(probed["success"], false, "{}", probed);
+    assert!(probed["dns"]["addresses"]
+        .as_array()
+        .expect("addresses")
+        .iter()
+        .any(|addr| addr == "127.0.0.1"));
+    assert_eq!(probed["tcp"]["ok"], true);
+    assert!(probed["tcp"]["connect_ms"].is_number());
+    assert_eq!(probed["banner"]["banner"], "SSH-2.0-StubSSH_1.2 probe-test");
+    assert_eq!(probed["banner"]["protocol_version"], "2.0");
+    assert_eq!(probed["banner"]["software"], "StubSSH_1.2");
+    assert_eq!(probed["failed"]["stage"], "host_key", "{}", probed);
+    assert!(probed.get("pin").is_none());
+
+    let probed = manager
+        .handle_action(json!({
+            "action": "probe",
+            "connection": {"host": "127.0.0.1", "port": closed_local_port()},
+        }))
+        .await
+        .expect("probe closed port");
+    assert_eq!(probed["success"], false);
+    assert_eq!(probed["failed"]["stage"], "tcp");
+    assert_eq!(probed["tcp"]["attempts"][0]["ok"], false);
+    assert!(probed.get("banner").is_none());
+
+    let err = manager
+        .handle_action(json!({"action": "probe", "connection": {"port": 22}}))
+        .await
+        .expect_err("host is required");
+    assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+    let err = manager
+        .handle_action(json!({"action": "probe"}))
+        .await
+        .expect_err("a target is required");
+    assert_eq!(
+        err.message,
+        "probe requires connection.host or profile_name"
+    );
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
@@ -0,0 +1,87 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::ssh::SshManager;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use serde_json::json;
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+#[tokio::test]
+async fn scratch_dir_is_validated_before_anything_runs_remotely() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let prev_scratch = std::env::var("INFRA_SSH_SCRATCH_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    std::env::remove_var("INFRA_SSH_SCRATCH_DIR");
+
+    let security = Arc::new(Security::new().expect("security"));
+    let profile_service = Arc::new(ProfileService::new(security.clone()).expect("profile service"));
+    profile_service
+        .set_profile(
+            "appliance",
+            &json!({
+                "type": "ssh",
+                "data": { "host": "127.0.0.1", "port": 1, "username": "ops", "scratch_dir": "var/tmp" },
+                "secrets": { "password": "pw" }
+            }),
+        )
+        .expect("seed profile");
+    let manager = SshManager::new(
+        Logger::new("test"),
+        security,
+        Validation::new(),
+        profile_service,
+        None,
+        None,
+        None,
+    );
+
+    let err = manager
+        .handle_action(json!({
+            "action": "exec_detached",
+            "profile_name": "appliance",
+            "command": "true",
+            "stdin": "secret",
+        }))
+        .await
+        .expect_err("relative profile scratch_dir");
+    assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+    assert_eq!(err.message, "scratch_dir must be an absolute directory");
+
+    std::env::set_var("INFRA_SSH_SCRATCH_DIR", "scratch");
+    let err = manager
+        .handle_action(json!({
+            "action": "jobs_gc",
+            "connection": { "host": "127.0.0.1", "port": 1, "username": "ops", "password": "pw" },
+        }))
+        .await
+        .expect_err("relative env scratch dir");
+    assert_eq!(
+        err.message,
+        "INFRA_SSH_SCRATCH_DIR must be an absolute directory"
+    );
+
+    let forgotten = manager
+        .handle_action(json!({"action": "job_forget", "job_id": "missing", "cleanup": true}))
+        .await
+        .expect("forget unknown job");
+    assert_eq!(forgotten["code"], "NOT_FOUND");
+
+    restore_env("INFRA_SSH_SCRATCH_DIR", prev_scratch);
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    let _ = std::fs::remove_dir_all(&tmp_dir);
+}
@@ -0,0 +1,100 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::ssh::SshManager;
+use infra::services::job::JobService;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+fn closed_local_port() -> u16 {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind probe port");
+    listener.local_addr().expect("probe addr").port()
+}
+
+#[tokio::test]
+async fn background_transfer_returns_job_id_and_records_the_outcome() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    let local_path = tmp_dir.join("payload.bin");
+    std::fs::write(&local_path, vec![0u8; 1024]).expect("payload");
+
+    let security = Arc::new(Security::new().expect("security"));
+    let profile_service = Arc::new(ProfileService::new(security.clone()).expect("profile service"));
+    let job_service = Arc::new(JobService::new(Logger::new("test")).expect("jobs"));
+    let manager = SshManager::new(
+        Logger::new("test"),
+        security,
+        Validation::new(),
+        profile_service,
+        None,
+        None,
+        Some(job_service.clone()),
+    );
+    let connection = serde_json::json!({
+        "host": "127.0.0.1",
+        "port": closed_local_port(),
+        "username": "deploy",
+        "password": "pw",
+    });
+
+    let err = manager
+        .handle_action(serde_json::json!({
+            "action": "sftp_upload",
+            "connection": connection,
+            "local_path": local_path,
+            "remote_path": "/tmp/payload.bin",
+            "max_rate_bps": 0,
+        }))
+        .await
+        .expect_err("rate must be positive");
+    assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+
+    let started = manager
+        .handle_action(serde_json::json!({
+            "action": "sftp_upload",
+            "connection": connection,
+            "local_path": local_path,
+            "remote_path": "/tmp/payload.bin",
+            "background": true,
+            "max_rate_bps": 4096,
+            "trace_id": "trace-transfer",
+        }))
+        .await
+        .expect("background upload");
+    assert_eq!(started["background"], true);
+    let job_id = started["job_id"].as_str().expect("job_id").to_string();
+    assert_eq!(started["transfer_id"], job_id.as_str());
+
+    let mut job = job_service.get(&job_id).expect("job");
+    for _ in 0..100 {
+        if job["status"] != "running" {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        job = job_service.get(&job_id).expect("job");
+    }
+    assert_eq!(job["kind"], "ssh_transfer");
+    assert_eq!(job["status"], "failed", "{}", job);
+    assert_eq!(job["trace_id"], "trace-transfer");
+    assert_eq!(job["progress"]["direction"], "upload");
+    assert_eq!(job["progress"]["max_rate_bps"], 4096);
+    assert!(job["error"].as_str().is_some());
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
@@ -0,0 +1,137 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::jobs::JobManager;
+use infra::managers::ssh::SshManager;
+use infra::services::job::JobService;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::validation::Validation;
+use serde_json::json;
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+fn closed_local_port() -> u16 {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind probe port");
+    listener.local_addr().expect("probe addr").port()
+}
+
+fn manager(job_service: Option<Arc<JobService>>) -> SshManager {
+    let security = Arc::new(Security::new().expect("security"));
+    let profile_service = Arc::new(ProfileService::new(security.clone()).expect("profile service"));
+    SshManager::new(
+        Logger::new("test"),
+        security,
+        Validation::new(),
+        profile_service,
+        None,
+        None,
+        job_service,
+    )
+}
+
+#[tokio::test]
+async fn watch_path_validates_and_runs_background_watches_as_local_jobs() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let keys = ["INFRA_PROFILES_DIR"];
+    let previous: Vec<Option<String>> = keys.iter().map(|key| std::env::var(key).ok()).collect();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+
+    let connection = json!({
+        "host": "127.0.0.1",
+        "port": closed_local_port(),
+        "username": "deploy",
+        "password": "pw",
+    });
+    let foreground = manager(None);
+    for (args, message) in [
+        (json!({}), "remote_path is required"),
+        (
+            json!({"remote_path": "/in/a.csv", "poll_interval_ms": 10}),
+            "poll_interval_ms must be an integer >= 250",
+        ),
+        (
+            json!({"remote_path": "/in/a.csv", "mtime_after": 1, "size_stable_for_ms": 1000}),
+            "watch_path takes one condition: mtime_after or size_stable_for_ms",
+        ),
+        (
+            json!({"remote_path": "/in/a.csv", "background": true}),
+            "background watches require the job service",
+        ),
+    ] {
+        let mut args = args;
+        args["action"] = json!("watch_path");
+        args["connection"] = connection.clone();
+        let err = foreground
+            .handle_action(args)
+            .await
+            .expect_err("invalid watch");
+        assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+        assert_eq!(err.message, message);
+    }
+
+    // A poll that cannot reach the host fails the call rather than reporting condition_met=false.
+    foreground
+        .handle_action(json!({
+            "action": "watch_path",
+            "connection": connection,
+            "remote_path": "/in/*.csv",
+            "timeout_ms": 2000,
+        }))
+        .await
+        .expect_err("unreachable host");
+
+    let job_service = Arc::new(JobService::new(Logger::new("test")).expect("jobs"));
+    let jobs = JobManager::new(
+        Logger::new("test"),
+        Validation::new(),
+        job_service.clone(),
+        None,
+    );
+    let started = manager(Some(job_service.clone()))
+        .handle_action(json!({
+            "action": "watch_path",
+            "connection": connection,
+            "remote_path": "/in/orders-*.csv",
+            "size_stable_for_ms": 5000,
+            "background": true,
+            "trace_id": "trace-watch",
+        }))
+        .await
+        .expect("background watch");
+    assert_eq!(started["background"], true);
+    assert_eq!(started["condition"]["type"], "size_stable_for_ms");
+    let followed = jobs
+        .handle_action(json!({
+            "action": "follow_job",
+            "job_id": started["job_id"],
+            "timeout_ms": 10_000,
+            "poll_interval_ms": 50,
+        }))
+        .await
+        .expect("follow job");
+    assert_eq!(followed["wait"]["completed"], true, "{}", followed);
+    assert_eq!(followed["job"]["raw_status"], "failed");
+    let job = job_service
+        .get(started["job_id"].as_str().unwrap())
+        .expect("job");
+    assert_eq!(job["kind"], "ssh_watch");
+    assert_eq!(job["trace_id"], "trace-watch");
+    assert_eq!(job["provider"]["remote_path"], "/in/orders-*.csv");
+
+    for (key, value) in keys.iter().zip(previous) {
+        restore_env(key, value);
+    }
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
@@ -0,0 +1,155 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::state::StateManager;
+use infra::services::logger::Logger;
+use infra::services::project::ProjectService;
+use infra::services::project_resolver::ProjectResolver;
+use infra::services::state::StateService;
+use infra::services::tool_executor::{ToolExecutor, ToolHandler};
+use infra::services::validation::Validation;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+#[tokio::test]
+async fn project_scope_namespaces_stored_results_per_target() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+
+    let logger = Logger::new("test");
+    let validation = Validation::new();
+    let state_service = Arc::new(StateService::new().expect("state"));
+    let project_service = Arc::new(ProjectService::new().expect("project"));
+    project_service
+        .set_project(
+            "shop",
+            &serde_json::json!({"targets": {"stage": {}, "prod": {}}}),
+        )
+        .expect("project");
+    let resolver = Arc::new(ProjectResolver::new(
+        validation,
+        project_service,
+        Some(state_service.clone()),
+    ));
+    let state = StateManager::new(logger.clone(), state_service.clone())
+        .with_project_resolver(resolver.clone());
+    let mut handlers: HashMap<String, Arc<dyn ToolHandler>> = HashMap::new();
+    handlers.insert("state".to_string(), Arc::new(state.clone()));
+    let executor = ToolExecutor::new(
+        logger,
+        state_service.clone(),
+        None,
+        None,
+        handlers,
+        HashMap::new(),
+    )
+    .with_project_resolver(resolver);
+
+    state_service
+        .set("migration_id", serde_json::json!(42), Some("session"))
+        .expect("seed");
+    let payload = executor
+        .execute(
+            "state",
+            serde_json::json!({
+                "action": "get",
+                "key": "migration_id",
+                "scope": "session",
+                "project": "shop",
+                "target": "stage",
+                "store_as": "last_migration",
+                "store_scope": "project",
+            }),
+        )
+        .await
+        .expect("stage call");
+    assert_eq!(
+        payload["meta"]["stored_key"],
+        "project/shop/stage/last_migration"
+    );
+
+    let stage = state
+        .handle_action(serde_json::json!({
+            "action": "get",
+            "key": "last_migration",
+            "scope": "project",
+            "project": "shop",
+            "target": "stage",
+        }))
+        .await
+        .expect("stage get");
+    assert_eq!(stage["scope"], "project");
+    assert_eq!(stage["value"]["value"], 42);
+    let prod = state
+        .handle_action(serde_json::json!({
+            "action": "get",
+            "key": "last_migration",
+            "scope": "project",
+            "project": "shop",
+            "target": "prod",
+        }))
+        .await
+        .expect("prod get");
+    assert!(prod["value"].is_null());
+    let global = state
+        .handle_action(serde_json::json!({"action": "get", "key": "last_migration"}))
+        .await
+        .expect("global get");
+    assert!(global["value"].is_null());
+
+    let listed = state
+        .handle_action(serde_json::json!({"action": "list", "project": "shop", "target": "stage"}))
+        .await
+        .expect("list");
+    assert_eq!(
+        listed["items"][0]["key"],
+        "project/shop/stage/last_migration"
+    );
+    assert_eq!(listed["namespace"], "project/shop/stage");
+
+    let err = executor
+        .execute(
+            "state",
+            serde_json::json!({
+                "action": "set",
+                "key": "side_effect",
+                "value": 1,
+                "scope": "session",
+                "store_as": "result",
+                "store_scope": "project",
+            }),
+        )
+        .await
+        .expect_err("no project resolvable");
+    assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+    assert!(
+        err.message.contains("store_scope=project"),
+        "{}",
+        err.message
+    );
+    let side = state_service
+        .get("side_effect", Some("session"))
+        .expect("side effect get");
+    assert!(side["value"].is_null(), "handler must not run");
+
+    let err = state
+        .handle_action(serde_json::json!({"action": "get", "key": "x", "scope": "project"}))
+        .await
+        .expect_err("project scope without project");
+    assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
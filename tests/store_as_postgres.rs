@@ -0,0 +1,222 @@
+use infra::errors::{ToolError, ToolErrorKind};
+use infra::managers::postgres::PostgresManager;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::state::StateService;
+use infra::services::tool_executor::{ToolExecutor, ToolHandler};
+use infra::services::validation::Validation;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+struct SmokeHandler;
+
+#[async_trait::async_trait]
+impl ToolHandler for SmokeHandler {
+    async fn handle(&self, _args: Value) -> Result<Value, ToolError> {
+        Ok(json!({
+            "success": true,
+            "smoke": {"status": 200, "stats": {"p95_ms": 120}},
+            "release": "v42",
+        }))
+    }
+}
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+fn executor() -> ToolExecutor {
+    let logger = Logger::new("test");
+    let security = Arc::new(Security::new().expect("security"));
+    let profile_service = Arc::new(ProfileService::new(security).expect("profile service"));
+    let postgres = Arc::new(PostgresManager::new(
+        logger.clone(),
+        Validation::new(),
+        profile_service,
+        None,
+        None,
+    ));
+    let mut handlers: HashMap<String, Arc<dyn ToolHandler>> = HashMap::new();
+    handlers.insert("sql".to_string(), postgres);
+    handlers.insert("smoke".to_string(), Arc::new(SmokeHandler));
+    ToolExecutor::new(
+        logger,
+        Arc::new(StateService::new().expect("state")),
+        None,
+        None,
+        handlers,
+        HashMap::new(),
+    )
+}
+
+fn store_call(postgres: Value) -> Value {
+    json!({"action": "check", "store_as": {"postgres": postgres}})
+}
+
+#[tokio::test]
+async fn store_as_postgres_appends_rows_without_failing_the_call() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let keys = ["INFRA_PROFILES_DIR"];
+    let previous: Vec<Option<String>> = keys.iter().map(|key| std::env::var(key).ok()).collect();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    let executor = executor();
+
+    // A malformed spec is rejected before the tool runs.
+    for (spec, message) in [
+        (
+            json!({"profile_name": "ops", "table": "deploys"}),
+            "store_as.postgres.mapping must be a non-empty object",
+        ),
+        (
+            json!({"table": "deploys", "mapping": {"status": "smoke.status"}}),
+            "store_as.postgres needs profile_name or target",
+        ),
+        (
+            json!({"profile_name": "ops", "table": "deploys", "mapping": {"status": "smoke.status"}, "missing": "drop"}),
+            "store_as.postgres.missing must be error, skip or null",
+        ),
+    ] {
+        let err = executor
+            .execute("smoke", store_call(spec))
+            .await
+            .expect_err("invalid spec");
+        assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+        assert_eq!(err.message, message);
+    }
+
+    // With the default missing=error a path the result lacks stores nothing; the call still
+    // succeeds and says why.
+    let unreachable = "postgres://app@127.0.0.1:1/app";
+    let response = executor
+        .execute(
+            "smoke",
+            store_call(json!({
+                "connection_url": unreachable,
+                "table": "deploys",
+                "mapping": {"status": "smoke.status", "p99": "smoke.stats.p99_ms"},
+            })),
+        )
+        .await
+        .expect("call succeeds");
+    assert_eq!(response["ok"], true);
+    assert_eq!(response["result"]["release"], "v42");
+    assert_eq!(
+        response["store_error"]["message"],
+        "store_as.postgres.mapping.p99: path 'smoke.stats.p99_ms' not found in the result"
+    );
+    let response = executor
+        .execute(
+            "smoke",
+            store_call(json!({
+                "connection_url": unreachable,
+                "table": "deploys",
+                "mapping": {"status": "smoke.status"},
+            })),
+        )
+        .await
+        .expect("call succeeds");
+    assert_eq!(response["ok"], true);
+    assert!(response["store_error"]["message"].is_string());
+
+    // Set INFRA_TEST_POSTGRES_URLS (comma-separated) to store rows on live servers.
+    let urls = std::env::var("INFRA_TEST_POSTGRES_URLS").unwrap_or_default();
+    for url in urls.split(',').map(str::trim).filter(|url| !url.is_empty()) {
+        let table = format!("infra_store_{}", uuid::Uuid::new_v4().simple());
+        let spec = |missing: &str, create_table: &str| {
+            json!({
+                "connection_url": url,
+                "table": table,
+                "mapping": {
+                    "release": "release",
+                    "status": "smoke.status",
+                    "p95_ms": "smoke.stats.p95_ms",
+                    "p99_ms": "smoke.stats.p99_ms",
+                },
+                "missing": missing,
+                "create_table": create_table,
+            })
+        };
+
+        let response = executor
+            .execute("smoke", store_call(spec("null", "never")))
+            .await
+            .expect("call succeeds");
+        assert_eq!(response["store_error"]["code"], "NOT_FOUND", "{}", response);
+        assert!(response["store_error"]["hint"]
+            .as_str()
+            .unwrap()
+            .contains("if_missing"));
+
+        for (missing, create_table) in [("null", "if_missing"), ("skip", "never")] {
+            let mut call = store_call(spec(missing, create_table));
+            call["trace_id"] = json!(format!("trace-{}", missing));
+            let response = executor.execute("smoke", call).await.expect("stored");
+            assert!(response.get("store_error").is_none(), "{}", response);
+        }
+
+        let stored = executor
+            .execute(
+                "sql",
+                json!({
+                    "action": "query",
+                    "connection_url": url,
+                    "sql": format!(
+                        "SELECT release, status, p95_ms, p99_ms, trace_id, created_at IS NOT NULL AS dated FROM \"{}\" ORDER BY trace_id",
+                        table
+                    ),
+                }),
+            )
+            .await
+            .expect("read rows");
+        let rows = &stored["result"]["rows"];
+        assert_eq!(
+            rows.as_array().map(|rows| rows.len()),
+            Some(2),
+            "{}",
+            stored
+        );
+        for (row, trace) in rows
+            .as_array()
+            .unwrap()
+            .iter()
+            .zip(["trace-null", "trace-skip"])
+        {
+            assert_eq!(row["release"], "v42");
+            assert_eq!(row["status"], 200);
+            assert_eq!(row["p95_ms"], 120);
+            assert_eq!(row["p99_ms"], Value::Null);
+            assert_eq!(row["trace_id"], trace);
+            assert_eq!(row["dated"], true);
+        }
+
+        executor
+            .execute(
+                "sql",
+                json!({
+                    "action": "query",
+                    "connection_url": url,
+                    "sql": format!("DROP TABLE \"{}\"", table),
+                    "apply": true,
+                    "confirm": true,
+                }),
+            )
+            .await
+            .expect("drop table");
+    }
+
+    for (key, value) in keys.iter().zip(previous) {
+        restore_env(key, value);
+    }
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
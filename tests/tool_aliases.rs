@@ -1,10 +1,14 @@
 mod common;
 use common::ENV_LOCK;
 
+use infra::errors::ToolErrorKind;
+use infra::services::alias::AliasService;
 use infra::services::logger::Logger;
 use infra::services::state::StateService;
 use infra::services::tool_executor::{ToolExecutor, ToolHandler};
+use infra::tooling::catalog::{effects_catalog, versions_catalog};
 use infra::tooling::names::{builtin_tool_alias_map_owned, canonical_tool_name};
+use infra::utils::listing::ListFilters;
 use serde_json::Value;
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -76,3 +80,178 @@ async fn short_alias_call_resolves_to_canonical_handler() {
         Some(true)
     );
 }
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+#[tokio::test]
+async fn parameterized_alias_substitutes_named_and_positional_arguments() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+
+    let alias_service = Arc::new(AliasService::new().expect("alias service"));
+    alias_service
+        .set_alias(
+            "deploy_web",
+            &serde_json::json!({
+                "tool": "ssh",
+                "positional": ["app", "version"],
+                "args": {
+                    "action": "deploy_file",
+                    "local_path": "./dist/{{app}}-{{version}}",
+                    "remote_path": "/srv/{{app}}/bin/app",
+                    "restart": "{{?restart}}"
+                }
+            }),
+        )
+        .expect("set alias");
+
+    let mut handlers: HashMap<String, Arc<dyn ToolHandler>> = HashMap::new();
+    handlers.insert("ssh".to_string(), Arc::new(DummyHandler));
+    let executor = ToolExecutor::new(
+        Logger::new("test"),
+        Arc::new(StateService::new().expect("state")),
+        Some(alias_service.clone()),
+        None,
+        handlers,
+        HashMap::new(),
+    );
+
+    let payload = executor
+        .execute(
+            "deploy_web",
+            serde_json::json!({ "argv": ["web", "1.2.0"], "apply": true }),
+        )
+        .await
+        .expect("positional call");
+    let args = payload.pointer("/result/args").expect("handler args");
+    assert_eq!(args["remote_path"], "/srv/web/bin/app");
+    assert_eq!(args["local_path"], "./dist/web-1.2.0");
+    assert_eq!(args["restart"], "");
+    assert!(args.get("app").is_none());
+    assert!(args.get("argv").is_none());
+
+    let payload = executor
+        .execute(
+            "deploy_web",
+            serde_json::json!({ "app": "api", "version": "2", "apply": true }),
+        )
+        .await
+        .expect("named call");
+    assert_eq!(
+        payload.pointer("/result/args/remote_path"),
+        Some(&Value::String("/srv/api/bin/app".to_string()))
+    );
+
+    let err = executor
+        .execute(
+            "deploy_web",
+            serde_json::json!({ "argv": ["web"], "apply": true }),
+        )
+        .await
+        .expect_err("missing variable should fail");
+    assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+    assert!(err
+        .message
+        .contains("missing template variable(s): version"));
+
+    let err = executor
+        .execute(
+            "deploy_web",
+            serde_json::json!({ "argv": ["a", "b", "c"], "apply": true }),
+        )
+        .await
+        .expect_err("too many positional arguments should fail");
+    assert!(err.message.contains("at most 2 positional"));
+
+    let listed = alias_service
+        .list_aliases(&ListFilters::from_args(
+            &serde_json::json!({ "query": "/srv/" }),
+        ))
+        .expect("list aliases");
+    let items = listed["aliases"].as_array().cloned().unwrap_or_default();
+    assert_eq!(items.len(), 1);
+    assert_eq!(
+        items[0]["variables"],
+        serde_json::json!(["app", "version", "restart"])
+    );
+    assert_eq!(
+        items[0]["positional"],
+        serde_json::json!(["app", "version"])
+    );
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+}
+
+#[tokio::test]
+async fn deprecated_action_alias_still_executes_and_carries_a_warning() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let logger = Logger::new("test");
+    let state_service = Arc::new(StateService::new().expect("state"));
+    let mut handlers: HashMap<String, Arc<dyn ToolHandler>> = HashMap::new();
+    handlers.insert("ssh".to_string(), Arc::new(DummyHandler));
+    let executor = ToolExecutor::new(
+        logger,
+        state_service,
+        None,
+        None,
+        handlers,
+        builtin_tool_alias_map_owned(),
+    );
+
+    let payload = executor
+        .execute(
+            "ssh",
+            serde_json::json!({"action": "connect", "profile_name": "web"}),
+        )
+        .await
+        .expect("deprecated alias should still execute");
+    assert_eq!(payload["result"]["handled"], true);
+    let warnings = payload["meta"]["warnings"].as_array().expect("warnings");
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0]["code"], "DEPRECATED_ACTION");
+    assert_eq!(warnings[0]["replacement"], "ssh action=profile_test");
+
+    let payload = executor
+        .execute(
+            "ssh",
+            serde_json::json!({"action": "profile_test", "profile_name": "web"}),
+        )
+        .await
+        .expect("replacement executes");
+    assert!(payload["meta"].get("warnings").is_none());
+
+    let ssh = effects_catalog(Some("ssh"));
+    let connect = ssh[0]["actions"]
+        .as_array()
+        .expect("actions")
+        .iter()
+        .find(|entry| entry["action"] == "connect")
+        .cloned()
+        .expect("connect entry");
+    assert_eq!(connect["deprecated"]["removal_planned"], "8.0.0");
+
+    let versions = versions_catalog();
+    assert_eq!(versions["crate_version"], env!("CARGO_PKG_VERSION"));
+    let ssh_version = versions["tools"]
+        .as_array()
+        .expect("tools")
+        .iter()
+        .find(|tool| tool["tool"] == "ssh")
+        .cloned()
+        .expect("ssh version");
+    assert_eq!(
+        ssh_version["contract_version"].as_str().map(str::len),
+        Some(12)
+    );
+    assert_eq!(ssh_version["deprecated"][0]["action"], "connect");
+}
@@ -1,5 +1,32 @@
-use infra::tooling::effects::resolve_tool_call_effects;
-use serde_json::json;
+use infra::errors::ToolErrorKind;
+use infra::services::logger::Logger;
+use infra::services::state::StateService;
+use infra::services::tool_executor::{ToolExecutor, ToolHandler};
+use infra::tooling::catalog::effects_catalog;
+use infra::tooling::effects::{resolve_steps_effects, resolve_tool_call_effects};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+#[derive(Clone)]
+struct EchoHandler;
+
+#[async_trait::async_trait]
+impl ToolHandler for EchoHandler {
+    async fn handle(&self, args: Value) -> Result<Value, infra::errors::ToolError> {
+        Ok(json!({ "success": true, "args": args }))
+    }
+}
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
 
 #[test]
 fn state_set_session_is_write_without_apply() {
@@ -78,6 +105,31 @@ fn api_request_post_is_write_requires_apply() {
     assert!(!effects.effects.irreversible);
 }
 
+#[test]
+fn project_foreach_inherits_the_fanned_out_call_effects() {
+    let read = resolve_tool_call_effects(
+        "project",
+        &json!({
+            "action": "project_foreach",
+            "projects": ["a", "b"],
+            "call": { "tool": "api", "action": "request", "args": { "method": "GET", "url": "/health" } }
+        }),
+    );
+    assert_eq!(read.effects.kind.as_deref(), Some("read"));
+    assert!(!read.effects.requires_apply);
+
+    let write = resolve_tool_call_effects(
+        "project",
+        &json!({
+            "action": "project_foreach",
+            "projects": ["a", "b"],
+            "call": { "tool": "api", "action": "request", "args": { "method": "DELETE", "url": "/items/1" } }
+        }),
+    );
+    assert!(write.effects.requires_apply);
+    assert!(write.effects.irreversible);
+}
+
 #[test]
 fn api_request_delete_is_irreversible_and_requires_apply() {
     let effects = resolve_tool_call_effects(
@@ -122,6 +174,24 @@ fn psql_query_drop_is_irreversible() {
     assert!(effects.effects.irreversible);
 }
 
+#[test]
+fn psql_dry_run_writes_are_read_without_apply() {
+    for args in [
+        json!({ "action": "update", "table": "users", "data": {"x": 1}, "dry_run": true }),
+        json!({ "action": "delete", "table": "users", "dry_run": true }),
+        json!({ "action": "query", "sql": "DELETE FROM users", "dry_run": true }),
+    ] {
+        let effects = resolve_tool_call_effects("sql", &args);
+        assert_eq!(effects.effects.kind.as_deref(), Some("read"), "{}", args);
+        assert!(!effects.effects.requires_apply);
+    }
+    let effects = resolve_tool_call_effects(
+        "sql",
+        &json!({ "action": "delete", "table": "users", "dry_run": false }),
+    );
+    assert_eq!(effects.effects.kind.as_deref(), Some("write"));
+}
+
 #[test]
 fn compatibility_only_capability_and_runbook_actions_do_not_require_apply() {
     for (tool, action) in [
@@ -159,3 +229,182 @@ fn canonical_receipt_policy_profile_target_actions_are_read_only() {
         assert!(!effects.effects.irreversible, "{tool}");
     }
 }
+
+#[test]
+fn ssh_batch_of_read_only_commands_does_not_require_apply() {
+    let effects = resolve_tool_call_effects(
+        "ssh",
+        &json!({
+            "action": "batch",
+            "commands": [
+                { "command": "uptime" },
+                { "command": "df -h | grep /srv 2>&1" },
+                { "command": "systemctl status nginx" }
+            ]
+        }),
+    );
+    assert_eq!(effects.effects.kind.as_deref(), Some("read"));
+    assert!(!effects.effects.requires_apply);
+    assert!(effects.trigger.is_none());
+}
+
+#[test]
+fn ssh_batch_names_the_command_that_requires_apply() {
+    let effects = resolve_tool_call_effects(
+        "ssh",
+        &json!({
+            "action": "batch",
+            "commands": [
+                { "command": "uptime" },
+                { "command": "sudo systemctl restart nginx" },
+                { "command": "echo done > /tmp/marker" }
+            ]
+        }),
+    );
+    assert_eq!(effects.effects.kind.as_deref(), Some("write"));
+    assert!(effects.effects.requires_apply);
+    assert_eq!(
+        effects.trigger.as_deref(),
+        Some("commands[1]: sudo systemctl restart nginx")
+    );
+}
+
+#[test]
+fn pipeline_run_is_classified_by_flow_sink() {
+    let effects = resolve_tool_call_effects(
+        "pipeline",
+        &json!({ "action": "run", "flow": "http_to_sftp", "http": { "url": "https://x" } }),
+    );
+    assert_eq!(effects.effects.kind.as_deref(), Some("write"));
+    assert!(effects.effects.requires_apply);
+    assert_eq!(effects.trigger.as_deref(), Some("sink sftp"));
+
+    let effects = resolve_tool_call_effects(
+        "pipeline",
+        &json!({ "action": "run", "flow": "postgres_to_http", "http": { "method": "DELETE" } }),
+    );
+    assert!(effects.effects.irreversible);
+    assert_eq!(effects.effects.class(), "destructive");
+
+    let effects = resolve_tool_call_effects(
+        "pipeline",
+        &json!({ "action": "run", "flow": "postgres_to_http", "dry_run": true, "http": { "body_template": { "id": "{{record.id}}" } } }),
+    );
+    assert_eq!(effects.effects.kind.as_deref(), Some("read"));
+    assert!(!effects.effects.requires_apply);
+
+    let effects = resolve_tool_call_effects(
+        "pipeline",
+        &json!({ "action": "run", "flow": "http_to_postgres", "postgres": { "table": "t", "create_table": "replace" } }),
+    );
+    assert!(effects.effects.irreversible);
+    assert_eq!(effects.trigger.as_deref(), Some("sink postgres"));
+
+    let effects = resolve_tool_call_effects(
+        "sql",
+        &json!({ "action": "insert_bulk", "table": "t", "create_table": "if_missing" }),
+    );
+    assert!(effects.effects.requires_apply);
+    assert!(!effects.effects.irreversible);
+    let effects = resolve_tool_call_effects(
+        "sql",
+        &json!({ "action": "insert_bulk", "table": "t", "create_table": "replace" }),
+    );
+    assert!(effects.effects.irreversible);
+}
+
+#[test]
+fn runbook_steps_effects_name_the_triggering_step() {
+    let effects = resolve_steps_effects(
+        "workspace.run",
+        &[
+            json!({ "id": "probe", "tool": "ssh", "args": { "action": "check_host" } }),
+            json!({
+                "id": "restart",
+                "tool": "ssh",
+                "args": { "action": "batch", "commands": [{ "command": "systemctl restart app" }] }
+            }),
+        ],
+    );
+    assert!(effects.effects.requires_apply);
+    assert_eq!(
+        effects.trigger.as_deref(),
+        Some("restart (ssh.batch → commands[0]: systemctl restart app)")
+    );
+}
+
+#[test]
+fn effects_catalog_lists_action_classes() {
+    let catalog = effects_catalog(Some("ssh"));
+    let actions = catalog[0]["actions"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default();
+    let class_of = |action: &str| {
+        actions
+            .iter()
+            .find(|entry| entry["action"] == action)
+            .and_then(|entry| entry["class"].as_str())
+            .map(|s| s.to_string())
+    };
+    assert_eq!(class_of("profile_list").as_deref(), Some("read"));
+    assert_eq!(class_of("sftp_upload").as_deref(), Some("write"));
+    assert_eq!(class_of("profile_delete").as_deref(), Some("destructive"));
+}
+
+#[tokio::test]
+async fn readonly_mode_denies_write_classified_batches_and_names_the_step() {
+    let _guard = ENV_LOCK.lock().await;
+    let prev_readonly = std::env::var("INFRA_READONLY").ok();
+
+    let mut handlers: HashMap<String, Arc<dyn ToolHandler>> = HashMap::new();
+    handlers.insert("ssh".to_string(), Arc::new(EchoHandler));
+    let executor = ToolExecutor::new(
+        Logger::new("test"),
+        Arc::new(StateService::new().expect("state")),
+        None,
+        None,
+        handlers,
+        HashMap::new(),
+    );
+    let write_batch = json!({
+        "action": "batch",
+        "commands": [{ "command": "uptime" }, { "command": "rm -rf /srv/cache" }]
+    });
+
+    std::env::remove_var("INFRA_READONLY");
+    let err = executor
+        .execute("ssh", write_batch.clone())
+        .await
+        .expect_err("write batch requires apply");
+    assert_eq!(err.kind, ToolErrorKind::Denied);
+    assert!(err.message.contains("apply=true"));
+    assert!(err.message.contains("commands[1]: rm -rf /srv/cache"));
+
+    std::env::set_var("INFRA_READONLY", "1");
+    let mut applied = write_batch.clone();
+    applied["apply"] = json!(true);
+    let err = executor
+        .execute("ssh", applied)
+        .await
+        .expect_err("readonly mode denies writes even with apply");
+    assert_eq!(err.kind, ToolErrorKind::Denied);
+    assert!(err.message.contains("INFRA_READONLY=1"));
+    assert!(err.message.contains("commands[1]"));
+    let details = err.details.clone().unwrap_or(Value::Null);
+    assert_eq!(
+        details["effects"]["trigger"],
+        "commands[1]: rm -rf /srv/cache"
+    );
+
+    let ok = executor
+        .execute(
+            "ssh",
+            json!({ "action": "batch", "commands": [{ "command": "uptime" }] }),
+        )
+        .await
+        .expect("read-only batch runs without apply in readonly mode");
+    assert_eq!(ok["meta"]["effects"]["kind"], "read");
+
+    restore_env("INFRA_READONLY", prev_readonly);
+}
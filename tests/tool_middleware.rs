@@ -0,0 +1,201 @@
+use async_trait::async_trait;
+use infra::app::App;
+use infra::errors::{ToolError, ToolErrorKind};
+use infra::services::middleware::{MiddlewareCall, ToolMiddleware};
+use serde_json::{json, Value};
+use std::io::{Read, Write};
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+// Tags every api request with the org header and leaves the org in the call metadata.
+struct OrgHeader;
+
+#[async_trait]
+impl ToolMiddleware for OrgHeader {
+    fn name(&self) -> &str {
+        "org_header"
+    }
+
+    async fn before(&self, call: &mut MiddlewareCall, args: Value) -> Result<Value, ToolError> {
+        call.metadata.insert("org".to_string(), json!("acme"));
+        let mut args = args;
+        if call.tool == "api" {
+            if !args["headers"].is_object() {
+                args["headers"] = json!({});
+            }
+            args["headers"]["X-Org-Id"] = json!("acme");
+        }
+        Ok(args)
+    }
+
+    async fn after(&self, call: &mut MiddlewareCall, result: Value) -> Result<Value, ToolError> {
+        let mut result = result;
+        if call.tool == "api" {
+            result["org_checked"] = json!(true);
+        }
+        Ok(result)
+    }
+}
+
+// Refuses sql deletes without a ticket; the ticket moves from the args into the metadata.
+struct TicketRequired;
+
+#[async_trait]
+impl ToolMiddleware for TicketRequired {
+    fn name(&self) -> &str {
+        "ticket_required"
+    }
+
+    async fn before(&self, call: &mut MiddlewareCall, args: Value) -> Result<Value, ToolError> {
+        if call.tool != "sql" || call.action.as_deref() != Some("delete") {
+            return Ok(args);
+        }
+        let mut args = args;
+        let ticket = args
+            .as_object_mut()
+            .and_then(|map| map.remove("ticket_id"))
+            .and_then(|v| v.as_str().map(str::to_string));
+        let Some(ticket) = ticket else {
+            return Err(ToolError::new(
+                ToolErrorKind::Denied,
+                "TICKET_REQUIRED",
+                "sql delete needs a ticket_id",
+            )
+            .with_details(json!({"org": call.metadata.get("org")})));
+        };
+        call.metadata.insert("ticket_id".to_string(), json!(ticket));
+        Ok(args)
+    }
+}
+
+// Answers each request with the X-Org-Id header it received.
+fn spawn_echo_stub(responses: usize) -> u16 {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind stub");
+    let port = listener.local_addr().expect("stub addr").port();
+    std::thread::spawn(move || {
+        for stream in listener.incoming().take(responses) {
+            let Ok(mut stream) = stream else { continue };
+            let mut buf = [0u8; 8192];
+            let read = stream.read(&mut buf).unwrap_or(0);
+            let head = String::from_utf8_lossy(&buf[..read]).to_string();
+            let org = head
+                .lines()
+                .find_map(|line| {
+                    let (name, value) = line.split_once(':')?;
+                    name.eq_ignore_ascii_case("x-org-id")
+                        .then(|| value.trim().to_string())
+                })
+                .unwrap_or_default();
+            let body = json!({"org": org}).to_string();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+    port
+}
+
+fn audit_entry(app: &App, trace_id: &str) -> Value {
+    let entries = app
+        .audit_service
+        .read_entries(10, 0, true, &json!({"trace_id": trace_id}))
+        .expect("audit entries");
+    entries["entries"][0].clone()
+}
+
+#[tokio::test]
+async fn middleware_rewrites_requests_and_denies_calls() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    let app = App::initialize_with_middleware(vec![Arc::new(OrgHeader), Arc::new(TicketRequired)])
+        .expect("app");
+    assert_eq!(
+        app.tool_executor.chain(),
+        vec![
+            "alias",
+            "presets",
+            "org_header.before",
+            "ticket_required.before",
+            "validation",
+            "effects_gate",
+            "dry_run",
+            "handler",
+            "ticket_required.after",
+            "org_header.after",
+            "redaction",
+            "audit",
+        ]
+    );
+
+    let port = spawn_echo_stub(1);
+    let response = app
+        .tool_executor
+        .execute(
+            "api",
+            json!({
+                "action": "request",
+                "method": "GET",
+                "url": format!("http://127.0.0.1:{}/whoami", port),
+                "trace_id": "mw-api",
+            }),
+        )
+        .await
+        .expect("api request");
+    assert_eq!(response["result"]["data"]["org"], "acme", "{}", response);
+    assert_eq!(response["result"]["org_checked"], true);
+    assert_eq!(audit_entry(&app, "mw-api")["middleware"]["org"], "acme");
+
+    let delete = json!({
+        "action": "delete",
+        "connection_url": "postgres://app@127.0.0.1:1/app",
+        "table": "users",
+        "filters": {"id": 1},
+        "apply": true,
+        "confirm": true,
+        "trace_id": "mw-denied",
+    });
+    let err = app
+        .tool_executor
+        .execute("psql", delete.clone())
+        .await
+        .expect_err("no ticket");
+    assert_eq!(err.kind, ToolErrorKind::Denied);
+    assert_eq!(err.code, "TICKET_REQUIRED");
+    assert_eq!(err.details.as_ref().expect("details")["org"], "acme");
+    let denied = audit_entry(&app, "mw-denied");
+    assert_eq!(denied["status"], "error");
+    assert_eq!(denied["error"]["code"], "TICKET_REQUIRED");
+
+    // With a ticket the call goes on to the handler (and the unreachable database).
+    let mut ticketed = delete;
+    ticketed["ticket_id"] = json!("OPS-42");
+    ticketed["trace_id"] = json!("mw-ticketed");
+    let err = app
+        .tool_executor
+        .execute("psql", ticketed)
+        .await
+        .expect_err("database is unreachable");
+    assert_ne!(err.code, "TICKET_REQUIRED");
+    let failed = audit_entry(&app, "mw-ticketed");
+    assert_eq!(failed["middleware"]["ticket_id"], "OPS-42");
+    assert!(failed["input"].get("ticket_id").is_none());
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
@@ -0,0 +1,219 @@
+use flate2::read::GzDecoder;
+use infra::app::App;
+use infra::utils::artifacts::resolve_artifact_path;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::io::Read;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+// Just enough ustar reading to check the bundle: path -> content.
+fn untar(gz: &[u8]) -> BTreeMap<String, Vec<u8>> {
+    let mut tar = Vec::new();
+    GzDecoder::new(gz).read_to_end(&mut tar).expect("gunzip");
+    let field = |block: &[u8]| {
+        let end = block.iter().position(|b| *b == 0).unwrap_or(block.len());
+        String::from_utf8_lossy(&block[..end]).to_string()
+    };
+    let mut out = BTreeMap::new();
+    let mut offset = 0;
+    while offset + 512 <= tar.len() && tar[offset] != 0 {
+        let header = &tar[offset..offset + 512];
+        let (name, prefix) = (field(&header[..100]), field(&header[345..500]));
+        let path = if prefix.is_empty() {
+            name
+        } else {
+            format!("{}/{}", prefix, name)
+        };
+        let size = usize::from_str_radix(field(&header[124..136]).trim(), 8).expect("size");
+        out.insert(path, tar[offset + 512..offset + 512 + size].to_vec());
+        offset += 512 + size.div_ceil(512) * 512;
+    }
+    out
+}
+
+#[tokio::test]
+async fn export_trace_bundles_calls_artifacts_and_evidence() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let keys = [
+        "INFRA_PROFILES_DIR",
+        "INFRA_CONTEXT_REPO_ROOT",
+        "INFRA_EVIDENCE_DIR",
+        "INFRA_RESULT_ARTIFACTS",
+    ];
+    let previous: Vec<Option<String>> = keys.iter().map(|key| std::env::var(key).ok()).collect();
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    let context_root = tmp_dir.join("context");
+    std::fs::create_dir_all(&context_root).expect("create context dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    std::env::set_var("INFRA_CONTEXT_REPO_ROOT", &context_root);
+    std::env::set_var("INFRA_EVIDENCE_DIR", tmp_dir.join("evidence"));
+    std::env::set_var("INFRA_RESULT_ARTIFACTS", "1");
+    let app = App::initialize().expect("app");
+    let trace = "deploy-42";
+
+    app.tool_executor
+        .execute(
+            "state",
+            json!({"action": "set", "key": "release", "value": "v42", "scope": "session", "trace_id": trace, "span_id": "s-set"}),
+        )
+        .await
+        .expect("state set");
+    app.tool_executor
+        .execute(
+            "artifacts",
+            json!({"action": "get", "uri": "artifact://runs/elsewhere/missing.json", "trace_id": trace, "span_id": "s-bad"}),
+        )
+        .await
+        .expect_err("missing artifact");
+
+    // A captured stdout that leaked a token and is larger than the per-file cap.
+    let stdout = resolve_artifact_path(
+        &context_root,
+        &format!("runs/{}/tool_calls/s-set/stdout.log", trace),
+    )
+    .expect("stdout path");
+    std::fs::create_dir_all(stdout.parent().unwrap()).unwrap();
+    let leaked = format!(
+        "deploying with ghp_{}\n{}",
+        "a".repeat(36),
+        "log line\n".repeat(400)
+    );
+    std::fs::write(&stdout, &leaked).unwrap();
+    std::fs::create_dir_all(tmp_dir.join("evidence")).unwrap();
+    std::fs::write(
+        tmp_dir.join("evidence").join("evidence-1.json"),
+        json!({"kind": "record", "trace_ids": [trace], "summary": "release checked"}).to_string(),
+    )
+    .unwrap();
+    std::fs::write(
+        tmp_dir.join("evidence").join("evidence-2.json"),
+        json!({"kind": "record", "trace_ids": ["other"]}).to_string(),
+    )
+    .unwrap();
+
+    let exported = app
+        .tool_executor
+        .execute(
+            "audit",
+            json!({"action": "export_trace", "trace_id": trace, "max_file_bytes": 2048}),
+        )
+        .await
+        .expect("export")["result"]
+        .clone();
+    assert_eq!(exported["success"], true, "{}", exported);
+    assert_eq!(exported["summary"]["calls"], 2, "{}", exported);
+    assert_eq!(exported["summary"]["errors"], 1);
+    assert_eq!(exported["summary"]["evidence"], 1);
+    assert_eq!(exported["summary"]["truncated_files"], 1);
+    assert_eq!(exported["summary"]["skipped_files"], 0);
+    let rel = exported["bundle"]["rel"].as_str().expect("rel");
+    assert!(rel.starts_with(&format!("runs/{}/trace-bundle-", trace)));
+    assert!(rel.ends_with(".tar.gz"));
+
+    let archive = std::fs::read(resolve_artifact_path(&context_root, rel).unwrap()).unwrap();
+    assert_eq!(exported["bundle"]["bytes"], archive.len());
+    let files = untar(&archive);
+    let root = format!("trace-{}", trace);
+    let manifest: Value =
+        serde_json::from_slice(&files[&format!("{}/manifest.json", root)]).expect("manifest");
+    assert_eq!(manifest["format"], "infra.trace_bundle");
+    assert_eq!(manifest["version"], 1);
+    assert_eq!(manifest["summary"], exported["summary"]);
+
+    let calls = manifest["calls"].as_array().expect("calls");
+    let set = calls.iter().find(|c| c["span_id"] == "s-set").expect("set");
+    let bad = calls.iter().find(|c| c["span_id"] == "s-bad").expect("bad");
+    assert_eq!(bad["error_code"], "NOT_FOUND");
+    let stdout_path = format!(
+        "{}/artifacts/runs/{}/tool_calls/s-set/stdout.log",
+        root, trace
+    );
+    assert!(set["files"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|f| f == stdout_path.as_str()));
+    assert!(set["files"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|f| f.as_str().unwrap().ends_with("/s-set/result.json")));
+
+    let audit = String::from_utf8(files[&format!("{}/audit.jsonl", root)].clone()).unwrap();
+    assert_eq!(audit.lines().count(), 2);
+
+    let bundled = String::from_utf8(files[&stdout_path].clone()).unwrap();
+    assert!(bundled.starts_with("deploying with ghp_***REDACTED***"));
+    assert!(bundled.ends_with(&format!(
+        "[infra export_trace: truncated, kept 2048 of {} bytes]\n",
+        leaked.len() - 22
+    )));
+    let entry = manifest["files"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|f| f["path"] == stdout_path.as_str())
+        .expect("stdout entry");
+    assert_eq!(entry["truncated"], true);
+    assert_eq!(
+        entry["source"],
+        format!("artifact://runs/{}/tool_calls/s-set/stdout.log", trace)
+    );
+
+    let evidence: Value =
+        serde_json::from_slice(&files[&format!("{}/evidence/evidence-1.json", root)]).unwrap();
+    assert_eq!(evidence["summary"], "release checked");
+    assert!(!files.contains_key(&format!("{}/evidence/evidence-2.json", root)));
+
+    // A tight total cap lists what no longer fits; earlier bundles are never bundled again.
+    let tight = app
+        .tool_executor
+        .execute(
+            "audit",
+            json!({"action": "export_trace", "trace_id": trace, "max_total_bytes": 1}),
+        )
+        .await
+        .expect("tight export")["result"]
+        .clone();
+    assert_eq!(tight["summary"]["files"], 0, "{}", tight);
+    let tight_files = untar(
+        &std::fs::read(
+            resolve_artifact_path(&context_root, tight["bundle"]["rel"].as_str().unwrap()).unwrap(),
+        )
+        .unwrap(),
+    );
+    let tight_manifest: Value =
+        serde_json::from_slice(&tight_files[&format!("{}/manifest.json", root)]).unwrap();
+    assert!(tight_manifest["files"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .all(|f| f["skipped"] == true
+            && f["path"].is_null()
+            && !f["source"].as_str().unwrap_or("").contains("trace-bundle-")));
+
+    let err = app
+        .tool_executor
+        .execute(
+            "audit",
+            json!({"action": "export_trace", "trace_id": "never-ran"}),
+        )
+        .await
+        .expect_err("unknown trace");
+    assert_eq!(err.kind, infra::errors::ToolErrorKind::NotFound);
+
+    for (key, value) in keys.iter().zip(previous) {
+        restore_env(key, value);
+    }
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
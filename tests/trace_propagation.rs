@@ -0,0 +1,111 @@
+use infra::errors::ToolErrorKind;
+use infra::managers::audit::AuditManager;
+use infra::managers::ssh::SshManager;
+use infra::services::audit::AuditService;
+use infra::services::logger::Logger;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use infra::services::state::StateService;
+use infra::services::tool_executor::{ToolExecutor, ToolHandler};
+use infra::services::validation::Validation;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+fn closed_local_port() -> u16 {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind probe port");
+    listener.local_addr().expect("probe addr").port()
+}
+
+#[tokio::test]
+async fn batch_commands_run_in_child_spans_and_audit_trace_renders_the_tree() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let prev_audit = std::env::var("INFRA_AUDIT_PATH").ok();
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    std::env::set_var("INFRA_AUDIT_PATH", tmp_dir.join("audit.jsonl"));
+
+    let logger = Logger::new("test");
+    let security = Arc::new(Security::new().expect("security"));
+    let profile_service = Arc::new(ProfileService::new(security.clone()).expect("profile service"));
+    let audit_service = Arc::new(AuditService::new(logger.clone()));
+    let ssh = SshManager::new(
+        logger.clone(),
+        security,
+        Validation::new(),
+        profile_service,
+        None,
+        None,
+        None,
+    )
+    .with_audit_service(audit_service.clone());
+    let mut handlers: HashMap<String, Arc<dyn ToolHandler>> = HashMap::new();
+    handlers.insert("ssh".to_string(), Arc::new(ssh));
+    let executor = ToolExecutor::new(
+        logger.clone(),
+        Arc::new(StateService::new().expect("state")),
+        None,
+        Some(audit_service.clone()),
+        handlers,
+        HashMap::new(),
+    );
+
+    let _ = executor
+        .execute(
+            "ssh",
+            serde_json::json!({
+                "action": "batch",
+                "connection": {
+                    "host": "127.0.0.1",
+                    "port": closed_local_port(),
+                    "username": "deploy",
+                    "password": "pw",
+                },
+                "commands": [{"command": "uptime"}, {"command": "df -h"}],
+                "stop_on_error": false,
+                "trace_id": "trace-batch",
+                "span_id": "span-batch",
+            }),
+        )
+        .await;
+
+    let audit = AuditManager::new(logger, audit_service);
+    let err = audit
+        .handle_action(serde_json::json!({"action": "audit_trace"}))
+        .await
+        .expect_err("trace_id required");
+    assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+
+    let traced = audit
+        .handle_action(serde_json::json!({"action": "audit_trace", "trace_id": "trace-batch"}))
+        .await
+        .expect("audit_trace");
+    let roots = traced["spans"].as_array().expect("spans");
+    assert_eq!(roots.len(), 1, "{}", traced);
+    assert_eq!(roots[0]["span_id"], "span-batch");
+    assert_eq!(roots[0]["action"], "batch");
+    let children = roots[0]["children"].as_array().expect("children");
+    assert_eq!(children.len(), 2);
+    for child in children {
+        assert_eq!(child["parent_span_id"], "span-batch");
+        assert_eq!(child["tool"], "ssh");
+        assert_eq!(child["status"], "error");
+        assert_ne!(child["span_id"], "span-batch");
+    }
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    restore_env("INFRA_AUDIT_PATH", prev_audit);
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
@@ -0,0 +1,272 @@
+use infra::app::App;
+use infra::errors::ToolErrorKind;
+use infra::services::profile::ProfileService;
+use infra::services::security::Security;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+async fn workspace(app: &App, args: Value) -> Result<Value, infra::errors::ToolError> {
+    app.tool_executor
+        .execute("workspace", args)
+        .await
+        .map(|response| response["result"].clone())
+}
+
+// A second service on the same store, for what the tools only show masked.
+fn profiles() -> ProfileService {
+    ProfileService::new(Arc::new(Security::new().expect("security"))).expect("profile service")
+}
+
+async fn exported(app: &App) -> Value {
+    workspace(app, json!({"action": "config_export"}))
+        .await
+        .expect("export")["document"]
+        .clone()
+}
+
+fn changes(result: &Value) -> Vec<(String, String)> {
+    let mut out: Vec<(String, String)> = result["items"]
+        .as_array()
+        .expect("items")
+        .iter()
+        .map(|item| {
+            (
+                format!(
+                    "{}.{}",
+                    item["section"].as_str().unwrap(),
+                    item["name"].as_str().unwrap()
+                ),
+                item["change"].as_str().unwrap().to_string(),
+            )
+        })
+        .collect();
+    out.sort();
+    out
+}
+
+#[tokio::test]
+async fn config_apply_is_idempotent_and_export_round_trips() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let prev_export = std::env::var("INFRA_ALLOW_SECRET_EXPORT").ok();
+    std::env::remove_var("INFRA_ALLOW_SECRET_EXPORT");
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    let app = App::initialize().expect("app");
+
+    let document = json!({
+        "version": 1,
+        "profiles": {
+            "prod-db": {
+                "type": "postgres",
+                "data": {"host": "db.internal", "port": 5432, "database": "app"},
+                "secrets": {"password": "ref:env:PROD_DB_PASSWORD"},
+            },
+        },
+        "projects": {"shop": {"description": "storefront", "targets": {"prod": {"postgres_profile": "prod-db"}}}},
+        // `web` extends `base`, which sorts after it: applied on the second pass.
+        "presets": {
+            "web": {"tool": "api", "extends": ["base"], "data": {"timeout_ms": 2000}},
+            "base": {"tool": "api", "data": {"headers": {"X-Team": "shop"}}},
+        },
+        "aliases": {"health": {"tool": "api", "args": {"action": "request", "url": "https://shop.example/health"}}},
+    });
+    let applied = workspace(
+        &app,
+        json!({"action": "config_apply", "document": document.clone()}),
+    )
+    .await
+    .expect("apply");
+    assert_eq!(applied["success"], true, "{}", applied);
+    assert_eq!(applied["summary"]["create"], 5, "{}", applied);
+    assert!(applied["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .all(|item| item["status"] == "applied"));
+    let profile = profiles()
+        .get_profile("prod-db", Some("postgres"))
+        .expect("profile");
+    assert_eq!(profile["secrets"]["password"], "ref:env:PROD_DB_PASSWORD");
+
+    let again = workspace(
+        &app,
+        json!({"action": "config_apply", "document": document.clone()}),
+    )
+    .await
+    .expect("apply again");
+    assert_eq!(again["summary"]["unchanged"], 5, "{}", again);
+    assert_eq!(again["summary"]["create"], 0);
+    assert_eq!(again["summary"]["update"], 0);
+
+    // A stored literal secret exports as a placeholder and survives the round trip.
+    profiles()
+        .set_profile(
+            "legacy-api",
+            &json!({"type": "api", "data": {"base_url": "https://legacy.example"}, "secrets": {"token": "s3cret"}}),
+        )
+        .expect("legacy profile");
+    let export_path = tmp_dir.join("infra-config.json");
+    let export = workspace(
+        &app,
+        json!({"action": "config_export", "document_path": export_path.display().to_string()}),
+    )
+    .await
+    .expect("export");
+    assert_eq!(
+        export["placeholders"],
+        json!(["profiles.legacy-api.secrets.token"])
+    );
+    let written = std::fs::read_to_string(&export_path).expect("exported file");
+    assert!(!written.contains("s3cret"));
+    assert!(written.contains("ref:env:PROD_DB_PASSWORD"));
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&export_path)
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    let round_trip = workspace(
+        &app,
+        json!({"action": "config_apply", "document_path": export_path.display().to_string()}),
+    )
+    .await
+    .expect("apply export");
+    assert_eq!(round_trip["summary"]["unchanged"], 6, "{}", round_trip);
+    let legacy = profiles().get_profile("legacy-api", None).expect("legacy");
+    assert_eq!(legacy["secrets"]["token"], "s3cret");
+
+    // Plan, then prune what the document no longer lists.
+    let mut trimmed = document.clone();
+    trimmed["aliases"] = json!({});
+    trimmed["presets"]["web"]["data"]["timeout_ms"] = json!(5000);
+    let plan = workspace(
+        &app,
+        json!({"action": "config_apply", "document": trimmed.clone(), "prune": true, "plan_only": true}),
+    )
+    .await
+    .expect("plan");
+    assert_eq!(
+        changes(&plan),
+        vec![
+            ("aliases.health".to_string(), "delete".to_string()),
+            ("presets.base".to_string(), "unchanged".to_string()),
+            ("presets.web".to_string(), "update".to_string()),
+            ("profiles.legacy-api".to_string(), "delete".to_string()),
+            ("profiles.prod-db".to_string(), "unchanged".to_string()),
+            ("projects.shop".to_string(), "unchanged".to_string()),
+        ]
+    );
+    assert!(exported(&app).await["aliases"].get("health").is_some());
+
+    let pruned = workspace(
+        &app,
+        json!({"action": "config_apply", "document": trimmed, "prune": true, "apply": true, "confirm": true}),
+    )
+    .await
+    .expect("prune");
+    assert_eq!(pruned["summary"]["delete"], 2, "{}", pruned);
+    let after = exported(&app).await;
+    assert_eq!(after["aliases"], json!({}));
+    assert!(after["profiles"].get("legacy-api").is_none());
+    assert_eq!(after["presets"]["web"]["data"]["timeout_ms"], 5000);
+
+    restore_env("INFRA_ALLOW_SECRET_EXPORT", prev_export);
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
+
+#[tokio::test]
+async fn config_apply_rejects_literal_secrets_and_yaml() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let prev_export = std::env::var("INFRA_ALLOW_SECRET_EXPORT").ok();
+    std::env::remove_var("INFRA_ALLOW_SECRET_EXPORT");
+    let tmp_dir = std::env::temp_dir().join(format!("infra-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    let app = App::initialize().expect("app");
+
+    let document = json!({
+        "profiles": {"db": {"type": "postgres", "data": {"host": "db"}, "secrets": {"password": "hunter2"}}},
+        "aliases": {"call": {"tool": "api", "args": {"headers": {"Authorization": "Bearer abc"}}}},
+    });
+    let err = workspace(
+        &app,
+        json!({"action": "config_apply", "document": document.clone()}),
+    )
+    .await
+    .expect_err("literal secrets");
+    assert_eq!(err.kind, ToolErrorKind::InvalidParams);
+    assert_eq!(err.code, "RAW_SECRET_IN_CONFIG");
+    assert_eq!(
+        err.details.as_ref().expect("details")["paths"],
+        json!([
+            "aliases.call.args.headers.Authorization",
+            "profiles.db.secrets.password"
+        ])
+    );
+    assert!(exported(&app).await["profiles"].get("db").is_none());
+
+    std::env::set_var("INFRA_ALLOW_SECRET_EXPORT", "1");
+    let applied = workspace(
+        &app,
+        json!({"action": "config_apply", "document": document}),
+    )
+    .await
+    .expect("break-glass apply");
+    assert_eq!(applied["summary"]["create"], 2, "{}", applied);
+    std::env::remove_var("INFRA_ALLOW_SECRET_EXPORT");
+
+    let err = workspace(
+        &app,
+        json!({"action": "config_apply", "document": {"profiles": {"new": {"type": "api", "secrets": {"token": "<stored-secret>"}}}}}),
+    )
+    .await
+    .expect("nothing stored");
+    assert_eq!(err["summary"]["failed"], 1);
+    assert_eq!(
+        err["items"][0]["error"],
+        "profiles.new.secrets.token has no stored value to keep"
+    );
+
+    let err = workspace(
+        &app,
+        json!({"action": "config_apply", "document": {"servers": {}}}),
+    )
+    .await
+    .expect_err("unknown section");
+    assert_eq!(err.message, "Unknown config section 'servers'");
+    let err = workspace(
+        &app,
+        json!({"action": "config_apply", "document_path": tmp_dir.join("infra.yaml").display().to_string()}),
+    )
+    .await
+    .expect_err("yaml");
+    assert!(
+        err.message.ends_with("is YAML; config documents are JSON"),
+        "{}",
+        err.message
+    );
+
+    restore_env("INFRA_ALLOW_SECRET_EXPORT", prev_export);
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
@@ -0,0 +1,245 @@
+use infra::services::alias::AliasService;
+use infra::services::audit::AuditService;
+use infra::services::capability::CapabilityService;
+use infra::services::context::ContextService;
+use infra::services::job::JobService;
+use infra::services::logger::Logger;
+use infra::services::preset::PresetService;
+use infra::services::profile::ProfileService;
+use infra::services::project::ProjectService;
+use infra::services::project_resolver::ProjectResolver;
+use infra::services::runbook::RunbookService;
+use infra::services::security::Security;
+use infra::services::state::StateService;
+use infra::services::validation::Validation;
+use infra::services::workspace::WorkspaceService;
+use std::sync::Arc;
+
+mod common;
+use common::ENV_LOCK;
+
+fn restore_env(key: &str, previous: Option<String>) {
+    match previous {
+        Some(value) => std::env::set_var(key, value),
+        None => std::env::remove_var(key),
+    }
+}
+
+#[tokio::test]
+async fn workspace_suggest_turns_recent_failures_into_next_actions() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let tmp_dir = std::env::temp_dir().join(format!("infra-workspace-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let prev_audit = std::env::var("INFRA_AUDIT_PATH").ok();
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    std::env::set_var("INFRA_AUDIT_PATH", tmp_dir.join("audit.jsonl"));
+
+    let logger = Logger::new("test");
+    let validation = Validation::new();
+    let security = Arc::new(Security::new().expect("security"));
+    let state_service = Arc::new(StateService::new().expect("state"));
+    let project_service = Arc::new(ProjectService::new().expect("project"));
+    let audit_service = Arc::new(AuditService::new(logger.clone()));
+    audit_service.append(&serde_json::json!({
+        "timestamp": "2026-01-01T00:00:00Z",
+        "status": "error",
+        "tool": "sql",
+        "action": "query",
+        "trace_id": "trace-sql",
+        "input": {"action": "query", "profile_name": "billing-db"},
+        "error": {
+            "kind": "internal",
+            "code": "INTERNAL",
+            "message": "PostgreSQL error: db error: ERROR: relation \"billing.invoices\" does not exist",
+            "details": {"sqlstate": "42P01"}
+        }
+    }));
+    audit_service.append(&serde_json::json!({
+        "timestamp": "2026-01-01T00:00:01Z",
+        "status": "ok",
+        "tool": "sql",
+        "action": "query",
+        "trace_id": "trace-ok"
+    }));
+
+    let workspace = WorkspaceService::new(
+        logger.clone(),
+        Arc::new(ContextService::new().expect("context")),
+        None,
+        Some(Arc::new(ProjectResolver::new(
+            validation,
+            project_service.clone(),
+            Some(state_service.clone()),
+        ))),
+        Arc::new(ProfileService::new(security.clone()).expect("profile")),
+        Arc::new(RunbookService::new().expect("runbook")),
+        Arc::new(CapabilityService::new(security).expect("cap")),
+        project_service,
+        Arc::new(AliasService::new().expect("alias")),
+        Arc::new(PresetService::new().expect("preset")),
+        state_service,
+    )
+    .with_history(audit_service, None);
+
+    let result = workspace
+        .suggest(&serde_json::json!({"cwd": tmp_dir, "audit_trace_id": "trace-sql"}))
+        .await
+        .expect("suggest");
+    let next_actions = result["next_actions"].as_array().expect("next_actions");
+    assert_eq!(next_actions.len(), 1);
+    assert_eq!(next_actions[0]["tool"], "sql");
+    assert_eq!(next_actions[0]["action"], "catalog_tables");
+    assert_eq!(next_actions[0]["args"]["profile_name"], "billing-db");
+    assert_eq!(next_actions[0]["args"]["schema"], "billing");
+    assert_eq!(next_actions[0]["source"]["trace_id"], "trace-sql");
+
+    let actions_only = workspace
+        .suggest(&serde_json::json!({"cwd": tmp_dir, "format": "actions", "audit_limit": 1}))
+        .await
+        .expect("suggest actions");
+    assert!(actions_only["next_actions"]
+        .as_array()
+        .expect("next_actions")
+        .is_empty());
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    restore_env("INFRA_AUDIT_PATH", prev_audit);
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}
+
+#[tokio::test]
+async fn workspace_summary_reports_failures_running_jobs_and_stale_checkpoints() {
+    let _guard = ENV_LOCK.lock().await;
+
+    let tmp_dir = std::env::temp_dir().join(format!("infra-workspace-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&tmp_dir).expect("create temp dir");
+    let prev_profiles = std::env::var("INFRA_PROFILES_DIR").ok();
+    let prev_audit = std::env::var("INFRA_AUDIT_PATH").ok();
+    std::env::set_var("INFRA_PROFILES_DIR", &tmp_dir);
+    std::env::set_var("INFRA_AUDIT_PATH", tmp_dir.join("audit.jsonl"));
+
+    let logger = Logger::new("test");
+    let validation = Validation::new();
+    let security = Arc::new(Security::new().expect("security"));
+    let state_service = Arc::new(StateService::new().expect("state"));
+    let project_service = Arc::new(ProjectService::new().expect("project"));
+    let audit_service = Arc::new(AuditService::new(logger.clone()));
+    audit_service.append(&serde_json::json!({
+        "timestamp": "2026-01-01T00:00:00Z",
+        "status": "error",
+        "tool": "ssh",
+        "action": "exec",
+        "trace_id": "trace-ssh",
+        "error": {"kind": "retryable", "code": "SSH_CONNECT_FAILED", "message": "refused"}
+    }));
+    let job_service = Arc::new(JobService::new(logger.clone()).expect("jobs"));
+    job_service.upsert(serde_json::json!({
+        "job_id": "job-running",
+        "kind": "local_exec",
+        "status": "running",
+        "started_at": (chrono::Utc::now() - chrono::Duration::minutes(5)).to_rfc3339(),
+    }));
+
+    let checkpoints = tmp_dir.join("pipeline-checkpoints");
+    std::fs::create_dir_all(&checkpoints).expect("checkpoints dir");
+    let stale = checkpoints.join("nightly.json");
+    std::fs::write(
+        &stale,
+        r#"{"checkpoint":"nightly","flow":"sftp_to_postgres","files":{"/in/a.csv":{}}}"#,
+    )
+    .expect("stale checkpoint");
+    std::fs::File::options()
+        .write(true)
+        .open(&stale)
+        .expect("open checkpoint")
+        .set_modified(std::time::SystemTime::now() - std::time::Duration::from_secs(7200))
+        .expect("age checkpoint");
+    std::fs::write(
+        checkpoints.join("fresh.json"),
+        r#"{"checkpoint":"fresh","flow":"sftp_to_postgres","files":{}}"#,
+    )
+    .expect("fresh checkpoint");
+    std::fs::create_dir_all(tmp_dir.join("cache")).expect("cache dir");
+    std::fs::write(tmp_dir.join("cache").join("entry.json"), "0123456789").expect("cache");
+
+    let workspace = WorkspaceService::new(
+        logger.clone(),
+        Arc::new(ContextService::new().expect("context")),
+        None,
+        Some(Arc::new(ProjectResolver::new(
+            validation,
+            project_service.clone(),
+            Some(state_service.clone()),
+        ))),
+        Arc::new(ProfileService::new(security.clone()).expect("profile")),
+        Arc::new(RunbookService::new().expect("runbook")),
+        Arc::new(CapabilityService::new(security).expect("cap")),
+        project_service,
+        Arc::new(AliasService::new().expect("alias")),
+        Arc::new(PresetService::new().expect("preset")),
+        state_service,
+    )
+    .with_history(audit_service, Some(job_service));
+
+    let result = workspace
+        .summarize(&serde_json::json!({"cwd": tmp_dir}))
+        .await
+        .expect("summarize");
+    let operations = &result["workspace"]["operations"];
+
+    let failure = &operations["recent_failures"]["items"][0];
+    assert_eq!(failure["tool"], "ssh");
+    assert_eq!(failure["error_code"], "SSH_CONNECT_FAILED");
+    assert_eq!(failure["next_action"]["action"], "audit_trace");
+    assert_eq!(failure["next_action"]["args"]["trace_id"], "trace-ssh");
+
+    let job = &operations["running_jobs"]["items"][0];
+    assert_eq!(job["job_id"], "job-running");
+    assert!(job["age_seconds"].as_i64().unwrap() >= 299);
+    assert_eq!(job["next_action"]["tool"], "job");
+    assert_eq!(job["next_action"]["action"], "follow_job");
+
+    let stale = operations["stale_checkpoints"]["items"].as_array().unwrap();
+    assert_eq!(stale.len(), 1);
+    assert_eq!(stale[0]["checkpoint"], "nightly");
+    assert_eq!(stale[0]["files_done"], 1);
+    assert_eq!(
+        stale[0]["next_action"]["args"],
+        serde_json::json!({"flow": "sftp_to_postgres", "checkpoint": "nightly"})
+    );
+
+    assert_eq!(operations["storage"]["cache"]["bytes"], 10);
+    assert_eq!(operations["storage"]["cache"]["files"], 1);
+
+    // Without history wiring the sources degrade instead of failing the summary.
+    let compact = WorkspaceService::new(
+        logger.clone(),
+        Arc::new(ContextService::new().expect("context")),
+        None,
+        None,
+        Arc::new(
+            ProfileService::new(Arc::new(Security::new().expect("security"))).expect("profile"),
+        ),
+        Arc::new(RunbookService::new().expect("runbook")),
+        Arc::new(
+            CapabilityService::new(Arc::new(Security::new().expect("security"))).expect("cap"),
+        ),
+        Arc::new(ProjectService::new().expect("project")),
+        Arc::new(AliasService::new().expect("alias")),
+        Arc::new(PresetService::new().expect("preset")),
+        Arc::new(StateService::new().expect("state")),
+    )
+    .summarize(&serde_json::json!({"cwd": tmp_dir, "format": "compact"}))
+    .await
+    .expect("summarize compact");
+    let operations = &compact["workspace"]["operations"];
+    assert_eq!(operations["recent_failures"]["status"], "unavailable");
+    assert_eq!(operations["running_jobs"]["status"], "unavailable");
+    assert_eq!(operations["stale_checkpoints"]["status"], "ok");
+
+    restore_env("INFRA_PROFILES_DIR", prev_profiles);
+    restore_env("INFRA_AUDIT_PATH", prev_audit);
+    std::fs::remove_dir_all(&tmp_dir).ok();
+}